@@ -54,11 +54,11 @@ pub struct CmdClean {
     prune_repeated: bool,
 
     /// Prune tags older that the given age (eg: 1y, 8w, 10d, 3h, 4m, 8s)
-    #[clap(long = "prune-if-older-than", group = "repo_data", value_parser = age_to_date)]
+    #[clap(long = "prune-if-older-than", group = "repo_data", value_parser = cli::age_to_date)]
     prune_if_older_than: Option<DateTime<Utc>>,
 
     /// Always keep data newer than the given age (eg: 1y, 8w, 10d, 3h, 4m, 8s)
-    #[clap(long = "keep-if-newer-than", group = "repo_data", value_parser = age_to_date)]
+    #[clap(long = "keep-if-newer-than", group = "repo_data", value_parser = cli::age_to_date)]
     keep_if_newer_than: Option<DateTime<Utc>>,
 
     /// Prune tags if there are more than this number in a stream
@@ -78,6 +78,14 @@ pub struct CmdClean {
     #[clap(long = "keep-proxies-with-no-links", group = "repo_data")]
     keep_proxies_with_no_links: bool,
 
+    /// Only remove as much untracked data as needed for this amount of
+    /// space to be free on the repository filesystem (eg: 500GB, 20G, 1T)
+    ///
+    /// Untracked data is removed in order of when it was last used, oldest
+    /// first. This is only supported for local filesystem repositories.
+    #[clap(long = "until-free", group = "repo_data", value_name = "SIZE", value_parser = cli::size_to_bytes)]
+    until_free: Option<u64>,
+
     // The number of concurrent tag stream scanning operations
     // that are buffered and allowed to run concurrently
     #[clap(
@@ -149,8 +157,10 @@ impl CmdClean {
             return Ok(0);
         }
 
+        let cancellation = spfs::operation::CancellationToken::new();
         let cleaner = spfs::Cleaner::new(&repo)
             .with_reporter(spfs::clean::ConsoleCleanReporter::default())
+            .with_cancellation(cancellation.clone())
             .with_dry_run(self.dry_run)
             .with_required_age(chrono::Duration::minutes(15))
             .with_prune_repeated_tags(self.prune_repeated)
@@ -159,6 +169,7 @@ impl CmdClean {
             .with_prune_tags_if_version_more_than(self.prune_if_more_than)
             .with_keep_tags_if_version_less_than(self.keep_if_less_than)
             .with_remove_proxies_with_no_links(!self.keep_proxies_with_no_links)
+            .with_free_space_target(self.until_free)
             .with_removal_concurrency(self.max_removal_concurrency)
             .with_discover_concurrency(self.max_discover_concurrency)
             .with_tag_stream_concurrency(self.max_tag_stream_concurrency);
@@ -178,6 +189,14 @@ impl CmdClean {
             }
         }
 
+        // stop the clean with an error when interrupted, nothing is
+        // removed if this happens before all attached objects are found
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancellation.cancel();
+            }
+        });
+
         let start = std::time::Instant::now();
         let result = cleaner.prune_all_tags_and_clean().await?;
         let duration = std::time::Instant::now() - start;
@@ -194,6 +213,8 @@ impl CmdClean {
             removed_renders,
             visited_proxies,
             removed_proxies,
+            removed_bytes,
+            available_space,
             errors,
         } = result;
 
@@ -223,6 +244,20 @@ impl CmdClean {
             "{visited_proxies:>12} proxies visited  [{:>6} {removed}]",
             removed_proxies.values().map(HashSet::len).sum::<usize>()
         );
+        if let (Some(target), Some(available)) = (self.until_free, available_space) {
+            println!(
+                "{:>12} {removed}, {} now free",
+                spfs::io::format_size(removed_bytes),
+                spfs::io::format_size(available)
+            );
+            if available < target {
+                println!(
+                    "{} could not remove enough untracked data to reach {} free",
+                    "Warning:".yellow(),
+                    spfs::io::format_size(target)
+                );
+            }
+        }
 
         if !errors.is_empty() {
             println!("Encountered {} {}", errors.len(), "errors".red());
@@ -232,23 +267,3 @@ impl CmdClean {
         Ok(0)
     }
 }
-
-fn age_to_date(age: &str) -> Result<DateTime<Utc>> {
-    let (num, postfix) = age.split_at(age.len() - 1);
-    let num: i64 = num
-        .parse()
-        .map_err(|err| spfs::Error::from(format!("{err:?}")))?;
-    if num < 0 {
-        miette::bail!("provided age must be greater than zero: '{age}'");
-    }
-
-    match postfix {
-        "y" => Ok(Utc::now() - chrono::Duration::weeks(num * 52)),
-        "w" => Ok(Utc::now() - chrono::Duration::weeks(num)),
-        "d" => Ok(Utc::now() - chrono::Duration::days(num)),
-        "h" => Ok(Utc::now() - chrono::Duration::hours(num)),
-        "m" => Ok(Utc::now() - chrono::Duration::minutes(num)),
-        "s" => Ok(Utc::now() - chrono::Duration::seconds(num)),
-        _ => miette::bail!("Unknown age postfix: '{postfix}', must be one of y, w, d, h, m, s"),
-    }
-}
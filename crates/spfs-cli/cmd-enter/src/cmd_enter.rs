@@ -54,6 +54,10 @@ pub struct CmdEnter {
     /// The name of the runtime
     #[clap(long)]
     runtime: String,
+
+    /// Set once this process has moved into its own user namespace
+    #[clap(skip)]
+    rootless: bool,
 }
 
 #[derive(Debug, Args)]
@@ -112,6 +116,9 @@ impl CommandName for CmdEnter {
 
 impl CmdEnter {
     pub fn run(&mut self, config: &spfs::Config) -> Result<i32> {
+        #[cfg(unix)]
+        self.enter_rootless_namespace(config)?;
+
         // we need a single-threaded runtime in order to properly setup
         // and enter the namespace of the runtime
         let rt = tokio::runtime::Builder::new_current_thread()
@@ -130,6 +137,32 @@ impl CmdEnter {
         }
     }
 
+    /// Move into a new user namespace when setting up a rootless runtime.
+    ///
+    /// This must happen before any other threads are started.
+    #[cfg(unix)]
+    fn enter_rootless_namespace(&mut self, config: &spfs::Config) -> Result<()> {
+        use spfs::runtime::{rootless, RootlessMode};
+
+        if self.make_durable.enabled || self.exit.enabled || self.remount.enabled {
+            // these operations are always run from within an
+            // existing runtime, which already has any namespace
+            return Ok(());
+        }
+        let mode = config.filesystem.rootless;
+        if !rootless::should_use_rootless(mode)? {
+            return Ok(());
+        }
+        match rootless::enter_user_namespace() {
+            Ok(()) => self.rootless = true,
+            Err(err) if mode == RootlessMode::Auto => {
+                tracing::debug!("falling back to setuid spfs-enter: {err}");
+            }
+            Err(err) => return Err(err.into()),
+        }
+        Ok(())
+    }
+
     #[cfg(unix)]
     pub async fn setup_runtime(
         &mut self,
@@ -169,6 +202,7 @@ impl CmdEnter {
             Ok(None)
         } else {
             let mut owned = spfs::runtime::OwnedRuntime::upgrade_as_owner(runtime).await?;
+            owned.config.rootless = self.rootless;
 
             // Enter the mount namespace before spawning the monitor process
             // so that the monitor can properly view and manage that namespace.
@@ -324,7 +358,9 @@ impl CmdEnter {
                         copy_link_limit_count = %render_summary.copy_link_limit_count.load(Ordering::Relaxed),
                         copy_wrong_mode_count = %render_summary.copy_wrong_mode_count.load(Ordering::Relaxed),
                         copy_wrong_owner_count = %render_summary.copy_wrong_owner_count.load(Ordering::Relaxed),
+                        copy_no_reflink_count = %render_summary.copy_no_reflink_count.load(Ordering::Relaxed),
                         link_count = %render_summary.link_count.load(Ordering::Relaxed),
+                        reflink_count = %render_summary.reflink_count.load(Ordering::Relaxed),
                         symlink_count = %render_summary.symlink_count.load(Ordering::Relaxed),
                         total_bytes_rendered = %render_summary.total_bytes_rendered.load(Ordering::Relaxed),
                         total_bytes_already_existed = %render_summary.total_bytes_already_existed.load(Ordering::Relaxed),
@@ -332,7 +368,9 @@ impl CmdEnter {
                         total_bytes_copied_link_limit = %render_summary.total_bytes_copied_link_limit.load(Ordering::Relaxed),
                         total_bytes_copied_wrong_mode = %render_summary.total_bytes_copied_wrong_mode.load(Ordering::Relaxed),
                         total_bytes_copied_wrong_owner = %render_summary.total_bytes_copied_wrong_owner.load(Ordering::Relaxed),
+                        total_bytes_copied_no_reflink = %render_summary.total_bytes_copied_no_reflink.load(Ordering::Relaxed),
                         total_bytes_linked = %render_summary.total_bytes_linked.load(Ordering::Relaxed),
+                        total_bytes_reflinked = %render_summary.total_bytes_reflinked.load(Ordering::Relaxed),
                         sync_time = %sync_time,
                         render_time = %render_time,
                         "Render summary");
@@ -0,0 +1,98 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use miette::{Context, Result};
+use spfs::Error;
+use spfs_cli_common as cli;
+use spfs_cli_common::CommandName;
+
+cli::main!(CmdExpose, sentry = false, sync = true);
+
+/// Mount a read-only view of an active runtime at another location
+///
+/// This makes the contents of a runtime available to processes that
+/// are not running inside of it, such as sidecar daemons, debuggers or
+/// render processes that join an existing session. The view is mounted
+/// in the mount namespace of this command, and the runtime is kept
+/// alive until it is removed again with --remove. Only the owner of a
+/// runtime can expose it.
+#[derive(Parser, Debug)]
+pub struct CmdExpose {
+    #[clap(flatten)]
+    pub logging: cli::Logging,
+
+    /// Remove a view that was previously exposed at the target path
+    #[clap(long)]
+    remove: bool,
+
+    /// The name or id of the runtime to expose
+    runtime: String,
+
+    /// An empty directory, owned by the current user and within the
+    /// configured expose root, to mount the runtime onto
+    target: PathBuf,
+}
+
+impl CommandName for CmdExpose {
+    fn command_name(&self) -> &'static str {
+        "expose"
+    }
+}
+
+impl CmdExpose {
+    pub fn run(&mut self, config: &spfs::Config) -> Result<i32> {
+        // because we are dealing with moving to a new linux namespace, we must
+        // ensure that all code still operates in a single os thread
+        let rt = new_current_thread()?;
+        let runtime = rt.block_on(async {
+            let storage = config.get_runtime_storage().await?;
+            storage.read_runtime(&self.runtime).await
+        })?;
+        // Shut down the tokio runtime (join threads) before attempting to
+        // enter the spfs runtime. This is only allowed in a single-threaded
+        // program.
+        drop(rt);
+
+        let expose_root = config.filesystem.expose_root();
+        let configurator = spfs::env::RuntimeConfigurator::default();
+        if self.remove {
+            configurator
+                .unexpose_runtime(&runtime, &self.target, expose_root)
+                .wrap_err("Failed to remove exposed runtime")?;
+            tracing::info!(
+                "removed exposed runtime {} from {:?}",
+                runtime.name(),
+                self.target
+            );
+            return Ok(0);
+        }
+
+        let mut try_counter = 0;
+        let exposed = loop {
+            try_counter += 1;
+            match configurator.expose_runtime(&runtime, &self.target, expose_root) {
+                Err(spfs::Error::String(err)) if err.contains("single-threaded") => {
+                    if try_counter % 50 == 0 {
+                        tracing::info!("Waiting for process to become single threaded: {err}");
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                Err(err) => return Err(err.into()),
+                Ok(exposed) => break exposed,
+            }
+        };
+        tracing::info!("exposed runtime {} at {:?}", runtime.name(), exposed.target);
+        Ok(0)
+    }
+}
+
+fn new_current_thread() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| Error::process_spawn_error("new_current_thread()", err, None).into())
+}
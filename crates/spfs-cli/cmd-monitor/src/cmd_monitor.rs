@@ -107,7 +107,7 @@ impl CmdMonitor {
             .build()
             .into_diagnostic()
             .wrap_err("Failed to establish async runtime")?;
-        let code = rt.block_on(self.run_async())?;
+        let code = rt.block_on(self.run_async(config))?;
         // the monitor is running in the background and, although not expected,
         // can take extra time to shutdown if needed
         rt.shutdown_timeout(std::time::Duration::from_secs(5));
@@ -141,7 +141,7 @@ impl CmdMonitor {
         }
     }
 
-    pub async fn run_async(&mut self) -> Result<i32> {
+    pub async fn run_async(&mut self, config: &spfs::Config) -> Result<i32> {
         let mut interrupt = signal(SignalKind::interrupt())
             .map_err(|err| Error::process_spawn_error("signal()", err, None))?;
         let mut quit = signal(SignalKind::quit())
@@ -157,7 +157,21 @@ impl CmdMonitor {
         let mut owned = spfs::runtime::OwnedRuntime::upgrade_as_monitor(runtime).await?;
         tracing::trace!("upgraded to owned runtime, waiting for empty runtime");
 
-        let fut = spfs::monitor::wait_for_empty_runtime(&owned);
+        let fut = async {
+            if owned.status.detached {
+                // the processes that were joined into a detached runtime
+                // are stopped along with it, rather than keeping it alive
+                tokio::try_join!(
+                    spfs::monitor::wait_for_empty_runtime(&owned),
+                    spfs::monitor::terminate_detached_runtime(&owned),
+                )?;
+            } else {
+                spfs::monitor::wait_for_empty_runtime(&owned).await?;
+            }
+            // the filesystem must remain available for as long
+            // as it is exposed to processes outside of the runtime
+            spfs::monitor::wait_for_exposed_mounts(&owned).await
+        };
         let res = tokio::select! {
             res = fut => {
                 tracing::info!("Monitor detected no more processes, cleaning up runtime...");
@@ -212,7 +226,34 @@ impl CmdMonitor {
             tracing::error!("failed to clean up runtime data: {err:?}")
         }
 
+        if let Some(unused_for) = &config.monitor.prune_renders_unused_for {
+            // the runtime has already been cleaned up, so any failure
+            // here only means that the renders will be pruned later
+            if let Err(err) = self.prune_renders(config, unused_for, &storage).await {
+                tracing::warn!("failed to prune unused renders: {err:?}");
+            }
+        }
+
         res?;
         Ok(0)
     }
+
+    /// Remove local renders that have not been used within the
+    /// configured amount of time, if they are due to be pruned.
+    async fn prune_renders(
+        &self,
+        config: &spfs::Config,
+        unused_for: &str,
+        runtimes: &spfs::runtime::Storage,
+    ) -> Result<()> {
+        let unused_for = spfs::tracking::parse_duration(unused_for)?;
+        let interval = spfs::tracking::parse_duration(&config.monitor.prune_renders_interval)?;
+        let repo = config.get_opened_local_repository().await?;
+        if let Some(removed) =
+            spfs::prune_unused_renders_if_due(&repo, runtimes, unused_for, interval).await?
+        {
+            tracing::info!("pruned {} unused render(s)", removed.len());
+        }
+        Ok(())
+    }
 }
@@ -15,7 +15,11 @@ use strum::VariantNames;
 cli::main!(CmdRender);
 
 #[derive(Debug, Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CmdRender {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(flatten)]
     sync: cli::Sync,
     #[clap(flatten)]
@@ -37,7 +41,8 @@ pub struct CmdRender {
     strategy: Option<spfs::storage::fs::RenderType>,
 
     /// The tag or digest of what to render, use a '+' to join multiple layers
-    reference: String,
+    #[clap(required = true)]
+    reference: Option<String>,
 
     /// Alternate path to render the manifest into (defaults to the local repository)
     target: Option<std::path::PathBuf>,
@@ -51,7 +56,11 @@ impl CommandName for CmdRender {
 
 impl CmdRender {
     pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
-        let mut env_spec = spfs::tracking::EnvSpec::parse(&self.reference)?;
+        if let Some(command) = &self.command {
+            return command.run(config).await;
+        }
+        let reference = self.reference.as_deref().unwrap_or_default();
+        let mut env_spec = spfs::tracking::EnvSpec::parse(reference)?;
         let (repo, origin, remotes) = tokio::try_join!(
             config.get_opened_local_repository(),
             config.try_get_remote("origin"),
@@ -175,3 +184,51 @@ impl CmdRender {
             .wrap_err("render layers")
     }
 }
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    Prune(CmdRenderPrune),
+}
+
+impl Command {
+    async fn run(&self, config: &spfs::Config) -> Result<i32> {
+        match self {
+            Self::Prune(cmd) => cmd.run(config).await,
+        }
+    }
+}
+
+/// Remove renders from the local repository that have not been used recently
+///
+/// Renders of the layers in any runtime are always kept.
+#[derive(Debug, clap::Args)]
+struct CmdRenderPrune {
+    /// Remove renders that have not been used within the given age (eg: 30d, 8w)
+    #[clap(long, value_name = "AGE")]
+    unused_for: String,
+
+    /// Only show the renders that would be removed
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl CmdRenderPrune {
+    async fn run(&self, config: &spfs::Config) -> Result<i32> {
+        let unused_since = cli::age_to_date(&self.unused_for)?;
+        let (repo, runtimes) = tokio::try_join!(
+            config.get_opened_local_repository(),
+            config.get_runtime_storage()
+        )?;
+        let removed =
+            spfs::prune_unused_renders(&repo, &runtimes, unused_since, self.dry_run).await?;
+        for digest in removed.iter() {
+            println!("{digest}");
+        }
+        if self.dry_run {
+            tracing::info!("{} render(s) would be removed", removed.len());
+        } else {
+            tracing::info!("removed {} render(s)", removed.len());
+        }
+        Ok(0)
+    }
+}
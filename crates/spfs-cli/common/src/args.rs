@@ -7,6 +7,7 @@ use std::panic::catch_unwind;
 #[cfg(feature = "sentry")]
 use std::sync::Mutex;
 
+use chrono::{DateTime, Utc};
 use miette::{Error, IntoDiagnostic, Result, WrapErr};
 #[cfg(feature = "sentry")]
 use once_cell::sync::OnceCell;
@@ -54,6 +55,11 @@ pub struct Sync {
         default_value_t = spfs::sync::DEFAULT_MAX_CONCURRENT_PAYLOADS
     )]
     pub max_concurrent_payloads: usize,
+
+    /// Limit the rate at which file payloads are transferred, in bytes
+    /// per second, with an optional K, M or G suffix (eg: 50M)
+    #[clap(long, env = "SPFS_SYNC_LIMIT_RATE", value_parser = parse_rate)]
+    pub limit_rate: Option<u64>,
 }
 
 impl Sync {
@@ -65,11 +71,14 @@ impl Sync {
         dest: &'dst spfs::storage::RepositoryHandle,
     ) -> spfs::Syncer<'src, 'dst, spfs::sync::ConsoleSyncReporter> {
         let policy = self.sync_policy();
-        spfs::Syncer::new(src, dest)
+        let mut syncer = spfs::Syncer::new(src, dest)
             .with_policy(policy)
             .with_max_concurrent_manifests(self.max_concurrent_manifests)
-            .with_max_concurrent_payloads(self.max_concurrent_payloads)
-            .with_reporter(spfs::sync::ConsoleSyncReporter::default())
+            .with_max_concurrent_payloads(self.max_concurrent_payloads);
+        if let Some(limit) = self.limit_rate {
+            syncer = syncer.with_rate_limit(limit);
+        }
+        syncer.with_reporter(spfs::sync::ConsoleSyncReporter::default())
     }
 
     /// The selected sync policy for these options
@@ -86,16 +95,32 @@ impl Sync {
     }
 }
 
+/// Parse a transfer rate in bytes per second, eg: 500K, 50M or 1G
+fn parse_rate(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1024),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024 * 1024),
+        Some((i, 'g' | 'G')) => (&value[..i], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid rate '{value}', expected a number like 500K or 50M"))?;
+    if number <= 0.0 {
+        return Err("rate must be greater than zero".into());
+    }
+    Ok((number * multiplier as f64) as u64)
+}
+
 /// Command line flags for configuring render operations
 #[derive(Debug, Clone, clap::Args)]
 pub struct Render {
     /// The total number of blobs that can be rendered concurrently
-    #[clap(
-        long,
-        env = "SPFS_RENDER_MAX_CONCURRENT_BLOBS",
-        default_value_t = spfs::storage::fs::DEFAULT_MAX_CONCURRENT_BLOBS
-    )]
-    pub max_concurrent_blobs: usize,
+    ///
+    /// Defaults to the `render.max_concurrent_blobs` value from the spfs config.
+    #[clap(long, env = "SPFS_RENDER_MAX_CONCURRENT_BLOBS")]
+    pub max_concurrent_blobs: Option<usize>,
 
     /// The total number of branches that can be processed concurrently
     /// at each level of the rendered file tree.
@@ -123,10 +148,12 @@ impl Render {
         Repo: spfs::storage::Repository + LocalRepository,
         Reporter: spfs::storage::fs::RenderReporter,
     {
-        spfs::storage::fs::Renderer::new(repo)
-            .with_max_concurrent_blobs(self.max_concurrent_blobs)
-            .with_max_concurrent_branches(self.max_concurrent_branches)
-            .with_reporter(reporter)
+        let mut renderer = spfs::storage::fs::Renderer::new(repo)
+            .with_max_concurrent_branches(self.max_concurrent_branches);
+        if let Some(max_concurrent_blobs) = self.max_concurrent_blobs {
+            renderer = renderer.with_max_concurrent_blobs(max_concurrent_blobs);
+        }
+        renderer.with_reporter(reporter)
     }
 }
 
@@ -620,3 +647,53 @@ pub fn capture_if_relevant(err: &Error) {
         }
     }
 }
+
+/// Parse a relative age (eg: '3w', '12h') into the date that is that far in the past
+///
+/// Supported postfixes are y, w, d, h, m and s.
+pub fn age_to_date(age: &str) -> Result<DateTime<Utc>> {
+    let (num, postfix) = age.split_at(age.len() - 1);
+    let num: i64 = num
+        .parse()
+        .map_err(|err| spfs::Error::from(format!("{err:?}")))?;
+    if num < 0 {
+        miette::bail!("provided age must be greater than zero: '{age}'");
+    }
+
+    match postfix {
+        "y" => Ok(Utc::now() - chrono::Duration::weeks(num * 52)),
+        "w" => Ok(Utc::now() - chrono::Duration::weeks(num)),
+        "d" => Ok(Utc::now() - chrono::Duration::days(num)),
+        "h" => Ok(Utc::now() - chrono::Duration::hours(num)),
+        "m" => Ok(Utc::now() - chrono::Duration::minutes(num)),
+        "s" => Ok(Utc::now() - chrono::Duration::seconds(num)),
+        _ => miette::bail!("Unknown age postfix: '{postfix}', must be one of y, w, d, h, m, s"),
+    }
+}
+
+/// Parse a size (eg: '500GB', '20G', '1T') into a number of bytes
+///
+/// Supported postfixes are K, M, G and T, which are always treated as
+/// powers of 1024, optionally followed by 'B' or 'iB'.
+pub fn size_to_bytes(size: &str) -> Result<u64> {
+    let trimmed = size.trim();
+    let number = trimmed
+        .strip_suffix(['B', 'b'])
+        .unwrap_or(trimmed)
+        .trim_end_matches(['i', 'I']);
+    let (number, multiplier) = match number.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&number[..number.len() - 1], 1024),
+        Some('M') => (&number[..number.len() - 1], 1024_u64.pow(2)),
+        Some('G') => (&number[..number.len() - 1], 1024_u64.pow(3)),
+        Some('T') => (&number[..number.len() - 1], 1024_u64.pow(4)),
+        _ => (number, 1),
+    };
+    let num: u64 = number
+        .trim()
+        .parse()
+        .map_err(|err| spfs::Error::from(format!("{err:?}")))?;
+    match num.checked_mul(multiplier) {
+        Some(bytes) => Ok(bytes),
+        None => miette::bail!("provided size is too large: '{size}'"),
+    }
+}
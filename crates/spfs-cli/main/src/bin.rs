@@ -18,6 +18,7 @@ mod cmd_log;
 mod cmd_ls;
 mod cmd_ls_tags;
 mod cmd_migrate;
+mod cmd_mirror;
 mod cmd_platforms;
 mod cmd_pull;
 mod cmd_push;
@@ -29,6 +30,8 @@ mod cmd_runtime_info;
 mod cmd_runtime_list;
 mod cmd_runtime_prune;
 mod cmd_runtime_remove;
+mod cmd_runtime_stop;
+mod cmd_runtime_supervise;
 mod cmd_search;
 #[cfg(feature = "server")]
 mod cmd_server;
@@ -87,6 +90,7 @@ pub enum Command {
     LsTags(cmd_ls_tags::CmdLsTags),
     Ls(cmd_ls::CmdLs),
     Migrate(cmd_migrate::CmdMigrate),
+    Mirror(cmd_mirror::CmdMirror),
     Check(cmd_check::CmdCheck),
     Read(cmd_read::CmdRead),
     Write(cmd_write::CmdWrite),
@@ -126,6 +130,7 @@ impl Opt {
             Command::LsTags(cmd) => cmd.run(config).await,
             Command::Ls(cmd) => cmd.run(config).await,
             Command::Migrate(cmd) => cmd.run(config).await,
+            Command::Mirror(cmd) => cmd.run(config).await,
             Command::Check(cmd) => cmd.run(config).await,
             Command::Read(cmd) => cmd.run(config).await,
             Command::Write(cmd) => cmd.run(config).await,
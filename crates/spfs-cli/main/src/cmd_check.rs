@@ -27,6 +27,26 @@ pub struct CmdCheck {
     #[clap(long)]
     pull: Option<Option<String>>,
 
+    /// Report statistics about the health of the whole repository
+    /// instead of checking the object graph
+    #[clap(long, conflicts_with_all = ["pull", "REF"])]
+    stats: bool,
+
+    /// Verify the data of the whole repository against another one,
+    /// reporting anything that is corrupt or that it does not have.
+    /// Defaults to "origin".
+    #[clap(
+        long = "reference",
+        value_name = "REMOTE",
+        conflicts_with_all = ["pull", "stats", "REF"]
+    )]
+    reference_repo: Option<Option<String>>,
+
+    /// Re-write missing blobs for any orphaned payloads that are found (with --stats),
+    /// or fetch corrupt data again from the reference repository (with --reference)
+    #[clap(long)]
+    repair: bool,
+
     /// Objects to recursively check, defaults to everything
     #[clap(name = "REF")]
     reference: Vec<String>,
@@ -36,6 +56,22 @@ impl CmdCheck {
     pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
         let repo = spfs::config::open_repository_from_string(config, self.remote.as_ref()).await?;
 
+        if self.stats {
+            return self.run_stats(&repo).await;
+        }
+        if let Some(reference) = self.reference_repo.take() {
+            let reference = reference.unwrap_or_else(|| "origin".to_owned());
+            if Some(&reference) == self.remote.as_ref() {
+                miette::bail!("Cannot check against the same repo as --remote");
+            }
+            let reference =
+                spfs::config::open_repository_from_string(config, Some(reference)).await?;
+            return self.run_reference(&repo, &reference).await;
+        }
+        if self.repair {
+            miette::bail!("--repair can only be used with --stats or --reference");
+        }
+
         let pull_from = match self.pull.take() {
             Some(name @ Some(_)) if name == self.remote => {
                 miette::bail!("Cannot --pull from same repo as --remote");
@@ -110,11 +146,10 @@ impl CmdCheck {
         println!(
             "{checked_payloads:>12} payloads visited ({missing_payloads} {missing}, {repaired_payloads} {repaired})",
         );
-        let human_bytes = match NumberPrefix::binary(checked_payload_bytes as f64) {
-            NumberPrefix::Standalone(amt) => format!("{amt} bytes"),
-            NumberPrefix::Prefixed(p, amt) => format!("{amt:.2} {}B", p.symbol()),
-        };
-        println!("{human_bytes:>12} total payload footprint");
+        println!(
+            "{:>12} total payload footprint",
+            human_bytes(checked_payload_bytes)
+        );
 
         if missing_objects + missing_payloads != 0 {
             if pull_from.is_none() {
@@ -125,4 +160,117 @@ impl CmdCheck {
         println!("No issues found");
         Ok(0)
     }
+
+    async fn run_stats(&self, repo: &spfs::storage::RepositoryHandle) -> Result<i32> {
+        let start = std::time::Instant::now();
+        let report = spfs::Checker::new(repo)
+            .with_repair_blobs(self.repair)
+            .check_database_integrity()
+            .await?;
+        let duration = std::time::Instant::now() - start;
+
+        let spfs::check::IntegrityReport {
+            total_objects,
+            total_payloads,
+            payload_bytes,
+            duplicated_render_bytes,
+            orphaned_payloads,
+            repaired_blobs,
+        } = report;
+
+        println!("{} after {duration:.0?}:", "Finished".bold());
+        let orphaned = "orphaned".red().italic();
+        let repaired = "repaired".cyan().italic();
+        println!("{total_objects:>12} objects");
+        println!(
+            "{total_payloads:>12} payloads ({} {orphaned}, {repaired_blobs} {repaired})",
+            orphaned_payloads.len()
+        );
+        println!("{:>12} total payload footprint", human_bytes(payload_bytes));
+        println!(
+            "{:>12} duplicated in renders",
+            human_bytes(duplicated_render_bytes)
+        );
+
+        if orphaned_payloads.len() != repaired_blobs {
+            for digest in orphaned_payloads.iter() {
+                tracing::warn!(%digest, "payload has no blob");
+            }
+            if !self.repair {
+                tracing::info!("running with `--repair` may be able to resolve these issues")
+            }
+            return Ok(1);
+        }
+        println!("No issues found");
+        Ok(0)
+    }
+
+    async fn run_reference(
+        &self,
+        repo: &spfs::storage::RepositoryHandle,
+        reference: &spfs::storage::RepositoryHandle,
+    ) -> Result<i32> {
+        let start = std::time::Instant::now();
+        let report = spfs::Checker::new(repo)
+            .with_repair_corrupt_data(self.repair)
+            .check_against_reference(reference)
+            .await?;
+        let duration = std::time::Instant::now() - start;
+
+        let spfs::check::ReferenceReport {
+            total_objects,
+            total_payloads,
+            corrupt_objects,
+            corrupt_payloads,
+            unknown_objects,
+            unknown_payloads,
+            repaired_objects,
+            repaired_payloads,
+            removed_renders,
+        } = report;
+
+        println!("{} after {duration:.0?}:", "Finished".bold());
+        let corrupt = "corrupt".red().italic();
+        let unknown = "not in reference".yellow().italic();
+        let repaired = "repaired".cyan().italic();
+        println!(
+            "{total_objects:>12} objects  ({} {corrupt}, {repaired_objects} {repaired}, {} {unknown})",
+            corrupt_objects.len(),
+            unknown_objects.len(),
+        );
+        println!(
+            "{total_payloads:>12} payloads ({} {corrupt}, {repaired_payloads} {repaired}, {} {unknown})",
+            corrupt_payloads.len(),
+            unknown_payloads.len(),
+        );
+        if removed_renders > 0 {
+            println!(
+                "{removed_renders:>12} renders removed to be rendered again from repaired payloads"
+            );
+        }
+
+        let unrepaired =
+            corrupt_objects.len() + corrupt_payloads.len() - repaired_objects - repaired_payloads;
+        if unrepaired != 0 {
+            for digest in corrupt_objects.iter() {
+                tracing::warn!(%digest, "object is corrupt");
+            }
+            for digest in corrupt_payloads.iter() {
+                tracing::warn!(%digest, "payload is corrupt");
+            }
+            if !self.repair {
+                tracing::info!("running with `--repair` may be able to resolve these issues")
+            }
+            return Ok(1);
+        }
+        println!("No issues found");
+        Ok(0)
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    match NumberPrefix::binary(bytes as f64) {
+        NumberPrefix::Standalone(amt) => format!("{amt} bytes"),
+        NumberPrefix::Prefixed(p, amt) => format!("{amt:.2} {}B", p.symbol()),
+    }
 }
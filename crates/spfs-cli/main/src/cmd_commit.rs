@@ -29,6 +29,23 @@ pub struct CmdCommit {
     #[clap(long)]
     path: Option<PathBuf>,
 
+    /// Only commit files that match this glob pattern (eg: 'lib/**')
+    ///
+    /// Patterns are matched against paths relative to the root of the
+    /// committed directory, or /spfs. Patterns without a '/' match file
+    /// and directory names at any depth. Can be given multiple times,
+    /// and when not given, all files are committed.
+    #[clap(long, value_name = "PATTERN", conflicts_with = "reference")]
+    include: Vec<String>,
+
+    /// Do not commit files that match this glob pattern (eg: '*.o')
+    ///
+    /// Anything within a matching directory is also excluded. Exclusions
+    /// take precedence over any --include patterns, and can be given
+    /// multiple times.
+    #[clap(long, value_name = "PATTERN", conflicts_with = "reference")]
+    exclude: Vec<String>,
+
     /// Combine existing items into a platform, use a '+' to join multiple
     #[clap(long = "ref")]
     reference: Option<String>,
@@ -85,10 +102,12 @@ pub struct CmdCommit {
 impl CmdCommit {
     pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
         let repo = spfs::config::open_repository_from_string(config, self.remote.clone()).await?;
+        let filter = spfs::tracking::GlobPathFilter::new(&self.include, &self.exclude)?;
 
         let result = {
             let committer = spfs::Committer::new(&repo)
                 .with_reporter(spfs::commit::ConsoleCommitReporter::default())
+                .with_path_filter(filter)
                 .with_max_concurrent_branches(self.max_concurrent_branches)
                 .with_max_concurrent_blobs(self.max_concurrent_blobs)
                 .with_allow_empty(self.allow_empty);
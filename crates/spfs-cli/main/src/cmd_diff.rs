@@ -4,6 +4,7 @@
 
 use clap::Args;
 use miette::Result;
+use spfs::tracking::{GlobPathFilter, PathFilter};
 
 /// Compare two spfs file system states
 #[derive(Debug, Args)]
@@ -15,12 +16,47 @@ pub struct CmdDiff {
     /// The tag or id to diff the base against, defaults to the contents of the spfs filesystem
     #[clap(value_name = "TO")]
     top: Option<String>,
+
+    /// Report files that were moved without changing their content as renames
+    #[clap(long, short = 'M')]
+    renames: bool,
+
+    /// Only show changes to paths that match this glob pattern (eg: 'lib/**')
+    ///
+    /// Patterns without a '/' match file and directory names at any depth.
+    /// Can be given multiple times.
+    #[clap(long, value_name = "PATTERN")]
+    include: Vec<String>,
+
+    /// Do not show changes to paths that match this glob pattern (eg: '*.o')
+    ///
+    /// Exclusions take precedence over any --include patterns, and can be
+    /// given multiple times.
+    #[clap(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
 }
 
 impl CmdDiff {
-    pub async fn run(&mut self, _config: &spfs::Config) -> Result<i32> {
-        let diffs = spfs::diff(self.base.as_ref(), self.top.as_ref()).await?;
-        let out = spfs::io::format_changes(diffs.iter());
+    pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
+        let filter = GlobPathFilter::new(&self.include, &self.exclude)?;
+        // the files in /spfs are hashed in the same way as
+        // the repository that the runtime is read from
+        let algorithm = config.get_local_repository().await?.digest_algorithm();
+        let out = if self.renames {
+            let mut changes =
+                spfs::diff_changeset(self.base.as_ref(), self.top.as_ref(), algorithm).await?;
+            changes
+                .changes
+                .retain(|change| filter.should_include_path(&change.path));
+            spfs::io::format_changeset(&changes)
+        } else {
+            let diffs = spfs::diff(self.base.as_ref(), self.top.as_ref(), algorithm).await?;
+            spfs::io::format_changes(
+                diffs
+                    .iter()
+                    .filter(|diff| filter.should_include_entry(&diff.path, diff.mode.is_dir())),
+            )
+        };
         if out.trim().is_empty() {
             tracing::info!("no changes");
         } else {
@@ -128,6 +128,7 @@ impl CmdInfo {
                         self.to_process.push_back(reference.to_string());
                     }
                 }
+                print_labels(obj.labels());
             }
 
             Enum::Layer(obj) => {
@@ -161,6 +162,7 @@ impl CmdInfo {
                         println!("  {} {}", "value:".bright_blue(), annotation.value());
                     }
                 }
+                print_labels(obj.labels());
 
                 if self.follow {
                     if let Some(manifest_digest) = obj.manifest() {
@@ -213,6 +215,39 @@ impl CmdInfo {
                     "size:".bright_blue(),
                     spfs::io::format_size(obj.size())
                 );
+                if let Some(chunks) = obj.chunks() {
+                    println!(
+                        " {} {}",
+                        "chunks:".bright_blue(),
+                        self.format_digest(*chunks, repo).await?
+                    );
+                    if self.follow {
+                        self.to_process.push_back(chunks.to_string());
+                    }
+                }
+            }
+
+            Enum::ChunkList(obj) => {
+                println!(
+                    "{}:\n{}",
+                    self.format_digest(obj.digest()?, repo).await?,
+                    "chunk list:".green()
+                );
+                println!(
+                    " {} {}",
+                    "size:".bright_blue(),
+                    spfs::io::format_size(obj.size())
+                );
+                println!(" {} {}", "chunks:".bright_blue(), obj.len());
+                if verbosity > 0 {
+                    for chunk in obj.iter_chunks() {
+                        println!(
+                            "  - {} {}",
+                            self.format_digest(*chunk.payload(), repo).await?,
+                            spfs::io::format_size(chunk.size_())
+                        );
+                    }
+                }
             }
         }
         Ok(())
@@ -323,3 +358,15 @@ impl CmdInfo {
         Ok(())
     }
 }
+
+/// Print the descriptive labels of a layer or platform, if it has any
+fn print_labels<'a>(labels: impl Iterator<Item = (&'a str, &'a str)>) {
+    let mut labels = labels.peekable();
+    if labels.peek().is_none() {
+        return;
+    }
+    println!(" {}", "labels:".bright_blue());
+    for (key, value) in labels {
+        println!("  {} {value}", format!("{key}:").bright_blue());
+    }
+}
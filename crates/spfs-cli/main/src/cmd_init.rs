@@ -5,7 +5,7 @@
 use std::path::PathBuf;
 
 use clap::{Args, Subcommand};
-use miette::Result;
+use miette::{Context, IntoDiagnostic, Result};
 
 /// Create an empty filesystem repository
 #[derive(Debug, Args)]
@@ -27,16 +27,63 @@ pub enum InitSubcommand {
     ///
     /// Does nothing when run on an existing repository
     Repo {
+        /// The algorithm used to digest the file payloads in the new repository
+        ///
+        /// One of sha256 or blake3. Defaults to the 'storage.digest_algorithm'
+        /// setting of the spfs config. Existing repositories continue to use
+        /// the algorithm that they were created with.
+        #[clap(long)]
+        digest_algorithm: Option<spfs::encoding::DigestAlgorithm>,
+
+        /// The oldest version of spk/spfs that is allowed to use the repository
+        ///
+        /// Older clients will refuse to open the repository with a message
+        /// asking them to upgrade. Given with --require-feature, these replace
+        /// any requirements that the repository already declares.
+        #[clap(long, value_name = "VERSION")]
+        min_client_version: Option<String>,
+
+        /// A repository feature that clients must support in order to use it
+        #[clap(
+            long = "require-feature",
+            value_name = "FEATURE",
+            value_parser = clap::builder::PossibleValuesParser::new(spfs::storage::SUPPORTED_FEATURES),
+        )]
+        required_features: Vec<String>,
+
         /// The root of the new repository
         path: PathBuf,
     },
 }
 
 impl InitSubcommand {
-    pub async fn run(&self, _config: &spfs::Config) -> Result<i32> {
+    pub async fn run(&self, config: &spfs::Config) -> Result<i32> {
         match self {
-            Self::Repo { path } => {
-                spfs::storage::fs::FsRepository::create(&path).await?;
+            Self::Repo {
+                digest_algorithm,
+                min_client_version,
+                required_features,
+                path,
+            } => {
+                let digest_algorithm = digest_algorithm.unwrap_or(config.storage.digest_algorithm);
+                let repo = spfs::storage::fs::OpenFsRepository::create_with_digest_algorithm(
+                    &path,
+                    digest_algorithm,
+                )
+                .await?;
+                if min_client_version.is_some() || !required_features.is_empty() {
+                    let min_client_version = min_client_version
+                        .as_deref()
+                        .map(str::parse)
+                        .transpose()
+                        .into_diagnostic()
+                        .wrap_err("Invalid minimum client version")?;
+                    let requirements = spfs::storage::ClientRequirements {
+                        min_client_version,
+                        features: required_features.iter().cloned().collect(),
+                    };
+                    repo.set_client_requirements(&requirements)?;
+                }
                 Ok(0)
             }
         }
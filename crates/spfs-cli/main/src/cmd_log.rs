@@ -17,6 +17,14 @@ pub struct CmdLog {
     #[clap(long, short)]
     remote: Option<String>,
 
+    /// Skip this many of the most recent entries in the history
+    #[clap(long, default_value_t = 0)]
+    skip: usize,
+
+    /// Show at most this many entries from the history
+    #[clap(long, short = 'n')]
+    max_count: Option<usize>,
+
     /// The tag to show history of
     tag: String,
 }
@@ -26,10 +34,18 @@ impl CmdLog {
         let repo = spfs::config::open_repository_from_string(config, self.remote.as_ref()).await?;
 
         let tag = spfs::tracking::TagSpec::parse(&self.tag)?;
-        let mut tag_stream = repo.read_tag(&tag).await?.enumerate();
+        let tag_stream = match self.max_count {
+            Some(limit) => {
+                let page = repo.read_tag_page(&tag, self.skip, limit).await?;
+                futures::stream::iter(page.into_iter().map(Ok)).boxed()
+            }
+            None => repo.read_tag(&tag).await?.skip(self.skip).boxed(),
+        };
+        let mut tag_stream = tag_stream.enumerate();
         while let Some((i, tag)) = tag_stream.next().await {
             let tag = tag?;
-            let spec = spfs::tracking::build_tag_spec(tag.org(), tag.name(), i as u64)?;
+            let version = (self.skip + i) as u64;
+            let spec = spfs::tracking::build_tag_spec(tag.org(), tag.name(), version)?;
             let spec_str = spec.to_string();
             println!(
                 "{} {} {} {}",
@@ -0,0 +1,120 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::time::Duration;
+
+use clap::Args;
+use futures::future::try_join_all;
+use miette::Result;
+use spfs::mirror::{Mirror, TagFilter};
+use spfs_cli_common as cli;
+
+/// Continuously sync new and updated tags to one or more repositories
+///
+/// The source repository is checked for changed tags every interval,
+/// and any that are selected by the include/exclude patterns are synced
+/// to all destinations. Tags that fail to sync are retried on the next
+/// check. The command runs until interrupted, unless --once is given.
+#[derive(Debug, Args)]
+pub struct CmdMirror {
+    #[clap(flatten)]
+    sync: cli::Sync,
+
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// The name or address of the repository to mirror tags from
+    ///
+    /// Defaults to the local repository
+    #[clap(long, short)]
+    from: Option<String>,
+
+    /// Only mirror tags that match this glob pattern (eg: 'spk/pkg/**')
+    ///
+    /// Patterns are matched against the tag path, and can be given
+    /// multiple times. When not given, all tags are included.
+    #[clap(long, value_name = "PATTERN")]
+    include: Vec<String>,
+
+    /// Do not mirror tags that match this glob pattern
+    ///
+    /// Exclusions take precedence over any --include patterns, and
+    /// can be given multiple times.
+    #[clap(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// The number of seconds to wait between checks for changed tags
+    #[clap(long, default_value_t = 30)]
+    interval: u64,
+
+    /// Sync the selected tags once and exit, instead of running continuously
+    #[clap(long)]
+    once: bool,
+
+    /// The name(s) or address(es) of the repositories to mirror tags into
+    #[clap(value_name = "DEST", required = true)]
+    destinations: Vec<String>,
+}
+
+impl CmdMirror {
+    pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
+        let filter = TagFilter::new(&self.include, &self.exclude)?;
+        let (source, destinations) = tokio::try_join!(
+            spfs::config::open_repository_from_string(config, self.from.as_ref()),
+            try_join_all(
+                self.destinations
+                    .iter()
+                    .map(|name| spfs::config::open_repository_from_string(config, Some(name)))
+            ),
+        )?;
+
+        // the latest version of each tag is always synced when mirroring
+        self.sync.sync = true;
+        let mut mirror = Mirror::new(&source, destinations.iter())
+            .with_filter(filter)
+            .with_policy(self.sync.sync_policy())
+            .with_max_concurrent_manifests(self.sync.max_concurrent_manifests)
+            .with_max_concurrent_payloads(self.sync.max_concurrent_payloads);
+        if let Some(limit) = self.sync.limit_rate {
+            mirror = mirror.with_rate_limit(limit);
+        }
+
+        let interval = Duration::from_secs(self.interval);
+        let shutdown = tokio::signal::ctrl_c();
+        tokio::pin!(shutdown);
+        loop {
+            let result = tokio::select! {
+                result = mirror.sync_once() => result,
+                _ = &mut shutdown => break,
+            };
+            let failed = match result {
+                Ok(result) => {
+                    for tag in result.synced.iter() {
+                        tracing::info!("mirrored {tag}");
+                    }
+                    for (tag, err) in result.failed.iter() {
+                        tracing::error!("failed to mirror {tag}: {err:?}");
+                    }
+                    if !result.synced.is_empty() {
+                        tracing::info!("{}", spfs::io::format_sync_summary(&result.summary));
+                    }
+                    !result.failed.is_empty()
+                }
+                Err(err) => {
+                    tracing::error!("failed to check for changed tags: {err:?}");
+                    true
+                }
+            };
+            if self.once {
+                return Ok(failed as i32);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = &mut shutdown => break,
+            }
+        }
+        tracing::info!("stopping mirror...");
+        Ok(0)
+    }
+}
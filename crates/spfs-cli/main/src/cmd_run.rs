@@ -5,10 +5,11 @@
 use std::collections::BTreeMap;
 use std::ffi::OsString;
 use std::io;
-use std::time::Instant;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use clap::{ArgGroup, Args};
-use miette::{miette, Context, IntoDiagnostic, Result};
+use miette::{bail, miette, Context, IntoDiagnostic, Result};
 use spfs::graph::object::EncodingFormat;
 use spfs::prelude::*;
 use spfs::runtime::KeyValuePairBuf;
@@ -107,6 +108,12 @@ impl Annotation {
 }
 
 /// Run a program in a configured spfs environment
+/// Where the output of detached runtime commands is logged
+const DETACHED_LOGS_DIR: &str = "/tmp/spfs-runtime/logs";
+
+/// How often to check whether a detached runtime is ready to be used
+const DETACHED_READY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Args)]
 #[clap(group(
     ArgGroup::new("runtime_id")
@@ -135,20 +142,34 @@ pub struct CmdRun {
     /// Use to keep the runtime around rather than deleting it when
     /// the process exits. This is best used with '--name NAME' to
     /// make rerunning the runtime easier at a later time.
-    #[clap(short, long, env = "SPFS_KEEP_RUNTIME")]
+    #[clap(short, long, visible_alias = "keep", env = "SPFS_KEEP_RUNTIME")]
     pub keep_runtime: bool,
 
     /// Provide a name for this runtime to make it easier to identify
-    #[clap(long)]
+    #[clap(long, visible_alias = "name")]
     pub runtime_name: Option<String>,
 
     /// Name of an existing durable runtime to reuse for this run
-    #[clap(long, value_name = "RUNTIME_NAME")]
+    ///
+    /// The runtime's stack and any edits made in it are restored
+    /// from the last time that it was run.
+    #[clap(long, visible_alias = "existing", value_name = "RUNTIME_NAME")]
     pub rerun: Option<String>,
 
     #[clap(flatten)]
     pub annotation: Annotation,
 
+    /// Start the runtime in the background and print its name
+    ///
+    /// The command is run without a terminal, and its output is written
+    /// to the log file that is recorded in the runtime's status, under
+    /// /tmp/spfs-runtime/logs. This returns once the runtime has been
+    /// set up and is ready to be joined. When no command is given, a small supervisor process keeps the
+    /// runtime alive instead. Use `spfs join <NAME>` to run more
+    /// commands in the runtime and `spfs runtime stop <NAME>` to end it.
+    #[clap(long)]
+    pub detach: bool,
+
     /// The tag or id of the desired runtime
     ///
     /// Use '-' to request an empty environment
@@ -324,6 +345,14 @@ impl CmdRun {
         runtime: &mut spfs::runtime::Runtime,
         start_time: &Instant,
     ) -> Result<i32> {
+        if self.detach && self.command.is_empty() {
+            // an interactive shell would exit right away without a
+            // terminal, so the runtime is held open by the supervisor
+            let spfs = std::env::current_exe()
+                .into_diagnostic()
+                .wrap_err("Failed to locate the spfs executable")?;
+            self.command = vec![spfs.into_os_string(), "runtime".into(), "supervise".into()];
+        }
         let command = match self.command.first() {
             Some(c) => c.clone(),
             None => Default::default(),
@@ -338,6 +367,11 @@ impl CmdRun {
                 .map(|s| s.to_string_lossy().to_string()),
         );
         runtime.status.editable = self.edit;
+        runtime.status.detached = self.detach;
+        if self.detach {
+            let log_file = Path::new(DETACHED_LOGS_DIR).join(format!("{}.log", runtime.name()));
+            runtime.status.log_file = Some(log_file);
+        }
         runtime.save_state_to_storage().await?;
 
         tracing::debug!("resolving entry process");
@@ -351,8 +385,100 @@ impl CmdRun {
             sync_time.as_secs_f64().to_string(),
         );
 
+        if self.detach {
+            return Self::spawn_detached(runtime, cmd).await;
+        }
+
         cmd.exec()
             .map(|_| 0)
             .wrap_err("Failed to execute runtime command")
     }
+
+    /// Start the runtime command in the background, printing the
+    /// name of the runtime so that it can be joined or stopped later.
+    ///
+    /// This waits until the runtime has an owner and a monitor, so
+    /// that it can be joined as soon as this returns.
+    #[cfg(unix)]
+    async fn spawn_detached(
+        runtime: &mut spfs::runtime::Runtime,
+        cmd: spfs::bootstrap::Command,
+    ) -> Result<i32> {
+        use std::os::unix::process::CommandExt;
+
+        let log_file = runtime
+            .status
+            .log_file
+            .clone()
+            .ok_or_else(|| miette!("Detached runtime has no log file"))?;
+        let (stdout, stderr) = open_log_file(&log_file)?;
+        let mut cmd = cmd.into_std();
+        cmd.stdin(std::process::Stdio::null())
+            .stdout(stdout)
+            .stderr(stderr)
+            // keep the runtime out of the foreground process group
+            // so that it does not receive signals from the terminal
+            .process_group(0);
+        let mut child = cmd
+            .spawn()
+            .into_diagnostic()
+            .wrap_err("Failed to start detached runtime command")?;
+        tracing::debug!("detached runtime process started with pid {}", child.id());
+
+        loop {
+            // the runtime is removed by its monitor if the command
+            // fails early, so the command is checked before the runtime
+            if let Some(status) = child.try_wait().into_diagnostic()? {
+                bail!(
+                    "Detached runtime command exited before the runtime was ready ({status}), see {}",
+                    log_file.display()
+                );
+            }
+            if let Err(err) = runtime.reload_state_from_storage().await {
+                if child.try_wait().into_diagnostic()?.is_some() {
+                    continue;
+                }
+                return Err(err.into());
+            }
+            if runtime.status.owner.is_some() && runtime.status.monitor.is_some() {
+                break;
+            }
+            tokio::time::sleep(DETACHED_READY_POLL_INTERVAL).await;
+        }
+        tracing::debug!("detached runtime output is logged to {}", log_file.display());
+        println!("{}", runtime.name());
+        Ok(0)
+    }
+
+    #[cfg(windows)]
+    async fn spawn_detached(
+        _runtime: &mut spfs::runtime::Runtime,
+        _cmd: spfs::bootstrap::Command,
+    ) -> Result<i32> {
+        Err(miette!("Detached runtimes are not supported on windows"))
+    }
+}
+}
+
+/// Open a new log file for the output of a detached runtime,
+/// returning a handle for each of stdout and stderr.
+#[cfg(unix)]
+fn open_log_file(path: &Path) -> Result<(std::fs::File, std::fs::File)> {
+    if let Some(parent) = path.parent() {
+        // the logs of all users are kept together, like the runtimes
+        spfs::runtime::makedirs_with_perms(parent, 0o777)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to create log directory {}", parent.display()))?;
+    }
+    let stdout = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to open log file {}", path.display()))?;
+    let stderr = stdout
+        .try_clone()
+        .into_diagnostic()
+        .wrap_err("Failed to duplicate log file handle")?;
+    Ok((stdout, stderr))
 }
@@ -25,6 +25,9 @@ pub enum Command {
     List(super::cmd_runtime_list::CmdRuntimeList),
     Prune(super::cmd_runtime_prune::CmdRuntimePrune),
     Remove(super::cmd_runtime_remove::CmdRuntimeRemove),
+    Stop(super::cmd_runtime_stop::CmdRuntimeStop),
+    #[clap(hide = true)]
+    Supervise(super::cmd_runtime_supervise::CmdRuntimeSupervise),
 }
 
 impl Command {
@@ -34,6 +37,8 @@ impl Command {
             Self::List(cmd) => cmd.run(config).await,
             Self::Prune(cmd) => cmd.run(config).await,
             Self::Remove(cmd) => cmd.run(config).await,
+            Self::Stop(cmd) => cmd.run(config).await,
+            Self::Supervise(cmd) => cmd.run(config).await,
         }
     }
 }
@@ -18,6 +18,14 @@ pub struct CmdRuntimeList {
     /// Only print the name of each runtime, no additional data
     #[clap(short, long)]
     quiet: bool,
+
+    /// Only list durable runtimes, which are kept after their process exits
+    #[clap(long)]
+    durable: bool,
+
+    /// Only list runtimes that were started in the background with `spfs run --detach`
+    #[clap(long)]
+    detached: bool,
 }
 
 impl CmdRuntimeList {
@@ -32,9 +40,14 @@ impl CmdRuntimeList {
 
         let known_processes = find_processes_and_mount_namespaces().await?;
 
-        let mut runtimes = runtime_storage.iter_runtimes().await;
+        let mut runtimes = if self.durable {
+            runtime_storage.iter_durable_runtimes().await
+        } else {
+            runtime_storage.iter_runtimes().await
+        };
         while let Some(runtime) = runtimes.next().await {
             match runtime {
+                Ok(runtime) if self.detached && !runtime.status.detached => continue,
                 Ok(runtime) => {
                     let mut message = runtime.name().to_string();
                     if !self.quiet {
@@ -114,7 +127,7 @@ impl CmdRuntimeList {
                         };
 
                         message = format!(
-                            "{message:37}\trunning={}\tpid={:<7}\teditable={}\tdurable={}\tstatus={process_status}",
+                            "{message:37}\trunning={}\tpid={:<7}\teditable={}\tdurable={}\tdetached={}\tstatus={process_status}",
                             runtime.status.running,
                             runtime
                                 .status
@@ -123,6 +136,7 @@ impl CmdRuntimeList {
                                 .unwrap_or_else(|| "unknown".to_string()),
                             runtime.status.editable,
                             runtime.is_durable(),
+                            runtime.status.detached,
                         )
                     }
                     println!("{message}");
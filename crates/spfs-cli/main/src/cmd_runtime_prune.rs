@@ -2,14 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use clap::Args;
 use miette::Result;
 #[cfg(unix)]
 use procfs::Current;
+use spfs_cli_common as cli;
 use tokio_stream::StreamExt;
 
-use super::cmd_runtime_remove::is_monitor_running;
+use super::cmd_runtime_remove::{is_monitor_running, is_process_running};
 
 /// Find and remove runtimes from the repository based on a pruning strategy
 #[derive(Debug, Args)]
@@ -35,6 +36,16 @@ pub struct CmdRuntimePrune {
     /// Remove runtimes started before last reboot
     #[clap(long)]
     from_before_boot: bool,
+
+    /// Remove durable runtimes that have not been used within the given
+    /// age (eg: '4w'), which are otherwise never pruned
+    #[clap(long, value_name = "AGE", value_parser = cli::age_to_date)]
+    expire_durable: Option<DateTime<Utc>>,
+
+    /// Remove runtimes started with `spfs run --detach` whose owner
+    /// and monitor processes are no longer running on this machine
+    #[clap(long)]
+    abandoned: bool,
 }
 
 impl CmdRuntimePrune {
@@ -48,11 +59,23 @@ impl CmdRuntimePrune {
         };
 
         // TODO: Clap 4.x AppGroup supports grouping flags better.
-        if !self.from_before_boot {
+        if !self.from_before_boot && self.expire_durable.is_none() && !self.abandoned {
             tracing::info!("No pruning strategy selected.");
             return Ok(1);
         }
 
+        if let Some(cutoff) = self.expire_durable {
+            for name in runtime_storage.expire_durable_runtimes(cutoff).await? {
+                tracing::info!("Expired durable runtime {name}");
+            }
+        }
+        if self.abandoned {
+            self.prune_abandoned(&runtime_storage).await;
+        }
+        if !self.from_before_boot {
+            return Ok(0);
+        }
+
         let default_author = spfs::runtime::Author::default();
 
         #[cfg(unix)]
@@ -146,4 +169,44 @@ impl CmdRuntimePrune {
 
         Ok(0)
     }
+
+    /// Remove the detached runtimes of this machine that no longer have
+    /// any process to keep them alive or to clean them up.
+    async fn prune_abandoned(&self, runtime_storage: &spfs::runtime::Storage) {
+        let default_author = spfs::runtime::Author::default();
+        let mut runtimes = runtime_storage.iter_runtimes().await;
+        while let Some(runtime) = runtimes.next().await {
+            let runtime = match runtime {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    tracing::error!("Failed to read runtime: {}", err);
+                    continue;
+                }
+            };
+            if !runtime.status.detached
+                || runtime.author.host_name != default_author.host_name
+                || (!self.ignore_user && runtime.author.user_name != default_author.user_name)
+            {
+                continue;
+            }
+            let owner_running = runtime.status.owner.is_some_and(is_process_running);
+            if owner_running || is_monitor_running(&runtime) {
+                continue;
+            }
+            let runtime_name = runtime.name().to_string();
+            let result = if runtime.is_durable() {
+                // durable runtimes are kept, but must be reset so that they can be rerun
+                let mut runtime = runtime;
+                runtime.reinit_for_reuse_and_save_to_storage().await
+            } else {
+                runtime_storage.remove_runtime(runtime.name()).await
+            };
+            match result {
+                Ok(()) => tracing::info!("Pruned abandoned runtime {name}", name = runtime_name),
+                Err(err) => {
+                    tracing::error!("Failed to prune runtime {name}: {err}", name = runtime_name)
+                }
+            }
+        }
+    }
 }
@@ -111,7 +111,7 @@ pub(crate) fn is_monitor_running(rt: &spfs::runtime::Runtime) -> bool {
 }
 
 #[cfg(unix)]
-fn is_process_running(pid: u32) -> bool {
+pub(crate) fn is_process_running(pid: u32) -> bool {
     // sending a null signal to the pid just allows us to check
     // if the process actually exists without affecting it
     let pid = nix::unistd::Pid::from_raw(pid as i32);
@@ -119,7 +119,7 @@ fn is_process_running(pid: u32) -> bool {
 }
 
 #[cfg(windows)]
-fn is_process_running(pid: u32) -> bool {
+pub(crate) fn is_process_running(pid: u32) -> bool {
     // PROCESS_SYNCHRONIZE seems like the most limited access we can request,
     // which simply allows us to wait on the PID
     let access = windows::Win32::System::Threading::PROCESS_SYNCHRONIZE;
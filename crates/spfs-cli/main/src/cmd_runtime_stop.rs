@@ -0,0 +1,84 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::Args;
+use miette::{bail, Result};
+
+/// Stop a runtime that was started in the background
+///
+/// The owner process of the runtime is asked to terminate, after
+/// which its monitor asks any processes that were joined into the
+/// runtime to terminate as well, and then cleans up the runtime.
+#[derive(Debug, Args)]
+pub struct CmdRuntimeStop {
+    /// Stop the runtime even if it was not started with `spfs run --detach`
+    #[clap(short, long)]
+    force: bool,
+
+    /// The name/id of the runtime to stop
+    name: String,
+}
+
+impl CmdRuntimeStop {
+    pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
+        let runtime_storage = config.get_runtime_storage().await?;
+        let runtime = runtime_storage.read_runtime(&self.name).await?;
+
+        if !runtime.status.detached && !self.force {
+            tracing::error!("Won't stop, this runtime was not started in the background");
+            tracing::error!(" > use --force to stop its owner process anyway");
+            return Ok(1);
+        }
+
+        let default_author = spfs::runtime::Author::default();
+        if runtime.author.host_name != default_author.host_name {
+            bail!(
+                "This runtime is running on a different machine: '{}'",
+                runtime.author.host_name
+            );
+        }
+
+        let Some(owner) = runtime.status.owner else {
+            tracing::error!("This runtime does not have an owner process to stop");
+            tracing::error!(" > use `spfs runtime prune --abandoned` to clean it up");
+            return Ok(1);
+        };
+        if !stop_owner(&runtime, owner).await? {
+            tracing::warn!("The owner process ({owner}) is no longer running");
+            tracing::warn!(" > use `spfs runtime prune --abandoned` to clean it up");
+            return Ok(1);
+        }
+        tracing::info!("Stopped runtime {}", runtime.name());
+        Ok(0)
+    }
+}
+
+/// Ask the owner of the runtime to terminate, returning false if it does not exist.
+///
+/// The owner's pid may have been reused by an unrelated process after it
+/// exited, so it is only signaled while it is still in the runtime's
+/// mount namespace.
+#[cfg(unix)]
+async fn stop_owner(runtime: &spfs::runtime::Runtime, pid: u32) -> Result<bool> {
+    use nix::errno::Errno;
+    use nix::sys::signal::{kill, Signal};
+
+    let Some(expected) = &runtime.config.mount_namespace else {
+        bail!("Cannot identify the owner process ({pid}), the runtime has no mount namespace");
+    };
+    match spfs::monitor::identify_mount_namespace_of_process(pid).await? {
+        Some(ns) if &ns == expected => {}
+        _ => return Ok(false),
+    }
+    match kill(nix::unistd::Pid::from_raw(pid as i32), Signal::SIGTERM) {
+        Ok(()) => Ok(true),
+        Err(Errno::ESRCH) => Ok(false),
+        Err(err) => bail!("Failed to stop process {pid}: {err}"),
+    }
+}
+
+#[cfg(windows)]
+async fn stop_owner(_runtime: &spfs::runtime::Runtime, _pid: u32) -> Result<bool> {
+    bail!("Stopping runtimes is not supported on windows")
+}
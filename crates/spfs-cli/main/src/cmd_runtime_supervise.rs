@@ -0,0 +1,42 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::Args;
+use miette::{IntoDiagnostic, Result};
+
+/// Keep the current runtime alive until this process is stopped
+///
+/// This is the supervisor that `spfs run --detach` leaves running in a
+/// runtime when no command is given. The runtime is cleaned up by its
+/// monitor as usual once this process and any others have exited.
+#[derive(Debug, Args)]
+pub struct CmdRuntimeSupervise {}
+
+impl CmdRuntimeSupervise {
+    #[cfg(unix)]
+    pub async fn run(&mut self, _config: &spfs::Config) -> Result<i32> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).into_diagnostic()?;
+        let mut interrupt = signal(SignalKind::interrupt()).into_diagnostic()?;
+        // the supervisor is not attached to a terminal, so there
+        // is no session for a hangup to meaningfully end
+        let mut hangup = signal(SignalKind::hangup()).into_diagnostic()?;
+        loop {
+            tokio::select! {
+                _ = terminate.recv() => break,
+                _ = interrupt.recv() => break,
+                _ = hangup.recv() => continue,
+            }
+        }
+        tracing::debug!("supervisor stopped, releasing the runtime");
+        Ok(0)
+    }
+
+    #[cfg(windows)]
+    pub async fn run(&mut self, _config: &spfs::Config) -> Result<i32> {
+        tokio::signal::ctrl_c().await.into_diagnostic()?;
+        Ok(0)
+    }
+}
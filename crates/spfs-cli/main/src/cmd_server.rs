@@ -33,6 +33,16 @@ pub struct CmdServer {
     /// The address to listen on for http requests
     #[clap(default_value = "0.0.0.0:7787")]
     http_address: std::net::SocketAddr,
+
+    /// Collect server metrics and serve them in prometheus format
+    /// from the '/metrics' path of the http server
+    #[clap(long)]
+    metrics: bool,
+
+    /// The name of the spk repository being served, which is used
+    /// when checking its access rules (defaults to the remote name)
+    #[clap(long)]
+    spk_repository_name: Option<String>,
 }
 
 impl CmdServer {
@@ -40,44 +50,31 @@ impl CmdServer {
         let repo = spfs::config::open_repository_from_string(config, self.remote.as_ref()).await?;
         let repo = std::sync::Arc::new(repo);
 
-        let payload_service =
-            spfs::server::PayloadService::new(repo.clone(), self.payloads_root.clone());
-        let http_server = {
-            let payload_service = payload_service.clone();
-            hyper::Server::bind(&self.http_address).serve(hyper::service::make_service_fn(
-                move |_| {
-                    let s = payload_service.clone();
-                    async move { Ok::<_, std::convert::Infallible>(s) }
-                },
-            ))
-        };
-        let http_future = http_server.with_graceful_shutdown(async {
-            if let Err(err) = tokio::signal::ctrl_c().await {
-                tracing::error!(?err, "Failed to setup graceful shutdown handler");
-            };
-            tracing::info!("shutting down http server...");
-        });
-        let grpc_future = tonic::transport::Server::builder()
-            .add_service(spfs::server::Repository::new_srv())
-            .add_service(spfs::server::TagService::new_srv(repo.clone()))
-            .add_service(spfs::server::DatabaseService::new_srv(repo))
-            .add_service(payload_service.into_srv())
-            .serve_with_shutdown(self.grpc_address, async {
+        // the access rules of an spk repository are stored in the
+        // repository itself, and an empty set of rules allows everything
+        let name = self
+            .spk_repository_name
+            .as_deref()
+            .or(self.remote.as_deref())
+            .unwrap_or("local");
+        let policy_repo =
+            spfs::config::open_repository_from_string(config, self.remote.as_ref()).await?;
+        let policy = spk_storage::AccessPolicy::from_spfs_repository(name, policy_repo)?;
+
+        let mut server = spfs::server::ServerBuilder::new(repo, self.payloads_root.clone())
+            .with_tag_policy(std::sync::Arc::new(policy));
+        if self.metrics {
+            server = server.with_metrics(std::sync::Arc::new(spfs::server::Metrics::new()));
+        }
+        let result = server
+            .serve(self.grpc_address, self.http_address, async {
                 if let Err(err) = tokio::signal::ctrl_c().await {
                     tracing::error!(?err, "Failed to setup graceful shutdown handler");
                 };
-                tracing::info!("shutting down gRPC server...");
-            });
-        tracing::info!("listening on: {}, {}", self.grpc_address, self.http_address);
-
-        // TODO: stop the other server when one fails so that
-        // the process can exit
-        let (grpc_result, http_result) = tokio::join!(grpc_future, http_future,);
-        if let Err(err) = grpc_result {
-            tracing::error!("gRPC server failed: {:?}", err);
-        }
-        if let Err(err) = http_result {
-            tracing::error!("http server failed: {:?}", err);
+            })
+            .await;
+        if let Err(err) = result {
+            tracing::error!("{err}");
         }
         Ok(0)
     }
@@ -31,7 +31,10 @@ pub struct CmdShell {
     pub no_edit: bool,
 
     /// Name of a previously run durable runtime to reuse for this run
-    #[clap(long, value_name = "RUNTIME_NAME")]
+    ///
+    /// The runtime's stack and any edits made in it are restored
+    /// from the last time that it was run.
+    #[clap(long, visible_alias = "existing", value_name = "RUNTIME_NAME")]
     pub rerun: Option<String>,
 
     /// Requires --rerun. Force reset the process fields of the
@@ -40,13 +43,13 @@ pub struct CmdShell {
     pub force: bool,
 
     /// Provide a name for this runtime to make it easier to identify
-    #[clap(long)]
+    #[clap(long, visible_alias = "name")]
     runtime_name: Option<String>,
 
     /// Use to keep the runtime around rather than deleting it when
     /// the process exits. This is best used with '--name NAME' to
     /// make rerunning the runtime easier at a later time.
-    #[clap(short, long, env = "SPFS_KEEP_RUNTIME")]
+    #[clap(short, long, visible_alias = "keep", env = "SPFS_KEEP_RUNTIME")]
     pub keep_runtime: bool,
 
     #[clap(flatten)]
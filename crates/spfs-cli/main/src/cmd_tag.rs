@@ -3,9 +3,11 @@
 // https://github.com/spkenv/spk
 
 use clap::Args;
+use colored::Colorize;
 use miette::Result;
 use spfs::prelude::*;
 use spfs::{self};
+use tokio_stream::StreamExt;
 
 /// Tag an object
 #[derive(Debug, Args)]
@@ -14,12 +16,20 @@ pub struct CmdTag {
     #[clap(long, short)]
     remote: Option<String>,
 
+    /// Instead of creating tags, report the tags that are pushed to
+    /// the repository as they change, until interrupted
+    ///
+    /// Only tags whose path matches the given glob pattern are reported,
+    /// eg: 'spk/spec/my-pkg/**'. A '*' does not match across '/'.
+    #[clap(long, value_name = "PATTERN", conflicts_with_all = ["reference", "tags"])]
+    watch: Option<String>,
+
     /// The reference or id of the item to tag
-    #[clap(value_name = "TARGET_REF")]
-    reference: String,
+    #[clap(value_name = "TARGET_REF", required_unless_present = "watch")]
+    reference: Option<String>,
 
     /// The tag(s) to point to the the given target
-    #[clap(value_name = "TAG", required = true)]
+    #[clap(value_name = "TAG", required_unless_present = "watch")]
     tags: Vec<String>,
 }
 
@@ -27,7 +37,21 @@ impl CmdTag {
     pub async fn run(&mut self, config: &spfs::Config) -> Result<i32> {
         let repo = spfs::config::open_repository_from_string(config, self.remote.as_ref()).await?;
 
-        let target = repo.read_ref(self.reference.as_str()).await?.digest()?;
+        if let Some(pattern) = &self.watch {
+            let mut tags = repo.watch_tags(pattern);
+            while let Some(tag) = tags.try_next().await? {
+                println!(
+                    "{} {} {}",
+                    tag.path(),
+                    "->".cyan(),
+                    tag.target.to_string().dimmed()
+                );
+            }
+            return Ok(0);
+        }
+
+        let reference = self.reference.as_deref().unwrap_or_default();
+        let target = repo.read_ref(reference).await?.digest()?;
         for tag in self.tags.iter() {
             let tag = tag.parse()?;
             repo.push_tag(&tag, &target).await?;
@@ -0,0 +1,60 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./algorithm_test.rs"]
+mod algorithm_test;
+
+/// The hashing algorithm used to calculate the [`crate::Digest`] of file payloads.
+///
+/// Every algorithm produces a digest of the same size, so digests
+/// from different algorithms cannot be told apart by their bytes
+/// alone. A repository declares the algorithm that it uses, which
+/// must match that of any repository that it is synced with.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    /// The original spfs algorithm, used by all existing repositories
+    #[default]
+    Sha256,
+    /// A much faster algorithm, especially for large payloads
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    /// All of the supported algorithms
+    pub const ALL: [Self; 2] = [Self::Sha256, Self::Blake3];
+
+    /// The name of this algorithm, as used in configuration
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+}
+
+impl Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| Error::UnknownDigestAlgorithm(s.to_owned()))
+    }
+}
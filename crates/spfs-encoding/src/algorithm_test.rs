@@ -0,0 +1,55 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::io::Write;
+
+use rstest::rstest;
+
+use super::DigestAlgorithm;
+use crate::{Digest, Hasher, EMPTY_DIGEST};
+
+#[rstest]
+#[case(DigestAlgorithm::Sha256, Digest::from(EMPTY_DIGEST))]
+#[case(
+    DigestAlgorithm::Blake3,
+    Digest::from(*blake3::Hash::from_hex(
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+    ).unwrap().as_bytes())
+)]
+fn test_hasher_empty_digest(#[case] algorithm: DigestAlgorithm, #[case] expected: Digest) {
+    let hasher = Hasher::with_algorithm(algorithm, std::io::sink());
+    assert_eq!(hasher.algorithm(), algorithm);
+    assert_eq!(hasher.digest(), expected);
+}
+
+#[rstest]
+fn test_hasher_algorithms_differ() {
+    let digest = |algorithm| {
+        let mut hasher = Hasher::with_algorithm(algorithm, std::io::sink());
+        hasher.write_all(b"hello, world").unwrap();
+        hasher.digest()
+    };
+    assert_ne!(
+        digest(DigestAlgorithm::Sha256),
+        digest(DigestAlgorithm::Blake3),
+        "different algorithms should produce different digests"
+    );
+}
+
+#[rstest]
+#[case("sha256", DigestAlgorithm::Sha256)]
+#[case("blake3", DigestAlgorithm::Blake3)]
+#[case("BLAKE3", DigestAlgorithm::Blake3)]
+fn test_digest_algorithm_parse(#[case] source: &str, #[case] expected: DigestAlgorithm) {
+    let actual: DigestAlgorithm = source.parse().expect("should be a valid algorithm");
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn test_digest_algorithm_parse_unknown() {
+    assert!(
+        "md5".parse::<DigestAlgorithm>().is_err(),
+        "unsupported algorithms should not parse"
+    );
+}
@@ -56,4 +56,8 @@ pub enum Error {
         /// A copy of the invalid string
         given: String,
     },
+
+    /// The named digest algorithm is not one that is supported
+    #[error("Unknown digest algorithm '{0}', expected one of: sha256, blake3")]
+    UnknownDigestAlgorithm(String),
 }
@@ -12,13 +12,62 @@ use data_encoding::BASE32;
 use ring::digest::{Context, SHA256};
 use tokio::io::{AsyncRead, AsyncWrite};
 
-use super::{binary, Digest};
+use super::{binary, Digest, DigestAlgorithm};
 use crate::{Error, Result};
 
 #[cfg(test)]
 #[path = "./hash_test.rs"]
 mod hash_test;
 
+/// The running state of one of the supported hashing algorithms
+pub enum HashContext {
+    /// The state of a sha256 digest
+    Sha256(Context),
+    /// The state of a blake3 digest
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HashContext {
+    fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Sha256 => Self::Sha256(Context::new(&SHA256)),
+            DigestAlgorithm::Blake3 => Self::Blake3(Box::default()),
+        }
+    }
+
+    /// The algorithm that this context is calculating
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        match self {
+            Self::Sha256(_) => DigestAlgorithm::Sha256,
+            Self::Blake3(_) => DigestAlgorithm::Blake3,
+        }
+    }
+
+    /// Add the given bytes to the digest
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(ctx) => ctx.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    fn finish(self) -> Digest {
+        match self {
+            Self::Sha256(ctx) => {
+                let ring_digest = ctx.finish();
+                let bytes = match ring_digest.as_ref().try_into() {
+                    Err(err) => panic!("internal error: {err:?}"),
+                    Ok(b) => b,
+                };
+                Digest(bytes)
+            }
+            Self::Blake3(hasher) => Digest(*hasher.finalize().as_bytes()),
+        }
+    }
+}
+
 /// The Hasher calculates a [`Digest`] from the bytes written to it.
 ///
 /// A write-though target can optionally specified
@@ -29,8 +78,11 @@ mod hash_test;
 ///
 /// If constructed with a [`tokio::io::AsyncRead`] instance,
 /// the hasher will instead act like an `AsyncRead`.
+///
+/// Unless otherwise specified, the hasher uses the default
+/// [`DigestAlgorithm`].
 pub struct Hasher<T> {
-    ctx: Context,
+    ctx: HashContext,
     target: T,
 }
 
@@ -38,29 +90,31 @@ impl<T> Hasher<T> {
     /// The target of the hasher will receive a copy
     /// of all bytes that are written to it
     pub fn with_target(writer: T) -> Self {
+        Self::with_algorithm(DigestAlgorithm::default(), writer)
+    }
+
+    /// Like [`Self::with_target`], but using the given algorithm
+    pub fn with_algorithm(algorithm: DigestAlgorithm, writer: T) -> Self {
         Self {
-            ctx: Context::new(&SHA256),
+            ctx: HashContext::new(algorithm),
             target: writer,
         }
     }
 
+    /// The algorithm being used by this hasher
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.ctx.algorithm()
+    }
+
     /// Finalize the hasher and return the digest
     pub fn digest(self) -> Digest {
-        let ring_digest = self.ctx.finish();
-        let bytes = match ring_digest.as_ref().try_into() {
-            Err(err) => panic!("internal error: {err:?}"),
-            Ok(b) => b,
-        };
-        Digest(bytes)
+        self.ctx.finish()
     }
 }
 
 impl Default for Hasher<std::io::Sink> {
     fn default() -> Self {
-        Self {
-            ctx: Context::new(&SHA256),
-            target: std::io::sink(),
-        }
+        Self::with_target(std::io::sink())
     }
 }
 
@@ -73,10 +127,7 @@ impl Hasher<std::io::Sink> {
 
 impl Default for Hasher<tokio::io::Sink> {
     fn default() -> Self {
-        Self {
-            ctx: Context::new(&SHA256),
-            target: tokio::io::sink(),
-        }
+        Self::with_target(tokio::io::sink())
     }
 }
 
@@ -88,7 +139,7 @@ impl Hasher<tokio::io::Sink> {
 }
 
 impl<T> std::ops::Deref for Hasher<T> {
-    type Target = Context;
+    type Target = HashContext;
 
     fn deref(&self) -> &Self::Target {
         &self.ctx
@@ -151,8 +202,16 @@ where
 impl Hasher<()> {
     /// Reads the given async reader to completion, returning
     /// the digest of its contents.
-    pub async fn hash_async_reader(mut reader: impl AsyncRead + Unpin) -> Result<Digest> {
-        let mut hasher = Hasher::new_async();
+    pub async fn hash_async_reader(reader: impl AsyncRead + Unpin) -> Result<Digest> {
+        Self::hash_async_reader_with(DigestAlgorithm::default(), reader).await
+    }
+
+    /// Like [`Self::hash_async_reader`], but using the given algorithm
+    pub async fn hash_async_reader_with(
+        algorithm: DigestAlgorithm,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> Result<Digest> {
+        let mut hasher = Hasher::with_algorithm(algorithm, tokio::io::sink());
         tokio::io::copy(&mut reader, &mut hasher)
             .await
             .map_err(Error::FailedRead)?;
@@ -12,10 +12,12 @@
 
 #![deny(missing_docs)]
 
+mod algorithm;
 mod binary;
 mod error;
 mod hash;
 
+pub use algorithm::DigestAlgorithm;
 pub use binary::{
     consume_header,
     read_digest,
@@ -31,7 +33,7 @@ pub use binary::{
     write_uint8,
 };
 pub use error::{Error, Result};
-pub use hash::{Decodable, Digestible, Encodable, Hasher, PartialDigest};
+pub use hash::{Decodable, Digestible, Encodable, HashContext, Hasher, PartialDigest};
 pub use spfs_proto::{parse_digest, Digest, DIGEST_SIZE, EMPTY_DIGEST, NULL_DIGEST};
 
 /// # Encoding Prelude
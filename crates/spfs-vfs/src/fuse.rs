@@ -122,6 +122,7 @@ impl Filesystem {
             entries,
             user_data: _,
             legacy_size,
+            xattrs,
         } = entry;
 
         let inode = self.allocate_inode();
@@ -136,6 +137,7 @@ impl Filesystem {
             entries,
             user_data: inode,
             legacy_size,
+            xattrs,
         });
         self.inodes.insert(inode, Arc::clone(&entry));
         entry
@@ -387,6 +389,17 @@ impl Filesystem {
                             break;
                         }
                         Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                            // chunked payloads have no single file, and
+                            // can only be streamed by reassembling the chunks
+                            #[cfg(feature = "fuse-backend-abi-7-31")]
+                            if let Ok((stream, _)) = fs_repo.open_payload(*digest).await {
+                                handle = Some(Handle::BlobStream {
+                                    entry,
+                                    stream: tokio::sync::Mutex::new(stream),
+                                });
+                                flags |= FOPEN_NONSEEKABLE | FOPEN_STREAM;
+                                break;
+                            }
                             continue;
                         }
                         Err(err) => err!(reply, err),
@@ -131,6 +131,7 @@ impl Mount {
             entries,
             user_data: _,
             legacy_size,
+            xattrs,
         } = entry;
 
         let inode = self.allocate_inode();
@@ -145,6 +146,7 @@ impl Mount {
             entries,
             user_data: inode,
             legacy_size,
+            xattrs,
         });
         self.inodes.insert(inode, Arc::clone(&entry));
         entry
@@ -7,7 +7,7 @@ use std::io::{BufWriter, Write};
 use std::sync::Arc;
 use std::time::Duration;
 
-use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use spfs::prelude::*;
 
 pub fn commit_benchmark(c: &mut Criterion) {
@@ -64,6 +64,32 @@ pub fn commit_benchmark(c: &mut Criterion) {
             async move { spfs::Committer::new(&repo).commit_dir(path).await }
         })
     });
+    // committing into an empty repository, where every file needs to be
+    // written, shows the effect of keeping files in memory once hashed
+    for max_buffered_blob_size in [0, spfs::commit::DEFAULT_MAX_BUFFERED_BLOB_SIZE] {
+        group.bench_with_input(
+            BenchmarkId::new("repo.commit_dir (new repo)", max_buffered_blob_size),
+            &max_buffered_blob_size,
+            |b, &max_buffered_blob_size| {
+                b.to_async(&tokio_runtime).iter(|| {
+                    let path = tempdir.path().to_owned();
+                    let repo_path = repo_path.path().join("new");
+                    async move {
+                        let _ = tokio::fs::remove_dir_all(&repo_path).await;
+                        let repo: RepositoryHandle =
+                            spfs::storage::fs::FsRepository::create(&repo_path)
+                                .await
+                                .expect("create spfs repo")
+                                .into();
+                        spfs::Committer::new(&repo)
+                            .with_max_buffered_blob_size(max_buffered_blob_size)
+                            .commit_dir(path)
+                            .await
+                    }
+                })
+            },
+        );
+    }
     group.finish();
 }
 
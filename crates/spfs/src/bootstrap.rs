@@ -186,6 +186,16 @@ pub fn build_interactive_shell_command(
             ],
             vars: vec![shell_message],
         }),
+        #[cfg(unix)]
+        Shell::Fish(fish) => {
+            let mut init_command = OsString::from("source ");
+            init_command.push(&rt.config.fish_startup_file);
+            Ok(Command {
+                executable: fish.into(),
+                args: vec!["--init-command".into(), init_command],
+                vars: vec![shell_message],
+            })
+        }
         #[cfg(windows)]
         Shell::Powershell(ps1) => Ok(Command {
             executable: ps1.into(),
@@ -221,6 +231,7 @@ where
     let startup_file = match shell.kind() {
         ShellKind::Bash => &runtime.config.sh_startup_file,
         ShellKind::Tcsh => &runtime.config.csh_startup_file,
+        ShellKind::Fish => &runtime.config.fish_startup_file,
         ShellKind::Powershell => {
             let mut cmd = command.into();
             for arg in args.into_iter().map(Into::into) {
@@ -384,6 +395,7 @@ where
 pub enum ShellKind {
     Bash,
     Tcsh,
+    Fish,
     Powershell,
 }
 
@@ -392,6 +404,7 @@ impl AsRef<str> for ShellKind {
         match self {
             Self::Bash => "bash",
             Self::Tcsh => "tcsh",
+            Self::Fish => "fish",
             Self::Powershell => "powershell.exe",
         }
     }
@@ -404,6 +417,8 @@ pub enum Shell {
     Bash(PathBuf),
     #[cfg(unix)]
     Tcsh(PathBuf),
+    #[cfg(unix)]
+    Fish(PathBuf),
     #[cfg(windows)]
     Powershell(PathBuf),
 }
@@ -415,6 +430,8 @@ impl Shell {
             Self::Bash(_) => ShellKind::Bash,
             #[cfg(unix)]
             Self::Tcsh(_) => ShellKind::Tcsh,
+            #[cfg(unix)]
+            Self::Fish(_) => ShellKind::Fish,
             #[cfg(windows)]
             Self::Powershell(_) => ShellKind::Powershell,
         }
@@ -427,6 +444,8 @@ impl Shell {
             Self::Bash(p) => p,
             #[cfg(unix)]
             Self::Tcsh(p) => p,
+            #[cfg(unix)]
+            Self::Fish(p) => p,
             #[cfg(windows)]
             Self::Powershell(p) => p,
         }
@@ -442,6 +461,8 @@ impl Shell {
             Some(n) if n == ShellKind::Bash.as_ref() => Ok(Self::Bash(path.to_owned())),
             #[cfg(unix)]
             Some(n) if n == ShellKind::Tcsh.as_ref() => Ok(Self::Tcsh(path.to_owned())),
+            #[cfg(unix)]
+            Some(n) if n == ShellKind::Fish.as_ref() => Ok(Self::Fish(path.to_owned())),
             #[cfg(windows)]
             Some(n) if n == ShellKind::Powershell.as_ref() => Ok(Self::Powershell(path.to_owned())),
             Some(_) => Err(Error::new(format!("Unsupported shell: {path:?}"))),
@@ -477,7 +498,12 @@ impl Shell {
             }
         }
 
-        for kind in &[ShellKind::Bash, ShellKind::Tcsh, ShellKind::Powershell] {
+        for kind in &[
+            ShellKind::Bash,
+            ShellKind::Tcsh,
+            ShellKind::Fish,
+            ShellKind::Powershell,
+        ] {
             if let Some(path) = which(kind) {
                 if let Ok(shell) = Shell::from_path(path) {
                     return Ok(shell);
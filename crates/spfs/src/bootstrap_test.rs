@@ -17,7 +17,8 @@ use crate::runtime;
     startup_script,
     startup_cmd,
     case("bash", "test.sh", "echo hi; export TEST_VALUE='spfs-test-value'"),
-    case("tcsh", "test.csh", "echo hi; setenv TEST_VALUE 'spfs-test-value'")
+    case("tcsh", "test.csh", "echo hi; setenv TEST_VALUE 'spfs-test-value'"),
+    case("fish", "test.fish", "echo hi; set -gx TEST_VALUE 'spfs-test-value'")
 )]
 #[tokio::test]
 #[serial_test::serial(env)] // env and config manipulation must be reliable
@@ -58,7 +59,11 @@ async fn test_shell_initialization_startup_scripts(
     let tmp_startup_dir = tmpdir.path().join("startup.d");
     std::fs::create_dir(&tmp_startup_dir).unwrap();
     rt.ensure_startup_scripts(None).unwrap();
-    for startup_script in &[&rt.config.sh_startup_file, &rt.config.csh_startup_file] {
+    for startup_script in &[
+        &rt.config.sh_startup_file,
+        &rt.config.csh_startup_file,
+        &rt.config.fish_startup_file,
+    ] {
         let mut cmd = Command::new("sed");
         cmd.arg("-i");
         cmd.arg(format!(
@@ -74,18 +79,10 @@ async fn test_shell_initialization_startup_scripts(
 
     std::env::set_var("SHELL", &shell_path);
 
-    match crate::Shell::find_best(None).unwrap() {
-        crate::Shell::Bash(_) if shell == "tcsh" => {
-            // Test will fail because we weren't able to
-            // find the shell we are trying to test
-            return;
-        }
-        crate::Shell::Tcsh(_) if shell == "bash" => {
-            // Test will fail because we weren't able to
-            // find the shell we are trying to test
-            return;
-        }
-        _ => {}
+    if crate::Shell::find_best(None).unwrap().kind().as_ref() != shell {
+        // Test will fail because we weren't able to
+        // find the shell we are trying to test
+        return;
     }
 
     let cmd = build_shell_initialized_command(&rt, None, "printenv", vec!["TEST_VALUE"]).unwrap();
@@ -97,7 +94,7 @@ async fn test_shell_initialization_startup_scripts(
     assert!(out.stdout.ends_with("spfs-test-value\n".as_bytes()));
 }
 
-#[rstest(shell, case("bash"), case("tcsh"))]
+#[rstest(shell, case("bash"), case("tcsh"), case("fish"))]
 #[tokio::test]
 #[serial_test::serial(env)] // env and config manipulation must be reliable
 async fn test_shell_initialization_no_startup_scripts(shell: &str, tmpdir: tempfile::TempDir) {
@@ -130,7 +127,11 @@ async fn test_shell_initialization_no_startup_scripts(shell: &str, tmpdir: tempf
     let tmp_startup_dir = tmpdir.path().join("startup.d");
     std::fs::create_dir(&tmp_startup_dir).unwrap();
     rt.ensure_startup_scripts(None).unwrap();
-    for startup_script in &[&rt.config.sh_startup_file, &rt.config.csh_startup_file] {
+    for startup_script in &[
+        &rt.config.sh_startup_file,
+        &rt.config.csh_startup_file,
+        &rt.config.fish_startup_file,
+    ] {
         let mut cmd = Command::new("sed");
         cmd.arg("-i");
         cmd.arg(format!(
@@ -152,7 +153,7 @@ async fn test_shell_initialization_no_startup_scripts(shell: &str, tmpdir: tempf
 }
 
 #[cfg(unix)]
-#[rstest(shell, case("bash"), case("tcsh"))]
+#[rstest(shell, case("bash"), case("tcsh"), case("fish"))]
 #[tokio::test]
 #[serial_test::serial(env)] // env manipulation must be reliable
 async fn test_find_alternate_bash(shell: &str, tmpdir: tempfile::TempDir) {
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::future::ready;
 use std::sync::Arc;
 
@@ -31,6 +31,8 @@ pub struct Checker<'repo, 'sync, Reporter: CheckReporter = SilentCheckReporter>
     processed_digests: Arc<dashmap::DashMap<encoding::Digest, CheckProgress>>,
     tag_stream_semaphore: Semaphore,
     object_semaphore: Semaphore,
+    repair_blobs: bool,
+    repair_corrupt_data: bool,
 }
 
 impl<'repo> Checker<'repo, 'static> {
@@ -47,6 +49,8 @@ impl<'repo> Checker<'repo, 'static> {
             processed_digests: Arc::new(Default::default()),
             tag_stream_semaphore: Semaphore::new(Self::DEFAULT_MAX_TAG_STREAM_CONCURRENCY),
             object_semaphore: Semaphore::new(Self::DEFAULT_MAX_OBJECT_CONCURRENCY),
+            repair_blobs: false,
+            repair_corrupt_data: false,
         }
     }
 }
@@ -68,6 +72,8 @@ where
             processed_digests: self.processed_digests,
             tag_stream_semaphore: self.tag_stream_semaphore,
             object_semaphore: self.object_semaphore,
+            repair_blobs: self.repair_blobs,
+            repair_corrupt_data: self.repair_corrupt_data,
         }
     }
 
@@ -90,9 +96,29 @@ where
             processed_digests: self.processed_digests,
             tag_stream_semaphore: self.tag_stream_semaphore,
             object_semaphore: self.object_semaphore,
+            repair_blobs: self.repair_blobs,
+            repair_corrupt_data: self.repair_corrupt_data,
         }
     }
 
+    /// Re-write the missing blob of any payload found while checking
+    /// the integrity of the repository.
+    ///
+    /// See [`Self::check_database_integrity`].
+    pub fn with_repair_blobs(mut self, repair_blobs: bool) -> Self {
+        self.repair_blobs = repair_blobs;
+        self
+    }
+
+    /// Fetch any corrupt object or payload found while checking the
+    /// repository against a reference one again from the reference.
+    ///
+    /// See [`Self::check_against_reference`].
+    pub fn with_repair_corrupt_data(mut self, repair_corrupt_data: bool) -> Self {
+        self.repair_corrupt_data = repair_corrupt_data;
+        self
+    }
+
     /// The maximum number of tag streams that can be read and processed at once
     pub fn with_max_tag_stream_concurrency(mut self, max_tag_stream_concurrency: usize) -> Self {
         self.tag_stream_semaphore = Semaphore::new(max_tag_stream_concurrency);
@@ -126,6 +152,275 @@ where
             .await
     }
 
+    /// Collect statistics about the health of the entire repository.
+    ///
+    /// Unlike the other checks, this does not walk the object graph but
+    /// instead visits every object and payload that is stored, identifying
+    /// payloads that are missing their blob. When enabled via
+    /// [`Self::with_repair_blobs`], these blobs are re-written using the
+    /// size of the payload data.
+    pub async fn check_database_integrity(&self) -> Result<IntegrityReport> {
+        let mut report = IntegrityReport {
+            total_objects: self
+                .repo
+                .find_digests(graph::DigestSearchCriteria::All)
+                .try_fold(0, |count, _| ready(Ok(count + 1)))
+                .await?,
+            ..Default::default()
+        };
+        let mut payloads = self
+            .repo
+            .iter_payload_digests()
+            .and_then(|digest| ready(Ok(self.check_payload_integrity(digest))))
+            .try_buffer_unordered(50);
+        while let Some(result) = payloads.try_next().await? {
+            report += result;
+        }
+        drop(payloads);
+        report.duplicated_render_bytes = self.count_duplicated_render_bytes().await?;
+        Ok(report)
+    }
+
+    async fn check_payload_integrity(&self, digest: encoding::Digest) -> Result<IntegrityReport> {
+        let mut report = IntegrityReport {
+            total_payloads: 1,
+            ..Default::default()
+        };
+        match self.repo.read_blob(digest).await {
+            Ok(blob) => report.payload_bytes = blob.size(),
+            Err(Error::UnknownObject(_)) => {
+                report.orphaned_payloads.insert(digest);
+                if self.repair_blobs {
+                    let (mut payload, filename) = self.repo.open_payload(digest).await?;
+                    let size = tokio::io::copy(&mut payload, &mut tokio::io::sink())
+                        .await
+                        .map_err(|err| Error::StorageReadError("copy of payload", filename, err))?;
+                    self.repo.write_blob(graph::Blob::new(digest, size)).await?;
+                    report.repaired_blobs += 1;
+                    report.payload_bytes = size;
+                }
+            }
+            Err(err) => return Err(err),
+        }
+        Ok(report)
+    }
+
+    /// Verify all of the data stored in the repository against a reference one.
+    ///
+    /// Like [`Self::check_database_integrity`], this visits every object and
+    /// payload that is stored rather than walking the object graph. The data of
+    /// each is read back to find any that no longer matches its digest, such as
+    /// from failing disks, and each is looked up in the reference repository,
+    /// which is usually the remote that the data was pulled from. When enabled
+    /// via [`Self::with_repair_corrupt_data`], corrupt data that the reference
+    /// repository has is removed and fetched from it again.
+    pub async fn check_against_reference(
+        &self,
+        reference: &storage::RepositoryHandle,
+    ) -> Result<ReferenceReport> {
+        let syncer = crate::Syncer::new(reference, self.repo);
+        let mut report = ReferenceReport::default();
+        let mut objects = self
+            .repo
+            .find_digests(graph::DigestSearchCriteria::All)
+            .and_then(|digest| {
+                ready(Ok(
+                    self.check_object_against_reference(digest, reference, &syncer)
+                ))
+            })
+            .try_buffer_unordered(50);
+        while let Some(result) = objects.try_next().await? {
+            report += result;
+        }
+        drop(objects);
+        let mut payloads = self
+            .repo
+            .iter_payload_digests()
+            .and_then(|digest| {
+                ready(Ok(
+                    self.check_payload_against_reference(digest, reference, &syncer)
+                ))
+            })
+            .try_buffer_unordered(50);
+        while let Some(result) = payloads.try_next().await? {
+            report += result;
+        }
+        drop(payloads);
+        if self.repair_corrupt_data {
+            let repaired = report
+                .corrupt_payloads
+                .difference(&report.unknown_payloads)
+                .copied()
+                .collect::<HashSet<_>>();
+            if !repaired.is_empty() {
+                report.removed_renders = self.remove_renders_using_payloads(&repaired).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Remove every render of a manifest that uses one of the given
+    /// payloads, so that it is rendered again from the repaired data.
+    ///
+    /// Rendered files are usually hard links to their payload, so they
+    /// still hold the corrupt data once the payload has been replaced.
+    /// Returns the number of renders that were removed.
+    async fn remove_renders_using_payloads(
+        &self,
+        payloads: &HashSet<encoding::Digest>,
+    ) -> Result<usize> {
+        let storage::RepositoryHandle::FS(repo) = self.repo else {
+            return Ok(0);
+        };
+        let repo = repo.opened().await?;
+        let mut removed = 0;
+        for (username, sub_repo) in repo.renders_for_all_users()? {
+            let rendered = sub_repo
+                .iter_rendered_manifests()
+                .try_collect::<Vec<_>>()
+                .await?;
+            for digest in rendered {
+                let manifest = match self.repo.read_manifest(digest).await {
+                    Ok(manifest) => manifest,
+                    Err(Error::UnknownObject(_)) => {
+                        tracing::warn!(%digest, %username, "cannot check render without its manifest");
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
+                let uses_payload = manifest
+                    .iter_entries()
+                    .any(|entry| entry.is_regular_file() && payloads.contains(entry.object()));
+                if uses_payload {
+                    tracing::info!(%digest, %username, "removing render of repaired payload");
+                    sub_repo.remove_rendered_manifest(digest).await?;
+                    removed += 1;
+                }
+            }
+            // proxies are also linked to the payload that they were made from
+            let Some(render_store) = &sub_repo.renders else {
+                continue;
+            };
+            for digest in payloads {
+                let path = render_store.proxy.build_digest_path(digest);
+                match tokio::fs::remove_file(&path).await {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(Error::StorageWriteError(
+                            "remove_file on proxy of repaired payload",
+                            path,
+                            err,
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    async fn check_object_against_reference(
+        &self,
+        digest: encoding::Digest,
+        reference: &storage::RepositoryHandle,
+        syncer: &crate::Syncer<'_, '_>,
+    ) -> Result<ReferenceReport> {
+        let mut report = ReferenceReport {
+            total_objects: 1,
+            ..Default::default()
+        };
+        let corrupt = match self.repo.read_object(digest).await {
+            Ok(obj) => !matches!(obj.digest(), Ok(actual) if actual == digest),
+            // the object was removed since it was found
+            Err(Error::UnknownObject(_)) => return Ok(report),
+            Err(err @ Error::StorageReadError(..)) => return Err(err),
+            Err(err) => {
+                tracing::debug!(%digest, "failed to decode object: {err}");
+                true
+            }
+        };
+        let in_reference = reference.has_object(digest).await;
+        if !in_reference {
+            report.unknown_objects.insert(digest);
+        }
+        if !corrupt {
+            return Ok(report);
+        }
+        report.corrupt_objects.insert(digest);
+        if self.repair_corrupt_data && in_reference {
+            self.repo.remove_object(digest).await?;
+            syncer.sync_digest(digest).await?;
+            report.repaired_objects += 1;
+        }
+        Ok(report)
+    }
+
+    async fn check_payload_against_reference(
+        &self,
+        digest: encoding::Digest,
+        reference: &storage::RepositoryHandle,
+        syncer: &crate::Syncer<'_, '_>,
+    ) -> Result<ReferenceReport> {
+        let mut report = ReferenceReport {
+            total_payloads: 1,
+            ..Default::default()
+        };
+        let (payload, _) = match self.repo.open_payload(digest).await {
+            Ok(payload) => payload,
+            // the payload was removed since it was found
+            Err(Error::UnknownObject(_) | Error::ObjectMissingPayload(..)) => return Ok(report),
+            Err(err) => return Err(err),
+        };
+        let corrupt =
+            match encoding::Hasher::hash_async_reader_with(self.repo.digest_algorithm(), payload)
+                .await
+            {
+                Ok(actual) => actual != digest,
+                Err(err) => {
+                    tracing::debug!(%digest, "failed to read payload: {err}");
+                    true
+                }
+            };
+        let in_reference = reference.has_payload(digest).await;
+        if !in_reference {
+            report.unknown_payloads.insert(digest);
+        }
+        if !corrupt {
+            return Ok(report);
+        }
+        report.corrupt_payloads.insert(digest);
+        if self.repair_corrupt_data && in_reference {
+            self.repo.remove_payload(digest).await?;
+            // Safety: the payload is being replaced with the same data,
+            // so any blob for it remains valid
+            unsafe { syncer.sync_payload(digest).await? };
+            report.repaired_payloads += 1;
+        }
+        Ok(report)
+    }
+
+    /// Count the bytes of rendered files whose data is not shared
+    /// with the payload storage of the repository.
+    ///
+    /// Renders are normally hard links back to their payload, so any
+    /// file that only has links within the render storage is taking up
+    /// additional space on disk.
+    async fn count_duplicated_render_bytes(&self) -> Result<u64> {
+        let storage::RepositoryHandle::FS(repo) = self.repo else {
+            return Ok(0);
+        };
+        let repo = repo.opened().await?;
+        let roots = repo
+            .renders_for_all_users()?
+            .into_iter()
+            .filter_map(|(_, sub_repo)| sub_repo.renders.map(|r| r.renders.root().to_owned()))
+            .collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || count_unshared_bytes(&roots))
+            .await?
+            .map_err(|err| {
+                Error::StorageReadError("walk of render storage", repo.root().join("renders"), err)
+            })
+    }
+
     /// Check the object(s) graph referenced by the given string.
     ///
     /// Any valid [`crate::tracking::EnvSpec`] is accepted as a reference.
@@ -308,6 +603,7 @@ where
                 self.must_check_blob_with_perms_opt(&obj, perms).await?
             }),
             Enum::Manifest(obj) => CheckObjectResult::Manifest(self.check_manifest(obj).await?),
+            Enum::ChunkList(obj) => CheckObjectResult::ChunkList(self.check_chunk_list(obj).await?),
         };
         self.reporter.checked_object(&res);
         Ok(res)
@@ -386,6 +682,26 @@ where
         Ok(res)
     }
 
+    /// Validate that the identified chunk list's chunks all exist.
+    ///
+    /// To also check if the chunk list object exists, use [`Self::check_digest`]
+    pub async fn check_chunk_list(
+        &self,
+        chunk_list: graph::ChunkList,
+    ) -> Result<CheckChunkListResult> {
+        let futures: FuturesUnordered<_> = chunk_list
+            .iter_chunks()
+            .map(|c| self.check_digest(*c.payload()))
+            .collect();
+        let results = futures.try_collect().await?;
+        let res = CheckChunkListResult {
+            chunk_list,
+            results,
+            repaired: false,
+        };
+        Ok(res)
+    }
+
     /// Validate that the identified annotation layer's value exists.
     pub async fn check_annotation(
         &self,
@@ -444,6 +760,16 @@ where
         blob: &graph::Blob,
         perms: Option<u32>,
     ) -> Result<CheckBlobResult> {
+        if let Some(chunks) = blob.chunks() {
+            // chunked blobs have no payload of their own, and
+            // each chunk is checked and reported as its own blob
+            let result = self.check_digest(*chunks).await?;
+            return Ok(CheckBlobResult::Chunked {
+                blob: blob.to_owned(),
+                result: Box::new(result),
+                repaired: false,
+            });
+        }
         self.reporter.visit_blob(blob);
         let result = unsafe {
             // Safety: this function may sync a payload and so
@@ -554,6 +880,39 @@ enum CheckProgress {
     CheckStarted,
 }
 
+#[cfg(unix)]
+fn count_unshared_bytes(roots: &[std::path::PathBuf]) -> std::io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    // (device, inode) => (link count, size, links seen)
+    let mut inodes = HashMap::<(u64, u64), (u64, u64, u64)>::new();
+    for root in roots {
+        for entry in walkdir::WalkDir::new(root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let meta = entry.metadata()?;
+            inodes
+                .entry((meta.dev(), meta.ino()))
+                .or_insert((meta.nlink(), meta.size(), 0))
+                .2 += 1;
+        }
+    }
+    Ok(inodes
+        .into_values()
+        .filter(|(nlink, _, seen)| seen >= nlink)
+        .map(|(_, size, _)| size)
+        .sum())
+}
+
+#[cfg(windows)]
+fn count_unshared_bytes(_roots: &[std::path::PathBuf]) -> std::io::Result<u64> {
+    // link counts are not readily available, so
+    // duplicated renders are not reported
+    Ok(0)
+}
+
 /// Receives updates from a check process to be reported.
 ///
 /// Unless the check runs into errors, every call to visit_* is
@@ -749,6 +1108,100 @@ impl std::iter::Sum<CheckSummary> for CheckSummary {
     }
 }
 
+/// Statistics about the health of an entire repository.
+///
+/// See [`Checker::check_database_integrity`].
+#[derive(Default, Debug)]
+pub struct IntegrityReport {
+    /// The number of objects stored in the repository
+    pub total_objects: usize,
+    /// The number of payloads stored in the repository
+    pub total_payloads: usize,
+    /// The total size of all payloads with a valid blob
+    pub payload_bytes: u64,
+    /// The total size of rendered files that are copies
+    /// rather than hard links of their payload
+    pub duplicated_render_bytes: u64,
+    /// Payloads that were found without a corresponding blob
+    pub orphaned_payloads: HashSet<encoding::Digest>,
+    /// The number of orphaned payloads whose blob was re-written
+    pub repaired_blobs: usize,
+}
+
+impl std::ops::AddAssign for IntegrityReport {
+    fn add_assign(&mut self, rhs: Self) {
+        // destructure to ensure that all fields are processed
+        // (causing compile errors for new ones that need to be added)
+        let IntegrityReport {
+            total_objects,
+            total_payloads,
+            payload_bytes,
+            duplicated_render_bytes,
+            orphaned_payloads,
+            repaired_blobs,
+        } = rhs;
+        self.total_objects += total_objects;
+        self.total_payloads += total_payloads;
+        self.payload_bytes += payload_bytes;
+        self.duplicated_render_bytes += duplicated_render_bytes;
+        self.orphaned_payloads.extend(orphaned_payloads);
+        self.repaired_blobs += repaired_blobs;
+    }
+}
+
+/// The result of verifying a repository against a reference one.
+///
+/// See [`Checker::check_against_reference`].
+#[derive(Default, Debug)]
+pub struct ReferenceReport {
+    /// The number of objects stored in the repository
+    pub total_objects: usize,
+    /// The number of payloads stored in the repository
+    pub total_payloads: usize,
+    /// Objects whose data does not match their digest
+    pub corrupt_objects: HashSet<encoding::Digest>,
+    /// Payloads whose data does not match their digest
+    pub corrupt_payloads: HashSet<encoding::Digest>,
+    /// Objects that the reference repository does not have
+    pub unknown_objects: HashSet<encoding::Digest>,
+    /// Payloads that the reference repository does not have
+    pub unknown_payloads: HashSet<encoding::Digest>,
+    /// The number of corrupt objects that were fetched again
+    pub repaired_objects: usize,
+    /// The number of corrupt payloads that were fetched again
+    pub repaired_payloads: usize,
+    /// The number of renders that used a repaired payload, which
+    /// were removed so that they are rendered again when next used
+    pub removed_renders: usize,
+}
+
+impl std::ops::AddAssign for ReferenceReport {
+    fn add_assign(&mut self, rhs: Self) {
+        // destructure to ensure that all fields are processed
+        // (causing compile errors for new ones that need to be added)
+        let ReferenceReport {
+            total_objects,
+            total_payloads,
+            corrupt_objects,
+            corrupt_payloads,
+            unknown_objects,
+            unknown_payloads,
+            repaired_objects,
+            repaired_payloads,
+            removed_renders,
+        } = rhs;
+        self.total_objects += total_objects;
+        self.total_payloads += total_payloads;
+        self.corrupt_objects.extend(corrupt_objects);
+        self.corrupt_payloads.extend(corrupt_payloads);
+        self.unknown_objects.extend(unknown_objects);
+        self.unknown_payloads.extend(unknown_payloads);
+        self.repaired_objects += repaired_objects;
+        self.repaired_payloads += repaired_payloads;
+        self.removed_renders += removed_renders;
+    }
+}
+
 #[derive(Debug)]
 pub struct CheckEnvResult {
     pub env: tracking::EnvSpec,
@@ -846,6 +1299,7 @@ pub enum CheckObjectResult {
     Blob(CheckBlobResult),
     Manifest(CheckManifestResult),
     Annotation(CheckAnnotationResult),
+    ChunkList(CheckChunkListResult),
 }
 
 impl CheckObjectResult {
@@ -861,6 +1315,7 @@ impl CheckObjectResult {
             CheckObjectResult::Blob(r) => r.set_repaired(),
             CheckObjectResult::Manifest(r) => r.set_repaired(),
             CheckObjectResult::Annotation(r) => r.set_repaired(),
+            CheckObjectResult::ChunkList(r) => r.set_repaired(),
         }
     }
 
@@ -878,6 +1333,7 @@ impl CheckObjectResult {
             Blob(res) => res.summary(),
             Manifest(res) => res.summary(),
             Annotation(res) => res.summary(),
+            ChunkList(res) => res.summary(),
         }
     }
 }
@@ -951,6 +1407,29 @@ impl CheckManifestResult {
     }
 }
 
+#[derive(Debug)]
+pub struct CheckChunkListResult {
+    pub repaired: bool,
+    pub chunk_list: graph::ChunkList,
+    pub results: Vec<CheckObjectResult>,
+}
+
+impl CheckChunkListResult {
+    /// Marks this result as being repaired.
+    fn set_repaired(&mut self) {
+        self.repaired = true;
+    }
+
+    pub fn summary(&self) -> CheckSummary {
+        let mut summary: CheckSummary = self.results.iter().map(|r| r.summary()).sum();
+        summary += CheckSummary::checked_one_object();
+        if self.repaired {
+            summary.repaired_objects += 1;
+        }
+        summary
+    }
+}
+
 #[derive(Debug)]
 pub enum CheckAnnotationResult {
     /// The annotation was stored directly in the layer and did not
@@ -1008,12 +1487,18 @@ pub enum CheckBlobResult {
         blob: graph::Blob,
         result: CheckPayloadResult,
     },
+    /// The blob was stored in chunks, which were checked
+    Chunked {
+        repaired: bool,
+        blob: graph::Blob,
+        result: Box<CheckObjectResult>,
+    },
 }
 
 impl CheckBlobResult {
     /// Marks this result as being repaired.
     fn set_repaired(&mut self) {
-        if let Self::Checked { repaired, .. } = self {
+        if let Self::Checked { repaired, .. } | Self::Chunked { repaired, .. } = self {
             *repaired = true;
         }
     }
@@ -1021,6 +1506,17 @@ impl CheckBlobResult {
     pub fn summary(&self) -> CheckSummary {
         match self {
             Self::Duplicate => CheckSummary::default(),
+            Self::Chunked {
+                repaired, result, ..
+            } => {
+                let mut summary = result.summary();
+                summary += CheckSummary {
+                    checked_objects: 1,
+                    repaired_objects: *repaired as usize,
+                    ..Default::default()
+                };
+                summary
+            }
             Self::Missing(digest) => CheckSummary {
                 missing_objects: Some(*digest).into_iter().collect(),
                 ..Default::default()
@@ -8,7 +8,7 @@ use spfs_encoding::prelude::*;
 use super::{CheckSummary, Checker};
 use crate::fixtures::*;
 use crate::graph::Database;
-use crate::storage::PayloadStorage;
+use crate::storage::{BlobStorage, PayloadStorage, RepositoryHandle};
 
 #[rstest]
 #[tokio::test]
@@ -230,3 +230,132 @@ async fn test_check_missing_object_recover(#[future] tmprepo: TempRepo) {
         "should see no missing payloads",
     );
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_check_database_integrity_repair(#[future] tmprepo: TempRepo) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let manifest = generate_tree(&tmprepo).await.to_graph_manifest();
+    let file = manifest
+        .iter_entries()
+        .find(|entry| entry.is_regular_file())
+        .expect("at least one regular file");
+
+    tracing::info!(digest=%file.object(), "remove object");
+    tmprepo
+        .remove_object(*file.object())
+        .await
+        .expect("failed to remove object");
+
+    let report = Checker::new(&tmprepo.repo())
+        .check_database_integrity()
+        .await
+        .unwrap();
+    tracing::info!("{report:#?}");
+    assert!(
+        report.orphaned_payloads.contains(file.object()),
+        "should find one orphaned payload"
+    );
+    assert_eq!(report.orphaned_payloads.len(), 1);
+    assert_eq!(report.repaired_blobs, 0, "should not repair unless asked");
+
+    let report = Checker::new(&tmprepo.repo())
+        .with_repair_blobs(true)
+        .check_database_integrity()
+        .await
+        .unwrap();
+    assert_eq!(report.repaired_blobs, 1, "should repair the missing blob");
+
+    let blob = tmprepo
+        .read_blob(*file.object())
+        .await
+        .expect("blob should be re-written");
+    assert_eq!(blob.size(), file.size());
+
+    let report = Checker::new(&tmprepo.repo())
+        .check_database_integrity()
+        .await
+        .unwrap();
+    assert!(report.orphaned_payloads.is_empty());
+    assert_eq!(report.total_payloads, report.total_objects - 1);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_check_against_reference_repair(#[future] tmprepo: TempRepo) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+    let reference = crate::fixtures::tmprepo("fs").await;
+
+    let manifest = generate_tree(&tmprepo).await.to_graph_manifest();
+    let digest = manifest.digest().unwrap();
+    crate::Syncer::new(&tmprepo.repo(), &reference.repo())
+        .sync_digest(digest)
+        .await
+        .expect("failed to sync reference repo");
+
+    let file = manifest
+        .iter_entries()
+        .find(|entry| entry.is_regular_file())
+        .expect("at least one regular file");
+    let fs_repo = match &*tmprepo {
+        RepositoryHandle::FS(fs) => fs.opened().await.unwrap(),
+        _ => panic!("Unexpected tmprepo type!"),
+    };
+    let rendered = crate::storage::fs::Renderer::new(&*fs_repo)
+        .render_manifest(&manifest, None)
+        .await
+        .expect("failed to render manifest");
+
+    let (_, path) = tmprepo
+        .open_payload(*file.object())
+        .await
+        .expect("payload should exist");
+    tracing::info!(digest=%file.object(), ?path, "corrupt payload");
+    // corrupted in place, as a failing disk would, which also
+    // changes any rendered file that is linked to the payload
+    let mut perms = std::fs::metadata(&path).unwrap().permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    perms.set_readonly(false);
+    std::fs::set_permissions(&path, perms).unwrap();
+    std::fs::write(&path, b"corrupted").unwrap();
+
+    let report = Checker::new(&tmprepo.repo())
+        .check_against_reference(&reference.repo())
+        .await
+        .unwrap();
+    tracing::info!("{report:#?}");
+    assert!(report.corrupt_payloads.contains(file.object()));
+    assert_eq!(report.corrupt_payloads.len(), 1);
+    assert!(report.corrupt_objects.is_empty());
+    assert!(report.unknown_objects.is_empty());
+    assert!(report.unknown_payloads.is_empty());
+    assert_eq!(
+        report.repaired_payloads, 0,
+        "should not repair unless asked"
+    );
+
+    let report = Checker::new(&tmprepo.repo())
+        .with_repair_corrupt_data(true)
+        .check_against_reference(&reference.repo())
+        .await
+        .unwrap();
+    assert_eq!(report.repaired_payloads, 1, "should repair the payload");
+    assert_eq!(
+        report.removed_renders, 1,
+        "should remove the render that is linked to the corrupt payload"
+    );
+    assert!(
+        !rendered.exists(),
+        "the render should be rendered again from the repaired payload"
+    );
+
+    let report = Checker::new(&tmprepo.repo())
+        .check_against_reference(&reference.repo())
+        .await
+        .unwrap();
+    assert!(report.corrupt_payloads.is_empty());
+    assert_eq!(report.total_payloads, report.total_objects - 1);
+}
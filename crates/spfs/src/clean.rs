@@ -7,6 +7,8 @@ use std::fmt::Write;
 use std::future::ready;
 #[cfg(unix)]
 use std::os::linux::fs::MetadataExt;
+use std::path::Path;
+use std::time::SystemTime;
 
 use chrono::{DateTime, Duration, Local, Utc};
 use colored::Colorize;
@@ -16,6 +18,8 @@ use once_cell::sync::OnceCell;
 use progress_bar_derive_macro::ProgressBar;
 
 use super::prune::PruneParameters;
+use crate::mirror::TagFilter;
+use crate::operation::{cancellable, CancellationToken};
 use crate::prelude::*;
 use crate::runtime::makedirs_with_perms;
 use crate::storage::fs::OpenFsRepository;
@@ -45,7 +49,10 @@ where
     must_be_older_than: DateTime<Utc>,
     prune_repeated_tags: bool,
     prune_params: PruneParameters,
+    preserved_tags: Option<TagFilter>,
     remove_proxies_with_no_links: bool,
+    free_space_target: Option<u64>,
+    cancellation: CancellationToken,
 }
 
 impl<'repo> Cleaner<'repo, SilentCleanReporter> {
@@ -55,6 +62,11 @@ impl<'repo> Cleaner<'repo, SilentCleanReporter> {
     pub const DEFAULT_DISCOVER_CONCURRENCY: usize = 50;
     /// See [`Cleaner::with_tag_stream_concurrency`]
     pub const DEFAULT_TAG_STREAM_CONCURRENCY: usize = 500;
+    /// See [`Cleaner::with_preserved_tags`]
+    ///
+    /// The audit logs of spk repositories are append-only records
+    /// of every change, and are never pruned by default.
+    pub const DEFAULT_PRESERVED_TAGS: &'static [&'static str] = &["spk/audit/**"];
 
     pub fn new(repo: &'repo storage::RepositoryHandle) -> Self {
         Self {
@@ -68,7 +80,13 @@ impl<'repo> Cleaner<'repo, SilentCleanReporter> {
             must_be_older_than: Utc::now(),
             prune_repeated_tags: false,
             prune_params: Default::default(),
+            preserved_tags: Some(
+                TagFilter::new(Self::DEFAULT_PRESERVED_TAGS, std::iter::empty::<&str>())
+                    .expect("default preserved tag patterns are valid"),
+            ),
             remove_proxies_with_no_links: true,
+            free_space_target: None,
+            cancellation: CancellationToken::new(),
         }
     }
 }
@@ -88,13 +106,27 @@ where
             must_be_older_than: self.must_be_older_than,
             prune_repeated_tags: self.prune_repeated_tags,
             prune_params: self.prune_params,
+            preserved_tags: self.preserved_tags,
             removal_concurrency: self.removal_concurrency,
             discover_concurrency: self.discover_concurrency,
             tag_stream_concurrency: self.tag_stream_concurrency,
             remove_proxies_with_no_links: self.remove_proxies_with_no_links,
+            free_space_target: self.free_space_target,
+            cancellation: self.cancellation,
         }
     }
 
+    /// Stop cleaning with [`Error::OperationCancelled`] once the
+    /// given token is cancelled.
+    ///
+    /// Nothing is removed if the clean is cancelled before all
+    /// attached objects have been discovered, otherwise the clean
+    /// is left partially complete.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
     // The number of concurrent tag stream scanning operations
     // that are buffered and allowed to run concurrently
     pub fn with_tag_stream_concurrency(mut self, tag_stream_concurrency: usize) -> Self {
@@ -196,6 +228,16 @@ where
         self
     }
 
+    /// Never prune the history of the tags selected by this filter,
+    /// replacing [`Cleaner::DEFAULT_PRESERVED_TAGS`].
+    ///
+    /// The objects connected to these tags are still found and kept
+    /// like those of any other tag.
+    pub fn with_preserved_tags(mut self, preserved_tags: Option<TagFilter>) -> Self {
+        self.preserved_tags = preserved_tags;
+        self
+    }
+
     /// When set, also remove any proxies that do not have any hard links
     /// regardless of if they are still attached in the repository.
     ///
@@ -209,6 +251,18 @@ where
         self
     }
 
+    /// When set, only remove as much detached data as is needed for
+    /// this many bytes to be available on the repository filesystem.
+    ///
+    /// Detached objects and payloads are removed in order of when they
+    /// were last used, oldest first, and any that are not needed to reach
+    /// the target are kept. This is only supported for local filesystem
+    /// repositories.
+    pub fn with_free_space_target(mut self, free_space_target: Option<u64>) -> Self {
+        self.free_space_target = free_space_target;
+        self
+    }
+
     /// Provide a human-readable summary of the current
     /// configuration for this cleaner.
     ///
@@ -230,6 +284,12 @@ where
             "VISIT".cyan()
         );
         if self.prune_repeated_tags || !self.prune_params.is_empty() {
+            if self.preserved_tags.is_some() {
+                let _ = writeln!(
+                    &mut out,
+                    " - skip pruning entirely for preserved tags (by default, spk audit logs)",
+                );
+            }
             if self.prune_repeated_tags {
                 let _ = writeln!(
                     &mut out,
@@ -284,19 +344,40 @@ where
             &mut out,
             " - {identify} any object that is not connected to a tag"
         );
-        let _ = writeln!(
-            &mut out,
-            " - {remove} that object unless it was created after {}",
-            self.must_be_older_than.with_timezone(&Local)
-        );
-        let _ = writeln!(
-            &mut out,
-            "Then, {scan} all of the payloads in the repository"
-        );
-        let _ = writeln!(
-            &mut out,
-            " - {remove} any payload that is not connected to a blob"
-        );
+        if let Some(target) = self.free_space_target {
+            let _ = writeln!(
+                &mut out,
+                " - {identify} any payload that is not connected to a blob"
+            );
+            let _ = writeln!(
+                &mut out,
+                "Then, {remove} the identified data that was least recently used first"
+            );
+            let _ = writeln!(
+                &mut out,
+                " - stop once {} is available on the repository filesystem",
+                crate::io::format_size(target)
+            );
+            let _ = writeln!(
+                &mut out,
+                " - keep any object that was created after {}",
+                self.must_be_older_than.with_timezone(&Local)
+            );
+        } else {
+            let _ = writeln!(
+                &mut out,
+                " - {remove} that object unless it was created after {}",
+                self.must_be_older_than.with_timezone(&Local)
+            );
+            let _ = writeln!(
+                &mut out,
+                "Then, {scan} all of the payloads in the repository"
+            );
+            let _ = writeln!(
+                &mut out,
+                " - {remove} any payload that is not connected to a blob"
+            );
+        }
         let _ = writeln!(
             &mut out,
             "Then, {scan} all of the renders in the repository"
@@ -314,10 +395,16 @@ where
     /// function returns as a success. In these cases, the clean should be considered
     /// partially complete depending on the nature of the errors.
     pub async fn prune_all_tags_and_clean(&self) -> Result<CleanResult> {
+        if self.free_space_target.is_some() {
+            // check this before anything is pruned or removed
+            self.fs_repository_for_free_space_target()?;
+        }
         let mut result = CleanResult::default();
         let mut stream = self.repo.iter_tag_streams().boxed();
         let mut futures = futures::stream::FuturesUnordered::new();
-        while let Some((tag_spec, _stream)) = stream.try_next().await? {
+        while let Some((tag_spec, _stream)) =
+            cancellable(&self.cancellation, stream.try_next()).await?
+        {
             if futures.len() > self.tag_stream_concurrency {
                 // if we've reached the limit, let the fastest half finish
                 // before adding additional futures. This is a crude way to
@@ -325,13 +412,13 @@ where
                 // completed futures for too long or needing to wait for the
                 // slowest ones too often
                 while futures.len() > self.tag_stream_concurrency / 2 {
-                    futures.try_next().await?;
+                    cancellable(&self.cancellation, futures.try_next()).await?;
                 }
             }
             futures.push(self.prune_tag_stream_and_walk(tag_spec));
         }
         drop(stream);
-        while let Some(r) = futures.try_next().await? {
+        while let Some(r) = cancellable(&self.cancellation, futures.try_next()).await? {
             result += r;
         }
 
@@ -351,7 +438,10 @@ where
         unsafe {
             // because we don't yet know if some detached objects will be
             // kept due to age, we cannot process these two steps in parallel
-            result += self.remove_unvisited_objects_and_payloads().await?;
+            result += match self.free_space_target {
+                Some(target) => self.remove_least_recently_used_until_free(target).await?,
+                None => self.remove_unvisited_objects_and_payloads().await?,
+            };
             result += self.remove_unvisited_renders_and_proxies().await?;
         }
         Ok(result)
@@ -367,9 +457,17 @@ where
         let mut to_prune = Vec::with_capacity(history.len() / 2);
         let mut to_keep = Vec::with_capacity(history.len() / 2);
         let mut seen_targets = std::collections::HashSet::new();
+        let preserved = self
+            .preserved_tags
+            .as_ref()
+            .is_some_and(|filter| filter.matches(&tag_spec));
         for (i, tag) in history.into_iter().enumerate() {
             let spec = tag.to_spec(i as u64);
             self.reporter.visit_tag(&tag);
+            if preserved {
+                to_keep.push(tag);
+                continue;
+            }
             if !seen_targets.insert(tag.target) && self.prune_repeated_tags {
                 to_prune.push(tag);
                 continue;
@@ -399,7 +497,7 @@ where
             .then(|tag| ready(self.discover_attached_objects(tag.target).boxed()))
             .buffer_unordered(self.discover_concurrency)
             .boxed();
-        while let Some(res) = walk_stream.try_next().await? {
+        while let Some(res) = cancellable(&self.cancellation, walk_stream.try_next()).await? {
             result += res;
         }
 
@@ -436,7 +534,7 @@ where
             .then(|child| ready(self.discover_attached_objects(child).boxed()))
             .buffer_unordered(self.discover_concurrency)
             .boxed();
-        while let Some(res) = walk_stream.try_next().await? {
+        while let Some(res) = cancellable(&self.cancellation, walk_stream.try_next()).await? {
             result += res;
         }
         Ok(result)
@@ -516,7 +614,7 @@ where
             .try_buffer_unordered(self.removal_concurrency)
             .boxed();
         let mut result = CleanResult::default();
-        while let Some(blob) = stream.try_next().await? {
+        while let Some(blob) = cancellable(&self.cancellation, stream.try_next()).await? {
             result.removed_payloads.insert(*blob.payload());
             self.reporter.payload_removed(&blob)
         }
@@ -555,7 +653,7 @@ where
             })
             .try_buffer_unordered(self.removal_concurrency)
             .boxed();
-        while let Some(blob) = stream.try_next().await? {
+        while let Some(blob) = cancellable(&self.cancellation, stream.try_next()).await? {
             result.removed_payloads.insert(*blob.payload());
             self.reporter.payload_removed(&blob)
         }
@@ -564,6 +662,267 @@ where
         Ok(result)
     }
 
+    fn fs_repository_for_free_space_target(&self) -> Result<&storage::fs::FsRepository> {
+        match self.repo {
+            storage::RepositoryHandle::FS(repo) => Ok(repo),
+            _ => Err(Error::String(format!(
+                "Cleaning to a free space target is only supported for local filesystem repositories, got: {}",
+                self.repo.address()
+            ))),
+        }
+    }
+
+    /// Remove detached objects and payloads, least recently used first,
+    /// until the target number of bytes is available on the filesystem.
+    ///
+    /// Any detached objects that are not removed become implicitly attached,
+    /// along with all of their children, so that their renders are also kept.
+    /// Payloads that are hard linked elsewhere, such as into a render, do not
+    /// count towards the target because removing them frees no space.
+    ///
+    /// # Safety
+    /// This function should only be called once the discovery of all attached
+    /// objects has completed successfully and with no errors. Otherwise, it may
+    /// remove data that is still being used
+    async unsafe fn remove_least_recently_used_until_free(
+        &self,
+        target: u64,
+    ) -> Result<CleanResult> {
+        let repo = self.fs_repository_for_free_space_target()?.opened().await?;
+        let mut result = CleanResult::default();
+        let mut candidates = Vec::new();
+        let mut blob_payloads = HashSet::new();
+
+        let mut stream = self
+            .repo
+            .iter_objects()
+            // we have no interest in removing attached items
+            .try_filter(|(digest, _object)| ready(!self.attached.contains(digest)))
+            .boxed();
+        while let Some((digest, object)) =
+            cancellable(&self.cancellation, stream.try_next()).await?
+        {
+            self.reporter.visit_object(&object);
+            result.visited_objects += 1;
+            let Some(mut usage) = FileUsage::read(&repo.objects.build_digest_path(&digest)).await?
+            else {
+                continue;
+            };
+            if let graph::object::Enum::Blob(blob) = object.to_enum() {
+                self.reporter.visit_payload(&blob);
+                result.visited_payloads += 1;
+                blob_payloads.insert(*blob.payload());
+                let path = repo.payloads.build_digest_path(blob.payload());
+                if let Some(payload_usage) = FileUsage::read(&path).await? {
+                    usage += payload_usage;
+                }
+            }
+            candidates.push(LeastRecentlyUsedCandidate {
+                digest,
+                object: Some(object),
+                usage,
+            });
+        }
+        drop(stream);
+
+        let mut stream = self
+            .repo
+            .iter_payload_digests()
+            .try_filter(|payload| {
+                ready(!self.attached.contains(payload) && !blob_payloads.contains(payload))
+            })
+            .boxed();
+        while let Some(payload) = cancellable(&self.cancellation, stream.try_next()).await? {
+            let usage = FileUsage::read(&repo.payloads.build_digest_path(&payload)).await?;
+            let size = usage.as_ref().map(|u| u.size).unwrap_or_default();
+            self.reporter
+                .visit_payload(&graph::Blob::new(payload, size));
+            result.visited_payloads += 1;
+            if let Some(usage) = usage {
+                candidates.push(LeastRecentlyUsedCandidate {
+                    digest: payload,
+                    object: None,
+                    usage,
+                });
+            }
+        }
+        drop(stream);
+
+        candidates.sort_by_key(|candidate| candidate.usage.last_used);
+        let index: HashMap<_, _> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| (candidate.digest, i))
+            .collect();
+
+        // objects that are too new to be removed become implicitly attached
+        let cutoff = SystemTime::from(self.must_be_older_than);
+        for candidate in candidates.iter() {
+            if candidate.object.is_some() && candidate.usage.modified >= cutoff {
+                self.attached.insert(candidate.digest);
+            }
+        }
+
+        // everything that will be kept is decided before anything is
+        // removed, so that the children of a kept object are never
+        // removed out from under it. Attaching those children can put
+        // the target out of reach again, and so the selection repeats
+        // until no more of the selected candidates need to be kept.
+        let mut available = available_space(&repo.root())?;
+        let selected = loop {
+            let mut expected = available;
+            let mut selected = HashSet::new();
+            for (i, candidate) in candidates.iter().enumerate() {
+                if expected >= target {
+                    break;
+                }
+                if self.attached.contains(&candidate.digest) {
+                    continue;
+                }
+                selected.insert(i);
+                expected = expected.saturating_add(candidate.usage.reclaimable);
+            }
+            let kept = candidates
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !selected.contains(i))
+                .map(|(_, candidate)| candidate);
+            let mut children = Vec::new();
+            for candidate in kept {
+                self.attached.insert(candidate.digest);
+                if let Some(object) = &candidate.object {
+                    children.extend(object.child_objects());
+                }
+            }
+            if !self.attach_candidate_children(children, &candidates, &index, &selected) {
+                break selected;
+            }
+        };
+
+        // parents are removed before their children, so that
+        // an object which turns out to be too new to remove can
+        // still keep all of its children
+        let mut selected: Vec<_> = selected.into_iter().collect();
+        selected.sort_by_key(|i| {
+            let kind = candidates[*i].object.as_ref().map(|o| o.to_enum());
+            match kind {
+                Some(graph::object::Enum::Platform(_)) => 0,
+                Some(graph::object::Enum::Layer(_)) => 1,
+                Some(graph::object::Enum::Manifest(_)) => 2,
+                Some(graph::object::Enum::ChunkList(_)) => 3,
+                Some(graph::object::Enum::Blob(_)) => 4,
+                None => 5,
+            }
+        });
+        let mut candidates: Vec<_> = candidates.into_iter().map(Some).collect();
+        for i in selected {
+            if self.cancellation.is_cancelled() {
+                return Err(Error::OperationCancelled);
+            }
+            let Some(LeastRecentlyUsedCandidate {
+                digest,
+                object,
+                usage,
+            }) = candidates[i].take()
+            else {
+                continue;
+            };
+            if self.attached.contains(&digest) {
+                continue;
+            }
+
+            let payload = match object {
+                Some(object) => {
+                    let removed = self.dry_run
+                        || match self
+                            .repo
+                            .remove_object_if_older_than(self.must_be_older_than, digest)
+                            .await
+                        {
+                            Err(Error::UnknownObject(_)) => true,
+                            res => res?,
+                        };
+                    if !removed {
+                        // the object was modified since it was first seen,
+                        // and so it is kept along with all of its children
+                        let mut children = object.child_objects();
+                        self.attached.insert(digest);
+                        while let Some(child) = children.pop() {
+                            if !self.attached.insert(child) {
+                                continue;
+                            }
+                            let Some(&i) = index.get(&child) else {
+                                continue;
+                            };
+                            if let Some(Some(object)) =
+                                candidates[i].as_ref().map(|c| c.object.as_ref())
+                            {
+                                children.extend(object.child_objects());
+                            }
+                        }
+                        continue;
+                    }
+                    self.reporter.object_removed(&object);
+                    result.removed_objects.insert(digest);
+                    match object.into_enum() {
+                        graph::object::Enum::Blob(blob) => Some(blob),
+                        _ => None,
+                    }
+                }
+                None => Some(graph::Blob::new(digest, usage.size)),
+            };
+            if let Some(blob) = payload {
+                if !self.dry_run {
+                    match self.repo.remove_payload(*blob.payload()).await {
+                        Err(Error::UnknownObject(_)) => {}
+                        res => res?,
+                    }
+                }
+                result.removed_payloads.insert(*blob.payload());
+                self.reporter.payload_removed(&blob);
+            }
+            tracing::debug!(
+                %digest,
+                size = usage.size,
+                freed = usage.reclaimable,
+                last_used = %DateTime::<Utc>::from(usage.last_used),
+                "removed least recently used data"
+            );
+            result.removed_bytes += usage.reclaimable;
+            available = available.saturating_add(usage.reclaimable);
+        }
+        result.available_space = Some(available);
+
+        Ok(result)
+    }
+
+    /// Attach the given children of kept objects, and all of their
+    /// children in turn, returning true if any of them were selected
+    /// for removal.
+    fn attach_candidate_children(
+        &self,
+        mut children: Vec<encoding::Digest>,
+        candidates: &[LeastRecentlyUsedCandidate],
+        index: &HashMap<encoding::Digest, usize>,
+        selected: &HashSet<usize>,
+    ) -> bool {
+        let mut changed = false;
+        while let Some(child) = children.pop() {
+            // anything that is not a candidate is already attached
+            let Some(&i) = index.get(&child) else {
+                continue;
+            };
+            if !self.attached.insert(child) {
+                continue;
+            }
+            changed |= selected.contains(&i);
+            if let Some(object) = &candidates[i].object {
+                children.extend(object.child_objects());
+            }
+        }
+        changed
+    }
+
     /// # Safety
     /// This function should only be called once the discovery of all attached
     /// objects has completed successfully and with no errors. Otherwise, it may
@@ -612,11 +971,14 @@ where
         repo: &storage::fs::OpenFsRepository,
     ) -> Result<CleanResult> {
         let mut result = CleanResult::default();
+        let started = Utc::now();
+        let mut visited = Vec::new();
         let mut stream = repo
             .iter_rendered_manifests()
             .try_filter_map(|digest| {
                 self.reporter.visit_render(&digest);
                 result.visited_renders += 1;
+                visited.push(digest);
                 if self.attached.contains(&digest) {
                     return ready(Ok(None));
                 }
@@ -641,12 +1003,23 @@ where
             .try_filter_map(|(digest, removed)| ready(Ok(removed.then_some(digest))))
             .boxed();
         let removed_for_user = result.removed_renders.entry(username.clone()).or_default();
-        while let Some(digest) = stream.try_next().await? {
+        while let Some(digest) = cancellable(&self.cancellation, stream.try_next()).await? {
             removed_for_user.insert(digest);
             self.reporter.render_removed(&digest);
         }
         drop(stream);
 
+        if !self.dry_run {
+            // the catalog is only used to find renders more quickly,
+            // so there's no need to fail the clean if it can't be updated
+            let present = visited
+                .into_iter()
+                .filter(|digest| !removed_for_user.contains(digest));
+            if let Err(err) = repo.compact_render_catalog(present, started).await {
+                tracing::warn!(?err, ?username, "failed to compact render catalog");
+            }
+        }
+
         if let Some(proxy_path) = repo.proxy_path() {
             result += self.clean_proxies(username, proxy_path.to_owned()).await?;
         }
@@ -762,6 +1135,13 @@ pub struct CleanResult {
     /// The proxy payloads removed (by associated username)
     pub removed_proxies: HashMap<Option<String>, HashSet<encoding::Digest>>,
 
+    /// The total size of the objects and payloads removed, which
+    /// is only known when cleaning to a free space target
+    pub removed_bytes: u64,
+    /// The space expected to be available on the repository filesystem
+    /// once the clean is complete, when cleaning to a free space target
+    pub available_space: Option<u64>,
+
     /// Non-fatal errors encountered while cleaning.
     ///
     /// These are errors that stopped one or more items from
@@ -795,6 +1175,8 @@ impl std::ops::AddAssign for CleanResult {
             removed_renders,
             visited_proxies,
             removed_proxies,
+            removed_bytes,
+            available_space,
             errors,
         } = rhs;
         for (spec, tags) in pruned_tags {
@@ -819,10 +1201,87 @@ impl std::ops::AddAssign for CleanResult {
         self.removed_payloads.extend(removed_payloads);
         self.visited_renders += visited_renders;
         self.visited_proxies += visited_proxies;
+        self.removed_bytes += removed_bytes;
+        self.available_space = available_space.or(self.available_space);
         self.errors.extend(errors);
     }
 }
 
+/// Detached data that may be removed to reach a free space target
+struct LeastRecentlyUsedCandidate {
+    /// The digest of the object, or of the payload if it has no blob
+    digest: encoding::Digest,
+    object: Option<graph::Object>,
+    usage: FileUsage,
+}
+
+/// The disk usage of one or more files in a repository
+struct FileUsage {
+    size: u64,
+    /// The number of bytes that removing the files would free, which
+    /// excludes files that are also hard linked elsewhere (eg: renders)
+    reclaimable: u64,
+    /// The latest time that any of the files were modified
+    modified: SystemTime,
+    /// The latest time that any of the files were accessed or modified
+    last_used: SystemTime,
+}
+
+impl FileUsage {
+    /// Read the usage of the file at the given path, if it exists
+    async fn read(path: &Path) -> Result<Option<Self>> {
+        let meta = match tokio::fs::symlink_metadata(path).await {
+            Ok(meta) => meta,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(Error::StorageReadError(
+                    "metadata on repository file",
+                    path.to_owned(),
+                    err,
+                ))
+            }
+        };
+        let modified = meta.modified().map_err(|err| {
+            Error::StorageReadError("modified time on repository file", path.to_owned(), err)
+        })?;
+        // access times are not tracked by every filesystem, and
+        // may not be updated when a file is only written to
+        let last_used = match meta.accessed() {
+            Ok(accessed) => accessed.max(modified),
+            Err(_) => modified,
+        };
+        let reclaimable = if meta.st_nlink() > 1 { 0 } else { meta.len() };
+        Ok(Some(Self {
+            size: meta.len(),
+            reclaimable,
+            modified,
+            last_used,
+        }))
+    }
+}
+
+impl std::ops::AddAssign for FileUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.size += rhs.size;
+        self.reclaimable += rhs.reclaimable;
+        self.modified = self.modified.max(rhs.modified);
+        self.last_used = self.last_used.max(rhs.last_used);
+    }
+}
+
+/// The number of bytes available to unprivileged users
+/// on the filesystem that contains the given path
+fn available_space(path: &Path) -> Result<u64> {
+    let stat = nix::sys::statvfs::statvfs(path).map_err(|err| {
+        Error::StorageReadError(
+            "statvfs on repository root",
+            path.to_owned(),
+            std::io::Error::from(err),
+        )
+    })?;
+    Ok(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
 pub trait CleanReporter: Send + Sync {
     /// Called when the cleaner visits a tag
     fn visit_tag(&self, _tag: &tracking::Tag) {}
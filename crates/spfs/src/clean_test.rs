@@ -6,6 +6,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::Utc;
+use futures::TryStreamExt;
 use rstest::rstest;
 use storage::prelude::*;
 use tokio::time::sleep;
@@ -185,6 +186,56 @@ async fn test_clean_untagged_objects(#[future] tmprepo: TempRepo, tmpdir: tempfi
     }
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_clean_keeps_history_of_preserved_tags(#[future] tmprepo: TempRepo) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let audit_tag = tracking::TagSpec::parse("spk/audit/my-pkg").unwrap();
+    let other_tag = tracking::TagSpec::parse("my_tag").unwrap();
+    for i in 0..3 {
+        let digest = tmprepo
+            .commit_blob(Box::pin(std::io::Cursor::new(
+                format!("entry {i}").into_bytes(),
+            )))
+            .await
+            .unwrap();
+        tmprepo.push_tag(&audit_tag, &digest).await.unwrap();
+        tmprepo.push_tag(&other_tag, &digest).await.unwrap();
+    }
+
+    let cleaner = Cleaner::new(&tmprepo)
+        .with_reporter(TracingCleanReporter)
+        .with_prune_tags_if_version_more_than(Some(0));
+    let result = cleaner
+        .prune_all_tags_and_clean()
+        .await
+        .expect("failed to clean objects");
+    println!("{result:#?}");
+
+    let audit_history: Vec<_> = tmprepo
+        .read_tag(&audit_tag)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        audit_history.len(),
+        3,
+        "preserved tags should never be pruned"
+    );
+    let other_history: Vec<_> = tmprepo
+        .read_tag(&other_tag)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(other_history.len(), 1, "other tags should still be pruned");
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_clean_untagged_objects_layers_platforms(#[future] tmprepo: TempRepo) {
@@ -277,6 +328,183 @@ async fn test_clean_manifest_renders(tmpdir: tempfile::TempDir) {
     );
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_clean_until_free_keeps_data_when_target_reached(
+    #[future] tmprepo: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let data_dir = tmpdir.path().join("data");
+    ensure(data_dir.join("dir/dir/test.file"), "hello");
+    let manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(data_dir.as_path())
+        .await
+        .unwrap();
+
+    let cleaner = Cleaner::new(&tmprepo)
+        .with_reporter(TracingCleanReporter)
+        .with_free_space_target(Some(0));
+    let result = cleaner
+        .prune_all_tags_and_clean()
+        .await
+        .expect("failed to clean repo");
+    println!("{result:#?}");
+
+    assert_eq!(result.removed_bytes, 0);
+    assert!(result.removed_objects.is_empty());
+    for node in manifest.walk() {
+        if !node.entry.kind.is_blob() {
+            continue;
+        }
+        tmprepo
+            .open_payload(node.entry.object)
+            .await
+            .expect("expected payload not to be cleaned");
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_clean_until_free_removes_least_recently_used(
+    #[future] tmprepo: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let old_dir = tmpdir.path().join("old");
+    ensure(old_dir.join("dir/dir/test.file"), "old hello");
+    crate::Committer::new(&tmprepo)
+        .commit_dir(old_dir.as_path())
+        .await
+        .unwrap();
+
+    // Ensure that the new data is used a measurable amount
+    // of time after the old data.
+    sleep(Duration::from_millis(250)).await;
+
+    let new_dir = tmpdir.path().join("new");
+    ensure(new_dir.join("dir/dir/test.file"), "new hello");
+    let new_manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(new_dir.as_path())
+        .await
+        .unwrap();
+
+    let RepositoryHandle::FS(fs_repo) = &*tmprepo else {
+        panic!("Unexpected tmprepo type!");
+    };
+    // one more byte than is currently available should
+    // only require the oldest data to be removed
+    let target = super::available_space(&fs_repo.root()).unwrap() + 1;
+    let cleaner = Cleaner::new(&tmprepo)
+        .with_reporter(TracingCleanReporter)
+        .with_free_space_target(Some(target));
+    let result = cleaner
+        .prune_all_tags_and_clean()
+        .await
+        .expect("failed to clean repo");
+    println!("{result:#?}");
+
+    assert!(result.removed_bytes > 0, "expected some data to be removed");
+    assert!(!result.removed_objects.is_empty());
+    tmprepo
+        .read_object(new_manifest.to_graph_manifest().digest().unwrap())
+        .await
+        .expect("expected recently used manifest not to be cleaned");
+    for node in new_manifest.walk() {
+        if !node.entry.kind.is_blob() {
+            continue;
+        }
+        tmprepo
+            .open_payload(node.entry.object)
+            .await
+            .expect("expected recently used payload not to be cleaned");
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_clean_until_free_keeps_children_of_recently_used(
+    #[future] tmprepo: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let old_dir = tmpdir.path().join("old");
+    ensure(old_dir.join("dir/shared.file"), "shared hello");
+    ensure(old_dir.join("dir/old.file"), "old hello");
+    crate::Committer::new(&tmprepo)
+        .commit_dir(old_dir.as_path())
+        .await
+        .unwrap();
+
+    sleep(Duration::from_millis(250)).await;
+
+    // the shared file is not written again, and so its data is
+    // as old as the rest of the old data even though it is needed
+    // by the most recently used manifest
+    let new_dir = tmpdir.path().join("new");
+    ensure(new_dir.join("dir/shared.file"), "shared hello");
+    ensure(new_dir.join("dir/new.file"), "new hello");
+    let new_manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(new_dir.as_path())
+        .await
+        .unwrap();
+
+    let RepositoryHandle::FS(fs_repo) = &*tmprepo else {
+        panic!("Unexpected tmprepo type!");
+    };
+    let target = super::available_space(&fs_repo.root()).unwrap() + 1;
+    let cleaner = Cleaner::new(&tmprepo)
+        .with_reporter(TracingCleanReporter)
+        .with_free_space_target(Some(target));
+    let result = cleaner
+        .prune_all_tags_and_clean()
+        .await
+        .expect("failed to clean repo");
+    println!("{result:#?}");
+
+    tmprepo
+        .read_object(new_manifest.to_graph_manifest().digest().unwrap())
+        .await
+        .expect("expected recently used manifest not to be cleaned");
+    for node in new_manifest.walk() {
+        if !node.entry.kind.is_blob() {
+            continue;
+        }
+        assert!(
+            !result.removed_payloads.contains(&node.entry.object),
+            "expected no payload of a kept manifest to be removed: {}",
+            node.path
+        );
+        tmprepo
+            .open_payload(node.entry.object)
+            .await
+            .expect("expected payload of a kept manifest not to be cleaned");
+    }
+}
+
+#[rstest]
+#[case::tar(tmprepo("tar"))]
+#[tokio::test]
+async fn test_clean_until_free_requires_fs_repo(
+    #[case]
+    #[future]
+    tmprepo: TempRepo,
+) {
+    let tmprepo = tmprepo.await;
+    let cleaner = Cleaner::new(&tmprepo).with_free_space_target(Some(1));
+    let res = cleaner.prune_all_tags_and_clean().await;
+    assert!(
+        res.is_err(),
+        "should not be able to clean to a free space target"
+    );
+}
+
 fn list_files<P: AsRef<std::path::Path>>(dirname: P) -> Vec<String> {
     let mut all_files = Vec::new();
 
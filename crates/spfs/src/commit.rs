@@ -2,25 +2,33 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::future::ready;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
-use futures::{FutureExt, StreamExt, TryStreamExt};
+use futures::{StreamExt, TryStreamExt};
 use once_cell::sync::OnceCell;
 use progress_bar_derive_macro::ProgressBar;
 use spfs_encoding::prelude::*;
+use tokio::io::AsyncReadExt;
 
 use super::status::remount_runtime;
+use crate::operation::{cancellable, CancellationToken};
 use crate::prelude::*;
-use crate::tracking::{BlobHasher, BlobRead, ManifestBuilder, PathFilter};
-use crate::{encoding, graph, runtime, storage, tracking, Error, Result};
+use crate::tracking::{BlobHasher, BlobRead, BlobReadExt, ManifestBuilder, PathFilter};
+use crate::{encoding, get_config, graph, runtime, storage, tracking, Error, Result};
 
 #[cfg(test)]
 #[path = "./commit_test.rs"]
 mod commit_test;
 
+/// The default size limit for files that are kept in memory after
+/// being hashed by the [`Committer`].
+/// See: [`Committer::with_max_buffered_blob_size`]
+pub const DEFAULT_MAX_BUFFERED_BLOB_SIZE: u64 = 256 * 1024;
+
 /// Hashes blob data in-memory.
 ///
 /// Used in conjunction with the [`Committer`], this hasher
@@ -30,12 +38,17 @@ mod commit_test;
 /// that already exists, but in a worst-case scenario will require
 /// reading the local files twice (once for hashing and once to copy
 /// into the repository)
-pub struct InMemoryBlobHasher;
+#[derive(Default)]
+pub struct InMemoryBlobHasher {
+    /// The algorithm used to digest each blob, which must
+    /// match the one used by the destination repository
+    pub digest_algorithm: encoding::DigestAlgorithm,
+}
 
 #[tonic::async_trait]
 impl BlobHasher for InMemoryBlobHasher {
     async fn hash_blob(&self, reader: Pin<Box<dyn BlobRead>>) -> Result<encoding::Digest> {
-        Ok(encoding::Hasher::hash_async_reader(reader).await?)
+        Ok(encoding::Hasher::hash_async_reader_with(self.digest_algorithm, reader).await?)
     }
 }
 
@@ -57,6 +70,280 @@ impl<'repo> BlobHasher for WriteToRepositoryBlobHasher<'repo> {
     }
 }
 
+/// Hashes blob data in-memory, writing blobs to a repository as soon as
+/// they have been hashed when used by [`Committer::commit_dir`].
+///
+/// This is the default hasher of the [`Committer`]. Files that are no
+/// larger than the committer's buffer size are kept in memory while they
+/// are hashed, and are then checked against the repository in batches and
+/// written from memory if they are missing, so that they are only read
+/// from disk once and are written while other files are still being
+/// hashed. Each unique payload is only checked and written once, no matter
+/// how many files share it. Larger files are streamed into the repository
+/// as they are hashed, unless payloads are chunked, in which case they are
+/// only hashed and are written afterwards if they are missing.
+///
+/// Outside of [`Committer::commit_dir`], such as when computing a
+/// manifest with [`Committer::manifest_for_path`], nothing is written and
+/// this behaves like the [`InMemoryBlobHasher`].
+pub struct PipelinedBlobHasher<'repo> {
+    repo: &'repo RepositoryHandle,
+    pipeline: Arc<CommitPipeline>,
+}
+
+tokio::task_local! {
+    /// The pipeline of the commit whose manifest is currently being computed
+    static ACTIVE_PIPELINE: Arc<CommitPipeline>;
+}
+
+impl<'repo> PipelinedBlobHasher<'repo> {
+    /// True if this hasher is being used to commit a directory
+    fn is_committing(&self) -> bool {
+        ACTIVE_PIPELINE
+            .try_with(|active| Arc::ptr_eq(active, &self.pipeline))
+            .unwrap_or_default()
+    }
+
+    /// Write a file that is too large to be kept in memory as it is
+    /// hashed, where `buffer` holds the data that was already read.
+    async fn commit_stream(
+        &self,
+        buffer: Vec<u8>,
+        reader: Pin<Box<dyn BlobRead>>,
+    ) -> Result<encoding::Digest> {
+        let permissions = reader.permissions();
+        let reader = tokio::io::BufReader::new(std::io::Cursor::new(buffer).chain(reader));
+        let reader: Pin<Box<dyn BlobRead>> = match permissions {
+            Some(permissions) => Box::pin(reader.with_permissions(permissions)),
+            None => Box::pin(reader),
+        };
+        // Safety: the blob for this payload is written by the committer,
+        // as soon as the payloads for the rest of the manifest are written
+        let (digest, size) = unsafe { self.repo.write_data(reader).await? };
+        if self.pipeline.claim(digest) {
+            self.pipeline
+                .blobs
+                .lock()
+                .expect("commit pipeline lock should not be poisoned")
+                .push(graph::Blob::new(digest, size).into());
+            self.pipeline.finish(digest, Some(true));
+        }
+        Ok(digest)
+    }
+}
+
+#[tonic::async_trait]
+impl<'repo> BlobHasher for PipelinedBlobHasher<'repo> {
+    async fn hash_blob(&self, mut reader: Pin<Box<dyn BlobRead>>) -> Result<encoding::Digest> {
+        if !self.is_committing() {
+            return Ok(encoding::Hasher::hash_async_reader_with(
+                self.repo.digest_algorithm(),
+                reader,
+            )
+            .await?);
+        }
+        let max_size = self.pipeline.max_buffered_size.load(Ordering::Relaxed);
+        let mut buffer = Vec::new();
+        (&mut reader)
+            .take(max_size.saturating_add(1))
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(encoding::Error::FailedRead)?;
+        if buffer.len() as u64 > max_size {
+            if !storage::chunking::is_enabled() {
+                return self.commit_stream(buffer, reader).await;
+            }
+            // the size of the file is not known until it has been read,
+            // so the committer writes it separately in case it is chunked
+            let mut hasher =
+                encoding::Hasher::with_algorithm(self.repo.digest_algorithm(), tokio::io::sink());
+            hasher.update(&buffer);
+            drop(buffer);
+            tokio::io::copy(&mut reader, &mut hasher)
+                .await
+                .map_err(encoding::Error::FailedRead)?;
+            return Ok(hasher.digest());
+        }
+        let mut hasher =
+            encoding::Hasher::with_algorithm(self.repo.digest_algorithm(), tokio::io::sink());
+        hasher.update(&buffer);
+        let digest = hasher.digest();
+        if !self.pipeline.claim(digest) {
+            // another file with the same contents is handling it
+            return Ok(digest);
+        }
+        if let Some(batch) = self.pipeline.queue(digest, buffer) {
+            self.pipeline.commit_batch(self.repo, batch).await?;
+        }
+        Ok(digest)
+    }
+}
+
+/// The number of buffered blobs that are checked against
+/// the repository at once by a [`PipelinedBlobHasher`]
+const PIPELINED_BATCH_SIZE: usize = 64;
+
+/// The number of blobs that are checked against
+/// the repository at once by [`Committer::commit_dir`]
+const EXISTING_BLOBS_BATCH_SIZE: usize = 1024;
+
+/// The state of a blob that was handled by a [`PipelinedBlobHasher`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PipelinedBlob {
+    /// The blob is still being checked or written
+    Pending,
+    /// The blob already existed in the repository
+    Existed,
+    /// The blob was written to the repository
+    Written,
+}
+
+/// The state that is shared between a [`Committer`]
+/// and its [`PipelinedBlobHasher`]
+#[derive(Default)]
+struct CommitPipeline {
+    max_buffered_size: AtomicU64,
+    handled: Mutex<HashMap<encoding::Digest, PipelinedBlob>>,
+    /// The buffered payloads that are waiting to be checked
+    /// against the repository and written if needed
+    queued: Mutex<Vec<(encoding::Digest, Vec<u8>)>>,
+    /// The blobs of the payloads that were written while hashing,
+    /// which are written in a batch once the commit is complete
+    blobs: Mutex<Vec<graph::Object>>,
+}
+
+impl CommitPipeline {
+    /// Claim the given blob to be checked and written, returning
+    /// false if it has already been claimed.
+    fn claim(&self, digest: encoding::Digest) -> bool {
+        let mut handled = self
+            .handled
+            .lock()
+            .expect("commit pipeline lock should not be poisoned");
+        if handled.contains_key(&digest) {
+            return false;
+        }
+        handled.insert(digest, PipelinedBlob::Pending);
+        true
+    }
+
+    /// Record the outcome of a claimed blob, where `None` means that
+    /// it failed and could be claimed again.
+    fn finish(&self, digest: encoding::Digest, written: Option<bool>) {
+        let mut handled = self
+            .handled
+            .lock()
+            .expect("commit pipeline lock should not be poisoned");
+        match written {
+            Some(true) => handled.insert(digest, PipelinedBlob::Written),
+            Some(false) => handled.insert(digest, PipelinedBlob::Existed),
+            None => handled.remove(&digest),
+        };
+    }
+
+    /// The state of the given blob, if it was handled while hashing
+    fn get(&self, digest: &encoding::Digest) -> Option<PipelinedBlob> {
+        self.handled
+            .lock()
+            .expect("commit pipeline lock should not be poisoned")
+            .get(digest)
+            .copied()
+    }
+
+    /// Queue a claimed payload to be committed, returning
+    /// a full batch once one is ready to be committed.
+    fn queue(
+        &self,
+        digest: encoding::Digest,
+        buffer: Vec<u8>,
+    ) -> Option<Vec<(encoding::Digest, Vec<u8>)>> {
+        let mut queued = self
+            .queued
+            .lock()
+            .expect("commit pipeline lock should not be poisoned");
+        queued.push((digest, buffer));
+        if queued.len() < PIPELINED_BATCH_SIZE {
+            return None;
+        }
+        Some(std::mem::take(&mut *queued))
+    }
+
+    /// Take any payloads that are still waiting to be committed
+    fn take_queued(&self) -> Vec<(encoding::Digest, Vec<u8>)> {
+        std::mem::take(
+            &mut *self
+                .queued
+                .lock()
+                .expect("commit pipeline lock should not be poisoned"),
+        )
+    }
+
+    /// Write each of the given payloads unless the repository already has it.
+    ///
+    /// Payloads that fail are released so that the committer
+    /// can read and write them again on its own.
+    async fn commit_batch(
+        &self,
+        repo: &RepositoryHandle,
+        batch: Vec<(encoding::Digest, Vec<u8>)>,
+    ) -> Result<()> {
+        let digests: Vec<_> = batch.iter().map(|(digest, _)| *digest).collect();
+        let has_objects = repo.has_objects(&digests).await;
+        let results = futures::future::join_all(batch.into_iter().zip(has_objects).map(
+            |((digest, buffer), has_object)| async move {
+                let result = self.commit_buffer(repo, digest, buffer, has_object).await;
+                self.finish(digest, result.as_ref().ok().copied());
+                result
+            },
+        ))
+        .await;
+        results.into_iter().collect::<Result<Vec<_>>>().map(|_| ())
+    }
+
+    /// Write the given payload data unless the repository already has it,
+    /// returning true if it was written.
+    async fn commit_buffer(
+        &self,
+        repo: &RepositoryHandle,
+        digest: encoding::Digest,
+        buffer: Vec<u8>,
+        has_object: bool,
+    ) -> Result<bool> {
+        if has_object && repo.has_payload(digest).await {
+            return Ok(false);
+        }
+        let size = buffer.len() as u64;
+        let reader = Box::pin(std::io::Cursor::new(buffer));
+        if storage::chunking::should_chunk(size) {
+            repo.commit_blob(reader).await?;
+            return Ok(true);
+        }
+        // Safety: the blob for this payload is written by the committer,
+        // as soon as the payloads for the rest of the manifest are written
+        let (written, size) = unsafe { repo.write_data(reader).await? };
+        if written != digest {
+            return Err(Error::String(format!(
+                "Payload was written with an unexpected digest: {written} != {digest}"
+            )));
+        }
+        self.blobs
+            .lock()
+            .expect("commit pipeline lock should not be poisoned")
+            .push(graph::Blob::new(digest, size).into());
+        Ok(true)
+    }
+
+    /// Take the blobs that still need to be written to the repository
+    fn take_blobs(&self) -> Vec<graph::Object> {
+        std::mem::take(
+            &mut *self
+                .blobs
+                .lock()
+                .expect("commit pipeline lock should not be poisoned"),
+        )
+    }
+}
+
 /// Manages the process of committing files to a repository
 pub struct Committer<
     'repo,
@@ -71,23 +358,49 @@ pub struct Committer<
     repo: &'repo storage::RepositoryHandle,
     reporter: Arc<Reporter>,
     builder: ManifestBuilder<H, F, Arc<Reporter>>,
+    /// Shared with the blob hasher, when it is a [`PipelinedBlobHasher`]
+    pipeline: Option<Arc<CommitPipeline>>,
     max_concurrent_blobs: usize,
     allow_empty: bool,
+    cancellation: CancellationToken,
 }
 
-impl<'repo> Committer<'repo, InMemoryBlobHasher, (), SilentCommitReporter> {
-    /// Create a new committer, with the default [`InMemoryBlobHasher`].
+impl<'repo> Committer<'repo, PipelinedBlobHasher<'repo>, (), SilentCommitReporter> {
+    /// Create a new committer, with the default [`PipelinedBlobHasher`].
+    ///
+    /// Extended attributes are captured if `storage.preserve_xattrs`
+    /// is enabled in the spfs config, and the buffer size of the hasher
+    /// is taken from `commit.max_buffered_blob_size`.
     pub fn new(repo: &'repo storage::RepositoryHandle) -> Self {
         let reporter = Arc::new(SilentCommitReporter);
+        let config = get_config();
+        let preserve_xattrs = config
+            .as_ref()
+            .map(|config| config.storage.preserve_xattrs)
+            .unwrap_or_default();
+        let max_buffered_size = config
+            .as_ref()
+            .map(|config| config.commit.max_buffered_blob_size)
+            .unwrap_or(DEFAULT_MAX_BUFFERED_BLOB_SIZE);
+        let pipeline = Arc::new(CommitPipeline {
+            max_buffered_size: AtomicU64::new(max_buffered_size),
+            ..Default::default()
+        });
         let builder = ManifestBuilder::new()
-            .with_blob_hasher(InMemoryBlobHasher)
+            .with_preserve_xattrs(preserve_xattrs)
+            .with_blob_hasher(PipelinedBlobHasher {
+                repo,
+                pipeline: Arc::clone(&pipeline),
+            })
             .with_reporter(Arc::clone(&reporter));
         Self {
             repo,
             reporter,
             builder,
+            pipeline: Some(pipeline),
             max_concurrent_blobs: tracking::DEFAULT_MAX_CONCURRENT_BLOBS,
             allow_empty: false,
+            cancellation: CancellationToken::new(),
         }
     }
 }
@@ -106,6 +419,15 @@ where
         self
     }
 
+    /// Stop committing with [`Error::OperationCancelled`] once the
+    /// given token is cancelled.
+    ///
+    /// Any blobs that were already written are left in the repository.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
     /// Set how many blobs should be processed at once.
     ///
     /// Defaults to [`tracking::DEFAULT_MAX_CONCURRENT_BLOBS`].
@@ -115,6 +437,24 @@ where
         self
     }
 
+    /// Set the size of the largest file that is kept in memory after it
+    /// is hashed, so that it does not need to be read again to be written.
+    ///
+    /// Up to [`Self::with_max_concurrent_blobs`] files of this size can be
+    /// held in memory at once. A size of zero reads every file that is not
+    /// already in the repository twice. This has no effect once another
+    /// blob hasher is given with [`Self::with_blob_hasher`].
+    ///
+    /// Defaults to the `commit.max_buffered_blob_size` value from the spfs config.
+    pub fn with_max_buffered_blob_size(self, max_buffered_blob_size: u64) -> Self {
+        if let Some(pipeline) = &self.pipeline {
+            pipeline
+                .max_buffered_size
+                .store(max_buffered_blob_size, Ordering::Relaxed);
+        }
+        self
+    }
+
     /// Set how many branches should be processed at once (during manifest building).
     ///
     /// Each tree/folder that is processed can have any number of subtrees. This number
@@ -131,10 +471,19 @@ where
         self
     }
 
+    /// Capture the extended attributes of files when committing.
+    ///
+    /// See [`ManifestBuilder::with_preserve_xattrs`].
+    pub fn with_preserve_xattrs(mut self, preserve_xattrs: bool) -> Self {
+        self.builder = self.builder.with_preserve_xattrs(preserve_xattrs);
+        self
+    }
+
     /// Use the given [`BlobHasher`] when building the manifest.
     ///
-    /// See [`InMemoryBlobHasher`] and [`WriteToRepositoryBlobHasher`] for
-    /// details on different strategies that can be employed when committing.
+    /// See [`PipelinedBlobHasher`], [`InMemoryBlobHasher`] and
+    /// [`WriteToRepositoryBlobHasher`] for details on different
+    /// strategies that can be employed when committing.
     pub fn with_blob_hasher<H2>(self, hasher: H2) -> Committer<'repo, H2, F, R>
     where
         H2: BlobHasher + Send + Sync,
@@ -143,8 +492,10 @@ where
             repo: self.repo,
             builder: self.builder.with_blob_hasher(hasher),
             reporter: self.reporter,
+            pipeline: None,
             max_concurrent_blobs: self.max_concurrent_blobs,
             allow_empty: self.allow_empty,
+            cancellation: self.cancellation,
         }
     }
 
@@ -158,8 +509,10 @@ where
             repo: self.repo,
             builder: self.builder.with_reporter(Arc::clone(&reporter)),
             reporter,
+            pipeline: self.pipeline,
             max_concurrent_blobs: self.max_concurrent_blobs,
             allow_empty: self.allow_empty,
+            cancellation: self.cancellation,
         }
     }
 
@@ -178,8 +531,10 @@ where
             repo: self.repo,
             builder: self.builder.with_path_filter(filter),
             reporter: self.reporter,
+            pipeline: self.pipeline,
             max_concurrent_blobs: self.max_concurrent_blobs,
             allow_empty: self.allow_empty,
+            cancellation: self.cancellation,
         }
     }
 
@@ -237,7 +592,9 @@ where
     /// Calculate the manifest for the given path.
     ///
     /// Returns a tuple of the canonicalized path and its
-    /// [`tracking::Manifest`].
+    /// [`tracking::Manifest`]. Unlike [`Self::commit_dir`], this does
+    /// not write anything to the repository when using the default
+    /// [`PipelinedBlobHasher`].
     pub async fn manifest_for_path<P>(&self, path: P) -> Result<(PathBuf, tracking::Manifest)>
     where
         P: AsRef<Path>,
@@ -252,85 +609,195 @@ where
     ///
     /// This collects all files to store as blobs and maintains a
     /// render of the manifest for use immediately.
+    ///
+    /// Any blobs that were not already written while the manifest was
+    /// computed (see [`PipelinedBlobHasher`]) are checked against the
+    /// repository first in batches, once for each unique payload, and only the missing
+    /// ones are read again from disk to be written.
     pub async fn commit_dir<P>(&self, path: P) -> Result<tracking::Manifest>
     where
         P: AsRef<Path>,
     {
-        let (path, manifest) = self.manifest_for_path(&path).await?;
+        let (path, manifest) = match &self.pipeline {
+            Some(pipeline) => {
+                let computed = ACTIVE_PIPELINE.scope(
+                    Arc::clone(pipeline),
+                    cancellable(&self.cancellation, self.manifest_for_path(&path)),
+                );
+                let computed = computed.await?;
+                let queued = pipeline.take_queued();
+                if !queued.is_empty() {
+                    // any failures are committed again below
+                    if let Err(err) = pipeline.commit_batch(self.repo, queued).await {
+                        tracing::debug!("failed to commit buffered payloads: {err}");
+                    }
+                }
+                computed
+            }
+            None => cancellable(&self.cancellation, self.manifest_for_path(&path)).await?,
+        };
+
+        // files with the same contents share a payload, which
+        // only needs to be checked and written once
+        let mut remaining: HashMap<encoding::Digest, Vec<tracking::OwnedManifestNode>> =
+            HashMap::new();
+        for node in manifest.walk_abs(".") {
+            if !node.entry.kind.is_blob() {
+                continue;
+            }
+            self.reporter.visit_blob(&node);
+            let handled = self
+                .pipeline
+                .as_ref()
+                .and_then(|pipeline| pipeline.get(&node.entry.object));
+            match handled {
+                Some(PipelinedBlob::Written) => self
+                    .reporter
+                    .committed_blob(&CommitBlobResult::Committed(node.into_owned())),
+                Some(PipelinedBlob::Existed) => self
+                    .reporter
+                    .committed_blob(&CommitBlobResult::AlreadyExists(node.into_owned())),
+                Some(PipelinedBlob::Pending) | None => remaining
+                    .entry(node.entry.object)
+                    .or_default()
+                    .push(node.into_owned()),
+            }
+        }
 
-        let mut stream = futures::stream::iter(manifest.walk_abs("."))
-            .filter_map(|node| {
-                if !node.entry.kind.is_blob() {
-                    return ready(None);
+        let mut remaining = remaining.into_iter();
+        let mut missing = Vec::new();
+        loop {
+            let batch: Vec<_> = remaining.by_ref().take(EXISTING_BLOBS_BATCH_SIZE).collect();
+            if batch.is_empty() {
+                break;
+            }
+            let digests: Vec<_> = batch.iter().map(|(digest, _)| *digest).collect();
+            let checked = async {
+                let has_objects = self.repo.has_objects(&digests).await;
+                let checked = futures::stream::iter(batch.into_iter().zip(has_objects))
+                    .map(|((digest, nodes), has_object)| async move {
+                        let exists = has_object && self.repo.has_payload(digest).await;
+                        (exists, nodes)
+                    })
+                    .buffer_unordered(self.max_concurrent_blobs)
+                    .collect::<Vec<_>>()
+                    .await;
+                Ok::<_, Error>(checked)
+            };
+            for (exists, nodes) in cancellable(&self.cancellation, checked).await? {
+                if !exists {
+                    missing.push(nodes);
+                    continue;
+                }
+                for node in nodes {
+                    self.reporter
+                        .committed_blob(&CommitBlobResult::AlreadyExists(node));
+                }
+            }
+        }
+
+        let mut stream = futures::stream::iter(missing)
+            .map(|nodes| {
+                let path = &path;
+                async move {
+                    let blob = self.commit_node(path, &nodes[0]).await?;
+                    Ok::<_, Error>((nodes, blob))
                 }
-                let relative_path = std::path::Path::new(node.path.as_str());
-                self.reporter.visit_blob(&node);
-                let local_path = path.join(relative_path);
-                let node = node.into_owned();
-                let fut = async move {
-                    let entry = &node.entry;
-                    let (has_object, has_payload) = tokio::join!(
-                        self.repo.has_object(entry.object),
-                        self.repo.has_payload(entry.object),
-                    );
-                    if has_object && has_payload {
-                        return Ok(CommitBlobResult::AlreadyExists(node));
-                    }
-                    let created = if entry.is_symlink() {
-                        let content = tokio::fs::read_link(&local_path)
-                            .await
-                            .map_err(|err| {
-                                // TODO: add better message for file missing
-                                Error::StorageWriteError(
-                                    "read link for committing",
-                                    local_path.clone(),
-                                    err,
-                                )
-                            })?
-                            .into_os_string()
-                            .into_string()
-                            .map_err(|_| {
-                                crate::Error::String(
-                                    "Symlinks must point to a valid utf-8 path".to_string(),
-                                )
-                            })?
-                            .into_bytes();
-                        let reader =
-                            Box::pin(tokio::io::BufReader::new(std::io::Cursor::new(content)));
-                        self.repo.commit_blob(reader).await?
-                    } else {
-                        let file = tokio::fs::File::open(&local_path).await.map_err(|err| {
-                            // TODO: add better message for file missing
-                            Error::StorageWriteError(
-                                "open file for committing",
-                                local_path.clone(),
-                                err,
-                            )
-                        })?;
-                        let reader = Box::pin(tokio::io::BufReader::new(file));
-                        self.repo.commit_blob(reader).await?
-                    };
-                    if created != entry.object {
-                        return Err(Error::String(format!(
-                            "File contents changed on disk during commit: {local_path:?} [{created} != {}", entry.object
-                        )));
-                    }
-                    Ok(CommitBlobResult::Committed(node))
-                };
-                ready(Some(fut.boxed()))
             })
             .buffer_unordered(self.max_concurrent_blobs)
             .boxed();
-        while let Some(result) = stream.try_next().await? {
-            self.reporter.committed_blob(&result);
+        let mut blobs = match &self.pipeline {
+            Some(pipeline) => pipeline.take_blobs(),
+            None => Vec::new(),
+        };
+        while let Some((nodes, blob)) = cancellable(&self.cancellation, stream.try_next()).await? {
+            blobs.extend(blob);
+            let mut nodes = nodes.into_iter();
+            if let Some(node) = nodes.next() {
+                self.reporter
+                    .committed_blob(&CommitBlobResult::Committed(node));
+            }
+            for node in nodes {
+                self.reporter
+                    .committed_blob(&CommitBlobResult::AlreadyExists(node));
+            }
         }
         drop(stream);
 
+        // the blobs of all the committed payloads are written together,
+        // which saves many small writes (and round trips) to the repository
+        self.repo.write_objects(&blobs).await?;
+
         let storable = manifest.to_graph_manifest();
         self.repo.write_object(&storable).await?;
 
         Ok(manifest)
     }
+
+    /// Write the payload of the file at the given node, which is
+    /// relative to `root`, returning any blob that still needs to be written.
+    async fn commit_node(
+        &self,
+        root: &Path,
+        node: &tracking::OwnedManifestNode,
+    ) -> Result<Option<graph::Object>> {
+        let entry = &node.entry;
+        let local_path = root.join(node.path.as_str());
+        let mut blob = None;
+        let created = if entry.is_symlink() {
+            let content = tokio::fs::read_link(&local_path)
+                .await
+                .map_err(|err| {
+                    // TODO: add better message for file missing
+                    Error::StorageWriteError("read link for committing", local_path.clone(), err)
+                })?
+                .into_os_string()
+                .into_string()
+                .map_err(|_| {
+                    crate::Error::String("Symlinks must point to a valid utf-8 path".to_string())
+                })?
+                .into_bytes();
+            let reader = Box::pin(tokio::io::BufReader::new(std::io::Cursor::new(content)));
+            self.write_blob_data(reader, entry.size(), &mut blob)
+                .await?
+        } else {
+            let file = tokio::fs::File::open(&local_path).await.map_err(|err| {
+                // TODO: add better message for file missing
+                Error::StorageWriteError("open file for committing", local_path.clone(), err)
+            })?;
+            let reader = Box::pin(tokio::io::BufReader::new(file));
+            self.write_blob_data(reader, entry.size(), &mut blob)
+                .await?
+        };
+        if created != entry.object {
+            return Err(Error::String(format!(
+                "File contents changed on disk during commit: {local_path:?} [{created} != {}",
+                entry.object
+            )));
+        }
+        Ok(blob)
+    }
+
+    /// Write the payload of a blob that is expected to have the given size.
+    ///
+    /// Small payloads are written without their blob, which is instead
+    /// stored in `blob` so that it can be written in a batch with others.
+    /// Larger payloads are committed as usual, so that they can be chunked.
+    async fn write_blob_data(
+        &self,
+        reader: Pin<Box<dyn BlobRead>>,
+        expected_size: u64,
+        blob: &mut Option<graph::Object>,
+    ) -> Result<encoding::Digest> {
+        if storage::chunking::should_chunk(expected_size) {
+            return self.repo.commit_blob(reader).await;
+        }
+        // Safety: the blob for this payload is written by the caller,
+        // as soon as the payloads for the rest of the manifest are written
+        let (digest, size) = unsafe { self.repo.write_data(reader).await? };
+        *blob = Some(graph::Blob::new(digest, size).into());
+        Ok(digest)
+    }
 }
 
 /// The result of committing a single file from a manifest
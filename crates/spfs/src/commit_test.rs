@@ -4,8 +4,9 @@
 
 use rstest::rstest;
 
-use super::Committer;
+use super::{Committer, DEFAULT_MAX_BUFFERED_BLOB_SIZE};
 use crate::fixtures::*;
+use crate::prelude::*;
 use crate::Error;
 
 #[rstest]
@@ -36,3 +37,86 @@ async fn test_commit_empty(tmpdir: tempfile::TempDir) {
         res => panic!("expected nothing to commit, got {res:?}"),
     }
 }
+
+#[rstest]
+#[case::unbuffered(0)]
+#[case::partially_buffered(8)]
+#[case::buffered(DEFAULT_MAX_BUFFERED_BLOB_SIZE)]
+#[tokio::test]
+async fn test_commit_dir_writes_all_blobs(
+    #[case] max_buffered_blob_size: u64,
+    #[future] tmprepo: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    let tmprepo = tmprepo.await;
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("small.txt"), "hello");
+    ensure(src_dir.join("dir/same.txt"), "hello");
+    ensure(
+        src_dir.join("large.txt"),
+        "hello, world, from a larger file",
+    );
+    ensure(
+        src_dir.join("dir/also_large.txt"),
+        "hello, world, from a larger file",
+    );
+
+    let manifest = Committer::new(&tmprepo)
+        .with_max_buffered_blob_size(max_buffered_blob_size)
+        .commit_dir(&src_dir)
+        .await
+        .unwrap();
+
+    for node in manifest.walk() {
+        if !node.entry.kind.is_blob() {
+            continue;
+        }
+        let digest = node.entry.object;
+        assert!(
+            tmprepo.has_payload(digest).await,
+            "missing payload for {}",
+            node.path
+        );
+        tmprepo
+            .read_blob(digest)
+            .await
+            .unwrap_or_else(|err| panic!("missing blob for {}: {err}", node.path));
+    }
+    assert!(
+        tmprepo
+            .has_object(manifest.to_graph_manifest().digest().unwrap())
+            .await
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_manifest_for_path_writes_nothing(
+    #[future] tmprepo: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    let tmprepo = tmprepo.await;
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("small.txt"), "hello");
+    ensure(
+        src_dir.join("large.txt"),
+        "hello, world, from a larger file",
+    );
+
+    let (_, manifest) = Committer::new(&tmprepo)
+        .with_max_buffered_blob_size(8)
+        .manifest_for_path(&src_dir)
+        .await
+        .unwrap();
+
+    for node in manifest.walk() {
+        if !node.entry.kind.is_blob() {
+            continue;
+        }
+        assert!(
+            !tmprepo.has_payload(node.entry.object).await,
+            "computing a manifest should not write {}",
+            node.path
+        );
+    }
+}
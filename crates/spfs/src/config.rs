@@ -3,11 +3,11 @@
 // https://github.com/spkenv/spk
 
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 use derive_builder::Builder;
-use once_cell::sync::OnceCell;
+use once_cell::sync::{Lazy, OnceCell};
 use relative_path::RelativePath;
 use serde::{Deserialize, Serialize};
 use storage::{FromConfig, FromUrl};
@@ -15,7 +15,7 @@ use tokio_stream::StreamExt;
 
 use crate::graph::DEFAULT_SPFS_ANNOTATION_LAYER_MAX_STRING_VALUE_SIZE;
 use crate::storage::{TagNamespaceBuf, TagStorageMut};
-use crate::{graph, runtime, storage, tracking, Error, Result};
+use crate::{encoding, graph, runtime, storage, tracking, Error, Result};
 
 #[cfg(test)]
 #[path = "./config_test.rs"]
@@ -57,7 +57,12 @@ const fn default_monitor_max_blocking_threads() -> NonZeroUsize {
     unsafe { NonZeroUsize::new_unchecked(2) }
 }
 
+fn default_monitor_prune_renders_interval() -> String {
+    "1d".to_string()
+}
+
 static CONFIG: OnceCell<RwLock<Arc<Config>>> = OnceCell::new();
+static LOADER: Lazy<RwLock<ConfigLoader>> = Lazy::new(|| RwLock::new(ConfigLoader::default()));
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
@@ -101,6 +106,9 @@ pub struct Storage {
     /// is owned by a different user than the current user. Only applies to
     /// payloads readable by "other".
     pub allow_payload_sharing_between_users: bool,
+    /// The strategy to use when rendering into the local repository,
+    /// if one is not otherwise specified (defaults to `HardLink`).
+    pub render_type: Option<crate::storage::fs::RenderType>,
     pub tag_namespace: Option<TagNamespaceBuf>,
     /// The strategy to use when generating new objects.
     ///
@@ -112,6 +120,26 @@ pub struct Storage {
     /// All available formats are still supported for reading.
     #[serde(default)]
     pub encoding_format: graph::object::EncodingFormat,
+    /// The algorithm used to digest file payloads when the local
+    /// repository is first created.
+    ///
+    /// Existing repositories always use the algorithm that they
+    /// were created with, and payloads can only be synced between
+    /// repositories that use the same one.
+    #[serde(default)]
+    pub digest_algorithm: encoding::DigestAlgorithm,
+    /// If true, the extended attributes of files (such as file
+    /// capabilities) are captured when committing, and applied
+    /// again when rendering. See [`crate::tracking::xattrs`] for
+    /// details on which attributes are kept.
+    pub preserve_xattrs: bool,
+    /// Payloads of at least this many bytes are split into
+    /// content-defined chunks when committed, so that similar large
+    /// files share most of their storage and only the changed chunks
+    /// need to be synced. Chunking is disabled when not set.
+    ///
+    /// See [`crate::storage::chunking`] for details.
+    pub chunk_threshold: Option<u64>,
 }
 
 impl Storage {
@@ -131,9 +159,13 @@ impl Default for Storage {
                 .map(|data| data.join(DEFAULT_USER_STORAGE))
                 .unwrap_or_else(|| PathBuf::from(FALLBACK_STORAGE_ROOT)),
             allow_payload_sharing_between_users: false,
+            render_type: None,
             tag_namespace: None,
             digest_strategy: graph::object::DigestStrategy::default(),
             encoding_format: graph::object::EncodingFormat::default(),
+            digest_algorithm: encoding::DigestAlgorithm::default(),
+            preserve_xattrs: false,
+            chunk_threshold: None,
         }
     }
 }
@@ -348,9 +380,29 @@ pub struct Filesystem {
     /// directly in the annotation layer.
     #[serde(default = "Filesystem::default_annotation_size_limit")]
     pub annotation_size_limit: usize,
+
+    /// Whether new runtimes are set up in a user namespace rather
+    /// than with the privileges of the setuid spfs-enter binary
+    pub rootless: crate::runtime::RootlessMode,
+
+    /// The directory that runtimes may be exposed within, see `spfs expose`
+    ///
+    /// Defaults to [`Filesystem::DEFAULT_EXPOSE_ROOT`] when not set.
+    pub expose_root: Option<PathBuf>,
 }
 
 impl Filesystem {
+    /// The directory that runtimes may be exposed within
+    /// when no other one is configured
+    pub const DEFAULT_EXPOSE_ROOT: &'static str = "/tmp";
+
+    /// The directory that runtimes may be exposed within
+    pub fn expose_root(&self) -> &Path {
+        self.expose_root
+            .as_deref()
+            .unwrap_or_else(|| Path::new(Self::DEFAULT_EXPOSE_ROOT))
+    }
+
     /// The default set of secondary repositories to be used by
     /// the runtime filesystem
     pub fn default_secondary_repositories() -> Vec<String> {
@@ -384,6 +436,39 @@ impl Default for Fuse {
     }
 }
 
+/// Configuration options for rendering layers to disk
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Render {
+    /// The total number of blobs that can be rendered concurrently
+    pub max_concurrent_blobs: usize,
+}
+
+impl Default for Render {
+    fn default() -> Self {
+        Self {
+            max_concurrent_blobs: crate::storage::fs::DEFAULT_MAX_CONCURRENT_BLOBS,
+        }
+    }
+}
+
+/// Configuration options for committing files to a repository
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Commit {
+    /// Files of up to this many bytes are kept in memory after being
+    /// hashed, so that they can be written without reading them again
+    pub max_buffered_blob_size: u64,
+}
+
+impl Default for Commit {
+    fn default() -> Self {
+        Self {
+            max_buffered_blob_size: crate::commit::DEFAULT_MAX_BUFFERED_BLOB_SIZE,
+        }
+    }
+}
+
 /// Configuration options for the monitor process
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
@@ -392,6 +477,13 @@ pub struct Monitor {
     pub worker_threads: NonZeroUsize,
     #[serde(default = "default_monitor_max_blocking_threads")]
     pub max_blocking_threads: NonZeroUsize,
+    /// When set, the monitor removes local renders that have not been
+    /// used within this amount of time (eg: '30d') once its runtime exits
+    pub prune_renders_unused_for: Option<String>,
+    /// The minimum amount of time between each automatic prune of
+    /// the local renders (eg: '12h')
+    #[serde(default = "default_monitor_prune_renders_interval")]
+    pub prune_renders_interval: String,
 }
 
 impl Default for Monitor {
@@ -399,6 +491,8 @@ impl Default for Monitor {
         Self {
             worker_threads: default_monitor_worker_threads(),
             max_blocking_threads: default_monitor_max_blocking_threads(),
+            prune_renders_unused_for: None,
+            prune_renders_interval: default_monitor_prune_renders_interval(),
         }
     }
 }
@@ -432,6 +526,8 @@ pub struct Config {
     pub remote: std::collections::HashMap<String, Remote>,
     pub fuse: Fuse,
     pub monitor: Monitor,
+    pub render: Render,
+    pub commit: Commit,
     pub sentry: Sentry,
 }
 
@@ -446,6 +542,14 @@ impl Config {
         load_config()
     }
 
+    /// Load the config again from the same sources as the current
+    /// one, and make it the current global config.
+    ///
+    /// See [`reload_config`].
+    pub fn reload() -> Result<Arc<Self>> {
+        reload_config()
+    }
+
     /// Make this config the current global one
     pub fn make_current(self) -> Result<Arc<Self>> {
         // Note we don't know if we won the race to set the value here,
@@ -503,10 +607,11 @@ impl Config {
                 Some(self.storage.root.join("ci").join(format!("pipeline_{id}")));
         }
 
-        let mut local_repo = storage::fs::OpenFsRepository::create(
+        let mut local_repo = storage::fs::OpenFsRepository::create_with_digest_algorithm(
             use_ci_isolated_storage_path
                 .as_ref()
                 .unwrap_or(&self.storage.root),
+            self.storage.digest_algorithm,
         )
         .await
         .map_err(|source| Error::FailedToOpenRepository {
@@ -622,7 +727,7 @@ pub trait ToAddress {
 /// Get the current spfs config, fetching it from disk if needed.
 pub fn get_config() -> Result<Arc<Config>> {
     let config = CONFIG.get_or_try_init(|| -> Result<RwLock<Arc<Config>>> {
-        Ok(RwLock::new(Arc::new(load_config()?)))
+        Ok(RwLock::new(Arc::new(current_loader()?.load()?)))
     })?;
     let lock = config.read().map_err(|err| {
         crate::Error::String(format!(
@@ -636,38 +741,173 @@ pub fn get_config() -> Result<Arc<Config>> {
 ///
 /// This includes the default, user and system configurations, if they exist.
 pub fn load_config() -> Result<Config> {
-    use config::FileFormat::Ini;
-    use config::{Config as RawConfig, Environment, File};
-
-    const USER_CONFIG_BASE: &str = "spfs/spfs";
-    let user_config = dirs::config_local_dir()
-        .map(|config| config.join(USER_CONFIG_BASE))
-        .ok_or_else(|| {
-            crate::Error::String(
-                "User config area could not be found, this platform may not be supported".into(),
-            )
-        })?;
+    ConfigLoader::default().load()
+}
 
-    let config = RawConfig::builder()
-        // for backwards compatibility we also support .conf as an ini extension
-        .add_source(File::new("/etc/spfs.conf", Ini).required(false))
-        // the system config can also be in any support format: toml, yaml, json, ini, etc
-        .add_source(File::with_name("/etc/spfs").required(false))
-        // for backwards compatibility we also support .conf as an ini extension
-        .add_source(File::new(&format!("{}.conf", user_config.display()), Ini).required(false))
-        // the user config can also be in any support format: toml, yaml, json, ini, etc
-        .add_source(File::with_name(&format!("{}", user_config.display())).required(false))
-        // Note: if a var using single underscores is set, it will have precedence
-        .add_source(
-            Environment::with_prefix("SPFS")
-                .prefix_separator("_")
-                .separator("__"),
-        )
-        // for backwards compatibility with vars not using double underscores
-        .add_source(Environment::with_prefix("SPFS").separator("_"))
-        .build()?;
+/// Load the spfs configuration again and make it the current global one.
+///
+/// The config is loaded from the same sources that were used for the
+/// current one, including any overrides from the [`ConfigLoader`] that
+/// was last made current. Config values that have already been read
+/// by the caller are not changed, but every later call to [`get_config`]
+/// will see the reloaded values, without the process needing to be
+/// started again.
+pub fn reload_config() -> Result<Arc<Config>> {
+    current_loader()?.load()?.make_current()
+}
+
+fn current_loader() -> Result<ConfigLoader> {
+    let lock = LOADER.read().map_err(|err| {
+        crate::Error::String(format!(
+            "Cannot load config, lock has been poisoned: {err:?}"
+        ))
+    })?;
+    Ok(lock.clone())
+}
 
-    Ok(Config::deserialize(config)?)
+/// Loads the spfs configuration from a number of layered sources.
+///
+/// Each source is applied over the ones before it, so that a value
+/// from a later source takes precedence over the same value from
+/// any earlier one. From lowest to highest precedence, the sources are:
+///
+/// 1. the system config, `/etc/spfs.conf` then `/etc/spfs.{toml,yaml,json,...}`
+/// 2. the user config, in the same formats under the local config directory
+///    (eg: `~/.config/spfs/spfs.toml`)
+/// 3. any additional files, in the order that they were added
+/// 4. `SPFS_*` environment variables
+/// 5. any overrides, in the order that they were added
+///
+/// The system, user and environment sources can each be turned off.
+#[derive(Clone, Debug)]
+pub struct ConfigLoader {
+    system: bool,
+    user: bool,
+    env: bool,
+    files: Vec<PathBuf>,
+    overrides: Vec<(String, String)>,
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self {
+            system: true,
+            user: true,
+            env: true,
+            files: Vec::new(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl ConfigLoader {
+    /// Set whether the system config files are loaded (defaults to true)
+    pub fn with_system_config(mut self, system: bool) -> Self {
+        self.system = system;
+        self
+    }
+
+    /// Set whether the user config files are loaded (defaults to true)
+    pub fn with_user_config(mut self, user: bool) -> Self {
+        self.user = user;
+        self
+    }
+
+    /// Set whether `SPFS_*` environment variables are loaded (defaults to true)
+    pub fn with_env(mut self, env: bool) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Load an additional config file, which must exist.
+    ///
+    /// The format of the file is determined by its extension.
+    pub fn with_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    /// Override a single config value, eg: `storage.root`.
+    ///
+    /// Overrides take precedence over every other source, and
+    /// are typically used for values given on the command line.
+    pub fn with_override<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    /// Load the config from the configured sources
+    pub fn load(&self) -> Result<Config> {
+        use config::FileFormat::Ini;
+        use config::{Config as RawConfig, Environment, File};
+
+        const USER_CONFIG_BASE: &str = "spfs/spfs";
+
+        let mut builder = RawConfig::builder();
+        if self.system {
+            builder = builder
+                // for backwards compatibility we also support .conf as an ini extension
+                .add_source(File::new("/etc/spfs.conf", Ini).required(false))
+                // the system config can also be in any support format: toml, yaml, json, ini, etc
+                .add_source(File::with_name("/etc/spfs").required(false));
+        }
+        if self.user {
+            let user_config = dirs::config_local_dir()
+                .map(|config| config.join(USER_CONFIG_BASE))
+                .ok_or_else(|| {
+                    crate::Error::String(
+                        "User config area could not be found, this platform may not be supported"
+                            .into(),
+                    )
+                })?;
+            builder = builder
+                // for backwards compatibility we also support .conf as an ini extension
+                .add_source(
+                    File::new(&format!("{}.conf", user_config.display()), Ini).required(false),
+                )
+                // the user config can also be in any support format: toml, yaml, json, ini, etc
+                .add_source(File::with_name(&format!("{}", user_config.display())).required(false));
+        }
+        for file in self.files.iter() {
+            builder = builder.add_source(File::from(file.as_path()).required(true));
+        }
+        if self.env {
+            builder = builder
+                // Note: if a var using single underscores is set, it will have precedence
+                .add_source(
+                    Environment::with_prefix("SPFS")
+                        .prefix_separator("_")
+                        .separator("__"),
+                )
+                // for backwards compatibility with vars not using double underscores
+                .add_source(Environment::with_prefix("SPFS").separator("_"));
+        }
+        for (key, value) in self.overrides.iter() {
+            builder = builder.set_override(key.as_str(), value.as_str())?;
+        }
+
+        Ok(Config::deserialize(builder.build()?)?)
+    }
+
+    /// Load the config from these sources and make it the current global one.
+    ///
+    /// These sources are also remembered and used by any later
+    /// call to [`reload_config`].
+    pub fn make_current(self) -> Result<Arc<Config>> {
+        let config = self.load()?;
+        let mut lock = LOADER.write().map_err(|err| {
+            crate::Error::String(format!(
+                "Cannot update config; lock has been poisoned: {err:?}"
+            ))
+        })?;
+        *lock = self;
+        drop(lock);
+        config.make_current()
+    }
 }
 
 /// Open the repository at the given url address
@@ -4,10 +4,10 @@
 
 use rstest::rstest;
 
-use super::{Config, Remote, RemoteConfig, RepositoryConfig};
+use super::{Config, ConfigLoader, Remote, RemoteConfig, RepositoryConfig};
 use crate::storage::prelude::*;
 use crate::storage::RepositoryHandle;
-use crate::{get_config, load_config};
+use crate::{get_config, load_config, reload_config};
 
 #[rstest]
 fn test_config_list_remote_names_empty() {
@@ -133,6 +133,76 @@ fn test_make_current_updates_config() {
     assert_eq!(current_config.user.name, changed_name);
 }
 
+#[rstest]
+fn test_config_loader_precedence() {
+    let tmpdir = tempfile::Builder::new()
+        .prefix("spfs-test")
+        .tempdir()
+        .unwrap();
+    let first = tmpdir.path().join("first.toml");
+    let second = tmpdir.path().join("second.yaml");
+    std::fs::write(&first, "[user]\nname = \"first\"\ndomain = \"first\"\n").unwrap();
+    std::fs::write(&second, "user:\n  name: second\n").unwrap();
+
+    let loader = ConfigLoader::default()
+        .with_system_config(false)
+        .with_user_config(false)
+        .with_env(false)
+        .with_file(&first)
+        .with_file(&second);
+    let config = loader.load().unwrap();
+    assert_eq!(config.user.name, "second", "later files take precedence");
+    assert_eq!(
+        config.user.domain, "first",
+        "values are merged across files"
+    );
+
+    let config = loader
+        .with_override("user.name", "override")
+        .load()
+        .unwrap();
+    assert_eq!(config.user.name, "override", "overrides take precedence");
+
+    ConfigLoader::default()
+        .with_file(tmpdir.path().join("missing.toml"))
+        .load()
+        .expect_err("additional files are required to exist");
+}
+
+#[rstest]
+fn test_reload_config_uses_current_loader() {
+    let tmpdir = tempfile::Builder::new()
+        .prefix("spfs-test")
+        .tempdir()
+        .unwrap();
+    let file = tmpdir.path().join("spfs.toml");
+    std::fs::write(&file, "[user]\nname = \"before\"\n").unwrap();
+
+    let config = ConfigLoader::default()
+        .with_system_config(false)
+        .with_user_config(false)
+        .with_env(false)
+        .with_file(&file)
+        .with_override("user.domain", "overridden")
+        .make_current()
+        .unwrap();
+    assert_eq!(config.user.name, "before");
+
+    std::fs::write(&file, "[user]\nname = \"after\"\n").unwrap();
+    let config = reload_config().unwrap();
+    assert_eq!(
+        config.user.name, "after",
+        "reload should read the file again"
+    );
+    assert_eq!(
+        config.user.domain, "overridden",
+        "reload should keep the overrides of the current loader"
+    );
+
+    // reset the loader for any other tests
+    ConfigLoader::default().make_current().unwrap();
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_remote_config_pinned_from_address() {
@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use super::resolve::compute_manifest;
 use super::status::{active_runtime, compute_runtime_manifest};
-use crate::{tracking, Result};
+use crate::{encoding, tracking, Result};
 
 ///  Return the changes going from 'base' to 'top'.
 ///
@@ -15,10 +15,41 @@ use crate::{tracking, Result};
 ///         (defaults to the current runtime)
 /// - **top**: The tag or id to diff the base against
 ///         (defaults to the contents of /spfs)
+/// - **algorithm**: The algorithm that the contents of /spfs are hashed
+///         with, which must be that of the repository that the base is
+///         read from
 pub async fn diff(
     base: Option<&String>,
     top: Option<&String>,
+    algorithm: encoding::DigestAlgorithm,
 ) -> Result<Vec<tracking::Diff<(), ()>>> {
+    let (base_manifest, top_manifest) = diff_manifests(base, top, algorithm).await?;
+
+    tracing::debug!("computing diffs");
+    Ok(tracking::compute_diff(&base_manifest, &top_manifest))
+}
+
+/// Return the set of changes going from 'base' to 'top', with renames.
+///
+/// The arguments are the same as for [`diff`], but unchanged entries
+/// are omitted and files that were moved without changing their
+/// content are reported as renamed.
+pub async fn diff_changeset(
+    base: Option<&String>,
+    top: Option<&String>,
+    algorithm: encoding::DigestAlgorithm,
+) -> Result<tracking::ChangeSet> {
+    let (base_manifest, top_manifest) = diff_manifests(base, top, algorithm).await?;
+
+    tracing::debug!("computing changeset");
+    Ok(tracking::compute_changeset(&base_manifest, &top_manifest))
+}
+
+async fn diff_manifests(
+    base: Option<&String>,
+    top: Option<&String>,
+    algorithm: encoding::DigestAlgorithm,
+) -> Result<(tracking::Manifest, tracking::Manifest)> {
     let base_manifest = match base {
         None => {
             tracing::debug!("computing runtime manifest as base");
@@ -34,7 +65,10 @@ pub async fn diff(
     let top_manifest = match top {
         None => {
             tracing::debug!("computing manifest for /spfs");
-            tracking::compute_manifest("/spfs").await?
+            tracking::ManifestBuilder::new()
+                .with_blob_hasher(algorithm)
+                .compute_manifest("/spfs")
+                .await?
         }
         Some(top) => {
             tracing::debug!(reference = ?top, "computing top manifest");
@@ -42,8 +76,7 @@ pub async fn diff(
         }
     };
 
-    tracing::debug!("computing diffs");
-    Ok(tracking::compute_diff(&base_manifest, &top_manifest))
+    Ok((base_manifest, top_manifest))
 }
 
 /// Build a manifest of the current set of changes
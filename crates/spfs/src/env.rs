@@ -223,46 +223,370 @@ impl<User> RuntimeConfigurator<User, NoMountNamespace> {
         self,
         rt: &runtime::Runtime,
     ) -> Result<RuntimeConfigurator<User, ThreadIsInMountNamespace>> {
-        check_can_join()?;
+        check_can_join(rt)?;
 
         let pid = match rt.status.owner {
             None => return Err(Error::RuntimeNotInitialized(rt.name().into())),
             Some(pid) => pid,
         };
 
-        let ns_path = std::path::Path::new("/proc")
-            .join(pid.to_string())
-            .join("ns/mnt");
-
-        tracing::debug!(?ns_path, "Getting process namespace");
-        let file = match std::fs::File::open(&ns_path) {
-            Ok(file) => file,
-            Err(err) => {
-                return match err.kind() {
-                    std::io::ErrorKind::NotFound => Err(Error::UnknownRuntime {
-                        runtime: rt.name().into(),
-                        source: Box::new(err),
-                    }),
-                    _ => Err(Error::RuntimeReadError(ns_path, err)),
-                }
-            }
-        };
-
-        if let Err(err) = nix::sched::setns(file, nix::sched::CloneFlags::empty()) {
-            return Err(match err {
-                nix::errno::Errno::EPERM => Error::new_errno(
-                    libc::EPERM,
-                    "spfs binary was not installed with required capabilities",
-                ),
-                _ => err.into(),
-            });
+        if rt.config.rootless {
+            // the mount namespace of a rootless runtime belongs to its own
+            // user namespace, which grants the privileges needed to enter it
+            join_user_namespace(rt, pid)?;
         }
 
+        let file = open_mount_namespace_of_runtime(rt, pid)?;
+        enter_mount_namespace_file(&file)?;
+
         std::env::set_var("SPFS_RUNTIME", rt.name());
         // Safety: we've just entered an existing mount namespace
         let ns = unsafe { ThreadIsInMountNamespace::existing() }?;
         Ok(RuntimeConfigurator::new(self.user, ns))
     }
+
+    /// Mount a read-only view of the /spfs filesystem of an existing
+    /// runtime at the target path, in the mount namespace of this process.
+    ///
+    /// This allows processes that are not running in the runtime, such
+    /// as sidecar daemons or debuggers, to read its contents. The runtime
+    /// must be owned by the current user and the target must be an empty
+    /// directory within the expose root that is also owned by them. The
+    /// new mount is recorded in the runtime's [`runtime::ExposedMounts`].
+    ///
+    /// This function will fail if called from a process with multiple threads.
+    pub fn expose_runtime(
+        &self,
+        rt: &runtime::Runtime,
+        target: &Path,
+        expose_root: &Path,
+    ) -> Result<runtime::ExposedMount> {
+        check_can_join(rt)?;
+        if rt.config.rootless {
+            return Err(
+                "Rootless runtimes cannot be exposed outside of their own user namespace".into(),
+            );
+        }
+        let pid = match rt.status.owner {
+            None => return Err(Error::RuntimeNotInitialized(rt.name().into())),
+            Some(pid) => pid,
+        };
+        check_runtime_owner(rt, pid)?;
+        // the target is resolved and opened before moving into the
+        // runtime, where the same path may refer to something else
+        let (target, target_dir) = check_expose_target(target, expose_root)?;
+
+        let current_ns_path = Path::new("/proc/self/ns/mnt");
+        let current_ns = std::fs::File::open(current_ns_path)
+            .map_err(|err| Error::RuntimeReadError(current_ns_path.into(), err))?;
+        let mount_namespace = std::fs::read_link(current_ns_path)
+            .map_err(|err| Error::RuntimeReadError(current_ns_path.into(), err))?;
+        let runtime_ns = open_mount_namespace_of_runtime(rt, pid)?;
+
+        tracing::debug!(?target, "exposing runtime filesystem...");
+        enter_mount_namespace_file(&runtime_ns)?;
+        let tree = mount_api::clone_tree(Path::new(SPFS_DIR));
+        // always return to the original namespace, even if
+        // the runtime's filesystem could not be cloned
+        let restored = nix::sched::setns(&current_ns, nix::sched::CloneFlags::CLONE_NEWNS);
+        let tree = tree?;
+        if let Err(err) = restored {
+            return Err(Error::wrap_nix(
+                err,
+                "Failed to return to the original mount namespace",
+            ));
+        }
+
+        mount_api::set_read_only(&tree)?;
+        mount_api::attach(&tree, &target_dir, &target)?;
+
+        let exposed = runtime::ExposedMount {
+            target: target.clone(),
+            mount_namespace,
+            owner: nix::unistd::getuid().as_raw(),
+            created: chrono::Utc::now(),
+        };
+        if let Err(err) = runtime::ExposedMounts::for_runtime(rt.name()).add(exposed.clone()) {
+            // a mount that is not recorded would not keep the
+            // runtime alive, and could not be removed again later
+            if let Err(err) = nix::mount::umount2(&target, nix::mount::MntFlags::MNT_DETACH) {
+                tracing::error!("Failed to remove unrecorded mount at {target:?}: {err}");
+            }
+            return Err(err);
+        }
+        Ok(exposed)
+    }
+
+    /// Remove a view of a runtime's filesystem that was created by
+    /// [`Self::expose_runtime`] in the mount namespace of this process.
+    ///
+    /// Only locations that were recorded as exposed by the current
+    /// user can be removed.
+    pub fn unexpose_runtime(
+        &self,
+        rt: &runtime::Runtime,
+        target: &Path,
+        expose_root: &Path,
+    ) -> Result<runtime::ExposedMount> {
+        // the runtime may have already exited while its filesystem
+        // remains exposed, in which case only the record is checked
+        if let Some(pid) = rt.status.owner {
+            match check_runtime_owner(rt, pid) {
+                Err(Error::UnknownRuntime { .. }) => {}
+                res => res?,
+            }
+        }
+        let target = std::fs::canonicalize(target)
+            .map_err(|err| Error::RuntimeReadError(target.to_owned(), err))?;
+        check_within_expose_root(&target, expose_root)?;
+        let current_ns_path = Path::new("/proc/self/ns/mnt");
+        let mount_namespace = std::fs::read_link(current_ns_path)
+            .map_err(|err| Error::RuntimeReadError(current_ns_path.into(), err))?;
+
+        let records = runtime::ExposedMounts::for_runtime(rt.name());
+        let known = records
+            .list()?
+            .into_iter()
+            .find(|m| m.is_at(&target, &mount_namespace));
+        match known {
+            Some(m) if m.owner == nix::unistd::getuid().as_raw() => {}
+            Some(_) => {
+                return Err(format!(
+                    "Runtime {} was exposed at {target:?} by another user",
+                    rt.name()
+                )
+                .into())
+            }
+            None => {
+                return Err(format!(
+                    "Runtime {} is not exposed at {target:?} in this mount namespace",
+                    rt.name()
+                )
+                .into())
+            }
+        }
+
+        tracing::debug!(?target, "removing exposed runtime filesystem...");
+        let result = nix::mount::umount2(&target, nix::mount::MntFlags::MNT_DETACH);
+        if let Err(err) = result {
+            return Err(Error::wrap_nix(
+                err,
+                format!("Failed to unmount {target:?}"),
+            ));
+        }
+        records.remove(&target, &mount_namespace)?.ok_or_else(|| {
+            Error::String(format!(
+                "Runtime {} was not exposed at {target:?}",
+                rt.name()
+            ))
+        })
+    }
+}
+
+/// Ensure that the process which owns a runtime belongs to the current user
+fn check_runtime_owner(rt: &runtime::Runtime, pid: u32) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let proc_path = Path::new("/proc").join(pid.to_string());
+    let meta = match std::fs::metadata(&proc_path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(Error::UnknownRuntime {
+                runtime: rt.name().into(),
+                source: Box::new(err),
+            })
+        }
+        Err(err) => return Err(Error::RuntimeReadError(proc_path, err)),
+    };
+    if meta.uid() != nix::unistd::getuid().as_raw() {
+        return Err(format!("Runtime {} is not owned by the current user", rt.name()).into());
+    }
+    Ok(())
+}
+
+/// Open the mount namespace of the process that owns a runtime
+fn open_mount_namespace_of_runtime(rt: &runtime::Runtime, pid: u32) -> Result<std::fs::File> {
+    let ns_path = std::path::Path::new("/proc")
+        .join(pid.to_string())
+        .join("ns/mnt");
+
+    tracing::debug!(?ns_path, "Getting process namespace");
+    match std::fs::File::open(&ns_path) {
+        Ok(file) => Ok(file),
+        Err(err) => match err.kind() {
+            std::io::ErrorKind::NotFound => Err(Error::UnknownRuntime {
+                runtime: rt.name().into(),
+                source: Box::new(err),
+            }),
+            _ => Err(Error::RuntimeReadError(ns_path, err)),
+        },
+    }
+}
+
+/// Move this process into the mount namespace of an open namespace file
+fn enter_mount_namespace_file(file: &std::fs::File) -> Result<()> {
+    if let Err(err) = nix::sched::setns(file, nix::sched::CloneFlags::empty()) {
+        return Err(match err {
+            nix::errno::Errno::EPERM => Error::new_errno(
+                libc::EPERM,
+                "spfs binary was not installed with required capabilities",
+            ),
+            _ => err.into(),
+        });
+    }
+    Ok(())
+}
+
+/// Ensure that a path is inside of the directory that
+/// runtimes may be exposed within.
+fn check_within_expose_root(target: &Path, expose_root: &Path) -> Result<()> {
+    let expose_root = std::fs::canonicalize(expose_root)
+        .map_err(|err| Error::RuntimeReadError(expose_root.to_owned(), err))?;
+    if target == expose_root || !target.starts_with(&expose_root) {
+        return Err(format!("Expose target must be within {expose_root:?}: {target:?}").into());
+    }
+    Ok(())
+}
+
+/// Ensure that the current user may mount a runtime over the target
+/// path, returning the canonical form of the path and the opened directory.
+fn check_expose_target(target: &Path, expose_root: &Path) -> Result<(PathBuf, std::fs::File)> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+
+    let target = std::fs::canonicalize(target)
+        .map_err(|err| Error::RuntimeReadError(target.to_owned(), err))?;
+    if target.starts_with(SPFS_DIR) {
+        return Err(format!("Cannot expose a runtime inside of {SPFS_DIR}: {target:?}").into());
+    }
+    check_within_expose_root(&target, expose_root)?;
+    // everything else is checked through the opened directory so
+    // that it cannot be swapped out before the runtime is mounted
+    let dir = std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_PATH | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC)
+        .open(&target)
+        .map_err(|err| match err.raw_os_error() {
+            Some(libc::ENOTDIR) | Some(libc::ELOOP) => {
+                Error::String(format!("Expose target must be a directory: {target:?}"))
+            }
+            _ => Error::RuntimeReadError(target.clone(), err),
+        })?;
+    let meta = dir
+        .metadata()
+        .map_err(|err| Error::RuntimeReadError(target.clone(), err))?;
+    if meta.uid() != nix::unistd::getuid().as_raw() {
+        return Err(format!("Expose target must be owned by the current user: {target:?}").into());
+    }
+    let fd_path = PathBuf::from(format!("/proc/self/fd/{}", dir.as_raw_fd()));
+    let mut entries =
+        std::fs::read_dir(fd_path).map_err(|err| Error::RuntimeReadError(target.clone(), err))?;
+    if entries.next().is_some() {
+        return Err(format!("Expose target must be an empty directory: {target:?}").into());
+    }
+    Ok((target, dir))
+}
+
+/// Wrappers for the parts of the linux mount api that are needed to
+/// move a mount between namespaces, which are not available in nix.
+mod mount_api {
+    use std::ffi::CString;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use crate::{Error, Result};
+
+    const OPEN_TREE_CLONE: libc::c_uint = 1;
+    const AT_RECURSIVE: libc::c_uint = 0x8000;
+    const MOVE_MOUNT_F_EMPTY_PATH: libc::c_uint = 0x4;
+    const MOVE_MOUNT_T_EMPTY_PATH: libc::c_uint = 0x40;
+    const MOUNT_ATTR_RDONLY: u64 = 0x1;
+
+    /// The `mount_attr` struct from linux/mount.h
+    #[repr(C)]
+    struct MountAttr {
+        attr_set: u64,
+        attr_clr: u64,
+        propagation: u64,
+        userns_fd: u64,
+    }
+
+    fn to_cstring(path: &Path) -> Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| Error::String(format!("Invalid path: {path:?}")))
+    }
+
+    /// Create a detached copy of the mount tree at the given path
+    pub(super) fn clone_tree(path: &Path) -> Result<OwnedFd> {
+        let c_path = to_cstring(path)?;
+        let flags = OPEN_TREE_CLONE | AT_RECURSIVE | libc::O_CLOEXEC as libc::c_uint;
+        // Safety: the path is a valid c string that outlives the call
+        let fd =
+            unsafe { libc::syscall(libc::SYS_open_tree, libc::AT_FDCWD, c_path.as_ptr(), flags) };
+        if fd < 0 {
+            return Err(Error::wrap_nix(
+                nix::errno::Errno::last(),
+                format!("Failed to clone mount tree: {path:?}"),
+            ));
+        }
+        // Safety: the syscall returned a new file descriptor that nothing else owns
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+    }
+
+    /// Make every mount in a detached mount tree read-only
+    pub(super) fn set_read_only(tree: &OwnedFd) -> Result<()> {
+        let attr = MountAttr {
+            attr_set: MOUNT_ATTR_RDONLY,
+            attr_clr: 0,
+            propagation: 0,
+            userns_fd: 0,
+        };
+        let empty = CString::default();
+        let flags = libc::AT_EMPTY_PATH as libc::c_uint | AT_RECURSIVE;
+        // Safety: the attr struct matches the kernel's definition and
+        // both pointers are valid for the duration of the call
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_mount_setattr,
+                tree.as_raw_fd(),
+                empty.as_ptr(),
+                flags,
+                &attr as *const MountAttr,
+                std::mem::size_of::<MountAttr>(),
+            )
+        };
+        if res < 0 {
+            return Err(Error::wrap_nix(
+                nix::errno::Errno::last(),
+                "Failed to make exposed mount read-only",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Attach a detached mount tree over an opened target directory
+    pub(super) fn attach(tree: &OwnedFd, target_dir: &impl AsRawFd, target: &Path) -> Result<()> {
+        let empty = CString::default();
+        // Safety: both paths are valid c strings that outlive the call
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_move_mount,
+                tree.as_raw_fd(),
+                empty.as_ptr(),
+                target_dir.as_raw_fd(),
+                empty.as_ptr(),
+                MOVE_MOUNT_F_EMPTY_PATH | MOVE_MOUNT_T_EMPTY_PATH,
+            )
+        };
+        if res < 0 {
+            return Err(Error::wrap_nix(
+                nix::errno::Errno::last(),
+                format!("Failed to mount exposed runtime at {target:?}"),
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Operations that do not need root but require the current thread to be in a
@@ -997,8 +1321,40 @@ impl<MountNamespace> RuntimeConfigurator<IsRootUser, MountNamespace> {
     }
 }
 
+// Moves this process into the user namespace of a rootless runtime
+fn join_user_namespace(rt: &runtime::Runtime, pid: u32) -> Result<()> {
+    let ns_path = std::path::Path::new("/proc")
+        .join(pid.to_string())
+        .join("ns/user");
+
+    tracing::debug!(?ns_path, "Getting process user namespace");
+    let file = match std::fs::File::open(&ns_path) {
+        Ok(file) => file,
+        Err(err) => {
+            return match err.kind() {
+                std::io::ErrorKind::NotFound => Err(Error::UnknownRuntime {
+                    runtime: rt.name().into(),
+                    source: Box::new(err),
+                }),
+                _ => Err(Error::RuntimeReadError(ns_path, err)),
+            }
+        }
+    };
+
+    if let Err(err) = nix::sched::setns(file, nix::sched::CloneFlags::CLONE_NEWUSER) {
+        return Err(match err {
+            nix::errno::Errno::EPERM => Error::new_errno(
+                libc::EPERM,
+                "rootless runtimes can only be joined by the user that created them",
+            ),
+            _ => err.into(),
+        });
+    }
+    Ok(())
+}
+
 // Checks if the current process will be able to join an existing runtime
-fn check_can_join() -> Result<()> {
+fn check_can_join(rt: &runtime::Runtime) -> Result<()> {
     match procfs::process::Process::myself()
         .map_err(|err| Error::String(err.to_string()))?
         .stat()
@@ -1017,7 +1373,9 @@ fn check_can_join() -> Result<()> {
         }
     }
 
-    if !have_required_join_capabilities()? {
+    // joining the user namespace of a rootless runtime
+    // grants all of the capabilities that are needed
+    if !rt.config.rootless && !have_required_join_capabilities()? {
         return Err("Missing required capabilities to join an existing runtime".into());
     }
     Ok(())
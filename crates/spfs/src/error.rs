@@ -52,6 +52,18 @@ pub enum Error {
     InvalidDateTime(#[from] chrono::ParseError),
     #[error("time specifier is not valid: {given} ({reason})")]
     InvalidTimeSpec { given: String, reason: String },
+    #[error("Invalid tag pattern '{pattern}'")]
+    InvalidTagPattern {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
+    #[error("Invalid path pattern '{pattern}'")]
+    InvalidPathPattern {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
     #[error("Invalid path {0}")]
     InvalidPath(std::path::PathBuf, #[source] io::Error),
     #[cfg(unix)]
@@ -61,6 +73,27 @@ pub enum Error {
     Utf8Error(#[from] Utf8Error),
     #[error("Error communicating with the server: {0:?}")]
     Tonic(#[from] tonic::Status),
+    #[error("Failed to send {0} request: {1:?}")]
+    HttpRequest(&'static str, #[source] hyper::Error),
+    #[error("Unexpected status code from payload server: {0}")]
+    UnexpectedHttpStatus(hyper::StatusCode),
+    #[error("Remote operation '{operation}' timed out after {timeout:?}")]
+    #[diagnostic(code("spfs::operation_timed_out"))]
+    OperationTimedOut {
+        operation: &'static str,
+        timeout: std::time::Duration,
+    },
+    #[error("Remote operation '{operation}' failed after {attempts} attempts")]
+    #[diagnostic(
+        code("spfs::retries_exhausted"),
+        help("The remote may be unreachable, see the 'retries' and 'timeout_ms' settings for this remote")
+    )]
+    RetriesExhausted {
+        operation: &'static str,
+        attempts: u32,
+        #[source]
+        source: Box<Self>,
+    },
     #[error(transparent)]
     TokioJoinError(#[from] tokio::task::JoinError),
     #[error("Failed to spawn {0}")]
@@ -81,6 +114,10 @@ pub enum Error {
     /// Denotes a reference that does not meet the syntax requirements
     #[error("Invalid Reference: {0}")]
     InvalidReference(String),
+    /// Denotes a tag that no longer points to the expected target
+    /// because it was changed by someone else
+    #[error("Tag was changed by another process: {0}")]
+    TagChanged(String),
     #[error("Repository does not support manifest rendering: {0:?}")]
     NoRenderStorage(url::Url),
     #[error("Object is not a {desired:?}: {digest}")]
@@ -90,6 +127,17 @@ pub enum Error {
     },
     #[error("Cannot write to a repository which has been pinned in time")]
     RepositoryIsPinned,
+    #[error("Cannot sync payloads from a repository that uses {src} digests into one that uses {dest} digests")]
+    #[diagnostic(
+        code("spfs::digest_algorithm_mismatch"),
+        help(
+            "Payloads can only be synced between repositories that use the same digest algorithm"
+        )
+    )]
+    DigestAlgorithmMismatch {
+        src: encoding::DigestAlgorithm,
+        dest: encoding::DigestAlgorithm,
+    },
 
     #[error("Failed to open repository: {repository}")]
     #[diagnostic(code("spfs::failed_to_open_repo"))]
@@ -109,6 +157,8 @@ pub enum Error {
 
     #[error("Nothing to commit, resulting filesystem would be empty")]
     NothingToCommit,
+    #[error("Operation was cancelled")]
+    OperationCancelled,
     #[error("No active runtime")]
     NoActiveRuntime,
     #[error("Runtime has not been initialized: {0}")]
@@ -159,6 +209,14 @@ pub enum Error {
     #[error("OverlayFS kernel module does not appear to be installed")]
     OverlayFsNotInstalled,
 
+    #[cfg(unix)]
+    #[error("Rootless runtimes are not supported on this system: {0}")]
+    #[diagnostic(
+        code("spfs::rootless_not_supported"),
+        help("Set filesystem.rootless to 'Auto' in the spfs config to fall back to the setuid spfs-enter binary")
+    )]
+    RootlessNotSupported(String),
+
     #[error("{}, and {} more errors during clean", errors.first().unwrap(), errors.len() - 1)]
     IncompleteClean { errors: Vec<Self> },
 
@@ -217,6 +275,24 @@ impl Error {
         }
     }
 
+    /// True if this error was caused by a condition that may
+    /// not persist if the same operation is tried again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Tonic(status) => matches!(
+                status.code(),
+                tonic::Code::Unavailable
+                    | tonic::Code::DeadlineExceeded
+                    | tonic::Code::Cancelled
+                    | tonic::Code::Aborted
+                    | tonic::Code::ResourceExhausted
+            ),
+            Self::HttpRequest(..) | Self::OperationTimedOut { .. } => true,
+            Self::UnexpectedHttpStatus(status) => status.is_server_error(),
+            _ => false,
+        }
+    }
+
     /// Create an [`Error::FailedToOpenRepository`] instance for
     /// a repository using its address and root cause.
     pub fn failed_to_open_repository<R: storage::Repository>(
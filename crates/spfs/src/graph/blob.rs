@@ -5,7 +5,7 @@
 use super::object::HeaderBuilder;
 use super::ObjectKind;
 use crate::encoding::Digest;
-use crate::{encoding, Result};
+use crate::{encoding, Error, Result};
 
 /// Blobs represent an arbitrary chunk of binary data, usually a file.
 pub type Blob = super::FlatObject<spfs_proto::Blob<'static>>;
@@ -15,6 +15,7 @@ impl std::fmt::Debug for Blob {
         f.debug_struct("Blob")
             .field("payload", &self.payload().to_string())
             .field("size", &self.size())
+            .field("chunks", &self.chunks().map(ToString::to_string))
             .finish()
     }
 }
@@ -49,7 +50,27 @@ impl Blob {
         self.proto().size_()
     }
 
+    /// The chunk list that holds the content of this blob, if
+    /// the payload was split into chunks when it was stored.
+    ///
+    /// Chunked blobs have no payload of their own, and are
+    /// instead read by reassembling each of the chunks in order.
+    #[inline]
+    pub fn chunks(&self) -> Option<&Digest> {
+        self.proto().chunks()
+    }
+
+    /// Return the digests of objects that this blob refers to.
+    pub fn child_objects(&self) -> Vec<Digest> {
+        self.chunks().copied().into_iter().collect()
+    }
+
     pub(super) fn legacy_encode(&self, writer: &mut impl std::io::Write) -> Result<()> {
+        if let Some(chunks) = self.chunks() {
+            return Err(Error::String(format!(
+                "Chunked blobs cannot be saved in the legacy encoding format (chunk list {chunks})"
+            )));
+        }
         encoding::write_digest(&mut *writer, self.payload())?;
         encoding::write_uint64(writer, self.size())?;
         Ok(())
@@ -61,6 +82,7 @@ pub struct BlobBuilder {
     header: super::object::HeaderBuilder,
     payload: encoding::Digest,
     size: u64,
+    chunks: Option<encoding::Digest>,
 }
 
 impl Default for BlobBuilder {
@@ -69,6 +91,7 @@ impl Default for BlobBuilder {
             header: super::object::HeaderBuilder::new(ObjectKind::Blob),
             payload: Default::default(),
             size: Default::default(),
+            chunks: None,
         }
     }
 }
@@ -92,6 +115,12 @@ impl BlobBuilder {
         self
     }
 
+    /// Identify the chunk list that holds the content of this blob
+    pub fn with_chunks(mut self, chunks: Digest) -> Self {
+        self.chunks = Some(chunks);
+        self
+    }
+
     pub fn build(&self) -> Blob {
         super::BUILDER.with_borrow_mut(|builder| {
             let blob = spfs_proto::Blob::create(
@@ -99,6 +128,7 @@ impl BlobBuilder {
                 &spfs_proto::BlobArgs {
                     payload: Some(&self.payload),
                     size_: self.size,
+                    chunks: self.chunks.as_ref(),
                 },
             );
             let any = spfs_proto::AnyObject::create(
@@ -0,0 +1,170 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use super::object::HeaderBuilder;
+use super::ObjectKind;
+use crate::encoding::Digest;
+use crate::{encoding, Result};
+
+#[cfg(test)]
+#[path = "./chunk_list_test.rs"]
+mod chunk_list_test;
+
+/// Chunk lists describe how to reassemble a large payload
+/// from a sequence of smaller blobs.
+///
+/// Large payloads are split at content-defined boundaries, so
+/// that the chunks of two similar files are mostly the same
+/// and only need to be stored and transferred once.
+pub type ChunkList = super::object::FlatObject<spfs_proto::ChunkList<'static>>;
+
+impl std::fmt::Debug for ChunkList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkList")
+            .field("chunks", &self.len())
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+impl ChunkList {
+    #[inline]
+    pub fn builder() -> ChunkListBuilder {
+        ChunkListBuilder::default()
+    }
+
+    /// Iterate the chunks of the payload, in order
+    #[inline]
+    pub fn iter_chunks(&self) -> impl Iterator<Item = &spfs_proto::Chunk> {
+        self.proto().chunks().iter()
+    }
+
+    /// The number of chunks in this list
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.proto().chunks().len()
+    }
+
+    /// True if this list has no chunks, as for an empty payload
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total size of the reassembled payload
+    pub fn size(&self) -> u64 {
+        self.iter_chunks().map(|c| c.size_()).sum()
+    }
+
+    /// Return the digests of objects that this chunk list refers to.
+    pub fn child_objects(&self) -> Vec<Digest> {
+        self.iter_chunks().map(|c| *c.payload()).collect()
+    }
+
+    pub(super) fn digest_encode(&self, writer: &mut impl std::io::Write) -> Result<()> {
+        self.legacy_encode(writer)
+    }
+
+    pub(super) fn legacy_encode(&self, writer: &mut impl std::io::Write) -> Result<()> {
+        encoding::write_uint64(&mut *writer, self.len() as u64)?;
+        for chunk in self.iter_chunks() {
+            encoding::write_digest(&mut *writer, chunk.payload())?;
+            encoding::write_uint64(&mut *writer, chunk.size_())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ChunkListBuilder {
+    header: HeaderBuilder,
+    chunks: Vec<spfs_proto::Chunk>,
+}
+
+impl Default for ChunkListBuilder {
+    fn default() -> Self {
+        Self {
+            header: HeaderBuilder::new(ObjectKind::ChunkList),
+            chunks: Vec::new(),
+        }
+    }
+}
+
+impl ChunkListBuilder {
+    pub fn with_header<F>(mut self, mut header: F) -> Self
+    where
+        F: FnMut(HeaderBuilder) -> HeaderBuilder,
+    {
+        self.header = header(self.header).with_object_kind(ObjectKind::ChunkList);
+        self
+    }
+
+    /// Add a chunk to the end of this list
+    pub fn with_chunk(mut self, payload: Digest, size: u64) -> Self {
+        self.chunks.push(spfs_proto::Chunk::new(&payload, size));
+        self
+    }
+
+    /// Add a number of chunks to the end of this list
+    pub fn with_chunks<I>(mut self, chunks: I) -> Self
+    where
+        I: IntoIterator<Item = (Digest, u64)>,
+    {
+        self.chunks.extend(
+            chunks
+                .into_iter()
+                .map(|(payload, size)| spfs_proto::Chunk::new(&payload, size)),
+        );
+        self
+    }
+
+    pub fn build(&self) -> ChunkList {
+        super::BUILDER.with_borrow_mut(|builder| {
+            let chunks = builder.create_vector(&self.chunks);
+            let chunk_list = spfs_proto::ChunkList::create(
+                builder,
+                &spfs_proto::ChunkListArgs {
+                    chunks: Some(chunks),
+                },
+            );
+            let any = spfs_proto::AnyObject::create(
+                builder,
+                &spfs_proto::AnyObjectArgs {
+                    object_type: spfs_proto::Object::ChunkList,
+                    object: Some(chunk_list.as_union_value()),
+                },
+            );
+            builder.finish_minimal(any);
+            let offset = unsafe {
+                // Safety: we have just created this buffer
+                // so already know the root type with certainty
+                flatbuffers::root_unchecked::<spfs_proto::AnyObject>(builder.finished_data())
+                    .object_as_chunk_list()
+                    .unwrap()
+                    ._tab
+                    .loc()
+            };
+            let obj = unsafe {
+                // Safety: the provided buf and offset mut contain
+                // a valid object and point to the contained chunk list
+                // which is what we've done
+                ChunkList::new_with_header(self.header.build(), builder.finished_data(), offset)
+            };
+            builder.reset(); // to be used again
+            obj
+        })
+    }
+
+    /// Read a data encoded using the legacy format, and
+    /// use the data to fill and complete this builder
+    pub fn legacy_decode(mut self, reader: &mut impl std::io::Read) -> Result<ChunkList> {
+        let num_chunks = encoding::read_uint64(&mut *reader)?;
+        for _ in 0..num_chunks {
+            let payload = encoding::read_digest(&mut *reader)?;
+            let size = encoding::read_uint64(&mut *reader)?;
+            self = self.with_chunk(payload, size);
+        }
+        Ok(self.build())
+    }
+}
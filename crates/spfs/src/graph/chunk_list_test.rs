@@ -0,0 +1,51 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::ChunkList;
+use crate::encoding;
+use crate::encoding::prelude::*;
+use crate::graph::object::{EncodingFormat, HeaderBuilder};
+use crate::graph::Blob;
+
+#[rstest]
+#[case(EncodingFormat::Legacy)]
+#[case(EncodingFormat::FlatBuffers)]
+fn test_chunk_list_encoding(#[case] format: EncodingFormat) {
+    let expected = ChunkList::builder()
+        .with_header(|h: HeaderBuilder| h.with_encoding_format(format))
+        .with_chunk(encoding::EMPTY_DIGEST.into(), 10)
+        .with_chunk(encoding::NULL_DIGEST.into(), 20)
+        .build();
+    assert_eq!(expected.size(), 30);
+
+    let mut stream = Vec::new();
+    expected.encode(&mut stream).unwrap();
+    let actual = crate::graph::Object::decode(&mut stream.as_slice())
+        .unwrap()
+        .into_chunk_list()
+        .unwrap();
+    assert_eq!(actual.digest().unwrap(), expected.digest().unwrap());
+    assert_eq!(actual.child_objects(), expected.child_objects());
+}
+
+#[rstest]
+fn test_chunked_blob_references_chunk_list() {
+    let chunks = ChunkList::builder()
+        .with_chunk(encoding::EMPTY_DIGEST.into(), 10)
+        .build();
+    let chunks_digest = chunks.digest().unwrap();
+    let blob = Blob::builder()
+        .with_payload(encoding::NULL_DIGEST.into())
+        .with_size(10)
+        .with_chunks(chunks_digest)
+        .build();
+    assert_eq!(
+        *blob.digest(),
+        encoding::Digest::from(encoding::NULL_DIGEST),
+        "chunked blobs should still share a digest with their payload"
+    );
+    assert_eq!(blob.child_objects(), vec![chunks_digest]);
+}
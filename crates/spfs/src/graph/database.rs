@@ -146,6 +146,17 @@ pub trait DatabaseView: Sync + Send {
     /// - [`Error::UnknownObject`]: if the object is not in this database
     async fn read_object(&self, digest: encoding::Digest) -> Result<Object>;
 
+    /// Read many objects from the database at once, in the order given.
+    ///
+    /// The default implementation reads each object concurrently, but
+    /// implementations may be able to read the batch more efficiently.
+    ///
+    /// # Errors:
+    /// - [`Error::UnknownObject`]: if any of the objects is not in this database
+    async fn read_objects(&self, digests: &[encoding::Digest]) -> Result<Vec<Object>> {
+        futures::future::try_join_all(digests.iter().map(|digest| self.read_object(*digest))).await
+    }
+
     /// Find the object digests in this database matching a search criteria.
     fn find_digests(
         &self,
@@ -155,6 +166,15 @@ pub trait DatabaseView: Sync + Send {
     /// Return true if this database contains the identified object
     async fn has_object(&self, digest: encoding::Digest) -> bool;
 
+    /// Check for many objects in the database at once, returning
+    /// whether each one exists in the order given.
+    ///
+    /// The default implementation checks each object concurrently, but
+    /// implementations may be able to check the batch more efficiently.
+    async fn has_objects(&self, digests: &[encoding::Digest]) -> Vec<bool> {
+        futures::future::join_all(digests.iter().map(|digest| self.has_object(*digest))).await
+    }
+
     /// Iterate all the object in this database.
     fn iter_objects(&self) -> DatabaseIterator<'_>;
 
@@ -228,10 +248,18 @@ impl<T: DatabaseView> DatabaseView for &T {
         DatabaseView::has_object(&**self, digest).await
     }
 
+    async fn has_objects(&self, digests: &[encoding::Digest]) -> Vec<bool> {
+        DatabaseView::has_objects(&**self, digests).await
+    }
+
     async fn read_object(&self, digest: encoding::Digest) -> Result<Object> {
         DatabaseView::read_object(&**self, digest).await
     }
 
+    async fn read_objects(&self, digests: &[encoding::Digest]) -> Result<Vec<Object>> {
+        DatabaseView::read_objects(&**self, digests).await
+    }
+
     fn find_digests(
         &self,
         search_criteria: DigestSearchCriteria,
@@ -253,6 +281,17 @@ pub trait Database: DatabaseView {
     /// Write an object to the database, for later retrieval.
     async fn write_object<T: ObjectProto>(&self, obj: &FlatObject<T>) -> Result<()>;
 
+    /// Write many objects to the database at once.
+    ///
+    /// The default implementation writes each object in turn, but
+    /// implementations may be able to write the batch more efficiently.
+    async fn write_objects(&self, objects: &[Object]) -> Result<()> {
+        for obj in objects {
+            self.write_object(obj).await?;
+        }
+        Ok(())
+    }
+
     /// Remove an object from the database.
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()>;
 
@@ -273,6 +312,10 @@ impl<T: Database> Database for &T {
         Database::write_object(&**self, obj).await
     }
 
+    async fn write_objects(&self, objects: &[Object]) -> Result<()> {
+        Database::write_objects(&**self, objects).await
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         Database::remove_object(&**self, digest).await
     }
@@ -7,7 +7,7 @@ use std::io::BufRead;
 use encoding::prelude::*;
 use spfs_proto::EntryArgs;
 
-use crate::{encoding, tracking, Result};
+use crate::{encoding, tracking, Error, Result};
 
 #[cfg(test)]
 #[path = "./entry_test.rs"]
@@ -26,6 +26,7 @@ impl<'buf> std::fmt::Debug for Entry<'buf> {
             .field("mode", &self.mode())
             .field("size", &self.size())
             .field("object", self.object())
+            .field("xattrs", &self.xattrs().map(|(n, _)| n).collect::<Vec<_>>())
             .finish()
     }
 }
@@ -47,6 +48,38 @@ impl<'buf> Entry<'buf> {
         size: u64,
         object: &encoding::Digest,
     ) -> flatbuffers::WIPOffset<spfs_proto::Entry<'fbb>> {
+        Self::build_with_xattrs(builder, name, kind, mode, size, object, std::iter::empty())
+    }
+
+    /// Construct a valid entry from its component parts, including
+    /// any extended attributes which must be given sorted by name
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_with_xattrs<'fbb, 'a>(
+        builder: &mut flatbuffers::FlatBufferBuilder<'fbb>,
+        name: &str,
+        kind: tracking::EntryKind,
+        mode: u32,
+        size: u64,
+        object: &encoding::Digest,
+        xattrs: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) -> flatbuffers::WIPOffset<spfs_proto::Entry<'fbb>> {
+        let xattrs = xattrs
+            .into_iter()
+            .map(|(name, value)| {
+                let name = builder.create_string(name);
+                let value = builder.create_vector(value);
+                spfs_proto::ExtendedAttribute::create(
+                    builder,
+                    &spfs_proto::ExtendedAttributeArgs {
+                        name: Some(name),
+                        value: Some(value),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        // entries without attributes are left exactly as they
+        // were before attributes could be stored
+        let xattrs = (!xattrs.is_empty()).then(|| builder.create_vector(&xattrs));
         let name = builder.create_string(name);
         spfs_proto::Entry::create(
             builder,
@@ -56,6 +89,7 @@ impl<'buf> Entry<'buf> {
                 mode,
                 size_: size,
                 object: Some(object),
+                xattrs,
             },
         )
     }
@@ -65,13 +99,17 @@ impl<'buf> Entry<'buf> {
         name: &str,
         entry: &tracking::Entry<T>,
     ) -> flatbuffers::WIPOffset<spfs_proto::Entry<'fbb>> {
-        Self::build(
+        Self::build_with_xattrs(
             builder,
             name,
             entry.kind,
             entry.mode,
             entry.size_for_legacy_encode(),
             &entry.object,
+            entry
+                .xattrs
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_slice())),
         )
     }
 
@@ -107,6 +145,20 @@ impl<'buf> Entry<'buf> {
         self.0.object()
     }
 
+    /// The extended attributes stored for this entry, sorted by name
+    pub fn xattrs(&self) -> impl Iterator<Item = (&'buf str, &'buf [u8])> {
+        self.0
+            .xattrs()
+            .into_iter()
+            .flatten()
+            .map(|xattr| (xattr.name(), xattr.value().bytes()))
+    }
+
+    #[inline]
+    pub fn has_xattrs(&self) -> bool {
+        self.0.xattrs().is_some_and(|xattrs| !xattrs.is_empty())
+    }
+
     #[inline]
     pub fn is_symlink(&self) -> bool {
         unix_mode::is_symlink(self.mode())
@@ -174,7 +226,7 @@ impl<'buf> encoding::Digestible for Entry<'buf> {
 
     fn digest(&self) -> std::result::Result<spfs_proto::Digest, Self::Error> {
         let mut hasher = encoding::Hasher::new_sync();
-        self.legacy_encode(&mut hasher)?;
+        self.digest_encode(&mut hasher)?;
         Ok(hasher.digest())
     }
 }
@@ -185,11 +237,29 @@ impl<'buf> Entry<'buf> {
         self.kind().encode(&mut *writer)?;
         encoding::write_uint64(&mut *writer, self.mode() as u64)?;
         encoding::write_uint64(&mut *writer, self.size_for_legacy_encode())?;
-        encoding::write_string(writer, self.name())?;
+        encoding::write_string(&mut *writer, self.name())?;
+        // attributes are only included when present so that the
+        // digest of every other entry remains unchanged
+        if self.has_xattrs() {
+            encoding::write_uint64(&mut *writer, self.xattrs().count() as u64)?;
+            for (name, value) in self.xattrs() {
+                encoding::write_string(&mut *writer, name)?;
+                encoding::write_uint64(&mut *writer, value.len() as u64)?;
+                writer
+                    .write_all(value)
+                    .map_err(|err| Error::Encoding(encoding::Error::FailedWrite(err)))?;
+            }
+        }
         Ok(())
     }
 
     pub(super) fn legacy_encode(&self, writer: &mut impl std::io::Write) -> Result<()> {
+        if self.has_xattrs() {
+            return Err(Error::String(format!(
+                "Entry '{}' has extended attributes, which cannot be stored in the legacy encoding format",
+                self.name()
+            )));
+        }
         encoding::write_digest(&mut *writer, self.object())?;
         self.kind().encode(&mut *writer)?;
         encoding::write_uint64(&mut *writer, self.mode() as u64)?;
@@ -246,6 +316,7 @@ impl EntryBuf {
                         }
                     },
                     name: Some(name),
+                    xattrs: None,
                 },
             );
             builder.finish_minimal(e);
@@ -277,6 +348,7 @@ impl EntryBuf {
                         }
                     },
                     name: Some(name),
+                    xattrs: None,
                 },
             );
             builder.finish_minimal(e);
@@ -13,6 +13,7 @@ pub enum ObjectKind {
     Platform = 3,
     Tree = 4,
     Mask = 5,
+    ChunkList = 6,
 }
 
 impl ObjectKind {
@@ -27,6 +28,7 @@ impl ObjectKind {
             x if x == spfs_proto::Object::Manifest => Some(Self::Manifest),
             x if x == spfs_proto::Object::Layer => Some(Self::Layer),
             x if x == spfs_proto::Object::Platform => Some(Self::Platform),
+            x if x == spfs_proto::Object::ChunkList => Some(Self::ChunkList),
             _ => None,
         }
     }
@@ -99,3 +101,17 @@ impl<'buf> HasKind for spfs_proto::Blob<'buf> {
         <Self as Kind>::kind()
     }
 }
+
+impl<'buf> Kind for spfs_proto::ChunkList<'buf> {
+    #[inline]
+    fn kind() -> ObjectKind {
+        ObjectKind::ChunkList
+    }
+}
+
+impl<'buf> HasKind for spfs_proto::ChunkList<'buf> {
+    #[inline]
+    fn kind(&self) -> ObjectKind {
+        <Self as Kind>::kind()
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeMap;
+
+use super::object::EncodingFormat;
+use crate::{encoding, Result};
+
+/// Descriptive key/value pairs that can be attached to a layer or platform.
+///
+/// Labels are not used by spfs itself, but help to identify where an
+/// object came from. Unlike annotations, they are not made available
+/// inside of a runtime. They are included in the digest of the object
+/// and are always stored sorted by key.
+pub type Labels = BTreeMap<String, String>;
+
+type LabelVector<'buf> =
+    flatbuffers::Vector<'buf, flatbuffers::ForwardsUOffset<spfs_proto::Label<'buf>>>;
+
+/// Add the given labels to the builder, returning nothing when
+/// there are none so that unlabeled objects are left exactly
+/// as they were before labels could be stored
+pub(super) fn build_labels<'fbb>(
+    builder: &mut flatbuffers::FlatBufferBuilder<'fbb>,
+    labels: &Labels,
+) -> Option<flatbuffers::WIPOffset<LabelVector<'fbb>>> {
+    if labels.is_empty() {
+        return None;
+    }
+    let labels = labels
+        .iter()
+        .map(|(key, value)| {
+            let key = builder.create_string(key);
+            let value = builder.create_string(value);
+            spfs_proto::Label::create(
+                builder,
+                &spfs_proto::LabelArgs {
+                    key: Some(key),
+                    value: Some(value),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    Some(builder.create_vector(&labels))
+}
+
+/// Drop the labels of an object that will be saved in the legacy
+/// encoding format, which has no way to store them.
+///
+/// This happens before the object is built so that its digest
+/// still matches once it has been saved and read back again.
+pub(super) fn labels_for_encoding(
+    labels: Labels,
+    encoding_format: EncodingFormat,
+    kind: &str,
+) -> Labels {
+    if labels.is_empty() || encoding_format != EncodingFormat::Legacy {
+        return labels;
+    }
+    tracing::warn!(
+        ?labels,
+        "Labels are not supported with the legacy encoding format, creating {kind} without them"
+    );
+    Labels::new()
+}
+
+/// Iterate the labels stored in an object, which are sorted by key
+pub(super) fn iter_labels<'buf>(
+    labels: Option<LabelVector<'buf>>,
+) -> impl Iterator<Item = (&'buf str, &'buf str)> {
+    labels
+        .into_iter()
+        .flatten()
+        .map(|label| (label.key(), label.value()))
+}
+
+/// Write the given labels as part of the digest of an object.
+///
+/// Nothing is written when there are no labels so that the
+/// digest of every unlabeled object remains unchanged.
+pub(super) fn digest_encode_labels(
+    writer: &mut impl std::io::Write,
+    labels: &[(&str, &str)],
+) -> Result<()> {
+    if labels.is_empty() {
+        return Ok(());
+    }
+    encoding::write_uint64(&mut *writer, labels.len() as u64)?;
+    for (key, value) in labels {
+        encoding::write_string(&mut *writer, key)?;
+        encoding::write_string(&mut *writer, value)?;
+    }
+    Ok(())
+}
@@ -4,8 +4,9 @@
 
 use spfs_proto::LayerArgs;
 
+use super::label::{build_labels, digest_encode_labels, iter_labels, labels_for_encoding};
 use super::object::HeaderBuilder;
-use super::{Annotation, AnnotationValue, ObjectKind};
+use super::{Annotation, AnnotationValue, Labels, ObjectKind};
 use crate::{encoding, Error, Result};
 
 #[cfg(test)]
@@ -29,6 +30,7 @@ impl std::fmt::Debug for Layer {
                     .map_or(String::from("None"), |d| d.to_string()),
             )
             .field("annotations", &self.annotations())
+            .field("labels", &self.labels().collect::<Vec<_>>())
             .finish()
     }
 }
@@ -105,6 +107,17 @@ impl Layer {
             .collect::<Vec<spfs_proto::Annotation>>()
     }
 
+    /// The descriptive labels of this layer, sorted by key
+    #[inline]
+    pub fn labels(&self) -> impl Iterator<Item = (&str, &str)> {
+        iter_labels(self.proto().labels())
+    }
+
+    /// The value of the named label, if this layer has it
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.labels().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
     /// Return the child object of this one in the object DG.
     #[inline]
     pub fn child_objects(&self) -> Vec<encoding::Digest> {
@@ -142,8 +155,10 @@ impl Layer {
                     .to_string(),
             ))
         };
+        result?;
 
-        result
+        let labels = self.labels().collect::<Vec<_>>();
+        digest_encode_labels(writer, &labels)
     }
 
     pub(super) fn legacy_encode(&self, writer: &mut impl std::io::Write) -> Result<()> {
@@ -153,6 +168,11 @@ impl Layer {
                 "Invalid Layer object for legacy encoding, it has annotation data. Annotations are not supported with legacy encoding".to_string(),
             ));
         }
+        if self.labels().next().is_some() {
+            return Err(Error::String(
+                "Invalid Layer object for legacy encoding, it has labels. Labels are not supported with legacy encoding".to_string(),
+            ));
+        }
         let result = if let Some(manifest_digest) = self.manifest() {
             encoding::write_digest(writer, manifest_digest).map_err(Error::Encoding)
         } else {
@@ -187,6 +207,7 @@ pub struct LayerBuilder<'a> {
     header: super::object::HeaderBuilder,
     manifest: Option<encoding::Digest>,
     annotations: Vec<KeyAnnotationValuePair<'a>>,
+    labels: Labels,
 }
 
 impl<'a> Default for LayerBuilder<'a> {
@@ -195,6 +216,7 @@ impl<'a> Default for LayerBuilder<'a> {
             header: super::object::HeaderBuilder::new(ObjectKind::Layer),
             manifest: None,
             annotations: Vec::new(),
+            labels: Labels::new(),
         }
     }
 }
@@ -223,6 +245,23 @@ impl<'a> LayerBuilder<'a> {
         self
     }
 
+    /// Add a descriptive label, replacing any existing value for the key
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add descriptive labels, replacing any existing values for the same keys
+    pub fn with_labels<K, V>(mut self, labels: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.labels
+            .extend(labels.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
     pub fn build(self) -> Layer {
         super::BUILDER.with_borrow_mut(|builder| {
             let ffb_annotations: Vec<_> = self
@@ -243,12 +282,15 @@ impl<'a> LayerBuilder<'a> {
                 })
                 .collect();
             let annotations = Some(builder.create_vector(&ffb_annotations));
+            let labels = labels_for_encoding(self.labels, self.header.encoding_format(), "layer");
+            let labels = build_labels(builder, &labels);
 
             let layer = spfs_proto::Layer::create(
                 builder,
                 &LayerArgs {
                     manifest: self.manifest.as_ref(),
                     annotations,
+                    labels,
                 },
             );
             let any = spfs_proto::AnyObject::create(
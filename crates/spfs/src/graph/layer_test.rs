@@ -113,3 +113,73 @@ fn test_layer_encoding_manifest_and_annotations(
         }
     }
 }
+
+#[rstest]
+fn test_layer_encoding_labels() {
+    let unlabeled = Layer::new(encoding::EMPTY_DIGEST.into());
+    let expected = Layer::builder()
+        .with_manifest(encoding::EMPTY_DIGEST.into())
+        .with_label("spk/pkg", "my-pkg/1.0.0/3I42H3S6")
+        .with_label("spk/component", "run")
+        .build();
+    assert_ne!(
+        expected.digest().unwrap(),
+        unlabeled.digest().unwrap(),
+        "labels should be included in the digest"
+    );
+
+    let mut stream = Vec::new();
+    expected.encode(&mut stream).unwrap();
+    let actual = Object::decode(&mut stream.as_slice())
+        .unwrap()
+        .into_layer()
+        .unwrap();
+    assert_eq!(actual.digest().unwrap(), expected.digest().unwrap());
+    assert_eq!(
+        actual.labels().collect::<Vec<_>>(),
+        vec![
+            ("spk/component", "run"),
+            ("spk/pkg", "my-pkg/1.0.0/3I42H3S6")
+        ],
+        "labels should be sorted by key"
+    );
+    assert_eq!(actual.label("spk/component"), Some("run"));
+    assert_eq!(actual.label("missing"), None);
+}
+
+#[rstest]
+fn test_layer_without_labels_is_unchanged() {
+    let expected = Layer::new(encoding::EMPTY_DIGEST.into());
+    let actual = Layer::builder()
+        .with_manifest(encoding::EMPTY_DIGEST.into())
+        .with_labels(Vec::<(String, String)>::new())
+        .build();
+    assert_eq!(actual.digest().unwrap(), expected.digest().unwrap());
+    assert!(actual.proto().labels().is_none());
+}
+
+#[rstest]
+#[serial_test::serial(config)]
+fn test_layer_labels_dropped_for_legacy_encoding() {
+    let mut config = Config::default();
+    config.storage.encoding_format = EncodingFormat::Legacy;
+    config.make_current().unwrap();
+
+    let unlabeled = Layer::new(encoding::EMPTY_DIGEST.into());
+    let expected = Layer::builder()
+        .with_manifest(encoding::EMPTY_DIGEST.into())
+        .with_label("spk/component", "run")
+        .build();
+    assert_eq!(expected.labels().count(), 0, "labels should be dropped");
+    assert_eq!(expected.digest().unwrap(), unlabeled.digest().unwrap());
+
+    let mut stream = Vec::new();
+    expected
+        .encode(&mut stream)
+        .expect("a layer built for the legacy format should encode");
+    let actual = Object::decode(&mut stream.as_slice())
+        .unwrap()
+        .into_layer()
+        .unwrap();
+    assert_eq!(actual.digest().unwrap(), expected.digest().unwrap());
+}
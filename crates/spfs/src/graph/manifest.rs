@@ -128,6 +128,10 @@ impl Manifest {
                     object: *entry.object(),
                     user_data: (),
                     legacy_size: entry.size_for_legacy_encode(),
+                    xattrs: entry
+                        .xattrs()
+                        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                        .collect(),
                 };
                 if entry.kind().is_tree() {
                     new_entry.object = encoding::NULL_DIGEST.into();
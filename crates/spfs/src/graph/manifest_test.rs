@@ -36,3 +36,38 @@ fn test_manifest_from_tracking_manifest() {
 
     assert!(tm == gm2tm);
 }
+
+#[rstest]
+fn test_manifest_xattrs_round_trip() {
+    let mut without = tracking::Manifest::<()>::default();
+    without.mkfile("plain").unwrap();
+    without.mkfile("ping").unwrap();
+
+    let mut tm = without.clone();
+    tm.mkfile("ping").unwrap().xattrs.insert(
+        tracking::xattrs::CAPABILITY_XATTR.to_string(),
+        vec![1, 2, 3],
+    );
+
+    let gm: Manifest = tm.to_graph_manifest();
+    let entry = gm
+        .iter_entries()
+        .find(|e| e.name() == "ping")
+        .expect("entry should be in manifest");
+    assert_eq!(
+        entry.xattrs().collect::<Vec<_>>(),
+        vec![(tracking::xattrs::CAPABILITY_XATTR, &[1_u8, 2, 3][..])]
+    );
+    assert!(
+        gm.iter_entries()
+            .find(|e| e.name() == "plain")
+            .is_some_and(|e| !e.has_xattrs()),
+        "entries without attributes should not store any"
+    );
+    assert_eq!(tm, gm.to_tracking_manifest());
+    assert_ne!(
+        gm.digest().unwrap(),
+        without.to_graph_manifest().digest().unwrap(),
+        "attributes should be included in the digest"
+    );
+}
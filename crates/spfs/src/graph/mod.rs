@@ -6,10 +6,12 @@
 
 mod annotation;
 mod blob;
+mod chunk_list;
 mod database;
 mod entry;
 pub mod error;
 mod kind;
+mod label;
 mod layer;
 mod manifest;
 pub mod object;
@@ -25,6 +27,7 @@ pub use annotation::{
     DEFAULT_SPFS_ANNOTATION_LAYER_MAX_STRING_VALUE_SIZE,
 };
 pub use blob::Blob;
+pub use chunk_list::ChunkList;
 pub use database::{
     Database,
     DatabaseIterator,
@@ -34,6 +37,7 @@ pub use database::{
 };
 pub use entry::Entry;
 pub use kind::{HasKind, Kind, ObjectKind};
+pub use label::Labels;
 pub use layer::{KeyAnnotationValuePair, Layer};
 pub use manifest::{Manifest, ManifestTreeCache};
 pub use object::{FlatObject, Object, ObjectProto};
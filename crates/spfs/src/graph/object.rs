@@ -10,7 +10,18 @@ use encoding::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use super::error::{ObjectError, ObjectResult};
-use super::{Annotation, Blob, DatabaseView, HasKind, Kind, Layer, Manifest, ObjectKind, Platform};
+use super::{
+    Annotation,
+    Blob,
+    ChunkList,
+    DatabaseView,
+    HasKind,
+    Kind,
+    Layer,
+    Manifest,
+    ObjectKind,
+    Platform,
+};
 use crate::encoding;
 use crate::storage::RepositoryHandle;
 
@@ -91,6 +102,10 @@ impl Object {
                         .with_header(|h| h.copy_from(header))
                         .legacy_decode(&mut reader)?
                         .into_object(),
+                    ObjectKind::ChunkList => ChunkList::builder()
+                        .with_header(|h| h.copy_from(header))
+                        .legacy_decode(&mut reader)?
+                        .into_object(),
                     ObjectKind::Tree | ObjectKind::Mask => {
                         // although these kinds used to be supported, they were never actually encoded
                         // separately into files and so should not appear in this context
@@ -161,7 +176,8 @@ impl Object {
             Enum::Platform(platform) => platform.child_objects(),
             Enum::Layer(layer) => layer.child_objects(),
             Enum::Manifest(manifest) => manifest.child_objects(),
-            Enum::Blob(_blob) => Vec::new(),
+            Enum::Blob(blob) => blob.child_objects(),
+            Enum::ChunkList(chunk_list) => chunk_list.child_objects(),
         }
     }
 
@@ -202,6 +218,7 @@ impl Object {
                         }
                     }
                     Enum::Blob(object) => total_size += object.size(),
+                    Enum::ChunkList(object) => total_size += object.size(),
                 }
             }
             items_to_process = std::mem::take(&mut next_iter_objects);
@@ -252,6 +269,7 @@ impl<T: ObjectProto> encoding::Digestible for FlatObject<T> {
             Enum::Platform(obj) => obj.digest_encode(&mut hasher)?,
             Enum::Layer(obj) => obj.digest_encode(&mut hasher)?,
             Enum::Manifest(obj) => obj.digest_encode(&mut hasher)?,
+            Enum::ChunkList(obj) => obj.digest_encode(&mut hasher)?,
             Enum::Blob(_obj) => unreachable!("handled above"),
         }
         Ok(hasher.digest())
@@ -276,6 +294,7 @@ impl<T: ObjectProto> encoding::Encodable for FlatObject<T> {
                     Enum::Manifest(obj) => obj.legacy_encode(&mut writer),
                     Enum::Layer(obj) => obj.legacy_encode(&mut writer),
                     Enum::Platform(obj) => obj.legacy_encode(&mut writer),
+                    Enum::ChunkList(obj) => obj.legacy_encode(&mut writer),
                 }
             }
             EncodingFormat::FlatBuffers => {
@@ -306,6 +325,7 @@ pub enum Enum {
     Layer(super::Layer),
     Manifest(super::Manifest),
     Blob(super::Blob),
+    ChunkList(super::ChunkList),
 }
 
 impl HasKind for Enum {
@@ -315,6 +335,7 @@ impl HasKind for Enum {
             Enum::Layer(_) => super::ObjectKind::Layer,
             Enum::Manifest(_) => super::ObjectKind::Manifest,
             Enum::Blob(_) => super::ObjectKind::Blob,
+            Enum::ChunkList(_) => super::ObjectKind::ChunkList,
         }
     }
 }
@@ -431,6 +452,11 @@ impl<T: ObjectProto> FlatObject<T> {
                 offset,
                 _t: PhantomData,
             }),
+            spfs_proto::Object::ChunkList => Enum::ChunkList(ChunkList {
+                buf: self.buf,
+                offset,
+                _t: PhantomData,
+            }),
             spfs_proto::Object::NONE | spfs_proto::Object(spfs_proto::Object::ENUM_MAX..) => {
                 unreachable!("already recognized kind")
             }
@@ -469,6 +495,14 @@ impl<T: ObjectProto> FlatObject<T> {
         }
     }
 
+    pub fn into_chunk_list(self) -> Option<super::ChunkList> {
+        if let Enum::ChunkList(l) = self.into_enum() {
+            Some(l)
+        } else {
+            None
+        }
+    }
+
     /// Clone (cheaply) this object and make a generic one
     #[inline]
     pub fn to_object(&self) -> Object {
@@ -764,6 +798,11 @@ impl HeaderBuilder {
         self
     }
 
+    /// The encoding format that the built header will specify
+    pub fn encoding_format(&self) -> EncodingFormat {
+        self.encoding_format
+    }
+
     /// Copy valid and known components from another header
     pub fn copy_from(mut self, other: &Header) -> Self {
         if let Some(digest_strategy) = other.digest_strategy() {
@@ -857,6 +896,7 @@ mod private {
     impl<'buf> Sealed for spfs_proto::Layer<'buf> {}
     impl<'buf> Sealed for spfs_proto::Manifest<'buf> {}
     impl<'buf> Sealed for spfs_proto::Blob<'buf> {}
+    impl<'buf> Sealed for spfs_proto::ChunkList<'buf> {}
 
     impl<T> super::ObjectProto for T where T: Sealed {}
 }
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use super::label::{build_labels, digest_encode_labels, iter_labels, labels_for_encoding};
 use super::object::HeaderBuilder;
-use super::{ObjectKind, Stack};
+use super::{Labels, ObjectKind, Stack};
 use crate::{encoding, Error, Result};
 
 #[cfg(test)]
@@ -21,6 +22,7 @@ impl std::fmt::Debug for Platform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Platform")
             .field("stack", &self.to_stack())
+            .field("labels", &self.labels().collect::<Vec<_>>())
             .finish()
     }
 }
@@ -50,6 +52,17 @@ impl Platform {
         self.proto().layers().iter()
     }
 
+    /// The descriptive labels of this platform, sorted by key
+    #[inline]
+    pub fn labels(&self) -> impl Iterator<Item = (&str, &str)> {
+        iter_labels(self.proto().labels())
+    }
+
+    /// The value of the named label, if this platform has it
+    pub fn label(&self, key: &str) -> Option<&str> {
+        self.labels().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
     /// Return the digests of objects that this manifest refers to.
     pub fn child_objects(&self) -> Vec<encoding::Digest> {
         self.iter_bottom_up().copied().collect()
@@ -65,10 +78,16 @@ impl Platform {
         for digest in digests.into_iter().rev() {
             encoding::write_digest(&mut *writer, digest)?;
         }
-        Ok(())
+        let labels = self.labels().collect::<Vec<_>>();
+        digest_encode_labels(writer, &labels)
     }
 
     pub(super) fn legacy_encode(&self, writer: &mut impl std::io::Write) -> Result<()> {
+        if self.labels().next().is_some() {
+            return Err(Error::String(
+                "Invalid Platform object for legacy encoding, it has labels. Labels are not supported with legacy encoding".to_string(),
+            ));
+        }
         // use a vec to know the name ahead of time and
         // avoid iterating the stack twice
         let digests = self.iter_bottom_up().collect::<Vec<_>>();
@@ -104,6 +123,7 @@ where
 pub struct PlatformBuilder {
     header: HeaderBuilder,
     stack: Stack,
+    labels: Labels,
 }
 
 impl Default for PlatformBuilder {
@@ -111,6 +131,7 @@ impl Default for PlatformBuilder {
         Self {
             header: HeaderBuilder::new(ObjectKind::Platform),
             stack: Stack::default(),
+            labels: Labels::new(),
         }
     }
 }
@@ -121,6 +142,23 @@ impl PlatformBuilder {
         self
     }
 
+    /// Add a descriptive label, replacing any existing value for the key
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add descriptive labels, replacing any existing values for the same keys
+    pub fn with_labels<K, V>(mut self, labels: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.labels
+            .extend(labels.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
     pub fn with_header<F>(mut self, mut header: F) -> Self
     where
         F: FnMut(HeaderBuilder) -> HeaderBuilder,
@@ -133,10 +171,14 @@ impl PlatformBuilder {
         super::BUILDER.with_borrow_mut(|builder| {
             let stack: Vec<_> = self.stack.iter_bottom_up().collect();
             let stack = builder.create_vector(&stack);
+            let labels =
+                labels_for_encoding(self.labels, self.header.encoding_format(), "platform");
+            let labels = build_labels(builder, &labels);
             let platform = spfs_proto::Platform::create(
                 builder,
                 &spfs_proto::PlatformArgs {
                     layers: Some(stack),
+                    labels,
                 },
             );
             let any = spfs_proto::AnyObject::create(
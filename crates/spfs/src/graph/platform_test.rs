@@ -22,3 +22,28 @@ fn test_platform_encoding() {
         .unwrap();
     assert_eq!(actual.digest().unwrap(), expected.digest().unwrap());
 }
+
+#[rstest]
+fn test_platform_encoding_labels() {
+    let layers: Vec<encoding::Digest> =
+        vec![encoding::EMPTY_DIGEST.into(), encoding::NULL_DIGEST.into()];
+    let unlabeled = Platform::from_iter(layers.clone());
+    let expected = Platform::builder()
+        .with_stack(layers.into_iter().collect())
+        .with_label("spk/solution", "my-pkg/1.0.0")
+        .build();
+    assert_ne!(
+        expected.digest().unwrap(),
+        unlabeled.digest().unwrap(),
+        "labels should be included in the digest"
+    );
+
+    let mut stream = Vec::new();
+    expected.encode(&mut stream).unwrap();
+    let actual = crate::graph::Object::decode(&mut stream.as_slice())
+        .unwrap()
+        .into_platform()
+        .unwrap();
+    assert_eq!(actual.digest().unwrap(), expected.digest().unwrap());
+    assert_eq!(actual.label("spk/solution"), Some("my-pkg/1.0.0"));
+}
@@ -85,7 +85,7 @@ impl<'buf> encoding::Digestible for Tree<'buf> {
 
     fn digest(&self) -> std::result::Result<spfs_proto::Digest, Self::Error> {
         let mut hasher = encoding::Hasher::new_sync();
-        self.legacy_encode(&mut hasher)?;
+        self.digest_encode(&mut hasher)?;
         Ok(hasher.digest())
     }
 }
@@ -119,16 +119,14 @@ impl TreeBuf {
                 .into_iter()
                 .map(|entry| {
                     let entry = entry.as_entry();
-                    let name = builder.create_string(entry.name());
-                    spfs_proto::Entry::create(
+                    Entry::build_with_xattrs(
                         builder,
-                        &spfs_proto::EntryArgs {
-                            kind: entry.kind().into(),
-                            object: Some(entry.object()),
-                            mode: entry.mode(),
-                            size_: entry.size_for_legacy_encode(),
-                            name: Some(name),
-                        },
+                        entry.name(),
+                        entry.kind(),
+                        entry.mode(),
+                        entry.size_for_legacy_encode(),
+                        entry.object(),
+                        entry.xattrs(),
                     )
                 })
                 .collect::<Vec<_>>();
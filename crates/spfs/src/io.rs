@@ -120,6 +120,47 @@ pub fn format_changes<'a, U1: 'a, U2: 'a>(
     format_diffs(diffs.filter(|x| !x.mode.is_unchanged()))
 }
 
+/// Return a human readable string rendering of the given changeset.
+///
+/// Ignores any additional entry user data.
+pub fn format_changeset<U>(changes: &tracking::ChangeSet<U>) -> String {
+    let mut outputs = Vec::new();
+    for change in changes.iter() {
+        let mut abouts = Vec::new();
+        if let tracking::ChangeKind::Modified(a, b) = &change.kind {
+            if a.mode != b.mode {
+                abouts.push(format!("mode {{{:06o}=>{:06o}}}", a.mode, b.mode));
+            }
+            if a.object != b.object {
+                abouts.push("content".to_string());
+            }
+            if a.size() != b.size() {
+                abouts.push(format!("size {{{}=>{}}}", a.size(), b.size()));
+            }
+        }
+        let about = if !abouts.is_empty() {
+            format!(" [{}]", abouts.join(", ")).dimmed().to_string()
+        } else {
+            "".to_string()
+        };
+        let mut out = String::new();
+        out += format!("{:>8}", change.kind).bold().as_ref();
+        if let tracking::ChangeKind::Renamed { from, .. } = &change.kind {
+            out += format!("/spfs{from} => ").as_ref();
+        }
+        out += format!("/spfs{}{about}", change.path).as_ref();
+        let out = match change.kind {
+            tracking::ChangeKind::Added(..) => out.green(),
+            tracking::ChangeKind::Removed(..) => out.red(),
+            tracking::ChangeKind::Modified(..) => out.bright_blue(),
+            tracking::ChangeKind::Renamed { .. } => out.yellow(),
+        };
+        outputs.push(out.to_string())
+    }
+
+    outputs.join("\n")
+}
+
 /// Return a human-readable representation of the sync summary data.
 pub fn format_sync_summary(summary: &super::sync::SyncSummary) -> String {
     let super::sync::SyncSummary {
@@ -171,6 +212,7 @@ pub async fn pretty_print_filepath(
                     graph::object::Enum::Layer(_) => "layer",
                     graph::object::Enum::Manifest(_) => "manifest",
                     graph::object::Enum::Blob(_) => "blob",
+                    graph::object::Enum::ChunkList(_) => "chunk list",
                 };
 
                 println!(
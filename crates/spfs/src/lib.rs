@@ -21,11 +21,15 @@ mod error;
 pub mod find_path;
 pub mod graph;
 pub mod io;
+pub mod mirror;
 #[cfg_attr(windows, path = "./monitor_win.rs")]
 pub mod monitor;
+pub mod operation;
 pub mod prelude;
 pub mod proto;
 mod prune;
+mod rate_limit;
+mod render_prune;
 mod repeating_timeout;
 mod resolve;
 pub mod runtime;
@@ -48,9 +52,10 @@ pub use bootstrap::{
 pub use check::Checker;
 pub use clean::Cleaner;
 pub use commit::Committer;
-pub use diff::{diff, diff_runtime_changes, runtime_active_changes};
+pub use diff::{diff, diff_changeset, diff_runtime_changes, runtime_active_changes};
 pub use encoding::Digest;
 pub use error::{Error, OsError, OsErrorExt, Result};
+pub use render_prune::{prune_unused_renders, prune_unused_renders_if_due};
 pub use resolve::{
     compute_environment_manifest,
     compute_manifest,
@@ -80,7 +85,9 @@ pub use self::config::{
     get_config,
     load_config,
     open_repository,
+    reload_config,
     Config,
+    ConfigLoader,
     RemoteAddress,
     RemoteConfig,
     Sentry,
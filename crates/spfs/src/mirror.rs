@@ -0,0 +1,210 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Continuous replication of tags from one repository to others
+
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+
+use crate::prelude::*;
+use crate::sync::{SyncPolicy, SyncSummary};
+use crate::{storage, tracking, Error, Result, Syncer};
+
+#[cfg(test)]
+#[path = "./mirror_test.rs"]
+mod mirror_test;
+
+/// Selects which tags are mirrored using glob patterns
+///
+/// Patterns are matched against the path of each tag, without
+/// any version number (eg: `spk/pkg/my-pkg/**`). A `*` does not
+/// match across `/` separators, but a `**` component will.
+#[derive(Clone, Debug, Default)]
+pub struct TagFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl TagFilter {
+    /// Create a filter from the given include and exclude patterns.
+    ///
+    /// A tag is selected if it matches any of the include patterns,
+    /// or there are none, and does not match any exclude pattern.
+    pub fn new<I, E>(include: I, exclude: E) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+    {
+        fn parse(pattern: &str) -> Result<glob::Pattern> {
+            glob::Pattern::new(pattern).map_err(|source| Error::InvalidTagPattern {
+                pattern: pattern.to_owned(),
+                source,
+            })
+        }
+        Ok(Self {
+            include: include
+                .into_iter()
+                .map(|p| parse(p.as_ref()))
+                .collect::<Result<_>>()?,
+            exclude: exclude
+                .into_iter()
+                .map(|p| parse(p.as_ref()))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Create a filter that selects only the tags matching one pattern.
+    pub fn matching(pattern: &str) -> Result<Self> {
+        Self::new([pattern], std::iter::empty::<&str>())
+    }
+
+    /// True if the given tag should be mirrored.
+    pub fn matches(&self, tag: &tracking::TagSpec) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let path = tag.path();
+        let matches = |pattern: &glob::Pattern| pattern.matches_with(path.as_str(), options);
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Replicates tags from a source repository to one or more destinations
+///
+/// Each call to [`Mirror::sync_once`] finds the tags that have changed
+/// in the source since the previous call and syncs them to every
+/// destination. Tags that fail to sync are retried on the next call.
+pub struct Mirror<'src, 'dst> {
+    src: &'src storage::RepositoryHandle,
+    destinations: Vec<&'dst storage::RepositoryHandle>,
+    filter: TagFilter,
+    policy: SyncPolicy,
+    max_concurrent_manifests: usize,
+    max_concurrent_payloads: usize,
+    rate_limit: Option<u64>,
+    /// The latest version of each tag that has been synced to all destinations
+    mirrored: HashMap<tracking::TagSpec, tracking::Tag>,
+}
+
+impl<'src, 'dst> Mirror<'src, 'dst> {
+    pub fn new<D>(src: &'src storage::RepositoryHandle, destinations: D) -> Self
+    where
+        D: IntoIterator<Item = &'dst storage::RepositoryHandle>,
+    {
+        Self {
+            src,
+            destinations: destinations.into_iter().collect(),
+            filter: TagFilter::default(),
+            // an existing tag in the destination must still be
+            // updated when a new version is pushed to the source
+            policy: SyncPolicy::LatestTags,
+            max_concurrent_manifests: crate::sync::DEFAULT_MAX_CONCURRENT_MANIFESTS,
+            max_concurrent_payloads: crate::sync::DEFAULT_MAX_CONCURRENT_PAYLOADS,
+            rate_limit: None,
+            mirrored: HashMap::new(),
+        }
+    }
+
+    /// Only mirror the tags selected by the given filter.
+    pub fn with_filter(mut self, filter: TagFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Specifies how data is synced to each destination, see [`SyncPolicy`].
+    ///
+    /// Policies that skip existing tags will not update tags
+    /// that already exist in the destination.
+    pub fn with_policy(mut self, policy: SyncPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set how many manifests/layers can be synced at once to each destination.
+    pub fn with_max_concurrent_manifests(mut self, concurrency: usize) -> Self {
+        self.max_concurrent_manifests = concurrency;
+        self
+    }
+
+    /// Set how many payloads/files can be synced at once to each destination.
+    pub fn with_max_concurrent_payloads(mut self, concurrency: usize) -> Self {
+        self.max_concurrent_payloads = concurrency;
+        self
+    }
+
+    /// Limit the rate at which payloads are synced to each destination, in bytes per second.
+    pub fn with_rate_limit(mut self, bytes_per_second: u64) -> Self {
+        self.rate_limit = Some(bytes_per_second);
+        self
+    }
+
+    /// Sync any selected tags that are new or changed since the last call.
+    ///
+    /// Errors syncing individual tags are collected in the result
+    /// rather than stopping the pass, and those tags will be tried
+    /// again on the next call.
+    pub async fn sync_once(&mut self) -> Result<MirrorResult> {
+        let mut changed = Vec::new();
+        let mut tags = self.src.iter_tags();
+        while let Some((spec, tag)) = tags.try_next().await? {
+            if !self.filter.matches(&spec) || self.mirrored.get(&spec) == Some(&tag) {
+                continue;
+            }
+            changed.push((spec, tag));
+        }
+
+        // one syncer per destination shares its cache of synced
+        // objects across all of the tags in this pass
+        let syncers = self
+            .destinations
+            .iter()
+            .map(|dest| {
+                let syncer = Syncer::new(self.src, dest)
+                    .with_policy(self.policy)
+                    .with_max_concurrent_manifests(self.max_concurrent_manifests)
+                    .with_max_concurrent_payloads(self.max_concurrent_payloads);
+                match self.rate_limit {
+                    Some(limit) => syncer.with_rate_limit(limit),
+                    None => syncer,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut result = MirrorResult::default();
+        for (spec, tag) in changed {
+            let mut failed = false;
+            for syncer in syncers.iter() {
+                match syncer.sync_tag(spec.clone()).await {
+                    Ok(res) => result.summary += res.summary(),
+                    Err(err) => {
+                        failed = true;
+                        result.failed.push((spec.clone(), err));
+                    }
+                }
+            }
+            if !failed {
+                result.synced.push(spec.clone());
+                self.mirrored.insert(spec, tag);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// The outcome of one [`Mirror::sync_once`] pass
+#[derive(Debug, Default)]
+pub struct MirrorResult {
+    /// The tags that were synced to all destinations
+    pub synced: Vec<tracking::TagSpec>,
+    /// The tags that failed to sync to one or more destinations
+    pub failed: Vec<(tracking::TagSpec, Error)>,
+    /// The combined summary of all syncs to all destinations
+    pub summary: SyncSummary,
+}
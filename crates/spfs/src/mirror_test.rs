@@ -0,0 +1,98 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{Mirror, TagFilter};
+use crate::fixtures::*;
+use crate::prelude::*;
+use crate::tracking;
+
+#[rstest]
+#[case(&[], &[], "any/tag", true)]
+#[case(&["spk/**"], &[], "spk/pkg/my-pkg/1.0.0/src", true)]
+#[case(&["spk/*"], &[], "spk/pkg/my-pkg", false)]
+#[case(&["spk/**"], &["spk/pkg/*-dev/**"], "spk/pkg/my-dev/1.0.0", false)]
+#[case(&[], &["scratch/**"], "scratch/test", false)]
+#[case(&["a/**", "b/**"], &[], "b/tag", true)]
+fn test_tag_filter(
+    #[case] include: &[&str],
+    #[case] exclude: &[&str],
+    #[case] tag: &str,
+    #[case] expected: bool,
+) {
+    let filter = TagFilter::new(include, exclude).unwrap();
+    let tag = tracking::TagSpec::parse(tag).unwrap();
+    assert_eq!(filter.matches(&tag), expected);
+}
+
+#[rstest]
+fn test_tag_filter_invalid_pattern() {
+    TagFilter::new(["spk/[pkg"], [] as [&str; 0]).expect_err("unclosed bracket should be invalid");
+}
+
+#[rstest]
+#[case::fs(tmprepo("fs"), tmprepo("fs"))]
+#[case::tar(tmprepo("tar"), tmprepo("tar"))]
+#[tokio::test]
+async fn test_mirror_syncs_new_and_updated_tags(
+    #[case]
+    #[future]
+    repo_a: TempRepo,
+    #[case]
+    #[future]
+    repo_b: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    init_logging();
+    let repo_a = repo_a.await;
+    let repo_b = repo_b.await;
+
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("dir/file.txt"), "hello");
+    let manifest = crate::Committer::new(&repo_a)
+        .commit_dir(src_dir.as_path())
+        .await
+        .unwrap();
+    let layer = repo_a
+        .create_layer(&manifest.to_graph_manifest())
+        .await
+        .unwrap();
+    let included = tracking::TagSpec::parse("mirror/included").unwrap();
+    let excluded = tracking::TagSpec::parse("other/excluded").unwrap();
+    for tag in [&included, &excluded] {
+        repo_a
+            .push_tag(tag, &layer.digest().unwrap())
+            .await
+            .unwrap();
+    }
+
+    let filter = TagFilter::new(["mirror/**"], [] as [&str; 0]).unwrap();
+    let mut mirror = Mirror::new(&repo_a, [&*repo_b]).with_filter(filter);
+
+    let result = mirror.sync_once().await.unwrap();
+    assert!(result.failed.is_empty(), "{:?}", result.failed);
+    assert_eq!(result.synced, vec![included.clone()]);
+    assert!(repo_b.has_tag(&included).await);
+    assert!(!repo_b.has_tag(&excluded).await);
+
+    let result = mirror.sync_once().await.unwrap();
+    assert!(
+        result.synced.is_empty(),
+        "unchanged tags should not be synced again"
+    );
+
+    let platform = repo_a
+        .create_platform(layer.digest().unwrap().into())
+        .await
+        .unwrap();
+    repo_a
+        .push_tag(&included, &platform.digest().unwrap())
+        .await
+        .unwrap();
+    let result = mirror.sync_once().await.unwrap();
+    assert_eq!(result.synced, vec![included.clone()]);
+    let synced = repo_b.resolve_tag(&included).await.unwrap();
+    assert_eq!(synced.target, platform.digest().unwrap());
+}
@@ -22,6 +22,12 @@ pub const PROC_DIR: &str = "/proc";
 
 pub const SPFS_MONITOR_FOREGROUND_LOGGING_VAR: &str = "SPFS_MONITOR_FOREGROUND_LOGGING";
 
+/// How often to check whether the exposed mounts of a runtime are still in use
+const EXPOSED_MOUNTS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often to check whether the owner of a detached runtime is still running
+const DETACHED_OWNER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// For internal process change messages
 #[derive(Debug)]
 enum PidEvent {
@@ -344,6 +350,69 @@ pub async fn wait_for_empty_runtime(rt: &runtime::Runtime) -> Result<()> {
     Ok(())
 }
 
+/// Wait until none of the locations where the runtime's filesystem
+/// was exposed are still in use.
+///
+/// An exposed mount is considered to be in use until it is removed
+/// or until there are no more processes in its mount namespace.
+pub async fn wait_for_exposed_mounts(rt: &runtime::Runtime) -> Result<()> {
+    let records = runtime::ExposedMounts::for_runtime(rt.name());
+    loop {
+        let exposed = records.list()?;
+        if exposed.is_empty() {
+            return Ok(());
+        }
+        let active_namespaces: HashSet<PathBuf> = find_processes_and_mount_namespaces()
+            .await?
+            .into_values()
+            .flatten()
+            .collect();
+        let in_use: Vec<_> = exposed
+            .iter()
+            .filter(|m| active_namespaces.contains(&m.mount_namespace))
+            .map(ToString::to_string)
+            .collect();
+        if in_use.is_empty() {
+            return Ok(());
+        }
+        tracing::debug!(?in_use, "waiting for exposed mounts to be removed");
+        tokio::time::sleep(EXPOSED_MOUNTS_POLL_INTERVAL).await;
+    }
+}
+
+/// When provided a detached runtime, wait for its owner to exit and
+/// then ask every process that remains in the runtime to terminate.
+///
+/// A detached runtime is ended by stopping its owner, but any processes
+/// that were joined into it would otherwise keep it alive indefinitely.
+pub async fn terminate_detached_runtime(rt: &runtime::Runtime) -> Result<()> {
+    let (Some(owner), Some(ns)) = (rt.status.owner, rt.config.mount_namespace.as_ref()) else {
+        return Ok(());
+    };
+    // the owner's pid may be reused once it exits, so it is
+    // only considered to be running while it is in the runtime
+    while identify_mount_namespace_of_process(owner).await?.as_ref() == Some(ns) {
+        tokio::time::sleep(DETACHED_OWNER_POLL_INTERVAL).await;
+    }
+
+    let remaining = find_other_processes_in_mount_namespace(ns).await?;
+    if !remaining.is_empty() {
+        tracing::info!(
+            ?remaining,
+            "owner of detached runtime exited, stopping its processes"
+        );
+    }
+    for pid in remaining {
+        let pid = nix::unistd::Pid::from_raw(pid as i32);
+        match nix::sys::signal::kill(pid, nix::sys::signal::Signal::SIGTERM) {
+            // the process may have exited on its own in the meantime
+            Ok(()) | Err(nix::errno::Errno::ESRCH) => {}
+            Err(err) => tracing::warn!(?err, %pid, "failed to stop process in detached runtime"),
+        }
+    }
+    Ok(())
+}
+
 /// Identify the mount namespace of the provided process id.
 ///
 /// Return None if the pid is not found.
@@ -28,6 +28,12 @@ pub async fn wait_for_empty_runtime(_rt: &runtime::Runtime) -> Result<()> {
     todo!()
 }
 
+/// When provided a detached runtime, wait for its owner to exit and
+/// then ask every process that remains in the runtime to terminate.
+pub async fn terminate_detached_runtime(_rt: &runtime::Runtime) -> Result<()> {
+    todo!()
+}
+
 /// Identify the mount namespace of the provided process id.
 ///
 /// Return None if the pid is not found.
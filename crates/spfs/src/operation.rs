@@ -0,0 +1,163 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Progress reporting and cancellation for long-running storage operations.
+//!
+//! The [`crate::Syncer`], [`crate::Committer`] and [`crate::Cleaner`] each
+//! have their own detailed reporter trait. Applications that only want to
+//! show a progress bar can instead implement [`OperationReporter`] and wrap
+//! it in an [`OperationProgress`], which adapts it to all of them.
+
+use std::future::Future;
+use std::sync::Arc;
+
+pub use tokio_util::sync::CancellationToken;
+
+use crate::{clean, commit, encoding, graph, sync, tracking, Error, Result};
+
+#[cfg(test)]
+#[path = "./operation_test.rs"]
+mod operation_test;
+
+/// Identifies the kind of long-running operation being reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Sync,
+    Commit,
+    Clean,
+}
+
+/// An amount of work within an operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of items (files, objects, etc)
+    pub items: u64,
+    /// The number of bytes of payload data
+    pub bytes: u64,
+}
+
+impl Progress {
+    pub fn items(items: u64) -> Self {
+        Self { items, bytes: 0 }
+    }
+
+    pub fn item_with_bytes(bytes: u64) -> Self {
+        Self { items: 1, bytes }
+    }
+}
+
+/// Receives generic progress updates from a long-running operation.
+///
+/// Totals are discovered incrementally as the operation runs, so
+/// the amount of discovered work can grow after progress has been
+/// made. Operations that cannot estimate their total work, like a
+/// clean, only report completed progress.
+pub trait OperationReporter: Send + Sync {
+    /// Called when additional work has been identified
+    fn discovered(&self, _operation: Operation, _progress: Progress) {}
+
+    /// Called when some amount of work has been completed
+    fn completed(&self, _operation: Operation, _progress: Progress) {}
+}
+
+impl<T> OperationReporter for Arc<T>
+where
+    T: OperationReporter + ?Sized,
+{
+    fn discovered(&self, operation: Operation, progress: Progress) {
+        (**self).discovered(operation, progress)
+    }
+
+    fn completed(&self, operation: Operation, progress: Progress) {
+        (**self).completed(operation, progress)
+    }
+}
+
+/// Adapts an [`OperationReporter`] to the detailed reporter
+/// traits of each storage operation.
+pub struct OperationProgress<R: OperationReporter> {
+    operation: Operation,
+    reporter: R,
+}
+
+impl<R: OperationReporter> OperationProgress<R> {
+    /// Report the progress of the given operation to `reporter`
+    pub fn new(operation: Operation, reporter: R) -> Self {
+        Self {
+            operation,
+            reporter,
+        }
+    }
+
+    fn discovered(&self, progress: Progress) {
+        self.reporter.discovered(self.operation, progress)
+    }
+
+    fn completed(&self, progress: Progress) {
+        self.reporter.completed(self.operation, progress)
+    }
+}
+
+impl<R: OperationReporter> sync::SyncReporter for OperationProgress<R> {
+    fn visit_blob(&self, blob: &graph::Blob) {
+        self.discovered(Progress::item_with_bytes(blob.size()));
+    }
+
+    fn synced_blob(&self, result: &sync::SyncBlobResult) {
+        self.completed(Progress::item_with_bytes(
+            result.summary().synced_payload_bytes,
+        ));
+    }
+}
+
+impl<R: OperationReporter> tracking::ComputeManifestReporter for OperationProgress<R> {
+    fn computed_entry(&self, entry: &tracking::Entry) {
+        if entry.kind.is_blob() {
+            self.discovered(Progress::item_with_bytes(entry.size()));
+        }
+    }
+}
+
+impl<R: OperationReporter> commit::CommitReporter for OperationProgress<R> {
+    fn committed_blob(&self, result: &commit::CommitBlobResult) {
+        self.completed(Progress::item_with_bytes(result.node().entry.size()));
+    }
+}
+
+impl<R: OperationReporter> clean::CleanReporter for OperationProgress<R> {
+    fn visit_tag(&self, _tag: &tracking::Tag) {
+        self.completed(Progress::items(1));
+    }
+
+    fn visit_object(&self, _object: &graph::Object) {
+        self.completed(Progress::items(1));
+    }
+
+    fn visit_payload(&self, payload: &graph::Blob) {
+        self.completed(Progress::item_with_bytes(payload.size()));
+    }
+
+    fn visit_proxy(&self, _proxy: &encoding::Digest) {
+        self.completed(Progress::items(1));
+    }
+
+    fn visit_render(&self, _render: &encoding::Digest) {
+        self.completed(Progress::items(1));
+    }
+}
+
+/// Run the given future to completion unless the token is cancelled first.
+///
+/// When cancelled, the future is dropped and [`Error::OperationCancelled`]
+/// is returned instead.
+pub async fn cancellable<F, T>(token: &CancellationToken, fut: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(Error::OperationCancelled),
+        res = fut => res,
+    }
+}
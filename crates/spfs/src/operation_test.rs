@@ -0,0 +1,113 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use rstest::rstest;
+
+use super::{CancellationToken, Operation, OperationProgress, OperationReporter, Progress};
+use crate::fixtures::*;
+use crate::prelude::*;
+use crate::{Committer, Error, Syncer};
+
+#[derive(Default)]
+struct CountingReporter {
+    discovered: std::sync::Mutex<Progress>,
+    completed: std::sync::Mutex<Progress>,
+}
+
+impl OperationReporter for CountingReporter {
+    fn discovered(&self, operation: Operation, progress: Progress) {
+        assert_eq!(operation, Operation::Commit);
+        let mut total = self.discovered.lock().unwrap();
+        total.items += progress.items;
+        total.bytes += progress.bytes;
+    }
+
+    fn completed(&self, operation: Operation, progress: Progress) {
+        assert_eq!(operation, Operation::Commit);
+        let mut total = self.completed.lock().unwrap();
+        total.items += progress.items;
+        total.bytes += progress.bytes;
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_commit_dir_reports_progress(#[future] tmprepo: TempRepo, tmpdir: tempfile::TempDir) {
+    let tmprepo = tmprepo.await;
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("dir/file.txt"), "hello");
+    ensure(src_dir.join("dir2/otherfile.txt"), "hello2");
+
+    let reporter = Arc::new(CountingReporter::default());
+    Committer::new(&tmprepo)
+        .with_reporter(OperationProgress::new(
+            Operation::Commit,
+            Arc::clone(&reporter),
+        ))
+        .commit_dir(&src_dir)
+        .await
+        .unwrap();
+
+    let expected = Progress {
+        items: 2,
+        bytes: 11,
+    };
+    assert_eq!(*reporter.discovered.lock().unwrap(), expected);
+    assert_eq!(*reporter.completed.lock().unwrap(), expected);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_commit_dir_cancelled(#[future] tmprepo: TempRepo, tmpdir: tempfile::TempDir) {
+    let tmprepo = tmprepo.await;
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("dir/file.txt"), "hello");
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let res = Committer::new(&tmprepo)
+        .with_cancellation(token)
+        .commit_dir(&src_dir)
+        .await;
+    assert!(
+        matches!(res, Err(Error::OperationCancelled)),
+        "expected commit to be cancelled, got {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sync_cancelled(
+    #[future]
+    #[from(tmprepo)]
+    repo_a: TempRepo,
+    #[future]
+    #[from(tmprepo)]
+    repo_b: TempRepo,
+    tmpdir: tempfile::TempDir,
+) {
+    let repo_a = repo_a.await;
+    let repo_b = repo_b.await;
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("dir/file.txt"), "hello");
+    let manifest = Committer::new(&repo_a).commit_dir(&src_dir).await.unwrap();
+    let layer = repo_a
+        .create_layer(&manifest.to_graph_manifest())
+        .await
+        .unwrap();
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let res = Syncer::new(&repo_a, &repo_b)
+        .with_cancellation(token)
+        .sync_env(layer.digest().unwrap().into())
+        .await;
+    assert!(
+        matches!(res, Err(Error::OperationCancelled)),
+        "expected sync to be cancelled, got {res:?}"
+    );
+    assert!(!repo_b.has_object(layer.digest().unwrap()).await);
+}
@@ -116,6 +116,9 @@ impl From<Error> for super::Error {
             crate::Error::InvalidReference(message) => {
                 super::error::Kind::InvalidReference(super::InvalidReferenceError { message })
             }
+            crate::Error::TagChanged(message) => {
+                super::error::Kind::TagChanged(super::TagChangedError { message })
+            }
             err => super::error::Kind::Other(format!("{err:?}")),
         });
         Self { kind }
@@ -143,6 +146,7 @@ impl From<super::Error> for Error {
             Some(super::error::Kind::InvalidReference(rpc)) => {
                 crate::Error::InvalidReference(rpc.message)
             }
+            Some(super::error::Kind::TagChanged(rpc)) => crate::Error::TagChanged(rpc.message),
             Some(super::error::Kind::Other(message)) => Error::String(message),
             None => Error::String("Server did not provide an error message".to_string()),
         }
@@ -166,6 +170,8 @@ impl From<&graph::object::Enum> for super::Object {
                 graph::object::Enum::Layer(o) => Kind::Layer(o.into()),
                 graph::object::Enum::Manifest(o) => Kind::Manifest(o.into()),
                 graph::object::Enum::Blob(o) => Kind::Blob(o.into()),
+                // newer object kinds are only sent in the flatbuffer format
+                graph::object::Enum::ChunkList(o) => Kind::Buffer(o.inner_bytes().clone()),
             }),
         }
     }
@@ -210,6 +216,10 @@ impl From<&graph::Platform> for super::Platform {
     fn from(source: &graph::Platform) -> Self {
         Self {
             stack: source.iter_bottom_up().map(Into::into).collect(),
+            labels: source
+                .labels()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
         }
     }
 }
@@ -218,13 +228,15 @@ impl TryFrom<super::Platform> for graph::Platform {
     type Error = Error;
 
     fn try_from(source: super::Platform) -> Result<Self> {
-        Ok(Self::from(
-            source
-                .stack
-                .into_iter()
-                .map(TryInto::try_into)
-                .collect::<Result<graph::Stack>>()?,
-        ))
+        let stack = source
+            .stack
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<graph::Stack>>()?;
+        Ok(Self::builder()
+            .with_stack(stack)
+            .with_labels(source.labels)
+            .build())
     }
 }
 
@@ -239,6 +251,10 @@ impl From<&graph::Layer> for super::Layer {
         Self {
             manifest: source.manifest().map(|m| m.into()),
             annotations,
+            labels: source
+                .labels()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
         }
     }
 }
@@ -247,35 +263,23 @@ impl TryFrom<super::Layer> for graph::Layer {
     type Error = Error;
     fn try_from(source: super::Layer) -> Result<Self> {
         let digest = Some(convert_digest(source.manifest)?);
+        if digest.is_none() && source.annotations.is_empty() {
+            return Err(Error::String(
+                "Creating a graph::Layer requires at least one of: a manifest digest, or an annotation".to_string(),
+            ));
+        }
 
+        let mut annotations: Vec<graph::KeyAnnotationValuePair> = Vec::new();
+        for a in source.annotations.iter() {
+            annotations.push((&a.key, a.value.as_ref().try_into()?));
+        }
+        let mut builder = Self::builder()
+            .with_annotations(annotations)
+            .with_labels(source.labels);
         if let Some(manifest_digest) = digest {
-            if !source.annotations.is_empty() {
-                let mut annotations: Vec<graph::KeyAnnotationValuePair> = Vec::new();
-                for a in source.annotations.iter() {
-                    annotations.push((&a.key, a.value.as_ref().try_into()?));
-                }
-                // A spfs filesystem layer with some annotations
-                Ok(Self::new_with_manifest_and_annotations(
-                    manifest_digest,
-                    annotations,
-                ))
-            } else {
-                // A typical filesystem layer
-                Ok(Self::new(manifest_digest))
-            }
-        } else if !source.annotations.is_empty() {
-            let mut annotations: Vec<graph::KeyAnnotationValuePair> = Vec::new();
-            for a in source.annotations.iter() {
-                annotations.push((&a.key, a.value.as_ref().try_into()?));
-            }
-
-            // An annotation only layer
-            Ok(Self::new_with_annotations(annotations))
-        } else {
-            Err(Error::String(
-                "Creating a graph::Layer requires at least one of: a manifest digest, or an annotation".to_string(),
-            ))
+            builder = builder.with_manifest(manifest_digest);
         }
+        Ok(builder.build())
     }
 }
 
@@ -300,21 +304,24 @@ impl TryFrom<super::Manifest> for graph::Manifest {
                 .into_iter()
                 .map(|entry: super::Entry| {
                     let kind = match super::EntryKind::try_from(entry.kind) {
-                        Ok(super::EntryKind::Tree) => spfs_proto::EntryKind::Tree,
-                        Ok(super::EntryKind::Blob) => spfs_proto::EntryKind::Blob,
-                        Ok(super::EntryKind::Mask) => spfs_proto::EntryKind::Mask,
+                        Ok(super::EntryKind::Tree) => tracking::EntryKind::Tree,
+                        Ok(super::EntryKind::Blob) => tracking::EntryKind::Blob(entry.size),
+                        Ok(super::EntryKind::Mask) => tracking::EntryKind::Mask,
                         Err(_) => return Err("Received unknown entry kind in rpc data".into()),
                     };
-                    let name = builder.create_string(&entry.name);
-                    Ok(spfs_proto::Entry::create(
+                    // attributes are stored sorted by name
+                    let mut xattrs: Vec<_> = entry.xattrs.iter().collect();
+                    xattrs.sort_unstable_by_key(|(name, _)| *name);
+                    Ok(graph::Entry::build_with_xattrs(
                         &mut builder,
-                        &spfs_proto::EntryArgs {
-                            kind,
-                            object: Some(&convert_digest(entry.object)?),
-                            mode: entry.mode,
-                            size_: entry.size,
-                            name: Some(name),
-                        },
+                        &entry.name,
+                        kind,
+                        entry.mode,
+                        entry.size,
+                        &convert_digest(entry.object)?,
+                        xattrs
+                            .into_iter()
+                            .map(|(name, value)| (name.as_str(), value.as_slice())),
                     ))
                 })
                 .collect::<Result<Vec<_>>>()?;
@@ -406,6 +413,10 @@ impl<'buf> From<&graph::Entry<'buf>> for super::Entry {
             mode: source.mode(),
             size: source.size(),
             name: source.name().to_owned(),
+            xattrs: source
+                .xattrs()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
         }
     }
 }
@@ -415,6 +426,7 @@ impl From<&graph::Blob> for super::Blob {
         Self {
             payload: Some(source.payload().into()),
             size: source.size(),
+            chunks: source.chunks().map(Into::into),
         }
     }
 }
@@ -422,7 +434,13 @@ impl From<&graph::Blob> for super::Blob {
 impl TryFrom<super::Blob> for graph::Blob {
     type Error = Error;
     fn try_from(source: super::Blob) -> Result<Self> {
-        Ok(Self::new(convert_digest(source.payload)?, source.size))
+        let mut builder = Self::builder()
+            .with_payload(convert_digest(source.payload)?)
+            .with_size(source.size);
+        if let Some(chunks) = source.chunks {
+            builder = builder.with_chunks(convert_digest(Some(chunks))?);
+        }
+        Ok(builder.build())
     }
 }
 
@@ -506,3 +524,40 @@ impl TryFrom<Option<super::DigestSearchCriteria>> for graph::DigestSearchCriteri
             .try_into()
     }
 }
+
+impl From<&storage::ClientRequirements> for super::GetCapabilitiesResponse {
+    fn from(requirements: &storage::ClientRequirements) -> Self {
+        Self {
+            server_version: crate::VERSION.to_string(),
+            min_client_version: requirements
+                .min_client_version
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            required_features: requirements.features.iter().cloned().collect(),
+            supported_features: storage::SUPPORTED_FEATURES
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            // the requirements do not include the algorithm,
+            // which is filled in by the server from its repository
+            digest_algorithm: String::new(),
+        }
+    }
+}
+
+impl TryFrom<super::GetCapabilitiesResponse> for storage::ClientRequirements {
+    type Error = Error;
+    fn try_from(response: super::GetCapabilitiesResponse) -> Result<Self> {
+        let min_client_version = match response.min_client_version.as_str() {
+            "" => None,
+            version => Some(semver::Version::parse(version).map_err(|err| {
+                Error::String(format!("Received invalid minimum client version: {err}"))
+            })?),
+        };
+        Ok(Self {
+            min_client_version,
+            features: response.required_features.into_iter().collect(),
+        })
+    }
+}
@@ -11,6 +11,10 @@ mod generated {
     tonic::include_proto!("spfs");
 }
 
+/// The request metadata entry where clients identify
+/// the user making changes to a repository
+pub(crate) const USER_METADATA_KEY: &str = "spfs-user";
+
 pub(crate) use conversions::{convert_digest, convert_from_datetime};
 pub use generated::*;
 pub(crate) use result::RpcResult;
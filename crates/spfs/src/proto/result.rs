@@ -87,18 +87,38 @@ rpc_result!(
     gen::read_tag_response::Result,
     gen::read_tag_response::TagList
 );
+rpc_result!(
+    gen::ReadTagPageResponse,
+    gen::read_tag_page_response::Result,
+    gen::read_tag_page_response::TagList
+);
 rpc_result!(gen::InsertTagResponse, gen::insert_tag_response::Result);
+rpc_result!(
+    gen::InsertTagIfMatchesResponse,
+    gen::insert_tag_if_matches_response::Result,
+    gen::Tag
+);
 rpc_result!(
     gen::RemoveTagStreamResponse,
     gen::remove_tag_stream_response::Result
 );
 rpc_result!(gen::RemoveTagResponse, gen::remove_tag_response::Result);
+rpc_result!(
+    gen::WatchTagsResponse,
+    gen::watch_tags_response::Result,
+    gen::Tag
+);
 
 rpc_result!(
     gen::ReadObjectResponse,
     gen::read_object_response::Result,
     gen::Object
 );
+rpc_result!(
+    gen::ReadObjectsResponse,
+    gen::read_objects_response::Result,
+    gen::read_objects_response::ObjectList
+);
 rpc_result!(
     gen::FindDigestsResponse,
     gen::find_digests_response::Result,
@@ -120,6 +140,10 @@ rpc_result!(
     gen::walk_objects_response::WalkObjectsItem
 );
 rpc_result!(gen::WriteObjectResponse, gen::write_object_response::Result);
+rpc_result!(
+    gen::WriteObjectsResponse,
+    gen::write_objects_response::Result
+);
 rpc_result!(
     gen::RemoveObjectResponse,
     gen::remove_object_response::Result
@@ -0,0 +1,168 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+use tokio::time::{Instant, Sleep};
+
+use crate::tracking::BlobRead;
+
+#[cfg(test)]
+#[path = "./rate_limit_test.rs"]
+mod rate_limit_test;
+
+/// The most bytes that a reader will claim from its limiter at once
+const MAX_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A token bucket that limits the rate at which data is transferred.
+///
+/// One limiter can be shared between any number of readers,
+/// which together will not exceed the configured rate.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    available: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Create a limiter that allows the given number of bytes through
+    /// each second, with bursts of up to one second's worth of data.
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            bucket: Mutex::new(Bucket {
+                available: bytes_per_second as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// The number of bytes allowed through this limiter each second
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    /// Claim up to `wanted` bytes from the bucket.
+    ///
+    /// Returns the number of bytes that were claimed, or how long to
+    /// wait before trying again if the bucket is currently empty.
+    fn try_claim(&self, wanted: usize) -> std::result::Result<usize, Duration> {
+        let rate = self.bytes_per_second as f64;
+        let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.available = (bucket.available + elapsed * rate).min(rate);
+        bucket.updated = now;
+
+        let wanted = wanted.min(self.bytes_per_second as usize).max(1);
+        if bucket.available >= 1.0 {
+            let claimed = wanted.min(bucket.available as usize);
+            bucket.available -= claimed as f64;
+            return Ok(claimed);
+        }
+        // wait long enough for the whole request to be available
+        // rather than waking repeatedly for a few bytes at a time
+        let missing = wanted as f64 - bucket.available;
+        Err(Duration::from_secs_f64(missing / rate))
+    }
+}
+
+pin_project! {
+    /// Limits the rate at which data can be read from the inner reader.
+    pub struct RateLimitedReader<R> {
+        #[pin]
+        inner: R,
+        limiter: Arc<RateLimiter>,
+        // bytes that have been claimed from the limiter but not yet read
+        allowance: usize,
+        delay: Option<Pin<Box<Sleep>>>,
+    }
+}
+
+impl<R> RateLimitedReader<R> {
+    pub fn new(inner: R, limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            inner,
+            limiter,
+            allowance: 0,
+            delay: None,
+        }
+    }
+
+    /// Wait until some data can be read according to the limiter.
+    fn poll_allowance(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.project();
+        while *this.allowance == 0 {
+            if let Some(delay) = this.delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                *this.delay = None;
+            }
+            match this.limiter.try_claim(MAX_CHUNK_SIZE) {
+                Ok(claimed) => *this.allowance = claimed,
+                Err(wait) => *this.delay = Some(Box::pin(tokio::time::sleep(wait))),
+            }
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<R> AsyncBufRead for RateLimitedReader<R>
+where
+    R: AsyncBufRead,
+{
+    fn poll_fill_buf(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::io::Result<&[u8]>> {
+        ready!(self.as_mut().poll_allowance(cx));
+        let this = self.project();
+        let allowance = *this.allowance;
+        let buf = ready!(this.inner.poll_fill_buf(cx))?;
+        Poll::Ready(Ok(&buf[..buf.len().min(allowance)]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.project();
+        *this.allowance = this.allowance.saturating_sub(amt);
+        this.inner.consume(amt)
+    }
+}
+
+impl<R> AsyncRead for RateLimitedReader<R>
+where
+    R: AsyncBufRead,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let count = data.len().min(buf.remaining());
+        buf.put_slice(&data[..count]);
+        self.consume(count);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R> BlobRead for RateLimitedReader<R>
+where
+    R: BlobRead,
+{
+    fn permissions(&self) -> Option<u32> {
+        self.inner.permissions()
+    }
+}
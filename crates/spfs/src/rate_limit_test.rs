@@ -0,0 +1,45 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rstest::rstest;
+use tokio::io::AsyncReadExt;
+
+use super::{RateLimitedReader, RateLimiter};
+
+#[rstest]
+fn test_rate_limiter_bursts_up_to_rate() {
+    let limiter = RateLimiter::new(1000);
+    assert_eq!(limiter.try_claim(600), Ok(600));
+    assert_eq!(
+        limiter.try_claim(600),
+        Ok(400),
+        "should only be able to claim what remains in the bucket"
+    );
+    assert!(
+        limiter.try_claim(600).is_err(),
+        "an empty bucket should require a wait"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_rate_limited_reader() {
+    let data = vec![7_u8; 1500];
+    let limiter = Arc::new(RateLimiter::new(1000));
+    let mut reader = RateLimitedReader::new(std::io::Cursor::new(data.clone()), limiter);
+
+    let start = std::time::Instant::now();
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).await.unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(out, data, "all data should be read through the limiter");
+    assert!(
+        elapsed >= Duration::from_millis(400),
+        "reading 1.5x the rate should take about half a second, took {elapsed:?}"
+    );
+}
@@ -0,0 +1,133 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+
+use crate::storage::fs::OpenFsRepository;
+use crate::{encoding, runtime, Error, Result};
+
+#[cfg(test)]
+#[path = "./render_prune_test.rs"]
+mod render_prune_test;
+
+/// The file in a renders directory whose modified time is
+/// when the renders were last pruned by [`prune_unused_renders_if_due`]
+const LAST_PRUNED_FILENAME: &str = "last-pruned";
+
+/// Remove the renders in a repository that have not been used since the
+/// given time, returning the digests of the renders that were removed.
+///
+/// Renders of the layers in any runtime of the given runtime storage are
+/// always kept, no matter when they were last used. When `dry_run` is
+/// true, the renders that would be removed are returned but left on disk.
+pub async fn prune_unused_renders(
+    repo: &OpenFsRepository,
+    runtimes: &runtime::Storage,
+    unused_since: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<Vec<encoding::Digest>> {
+    let started = Utc::now();
+    let in_use = find_renders_in_use(repo, runtimes).await?;
+
+    let mut visited = Vec::new();
+    let mut removed = Vec::new();
+    let mut renders = repo.iter_rendered_manifests();
+    while let Some(digest) = renders.try_next().await? {
+        visited.push(digest);
+        if in_use.contains(&digest) {
+            continue;
+        }
+        let Some(last_used) = repo.rendered_manifest_last_used(digest).await? else {
+            continue;
+        };
+        if last_used >= unused_since {
+            continue;
+        }
+        if !dry_run {
+            tracing::debug!(%digest, %last_used, "removing unused render");
+            repo.remove_rendered_manifest(digest).await?;
+        }
+        removed.push(digest);
+    }
+    drop(renders);
+
+    if !dry_run && !removed.is_empty() {
+        let removed_set = removed.iter().collect::<HashSet<_>>();
+        let present = visited
+            .into_iter()
+            .filter(|digest| !removed_set.contains(digest));
+        if let Err(err) = repo.compact_render_catalog(present, started).await {
+            tracing::warn!(?err, "failed to compact render catalog");
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove the renders that have not been used within the given amount
+/// of time like [`prune_unused_renders`], but only when they have not
+/// already been pruned within the given interval.
+///
+/// Returns None if the renders were not pruned.
+pub async fn prune_unused_renders_if_due(
+    repo: &OpenFsRepository,
+    runtimes: &runtime::Storage,
+    unused_for: std::time::Duration,
+    interval: std::time::Duration,
+) -> Result<Option<Vec<encoding::Digest>>> {
+    let unused_for = chrono::Duration::from_std(unused_for)
+        .map_err(|err| Error::String(format!("Invalid render prune age: {err}")))?;
+    let Some(render_store) = &repo.renders else {
+        return Ok(None);
+    };
+    let marker = render_store.renders.root().join(LAST_PRUNED_FILENAME);
+    match tokio::fs::symlink_metadata(&marker)
+        .await
+        .and_then(|metadata| metadata.modified())
+    {
+        Ok(mtime) if mtime.elapsed().unwrap_or_default() < interval => return Ok(None),
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(Error::StorageReadError(
+                "modified time of last pruned marker",
+                marker,
+                err,
+            ))
+        }
+    }
+    // the marker is updated first so that other processes
+    // do not try to prune the same renders at the same time
+    tokio::fs::write(&marker, b"")
+        .await
+        .map_err(|err| Error::StorageWriteError("update last pruned marker", marker, err))?;
+    prune_unused_renders(repo, runtimes, Utc::now() - unused_for, false)
+        .await
+        .map(Some)
+}
+
+/// Find the rendered manifests of every layer in the given runtimes.
+async fn find_renders_in_use(
+    repo: &OpenFsRepository,
+    runtimes: &runtime::Storage,
+) -> Result<HashSet<encoding::Digest>> {
+    let mut in_use = HashSet::new();
+    let mut runtimes = runtimes.iter_runtimes().await;
+    while let Some(runtime) = runtimes.try_next().await? {
+        let layers =
+            match crate::resolve_stack_to_layers_with_repo(&runtime.status.stack, repo).await {
+                Ok(layers) => layers,
+                Err(err) => {
+                    // the layers of this runtime are not in the repository,
+                    // and so cannot have been rendered from it
+                    tracing::debug!(?err, runtime = %runtime.name(), "skipping runtime");
+                    continue;
+                }
+            };
+        in_use.extend(layers.iter().filter_map(|layer| layer.manifest()).copied());
+    }
+    Ok(in_use)
+}
@@ -0,0 +1,109 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use rstest::rstest;
+
+use super::{prune_unused_renders, prune_unused_renders_if_due};
+use crate::fixtures::*;
+use crate::prelude::*;
+use crate::storage::fs::{FsRepository, Renderer};
+use crate::storage::RepositoryHandle;
+use crate::{runtime, Committer};
+
+#[rstest]
+#[tokio::test]
+async fn test_prune_unused_renders_keeps_runtime_layers(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let handle = RepositoryHandle::from(
+        FsRepository::create(tmpdir.path().join("repo"))
+            .await
+            .unwrap(),
+    );
+    ensure(tmpdir.path().join("used/file.txt"), "used");
+    ensure(tmpdir.path().join("unused/file.txt"), "unused");
+    let mut layers = Vec::new();
+    for name in ["used", "unused"] {
+        let manifest = Committer::new(&handle)
+            .commit_dir(tmpdir.path().join(name))
+            .await
+            .unwrap()
+            .to_graph_manifest();
+        layers.push(handle.create_layer(&manifest).await.unwrap());
+    }
+    let RepositoryHandle::FS(fs) = &handle else {
+        panic!("Unexpected repository type!");
+    };
+    let repo = fs.opened().await.unwrap();
+    for layer in layers.iter() {
+        let manifest = repo
+            .read_manifest(*layer.manifest().unwrap())
+            .await
+            .unwrap();
+        Renderer::new(&*repo)
+            .render_manifest(&manifest, None)
+            .await
+            .unwrap();
+    }
+    let used = *layers[0].manifest().unwrap();
+    let unused = *layers[1].manifest().unwrap();
+
+    let runtimes = runtime::Storage::new(RepositoryHandle::from(Arc::clone(&repo))).unwrap();
+    let mut rt = runtimes.create_runtime(false, Vec::new()).await.unwrap();
+    rt.push_digest(layers[0].digest().unwrap());
+    rt.save_state_to_storage().await.unwrap();
+
+    // every render was used before this time
+    let unused_since = Utc::now() + Duration::hours(1);
+    let removed = prune_unused_renders(&repo, &runtimes, unused_since, true)
+        .await
+        .unwrap();
+    assert_eq!(removed, vec![unused]);
+    assert!(
+        repo.has_rendered_manifest(unused).await,
+        "a dry run should not remove anything"
+    );
+
+    let removed = prune_unused_renders(&repo, &runtimes, unused_since, false)
+        .await
+        .unwrap();
+    assert_eq!(removed, vec![unused]);
+    assert!(!repo.has_rendered_manifest(unused).await);
+    assert!(
+        repo.has_rendered_manifest(used).await,
+        "renders used by a runtime should be kept"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_prune_unused_renders_if_due(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let handle = RepositoryHandle::from(
+        FsRepository::create(tmpdir.path().join("repo"))
+            .await
+            .unwrap(),
+    );
+    let RepositoryHandle::FS(fs) = &handle else {
+        panic!("Unexpected repository type!");
+    };
+    let repo = fs.opened().await.unwrap();
+    let runtimes = runtime::Storage::new(RepositoryHandle::from(Arc::clone(&repo))).unwrap();
+    let unused_for = std::time::Duration::from_secs(30 * 24 * 3600);
+    let interval = std::time::Duration::from_secs(3600);
+
+    let pruned = prune_unused_renders_if_due(&repo, &runtimes, unused_for, interval)
+        .await
+        .unwrap();
+    assert!(pruned.is_some(), "renders should be pruned the first time");
+    let pruned = prune_unused_renders_if_due(&repo, &runtimes, unused_for, interval)
+        .await
+        .unwrap();
+    assert!(
+        pruned.is_none(),
+        "renders should not be pruned again within the interval"
+    );
+}
@@ -432,7 +432,7 @@ where
             graph::object::Enum::Manifest(manifest) => {
                 layers.push(graph::Layer::new(manifest.digest().unwrap()))
             }
-            obj @ graph::object::Enum::Blob(_) => {
+            obj @ (graph::object::Enum::Blob(_) | graph::object::Enum::ChunkList(_)) => {
                 return Err(format!(
                     "Cannot resolve object into a mountable filesystem layer: {:?}",
                     obj.kind()
@@ -0,0 +1,191 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Privileged records of where runtimes have been exposed, see `spfs expose`.
+//!
+//! These records decide which locations may later be unmounted by the
+//! privileged spfs-expose binary, so unlike the rest of a runtime's
+//! state they are not kept in the user-writable runtime storage.
+
+use std::fmt::Display;
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use nix::unistd::Uid;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./exposed_test.rs"]
+mod exposed_test;
+
+/// The directory where spfs-expose records the exposed mounts of each runtime
+pub const EXPOSED_MOUNTS_DIR: &str = "/run/spfs/exposed";
+
+/// A read-only view of a runtime's /spfs filesystem that was
+/// mounted at another location, see `spfs expose`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExposedMount {
+    /// The path where the filesystem was mounted
+    pub target: PathBuf,
+    /// The mount namespace that the target path was mounted in,
+    /// as read from `/proc/<pid>/ns/mnt`
+    pub mount_namespace: PathBuf,
+    /// The id of the user that exposed the runtime
+    pub owner: u32,
+    /// When the filesystem was exposed
+    pub created: DateTime<Utc>,
+}
+
+impl ExposedMount {
+    /// True if this mount is at the given path in the given mount namespace
+    pub fn is_at(&self, target: &Path, mount_namespace: &Path) -> bool {
+        self.target == target && self.mount_namespace == mount_namespace
+    }
+}
+
+impl Display for ExposedMount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} in {}",
+            self.target.display(),
+            self.mount_namespace.display()
+        )
+    }
+}
+
+/// The locations where a single runtime has been exposed.
+///
+/// Records are written by spfs-expose into a directory that only it can
+/// modify: the state directory and each record are handed to root once
+/// written, and records that are not owned by root are ignored when read.
+#[derive(Debug, Clone)]
+pub struct ExposedMounts {
+    path: PathBuf,
+    owner: Option<Uid>,
+}
+
+impl ExposedMounts {
+    /// The exposed mounts of the named runtime
+    pub fn for_runtime(runtime_name: &str) -> Self {
+        Self::new(Path::new(EXPOSED_MOUNTS_DIR), runtime_name)
+    }
+
+    /// The exposed mounts of the named runtime, recorded in `state_dir`
+    pub fn new(state_dir: &Path, runtime_name: &str) -> Self {
+        Self {
+            path: state_dir.join(format!("{runtime_name}.json")),
+            owner: Some(Uid::from_raw(0)),
+        }
+    }
+
+    /// Load all of the recorded mounts, which is empty
+    /// if the runtime was never exposed.
+    pub fn list(&self) -> Result<Vec<ExposedMount>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(Error::RuntimeReadError(self.path.clone(), err)),
+        };
+        if let Some(owner) = self.owner {
+            let meta = file
+                .metadata()
+                .map_err(|err| Error::RuntimeReadError(self.path.clone(), err))?;
+            if meta.uid() != owner.as_raw() {
+                tracing::warn!(
+                    path = ?self.path,
+                    "Ignoring exposed mounts that were not recorded by spfs-expose"
+                );
+                return Ok(Vec::new());
+            }
+        }
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|err| Error::String(format!("Invalid exposed mounts {:?}: {err}", self.path)))
+    }
+
+    /// Record that the runtime was exposed at another location,
+    /// replacing any existing record for the same location.
+    pub fn add(&self, mount: ExposedMount) -> Result<()> {
+        let mut mounts = self.list()?;
+        mounts.retain(|m| !m.is_at(&mount.target, &mount.mount_namespace));
+        mounts.push(mount);
+        self.save(&mounts)
+    }
+
+    /// Forget a location where the runtime was exposed.
+    ///
+    /// Returns the removed record, or None if the runtime was
+    /// not known to be exposed at that location.
+    pub fn remove(&self, target: &Path, mount_namespace: &Path) -> Result<Option<ExposedMount>> {
+        let mut mounts = self.list()?;
+        let Some(index) = mounts.iter().position(|m| m.is_at(target, mount_namespace)) else {
+            return Ok(None);
+        };
+        let removed = mounts.remove(index);
+        self.save(&mounts)?;
+        Ok(Some(removed))
+    }
+
+    fn save(&self, mounts: &[ExposedMount]) -> Result<()> {
+        if mounts.is_empty() {
+            return match std::fs::remove_file(&self.path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    Err(Error::RuntimeWriteError(self.path.clone(), err))
+                }
+                _ => Ok(()),
+            };
+        }
+        let Some(state_dir) = self.path.parent() else {
+            return Err(format!("Invalid exposed mounts path: {:?}", self.path).into());
+        };
+        if !state_dir.exists() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o755)
+                .create(state_dir)
+                .map_err(|err| Error::RuntimeWriteError(state_dir.to_owned(), err))?;
+            self.chown(state_dir)?;
+        }
+
+        // written aside and moved into place so that
+        // readers never see a partially written record
+        let working = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        let result = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o644)
+            .open(&working)
+            .map_err(|err| Error::RuntimeWriteError(working.clone(), err))
+            .and_then(|mut file| {
+                let data = serde_json::to_vec_pretty(mounts)
+                    .map_err(|err| Error::String(format!("Failed to write {working:?}: {err}")))?;
+                file.write_all(&data)
+                    .map_err(|err| Error::RuntimeWriteError(working.clone(), err))
+            })
+            .and_then(|_| self.chown(&working))
+            .and_then(|_| {
+                std::fs::rename(&working, &self.path)
+                    .map_err(|err| Error::RuntimeWriteError(self.path.clone(), err))
+            });
+        if result.is_err() {
+            let _ = std::fs::remove_file(&working);
+        }
+        result
+    }
+
+    fn chown(&self, path: &Path) -> Result<()> {
+        let Some(owner) = self.owner else {
+            return Ok(());
+        };
+        nix::unistd::chown(path, Some(owner), Some(nix::unistd::Gid::from_raw(0)))
+            .map_err(|err| Error::wrap_nix(err, format!("Failed to change owner of {path:?}")))
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::path::PathBuf;
+
+use rstest::rstest;
+
+use super::{ExposedMount, ExposedMounts};
+use crate::fixtures::*;
+
+#[rstest]
+fn test_exposed_mounts(tmpdir: tempfile::TempDir) {
+    let state_dir = tmpdir.path().join("exposed");
+    let mut exposed = ExposedMounts::new(&state_dir, "my-runtime");
+    // only root can hand records over to root
+    exposed.owner = None;
+
+    let namespace = PathBuf::from("mnt:[4026531841]");
+    let mount = |target: &str| ExposedMount {
+        target: PathBuf::from(target),
+        mount_namespace: namespace.clone(),
+        owner: 1000,
+        created: chrono::Utc::now(),
+    };
+
+    assert!(exposed.list().unwrap().is_empty());
+    exposed.add(mount("/tmp/one")).unwrap();
+    exposed.add(mount("/tmp/two")).unwrap();
+    exposed.add(mount("/tmp/one")).unwrap();
+    assert_eq!(
+        exposed.list().unwrap().len(),
+        2,
+        "exposing the same location twice should replace the record"
+    );
+
+    let other_namespace = PathBuf::from("mnt:[4026531842]");
+    let removed = exposed
+        .remove("/tmp/one".as_ref(), &other_namespace)
+        .unwrap();
+    assert!(removed.is_none(), "the mount namespace must also match");
+    let removed = exposed.remove("/tmp/one".as_ref(), &namespace).unwrap();
+    assert_eq!(removed.map(|m| m.target), Some(PathBuf::from("/tmp/one")));
+
+    let targets: Vec<_> = exposed
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|m| m.target)
+        .collect();
+    assert_eq!(targets, vec![PathBuf::from("/tmp/two")]);
+
+    exposed.remove("/tmp/two".as_ref(), &namespace).unwrap();
+    assert!(
+        !state_dir.join("my-runtime.json").exists(),
+        "the record should be removed along with its last mount"
+    );
+}
+
+#[rstest]
+fn test_exposed_mounts_ignore_unprivileged_records(tmpdir: tempfile::TempDir) {
+    if nix::unistd::getuid().is_root() {
+        // every record written by root is privileged
+        return;
+    }
+    let mut writer = ExposedMounts::new(tmpdir.path(), "my-runtime");
+    writer.owner = None;
+    writer
+        .add(ExposedMount {
+            target: PathBuf::from("/home"),
+            mount_namespace: PathBuf::from("mnt:[4026531841]"),
+            owner: 1000,
+            created: chrono::Utc::now(),
+        })
+        .unwrap();
+
+    let reader = ExposedMounts::new(tmpdir.path(), "my-runtime");
+    assert!(
+        reader.list().unwrap().is_empty(),
+        "records owned by a regular user must not be trusted"
+    );
+}
@@ -4,10 +4,16 @@
 
 //! Handles the setup and initialization of runtime environments
 
+#[cfg(unix)]
+mod exposed;
 #[cfg(unix)]
 pub mod overlayfs;
 #[cfg(unix)]
+pub mod rootless;
+#[cfg(unix)]
 mod startup_csh;
+#[cfg(unix)]
+mod startup_fish;
 #[cfg(windows)]
 mod startup_ps;
 #[cfg(unix)]
@@ -16,6 +22,8 @@ mod storage;
 #[cfg(windows)]
 pub mod winfsp;
 
+#[cfg(unix)]
+pub use exposed::{ExposedMount, ExposedMounts, EXPOSED_MOUNTS_DIR};
 #[cfg(unix)]
 pub use overlayfs::is_removed_entry;
 pub use storage::{
@@ -30,6 +38,7 @@ pub use storage::{
     LiveLayerFile,
     MountBackend,
     OwnedRuntime,
+    RootlessMode,
     Runtime,
     Status,
     Storage,
@@ -0,0 +1,143 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Rootless runtimes, which do not rely on the setuid spfs-enter binary.
+//!
+//! Instead of becoming the real root user, spfs-enter moves itself into
+//! a new user namespace where the invoking user is mapped to root. The
+//! mount namespace of the runtime is then owned by this user namespace,
+//! which grants enough privilege to mount the runtime filesystems
+//! without having any additional privileges on the host.
+//!
+//! Processes in a rootless runtime see themselves as root, and files
+//! that belong to any other user appear to be owned by the overflow
+//! user (typically `nobody`).
+//!
+//! Idmapped mounts are not used to correct these owners. Idmapping a
+//! mount requires privileges over the filesystem that it comes from,
+//! which an unprivileged user namespace does not have for the host's
+//! filesystems, so they would still need a privileged helper.
+
+use super::RootlessMode;
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./rootless_test.rs"]
+mod rootless_test;
+
+/// The first kernel version that allows overlayfs
+/// to be mounted from within a user namespace
+const MIN_KERNEL_VERSION: (u32, u32) = (5, 11);
+
+/// Decide if the current process should set up a rootless runtime.
+///
+/// When the mode is [`RootlessMode::Auto`] any reason that rootless
+/// runtimes cannot be used is logged and the setuid behavior is
+/// used instead. When the mode is [`RootlessMode::Always`] the reason
+/// is returned as an error.
+pub fn should_use_rootless(mode: RootlessMode) -> Result<bool> {
+    match mode {
+        RootlessMode::Never => Ok(false),
+        RootlessMode::Always => check_support().map(|_| true),
+        RootlessMode::Auto => {
+            if nix::unistd::geteuid().is_root() {
+                // spfs-enter was installed with the setuid bit
+                // or is being run by root, neither needs a namespace
+                return Ok(false);
+            }
+            match check_support() {
+                Ok(()) => Ok(true),
+                Err(err) => {
+                    tracing::debug!("falling back to setuid spfs-enter: {err}");
+                    Ok(false)
+                }
+            }
+        }
+    }
+}
+
+/// Check that rootless runtimes can be set up on this system.
+pub fn check_support() -> Result<()> {
+    if read_sysctl("kernel/unprivileged_userns_clone") == Some(0) {
+        return Err(Error::RootlessNotSupported(
+            "unprivileged user namespaces are disabled (kernel.unprivileged_userns_clone)".into(),
+        ));
+    }
+    if read_sysctl("user/max_user_namespaces") == Some(0) {
+        return Err(Error::RootlessNotSupported(
+            "user namespaces are disabled (user.max_user_namespaces)".into(),
+        ));
+    }
+    if read_sysctl("kernel/apparmor_restrict_unprivileged_userns") == Some(1) {
+        return Err(Error::RootlessNotSupported(
+            "unprivileged user namespaces are restricted by apparmor (kernel.apparmor_restrict_unprivileged_userns)".into(),
+        ));
+    }
+
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map_err(|err| Error::String(format!("Failed to read kernel version: {err}")))?;
+    match parse_kernel_version(&release) {
+        Some(version) if version >= MIN_KERNEL_VERSION => Ok(()),
+        Some((major, minor)) => Err(Error::RootlessNotSupported(format!(
+            "kernel {major}.{minor} cannot mount overlayfs in a user namespace, {}.{} or newer is required",
+            MIN_KERNEL_VERSION.0, MIN_KERNEL_VERSION.1
+        ))),
+        None => Err(Error::RootlessNotSupported(format!(
+            "unrecognized kernel version {:?}",
+            release.trim()
+        ))),
+    }
+}
+
+/// Move this process into a new user namespace where the
+/// invoking user is mapped to root.
+///
+/// Any privileges that were gained from the setuid bit are dropped
+/// first so that the new namespace is owned by the invoking user.
+/// This function will fail if called from a process with multiple threads.
+pub fn enter_user_namespace() -> Result<()> {
+    tracing::debug!("entering user namespace...");
+    let uid = nix::unistd::getuid();
+    let gid = nix::unistd::getgid();
+    if nix::unistd::geteuid() != uid {
+        nix::unistd::setuid(uid)
+            .map_err(|err| Error::wrap_nix(err, "Failed to drop setuid privileges"))?;
+    }
+
+    if let Err(err) = nix::sched::unshare(nix::sched::CloneFlags::CLONE_NEWUSER) {
+        return Err(Error::wrap_nix(err, "Failed to enter user namespace"));
+    }
+
+    // an unprivileged process must give up the ability to
+    // change its supplementary groups before mapping its group
+    write_proc_file("/proc/self/setgroups", "deny")?;
+    write_proc_file("/proc/self/uid_map", &format!("0 {uid} 1\n"))?;
+    write_proc_file("/proc/self/gid_map", &format!("0 {gid} 1\n"))?;
+    Ok(())
+}
+
+/// Parse the major and minor version from a kernel release string
+fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut parts = release.trim().splitn(3, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?;
+    let digits = minor
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(minor.len());
+    let minor = minor[..digits].parse().ok()?;
+    Some((major, minor))
+}
+
+/// Read a numeric kernel parameter, if it exists
+fn read_sysctl(name: &str) -> Option<i64> {
+    std::fs::read_to_string(std::path::Path::new("/proc/sys").join(name))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn write_proc_file(path: &str, content: &str) -> Result<()> {
+    std::fs::write(path, content).map_err(|err| Error::RuntimeWriteError(path.into(), err))
+}
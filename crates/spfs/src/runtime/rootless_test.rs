@@ -0,0 +1,27 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{parse_kernel_version, should_use_rootless};
+use crate::runtime::RootlessMode;
+
+#[rstest]
+#[case("5.11.0", Some((5, 11)))]
+#[case("5.14.0-362.8.1.el9_3.x86_64\n", Some((5, 14)))]
+#[case("3.10.0-1160.71.1.el7.x86_64", Some((3, 10)))]
+#[case("6.8-rc1", Some((6, 8)))]
+#[case("6", None)]
+#[case("not-a-kernel", None)]
+fn test_parse_kernel_version(#[case] release: &str, #[case] expected: Option<(u32, u32)>) {
+    assert_eq!(parse_kernel_version(release), expected);
+}
+
+#[rstest]
+fn test_rootless_never() {
+    assert!(
+        !should_use_rootless(RootlessMode::Never).unwrap(),
+        "should never use rootless runtimes when disabled"
+    );
+}
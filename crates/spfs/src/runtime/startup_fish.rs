@@ -0,0 +1,45 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+pub fn source<T>(tmpdir: Option<&T>) -> String
+where
+    T: AsRef<str>,
+{
+    let tmpdir_replacement = tmpdir
+        .as_ref()
+        .map(|value| {
+            format!(
+                r#"# Re-assign $TMPDIR because this value is lost when
+# exec'ing a privileged process.
+set -gx TMPDIR "{}"
+
+"#,
+                value.as_ref()
+            )
+        })
+        .unwrap_or_default();
+
+    // fish always loads the user's config.fish on startup, so
+    // unlike the other shells there is no rc file to source here
+    format!(
+        r#"#!/usr/bin/env fish
+{tmpdir_replacement}
+set startup_dir "/spfs/etc/spfs/startup.d"
+if test -d "$startup_dir"
+    for file in (/bin/ls $startup_dir | grep '\.fish$')
+        set -q SPFS_DEBUG; and echo source $startup_dir/$file 1>&2
+        source $startup_dir/$file; or true
+    end
+end
+
+if test (count $argv) -ne 0
+    exec $argv
+end
+
+if test -n "$SPFS_SHELL_MESSAGE"
+    echo "$SPFS_SHELL_MESSAGE" 1>&2
+end
+"#
+    )
+}
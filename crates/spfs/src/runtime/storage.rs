@@ -19,6 +19,7 @@ use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use futures::{Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
@@ -27,7 +28,7 @@ use tokio::io::AsyncReadExt;
 #[cfg(windows)]
 use super::startup_ps;
 #[cfg(unix)]
-use super::{startup_csh, startup_sh};
+use super::{startup_csh, startup_fish, startup_sh};
 use crate::encoding::Digest;
 use crate::env::SPFS_DIR_PREFIX;
 use crate::graph::object::Enum;
@@ -113,6 +114,15 @@ pub struct Status {
     /// An empty command signifies that this runtime is being
     /// used to launch an interactive shell environment
     pub command: Vec<String>,
+    /// Whether this runtime was started in the background
+    ///
+    /// A detached runtime is kept alive by its owner process, which
+    /// is not attached to any terminal, until that process is stopped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub detached: bool,
+    /// The file that receives the output of a detached runtime's command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<PathBuf>,
 }
 
 /// Data needed to bind mount a path onto an /spfs backend that uses
@@ -378,6 +388,12 @@ pub struct Config {
     pub sh_startup_file: PathBuf,
     /// The location of the startup script for csh-based shells
     pub csh_startup_file: PathBuf,
+    /// The location of the startup script for fish shells
+    ///
+    /// Runtimes saved before this was added load it as an empty
+    /// path, which is then replaced by the default location.
+    #[serde(default)] // for backwards-compatibility with existing runtimes
+    pub fish_startup_file: PathBuf,
     /// The location of the expect utility script used for csh-based shell environments
     /// \[DEPRECATED\] This field still exists for spk/spfs interop but is unused
     #[serde(skip_deserializing, default = "Config::default_csh_expect_file")]
@@ -403,6 +419,10 @@ pub struct Config {
     /// List of live layers to add on top of the runtime's overlayfs
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub live_layers: Vec<LiveLayer>,
+    /// Whether this runtime was set up in its own user namespace
+    /// instead of with the privileges of the setuid spfs-enter binary
+    #[serde(default)]
+    pub rootless: bool,
 }
 
 impl Default for Config {
@@ -418,6 +438,7 @@ impl Config {
     const WORK_DIR: &'static str = "work";
     const SH_STARTUP_FILE: &'static str = "startup.sh";
     const CSH_STARTUP_FILE: &'static str = ".cshrc";
+    const FISH_STARTUP_FILE: &'static str = "startup.fish";
     const PS_STARTUP_FILE: &'static str = "startup.ps1";
     const DEV_NULL: &'static str = "/dev/null";
 
@@ -437,6 +458,7 @@ impl Config {
             work_dir: root.join(Self::WORK_DIR),
             sh_startup_file: root.join(Self::SH_STARTUP_FILE),
             csh_startup_file: root.join(Self::CSH_STARTUP_FILE),
+            fish_startup_file: root.join(Self::FISH_STARTUP_FILE),
             csh_expect_file: Self::default_csh_expect_file(),
             ps_startup_file: temp_dir().join(Self::PS_STARTUP_FILE),
             runtime_dir: Some(root),
@@ -446,6 +468,15 @@ impl Config {
             secondary_repositories: Vec::new(),
             durable: false,
             live_layers: Vec::new(),
+            rootless: false,
+        }
+    }
+
+    /// Fill in the locations of any startup files that were not
+    /// saved with this config, alongside the sh startup file.
+    fn fill_missing_startup_files(&mut self) {
+        if self.fish_startup_file.as_os_str().is_empty() {
+            self.fish_startup_file = self.sh_startup_file.with_file_name(Self::FISH_STARTUP_FILE);
         }
     }
 
@@ -457,6 +488,7 @@ impl Config {
         self.work_dir = root.join(Self::WORK_DIR);
         self.sh_startup_file = root.join(Self::SH_STARTUP_FILE);
         self.csh_startup_file = root.join(Self::CSH_STARTUP_FILE);
+        self.fish_startup_file = root.join(Self::FISH_STARTUP_FILE);
         self.runtime_dir = Some(root);
     }
 }
@@ -521,6 +553,33 @@ impl MountBackend {
     }
 }
 
+/// Controls whether runtimes are set up using a user namespace
+/// rather than the privileges of the setuid spfs-enter binary
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    strum::Display,
+    strum::EnumString,
+    strum::VariantNames,
+    Serialize,
+    Deserialize,
+)]
+pub enum RootlessMode {
+    /// Always rely on the privileges of the setuid spfs-enter binary
+    #[default]
+    Never,
+    /// Use a user namespace when spfs-enter was not installed with
+    /// root privileges and the system supports it, otherwise fall back
+    /// to the privileges of the setuid binary
+    Auto,
+    /// Always use a user namespace, failing when the system does not support it
+    Always,
+}
+
 /// Stores the complete information of a single runtime.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Data {
@@ -800,6 +859,8 @@ impl Runtime {
         // namespace fields so the runtime can be rerun in future.
         self.status.owner = None;
         self.status.monitor = None;
+        self.status.detached = false;
+        self.status.log_file = None;
         self.config.mount_namespace = None;
 
         self.save_state_to_storage().await
@@ -891,7 +952,10 @@ impl Runtime {
                 }
             }
 
-            let manifest = crate::tracking::compute_manifest(tmp_dir.path()).await?;
+            let manifest = crate::tracking::ManifestBuilder::new()
+                .with_blob_hasher(self.storage.inner.digest_algorithm())
+                .compute_manifest(tmp_dir.path())
+                .await?;
 
             // This creates and saves the layer into the same repo as
             // the one the runtime is in.
@@ -986,6 +1050,12 @@ impl Runtime {
         self.status.stack.push(digest)
     }
 
+    /// The layers that were created by flattening parts of the stack
+    /// when it was too large to be mounted as-is.
+    pub fn flattened_layers(&self) -> &HashSet<Digest> {
+        &self.status.flattened_layers
+    }
+
     /// Generate a platform with all the layers from this runtime
     /// properly stacked.
     pub fn to_platform(&self) -> graph::Platform {
@@ -1013,6 +1083,12 @@ impl Runtime {
             startup_csh::source(tmpdir_value_for_child_process),
         )
         .map_err(|err| Error::RuntimeWriteError(self.config.csh_startup_file.clone(), err))?;
+        #[cfg(unix)]
+        std::fs::write(
+            &self.config.fish_startup_file,
+            startup_fish::source(tmpdir_value_for_child_process),
+        )
+        .map_err(|err| Error::RuntimeWriteError(self.config.fish_startup_file.clone(), err))?;
         #[cfg(windows)]
         std::fs::write(
             &self.config.ps_startup_file,
@@ -1224,7 +1300,8 @@ impl Storage {
             .read_to_string(&mut data)
             .await
             .map_err(|err| Error::RuntimeReadError(filename, err))?;
-        let config: Data = serde_json::from_str(&data)?;
+        let mut config: Data = serde_json::from_str(&data)?;
+        config.config.fill_missing_startup_files();
         Ok(Runtime {
             data: config,
             storage: self.clone(),
@@ -1486,6 +1563,46 @@ impl Storage {
                 }),
         )
     }
+    /// Iterate through all currently stored durable runtimes
+    pub async fn iter_durable_runtimes(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = Result<Runtime>> + Send>> {
+        Box::pin(
+            self.iter_runtimes()
+                .await
+                .try_filter(|runtime| futures::future::ready(runtime.is_durable())),
+        )
+    }
+
+    /// Return the time that the named runtime was last saved.
+    ///
+    /// Durable runtimes are saved each time they are rerun, so this
+    /// also identifies when they were last used.
+    pub async fn runtime_last_saved<S: AsRef<str>>(&self, name: S) -> Result<DateTime<Utc>> {
+        let meta_tag = runtime_tag(RuntimeDataType::Metadata, name.as_ref())?;
+        Ok(self.inner.resolve_tag(&meta_tag).await?.time)
+    }
+
+    /// Remove all durable runtimes that have not been used since the
+    /// given time, returning the names of the removed runtimes.
+    ///
+    /// Runtimes that are currently owned by a process are never removed.
+    pub async fn expire_durable_runtimes(&self, cutoff: DateTime<Utc>) -> Result<Vec<String>> {
+        let runtimes: Vec<_> = self.iter_durable_runtimes().await.try_collect().await?;
+        let mut expired = Vec::new();
+        for runtime in runtimes {
+            if runtime.status.owner.is_some() {
+                tracing::debug!("not expiring runtime in use: {}", runtime.name());
+                continue;
+            }
+            if self.runtime_last_saved(runtime.name()).await? >= cutoff {
+                continue;
+            }
+            self.remove_runtime(runtime.name()).await?;
+            expired.push(runtime.name().to_owned());
+        }
+        Ok(expired)
+    }
 }
 
 /// Specifies a type of runtime data being stored
@@ -151,6 +151,43 @@ fn test_config_serialization() {
     assert_eq!(actual, expected);
 }
 
+#[rstest]
+fn test_config_missing_fish_startup_file() {
+    let mut data = Data::new("spfs-testing");
+    data.config.set_root("/tmp/spfs-runtime/testing");
+    let mut json = serde_json::to_value(&data).expect("failed to serialize config");
+    json["config"]
+        .as_object_mut()
+        .unwrap()
+        .remove("fish_startup_file");
+    let mut actual: Data = serde_json::from_value(json).expect("failed to deserialize config data");
+    actual.config.fill_missing_startup_files();
+    assert_eq!(
+        actual.config.fish_startup_file,
+        PathBuf::from("/tmp/spfs-runtime/testing/startup.fish"),
+        "a runtime saved without a fish startup file should get one next to the others"
+    );
+}
+
+#[rstest]
+fn test_config_detached_serialization() {
+    let data = Data::new("spfs-testing");
+    let json = serde_json::to_string(&data).expect("failed to serialize config");
+    assert!(
+        !json.contains("detached"),
+        "attached runtimes should not record the detached flag: {json}"
+    );
+    let actual: Data = serde_json::from_str(&json).expect("failed to deserialize config data");
+    assert!(!actual.status.detached, "should default to attached");
+
+    let mut expected = Data::new("spfs-testing");
+    expected.status.detached = true;
+    expected.status.log_file = Some("/tmp/spfs-runtime/logs/spfs-testing.log".into());
+    let json = serde_json::to_string(&expected).expect("failed to serialize config");
+    let actual: Data = serde_json::from_str(&json).expect("failed to deserialize config data");
+    assert_eq!(actual, expected);
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_storage_create_runtime(tmpdir: tempfile::TempDir) {
@@ -620,6 +657,55 @@ async fn test_storage_iter_runtimes(tmpdir: tempfile::TempDir) {
     assert_eq!(runtimes.len(), 4);
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_storage_expire_durable_runtimes(tmpdir: tempfile::TempDir) {
+    let root = tmpdir.path().to_string_lossy().to_string();
+    let repo = crate::storage::RepositoryHandle::from(
+        crate::storage::fs::FsRepository::create(root)
+            .await
+            .unwrap(),
+    );
+    let storage = Storage::new(repo).unwrap();
+
+    let transient = storage
+        .create_named_runtime("transient", false, Vec::new())
+        .await
+        .expect("failed to create runtime");
+    let durable = storage
+        .create_named_runtime("durable", true, Vec::new())
+        .await
+        .expect("failed to create durable runtime");
+    let runtimes: Vec<_> = storage
+        .iter_durable_runtimes()
+        .await
+        .try_collect()
+        .await
+        .expect("unexpected error while listing runtimes");
+    assert_eq!(runtimes.len(), 1);
+    assert_eq!(runtimes[0].name(), durable.name());
+
+    let expired = storage
+        .expire_durable_runtimes(chrono::Utc::now() - chrono::Duration::hours(1))
+        .await
+        .expect("failed to expire runtimes");
+    assert!(expired.is_empty(), "recently used runtimes should be kept");
+
+    let expired = storage
+        .expire_durable_runtimes(chrono::Utc::now() + chrono::Duration::hours(1))
+        .await
+        .expect("failed to expire runtimes");
+    assert_eq!(expired, vec![durable.name().to_string()]);
+    storage
+        .read_runtime(transient.name())
+        .await
+        .expect("transient runtimes should never be expired");
+    storage
+        .read_runtime(durable.name())
+        .await
+        .expect_err("expired runtime should be removed");
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_runtime_reset(tmpdir: tempfile::TempDir) {
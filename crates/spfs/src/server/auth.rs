@@ -0,0 +1,163 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[cfg(test)]
+#[path = "./auth_test.rs"]
+mod auth_test;
+
+/// Decides which requests the server will handle.
+///
+/// The hook is given the parts of each grpc and payload http request
+/// that come before its body, such as the path and headers, so that it
+/// can check any credentials that the client sent along with it. The
+/// address of the client is also given when it is known.
+#[tonic::async_trait]
+pub trait AuthHook: std::fmt::Debug + Send + Sync {
+    /// Return an error that describes why the request is not allowed
+    async fn authorize(
+        &self,
+        request: &hyper::http::request::Parts,
+        peer: Option<SocketAddr>,
+    ) -> Result<(), String>;
+}
+
+/// A tower layer that rejects any requests that are not allowed
+/// by an [`AuthHook`].
+///
+/// Rejected grpc requests fail with an `UNAUTHENTICATED` status, and
+/// other http requests with a `401 Unauthorized` response.
+#[derive(Debug, Clone)]
+pub struct AuthLayer {
+    hook: Option<Arc<dyn AuthHook>>,
+}
+
+impl AuthLayer {
+    pub fn new(hook: Arc<dyn AuthHook>) -> Self {
+        Self { hook: Some(hook) }
+    }
+
+    /// A layer that passes all requests through without checking them.
+    pub fn disabled() -> Self {
+        Self { hook: None }
+    }
+}
+
+impl<S> tower::Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            hook: self.hook.clone(),
+            peer: None,
+        }
+    }
+}
+
+/// The service created by an [`AuthLayer`].
+#[derive(Debug, Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    hook: Option<Arc<dyn AuthHook>>,
+    peer: Option<SocketAddr>,
+}
+
+impl<S> AuthService<S> {
+    /// Give the address of the client that this service handles
+    /// the requests of, for servers that do not otherwise record it
+    /// in the requests.
+    pub fn with_peer(mut self, peer: SocketAddr) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+}
+
+impl<S> tonic::server::NamedService for AuthService<S>
+where
+    S: tonic::server::NamedService,
+{
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B, ResBody> tower::Service<hyper::http::Request<B>> for AuthService<S>
+where
+    S: tower::Service<hyper::http::Request<B>, Response = hyper::http::Response<ResBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::http::Request<B>) -> Self::Future {
+        let Some(hook) = self.hook.clone() else {
+            return Box::pin(self.inner.call(req));
+        };
+        // the inner service was made ready for this request, so it is
+        // taken to be called once the request has been authorized
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        // tonic records the address of each grpc client in the request
+        let peer = self.peer.or_else(|| {
+            req.extensions()
+                .get::<tonic::transport::server::TcpConnectInfo>()
+                .and_then(|info| info.remote_addr())
+        });
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            if let Err(reason) = hook.authorize(&parts, peer).await {
+                tracing::debug!(path = %parts.uri.path(), ?peer, %reason, "rejected request");
+                return Ok(rejection(&parts, reason));
+            }
+            inner
+                .call(hyper::http::Request::from_parts(parts, body))
+                .await
+        })
+    }
+}
+
+/// Create the response for a request that was not authorized.
+fn rejection<ResBody: Default>(
+    request: &hyper::http::request::Parts,
+    reason: String,
+) -> hyper::http::Response<ResBody> {
+    let mut response = hyper::http::Response::new(ResBody::default());
+    let is_grpc = request
+        .headers
+        .get(hyper::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/grpc"));
+    if !is_grpc {
+        *response.status_mut() = hyper::http::StatusCode::UNAUTHORIZED;
+        return response;
+    }
+    // grpc clients expect a successful http response,
+    // with the actual status in the headers
+    let headers = response.headers_mut();
+    headers.insert(
+        hyper::http::header::CONTENT_TYPE,
+        hyper::http::HeaderValue::from_static("application/grpc"),
+    );
+    if tonic::Status::unauthenticated(reason)
+        .add_header(headers)
+        .is_err()
+    {
+        *response.status_mut() = hyper::http::StatusCode::UNAUTHORIZED;
+    }
+    response
+}
@@ -0,0 +1,145 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::http::{header, Request, Response, StatusCode};
+use rstest::rstest;
+use tower::{Layer, Service};
+
+use super::{AuthHook, AuthLayer};
+
+/// Allows requests that carry the expected token
+#[derive(Debug)]
+struct TokenHook;
+
+#[tonic::async_trait]
+impl AuthHook for TokenHook {
+    async fn authorize(
+        &self,
+        request: &hyper::http::request::Parts,
+        _peer: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        match request.headers.get(header::AUTHORIZATION) {
+            Some(value) if value == "Bearer secret" => Ok(()),
+            _ => Err("a valid token is required".to_string()),
+        }
+    }
+}
+
+/// Responds to every request with an empty 200 response
+#[derive(Clone)]
+struct OkService;
+
+impl Service<Request<()>> for OkService {
+    type Response = Response<String>;
+    type Error = Infallible;
+    type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: Request<()>) -> Self::Future {
+        std::future::ready(Ok(Response::new("ok".to_string())))
+    }
+}
+
+async fn send(layer: &AuthLayer, req: Request<()>) -> Response<String> {
+    let mut service = layer.layer(OkService);
+    std::future::poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .unwrap();
+    service.call(req).await.unwrap()
+}
+
+#[rstest]
+#[case::http_allowed(None, Some("Bearer secret"), StatusCode::OK, "ok")]
+#[case::http_rejected(None, Some("Bearer wrong"), StatusCode::UNAUTHORIZED, "")]
+#[case::http_missing(None, None, StatusCode::UNAUTHORIZED, "")]
+#[case::grpc_allowed(Some("application/grpc"), Some("Bearer secret"), StatusCode::OK, "ok")]
+#[tokio::test]
+async fn test_auth_layer(
+    #[case] content_type: Option<&str>,
+    #[case] token: Option<&str>,
+    #[case] expected_status: StatusCode,
+    #[case] expected_body: &str,
+) {
+    let layer = AuthLayer::new(Arc::new(TokenHook));
+    let mut req = Request::builder().uri("/some/payload");
+    if let Some(content_type) = content_type {
+        req = req.header(header::CONTENT_TYPE, content_type);
+    }
+    if let Some(token) = token {
+        req = req.header(header::AUTHORIZATION, token);
+    }
+    let response = send(&layer, req.body(()).unwrap()).await;
+    assert_eq!(response.status(), expected_status);
+    assert_eq!(response.body(), expected_body);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_auth_layer_grpc_rejected() {
+    let layer = AuthLayer::new(Arc::new(TokenHook));
+    let req = Request::builder()
+        .uri("/spfs.TagService/InsertTag")
+        .header(header::CONTENT_TYPE, "application/grpc")
+        .body(())
+        .unwrap();
+    let response = send(&layer, req).await;
+    // grpc errors are reported in the headers of a successful response
+    assert_eq!(response.status(), StatusCode::OK);
+    let status = tonic::Status::from_header_map(response.headers())
+        .expect("response should have a grpc status");
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    assert_eq!(status.message(), "a valid token is required");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_auth_layer_disabled() {
+    let req = Request::builder().uri("/").body(()).unwrap();
+    let response = send(&AuthLayer::disabled(), req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Allows requests only from loopback addresses
+#[derive(Debug)]
+struct LocalHook;
+
+#[tonic::async_trait]
+impl AuthHook for LocalHook {
+    async fn authorize(
+        &self,
+        _request: &hyper::http::request::Parts,
+        peer: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        match peer {
+            Some(peer) if peer.ip().is_loopback() => Ok(()),
+            _ => Err("only local clients are allowed".to_string()),
+        }
+    }
+}
+
+#[rstest]
+#[case::local(Some("127.0.0.1:4000"), StatusCode::OK)]
+#[case::remote(Some("10.0.0.1:4000"), StatusCode::UNAUTHORIZED)]
+#[case::unknown(None, StatusCode::UNAUTHORIZED)]
+#[tokio::test]
+async fn test_auth_layer_peer(#[case] peer: Option<&str>, #[case] expected_status: StatusCode) {
+    let mut service = AuthLayer::new(Arc::new(LocalHook)).layer(OkService);
+    if let Some(peer) = peer {
+        service = service.with_peer(peer.parse().unwrap());
+    }
+    std::future::poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .unwrap();
+    let req = Request::builder().uri("/some/payload").body(()).unwrap();
+    let response = service.call(req).await.unwrap();
+    assert_eq!(response.status(), expected_status);
+}
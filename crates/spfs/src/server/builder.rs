@@ -0,0 +1,240 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::FutureExt;
+use tonic::transport::server::Routes;
+use tower::Layer;
+
+use super::{
+    AuthHook,
+    AuthLayer,
+    AuthService,
+    DatabaseService,
+    Metrics,
+    MetricsLayer,
+    MetricsService,
+    PayloadService,
+    Repository,
+    TagPolicy,
+    TagService,
+};
+use crate::proto::database_service_server::DatabaseServiceServer;
+use crate::proto::repository_server::RepositoryServer;
+use crate::proto::tag_service_server::TagServiceServer;
+use crate::{storage, Error, Result};
+
+/// Limits on the requests that are handled by a server
+#[derive(Clone, Debug, Default)]
+pub struct ServerLimits {
+    /// The largest grpc message that is accepted or sent, in bytes
+    pub max_message_size: Option<usize>,
+    /// The most grpc requests that are handled at once on each connection
+    ///
+    /// Only applied by [`ServerBuilder::serve`], as the connections
+    /// of an embedding server are not managed by spfs.
+    pub concurrency_per_connection: Option<usize>,
+    /// How long a grpc request can take before it fails
+    ///
+    /// Only applied by [`ServerBuilder::serve`], as the connections
+    /// of an embedding server are not managed by spfs.
+    pub request_timeout: Option<Duration>,
+}
+
+/// Configures the services of an spfs server for a repository.
+///
+/// An spfs server is made of grpc services that handle the repository
+/// requests, and an http service that uploads and downloads the payload
+/// data (see [`PayloadService`]). They can be served on their own with
+/// [`Self::serve`], or embedded into another server by adding the
+/// [`Self::grpc_routes`] to its router and handing its payload requests
+/// to the [`Self::http_service`]. Either way, clients must be given the
+/// `payloads_root` url that reaches the http service.
+#[derive(Clone, Debug)]
+pub struct ServerBuilder {
+    repo: Arc<storage::RepositoryHandle>,
+    payloads_root: url::Url,
+    metrics: Option<Arc<Metrics>>,
+    tag_policy: Option<Arc<dyn TagPolicy>>,
+    auth: Option<Arc<dyn AuthHook>>,
+    limits: ServerLimits,
+}
+
+impl ServerBuilder {
+    /// Serve the given repository, where `payloads_root` is the external
+    /// url that clients can use to reach the payload http service.
+    pub fn new(repo: Arc<storage::RepositoryHandle>, payloads_root: url::Url) -> Self {
+        Self {
+            repo,
+            payloads_root,
+            metrics: None,
+            tag_policy: None,
+            auth: None,
+            limits: ServerLimits::default(),
+        }
+    }
+
+    /// Record requests and payload transfers into the given metrics,
+    /// and serve them from the http service.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Check the tag changes made by clients with the given policy.
+    pub fn with_tag_policy(mut self, policy: Arc<dyn TagPolicy>) -> Self {
+        self.tag_policy = Some(policy);
+        self
+    }
+
+    /// Check every grpc and http request with the given hook.
+    pub fn with_auth(mut self, hook: Arc<dyn AuthHook>) -> Self {
+        self.auth = Some(hook);
+        self
+    }
+
+    /// Apply the given limits to the requests of clients.
+    pub fn with_limits(mut self, limits: ServerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn auth_layer(&self) -> AuthLayer {
+        match &self.auth {
+            Some(hook) => AuthLayer::new(Arc::clone(hook)),
+            None => AuthLayer::disabled(),
+        }
+    }
+
+    fn metrics_layer(&self) -> MetricsLayer {
+        match &self.metrics {
+            Some(metrics) => MetricsLayer::new(Arc::clone(metrics)),
+            None => MetricsLayer::disabled(),
+        }
+    }
+
+    /// Wrap a grpc service with the layers that apply to all requests
+    fn layered<S>(&self, service: S) -> AuthService<MetricsService<S>> {
+        self.auth_layer().layer(self.metrics_layer().layer(service))
+    }
+
+    /// The payload service, with metrics when they are enabled
+    fn payload_service(&self) -> PayloadService {
+        let service = PayloadService::new(Arc::clone(&self.repo), self.payloads_root.clone());
+        match &self.metrics {
+            Some(metrics) => service.with_metrics(Arc::clone(metrics)),
+            None => service,
+        }
+    }
+
+    /// The grpc services of the server.
+    ///
+    /// These can be added to a [`tonic::transport::Server`], or turned
+    /// into an axum router with [`Routes::into_router`] to be merged
+    /// with the routes of another service.
+    pub fn grpc_routes(&self) -> Routes {
+        let mut repository = RepositoryServer::new(Repository::new(Arc::clone(&self.repo)));
+        let mut tag_service = TagService::new(Arc::clone(&self.repo));
+        if let Some(policy) = &self.tag_policy {
+            tag_service = tag_service.with_policy(Arc::clone(policy));
+        }
+        let mut tags = TagServiceServer::new(tag_service);
+        let mut database = DatabaseServiceServer::new(DatabaseService::new(Arc::clone(&self.repo)));
+        let mut payloads = self.payload_service().into_srv();
+        if let Some(max) = self.limits.max_message_size {
+            repository = repository
+                .max_decoding_message_size(max)
+                .max_encoding_message_size(max);
+            tags = tags
+                .max_decoding_message_size(max)
+                .max_encoding_message_size(max);
+            database = database
+                .max_decoding_message_size(max)
+                .max_encoding_message_size(max);
+            payloads = payloads
+                .max_decoding_message_size(max)
+                .max_encoding_message_size(max);
+        }
+        Routes::new(self.layered(repository))
+            .add_service(self.layered(tags))
+            .add_service(self.layered(database))
+            .add_service(self.layered(payloads))
+    }
+
+    /// The http service that uploads and downloads payload data.
+    ///
+    /// A new service should be created for each connection, so that the
+    /// open connections are counted when metrics are enabled, and given
+    /// the address of the client, if known, for the auth hook. Requests
+    /// must be handed to it with the same path that they were made with,
+    /// relative to the `payloads_root` url.
+    pub fn http_service(&self, peer: Option<std::net::SocketAddr>) -> AuthService<PayloadService> {
+        let service = self
+            .auth_layer()
+            .layer(self.payload_service().for_connection());
+        match peer {
+            Some(peer) => service.with_peer(peer),
+            None => service,
+        }
+    }
+
+    /// Serve the grpc and http services on the given addresses,
+    /// until the `shutdown` future completes.
+    pub async fn serve<F>(
+        self,
+        grpc_address: std::net::SocketAddr,
+        http_address: std::net::SocketAddr,
+        shutdown: F,
+    ) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let shutdown = shutdown.shared();
+        let http_server = {
+            let builder = self.clone();
+            hyper::Server::try_bind(&http_address)
+                .map_err(|err| Error::String(format!("Failed to bind http server: {err}")))?
+                .serve(hyper::service::make_service_fn(
+                    move |conn: &hyper::server::conn::AddrStream| {
+                        let service = builder.http_service(Some(conn.remote_addr()));
+                        async move { Ok::<_, std::convert::Infallible>(service) }
+                    },
+                ))
+        };
+        let http_future = http_server.with_graceful_shutdown({
+            let shutdown = shutdown.clone();
+            async move {
+                shutdown.await;
+                tracing::info!("shutting down http server...");
+            }
+        });
+
+        let mut grpc_server = tonic::transport::Server::builder();
+        if let Some(limit) = self.limits.concurrency_per_connection {
+            grpc_server = grpc_server.concurrency_limit_per_connection(limit);
+        }
+        if let Some(timeout) = self.limits.request_timeout {
+            grpc_server = grpc_server.timeout(timeout);
+        }
+        let grpc_future = grpc_server
+            .add_routes(self.grpc_routes())
+            .serve_with_shutdown(grpc_address, async move {
+                shutdown.await;
+                tracing::info!("shutting down gRPC server...");
+            });
+        tracing::info!("listening on: {grpc_address}, {http_address}");
+
+        // TODO: stop the other server when one fails so that
+        // the process can exit
+        let (grpc_result, http_result) = tokio::join!(grpc_future, http_future);
+        if let Err(err) = &http_result {
+            tracing::error!("http server failed: {err:?}");
+        }
+        grpc_result.map_err(|err| Error::String(format!("gRPC server failed: {err:?}")))?;
+        http_result.map_err(|err| Error::String(format!("http server failed: {err:?}")))
+    }
+}
@@ -41,6 +41,22 @@ impl proto::database_service_server::DatabaseService for DatabaseService {
         }))
     }
 
+    async fn has_objects(
+        &self,
+        request: Request<proto::HasObjectsRequest>,
+    ) -> Result<Response<proto::HasObjectsResponse>, Status> {
+        let request = request.into_inner();
+        let digests = request
+            .digests
+            .into_iter()
+            .map(|digest| convert_digest(Some(digest)))
+            .collect::<crate::Result<Vec<_>>>()
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        Ok(Response::new(proto::HasObjectsResponse {
+            exists: self.repo.has_objects(&digests).await,
+        }))
+    }
+
     async fn read_object(
         &self,
         request: Request<proto::ReadObjectRequest>,
@@ -52,6 +68,23 @@ impl proto::database_service_server::DatabaseService for DatabaseService {
         Ok(Response::new(result))
     }
 
+    async fn read_objects(
+        &self,
+        request: Request<proto::ReadObjectsRequest>,
+    ) -> Result<Response<proto::ReadObjectsResponse>, Status> {
+        let request = request.into_inner();
+        let digests = proto::handle_error!(request
+            .digests
+            .into_iter()
+            .map(|digest| convert_digest(Some(digest)))
+            .collect::<crate::Result<Vec<_>>>());
+        let objects = { proto::handle_error!(self.repo.read_objects(&digests).await) };
+        let result = proto::ReadObjectsResponse::ok(proto::read_objects_response::ObjectList {
+            objects: objects.iter().map(Into::into).collect(),
+        });
+        Ok(Response::new(result))
+    }
+
     async fn find_digests(
         &self,
         request: Request<proto::FindDigestsRequest>,
@@ -102,6 +135,23 @@ impl proto::database_service_server::DatabaseService for DatabaseService {
         Ok(Response::new(result))
     }
 
+    async fn write_objects(
+        &self,
+        request: Request<proto::WriteObjectsRequest>,
+    ) -> Result<Response<proto::WriteObjectsResponse>, Status> {
+        let request = request.into_inner();
+        let objects = proto::handle_error!(request
+            .objects
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<crate::Result<Vec<crate::graph::Object>>>());
+        {
+            proto::handle_error!(self.repo.write_objects(&objects).await)
+        };
+        let result = proto::WriteObjectsResponse::ok(proto::Ok {});
+        Ok(Response::new(result))
+    }
+
     async fn remove_object(
         &self,
         request: Request<proto::RemoveObjectRequest>,
@@ -0,0 +1,277 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+#[cfg(test)]
+#[path = "./metrics_test.rs"]
+mod metrics_test;
+
+/// The grpc path of tag insert requests, timed as tag pushes
+const INSERT_TAG_PATH: &str = "/spfs.TagService/InsertTag";
+/// The grpc path of tag resolve requests
+const RESOLVE_TAG_PATH: &str = "/spfs.TagService/ResolveTag";
+
+/// The upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Collects operational metrics for a running server.
+///
+/// Metrics are rendered in the prometheus text exposition format
+/// and served by the [`super::PayloadService`] http server on
+/// the `/metrics` path when enabled.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: DashMap<String, AtomicU64>,
+    payload_bytes_uploaded: AtomicU64,
+    payload_bytes_downloaded: AtomicU64,
+    open_connections: AtomicI64,
+    tag_push_latency: Histogram,
+    tag_resolve_latency: Histogram,
+}
+
+impl Metrics {
+    /// The http path where metrics are served
+    pub const HTTP_PATH: &'static str = "/metrics";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request made to the given grpc or http path.
+    pub fn record_request(&self, path: &str) {
+        if let Some(count) = self.requests.get(path) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.requests
+            .entry(path.to_string())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the duration of a completed request to the given path.
+    ///
+    /// Only paths that have a latency histogram are tracked.
+    pub fn record_latency(&self, path: &str, duration: Duration) {
+        match path {
+            INSERT_TAG_PATH => self.tag_push_latency.observe(duration),
+            RESOLVE_TAG_PATH => self.tag_resolve_latency.observe(duration),
+            _ => {}
+        }
+    }
+
+    /// Record the number of payload bytes received from a client.
+    pub fn record_payload_upload(&self, bytes: u64) {
+        self.payload_bytes_uploaded
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Record the number of payload bytes sent to a client.
+    pub fn record_payload_download(&self, bytes: u64) {
+        self.payload_bytes_downloaded
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Mark a new connection as open until the returned guard is dropped.
+    pub fn track_connection(self: &Arc<Self>) -> ConnectionGuard {
+        self.open_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard(Arc::clone(self))
+    }
+
+    /// Render all metrics in the prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP spfs_server_requests_total Number of requests received, by path\n");
+        out.push_str("# TYPE spfs_server_requests_total counter\n");
+        let mut requests = self
+            .requests
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect::<Vec<_>>();
+        requests.sort();
+        for (path, count) in requests {
+            let _ = writeln!(out, "spfs_server_requests_total{{path=\"{path}\"}} {count}");
+        }
+        write_metric(
+            &mut out,
+            "spfs_server_payload_bytes_uploaded_total",
+            "counter",
+            "Number of payload bytes received from clients",
+            self.payload_bytes_uploaded.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "spfs_server_payload_bytes_downloaded_total",
+            "counter",
+            "Number of payload bytes sent to clients",
+            self.payload_bytes_downloaded.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut out,
+            "spfs_server_open_connections",
+            "gauge",
+            "Number of currently open http connections",
+            self.open_connections.load(Ordering::Relaxed),
+        );
+        self.tag_push_latency.render(
+            &mut out,
+            "spfs_server_tag_push_duration_seconds",
+            "Time taken to push (insert) a tag",
+        );
+        self.tag_resolve_latency.render(
+            &mut out,
+            "spfs_server_tag_resolve_duration_seconds",
+            "Time taken to resolve a tag",
+        );
+        out
+    }
+}
+
+fn write_metric(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// Keeps a connection counted as open in [`Metrics`] until dropped.
+#[derive(Debug)]
+pub struct ConnectionGuard(Arc<Metrics>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.open_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A cumulative histogram of durations, using [`LATENCY_BUCKETS`].
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(self.buckets.iter()) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(self.buckets.iter()) {
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{le=\"{bound}\"}} {}",
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "{name}_sum {sum}");
+        let _ = writeln!(out, "{name}_count {count}");
+    }
+}
+
+/// A tower layer that records grpc request counts and latencies.
+///
+/// This is intended to be added to a [`tonic::transport::Server`]
+/// that hosts the spfs services.
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+        }
+    }
+
+    /// A layer that passes all requests through without recording anything.
+    pub fn disabled() -> Self {
+        Self { metrics: None }
+    }
+}
+
+impl<S> tower::Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// The service created by a [`MetricsLayer`].
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl<S> tonic::server::NamedService for MetricsService<S>
+where
+    S: tonic::server::NamedService,
+{
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> tower::Service<hyper::http::Request<B>> for MetricsService<S>
+where
+    S: tower::Service<hyper::http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: hyper::http::Request<B>) -> Self::Future {
+        let Some(metrics) = self.metrics.clone() else {
+            return Box::pin(self.inner.call(req));
+        };
+        let path = req.uri().path().to_string();
+        metrics.record_request(&path);
+        let start = Instant::now();
+        let future = self.inner.call(req);
+        Box::pin(async move {
+            let result = future.await;
+            metrics.record_latency(&path, start.elapsed());
+            result
+        })
+    }
+}
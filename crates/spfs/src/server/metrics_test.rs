@@ -0,0 +1,41 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{Metrics, INSERT_TAG_PATH, RESOLVE_TAG_PATH};
+
+#[test]
+fn test_metrics_render() {
+    let metrics = Arc::new(Metrics::new());
+    metrics.record_request(RESOLVE_TAG_PATH);
+    metrics.record_request(RESOLVE_TAG_PATH);
+    metrics.record_request(INSERT_TAG_PATH);
+    metrics.record_latency(RESOLVE_TAG_PATH, Duration::from_millis(20));
+    metrics.record_latency(RESOLVE_TAG_PATH, Duration::from_secs(3));
+    metrics.record_payload_upload(100);
+    metrics.record_payload_download(25);
+    metrics.record_payload_download(25);
+    let guard = metrics.track_connection();
+
+    let rendered = metrics.render();
+    let lines: Vec<_> = rendered.lines().collect();
+    assert!(lines.contains(&"spfs_server_requests_total{path=\"/spfs.TagService/ResolveTag\"} 2"));
+    assert!(lines.contains(&"spfs_server_requests_total{path=\"/spfs.TagService/InsertTag\"} 1"));
+    assert!(lines.contains(&"spfs_server_payload_bytes_uploaded_total 100"));
+    assert!(lines.contains(&"spfs_server_payload_bytes_downloaded_total 50"));
+    assert!(lines.contains(&"spfs_server_open_connections 1"));
+    assert!(lines.contains(&"spfs_server_tag_resolve_duration_seconds_bucket{le=\"0.01\"} 0"));
+    assert!(lines.contains(&"spfs_server_tag_resolve_duration_seconds_bucket{le=\"0.025\"} 1"));
+    assert!(lines.contains(&"spfs_server_tag_resolve_duration_seconds_bucket{le=\"5\"} 2"));
+    assert!(lines.contains(&"spfs_server_tag_resolve_duration_seconds_bucket{le=\"+Inf\"} 2"));
+    assert!(lines.contains(&"spfs_server_tag_push_duration_seconds_count 0"));
+
+    drop(guard);
+    assert!(metrics
+        .render()
+        .lines()
+        .any(|l| l == "spfs_server_open_connections 0"));
+}
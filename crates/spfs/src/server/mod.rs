@@ -3,12 +3,21 @@
 // https://github.com/spkenv/spk
 
 //! Remote rpc server implementation of the spfs repository
+//!
+//! The [`ServerBuilder`] configures all of the services, so that
+//! they can be served on their own or embedded into another server.
+mod auth;
+mod builder;
 mod database;
+mod metrics;
 mod payload;
 mod repository;
 mod tag;
 
+pub use auth::{AuthHook, AuthLayer, AuthService};
+pub use builder::{ServerBuilder, ServerLimits};
 pub use database::DatabaseService;
+pub use metrics::{ConnectionGuard, Metrics, MetricsLayer, MetricsService};
 pub use payload::PayloadService;
 pub use repository::Repository;
-pub use tag::TagService;
+pub use tag::{TagPolicy, TagService};
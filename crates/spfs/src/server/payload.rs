@@ -7,12 +7,26 @@ use std::sync::Arc;
 
 use futures::{Stream, StreamExt};
 use prost::Message;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tonic::{Request, Response, Status};
 
+use super::{ConnectionGuard, Metrics};
 use crate::prelude::*;
 use crate::proto::payload_service_server::PayloadServiceServer;
 use crate::proto::{self, convert_digest, RpcResult};
 use crate::storage;
+use crate::storage::rpc::{ByteRange, RangeRequest};
+
+#[cfg(test)]
+#[path = "./payload_test.rs"]
+mod payload_test;
+
+/// The route that payload downloads are counted under in the metrics
+pub const PAYLOAD_DOWNLOAD_ROUTE: &str = "/payload/{digest}";
+/// The route that payload uploads are counted under in the metrics
+pub const PAYLOAD_UPLOAD_ROUTE: &str = "/payload";
+/// The route that any other payload http requests are counted under
+const PAYLOAD_OTHER_ROUTE: &str = "/payload/other";
 
 /// The payload service is both a gRPC service AND an http server
 ///
@@ -22,10 +36,15 @@ use crate::storage;
 /// at large file transfers. It is also a useful way to allow for
 /// partitioning and/or migration of the underlying file storage in
 /// the future
+///
+/// When configured with [`Metrics`], the http server also serves them
+/// on the [`Metrics::HTTP_PATH`] path.
 #[derive(Debug, Clone)]
 pub struct PayloadService {
     repo: Arc<storage::RepositoryHandle>,
     external_root: url::Url,
+    metrics: Option<Arc<Metrics>>,
+    connection: Option<Arc<ConnectionGuard>>,
 }
 
 #[tonic::async_trait]
@@ -115,9 +134,30 @@ impl hyper::service::Service<hyper::http::Request<hyper::Body>> for PayloadServi
     }
 
     fn call(&mut self, req: hyper::http::Request<hyper::Body>) -> Self::Future {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_request(request_route(&req));
+            if req.method() == hyper::Method::GET && req.uri().path() == Metrics::HTTP_PATH {
+                return Box::pin(futures::future::ready(
+                    hyper::Response::builder()
+                        .status(hyper::http::StatusCode::OK)
+                        .header(
+                            hyper::http::header::CONTENT_TYPE,
+                            "text/plain; version=0.0.4",
+                        )
+                        .body(metrics.render().into())
+                        .map_err(|e| crate::Error::String(e.to_string())),
+                ));
+            }
+        }
         match *req.method() {
-            hyper::Method::POST => Box::pin(handle_upload(self.repo.clone(), req)),
-            hyper::Method::GET => Box::pin(handle_download(self.repo.clone(), req)),
+            hyper::Method::POST => {
+                Box::pin(handle_upload(self.repo.clone(), self.metrics.clone(), req))
+            }
+            hyper::Method::GET => Box::pin(handle_download(
+                self.repo.clone(),
+                self.metrics.clone(),
+                req,
+            )),
             _ => Box::pin(futures::future::ready(
                 hyper::Response::builder()
                     .status(hyper::http::StatusCode::METHOD_NOT_ALLOWED)
@@ -128,14 +168,47 @@ impl hyper::service::Service<hyper::http::Request<hyper::Body>> for PayloadServi
     }
 }
 
+/// The route template of a payload http request, so that requests are
+/// counted together rather than once for the path of each payload.
+fn request_route<B>(req: &hyper::http::Request<B>) -> &'static str {
+    match *req.method() {
+        hyper::Method::GET if req.uri().path() == Metrics::HTTP_PATH => Metrics::HTTP_PATH,
+        hyper::Method::GET => PAYLOAD_DOWNLOAD_ROUTE,
+        hyper::Method::POST => PAYLOAD_UPLOAD_ROUTE,
+        _ => PAYLOAD_OTHER_ROUTE,
+    }
+}
+
 impl PayloadService {
     pub fn new(repo: Arc<storage::RepositoryHandle>, external_root: url::Url) -> Self {
         Self {
             repo,
             external_root,
+            metrics: None,
+            connection: None,
         }
     }
 
+    /// Record payload transfers into the given metrics, and serve
+    /// them from the http server.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Create a copy of this service to handle a new http connection.
+    ///
+    /// If metrics are enabled, the connection is counted as open
+    /// until the returned service (and all its clones) are dropped.
+    pub fn for_connection(&self) -> Self {
+        let mut service = self.clone();
+        service.connection = self
+            .metrics
+            .as_ref()
+            .map(|m| Arc::new(m.track_connection()));
+        service
+    }
+
     pub fn new_srv(
         repo: Arc<storage::RepositoryHandle>,
         external_root: url::Url,
@@ -150,6 +223,7 @@ impl PayloadService {
 
 async fn handle_upload(
     repo: Arc<storage::RepositoryHandle>,
+    metrics: Option<Arc<Metrics>>,
     mut req: hyper::http::Request<hyper::Body>,
 ) -> crate::Result<hyper::http::Response<hyper::Body>> {
     let content_type = req.headers_mut().remove(hyper::http::header::CONTENT_TYPE);
@@ -157,12 +231,12 @@ async fn handle_upload(
     match content_type.as_ref().map(|v| v.to_str()) {
         None | Some(Ok("application/octet-stream")) => {
             let reader = Box::pin(reader);
-            handle_uncompressed_upload(repo, reader).await
+            handle_uncompressed_upload(repo, metrics, reader).await
         }
         Some(Ok("application/x-bzip2")) => {
             let reader = async_compression::tokio::bufread::BzDecoder::new(reader);
             let reader = Box::pin(tokio::io::BufReader::new(reader));
-            handle_uncompressed_upload(repo, reader).await
+            handle_uncompressed_upload(repo, metrics, reader).await
         }
         _ => hyper::http::Response::builder()
             .status(hyper::http::StatusCode::UNSUPPORTED_MEDIA_TYPE)
@@ -173,6 +247,7 @@ async fn handle_upload(
 
 async fn handle_uncompressed_upload(
     repo: Arc<storage::RepositoryHandle>,
+    metrics: Option<Arc<Metrics>>,
     reader: Pin<Box<dyn crate::tracking::BlobRead>>,
 ) -> crate::Result<hyper::http::Response<hyper::Body>> {
     // Safety: it is unsafe to create a payload without its corresponding
@@ -185,6 +260,9 @@ async fn handle_uncompressed_upload(
             "An error occurred while spawning a thread for this operation: {err:?}"
         ))
     })?;
+    if let Some(metrics) = metrics {
+        metrics.record_payload_upload(size);
+    }
     let result = crate::proto::write_payload_response::UploadResponse::ok(
         crate::proto::write_payload_response::upload_response::UploadResult {
             digest: Some(digest.into()),
@@ -209,11 +287,44 @@ fn body_to_reader(body: hyper::Body) -> Pin<Box<impl crate::tracking::BlobRead>>
 
 async fn handle_download(
     repo: Arc<storage::RepositoryHandle>,
+    metrics: Option<Arc<Metrics>>,
     mut req: hyper::http::Request<hyper::Body>,
 ) -> crate::Result<hyper::http::Response<hyper::Body>> {
     let relative_path = req.uri().path().trim_start_matches('/');
     let digest = crate::encoding::Digest::parse(relative_path)?;
+    let range = req
+        .headers()
+        .get(hyper::http::header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    if let Some(range) = range {
+        // the size of the payload is taken from its blob, and the
+        // whole payload is sent when there is no blob to check
+        if let Ok(blob) = repo.read_blob(digest).await {
+            match RangeRequest::parse(&range, blob.size()) {
+                RangeRequest::Full => {}
+                RangeRequest::Partial(range) => {
+                    return handle_range_download(repo, metrics, digest, range, blob.size()).await;
+                }
+                RangeRequest::Unsatisfiable => {
+                    return hyper::Response::builder()
+                        .status(hyper::http::StatusCode::RANGE_NOT_SATISFIABLE)
+                        .header(
+                            hyper::http::header::CONTENT_RANGE,
+                            format!("bytes */{}", blob.size()),
+                        )
+                        .body(hyper::Body::empty())
+                        .map_err(|e| crate::Error::String(e.to_string()));
+                }
+            }
+        }
+    }
     let (uncompressed_reader, _) = repo.open_payload(digest).await?;
+    let count_sent = move |chunk: &std::io::Result<bytes::Bytes>| {
+        if let (Some(metrics), Ok(chunk)) = (&metrics, chunk) {
+            metrics.record_payload_download(chunk.len() as u64);
+        }
+    };
     let accepted = req
         .headers_mut()
         .get_all(hyper::http::header::ACCEPT)
@@ -227,9 +338,14 @@ async fn handle_download(
                 }
                 Ok("application/x-bzip2") => {
                     return (
-                        hyper::Body::wrap_stream(tokio_util::io::ReaderStream::new(
-                            async_compression::tokio::bufread::BzEncoder::new(uncompressed_reader),
-                        )),
+                        hyper::Body::wrap_stream(
+                            tokio_util::io::ReaderStream::new(
+                                async_compression::tokio::bufread::BzEncoder::new(
+                                    uncompressed_reader,
+                                ),
+                            )
+                            .inspect(count_sent),
+                        ),
                         accepted.to_owned(),
                     )
                 }
@@ -237,7 +353,9 @@ async fn handle_download(
             }
         }
         (
-            hyper::Body::wrap_stream(tokio_util::io::ReaderStream::new(uncompressed_reader)),
+            hyper::Body::wrap_stream(
+                tokio_util::io::ReaderStream::new(uncompressed_reader).inspect(count_sent),
+            ),
             hyper::http::HeaderValue::from_static("application/octet-stream"),
         )
     };
@@ -245,6 +363,73 @@ async fn handle_download(
     hyper::Response::builder()
         .status(hyper::http::StatusCode::OK)
         .header(hyper::http::header::CONTENT_TYPE, content_type)
+        .header(hyper::http::header::ACCEPT_RANGES, "bytes")
+        .body(body)
+        .map_err(|e| crate::Error::String(e.to_string()))
+}
+
+/// Send a single range of bytes from a payload, which is never compressed.
+async fn handle_range_download(
+    repo: Arc<storage::RepositoryHandle>,
+    metrics: Option<Arc<Metrics>>,
+    digest: crate::encoding::Digest,
+    range: ByteRange,
+    size: u64,
+) -> crate::Result<hyper::http::Response<hyper::Body>> {
+    let reader = open_payload_at(&repo, digest, range.start, size).await?;
+    let count_sent = move |chunk: &std::io::Result<bytes::Bytes>| {
+        if let (Some(metrics), Ok(chunk)) = (&metrics, chunk) {
+            metrics.record_payload_download(chunk.len() as u64);
+        }
+    };
+    let body = hyper::Body::wrap_stream(
+        tokio_util::io::ReaderStream::new(reader.take(range.size())).inspect(count_sent),
+    );
+    hyper::Response::builder()
+        .status(hyper::http::StatusCode::PARTIAL_CONTENT)
+        .header(
+            hyper::http::header::CONTENT_TYPE,
+            "application/octet-stream",
+        )
+        .header(
+            hyper::http::header::CONTENT_RANGE,
+            range.to_content_range_header(size),
+        )
+        .header(hyper::http::header::CONTENT_LENGTH, range.size())
+        .header(hyper::http::header::ACCEPT_RANGES, "bytes")
         .body(body)
         .map_err(|e| crate::Error::String(e.to_string()))
 }
+
+/// Open a payload of the given size for reading from an offset.
+async fn open_payload_at(
+    repo: &storage::RepositoryHandle,
+    digest: crate::encoding::Digest,
+    offset: u64,
+    size: u64,
+) -> crate::Result<Pin<Box<dyn BlobRead>>> {
+    let (mut reader, filename) = repo.open_payload(digest).await?;
+    if offset == 0 {
+        return Ok(reader);
+    }
+
+    // payloads that are stored in a single file on disk can be read
+    // from the offset directly, rather than reading and discarding
+    // everything that comes before it
+    let is_payload_file = tokio::fs::metadata(&filename)
+        .await
+        .is_ok_and(|meta| meta.is_file() && meta.len() == size);
+    if is_payload_file {
+        if let Ok(mut file) = tokio::fs::File::open(&filename).await {
+            if file.seek(std::io::SeekFrom::Start(offset)).await.is_ok() {
+                return Ok(Box::pin(tokio::io::BufReader::new(file)));
+            }
+        }
+    }
+    tokio::io::copy(&mut (&mut reader).take(offset), &mut tokio::io::sink())
+        .await
+        .map_err(|err| {
+            crate::Error::StorageReadError("skip to the requested range of payload", filename, err)
+        })?;
+    Ok(reader)
+}
@@ -0,0 +1,39 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{request_route, PAYLOAD_DOWNLOAD_ROUTE, PAYLOAD_UPLOAD_ROUTE};
+use crate::server::Metrics;
+
+#[rstest]
+#[case::download(
+    hyper::Method::GET,
+    "/KE2HTJVBCBB2M5UYW2G6K4WDAKP2YKXTROMXJAXBRGHGTFWVXJ2Q====",
+    PAYLOAD_DOWNLOAD_ROUTE
+)]
+#[case::other_download(
+    hyper::Method::GET,
+    "/GRLC5N2ZSDOBCN3UO5OXEDSVEAAT3AJ2JSBFWZ2D6SBHWSA4PFXA====",
+    PAYLOAD_DOWNLOAD_ROUTE
+)]
+#[case::upload(hyper::Method::POST, "/", PAYLOAD_UPLOAD_ROUTE)]
+#[case::metrics(hyper::Method::GET, Metrics::HTTP_PATH, Metrics::HTTP_PATH)]
+#[case::other(
+    hyper::Method::DELETE,
+    "/KE2HTJVBCBB2M5UYW2G6K4WDAKP2YKXTROMXJAXBRGHGTFWVXJ2Q====",
+    "/payload/other"
+)]
+fn test_payload_request_route(
+    #[case] method: hyper::Method,
+    #[case] path: &str,
+    #[case] expected: &str,
+) {
+    let req = hyper::Request::builder()
+        .method(method)
+        .uri(path)
+        .body(())
+        .unwrap();
+    assert_eq!(request_route(&req), expected);
+}
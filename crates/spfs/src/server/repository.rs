@@ -2,13 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::sync::Arc;
+
 use proto::repository_server::RepositoryServer;
 use tonic::{Request, Response, Status};
 
-use crate::proto;
+use crate::prelude::*;
+use crate::{proto, storage};
 
-#[derive(Debug, Default, Clone)]
-pub struct Repository {}
+#[derive(Debug, Clone)]
+pub struct Repository {
+    repo: Arc<storage::RepositoryHandle>,
+}
 
 #[tonic::async_trait]
 impl proto::repository_server::Repository for Repository {
@@ -19,14 +24,42 @@ impl proto::repository_server::Repository for Repository {
         let data = proto::PingResponse::default();
         Ok(Response::new(data))
     }
+
+    async fn get_capabilities(
+        &self,
+        _request: Request<proto::GetCapabilitiesRequest>,
+    ) -> std::result::Result<Response<proto::GetCapabilitiesResponse>, Status> {
+        let requirements = self.client_requirements().await?;
+        let mut data = proto::GetCapabilitiesResponse::from(&requirements);
+        data.digest_algorithm = self.repo.digest_algorithm().name().to_string();
+        Ok(Response::new(data))
+    }
 }
 
 impl Repository {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(repo: Arc<storage::RepositoryHandle>) -> Self {
+        Self { repo }
+    }
+
+    pub fn new_srv(repo: Arc<storage::RepositoryHandle>) -> RepositoryServer<Self> {
+        RepositoryServer::new(Self::new(repo))
     }
 
-    pub fn new_srv() -> RepositoryServer<Self> {
-        RepositoryServer::new(Self::new())
+    /// The requirements that clients must meet to use the served repository.
+    ///
+    /// Only filesystem repositories declare any requirements, other
+    /// repository types are checked by the clients that open them.
+    async fn client_requirements(
+        &self,
+    ) -> std::result::Result<storage::ClientRequirements, Status> {
+        let storage::RepositoryHandle::FS(repo) = &*self.repo else {
+            return Ok(Default::default());
+        };
+        let repo = repo
+            .opened()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        repo.client_requirements()
+            .map_err(|err| Status::internal(err.to_string()))
     }
 }
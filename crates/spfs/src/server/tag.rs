@@ -3,9 +3,10 @@
 // https://github.com/spkenv/spk
 
 use std::convert::TryInto;
+use std::pin::Pin;
 use std::sync::Arc;
 
-use futures::TryStreamExt;
+use futures::{Stream, TryStreamExt};
 use relative_path::RelativePath;
 use tokio_stream::StreamExt;
 use tonic::{Request, Response, Status};
@@ -14,6 +15,7 @@ use crate::prelude::*;
 use crate::proto::tag_service_server::TagServiceServer;
 use crate::proto::{self, convert_digest, RpcResult};
 use crate::storage::{self, TagNamespace};
+use crate::tracking;
 
 fn string_to_namespace(namespace: &String) -> Option<&TagNamespace> {
     if namespace.is_empty() {
@@ -23,13 +25,43 @@ fn string_to_namespace(namespace: &String) -> Option<&TagNamespace> {
     }
 }
 
+/// Decides which tag changes the server will accept from its clients.
+///
+/// The user is the one reported by the client making the request,
+/// which is not authenticated by the server.
+#[tonic::async_trait]
+pub trait TagPolicy: std::fmt::Debug + Send + Sync {
+    /// Return an error if the user may not create the given tag
+    async fn check_insert(&self, _tag: &tracking::Tag, _user: &str) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// Return an error if the user may not remove the given tag
+    async fn check_remove(&self, _tag: &tracking::TagSpec, _user: &str) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+/// Identify the user that sent a request, if the client provided one
+fn request_user<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(proto::USER_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string)
+}
+
 #[derive(Debug, Clone)]
 pub struct TagService {
     repo: Arc<storage::RepositoryHandle>,
+    policy: Option<Arc<dyn TagPolicy>>,
 }
 
 #[tonic::async_trait]
 impl proto::tag_service_server::TagService for TagService {
+    type WatchTagsStream =
+        Pin<Box<dyn Stream<Item = Result<proto::WatchTagsResponse, Status>> + Send>>;
+
     async fn ls_tags(
         &self,
         request: Request<proto::LsTagsRequest>,
@@ -120,12 +152,42 @@ impl proto::tag_service_server::TagService for TagService {
         Ok(Response::new(data))
     }
 
+    async fn read_tag_page(
+        &self,
+        request: tonic::Request<proto::ReadTagPageRequest>,
+    ) -> Result<tonic::Response<proto::ReadTagPageResponse>, tonic::Status> {
+        let request = request.into_inner();
+        let tag_spec = proto::handle_error!(request.tag_spec.parse());
+        let offset = proto::handle_error!(usize::try_from(request.offset)
+            .map_err(|err| crate::Error::String(format!("invalid page offset: {err}"))));
+        let limit = proto::handle_error!(usize::try_from(request.limit)
+            .map_err(|err| crate::Error::String(format!("invalid page limit: {err}"))));
+        let tags = proto::handle_error!(
+            self.repo
+                .read_tag_page_in_namespace(
+                    string_to_namespace(&request.namespace),
+                    &tag_spec,
+                    offset,
+                    limit
+                )
+                .await
+        );
+        let tags = tags.iter().map(Into::into).collect();
+        let data = proto::ReadTagPageResponse::ok(proto::read_tag_page_response::TagList { tags });
+        Ok(Response::new(data))
+    }
+
     async fn insert_tag(
         &self,
         request: tonic::Request<proto::InsertTagRequest>,
     ) -> Result<tonic::Response<proto::InsertTagResponse>, tonic::Status> {
+        let user = request_user(&request);
         let request = request.into_inner();
-        let tag = proto::handle_error!(request.tag.try_into());
+        let tag: tracking::Tag = proto::handle_error!(request.tag.try_into());
+        if let Some(policy) = &self.policy {
+            let user = user.unwrap_or_else(|| tag.username_without_org().to_string());
+            proto::handle_error!(policy.check_insert(&tag, &user).await);
+        }
         proto::handle_error!(
             self.repo
                 .insert_tag_in_namespace(string_to_namespace(&request.namespace), &tag)
@@ -135,12 +197,44 @@ impl proto::tag_service_server::TagService for TagService {
         Ok(Response::new(data))
     }
 
+    async fn insert_tag_if_matches(
+        &self,
+        request: tonic::Request<proto::InsertTagIfMatchesRequest>,
+    ) -> Result<tonic::Response<proto::InsertTagIfMatchesResponse>, tonic::Status> {
+        let user = request_user(&request);
+        let request = request.into_inner();
+        let tag: tracking::Tag = proto::handle_error!(request.tag.try_into());
+        let expected = match request.expected {
+            Some(expected) => Some(proto::handle_error!(convert_digest(Some(expected)))),
+            None => None,
+        };
+        if let Some(policy) = &self.policy {
+            let user = user.unwrap_or_else(|| tag.username_without_org().to_string());
+            proto::handle_error!(policy.check_insert(&tag, &user).await);
+        }
+        let tag = proto::handle_error!(
+            self.repo
+                .insert_tag_if_matches_in_namespace(
+                    string_to_namespace(&request.namespace),
+                    &tag,
+                    expected.as_ref(),
+                )
+                .await
+        );
+        let data = proto::InsertTagIfMatchesResponse::ok((&tag).into());
+        Ok(Response::new(data))
+    }
+
     async fn remove_tag_stream(
         &self,
         request: tonic::Request<proto::RemoveTagStreamRequest>,
     ) -> Result<tonic::Response<proto::RemoveTagStreamResponse>, tonic::Status> {
+        let user = request_user(&request).unwrap_or_default();
         let request = request.into_inner();
         let tag_spec = proto::handle_error!(request.tag_spec.parse());
+        if let Some(policy) = &self.policy {
+            proto::handle_error!(policy.check_remove(&tag_spec, &user).await);
+        }
         proto::handle_error!(
             self.repo
                 .remove_tag_stream_in_namespace(string_to_namespace(&request.namespace), &tag_spec)
@@ -155,8 +249,13 @@ impl proto::tag_service_server::TagService for TagService {
         &self,
         request: tonic::Request<proto::RemoveTagRequest>,
     ) -> Result<tonic::Response<proto::RemoveTagResponse>, tonic::Status> {
+        let user = request_user(&request).unwrap_or_default();
         let request = request.into_inner();
-        let tag = proto::handle_error!(request.tag.try_into());
+        let tag: tracking::Tag = proto::handle_error!(request.tag.try_into());
+        if let Some(policy) = &self.policy {
+            let tag_spec = tag.to_spec(0);
+            proto::handle_error!(policy.check_remove(&tag_spec, &user).await);
+        }
         proto::handle_error!(
             self.repo
                 .remove_tag_in_namespace(string_to_namespace(&request.namespace), &tag)
@@ -166,14 +265,41 @@ impl proto::tag_service_server::TagService for TagService {
         let data = proto::RemoveTagResponse::ok(proto::Ok {});
         Ok(Response::new(data))
     }
+
+    async fn watch_tags(
+        &self,
+        request: tonic::Request<proto::WatchTagsRequest>,
+    ) -> Result<tonic::Response<Self::WatchTagsStream>, tonic::Status> {
+        let request = request.into_inner();
+        let stream = self
+            .repo
+            .watch_tags_in_namespace(string_to_namespace(&request.namespace), &request.pattern)
+            .map(|result| {
+                proto::WatchTagsResponse::from_result(result.map(|t| proto::Tag::from(&t)))
+            })
+            .map(Ok);
+        let stream: Self::WatchTagsStream = Box::pin(stream);
+        Ok(Response::new(stream))
+    }
 }
 
 impl TagService {
     pub fn new(repo: Arc<storage::RepositoryHandle>) -> Self {
-        Self { repo }
+        Self { repo, policy: None }
+    }
+
+    /// Check all tag changes against the given policy before
+    /// applying them to the repository.
+    pub fn with_policy(mut self, policy: Arc<dyn TagPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
     }
 
     pub fn new_srv(repo: Arc<storage::RepositoryHandle>) -> TagServiceServer<Self> {
-        TagServiceServer::new(Self::new(repo))
+        Self::new(repo).into_srv()
+    }
+
+    pub fn into_srv(self) -> TagServiceServer<Self> {
+        TagServiceServer::new(self)
     }
 }
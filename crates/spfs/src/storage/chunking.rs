@@ -0,0 +1,172 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Content-defined chunking of large payloads.
+//!
+//! Large payloads, such as archives that change only slightly from
+//! one version to the next, can be split into chunks that are each
+//! stored as their own blob. Chunk boundaries are chosen based on the
+//! content itself using a gear rolling hash, so an insertion or removal
+//! in one part of a file only changes the chunks around it and the
+//! remaining chunks are shared with previous versions, both in storage
+//! and when syncing between repositories.
+//!
+//! Chunking is enabled in the spfs config with `storage.chunk_threshold`.
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::Repository;
+use crate::{encoding, graph, Error, Result};
+
+#[cfg(test)]
+#[path = "./chunking_test.rs"]
+mod chunking_test;
+
+/// No chunk is made smaller than this, except for the last one
+pub const MIN_CHUNK_SIZE: usize = 256 * 1024;
+
+/// No chunk is made larger than this, and payloads
+/// that are not larger than this are never chunked
+pub const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// The number of high bits in the rolling hash that must be
+/// zero to mark a boundary, which gives an average chunk size
+/// of about 1MiB past the minimum
+const BOUNDARY_BITS: u32 = 20;
+
+/// The random values used to roll the hash for each possible byte.
+///
+/// These values are generated deterministically with splitmix64 and
+/// must never change, otherwise newly stored chunks will no longer
+/// line up with existing ones.
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < table.len() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// True if a payload of the given size should be chunked when
+/// it is committed, as configured by `storage.chunk_threshold`.
+pub fn should_chunk(size: u64) -> bool {
+    let threshold = crate::get_config()
+        .ok()
+        .and_then(|config| config.storage.chunk_threshold);
+    match threshold {
+        Some(threshold) => size >= threshold && size > MAX_CHUNK_SIZE as u64,
+        None => false,
+    }
+}
+
+/// True if any payloads are chunked when they are committed.
+pub fn is_enabled() -> bool {
+    should_chunk(u64::MAX)
+}
+
+/// Find the length of the first chunk in the given data.
+///
+/// Only the first [`MAX_CHUNK_SIZE`] bytes of data are considered,
+/// and the whole of the data is returned as one chunk if it is
+/// no larger than [`MIN_CHUNK_SIZE`] or no boundary can be found.
+pub fn find_boundary(data: &[u8]) -> usize {
+    if data.len() <= MIN_CHUNK_SIZE {
+        return data.len();
+    }
+    let end = data.len().min(MAX_CHUNK_SIZE);
+    let mut hash = 0u64;
+    for (i, byte) in data[MIN_CHUNK_SIZE..end].iter().enumerate() {
+        hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+        if hash >> (u64::BITS - BOUNDARY_BITS) == 0 {
+            return MIN_CHUNK_SIZE + i + 1;
+        }
+    }
+    end
+}
+
+/// Splits the data from a reader into content-defined chunks.
+pub struct Chunker<R> {
+    reader: R,
+    buf: Vec<u8>,
+    eof: bool,
+}
+
+impl<R> Chunker<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::with_capacity(MAX_CHUNK_SIZE),
+            eof: false,
+        }
+    }
+
+    /// Read the next chunk of data, or None once all data has been read.
+    pub async fn next_chunk(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        while !self.eof && self.buf.len() < MAX_CHUNK_SIZE {
+            let start = self.buf.len();
+            self.buf.resize(MAX_CHUNK_SIZE, 0);
+            let read = self.reader.read(&mut self.buf[start..]).await;
+            let count = match read {
+                Ok(count) => count,
+                Err(err) => {
+                    self.buf.truncate(start);
+                    return Err(err);
+                }
+            };
+            self.buf.truncate(start + count);
+            self.eof = count == 0;
+        }
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let rest = self.buf.split_off(find_boundary(&self.buf));
+        Ok(Some(std::mem::replace(&mut self.buf, rest)))
+    }
+}
+
+/// Split a stored payload into content-defined chunks, writing each
+/// chunk as its own blob along with the list of all chunks.
+///
+/// Returns the blob that should be saved in place of the original,
+/// which refers to the new chunk list. The original payload is left
+/// in place so that it can be removed once the returned blob is saved.
+pub async fn chunk_payload<R>(repo: &R, digest: encoding::Digest) -> Result<graph::Blob>
+where
+    R: Repository + ?Sized,
+{
+    let (reader, path) = repo.open_payload(digest).await?;
+    let mut chunker = Chunker::new(reader);
+    let mut chunks = Vec::new();
+    let mut size = 0;
+    while let Some(chunk) = chunker
+        .next_chunk()
+        .await
+        .map_err(|err| Error::StorageReadError("read on payload to chunk", path.clone(), err))?
+    {
+        let reader = Box::pin(std::io::Cursor::new(chunk));
+        // Safety: a blob is written for each chunk immediately after
+        let (chunk_digest, chunk_size) = unsafe { repo.write_data(reader).await? };
+        repo.write_object(&graph::Blob::new(chunk_digest, chunk_size))
+            .await?;
+        chunks.push((chunk_digest, chunk_size));
+        size += chunk_size;
+    }
+    let chunk_list = graph::ChunkList::builder().with_chunks(chunks).build();
+    repo.write_object(&chunk_list).await?;
+    Ok(graph::Blob::builder()
+        .with_payload(digest)
+        .with_size(size)
+        .with_chunks(chunk_list.digest()?)
+        .build())
+}
@@ -0,0 +1,120 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use tokio::io::AsyncReadExt;
+
+use super::{chunk_payload, find_boundary, Chunker, MAX_CHUNK_SIZE, MIN_CHUNK_SIZE};
+use crate::fixtures::*;
+use crate::prelude::*;
+
+/// Generate some data that is random enough to find chunk boundaries in
+fn random_data(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+async fn read_chunks(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut chunker = Chunker::new(data);
+    let mut chunks = Vec::new();
+    while let Some(chunk) = chunker.next_chunk().await.unwrap() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+#[rstest]
+fn test_find_boundary_limits() {
+    let data = random_data(MIN_CHUNK_SIZE, 1);
+    assert_eq!(
+        find_boundary(&data),
+        data.len(),
+        "data no larger than the minimum should be one chunk"
+    );
+    let data = vec![0; MAX_CHUNK_SIZE * 2];
+    assert_eq!(
+        find_boundary(&data),
+        MAX_CHUNK_SIZE,
+        "chunks should be cut at the maximum size when no boundary is found"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chunker_sizes() {
+    let data = random_data(MAX_CHUNK_SIZE * 4, 2);
+    let chunks = read_chunks(&data).await;
+    assert!(chunks.len() > 1, "should find at least one boundary");
+    let (last, rest) = chunks.split_last().unwrap();
+    for chunk in rest {
+        assert!(chunk.len() >= MIN_CHUNK_SIZE && chunk.len() <= MAX_CHUNK_SIZE);
+    }
+    assert!(!last.is_empty() && last.len() <= MAX_CHUNK_SIZE);
+    assert_eq!(chunks.concat(), data, "chunks should reassemble the data");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chunker_shares_unchanged_chunks() {
+    let original = random_data(MAX_CHUNK_SIZE * 4, 3);
+    let mut changed = original.clone();
+    // an insertion near the start should only affect the chunks around it
+    changed.splice(1000..1000, b"some inserted data".iter().copied());
+
+    let original_chunks = read_chunks(&original).await;
+    let changed_chunks = read_chunks(&changed).await;
+    let shared = changed_chunks
+        .iter()
+        .filter(|c| original_chunks.contains(c))
+        .count();
+    assert!(
+        shared + 2 >= original_chunks.len(),
+        "all but the chunks around the change should be shared, got {shared} of {}",
+        original_chunks.len()
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chunked_payload_read_and_sync() {
+    init_logging();
+    let dest = tmprepo("fs").await;
+    let tmprepo = tmprepo("fs").await;
+    let data = random_data(MAX_CHUNK_SIZE * 3, 4);
+    let reader = Box::pin(std::io::Cursor::new(data.clone()));
+    // Safety: the chunked blob is written below
+    let (digest, _) = unsafe { tmprepo.write_data(reader).await.unwrap() };
+
+    let blob = chunk_payload(&*tmprepo, digest).await.unwrap();
+    assert!(blob.chunks().is_some(), "should create a chunk list");
+    tmprepo.write_blob(blob).await.unwrap();
+    tmprepo.remove_payload(digest).await.unwrap();
+    assert!(
+        tmprepo.has_payload(digest).await,
+        "chunked blobs should still report having a payload"
+    );
+
+    let (mut payload, _) = tmprepo.open_payload(digest).await.unwrap();
+    let mut actual = Vec::new();
+    payload.read_to_end(&mut actual).await.unwrap();
+    assert!(actual == data, "should reassemble the original payload");
+
+    crate::Syncer::new(&tmprepo, &dest)
+        .sync_digest(digest)
+        .await
+        .unwrap();
+    let synced = dest.read_blob(digest).await.unwrap();
+    assert!(synced.chunks().is_some(), "should sync the chunked blob");
+    let (mut payload, _) = dest.open_payload(digest).await.unwrap();
+    let mut actual = Vec::new();
+    payload.read_to_end(&mut actual).await.unwrap();
+    assert!(actual == data, "should reassemble the synced payload");
+}
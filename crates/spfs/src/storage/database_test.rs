@@ -34,3 +34,42 @@ async fn test_object_existence(
     let actual = tmprepo.has_object(digest).await;
     assert!(!actual, "object should not exist after being removed");
 }
+
+#[rstest]
+#[case::fs(tmprepo("fs"))]
+#[case::tar(tmprepo("tar"))]
+#[cfg_attr(feature = "server", case::rpc(tmprepo("rpc")))]
+#[tokio::test]
+async fn test_objects_batch(
+    #[case]
+    #[future]
+    tmprepo: TempRepo,
+) {
+    let tmprepo = tmprepo.await;
+    let objects = (0..5)
+        .map(|size| graph::Object::from(graph::Blob::new(random_digest(), size)))
+        .collect::<Vec<_>>();
+    tmprepo
+        .write_objects(&objects)
+        .await
+        .expect("failed to write objects");
+
+    let digests = objects
+        .iter()
+        .rev()
+        .map(|obj| obj.digest().unwrap())
+        .collect::<Vec<_>>();
+    let read = tmprepo.read_objects(&digests).await.unwrap();
+    assert_eq!(
+        read.iter()
+            .map(|obj| obj.digest().unwrap())
+            .collect::<Vec<_>>(),
+        digests,
+        "objects should be read in the order requested"
+    );
+
+    tmprepo
+        .read_objects(&[digests[0], random_digest()])
+        .await
+        .expect_err("reading an unknown object should fail");
+}
@@ -90,9 +90,63 @@ pub enum OpenRepositoryError {
         #[from]
         source: tonic::transport::Error,
     },
+    #[error(
+        "Repository {address} digests payloads with {advertised}, but {configured} was configured"
+    )]
+    #[diagnostic(
+        code("spfs::storage::digest_algorithm_mismatch"),
+        help(
+            "Remove the digest_algorithm setting of this remote, or change it to match the server"
+        )
+    )]
+    DigestAlgorithmMismatch {
+        address: String,
+        configured: crate::encoding::DigestAlgorithm,
+        advertised: crate::encoding::DigestAlgorithm,
+    },
+    #[error("Invalid request headers for repository {address}: {reason}")]
+    InvalidRequestHeaders { address: String, reason: String },
     #[error("Pinned repository is read only")]
     RepositoryIsPinned,
 
+    #[error("Failed to read the repository digest algorithm: {path:?}")]
+    #[diagnostic(help(
+        "The file should contain the name of a supported digest algorithm, eg: sha256 or blake3"
+    ))]
+    InvalidDigestAlgorithm {
+        path: std::path::PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("Repository {address} requires spfs {min_client_version} or newer [current: {client_version}]")]
+    #[diagnostic(
+        code("spfs::storage::client_too_old"),
+        help("Upgrade spk/spfs to at least {min_client_version} to use this repository, or contact your system administrator")
+    )]
+    ClientVersionTooOld {
+        address: String,
+        client_version: semver::Version,
+        min_client_version: semver::Version,
+    },
+    #[error("Repository {address} requires features that this version of spfs does not support: {}", features.join(", "))]
+    #[diagnostic(
+        code("spfs::storage::unsupported_features"),
+        help("Upgrade spk/spfs to a version that supports these features, or contact your system administrator")
+    )]
+    UnsupportedFeatures {
+        address: String,
+        features: Vec<String>,
+    },
+    #[error("Failed to read the repository client requirements: {path:?}")]
+    InvalidClientRequirements {
+        path: std::path::PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("Failed to read the capabilities of the remote repository")]
+    FailedToReadCapabilities {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
     #[error("Failed to set tag namespace '{tag_namespace}'")]
     FailedToSetTagNamespace {
         tag_namespace: TagNamespaceBuf,
@@ -161,6 +161,10 @@ impl graph::Database for FallbackProxy {
         Ok(())
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        self.primary.write_objects(objects).await
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         self.primary.remove_object(digest).await?;
         Ok(())
@@ -180,6 +184,10 @@ impl graph::Database for FallbackProxy {
 
 #[async_trait::async_trait]
 impl PayloadStorage for FallbackProxy {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        self.primary.digest_algorithm()
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         if self.primary.has_payload(digest).await {
             return true;
@@ -322,6 +330,18 @@ impl TagStorage for FallbackProxy {
         self.primary.read_tag_in_namespace(namespace, tag).await
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        self.primary
+            .read_tag_page_in_namespace(namespace, tag, offset, limit)
+            .await
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -331,6 +351,17 @@ impl TagStorage for FallbackProxy {
         Ok(())
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        self.primary
+            .insert_tag_if_matches_in_namespace(namespace, tag, expected)
+            .await
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -350,6 +381,14 @@ impl TagStorage for FallbackProxy {
         self.primary.remove_tag_in_namespace(namespace, tag).await?;
         Ok(())
     }
+
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        self.primary.watch_tags_in_namespace(namespace, pattern)
+    }
 }
 
 impl TagStorageMut for FallbackProxy {
@@ -4,17 +4,21 @@
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
 use std::pin::Pin;
 
 use chrono::{DateTime, Utc};
 use close_err::Closable;
 use encoding::prelude::*;
-use futures::{Stream, StreamExt, TryFutureExt};
+use futures::{Stream, StreamExt, TryFutureExt, TryStreamExt};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::graph::{DatabaseView, Object, ObjectProto};
 use crate::{encoding, graph, Error, Result};
 
+/// The number of staged object files that are synced at once
+const STAGED_OBJECT_SYNC_CONCURRENCY: usize = 32;
+
 #[async_trait::async_trait]
 impl DatabaseView for super::FsRepository {
     async fn has_object(&self, digest: encoding::Digest) -> bool {
@@ -28,6 +32,10 @@ impl DatabaseView for super::FsRepository {
         self.opened().await?.read_object(digest).await
     }
 
+    async fn read_objects(&self, digests: &[encoding::Digest]) -> Result<Vec<graph::Object>> {
+        self.opened().await?.read_objects(digests).await
+    }
+
     fn find_digests(
         &self,
         search_criteria: graph::DigestSearchCriteria,
@@ -60,6 +68,10 @@ impl graph::Database for super::FsRepository {
         self.opened().await?.write_object(obj).await
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        self.opened().await?.write_objects(objects).await
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> crate::Result<()> {
         self.opened().await?.remove_object(digest).await
     }
@@ -128,17 +140,136 @@ impl DatabaseView for super::OpenFsRepository {
 #[async_trait::async_trait]
 impl graph::Database for super::OpenFsRepository {
     async fn write_object<T: ObjectProto>(&self, obj: &graph::FlatObject<T>) -> Result<()> {
+        match self.stage_object(obj).await? {
+            Some(staged) => self.commit_staged_object(staged).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        let mut staged = Vec::with_capacity(objects.len());
+        for obj in objects {
+            match self.stage_object(obj).await {
+                Ok(Some(object)) => staged.push(object),
+                Ok(None) => continue,
+                Err(err) => {
+                    discard_staged_objects(staged).await;
+                    return Err(err);
+                }
+            }
+        }
+        if staged.is_empty() {
+            return Ok(());
+        }
+        // syncing the batch concurrently is much cheaper than
+        // syncing each of the small object files in turn
+        if let Err(err) = sync_staged_objects(&staged).await {
+            discard_staged_objects(staged).await;
+            return Err(err);
+        }
+        let mut staged = staged.into_iter();
+        while let Some(object) = staged.next() {
+            if let Err(err) = self.commit_staged_object(object).await {
+                discard_staged_objects(staged.collect()).await;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_object(&self, digest: encoding::Digest) -> crate::Result<()> {
+        let filepath = self.objects.build_digest_path(&digest);
+
+        // this might fail but we don't consider that fatal just yet
+        #[cfg(unix)]
+        let _ = tokio::fs::set_permissions(&filepath, std::fs::Permissions::from_mode(0o777)).await;
+
+        if let Err(err) = tokio::fs::remove_file(&filepath).await {
+            return match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(Error::StorageWriteError(
+                    "remove_file on object file in remove_object",
+                    filepath,
+                    err,
+                )),
+            };
+        }
+        tracing::trace!(%digest, "removed object from db");
+        Ok(())
+    }
+
+    async fn remove_object_if_older_than(
+        &self,
+        older_than: DateTime<Utc>,
+        digest: encoding::Digest,
+    ) -> crate::Result<bool> {
+        let filepath = self.objects.build_digest_path(&digest);
+
+        // this might fail but we don't consider that fatal just yet
+        #[cfg(unix)]
+        let _ = tokio::fs::set_permissions(&filepath, std::fs::Permissions::from_mode(0o777)).await;
+
+        let metadata = tokio::fs::symlink_metadata(&filepath)
+            .await
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => Error::UnknownObject(digest),
+                _ => Error::StorageReadError(
+                    "symlink_metadata on digest path",
+                    filepath.clone(),
+                    err,
+                ),
+            })?;
+
+        let mtime = metadata.modified().map_err(|err| {
+            Error::StorageReadError(
+                "modified on symlink metadata of digest path",
+                filepath.clone(),
+                err,
+            )
+        })?;
+
+        if DateTime::<Utc>::from(mtime) >= older_than {
+            return Ok(false);
+        }
+
+        if let Err(err) = tokio::fs::remove_file(&filepath).await {
+            return match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(true),
+                _ => Err(Error::StorageWriteError(
+                    "remove_file on object file in remove_object_if_older_than",
+                    filepath,
+                    err,
+                )),
+            };
+        }
+        Ok(true)
+    }
+}
+
+/// An object that has been written to a working file but not yet moved
+/// into its final location in the database.
+struct StagedObject {
+    working_file: PathBuf,
+    filepath: PathBuf,
+}
+
+impl super::OpenFsRepository {
+    /// Write an object to a new working file, so that other processes
+    /// do not try to read the incomplete object from the database.
+    ///
+    /// Returns None if the object already exists in the database.
+    async fn stage_object<T: ObjectProto>(
+        &self,
+        obj: &graph::FlatObject<T>,
+    ) -> Result<Option<StagedObject>> {
         let digest = obj.digest()?;
         let filepath = self.objects.build_digest_path(&digest);
         if filepath.exists() {
             tracing::trace!(%digest, kind=%std::any::type_name::<T>(), "object already exists");
-            return Ok(());
+            return Ok(None);
         }
         tracing::trace!(%digest, kind=%std::any::type_name::<T>(), "writing object to db");
 
-        // we need to use a temporary file here, so that
-        // other processes don't try to read our incomplete
-        // object from the database
         let uuid = uuid::Uuid::new_v4().to_string();
         let working_file = self.objects.workdir().join(uuid);
         self.objects.ensure_base_dir(&working_file)?;
@@ -194,6 +325,18 @@ impl graph::Database for super::OpenFsRepository {
                 ));
             }
         }
+        Ok(Some(StagedObject {
+            working_file,
+            filepath,
+        }))
+    }
+
+    /// Move a staged object into its final location in the database.
+    async fn commit_staged_object(&self, staged: StagedObject) -> Result<()> {
+        let StagedObject {
+            working_file,
+            filepath,
+        } = staged;
         self.objects.ensure_base_dir(&filepath)?;
         match tokio::fs::rename(&working_file, &filepath).await {
             Ok(_) => Ok(()),
@@ -207,72 +350,40 @@ impl graph::Database for super::OpenFsRepository {
             }
         }
     }
+}
 
-    async fn remove_object(&self, digest: encoding::Digest) -> crate::Result<()> {
-        let filepath = self.objects.build_digest_path(&digest);
-
-        // this might fail but we don't consider that fatal just yet
-        #[cfg(unix)]
-        let _ = tokio::fs::set_permissions(&filepath, std::fs::Permissions::from_mode(0o777)).await;
-
-        if let Err(err) = tokio::fs::remove_file(&filepath).await {
-            return match err.kind() {
-                std::io::ErrorKind::NotFound => Ok(()),
-                _ => Err(Error::StorageWriteError(
-                    "remove_file on object file in remove_object",
-                    filepath,
-                    err,
-                )),
-            };
-        }
-        tracing::trace!(%digest, "removed object from db");
-        Ok(())
-    }
-
-    async fn remove_object_if_older_than(
-        &self,
-        older_than: DateTime<Utc>,
-        digest: encoding::Digest,
-    ) -> crate::Result<bool> {
-        let filepath = self.objects.build_digest_path(&digest);
-
-        // this might fail but we don't consider that fatal just yet
-        #[cfg(unix)]
-        let _ = tokio::fs::set_permissions(&filepath, std::fs::Permissions::from_mode(0o777)).await;
-
-        let metadata = tokio::fs::symlink_metadata(&filepath)
-            .await
-            .map_err(|err| match err.kind() {
-                std::io::ErrorKind::NotFound => Error::UnknownObject(digest),
-                _ => Error::StorageReadError(
-                    "symlink_metadata on digest path",
-                    filepath.clone(),
+/// Flush the given staged object files to disk, without waiting
+/// on any other writes that are pending for the same filesystem.
+async fn sync_staged_objects(staged: &[StagedObject]) -> Result<()> {
+    futures::stream::iter(staged)
+        .map(|object| async move {
+            let file = tokio::fs::OpenOptions::new()
+                .write(true)
+                .open(&object.working_file)
+                .await
+                .map_err(|err| {
+                    Error::StorageWriteError(
+                        "open on object file for sync",
+                        object.working_file.clone(),
+                        err,
+                    )
+                })?;
+            file.sync_data().await.map_err(|err| {
+                Error::StorageWriteError(
+                    "sync_data on object file",
+                    object.working_file.clone(),
                     err,
-                ),
-            })?;
-
-        let mtime = metadata.modified().map_err(|err| {
-            Error::StorageReadError(
-                "modified on symlink metadata of digest path",
-                filepath.clone(),
-                err,
-            )
-        })?;
-
-        if DateTime::<Utc>::from(mtime) >= older_than {
-            return Ok(false);
-        }
+                )
+            })
+        })
+        .buffer_unordered(STAGED_OBJECT_SYNC_CONCURRENCY)
+        .try_collect::<()>()
+        .await
+}
 
-        if let Err(err) = tokio::fs::remove_file(&filepath).await {
-            return match err.kind() {
-                std::io::ErrorKind::NotFound => Ok(true),
-                _ => Err(Error::StorageWriteError(
-                    "remove_file on object file in remove_object_if_older_than",
-                    filepath,
-                    err,
-                )),
-            };
-        }
-        Ok(true)
+/// Remove the working files of objects that will not be committed.
+async fn discard_staged_objects(staged: Vec<StagedObject>) {
+    for object in staged {
+        let _ = tokio::fs::remove_file(&object.working_file).await;
     }
 }
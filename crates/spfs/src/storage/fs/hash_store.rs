@@ -42,6 +42,8 @@ pub struct FsHashStore {
     pub directory_permissions: u32,
     /// permissions used when creating new files
     pub file_permissions: u32,
+    /// algorithm used to digest the data written to this store
+    pub digest_algorithm: encoding::DigestAlgorithm,
 }
 
 impl FsHashStore {
@@ -60,6 +62,7 @@ impl FsHashStore {
             root: root.as_ref().to_path_buf(),
             directory_permissions: 0o777, // this is a shared store for all users
             file_permissions: 0o666,      // read+write is required to make hard links
+            digest_algorithm: encoding::DigestAlgorithm::default(),
         }
     }
 
@@ -239,7 +242,7 @@ impl FsHashStore {
                     )
                 })?,
         );
-        let mut hasher = encoding::Hasher::with_target(&mut writer);
+        let mut hasher = encoding::Hasher::with_algorithm(self.digest_algorithm, &mut writer);
         let copied = match tokio::io::copy(&mut reader, &mut hasher).await {
             Err(err) => {
                 let _ = tokio::fs::remove_file(&working_file).await;
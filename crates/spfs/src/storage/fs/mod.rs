@@ -8,16 +8,19 @@ mod database;
 mod hash_store;
 mod manifest_render_path;
 mod payloads;
+mod render_catalog;
 mod render_summary;
 mod renderer;
 mod repository;
 mod tag;
+mod tag_watch;
 
 pub mod migrations;
 mod render_reporter;
 
 pub use hash_store::FsHashStore;
 pub use manifest_render_path::ManifestRenderPath;
+pub use render_catalog::{RenderCatalog, RenderRecord, RENDER_CATALOG_FILENAME};
 pub use render_reporter::{
     ConsoleRenderReporter,
     MultiReporter,
@@ -32,11 +35,13 @@ pub use renderer::{
     DEFAULT_MAX_CONCURRENT_BRANCHES,
 };
 pub use repository::{
+    read_client_requirements,
     read_last_migration_version,
     Config,
     FsRepository,
     OpenFsRepository,
     Params,
     RenderStore,
+    CLIENT_REQUIREMENTS_FILE,
     DURABLE_EDITS_DIR,
 };
@@ -2,11 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::VecDeque;
 use std::io::ErrorKind;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::task::{ready as ready_poll, Poll};
 
 use futures::future::ready;
 use futures::{Stream, StreamExt, TryFutureExt};
+use tokio::io::{AsyncRead, ReadBuf};
 
 use super::{FsRepository, OpenFsRepository};
 use crate::storage::prelude::*;
@@ -15,6 +19,10 @@ use crate::{encoding, graph, Error, Result};
 
 #[async_trait::async_trait]
 impl crate::storage::PayloadStorage for FsRepository {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        FsRepository::digest_algorithm(self)
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         let Ok(opened) = self.opened().await else {
             return false;
@@ -53,9 +61,17 @@ impl crate::storage::PayloadStorage for FsRepository {
 
 #[async_trait::async_trait]
 impl crate::storage::PayloadStorage for OpenFsRepository {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        self.payloads.digest_algorithm
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         let path = self.payloads.build_digest_path(&digest);
-        tokio::fs::symlink_metadata(path).await.is_ok()
+        if tokio::fs::symlink_metadata(path).await.is_ok() {
+            return true;
+        }
+        // chunked blobs have no payload file of their own
+        matches!(self.read_blob(digest).await, Ok(blob) if blob.chunks().is_some())
     }
 
     fn iter_payload_digests(&self) -> Pin<Box<dyn Stream<Item = Result<encoding::Digest>> + Send>> {
@@ -81,7 +97,13 @@ impl crate::storage::PayloadStorage for OpenFsRepository {
                     // Return an error specific to this situation, whether the
                     // blob is really unknown or just the payload is missing.
                     match self.read_blob(digest).await {
-                        Ok(blob) => Err(Error::ObjectMissingPayload(blob.into(), digest)),
+                        Ok(blob) => match blob.chunks() {
+                            Some(chunks) => {
+                                let reader = self.open_chunks(*chunks).await?;
+                                Ok((Box::pin(tokio::io::BufReader::new(reader)), path))
+                            }
+                            None => Err(Error::ObjectMissingPayload(blob.into(), digest)),
+                        },
                         Err(
                             err @ Error::NotCorrectKind {
                                 desired: graph::ObjectKind::Blob,
@@ -111,3 +133,63 @@ impl crate::storage::PayloadStorage for OpenFsRepository {
         }
     }
 }
+
+impl OpenFsRepository {
+    /// Open a reader over each of the chunks in a chunk list, in order.
+    async fn open_chunks(&self, digest: encoding::Digest) -> Result<ChunkedPayloadReader> {
+        let chunk_list =
+            self.read_object(digest)
+                .await?
+                .into_chunk_list()
+                .ok_or(Error::NotCorrectKind {
+                    desired: graph::ObjectKind::ChunkList,
+                    digest,
+                })?;
+        let paths = chunk_list
+            .iter_chunks()
+            .map(|chunk| self.payloads.build_digest_path(chunk.payload()))
+            .collect();
+        Ok(ChunkedPayloadReader {
+            paths,
+            current: None,
+        })
+    }
+}
+
+/// Reads the payloads of a chunked blob one after the other,
+/// opening each one only once the previous has been read.
+struct ChunkedPayloadReader {
+    paths: VecDeque<PathBuf>,
+    current: Option<tokio::fs::File>,
+}
+
+impl AsyncRead for ChunkedPayloadReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            let current = match this.current.take() {
+                Some(current) => this.current.insert(current),
+                None => {
+                    let Some(path) = this.paths.pop_front() else {
+                        return Poll::Ready(Ok(()));
+                    };
+                    // opening a file is quick enough to do here, and
+                    // this avoids needing to store the open future
+                    let file = std::fs::File::open(&path)?;
+                    this.current.insert(tokio::fs::File::from_std(file))
+                }
+            };
+            let filled = buf.filled().len();
+            ready_poll!(Pin::new(current).poll_read(cx, buf))?;
+            if buf.filled().len() > filled || buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            // the end of this chunk, continue with the next one
+            this.current = None;
+        }
+    }
+}
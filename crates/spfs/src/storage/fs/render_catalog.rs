@@ -0,0 +1,562 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::OpenFsRepository;
+use crate::storage::prelude::*;
+use crate::{encoding, Error, Result};
+
+#[cfg(test)]
+#[path = "./render_catalog_test.rs"]
+mod render_catalog_test;
+
+/// The name of the file in a renders directory that holds its catalog
+pub const RENDER_CATALOG_FILENAME: &str = "catalog.jsonl";
+
+/// Held while writing to the catalog. This is a separate file because
+/// the catalog itself is replaced whenever it is compacted.
+const RENDER_CATALOG_LOCK_FILENAME: &str = "catalog.lock";
+
+/// A render that is already known to have been used this recently
+/// is not recorded as used again, to avoid writing to the catalog
+/// every time that a runtime is started.
+const USAGE_RESOLUTION_SECONDS: i64 = 10 * 60;
+
+/// Information about a completed render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderRecord {
+    /// The digest of the manifest that was rendered
+    pub digest: encoding::Digest,
+    /// The total size of the files in the render, in bytes
+    pub size: u64,
+    /// When the render was completed
+    pub completed: DateTime<Utc>,
+    /// When the render was last used, which is never before it was completed
+    pub last_used: DateTime<Utc>,
+}
+
+/// A single line in the catalog file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum CatalogEntry {
+    Completed {
+        digest: String,
+        size: u64,
+        time: DateTime<Utc>,
+    },
+    Used {
+        digest: String,
+        time: DateTime<Utc>,
+    },
+    Removed {
+        digest: String,
+    },
+}
+
+/// Identifies a version of the catalog file, which changes whenever
+/// it is appended to or replaced by any process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CatalogStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl CatalogStamp {
+    fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// The state of a catalog after reading all of its entries.
+#[derive(Default)]
+struct CatalogState {
+    records: HashMap<encoding::Digest, RenderRecord>,
+    /// Renders whose last entry says that they were removed
+    removed: HashSet<encoding::Digest>,
+    /// The version of the file that was read, or None if it did not exist
+    stamp: Option<CatalogStamp>,
+}
+
+/// The records of a catalog, as of the version of its file that was read.
+struct CachedCatalog {
+    records: HashMap<encoding::Digest, RenderRecord>,
+    /// None when the cache must be read again before it is next used
+    stamp: Option<CatalogStamp>,
+}
+
+impl CatalogState {
+    fn apply(&mut self, entry: CatalogEntry) -> Result<()> {
+        match entry {
+            CatalogEntry::Completed { digest, size, time } => {
+                let digest = encoding::parse_digest(digest)?;
+                self.removed.remove(&digest);
+                let last_used = self
+                    .records
+                    .get(&digest)
+                    .map(|record| record.last_used.max(time))
+                    .unwrap_or(time);
+                self.records.insert(
+                    digest,
+                    RenderRecord {
+                        digest,
+                        size,
+                        completed: time,
+                        last_used,
+                    },
+                );
+            }
+            CatalogEntry::Used { digest, time } => {
+                let digest = encoding::parse_digest(digest)?;
+                if let Some(record) = self.records.get_mut(&digest) {
+                    record.last_used = record.last_used.max(time);
+                }
+            }
+            CatalogEntry::Removed { digest } => {
+                let digest = encoding::parse_digest(digest)?;
+                self.records.remove(&digest);
+                self.removed.insert(digest);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the completed renders in a renders directory.
+///
+/// Checking the catalog needs a single read, where checking for each
+/// rendered directory can be slow on network filesystems with a cold
+/// cache. The catalog is an append-only log of renders being completed,
+/// used and removed, which is compacted by `spfs clean`. Renders that
+/// are not in the catalog, such as those made before it existed, must
+/// still be checked for on disk.
+pub struct RenderCatalog {
+    root: PathBuf,
+    /// The records in the catalog, once it has been read
+    cache: tokio::sync::Mutex<Option<CachedCatalog>>,
+}
+
+impl RenderCatalog {
+    /// Open the catalog of the given renders directory.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            cache: Default::default(),
+        }
+    }
+
+    /// The location of the catalog file.
+    pub fn path(&self) -> PathBuf {
+        self.root.join(RENDER_CATALOG_FILENAME)
+    }
+
+    /// Find the record for a completed render, if it is in the catalog.
+    ///
+    /// The catalog is read again whenever the file has changed since
+    /// it was last read, so that renders removed by other processes
+    /// are not reported as completed.
+    pub async fn get(&self, digest: &encoding::Digest) -> Result<Option<RenderRecord>> {
+        let mut cache = self.cache.lock().await;
+        let is_current = match cache.as_ref() {
+            Some(CachedCatalog {
+                stamp: Some(stamp), ..
+            }) => self.read_stamp().await? == Some(*stamp),
+            _ => false,
+        };
+        if !is_current {
+            let state = self.read_state().await?;
+            *cache = Some(CachedCatalog {
+                records: state.records,
+                stamp: state.stamp,
+            });
+        }
+        Ok(cache
+            .as_ref()
+            .and_then(|cached| cached.records.get(digest))
+            .copied())
+    }
+
+    /// Read all of the records in the catalog, sorted by digest.
+    pub async fn read_all(&self) -> Result<Vec<RenderRecord>> {
+        let state = self.read_state().await?;
+        let mut sorted = state.records.values().copied().collect::<Vec<_>>();
+        sorted.sort_by_key(|record| record.digest);
+        *self.cache.lock().await = Some(CachedCatalog {
+            records: state.records,
+            stamp: state.stamp,
+        });
+        Ok(sorted)
+    }
+
+    /// Record that the identified render was completed, along with its size.
+    pub async fn record_completed(&self, digest: encoding::Digest, size: u64) -> Result<()> {
+        let time = Utc::now();
+        self.append(vec![CatalogEntry::Completed {
+            digest: digest.to_string(),
+            size,
+            time,
+        }])
+        .await?;
+        if let Some(cached) = self.cache.lock().await.as_mut() {
+            cached.records.insert(
+                digest,
+                RenderRecord {
+                    digest,
+                    size,
+                    completed: time,
+                    last_used: time,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Record that the identified render was used.
+    ///
+    /// Nothing is written for renders that are not in the catalog, or
+    /// that are already known to have been used very recently.
+    pub async fn record_used(&self, digest: encoding::Digest) -> Result<()> {
+        let Some(record) = self.get(&digest).await? else {
+            return Ok(());
+        };
+        let time = Utc::now();
+        if (time - record.last_used).num_seconds() < USAGE_RESOLUTION_SECONDS {
+            return Ok(());
+        }
+        self.append(vec![CatalogEntry::Used {
+            digest: digest.to_string(),
+            time,
+        }])
+        .await?;
+        if let Some(record) = self
+            .cache
+            .lock()
+            .await
+            .as_mut()
+            .and_then(|cached| cached.records.get_mut(&digest))
+        {
+            record.last_used = time;
+        }
+        Ok(())
+    }
+
+    /// Record that the identified render is being removed.
+    ///
+    /// This must be done before the render is removed from disk, so
+    /// that it is never in the catalog without existing.
+    pub async fn record_removed(&self, digest: encoding::Digest) -> Result<()> {
+        self.append(vec![CatalogEntry::Removed {
+            digest: digest.to_string(),
+        }])
+        .await?;
+        if let Some(cached) = self.cache.lock().await.as_mut() {
+            cached.records.remove(&digest);
+        }
+        Ok(())
+    }
+
+    /// Rewrite the catalog so that it only contains the given renders,
+    /// which should be all of the renders that currently exist on disk.
+    ///
+    /// Records that are already in the catalog are kept over the given
+    /// ones. Cataloged renders that were completed since the given time
+    /// are also kept, as they may have been created after the renders on
+    /// disk were listed, and renders that were removed are never added back.
+    pub async fn compact<I>(&self, present: I, since: DateTime<Utc>) -> Result<()>
+    where
+        I: IntoIterator<Item = RenderRecord>,
+    {
+        let present = present
+            .into_iter()
+            .map(|record| (record.digest, record))
+            .collect::<HashMap<_, _>>();
+        let root = self.root.clone();
+        let records = tokio::task::spawn_blocking(move || -> Result<_> {
+            let _lock = lock_catalog(&root)?;
+            let path = root.join(RENDER_CATALOG_FILENAME);
+            let state = read_catalog(&path)?;
+            let mut records = state
+                .records
+                .into_iter()
+                .filter(|(digest, record)| {
+                    present.contains_key(digest) || record.completed >= since
+                })
+                .collect::<HashMap<_, _>>();
+            for (digest, record) in present {
+                if !state.removed.contains(&digest) {
+                    records.entry(digest).or_insert(record);
+                }
+            }
+
+            let mut sorted = records.values().collect::<Vec<_>>();
+            sorted.sort_by_key(|record| (record.completed, record.digest));
+            let mut data = Vec::new();
+            for record in sorted {
+                let digest = record.digest.to_string();
+                serde_json::to_writer(
+                    &mut data,
+                    &CatalogEntry::Completed {
+                        digest: digest.clone(),
+                        size: record.size,
+                        time: record.completed,
+                    },
+                )?;
+                data.push(b'\n');
+                if record.last_used > record.completed {
+                    serde_json::to_writer(
+                        &mut data,
+                        &CatalogEntry::Used {
+                            digest,
+                            time: record.last_used,
+                        },
+                    )?;
+                    data.push(b'\n');
+                }
+            }
+
+            // the new catalog is moved into place so that it
+            // can still be read without holding the lock
+            let working_path = root.join(format!(
+                "{RENDER_CATALOG_FILENAME}.{}",
+                uuid::Uuid::new_v4()
+            ));
+            std::fs::write(&working_path, data).map_err(|err| {
+                Error::StorageWriteError(
+                    "write compacted render catalog",
+                    working_path.clone(),
+                    err,
+                )
+            })?;
+            std::fs::rename(&working_path, &path).map_err(|err| {
+                let _ = std::fs::remove_file(&working_path);
+                Error::StorageWriteError("rename compacted render catalog", path.clone(), err)
+            })?;
+            Ok(records)
+        })
+        .await
+        .expect("syscall should not panic")?;
+        *self.cache.lock().await = Some(CachedCatalog {
+            records,
+            stamp: None,
+        });
+        Ok(())
+    }
+
+    async fn read_state(&self) -> Result<CatalogState> {
+        let path = self.path();
+        tokio::task::spawn_blocking(move || read_catalog(&path))
+            .await
+            .expect("syscall should not panic")
+    }
+
+    async fn read_stamp(&self) -> Result<Option<CatalogStamp>> {
+        let path = self.path();
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) => Ok(Some(CatalogStamp::from_metadata(&metadata))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::StorageReadError(
+                "metadata of render catalog",
+                path,
+                err,
+            )),
+        }
+    }
+
+    async fn append(&self, entries: Vec<CatalogEntry>) -> Result<()> {
+        let mut data = Vec::new();
+        for entry in entries.iter() {
+            serde_json::to_writer(&mut data, entry)?;
+            data.push(b'\n');
+        }
+        let root = self.root.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let _lock = lock_catalog(&root)?;
+            // the catalog is opened while holding the lock so that
+            // the write can never land in a file that was replaced
+            let path = root.join(RENDER_CATALOG_FILENAME);
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|err| {
+                    Error::StorageWriteError("open render catalog", path.clone(), err)
+                })?;
+            file.write_all(&data)
+                .map_err(|err| Error::StorageWriteError("append to render catalog", path, err))
+        })
+        .await
+        .expect("syscall should not panic")
+    }
+}
+
+impl Clone for RenderCatalog {
+    fn clone(&self) -> Self {
+        Self::new(self.root.clone())
+    }
+}
+
+impl std::fmt::Debug for RenderCatalog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderCatalog")
+            .field("root", &self.root)
+            .finish()
+    }
+}
+
+impl OpenFsRepository {
+    /// When the identified render was last used, or None if it does not exist.
+    ///
+    /// Renders that are not in the catalog use the time that
+    /// they were last modified instead.
+    pub async fn rendered_manifest_last_used(
+        &self,
+        digest: encoding::Digest,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let Some(render_store) = &self.renders else {
+            return Ok(None);
+        };
+        if let Some(record) = render_store.catalog.get(&digest).await? {
+            return Ok(Some(record.last_used));
+        }
+        let rendered_dirpath = render_store.renders.build_digest_path(&digest);
+        match tokio::fs::symlink_metadata(&rendered_dirpath)
+            .await
+            .and_then(|metadata| metadata.modified())
+        {
+            Ok(mtime) => Ok(Some(DateTime::<Utc>::from(mtime))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::StorageReadError(
+                "modified time of rendered dir path",
+                rendered_dirpath,
+                err,
+            )),
+        }
+    }
+
+    /// Rewrite the render catalog to match the given renders, which
+    /// should be all of the renders that currently exist on disk.
+    ///
+    /// Any of these renders that are missing from the catalog are added
+    /// to it, using the rendered manifest for their size and the modified
+    /// time of the render in place of when it was completed.
+    /// See [`RenderCatalog::compact`].
+    pub async fn compact_render_catalog<I>(&self, present: I, since: DateTime<Utc>) -> Result<()>
+    where
+        I: IntoIterator<Item = encoding::Digest>,
+    {
+        let Some(render_store) = &self.renders else {
+            return Ok(());
+        };
+        let catalog = &render_store.catalog;
+        let cataloged = catalog
+            .read_all()
+            .await?
+            .into_iter()
+            .map(|record| (record.digest, record))
+            .collect::<HashMap<_, _>>();
+
+        let mut records = Vec::new();
+        for digest in present {
+            if let Some(record) = cataloged.get(&digest) {
+                records.push(*record);
+                continue;
+            }
+            let rendered_dirpath = render_store.renders.build_digest_path(&digest);
+            let mtime = match tokio::fs::symlink_metadata(&rendered_dirpath)
+                .await
+                .and_then(|metadata| metadata.modified())
+            {
+                Ok(mtime) => DateTime::<Utc>::from(mtime),
+                // the render was removed since it was found
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(Error::StorageReadError(
+                        "modified time of rendered dir path",
+                        rendered_dirpath,
+                        err,
+                    ))
+                }
+            };
+            let size = match self.read_manifest(digest).await {
+                Ok(manifest) => manifest.iter_entries().map(|entry| entry.size()).sum(),
+                Err(err) => {
+                    // leave it to be checked for on disk
+                    tracing::debug!(?err, %digest, "not cataloging render without a manifest");
+                    continue;
+                }
+            };
+            records.push(RenderRecord {
+                digest,
+                size,
+                completed: mtime,
+                last_used: mtime,
+            });
+        }
+        catalog.compact(records, since).await
+    }
+}
+
+/// Take an exclusive lock for writing to the catalog in the given
+/// directory, which is released when the returned file is dropped.
+fn lock_catalog(root: &Path) -> Result<std::fs::File> {
+    let path = root.join(RENDER_CATALOG_LOCK_FILENAME);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|err| Error::StorageWriteError("open render catalog lock", path.clone(), err))?;
+    #[cfg(unix)]
+    {
+        use std::os::fd::AsRawFd;
+        nix::fcntl::flock(file.as_raw_fd(), nix::fcntl::FlockArg::LockExclusive)
+            .map_err(|err| Error::StorageWriteError("lock render catalog", path, err.into()))?;
+    }
+    Ok(file)
+}
+
+fn read_catalog(path: &Path) -> Result<CatalogState> {
+    let mut state = CatalogState::default();
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(state),
+        Err(err) => {
+            return Err(Error::StorageReadError(
+                "open render catalog",
+                path.to_owned(),
+                err,
+            ))
+        }
+    };
+    // taken before reading so that any later change is noticed
+    let metadata = file.metadata().map_err(|err| {
+        Error::StorageReadError("metadata of render catalog", path.to_owned(), err)
+    })?;
+    state.stamp = Some(CatalogStamp::from_metadata(&metadata));
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line.map_err(|err| {
+            Error::StorageReadError("read line of render catalog", path.to_owned(), err)
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // the last line may be incomplete if a write was interrupted
+        let res = serde_json::from_str::<CatalogEntry>(&line)
+            .map_err(Error::from)
+            .and_then(|entry| state.apply(entry));
+        if let Err(err) = res {
+            tracing::debug!(?err, ?path, %line, "skipping invalid render catalog entry");
+        }
+    }
+    Ok(state)
+}
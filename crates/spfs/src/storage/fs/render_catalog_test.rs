@@ -0,0 +1,120 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use chrono::{Duration, Utc};
+use rstest::rstest;
+
+use super::{RenderCatalog, RenderRecord};
+use crate::fixtures::*;
+
+#[rstest]
+#[tokio::test]
+async fn test_catalog_records_completed_and_removed(tmpdir: tempfile::TempDir) {
+    let digest = random_digest();
+    let catalog = RenderCatalog::new(tmpdir.path());
+    assert_eq!(catalog.get(&digest).await.unwrap(), None);
+
+    catalog.record_completed(digest, 42).await.unwrap();
+    let record = catalog
+        .get(&digest)
+        .await
+        .unwrap()
+        .expect("completed render should be cached");
+    assert_eq!(record.size, 42);
+
+    let reopened = RenderCatalog::new(tmpdir.path());
+    assert_eq!(
+        reopened.get(&digest).await.unwrap(),
+        Some(record),
+        "completed render should be read from the catalog file"
+    );
+
+    catalog.record_removed(digest).await.unwrap();
+    assert_eq!(catalog.get(&digest).await.unwrap(), None);
+    let reopened = RenderCatalog::new(tmpdir.path());
+    assert_eq!(
+        reopened.get(&digest).await.unwrap(),
+        None,
+        "removed render should not be read from the catalog file"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_catalog_sees_changes_from_other_instances(tmpdir: tempfile::TempDir) {
+    let digest = random_digest();
+    let catalog = RenderCatalog::new(tmpdir.path());
+    let other = RenderCatalog::new(tmpdir.path());
+    catalog.record_completed(digest, 1).await.unwrap();
+    assert!(other.get(&digest).await.unwrap().is_some());
+
+    catalog.record_removed(digest).await.unwrap();
+    assert_eq!(
+        other.get(&digest).await.unwrap(),
+        None,
+        "a render removed by another process should not stay cached"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_catalog_ignores_incomplete_entries(tmpdir: tempfile::TempDir) {
+    let digest = random_digest();
+    let catalog = RenderCatalog::new(tmpdir.path());
+    catalog.record_completed(digest, 1).await.unwrap();
+    let mut data = std::fs::read_to_string(catalog.path()).unwrap();
+    data.push_str(r#"{"op":"removed","dig"#);
+    std::fs::write(catalog.path(), data).unwrap();
+
+    let reopened = RenderCatalog::new(tmpdir.path());
+    assert!(
+        reopened.get(&digest).await.unwrap().is_some(),
+        "an interrupted write should not affect the other entries"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_catalog_compact(tmpdir: tempfile::TempDir) {
+    let catalog = RenderCatalog::new(tmpdir.path());
+    let kept = random_digest();
+    let missing = random_digest();
+    let removed = random_digest();
+    let recent = random_digest();
+    let uncataloged = random_digest();
+    catalog.record_completed(kept, 1).await.unwrap();
+    catalog.record_completed(missing, 2).await.unwrap();
+    catalog.record_completed(removed, 3).await.unwrap();
+    catalog.record_removed(removed).await.unwrap();
+
+    let since = Utc::now();
+    catalog.record_completed(recent, 4).await.unwrap();
+
+    let old = since - Duration::hours(1);
+    let found = [kept, removed, uncataloged].map(|digest| RenderRecord {
+        digest,
+        size: 5,
+        completed: old,
+        last_used: old,
+    });
+    // the recent render was not found on disk, but may have been
+    // completed after the renders were listed
+    catalog.compact(found, since).await.unwrap();
+
+    let reopened = RenderCatalog::new(tmpdir.path());
+    let records = reopened.read_all().await.unwrap();
+    let mut digests = records.iter().map(|r| r.digest).collect::<Vec<_>>();
+    let mut expected = vec![kept, recent, uncataloged];
+    digests.sort();
+    expected.sort();
+    assert_eq!(digests, expected);
+
+    let kept_record = reopened.get(&kept).await.unwrap().unwrap();
+    assert_eq!(
+        kept_record.size, 1,
+        "existing records should be preferred over the given ones"
+    );
+    let uncataloged_record = reopened.get(&uncataloged).await.unwrap().unwrap();
+    assert_eq!(uncataloged_record.completed, old);
+}
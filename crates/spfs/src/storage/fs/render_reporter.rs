@@ -21,6 +21,10 @@ pub enum RenderBlobResult {
     PayloadCopiedWrongOwner,
     /// Payload was able to be hard linked.
     PayloadHardLinked,
+    /// Payload data was shared with a reflink.
+    PayloadReflinked,
+    /// A reflink was requested but not supported, so a copy was made.
+    PayloadCopiedNoReflink,
     /// Payload was a symlink and already existed.
     SymlinkAlreadyExists,
     /// Payload was a symlink and was written.
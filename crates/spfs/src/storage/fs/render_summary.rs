@@ -18,7 +18,9 @@ pub struct RenderSummary {
     pub copy_link_limit_count: AtomicUsize,
     pub copy_wrong_mode_count: AtomicUsize,
     pub copy_wrong_owner_count: AtomicUsize,
+    pub copy_no_reflink_count: AtomicUsize,
     pub link_count: AtomicUsize,
+    pub reflink_count: AtomicUsize,
     pub symlink_count: AtomicUsize,
 
     pub total_bytes_rendered: AtomicUsize,
@@ -27,7 +29,9 @@ pub struct RenderSummary {
     pub total_bytes_copied_link_limit: AtomicUsize,
     pub total_bytes_copied_wrong_mode: AtomicUsize,
     pub total_bytes_copied_wrong_owner: AtomicUsize,
+    pub total_bytes_copied_no_reflink: AtomicUsize,
     pub total_bytes_linked: AtomicUsize,
+    pub total_bytes_reflinked: AtomicUsize,
 }
 
 /// Associate a `RenderBlobResult` with the size of the entry that was rendered.
@@ -102,6 +106,25 @@ impl RenderSummary {
                 self.total_bytes_linked
                     .fetch_add(entry_size, Ordering::Relaxed);
             }
+            RenderBlobResult::PayloadReflinked => {
+                self.reflink_count.fetch_add(1, Ordering::Relaxed);
+
+                self.total_bytes_rendered
+                    .fetch_add(entry_size, Ordering::Relaxed);
+                self.total_bytes_reflinked
+                    .fetch_add(entry_size, Ordering::Relaxed);
+            }
+            RenderBlobResult::PayloadCopiedNoReflink => {
+                self.copy_count.fetch_add(1, Ordering::Relaxed);
+                self.copy_no_reflink_count.fetch_add(1, Ordering::Relaxed);
+
+                self.total_bytes_rendered
+                    .fetch_add(entry_size, Ordering::Relaxed);
+                self.total_bytes_copied
+                    .fetch_add(entry_size, Ordering::Relaxed);
+                self.total_bytes_copied_no_reflink
+                    .fetch_add(entry_size, Ordering::Relaxed);
+            }
             RenderBlobResult::SymlinkAlreadyExists => {
                 self.already_existed_count.fetch_add(1, Ordering::Relaxed);
 
@@ -128,3 +128,233 @@ async fn test_render_manifest_with_repo(
         rendered_manifest.to_graph_manifest().digest().unwrap()
     );
 }
+
+#[rstest]
+#[tokio::test]
+#[serial_test::serial(config)]
+async fn test_render_manifest_reflink(tmpdir: tempfile::TempDir) {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut config = Config::default();
+    config.storage.render_type = Some(super::RenderType::Reflink);
+    config.make_current().unwrap();
+
+    let tmprepo = Arc::new(
+        FsRepository::create(tmpdir.path().join("repo"))
+            .await
+            .unwrap()
+            .into(),
+    );
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("dir1.0/dir2.0/file.txt"), "somedata");
+    ensure(src_dir.join("dir2.0/file.txt"), "evenmoredata");
+    ensure(src_dir.join("file.txt"), "rootdata");
+
+    let expected_manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(&src_dir)
+        .await
+        .unwrap();
+    let manifest = expected_manifest.to_graph_manifest();
+
+    // Safety: tmprepo was created as an FsRepository
+    let tmprepo = match &*tmprepo {
+        RepositoryHandle::FS(fs) => fs.opened().await.unwrap(),
+        _ => panic!("Unexpected tmprepo type!"),
+    };
+
+    // no render type is given, so the one from the config should be used
+    let render = super::Renderer::new(&*tmprepo)
+        .render_manifest(&manifest, None)
+        .await
+        .unwrap();
+    let rendered_manifest = tracking::compute_manifest(&render).await.unwrap();
+    assert_eq!(
+        expected_manifest.to_graph_manifest().digest().unwrap(),
+        rendered_manifest.to_graph_manifest().digest().unwrap()
+    );
+    for node in rendered_manifest.walk_abs(render.to_str().unwrap()) {
+        if node.entry.kind.is_blob() {
+            let metadata = std::fs::symlink_metadata(node.path.to_path("/")).unwrap();
+            assert_eq!(
+                metadata.nlink(),
+                1,
+                "reflinked or copied files should not be hard linked to the payload"
+            );
+        }
+    }
+}
+
+#[rstest]
+#[tokio::test]
+#[serial_test::serial(config)]
+async fn test_render_manifest_read_only_dirs(tmpdir: tempfile::TempDir) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut config = Config::default();
+    config.render.max_concurrent_blobs = 2;
+    config.make_current().unwrap();
+
+    let tmprepo = Arc::new(
+        FsRepository::create(tmpdir.path().join("repo"))
+            .await
+            .unwrap()
+            .into(),
+    );
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("locked/nested/file.txt"), "somedata");
+    ensure(src_dir.join("locked/file.txt"), "someotherdata");
+    ensure(src_dir.join("file.txt"), "rootdata");
+    for dir in ["locked/nested", "locked"] {
+        std::fs::set_permissions(src_dir.join(dir), std::fs::Permissions::from_mode(0o555))
+            .unwrap();
+    }
+
+    let expected_manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(&src_dir)
+        .await
+        .unwrap();
+    let manifest = expected_manifest.to_graph_manifest();
+
+    // Safety: tmprepo was created as an FsRepository
+    let tmprepo = match &*tmprepo {
+        RepositoryHandle::FS(fs) => fs.opened().await.unwrap(),
+        _ => panic!("Unexpected tmprepo type!"),
+    };
+
+    // the contents of each directory must be rendered before
+    // its permissions prevent anything more being written into it
+    let target_dir = tmpdir.path().join("target");
+    super::Renderer::new(&*tmprepo)
+        .render_manifest_into_dir(&manifest, &target_dir, super::RenderType::Copy)
+        .await
+        .unwrap();
+    let rendered_manifest = tracking::compute_manifest(&target_dir).await.unwrap();
+    assert_eq!(
+        expected_manifest.to_graph_manifest().digest().unwrap(),
+        rendered_manifest.to_graph_manifest().digest().unwrap()
+    );
+
+    for root in [&src_dir, &target_dir] {
+        super::open_perms_and_remove_all(root).await.unwrap();
+    }
+}
+
+#[rstest]
+#[tokio::test]
+#[serial_test::serial(config)]
+async fn test_render_manifest_preserves_xattrs(tmpdir: tempfile::TempDir) {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::MetadataExt;
+
+    let mut config = Config::default();
+    config.storage.preserve_xattrs = true;
+    config.make_current().unwrap();
+
+    let tmprepo = Arc::new(
+        FsRepository::create(tmpdir.path().join("repo"))
+            .await
+            .unwrap()
+            .into(),
+    );
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("bin/tool"), "binarydata");
+    ensure(src_dir.join("plain.txt"), "rootdata");
+    let tool = std::fs::File::open(src_dir.join("bin/tool")).unwrap();
+    match tracking::xattrs::set_xattr(tool.as_raw_fd(), "user.spfs-test", b"value") {
+        Ok(()) => {}
+        Err(err) if err.raw_os_error() == Some(libc::ENOTSUP) => {
+            // user attributes are not supported on all filesystems
+            return;
+        }
+        Err(err) => panic!("failed to set test xattr: {err}"),
+    }
+
+    let expected_manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(&src_dir)
+        .await
+        .unwrap();
+    assert!(
+        !expected_manifest
+            .get_path("bin/tool")
+            .unwrap()
+            .xattrs
+            .is_empty(),
+        "attributes should be captured when enabled in the config"
+    );
+    let manifest = expected_manifest.to_graph_manifest();
+
+    // Safety: tmprepo was created as an FsRepository
+    let tmprepo = match &*tmprepo {
+        RepositoryHandle::FS(fs) => fs.opened().await.unwrap(),
+        _ => panic!("Unexpected tmprepo type!"),
+    };
+
+    let render = super::Renderer::new(&*tmprepo)
+        .render_manifest(&manifest, Some(super::RenderType::HardLink))
+        .await
+        .unwrap();
+    let xattrs = tracking::xattrs::read_xattrs(&render.join("bin/tool")).unwrap();
+    assert_eq!(
+        xattrs.get("user.spfs-test").map(Vec::as_slice),
+        Some(&b"value"[..]),
+        "attributes should be applied to the rendered file"
+    );
+    let metadata = std::fs::symlink_metadata(render.join("bin/tool")).unwrap();
+    assert_eq!(
+        metadata.nlink(),
+        1,
+        "files with attributes should be copied rather than hard linked"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+#[serial_test::serial(config)]
+async fn test_render_manifest_is_cataloged(tmpdir: tempfile::TempDir) {
+    let config = Config::default();
+    config.make_current().unwrap();
+
+    let tmprepo = Arc::new(
+        FsRepository::create(tmpdir.path().join("repo"))
+            .await
+            .unwrap()
+            .into(),
+    );
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("dir1.0/file.txt"), "somedata");
+    ensure(src_dir.join("file.txt"), "rootdata");
+
+    let expected_manifest = crate::Committer::new(&tmprepo)
+        .commit_dir(&src_dir)
+        .await
+        .unwrap();
+    let manifest = expected_manifest.to_graph_manifest();
+    let digest = manifest.digest().unwrap();
+
+    // Safety: tmprepo was created as an FsRepository
+    let tmprepo = match &*tmprepo {
+        RepositoryHandle::FS(fs) => fs.opened().await.unwrap(),
+        _ => panic!("Unexpected tmprepo type!"),
+    };
+    let catalog = &tmprepo.renders.as_ref().unwrap().catalog;
+
+    super::Renderer::new(&*tmprepo)
+        .render_manifest(&manifest, None)
+        .await
+        .unwrap();
+    let record = catalog
+        .get(&digest)
+        .await
+        .unwrap()
+        .expect("completed render should be added to the catalog");
+    assert_eq!(
+        record.size,
+        ("somedata".len() + "rootdata".len()) as u64,
+        "catalog should record the size of the rendered files"
+    );
+    assert!(tmprepo.has_rendered_manifest(digest).await);
+
+    tmprepo.remove_rendered_manifest(digest).await.unwrap();
+    assert_eq!(catalog.get(&digest).await.unwrap(), None);
+    assert!(!tmprepo.has_rendered_manifest(digest).await);
+}
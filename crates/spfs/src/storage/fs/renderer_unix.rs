@@ -11,7 +11,6 @@ use std::sync::Arc;
 
 use async_stream::try_stream;
 use chrono::{DateTime, Utc};
-use futures::future::ready;
 use futures::{FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use nix::fcntl::OFlag;
 use nix::sys::stat::Mode;
@@ -27,6 +26,7 @@ use crate::storage::fs::{
     ManifestRenderPath,
     OpenFsRepository,
     RenderReporter,
+    RenderStore,
     SilentRenderReporter,
 };
 use crate::storage::prelude::*;
@@ -45,11 +45,26 @@ pub const DEFAULT_MAX_CONCURRENT_BLOBS: usize = 100;
 /// See: [`Renderer::with_max_concurrent_branches`]
 pub const DEFAULT_MAX_CONCURRENT_BRANCHES: usize = 5;
 
-#[derive(Debug, Copy, Clone, strum::EnumString, strum::VariantNames, strum::IntoStaticStr)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::EnumString,
+    strum::VariantNames,
+    strum::IntoStaticStr,
+)]
 pub enum RenderType {
     HardLink,
     HardLinkNoProxy,
     Copy,
+    /// Clone the payload's data blocks into a new file where the
+    /// filesystem supports it (eg: btrfs, xfs), otherwise copy it.
+    ///
+    /// Like a copy, each rendered file has its own owner and
+    /// permissions, but like a hard link it takes no extra space.
+    Reflink,
 }
 
 impl OpenFsRepository {
@@ -61,12 +76,10 @@ impl OpenFsRepository {
     }
 
     pub async fn has_rendered_manifest(&self, digest: encoding::Digest) -> bool {
-        let renders = match &self.renders {
-            Some(render_store) => &render_store.renders,
-            None => return false,
-        };
-        let rendered_dir = renders.build_digest_path(&digest);
-        was_render_completed(rendered_dir).await
+        match &self.renders {
+            Some(render_store) => is_render_completed(render_store, &digest).await,
+            None => false,
+        }
     }
 
     pub fn iter_rendered_manifests<'db>(
@@ -88,10 +101,14 @@ impl OpenFsRepository {
 
     /// Remove the identified render from this storage.
     pub async fn remove_rendered_manifest(&self, digest: crate::encoding::Digest) -> Result<()> {
-        let renders = match &self.renders {
-            Some(render_store) => &render_store.renders,
+        let render_store = match &self.renders {
+            Some(render_store) => render_store,
             None => return Ok(()),
         };
+        // the render is removed from the catalog first so that it can
+        // never be found there after it's been removed from disk
+        render_store.catalog.record_removed(digest).await?;
+        let renders = &render_store.renders;
         let rendered_dirpath = renders.build_digest_path(&digest);
         let workdir = renders.workdir();
         if let Err(err) = makedirs_with_perms(&workdir, renders.directory_permissions) {
@@ -127,11 +144,18 @@ impl OpenFsRepository {
         older_than: DateTime<Utc>,
         digest: encoding::Digest,
     ) -> Result<bool> {
-        let renders = match &self.renders {
-            Some(render_store) => &render_store.renders,
+        let render_store = match &self.renders {
+            Some(render_store) => render_store,
             None => return Ok(false),
         };
-        let rendered_dirpath = renders.build_digest_path(&digest);
+        if let Some(record) = render_store.catalog.get(&digest).await? {
+            if record.last_used >= older_than {
+                return Ok(false);
+            }
+            self.remove_rendered_manifest(digest).await?;
+            return Ok(true);
+        }
+        let rendered_dirpath = render_store.renders.build_digest_path(&digest);
 
         let metadata = match tokio::fs::symlink_metadata(&rendered_dirpath).await {
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
@@ -198,15 +222,32 @@ pub struct Renderer<'repo, Repo, Reporter: RenderReporter = SilentRenderReporter
     reporter: Arc<Reporter>,
     blob_semaphore: BlobSemaphore,
     max_concurrent_branches: usize,
+    preserve_xattrs: bool,
 }
 
 impl<'repo, Repo> Renderer<'repo, Repo, SilentRenderReporter> {
+    /// Create a renderer for the given repository.
+    ///
+    /// The number of blobs rendered concurrently defaults to the
+    /// `render.max_concurrent_blobs` value from the spfs config,
+    /// and extended attributes are applied if `storage.preserve_xattrs`
+    /// is enabled.
     pub fn new(repo: &'repo Repo) -> Self {
+        let config = get_config();
+        let max_concurrent_blobs = config
+            .as_ref()
+            .map(|config| config.render.max_concurrent_blobs)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_BLOBS);
+        let preserve_xattrs = config
+            .as_ref()
+            .map(|config| config.storage.preserve_xattrs)
+            .unwrap_or_default();
         Self {
             repo,
             reporter: Arc::new(SilentRenderReporter),
-            blob_semaphore: BlobSemaphore(Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_BLOBS))),
+            blob_semaphore: BlobSemaphore(Arc::new(Semaphore::new(max_concurrent_blobs))),
             max_concurrent_branches: DEFAULT_MAX_CONCURRENT_BRANCHES,
+            preserve_xattrs,
         }
     }
 }
@@ -227,6 +268,7 @@ where
             reporter: reporter.into(),
             blob_semaphore: self.blob_semaphore,
             max_concurrent_branches: self.max_concurrent_branches,
+            preserve_xattrs: self.preserve_xattrs,
         }
     }
 
@@ -236,6 +278,16 @@ where
         self
     }
 
+    /// Apply the extended attributes stored in manifests to rendered files.
+    ///
+    /// Files with attributes are always copied rather than hard
+    /// linked, since a hard link shares its attributes with the
+    /// payload in the repository.
+    pub fn with_preserve_xattrs(mut self, preserve_xattrs: bool) -> Self {
+        self.preserve_xattrs = preserve_xattrs;
+        self
+    }
+
     /// Set how many branches should be processed at once.
     ///
     /// Each tree that is processed can have any number of subtrees. This number
@@ -317,9 +369,13 @@ where
         render_type: Option<RenderType>,
     ) -> Result<PathBuf> {
         let render_store = self.repo.render_store()?;
-        let rendered_dirpath = render_store.renders.build_digest_path(&manifest.digest()?);
-        if was_render_completed(&rendered_dirpath).await {
+        let digest = manifest.digest()?;
+        let rendered_dirpath = render_store.renders.build_digest_path(&digest);
+        if is_render_completed(render_store, &digest).await {
             tracing::trace!(path = ?rendered_dirpath, "render already completed");
+            if let Err(err) = render_store.catalog.record_used(digest).await {
+                tracing::warn!(?err, "failed to record use of render in catalog");
+            }
             return Ok(rendered_dirpath);
         }
         tracing::trace!(path = ?rendered_dirpath, "rendering manifest...");
@@ -337,7 +393,9 @@ where
         self.render_manifest_into_dir(
             manifest,
             &working_dir,
-            render_type.unwrap_or(RenderType::HardLink),
+            render_type
+                .or_else(|| get_config().ok().and_then(|c| c.storage.render_type))
+                .unwrap_or(RenderType::HardLink),
         )
         .await
         .map_err(|err| {
@@ -373,6 +431,11 @@ where
             },
         }
 
+        let size = manifest.iter_entries().map(|entry| entry.size()).sum();
+        if let Err(err) = render_store.catalog.record_completed(digest, size).await {
+            tracing::warn!(?err, "failed to record completed render in catalog");
+        }
+
         Ok(rendered_dirpath)
     }
 
@@ -387,7 +450,6 @@ where
         P: AsRef<Path>,
     {
         self.reporter.visit_layer(manifest);
-        let root_node = manifest.root();
         let target_dir = target_dir.as_ref();
         tokio::fs::create_dir_all(target_dir).await.map_err(|err| {
             Error::StorageWriteError(
@@ -412,9 +474,8 @@ where
             self.reporter.visit_entry(entry);
         }
 
-        let manifest_tree_cache = manifest.get_tree_cache();
         let mut res = self
-            .render_into_dir_fd(root_dir, &root_node, &manifest_tree_cache, render_type)
+            .render_into_dir_fd(root_dir, manifest, render_type)
             .await;
         if let Err(Error::StorageWriteError(_, p, _)) = &mut res {
             *p = target_dir.join(p.as_path());
@@ -424,12 +485,17 @@ where
         Ok(())
     }
 
-    #[async_recursion::async_recursion]
+    /// Render the contents of a manifest into an already opened directory.
+    ///
+    /// This happens in three phases so that each one can be batched or
+    /// run concurrently: all of the directories are created first, then
+    /// the blobs and symlinks within them, and finally the permissions
+    /// of each directory are set once nothing else needs to be written
+    /// into it.
     async fn render_into_dir_fd<Fd>(
         &self,
         root_dir_fd: Fd,
-        tree: &graph::Tree<'async_recursion>,
-        manifest_tree_cache: &graph::ManifestTreeCache,
+        manifest: &graph::Manifest,
         render_type: RenderType,
     ) -> Result<()>
     where
@@ -441,89 +507,132 @@ where
         // other code can run in the current thread).
         // Instead, we try to rely on generating the structure
         // first with open permissions and then locking it down
+        let root_dir_fd = root_dir_fd.as_raw_fd();
+        let dirs = collect_render_dirs(manifest)?;
 
-        // randomize the entries so that multiple processes starting
+        let paths = dirs
+            .iter()
+            .skip(1)
+            .map(|dir| dir.path.clone())
+            .collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            for path in paths {
+                // leave the permissions open for now, so that
+                // the structure inside can be generated without
+                // privileged access
+                match nix::sys::stat::mkdirat(root_dir_fd, path.as_path(), Mode::all()) {
+                    Ok(_) | Err(nix::errno::Errno::EEXIST) => {}
+                    Err(err) => {
+                        return Err(Error::StorageWriteError(
+                            "create dir during render",
+                            path,
+                            err.into(),
+                        ))
+                    }
+                }
+            }
+            Ok(())
+        })
+        .await
+        .expect("syscall should not panic")?;
+
+        // randomize the order so that multiple processes starting
         // at the same time don't have as much contention trying to render
         // the same files as one another at the same time. This can happen,
         // for example, when multiple frames land on the same machine in a
         // render farm and they both start to render the same env at the same time
-        let mut entries = tree.entries().collect::<Vec<_>>();
-        entries.shuffle(&mut rand::thread_rng());
+        let mut order = (0..dirs.len()).collect::<Vec<_>>();
+        order.shuffle(&mut rand::thread_rng());
+        let mut stream = futures::stream::iter(order.into_iter().map(|i| &dirs[i]))
+            .map(|dir| self.render_dir_entries(root_dir_fd, dir, render_type))
+            .buffer_unordered(self.max_concurrent_branches);
+        while let Some(res) = stream.next().await {
+            res?;
+        }
 
-        let root_dir_fd = root_dir_fd.as_raw_fd();
-        let mut stream = futures::stream::iter(entries)
-            .then(move |entry| {
-                let fut = async move {
-                    let mut root_path = PathBuf::from(entry.name());
-                    match entry.kind() {
-                        tracking::EntryKind::Tree => {
-                            let tree = manifest_tree_cache.get(entry.object()).ok_or_else(|| {
-                                Error::String(format!("Failed to render: manifest is internally inconsistent (missing child tree {})", *entry.object()))
-                            })?;
-
-                            let child_dir = create_and_open_dir_at(root_dir_fd, entry.name().to_owned())
-                                .await
-                                .map_err(|err| {
-                                    Error::StorageWriteError(
-                                        "create dir during render",
-                                        PathBuf::from(entry.name()),
-                                        err,
-                                    )
-                                })?;
-                            let mut res = self
-                                .render_into_dir_fd(
-                                    child_dir.as_raw_fd(),
-                                    tree,
-                                    manifest_tree_cache,
-                                    render_type,
-                                )
-                                .await;
-                            if res.is_ok() {
-                                let mode = Mode::from_bits_truncate(entry.mode());
-                                res = tokio::task::spawn_blocking(move || {
-                                    nix::sys::stat::fchmod(
-                                        child_dir.as_raw_fd(),
-                                        mode,
-                                    )
-                                })
-                                .await
-                                .expect("syscall should not panic")
-                                .map_err(|err| {
-                                    Error::StorageWriteError(
-                                        "set_permissions on rendered dir",
-                                        PathBuf::new(),
-                                        err.into(),
-                                    )
-                                });
-                            }
-                            if let Err(Error::StorageWriteError(_, p, _)) = &mut res {
-                                root_path.push(p.as_path());
-                                *p = root_path;
-                            }
-                            res.map(|_| None).map_err(|err| err.wrap(format!("render_into_dir '{}'", entry.name())))
-                        }
-                        tracking::EntryKind::Mask => Ok(None),
-                        tracking::EntryKind::Blob(_) => {
-                            self.render_blob(root_dir_fd, entry, render_type).await.map(Some).map_err(|err| err.wrap(format!("render blob '{}'", entry.name())))
-                        }
-                    }.map(|render_blob_result_opt| (entry, render_blob_result_opt))
-                };
-                ready(fut.boxed())
+        // children are set before their parents, in case the new
+        // mode of a parent does not allow them to be modified
+        for dir in dirs.iter().skip(1).rev() {
+            let path = dir.path.clone();
+            let mode = Mode::from_bits_truncate(dir.mode);
+            tokio::task::spawn_blocking(move || {
+                nix::sys::stat::fchmodat(
+                    Some(root_dir_fd),
+                    path.as_path(),
+                    mode,
+                    nix::sys::stat::FchmodatFlags::FollowSymlink,
+                )
+                .map_err(|err| {
+                    Error::StorageWriteError("set_permissions on rendered dir", path, err.into())
+                })
             })
-            .buffer_unordered(self.max_concurrent_branches)
-            .boxed();
+            .await
+            .expect("syscall should not panic")?;
+            if let Some(entry) = dir.entry {
+                self.reporter.rendered_entry(entry);
+            }
+        }
 
-        while let Some(res) = stream.next().await {
+        Ok(())
+    }
+
+    /// Render the blobs and symlinks directly within one directory,
+    /// which must have already been created.
+    async fn render_dir_entries(
+        &self,
+        root_dir_fd: std::os::fd::RawFd,
+        dir: &RenderDir<'_>,
+        render_type: RenderType,
+    ) -> Result<()> {
+        let path = dir.path.clone();
+        let dir_fd = tokio::task::spawn_blocking(move || -> Result<tokio::fs::File> {
+            let fd = nix::fcntl::openat(
+                root_dir_fd,
+                path.as_path(),
+                OFlag::O_DIRECTORY | OFlag::O_PATH,
+                Mode::empty(),
+            )
+            .map_err(|err| Error::StorageWriteError("open dir during render", path, err.into()))?;
+            // Safety: from_raw_fd takes ownership of this fd which is what we want
+            Ok(unsafe { tokio::fs::File::from_raw_fd(fd) })
+        })
+        .await
+        .expect("syscall should not panic")?;
+        let dir_fd = dir_fd.as_raw_fd();
+
+        let mut entries = dir.tree.entries().collect::<Vec<_>>();
+        entries.shuffle(&mut rand::thread_rng());
+        let mut blobs = futures::stream::FuturesUnordered::new();
+        for entry in entries {
+            match entry.kind() {
+                tracking::EntryKind::Blob(_) => blobs.push(
+                    self.render_blob(dir_fd, entry, render_type)
+                        .map(move |res| (entry, res)),
+                ),
+                tracking::EntryKind::Mask => self.reporter.rendered_entry(entry),
+                // reported once their permissions have been set
+                tracking::EntryKind::Tree => {}
+            }
+        }
+        // every blob waits on the shared semaphore, so this will
+        // not render more than the configured number at once
+        while let Some((entry, res)) = blobs.next().await {
             match res {
-                Err(error) => return Err(error),
-                Ok((entry, Some(render_blob_result))) => {
+                Ok(render_blob_result) => {
                     self.reporter.rendered_blob(entry, &render_blob_result);
                     self.reporter.rendered_entry(entry);
                 }
-                Ok((entry, _)) => self.reporter.rendered_entry(entry),
+                Err(mut err) => {
+                    if let Error::StorageWriteError(_, p, _) = &mut err {
+                        *p = dir.path.join(p.as_path());
+                    }
+                    return Err(err.wrap(format!(
+                        "render blob '{}'",
+                        dir.path.join(entry.name()).display()
+                    )));
+                }
             }
         }
-
         Ok(())
     }
 
@@ -592,8 +701,32 @@ where
                 Ok(RenderBlobResult::SymlinkWritten)
             };
         }
-        // Free up file resources as early as possible.
-        drop(reader);
+        // Chunked payloads have no single file that can be linked or
+        // reflinked, and so are rendered by copying from the reader.
+        let chunked_payload = match tokio::fs::symlink_metadata(&filename).await {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Some(reader),
+            _ => {
+                // Free up file resources as early as possible.
+                drop(reader);
+                None
+            }
+        };
+
+        let xattrs: Vec<_> = if self.preserve_xattrs {
+            entry
+                .xattrs()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let render_type = match render_type {
+            _ if chunked_payload.is_some() => RenderType::Copy,
+            RenderType::HardLink | RenderType::HardLinkNoProxy if !xattrs.is_empty() => {
+                RenderType::Copy
+            }
+            render_type => render_type,
+        };
 
         let mut committed_path = self.repo.payloads().build_digest_path(entry.object());
         Ok(match render_type {
@@ -882,18 +1015,59 @@ where
                     };
                 }
             }
+            RenderType::Reflink => {
+                let name = entry.name().to_owned();
+                let mode = entry.mode();
+                let reflinked = tokio::task::spawn_blocking(move || -> std::io::Result<bool> {
+                    let payload_file = std::fs::File::open(&committed_path)?;
+                    // create with open permissions, as they will be set to the proper mode below
+                    let fd = nix::fcntl::openat(
+                        target_dir_fd,
+                        name.as_str(),
+                        OFlag::O_RDWR | OFlag::O_CREAT | OFlag::O_TRUNC,
+                        Mode::all(),
+                    )?;
+                    // Safety: from_raw_fd takes ownership of this fd which is what we want
+                    let rendered_file = unsafe { std::fs::File::from_raw_fd(fd) };
+                    let reflinked = reflink_or_copy(&payload_file, &rendered_file)?;
+                    nix::sys::stat::fchmod(
+                        rendered_file.as_raw_fd(),
+                        Mode::from_bits_truncate(mode),
+                    )?;
+                    set_rendered_xattrs(rendered_file.as_raw_fd(), &name, &xattrs)?;
+                    Ok(reflinked)
+                })
+                .await
+                .expect("syscall should not panic")
+                .map_err(|err| {
+                    Error::StorageWriteError(
+                        "reflink of blob to rendered file",
+                        PathBuf::from(entry.name()),
+                        err,
+                    )
+                })?;
+                if reflinked {
+                    RenderBlobResult::PayloadReflinked
+                } else {
+                    RenderBlobResult::PayloadCopiedNoReflink
+                }
+            }
             RenderType::Copy => {
                 let name = entry.name().to_owned();
-                let mut payload_file =
-                    tokio::fs::File::open(&committed_path)
-                        .await
-                        .map_err(|err| {
-                            Error::StorageReadError(
-                                "open of payload source file",
-                                committed_path,
-                                err,
-                            )
-                        })?;
+                let mut payload_file: Pin<Box<dyn tracking::BlobRead>> = match chunked_payload {
+                    Some(reader) => reader,
+                    None => Box::pin(tokio::io::BufReader::new(
+                        tokio::fs::File::open(&committed_path)
+                            .await
+                            .map_err(|err| {
+                                Error::StorageReadError(
+                                    "open of payload source file",
+                                    committed_path,
+                                    err,
+                                )
+                            })?,
+                    )),
+                };
                 let mut rendered_file =
                     tokio::task::spawn_blocking(move || -> std::io::Result<tokio::fs::File> {
                         // create with open permissions, as they will be set to the proper mode in the future
@@ -925,11 +1099,13 @@ where
                         )
                     })?;
                 let mode = entry.mode();
-                return tokio::task::spawn_blocking(move || {
+                let name = entry.name().to_owned();
+                return tokio::task::spawn_blocking(move || -> std::io::Result<()> {
                     nix::sys::stat::fchmod(
                         rendered_file.as_raw_fd(),
                         Mode::from_bits_truncate(mode),
-                    )
+                    )?;
+                    set_rendered_xattrs(rendered_file.as_raw_fd(), &name, &xattrs)
                 })
                 .await
                 .expect("syscall should not panic")
@@ -938,7 +1114,7 @@ where
                     Error::StorageWriteError(
                         "set permissions on copied payload",
                         PathBuf::from(entry.name()),
-                        err.into(),
+                        err,
                     )
                 });
             }
@@ -946,30 +1122,126 @@ where
     }
 }
 
-async fn create_and_open_dir_at<A>(dir_fd: A, name: String) -> std::io::Result<tokio::fs::File>
-where
-    A: AsRawFd + Send + 'static,
-{
-    tokio::task::spawn_blocking(move || {
-        // leave the permissions open for now, so that
-        // the structure inside can be generated without
-        // privileged access
-        match nix::sys::stat::mkdirat(dir_fd.as_raw_fd(), name.as_str(), Mode::all()) {
-            Ok(_) | Err(nix::errno::Errno::EEXIST) => {}
-            Err(err) => return Err(err.into()),
+/// Apply the extended attributes of an entry to its rendered file.
+///
+/// Some attributes, such as file capabilities, can only be set with
+/// elevated privileges or on filesystems that support them. These are
+/// skipped with a warning rather than failing the whole render.
+fn set_rendered_xattrs(
+    fd: std::os::fd::RawFd,
+    name: &str,
+    xattrs: &[(String, Vec<u8>)],
+) -> std::io::Result<()> {
+    for (attr, value) in xattrs {
+        match tracking::xattrs::set_xattr(fd, attr, value) {
+            Ok(()) => {}
+            Err(err)
+                if err.raw_os_error() == Some(nix::errno::Errno::EPERM as i32)
+                    || err.raw_os_error() == Some(nix::errno::Errno::ENOTSUP as i32) =>
+            {
+                tracing::warn!("Unable to set {attr} on rendered file {name}: {err}");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// A directory to be created while rendering a manifest.
+struct RenderDir<'a> {
+    /// The path of this directory relative to the render root
+    path: PathBuf,
+    /// The entry that describes this directory, or None for the root
+    entry: Option<graph::Entry<'a>>,
+    tree: graph::Tree<'a>,
+    mode: u32,
+}
+
+/// Find every directory in the manifest, ordered so
+/// that each one comes after its parent.
+fn collect_render_dirs(manifest: &graph::Manifest) -> Result<Vec<RenderDir<'_>>> {
+    let manifest_tree_cache = manifest.get_tree_cache();
+    let mut dirs = vec![RenderDir {
+        path: PathBuf::new(),
+        entry: None,
+        tree: manifest.root(),
+        mode: 0o777,
+    }];
+    let mut next = 0;
+    while next < dirs.len() {
+        let parent = dirs[next].path.clone();
+        let tree = dirs[next].tree;
+        next += 1;
+        for entry in tree.entries() {
+            if !entry.kind().is_tree() {
+                continue;
+            }
+            let tree = manifest_tree_cache.get(entry.object()).ok_or_else(|| {
+                Error::String(format!(
+                    "Failed to render: manifest is internally inconsistent (missing child tree {})",
+                    *entry.object()
+                ))
+            })?;
+            dirs.push(RenderDir {
+                path: parent.join(entry.name()),
+                entry: Some(entry),
+                tree: *tree,
+                mode: entry.mode(),
+            });
         }
-        let fd = nix::fcntl::openat(
-            dir_fd.as_raw_fd(),
-            name.as_str(),
-            OFlag::O_DIRECTORY | OFlag::O_RDONLY,
-            Mode::all(),
+    }
+    Ok(dirs)
+}
+
+// FICLONE is defined as _IOW(0x94, 9, int) in linux/fs.h
+nix::ioctl_write_int!(ficlone, 0x94, 9);
+
+/// Fill `dst` with the contents of `src`, sharing the underlying data
+/// blocks rather than copying them if the filesystem supports it.
+///
+/// Returns false if the data had to be copied instead.
+fn reflink_or_copy(src: &std::fs::File, dst: &std::fs::File) -> std::io::Result<bool> {
+    // Safety: both file descriptors are valid for the duration of the call
+    match unsafe {
+        ficlone(
+            dst.as_raw_fd(),
+            src.as_raw_fd() as nix::sys::ioctl::ioctl_param_type,
         )
-        .map_err(std::io::Error::from)?;
-        // Safety: from_raw_fd takes ownership of this fd which is what we want
-        Ok(unsafe { tokio::fs::File::from_raw_fd(fd) })
-    })
-    .await
-    .map_err(|_join_err| std::io::Error::new(std::io::ErrorKind::Other, "mkdir task panic'd"))?
+    } {
+        Ok(_) => return Ok(true),
+        // the filesystem does not support reflinks, or the files
+        // are on different filesystems
+        Err(
+            nix::errno::Errno::EOPNOTSUPP
+            | nix::errno::Errno::ENOTTY
+            | nix::errno::Errno::EXDEV
+            | nix::errno::Errno::EINVAL,
+        ) => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    // copy_file_range still avoids moving the data through userspace,
+    // and can share blocks on some filesystems where FICLONE cannot
+    let len = src.metadata()?.len();
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = usize::try_from(remaining).unwrap_or(usize::MAX);
+        match nix::fcntl::copy_file_range(src, None, dst, None, chunk) {
+            Ok(0) => break,
+            Ok(copied) => remaining = remaining.saturating_sub(copied as u64),
+            Err(
+                nix::errno::Errno::ENOSYS
+                | nix::errno::Errno::EOPNOTSUPP
+                | nix::errno::Errno::EXDEV
+                | nix::errno::Errno::EINVAL,
+            ) if remaining == len => {
+                std::io::copy(&mut &*src, &mut &*dst)?;
+                break;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(false)
 }
 
 /// Walks down a filesystem tree, opening permissions on each file before removing
@@ -1011,6 +1283,17 @@ pub async fn open_perms_and_remove_all(root: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Check the render catalog for a completed render, falling back to
+/// looking on disk for renders that are not in the catalog.
+async fn is_render_completed(render_store: &RenderStore, digest: &encoding::Digest) -> bool {
+    match render_store.catalog.get(digest).await {
+        Ok(Some(_)) => return true,
+        Ok(None) => {}
+        Err(err) => tracing::warn!(?err, "failed to read render catalog"),
+    }
+    was_render_completed(render_store.renders.build_digest_path(digest)).await
+}
+
 async fn was_render_completed<P: AsRef<Path>>(render_path: P) -> bool {
     tokio::fs::try_exists(render_path).await.unwrap_or_default()
 }
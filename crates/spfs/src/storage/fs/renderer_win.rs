@@ -13,9 +13,9 @@ use tokio::sync::Semaphore;
 
 use crate::prelude::*;
 use crate::runtime::makedirs_with_perms;
-use crate::storage::fs::{OpenFsRepository, RenderReporter, SilentRenderReporter};
+use crate::storage::fs::{OpenFsRepository, RenderReporter, RenderStore, SilentRenderReporter};
 use crate::storage::LocalRepository;
-use crate::{encoding, graph, tracking, Error, OsError, Result};
+use crate::{encoding, get_config, graph, tracking, Error, OsError, Result};
 
 #[cfg(test)]
 #[path = "./renderer_test.rs"]
@@ -29,11 +29,26 @@ pub const DEFAULT_MAX_CONCURRENT_BLOBS: usize = 100;
 /// See: [`Renderer::with_max_concurrent_branches`]
 pub const DEFAULT_MAX_CONCURRENT_BRANCHES: usize = 5;
 
-#[derive(Debug, Copy, Clone, strum::EnumString, strum::VariantNames, strum::IntoStaticStr)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::EnumString,
+    strum::VariantNames,
+    strum::IntoStaticStr,
+)]
 pub enum RenderType {
     HardLink,
     HardLinkNoProxy,
     Copy,
+    /// Clone the payload's data blocks into a new file where the
+    /// filesystem supports it (eg: btrfs, xfs), otherwise copy it.
+    ///
+    /// Like a copy, each rendered file has its own owner and
+    /// permissions, but like a hard link it takes no extra space.
+    Reflink,
 }
 
 impl OpenFsRepository {
@@ -45,12 +60,10 @@ impl OpenFsRepository {
     }
 
     pub async fn has_rendered_manifest(&self, digest: encoding::Digest) -> bool {
-        let renders = match &self.renders {
-            Some(render_store) => &render_store.renders,
-            None => return false,
-        };
-        let rendered_dir = renders.build_digest_path(&digest);
-        was_render_completed(rendered_dir).await
+        match &self.renders {
+            Some(render_store) => is_render_completed(render_store, &digest).await,
+            None => false,
+        }
     }
 
     pub fn iter_rendered_manifests<'db>(
@@ -79,10 +92,14 @@ impl OpenFsRepository {
 
     /// Remove the identified render from this storage.
     pub async fn remove_rendered_manifest(&self, digest: crate::encoding::Digest) -> Result<()> {
-        let renders = match &self.renders {
-            Some(render_store) => &render_store.renders,
+        let render_store = match &self.renders {
+            Some(render_store) => render_store,
             None => return Ok(()),
         };
+        // the render is removed from the catalog first so that it can
+        // never be found there after it's been removed from disk
+        render_store.catalog.record_removed(digest).await?;
+        let renders = &render_store.renders;
         let rendered_dirpath = renders.build_digest_path(&digest);
         let workdir = renders.workdir();
         makedirs_with_perms(&workdir, renders.directory_permissions).map_err(|source| {
@@ -114,11 +131,18 @@ impl OpenFsRepository {
         older_than: DateTime<Utc>,
         digest: encoding::Digest,
     ) -> Result<bool> {
-        let renders = match &self.renders {
-            Some(render_store) => &render_store.renders,
+        let render_store = match &self.renders {
+            Some(render_store) => render_store,
             None => return Ok(false),
         };
-        let rendered_dirpath = renders.build_digest_path(&digest);
+        if let Some(record) = render_store.catalog.get(&digest).await? {
+            if record.last_used >= older_than {
+                return Ok(false);
+            }
+            self.remove_rendered_manifest(digest).await?;
+            return Ok(true);
+        }
+        let rendered_dirpath = render_store.renders.build_digest_path(&digest);
 
         let metadata = match tokio::fs::symlink_metadata(&rendered_dirpath).await {
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
@@ -178,11 +202,18 @@ pub struct Renderer<'repo, Repo, Reporter: RenderReporter = SilentRenderReporter
 }
 
 impl<'repo, Repo> Renderer<'repo, Repo, SilentRenderReporter> {
+    /// Create a renderer for the given repository.
+    ///
+    /// The number of blobs rendered concurrently defaults to the
+    /// `render.max_concurrent_blobs` value from the spfs config.
     pub fn new(repo: &'repo Repo) -> Self {
+        let max_concurrent_blobs = get_config()
+            .map(|config| config.render.max_concurrent_blobs)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_BLOBS);
         Self {
             repo,
             reporter: Arc::new(SilentRenderReporter),
-            blob_semaphore: BlobSemaphore(Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_BLOBS))),
+            blob_semaphore: BlobSemaphore(Arc::new(Semaphore::new(max_concurrent_blobs))),
             max_concurrent_branches: DEFAULT_MAX_CONCURRENT_BRANCHES,
         }
     }
@@ -294,9 +325,13 @@ where
         render_type: Option<RenderType>,
     ) -> Result<PathBuf> {
         let render_store = self.repo.render_store()?;
-        let rendered_dirpath = render_store.renders.build_digest_path(&manifest.digest()?);
-        if was_render_completed(&rendered_dirpath).await {
+        let digest = manifest.digest()?;
+        let rendered_dirpath = render_store.renders.build_digest_path(&digest);
+        if is_render_completed(render_store, &digest).await {
             tracing::trace!(path = ?rendered_dirpath, "render already completed");
+            if let Err(err) = render_store.catalog.record_used(digest).await {
+                tracing::warn!(?err, "failed to record use of render in catalog");
+            }
             return Ok(rendered_dirpath);
         }
         tracing::trace!(path = ?rendered_dirpath, "rendering manifest...");
@@ -310,7 +345,9 @@ where
         self.render_manifest_into_dir(
             manifest,
             &working_dir,
-            render_type.unwrap_or(RenderType::HardLink),
+            render_type
+                .or_else(|| get_config().ok().and_then(|c| c.storage.render_type))
+                .unwrap_or(RenderType::HardLink),
         )
         .await
         .map_err(|err| {
@@ -346,6 +383,11 @@ where
             },
         }
 
+        let size = manifest.iter_entries().map(|entry| entry.size()).sum();
+        if let Err(err) = render_store.catalog.record_completed(digest, size).await {
+            tracing::warn!(?err, "failed to record completed render in catalog");
+        }
+
         Ok(rendered_dirpath)
     }
 
@@ -400,6 +442,17 @@ pub async fn open_perms_and_remove_all(root: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Check the render catalog for a completed render, falling back to
+/// looking on disk for renders that are not in the catalog.
+async fn is_render_completed(render_store: &RenderStore, digest: &encoding::Digest) -> bool {
+    match render_store.catalog.get(digest).await {
+        Ok(Some(_)) => return true,
+        Ok(None) => {}
+        Err(err) => tracing::warn!(?err, "failed to read render catalog"),
+    }
+    was_render_completed(render_store.renders.build_digest_path(digest)).await
+}
+
 async fn was_render_completed<P: AsRef<Path>>(render_path: P) -> bool {
     tokio::fs::try_exists(render_path).await.unwrap_or_default()
 }
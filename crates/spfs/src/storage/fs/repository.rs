@@ -9,29 +9,43 @@ use std::io::Write;
 #[cfg(unix)]
 use std::os::unix::prelude::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
 
 use arc_swap::ArcSwap;
 
 use super::hash_store::PROXY_DIRNAME;
 use super::migrations::{MigrationError, MigrationResult};
-use super::FsHashStore;
+use super::{FsHashStore, RenderCatalog};
 use crate::config::{pathbuf_deserialize_with_tilde_expansion, ToAddress};
 use crate::runtime::makedirs_with_perms;
 use crate::storage::prelude::*;
 use crate::storage::{
+    chunking,
+    ClientRequirements,
     LocalRepository,
     OpenRepositoryError,
     OpenRepositoryResult,
     TagNamespace,
     TagNamespaceBuf,
 };
-use crate::{Error, Result};
+use crate::tracking::BlobRead;
+use crate::{encoding, graph, Error, Result};
 
 /// The directory name within the repo where durable runtimes keep
 /// their upper path roots and upper/work directories.
 pub const DURABLE_EDITS_DIR: &str = "durable_edits";
 
+/// The file within the repo that declares the algorithm used
+/// to digest payloads. Repositories without this file use the
+/// default algorithm.
+pub const DIGEST_ALGORITHM_FILE: &str = "DIGEST_ALGORITHM";
+
+/// The file within the repo that declares the client version and
+/// features required to use it. Repositories without this file
+/// can be used by any compatible version of spfs.
+pub const CLIENT_REQUIREMENTS_FILE: &str = "CLIENT_REQUIREMENTS";
+
 /// Configuration for an fs repository
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct Config {
@@ -63,6 +77,9 @@ pub struct Params {
     #[serde(default)]
     pub lazy: bool,
     pub tag_namespace: Option<TagNamespaceBuf>,
+    /// The algorithm to declare when creating a new repository,
+    /// existing repositories always use the one they declared
+    pub digest_algorithm: Option<encoding::DigestAlgorithm>,
 }
 
 #[async_trait::async_trait]
@@ -88,6 +105,7 @@ impl FromUrl for Config {
 pub struct RenderStore {
     pub proxy: FsHashStore,
     pub renders: FsHashStore,
+    pub catalog: RenderCatalog,
 }
 
 impl RenderStore {
@@ -96,7 +114,11 @@ impl RenderStore {
         let renders_dir = root.join("renders").join(username);
         FsHashStore::open(renders_dir.join(PROXY_DIRNAME))
             .and_then(|proxy| {
-                FsHashStore::open(&renders_dir).map(|renders| RenderStore { proxy, renders })
+                FsHashStore::open(&renders_dir).map(|renders| RenderStore {
+                    proxy,
+                    catalog: RenderCatalog::new(renders.root()),
+                    renders,
+                })
             })
             .map_err(|source| Error::FailedToOpenRepository {
                 repository: format!("<Render Storage for {}>", username.display()),
@@ -110,6 +132,7 @@ impl Clone for RenderStore {
         Self {
             proxy: FsHashStore::open_unchecked(self.proxy.root()),
             renders: FsHashStore::open_unchecked(self.renders.root()),
+            catalog: self.catalog.clone(),
         }
     }
 }
@@ -166,6 +189,16 @@ impl FsRepository {
         )))))
     }
 
+    /// Open a filesystem repository, creating it if necessary
+    /// with the given payload digest algorithm
+    pub async fn create_with_digest_algorithm<P: AsRef<Path>>(
+        root: P,
+        digest_algorithm: encoding::DigestAlgorithm,
+    ) -> OpenRepositoryResult<Self> {
+        let repo = OpenFsRepository::create_with_digest_algorithm(root, digest_algorithm).await?;
+        Ok(repo.into())
+    }
+
     // Open a repository over the given directory, which must already
     // exist and be properly setup as a repository
     pub async fn open<P: AsRef<Path>>(root: P) -> OpenRepositoryResult<Self> {
@@ -221,6 +254,18 @@ impl FsRepository {
         }
     }
 
+    /// The algorithm used to digest payloads in this repository
+    pub fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        match &**self.0.load() {
+            InnerFsRepository::Open(repo) => repo.payloads.digest_algorithm,
+            InnerFsRepository::Closed(config) => read_digest_algorithm(&config.path)
+                .ok()
+                .flatten()
+                .or(config.params.digest_algorithm)
+                .unwrap_or_default(),
+        }
+    }
+
     pub fn get_tag_namespace(&self) -> Option<Cow<'_, TagNamespace>> {
         match &**self.0.load() {
             InnerFsRepository::Open(repo) => repo
@@ -262,10 +307,15 @@ impl BlobStorage for FsRepository {}
 impl ManifestStorage for FsRepository {}
 impl LayerStorage for FsRepository {}
 impl PlatformStorage for FsRepository {}
+#[async_trait::async_trait]
 impl Repository for FsRepository {
     fn address(&self) -> url::Url {
         url::Url::from_directory_path(self.root()).unwrap()
     }
+
+    async fn commit_blob(&self, reader: Pin<Box<dyn BlobRead>>) -> Result<encoding::Digest> {
+        self.opened().await?.commit_blob(reader).await
+    }
 }
 
 impl std::fmt::Debug for FsRepository {
@@ -294,7 +344,11 @@ impl FromConfig for OpenFsRepository {
 
     async fn from_config(config: Self::Config) -> crate::storage::OpenRepositoryResult<Self> {
         let repo = if config.params.create {
-            Self::create(&config.path).await
+            Self::create_with_digest_algorithm(
+                &config.path,
+                config.params.digest_algorithm.unwrap_or_default(),
+            )
+            .await
         } else {
             Self::open(&config.path).await
         };
@@ -310,7 +364,10 @@ impl Clone for OpenFsRepository {
         let root = self.root.clone();
         Self {
             objects: FsHashStore::open_unchecked(root.join("objects")),
-            payloads: FsHashStore::open_unchecked(root.join("payloads")),
+            payloads: FsHashStore {
+                digest_algorithm: self.payloads.digest_algorithm,
+                ..FsHashStore::open_unchecked(root.join("payloads"))
+            },
             renders: self.renders.clone(),
             root,
             tag_namespace: self.tag_namespace.clone(),
@@ -341,6 +398,7 @@ impl OpenFsRepository {
                 create: false,
                 lazy: false,
                 tag_namespace: self.tag_namespace.clone(),
+                digest_algorithm: None,
             },
         }
         .to_address()
@@ -354,6 +412,18 @@ impl OpenFsRepository {
 
     /// Establish a new filesystem repository
     pub async fn create<P: AsRef<Path>>(root: P) -> OpenRepositoryResult<Self> {
+        Self::create_with_digest_algorithm(root, encoding::DigestAlgorithm::default()).await
+    }
+
+    /// Establish a new filesystem repository that digests its
+    /// payloads with the given algorithm.
+    ///
+    /// If the repository already exists, it continues to
+    /// use the algorithm that it was created with.
+    pub async fn create_with_digest_algorithm<P: AsRef<Path>>(
+        root: P,
+        digest_algorithm: encoding::DigestAlgorithm,
+    ) -> OpenRepositoryResult<Self> {
         let root = root.as_ref();
         // avoid creating any blocking tasks so as to not spawn
         // threads for the case where this repo is being opened as
@@ -383,11 +453,20 @@ impl OpenFsRepository {
         }
 
         set_last_migration(&root, None).await?;
+        write_digest_algorithm_if_missing(&root, digest_algorithm)?;
         // Safety: we canonicalized `root` and we just changed the repo
         // `VERSION` to our version, so it is compatible.
         // FIXME: No attempt to check if the repo already existed and is
         // actually incompatible.
-        unsafe { Self::open_unchecked(root) }
+        let repo = unsafe { Self::open_unchecked(&root)? };
+        if repo.payloads.digest_algorithm != digest_algorithm {
+            tracing::debug!(
+                repo = %root.display(),
+                "Existing repository uses {} digests, ignoring the requested {digest_algorithm}",
+                repo.payloads.digest_algorithm
+            );
+        }
+        Ok(repo)
     }
 
     /// Return the configured tag namespace, if any.
@@ -449,9 +528,16 @@ impl OpenFsRepository {
     unsafe fn open_unchecked<P: AsRef<Path>>(root: P) -> OpenRepositoryResult<Self> {
         let root = root.as_ref();
         let username = whoami::username();
+        // checked before anything else is read, since an older
+        // client may not understand the rest of the repository
+        read_client_requirements(root)?
+            .unwrap_or_default()
+            .check(&url::Url::from_directory_path(root).unwrap())?;
+        let mut payloads = FsHashStore::open(root.join("payloads"))?;
+        payloads.digest_algorithm = read_digest_algorithm(root)?.unwrap_or_default();
         Ok(Self {
             objects: FsHashStore::open(root.join("objects"))?,
-            payloads: FsHashStore::open(root.join("payloads"))?,
+            payloads,
             renders: RenderStore::for_user(root, username).ok(),
             root: root.to_owned(),
             tag_namespace: None,
@@ -475,6 +561,23 @@ impl OpenFsRepository {
         set_last_migration(self.root(), Some(version)).await
     }
 
+    /// The client version and features required to use this repository.
+    pub fn client_requirements(&self) -> OpenRepositoryResult<ClientRequirements> {
+        Ok(read_client_requirements(&self.root)?.unwrap_or_default())
+    }
+
+    /// Declare the client version and features required to use this repository.
+    ///
+    /// Clients that do not meet these requirements will fail to open
+    /// the repository, so this should only be changed once all clients
+    /// that need access have been upgraded.
+    pub fn set_client_requirements(
+        &self,
+        requirements: &ClientRequirements,
+    ) -> OpenRepositoryResult<()> {
+        write_client_requirements(&self.root, requirements)
+    }
+
     /// True if this repo is setup to generate local manifest renders.
     pub fn has_renders(&self) -> bool {
         self.renders.is_some()
@@ -520,7 +623,10 @@ impl OpenFsRepository {
                     username,
                     Self {
                         objects: FsHashStore::open_unchecked(self.root.join("objects")),
-                        payloads: FsHashStore::open_unchecked(self.root.join("payloads")),
+                        payloads: FsHashStore {
+                            digest_algorithm: self.payloads.digest_algorithm,
+                            ..FsHashStore::open_unchecked(self.root.join("payloads"))
+                        },
                         renders: self
                             .renders
                             .as_ref()
@@ -538,10 +644,40 @@ impl BlobStorage for OpenFsRepository {}
 impl ManifestStorage for OpenFsRepository {}
 impl LayerStorage for OpenFsRepository {}
 impl PlatformStorage for OpenFsRepository {}
+#[async_trait::async_trait]
 impl Repository for OpenFsRepository {
     fn address(&self) -> url::Url {
         url::Url::from_directory_path(self.root()).unwrap()
     }
+
+    /// Commit the data from 'reader' as a blob in this repository.
+    ///
+    /// Large payloads are split into chunks when enabled
+    /// in the config, see [`chunking::should_chunk`].
+    async fn commit_blob(&self, reader: Pin<Box<dyn BlobRead>>) -> Result<encoding::Digest> {
+        // Safety: it is unsafe to write data without also creating a blob
+        // to track that payload, which is exactly what this function is doing
+        let (digest, size) = unsafe { self.write_data(reader).await? };
+        if !chunking::should_chunk(size) {
+            self.write_object(&graph::Blob::new(digest, size)).await?;
+            return Ok(digest);
+        }
+        match self.read_blob(digest).await {
+            Ok(existing) if existing.chunks().is_some() => {
+                // the full payload is not needed for blobs that
+                // were already stored as chunks
+                self.remove_payload(digest).await?;
+            }
+            Ok(_) => {}
+            Err(Error::UnknownObject(_)) => {
+                let blob = chunking::chunk_payload(self, digest).await?;
+                self.write_object(&blob).await?;
+                self.remove_payload(digest).await?;
+            }
+            Err(err) => return Err(err),
+        }
+        Ok(digest)
+    }
 }
 
 impl std::fmt::Debug for OpenFsRepository {
@@ -651,3 +787,126 @@ fn write_version_file<P: AsRef<Path>>(root: P, version: &semver::Version) -> Mig
     })?;
     Ok(())
 }
+
+/// Read the digest algorithm declared by the repository with the given root directory.
+///
+/// Return None if no algorithm file was found, or was empty.
+pub fn read_digest_algorithm<P: AsRef<Path>>(
+    root: P,
+) -> OpenRepositoryResult<Option<encoding::DigestAlgorithm>> {
+    // this is read synchronously for the same reasons
+    // that a repository is opened without blocking tasks
+    let path = root.as_ref().join(DIGEST_ALGORITHM_FILE);
+    let algorithm = match std::fs::read_to_string(&path) {
+        Ok(algorithm) => algorithm,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(OpenRepositoryError::InvalidDigestAlgorithm {
+                path,
+                source: Box::new(err),
+            })
+        }
+    };
+    let algorithm = algorithm.trim();
+    if algorithm.is_empty() {
+        return Ok(None);
+    }
+    algorithm.parse().map(Some).map_err(|err: encoding::Error| {
+        OpenRepositoryError::InvalidDigestAlgorithm {
+            path,
+            source: Box::new(err),
+        }
+    })
+}
+
+/// Read the client requirements declared by the repository with the given root directory.
+///
+/// Return None if no requirements file was found.
+pub fn read_client_requirements<P: AsRef<Path>>(
+    root: P,
+) -> OpenRepositoryResult<Option<ClientRequirements>> {
+    // this is read synchronously for the same reasons
+    // that a repository is opened without blocking tasks
+    let path = root.as_ref().join(CLIENT_REQUIREMENTS_FILE);
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(OpenRepositoryError::InvalidClientRequirements {
+                path,
+                source: Box::new(err),
+            })
+        }
+    };
+    serde_yaml::from_reader(file).map(Some).map_err(|err| {
+        OpenRepositoryError::InvalidClientRequirements {
+            path,
+            source: Box::new(err),
+        }
+    })
+}
+
+/// Replace the client requirements of the repository with the given root directory.
+fn write_client_requirements<P: AsRef<Path>>(
+    root: P,
+    requirements: &ClientRequirements,
+) -> OpenRepositoryResult<()> {
+    let root = root.as_ref();
+    let path = root.join(CLIENT_REQUIREMENTS_FILE);
+    let not_initialized = |source| OpenRepositoryError::PathNotInitialized {
+        path: path.clone(),
+        source,
+    };
+    // the file is written in full before being moved into place so
+    // that it is never observed partially written by a concurrent reader
+    let mut temp_file = tempfile::NamedTempFile::new_in(root).map_err(not_initialized)?;
+    #[cfg(unix)]
+    temp_file
+        .as_file()
+        .set_permissions(Permissions::from_mode(0o644))
+        .map_err(not_initialized)?;
+    serde_yaml::to_writer(&mut temp_file, requirements).map_err(|err| {
+        OpenRepositoryError::InvalidClientRequirements {
+            path: path.clone(),
+            source: Box::new(err),
+        }
+    })?;
+    temp_file
+        .persist(&path)
+        .map_err(|err| not_initialized(err.error))?;
+    Ok(())
+}
+
+/// Declare the digest algorithm of the repository with the given
+/// root directory, unless it has already declared one.
+fn write_digest_algorithm_if_missing<P: AsRef<Path>>(
+    root: P,
+    algorithm: encoding::DigestAlgorithm,
+) -> OpenRepositoryResult<()> {
+    let root = root.as_ref();
+    let path = root.join(DIGEST_ALGORITHM_FILE);
+    if path.exists() {
+        return Ok(());
+    }
+    let not_initialized = |source| OpenRepositoryError::PathNotInitialized {
+        path: path.clone(),
+        source,
+    };
+    // the file is written in full before being moved into place so
+    // that it is never observed empty by a concurrent reader
+    let mut temp_file = tempfile::NamedTempFile::new_in(root).map_err(not_initialized)?;
+    #[cfg(unix)]
+    temp_file
+        .as_file()
+        .set_permissions(Permissions::from_mode(0o644))
+        .map_err(not_initialized)?;
+    temp_file
+        .write_all(algorithm.name().as_bytes())
+        .map_err(not_initialized)?;
+    match temp_file.persist_noclobber(&path) {
+        Ok(_) => Ok(()),
+        // another process declared the algorithm first
+        Err(err) if err.error.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        Err(err) => Err(not_initialized(err.error)),
+    }
+}
@@ -15,12 +15,12 @@ use std::task::Poll;
 use close_err::Closable;
 use encoding::{Decodable, Encodable};
 use futures::future::ready;
-use futures::{Future, Stream, StreamExt, TryFutureExt};
+use futures::{Future, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use relative_path::RelativePath;
 use tokio::io::{AsyncRead, AsyncSeek, AsyncWriteExt, ReadBuf};
 
 use super::{FsRepository, OpenFsRepository};
-use crate::storage::tag::{EntryType, TagSpecAndTagStream, TagStream};
+use crate::storage::tag::{next_tag_if_matches, EntryType, TagSpecAndTagStream, TagStream};
 use crate::storage::{
     TagNamespace,
     TagNamespaceBuf,
@@ -30,7 +30,7 @@ use crate::storage::{
 };
 use crate::{encoding, tracking, Error, OsError, OsErrorExt, Result};
 
-const TAG_EXT: &str = "tag";
+pub(super) const TAG_EXT: &str = "tag";
 
 #[async_trait::async_trait]
 impl TagStorage for FsRepository {
@@ -96,6 +96,19 @@ impl TagStorage for FsRepository {
             .await
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        self.opened()
+            .await?
+            .read_tag_page_in_namespace(namespace, tag, offset, limit)
+            .await
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -107,6 +120,18 @@ impl TagStorage for FsRepository {
             .await
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        self.opened()
+            .await?
+            .insert_tag_if_matches_in_namespace(namespace, tag, expected)
+            .await
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -128,6 +153,19 @@ impl TagStorage for FsRepository {
             .remove_tag_in_namespace(namespace, tag)
             .await
     }
+
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        let pattern = pattern.to_owned();
+        let namespace = namespace.map(ToOwned::to_owned);
+        self.opened()
+            .map_ok(move |opened| opened.watch_tags_in_namespace(namespace.as_deref(), &pattern))
+            .try_flatten_stream()
+            .boxed()
+    }
 }
 
 impl OpenFsRepository {
@@ -261,6 +299,22 @@ impl TagStorage for OpenFsRepository {
         }
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        let path = tag.to_path(self.tags_root_in_namespace(namespace));
+        match read_tag_file(path).await {
+            Err(err) if err.is_os_not_found() => Err(Error::UnknownReference(tag.to_string())),
+            Err(err) => Err(err),
+            // skipped tags are seeked over without being decoded
+            Ok(stream) => stream.skip_newest(offset).take(limit).try_collect().await,
+        }
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -316,6 +370,41 @@ impl TagStorage for OpenFsRepository {
         working_file.write_tags(&tags).await
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        let tag_spec = tracking::build_tag_spec(tag.org(), tag.name(), 0)?;
+        let filepath = tag_spec.to_path(self.tags_root_in_namespace(namespace));
+        crate::runtime::makedirs_with_perms(filepath.parent().unwrap(), 0o777).map_err(|err| {
+            Error::StorageWriteError(
+                "insert_tag_if_matches::create_parent",
+                filepath.clone(),
+                err,
+            )
+        })?;
+        // the tag remains locked from reading the current head until the
+        // new tag is written, so that no one else can change it in between
+        let working_file = TagWorkingFile::new(&filepath).await?;
+
+        let mut tags: Vec<tracking::Tag> =
+            match self.read_tag_in_namespace(namespace, &tag_spec).await {
+                Ok(stream) => stream.try_collect().await?,
+                Err(Error::UnknownReference(_)) => Vec::new(),
+                Err(err) => return Err(err),
+            };
+        let Some(new_tag) = next_tag_if_matches(tag, tags.first(), expected)? else {
+            return Ok(tags.remove(0));
+        };
+        // tags are read newest first, but stored oldest first
+        tags.reverse();
+        tags.push(new_tag.clone());
+        working_file.write_tags(&tags).await?;
+        Ok(new_tag)
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -405,6 +494,20 @@ impl TagStorage for OpenFsRepository {
 
         working_file.write_tags(&tags).await
     }
+
+    /// Watch the tag files in this repository for changes.
+    ///
+    /// Changes are received from inotify where it is available, and
+    /// otherwise found by periodically scanning the tag files. Tags that
+    /// are changed by other hosts on a network filesystem may only be
+    /// seen when the repository is polled.
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        super::tag_watch::watch_tags(self.tags_root_in_namespace(namespace), pattern)
+    }
 }
 
 impl TagStorageMut for FsRepository {
@@ -558,7 +661,7 @@ async fn write_tags_to_path(filepath: &PathBuf, tags: &[tracking::Tag]) -> Resul
 ///
 /// This iterator outputs tags from latest to earliest, ie backwards
 /// stating at the latest version of the tag.
-async fn read_tag_file<P>(path: P) -> Result<TagIter>
+pub(super) async fn read_tag_file<P>(path: P) -> Result<TagIter>
 where
     P: AsRef<Path>,
 {
@@ -614,11 +717,12 @@ impl std::fmt::Debug for TagIterState {
 /// starting with the latest version of tag
 ///
 /// Tag files are written
-struct TagIter {
+pub(super) struct TagIter {
     buf: Vec<u8>,
     sizes: Vec<u64>,
     state: Option<TagIterState>,
     filename: PathBuf,
+    skip: usize,
 }
 
 impl TagIter {
@@ -631,8 +735,16 @@ impl TagIter {
                 bytes_read: 0,
             }),
             filename,
+            skip: 0,
         }
     }
+
+    /// Skip over the given number of the newest tags in the file
+    /// without reading or decoding them.
+    fn skip_newest(mut self, count: usize) -> Self {
+        self.skip = count;
+        self
+    }
 }
 
 impl Stream for TagIter {
@@ -669,6 +781,8 @@ impl Stream for TagIter {
                             // if the read completed but did not return anything,
                             // we are to interpret it as an EOF and so will move on to
                             // reading back any tags that were indexed
+                            let keep = self.sizes.len().saturating_sub(self.skip);
+                            self.sizes.truncate(keep);
                             return match self.sizes.pop() {
                                 Some(size) => {
                                     let last_tag_start =
@@ -821,7 +935,10 @@ impl Stream for TagIter {
     }
 }
 
-fn tag_from_path<P: AsRef<Path>, R: AsRef<Path>>(path: P, root: R) -> Result<tracking::TagSpec> {
+pub(super) fn tag_from_path<P: AsRef<Path>, R: AsRef<Path>>(
+    path: P,
+    root: R,
+) -> Result<tracking::TagSpec> {
     let mut path = path.as_ref().to_path_buf();
     let filename = match path.file_stem() {
         Some(stem) => stem.to_owned(),
@@ -0,0 +1,258 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Watches the tag files of a filesystem repository for changes
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use futures::StreamExt;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use super::tag::{read_tag_file, tag_from_path, TAG_EXT};
+use crate::mirror::TagFilter;
+use crate::storage::tag::TagStream;
+use crate::storage::TAG_NAMESPACE_MARKER;
+use crate::{encoding, Error, OsErrorExt, Result};
+
+#[cfg(test)]
+#[path = "./tag_watch_test.rs"]
+mod tag_watch_test;
+
+/// How often the tag files are scanned for changes, which is
+/// the only way to see changes made by other hosts to a
+/// repository on a network filesystem
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait before checking again for new events,
+/// which also bounds how long a dropped watcher lingers
+#[cfg(target_os = "linux")]
+const INOTIFY_WAIT: Duration = Duration::from_millis(200);
+
+/// The state that is carried between items of a tag watch stream
+struct WatchState {
+    root: PathBuf,
+    filter: TagFilter,
+    changes: UnboundedReceiver<Result<PathBuf>>,
+    /// The digest of the latest tag that was seen in each tag file
+    heads: HashMap<PathBuf, encoding::Digest>,
+}
+
+/// Stream the new latest version of each tag under `root` that
+/// matches the given pattern, each time that its tag file changes.
+///
+/// Tag files are being watched by the time that this function
+/// returns, even if the stream has not yet been polled.
+pub(super) fn watch_tags(root: PathBuf, pattern: &str) -> TagStream {
+    let filter = match TagFilter::matching(pattern) {
+        Ok(filter) => filter,
+        Err(err) => return Box::pin(futures::stream::once(async { Err(err) })),
+    };
+    if let Err(err) = crate::runtime::makedirs_with_perms(&root, 0o777) {
+        return Box::pin(futures::stream::once(async move {
+            Err(Error::StorageWriteError(
+                "watch_tags::create_root",
+                root,
+                err,
+            ))
+        }));
+    }
+
+    let (sender, changes) = unbounded_channel();
+    spawn_watcher(root.clone(), sender);
+    let state = WatchState {
+        root,
+        filter,
+        changes,
+        heads: HashMap::new(),
+    };
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        let item = next_changed_tag(&mut state).await?;
+        Some((item, state))
+    }))
+}
+
+/// Wait for the next change to a matching tag, returning
+/// None once the watcher has stopped.
+async fn next_changed_tag(state: &mut WatchState) -> Option<Result<crate::tracking::Tag>> {
+    loop {
+        let path = match state.changes.recv().await? {
+            Ok(path) => path,
+            Err(err) => return Some(Err(err)),
+        };
+        let Ok(spec) = tag_from_path(&path, &state.root) else {
+            continue;
+        };
+        if !state.filter.matches(&spec) {
+            continue;
+        }
+        let head = match read_tag_file(&path).await {
+            Ok(mut tags) => tags.next().await,
+            // the tag was removed entirely
+            Err(err) if err.is_os_not_found() => None,
+            Err(err) => Some(Err(err)),
+        };
+        let tag = match head {
+            Some(Ok(tag)) => tag,
+            Some(Err(err)) => return Some(Err(err)),
+            None => {
+                state.heads.remove(&path);
+                continue;
+            }
+        };
+        let digest = match tag.digest() {
+            Ok(digest) => digest,
+            Err(err) => return Some(Err(err)),
+        };
+        // removing an older version of a tag rewrites
+        // the file without changing the latest version
+        if state.heads.insert(path, digest) == Some(digest) {
+            continue;
+        }
+        return Some(Ok(tag));
+    }
+}
+
+fn is_tag_file(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(TAG_EXT))
+}
+
+/// Tag namespaces are stored as subdirectories of the tags root,
+/// but are not a part of the tags that are being watched
+fn is_namespace_dir(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().contains(TAG_NAMESPACE_MARKER))
+        .unwrap_or(false)
+}
+
+/// Walk the directories under root, excluding any tag namespaces
+fn walk_tags_root(root: &Path) -> impl Iterator<Item = walkdir::DirEntry> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_namespace_dir(entry.path()))
+        .filter_map(|entry| entry.ok())
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_watcher(root: PathBuf, sender: UnboundedSender<Result<PathBuf>>) {
+    use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+
+    let inotify = match Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC) {
+        Ok(inotify) => inotify,
+        Err(err) => {
+            tracing::debug!(?err, "inotify is not available, polling for tag changes");
+            return spawn_polling_watcher(root, sender);
+        }
+    };
+
+    let mut dirs = HashMap::new();
+    add_watches(&inotify, &root, &mut dirs, None);
+
+    // inotify only reports changes made on this host, so the tags
+    // are also polled to catch those written by other hosts. A
+    // change that is reported by both is only streamed once, since
+    // the latest version of the tag will not have changed again
+    spawn_polling_watcher(root, sender.clone());
+
+    std::thread::spawn(move || {
+        while !sender.is_closed() {
+            let events = match inotify.read_events() {
+                Ok(events) => events,
+                Err(nix::errno::Errno::EAGAIN) => {
+                    std::thread::sleep(INOTIFY_WAIT);
+                    continue;
+                }
+                Err(err) => {
+                    let _ = sender.send(Err(Error::Nix(err)));
+                    return;
+                }
+            };
+            for event in events {
+                if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+                    dirs.remove(&event.wd);
+                    continue;
+                }
+                let (Some(dir), Some(name)) = (dirs.get(&event.wd), event.name) else {
+                    continue;
+                };
+                let path = dir.join(name);
+                if event.mask.contains(AddWatchFlags::IN_ISDIR) {
+                    if !is_namespace_dir(&path) {
+                        add_watches(&inotify, &path, &mut dirs, Some(&sender));
+                    }
+                } else if is_tag_file(&path) && !event.mask.contains(AddWatchFlags::IN_CREATE) {
+                    let _ = sender.send(Ok(path));
+                }
+            }
+        }
+    });
+}
+
+/// Watch the given directory and all directories under it,
+/// sending any tag files that are found if requested.
+#[cfg(target_os = "linux")]
+fn add_watches(
+    inotify: &nix::sys::inotify::Inotify,
+    dir: &Path,
+    dirs: &mut HashMap<nix::sys::inotify::WatchDescriptor, PathBuf>,
+    found: Option<&UnboundedSender<Result<PathBuf>>>,
+) {
+    use nix::sys::inotify::AddWatchFlags;
+
+    // tag files are replaced by renaming a working file over
+    // them, but may also be written in place
+    let flags =
+        AddWatchFlags::IN_CLOSE_WRITE | AddWatchFlags::IN_MOVED_TO | AddWatchFlags::IN_CREATE;
+    for entry in walk_tags_root(dir) {
+        let path = entry.path();
+        if entry.file_type().is_dir() {
+            match inotify.add_watch(path, flags) {
+                Ok(wd) => {
+                    dirs.insert(wd, path.to_owned());
+                }
+                Err(err) => tracing::warn!(?err, ?path, "Failed to watch tags directory"),
+            }
+        } else if let Some(sender) = found {
+            // files can be written into a new directory
+            // before it has been watched
+            if is_tag_file(path) {
+                let _ = sender.send(Ok(path.to_owned()));
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn spawn_watcher(root: PathBuf, sender: UnboundedSender<Result<PathBuf>>) {
+    spawn_polling_watcher(root, sender)
+}
+
+/// Watch for changes by periodically comparing the
+/// modification time of every tag file under root.
+fn spawn_polling_watcher(root: PathBuf, sender: UnboundedSender<Result<PathBuf>>) {
+    let scan = move || -> HashMap<PathBuf, SystemTime> {
+        walk_tags_root(&root)
+            .filter(|entry| entry.file_type().is_file() && is_tag_file(entry.path()))
+            .filter_map(|entry| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((entry.into_path(), modified))
+            })
+            .collect()
+    };
+    let mut previous = scan();
+    std::thread::spawn(move || {
+        while !sender.is_closed() {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = scan();
+            for (path, modified) in current.iter() {
+                if previous.get(path) != Some(modified) {
+                    let _ = sender.send(Ok(path.clone()));
+                }
+            }
+            previous = current;
+        }
+    });
+}
@@ -0,0 +1,37 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use tokio::sync::mpsc::unbounded_channel;
+
+use super::{spawn_polling_watcher, POLL_INTERVAL};
+use crate::fixtures::*;
+
+#[rstest]
+#[tokio::test]
+async fn test_polling_watcher_sees_changed_tags(tmpdir: tempfile::TempDir) {
+    let root = tmpdir.path().to_owned();
+    let existing = root.join("spi/stable/existing.tag");
+    ensure(existing.clone(), "old");
+
+    let (sender, mut changes) = unbounded_channel();
+    spawn_polling_watcher(root.clone(), sender);
+
+    // files that are not tags, and tags that existed before
+    // the watch began, are not reported until they change
+    let written = root.join("spi/stable/written.tag");
+    ensure(root.join("spi/stable/notes.txt"), "ignored");
+    ensure(written.clone(), "new");
+
+    let path = tokio::time::timeout(POLL_INTERVAL * 5, changes.recv())
+        .await
+        .expect("should be notified of the written tag file")
+        .expect("watcher should still be running")
+        .expect("watcher should not fail");
+    assert_eq!(path, written);
+    assert!(
+        changes.try_recv().is_err(),
+        "should not report unchanged or non-tag files"
+    );
+}
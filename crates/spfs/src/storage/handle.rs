@@ -117,6 +117,19 @@ impl TagStorage for RepositoryHandle {
         })
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        each_variant!(self, repo, {
+            repo.read_tag_page_in_namespace(namespace, tag, offset, limit)
+                .await
+        })
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -127,6 +140,18 @@ impl TagStorage for RepositoryHandle {
         })
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        each_variant!(self, repo, {
+            repo.insert_tag_if_matches_in_namespace(namespace, tag, expected)
+                .await
+        })
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -146,6 +171,16 @@ impl TagStorage for RepositoryHandle {
             repo.remove_tag_in_namespace(namespace, tag).await
         })
     }
+
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        each_variant!(self, repo, {
+            repo.watch_tags_in_namespace(namespace, pattern)
+        })
+    }
 }
 
 impl TagStorageMut for RepositoryHandle {
@@ -166,6 +201,10 @@ impl TagStorageMut for RepositoryHandle {
 
 #[async_trait::async_trait]
 impl PayloadStorage for RepositoryHandle {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        each_variant!(self, repo, { repo.digest_algorithm() })
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         each_variant!(self, repo, { repo.has_payload(digest).await })
     }
@@ -206,10 +245,18 @@ impl DatabaseView for RepositoryHandle {
         each_variant!(self, repo, { repo.has_object(digest).await })
     }
 
+    async fn has_objects(&self, digests: &[encoding::Digest]) -> Vec<bool> {
+        each_variant!(self, repo, { repo.has_objects(digests).await })
+    }
+
     async fn read_object(&self, digest: encoding::Digest) -> Result<graph::Object> {
         each_variant!(self, repo, { repo.read_object(digest).await })
     }
 
+    async fn read_objects(&self, digests: &[encoding::Digest]) -> Result<Vec<graph::Object>> {
+        each_variant!(self, repo, { repo.read_objects(digests).await })
+    }
+
     fn find_digests(
         &self,
         search_criteria: graph::DigestSearchCriteria,
@@ -232,6 +279,10 @@ impl Database for RepositoryHandle {
         each_variant!(self, repo, { repo.write_object(obj).await })
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        each_variant!(self, repo, { repo.write_objects(objects).await })
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         each_variant!(self, repo, { repo.remove_object(digest).await })
     }
@@ -336,6 +387,19 @@ impl TagStorage for Arc<RepositoryHandle> {
         })
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        each_variant!(&**self, repo, {
+            repo.read_tag_page_in_namespace(namespace, tag, offset, limit)
+                .await
+        })
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -346,6 +410,18 @@ impl TagStorage for Arc<RepositoryHandle> {
         })
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        each_variant!(&**self, repo, {
+            repo.insert_tag_if_matches_in_namespace(namespace, tag, expected)
+                .await
+        })
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -365,10 +441,24 @@ impl TagStorage for Arc<RepositoryHandle> {
             repo.remove_tag_in_namespace(namespace, tag).await
         })
     }
+
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        each_variant!(&**self, repo, {
+            repo.watch_tags_in_namespace(namespace, pattern)
+        })
+    }
 }
 
 #[async_trait::async_trait]
 impl PayloadStorage for Arc<RepositoryHandle> {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        each_variant!(&**self, repo, { repo.digest_algorithm() })
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         each_variant!(&**self, repo, { repo.has_payload(digest).await })
     }
@@ -409,10 +499,18 @@ impl DatabaseView for Arc<RepositoryHandle> {
         each_variant!(&**self, repo, { repo.has_object(digest).await })
     }
 
+    async fn has_objects(&self, digests: &[encoding::Digest]) -> Vec<bool> {
+        each_variant!(&**self, repo, { repo.has_objects(digests).await })
+    }
+
     async fn read_object(&self, digest: encoding::Digest) -> Result<graph::Object> {
         each_variant!(&**self, repo, { repo.read_object(digest).await })
     }
 
+    async fn read_objects(&self, digests: &[encoding::Digest]) -> Result<Vec<graph::Object>> {
+        each_variant!(&**self, repo, { repo.read_objects(digests).await })
+    }
+
     fn find_digests(
         &self,
         search_criteria: graph::DigestSearchCriteria,
@@ -435,6 +533,10 @@ impl Database for Arc<RepositoryHandle> {
         each_variant!(&**self, repo, { repo.write_object(obj).await })
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        each_variant!(&**self, repo, { repo.write_objects(objects).await })
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         each_variant!(&**self, repo, { repo.remove_object(digest).await })
     }
@@ -3,12 +3,14 @@
 // https://github.com/spkenv/spk
 
 mod blob;
+pub mod chunking;
 mod error;
 mod layer;
 mod manifest;
 pub mod payload;
 mod platform;
 mod repository;
+mod requirements;
 mod tag;
 mod tag_namespace;
 
@@ -33,6 +35,7 @@ pub use payload::PayloadStorage;
 pub use platform::PlatformStorage;
 pub use proxy::{Config, ProxyRepository};
 pub use repository::{LocalRepository, Repository};
+pub use requirements::{ClientRequirements, SUPPORTED_FEATURES};
 pub use tag::{EntryType, TagStorage, TagStorageMut};
 pub use tag_namespace::{TagNamespace, TagNamespaceBuf, TAG_NAMESPACE_MARKER};
 
@@ -16,6 +16,9 @@ mod payload_test;
 /// Stores arbitrary binary data payloads using their content digest.
 #[async_trait::async_trait]
 pub trait PayloadStorage: Sync + Send {
+    /// The algorithm used to calculate the digest of payloads in this storage.
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm;
+
     /// Iterate all the payloads in this storage.
     fn iter_payload_digests(&self) -> Pin<Box<dyn Stream<Item = Result<encoding::Digest>> + Send>>;
 
@@ -52,6 +55,10 @@ pub trait PayloadStorage: Sync + Send {
 
 #[async_trait::async_trait]
 impl<T: PayloadStorage> PayloadStorage for &T {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        PayloadStorage::digest_algorithm(&**self)
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         PayloadStorage::has_payload(&**self, digest).await
     }
@@ -121,3 +121,35 @@ async fn test_payloads_iter(
     actual.sort();
     assert_eq!(actual, expected, "iter should return all stored digests");
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_payload_digest_algorithm(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let algorithm = crate::encoding::DigestAlgorithm::Blake3;
+    let repo = super::fs::FsRepository::create_with_digest_algorithm(tmpdir.path(), algorithm)
+        .await
+        .expect("failed to create repository");
+    assert_eq!(repo.digest_algorithm(), algorithm);
+
+    let bytes = "simple string data".as_bytes();
+    // Safety: we are intentionally calling this function to test it
+    let (digest, _) = unsafe {
+        repo.write_data(Box::pin(bytes))
+            .await
+            .expect("failed to write payload data")
+    };
+    let expected = crate::encoding::Hasher::hash_async_reader_with(algorithm, bytes)
+        .await
+        .unwrap();
+    assert_eq!(digest, expected, "payload should be digested with blake3");
+
+    let reopened = super::fs::FsRepository::create(tmpdir.path())
+        .await
+        .expect("failed to reopen repository");
+    assert_eq!(
+        reopened.digest_algorithm(),
+        algorithm,
+        "an existing repository should keep its digest algorithm"
+    );
+}
@@ -98,6 +98,10 @@ where
         self.inner.write_object(obj).await
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        self.inner.write_objects(objects).await
+    }
+
     async fn remove_object(&self, _digest: encoding::Digest) -> crate::Result<()> {
         Err(Error::RepositoryIsPinned)
     }
@@ -116,6 +120,10 @@ impl<T> PayloadStorage for PinnedRepository<T>
 where
     T: PayloadStorage + 'static,
 {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        self.inner.digest_algorithm()
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         self.inner.has_payload(digest).await
     }
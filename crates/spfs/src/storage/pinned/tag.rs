@@ -200,6 +200,15 @@ where
         Err(Error::RepositoryIsPinned)
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        _namespace: Option<&TagNamespace>,
+        _tag: &tracking::Tag,
+        _expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        Err(Error::RepositoryIsPinned)
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         _namespace: Option<&TagNamespace>,
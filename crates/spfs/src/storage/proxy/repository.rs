@@ -170,6 +170,10 @@ impl graph::Database for ProxyRepository {
         Ok(())
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        self.primary.write_objects(objects).await
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         self.primary.remove_object(digest).await?;
         Ok(())
@@ -189,6 +193,10 @@ impl graph::Database for ProxyRepository {
 
 #[async_trait::async_trait]
 impl PayloadStorage for ProxyRepository {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        self.primary.digest_algorithm()
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         if self.primary.has_payload(digest).await {
             return true;
@@ -299,6 +307,17 @@ impl TagStorage for ProxyRepository {
         Ok(())
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        self.primary
+            .insert_tag_if_matches_in_namespace(namespace, tag, expected)
+            .await
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -318,6 +337,21 @@ impl TagStorage for ProxyRepository {
         self.primary.remove_tag_in_namespace(namespace, tag).await?;
         Ok(())
     }
+
+    /// Watches the primary and all secondary repositories.
+    ///
+    /// A tag that is synced between these repositories may
+    /// be yielded once for each of them.
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        let streams = std::iter::once(&self.primary)
+            .chain(self.secondary.iter())
+            .map(|repo| repo.watch_tags_in_namespace(namespace, pattern));
+        Box::pin(futures::stream::select_all(streams))
+    }
 }
 
 impl TagStorageMut for ProxyRepository {
@@ -0,0 +1,86 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeSet;
+
+use super::{OpenRepositoryError, OpenRepositoryResult};
+
+#[cfg(test)]
+#[path = "./requirements_test.rs"]
+mod requirements_test;
+
+/// The optional repository features that this version of spfs understands.
+///
+/// A repository can list any of these as required, so that clients
+/// which do not understand them refuse to use it instead of failing
+/// later on data that they cannot read.
+pub const SUPPORTED_FEATURES: &[&str] = &[
+    // blobs whose payloads are stored as a list of smaller chunks
+    "chunked-blobs",
+    // payloads that are digested with blake3 rather than sha256
+    "blake3-digests",
+    // tags that are stored under a tag namespace
+    "tag-namespaces",
+];
+
+/// What a client must support in order to safely use a repository.
+///
+/// These are published by the repository so that mixed-version rollouts
+/// fail early with clear guidance rather than with confusing errors
+/// about data that an older client cannot understand.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ClientRequirements {
+    /// The oldest version of spk/spfs that can use the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_client_version: Option<semver::Version>,
+    /// The features that a client must support to use the repository
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub features: BTreeSet<String>,
+}
+
+impl ClientRequirements {
+    /// True if no specific client version or features are required.
+    pub fn is_empty(&self) -> bool {
+        self.min_client_version.is_none() && self.features.is_empty()
+    }
+
+    /// Check that this version of spfs meets these requirements.
+    pub fn check(&self, address: &url::Url) -> OpenRepositoryResult<()> {
+        let client_version =
+            semver::Version::parse(crate::VERSION).expect("crate::VERSION is a valid semver value");
+        self.check_client(address, &client_version, SUPPORTED_FEATURES)
+    }
+
+    /// Check that a client of the given version, supporting
+    /// the given features, meets these requirements.
+    pub fn check_client(
+        &self,
+        address: &url::Url,
+        client_version: &semver::Version,
+        supported_features: &[&str],
+    ) -> OpenRepositoryResult<()> {
+        if let Some(min_client_version) = &self.min_client_version {
+            if client_version < min_client_version {
+                return Err(OpenRepositoryError::ClientVersionTooOld {
+                    address: address.to_string(),
+                    client_version: client_version.clone(),
+                    min_client_version: min_client_version.clone(),
+                });
+            }
+        }
+        let unsupported = self
+            .features
+            .iter()
+            .filter(|f| !supported_features.contains(&f.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            return Err(OpenRepositoryError::UnsupportedFeatures {
+                address: address.to_string(),
+                features: unsupported,
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{ClientRequirements, SUPPORTED_FEATURES};
+use crate::fixtures::*;
+use crate::storage::OpenRepositoryError;
+
+fn address() -> url::Url {
+    "file:///tmp/repo".parse().unwrap()
+}
+
+#[rstest]
+#[case("", "1.0.0", true)]
+#[case("1.0.0", "1.0.0", true)]
+#[case("1.0.0", "1.2.0", true)]
+#[case("1.2.0", "1.0.0", false)]
+#[case("1.0.0", "1.0.0-beta.1", false)]
+fn test_requirements_min_client_version(
+    #[case] min_version: &str,
+    #[case] client_version: &str,
+    #[case] expected: bool,
+) {
+    let requirements = ClientRequirements {
+        min_client_version: (!min_version.is_empty()).then(|| min_version.parse().unwrap()),
+        ..Default::default()
+    };
+    let result = requirements.check_client(
+        &address(),
+        &client_version.parse().unwrap(),
+        SUPPORTED_FEATURES,
+    );
+    match (result, expected) {
+        (Ok(()), true) => {}
+        (Err(OpenRepositoryError::ClientVersionTooOld { .. }), false) => {}
+        (result, _) => {
+            panic!("unexpected result for {client_version} >= {min_version}: {result:?}")
+        }
+    }
+}
+
+#[rstest]
+fn test_requirements_unsupported_features() {
+    let requirements = ClientRequirements {
+        features: ["chunked-blobs", "from-the-future"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        ..Default::default()
+    };
+    let client_version = crate::VERSION.parse().unwrap();
+    let err = requirements
+        .check_client(&address(), &client_version, &["chunked-blobs"])
+        .expect_err("should fail when a feature is not supported");
+    match err {
+        OpenRepositoryError::UnsupportedFeatures { features, .. } => {
+            assert_eq!(features, vec!["from-the-future".to_string()]);
+        }
+        err => panic!("expected unsupported features error, got: {err:?}"),
+    }
+    requirements
+        .check_client(
+            &address(),
+            &client_version,
+            &["chunked-blobs", "from-the-future"],
+        )
+        .expect("should succeed when all features are supported");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_fs_repo_checks_client_requirements(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let root = tmpdir.path().join("repo");
+    let repo = crate::storage::fs::OpenFsRepository::create(&root)
+        .await
+        .unwrap();
+    assert!(repo.client_requirements().unwrap().is_empty());
+
+    let requirements = ClientRequirements {
+        min_client_version: Some("9999.0.0".parse().unwrap()),
+        ..Default::default()
+    };
+    repo.set_client_requirements(&requirements).unwrap();
+    assert_eq!(repo.client_requirements().unwrap(), requirements);
+
+    let err = crate::storage::fs::OpenFsRepository::open(&root)
+        .await
+        .expect_err("should not open a repository that requires a newer client");
+    assert!(
+        matches!(err, OpenRepositoryError::ClientVersionTooOld { .. }),
+        "expected client too old error, got: {err:?}"
+    );
+}
@@ -11,35 +11,103 @@ use proto::RpcResult;
 use crate::graph::{self, ObjectProto};
 use crate::{encoding, proto, storage, Result};
 
+#[cfg(test)]
+#[path = "./database_test.rs"]
+mod database_test;
+
+/// The largest total size of the objects sent in a single request to
+/// write objects, which leaves room under the 4MiB message size limit
+/// that grpc servers apply by default.
+const WRITE_OBJECTS_BATCH_BYTES: usize = 3 * 1024 * 1024;
+
 #[async_trait::async_trait]
 impl graph::DatabaseView for super::RpcRepository {
     async fn has_object(&self, digest: encoding::Digest) -> bool {
         let request = proto::HasObjectRequest {
             digest: Some(digest.into()),
         };
-        self.db_client
-            .clone()
-            .has_object(request)
+        self.retry_policy
+            .run("check object", || {
+                let mut client = self.db_client.clone();
+                let request = request.clone();
+                async move { Ok(client.has_object(request).await?) }
+            })
             .await
             .ok()
             .map(|resp| resp.into_inner().exists)
             .unwrap_or(false)
     }
 
+    async fn has_objects(&self, digests: &[encoding::Digest]) -> Vec<bool> {
+        if digests.is_empty() {
+            return Vec::new();
+        }
+        let request = proto::HasObjectsRequest {
+            digests: digests.iter().map(|digest| (*digest).into()).collect(),
+        };
+        let res = self
+            .retry_policy
+            .run("check objects", || {
+                let mut client = self.db_client.clone();
+                let request = request.clone();
+                async move { Ok(client.has_objects(request).await?) }
+            })
+            .await;
+        match res {
+            Ok(resp) if resp.get_ref().exists.len() == digests.len() => resp.into_inner().exists,
+            _ => {
+                // older servers can only check one object at a time
+                futures::future::join_all(digests.iter().map(|digest| self.has_object(*digest)))
+                    .await
+            }
+        }
+    }
+
     async fn read_object(&self, digest: encoding::Digest) -> Result<graph::Object> {
         let request = proto::ReadObjectRequest {
             digest: Some(digest.into()),
         };
         let obj = self
-            .db_client
-            .clone()
-            .read_object(request)
+            .retry_policy
+            .run("read object", || {
+                let mut client = self.db_client.clone();
+                let request = request.clone();
+                async move { Ok(client.read_object(request).await?) }
+            })
             .await?
             .into_inner()
             .to_result()?;
         obj.try_into()
     }
 
+    async fn read_objects(&self, digests: &[encoding::Digest]) -> Result<Vec<graph::Object>> {
+        if digests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = proto::ReadObjectsRequest {
+            digests: digests.iter().map(|digest| (*digest).into()).collect(),
+        };
+        let res = self
+            .retry_policy
+            .run("read objects", || {
+                let mut client = self.db_client.clone();
+                let request = request.clone();
+                async move { Ok(client.read_objects(request).await?) }
+            })
+            .await;
+        let objects = match res {
+            Err(crate::Error::Tonic(status)) if status.code() == tonic::Code::Unimplemented => {
+                // older servers can only read one object at a time
+                return futures::future::try_join_all(
+                    digests.iter().map(|digest| self.read_object(*digest)),
+                )
+                .await;
+            }
+            res => res?.into_inner().to_result()?.objects,
+        };
+        objects.into_iter().map(TryInto::try_into).collect()
+    }
+
     fn find_digests(
         &self,
         search_criteria: graph::DigestSearchCriteria,
@@ -72,15 +140,54 @@ impl graph::Database for super::RpcRepository {
         let request = proto::WriteObjectRequest {
             object: Some(obj.into()),
         };
-        self.db_client
-            .clone()
-            .write_object(request)
+        self.retry_policy
+            .run("write object", || {
+                let mut client = self.db_client.clone();
+                let request = request.clone();
+                async move { Ok(client.write_object(request).await?) }
+            })
             .await?
             .into_inner()
             .to_result()?;
         Ok(())
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        let mut written = 0;
+        for batch in split_write_batches(objects, WRITE_OBJECTS_BATCH_BYTES) {
+            if let [obj] = batch {
+                self.write_object(obj).await?;
+                written += 1;
+                continue;
+            }
+            let request = proto::WriteObjectsRequest {
+                objects: batch.iter().map(Into::into).collect(),
+            };
+            let res = self
+                .retry_policy
+                .run("write objects", || {
+                    let mut client = self.db_client.clone();
+                    let request = request.clone();
+                    async move { Ok(client.write_objects(request).await?) }
+                })
+                .await;
+            match res {
+                Err(crate::Error::Tonic(status)) if status.code() == tonic::Code::Unimplemented => {
+                    // older servers can only write one object at a time
+                    for obj in &objects[written..] {
+                        self.write_object(obj).await?;
+                    }
+                    return Ok(());
+                }
+                res => {
+                    res?.into_inner().to_result()?;
+                }
+            }
+            written += batch.len();
+        }
+        Ok(())
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         let request = proto::RemoveObjectRequest {
             digest: Some(digest.into()),
@@ -117,3 +224,31 @@ impl storage::PlatformStorage for super::RpcRepository {}
 impl storage::LayerStorage for super::RpcRepository {}
 impl storage::ManifestStorage for super::RpcRepository {}
 impl storage::BlobStorage for super::RpcRepository {}
+
+/// Split objects into batches of no more than `max_bytes` in total, so
+/// that each can be written without exceeding the server's message size.
+///
+/// Objects that are too large to share a request are put in a batch alone.
+fn split_write_batches(
+    objects: &[graph::Object],
+    max_bytes: usize,
+) -> impl Iterator<Item = &[graph::Object]> {
+    let mut remaining = objects;
+    std::iter::from_fn(move || {
+        if remaining.is_empty() {
+            return None;
+        }
+        let mut size = 0;
+        let mut count = 0;
+        for obj in remaining {
+            size += obj.inner_bytes().len();
+            if count > 0 && size > max_bytes {
+                break;
+            }
+            count += 1;
+        }
+        let (batch, rest) = remaining.split_at(count);
+        remaining = rest;
+        Some(batch)
+    })
+}
@@ -0,0 +1,50 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::split_write_batches;
+use crate::encoding::prelude::*;
+use crate::fixtures::*;
+use crate::graph::{Blob, Object};
+
+fn blobs(count: usize) -> Vec<Object> {
+    (0..count)
+        .map(|i| Blob::new(random_digest(), i as u64).into_object())
+        .collect()
+}
+
+#[rstest]
+fn test_split_write_batches_limits_size() {
+    let objects = blobs(10);
+    let size = objects[0].inner_bytes().len();
+    let batches = split_write_batches(&objects, size * 3).collect::<Vec<_>>();
+    assert_eq!(
+        batches.iter().map(|b| b.len()).collect::<Vec<_>>(),
+        vec![3, 3, 3, 1]
+    );
+    let digests = |objects: &[Object]| {
+        objects
+            .iter()
+            .map(|o| o.digest().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        digests(&batches.concat()),
+        digests(&objects),
+        "every object should be written in order"
+    );
+}
+
+#[rstest]
+fn test_split_write_batches_large_objects_alone() {
+    let objects = blobs(3);
+    let batches = split_write_batches(&objects, 1).collect::<Vec<_>>();
+    assert_eq!(
+        batches.iter().map(|b| b.len()).collect::<Vec<_>>(),
+        vec![1, 1, 1],
+        "objects larger than the limit should still be written"
+    );
+    assert_eq!(split_write_batches(&[], 1).count(), 0);
+}
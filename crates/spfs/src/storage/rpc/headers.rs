@@ -0,0 +1,81 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use hyper::http::{header, HeaderMap, HeaderName, HeaderValue};
+use tonic::metadata::MetadataMap;
+
+use super::Params;
+
+#[cfg(test)]
+#[path = "./headers_test.rs"]
+mod headers_test;
+
+/// The channel that the grpc clients of an rpc repository use,
+/// which adds the configured headers to each request
+pub type Channel =
+    tonic::service::interceptor::InterceptedService<tonic::transport::Channel, RequestHeaders>;
+
+/// The headers that are sent with every grpc and http request made
+/// to an rpc repository, such as the credentials for its server.
+#[derive(Clone, Debug, Default)]
+pub struct RequestHeaders {
+    headers: Arc<HeaderMap>,
+}
+
+impl RequestHeaders {
+    /// Collect the token and headers of the given connection parameters.
+    pub fn from_params(params: &Params) -> Result<Self, String> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in params.headers.iter() {
+            let name = HeaderName::try_from(name.as_str())
+                .map_err(|err| format!("Invalid header name '{name}': {err}"))?;
+            let value = HeaderValue::try_from(value.as_str())
+                .map_err(|err| format!("Invalid value for header '{name}': {err}"))?;
+            headers.append(name, value);
+        }
+        if let Some(token) = &params.token {
+            let mut value = HeaderValue::try_from(format!("Bearer {token}"))
+                .map_err(|err| format!("Invalid repository token: {err}"))?;
+            // keeps the token out of any debug output
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        Ok(Self {
+            headers: Arc::new(headers),
+        })
+    }
+
+    /// Add these headers to an http request.
+    pub fn apply(
+        &self,
+        mut builder: hyper::http::request::Builder,
+    ) -> hyper::http::request::Builder {
+        if let Some(headers) = builder.headers_mut() {
+            for (name, value) in self.headers.iter() {
+                headers.append(name, value.clone());
+            }
+        }
+        builder
+    }
+}
+
+impl tonic::service::Interceptor for RequestHeaders {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        if self.headers.is_empty() {
+            return Ok(request);
+        }
+        // grpc metadata are sent as the headers of the http request
+        let mut headers = std::mem::take(request.metadata_mut()).into_headers();
+        for (name, value) in self.headers.iter() {
+            headers.append(name, value.clone());
+        }
+        *request.metadata_mut() = MetadataMap::from_headers(headers);
+        Ok(request)
+    }
+}
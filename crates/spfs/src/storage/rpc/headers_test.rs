@@ -0,0 +1,55 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use tonic::service::Interceptor;
+
+use super::RequestHeaders;
+use crate::storage::rpc::Params;
+
+fn params() -> Params {
+    Params {
+        token: Some("secret".to_string()),
+        headers: [("x-site".to_string(), "studio".to_string())].into(),
+        ..Default::default()
+    }
+}
+
+#[rstest]
+fn test_request_headers_grpc() {
+    let mut headers = RequestHeaders::from_params(&params()).unwrap();
+    let request = headers.call(tonic::Request::new(())).unwrap();
+    let metadata = request.metadata();
+    assert_eq!(metadata.get("authorization").unwrap(), "Bearer secret");
+    assert_eq!(metadata.get("x-site").unwrap(), "studio");
+}
+
+#[rstest]
+fn test_request_headers_http() {
+    let headers = RequestHeaders::from_params(&params()).unwrap();
+    let request = headers
+        .apply(hyper::Request::builder().uri("/payload"))
+        .body(())
+        .unwrap();
+    assert_eq!(
+        request.headers().get(hyper::header::AUTHORIZATION).unwrap(),
+        "Bearer secret"
+    );
+    assert_eq!(request.headers().get("x-site").unwrap(), "studio");
+}
+
+#[rstest]
+fn test_request_headers_hide_token() {
+    let headers = RequestHeaders::from_params(&params()).unwrap();
+    assert!(!format!("{headers:?}").contains("secret"));
+}
+
+#[rstest]
+fn test_request_headers_invalid() {
+    let params = Params {
+        headers: [("bad header".to_string(), "value".to_string())].into(),
+        ..Default::default()
+    };
+    assert!(RequestHeaders::from_params(&params).is_err());
+}
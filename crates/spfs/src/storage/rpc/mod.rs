@@ -5,8 +5,14 @@
 //! Storage implementation which is a client of the built-in spfs server
 
 mod database;
+mod headers;
 mod payload;
+mod range;
 mod repository;
+mod retry;
 mod tag;
 
-pub use repository::{Config, Params, RpcRepository};
+pub use headers::{Channel, RequestHeaders};
+pub use range::{ByteRange, RangeRequest};
+pub use repository::{Config, Params, RpcRepository, DEFAULT_DOWNLOAD_CONCURRENCY};
+pub use retry::{RetryPolicy, DEFAULT_RETRY_BACKOFF, MAX_RETRY_BACKOFF};
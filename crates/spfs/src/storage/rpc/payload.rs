@@ -8,19 +8,35 @@ use std::pin::Pin;
 use futures::{Stream, StreamExt, TryStreamExt};
 use prost::Message;
 
+use super::ByteRange;
 use crate::proto::{self, RpcResult};
 use crate::tracking::BlobRead;
 use crate::{encoding, storage, Error, Result};
 
+#[cfg(all(test, feature = "server"))]
+#[path = "./payload_test.rs"]
+mod payload_test;
+
+/// The number of chunks of each segment that are held in memory
+/// while waiting for the earlier segments of a payload to be read
+const SEGMENT_BUFFER_CHUNKS: usize = 16;
+
 #[async_trait::async_trait]
 impl storage::PayloadStorage for super::RpcRepository {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        self.digest_algorithm
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         let request = proto::HasPayloadRequest {
             digest: Some(digest.into()),
         };
-        self.payload_client
-            .clone()
-            .has_payload(request)
+        self.retry_policy
+            .run("check payload", || {
+                let mut client = self.payload_client.clone();
+                let request = request.clone();
+                async move { Ok(client.has_payload(request).await?) }
+            })
             .await
             .ok()
             .map(|resp| resp.into_inner().exists)
@@ -56,7 +72,9 @@ impl storage::PayloadStorage for super::RpcRepository {
             compressed_reader,
             tokio_util::codec::BytesCodec::new(),
         );
-        let request = hyper::Request::builder()
+        let request = self
+            .headers
+            .apply(hyper::Request::builder())
             .method(hyper::Method::POST)
             .header(hyper::http::header::CONTENT_TYPE, "application/x-bzip2")
             .uri(&option.url)
@@ -64,16 +82,15 @@ impl storage::PayloadStorage for super::RpcRepository {
             .map_err(|err| {
                 crate::Error::String(format!("Failed to build upload request: {err:?}"))
             })?;
-        let resp = self.http_client.request(request).await.map_err(|err| {
-            crate::Error::String(format!("Failed to send upload request: {err:?}"))
-        })?;
+        let resp = self
+            .http_client
+            .request(request)
+            .await
+            .map_err(|err| Error::HttpRequest("upload", err))?;
         if !resp.status().is_success() {
             // the server is expected to return all errors via the gRPC message
             // payload in the body. Any other status code is unexpected
-            return Err(crate::Error::String(format!(
-                "Unexpected status code from payload server: {}",
-                resp.status()
-            )));
+            return Err(Error::UnexpectedHttpStatus(resp.status()));
         }
         let bytes = hyper::body::to_bytes(resp.into_body())
             .await
@@ -91,6 +108,85 @@ impl storage::PayloadStorage for super::RpcRepository {
         let request = proto::OpenPayloadRequest {
             digest: Some(digest.into()),
         };
+        let Some(segment_size) = self.download_segment_bytes else {
+            // the download is only retried until the server begins to
+            // respond, after which the payload is streamed to the caller
+            let (resp, url_str) = self
+                .retry_policy
+                .run("download payload", || {
+                    self.start_download(request.clone(), None)
+                })
+                .await?;
+            let stream = open_download_stream(resp)?;
+            return Ok((stream, url_str.into()));
+        };
+
+        let first = ByteRange {
+            start: 0,
+            end: segment_size - 1,
+        };
+        let (resp, url_str) = self
+            .retry_policy
+            .run("download payload", || {
+                self.start_download(request.clone(), Some(first))
+            })
+            .await?;
+        if resp.status() != hyper::StatusCode::PARTIAL_CONTENT {
+            // the server does not support range requests, or
+            // decided to send the whole payload anyway
+            let stream = open_download_stream(resp)?;
+            return Ok((stream, url_str.into()));
+        }
+        let size = resp
+            .headers()
+            .get(hyper::http::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(ByteRange::parse_content_range)
+            .map(|(_, size)| size)
+            .ok_or_else(|| {
+                Error::String("Payload server sent a partial download without its size".into())
+            })?;
+
+        // the remaining segments are each downloaded in their own task
+        // so that they are fetched concurrently while the earlier
+        // segments are being read
+        let repo = self.clone();
+        let url = url_str.clone();
+        let remaining = futures::stream::iter(ByteRange::segments(size, segment_size))
+            .skip(1)
+            .map(move |range| futures::future::ready(repo.download_segment(url.clone(), range)))
+            .buffered(self.download_concurrency)
+            .flatten();
+        let stream = body_to_stream(resp.into_body()).chain(remaining);
+        let reader = tokio::io::BufReader::new(tokio_util::io::StreamReader::new(stream));
+        Ok((Box::pin(reader), url_str.into()))
+    }
+
+    async fn remove_payload(&self, digest: encoding::Digest) -> Result<()> {
+        let request = proto::RemovePayloadRequest {
+            digest: Some(digest.into()),
+        };
+        self.payload_client
+            .clone()
+            .remove_payload(request)
+            .await?
+            .into_inner()
+            .to_result()?;
+        Ok(())
+    }
+}
+
+impl super::RpcRepository {
+    /// Request the location of a payload, and begin downloading it.
+    ///
+    /// When a range is given, only those bytes of the uncompressed
+    /// payload are requested, though the server may still choose to
+    /// send the whole payload.
+    async fn start_download(
+        &self,
+        request: proto::OpenPayloadRequest,
+        range: Option<ByteRange>,
+    ) -> Result<(hyper::http::Response<hyper::Body>, String)> {
         let option = self
             .payload_client
             .clone()
@@ -98,45 +194,150 @@ impl storage::PayloadStorage for super::RpcRepository {
             .await?
             .into_inner()
             .to_result()?;
-        let url_str = option
-            .locations
-            .first()
-            .ok_or_else(|| crate::Error::String("upload option gave no locations to try".into()))?;
-        let req = hyper::Request::builder()
-            .uri(url_str)
-            .method(hyper::http::Method::GET)
-            .header(hyper::http::header::ACCEPT, "application/x-bzip2")
+        let url_str =
+            option.locations.into_iter().next().ok_or_else(|| {
+                crate::Error::String("upload option gave no locations to try".into())
+            })?;
+        let mut builder = self
+            .headers
+            .apply(hyper::Request::builder())
+            .uri(&url_str)
+            .method(hyper::http::Method::GET);
+        builder = match range {
+            // ranges always refer to the uncompressed payload data
+            Some(range) => builder.header(hyper::http::header::RANGE, range.to_range_header()),
+            None => builder.header(hyper::http::header::ACCEPT, "application/x-bzip2"),
+        };
+        let req = builder
             .header(hyper::http::header::ACCEPT, "application/octet-stream")
             .body(hyper::Body::empty())
             .map_err(|err| {
                 crate::Error::String(format!("Failed to build download request: {err:?}"))
             })?;
-        let resp = self.http_client.request(req).await.map_err(|err| {
-            crate::Error::String(format!("Failed to send download request: {err:?}"))
-        })?;
+        let resp = self
+            .http_client
+            .request(req)
+            .await
+            .map_err(|err| Error::HttpRequest("download", err))?;
         if !resp.status().is_success() {
             // the server is expected to return all errors via the gRPC message
             // payload in the body. Any other status code is unexpected
-            return Err(crate::Error::String(format!(
-                "Unexpected status code from payload server: {}",
-                resp.status()
-            )));
+            return Err(Error::UnexpectedHttpStatus(resp.status()));
         }
-        let stream = open_download_stream(resp)?;
-        Ok((stream, url_str.into()))
+        Ok((resp, url_str))
     }
 
-    async fn remove_payload(&self, digest: encoding::Digest) -> Result<()> {
-        let request = proto::RemovePayloadRequest {
-            digest: Some(digest.into()),
-        };
-        self.payload_client
-            .clone()
-            .remove_payload(request)
-            .await?
-            .into_inner()
-            .to_result()?;
-        Ok(())
+    /// Download one segment of a payload from the given location.
+    ///
+    /// The segment is downloaded in its own task and streamed through
+    /// a bounded channel, so that only a few chunks of it are held in
+    /// memory until they are read.
+    fn download_segment(
+        &self,
+        url: String,
+        range: ByteRange,
+    ) -> impl Stream<Item = std::io::Result<bytes::Bytes>> + Send + 'static {
+        let (sender, receiver) = tokio::sync::mpsc::channel(SEGMENT_BUFFER_CHUNKS);
+        let repo = self.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = repo.send_segment(&url, range, &sender).await {
+                let err = std::io::Error::new(std::io::ErrorKind::Other, err);
+                // the reader may already be gone, in which case
+                // there is no one left to report the error to
+                let _ = sender.send(Err(err)).await;
+            }
+        });
+        tokio_stream::wrappers::ReceiverStream::new(receiver)
+    }
+
+    /// Send the bytes of one segment of a payload as they arrive.
+    ///
+    /// If the download is interrupted, it is resumed from the first
+    /// byte that was not yet received, at most as many times as the
+    /// retry policy allows.
+    async fn send_segment(
+        &self,
+        url: &str,
+        range: ByteRange,
+        sender: &tokio::sync::mpsc::Sender<std::io::Result<bytes::Bytes>>,
+    ) -> Result<()> {
+        let mut received = 0;
+        let mut interruptions = 0;
+        loop {
+            let remaining = ByteRange {
+                start: range.start + received,
+                end: range.end,
+            };
+            let resp = self
+                .retry_policy
+                .run("download payload segment", || {
+                    self.request_segment(url, remaining)
+                })
+                .await?;
+            let mut body = resp.into_body();
+            let interrupted = loop {
+                let chunk = match body.next().await {
+                    None => break None,
+                    Some(Err(err)) => break Some(err),
+                    Some(Ok(chunk)) => chunk,
+                };
+                received += chunk.len() as u64;
+                if received > range.size() {
+                    break None;
+                }
+                if sender.send(Ok(chunk)).await.is_err() {
+                    // the reader was dropped and does not need the rest
+                    return Ok(());
+                }
+            };
+            match interrupted {
+                None if received == range.size() => return Ok(()),
+                None => {
+                    return Err(Error::String(format!(
+                        "Payload server sent {received} bytes for a segment of {} bytes",
+                        range.size()
+                    )))
+                }
+                Some(err) if interruptions >= self.retry_policy.retries => {
+                    return Err(Error::HttpRequest("download", err));
+                }
+                Some(err) => {
+                    interruptions += 1;
+                    tracing::debug!(
+                        "payload segment interrupted after {received} bytes, resuming [{interruptions}/{}]: {err}",
+                        self.retry_policy.retries
+                    );
+                }
+            }
+        }
+    }
+
+    /// Request the given bytes of a payload from the given location.
+    async fn request_segment(
+        &self,
+        url: &str,
+        range: ByteRange,
+    ) -> Result<hyper::http::Response<hyper::Body>> {
+        let req = self
+            .headers
+            .apply(hyper::Request::builder())
+            .uri(url)
+            .method(hyper::http::Method::GET)
+            .header(hyper::http::header::RANGE, range.to_range_header())
+            .header(hyper::http::header::ACCEPT, "application/octet-stream")
+            .body(hyper::Body::empty())
+            .map_err(|err| {
+                crate::Error::String(format!("Failed to build download request: {err:?}"))
+            })?;
+        let resp = self
+            .http_client
+            .request(req)
+            .await
+            .map_err(|err| Error::HttpRequest("download", err))?;
+        if resp.status() != hyper::StatusCode::PARTIAL_CONTENT {
+            return Err(Error::UnexpectedHttpStatus(resp.status()));
+        }
+        Ok(resp)
     }
 }
 
@@ -157,11 +358,15 @@ fn open_download_stream(
     }
 }
 
-fn body_to_reader(body: hyper::Body) -> Pin<Box<impl BlobRead>> {
+fn body_to_stream(
+    body: hyper::Body,
+) -> impl Stream<Item = std::io::Result<bytes::Bytes>> + Send + Sync + 'static {
     // the stream must return io errors in order to be converted to a reader
-    let mapped_stream =
-        body.map(|chunk| chunk.map_err(|e| futures::io::Error::new(std::io::ErrorKind::Other, e)));
-    let stream_reader = tokio_util::io::StreamReader::new(mapped_stream);
+    body.map(|chunk| chunk.map_err(|e| futures::io::Error::new(std::io::ErrorKind::Other, e)))
+}
+
+fn body_to_reader(body: hyper::Body) -> Pin<Box<impl BlobRead>> {
+    let stream_reader = tokio_util::io::StreamReader::new(body_to_stream(body));
     let buffered_reader = tokio::io::BufReader::new(stream_reader);
     Box::pin(buffered_reader)
 }
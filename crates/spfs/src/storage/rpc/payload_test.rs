@@ -0,0 +1,52 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use tokio::io::AsyncReadExt;
+
+use crate::fixtures::*;
+use crate::prelude::*;
+use crate::storage::rpc::RpcRepository;
+
+#[rstest]
+#[case::single_stream(None)]
+#[case::one_segment(Some(4096))]
+#[case::many_segments(Some(7))]
+#[case::exact_segments(Some(10))]
+#[tokio::test]
+async fn test_payload_round_trip(#[case] segment_size: Option<u64>) {
+    init_logging();
+    let tmprepo = tmprepo("rpc").await;
+    let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+
+    // Safety: we are intentionally calling this function to test it
+    let (digest, size) = unsafe {
+        tmprepo
+            .write_data(Box::pin(std::io::Cursor::new(data.clone())))
+            .await
+            .expect("failed to upload payload data")
+    };
+    assert_eq!(size, data.len() as u64);
+
+    let mut address = tmprepo.address();
+    if let Some(segment_size) = segment_size {
+        address.set_query(Some(&format!(
+            "download_segment_bytes={segment_size}&download_concurrency=2"
+        )));
+    }
+    let client = RpcRepository::from_url(&address)
+        .await
+        .expect("failed to connect to the test server");
+
+    let mut actual = Vec::new();
+    client
+        .open_payload(digest)
+        .await
+        .expect("failed to download payload")
+        .0
+        .read_to_end(&mut actual)
+        .await
+        .expect("failed to read downloaded payload");
+    assert_eq!(actual, data, "downloaded payload should match the upload");
+}
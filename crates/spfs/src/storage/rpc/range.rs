@@ -0,0 +1,121 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+#[cfg(test)]
+#[path = "./range_test.rs"]
+mod range_test;
+
+/// An inclusive range of bytes within a payload, as used by
+/// the http `Range` and `Content-Range` headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ByteRange {
+    /// The offset of the first byte in the range
+    pub start: u64,
+    /// The offset of the last byte in the range
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// The number of bytes in this range
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Split a payload of the given size into consecutive
+    /// ranges of at most `segment_size` bytes each.
+    pub fn segments(size: u64, segment_size: u64) -> Vec<Self> {
+        let segment_size = segment_size.max(1);
+        (0..size)
+            .step_by(segment_size as usize)
+            .map(|start| Self {
+                start,
+                end: start.saturating_add(segment_size).min(size) - 1,
+            })
+            .collect()
+    }
+
+    /// The value of a `Range` header that requests these bytes
+    pub fn to_range_header(&self) -> String {
+        format!("bytes={}-{}", self.start, self.end)
+    }
+
+    /// The value of a `Content-Range` header that describes these
+    /// bytes of a payload with the given total size
+    pub fn to_content_range_header(&self, size: u64) -> String {
+        format!("bytes {}-{}/{size}", self.start, self.end)
+    }
+
+    /// Parse the value of a `Content-Range` header, returning the
+    /// range of bytes and the total size of the payload.
+    pub fn parse_content_range(value: &str) -> Option<(Self, u64)> {
+        let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+        let (start, end) = range.split_once('-')?;
+        let range = Self {
+            start: start.trim().parse().ok()?,
+            end: end.trim().parse().ok()?,
+        };
+        let size = size.trim().parse().ok()?;
+        if range.end < range.start || range.end >= size {
+            return None;
+        }
+        Some((range, size))
+    }
+}
+
+/// What to send in response to the `Range` header of a download
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The whole payload should be sent
+    Full,
+    /// Only the given bytes of the payload should be sent
+    Partial(ByteRange),
+    /// None of the requested bytes exist in the payload
+    Unsatisfiable,
+}
+
+impl RangeRequest {
+    /// Interpret the value of a `Range` header for a payload of the given size.
+    ///
+    /// Only a single range of bytes is supported. Any other request,
+    /// including one that is not valid, is answered with the whole
+    /// payload, as is any request for an empty payload.
+    pub fn parse(value: &str, size: u64) -> Self {
+        let Some(spec) = value.trim().strip_prefix("bytes=") else {
+            return Self::Full;
+        };
+        if spec.contains(',') || size == 0 {
+            return Self::Full;
+        }
+        let Some((start, end)) = spec.split_once('-') else {
+            return Self::Full;
+        };
+        let last = size - 1;
+        match (start.trim(), end.trim()) {
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(0) => Self::Unsatisfiable,
+                Ok(suffix) => Self::Partial(ByteRange {
+                    start: size.saturating_sub(suffix),
+                    end: last,
+                }),
+                Err(_) => Self::Full,
+            },
+            (start, end) => {
+                let Ok(start) = start.parse::<u64>() else {
+                    return Self::Full;
+                };
+                let end = match end {
+                    "" => last,
+                    end => match end.parse::<u64>() {
+                        Ok(end) if end >= start => end.min(last),
+                        _ => return Self::Full,
+                    },
+                };
+                if start > last {
+                    return Self::Unsatisfiable;
+                }
+                Self::Partial(ByteRange { start, end })
+            }
+        }
+    }
+}
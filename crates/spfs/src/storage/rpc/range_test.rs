@@ -0,0 +1,61 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{ByteRange, RangeRequest};
+
+fn partial(start: u64, end: u64) -> RangeRequest {
+    RangeRequest::Partial(ByteRange { start, end })
+}
+
+#[rstest]
+#[case::bounded("bytes=0-9", partial(0, 9))]
+#[case::open_ended("bytes=90-", partial(90, 99))]
+#[case::suffix("bytes=-10", partial(90, 99))]
+#[case::suffix_longer_than_payload("bytes=-500", partial(0, 99))]
+#[case::end_past_payload("bytes=50-500", partial(50, 99))]
+#[case::start_past_payload("bytes=100-200", RangeRequest::Unsatisfiable)]
+#[case::empty_suffix("bytes=-0", RangeRequest::Unsatisfiable)]
+#[case::multiple_ranges("bytes=0-9,20-29", RangeRequest::Full)]
+#[case::reversed("bytes=9-0", RangeRequest::Full)]
+#[case::other_unit("items=0-9", RangeRequest::Full)]
+#[case::invalid("bytes=a-b", RangeRequest::Full)]
+fn test_range_request_parse(#[case] header: &str, #[case] expected: RangeRequest) {
+    assert_eq!(RangeRequest::parse(header, 100), expected);
+}
+
+#[rstest]
+fn test_range_request_empty_payload() {
+    assert_eq!(
+        RangeRequest::parse("bytes=0-9", 0),
+        RangeRequest::Full,
+        "an empty payload should always be sent in full"
+    );
+}
+
+#[rstest]
+fn test_byte_range_segments() {
+    let segments = ByteRange::segments(25, 10);
+    assert_eq!(
+        segments,
+        vec![
+            ByteRange { start: 0, end: 9 },
+            ByteRange { start: 10, end: 19 },
+            ByteRange { start: 20, end: 24 },
+        ]
+    );
+    assert_eq!(segments.iter().map(ByteRange::size).sum::<u64>(), 25);
+    assert!(ByteRange::segments(0, 10).is_empty());
+}
+
+#[rstest]
+fn test_byte_range_content_range_round_trip() {
+    let range = ByteRange { start: 10, end: 19 };
+    let header = range.to_content_range_header(25);
+    assert_eq!(header, "bytes 10-19/25");
+    assert_eq!(ByteRange::parse_content_range(&header), Some((range, 25)));
+    assert_eq!(ByteRange::parse_content_range("bytes */25"), None);
+    assert_eq!(ByteRange::parse_content_range("bytes 10-30/25"), None);
+}
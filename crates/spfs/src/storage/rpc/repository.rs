@@ -2,15 +2,26 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::BTreeMap;
+
 use storage::FromUrl;
 
+use super::{Channel, RequestHeaders, RetryPolicy};
 use crate::config::ToAddress;
 use crate::proto::database_service_client::DatabaseServiceClient;
 use crate::proto::payload_service_client::PayloadServiceClient;
 use crate::proto::repository_client::RepositoryClient;
 use crate::proto::tag_service_client::TagServiceClient;
 use crate::storage::{OpenRepositoryError, OpenRepositoryResult, TagNamespace, TagNamespaceBuf};
-use crate::{proto, storage, Result};
+use crate::{encoding, proto, storage, Result};
+
+#[cfg(test)]
+#[path = "./repository_test.rs"]
+mod repository_test;
+
+/// The number of payload segments that are downloaded at once,
+/// when not otherwise configured
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
 
 /// Configures an rpc repository connection
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -31,6 +42,37 @@ pub struct Params {
     /// Default is no timeout
     pub timeout_ms: Option<u64>,
 
+    /// The number of times that a read request is retried after
+    /// failing because of a dropped connection or timeout
+    ///
+    /// Default is no retries
+    pub retries: Option<u32>,
+
+    /// The delay before the first retry of a failed request,
+    /// which doubles for each retry after that
+    ///
+    /// Default is 250 ms
+    pub retry_backoff_ms: Option<u64>,
+
+    /// Download payloads in segments of this many bytes, which
+    /// are fetched in parallel and retried individually
+    ///
+    /// Default is to download each payload in a single stream
+    pub download_segment_bytes: Option<u64>,
+
+    /// The number of segments of a payload that are downloaded at once
+    ///
+    /// Default is 4
+    pub download_concurrency: Option<usize>,
+
+    /// The algorithm that the remote repository uses to digest payloads,
+    /// which must match that of any repository synced with it
+    ///
+    /// Default is the algorithm advertised by the server, or sha256 for
+    /// servers that do not advertise one. Connecting fails if this is set
+    /// and the server advertises a different algorithm
+    pub digest_algorithm: Option<encoding::DigestAlgorithm>,
+
     /// Maximum message size that the client will accept from the server
     ///
     /// Default is 4 Mb
@@ -43,6 +85,18 @@ pub struct Params {
 
     /// optional tag namespace to use when querying tags
     pub tag_namespace: Option<TagNamespaceBuf>,
+
+    /// A token that is sent as a bearer token in the authorization
+    /// header of every grpc and payload request, for servers that
+    /// require one
+    ///
+    /// Default is no token
+    pub token: Option<String>,
+
+    /// Additional headers that are sent with every grpc
+    /// and payload request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
 }
 
 #[async_trait::async_trait]
@@ -76,11 +130,18 @@ impl ToAddress for Config {
 #[derive(Clone, Debug)]
 pub struct RpcRepository {
     address: url::Url,
-    pub(super) repo_client: RepositoryClient<tonic::transport::Channel>,
-    pub(super) tag_client: TagServiceClient<tonic::transport::Channel>,
-    pub(super) db_client: DatabaseServiceClient<tonic::transport::Channel>,
-    pub(super) payload_client: PayloadServiceClient<tonic::transport::Channel>,
+    pub(super) repo_client: RepositoryClient<Channel>,
+    pub(super) tag_client: TagServiceClient<Channel>,
+    pub(super) db_client: DatabaseServiceClient<Channel>,
+    pub(super) payload_client: PayloadServiceClient<Channel>,
     pub(super) http_client: hyper::Client<hyper::client::HttpConnector, hyper::Body>,
+    /// the headers that are added to each payload http request
+    pub(super) headers: RequestHeaders,
+    pub(super) retry_policy: RetryPolicy,
+    /// the size of the segments that payloads are downloaded in, if any
+    pub(super) download_segment_bytes: Option<u64>,
+    pub(super) download_concurrency: usize,
+    pub(super) digest_algorithm: encoding::DigestAlgorithm,
     /// the namespace to use for tag resolution. If set, then this is treated
     /// as "chroot" of the real tag root.
     tag_namespace: Option<TagNamespaceBuf>,
@@ -111,17 +172,34 @@ impl RpcRepository {
                 address: config.address.to_string(),
                 source,
             })?;
+        let mut retry_policy = RetryPolicy {
+            retries: config.params.retries.unwrap_or_default(),
+            ..Default::default()
+        };
+        if let Some(ms) = config.params.retry_backoff_ms {
+            retry_policy.backoff = std::time::Duration::from_millis(ms);
+        }
         if let Some(ms) = config.params.timeout_ms {
-            endpoint = endpoint.timeout(std::time::Duration::from_millis(ms));
+            let timeout = std::time::Duration::from_millis(ms);
+            endpoint = endpoint.timeout(timeout);
+            retry_policy.timeout = Some(timeout);
         }
-        let channel = match config.params.lazy {
+        let headers = RequestHeaders::from_params(&config.params).map_err(|reason| {
+            OpenRepositoryError::InvalidRequestHeaders {
+                address: config.address.to_string(),
+                reason,
+            }
+        })?;
+        let lazy = config.params.lazy;
+        let channel = match lazy {
             true => endpoint.connect_lazy(),
             false => endpoint.connect().await?,
         };
-        let mut repo_client = RepositoryClient::new(channel.clone());
-        let mut tag_client = TagServiceClient::new(channel.clone());
-        let mut db_client = DatabaseServiceClient::new(channel.clone());
-        let mut payload_client = PayloadServiceClient::new(channel);
+        let mut repo_client = RepositoryClient::with_interceptor(channel.clone(), headers.clone());
+        let mut tag_client = TagServiceClient::with_interceptor(channel.clone(), headers.clone());
+        let mut db_client =
+            DatabaseServiceClient::with_interceptor(channel.clone(), headers.clone());
+        let mut payload_client = PayloadServiceClient::with_interceptor(channel, headers.clone());
         if let Some(max) = config.params.max_decode_message_size_bytes {
             repo_client = repo_client.max_decoding_message_size(max);
             tag_client = tag_client.max_decoding_message_size(max);
@@ -134,15 +212,37 @@ impl RpcRepository {
             db_client = db_client.max_encoding_message_size(max);
             payload_client = payload_client.max_encoding_message_size(max);
         }
-        Ok(Self {
+        let mut repo = Self {
             address: config.to_address().expect("an internally valid config"),
             repo_client,
             tag_client,
             db_client,
             payload_client,
             http_client: hyper::Client::new(),
+            headers,
+            retry_policy,
+            download_segment_bytes: config.params.download_segment_bytes.filter(|b| *b > 0),
+            download_concurrency: config
+                .params
+                .download_concurrency
+                .unwrap_or(DEFAULT_DOWNLOAD_CONCURRENCY)
+                .max(1),
+            digest_algorithm: config.params.digest_algorithm.unwrap_or_default(),
             tag_namespace: config.params.tag_namespace,
-        })
+        };
+        if !lazy {
+            // lazy connections are not checked, since doing so
+            // would require connecting to the server right away
+            if let Some(capabilities) = repo.capabilities().await? {
+                repo.digest_algorithm = negotiate_digest_algorithm(
+                    &repo.address,
+                    config.params.digest_algorithm,
+                    &capabilities.digest_algorithm,
+                )?;
+                requirements_from_capabilities(capabilities)?.check(&repo.address)?;
+            }
+        }
+        Ok(repo)
     }
 
     /// The round-trip time taken to ping this repository over grpc, if successful
@@ -152,6 +252,50 @@ impl RpcRepository {
         Ok(start.elapsed())
     }
 
+    /// The client version and features required to use the remote repository.
+    ///
+    /// Returns None when the server is too old to advertise them.
+    pub async fn client_requirements(
+        &self,
+    ) -> OpenRepositoryResult<Option<storage::ClientRequirements>> {
+        match self.capabilities().await? {
+            Some(capabilities) => requirements_from_capabilities(capabilities).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The capabilities that the remote repository advertises.
+    ///
+    /// Returns None when the server is too old to advertise them.
+    async fn capabilities(&self) -> OpenRepositoryResult<Option<proto::GetCapabilitiesResponse>> {
+        let response = match self
+            .repo_client
+            .clone()
+            .get_capabilities(proto::GetCapabilitiesRequest {})
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(status) if status.code() == tonic::Code::Unimplemented => return Ok(None),
+            Err(status) => {
+                return Err(OpenRepositoryError::FailedToReadCapabilities {
+                    source: Box::new(status),
+                })
+            }
+        };
+        tracing::debug!(
+            server_version = %response.server_version,
+            supported_features = ?response.supported_features,
+            digest_algorithm = %response.digest_algorithm,
+            "read remote repository capabilities"
+        );
+        Ok(Some(response))
+    }
+
+    /// The policy used to retry failed requests to this repository.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
     /// The namespace to use for tag resolution.
     pub fn tag_namespace(&self) -> Option<&TagNamespace> {
         self.tag_namespace.as_deref()
@@ -173,3 +317,44 @@ impl storage::Repository for RpcRepository {
         self.address.clone()
     }
 }
+
+fn requirements_from_capabilities(
+    capabilities: proto::GetCapabilitiesResponse,
+) -> OpenRepositoryResult<storage::ClientRequirements> {
+    storage::ClientRequirements::try_from(capabilities).map_err(|err| {
+        OpenRepositoryError::FailedToReadCapabilities {
+            source: Box::new(err),
+        }
+    })
+}
+
+/// Decide which digest algorithm to use with a remote repository,
+/// given the one that was configured for it, if any, and the one that
+/// its server advertised.
+///
+/// Servers that are too old to advertise an algorithm always use
+/// sha256, unless the client was configured otherwise.
+pub(super) fn negotiate_digest_algorithm(
+    address: &url::Url,
+    configured: Option<encoding::DigestAlgorithm>,
+    advertised: &str,
+) -> OpenRepositoryResult<encoding::DigestAlgorithm> {
+    if advertised.is_empty() {
+        return Ok(configured.unwrap_or_default());
+    }
+    let advertised = advertised
+        .parse::<encoding::DigestAlgorithm>()
+        .map_err(|err| OpenRepositoryError::FailedToReadCapabilities {
+            source: Box::new(err),
+        })?;
+    match configured {
+        Some(configured) if configured != advertised => {
+            Err(OpenRepositoryError::DigestAlgorithmMismatch {
+                address: address.to_string(),
+                configured,
+                advertised,
+            })
+        }
+        _ => Ok(advertised),
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::negotiate_digest_algorithm;
+use crate::encoding::DigestAlgorithm;
+use crate::storage::OpenRepositoryError;
+
+#[rstest]
+#[case::old_server(None, "", DigestAlgorithm::Sha256)]
+#[case::old_server_configured(Some(DigestAlgorithm::Blake3), "", DigestAlgorithm::Blake3)]
+#[case::advertised(None, "blake3", DigestAlgorithm::Blake3)]
+#[case::matching(Some(DigestAlgorithm::Blake3), "blake3", DigestAlgorithm::Blake3)]
+fn test_negotiate_digest_algorithm(
+    #[case] configured: Option<DigestAlgorithm>,
+    #[case] advertised: &str,
+    #[case] expected: DigestAlgorithm,
+) {
+    let address = url::Url::parse("http2://localhost:7737").unwrap();
+    let actual = negotiate_digest_algorithm(&address, configured, advertised)
+        .expect("algorithm should be negotiated");
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn test_negotiate_digest_algorithm_mismatch() {
+    let address = url::Url::parse("http2://localhost:7737").unwrap();
+    let result = negotiate_digest_algorithm(&address, Some(DigestAlgorithm::Sha256), "blake3");
+    assert!(matches!(
+        result,
+        Err(OpenRepositoryError::DigestAlgorithmMismatch { .. })
+    ));
+}
+
+#[rstest]
+fn test_negotiate_digest_algorithm_unknown() {
+    let address = url::Url::parse("http2://localhost:7737").unwrap();
+    let result = negotiate_digest_algorithm(&address, None, "md5");
+    assert!(matches!(
+        result,
+        Err(OpenRepositoryError::FailedToReadCapabilities { .. })
+    ));
+}
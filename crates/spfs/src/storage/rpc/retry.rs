@@ -0,0 +1,88 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./retry_test.rs"]
+mod retry_test;
+
+/// The delay before the first retry, when not otherwise configured
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// The longest delay between two retries of the same operation
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Controls how requests to an rpc repository are retried
+/// when they fail for a reason that may be temporary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of times that a failed request is tried again
+    pub retries: u32,
+    /// The delay before the first retry, which doubles for each
+    /// subsequent retry up to [`MAX_RETRY_BACKOFF`]
+    pub backoff: Duration,
+    /// How long to wait for each attempt before abandoning it
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: DEFAULT_RETRY_BACKOFF,
+            timeout: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Run an operation until it succeeds, fails with an error that
+    /// is not transient, or all of the allowed retries are used.
+    ///
+    /// The operation is identified by name in any error that is
+    /// returned once the retries have been exhausted.
+    pub async fn run<F, Fut, T>(&self, operation: &'static str, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempts = 0;
+        let mut backoff = self.backoff;
+        loop {
+            attempts += 1;
+            let result = match self.timeout {
+                None => f().await,
+                Some(timeout) => match tokio::time::timeout(timeout, f()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(Error::OperationTimedOut { operation, timeout }),
+                },
+            };
+            let err = match result {
+                Ok(value) => return Ok(value),
+                Err(err) if !err.is_transient() => return Err(err),
+                Err(err) => err,
+            };
+            if attempts > self.retries {
+                if self.retries == 0 {
+                    return Err(err);
+                }
+                return Err(Error::RetriesExhausted {
+                    operation,
+                    attempts,
+                    source: Box::new(err),
+                });
+            }
+            tracing::debug!(
+                "{operation} failed, retrying in {backoff:?} [{attempts}/{}]: {err}",
+                self.retries
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+        }
+    }
+}
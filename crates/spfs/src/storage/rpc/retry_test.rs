@@ -0,0 +1,95 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use rstest::rstest;
+
+use super::RetryPolicy;
+use crate::Error;
+
+fn policy(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        retries,
+        backoff: Duration::from_millis(1),
+        timeout: None,
+    }
+}
+
+fn unavailable() -> Error {
+    tonic::Status::unavailable("connection reset").into()
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_retry_until_success() {
+    let calls = AtomicU32::new(0);
+    let result = policy(3)
+        .run("test", || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(unavailable()),
+                n => Ok(n),
+            }
+        })
+        .await
+        .expect("should succeed after retrying");
+    assert_eq!(result, 2);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_retry_exhausted() {
+    let calls = AtomicU32::new(0);
+    let result: crate::Result<()> = policy(2)
+        .run("test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(unavailable())
+        })
+        .await;
+    assert!(
+        matches!(result, Err(Error::RetriesExhausted { attempts: 3, .. })),
+        "expected retries to be exhausted, got: {result:?}"
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_retry_skips_permanent_errors() {
+    let calls = AtomicU32::new(0);
+    let result: crate::Result<()> = policy(3)
+        .run("test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(tonic::Status::not_found("no such tag").into())
+        })
+        .await;
+    assert!(matches!(result, Err(Error::Tonic(_))));
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        1,
+        "errors that are not transient should not be retried"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_retry_timeout() {
+    let mut policy = policy(1);
+    policy.timeout = Some(Duration::from_millis(10));
+    let result: crate::Result<()> = policy
+        .run("test", || async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await;
+    match result {
+        Err(Error::RetriesExhausted { source, .. }) => assert!(
+            matches!(*source, Error::OperationTimedOut { .. }),
+            "expected a timeout, got: {source:?}"
+        ),
+        _ => panic!("expected retries to be exhausted, got: {result:?}"),
+    }
+}
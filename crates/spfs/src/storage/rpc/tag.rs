@@ -10,6 +10,7 @@ use std::sync::Arc;
 use futures::{Stream, TryStreamExt};
 use relative_path::RelativePath;
 
+use super::RetryPolicy;
 use crate::proto::tag_service_client::TagServiceClient;
 use crate::proto::{self, RpcResult};
 use crate::storage::tag::TagSpecAndTagStream;
@@ -35,9 +36,12 @@ impl storage::TagStorage for super::RpcRepository {
                 .unwrap_or_default(),
         };
         let response = self
-            .tag_client
-            .clone()
-            .resolve_tag(request)
+            .retry_policy
+            .run("resolve tag", || {
+                let mut client = self.tag_client.clone();
+                let request = request.clone();
+                async move { Ok(client.resolve_tag(request).await?) }
+            })
             .await?
             .into_inner();
         response.to_result()?.try_into()
@@ -104,12 +108,13 @@ impl storage::TagStorage for super::RpcRepository {
             })
             .try_flatten();
         let client = self.tag_client.clone();
+        let retry_policy = self.retry_policy;
         let tag_namespace = Arc::new(namespace.map(ToOwned::to_owned));
         let stream = stream.and_then(move |spec| {
             let client = client.clone();
             let tag_namespace = Arc::clone(&tag_namespace);
             async move {
-                match read_tag(client, tag_namespace.as_deref(), &spec).await {
+                match read_tag(client, retry_policy, tag_namespace.as_deref(), &spec).await {
                     Ok(tags) => Ok((spec, tags)),
                     Err(err) => Err(err),
                 }
@@ -124,7 +129,37 @@ impl storage::TagStorage for super::RpcRepository {
         namespace: Option<&TagNamespace>,
         tag: &tracking::TagSpec,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>>> {
-        read_tag(self.tag_client.clone(), namespace, tag).await
+        read_tag(self.tag_client.clone(), self.retry_policy, namespace, tag).await
+    }
+
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        let request = proto::ReadTagPageRequest {
+            tag_spec: tag.to_string(),
+            namespace: namespace.map(|p| p.to_string()).unwrap_or_default(),
+            offset: offset as u64,
+            limit: limit as u64,
+        };
+        let response = self
+            .retry_policy
+            .run("read tag page", || {
+                let mut client = self.tag_client.clone();
+                let request = request.clone();
+                async move { Ok(client.read_tag_page(request).await?) }
+            })
+            .await?
+            .into_inner()
+            .to_result()?;
+        response
+            .tags
+            .into_iter()
+            .map(tracking::Tag::try_from)
+            .collect()
     }
 
     async fn insert_tag_in_namespace(
@@ -139,13 +174,33 @@ impl storage::TagStorage for super::RpcRepository {
         let _response = self
             .tag_client
             .clone()
-            .insert_tag(request)
+            .insert_tag(with_user(request))
             .await?
             .into_inner()
             .to_result()?;
         Ok(())
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        let request = proto::InsertTagIfMatchesRequest {
+            tag: Some(tag.into()),
+            namespace: namespace.map(|p| p.to_string()).unwrap_or_default(),
+            expected: expected.map(Into::into),
+        };
+        self.tag_client
+            .clone()
+            .insert_tag_if_matches(with_user(request))
+            .await?
+            .into_inner()
+            .to_result()?
+            .try_into()
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -158,7 +213,7 @@ impl storage::TagStorage for super::RpcRepository {
         let _response = self
             .tag_client
             .clone()
-            .remove_tag_stream(request)
+            .remove_tag_stream(with_user(request))
             .await?
             .into_inner()
             .to_result()?;
@@ -177,12 +232,46 @@ impl storage::TagStorage for super::RpcRepository {
         let _response = self
             .tag_client
             .clone()
-            .remove_tag(request)
+            .remove_tag(with_user(request))
             .await?
             .into_inner()
             .to_result()?;
         Ok(())
     }
+
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        let request = proto::WatchTagsRequest {
+            pattern: pattern.to_string(),
+            namespace: namespace.map(|p| p.to_string()).unwrap_or_default(),
+        };
+        let mut client = self.tag_client.clone();
+        let stream = futures::stream::once(async move { client.watch_tags(request).await })
+            .map_err(crate::Error::from)
+            .map_ok(|r| r.into_inner().map_err(crate::Error::from))
+            .try_flatten()
+            .and_then(|t| async { t.to_result() })
+            .and_then(|t| async { t.try_into() });
+        Box::pin(stream)
+    }
+}
+
+/// Identify the current user to the server, so that it can
+/// decide whether they are allowed to change tags.
+fn with_user<T>(message: T) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    let user = crate::get_config()
+        .ok()
+        .and_then(|config| config.user.name.parse().ok());
+    if let Some(user) = user {
+        request
+            .metadata_mut()
+            .insert(proto::USER_METADATA_KEY, user);
+    }
+    request
 }
 
 impl storage::TagStorageMut for super::RpcRepository {
@@ -195,7 +284,8 @@ impl storage::TagStorageMut for super::RpcRepository {
 }
 
 async fn read_tag(
-    mut client: TagServiceClient<tonic::transport::Channel>,
+    client: TagServiceClient<super::Channel>,
+    retry_policy: RetryPolicy,
     tag_namespace: Option<&TagNamespace>,
     tag: &tracking::TagSpec,
 ) -> Result<Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>>> {
@@ -203,7 +293,15 @@ async fn read_tag(
         tag_spec: tag.to_string(),
         namespace: tag_namespace.map(|p| p.to_string()).unwrap_or_default(),
     };
-    let response = client.read_tag(request).await?.into_inner().to_result()?;
+    let response = retry_policy
+        .run("read tag", || {
+            let mut client = client.clone();
+            let request = request.clone();
+            async move { Ok(client.read_tag(request).await?) }
+        })
+        .await?
+        .into_inner()
+        .to_result()?;
     let items: Result<Vec<_>> = response
         .tags
         .into_iter()
@@ -201,6 +201,34 @@ pub trait TagStorage: Send + Sync {
         tag: &tracking::TagSpec,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>>>;
 
+    /// Read a page of the tag stream for the given tag.
+    ///
+    /// Tags are returned newest first, skipping the `offset` most recent
+    /// entries and returning at most `limit` tags.
+    async fn read_tag_page(
+        &self,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        self.read_tag_page_in_namespace(self.get_tag_namespace().as_deref(), tag, offset, limit)
+            .await
+    }
+
+    /// Read a page of the tag stream for the given tag in the given namespace.
+    ///
+    /// See [`Self::read_tag_page`].
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        let stream = self.read_tag_in_namespace(namespace, tag).await?;
+        stream.skip(offset).take(limit).collect().await
+    }
+
     /// Push the given tag onto the tag stream.
     async fn push_tag(
         &self,
@@ -227,6 +255,61 @@ pub trait TagStorage: Send + Sync {
         Ok(new_tag)
     }
 
+    /// Push the given tag onto the tag stream, but only if the stream
+    /// currently points to the expected target.
+    ///
+    /// An `expected` value of `None` requires that the tag does not exist
+    /// yet. Unlike checking the tag before pushing it, this is safe to use
+    /// when other processes may be changing the same tag.
+    ///
+    /// # Errors:
+    /// - [`Error::TagChanged`]: if the tag does not point to the expected target
+    async fn push_tag_if_matches(
+        &self,
+        tag: &tracking::TagSpec,
+        expected: Option<&encoding::Digest>,
+        target: &encoding::Digest,
+    ) -> Result<tracking::Tag> {
+        let new_tag = tracking::Tag::new(tag.org(), tag.name(), *target)?;
+        self.insert_tag_if_matches_in_namespace(
+            self.get_tag_namespace().as_deref(),
+            &new_tag,
+            expected,
+        )
+        .await
+    }
+
+    /// Insert the given tag as the newest one in its stream, but only if
+    /// the stream currently points to the expected target.
+    ///
+    /// The parent of the given tag is replaced by the current head of the
+    /// stream, and the tag that was stored is returned. Nothing is inserted
+    /// if the stream already points to the new target. Storage that cannot
+    /// do this atomically should override this method, as the default
+    /// implementation reads the tag and then inserts the new one.
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        let current = match self
+            .resolve_tag_in_namespace(namespace, &tag.to_spec(0))
+            .await
+        {
+            Ok(current) => Some(current),
+            Err(Error::UnknownReference(_)) => None,
+            Err(err) => return Err(err),
+        };
+        match next_tag_if_matches(tag, current.as_ref(), expected)? {
+            Some(new_tag) => {
+                self.insert_tag_in_namespace(namespace, &new_tag).await?;
+                Ok(new_tag)
+            }
+            None => Ok(current.unwrap_or_else(|| tag.clone())),
+        }
+    }
+
     /// Insert the given tag into the tag stream, regardless of if it's valid.
     ///
     /// This insertion must sort the tag in order of datetime with any
@@ -278,6 +361,36 @@ pub trait TagStorage: Send + Sync {
         namespace: Option<&TagNamespace>,
         tag: &tracking::Tag,
     ) -> Result<()>;
+
+    /// Stream the tags that change in this storage, as they change.
+    ///
+    /// Only tags whose path matches the given glob pattern are
+    /// yielded (see [`crate::mirror::TagFilter`]). Each time a matching
+    /// tag is pushed after the watch has started, the new latest version
+    /// of that tag is yielded. The stream does not end on its own.
+    fn watch_tags(
+        &self,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        self.watch_tags_in_namespace(self.get_tag_namespace().as_deref(), pattern)
+    }
+
+    /// Stream the tags that change in the given namespace, as they change.
+    ///
+    /// See [`Self::watch_tags`]. The default implementation yields
+    /// a single error for storage that cannot be watched.
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        let _ = (namespace, pattern);
+        Box::pin(futures::stream::once(async {
+            Err(Error::String(
+                "This repository does not support watching tags".to_string(),
+            ))
+        }))
+    }
 }
 
 #[async_trait::async_trait]
@@ -318,6 +431,16 @@ impl<T: TagStorage> TagStorage for &T {
         TagStorage::read_tag_in_namespace(&**self, namespace, tag).await
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        TagStorage::read_tag_page_in_namespace(&**self, namespace, tag, offset, limit).await
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -326,6 +449,15 @@ impl<T: TagStorage> TagStorage for &T {
         TagStorage::insert_tag_in_namespace(&**self, namespace, tag).await
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        TagStorage::insert_tag_if_matches_in_namespace(&**self, namespace, tag, expected).await
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -341,6 +473,36 @@ impl<T: TagStorage> TagStorage for &T {
     ) -> Result<()> {
         TagStorage::remove_tag_in_namespace(&**self, namespace, tag).await
     }
+
+    fn watch_tags_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        pattern: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<tracking::Tag>> + Send>> {
+        TagStorage::watch_tags_in_namespace(&**self, namespace, pattern)
+    }
+}
+
+/// Check the current head of a tag stream against the expected target,
+/// returning the tag that should be inserted on top of it.
+///
+/// Returns `None` when the stream already points to the target of the
+/// given tag, and there is nothing to insert.
+pub(crate) fn next_tag_if_matches(
+    tag: &tracking::Tag,
+    current: Option<&tracking::Tag>,
+    expected: Option<&encoding::Digest>,
+) -> Result<Option<tracking::Tag>> {
+    if current.map(|c| &c.target) != expected {
+        return Err(Error::TagChanged(tag.to_spec(0).to_string()));
+    }
+    let mut new_tag = tag.clone();
+    new_tag.parent = match current {
+        Some(current) if current.target == tag.target => return Ok(None),
+        Some(current) => current.digest()?,
+        None => encoding::NULL_DIGEST.into(),
+    };
+    Ok(Some(new_tag))
 }
 
 pub trait TagStorageMut {
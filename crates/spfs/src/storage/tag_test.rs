@@ -67,6 +67,44 @@ async fn test_tag_stream(
     assert_eq!(found.unwrap(), vec![base.with_version(1)]);
 }
 
+#[rstest]
+#[case::fs(tmprepo("fs"))]
+#[case::tar(tmprepo("tar"))]
+#[cfg_attr(feature = "server", case::rpc(tmprepo("rpc")))]
+#[tokio::test]
+async fn test_read_tag_page(
+    #[case]
+    #[future]
+    tmprepo: TempRepo,
+) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let base = crate::tracking::TagSpec::parse("hello/world").unwrap();
+    let mut pushed = Vec::new();
+    for i in 0..5u8 {
+        let mut h = encoding::Hasher::new_sync();
+        h.update(&[i]);
+        let tag = tmprepo
+            .push_tag(&base, &h.digest())
+            .await
+            .expect("failed to push tag");
+        pushed.insert(0, tag);
+    }
+
+    let page = tmprepo.read_tag_page(&base, 0, 2).await.unwrap();
+    assert_eq!(page, pushed[..2]);
+    let page = tmprepo.read_tag_page(&base, 1, 3).await.unwrap();
+    assert_eq!(page, pushed[1..4]);
+    let page = tmprepo.read_tag_page(&base, 3, 10).await.unwrap();
+    assert_eq!(page, pushed[3..]);
+    let page = tmprepo.read_tag_page(&base, 10, 10).await.unwrap();
+    assert!(page.is_empty());
+
+    let missing = crate::tracking::TagSpec::parse("hello/missing").unwrap();
+    assert!(tmprepo.read_tag_page(&missing, 0, 10).await.is_err());
+}
+
 #[rstest]
 #[case::fs(tmprepo("fs"))]
 #[case::tar(tmprepo("tar"))]
@@ -107,6 +145,78 @@ async fn test_tag_no_duplication(
     );
 }
 
+#[rstest]
+#[case::fs(tmprepo("fs"))]
+#[case::tar(tmprepo("tar"))]
+#[cfg_attr(feature = "server", case::rpc(tmprepo("rpc")))]
+#[tokio::test]
+async fn test_push_tag_if_matches(
+    #[case]
+    #[future]
+    tmprepo: TempRepo,
+) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let digest1 = encoding::Hasher::new_sync().digest();
+    let mut h = encoding::Hasher::new_sync();
+    h.update(b"hello");
+    let digest2 = h.digest();
+
+    let spec = tracking::TagSpec::parse("hello/world").unwrap();
+    let tag1 = tmprepo
+        .push_tag_if_matches(&spec, None, &digest1)
+        .await
+        .expect("should push a tag that does not exist yet");
+    let err = tmprepo
+        .push_tag_if_matches(&spec, None, &digest2)
+        .await
+        .expect_err("should not push a tag that already exists");
+    assert!(matches!(err, crate::Error::TagChanged(_)), "{err:?}");
+    let err = tmprepo
+        .push_tag_if_matches(&spec, Some(&digest2), &digest1)
+        .await
+        .expect_err("should not push over an unexpected target");
+    assert!(matches!(err, crate::Error::TagChanged(_)), "{err:?}");
+
+    let tag2 = tmprepo
+        .push_tag_if_matches(&spec, Some(&digest1), &digest2)
+        .await
+        .expect("should push over the expected target");
+    assert_eq!(tag2.parent, tag1.digest().unwrap());
+    assert_eq!(tmprepo.resolve_tag(&spec).await.unwrap(), tag2);
+    assert_eq!(
+        tmprepo.resolve_tag(&spec.with_version(1)).await.unwrap(),
+        tag1
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_push_tag_if_matches_concurrent(#[future] tmprepo: TempRepo) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+
+    let spec = tracking::TagSpec::parse("hello/world").unwrap();
+    let digests: Vec<_> = (0..10u8)
+        .map(|i| {
+            let mut h = encoding::Hasher::new_sync();
+            h.update(&[i]);
+            h.digest()
+        })
+        .collect();
+    let results = futures::future::join_all(
+        digests
+            .iter()
+            .map(|digest| tmprepo.push_tag_if_matches(&spec, None, digest)),
+    )
+    .await;
+
+    let pushed: Vec<_> = results.into_iter().filter_map(|r| r.ok()).collect();
+    assert_eq!(pushed.len(), 1, "only one push should create the tag");
+    assert_eq!(tmprepo.resolve_tag(&spec).await.unwrap(), pushed[0]);
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_tag_permissions(tmpdir: tempfile::TempDir) {
@@ -322,6 +432,49 @@ async fn test_rm_tags(
     );
 }
 
+#[rstest]
+#[case::fs(tmprepo("fs"))]
+#[cfg_attr(feature = "server", case::rpc(tmprepo("rpc")))]
+#[tokio::test]
+async fn test_watch_tags(
+    #[case]
+    #[future]
+    tmprepo: TempRepo,
+) {
+    init_logging();
+    let tmprepo = tmprepo.await;
+    let wait = std::time::Duration::from_secs(10);
+
+    let mut watch = tmprepo.watch_tags("spi/stable/*");
+    // polling once allows a remote watch to be established
+    // before any of the tags are pushed
+    let _ = tokio::time::timeout(std::time::Duration::from_millis(500), watch.next()).await;
+
+    let ignored = tracking::TagSpec::parse("spi/latest/my_tag").unwrap();
+    let spec = tracking::TagSpec::parse("spi/stable/my_tag").unwrap();
+    tmprepo.push_tag(&ignored, &random_digest()).await.unwrap();
+    let first = tmprepo.push_tag(&spec, &random_digest()).await.unwrap();
+    let second = tmprepo.push_tag(&spec, &random_digest()).await.unwrap();
+
+    let tag = tokio::time::timeout(wait, watch.next())
+        .await
+        .expect("should be notified of the first tag")
+        .unwrap()
+        .unwrap();
+    assert_eq!(tag.to_spec(0), spec, "should only yield matching tags");
+    if tag == first {
+        let tag = tokio::time::timeout(wait, watch.next())
+            .await
+            .expect("should be notified of the second tag")
+            .unwrap()
+            .unwrap();
+        assert_eq!(tag, second);
+    } else {
+        // both pushes may be seen as a single change
+        assert_eq!(tag, second);
+    }
+}
+
 #[rstest]
 #[case::fs(tmprepo("fs"))]
 #[tokio::test]
@@ -124,6 +124,14 @@ impl TarRepository {
             .map_err(|err| Error::RuntimeWriteError(path, err))
     }
 
+    /// The temporary directory that the archive has been unpacked into.
+    ///
+    /// Any additional files written into this directory are
+    /// included in the archive the next time that it is flushed.
+    pub fn root(&self) -> &Path {
+        self.repo_dir.path()
+    }
+
     // Open a repository over the given directory, which must already
     // exist and be a repository
     pub async fn open<P: AsRef<Path>>(path: P) -> OpenRepositoryResult<Self> {
@@ -256,6 +264,12 @@ impl graph::Database for TarRepository {
         Ok(())
     }
 
+    async fn write_objects(&self, objects: &[graph::Object]) -> Result<()> {
+        self.repo.write_objects(objects).await?;
+        self.up_to_date.store(false, Ordering::Release);
+        Ok(())
+    }
+
     async fn remove_object(&self, digest: encoding::Digest) -> Result<()> {
         self.repo.remove_object(digest).await?;
         self.up_to_date.store(false, Ordering::Release);
@@ -280,6 +294,10 @@ impl graph::Database for TarRepository {
 
 #[async_trait::async_trait]
 impl PayloadStorage for TarRepository {
+    fn digest_algorithm(&self) -> encoding::DigestAlgorithm {
+        self.repo.digest_algorithm()
+    }
+
     async fn has_payload(&self, digest: encoding::Digest) -> bool {
         self.repo.has_payload(digest).await
     }
@@ -361,6 +379,18 @@ impl TagStorage for TarRepository {
         self.repo.read_tag_in_namespace(namespace, tag).await
     }
 
+    async fn read_tag_page_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::TagSpec,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<tracking::Tag>> {
+        self.repo
+            .read_tag_page_in_namespace(namespace, tag, offset, limit)
+            .await
+    }
+
     async fn insert_tag_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
@@ -372,6 +402,21 @@ impl TagStorage for TarRepository {
         Ok(())
     }
 
+    async fn insert_tag_if_matches_in_namespace(
+        &self,
+        namespace: Option<&TagNamespace>,
+        tag: &tracking::Tag,
+        expected: Option<&encoding::Digest>,
+    ) -> Result<tracking::Tag> {
+        let tag = self
+            .repo
+            .insert_tag_if_matches_in_namespace(namespace, tag, expected)
+            .await?;
+        self.up_to_date
+            .store(false, std::sync::atomic::Ordering::Release);
+        Ok(tag)
+    }
+
     async fn remove_tag_stream_in_namespace(
         &self,
         namespace: Option<&TagNamespace>,
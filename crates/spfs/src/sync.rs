@@ -10,7 +10,9 @@ use progress_bar_derive_macro::ProgressBar;
 use tokio::sync::Semaphore;
 
 use crate::graph::AnnotationValue;
+use crate::operation::{cancellable, CancellationToken};
 use crate::prelude::*;
+pub use crate::rate_limit::{RateLimitedReader, RateLimiter};
 use crate::{encoding, graph, storage, tracking, Error, Result};
 
 /// The default limit for concurrent manifest sync operations
@@ -79,7 +81,9 @@ pub struct Syncer<'src, 'dst, Reporter: SyncReporter = SilentSyncReporter> {
     policy: SyncPolicy,
     manifest_semaphore: Arc<Semaphore>,
     payload_semaphore: Arc<Semaphore>,
+    rate_limiter: Option<Arc<RateLimiter>>,
     processed_digests: Arc<dashmap::DashSet<encoding::Digest>>,
+    cancellation: CancellationToken,
 }
 
 impl<'src, 'dst> Syncer<'src, 'dst> {
@@ -94,7 +98,9 @@ impl<'src, 'dst> Syncer<'src, 'dst> {
             policy: SyncPolicy::default(),
             manifest_semaphore: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_MANIFESTS)),
             payload_semaphore: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_PAYLOADS)),
+            rate_limiter: None,
             processed_digests: Arc::new(Default::default()),
+            cancellation: CancellationToken::new(),
         }
     }
 }
@@ -120,7 +126,9 @@ where
             policy: self.policy,
             manifest_semaphore: Arc::clone(&self.manifest_semaphore),
             payload_semaphore: Arc::clone(&self.payload_semaphore),
+            rate_limiter: self.rate_limiter.clone(),
             processed_digests: Arc::clone(&self.processed_digests),
+            cancellation: self.cancellation.clone(),
         }
     }
 
@@ -150,6 +158,15 @@ where
         self
     }
 
+    /// Limit the rate at which payload data is transferred, in bytes per second.
+    ///
+    /// The limit is shared by all of the payloads being synced at once,
+    /// and by any syncers that are cloned from this one.
+    pub fn with_rate_limit(mut self, bytes_per_second: u64) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(bytes_per_second)));
+        self
+    }
+
     /// Report progress to the given instance, replacing any existing one
     pub fn with_reporter<T, R>(self, reporter: T) -> Syncer<'src, 'dst, R>
     where
@@ -163,10 +180,21 @@ where
             policy: self.policy,
             manifest_semaphore: self.manifest_semaphore,
             payload_semaphore: self.payload_semaphore,
+            rate_limiter: self.rate_limiter,
             processed_digests: self.processed_digests,
+            cancellation: self.cancellation,
         }
     }
 
+    /// Stop syncing with [`Error::OperationCancelled`] once the
+    /// given token is cancelled, replacing any existing one.
+    ///
+    /// Any data that was already synced is left in the destination.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
     /// Sync the object(s) referenced by the given string.
     ///
     /// Any valid [`crate::tracking::EnvSpec`] is accepted as a reference.
@@ -183,7 +211,7 @@ where
             futures.push(self.sync_env_item(item));
         }
         let mut results = Vec::with_capacity(env.len());
-        while let Some(result) = futures.try_next().await? {
+        while let Some(result) = cancellable(&self.cancellation, futures.try_next()).await? {
             results.push(result);
         }
         let res = SyncEnvResult { env, results };
@@ -276,6 +304,7 @@ where
             Enum::Platform(obj) => SyncObjectResult::Platform(self.sync_platform(obj).await?),
             Enum::Blob(obj) => SyncObjectResult::Blob(self.sync_blob(&obj).await?),
             Enum::Manifest(obj) => SyncObjectResult::Manifest(self.sync_manifest(obj).await?),
+            Enum::ChunkList(obj) => SyncObjectResult::ChunkList(self.sync_chunk_list(obj).await?),
         };
         self.reporter.synced_object(&res);
         Ok(res)
@@ -291,14 +320,25 @@ where
         }
         self.reporter.visit_platform(&platform);
 
-        let mut futures = FuturesUnordered::new();
+        let mut results = Vec::new();
+        let mut digests = Vec::new();
         for digest in platform.iter_bottom_up() {
-            futures.push(self.sync_digest(*digest));
+            if self.processed_digests.contains(digest) {
+                results.push(SyncObjectResult::Duplicate);
+            } else {
+                digests.push(*digest);
+            }
         }
-        let mut results = Vec::with_capacity(futures.len());
-        while let Some(result) = futures.try_next().await? {
+        // the layers are read together to save on round trips
+        // to the source repository, but are then synced concurrently
+        let mut futures = FuturesUnordered::new();
+        for obj in self.read_objects_with_fallback(&digests).await? {
+            futures.push(self.sync_object(obj));
+        }
+        while let Some(result) = cancellable(&self.cancellation, futures.try_next()).await? {
             results.push(result);
         }
+        drop(futures);
 
         self.dest.write_object(&platform).await?;
 
@@ -373,13 +413,14 @@ where
         for entry in entries {
             futures.push(self.sync_entry(entry));
         }
-        while let Some(res) = futures.try_next().await? {
+        while let Some(res) = cancellable(&self.cancellation, futures.try_next()).await? {
             results.push(res);
         }
+        drop(futures);
 
+        self.write_synced_blobs(&results).await?;
         self.dest.write_object(&manifest).await?;
 
-        drop(futures);
         let res = SyncManifestResult::Synced { manifest, results };
         self.reporter.synced_manifest(&res);
         Ok(res)
@@ -412,15 +453,56 @@ where
             return Ok(SyncEntryResult::Skipped);
         }
         self.reporter.visit_entry(&entry);
-        let blob = graph::Blob::new(*entry.object(), entry.size());
+        let mut blob = graph::Blob::new(*entry.object(), entry.size());
+        if entry.size() > storage::chunking::MAX_CHUNK_SIZE as u64 {
+            // large payloads may have been stored as chunks, which
+            // is only known by the blob in the source repository
+            if let Ok(src_blob) = self.src.read_blob(*entry.object()).await {
+                blob = src_blob;
+            }
+        }
+        // the blob object itself is written later, along with
+        // the rest of the blobs in the manifest
         let result = self
-            .sync_blob_with_perms_opt(&blob, Some(entry.mode()))
+            .sync_blob_payload_with_perms_opt(&blob, Some(entry.mode()))
             .await?;
         let res = SyncEntryResult::Synced { result };
         self.reporter.synced_entry(&res);
         Ok(res)
     }
 
+    /// Write the blob objects of all the entries that were synced in one batch.
+    async fn write_synced_blobs(&self, results: &[SyncEntryResult]) -> Result<()> {
+        let synced = results
+            .iter()
+            .filter_map(|res| match res {
+                SyncEntryResult::Synced { result } => Some(result),
+                SyncEntryResult::Skipped | SyncEntryResult::Duplicate => None,
+            })
+            .filter_map(|result| match result {
+                SyncBlobResult::Synced { blob, .. } | SyncBlobResult::Chunked { blob, .. } => {
+                    Some((blob, result))
+                }
+                SyncBlobResult::Skipped | SyncBlobResult::Duplicate => None,
+            })
+            .collect::<Vec<_>>();
+        if synced.is_empty() {
+            return Ok(());
+        }
+        let objects = synced
+            .iter()
+            .map(|(blob, _)| graph::Object::from((*blob).to_owned()))
+            .collect::<Vec<_>>();
+        self.dest.write_objects(&objects).await?;
+        for (blob, result) in synced {
+            self.processed_digests.insert(*blob.digest());
+            if matches!(result, SyncBlobResult::Synced { .. }) {
+                self.reporter.synced_blob(result);
+            }
+        }
+        Ok(())
+    }
+
     /// Sync the identified blob to the destination repository.
     pub async fn sync_blob(&self, blob: &graph::Blob) -> Result<SyncBlobResult> {
         self.sync_blob_with_perms_opt(blob, None).await
@@ -430,6 +512,27 @@ where
         &self,
         blob: &graph::Blob,
         perms: Option<u32>,
+    ) -> Result<SyncBlobResult> {
+        let res = self.sync_blob_payload_with_perms_opt(blob, perms).await?;
+        if let SyncBlobResult::Synced { blob, .. } | SyncBlobResult::Chunked { blob, .. } = &res {
+            self.dest.write_blob(blob.to_owned()).await?;
+            self.processed_digests.insert(*blob.digest());
+        }
+        if matches!(res, SyncBlobResult::Synced { .. }) {
+            self.reporter.synced_blob(&res);
+        }
+        Ok(res)
+    }
+
+    /// Sync the payload or chunks of a blob, but not the blob object itself.
+    ///
+    /// The caller is responsible for writing the blob object to the
+    /// destination for any blob that is synced, and then marking it
+    /// as processed.
+    async fn sync_blob_payload_with_perms_opt(
+        &self,
+        blob: &graph::Blob,
+        perms: Option<u32>,
     ) -> Result<SyncBlobResult> {
         let digest = blob.digest();
         if self.processed_digests.contains(digest) {
@@ -445,6 +548,23 @@ where
             self.processed_digests.insert(*digest);
             return Ok(SyncBlobResult::Skipped);
         }
+        if let Some(chunks) = blob.chunks() {
+            // chunked payloads are synced one chunk at a time, so that
+            // only the chunks missing from the destination are transferred
+            let chunk_list = self
+                .read_object_with_fallback(*chunks)
+                .await?
+                .into_chunk_list()
+                .ok_or(Error::NotCorrectKind {
+                    desired: graph::ObjectKind::ChunkList,
+                    digest: *chunks,
+                })?;
+            let result = self.sync_chunk_list(chunk_list).await?;
+            return Ok(SyncBlobResult::Chunked {
+                blob: blob.to_owned(),
+                result,
+            });
+        }
         self.reporter.visit_blob(blob);
         // Safety: sync_payload is unsafe to call unless the blob
         // is synced with it, which is the purpose of this function.
@@ -452,13 +572,45 @@ where
             self.sync_payload_with_perms_opt(*blob.payload(), perms)
                 .await?
         };
-        self.dest.write_blob(blob.to_owned()).await?;
-        self.processed_digests.insert(*digest);
-        let res = SyncBlobResult::Synced {
+        Ok(SyncBlobResult::Synced {
             blob: blob.to_owned(),
             result,
+        })
+    }
+
+    /// Sync each of the chunks in a chunk list, followed by the list itself.
+    #[async_recursion::async_recursion]
+    pub async fn sync_chunk_list(
+        &self,
+        chunk_list: graph::ChunkList,
+    ) -> Result<SyncChunkListResult> {
+        let digest = chunk_list.digest()?;
+        if !self.processed_digests.insert(digest) {
+            return Ok(SyncChunkListResult::Duplicate);
+        }
+        if self.policy.check_existing_objects() && self.dest.has_object(digest).await {
+            return Ok(SyncChunkListResult::Skipped);
+        }
+        self.reporter.visit_chunk_list(&chunk_list);
+
+        let mut futures = FuturesUnordered::new();
+        for chunk in chunk_list.iter_chunks() {
+            let blob = graph::Blob::new(*chunk.payload(), chunk.size_());
+            futures.push(async move { self.sync_blob(&blob).await });
+        }
+        let mut results = Vec::with_capacity(futures.len());
+        while let Some(result) = cancellable(&self.cancellation, futures.try_next()).await? {
+            results.push(result);
+        }
+        drop(futures);
+
+        self.dest.write_object(&chunk_list).await?;
+
+        let res = SyncChunkListResult::Synced {
+            chunk_list,
+            results,
         };
-        self.reporter.synced_blob(&res);
+        self.reporter.synced_chunk_list(&res);
         Ok(res)
     }
 
@@ -495,20 +647,30 @@ where
             return Ok(SyncPayloadResult::Skipped);
         }
 
+        let (src, dest) = (self.src.digest_algorithm(), self.dest.digest_algorithm());
+        if src != dest {
+            return Err(Error::DigestAlgorithmMismatch { src, dest });
+        }
+
         self.reporter.visit_payload(digest);
         let _permit = self.payload_semaphore.acquire().await;
         debug_assert!(
             _permit.is_ok(),
             "We never close the semaphore and so should never see errors"
         );
-        let (mut payload, _) = self.src.open_payload(digest).await?;
+        let (mut payload, _) =
+            cancellable(&self.cancellation, self.src.open_payload(digest)).await?;
         if let Some(perms) = perms {
             payload = Box::pin(payload.with_permissions(perms));
         }
+        if let Some(limiter) = &self.rate_limiter {
+            payload = Box::pin(RateLimitedReader::new(payload, Arc::clone(limiter)));
+        }
 
         // Safety: this is the unsafe part where we actually create
         // the payload without a corresponding blob
-        let (created_digest, size) = unsafe { self.dest.write_data(payload).await? };
+        let (created_digest, size) =
+            cancellable(&self.cancellation, unsafe { self.dest.write_data(payload) }).await?;
         if digest != created_digest {
             return Err(Error::String(format!(
                 "Source repository provided payload that did not match the requested digest: wanted {digest}, got {created_digest}. wrote {size} bytes",
@@ -520,6 +682,25 @@ where
         Ok(res)
     }
 
+    async fn read_objects_with_fallback(
+        &self,
+        digests: &[encoding::Digest],
+    ) -> Result<Vec<graph::Object>> {
+        match self.src.read_objects(digests).await {
+            Err(_) if self.policy.check_existing_objects() => {
+                // a batch fails as a whole, so each object is read on its own
+                // to recover any that are only in the destination repository
+                futures::future::try_join_all(
+                    digests
+                        .iter()
+                        .map(|digest| self.read_object_with_fallback(*digest)),
+                )
+                .await
+            }
+            res => res,
+        }
+    }
+
     async fn read_object_with_fallback(&self, digest: encoding::Digest) -> Result<graph::Object> {
         let res = self.src.read_object(digest).await;
         match res {
@@ -599,6 +780,12 @@ pub trait SyncReporter: Send + Sync {
     /// Called when a blob has finished syncing
     fn synced_blob(&self, _result: &SyncBlobResult) {}
 
+    /// Called when the chunks of a large blob have been identified to sync
+    fn visit_chunk_list(&self, _chunk_list: &graph::ChunkList) {}
+
+    /// Called when the chunks of a large blob have finished syncing
+    fn synced_chunk_list(&self, _result: &SyncChunkListResult) {}
+
     /// Called when a payload has been identified to sync
     fn visit_payload(&self, _digest: encoding::Digest) {}
 
@@ -804,6 +991,7 @@ pub enum SyncObjectResult {
     Blob(SyncBlobResult),
     Manifest(SyncManifestResult),
     Annotation(SyncAnnotationResult),
+    ChunkList(SyncChunkListResult),
 }
 
 impl SyncObjectResult {
@@ -820,6 +1008,7 @@ impl SyncObjectResult {
             R::Blob(res) => res.summary(),
             R::Manifest(res) => res.summary(),
             R::Annotation(res) => res.summary(),
+            R::ChunkList(res) => res.summary(),
         }
     }
 }
@@ -961,6 +1150,11 @@ pub enum SyncBlobResult {
         blob: graph::Blob,
         result: SyncPayloadResult,
     },
+    /// The blob was synced by syncing each of its chunks
+    Chunked {
+        blob: graph::Blob,
+        result: SyncChunkListResult,
+    },
 }
 
 impl SyncBlobResult {
@@ -972,6 +1166,37 @@ impl SyncBlobResult {
                 summary += SyncSummary::synced_one_object();
                 summary
             }
+            Self::Chunked { result, .. } => {
+                let mut summary = result.summary();
+                summary += SyncSummary::synced_one_object();
+                summary
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncChunkListResult {
+    /// The chunk list did not need to be synced
+    Skipped,
+    /// The chunk list was already synced in this session
+    Duplicate,
+    /// The chunk list was at least partially synced
+    Synced {
+        chunk_list: graph::ChunkList,
+        results: Vec<SyncBlobResult>,
+    },
+}
+
+impl SyncChunkListResult {
+    pub fn summary(&self) -> SyncSummary {
+        match self {
+            Self::Skipped | Self::Duplicate => SyncSummary::skipped_one_object(),
+            Self::Synced { results, .. } => {
+                let mut summary = results.iter().map(|r| r.summary()).sum();
+                summary += SyncSummary::synced_one_object();
+                summary
+            }
         }
     }
 }
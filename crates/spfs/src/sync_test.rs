@@ -271,6 +271,47 @@ async fn test_sync_through_tar(
     assert!(repo_b.has_object(layer.digest().unwrap()).await);
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_sync_with_rate_limit(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let repo_a = tmprepo("fs").await;
+    let repo_b = tmprepo("fs").await;
+
+    let src_dir = tmpdir.path().join("source");
+    ensure(src_dir.join("file.bin"), &"x".repeat(1500));
+
+    let manifest = crate::Committer::new(&repo_a)
+        .commit_dir(src_dir.as_path())
+        .await
+        .unwrap();
+    let layer = repo_a
+        .create_layer(&manifest.to_graph_manifest())
+        .await
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    Syncer::new(&repo_a, &repo_b)
+        .with_rate_limit(1000)
+        .sync_digest(layer.digest().unwrap())
+        .await
+        .expect("failed to sync layer");
+    let elapsed = start.elapsed();
+
+    for node in manifest.walk() {
+        if node.entry.kind.is_blob() {
+            assert!(
+                repo_b.has_payload(node.entry.object).await,
+                "payload should be synced through the limiter"
+            );
+        }
+    }
+    assert!(
+        elapsed >= std::time::Duration::from_millis(400),
+        "syncing 1.5x the rate limit should take about half a second, took {elapsed:?}"
+    );
+}
+
 #[fixture]
 async fn config(tmpdir: tempfile::TempDir) -> (tempfile::TempDir, Config) {
     let repo_path = tmpdir.path().join("repo");
@@ -0,0 +1,203 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use relative_path::RelativePathBuf;
+
+use super::{compute_diff, DiffMode, Entry, Manifest};
+use crate::encoding;
+
+#[cfg(test)]
+#[path = "./changeset_test.rs"]
+mod changeset_test;
+
+/// Identifies how a single file system entry changed
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ChangeKind<U = ()> {
+    Added(Entry<U>),
+    Removed(Entry<U>),
+    Modified(Entry<U>, Entry<U>),
+    /// The same content was moved here from another path
+    Renamed {
+        from: RelativePathBuf,
+        before: Entry<U>,
+        after: Entry<U>,
+    },
+}
+
+impl<U> std::fmt::Display for ChangeKind<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(..) => f.write_str("+"),
+            Self::Removed(..) => f.write_str("-"),
+            Self::Modified(..) => f.write_str("~"),
+            Self::Renamed { .. } => f.write_str(">"),
+        }
+    }
+}
+
+impl<U> ChangeKind<U> {
+    pub fn is_added(&self) -> bool {
+        matches!(self, Self::Added(..))
+    }
+    pub fn is_removed(&self) -> bool {
+        matches!(self, Self::Removed(..))
+    }
+    pub fn is_modified(&self) -> bool {
+        matches!(self, Self::Modified(..))
+    }
+    pub fn is_renamed(&self) -> bool {
+        matches!(self, Self::Renamed { .. })
+    }
+}
+
+/// A change to a single path between two manifests
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Change<U = ()> {
+    pub kind: ChangeKind<U>,
+    /// The path of the entry after the change, or the removed path
+    pub path: RelativePathBuf,
+}
+
+impl<U> std::fmt::Display for Change<U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ChangeKind::Renamed { from, .. } => {
+                write!(f, "{} {from} => {}", self.kind, self.path)
+            }
+            kind => write!(f, "{kind} {}", self.path),
+        }
+    }
+}
+
+/// The complete set of changes going from one manifest to another.
+///
+/// Unlike [`compute_diff`], unchanged entries are not included and
+/// files that were moved without changing their content are reported
+/// as a single rename rather than a removal and an addition.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ChangeSet<U = ()> {
+    pub changes: Vec<Change<U>>,
+}
+
+impl<U> Default for ChangeSet<U> {
+    fn default() -> Self {
+        Self {
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl<U> ChangeSet<U> {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Change<U>> {
+        self.changes.iter()
+    }
+}
+
+impl<U> IntoIterator for ChangeSet<U> {
+    type Item = Change<U>;
+    type IntoIter = std::vec::IntoIter<Change<U>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+impl<'a, U> IntoIterator for &'a ChangeSet<U> {
+    type Item = &'a Change<U>;
+    type IntoIter = std::slice::Iter<'a, Change<U>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+/// Compute the set of changes going from manifest `a` to manifest `b`.
+///
+/// A file that was removed from one path and added at another with
+/// the same content digest is reported as renamed. Empty files are
+/// never considered renames, since any two of them share a digest.
+pub fn compute_changeset<U: Clone>(a: &Manifest<U>, b: &Manifest<U>) -> ChangeSet<U> {
+    let diffs = compute_diff(a, b);
+
+    // removed files are paired with added ones in path order, so
+    // that the result is stable for the same inputs
+    let mut removed_by_digest: HashMap<encoding::Digest, VecDeque<usize>> = HashMap::new();
+    for (index, diff) in diffs.iter().enumerate() {
+        if let DiffMode::Removed(entry) = &diff.mode {
+            if is_rename_candidate(entry) {
+                removed_by_digest
+                    .entry(entry.object)
+                    .or_default()
+                    .push_back(index);
+            }
+        }
+    }
+
+    let mut renamed_from = HashMap::new();
+    for (index, diff) in diffs.iter().enumerate() {
+        let DiffMode::Added(added) = &diff.mode else {
+            continue;
+        };
+        if !is_rename_candidate(added) {
+            continue;
+        }
+        let Some(candidates) = removed_by_digest.get_mut(&added.object) else {
+            continue;
+        };
+        let position = candidates.iter().position(|i| match &diffs[*i].mode {
+            DiffMode::Removed(removed) => removed.is_symlink() == added.is_symlink(),
+            _ => false,
+        });
+        if let Some(removed) = position.and_then(|p| candidates.remove(p)) {
+            renamed_from.insert(index, removed);
+        }
+    }
+    let consumed: HashSet<_> = renamed_from.values().copied().collect();
+
+    let mut changes = Vec::new();
+    for (index, diff) in diffs.iter().enumerate() {
+        if consumed.contains(&index) {
+            continue;
+        }
+        let kind = match &diff.mode {
+            DiffMode::Unchanged(_) => continue,
+            DiffMode::Changed(before, after) => ChangeKind::Modified(before.clone(), after.clone()),
+            DiffMode::Removed(entry) => ChangeKind::Removed(entry.clone()),
+            DiffMode::Added(entry) => match renamed_from.get(&index) {
+                Some(removed) => {
+                    let source = &diffs[*removed];
+                    let DiffMode::Removed(before) = &source.mode else {
+                        unreachable!("only removed entries are paired as renames");
+                    };
+                    ChangeKind::Renamed {
+                        from: source.path.clone(),
+                        before: before.clone(),
+                        after: entry.clone(),
+                    }
+                }
+                None => ChangeKind::Added(entry.clone()),
+            },
+        };
+        changes.push(Change {
+            kind,
+            path: diff.path.clone(),
+        });
+    }
+
+    ChangeSet { changes }
+}
+
+fn is_rename_candidate<U>(entry: &Entry<U>) -> bool {
+    entry.kind.is_blob() && entry.size() > 0
+}
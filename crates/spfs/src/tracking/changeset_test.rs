@@ -0,0 +1,96 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use relative_path::RelativePath;
+use rstest::rstest;
+
+use super::{compute_changeset, ChangeKind};
+use crate::fixtures::*;
+use crate::tracking::{compute_manifest, Manifest};
+
+#[rstest]
+fn test_compute_changeset_empty() {
+    let a = Manifest::<()>::default();
+    let b = Manifest::<()>::default();
+
+    assert!(compute_changeset(&a, &b).is_empty());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_compute_changeset_same(tmpdir: tempfile::TempDir) {
+    let dir = tmpdir.path();
+    std::fs::create_dir_all(dir.join("dir")).unwrap();
+    std::fs::write(dir.join("dir/file"), "data").unwrap();
+
+    let manifest = compute_manifest(&dir).await.unwrap();
+    assert!(
+        compute_changeset(&manifest, &manifest).is_empty(),
+        "unchanged entries should not be included"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_compute_changeset_renamed(tmpdir: tempfile::TempDir) {
+    let dir = tmpdir.path();
+    let a_dir = dir.join("a");
+    let b_dir = dir.join("b");
+    std::fs::create_dir_all(a_dir.join("old")).unwrap();
+    std::fs::create_dir_all(b_dir.join("new")).unwrap();
+    std::fs::write(a_dir.join("old/file"), "data").unwrap();
+    std::fs::write(b_dir.join("new/file"), "data").unwrap();
+    std::fs::write(a_dir.join("changed"), "before").unwrap();
+    std::fs::write(b_dir.join("changed"), "after").unwrap();
+
+    let a = compute_manifest(a_dir).await.unwrap();
+    let b = compute_manifest(b_dir).await.unwrap();
+    let changes = compute_changeset(&a, &b).changes;
+
+    let renamed: Vec<_> = changes.iter().filter(|c| c.kind.is_renamed()).collect();
+    assert_eq!(renamed.len(), 1, "expected a single rename: {changes:#?}");
+    assert_eq!(&renamed[0].path, &RelativePath::new("/new/file"));
+    match &renamed[0].kind {
+        ChangeKind::Renamed { from, .. } => assert_eq!(from, &RelativePath::new("/old/file")),
+        _ => unreachable!(),
+    }
+    assert_eq!(renamed[0].to_string(), "> /old/file => /new/file");
+
+    assert!(
+        !changes
+            .iter()
+            .any(|c| c.path == RelativePath::new("/old/file")),
+        "the renamed file should not also be reported as removed"
+    );
+    let changed = changes
+        .iter()
+        .find(|c| c.path == RelativePath::new("/changed"))
+        .expect("changed file should be reported");
+    assert!(changed.kind.is_modified());
+    let old_dir = changes
+        .iter()
+        .find(|c| c.path == RelativePath::new("/old"))
+        .expect("removed directory should be reported");
+    assert!(old_dir.kind.is_removed());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_compute_changeset_empty_files_not_renamed(tmpdir: tempfile::TempDir) {
+    let dir = tmpdir.path();
+    let a_dir = dir.join("a");
+    let b_dir = dir.join("b");
+    std::fs::create_dir_all(&a_dir).unwrap();
+    std::fs::create_dir_all(&b_dir).unwrap();
+    std::fs::write(a_dir.join("old"), "").unwrap();
+    std::fs::write(b_dir.join("new"), "").unwrap();
+
+    let a = compute_manifest(a_dir).await.unwrap();
+    let b = compute_manifest(b_dir).await.unwrap();
+    let changes = compute_changeset(&a, &b).changes;
+
+    assert_eq!(changes.len(), 2, "{changes:#?}");
+    assert!(changes.iter().any(|c| c.kind.is_added()));
+    assert!(changes.iter().any(|c| c.kind.is_removed()));
+}
@@ -105,6 +105,9 @@ impl<U1, U2> Diff<U1, U2> {
             if a.object != b.object {
                 details = format!("{details} {{!content!}}");
             }
+            if a.xattrs != b.xattrs {
+                details = format!("{details} {{!xattrs!}}");
+            }
         }
         details
     }
@@ -6,6 +6,7 @@ use std::io::BufRead;
 use std::str::FromStr;
 use std::string::ToString;
 
+use super::xattrs::Xattrs;
 use crate::{encoding, Error, Result};
 
 #[cfg(test)]
@@ -113,6 +114,9 @@ pub struct Entry<T = ()> {
     pub user_data: T,
     /// The size associated with non-blob entries.
     pub legacy_size: u64,
+    /// The extended attributes of this entry, which are only
+    /// captured when enabled (see [`super::xattrs`]).
+    pub xattrs: Xattrs,
 }
 
 impl<T> std::fmt::Debug for Entry<T>
@@ -129,14 +133,19 @@ where
             entries,
             user_data,
             legacy_size: _,
+            xattrs,
         } = self;
-        f.debug_struct("Entry")
+        let mut debug = f.debug_struct("Entry");
+        debug
             .field("kind", kind)
             .field("mode", &format!("{mode:#06o}"))
             .field("object", object)
             .field("entries", entries)
-            .field("user_data", user_data)
-            .finish()
+            .field("user_data", user_data);
+        if !xattrs.is_empty() {
+            debug.field("xattrs", &xattrs.keys().collect::<Vec<_>>());
+        }
+        debug.finish()
     }
 }
 
@@ -151,11 +160,13 @@ impl<T, T2> PartialEq<Entry<T2>> for Entry<T> {
             entries,
             user_data: _,
             legacy_size: _,
+            xattrs,
         } = other;
         if self.kind != *kind
             || self.mode != *mode
             || self.size() != other.size()
             || self.object != *object
+            || self.xattrs != *xattrs
         {
             return false;
         }
@@ -196,6 +207,7 @@ impl<T> Entry<T> {
             entries: Default::default(),
             user_data,
             legacy_size: 0,
+            xattrs: Default::default(),
         }
     }
 
@@ -208,6 +220,7 @@ impl<T> Entry<T> {
             entries: Default::default(),
             user_data,
             legacy_size: 0,
+            xattrs: Default::default(),
         }
     }
 
@@ -221,6 +234,7 @@ impl<T> Entry<T> {
             entries: Default::default(),
             user_data,
             legacy_size: 0,
+            xattrs: Default::default(),
         }
     }
 
@@ -234,6 +248,7 @@ impl<T> Entry<T> {
             entries: Default::default(),
             user_data,
             legacy_size: 0,
+            xattrs: Default::default(),
         }
     }
 
@@ -318,6 +333,7 @@ impl<T> Entry<T> {
                 .collect(),
             user_data: (),
             legacy_size: self.legacy_size,
+            xattrs: self.xattrs,
         }
     }
 
@@ -334,6 +350,7 @@ impl<T> Entry<T> {
                 .collect(),
             user_data,
             legacy_size: self.legacy_size,
+            xattrs: self.xattrs,
         }
     }
 
@@ -351,6 +368,7 @@ impl<T> Entry<T> {
                 .collect(),
             user_data,
             legacy_size: self.legacy_size,
+            xattrs: self.xattrs,
         }
     }
 }
@@ -359,10 +377,24 @@ impl<T> Entry<T>
 where
     T: Clone,
 {
+    /// Copy this entry, leaving out any child entries.
+    pub fn clone_without_entries(&self) -> Self {
+        Self {
+            kind: self.kind,
+            object: self.object,
+            mode: self.mode,
+            entries: Default::default(),
+            user_data: self.user_data.clone(),
+            legacy_size: self.legacy_size,
+            xattrs: self.xattrs.clone(),
+        }
+    }
+
     pub fn update(&mut self, other: &Self) {
         self.kind = other.kind;
         self.object = other.object;
         self.mode = other.mode;
+        self.xattrs = other.xattrs.clone();
         if !self.kind.is_tree() {
             return;
         }
@@ -10,7 +10,6 @@ use std::os::windows::fs::MetadataExt;
 use std::pin::Pin;
 use std::sync::Arc;
 
-use futures::future::ready;
 use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
 use itertools::Itertools;
 use miette::Diagnostic;
@@ -198,6 +197,54 @@ where
     pub fn update(&mut self, other: &Self) {
         self.root.update(&other.root)
     }
+
+    /// Create a copy of this manifest that only holds the entries
+    /// for which the given function returns true.
+    ///
+    /// The function is given the path of each entry and whether it
+    /// is a directory. A directory that is kept holds all of its
+    /// contents, and any directory that holds a kept entry is also
+    /// kept so that the entry can still be reached.
+    pub fn filter<F>(&self, mut keep: F) -> Self
+    where
+        F: FnMut(&RelativePath, bool) -> bool,
+    {
+        let mut root = self.root.clone_without_entries();
+        root.entries = filter_entries(&self.root.entries, RelativePath::new(""), &mut keep);
+        Self {
+            header: self.header.clone(),
+            root,
+        }
+    }
+}
+
+fn filter_entries<T, F>(
+    entries: &HashMap<String, Entry<T>>,
+    parent: &RelativePath,
+    keep: &mut F,
+) -> HashMap<String, Entry<T>>
+where
+    T: Clone,
+    F: FnMut(&RelativePath, bool) -> bool,
+{
+    let mut kept = HashMap::new();
+    for (name, entry) in entries.iter() {
+        let path = parent.join(name);
+        if keep(&path, entry.kind.is_tree()) {
+            kept.insert(name.clone(), entry.clone());
+            continue;
+        }
+        if !entry.kind.is_tree() {
+            continue;
+        }
+        let children = filter_entries(&entry.entries, &path, keep);
+        if !children.is_empty() {
+            let mut dir = entry.clone_without_entries();
+            dir.entries = children;
+            kept.insert(name.clone(), dir);
+        }
+    }
+    kept
 }
 
 impl<T> Manifest<T> {
@@ -392,6 +439,13 @@ impl BlobHasher for () {
     }
 }
 
+#[tonic::async_trait]
+impl BlobHasher for encoding::DigestAlgorithm {
+    async fn hash_blob(&self, reader: Pin<Box<dyn BlobRead>>) -> Result<encoding::Digest> {
+        Ok(encoding::Hasher::hash_async_reader_with(*self, reader).await?)
+    }
+}
+
 pub async fn compute_manifest<P: AsRef<std::path::Path> + Send>(path: P) -> Result<Manifest> {
     let builder = ManifestBuilder::new();
     builder.compute_manifest(path).await
@@ -401,6 +455,16 @@ pub async fn compute_manifest<P: AsRef<std::path::Path> + Send>(path: P) -> Resu
 /// while it's being constructed
 pub trait PathFilter {
     fn should_include_path(&self, path: &RelativePath) -> bool;
+
+    /// Like [`PathFilter::should_include_path`], but for an entry
+    /// that is known to be a directory or not.
+    ///
+    /// Filters can use this to keep the directories that lead to
+    /// the files that they match without keeping any other files.
+    fn should_include_entry(&self, path: &RelativePath, is_dir: bool) -> bool {
+        let _ = is_dir;
+        self.should_include_path(path)
+    }
 }
 
 impl PathFilter for () {
@@ -447,6 +511,7 @@ where
     reporter: R,
     blob_semaphore: Arc<Semaphore>,
     max_concurrent_branches: usize,
+    preserve_xattrs: bool,
 }
 
 impl ManifestBuilder<(), (), ()> {
@@ -463,6 +528,7 @@ impl Default for ManifestBuilder<(), (), ()> {
             reporter: (),
             blob_semaphore: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_BLOBS)),
             max_concurrent_branches: DEFAULT_MAX_CONCURRENT_BRANCHES,
+            preserve_xattrs: false,
         }
     }
 }
@@ -493,6 +559,15 @@ where
         self
     }
 
+    /// Capture the extended attributes of regular files, such as
+    /// file capabilities, into the manifest (see [`super::xattrs`]).
+    ///
+    /// Defaults to false.
+    pub fn with_preserve_xattrs(mut self, preserve_xattrs: bool) -> Self {
+        self.preserve_xattrs = preserve_xattrs;
+        self
+    }
+
     /// Use the provided hasher when building the manifest.
     ///
     /// The hasher turns blob contents into a digest to be included
@@ -509,6 +584,7 @@ where
             reporter: self.reporter,
             blob_semaphore: self.blob_semaphore,
             max_concurrent_branches: self.max_concurrent_branches,
+            preserve_xattrs: self.preserve_xattrs,
         }
     }
 
@@ -528,6 +604,7 @@ where
             reporter: self.reporter,
             blob_semaphore: self.blob_semaphore,
             max_concurrent_branches: self.max_concurrent_branches,
+            preserve_xattrs: self.preserve_xattrs,
         }
     }
 
@@ -542,6 +619,7 @@ where
             reporter,
             blob_semaphore: self.blob_semaphore,
             max_concurrent_branches: self.max_concurrent_branches,
+            preserve_xattrs: self.preserve_xattrs,
         }
     }
 
@@ -575,25 +653,30 @@ where
             .try_filter_map(|dir_entry| {
                 let dir_entry = Arc::new(dir_entry);
                 let path = base.join(dir_entry.file_name());
-
-                // Skip entries that are not matched by our filter
-                if let Ok(rel_path) = path.strip_prefix(&*root) {
-                    let cow = rel_path.to_string_lossy();
-                    let rel_path = RelativePath::new(&cow);
-                    if !self.filter.should_include_path(rel_path) {
-                        // Move on the next directory entry.
-                        return ready(Ok(None));
+                let root = Arc::clone(&root);
+                async move {
+                    // Skip entries that are not matched by our filter
+                    if let Ok(rel_path) = path.strip_prefix(&*root) {
+                        let cow = rel_path.to_string_lossy();
+                        let rel_path = RelativePath::new(&cow);
+                        let is_dir = dir_entry
+                            .file_type()
+                            .await
+                            .map(|file_type| file_type.is_dir())
+                            .unwrap_or_default();
+                        if !self.filter.should_include_entry(rel_path, is_dir) {
+                            // Move on the next directory entry.
+                            return Ok(None);
+                        }
                     }
-                }
 
-                let root = Arc::clone(&root);
-                let dir_entry = Arc::clone(&dir_entry);
-                let file_name = dir_entry.file_name().to_string_lossy().to_string();
-                ready(Ok(Some(
-                    self.compute_node(root, path, dir_entry)
-                        .map_ok(|e| (file_name, e))
-                        .boxed(),
-                )))
+                    let file_name = dir_entry.file_name().to_string_lossy().to_string();
+                    Ok::<_, Error>(Some(
+                        self.compute_node(root, path, dir_entry)
+                            .map_ok(|e| (file_name, e))
+                            .boxed(),
+                    ))
+                }
             })
             .try_buffer_unordered(self.max_concurrent_branches)
             .boxed();
@@ -707,6 +790,17 @@ where
 
             entry.kind = EntryKind::Blob(file_size);
             entry.object = self.hasher.hash_blob(Box::pin(reader)).await?;
+
+            #[cfg(unix)]
+            if self.preserve_xattrs {
+                let path = path.as_ref().to_owned();
+                entry.xattrs = tokio::task::spawn_blocking(move || {
+                    super::xattrs::read_xattrs(&path)
+                        .map_err(|err| Error::StorageReadError("read xattrs of blob", path, err))
+                })
+                .await
+                .expect("syscall should not panic")?;
+            }
         }
 
         #[cfg(unix)]
@@ -125,3 +125,33 @@ async fn test_layer_manifests_removal() {
 
     compute_manifest("./src").await.unwrap();
 }
+
+#[rstest]
+#[tokio::test]
+#[serial_test::serial(config)]
+async fn test_manifest_filter(tmpdir: tempfile::TempDir) {
+    let dir = tmpdir.path().join("all");
+    ensure(dir.join("lib/liba.so"), "a");
+    ensure(dir.join("lib/python/b.py"), "b");
+    ensure(dir.join("bin/tool"), "tool");
+    ensure(dir.join("share/doc/README"), "readme");
+    ensure(dir.join("share/icons/icon.png"), "icon");
+    let manifest = compute_manifest(&dir).await.unwrap();
+
+    let filtered = manifest.filter(|path, _| path == "lib" || path == "share/doc/README");
+
+    let only_dir = tmpdir.path().join("only");
+    ensure(only_dir.join("lib/liba.so"), "a");
+    ensure(only_dir.join("lib/python/b.py"), "b");
+    ensure(only_dir.join("share/doc/README"), "readme");
+    let expected = compute_manifest(&only_dir).await.unwrap();
+
+    assert!(filtered.get_path("bin").is_none());
+    assert!(filtered.get_path("share/icons").is_none());
+    assert_eq!(filtered, expected);
+    assert_eq!(
+        filtered.to_graph_manifest().digest().unwrap(),
+        expected.to_graph_manifest().digest().unwrap(),
+        "filtered directories should have the same digest as if they were captured as-is"
+    );
+}
@@ -5,14 +5,18 @@
 //! Object tracking and definitions
 
 pub mod blob_reader;
+mod changeset;
 mod diff;
 mod entry;
 mod env;
 pub mod manifest;
 mod object;
+mod path_filter;
 mod tag;
+pub mod xattrs;
 
 pub use blob_reader::{BlobRead, BlobReadExt};
+pub use changeset::{compute_changeset, Change, ChangeKind, ChangeSet};
 pub use diff::{compute_diff, Diff, DiffMode};
 pub use entry::{Entry, EntryKind};
 pub use env::{EnvSpec, EnvSpecItem, ENV_SPEC_EMPTY, ENV_SPEC_SEPARATOR};
@@ -29,6 +33,8 @@ pub use manifest::{
     DEFAULT_MAX_CONCURRENT_BRANCHES,
 };
 pub use object::Object;
+pub use path_filter::GlobPathFilter;
 pub use tag::{build_tag_spec, split_tag_spec, Tag, TagSpec};
+pub use xattrs::Xattrs;
 mod time_spec;
 pub use time_spec::{parse_duration, parse_time, TimeSpec};
@@ -0,0 +1,143 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use relative_path::RelativePath;
+
+use super::PathFilter;
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./path_filter_test.rs"]
+mod path_filter_test;
+
+const MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Selects the files in a manifest or commit using glob patterns
+///
+/// Patterns are matched against paths relative to the root of the
+/// filesystem (eg: `lib/*.so`). A `*` does not match across `/`
+/// separators, but a `**` component will. Patterns without any `/`
+/// are matched against the name of each file and directory, at any
+/// depth (eg: `*.o` or `.cache`).
+#[derive(Clone, Debug, Default)]
+pub struct GlobPathFilter {
+    include: Vec<PathPattern>,
+    exclude: Vec<PathPattern>,
+}
+
+impl GlobPathFilter {
+    /// Create a filter from the given include and exclude patterns.
+    ///
+    /// A path is selected if it, or one of its parent directories,
+    /// matches any of the include patterns, or there are none. Any
+    /// path that matches an exclude pattern is not selected, along
+    /// with everything under it.
+    pub fn new<I, E>(include: I, exclude: E) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        E: IntoIterator,
+        E::Item: AsRef<str>,
+    {
+        Ok(Self {
+            include: include
+                .into_iter()
+                .map(|p| PathPattern::new(p.as_ref()))
+                .collect::<Result<_>>()?,
+            exclude: exclude
+                .into_iter()
+                .map(|p| PathPattern::new(p.as_ref()))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl PathFilter for GlobPathFilter {
+    fn should_include_path(&self, path: &RelativePath) -> bool {
+        // without knowing, assume that the path could be
+        // a directory that contains other matching paths
+        self.should_include_entry(path, true)
+    }
+
+    fn should_include_entry(&self, path: &RelativePath, is_dir: bool) -> bool {
+        if self.exclude.iter().any(|p| p.matches_self_or_parent(path)) {
+            return false;
+        }
+        if self.include.is_empty() || self.include.iter().any(|p| p.matches_self_or_parent(path)) {
+            return true;
+        }
+        is_dir && self.include.iter().any(|p| p.may_match_within(path))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PathPattern {
+    pattern: glob::Pattern,
+    /// The pattern for each component of the path, or
+    /// none if this pattern only matches file names
+    components: Option<Vec<glob::Pattern>>,
+}
+
+impl PathPattern {
+    fn new(pattern: &str) -> Result<Self> {
+        let parse = |pattern: &str| {
+            glob::Pattern::new(pattern).map_err(|source| Error::InvalidPathPattern {
+                pattern: pattern.to_owned(),
+                source,
+            })
+        };
+        let trimmed = pattern.trim_start_matches('/').trim_end_matches('/');
+        let components = match trimmed.contains('/') || pattern.starts_with('/') {
+            false => None,
+            true => Some(trimmed.split('/').map(parse).collect::<Result<Vec<_>>>()?),
+        };
+        Ok(Self {
+            pattern: parse(trimmed)?,
+            components,
+        })
+    }
+
+    fn matches(&self, path: &RelativePath) -> bool {
+        match &self.components {
+            None => path
+                .file_name()
+                .map(|name| self.pattern.matches_with(name, MATCH_OPTIONS))
+                .unwrap_or_default(),
+            Some(_) => self.pattern.matches_with(path.as_str(), MATCH_OPTIONS),
+        }
+    }
+
+    fn matches_self_or_parent(&self, path: &RelativePath) -> bool {
+        let mut current = Some(path);
+        while let Some(path) = current.filter(|p| !p.as_str().is_empty()) {
+            if self.matches(path) {
+                return true;
+            }
+            current = path.parent();
+        }
+        false
+    }
+
+    /// True if the given directory could contain a path that matches.
+    fn may_match_within(&self, dir: &RelativePath) -> bool {
+        let Some(components) = &self.components else {
+            return true;
+        };
+        let mut patterns = components.iter();
+        for name in dir.components().map(|c| c.as_str()) {
+            match patterns.next() {
+                Some(pattern) if pattern.as_str() == "**" => return true,
+                Some(pattern) if pattern.matches_with(name, MATCH_OPTIONS) => continue,
+                _ => return false,
+            }
+        }
+        // a directory that matches the whole pattern is already
+        // selected, so any remaining patterns are within it
+        patterns.next().is_some()
+    }
+}
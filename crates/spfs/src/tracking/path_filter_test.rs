@@ -0,0 +1,44 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use relative_path::RelativePath;
+use rstest::rstest;
+
+use super::GlobPathFilter;
+use crate::tracking::PathFilter;
+
+#[rstest]
+#[case(&[], &[], "any/file", false, true)]
+#[case(&[], &["*.o"], "src/main.o", false, false)]
+#[case(&[], &["*.o"], "src/main.c", false, true)]
+#[case(&[], &[".cache"], "build/.cache/data", false, false)]
+#[case(&[], &["/build"], "build", true, false)]
+#[case(&[], &["/build"], "src/build", true, true)]
+#[case(&["lib/*.so"], &[], "lib", true, true)]
+#[case(&["lib/*.so"], &[], "lib/libfoo.so", false, true)]
+#[case(&["lib/*.so"], &[], "lib/readme", false, false)]
+#[case(&["lib/*.so"], &[], "bin", true, false)]
+#[case(&["include"], &[], "include/foo/foo.h", false, true)]
+#[case(&["share/**/*.py"], &[], "share/python/site", true, true)]
+#[case(&["share/**/*.py"], &[], "share/python/readme", false, false)]
+#[case(&["lib/**"], &["*.a"], "lib/libfoo.a", false, false)]
+fn test_glob_path_filter(
+    #[case] include: &[&str],
+    #[case] exclude: &[&str],
+    #[case] path: &str,
+    #[case] is_dir: bool,
+    #[case] expected: bool,
+) {
+    let filter = GlobPathFilter::new(include, exclude).unwrap();
+    assert_eq!(
+        filter.should_include_entry(RelativePath::new(path), is_dir),
+        expected
+    );
+}
+
+#[rstest]
+fn test_glob_path_filter_invalid_pattern() {
+    GlobPathFilter::new(["lib/[abc"], [] as [&str; 0])
+        .expect_err("unclosed bracket should be invalid");
+}
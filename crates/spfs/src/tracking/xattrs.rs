@@ -0,0 +1,135 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Extended attributes of files in a manifest.
+//!
+//! Only a subset of attributes are kept, being those that describe
+//! the file itself rather than the system that it was created on:
+//! any attribute in the `user.` namespace, and the file capabilities
+//! (`security.capability`) that allow a binary to gain privileges
+//! such as `cap_net_raw` when it is executed. Attributes are only
+//! captured from regular files, and only when enabled in the spfs
+//! config with `storage.preserve_xattrs`.
+
+use std::collections::BTreeMap;
+
+#[cfg(all(test, unix))]
+#[path = "./xattrs_test.rs"]
+mod xattrs_test;
+
+/// The extended attributes of a single file, by name
+pub type Xattrs = BTreeMap<String, Vec<u8>>;
+
+/// The name of the extended attribute that stores file capabilities
+pub const CAPABILITY_XATTR: &str = "security.capability";
+
+/// The namespace of extended attributes that can be freely set by users
+pub const USER_XATTR_PREFIX: &str = "user.";
+
+/// True if the named attribute should be kept when capturing files.
+pub fn is_preserved_xattr(name: &str) -> bool {
+    name.starts_with(USER_XATTR_PREFIX) || name == CAPABILITY_XATTR
+}
+
+/// Read the preserved extended attributes of a file, without following symlinks.
+///
+/// Filesystems that do not support extended attributes are
+/// treated as though the file has none.
+#[cfg(unix)]
+pub fn read_xattrs(path: &std::path::Path) -> std::io::Result<Xattrs> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let names = match read_sized(|buf, size| {
+        // Safety: the path is a valid c string and the buffer
+        // is either null or valid for `size` bytes
+        unsafe { libc::llistxattr(c_path.as_ptr(), buf as *mut libc::c_char, size) }
+    }) {
+        Ok(names) => names,
+        Err(err) if err.raw_os_error() == Some(libc::ENOTSUP) => return Ok(Xattrs::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut xattrs = Xattrs::new();
+    for name in names.split(|b| *b == 0).filter(|n| !n.is_empty()) {
+        let Ok(name) = std::str::from_utf8(name) else {
+            continue;
+        };
+        if !is_preserved_xattr(name) {
+            continue;
+        }
+        let c_name = CString::new(name)?;
+        let value = match read_sized(|buf, size| {
+            // Safety: the path and name are valid c strings and the
+            // buffer is either null or valid for `size` bytes
+            unsafe {
+                libc::lgetxattr(
+                    c_path.as_ptr(),
+                    c_name.as_ptr(),
+                    buf as *mut libc::c_void,
+                    size,
+                )
+            }
+        }) {
+            Ok(value) => value,
+            // removed since it was listed
+            Err(err) if err.raw_os_error() == Some(libc::ENODATA) => continue,
+            Err(err) => return Err(err),
+        };
+        xattrs.insert(name.to_owned(), value);
+    }
+    Ok(xattrs)
+}
+
+/// Set an extended attribute on an open file.
+#[cfg(unix)]
+pub fn set_xattr(fd: std::os::fd::RawFd, name: &str, value: &[u8]) -> std::io::Result<()> {
+    let c_name = std::ffi::CString::new(name)?;
+    // Safety: the name is a valid c string and the value
+    // is valid for its length
+    let res = unsafe {
+        libc::fsetxattr(
+            fd,
+            c_name.as_ptr(),
+            value.as_ptr() as *const libc::c_void,
+            value.len(),
+            0,
+        )
+    };
+    if res < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Call a function that fills a buffer in the style of getxattr(2),
+/// first asking for the size of buffer that is needed.
+#[cfg(unix)]
+fn read_sized<F>(read: F) -> std::io::Result<Vec<u8>>
+where
+    F: Fn(*mut u8, usize) -> libc::ssize_t,
+{
+    loop {
+        let size = read(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut buf = vec![0; size as usize];
+        if buf.is_empty() {
+            return Ok(buf);
+        }
+        let size = read(buf.as_mut_ptr(), buf.len());
+        if size < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ERANGE) {
+                // the value grew since the size was checked
+                continue;
+            }
+            return Err(err);
+        }
+        buf.truncate(size as usize);
+        return Ok(buf);
+    }
+}
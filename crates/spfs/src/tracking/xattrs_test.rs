@@ -0,0 +1,41 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::os::fd::AsRawFd;
+
+use rstest::rstest;
+
+use super::{is_preserved_xattr, read_xattrs, set_xattr};
+use crate::fixtures::*;
+
+#[rstest]
+#[case("user.checksum", true)]
+#[case("security.capability", true)]
+#[case("security.selinux", false)]
+#[case("system.posix_acl_access", false)]
+#[case("trusted.overlay.opaque", false)]
+fn test_is_preserved_xattr(#[case] name: &str, #[case] expected: bool) {
+    assert_eq!(is_preserved_xattr(name), expected);
+}
+
+#[rstest]
+fn test_read_xattrs_round_trip(tmpdir: tempfile::TempDir) {
+    let path = tmpdir.path().join("file");
+    let file = std::fs::File::create(&path).unwrap();
+    match set_xattr(file.as_raw_fd(), "user.spfs-test", b"value") {
+        Ok(()) => {}
+        Err(err) if err.raw_os_error() == Some(libc::ENOTSUP) => {
+            // user attributes are not supported on all filesystems
+            return;
+        }
+        Err(err) => panic!("failed to set test xattr: {err}"),
+    }
+
+    let xattrs = read_xattrs(&path).unwrap();
+    assert_eq!(
+        xattrs.get("user.spfs-test").map(Vec::as_slice),
+        Some(&b"value"[..]),
+        "should read back the attribute that was set"
+    );
+}
@@ -0,0 +1,50 @@
+#!/bin/bash
+
+# Copyright (c) Contributors to the SPK project.
+# SPDX-License-Identifier: Apache-2.0
+# https://github.com/spkenv/spk
+
+set -o errexit
+
+# test that detached runtimes can be joined, log their output, and stop
+# along with the processes that were joined into them
+
+wait_for_runtime_removal() {
+    until ! spfs runtime info "$1" &> /dev/null; do sleep 1; done
+}
+
+# the runtime is ready to be joined as soon as its name is printed
+runtime=$(spfs run --detach -)
+spfs runtime list --detached -q | grep -q "$runtime"
+spfs join "$runtime" -- touch /spfs/detached-marker
+spfs join "$runtime" -- test -f /spfs/detached-marker
+
+# stopping the runtime also stops the processes joined into it
+spfs join "$runtime" -- sleep 600 &
+joined=$!
+sleep 2
+spfs runtime stop "$runtime"
+wait $joined || true
+wait_for_runtime_removal "$runtime"
+
+# the output of a detached command is kept in its log file
+runtime=$(spfs run --detach - -- bash -c 'echo hello-from-detached; exec sleep 600')
+log_file=$(spfs runtime info "$runtime" | grep '"log_file"' | cut -d '"' -f 4)
+until grep -q hello-from-detached "$log_file"; do sleep 1; done
+spfs runtime stop "$runtime"
+wait_for_runtime_removal "$runtime"
+test -f "$log_file"
+rm "$log_file"
+
+# runtimes that are not detached are not stopped without --force
+spfs run - -- sleep 600 &
+attached=$!
+sleep 4
+runtime=$(spfs runtime list -q | head -n 1)
+if spfs runtime stop "$runtime"; then
+    echo "should not stop a runtime that is not detached"
+    exit 1
+fi
+spfs runtime stop --force "$runtime"
+wait $attached || true
+wait_for_runtime_removal "$runtime"
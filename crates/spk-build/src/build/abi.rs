@@ -0,0 +1,212 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use object::read::elf::{Dyn, FileHeader, Sym};
+use object::{elf, Endianness, FileKind};
+use relative_path::RelativePathBuf;
+use serde::{Deserialize, Serialize};
+use spfs::prelude::*;
+use spk_schema::BuildIdent;
+use spk_storage::RepositoryHandle;
+use tokio::io::AsyncReadExt;
+
+use super::binary::build_abi_path;
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./abi_test.rs"]
+mod abi_test;
+
+/// The binary interface of the shared libraries in a package build.
+///
+/// This is saved alongside the other build artifacts as `abi.json`
+/// for packages that validate the `RemovedSymbols` rule, so that later
+/// versions can be compared against it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageAbi {
+    /// The shared libraries in the package, by their path in spfs
+    pub libraries: BTreeMap<String, LibraryAbi>,
+}
+
+impl PackageAbi {
+    /// Analyze the shared libraries amongst the given files, which
+    /// are relative to the prefix that the package was built into.
+    ///
+    /// Files that are not ELF shared libraries are ignored.
+    pub fn collect<I>(prefix: &Path, paths: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = RelativePathBuf>,
+    {
+        let mut libraries = BTreeMap::new();
+        for path in paths {
+            if !is_shared_library_name(&path) {
+                continue;
+            }
+            let on_disk = path.to_path(prefix);
+            let data = std::fs::read(&on_disk).map_err(|err| Error::FileOpenError(on_disk, err))?;
+            if let Some(library) = LibraryAbi::from_elf(&data) {
+                libraries.insert(format!("/{path}"), library);
+            }
+        }
+        Ok(Self { libraries })
+    }
+
+    /// The symbols exported by the libraries of a previous build
+    /// that are no longer exported by these libraries.
+    ///
+    /// Libraries are matched by their soname, or their path if they
+    /// have none. Removed symbols are listed under the path of the
+    /// library in this build, or under its previous path if the
+    /// library was removed entirely.
+    pub fn removed_symbols(&self, previous: &Self) -> BTreeMap<String, BTreeSet<String>> {
+        let current = self
+            .libraries
+            .iter()
+            .map(|(path, library)| (library.name(path), (path, library)))
+            .collect::<HashMap<_, _>>();
+        let mut removed = BTreeMap::new();
+        for (path, library) in previous.libraries.iter() {
+            let (path, symbols) = match current.get(library.name(path)) {
+                Some((path, current)) => (
+                    *path,
+                    library
+                        .symbols
+                        .difference(&current.symbols)
+                        .cloned()
+                        .collect::<BTreeSet<_>>(),
+                ),
+                None => (path, library.symbols.clone()),
+            };
+            if !symbols.is_empty() {
+                removed
+                    .entry(path.clone())
+                    .or_insert_with(BTreeSet::new)
+                    .extend(symbols);
+            }
+        }
+        removed
+    }
+}
+
+/// The binary interface of a single shared library.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryAbi {
+    /// The name that dependent binaries link against, if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soname: Option<String>,
+    /// The defined symbols that are visible to other binaries
+    #[serde(default)]
+    pub symbols: BTreeSet<String>,
+}
+
+impl LibraryAbi {
+    /// Read the exported symbols and soname of an ELF shared object.
+    ///
+    /// Returns None if the data is not a valid ELF shared object.
+    pub fn from_elf(data: &[u8]) -> Option<Self> {
+        match FileKind::parse(data).ok()? {
+            FileKind::Elf32 => Self::from_elf_file::<elf::FileHeader32<Endianness>>(data),
+            FileKind::Elf64 => Self::from_elf_file::<elf::FileHeader64<Endianness>>(data),
+            _ => None,
+        }
+    }
+
+    fn from_elf_file<Elf>(data: &[u8]) -> Option<Self>
+    where
+        Elf: FileHeader<Endian = Endianness>,
+    {
+        let header = Elf::parse(data).ok()?;
+        let endian = header.endian().ok()?;
+        if header.e_type(endian) != elf::ET_DYN {
+            return None;
+        }
+        let sections = header.sections(endian, data).ok()?;
+        let mut library = Self::default();
+
+        if let Some((entries, link)) = sections.dynamic(endian, data).ok()? {
+            let strings = sections.strings(endian, data, link).ok()?;
+            library.soname = entries
+                .iter()
+                .take_while(|entry| entry.tag32(endian) != Some(elf::DT_NULL))
+                .find(|entry| entry.tag32(endian) == Some(elf::DT_SONAME))
+                .and_then(|entry| entry.string(endian, strings).ok())
+                .and_then(|name| std::str::from_utf8(name).ok())
+                .map(String::from);
+        }
+
+        let symbols = sections.symbols(endian, data, elf::SHT_DYNSYM).ok()?;
+        for symbol in symbols.iter() {
+            let is_exported = !symbol.is_undefined(endian)
+                && matches!(symbol.st_bind(), elf::STB_GLOBAL | elf::STB_WEAK)
+                && !matches!(symbol.st_type(), elf::STT_SECTION | elf::STT_FILE)
+                && matches!(
+                    symbol.st_visibility(),
+                    elf::STV_DEFAULT | elf::STV_PROTECTED
+                );
+            if !is_exported {
+                continue;
+            }
+            let name = symbol.name(endian, symbols.strings()).ok()?;
+            let name = std::str::from_utf8(name).ok()?;
+            if !name.is_empty() {
+                library.symbols.insert(name.to_string());
+            }
+        }
+        Some(library)
+    }
+
+    /// The name used to identify this library between builds
+    fn name<'a>(&'a self, path: &'a str) -> &'a str {
+        self.soname.as_deref().unwrap_or(path)
+    }
+}
+
+/// Read the analyzed binary interface of a published package build,
+/// if it was saved when the package was built.
+///
+/// Only packages in spfs repositories can be read.
+pub async fn read_package_abi(
+    repo: &RepositoryHandle,
+    pkg: &BuildIdent,
+) -> Result<Option<PackageAbi>> {
+    let Some(spfs_repo) = repo.spfs_repository_for(pkg).await? else {
+        return Ok(None);
+    };
+    let abi_path = build_abi_path(pkg);
+    // every component includes the package metadata, so
+    // the first one that has a manifest is enough
+    for layer in repo.read_components(pkg).await?.values() {
+        let layer = spfs_repo.read_layer(*layer).await?;
+        let Some(manifest) = layer.manifest() else {
+            continue;
+        };
+        let manifest = spfs_repo
+            .read_manifest(*manifest)
+            .await?
+            .to_tracking_manifest();
+        let Some(entry) = manifest.get_path(abi_path.as_str()) else {
+            return Ok(None);
+        };
+        let (mut reader, filename) = spfs_repo.open_payload(entry.object).await?;
+        let mut json = String::new();
+        reader
+            .read_to_string(&mut json)
+            .await
+            .map_err(|err| Error::FileOpenError(filename, err))?;
+        return serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|err| Error::String(format!("Failed to read abi of {pkg}: {err}")));
+    }
+    Ok(None)
+}
+
+/// True if the file name looks like a shared library (eg: libfoo.so.1)
+fn is_shared_library_name(path: &RelativePathBuf) -> bool {
+    path.file_name()
+        .map(|name| name.ends_with(".so") || name.contains(".so."))
+        .unwrap_or_default()
+}
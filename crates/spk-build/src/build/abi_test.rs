@@ -0,0 +1,180 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use relative_path::RelativePathBuf;
+use rstest::rstest;
+
+use super::{LibraryAbi, PackageAbi};
+
+const STB_LOCAL: u8 = 0;
+const STB_GLOBAL: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+/// A symbol to include in a test library as (name, binding, defined)
+type TestSymbol<'a> = (&'a str, u8, bool);
+
+/// Create a minimal little-endian 64 bit ELF shared object which
+/// has only a dynamic symbol table and a dynamic section.
+fn make_elf_library(soname: &str, symbols: &[TestSymbol<'_>]) -> Vec<u8> {
+    let mut strings = vec![0u8];
+    let mut add_string = |s: &str| {
+        let index = strings.len() as u32;
+        strings.extend(s.as_bytes());
+        strings.push(0);
+        index
+    };
+    let soname_index = add_string(soname);
+    let mut dynsym = vec![0u8; 24];
+    for &(name, binding, defined) in symbols {
+        dynsym.extend(add_string(name).to_le_bytes());
+        dynsym.push((binding << 4) | STT_FUNC);
+        dynsym.push(0);
+        dynsym.extend(u16::from(defined).to_le_bytes());
+        dynsym.extend([0u8; 16]);
+    }
+    let mut dynamic = Vec::new();
+    dynamic.extend(14u64.to_le_bytes());
+    dynamic.extend(u64::from(soname_index).to_le_bytes());
+    dynamic.extend([0u8; 16]);
+
+    let strings_offset = 64;
+    let dynsym_offset = strings_offset + strings.len();
+    let dynamic_offset = dynsym_offset + dynsym.len();
+    let sections_offset = dynamic_offset + dynamic.len();
+
+    let mut data = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+    data.resize(16, 0);
+    data.extend(3u16.to_le_bytes());
+    data.resize(0x28, 0);
+    data.extend((sections_offset as u64).to_le_bytes());
+    data.resize(0x3A, 0);
+    data.extend(64u16.to_le_bytes());
+    data.extend(4u16.to_le_bytes());
+    data.resize(64, 0);
+    data.extend(&strings);
+    data.extend(&dynsym);
+    data.extend(&dynamic);
+
+    let mut section = |kind: u32, offset: usize, size: usize, link: u32, entry_size: u64| {
+        let start = data.len();
+        data.extend(0u32.to_le_bytes());
+        data.extend(kind.to_le_bytes());
+        data.resize(start + 24, 0);
+        data.extend((offset as u64).to_le_bytes());
+        data.extend((size as u64).to_le_bytes());
+        data.extend(link.to_le_bytes());
+        data.resize(start + 56, 0);
+        data.extend(entry_size.to_le_bytes());
+    };
+    section(0, 0, 0, 0, 0);
+    section(3, strings_offset, strings.len(), 0, 0);
+    section(11, dynsym_offset, dynsym.len(), 1, 24);
+    section(6, dynamic_offset, dynamic.len(), 1, 16);
+    data
+}
+
+fn library(soname: Option<&str>, symbols: &[&str]) -> LibraryAbi {
+    LibraryAbi {
+        soname: soname.map(String::from),
+        symbols: symbols.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[rstest]
+fn test_library_abi_from_elf() {
+    let data = make_elf_library(
+        "libexample.so.1",
+        &[
+            ("example_init", STB_GLOBAL, true),
+            ("example_helper", STB_LOCAL, true),
+            ("malloc", STB_GLOBAL, false),
+        ],
+    );
+    let abi = LibraryAbi::from_elf(&data).expect("should read the test library");
+    assert_eq!(abi, library(Some("libexample.so.1"), &["example_init"]));
+}
+
+#[rstest]
+fn test_library_abi_from_elf_ignores_other_files() {
+    assert_eq!(LibraryAbi::from_elf(b"#!/bin/bash\necho hi"), None);
+    let mut data = make_elf_library("libexample.so", &[]);
+    data.truncate(100);
+    assert_eq!(
+        LibraryAbi::from_elf(&data),
+        None,
+        "a truncated file should not be read"
+    );
+}
+
+#[rstest]
+fn test_package_abi_collect() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmpdir.path().join("lib")).unwrap();
+    std::fs::write(
+        tmpdir.path().join("lib/libexample.so.1"),
+        make_elf_library("libexample.so.1", &[("example_init", STB_GLOBAL, true)]),
+    )
+    .unwrap();
+    std::fs::write(tmpdir.path().join("lib/notes.txt"), "not a library").unwrap();
+
+    let abi = PackageAbi::collect(
+        tmpdir.path(),
+        [
+            RelativePathBuf::from("lib/libexample.so.1"),
+            RelativePathBuf::from("lib/notes.txt"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        abi.libraries.keys().collect::<Vec<_>>(),
+        vec!["/lib/libexample.so.1"]
+    );
+}
+
+#[rstest]
+fn test_package_abi_removed_symbols() {
+    let previous = PackageAbi {
+        libraries: [
+            (
+                "/lib/libexample.so.1.0".to_string(),
+                library(Some("libexample.so.1"), &["init", "run", "old"]),
+            ),
+            ("/lib/libgone.so".to_string(), library(None, &["gone"])),
+            ("/lib/libsame.so".to_string(), library(None, &["same"])),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    let current = PackageAbi {
+        libraries: [
+            // renamed files are still matched by their soname
+            (
+                "/lib/libexample.so.1.1".to_string(),
+                library(Some("libexample.so.1"), &["init", "run", "new"]),
+            ),
+            ("/lib/libsame.so".to_string(), library(None, &["same"])),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    let removed = current.removed_symbols(&previous);
+    let expected: BTreeMap<_, _> = [
+        ("/lib/libexample.so.1.1", vec!["old"]),
+        ("/lib/libgone.so", vec!["gone"]),
+    ]
+    .into_iter()
+    .map(|(path, symbols)| {
+        let symbols = symbols
+            .into_iter()
+            .map(String::from)
+            .collect::<BTreeSet<_>>();
+        (path.to_string(), symbols)
+    })
+    .collect();
+    assert_eq!(removed, expected);
+    assert!(previous.removed_symbols(&previous).is_empty());
+}
@@ -11,7 +11,7 @@ use std::sync::Arc;
 use futures::StreamExt;
 use relative_path::RelativePathBuf;
 use spfs::prelude::*;
-use spfs::tracking::DiffMode;
+use spfs::tracking::{DiffMode, GlobPathFilter, PathFilter};
 use spk_exec::{
     pull_resolved_runtime_layers,
     resolve_runtime_layers,
@@ -23,24 +23,34 @@ use spk_schema::foundation::format::FormatIdent;
 use spk_schema::foundation::ident_build::Build;
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::foundation::version::Compatibility;
 use spk_schema::ident::{PkgRequest, PreReleasePolicy, RangeIdent, RequestedBy, VersionIdent};
+use spk_schema::validation::ValidationMatcher;
 use spk_schema::variant::Override;
 use spk_schema::{
+    component_startup_script_stem,
+    startup_script_stem,
     BuildIdent,
     ComponentFileMatchMode,
     ComponentSpecList,
+    EnvOp,
     InputVariant,
     Package,
     PackageMut,
+    ScriptInterpreter,
     Variant,
     VariantExt,
 };
 use spk_solve::graph::Graph;
-use spk_solve::solution::Solution;
-use spk_solve::{BoxedResolverCallback, Named, ResolverCallback, Solver};
+use spk_solve::solution::{PackageSource, Solution};
+use spk_solve::{BoxedResolverCallback, Named, ResolverCallback, Solver, Versioned};
 use spk_storage as storage;
 
-use crate::report::{BuildOutputReport, BuildReport, BuildSetupReport};
+use super::abi::{read_package_abi, PackageAbi};
+use super::provenance::{BuildProvenance, ProvenanceSource};
+use super::relocate::relocate_build_output;
+use super::sandbox::sandbox_command;
+use crate::report::{AbiReport, BuildOutputReport, BuildReport, BuildSetupReport};
 use crate::validation::{Report, Validator};
 use crate::{Error, Result};
 
@@ -48,9 +58,24 @@ use crate::{Error, Result};
 #[path = "./binary_test.rs"]
 mod binary_test;
 
+/// The shells that startup scripts are generated for, along with
+/// the file extension that spfs uses to find them for each shell.
+const STARTUP_SCRIPT_SHELLS: &[(spfs::ShellKind, &str)] = &[
+    (spfs::ShellKind::Bash, "sh"),
+    (spfs::ShellKind::Tcsh, "csh"),
+    (spfs::ShellKind::Fish, "fish"),
+    (spfs::ShellKind::Powershell, "ps1"),
+];
+
+/// The spfs layer label that holds the package that a component layer was built for
+pub const PACKAGE_LAYER_LABEL: &str = "spk/package";
+/// The spfs layer label that holds the name of the component that a layer contains
+pub const COMPONENT_LAYER_LABEL: &str = "spk/component";
+
 /// Denotes an error during the build process.
 #[derive(Debug, miette::Diagnostic, thiserror::Error)]
 #[error("Build error: {message}")]
+#[diagnostic(code(spk::build::failed))]
 pub struct BuildError {
     pub message: String,
 }
@@ -138,8 +163,11 @@ pub struct BinaryPackageBuilder<'a, Recipe> {
     last_solve_graph: Arc<tokio::sync::RwLock<Graph>>,
     repos: Vec<Arc<storage::RepositoryHandle>>,
     interactive: bool,
+    script_output_to_stderr: bool,
     conflicting_packages: HashMap<ConflictingPackagePair, HashSet<RelativePathBuf>>,
     allow_circular_dependencies: bool,
+    source_provenance: Option<ProvenanceSource>,
+    source_digest: Option<spfs::encoding::Digest>,
 }
 
 impl<'a, Recipe> BinaryPackageBuilder<'a, Recipe>
@@ -167,8 +195,11 @@ where
             last_solve_graph: Arc::new(tokio::sync::RwLock::new(Graph::new())),
             repos: Default::default(),
             interactive: false,
+            script_output_to_stderr: false,
             conflicting_packages: Default::default(),
             allow_circular_dependencies: false,
+            source_provenance: None,
+            source_digest: None,
         }
     }
 
@@ -198,6 +229,17 @@ where
         self
     }
 
+    /// Require the resolved source package to have the given
+    /// digest for its source component.
+    ///
+    /// This ensures that a build uses exactly the sources that were
+    /// collected, even when the source package was made and published
+    /// from another host.
+    pub fn with_source_digest(&mut self, digest: spfs::encoding::Digest) -> &mut Self {
+        self.source_digest = Some(digest);
+        self
+    }
+
     /// Use the given repository when resolving source and build environment packages
     pub fn with_repository(&mut self, repo: Arc<storage::RepositoryHandle>) -> &mut Self {
         self.repos.push(repo);
@@ -213,6 +255,23 @@ where
         self
     }
 
+    /// Route the packages in each namespace to specific repositories
+    /// when resolving source and build environment packages
+    pub fn with_namespace_routes(&mut self, routes: spk_config::Namespaces) -> &mut Self {
+        self.solver.set_namespace_routes(routes);
+        self
+    }
+
+    /// Prefer builds from the repositories with the highest given priority
+    /// when resolving source and build environment packages
+    pub fn with_repository_priorities(
+        &mut self,
+        priorities: impl IntoIterator<Item = (String, i64)>,
+    ) -> &mut Self {
+        self.solver.set_repository_priorities(priorities);
+        self
+    }
+
     /// Provide a function that will be called when resolving the source package.
     ///
     /// This function should run the provided solver runtime to
@@ -249,6 +308,14 @@ where
         self
     }
 
+    /// Send the stdout of the build script to stderr instead, so
+    /// that it cannot be mixed into machine-readable output from
+    /// this process (eg: when reporting results as json)
+    pub fn with_script_output_to_stderr(&mut self, to_stderr: bool) -> &mut Self {
+        self.script_output_to_stderr = to_stderr;
+        self
+    }
+
     /// Return the resolve graph from the build environment.
     ///
     /// This is most useful for debugging build environments that failed to resolve,
@@ -310,21 +377,25 @@ where
         let all_options = self.recipe.resolve_options(&variant)?;
         tracing::debug!("  build options: {all_options}");
 
-        if let BuildSource::SourcePackage(ident) = self.source.clone() {
-            tracing::debug!("Resolving source package for build");
-            let solution = self.resolve_source_package(&all_options, ident).await?;
-            runtime
-                .status
-                .stack
-                .extend(resolve_runtime_layers(requires_localization, &solution).await?);
+        match self.source.clone() {
+            BuildSource::SourcePackage(ident) => {
+                tracing::debug!("Resolving source package for build");
+                let solution = self.resolve_source_package(&all_options, ident).await?;
+                runtime
+                    .status
+                    .stack
+                    .extend(resolve_runtime_layers(requires_localization, &solution).await?);
+                self.source_provenance = ProvenanceSource::from_source_solution(&solution);
+            }
+            BuildSource::LocalPath(path) => {
+                self.source_provenance = Some(ProvenanceSource::from_local_path(&path));
+            }
         };
 
         tracing::debug!("Resolving build environment");
         let solution = self
             .resolve_build_environment(&all_options, &variant)
             .await?;
-        self.environment
-            .extend(solution.to_environment(Some(std::env::vars())));
 
         let full_variant = variant
             .clone()
@@ -357,6 +428,9 @@ where
         );
         runtime.save_state_to_storage().await?;
         spfs::remount_runtime(&runtime).await?;
+        // files written by the hooks are committed to their own layer,
+        // so that they are not collected as part of the build
+        crate::install_hooks::run_install_hooks(&mut runtime, &solution).await?;
 
         let package = self.recipe.generate_binary_build(
             &VariantPair {
@@ -442,6 +516,9 @@ where
 
         let (solution, graph) = self.source_resolver.solve(&self.solver).await?;
         self.last_solve_graph = graph;
+        if let Some(expected) = self.source_digest {
+            verify_source_digest(&solution, expected)?;
+        }
         Ok(solution)
     }
 
@@ -508,13 +585,44 @@ where
         input: &BuildSetupReport<Recipe::Output, V>,
     ) -> Result<BuildOutputReport> {
         let options = input.variant.options();
-        self.build_artifacts(&input.package, &options).await?;
+        self.build_artifacts(&input.package, &options, &input.environment)
+            .await?;
 
         let source_ident =
             VersionIdent::new(self.recipe.name().to_owned(), self.recipe.version().clone())
                 .into_any(Some(Build::Source));
         let sources_dir = data_path(&source_ident);
 
+        if let Some(relocate) = input.package.build_relocate() {
+            tracing::info!(
+                "Checking build output for references to {}...",
+                self.prefix.display()
+            );
+            let metadata_dir = data_path(input.package.ident());
+            let changes = spfs::runtime_active_changes().await?;
+            let paths = changes
+                .walk()
+                .filter(|node| node.entry.is_regular_file())
+                .filter(|node| {
+                    !node.path.starts_with(&sources_dir) && !node.path.starts_with(&metadata_dir)
+                })
+                .map(|node| node.path.to_path(&self.prefix))
+                .collect::<Vec<_>>();
+            relocate_build_output(&self.prefix, paths, relocate)?;
+        }
+
+        let analyze_abi = input
+            .package
+            .validation()
+            .to_expanded_rules()
+            .iter()
+            .any(|rule| matches!(rule.condition(), ValidationMatcher::RemovedSymbols { .. }));
+        let abi = if analyze_abi {
+            Some(self.analyze_abi(&input.package, &sources_dir).await?)
+        } else {
+            None
+        };
+
         let active_changes = spfs::runtime_active_changes()
             .await?
             .take_root()
@@ -555,11 +663,110 @@ where
             })
             .collect();
 
+        if let Some(capture) = input.package.build_capture() {
+            let filter = GlobPathFilter::new(&capture.include, &capture.exclude)?;
+            let metadata_dir = data_path(input.package.ident());
+            collected_changes.retain(|diff| {
+                // the package's own metadata is always collected
+                metadata_dir.starts_with(&diff.path)
+                    || diff.path.starts_with(&metadata_dir)
+                    || filter.should_include_entry(&diff.path, diff.mode.is_dir())
+            });
+        }
+
         tracing::info!("Committing package contents...");
-        commit_component_layers(input, collected_changes).await
+        let mut output = commit_component_layers(input, collected_changes).await?;
+        output.prefix = self.prefix.clone();
+        output.abi = abi;
+        Ok(output)
+    }
+
+    /// Save the exported symbols of the libraries created by the
+    /// build, and find those of the previous version to compare against.
+    async fn analyze_abi(
+        &self,
+        package: &Recipe::Output,
+        sources_dir: &RelativePathBuf,
+    ) -> Result<AbiReport> {
+        tracing::info!("Analyzing the symbols exported by built libraries...");
+        let metadata_dir = data_path(package.ident());
+        let changes = spfs::runtime_active_changes().await?;
+        let paths = changes
+            .walk()
+            .filter(|node| node.entry.is_regular_file())
+            .filter(|node| {
+                !node.path.starts_with(sources_dir) && !node.path.starts_with(&metadata_dir)
+            })
+            .map(|node| node.path);
+        let current = PackageAbi::collect(&self.prefix, paths)?;
+
+        let build_abi = build_abi_path(package.ident()).to_path(&self.prefix);
+        let mut writer = std::fs::File::create(&build_abi)
+            .map_err(|err| Error::FileOpenError(build_abi.to_owned(), err))?;
+        serde_json::to_writer_pretty(&mut writer, &current)
+            .map_err(|err| Error::String(format!("Failed to save build abi: {err}")))?;
+        writer
+            .sync_data()
+            .map_err(|err| Error::FileWriteError(build_abi.to_owned(), err))?;
+
+        let previous = self.find_previous_abi(package).await?;
+        Ok(AbiReport { current, previous })
+    }
+
+    /// Find the saved abi of the same build of the closest previous
+    /// version of this package, as long as the package being built
+    /// claims to be binary compatible with that version.
+    async fn find_previous_abi(
+        &self,
+        package: &Recipe::Output,
+    ) -> Result<Option<(BuildIdent, PackageAbi)>> {
+        let pkg = package.ident();
+        let mut previous = None;
+        for repo in self.repos.iter() {
+            for version in repo.list_package_versions(pkg.name()).await?.iter() {
+                if **version >= *pkg.version() {
+                    continue;
+                }
+                if previous
+                    .as_ref()
+                    .is_some_and(|(highest, _)| version <= highest)
+                {
+                    continue;
+                }
+                previous = Some((Arc::clone(version), repo));
+            }
+        }
+        let Some((version, repo)) = previous else {
+            return Ok(None);
+        };
+        if let Compatibility::Incompatible(reason) = package.is_binary_compatible(&version) {
+            tracing::debug!("not comparing abi to {version}: {reason}");
+            return Ok(None);
+        }
+        let version = VersionIdent::new(pkg.name().to_owned(), (*version).clone());
+        let Some(build) = repo
+            .list_package_builds(&version)
+            .await?
+            .into_iter()
+            .find(|build| build.build() == pkg.build())
+        else {
+            tracing::debug!(
+                "not comparing abi, no {} build of {}",
+                pkg.build(),
+                version.format_ident()
+            );
+            return Ok(None);
+        };
+        let abi = read_package_abi(repo, &build).await?;
+        Ok(abi.map(|abi| (build, abi)))
     }
 
-    async fn build_artifacts<O>(&mut self, package: &Recipe::Output, options: O) -> Result<()>
+    async fn build_artifacts<O>(
+        &mut self,
+        package: &Recipe::Output,
+        options: O,
+        environment: &Solution,
+    ) -> Result<()>
     where
         O: AsRef<OptionMap>,
     {
@@ -567,7 +774,11 @@ where
         let metadata_dir = data_path(pkg).to_path(&self.prefix);
         let build_spec = build_spec_path(pkg).to_path(&self.prefix);
         let build_options = build_options_path(pkg).to_path(&self.prefix);
-        let build_script = build_script_path(pkg).to_path(&self.prefix);
+        let build_script_source = package.build_script();
+        let interpreter = ScriptInterpreter::from_script_text(&build_script_source);
+        let build_script = build_script_path_for(pkg, &interpreter).to_path(&self.prefix);
+        let build_provenance = build_provenance_path(pkg).to_path(&self.prefix);
+        let build_log = build_log_path(pkg).to_path(&self.prefix);
 
         std::fs::create_dir_all(&metadata_dir)
             .map_err(|err| Error::DirectoryCreateError(metadata_dir.to_owned(), err))?;
@@ -584,21 +795,45 @@ where
             let mut writer = std::fs::File::create(&build_script)
                 .map_err(|err| Error::FileOpenError(build_script.to_owned(), err))?;
             writer
-                .write_all(package.build_script().as_bytes())
+                .write_all(build_script_source.as_bytes())
                 .map_err(|err| Error::String(format!("Failed to save build script: {err}")))?;
             writer
                 .sync_data()
                 .map_err(|err| Error::FileWriteError(build_script.to_owned(), err))?;
         }
         {
+            // secret options are given to the build script below,
+            // but their values are never recorded with the package
+            let secret = package
+                .get_build_options()
+                .iter()
+                .filter(|opt| opt.is_secret())
+                .map(|opt| opt.full_name().without_namespace().to_owned())
+                .collect::<HashSet<_>>();
+            let recorded = options
+                .as_ref()
+                .iter()
+                .filter(|(name, _)| !secret.contains(name.without_namespace()))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect::<OptionMap>();
             let mut writer = std::fs::File::create(&build_options)
                 .map_err(|err| Error::FileOpenError(build_options.to_owned(), err))?;
-            serde_json::to_writer_pretty(&mut writer, options.as_ref())
+            serde_json::to_writer_pretty(&mut writer, &recorded)
                 .map_err(|err| Error::String(format!("Failed to save build options: {err}")))?;
             writer
                 .sync_data()
                 .map_err(|err| Error::FileWriteError(build_options.to_owned(), err))?;
         }
+        if let Some(source) = self.source_provenance.take() {
+            let provenance = BuildProvenance::collect(source, environment)?;
+            let mut writer = std::fs::File::create(&build_provenance)
+                .map_err(|err| Error::FileOpenError(build_provenance.to_owned(), err))?;
+            serde_json::to_writer_pretty(&mut writer, &provenance)
+                .map_err(|err| Error::String(format!("Failed to save build provenance: {err}")))?;
+            writer
+                .sync_data()
+                .map_err(|err| Error::FileWriteError(build_provenance.to_owned(), err))?;
+        }
         for cmpt in package.components().iter() {
             let marker_path = component_marker_path(pkg, &cmpt.name).to_path(&self.prefix);
             std::fs::File::create(&marker_path)
@@ -626,15 +861,34 @@ where
             spfs::build_interactive_shell_command(&runtime, Some("bash"))?
         } else {
             use std::ffi::OsString;
+            let args = interpreter
+                .args()
+                .iter()
+                .map(OsString::from)
+                .chain(std::iter::once(build_script.into_os_string()));
             spfs::build_shell_initialized_command(
                 &runtime,
                 Some("bash"),
-                OsString::from("bash"),
-                [OsString::from("-ex"), build_script.into_os_string()],
+                OsString::from(interpreter.program()),
+                args,
             )?
         };
 
         let mut cmd = cmd.into_std();
+        // the build script would otherwise inherit the entire environment
+        // of this process, but only the allowed host variables are kept
+        let build_environment = package.build_environment();
+        for (name, _) in std::env::vars_os() {
+            if !name
+                .to_str()
+                .is_some_and(|name| build_environment.is_allowed(name))
+            {
+                cmd.env_remove(name);
+            }
+        }
+        let host_environment = build_environment.host_environment(std::env::vars());
+        self.environment
+            .extend(environment.to_environment(Some(host_environment)));
         cmd.envs(self.environment.drain());
         cmd.envs(options.as_ref().to_environment());
         cmd.envs(package.get_build_env());
@@ -645,9 +899,20 @@ where
         //  the dependencies, is not supported by spfs, etc)
         cmd.env("SHELL", "bash");
         cmd.current_dir(&source_dir);
+        if let Some(sandbox) = package.build_sandbox() {
+            tracing::debug!(?sandbox, "running build script in sandbox");
+            cmd = sandbox_command(cmd, sandbox)?;
+        }
 
-        match cmd
-            .status()
+        // interactive builds are attached to the terminal and are not logged
+        let status = if self.interactive {
+            cmd.status()
+        } else {
+            let log = std::fs::File::create(&build_log)
+                .map_err(|err| Error::FileOpenError(build_log.to_owned(), err))?;
+            status_with_log(&mut cmd, log, self.script_output_to_stderr)
+        };
+        match status
             .map_err(|err| {
                 Error::ProcessSpawnError(spfs::Error::process_spawn_error(
                     "build script",
@@ -675,6 +940,17 @@ where
 
     fn generate_startup_scripts(&self, package: &impl Package) -> Result<()> {
         let ops = package.runtime_environment();
+        self.write_startup_scripts(&startup_script_stem(package.name(), ops), ops)?;
+        for component in package.components().iter() {
+            let file_stem = component_startup_script_stem(package.name(), &component.name, ops);
+            self.write_startup_scripts(&file_stem, &component.environment)?;
+        }
+        Ok(())
+    }
+
+    /// Write a startup script for each supported shell that
+    /// applies the given ops, unless there are none.
+    fn write_startup_scripts(&self, file_stem: &str, ops: &[EnvOp]) -> Result<()> {
         if ops.is_empty() {
             return Ok(());
         }
@@ -686,35 +962,17 @@ where
             }
         }
 
-        let mut startup_file_csh = startup_dir.join(format!("spk_{}.csh", package.name()));
-        let mut startup_file_sh = startup_dir.join(format!("spk_{}.sh", package.name()));
-        let mut csh_file = std::fs::File::create(&startup_file_csh)
-            .map_err(|err| Error::FileOpenError(startup_file_csh.to_owned(), err))?;
-        let mut sh_file = std::fs::File::create(&startup_file_sh)
-            .map_err(|err| Error::FileOpenError(startup_file_sh.to_owned(), err))?;
-
-        for op in ops {
-            if let Some(priority) = op.priority() {
-                let original_startup_file_sh_name = startup_file_sh.clone();
-                let original_startup_file_csh_name = startup_file_csh.clone();
-
-                startup_file_sh.set_file_name(format!("{priority:02}_spk_{}.sh", package.name()));
-                startup_file_csh.set_file_name(format!("{priority:02}_spk_{}.csh", package.name()));
-
-                std::fs::rename(original_startup_file_sh_name, &startup_file_sh)
-                    .map_err(|err| Error::FileWriteError(startup_file_sh.to_owned(), err))?;
-                std::fs::rename(original_startup_file_csh_name, &startup_file_csh)
-                    .map_err(|err| Error::FileWriteError(startup_file_csh.to_owned(), err))?;
-
-                continue;
+        for (shell, extension) in STARTUP_SCRIPT_SHELLS {
+            let startup_file = startup_script_path(file_stem, extension).to_path(&self.prefix);
+            let mut file = std::fs::File::create(&startup_file)
+                .map_err(|err| Error::FileOpenError(startup_file.to_owned(), err))?;
+            for op in ops {
+                if op.priority().is_some() {
+                    continue;
+                }
+                file.write_fmt(format_args!("{}\n", op.source_for_shell(*shell)))
+                    .map_err(|err| Error::FileWriteError(startup_file.to_owned(), err))?;
             }
-
-            csh_file
-                .write_fmt(format_args!("{}\n", op.tcsh_source()))
-                .map_err(|err| Error::FileWriteError(startup_file_csh.to_owned(), err))?;
-            sh_file
-                .write_fmt(format_args!("{}\n", op.bash_source()))
-                .map_err(|err| Error::FileWriteError(startup_file_sh.to_owned(), err))?;
         }
         Ok(())
     }
@@ -755,11 +1013,16 @@ where
         input.package.ident(),
         &collected_layer,
         input.package.components(),
+        input.package.runtime_environment(),
     )?;
     let mut components = HashMap::new();
     for (component, manifest) in manifests {
         let storable_manifest = manifest.to_graph_manifest();
-        let layer = spfs::graph::Layer::new(storable_manifest.digest().unwrap());
+        let layer = spfs::graph::Layer::builder()
+            .with_manifest(storable_manifest.digest().unwrap())
+            .with_label(PACKAGE_LAYER_LABEL, input.package.ident().to_string())
+            .with_label(COMPONENT_LAYER_LABEL, component.to_string())
+            .build();
         let layer_digest = layer.digest().unwrap();
         #[rustfmt::skip]
         tokio::try_join!(
@@ -778,6 +1041,7 @@ where
         collected_layer,
         collected_changes,
         components,
+        prefix: Default::default(),
     })
 }
 
@@ -785,7 +1049,24 @@ fn split_manifest_by_component(
     pkg: &BuildIdent,
     manifest: &spfs::tracking::Manifest,
     components: &ComponentSpecList,
+    environment: &[EnvOp],
 ) -> Result<HashMap<Component, spfs::tracking::Manifest>> {
+    // the startup scripts for the environment of a component
+    // are only ever included in that component
+    let component_scripts = components
+        .iter()
+        .filter(|component| !component.environment.is_empty())
+        .map(|component| {
+            let file_stem = component_startup_script_stem(pkg.name(), &component.name, environment);
+            let paths = STARTUP_SCRIPT_SHELLS
+                .iter()
+                .map(|(_, extension)| startup_script_path(&file_stem, extension))
+                .collect::<Vec<_>>();
+            (component.name.clone(), paths)
+        })
+        .collect::<HashMap<_, _>>();
+    let all_component_scripts = component_scripts.values().flatten().collect::<HashSet<_>>();
+
     let mut seen = HashSet::new();
     let mut manifests = HashMap::with_capacity(components.len());
     for component in components.iter() {
@@ -803,12 +1084,20 @@ fn split_manifest_by_component(
         relevant_paths.insert(build_spec_path(pkg));
         relevant_paths.insert(build_options_path(pkg));
         relevant_paths.insert(build_script_path(pkg));
+        relevant_paths.insert(build_provenance_path(pkg));
+        relevant_paths.insert(build_abi_path(pkg));
         relevant_paths.insert(component_marker_path(pkg, &component.name));
         relevant_paths.extend(path_and_parents(data_path(pkg)));
+        for script in component_scripts.get(&component.name).into_iter().flatten() {
+            relevant_paths.extend(path_and_parents(script.to_owned()));
+        }
         for node in manifest.walk() {
-            if node.path.strip_prefix(data_path(pkg)).is_ok() {
-                // paths within the metadata directory are controlled
-                // separately and cannot be included by the component spec
+            if node.path.strip_prefix(data_path(pkg)).is_ok()
+                || all_component_scripts.contains(&node.path)
+            {
+                // paths within the metadata directory and the startup scripts
+                // of components are controlled separately and cannot be
+                // included by the component spec
                 continue;
             }
             if component
@@ -845,6 +1134,112 @@ fn split_manifest_by_component(
     Ok(manifests)
 }
 
+/// Check that the source package resolved in the given solution
+/// has the expected digest for its source component.
+fn verify_source_digest(solution: &Solution, expected: spfs::encoding::Digest) -> Result<()> {
+    let Some(solved) = solution.items().next() else {
+        return Err(Error::String(
+            "No source package was resolved to verify".to_string(),
+        ));
+    };
+    let actual = match &solved.source {
+        PackageSource::Repository { components, .. } => components.get(&Component::Source),
+        _ => None,
+    };
+    let ident = solved.spec.ident().to_string();
+    match actual {
+        Some(actual) if *actual == expected => Ok(()),
+        Some(actual) => Err(Error::SourceDigestMismatch {
+            ident,
+            expected,
+            actual: *actual,
+        }),
+        None => Err(Error::String(format!(
+            "Source package {ident} has no source component to verify"
+        ))),
+    }
+}
+
+/// Run the given command to completion, copying its stdout and
+/// stderr into the log file as well as to this process' own. The
+/// command's stdout is copied to this process' stderr when
+/// `stdout_to_stderr` is set.
+fn status_with_log(
+    cmd: &mut std::process::Command,
+    log: std::fs::File,
+    stdout_to_stderr: bool,
+) -> std::io::Result<std::process::ExitStatus> {
+    use std::process::Stdio;
+
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let log = Arc::new(std::sync::Mutex::new(log));
+    let mut copies = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let output: Box<dyn Write + Send> = if stdout_to_stderr {
+            Box::new(std::io::stderr())
+        } else {
+            Box::new(std::io::stdout())
+        };
+        copies.push(tee_output(stdout, output, Arc::clone(&log)));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        copies.push(tee_output(stderr, std::io::stderr(), Arc::clone(&log)));
+    }
+    let status = child.wait()?;
+    for copy in copies {
+        // the output is only lost if a copy panicked
+        let _ = copy.join();
+    }
+    log.lock()
+        .map_err(|_| std::io::Error::other("build log was poisoned"))?
+        .sync_data()?;
+    Ok(status)
+}
+
+/// Copy everything from the reader into both the output and the log,
+/// on a separate thread until the reader is closed.
+fn tee_output<R, W>(
+    mut reader: R,
+    mut output: W,
+    log: Arc<std::sync::Mutex<std::fs::File>>,
+) -> std::thread::JoinHandle<()>
+where
+    R: std::io::Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut buf = [0; 8192];
+        let mut logging = true;
+        loop {
+            let count = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(count) => count,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    tracing::warn!("Failed to read build output: {err}");
+                    break;
+                }
+            };
+            let _ = output.write_all(&buf[..count]);
+            let _ = output.flush();
+            if !logging {
+                continue;
+            }
+            if let Ok(mut log) = log.lock() {
+                if let Err(err) = log.write_all(&buf[..count]) {
+                    tracing::warn!("Failed to write build log, it will be incomplete: {err}");
+                    logging = false;
+                }
+            }
+        }
+    })
+}
+
+/// Return the path of a startup script, relative to the prefix.
+fn startup_script_path(file_stem: &str, extension: &str) -> RelativePathBuf {
+    RelativePathBuf::from("etc/spfs/startup.d").join(format!("{file_stem}.{extension}"))
+}
+
 /// Return the file path for the given source package's files.
 pub fn source_package_path(pkg: &BuildIdent) -> RelativePathBuf {
     data_path(pkg)
@@ -874,6 +1269,42 @@ pub fn build_script_path(pkg: &BuildIdent) -> RelativePathBuf {
     data_path(pkg).join("build.sh")
 }
 
+/// Return the file path for the given build's build script
+/// when it is run by the given interpreter.
+///
+/// Scripts that are run by bash are stored in the [`build_script_path`],
+/// other languages get a matching extension, eg: build.py
+pub fn build_script_path_for(pkg: &BuildIdent, interpreter: &ScriptInterpreter) -> RelativePathBuf {
+    match interpreter.file_extension() {
+        "sh" => build_script_path(pkg),
+        ext => data_path(pkg).join(format!("build.{ext}")),
+    }
+}
+
+/// Return the file path for the given build's provenance.json file.
+///
+/// This file is created during a build and stores a
+/// [`BuildProvenance`] record of how the package was built
+pub fn build_provenance_path(pkg: &BuildIdent) -> RelativePathBuf {
+    data_path(pkg).join("provenance.json")
+}
+
+/// Return the file path for the given build's abi.json file.
+///
+/// This file is created during a build when the package validates
+/// `RemovedSymbols` and stores the [`PackageAbi`] of its libraries
+pub fn build_abi_path(pkg: &BuildIdent) -> RelativePathBuf {
+    data_path(pkg).join("abi.json")
+}
+
+/// Return the file path for the given build's build.log file.
+///
+/// This file is created during a build and stores the combined
+/// stdout and stderr of the build script
+pub fn build_log_path(pkg: &BuildIdent) -> RelativePathBuf {
+    data_path(pkg).join("build.log")
+}
+
 /// Return the file path for the given build's build.sh file.
 ///
 /// This file is created during a build and stores the bash
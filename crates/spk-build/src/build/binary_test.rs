@@ -17,7 +17,13 @@ use spk_solve::Solution;
 use spk_storage::fixtures::*;
 use spk_storage::{self as storage, Repository};
 
-use super::{BinaryPackageBuilder, BuildSource};
+use super::{
+    status_with_log,
+    BinaryPackageBuilder,
+    BuildSource,
+    COMPONENT_LAYER_LABEL,
+    PACKAGE_LAYER_LABEL,
+};
 use crate::build::SourcePackageBuilder;
 
 #[rstest]
@@ -36,17 +42,58 @@ fn test_split_manifest_permissions() {
                 entries: Default::default(),
                 user_data: (),
                 legacy_size: 0,
+                xattrs: Default::default(),
             },
         )
         .unwrap();
     let pkg = "mypkg/1.0.0/3I42H3S6".parse().unwrap();
     let spec = ComponentSpecList::default();
-    let components = super::split_manifest_by_component(&pkg, &manifest, &spec).unwrap();
+    let components = super::split_manifest_by_component(&pkg, &manifest, &spec, &[]).unwrap();
     let run = components.get(&Component::Run).unwrap();
     assert_eq!(run.get_path("bin").unwrap().mode, 0o754);
     assert_eq!(run.get_path("bin/runme").unwrap().mode, 0o555);
 }
 
+#[rstest]
+fn test_split_manifest_component_startup_scripts() {
+    use spfs::tracking::{Entry, EntryKind, Manifest};
+    let mut manifest = Manifest::default();
+    manifest.mkdirs("etc/spfs/startup.d").unwrap();
+    for script in ["spk_mypkg.sh", "spk_mypkg_python.sh"] {
+        manifest
+            .mknod(
+                format!("etc/spfs/startup.d/{script}"),
+                Entry {
+                    kind: EntryKind::Blob(0),
+                    object: EMPTY_DIGEST.into(),
+                    mode: 0o644,
+                    entries: Default::default(),
+                    user_data: (),
+                    legacy_size: 0,
+                    xattrs: Default::default(),
+                },
+            )
+            .unwrap();
+    }
+    let pkg = "mypkg/1.0.0/3I42H3S6".parse().unwrap();
+    let spec: ComponentSpecList = serde_yaml::from_str(
+        "[{name: run, files: ['*']}, {name: python, files: ['*'], environment: [{set: PYTHON, value: '1'}]}]",
+    )
+    .unwrap();
+    let components = super::split_manifest_by_component(&pkg, &manifest, &spec, &[]).unwrap();
+    let run = components.get(&Component::Run).unwrap();
+    let python = components.get(&Component::Named("python".into())).unwrap();
+    assert!(run.get_path("etc/spfs/startup.d/spk_mypkg.sh").is_some());
+    assert!(
+        run.get_path("etc/spfs/startup.d/spk_mypkg_python.sh")
+            .is_none(),
+        "the script for a component environment should not be in other components"
+    );
+    assert!(python
+        .get_path("etc/spfs/startup.d/spk_mypkg_python.sh")
+        .is_some());
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_empty_var_option_is_not_a_request() {
@@ -128,6 +175,44 @@ async fn test_build_workdir(tmpdir: tempfile::TempDir) {
     );
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_build_environment_filters_host_vars(tmpdir: tempfile::TempDir) {
+    let rt = spfs_runtime().await;
+    std::env::set_var("SPK_TEST_LEAKED_VAR", "leaked");
+    std::env::set_var("SPK_TEST_ALLOWED_VAR", "allowed");
+    let out_file = tmpdir.path().join("out.log");
+    let recipe = recipe!({
+        "pkg": "test/1.0.0",
+        "build": {
+            "script": format!(
+                "echo ${{SPK_TEST_LEAKED_VAR:-unset}} $SPK_TEST_ALLOWED_VAR $SPK_TEST_SET_VAR > {out_file:?}"
+            ),
+            "environment": {
+                "allow": ["SPK_TEST_ALLOWED_*"],
+                "set": {"SPK_TEST_SET_VAR": "set"},
+            },
+            "validation": {
+                "rules": [{"allow": "EmptyPackage"}]
+            }
+        }
+    });
+
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    BinaryPackageBuilder::from_recipe(recipe)
+        .with_source(BuildSource::LocalPath(tmpdir.path().to_owned()))
+        .build_and_publish(&option_map! {}, &*rt.tmprepo)
+        .await
+        .unwrap();
+
+    let out = std::fs::read_to_string(out_file).unwrap();
+    assert_eq!(
+        out.trim(),
+        "unset allowed set",
+        "only allowed and set variables should reach the build script"
+    );
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_build_package_options() {
@@ -615,6 +700,103 @@ async fn test_build_package_source_cleanup() {
     );
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_build_verifies_source_digest() {
+    let rt = spfs_runtime().await;
+    let spec = recipe!(
+        {
+            "pkg": "my-pkg/1.0.0",
+            "sources": [{"path": "../../examples", "subdir": "examples"}],
+            "build": {"script": "echo building..."},
+        }
+    );
+    rt.tmprepo.publish_recipe(&spec).await.unwrap();
+    let (_, components) = SourcePackageBuilder::from_recipe(spec.clone())
+        .build_and_publish(".", &*rt.tmprepo)
+        .await
+        .unwrap();
+    let source_digest = *components.get(&Component::Source).unwrap();
+
+    let res = BinaryPackageBuilder::from_recipe(spec.clone())
+        .with_repository(rt.tmprepo.clone())
+        .with_source_digest(EMPTY_DIGEST.into())
+        .build_and_publish(option_map! {}, &*rt.tmprepo)
+        .await;
+    match res {
+        Err(crate::Error::SourceDigestMismatch { actual, .. }) => {
+            assert_eq!(actual, source_digest)
+        }
+        res => panic!("a source package with a different digest should not be built, got {res:?}"),
+    }
+
+    BinaryPackageBuilder::from_recipe(spec)
+        .with_repository(rt.tmprepo.clone())
+        .with_source_digest(source_digest)
+        .build_and_publish(option_map! {}, &*rt.tmprepo)
+        .await
+        .unwrap();
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_build_capture_excludes_files() {
+    let rt = spfs_runtime().await;
+    let spec = recipe!(
+        {
+            "pkg": "capture-test/1.0.0",
+            "sources": [],
+            "build": {
+                "script": [
+                    "mkdir -p $PREFIX/lib $PREFIX/.cache",
+                    "touch $PREFIX/lib/libcapture.so",
+                    "touch $PREFIX/lib/capture.o",
+                    "touch $PREFIX/.cache/junk",
+                ],
+                "capture": {"exclude": ["*.o", ".cache"]},
+            },
+        }
+    );
+    rt.tmprepo.publish_recipe(&spec).await.unwrap();
+
+    let (pkg, _) = BinaryPackageBuilder::from_recipe(spec)
+        .with_repository(rt.tmprepo.clone())
+        .build_and_publish(option_map! {}, &*rt.tmprepo)
+        .await
+        .unwrap();
+
+    let digest = *storage::local_repository()
+        .await
+        .unwrap()
+        .read_components(pkg.ident())
+        .await
+        .unwrap()
+        .get(&Component::Run)
+        .unwrap();
+    let config = spfs::get_config().unwrap();
+    let repo = config.get_local_repository().await.unwrap();
+    let layer = repo.read_layer(digest).await.unwrap();
+    let manifest = repo
+        .read_manifest(*layer.manifest().expect("layer should have a manifest"))
+        .await
+        .unwrap()
+        .to_tracking_manifest();
+
+    assert!(manifest.get_path("lib/libcapture.so").is_some());
+    assert!(
+        manifest.get_path("lib/capture.o").is_none(),
+        "excluded files should not be collected"
+    );
+    assert!(
+        manifest.get_path(".cache").is_none(),
+        "excluded directories should not be collected"
+    );
+    assert!(
+        manifest.get_path(data_path(pkg.ident())).is_some(),
+        "package metadata should always be collected"
+    );
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_build_filters_reset_files() {
@@ -792,6 +974,45 @@ async fn test_build_components_metadata() {
     }
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_build_labels_component_layers() {
+    let rt = spfs_runtime().await;
+    let spec = recipe!(
+        {
+            "pkg": "mypkg/1.0.0",
+            "build": {
+                "script": "echo building...",
+            },
+        }
+    );
+    rt.tmprepo.publish_recipe(&spec).await.unwrap();
+    let (spec, _) = BinaryPackageBuilder::from_recipe(spec.clone())
+        .with_source(BuildSource::LocalPath(".".into()))
+        .build_and_publish(option_map! {}, &*rt.tmprepo)
+        .await
+        .unwrap();
+
+    let published = storage::local_repository()
+        .await
+        .unwrap()
+        .read_components(spec.ident())
+        .await
+        .unwrap();
+    let config = spfs::get_config().unwrap();
+    let repo = config.get_local_repository().await.unwrap();
+    for (component, digest) in published {
+        let layer = repo.read_layer(digest).await.unwrap();
+        let package = spec.ident().to_string();
+        assert_eq!(layer.label(PACKAGE_LAYER_LABEL), Some(package.as_str()));
+        assert_eq!(
+            layer.label(COMPONENT_LAYER_LABEL),
+            Some(component.as_str()),
+            "each layer should be labeled with the component that it holds"
+        );
+    }
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_build_add_startup_files(tmpdir: tempfile::TempDir) {
@@ -840,6 +1061,23 @@ async fn test_build_add_startup_files(tmpdir: tempfile::TempDir) {
         .stdout;
 
     assert_eq!(String::from_utf8_lossy(&tcsh_value), "1.7:true:append\n");
+
+    let ps1_file = tmpdir.path().join("etc/spfs/startup.d/spk_testpkg.ps1");
+    assert!(ps1_file.exists());
+    let fish_file = tmpdir.path().join("etc/spfs/startup.d/spk_testpkg.fish");
+    assert!(fish_file.exists());
+
+    let fish_value = match std::process::Command::new("fish")
+        .args(["--no-config", "-c"])
+        .arg(format!("source {fish_file:?}; printenv TESTPKG"))
+        .output()
+    {
+        Ok(output) => output.stdout,
+        // fish is not installed on all systems
+        Err(_) => return,
+    };
+
+    assert_eq!(String::from_utf8_lossy(&fish_value), "1.7:true:append\n");
 }
 
 #[rstest]
@@ -1137,3 +1375,24 @@ async fn test_build_options_respect_components() {
         panic!("build script for 'top' expected to succeed");
     }
 }
+
+#[rstest]
+fn test_status_with_log_captures_output(tmpdir: tempfile::TempDir) {
+    let log_path = tmpdir.path().join("build.log");
+    let log = std::fs::File::create(&log_path).unwrap();
+    let mut cmd = std::process::Command::new("bash");
+    cmd.args(["-c", "echo to-stdout; echo to-stderr >&2; exit 3"]);
+
+    let status = status_with_log(&mut cmd, log).unwrap();
+    assert_eq!(status.code(), Some(3), "exit status should be preserved");
+
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    assert!(
+        log.contains("to-stdout\n"),
+        "stdout should be logged, got: {log}"
+    );
+    assert!(
+        log.contains("to-stderr\n"),
+        "stderr should be logged, got: {log}"
+    );
+}
@@ -2,12 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+mod abi;
 mod binary;
+mod provenance;
+mod relocate;
+mod sandbox;
 mod sources;
 
+pub use abi::{read_package_abi, LibraryAbi, PackageAbi};
 pub use binary::{
+    build_abi_path,
+    build_log_path,
     build_options_path,
+    build_provenance_path,
     build_script_path,
+    build_script_path_for,
     build_spec_path,
     commit_component_layers,
     component_marker_path,
@@ -15,5 +24,16 @@ pub use binary::{
     BinaryPackageBuilder,
     BuildError,
     BuildSource,
+    COMPONENT_LAYER_LABEL,
+    PACKAGE_LAYER_LABEL,
+};
+pub use provenance::{BuildProvenance, ProvenancePackage, ProvenanceSource};
+pub use relocate::{relocate_build_output, PrefixReference};
+pub use sandbox::sandbox_command;
+pub use sources::{
+    validate_source_changeset,
+    CollectedGitSource,
+    CollectionError,
+    SourcePackageBuilder,
+    GIT_SOURCES_FILE,
 };
-pub use sources::{validate_source_changeset, CollectionError, SourcePackageBuilder};
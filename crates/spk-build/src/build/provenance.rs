@@ -0,0 +1,153 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::Package;
+use spk_solve::solution::{PackageSource, Solution, SolvedRequest};
+
+use crate::Result;
+
+#[cfg(test)]
+#[path = "./provenance_test.rs"]
+mod provenance_test;
+
+/// Records where, when, by whom and from what a package build was made.
+///
+/// This is saved alongside the other build artifacts as `provenance.json`
+/// so that published packages can be audited after the fact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildProvenance {
+    /// The name of the host that ran the build
+    pub host: String,
+    /// The user that ran the build
+    pub user: String,
+    /// When the build was run
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// The version of spk that ran the build
+    pub spk_version: String,
+    /// The source files that were built
+    pub source: ProvenanceSource,
+    /// The packages that made up the build environment
+    pub environment: Vec<ProvenancePackage>,
+}
+
+impl BuildProvenance {
+    /// Collect the provenance of a build that is about to run
+    /// in the current process using the given source and
+    /// build environment.
+    pub fn collect(source: ProvenanceSource, environment: &Solution) -> Result<Self> {
+        let environment = environment
+            .items()
+            .map(ProvenancePackage::from_solved_request)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            host: whoami::hostname(),
+            user: whoami::username(),
+            timestamp: chrono::Utc::now(),
+            spk_version: env!("CARGO_PKG_VERSION").to_string(),
+            source,
+            environment,
+        })
+    }
+}
+
+/// Identifies the source files used in a build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ProvenanceSource {
+    /// The build used a source package from a repository
+    SourcePackage {
+        /// The identifier of the source package
+        ident: String,
+        /// The repository that the source package came from
+        repository: Option<String>,
+        /// The spfs layers of the source package's components
+        layers: BTreeMap<Component, spfs::Digest>,
+    },
+    /// The build used a set of local files
+    LocalPath {
+        /// The local directory that was built
+        path: PathBuf,
+        /// The version control commit checked out in the
+        /// directory at the time of the build, if any
+        vcs_commit: Option<String>,
+    },
+}
+
+impl ProvenanceSource {
+    /// Describe the source package resolved in the given solution.
+    pub fn from_source_solution(solution: &Solution) -> Option<Self> {
+        let solved = solution.items().next()?;
+        let layers = match &solved.source {
+            PackageSource::Repository { components, .. } => {
+                components.iter().map(|(c, d)| (c.clone(), *d)).collect()
+            }
+            _ => Default::default(),
+        };
+        Some(Self::SourcePackage {
+            ident: solved.spec.ident().to_string(),
+            repository: solved.repo_name().map(|n| n.to_string()),
+            layers,
+        })
+    }
+
+    /// Describe a local source directory, including the git
+    /// commit that is checked out there, if any.
+    pub fn from_local_path(path: &Path) -> Self {
+        Self::LocalPath {
+            path: path.to_owned(),
+            vcs_commit: find_vcs_commit(path),
+        }
+    }
+}
+
+/// A package that was present in the build environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenancePackage {
+    /// The identifier of the resolved package build
+    pub ident: String,
+    /// The repository that the package came from, if any
+    pub repository: Option<String>,
+    /// The spfs layers of the components that were used
+    pub layers: BTreeMap<Component, spfs::Digest>,
+}
+
+impl ProvenancePackage {
+    fn from_solved_request(solved: &SolvedRequest) -> Result<Self> {
+        let layers = match solved.component_layers() {
+            Ok(layers) => layers.into_iter().collect(),
+            Err(spk_solve::solution::Error::EmbeddedHasNoComponentLayers)
+            | Err(spk_solve::solution::Error::SpkInternalTestHasNoComponentLayers) => {
+                Default::default()
+            }
+            Err(err) => return Err(spk_solve::Error::from(err).into()),
+        };
+        Ok(Self {
+            ident: solved.spec.ident().to_string(),
+            repository: solved.repo_name().map(|n| n.to_string()),
+            layers,
+        })
+    }
+}
+
+/// Find the git commit checked out in the given directory, if any.
+fn find_vcs_commit(path: &Path) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .arg("rev-parse")
+        .arg("HEAD")
+        .current_dir(path)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?;
+    let commit = commit.trim();
+    (!commit.is_empty()).then(|| commit.to_string())
+}
@@ -0,0 +1,38 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::option_map;
+use spk_solve::solution::Solution;
+
+use super::{BuildProvenance, ProvenanceSource};
+
+#[rstest]
+fn test_provenance_local_path_without_vcs() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let source = ProvenanceSource::from_local_path(tmpdir.path());
+    assert_eq!(
+        source,
+        ProvenanceSource::LocalPath {
+            path: tmpdir.path().to_owned(),
+            vcs_commit: None,
+        }
+    );
+}
+
+#[rstest]
+fn test_provenance_round_trip() {
+    let source = ProvenanceSource::LocalPath {
+        path: "/some/dir".into(),
+        vcs_commit: Some("abc123".into()),
+    };
+    let provenance = BuildProvenance::collect(source, &Solution::new(option_map! {})).unwrap();
+    assert_eq!(provenance.spk_version, env!("CARGO_PKG_VERSION"));
+    assert!(provenance.environment.is_empty());
+
+    let serialized = serde_json::to_string(&provenance).unwrap();
+    assert!(serialized.contains("\"kind\":\"localPath\""));
+    let deserialized: BuildProvenance = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, provenance);
+}
@@ -0,0 +1,400 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Finds and rewrites references to the build prefix, see [`RelocateSpec`]
+
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use object::read::elf::{Dyn, FileHeader, ProgramHeader};
+use object::read::StringTable;
+use object::{elf, Endianness, FileKind};
+use spk_schema::{RelocateFileKind, RelocateMode, RelocateSpec};
+
+use super::binary::BuildError;
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./relocate_test.rs"]
+mod relocate_test;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// A reference to the build prefix that was found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixReference {
+    pub path: PathBuf,
+    pub kind: RelocateFileKind,
+    /// The value that refers to the build prefix
+    pub value: String,
+    /// The value that this reference was replaced with, if any
+    pub rewritten: Option<String>,
+}
+
+impl std::fmt::Display for PrefixReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] {}", self.path.display(), self.kind, self.value)?;
+        if let Some(rewritten) = &self.rewritten {
+            write!(f, " -> {rewritten}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Check the given files for references to the build prefix,
+/// handling them as configured in the relocate spec.
+///
+/// Only regular files are checked, and any other paths are skipped.
+pub fn relocate_build_output<I>(
+    prefix: &Path,
+    paths: I,
+    spec: &RelocateSpec,
+) -> Result<Vec<PrefixReference>>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    let rewrite = spec.mode == RelocateMode::Rewrite;
+    let mut references = Vec::new();
+    for path in paths {
+        match std::fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_file() => {}
+            Ok(_) => continue,
+            Err(err) => return Err(Error::FileOpenError(path, err)),
+        }
+        // only the start of each file is needed to tell if it
+        // should be checked, which avoids reading most of them
+        let header = read_header(&path, ELF_MAGIC.len())?;
+        let kind = if header.starts_with(ELF_MAGIC) {
+            RelocateFileKind::Elf
+        } else if header.starts_with(b"#!") {
+            RelocateFileKind::Script
+        } else if path.extension().is_some_and(|ext| ext == "pc") {
+            RelocateFileKind::PkgConfig
+        } else {
+            continue;
+        };
+        if !spec.includes(kind) {
+            continue;
+        }
+        let data = std::fs::read(&path).map_err(|err| Error::FileOpenError(path.clone(), err))?;
+        let found = match kind {
+            RelocateFileKind::Elf => relocate_elf(prefix, &path, &data, rewrite)?,
+            RelocateFileKind::Script => relocate_script(prefix, &path, &data, rewrite)?,
+            RelocateFileKind::PkgConfig => relocate_pkg_config(prefix, &path, &data, rewrite)?,
+        };
+        references.extend(found);
+    }
+
+    for reference in references.iter() {
+        match reference.rewritten {
+            Some(_) => tracing::info!("relocated: {reference}"),
+            None => tracing::warn!("not relocatable: {reference}"),
+        }
+    }
+    let remaining = references
+        .iter()
+        .filter(|r| r.rewritten.is_none())
+        .collect::<Vec<_>>();
+    if spec.mode == RelocateMode::Error && !remaining.is_empty() {
+        let list = remaining
+            .iter()
+            .map(|r| format!("\n - {r}"))
+            .collect::<String>();
+        return Err(BuildError::new_error(format_args!(
+            "Build output refers to the build prefix {} and is not relocatable:{list}",
+            prefix.display()
+        )));
+    }
+    Ok(references)
+}
+
+/// Check and optionally rewrite the RPATH or RUNPATH of an elf file
+fn relocate_elf(
+    prefix: &Path,
+    path: &Path,
+    data: &[u8],
+    rewrite: bool,
+) -> Result<Vec<PrefixReference>> {
+    let Some((rpath, is_runpath)) = read_elf_rpath(data) else {
+        return Ok(Vec::new());
+    };
+    let dir = path.parent().unwrap_or(prefix);
+    let mut found = false;
+    let relocated = rpath
+        .split(':')
+        .map(|entry| match Path::new(entry).strip_prefix(prefix) {
+            Ok(rel) => {
+                found = true;
+                let target = prefix.join(rel);
+                format!("$ORIGIN/{}", relative_path(dir, &target).display())
+                    .trim_end_matches('/')
+                    .to_string()
+            }
+            Err(_) => entry.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(":");
+    if !found {
+        return Ok(Vec::new());
+    }
+
+    let mut reference = PrefixReference {
+        path: path.to_owned(),
+        kind: RelocateFileKind::Elf,
+        value: rpath,
+        rewritten: None,
+    };
+    if rewrite {
+        let Some(patchelf) = spfs::which("patchelf") else {
+            return Err(Error::String(
+                "patchelf is required to rewrite the rpath of elf files, but was not found"
+                    .to_string(),
+            ));
+        };
+        let mut cmd = Command::new(patchelf);
+        cmd.arg("--set-rpath").arg(&relocated);
+        if !is_runpath {
+            cmd.arg("--force-rpath");
+        }
+        cmd.arg(path);
+        let status = while_writable(path, || {
+            cmd.status().map_err(|err| {
+                Error::ProcessSpawnError(spfs::Error::process_spawn_error("patchelf", err, None))
+            })
+        })?;
+        if !status.success() {
+            return Err(BuildError::new_error(format_args!(
+                "Failed to rewrite rpath of {}: patchelf {status}",
+                path.display()
+            )));
+        }
+        reference.rewritten = Some(relocated);
+    }
+    Ok(vec![reference])
+}
+
+/// Check and optionally rewrite the interpreter of a script.
+///
+/// An interpreter within the build prefix is replaced by finding
+/// it with `/usr/bin/env` instead, which is only possible when the
+/// shebang line has no additional arguments.
+fn relocate_script(
+    prefix: &Path,
+    path: &Path,
+    data: &[u8],
+    rewrite: bool,
+) -> Result<Vec<PrefixReference>> {
+    let line_end = data.iter().position(|b| *b == b'\n').unwrap_or(data.len());
+    let Ok(shebang) = std::str::from_utf8(&data[2..line_end]) else {
+        return Ok(Vec::new());
+    };
+    let mut parts = shebang.split_whitespace();
+    let Some(interpreter) = parts.next() else {
+        return Ok(Vec::new());
+    };
+    if Path::new(interpreter).strip_prefix(prefix).is_err() {
+        return Ok(Vec::new());
+    }
+    let mut reference = PrefixReference {
+        path: path.to_owned(),
+        kind: RelocateFileKind::Script,
+        value: format!("#!{}", shebang.trim()),
+        rewritten: None,
+    };
+    let name = Path::new(interpreter).file_name();
+    if let (true, None, Some(name)) = (rewrite, parts.next(), name) {
+        let replacement = format!("#!/usr/bin/env {}", name.to_string_lossy());
+        let mut content = replacement.clone().into_bytes();
+        content.extend_from_slice(&data[line_end..]);
+        write_file(path, &content)?;
+        reference.rewritten = Some(replacement);
+    }
+    Ok(vec![reference])
+}
+
+/// Check and optionally rewrite the paths in a pkg-config file.
+///
+/// Paths in the build prefix are made relative to the
+/// location of the file itself using `${pcfiledir}`.
+fn relocate_pkg_config(
+    prefix: &Path,
+    path: &Path,
+    data: &[u8],
+    rewrite: bool,
+) -> Result<Vec<PrefixReference>> {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return Ok(Vec::new());
+    };
+    let Some(prefix_str) = prefix.to_str() else {
+        return Ok(Vec::new());
+    };
+    let dir = path.parent().unwrap_or(prefix);
+    let relative = relative_path(dir, prefix);
+    let replacement = match relative.as_os_str().is_empty() {
+        true => "${pcfiledir}".to_string(),
+        false => format!("${{pcfiledir}}/{}", relative.display()),
+    };
+    let Some(relocated) = replace_prefix(text, prefix_str, &replacement) else {
+        return Ok(Vec::new());
+    };
+
+    let mut references = Vec::new();
+    for (line, new_line) in text.lines().zip(relocated.lines()) {
+        if line == new_line {
+            continue;
+        }
+        references.push(PrefixReference {
+            path: path.to_owned(),
+            kind: RelocateFileKind::PkgConfig,
+            value: line.to_string(),
+            rewritten: rewrite.then(|| new_line.to_string()),
+        });
+    }
+    if rewrite {
+        write_file(path, relocated.as_bytes())?;
+    }
+    Ok(references)
+}
+
+/// Replace all occurrences of the prefix that are a whole
+/// path or the start of a path, returning None if there are none.
+fn replace_prefix(text: &str, prefix: &str, replacement: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut found = false;
+    while let Some(index) = rest.find(prefix) {
+        let after = &rest[index + prefix.len()..];
+        // the prefix may be preceded by a flag such as -I or -L,
+        // but not by another path that it would be a part of
+        let preceded_by_path = rest[..index]
+            .rsplit(|c: char| c.is_whitespace() || "=:\"'".contains(c))
+            .next()
+            .is_some_and(|token| token.contains('/') || token.ends_with('.'));
+        let followed_by_path = after
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || "_-.".contains(c));
+        result.push_str(&rest[..index]);
+        if preceded_by_path || followed_by_path {
+            result.push_str(prefix);
+        } else {
+            result.push_str(replacement);
+            found = true;
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+    found.then_some(result)
+}
+
+/// Compute the relative path from one absolute directory to another path
+fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from = from_dir.components().collect::<Vec<_>>();
+    let to = to.components().collect::<Vec<_>>();
+    let common = from
+        .iter()
+        .zip(to.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push(Component::ParentDir);
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Read the RPATH or RUNPATH of an elf file, along with
+/// true if the value came from a RUNPATH entry.
+///
+/// Returns None if the file is not a valid elf file
+/// or does not have either entry.
+fn read_elf_rpath(data: &[u8]) -> Option<(String, bool)> {
+    match FileKind::parse(data).ok()? {
+        FileKind::Elf32 => read_elf_file_rpath::<elf::FileHeader32<Endianness>>(data),
+        FileKind::Elf64 => read_elf_file_rpath::<elf::FileHeader64<Endianness>>(data),
+        _ => None,
+    }
+}
+
+fn read_elf_file_rpath<Elf>(data: &[u8]) -> Option<(String, bool)>
+where
+    Elf: FileHeader<Endian = Endianness>,
+{
+    let header = Elf::parse(data).ok()?;
+    let endian = header.endian().ok()?;
+    let segments = header.program_headers(endian, data).ok()?;
+    let entries = segments
+        .iter()
+        .find_map(|segment| segment.dynamic(endian, data).transpose())?
+        .ok()?;
+
+    let mut strtab: Option<u64> = None;
+    let mut strsz: Option<u64> = None;
+    let mut rpath = None;
+    for entry in entries
+        .iter()
+        .take_while(|entry| entry.tag32(endian) != Some(elf::DT_NULL))
+    {
+        match entry.tag32(endian) {
+            Some(elf::DT_STRTAB) => strtab = Some(entry.d_val(endian).into()),
+            Some(elf::DT_STRSZ) => strsz = Some(entry.d_val(endian).into()),
+            Some(elf::DT_RPATH) if rpath.is_none() => rpath = Some((entry, false)),
+            Some(elf::DT_RUNPATH) => rpath = Some((entry, true)),
+            _ => {}
+        }
+    }
+    let (entry, is_runpath) = rpath?;
+    // the string table is given as a virtual address, which
+    // must be mapped back to the file using the loaded segments
+    let (strtab, strsz) = (strtab?, strsz?);
+    let table = segments
+        .iter()
+        .filter(|segment| segment.p_type(endian) == elf::PT_LOAD)
+        .find_map(|segment| segment.data_range(endian, data, strtab, strsz).ok()?)?;
+    let strings = StringTable::new(table, 0, strsz);
+    let value = entry.string(endian, strings).ok()?;
+    let value = std::str::from_utf8(value).ok()?;
+    Some((value.to_string(), is_runpath))
+}
+
+/// Read up to the first `len` bytes of a file.
+fn read_header(path: &Path, len: usize) -> Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(len);
+    std::fs::File::open(path)
+        .and_then(|file| file.take(len as u64).read_to_end(&mut header))
+        .map_err(|err| Error::FileOpenError(path.to_owned(), err))?;
+    Ok(header)
+}
+
+/// Run a function that modifies the given file, allowing the owner
+/// to write to it for the duration if it is read-only.
+///
+/// The original permissions of the file are always restored.
+fn while_writable<T>(path: &Path, modify: impl FnOnce() -> Result<T>) -> Result<T> {
+    let permissions = std::fs::metadata(path)
+        .map_err(|err| Error::FileOpenError(path.to_owned(), err))?
+        .permissions();
+    if permissions.mode() & 0o200 != 0 {
+        return modify();
+    }
+    let writable = std::fs::Permissions::from_mode(permissions.mode() | 0o200);
+    std::fs::set_permissions(path, writable)
+        .map_err(|err| Error::FileWriteError(path.to_owned(), err))?;
+    let result = modify();
+    std::fs::set_permissions(path, permissions)
+        .map_err(|err| Error::FileWriteError(path.to_owned(), err))?;
+    result
+}
+
+/// Replace the contents of a file, keeping its permissions.
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    while_writable(path, || {
+        std::fs::write(path, content).map_err(|err| Error::FileWriteError(path.to_owned(), err))
+    })
+}
@@ -0,0 +1,162 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use rstest::rstest;
+use spk_schema::{RelocateFileKind, RelocateMode, RelocateSpec};
+
+use super::{read_elf_rpath, relative_path, relocate_build_output, replace_prefix};
+
+/// Create a minimal 64-bit elf file with the given RUNPATH
+fn elf_with_runpath(runpath: &str) -> Vec<u8> {
+    let strtab = 240u64;
+    let mut data = vec![0u8; strtab as usize];
+    data.extend_from_slice(b"\0");
+    data.extend_from_slice(runpath.as_bytes());
+    data.extend_from_slice(b"\0");
+    let len = data.len() as u64;
+    let strsz = len - strtab;
+
+    data[..4].copy_from_slice(b"\x7fELF");
+    data[4] = 2; // 64-bit
+    data[5] = 1; // little endian
+    data[6] = 1; // current version
+    data[0x20..0x28].copy_from_slice(&64u64.to_le_bytes());
+    data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+    data[0x38..0x3a].copy_from_slice(&2u16.to_le_bytes());
+    let mut phdr = |at: usize, kind: u32, offset: u64, filesz: u64| {
+        data[at..at + 4].copy_from_slice(&kind.to_le_bytes());
+        data[at + 8..at + 16].copy_from_slice(&offset.to_le_bytes());
+        data[at + 16..at + 24].copy_from_slice(&offset.to_le_bytes());
+        data[at + 32..at + 40].copy_from_slice(&filesz.to_le_bytes());
+    };
+    phdr(64, 1, 0, len);
+    phdr(120, 2, 176, 64);
+    let entries = [(5u64, strtab), (10, strsz), (29, 1), (0, 0)];
+    for (i, (tag, value)) in entries.into_iter().enumerate() {
+        let at = 176 + i * 16;
+        data[at..at + 8].copy_from_slice(&tag.to_le_bytes());
+        data[at + 8..at + 16].copy_from_slice(&value.to_le_bytes());
+    }
+    data
+}
+
+#[rstest]
+fn test_read_elf_rpath() {
+    let data = elf_with_runpath("/spfs/lib:$ORIGIN");
+    assert_eq!(
+        read_elf_rpath(&data),
+        Some(("/spfs/lib:$ORIGIN".to_string(), true))
+    );
+    assert_eq!(read_elf_rpath(&data[..100]), None, "truncated file");
+}
+
+#[rstest]
+#[case("/spfs/bin", "/spfs/lib", "../lib")]
+#[case("/spfs/lib/python3/site-packages", "/spfs/lib", "../..")]
+#[case("/spfs/lib", "/spfs/lib", "")]
+#[case("/spfs", "/spfs/lib64", "lib64")]
+fn test_relative_path(#[case] from: &str, #[case] to: &str, #[case] expected: &str) {
+    assert_eq!(
+        relative_path(Path::new(from), Path::new(to)),
+        PathBuf::from(expected)
+    );
+}
+
+#[rstest]
+#[case("prefix=/spfs", Some("prefix=${pcfiledir}/../.."))]
+#[case("libdir=/spfs/lib", Some("libdir=${pcfiledir}/../../lib"))]
+#[case(
+    "Cflags: -I/spfs/include",
+    Some("Cflags: -I${pcfiledir}/../../include")
+)]
+#[case("libdir=/spfsx/lib", None)]
+#[case("libdir=/mnt/spfs/lib", None)]
+fn test_replace_prefix(#[case] text: &str, #[case] expected: Option<&str>) {
+    assert_eq!(
+        replace_prefix(text, "/spfs", "${pcfiledir}/../..").as_deref(),
+        expected
+    );
+}
+
+#[rstest]
+fn test_relocate_rewrite_script_and_pkg_config() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let prefix = tmpdir.path();
+    std::fs::create_dir_all(prefix.join("bin")).unwrap();
+    std::fs::create_dir_all(prefix.join("lib/pkgconfig")).unwrap();
+    let script = prefix.join("bin/tool");
+    let with_args = prefix.join("bin/tool-unbuffered");
+    let pc = prefix.join("lib/pkgconfig/example.pc");
+    let interpreter = prefix.join("bin/python");
+    std::fs::write(&script, format!("#!{}\nprint()\n", interpreter.display())).unwrap();
+    std::fs::write(&with_args, format!("#!{} -u\n", interpreter.display())).unwrap();
+    std::fs::write(&pc, format!("prefix={}\nName: example\n", prefix.display())).unwrap();
+
+    let spec = RelocateSpec {
+        mode: RelocateMode::Rewrite,
+        files: RelocateFileKind::all(),
+    };
+    let references =
+        relocate_build_output(prefix, [script.clone(), with_args, pc.clone()], &spec).unwrap();
+    assert_eq!(references.len(), 3);
+    assert_eq!(
+        references.iter().filter(|r| r.rewritten.is_none()).count(),
+        1,
+        "a shebang with arguments cannot be rewritten"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&script).unwrap(),
+        "#!/usr/bin/env python\nprint()\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&pc).unwrap(),
+        "prefix=${pcfiledir}/../..\nName: example\n"
+    );
+}
+
+#[rstest]
+fn test_relocate_rewrite_read_only_file() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let prefix = tmpdir.path();
+    let script = prefix.join("tool");
+    std::fs::write(&script, format!("#!{}/bin/python\n", prefix.display())).unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+    let spec = RelocateSpec {
+        mode: RelocateMode::Rewrite,
+        files: RelocateFileKind::all(),
+    };
+    relocate_build_output(prefix, [script.clone()], &spec).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&script).unwrap(),
+        "#!/usr/bin/env python\n"
+    );
+    let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o555, "the file should still be read-only");
+}
+
+#[rstest]
+fn test_relocate_error_mode() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let prefix = tmpdir.path();
+    let lib = prefix.join("libexample.so");
+    std::fs::write(
+        &lib,
+        elf_with_runpath(&prefix.join("lib").to_string_lossy()),
+    )
+    .unwrap();
+
+    let mut spec = RelocateSpec {
+        mode: RelocateMode::Error,
+        files: vec![RelocateFileKind::Script],
+    };
+    relocate_build_output(prefix, [lib.clone()], &spec)
+        .expect("elf files should be skipped when not included");
+    spec.files = RelocateFileKind::all();
+    relocate_build_output(prefix, [lib], &spec)
+        .expect_err("should fail when the rpath refers to the build prefix");
+}
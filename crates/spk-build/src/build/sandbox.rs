@@ -0,0 +1,211 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Runs build scripts within the restrictions of a [`SandboxSpec`]
+
+use std::process::Command;
+
+use spk_schema::SandboxSpec;
+
+use crate::{Error, Result};
+
+#[cfg(all(test, target_os = "linux"))]
+#[path = "./sandbox_test.rs"]
+mod sandbox_test;
+
+/// Wrap the given command so that it runs within the given sandbox.
+///
+/// Resource limits are applied by running the command in a transient
+/// systemd scope, which leaves the creation and cleanup of the cgroup
+/// to systemd. Network and filesystem restrictions are applied by moving
+/// the command into new namespaces just before it is executed.
+#[cfg(target_os = "linux")]
+pub fn sandbox_command(cmd: Command, sandbox: &SandboxSpec) -> Result<Command> {
+    use std::os::unix::process::CommandExt;
+
+    let mut cmd = if sandbox.has_resource_limits() {
+        wrap_in_scope(cmd, sandbox)?
+    } else {
+        cmd
+    };
+    let namespaces = linux::Namespaces::new(sandbox)?;
+    // Safety: the closure only makes system calls with data that
+    // was prepared before forking, and does not allocate
+    unsafe {
+        cmd.pre_exec(move || namespaces.enter().map_err(std::io::Error::from));
+    }
+    Ok(cmd)
+}
+
+/// Wrap the given command so that it runs within the given sandbox.
+#[cfg(not(target_os = "linux"))]
+pub fn sandbox_command(_cmd: Command, _sandbox: &SandboxSpec) -> Result<Command> {
+    Err(Error::String(
+        "Sandboxed builds are only supported on linux".to_string(),
+    ))
+}
+
+/// Create a command that runs the given one in a new systemd scope
+/// with the resource limits of the given sandbox.
+#[cfg(target_os = "linux")]
+fn wrap_in_scope(cmd: Command, sandbox: &SandboxSpec) -> Result<Command> {
+    let Some(systemd_run) = spfs::which("systemd-run") else {
+        return Err(Error::String(
+            "systemd-run is required to apply the resource limits of the build sandbox, but was not found".to_string(),
+        ));
+    };
+    let mut scope = Command::new(systemd_run);
+    scope.args(["--user", "--scope", "--quiet", "--collect"]);
+    if let Some(cpus) = sandbox.cpus {
+        scope
+            .arg("--property")
+            .arg(format!("CPUQuota={}%", u64::from(cpus.get()) * 100));
+    }
+    if let Some(memory) = sandbox.memory {
+        scope
+            .arg("--property")
+            .arg(format!("MemoryMax={}", memory.bytes()))
+            .arg("--property")
+            .arg("MemorySwapMax=0");
+    }
+    scope.arg("--");
+    // systemd-run itself may need variables that are removed from the
+    // build environment, so they are only removed for the wrapped command
+    let removed: Vec<_> = cmd
+        .get_envs()
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| name)
+        .collect();
+    if !removed.is_empty() {
+        scope.arg("env");
+        for name in removed {
+            scope.arg("-u").arg(name);
+        }
+    }
+    scope.arg(cmd.get_program()).args(cmd.get_args());
+    for (name, value) in cmd.get_envs() {
+        if let Some(value) = value {
+            scope.env(name, value);
+        }
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        scope.current_dir(dir);
+    }
+    Ok(scope)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::path::PathBuf;
+
+    use nix::fcntl::OFlag;
+    use nix::mount::MsFlags;
+    use nix::sched::CloneFlags;
+    use nix::sys::stat::Mode;
+    use nix::sys::statvfs::FsFlags;
+    use spk_schema::SandboxSpec;
+
+    use crate::{Error, Result};
+
+    /// The namespaces that a sandboxed build is moved into
+    pub(super) struct Namespaces {
+        isolate_network: bool,
+        uid_map: String,
+        gid_map: String,
+        /// Each path to make read-only along with the flags of
+        /// its current mount, which must be preserved when remounting
+        read_only_paths: Vec<(PathBuf, MsFlags)>,
+    }
+
+    impl Namespaces {
+        pub(super) fn new(sandbox: &SandboxSpec) -> Result<Self> {
+            let uid = nix::unistd::getuid();
+            let gid = nix::unistd::getgid();
+            let mut read_only_paths = Vec::with_capacity(sandbox.read_only_paths.len());
+            for path in sandbox.read_only_paths.iter() {
+                let stat = nix::sys::statvfs::statvfs(path).map_err(|err| {
+                    Error::String(format!(
+                        "Invalid read-only path for build sandbox {}: {err}",
+                        path.display()
+                    ))
+                })?;
+                read_only_paths.push((path.clone(), locked_mount_flags(stat.flags())));
+            }
+            Ok(Self {
+                isolate_network: !sandbox.network,
+                // the current user is mapped to itself so that file
+                // ownership in /spfs is the same inside of the sandbox
+                uid_map: format!("{uid} {uid} 1\n"),
+                gid_map: format!("{gid} {gid} 1\n"),
+                read_only_paths,
+            })
+        }
+
+        /// Move the current process into the new namespaces.
+        ///
+        /// This is called in the forked child process, and so must
+        /// only make system calls without allocating.
+        pub(super) fn enter(&self) -> nix::Result<()> {
+            let mut flags = CloneFlags::empty();
+            if self.isolate_network {
+                flags |= CloneFlags::CLONE_NEWNET;
+            }
+            if !self.read_only_paths.is_empty() {
+                flags |= CloneFlags::CLONE_NEWNS;
+            }
+            if flags.is_empty() {
+                return Ok(());
+            }
+            // a new user namespace allows these to be created
+            // without needing any additional privileges
+            nix::sched::unshare(flags | CloneFlags::CLONE_NEWUSER)?;
+            write_file("/proc/self/setgroups", b"deny")?;
+            write_file("/proc/self/uid_map", self.uid_map.as_bytes())?;
+            write_file("/proc/self/gid_map", self.gid_map.as_bytes())?;
+
+            for (path, locked_flags) in self.read_only_paths.iter() {
+                nix::mount::mount(
+                    Some(path),
+                    path,
+                    None::<&str>,
+                    MsFlags::MS_BIND | MsFlags::MS_REC,
+                    None::<&str>,
+                )?;
+                nix::mount::mount(
+                    None::<&str>,
+                    path,
+                    None::<&str>,
+                    MsFlags::MS_BIND | MsFlags::MS_REMOUNT | MsFlags::MS_RDONLY | *locked_flags,
+                    None::<&str>,
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    /// The flags of an existing mount which cannot be cleared
+    /// when it is remounted from within a user namespace.
+    fn locked_mount_flags(flags: FsFlags) -> MsFlags {
+        let mut locked = MsFlags::empty();
+        for (fs_flag, ms_flag) in [
+            (FsFlags::ST_NOSUID, MsFlags::MS_NOSUID),
+            (FsFlags::ST_NODEV, MsFlags::MS_NODEV),
+            (FsFlags::ST_NOEXEC, MsFlags::MS_NOEXEC),
+            (FsFlags::ST_NOATIME, MsFlags::MS_NOATIME),
+            (FsFlags::ST_NODIRATIME, MsFlags::MS_NODIRATIME),
+        ] {
+            if flags.contains(fs_flag) {
+                locked |= ms_flag;
+            }
+        }
+        locked
+    }
+
+    fn write_file(path: &str, data: &[u8]) -> nix::Result<()> {
+        let fd = nix::fcntl::open(path, OFlag::O_WRONLY, Mode::empty())?;
+        let result = nix::unistd::write(fd, data);
+        nix::unistd::close(fd)?;
+        result.map(|_| ())
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::process::Command;
+
+use rstest::rstest;
+use spk_schema::foundation::fixtures::*;
+use spk_schema::SandboxSpec;
+
+use super::{sandbox_command, wrap_in_scope};
+
+#[rstest]
+fn test_wrap_in_scope_applies_limits() {
+    let sandbox: SandboxSpec = serde_yaml::from_str("{cpus: 2, memory: 1G}").unwrap();
+    let mut cmd = Command::new("bash");
+    cmd.args(["-ex", "build.sh"]).env("PREFIX", "/spfs");
+
+    let Ok(scope) = wrap_in_scope(cmd, &sandbox) else {
+        println!("systemd-run not available on this system");
+        return;
+    };
+    let args: Vec<_> = scope
+        .get_args()
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
+    assert!(args.contains(&"CPUQuota=200%".to_string()), "{args:?}");
+    assert!(
+        args.contains(&"MemoryMax=1073741824".to_string()),
+        "{args:?}"
+    );
+    assert!(
+        args.ends_with(&["--".into(), "bash".into(), "-ex".into(), "build.sh".into()]),
+        "the original command should be run in the scope: {args:?}"
+    );
+    assert!(
+        scope
+            .get_envs()
+            .any(|(name, value)| name == "PREFIX" && value == Some("/spfs".as_ref())),
+        "the environment of the original command should be preserved"
+    );
+}
+
+#[rstest]
+fn test_wrap_in_scope_removes_vars_from_command_only() {
+    let sandbox: SandboxSpec = serde_yaml::from_str("{cpus: 1}").unwrap();
+    let mut cmd = Command::new("bash");
+    cmd.arg("build.sh").env_remove("CFLAGS");
+
+    let Ok(scope) = wrap_in_scope(cmd, &sandbox) else {
+        println!("systemd-run not available on this system");
+        return;
+    };
+    let args: Vec<_> = scope
+        .get_args()
+        .map(|a| a.to_string_lossy().to_string())
+        .collect();
+    assert!(
+        args.ends_with(&[
+            "--".into(),
+            "env".into(),
+            "-u".into(),
+            "CFLAGS".into(),
+            "bash".into(),
+            "build.sh".into()
+        ]),
+        "removed variables should be unset for the original command: {args:?}"
+    );
+    assert!(scope.get_envs().all(|(name, _)| name != "CFLAGS"));
+}
+
+#[rstest]
+fn test_sandbox_read_only_paths(tmpdir: tempfile::TempDir) {
+    let sandbox = SandboxSpec {
+        network: true,
+        read_only_paths: vec![tmpdir.path().to_owned()],
+        ..Default::default()
+    };
+    let mut cmd = Command::new("touch");
+    cmd.arg(tmpdir.path().join("file"));
+    let mut cmd = sandbox_command(cmd, &sandbox).unwrap();
+    let status = match cmd.status() {
+        Ok(status) => status,
+        Err(err) => {
+            println!("user namespaces are not available on this system: {err}");
+            return;
+        }
+    };
+    assert!(!status.success(), "should not be able to write to the path");
+    assert!(!tmpdir.path().join("file").exists());
+}
@@ -9,7 +9,7 @@ use relative_path::{RelativePath, RelativePathBuf};
 use spfs::prelude::*;
 use spk_schema::foundation::env::data_path;
 use spk_schema::foundation::ident_component::Component;
-use spk_schema::{Package, PackageMut};
+use spk_schema::{Package, PackageMut, SourceSpec};
 use spk_storage as storage;
 
 use crate::{Error, Result};
@@ -21,6 +21,7 @@ mod sources_test;
 /// Denotes an error during the build process.
 #[derive(Debug, miette::Diagnostic, thiserror::Error)]
 #[error("Collection error: {message}")]
+#[diagnostic(code(spk::build::collection_failed))]
 pub struct CollectionError {
     pub message: String,
 }
@@ -114,7 +115,7 @@ where
         collect_sources(package, &source_dir)?;
 
         tracing::info!("Validating source package contents...");
-        let diffs = spfs::diff(None, None).await?;
+        let diffs = spfs::diff(None, None, repo.digest_algorithm()).await?;
         validate_source_changeset(
             diffs,
             RelativePathBuf::from(source_dir.to_string_lossy().to_string()),
@@ -137,6 +138,7 @@ where
         .map_err(|err| Error::DirectoryCreateError(source_dir.to_owned(), err))?;
 
     let env = spec.get_build_env();
+    let mut git_sources = Vec::new();
     for source in spec.sources().iter() {
         let target_dir = match source.subdir() {
             Some(subdir) => subdir.to_path(source_dir),
@@ -147,10 +149,48 @@ where
         source.collect(&target_dir, &env).map_err(|err| {
             CollectionError::new_error(format_args!("Failed to collect source: {err}\n{source:?}"))
         })?;
+        if let SourceSpec::Git(git) = source {
+            let commit = git.collected_commit(&target_dir)?;
+            tracing::info!("Collected {} at commit {commit}", git.git);
+            git_sources.push(CollectedGitSource {
+                git: git.git.clone(),
+                reference: git.reference.clone(),
+                commit,
+                subdir: git.subdir.clone(),
+            });
+        }
+    }
+
+    if !git_sources.is_empty() {
+        // the exact commits are recorded so that these
+        // sources can be reproduced even if a branch moves
+        let path = source_dir.join(GIT_SOURCES_FILE);
+        let data = serde_json::to_vec_pretty(&git_sources)
+            .map_err(|err| Error::String(format!("Failed to serialize git sources: {err}")))?;
+        std::fs::write(&path, data).map_err(|err| Error::FileWriteError(path, err))?;
     }
     Ok(())
 }
 
+/// The file in a source package that records the
+/// commit that was collected for each git source.
+pub const GIT_SOURCES_FILE: &str = "git_sources.json";
+
+/// The commit that was collected for one git source.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CollectedGitSource {
+    /// The url or path of the repository
+    pub git: String,
+    /// The reference that was requested, if any
+    #[serde(rename = "ref", default, skip_serializing_if = "String::is_empty")]
+    pub reference: String,
+    /// The full hash of the commit that was collected
+    pub commit: String,
+    /// The subdirectory that the sources were collected into, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+}
+
 /// Validate the set of diffs for a source package build.
 ///
 /// # Errors:
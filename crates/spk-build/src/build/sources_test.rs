@@ -8,7 +8,7 @@ use spk_schema::ident::build_ident;
 use spk_schema::{v0, GitSource, LocalSource, ScriptSource, SourceSpec, Spec, TarSource};
 use spk_storage::fixtures::*;
 
-use super::{collect_sources, validate_source_changeset};
+use super::{collect_sources, validate_source_changeset, CollectedGitSource, GIT_SOURCES_FILE};
 
 #[rstest]
 fn test_validate_sources_changeset_nothing() {
@@ -80,6 +80,8 @@ async fn test_sources_subdir(_tmpdir: tempfile::TempDir) {
         subdir: Some("git_repo".to_string()),
         depth: 1,
         reference: String::new(),
+        submodules: Default::default(),
+        lfs: true,
     };
     let source_dir = rt.tmpdir.path().join("source");
     source_dir.join("file.txt").ensure();
@@ -103,6 +105,15 @@ async fn test_sources_subdir(_tmpdir: tempfile::TempDir) {
     assert!(dest_dir.join("archive/src").is_dir());
     assert!(dest_dir.join("archive/src/src/lib.rs").is_file());
     assert!(dest_dir.join("git_repo/crates/spk/src/cli.rs").is_file());
+    let git_sources: Vec<CollectedGitSource> =
+        serde_json::from_slice(&std::fs::read(dest_dir.join(GIT_SOURCES_FILE)).unwrap()).unwrap();
+    assert_eq!(
+        git_sources.len(),
+        1,
+        "should record the collected git source"
+    );
+    assert_eq!(git_sources[0].subdir.as_deref(), Some("git_repo"));
+    assert_eq!(git_sources[0].commit.len(), 40);
     assert!(
         !dest_dir.join("local/.git").exists(),
         "should exclude git repo"
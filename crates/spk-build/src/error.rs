@@ -30,7 +30,18 @@ pub enum Error {
     #[error(transparent)]
     #[diagnostic(forward(0))]
     ProcessSpawnError(spfs::Error),
+    #[error("Source package {ident} has digest {actual}, expected {expected}")]
+    #[diagnostic(
+        code(spk::build::source_digest_mismatch),
+        help = "The source package was changed or rebuilt since the expected digest was recorded"
+    )]
+    SourceDigestMismatch {
+        ident: String,
+        expected: spfs::encoding::Digest,
+        actual: spfs::encoding::Digest,
+    },
     #[error("Package validation failed")]
+    #[diagnostic(code(spk::build::validation_failed))]
     ValidationFailed {
         #[related]
         errors: Vec<crate::validation::Error>,
@@ -61,6 +72,7 @@ pub enum Error {
 
     #[error("Use of obsolete validators via 'build.validation.disabled'")]
     #[diagnostic(
+        code(spk::build::obsolete_validators),
         help = "Replace them with the new 'build.validation.rules', as appropriate. https://spkenv.dev/ref/spec/#validationspec"
     )]
     UseOfObsoleteValidators,
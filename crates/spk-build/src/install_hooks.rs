@@ -0,0 +1,389 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Runs the install hooks of the packages in an environment
+
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use spfs::graph::object::EncodingFormat;
+use spfs::prelude::*;
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::{BuildEnvironmentSpec, InstallHook, Package};
+use spk_solve::solution::{Solution, SolvedRequest};
+use tokio::io::AsyncWriteExt;
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./install_hooks_test.rs"]
+mod install_hooks_test;
+
+/// The runtime annotation that records the install hooks that were run
+pub const SPK_INSTALL_HOOKS_KEY: &str = "spk_install_hooks";
+
+/// How an install hook finished
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstallHookStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+/// A record of an install hook that was run, for auditing.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InstallHookRecord {
+    /// When the hook was started, in seconds since the unix epoch
+    pub time: u64,
+    pub user: String,
+    pub host: String,
+    /// The name of the runtime that the hook was run in, or
+    /// the runtime directory that the environment was rendered into
+    pub runtime: String,
+    /// The package build that the hook came from
+    pub package: String,
+    pub hook: String,
+    pub status: InstallHookStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+/// Where the install hooks of an environment are run
+#[derive(Clone, Copy)]
+enum HookTarget<'a> {
+    /// Within a mounted spfs runtime
+    Runtime(&'a spfs::runtime::Runtime),
+    /// Within a directory that the environment was rendered into
+    Dir(&'a Path),
+}
+
+impl HookTarget<'_> {
+    fn name(&self) -> String {
+        match self {
+            Self::Runtime(rt) => rt.name().to_string(),
+            Self::Dir(root) => root.display().to_string(),
+        }
+    }
+}
+
+/// Decide which packages are allowed to run their install hooks,
+/// according to the site policy.
+///
+/// Returns None if no hooks are allowed to run at all.
+pub fn allowed_hook_packages<'a>(
+    policy: &spk_config::InstallHooks,
+    solution: &'a Solution,
+) -> Option<Vec<&'a SolvedRequest>> {
+    if policy.disabled {
+        return None;
+    }
+    let allowed = policy.allowed_packages();
+    Some(
+        solution
+            .items()
+            .filter(|item| !item.spec.install_hooks().is_empty())
+            .filter(|item| {
+                let name = item.request.pkg.name.as_str();
+                let is_allowed = allowed.is_empty() || allowed.contains(&name);
+                if !is_allowed {
+                    tracing::warn!(
+                        "Skipping the install hooks of {name}, it is not allowed by the site policy"
+                    );
+                }
+                is_allowed
+            })
+            .collect(),
+    )
+}
+
+/// Load the site policy, and find the packages whose hooks should be run.
+///
+/// Returns None if there are no hooks to run.
+fn load_hook_packages(
+    solution: &Solution,
+) -> Result<Option<(spk_config::InstallHooks, Vec<&SolvedRequest>)>> {
+    if solution
+        .items()
+        .all(|item| item.spec.install_hooks().is_empty())
+    {
+        return Ok(None);
+    }
+    let config = spk_config::get_config().map_err(|err| Error::String(err.to_string()))?;
+    let policy = config.install_hooks.clone();
+    let Some(packages) = allowed_hook_packages(&policy, solution) else {
+        tracing::info!("Install hooks are disabled by the site policy");
+        return Ok(None);
+    };
+    if packages.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((policy, packages)))
+}
+
+/// Run the install hooks of the packages in the given solution.
+///
+/// The runtime must already be set up with the packages in the
+/// solution. Hooks run one at a time in the order of the solution,
+/// each in its own sandbox, and a failed hook is reported but does
+/// not stop the others. Each hook that is run is recorded in the
+/// runtime, and in the audit log of the site policy, if any.
+///
+/// The runtime is made editable while the hooks run. When it had no
+/// working changes beforehand, the files written by the hooks are
+/// committed to a new layer at the top of the runtime's stack, and
+/// the runtime is returned to its original editable state.
+pub async fn run_install_hooks(rt: &mut spfs::runtime::Runtime, solution: &Solution) -> Result<()> {
+    let Some((policy, packages)) = load_hook_packages(solution)? else {
+        return Ok(());
+    };
+    let backend = &rt.config.mount_backend;
+    if backend.is_fuse_only() || backend.is_winfsp() {
+        tracing::warn!(
+            "Skipping install hooks, the {backend} mount backend does not support editable runtimes"
+        );
+        return Ok(());
+    }
+
+    let was_editable = rt.status.editable;
+    let had_changes = std::fs::read_dir(&rt.config.upper_dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if !was_editable {
+        rt.status.editable = true;
+        rt.save_state_to_storage().await?;
+        spfs::remount_runtime(rt).await?;
+    }
+
+    let records = run_hooks(HookTarget::Runtime(rt), &policy, solution, packages).await;
+    let committed = match had_changes {
+        // working changes that were already in the runtime are
+        // left alone, along with anything that the hooks added
+        true => Ok(()),
+        false => commit_hook_changes(rt).await,
+    };
+    rt.status.editable = was_editable;
+    rt.save_state_to_storage().await?;
+    spfs::remount_runtime(rt).await?;
+    committed?;
+    let records = records?;
+
+    let spfs_config = spfs::Config::current()?;
+    // Annotations are only supported with FlatFileBuffers
+    if spfs_config.storage.encoding_format == EncodingFormat::FlatBuffers && !records.is_empty() {
+        let data = serde_json::to_string(&records).map_err(|err| Error::String(err.to_string()))?;
+        rt.add_annotation(
+            SPK_INSTALL_HOOKS_KEY,
+            &data,
+            spfs_config.filesystem.annotation_size_limit,
+        )
+        .await?;
+        rt.save_state_to_storage().await?;
+    }
+    Ok(())
+}
+
+/// Run the install hooks of the packages in the given solution
+/// within the active runtime.
+///
+/// See [`run_install_hooks`].
+pub async fn run_current_install_hooks(solution: &Solution) -> Result<()> {
+    let mut rt = spfs::active_runtime().await?;
+    run_install_hooks(&mut rt, solution).await
+}
+
+/// Run the install hooks of the packages in the given solution
+/// within a directory that the solution was rendered into.
+///
+/// Each hook is run with the environment of the directory's
+/// activation script, and is recorded only in the audit log of
+/// the site policy, if any. See [`run_install_hooks`].
+pub async fn run_install_hooks_in_dir(root: &Path, solution: &Solution) -> Result<()> {
+    let Some((policy, packages)) = load_hook_packages(solution)? else {
+        return Ok(());
+    };
+    run_hooks(HookTarget::Dir(root), &policy, solution, packages).await?;
+    Ok(())
+}
+
+/// Commit the working changes of a runtime to a new layer
+/// at the top of its stack, leaving the upper dir empty.
+async fn commit_hook_changes(rt: &mut spfs::runtime::Runtime) -> Result<()> {
+    let repo = spfs::get_config()?.get_local_repository_handle().await?;
+    let manifest = spfs::Committer::new(&repo)
+        .commit_dir(&rt.config.upper_dir)
+        .await?;
+    if manifest.is_empty() {
+        return Ok(());
+    }
+    let layer = repo.create_layer(&manifest.to_graph_manifest()).await?;
+    rt.push_digest(layer.digest()?);
+    rt.reset_all()?;
+    Ok(())
+}
+
+/// Run the hooks of the given packages, one at a time, returning
+/// a record of each one.
+async fn run_hooks(
+    target: HookTarget<'_>,
+    policy: &spk_config::InstallHooks,
+    solution: &Solution,
+    packages: Vec<&SolvedRequest>,
+) -> Result<Vec<InstallHookRecord>> {
+    let mut audit_log = None;
+    if !policy.audit_log.is_empty() {
+        // hooks are only run when they can be audited, so
+        // the log is opened before any of them are started
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&policy.audit_log)
+            .map_err(|err| Error::FileWriteError(policy.audit_log.clone().into(), err))?;
+        audit_log = Some(file);
+    }
+    let timeout = (policy.timeout_seconds > 0).then(|| Duration::from_secs(policy.timeout_seconds));
+
+    let mut records = Vec::new();
+    for item in packages {
+        for hook in item.spec.install_hooks() {
+            let package = item.spec.ident().format_ident();
+            tracing::info!("Running install hook: {package} {}", hook.name);
+            let time = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let start = Instant::now();
+            let (status, exit_code) = match run_hook(target, solution, item, hook, timeout).await {
+                Ok(Some(0)) => (InstallHookStatus::Succeeded, Some(0)),
+                Ok(Some(code)) => {
+                    tracing::warn!(
+                        "Install hook {package} {} failed with exit code {code}",
+                        hook.name
+                    );
+                    (InstallHookStatus::Failed, Some(code))
+                }
+                Ok(None) => {
+                    tracing::warn!(
+                        "Install hook {package} {} was stopped after {}s",
+                        hook.name,
+                        start.elapsed().as_secs()
+                    );
+                    (InstallHookStatus::TimedOut, None)
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Install hook {package} {} could not be run: {err}",
+                        hook.name
+                    );
+                    (InstallHookStatus::Failed, None)
+                }
+            };
+            let record = InstallHookRecord {
+                time,
+                user: whoami::username(),
+                host: whoami::hostname(),
+                runtime: target.name(),
+                package: item.spec.ident().to_string(),
+                hook: hook.name.clone(),
+                status,
+                exit_code,
+            };
+            if let Some(file) = audit_log.as_mut() {
+                let line =
+                    serde_json::to_string(&record).map_err(|err| Error::String(err.to_string()))?;
+                writeln!(file, "{line}")
+                    .map_err(|err| Error::FileWriteError(policy.audit_log.clone().into(), err))?;
+            }
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Run a single install hook in its sandbox, returning its exit
+/// code, or None if it was stopped for taking too long.
+async fn run_hook(
+    target: HookTarget<'_>,
+    solution: &Solution,
+    item: &SolvedRequest,
+    hook: &InstallHook,
+    timeout: Option<Duration>,
+) -> Result<Option<i32>> {
+    let interpreter = hook.script.interpreter();
+    // the script is given to the interpreter on stdin, so
+    // that nothing needs to be written into the environment
+    let args = interpreter
+        .args()
+        .iter()
+        .map(OsString::from)
+        .chain(std::iter::once(OsString::from("-")));
+    let mut cmd = match target {
+        HookTarget::Runtime(rt) => spfs::build_shell_initialized_command(
+            rt,
+            Some("bash"),
+            OsString::from(interpreter.program()),
+            args,
+        )?
+        .into_std(),
+        HookTarget::Dir(root) => {
+            let activate = spk_exec::activation_script_path("sh").to_path(root);
+            let mut cmd = std::process::Command::new("bash");
+            cmd.arg("-c")
+                .arg(r#"source "$0" && exec "$@""#)
+                .arg(activate)
+                .arg(interpreter.program())
+                .args(args);
+            cmd
+        }
+    };
+
+    // like a build script, a hook only sees the default
+    // host variables along with the environment itself
+    let host_vars = BuildEnvironmentSpec::default();
+    for (name, _) in std::env::vars_os() {
+        if !name.to_str().is_some_and(|name| host_vars.is_allowed(name)) {
+            cmd.env_remove(name);
+        }
+    }
+    cmd.envs(solution.to_environment(Some(host_vars.host_environment(std::env::vars()))));
+    cmd.env("SPK_HOOK_PACKAGE", item.spec.ident().to_string());
+    cmd.env("SPK_HOOK_NAME", &hook.name);
+    cmd.env("SHELL", "bash");
+    cmd.current_dir(std::env::temp_dir());
+    cmd.stdin(Stdio::piped());
+    let cmd = crate::sandbox_command(cmd, &hook.sandbox)?;
+
+    let mut child = tokio::process::Command::from(cmd)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| {
+            Error::ProcessSpawnError(spfs::Error::process_spawn_error("install hook", err, None))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let source = hook.script.to_source();
+        stdin
+            .write_all(source.as_bytes())
+            .await
+            .map_err(|err| Error::String(format!("Failed to send install hook script: {err}")))?;
+    }
+    let wait = async {
+        child
+            .wait()
+            .await
+            .map_err(|err| Error::String(format!("Failed to wait for install hook: {err}")))
+    };
+    let status = match timeout {
+        None => wait.await?,
+        Some(timeout) => match tokio::time::timeout(timeout, wait).await {
+            Ok(status) => status?,
+            // the child is killed when it is dropped on return
+            Err(_) => return Ok(None),
+        },
+    };
+    Ok(Some(status.code().unwrap_or(-1)))
+}
@@ -0,0 +1,77 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::FromYaml;
+use spk_schema::Spec;
+use spk_solve::solution::fixtures::solution_from_specs;
+use spk_solve::solution::Solution;
+
+use super::{allowed_hook_packages, run_install_hooks_in_dir};
+
+fn solution_from_yaml(specs: &[&str]) -> Solution {
+    solution_from_specs(specs.iter().map(|yaml| Spec::from_yaml(yaml).unwrap()))
+}
+
+fn solution_with_hooks() -> Solution {
+    solution_from_yaml(&[
+        "{pkg: fonts/1.0.0/3I42H3S6, install: {hooks: [{name: cache, script: fc-cache}]}}",
+        "{pkg: plugins/1.0.0/3I42H3S6, install: {hooks: [{name: scan, script: scan}]}}",
+        "{pkg: plain/1.0.0/3I42H3S6}",
+    ])
+}
+
+#[rstest]
+#[case("", &["fonts", "plugins"])]
+#[case("fonts", &["fonts"])]
+#[case("plain, other", &[])]
+fn test_install_hooks_allowed_packages(#[case] allowed: &str, #[case] expected: &[&str]) {
+    let solution = solution_with_hooks();
+    let policy = spk_config::InstallHooks {
+        allowed_packages: allowed.to_string(),
+        ..Default::default()
+    };
+    let allowed = allowed_hook_packages(&policy, &solution).expect("hooks should be enabled");
+    let names: Vec<_> = allowed
+        .iter()
+        .map(|item| item.request.pkg.name.as_str())
+        .collect();
+    assert_eq!(names, expected);
+}
+
+#[rstest]
+fn test_install_hooks_disabled() {
+    let solution = solution_with_hooks();
+    let policy = spk_config::InstallHooks {
+        disabled: true,
+        ..Default::default()
+    };
+    assert!(allowed_hook_packages(&policy, &solution).is_none());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_install_hooks_run_in_runtime_dir() {
+    let root = tempfile::tempdir().unwrap();
+    let activate = spk_exec::activation_script_path("sh").to_path(root.path());
+    std::fs::create_dir_all(activate.parent().unwrap()).unwrap();
+    std::fs::write(
+        &activate,
+        format!("export SPK_RUNTIME_DIR={}\n", root.path().display()),
+    )
+    .unwrap();
+    let solution = solution_from_yaml(&[r#"{pkg: fonts/1.0.0/3I42H3S6, install: {hooks: [{
+            name: cache,
+            script: 'echo "$SPK_HOOK_PACKAGE $SPK_HOOK_NAME" > "$SPK_RUNTIME_DIR/marker"',
+            sandbox: {network: true},
+        }]}}"#]);
+
+    run_install_hooks_in_dir(root.path(), &solution)
+        .await
+        .expect("install hooks should run");
+
+    let marker = std::fs::read_to_string(root.path().join("marker"))
+        .expect("the hook should have written into the runtime dir");
+    assert_eq!(marker, "fonts/1.0.0/3I42H3S6 cache\n");
+}
@@ -4,6 +4,7 @@
 
 mod build;
 mod error;
+pub mod install_hooks;
 pub mod report;
 pub mod validation;
 
@@ -12,15 +13,30 @@ pub mod validation;
 mod archive_test;
 
 pub use build::{
+    build_abi_path,
+    build_log_path,
     build_options_path,
+    build_provenance_path,
     build_script_path,
+    build_script_path_for,
     build_spec_path,
     commit_component_layers,
     component_marker_path,
+    read_package_abi,
+    sandbox_command,
     source_package_path,
     validate_source_changeset,
     BinaryPackageBuilder,
+    BuildProvenance,
     BuildSource,
+    CollectedGitSource,
+    LibraryAbi,
+    PackageAbi,
+    ProvenancePackage,
+    ProvenanceSource,
     SourcePackageBuilder,
+    COMPONENT_LAYER_LABEL,
+    GIT_SOURCES_FILE,
+    PACKAGE_LAYER_LABEL,
 };
 pub use error::{Error, Result};
@@ -2,12 +2,16 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
+use relative_path::RelativePathBuf;
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::{BuildIdent, Package, Variant};
 use spk_solve::Solution;
 
+use crate::PackageAbi;
+
 /// The build report is constructed by the [`crate::BinaryPackageBuilder`]
 /// during its execution and contains detailed information about
 /// the build setup, execution, and output.
@@ -53,6 +57,37 @@ pub struct BuildOutputReport {
     pub collected_changes: Vec<spfs::tracking::Diff<BuildIdent, BuildIdent>>,
     /// A report for each component generated by this build
     pub components: HashMap<Component, BuiltComponentReport>,
+    /// The directory that the package was built into, where
+    /// the collected files can still be read
+    pub prefix: PathBuf,
+    /// The exported symbols of the built libraries, if the
+    /// package validates them
+    pub abi: Option<AbiReport>,
+}
+
+impl BuildOutputReport {
+    /// Every entry that was included in any of the built components.
+    ///
+    /// Entries that appear in more than one component are only
+    /// returned once, and all entries are sorted by path.
+    pub fn component_entries(&self) -> BTreeMap<RelativePathBuf, &spfs::tracking::Entry> {
+        self.components
+            .values()
+            .flat_map(|c| c.manifest.walk_abs("/"))
+            .map(|node| (node.path, node.entry))
+            .collect()
+    }
+}
+
+/// The analyzed binary interface of a build, see [`crate::PackageAbi`]
+#[derive(Debug)]
+pub struct AbiReport {
+    /// The exported symbols of the libraries created by this build
+    pub current: PackageAbi,
+    /// The same build of the previous version of this package and
+    /// its exported symbols, if this version claims to be binary
+    /// compatible with it and its symbols were saved
+    pub previous: Option<(BuildIdent, PackageAbi)>,
 }
 
 /// Details for one component generated by a binary build
@@ -0,0 +1,71 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{
+    ValidationMatcherDiscriminants,
+    ValidationRuleDiscriminants as RuleKind,
+};
+use spk_schema::{Package, Variant};
+
+use super::package_files::{package_files, report_matched_files};
+use super::{Error, Report};
+use crate::report::{BuildReport, BuildSetupReport};
+
+#[cfg(test)]
+#[path = "./broken_symlinks_test.rs"]
+mod broken_symlinks_test;
+
+pub struct BrokenSymlinksValidator<'a> {
+    pub kind: RuleKind,
+    pub paths: Option<&'a FileMatcher>,
+}
+
+impl<'a> super::validator::sealed::Sealed for BrokenSymlinksValidator<'a> {}
+
+#[async_trait::async_trait]
+impl<'a> super::Validator for BrokenSymlinksValidator<'a> {
+    async fn validate_setup<P, V>(&self, _setup: &BuildSetupReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        Report::entire_build_not_matched(ValidationMatcherDiscriminants::BrokenSymlinks)
+    }
+
+    async fn validate_build<P, V>(&self, report: &BuildReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        let matched = package_files(report, self.paths)
+            .into_iter()
+            .filter(|(_, entry)| entry.is_symlink())
+            .filter_map(|(path, _)| {
+                // symlinks are resolved from where they were built so
+                // that relative targets are found next to the link
+                let on_disk = path.to_path(&report.output.prefix);
+                match std::fs::metadata(&on_disk) {
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    _ => return None,
+                }
+                let target = std::fs::read_link(&on_disk)
+                    .map(|t| t.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let error = Error::BrokenSymlinksDenied {
+                    path: path.clone(),
+                    target,
+                };
+                Some((path, error))
+            });
+        report_matched_files(
+            self.kind,
+            ValidationMatcherDiscriminants::BrokenSymlinks,
+            self.paths,
+            report,
+            matched,
+            Error::BrokenSymlinksRequired,
+        )
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spk_schema::validation::ValidationMatcher;
+use spk_schema::ValidationRule;
+
+use crate::validation::fixtures::report_for;
+use crate::validation::Validator;
+
+#[tokio::test]
+async fn test_validate_broken_symlinks() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmpdir.path().join("lib")).unwrap();
+    std::fs::write(tmpdir.path().join("lib/libfoo.so.1"), "lib").unwrap();
+    std::os::unix::fs::symlink("libfoo.so.1", tmpdir.path().join("lib/libfoo.so")).unwrap();
+    let report = report_for(tmpdir.path()).await;
+
+    let deny = ValidationRule::Deny {
+        condition: ValidationMatcher::BrokenSymlinks { paths: None },
+    };
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect("relative symlinks to existing files are not broken");
+
+    std::os::unix::fs::symlink("missing.so.2", tmpdir.path().join("lib/missing.so")).unwrap();
+    let report = report_for(tmpdir.path()).await;
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect_err("should get error when a symlink target does not exist");
+}
@@ -0,0 +1,108 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::io::Read;
+use std::path::Path;
+
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{
+    ValidationMatcherDiscriminants,
+    ValidationRuleDiscriminants as RuleKind,
+};
+use spk_schema::{Package, Variant};
+
+use super::package_files::{package_files, report_matched_files};
+use super::{Error, Report};
+use crate::report::{BuildReport, BuildSetupReport};
+
+#[cfg(test)]
+#[path = "./embedded_prefix_test.rs"]
+mod embedded_prefix_test;
+
+/// The number of bytes read at once when searching file contents
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub struct EmbeddedPrefixValidator<'a> {
+    pub kind: RuleKind,
+    pub paths: Option<&'a FileMatcher>,
+}
+
+impl<'a> super::validator::sealed::Sealed for EmbeddedPrefixValidator<'a> {}
+
+#[async_trait::async_trait]
+impl<'a> super::Validator for EmbeddedPrefixValidator<'a> {
+    async fn validate_setup<P, V>(&self, _setup: &BuildSetupReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        Report::entire_build_not_matched(ValidationMatcherDiscriminants::EmbeddedPrefix)
+    }
+
+    async fn validate_build<P, V>(&self, report: &BuildReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        let prefix = report.output.prefix.to_string_lossy().to_string();
+        let matched = package_files(report, self.paths)
+            .into_iter()
+            .filter(|(_, entry)| entry.is_regular_file())
+            .filter(|_| !prefix.is_empty())
+            .filter(|(path, _)| {
+                let on_disk = path.to_path(&report.output.prefix);
+                match file_contains(&on_disk, prefix.as_bytes()) {
+                    Ok(found) => found,
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to check {} for the build prefix: {err}",
+                            on_disk.display()
+                        );
+                        false
+                    }
+                }
+            })
+            .map(|(path, _)| {
+                let error = Error::EmbeddedPrefixDenied {
+                    path: path.clone(),
+                    prefix: prefix.clone(),
+                };
+                (path, error)
+            });
+        report_matched_files(
+            self.kind,
+            ValidationMatcherDiscriminants::EmbeddedPrefix,
+            self.paths,
+            report,
+            matched,
+            Error::EmbeddedPrefixRequired,
+        )
+    }
+}
+
+/// Search the contents of a file for the given bytes without
+/// reading the whole file into memory at once
+fn file_contains(path: &Path, needle: &[u8]) -> std::io::Result<bool> {
+    if needle.is_empty() {
+        return Ok(true);
+    }
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0; CHUNK_SIZE + needle.len()];
+    let mut filled = 0;
+    loop {
+        let read = file.read(&mut buffer[filled..])?;
+        if read == 0 {
+            return Ok(false);
+        }
+        filled += read;
+        if buffer[..filled].windows(needle.len()).any(|w| w == needle) {
+            return Ok(true);
+        }
+        // keep the end of this chunk in case the
+        // needle spans across two reads
+        let keep = (needle.len() - 1).min(filled);
+        buffer.copy_within(filled - keep..filled, 0);
+        filled = keep;
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::validation::ValidationMatcher;
+use spk_schema::ValidationRule;
+
+use super::{file_contains, CHUNK_SIZE};
+use crate::validation::fixtures::report_for;
+use crate::validation::Validator;
+
+#[tokio::test]
+async fn test_validate_embedded_prefix() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let prefix = tmpdir.path().to_string_lossy().to_string();
+    std::fs::write(tmpdir.path().join("relative.cfg"), "root=../lib").unwrap();
+    let report = report_for(tmpdir.path()).await;
+
+    let require = ValidationRule::Require {
+        condition: ValidationMatcher::EmbeddedPrefix { paths: None },
+    };
+    let deny = ValidationRule::Deny {
+        condition: ValidationMatcher::EmbeddedPrefix { paths: None },
+    };
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect("should not match files without the prefix");
+    require
+        .validate_build(&report)
+        .await
+        .into_result()
+        .expect_err("should get error when no file contains the prefix");
+
+    std::fs::write(
+        tmpdir.path().join("absolute.cfg"),
+        format!("root={prefix}/lib"),
+    )
+    .unwrap();
+    let report = report_for(tmpdir.path()).await;
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect_err("should get error when a file contains the prefix");
+}
+
+#[rstest]
+#[case::start(0)]
+#[case::across_chunks(CHUNK_SIZE - 3)]
+#[case::end(CHUNK_SIZE * 2)]
+fn test_file_contains(#[case] offset: usize) {
+    let tmpfile = tempfile::NamedTempFile::new().unwrap();
+    let mut data = vec![b'x'; offset];
+    data.extend_from_slice(b"/spfs");
+    std::fs::write(tmpfile.path(), data).unwrap();
+
+    assert!(file_contains(tmpfile.path(), b"/spfs").unwrap());
+    assert!(!file_contains(tmpfile.path(), b"/other").unwrap());
+}
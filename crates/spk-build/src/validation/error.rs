@@ -5,6 +5,7 @@
 use miette::Diagnostic;
 use relative_path::RelativePathBuf;
 use spfs::env::SPFS_DIR;
+use spk_schema::validation::FileSize;
 use spk_schema::{BuildIdent, Request};
 use thiserror::Error;
 
@@ -173,4 +174,110 @@ pub enum Error {
     #[error("Package should not have a license specified")]
     #[diagnostic(severity(warning), code(spk::build::validation::spdx_license))]
     SpdxLicenseDenied,
+
+    #[error(
+        r#"Package files must not be larger than {limit}
+
+    {SPFS_DIR}{path} is {size} bytes
+"#
+    )]
+    #[diagnostic(severity(warning), code(spk::build::validation::large_files))]
+    LargeFilesDenied {
+        path: RelativePathBuf,
+        size: u64,
+        limit: FileSize,
+    },
+    #[error("Build was expected to include a file larger than {limit}, but didn't")]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::large_files),
+        help("This would need to be explicitly enabled in the package spec, which might have additional details")
+    )]
+    LargeFilesRequired { limit: FileSize },
+
+    #[error(
+        r#"Package files must not contain the path that they were built into
+
+    {SPFS_DIR}{path} contains {prefix}
+"#
+    )]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::embedded_prefix),
+        help("Files that refer to the build prefix cannot be relocated, consider using relative paths instead")
+    )]
+    EmbeddedPrefixDenied {
+        path: RelativePathBuf,
+        prefix: String,
+    },
+    #[error("Build was expected to include a file that contains its prefix, but didn't")]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::embedded_prefix),
+        help("This would need to be explicitly enabled in the package spec, which might have additional details")
+    )]
+    EmbeddedPrefixRequired,
+
+    #[error(
+        r#"Package files must not have the setuid or setgid permission bits
+
+    {SPFS_DIR}{path} has mode {mode:06o}
+"#
+    )]
+    #[diagnostic(severity(warning), code(spk::build::validation::setuid_files))]
+    SetuidFilesDenied { path: RelativePathBuf, mode: u32 },
+    #[error("Build was expected to include a setuid or setgid file, but didn't")]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::setuid_files),
+        help("This would need to be explicitly enabled in the package spec, which might have additional details")
+    )]
+    SetuidFilesRequired,
+
+    #[error(
+        r#"Package symlinks must point to an existing file
+
+    {SPFS_DIR}{path} -> {target}
+"#
+    )]
+    #[diagnostic(severity(warning), code(spk::build::validation::broken_symlinks))]
+    BrokenSymlinksDenied {
+        path: RelativePathBuf,
+        target: String,
+    },
+    #[error("Build was expected to include a broken symlink, but didn't")]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::broken_symlinks),
+        help("This would need to be explicitly enabled in the package spec, which might have additional details")
+    )]
+    BrokenSymlinksRequired,
+
+    #[error(
+        r#"Libraries must export all of the symbols of {previous}, as this version claims to be binary compatible with it
+
+    {SPFS_DIR}{path} no longer exports:
+    {symbols}
+"#
+    )]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::removed_symbols),
+        help("Restore the removed symbols, or change the package's compat so that it is not binary compatible with the previous version")
+    )]
+    RemovedSymbolsDenied {
+        path: RelativePathBuf,
+        previous: BuildIdent,
+        /// The removed symbols, one per line
+        symbols: String,
+    },
+    #[error(
+        "Build was expected to remove symbols from a library of the previous version, but didn't"
+    )]
+    #[diagnostic(
+        severity(warning),
+        code(spk::build::validation::removed_symbols),
+        help("This would need to be explicitly enabled in the package spec, which might have additional details")
+    )]
+    RemovedSymbolsRequired,
 }
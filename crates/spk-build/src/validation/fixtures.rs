@@ -0,0 +1,42 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::path::Path;
+
+use spfs::tracking::Manifest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::{v0, Package};
+use spk_solve::Solution;
+
+use crate::report::{BuildOutputReport, BuildReport, BuildSetupReport, BuiltComponentReport};
+
+/// Create a report for a build of a test package whose run
+/// component contains all of the files under the given prefix.
+pub async fn report_for(prefix: &Path) -> BuildReport<v0::Spec, v0::Variant> {
+    let package = v0::Spec::new("test-pkg/1.0.0/3I42H3S6".parse().unwrap());
+    let manifest = spfs::tracking::compute_manifest(prefix).await.unwrap();
+    BuildReport {
+        output: BuildOutputReport {
+            components: [(
+                Component::Run,
+                BuiltComponentReport {
+                    layer: spfs::encoding::NULL_DIGEST.into(),
+                    manifest,
+                },
+            )]
+            .into_iter()
+            .collect(),
+            prefix: prefix.to_owned(),
+            ..Default::default()
+        },
+        setup: BuildSetupReport {
+            environment: Solution::default(),
+            variant: package.build.variants.first().cloned().unwrap_or_default(),
+            environment_filesystem: Manifest::new(
+                spfs::tracking::Entry::empty_dir_with_open_perms_with_data(package.ident().clone()),
+            ),
+            package,
+        },
+    }
+}
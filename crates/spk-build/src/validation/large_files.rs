@@ -0,0 +1,66 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{
+    FileSize,
+    ValidationMatcherDiscriminants,
+    ValidationRuleDiscriminants as RuleKind,
+    DEFAULT_LARGE_FILE_LIMIT,
+};
+use spk_schema::{Package, Variant};
+
+use super::package_files::{package_files, report_matched_files};
+use super::{Error, Report};
+use crate::report::{BuildReport, BuildSetupReport};
+
+#[cfg(test)]
+#[path = "./large_files_test.rs"]
+mod large_files_test;
+
+pub struct LargeFilesValidator<'a> {
+    pub kind: RuleKind,
+    pub limit: Option<FileSize>,
+    pub paths: Option<&'a FileMatcher>,
+}
+
+impl<'a> super::validator::sealed::Sealed for LargeFilesValidator<'a> {}
+
+#[async_trait::async_trait]
+impl<'a> super::Validator for LargeFilesValidator<'a> {
+    async fn validate_setup<P, V>(&self, _setup: &BuildSetupReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        Report::entire_build_not_matched(ValidationMatcherDiscriminants::LargeFiles)
+    }
+
+    async fn validate_build<P, V>(&self, report: &BuildReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        let limit = self.limit.unwrap_or(DEFAULT_LARGE_FILE_LIMIT);
+        let matched = package_files(report, self.paths)
+            .into_iter()
+            .filter(|(_, entry)| entry.is_regular_file() && entry.size() > limit.0)
+            .map(|(path, entry)| {
+                let error = Error::LargeFilesDenied {
+                    path: path.clone(),
+                    size: entry.size(),
+                    limit,
+                };
+                (path, error)
+            });
+        report_matched_files(
+            self.kind,
+            ValidationMatcherDiscriminants::LargeFiles,
+            self.paths,
+            report,
+            matched,
+            Error::LargeFilesRequired { limit },
+        )
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{FileSize, ValidationMatcher};
+use spk_schema::ValidationRule;
+
+use crate::validation::fixtures::report_for;
+use crate::validation::{Report, Validator};
+
+#[tokio::test]
+async fn test_validate_large_files() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmpdir.path().join("data")).unwrap();
+    std::fs::write(tmpdir.path().join("data/big.bin"), vec![0; 2048]).unwrap();
+    std::fs::write(tmpdir.path().join("small.txt"), "small").unwrap();
+    let report = report_for(tmpdir.path()).await;
+
+    let deny = ValidationRule::Deny {
+        condition: ValidationMatcher::LargeFiles {
+            limit: Some(FileSize(1024)),
+            paths: None,
+        },
+    };
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect_err("should get error when a file is over the limit");
+
+    ValidationRule::Deny {
+        condition: ValidationMatcher::LargeFiles {
+            limit: None,
+            paths: None,
+        },
+    }
+    .validate_build(&report)
+    .await
+    .into_result()
+    .expect("should use the default limit when none is given");
+
+    let allow = ValidationRule::Allow {
+        condition: ValidationMatcher::LargeFiles {
+            limit: Some(FileSize(1024)),
+            paths: Some(FileMatcher::new(["/data/"]).unwrap()),
+        },
+    };
+    Report::from_iter([
+        allow.validate_build(&report).await,
+        deny.validate_build(&report).await,
+    ])
+    .into_result()
+    .expect("allowing specific paths should override a general deny");
+}
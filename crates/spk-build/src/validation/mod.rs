@@ -3,25 +3,38 @@
 // https://github.com/spkenv/spk
 
 mod alter_existing_files;
+mod broken_symlinks;
 mod collect_all_files;
 mod collect_existing_files;
+mod embedded_prefix;
 mod empty_package;
 mod error;
+#[cfg(test)]
+mod fixtures;
 mod inherit_requirements;
+mod large_files;
 mod long_var_description;
+mod package_files;
 mod recursive_build;
+mod removed_symbols;
+mod setuid_files;
 mod spdx_license;
 mod strong_inheritance_var_desc;
 mod validator;
 
 pub use alter_existing_files::AlterExistingFilesValidator;
+pub use broken_symlinks::BrokenSymlinksValidator;
 pub use collect_all_files::CollectAllFilesValidator;
 pub use collect_existing_files::CollectExistingFilesValidator;
+pub use embedded_prefix::EmbeddedPrefixValidator;
 pub use empty_package::EmptyPackageValidator;
 pub use error::{Error, Result};
 pub use inherit_requirements::InheritRequirementsValidator;
+pub use large_files::LargeFilesValidator;
 pub use long_var_description::LongVarDescriptionValidator;
 pub use recursive_build::RecursiveBuildValidator;
+pub use removed_symbols::RemovedSymbolsValidator;
+pub use setuid_files::SetuidFilesValidator;
 pub use spdx_license::SpdxLicenseValidator;
 pub use strong_inheritance_var_desc::StrongInheritanceVarDescriptionValidator;
 pub use validator::{Outcome, Report, Status, Subject, Validator};
@@ -0,0 +1,102 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use relative_path::RelativePathBuf;
+use spk_schema::foundation::env::data_path;
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{
+    ValidationMatcherDiscriminants,
+    ValidationRuleDiscriminants as RuleKind,
+};
+use spk_schema::{Package, Variant};
+
+use super::{Error, Outcome, Report, Status, Subject};
+use crate::report::BuildReport;
+
+/// The files and symlinks collected into the package being built,
+/// limited to those selected by `paths`, if given.
+///
+/// Package metadata is managed by spk and never included.
+pub fn package_files<'a, P, V>(
+    report: &'a BuildReport<P, V>,
+    paths: Option<&FileMatcher>,
+) -> Vec<(RelativePathBuf, &'a spfs::tracking::Entry)>
+where
+    P: Package,
+    V: Variant,
+{
+    let metadata = data_path(report.setup.package.ident());
+    report
+        .output
+        .component_entries()
+        .into_iter()
+        .filter(|(_, entry)| entry.is_regular_file() || entry.is_symlink())
+        .filter(|(path, _)| path.strip_prefix(&metadata).is_err())
+        .filter(|(path, _)| {
+            paths
+                .map(|m| m.matches(path.to_path("/"), false))
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+/// Create the report for a validator that matches individual files.
+///
+/// Rules that are limited to a set of paths are considered more
+/// specific than those that are not, and will override them for
+/// any file that they match.
+pub fn report_matched_files<P, V, I>(
+    kind: RuleKind,
+    condition: ValidationMatcherDiscriminants,
+    paths: Option<&FileMatcher>,
+    report: &BuildReport<P, V>,
+    matched: I,
+    required: Error,
+) -> Report
+where
+    P: Package,
+    V: Variant + Send + Sync,
+    I: IntoIterator<Item = (RelativePathBuf, Error)>,
+{
+    let locality = paths
+        .map(|m| format!("paths/{}", m.rules().join(",")))
+        .unwrap_or_default();
+    let owner = report.setup.package.ident();
+    let mut matched = matched.into_iter().peekable();
+    if matched.peek().is_none() {
+        return match kind {
+            RuleKind::Require => Outcome {
+                condition,
+                locality,
+                subject: Subject::Everything,
+                status: Status::Denied(required),
+            }
+            .into(),
+            RuleKind::Allow | RuleKind::Deny => {
+                Report::entire_build_not_matched_at(condition, [locality])
+            }
+        };
+    }
+    match kind {
+        RuleKind::Require => Report::entire_build_allowed_at(condition, [locality]),
+        // allowed files are still reported individually so that
+        // a rule for some paths does not also allow all others
+        RuleKind::Allow => matched
+            .map(|(path, _)| Outcome {
+                condition,
+                locality: locality.clone(),
+                subject: Subject::Path(owner.clone(), path),
+                status: Status::Allowed,
+            })
+            .collect(),
+        RuleKind::Deny => matched
+            .map(|(path, error)| Outcome {
+                condition,
+                locality: locality.clone(),
+                subject: Subject::Path(owner.clone(), path),
+                status: Status::Denied(error),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use itertools::Itertools;
+use relative_path::RelativePathBuf;
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{
+    ValidationMatcherDiscriminants,
+    ValidationRuleDiscriminants as RuleKind,
+};
+use spk_schema::{Package, Variant};
+
+use super::package_files::report_matched_files;
+use super::{Error, Report};
+use crate::report::{BuildReport, BuildSetupReport};
+
+#[cfg(test)]
+#[path = "./removed_symbols_test.rs"]
+mod removed_symbols_test;
+
+pub struct RemovedSymbolsValidator<'a> {
+    pub kind: RuleKind,
+    pub paths: Option<&'a FileMatcher>,
+}
+
+impl<'a> super::validator::sealed::Sealed for RemovedSymbolsValidator<'a> {}
+
+#[async_trait::async_trait]
+impl<'a> super::Validator for RemovedSymbolsValidator<'a> {
+    async fn validate_setup<P, V>(&self, _setup: &BuildSetupReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        Report::entire_build_not_matched(ValidationMatcherDiscriminants::RemovedSymbols)
+    }
+
+    async fn validate_build<P, V>(&self, report: &BuildReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        let removed = match &report.output.abi {
+            Some(abi) => match &abi.previous {
+                Some((previous, previous_abi)) => abi
+                    .current
+                    .removed_symbols(previous_abi)
+                    .into_iter()
+                    .map(|(path, symbols)| (previous, path, symbols))
+                    .collect(),
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        };
+        let matched = removed
+            .into_iter()
+            .filter(|(_, path, _)| self.paths.map(|m| m.matches(path, false)).unwrap_or(true))
+            .map(|(previous, path, symbols)| {
+                let path = RelativePathBuf::from(path);
+                let error = Error::RemovedSymbolsDenied {
+                    path: path.clone(),
+                    previous: previous.clone(),
+                    symbols: symbols.iter().join("\n    "),
+                };
+                (path, error)
+            });
+        report_matched_files(
+            self.kind,
+            ValidationMatcherDiscriminants::RemovedSymbols,
+            self.paths,
+            report,
+            matched,
+            Error::RemovedSymbolsRequired,
+        )
+    }
+}
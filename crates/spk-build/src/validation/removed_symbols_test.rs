@@ -0,0 +1,91 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spfs::tracking::Manifest;
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::ValidationMatcher;
+use spk_schema::{v0, Package, ValidationRule};
+use spk_solve::Solution;
+
+use crate::report::{AbiReport, BuildOutputReport, BuildReport, BuildSetupReport};
+use crate::validation::{Report, Validator};
+use crate::{LibraryAbi, PackageAbi};
+
+fn package_abi(path: &str, symbols: &[&str]) -> PackageAbi {
+    let library = LibraryAbi {
+        soname: None,
+        symbols: symbols.iter().map(|s| s.to_string()).collect(),
+    };
+    PackageAbi {
+        libraries: [(path.to_string(), library)].into_iter().collect(),
+    }
+}
+
+fn report_with(abi: Option<AbiReport>) -> BuildReport<v0::Spec, v0::Variant> {
+    let package = v0::Spec::new("test-pkg/1.1.0/3I42H3S6".parse().unwrap());
+    BuildReport {
+        output: BuildOutputReport {
+            abi,
+            ..Default::default()
+        },
+        setup: BuildSetupReport {
+            environment: Solution::default(),
+            variant: package.build.variants.first().cloned().unwrap_or_default(),
+            environment_filesystem: Manifest::new(
+                spfs::tracking::Entry::empty_dir_with_open_perms_with_data(package.ident().clone()),
+            ),
+            package,
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_validate_removed_symbols() {
+    let previous = package_abi("/lib/libtest.so", &["init", "run"]);
+    let current = package_abi("/lib/libtest.so", &["init"]);
+    let report = report_with(Some(AbiReport {
+        current,
+        previous: Some(("test-pkg/1.0.0/3I42H3S6".parse().unwrap(), previous)),
+    }));
+
+    let deny = ValidationRule::Deny {
+        condition: ValidationMatcher::RemovedSymbols { paths: None },
+    };
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect_err("should get error when a compatible version removes symbols");
+
+    let allow = ValidationRule::Allow {
+        condition: ValidationMatcher::RemovedSymbols {
+            paths: Some(FileMatcher::new(["/lib/libtest.so"]).unwrap()),
+        },
+    };
+    Report::from_iter([
+        deny.validate_build(&report).await,
+        allow.validate_build(&report).await,
+    ])
+    .into_result()
+    .expect("should allow removed symbols for libraries that are named explicitly");
+}
+
+#[tokio::test]
+async fn test_validate_removed_symbols_without_previous_version() {
+    let current = package_abi("/lib/libtest.so", &["init"]);
+    let deny = ValidationRule::Deny {
+        condition: ValidationMatcher::RemovedSymbols { paths: None },
+    };
+    for report in [
+        report_with(None),
+        report_with(Some(AbiReport {
+            current,
+            previous: None,
+        })),
+    ] {
+        deny.validate_build(&report)
+            .await
+            .into_result()
+            .expect("there is nothing to compare against without a compatible previous version");
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::{
+    ValidationMatcherDiscriminants,
+    ValidationRuleDiscriminants as RuleKind,
+};
+use spk_schema::{Package, Variant};
+
+use super::package_files::{package_files, report_matched_files};
+use super::{Error, Report};
+use crate::report::{BuildReport, BuildSetupReport};
+
+#[cfg(test)]
+#[path = "./setuid_files_test.rs"]
+mod setuid_files_test;
+
+/// The permission bits that allow a file to be executed
+/// as its owner or group rather than the current user
+const SETUID_BITS: u32 = 0o6000;
+
+pub struct SetuidFilesValidator<'a> {
+    pub kind: RuleKind,
+    pub paths: Option<&'a FileMatcher>,
+}
+
+impl<'a> super::validator::sealed::Sealed for SetuidFilesValidator<'a> {}
+
+#[async_trait::async_trait]
+impl<'a> super::Validator for SetuidFilesValidator<'a> {
+    async fn validate_setup<P, V>(&self, _setup: &BuildSetupReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        Report::entire_build_not_matched(ValidationMatcherDiscriminants::SetuidFiles)
+    }
+
+    async fn validate_build<P, V>(&self, report: &BuildReport<P, V>) -> Report
+    where
+        P: Package,
+        V: Variant + Send + Sync,
+    {
+        let matched = package_files(report, self.paths)
+            .into_iter()
+            .filter(|(_, entry)| entry.is_regular_file() && entry.mode & SETUID_BITS != 0)
+            .map(|(path, entry)| {
+                let error = Error::SetuidFilesDenied {
+                    path: path.clone(),
+                    mode: entry.mode,
+                };
+                (path, error)
+            });
+        report_matched_files(
+            self.kind,
+            ValidationMatcherDiscriminants::SetuidFiles,
+            self.paths,
+            report,
+            matched,
+            Error::SetuidFilesRequired,
+        )
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::os::unix::fs::PermissionsExt;
+
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::validation::ValidationMatcher;
+use spk_schema::ValidationRule;
+
+use crate::validation::fixtures::report_for;
+use crate::validation::{Report, Validator};
+
+#[tokio::test]
+async fn test_validate_setuid_files() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(tmpdir.path().join("bin")).unwrap();
+    let tool = tmpdir.path().join("bin/tool");
+    std::fs::write(&tool, "#!/bin/sh").unwrap();
+    std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o4755)).unwrap();
+    let report = report_for(tmpdir.path()).await;
+
+    let deny = ValidationRule::Deny {
+        condition: ValidationMatcher::SetuidFiles { paths: None },
+    };
+    deny.validate_build(&report)
+        .await
+        .into_result()
+        .expect_err("should get error when a file has the setuid bit");
+
+    let allow = ValidationRule::Allow {
+        condition: ValidationMatcher::SetuidFiles {
+            paths: Some(FileMatcher::new(["/bin/tool"]).unwrap()),
+        },
+    };
+    Report::from_iter([
+        deny.validate_build(&report).await,
+        allow.validate_build(&report).await,
+    ])
+    .into_result()
+    .expect("should allow setuid files that are named explicitly");
+}
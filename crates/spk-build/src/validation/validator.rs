@@ -82,6 +82,42 @@ macro_rules! rule_to_validator {
                 let $bind = super::InheritRequirementsValidator { kind, packages };
                 $op
             }
+            ValidationMatcher::LargeFiles { limit, paths } => {
+                let $bind = super::LargeFilesValidator {
+                    kind,
+                    limit: *limit,
+                    paths: paths.as_ref(),
+                };
+                $op
+            }
+            ValidationMatcher::EmbeddedPrefix { paths } => {
+                let $bind = super::EmbeddedPrefixValidator {
+                    kind,
+                    paths: paths.as_ref(),
+                };
+                $op
+            }
+            ValidationMatcher::SetuidFiles { paths } => {
+                let $bind = super::SetuidFilesValidator {
+                    kind,
+                    paths: paths.as_ref(),
+                };
+                $op
+            }
+            ValidationMatcher::BrokenSymlinks { paths } => {
+                let $bind = super::BrokenSymlinksValidator {
+                    kind,
+                    paths: paths.as_ref(),
+                };
+                $op
+            }
+            ValidationMatcher::RemovedSymbols { paths } => {
+                let $bind = super::RemovedSymbolsValidator {
+                    kind,
+                    paths: paths.as_ref(),
+                };
+                $op
+            }
         }
     }};
 }
@@ -222,10 +258,15 @@ impl Report {
     }
 
     /// Convert this report into a set of errors from the current state
+    ///
+    /// Errors are grouped by the condition that produced them
+    /// so that the same build always reports them in the same order.
     pub fn into_errors(self) -> Vec<Error> {
-        self.by_kind
-            .into_values()
-            .flatten()
+        let mut by_kind: Vec<_> = self.by_kind.into_iter().collect();
+        by_kind.sort_by_key(|(kind, _)| *kind);
+        by_kind
+            .into_iter()
+            .flat_map(|(_, outcomes)| outcomes)
             .filter_map(|r| r.into_error())
             .collect()
     }
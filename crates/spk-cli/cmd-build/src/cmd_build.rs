@@ -4,7 +4,7 @@
 
 use clap::Args;
 use miette::Result;
-use spk_cli_common::{flags, CommandArgs, Run};
+use spk_cli_common::{flags, CommandArgs, Reporter, Run};
 use spk_cmd_make_binary::cmd_make_binary::PackageSpecifier;
 
 #[cfg(test)]
@@ -52,6 +52,11 @@ pub struct Build {
     /// this package.
     #[clap(long)]
     pub allow_circular_dependencies: bool,
+
+    /// Skip packages that do not support the current os and arch,
+    /// instead of failing the build.
+    #[clap(long)]
+    pub skip_unsupported: bool,
 }
 
 #[derive(Debug)]
@@ -89,6 +94,7 @@ impl Run for Build {
                 verbose: self.verbose,
                 packages: packages.clone(),
                 runtime: self.runtime.clone(),
+                publish_to: None,
                 created_src: spk_cli_common::BuildResult::default(),
             };
             let idents = make_source.make_source().await?;
@@ -100,6 +106,8 @@ impl Run for Build {
                 repos: self.repos.clone(),
                 options: self.options.clone(),
                 here: self.here,
+                source_repo: None,
+                source_digest: None,
                 interactive: self.interactive,
                 env: self.env,
                 packages: packages
@@ -112,6 +120,7 @@ impl Run for Build {
                 variant: self.variant.clone(),
                 formatter_settings: self.formatter_settings.clone(),
                 allow_circular_dependencies: self.allow_circular_dependencies,
+                skip_unsupported: self.skip_unsupported,
                 created_builds: spk_cli_common::BuildResult::default(),
             };
             let exit_status = make_binary.run().await?;
@@ -124,10 +133,13 @@ impl Run for Build {
             }
         }
 
-        println!("Completed builds:");
-        for (_, artifact) in builds_for_summary.iter() {
-            println!("   {artifact}");
-        }
+        Reporter::current().report(&builds_for_summary, || {
+            println!("Completed builds:");
+            for (_, artifact) in builds_for_summary.iter() {
+                println!("   {artifact}");
+            }
+            Ok(())
+        })?;
 
         Ok(BuildResult {
             exit_status: 0,
@@ -2,11 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use clap::Args;
-use miette::Result;
+use std::sync::Arc;
+
+use clap::{Args, Parser};
+use miette::{Context, IntoDiagnostic, Result};
 use spk_cli_common::{flags, CommandArgs, Run};
+use spk_convert::PipConverter;
+use spk_schema::foundation::format::FormatIdent;
+use spk_storage as storage;
 
 /// Convert a package from an external packaging system for use in spk
+///
+/// The `pip` converter is built in, other converters are run from
+/// a `spk-convert-<converter>` package.
 #[derive(Args)]
 pub struct Convert {
     #[clap(flatten)]
@@ -40,6 +48,10 @@ impl Run for Convert {
     type Output = i32;
 
     async fn run(&mut self) -> Result<Self::Output> {
+        if self.converter == "pip" {
+            return self.convert_pip().await;
+        }
+
         let converter_package = format!("spk-convert-{}", self.converter);
 
         let mut command = vec![converter_package.clone()];
@@ -57,6 +69,7 @@ impl Run for Convert {
             requests: self.requests.clone(),
             verbose: self.verbose,
             formatter_settings: self.formatter_settings.clone(),
+            freeze: false,
             requested: vec![converter_package],
             command,
         };
@@ -64,6 +77,117 @@ impl Run for Convert {
     }
 }
 
+impl Convert {
+    async fn convert_pip(&mut self) -> Result<i32> {
+        let mut args = vec!["spk convert pip".to_string()];
+        args.extend(self.args.iter().cloned());
+        let pip = ConvertPip::try_parse_from(&args).map_err(|err| miette::miette!("{err}"))?;
+
+        let mut converter = PipConverter::default();
+        converter
+            .with_python_version(&pip.python_version)
+            .with_python_abi(Some(pip.python_abi()))
+            .with_follow_deps(!pip.no_deps)
+            .with_cli_args(args.join(" "));
+        if let Some(exe) = &pip.pip {
+            converter.with_pip(exe);
+        }
+
+        let options = self.options.get_options()?;
+        let (_runtime, local, repos) = tokio::try_join!(
+            self.runtime.ensure_active_runtime(&["convert"]),
+            async {
+                storage::local_repository()
+                    .await
+                    .map_err(miette::Error::from)
+            },
+            async {
+                self.solver
+                    .repos
+                    .get_repos_for_non_destructive_operation()
+                    .await
+            },
+        )?;
+        let local = storage::RepositoryHandle::from(local);
+        let repos = repos
+            .into_iter()
+            .map(|(_, r)| Arc::new(r))
+            .collect::<Vec<_>>();
+
+        tracing::info!("resolving pip requirements: {}", pip.requirements.join(" "));
+        let packages = converter.resolve(&pip.requirements).await?;
+        let recipes = converter.generate_recipes(&packages)?;
+        let converted = converter
+            .build_recipes(recipes, &options, &local, repos)
+            .await?;
+
+        println!("\nThe following packages were converted:\n");
+        for package in converted.iter() {
+            println!(
+                "  {} {} => {}",
+                package.pypi_name,
+                package.pypi_version,
+                package.ident.format_ident()
+            );
+        }
+        println!("\nThese packages are now available in the local repository");
+
+        if let Some(path) = &pip.mapping {
+            let file = std::fs::File::create(path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to create {path:?}"))?;
+            serde_json::to_writer_pretty(file, &converted)
+                .into_diagnostic()
+                .wrap_err("Failed to write pip package mapping")?;
+        }
+        Ok(0)
+    }
+}
+
+/// Convert and build packages from PyPI using pip
+#[derive(Parser)]
+struct ConvertPip {
+    /// The version of python to convert packages for
+    #[clap(long, default_value = "3.7")]
+    python_version: String,
+
+    /// The python abi to convert packages for (defaults to the
+    /// cpython abi for the python version, eg: cp39)
+    #[clap(long)]
+    python_abi: Option<String>,
+
+    /// Do not follow and convert dependencies of the requested pip packages
+    #[clap(long)]
+    no_deps: bool,
+
+    /// The pip executable used to resolve requirements
+    #[clap(long)]
+    pip: Option<String>,
+
+    /// Write the mapping of pip packages to spk packages to this json file
+    #[clap(long, value_name = "FILE")]
+    mapping: Option<std::path::PathBuf>,
+
+    /// The pip requirements to convert (eg: pytest, 'PySide2>=5')
+    #[clap(required = true, value_name = "NAME[VERSION]")]
+    requirements: Vec<String>,
+}
+
+impl ConvertPip {
+    fn python_abi(&self) -> String {
+        if let Some(abi) = &self.python_abi {
+            return abi.clone();
+        }
+        let version = self.python_version.replace('.', "");
+        // python versions before 3.8 used the 'm' abi flag
+        match self.python_version.split_once('.') {
+            Some(("3", minor)) if minor.parse().unwrap_or(u32::MAX) < 8 => format!("cp{version}m"),
+            Some(("2", _)) => format!("cp{version}m"),
+            _ => format!("cp{version}"),
+        }
+    }
+}
+
 impl CommandArgs for Convert {
     fn get_positional_args(&self) -> Vec<String> {
         // The important positional args for a convert are the specified converter and args
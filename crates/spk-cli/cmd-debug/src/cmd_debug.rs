@@ -46,10 +46,11 @@ impl Run for Debug {
         // are developing and haven't changed the package version number from
         // an existing published package.
         repos.sort_unstable_by_key(|(repo_name, _)| i32::from(repo_name != "local"));
+        let repos = storage::ChainedRepository::new(repos.into_iter().map(|(_, r)| r).collect());
 
         let mut source_layers = HashMap::new();
 
-        'next_request: for solved in env.items() {
+        for solved in env.items() {
             if let Some(Build::BuildId(_)) = solved.request.pkg.build.as_ref() {
                 let mut source_pkg = solved.request.pkg.clone();
                 source_pkg.build = Some(Build::Source);
@@ -64,16 +65,15 @@ impl Run for Debug {
                     // Search for a repo that has this source package.
                     // TODO: It would be useful if it was possible to know what repo
                     // a package found in runtime repo came from.
-                    for (_, repo) in repos.iter() {
-                        if let Ok(comps) = repo.read_components(&ident).await {
-                            if self.verbose > 0 && !comps.is_empty() {
-                                tracing::info!("Adding source package: {}", ident.format_ident());
-                            }
-                            for digest in comps.values() {
-                                source_layers.insert(*digest, repo);
-                            }
-                            continue 'next_request;
+                    if let Some(repo) = repos.find_build_repository(&ident).await? {
+                        let comps = repo.read_components(&ident).await?;
+                        if self.verbose > 0 && !comps.is_empty() {
+                            tracing::info!("Adding source package: {}", ident.format_ident());
                         }
+                        for digest in comps.values() {
+                            source_layers.insert(*digest, (repo, ident.clone()));
+                        }
+                        continue;
                     }
 
                     if self.verbose > 0 {
@@ -90,13 +90,18 @@ impl Run for Debug {
 
         let mut rt = spfs::active_runtime().await?;
 
-        for (layer, repo) in source_layers {
+        for (layer, (repo, ident)) in source_layers {
             if !local_repo.has_object(layer).await {
-                if let storage::RepositoryHandle::SPFS(repo) = repo {
-                    let syncer = spfs::Syncer::new(repo, &local_repo)
-                        .with_reporter(spfs::sync::ConsoleSyncReporter::default());
-                    syncer.sync_digest(layer).await?;
-                }
+                let Some(repo) = repo.spfs_repository_for(&ident).await? else {
+                    miette::bail!(
+                        "Cannot fetch {} from {}, it is not an spfs repository",
+                        ident.format_ident(),
+                        repo.name()
+                    );
+                };
+                let syncer = spfs::Syncer::new(repo, &local_repo)
+                    .with_reporter(spfs::sync::ConsoleSyncReporter::default());
+                syncer.sync_digest(layer).await?;
             }
 
             rt.push_digest(layer);
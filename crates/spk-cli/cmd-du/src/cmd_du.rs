@@ -339,7 +339,7 @@ impl<T: Output> Du<T> {
                                 let mut pkg_du_with_component = pkg_du_with_build.clone();
                                 pkg_du_with_component.component = component;
 
-                                let spk_storage::RepositoryHandle::SPFS(repo) = repo else { continue; };
+                                let Some(repo) = repo.spfs_repository_for(spec.ident()).await? else { continue; };
 
                                 let mut item = repo.read_object(digest).await?;
                                 let mut items_to_process: Vec<spfs::graph::Object> = vec![item];
@@ -376,7 +376,7 @@ impl<T: Output> Du<T> {
                                                     }
                                                 }
                                             }
-                                            Enum::Blob(_) => self.output.warn(format_args!("Blob object cannot have disk usage generated")),
+                                            Enum::Blob(_) | Enum::ChunkList(_) => self.output.warn(format_args!("Blob object cannot have disk usage generated")),
                                         }
                                     }
                                     items_to_process = std::mem::take(&mut next_iter_objects);
@@ -80,10 +80,12 @@ async fn test_du_trivially_works() {
         "183local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/spec.yaml",
         "0local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.cmpt",
         "17local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.sh",
+        "35local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.log",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/options.json",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/run.cmpt",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/spec.yaml",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.sh",
+        "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.log",
     ];
 
     for output in opt.du.output.vec.lock().unwrap().iter() {
@@ -363,7 +365,7 @@ async fn test_du_summarize_output_enabled() {
     let mut opt = Opt::try_parse_from(["du", "local/my-pkg", "-s"]).unwrap();
     opt.du.run().await.unwrap();
 
-    let expected_output = format!("202local/my-pkg/{}", "".red());
+    let expected_output = format!("237local/my-pkg/{}", "".red());
     let mut generated_output = opt.du.output.vec.lock().unwrap()[0].clone();
     generated_output.retain(|c| !c.is_whitespace());
 
@@ -407,11 +409,13 @@ async fn test_du_summarize_output_is_not_enabled() {
         "2local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/options.json",
         "0local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.cmpt",
         "17local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.sh",
+        "35local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.log",
         "183local/my-pkg/1.0.0/3I42H3S6/:build/spk/pkg/my-pkg/1.0.0/3I42H3S6/spec.yaml",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/spec.yaml",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/options.json",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/run.cmpt",
         "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.sh",
+        "0local/my-pkg/1.0.0/3I42H3S6/:run/spk/pkg/my-pkg/1.0.0/3I42H3S6/build.log",
     ];
 
     for output in opt.du.output.vec.lock().unwrap().iter() {
@@ -470,7 +474,7 @@ async fn test_deprecate_flag() {
 
     let mut opt_with_deprecate_flag = Opt::try_parse_from(["du", "local/my-pkg", "-ds"]).unwrap();
     opt_with_deprecate_flag.du.run().await.unwrap();
-    let expected_output = format!("219local/my-pkg/{}", "DEPRECATED".red());
+    let expected_output = format!("254local/my-pkg/{}", "DEPRECATED".red());
     let mut generated_output = opt_with_deprecate_flag.du.output.vec.lock().unwrap()[0].clone();
     generated_output.retain(|c| !c.is_whitespace());
     assert_eq!(expected_output, generated_output);
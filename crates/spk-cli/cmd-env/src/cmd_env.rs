@@ -2,19 +2,41 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use clap::Args;
-use miette::{Context, Result};
-use spk_cli_common::{build_required_packages, flags, CommandArgs, Run};
-use spk_exec::setup_runtime;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use miette::{Context, IntoDiagnostic, Result};
+use spk_cli_common::install_hooks::run_install_hooks_in_dir;
+use spk_cli_common::{
+    build_required_packages,
+    current_env,
+    flags,
+    run_install_hooks,
+    CommandArgs,
+    Run,
+};
+use spk_exec::{
+    activation_script_path,
+    prepare_runtime_dir,
+    render_runtime_dir,
+    setup_filtered_runtime,
+    PathFilters,
+};
+use spk_schema::foundation::name::PkgName;
+use spk_schema::ident::{Request, RequestedBy};
 #[cfg(feature = "statsd")]
 use spk_solve::{get_metrics_client, SPK_RUN_TIME_METRIC};
 
+use crate::cmd_env_check::EnvCheck;
+use crate::cmd_env_diff::EnvDiff;
+use crate::cmd_env_licenses::EnvLicenses;
+
 /// Resolve and run an environment on-the-fly
 ///
 /// Use '--' to separate the command from requests. If no command is given,
 /// spawn a new shell
 #[derive(Args)]
-#[clap(visible_aliases = &["run", "shell"])]
+#[clap(visible_aliases = &["run", "shell"], args_conflicts_with_subcommands = true)]
 pub struct Env {
     #[clap(flatten)]
     pub solver: flags::Solver,
@@ -31,6 +53,38 @@ pub struct Env {
     #[clap(flatten)]
     pub formatter_settings: flags::DecisionFormatterSettings,
 
+    /// Print the packages in the current environment as a list of
+    /// exactly pinned requests, and exit
+    ///
+    /// The output can be used as install requirements in a recipe
+    /// or to recreate the current environment later on.
+    #[clap(long, conflicts_with_all = &["REQUESTS", "command"])]
+    pub freeze: bool,
+
+    /// Render the resolved environment into this directory instead
+    /// of an spfs runtime, along with scripts that activate it
+    ///
+    /// The directory must be empty or not exist. This does not need
+    /// spfs to be mounted, and is useful for containers or machines
+    /// where the privileged spfs binaries are not available. If a command
+    /// is given, it is run with the environment activated.
+    #[clap(long, value_name = "DIR", conflicts_with = "freeze")]
+    pub runtime_dir: Option<PathBuf>,
+
+    /// Do not run the install hooks of the packages in the environment
+    #[clap(long)]
+    pub no_install_hooks: bool,
+
+    /// Only include the files of a package that match a path pattern,
+    /// given as NAME:PATTERN (eg: 'big-pkg:/lib/')
+    ///
+    /// Patterns follow the gitignore format, relative to /spfs, and can be
+    /// given more than once. The package metadata and startup scripts are
+    /// always included. This reduces the data that needs to be synced and
+    /// rendered when only part of a large package is needed.
+    #[clap(long, value_name = "NAME:PATTERN", conflicts_with_all = &["freeze", "runtime_dir"])]
+    pub paths: Vec<String>,
+
     /// The requests to resolve and run
     #[clap(name = "REQUESTS")]
     pub requested: Vec<String>,
@@ -38,9 +92,20 @@ pub struct Env {
     /// An optional command to run in the resolved environment.
     ///
     /// Use '--' to separate the command from requests. If no command is given,
-    /// spawn a new shell
+    /// spawn a new shell. The runtime is removed once the command exits, unless
+    /// --keep-runtime is given, and spk exits with the command's exit code.
     #[clap(raw = true)]
     pub command: Vec<String>,
+
+    #[clap(subcommand)]
+    pub subcommand: Option<EnvCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum EnvCommand {
+    Check(EnvCheck),
+    Diff(EnvDiff),
+    Licenses(EnvLicenses),
 }
 
 #[async_trait::async_trait]
@@ -48,6 +113,19 @@ impl Run for Env {
     type Output = i32;
 
     async fn run(&mut self) -> Result<Self::Output> {
+        match &self.subcommand {
+            Some(EnvCommand::Check(check)) => return check.run().await,
+            Some(EnvCommand::Diff(diff)) => return diff.run(self.verbose).await,
+            Some(EnvCommand::Licenses(licenses)) => return licenses.run(self.verbose).await,
+            None => {}
+        }
+        if self.freeze {
+            return self.print_frozen_requests().await;
+        }
+        if let Some(runtime_dir) = self.runtime_dir.clone() {
+            return self.render_into_runtime_dir(&runtime_dir).await;
+        }
+
         let mut rt = self
             .runtime
             .ensure_active_runtime(&["env", "run", "shell"])
@@ -64,6 +142,7 @@ impl Run for Env {
             rt.config.live_layers = live_layers;
         }
 
+        let path_filters = self.path_filters()?;
         let mut solver = self.solver.get_solver(&self.options).await?;
 
         let requests = self
@@ -78,10 +157,18 @@ impl Run for Env {
         let (solution, _) = formatter.run_and_print_resolve(&solver).await?;
 
         let solution = build_required_packages(&solution).await?;
+        for name in path_filters.names() {
+            if solution.get(name.as_str()).is_none() {
+                tracing::warn!("Path filter given for {name}, but it is not in the environment");
+            }
+        }
 
         rt.status.editable =
             self.runtime.editable() || self.requests.any_build_stage_requests(&self.requested)?;
-        setup_runtime(&mut rt, &solution).await?;
+        setup_filtered_runtime(&mut rt, &solution, &path_filters).await?;
+        if !self.no_install_hooks {
+            run_install_hooks(&mut rt, &solution).await?;
+        }
 
         let env = solution.to_environment(Some(std::env::vars()));
 
@@ -119,8 +206,82 @@ impl Run for Env {
     }
 }
 
+impl Env {
+    fn path_filters(&self) -> Result<PathFilters> {
+        let mut filters = PathFilters::default();
+        for value in self.paths.iter() {
+            let Some((name, pattern)) = value.split_once(':') else {
+                miette::bail!("Invalid path filter '{value}', expected NAME:PATTERN");
+            };
+            filters.add(PkgName::new(name)?.to_owned(), [pattern])?;
+        }
+        Ok(filters)
+    }
+
+    async fn render_into_runtime_dir(&self, runtime_dir: &Path) -> Result<i32> {
+        let root = prepare_runtime_dir(runtime_dir)?;
+        let mut solver = self.solver.get_solver(&self.options).await?;
+        let requests = self
+            .requests
+            .parse_requests(&self.requested, &self.options, solver.repositories())
+            .await?;
+        for request in requests {
+            solver.add_request(request)
+        }
+
+        let formatter = self.formatter_settings.get_formatter(self.verbose)?;
+        let (solution, _) = formatter.run_and_print_resolve(&solver).await?;
+        let solution = build_required_packages(&solution).await?;
+        render_runtime_dir(&solution, &root).await?;
+        if !self.no_install_hooks {
+            run_install_hooks_in_dir(&root, &solution).await?;
+        }
+
+        let activate = activation_script_path("sh").to_path(&root);
+        if self.command.is_empty() {
+            tracing::info!("Rendered environment into {}", root.display());
+            tracing::info!("To activate it, run: source {}", activate.display());
+            return Ok(0);
+        }
+
+        let status = std::process::Command::new("bash")
+            .arg("-c")
+            .arg(r#"source "$0" && exec "$@""#)
+            .arg(&activate)
+            .args(&self.command)
+            .status()
+            .into_diagnostic()
+            .wrap_err("Failed to execute command in runtime dir")?;
+        Ok(status.code().unwrap_or(1))
+    }
+
+    async fn print_frozen_requests(&self) -> Result<i32> {
+        let solution = current_env().await?;
+        let requests: Vec<_> = solution
+            .to_pinned_requests(RequestedBy::CurrentEnvironment)
+            .into_iter()
+            .map(Request::Pkg)
+            .collect();
+        let yaml = serde_yaml::to_string(&requests)
+            .into_diagnostic()
+            .wrap_err("Failed to serialize pinned requests")?;
+        print!("{yaml}");
+        Ok(0)
+    }
+}
+
 impl CommandArgs for Env {
     fn get_positional_args(&self) -> Vec<String> {
+        match &self.subcommand {
+            Some(EnvCommand::Check(_)) => return Vec::new(),
+            Some(EnvCommand::Diff(diff)) => {
+                return std::iter::once(diff.from.clone())
+                    .chain(diff.to.clone())
+                    .collect();
+            }
+            Some(EnvCommand::Licenses(licenses)) => return licenses.env.iter().cloned().collect(),
+            None => {}
+        }
         self.requested.clone()
     }
 }
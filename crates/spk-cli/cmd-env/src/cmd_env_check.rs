@@ -0,0 +1,369 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::Args;
+use miette::Result;
+use serde::Serialize;
+use spfs::storage::fs::OpenFsRepository;
+use spfs::storage::RepositoryHandle;
+use spk_cli_common::{current_env, Reporter};
+use spk_schema::foundation::spec_ops::Named;
+use spk_schema::{startup_script_stem, BuildIdent, EnvOp, Package};
+use spk_solve::Solution;
+
+#[cfg(test)]
+#[path = "./cmd_env_check_test.rs"]
+mod cmd_env_check_test;
+
+/// Check the health of the active runtime
+///
+/// Verifies that every layer of the runtime and all of their payloads are
+/// in the repositories that the runtime reads from, that the layers have been rendered when the
+/// runtime needs it, and that the startup scripts and environment variables
+/// of the packages have been applied to the current shell. Each problem is
+/// reported along with the step that can repair it. Exits with 1 if any
+/// problems remain. Use 'spk --output json' to get the report as json.
+#[derive(Args)]
+pub struct EnvCheck {
+    /// Repair what can be repaired, by syncing any missing objects and
+    /// payloads from the remote repository and re-rendering the runtime
+    #[clap(long)]
+    pub repair: bool,
+
+    /// The name or address of the repository that missing
+    /// data is synced from when repairing
+    #[clap(long, short, default_value = "origin")]
+    pub remote: String,
+}
+
+/// A single problem found in the active runtime
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "problem", rename_all = "kebab-case")]
+pub enum EnvProblem {
+    /// An object needed by the runtime is not in its repositories
+    MissingObject { digest: String },
+    /// A payload needed by the runtime is not in its repositories
+    MissingPayload { digest: String },
+    /// A layer of the runtime has not been rendered
+    NotRendered { digest: String },
+    /// The startup script of a package is not in the runtime
+    MissingStartupScript { pkg: BuildIdent, path: PathBuf },
+    /// A variable that spk sets does not match the environment
+    Variable {
+        name: String,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    /// A value that a package adds to a variable is not in it
+    MissingValue {
+        pkg: BuildIdent,
+        name: String,
+        value: String,
+    },
+}
+
+impl EnvProblem {
+    /// True if this problem can be fixed with `--repair`
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::Variable { .. } | Self::MissingValue { .. })
+    }
+
+    /// A description of how to fix this problem, where missing
+    /// data is repaired from the named remote repository
+    pub fn repair_step(&self, remote: &str) -> String {
+        match self {
+            Self::MissingObject { .. } | Self::MissingPayload { .. } => format!(
+                "run 'spk env check --repair' to sync it from the {remote} repository, \
+                or 'spfs sync' it from another repository that has it"
+            ),
+            Self::NotRendered { .. } => {
+                "run 'spk env check --repair' to render it and remount the runtime".to_string()
+            }
+            Self::MissingStartupScript { .. } => {
+                "run 'spk env check --repair' to remount the runtime".to_string()
+            }
+            Self::Variable { .. } | Self::MissingValue { .. } => {
+                "run 'spfs shell' to start a new shell with the environment applied again"
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for EnvProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingObject { digest } => write!(f, "object {digest} is missing"),
+            Self::MissingPayload { digest } => write!(f, "payload {digest} is missing"),
+            Self::NotRendered { digest } => write!(f, "layer {digest} is not rendered"),
+            Self::MissingStartupScript { pkg, path } => {
+                write!(f, "{pkg}: startup script {} is missing", path.display())
+            }
+            Self::Variable {
+                name,
+                expected,
+                actual,
+            } => {
+                let expected = expected.as_deref().unwrap_or("(unset)");
+                let actual = actual.as_deref().unwrap_or("(unset)");
+                write!(f, "${name} is '{actual}', expected '{expected}'")
+            }
+            Self::MissingValue { pkg, name, value } => {
+                write!(f, "{pkg}: ${name} does not contain '{value}'")
+            }
+        }
+    }
+}
+
+/// A problem along with how to fix it, as reported to the user
+#[derive(Serialize)]
+struct ReportedProblem<'a> {
+    #[serde(flatten)]
+    problem: &'a EnvProblem,
+    repair: String,
+}
+
+#[derive(Serialize)]
+struct CheckReport<'a> {
+    runtime: &'a str,
+    problems: Vec<ReportedProblem<'a>>,
+    repaired: usize,
+}
+
+impl EnvCheck {
+    pub async fn run(&self) -> Result<i32> {
+        let solution = current_env().await?;
+        let mut rt = spfs::active_runtime().await?;
+        let config = spfs::get_config()?;
+        let local = Arc::new(config.get_opened_local_repository().await?);
+        let repo = RepositoryHandle::from(Arc::clone(&local));
+        // a runtime that is not localized reads its data from the
+        // configured runtime repositories, and not just the local one
+        let runtime_repo = match rt.config.mount_backend.requires_localization() {
+            true => None,
+            false => Some(spfs::get_runtime_backing_repo(&rt).await?),
+        };
+        let mut remote = None;
+        if self.repair {
+            remote = Some(config.get_remote(&self.remote).await?);
+        }
+
+        let mut checker = spfs::Checker::new(runtime_repo.as_ref().unwrap_or(&repo));
+        if let Some(remote) = &remote {
+            checker = checker.with_repair_source(remote);
+        }
+        let mut summary = spfs::check::CheckSummary::default();
+        for digest in rt.status.stack.iter_bottom_up() {
+            summary += checker.check_digest(digest).await?.summary();
+        }
+        drop(checker);
+        let mut repaired = summary.repaired_objects + summary.repaired_payloads;
+        let mut problems: Vec<_> = summary
+            .missing_objects
+            .iter()
+            .map(|digest| EnvProblem::MissingObject {
+                digest: digest.to_string(),
+            })
+            .chain(
+                summary
+                    .missing_payloads
+                    .iter()
+                    .map(|digest| EnvProblem::MissingPayload {
+                        digest: digest.to_string(),
+                    }),
+            )
+            .collect();
+
+        // the rest of the runtime cannot be checked
+        // properly until all of its data is available
+        if problems.is_empty() {
+            let mut mount_problems = check_mount(&rt, &repo, &local, &solution).await?;
+            if self.repair && !mount_problems.is_empty() {
+                tracing::info!("Remounting runtime {}", rt.name());
+                spfs::remount_runtime(&rt).await?;
+                // remounting may have flattened a different set of layers
+                rt = spfs::active_runtime().await?;
+                let remaining = check_mount(&rt, &repo, &local, &solution).await?;
+                repaired += mount_problems.len().saturating_sub(remaining.len());
+                mount_problems = remaining;
+            }
+            problems.extend(mount_problems);
+        }
+        problems.extend(check_variables(&solution, &std::env::vars().collect()));
+
+        let report = CheckReport {
+            runtime: rt.name(),
+            problems: problems
+                .iter()
+                .map(|problem| ReportedProblem {
+                    problem,
+                    repair: problem.repair_step(&self.remote),
+                })
+                .collect(),
+            repaired,
+        };
+        Reporter::current().report(&report, || {
+            if repaired > 0 {
+                println!("Repaired {repaired} problem(s) in runtime {}", rt.name());
+            }
+            if report.problems.is_empty() {
+                println!("No problems found in runtime {}", rt.name());
+            }
+            for reported in report.problems.iter() {
+                println!("{}", reported.problem);
+                println!("  to fix: {}", reported.repair);
+            }
+            Ok(())
+        })?;
+
+        if problems.is_empty() {
+            return Ok(0);
+        }
+        if !self.repair && problems.iter().any(EnvProblem::is_repairable) {
+            tracing::info!("running with `--repair` may be able to resolve some of these problems");
+        }
+        Ok(1)
+    }
+}
+
+/// Check the parts of the runtime that are fixed by remounting it
+async fn check_mount(
+    rt: &spfs::runtime::Runtime,
+    repo: &RepositoryHandle,
+    local: &OpenFsRepository,
+    solution: &Solution,
+) -> Result<Vec<EnvProblem>> {
+    let mut problems = Vec::new();
+    if rt.config.mount_backend.requires_localization() {
+        problems.extend(check_renders(rt, repo, local).await?);
+    }
+    problems.extend(check_startup_scripts(solution, Path::new("/spfs")));
+    Ok(problems)
+}
+
+/// Find the layers of the runtime that have not been rendered
+async fn check_renders(
+    rt: &spfs::runtime::Runtime,
+    repo: &RepositoryHandle,
+    local: &OpenFsRepository,
+) -> Result<Vec<EnvProblem>> {
+    let manifests: Vec<_> = if rt.flattened_layers().is_empty() {
+        spfs::resolve_stack_to_layers(&rt.status.stack, Some(repo))
+            .await?
+            .iter()
+            .filter_map(|layer| layer.manifest().copied())
+            .collect()
+    } else {
+        // which layers were merged into the flattened ones is not
+        // recorded, so only the flattened renders can be checked
+        rt.flattened_layers().iter().copied().collect()
+    };
+    let mut problems = Vec::new();
+    for digest in manifests {
+        if !local.has_rendered_manifest(digest).await {
+            problems.push(EnvProblem::NotRendered {
+                digest: digest.to_string(),
+            });
+        }
+    }
+    Ok(problems)
+}
+
+/// Find the packages whose startup script is missing from the given root.
+pub fn check_startup_scripts(solution: &Solution, root: &Path) -> Vec<EnvProblem> {
+    solution
+        .items()
+        .filter(|item| !item.spec.runtime_environment().is_empty())
+        .filter_map(|item| {
+            let stem = startup_script_stem(item.spec.name(), item.spec.runtime_environment());
+            let path = root
+                .join("etc")
+                .join("spfs")
+                .join("startup.d")
+                .join(format!("{stem}.sh"));
+            (!path.exists()).then(|| EnvProblem::MissingStartupScript {
+                pkg: item.spec.ident().clone(),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Compare the given environment to the one expected for the solution.
+///
+/// Only the variables that spk sets for each package are compared exactly.
+/// The values that packages set, append or prepend in their own environment
+/// are expected to be found in the final variables, except for those that
+/// refer to other variables and so cannot be known ahead of time.
+pub fn check_variables(solution: &Solution, env: &HashMap<String, String>) -> Vec<EnvProblem> {
+    let is_spk_var = |name: &&String| name.starts_with("SPK_PKG_") || *name == "SPK_ACTIVE_PREFIX";
+    let expected = solution.to_environment(None::<HashMap<String, String>>);
+    let names: BTreeSet<_> = expected
+        .keys()
+        .filter(is_spk_var)
+        .chain(env.keys().filter(is_spk_var))
+        .collect();
+    let mut problems = Vec::new();
+    for name in names {
+        let (expected, actual) = (expected.get(name), env.get(name));
+        if expected != actual {
+            problems.push(EnvProblem::Variable {
+                name: name.clone(),
+                expected: expected.cloned(),
+                actual: actual.cloned(),
+            });
+        }
+    }
+
+    // the startup scripts are sourced in the order of their file names,
+    // and a later set replaces anything done by the ones before it
+    let mut packages: Vec<_> = solution
+        .items()
+        .map(|item| {
+            let ops = item.spec.runtime_environment();
+            (
+                startup_script_stem(item.spec.name(), ops),
+                item.spec.ident(),
+                ops,
+            )
+        })
+        .collect();
+    packages.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut values: BTreeMap<&str, Vec<(&BuildIdent, &str)>> = BTreeMap::new();
+    for (_, pkg, ops) in packages.iter() {
+        for op in ops.iter() {
+            match op {
+                EnvOp::Set(op) => {
+                    values.insert(&op.set, vec![(*pkg, op.value.as_str())]);
+                }
+                EnvOp::Append(op) => values
+                    .entry(&op.append)
+                    .or_default()
+                    .push((*pkg, op.value.as_str())),
+                EnvOp::Prepend(op) => values
+                    .entry(&op.prepend)
+                    .or_default()
+                    .push((*pkg, op.value.as_str())),
+                EnvOp::Comment(_) | EnvOp::Priority(_) => {}
+            }
+        }
+    }
+    for (name, values) in values {
+        let actual = env.get(name).map(String::as_str).unwrap_or_default();
+        for (pkg, value) in values {
+            if !value.contains('$') && !actual.contains(value) {
+                problems.push(EnvProblem::MissingValue {
+                    pkg: pkg.clone(),
+                    name: name.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+    problems
+}
@@ -0,0 +1,116 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rstest::rstest;
+use spk_solve::solution::fixtures::solution_from_specs;
+use spk_solve::solution::Solution;
+use spk_solve::spec;
+
+use super::{check_startup_scripts, check_variables, EnvProblem};
+
+fn make_solution() -> Solution {
+    solution_from_specs([
+        spec!({"pkg": "plain/1.0.0/3I42H3S6"}),
+        spec!({
+            "pkg": "tool/1.0.0/3I42H3S6",
+            "install": {"environment": [
+                {"prepend": "PATH", "value": "/spfs/opt/tool/bin"},
+                {"append": "TOOL_PLUGINS", "value": "/spfs/opt/tool/plugins"},
+            ]},
+        }),
+        spec!({
+            "pkg": "plugins/1.0.0/3I42H3S6",
+            "install": {"environment": [
+                {"priority": 99},
+                {"set": "TOOL_PLUGINS", "value": "/spfs/opt/plugins"},
+                {"append": "TOOL_PLUGINS", "value": "${HOME}/plugins"},
+            ]},
+        }),
+    ])
+}
+
+fn applied_environment(solution: &Solution) -> HashMap<String, String> {
+    let mut env = solution.to_environment(None::<HashMap<String, String>>);
+    env.insert("PATH".into(), "/spfs/opt/tool/bin:/usr/bin".into());
+    // the plugins script has a higher priority, so the tool appends to its value
+    env.insert(
+        "TOOL_PLUGINS".into(),
+        "/spfs/opt/plugins:/home/me/plugins:/spfs/opt/tool/plugins".into(),
+    );
+    env
+}
+
+#[rstest]
+fn test_check_variables_applied() {
+    let solution = make_solution();
+    let env = applied_environment(&solution);
+    assert_eq!(check_variables(&solution, &env), Vec::new());
+}
+
+#[rstest]
+fn test_check_variables_stale() {
+    let solution = make_solution();
+    let mut env = applied_environment(&solution);
+    env.remove("SPK_PKG_plugins");
+    env.insert("SPK_PKG_removed".into(), "removed/1.0.0/3I42H3S6".into());
+
+    let problems = check_variables(&solution, &env);
+    assert_eq!(
+        problems,
+        vec![
+            EnvProblem::Variable {
+                name: "SPK_PKG_plugins".into(),
+                expected: Some("plugins/1.0.0/3I42H3S6".into()),
+                actual: None,
+            },
+            EnvProblem::Variable {
+                name: "SPK_PKG_removed".into(),
+                expected: None,
+                actual: Some("removed/1.0.0/3I42H3S6".into()),
+            },
+        ]
+    );
+    assert!(problems.iter().all(|problem| !problem.is_repairable()));
+}
+
+#[rstest]
+fn test_check_variables_missing_value() {
+    let solution = make_solution();
+    let mut env = applied_environment(&solution);
+    env.insert("PATH".into(), "/usr/bin".into());
+
+    let problems = check_variables(&solution, &env);
+    assert_eq!(
+        problems.len(),
+        1,
+        "only the missing value is reported: {problems:?}"
+    );
+    assert_eq!(
+        problems[0].to_string(),
+        "tool/1.0.0/3I42H3S6: $PATH does not contain '/spfs/opt/tool/bin'"
+    );
+}
+
+#[rstest]
+fn test_check_startup_scripts() {
+    let solution = make_solution();
+    let problems = check_startup_scripts(&solution, Path::new("/does/not/exist"));
+    let paths: Vec<_> = problems
+        .iter()
+        .map(|problem| match problem {
+            EnvProblem::MissingStartupScript { path, .. } => path.clone(),
+            problem => panic!("expected a missing startup script, got: {problem:?}"),
+        })
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            Path::new("/does/not/exist/etc/spfs/startup.d/spk_tool.sh"),
+            Path::new("/does/not/exist/etc/spfs/startup.d/99_spk_plugins.sh"),
+        ]
+    );
+}
@@ -0,0 +1,294 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use clap::Args;
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Serialize;
+use spfs::tracking::{ChangeKind, ChangeSet};
+use spk_cli_common::{current_env, flags, Reporter};
+use spk_exec::solution_to_resolved_runtime_layers;
+use spk_schema::ident::Request;
+use spk_schema::{BuildIdent, Package};
+use spk_solve::Solution;
+
+#[cfg(test)]
+#[path = "./cmd_env_diff_test.rs"]
+mod cmd_env_diff_test;
+
+/// Compare the packages, and optionally the files, of two environments
+///
+/// Each environment is either a lockfile as written by 'spk env --freeze',
+/// or a quoted list of requests separated by spaces (eg: "python/3 gcc/9")
+/// which is resolved to find the packages that it would contain. Use
+/// 'spk --output json' to get the differences as json.
+#[derive(Args)]
+pub struct EnvDiff {
+    #[clap(flatten)]
+    pub solver: flags::Solver,
+    #[clap(flatten)]
+    pub options: flags::Options,
+    #[clap(flatten)]
+    pub requests: flags::Requests,
+
+    #[clap(flatten)]
+    pub formatter_settings: flags::DecisionFormatterSettings,
+
+    /// Also compare the files of the two environments
+    #[clap(long)]
+    pub files: bool,
+
+    /// The environment to use as the base of the comparison
+    #[clap(value_name = "FROM")]
+    pub from: String,
+
+    /// The environment to compare against the base
+    ///
+    /// Defaults to the packages in the current environment.
+    #[clap(value_name = "TO")]
+    pub to: Option<String>,
+}
+
+impl EnvDiff {
+    pub async fn run(&self, verbosity: u8) -> Result<i32> {
+        let from = self.resolve(&self.from, verbosity).await?;
+        let to = match &self.to {
+            Some(to) => self.resolve(to, verbosity).await?,
+            None => current_env().await?,
+        };
+
+        let packages = diff_packages(&from, &to);
+        let files = if self.files {
+            let from_layers = solution_to_resolved_runtime_layers(&from)?;
+            let to_layers = solution_to_resolved_runtime_layers(&to)?;
+            let (from_manifest, to_manifest) =
+                tokio::try_join!(from_layers.merged_manifest(), to_layers.merged_manifest())?;
+            Some(spfs::tracking::compute_changeset(
+                &from_manifest,
+                &to_manifest,
+            ))
+        } else {
+            None
+        };
+
+        let report = EnvDiffReport {
+            packages: &packages,
+            files: files.as_ref().map(FileChange::from_changeset),
+        };
+        Reporter::current().report(&report, || {
+            let no_file_changes = files.as_ref().map(ChangeSet::is_empty).unwrap_or(true);
+            if packages.is_empty() && no_file_changes {
+                tracing::info!("no changes");
+                return Ok(());
+            }
+            for change in packages.iter() {
+                println!("{change}");
+            }
+            if let Some(files) = files.as_ref().filter(|files| !files.is_empty()) {
+                println!("{}", spfs::io::format_changeset(files));
+            }
+            Ok(())
+        })?;
+        Ok(0)
+    }
+
+    async fn resolve(&self, env: &str, verbosity: u8) -> Result<Solution> {
+        resolve_environment(
+            env,
+            &self.solver,
+            &self.options,
+            &self.requests,
+            &self.formatter_settings,
+            verbosity,
+        )
+        .await
+    }
+}
+
+/// Find the packages of an environment from either a lockfile or a
+/// list of requests separated by spaces.
+pub(crate) async fn resolve_environment(
+    env: &str,
+    solver: &flags::Solver,
+    options: &flags::Options,
+    requests: &flags::Requests,
+    formatter_settings: &flags::DecisionFormatterSettings,
+    verbosity: u8,
+) -> Result<Solution> {
+    let mut solver = solver.get_solver(options).await?;
+    let path = Path::new(env);
+    let requests = if path.is_file() {
+        read_lockfile(path)?
+    } else {
+        requests
+            .parse_requests(env.split_whitespace(), options, solver.repositories())
+            .await?
+    };
+    for request in requests {
+        solver.add_request(request)
+    }
+
+    let formatter = formatter_settings.get_formatter(verbosity)?;
+    let (solution, _) = formatter
+        .run_and_print_resolve(&solver)
+        .await
+        .wrap_err_with(|| format!("Failed to resolve environment: {env}"))?;
+    Ok(solution)
+}
+
+/// Read the pinned requests from a lockfile, as written by 'spk env --freeze'
+fn read_lockfile(path: &Path) -> Result<Vec<Request>> {
+    let file = std::fs::File::open(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to open lockfile: {}", path.display()))?;
+    serde_yaml::from_reader(file)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to read lockfile: {}", path.display()))
+}
+
+/// How a package differs between two environments
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageChangeKind {
+    Added,
+    Removed,
+    Upgraded,
+    Downgraded,
+    /// The same version with a different build
+    Rebuilt,
+}
+
+impl std::fmt::Display for PackageChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Upgraded => "upgraded",
+            Self::Downgraded => "downgraded",
+            Self::Rebuilt => "rebuilt",
+        })
+    }
+}
+
+/// A single package that differs between two environments
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PackageChange {
+    pub kind: PackageChangeKind,
+    pub name: String,
+    /// The package build in the base environment, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<BuildIdent>,
+    /// The package build in the compared environment, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<BuildIdent>,
+}
+
+impl std::fmt::Display for PackageChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.from, &self.to) {
+            (Some(from), Some(to)) => write!(
+                f,
+                "{:<10} {from} -> {}/{}",
+                self.kind,
+                to.version(),
+                to.build()
+            ),
+            (Some(pkg), None) | (None, Some(pkg)) => write!(f, "{:<10} {pkg}", self.kind),
+            (None, None) => write!(f, "{:<10} {}", self.kind, self.name),
+        }
+    }
+}
+
+/// Compare the packages of two solutions by name.
+///
+/// The returned changes are sorted by package name, and packages
+/// with the same build in both solutions are not included.
+pub fn diff_packages(from: &Solution, to: &Solution) -> Vec<PackageChange> {
+    let by_name = |solution: &Solution| {
+        solution
+            .items()
+            .map(|item| {
+                let ident = item.spec.ident().clone();
+                (ident.name().to_string(), ident)
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    let from = by_name(from);
+    let mut to = by_name(to);
+
+    let mut changes = Vec::new();
+    for (name, before) in from {
+        let Some(after) = to.remove(&name) else {
+            changes.push(PackageChange {
+                kind: PackageChangeKind::Removed,
+                name,
+                from: Some(before),
+                to: None,
+            });
+            continue;
+        };
+        let kind = match after.version().cmp(before.version()) {
+            Ordering::Greater => PackageChangeKind::Upgraded,
+            Ordering::Less => PackageChangeKind::Downgraded,
+            Ordering::Equal if after.build() != before.build() => PackageChangeKind::Rebuilt,
+            Ordering::Equal => continue,
+        };
+        changes.push(PackageChange {
+            kind,
+            name,
+            from: Some(before),
+            to: Some(after),
+        });
+    }
+    changes.extend(to.into_iter().map(|(name, after)| PackageChange {
+        kind: PackageChangeKind::Added,
+        name,
+        from: None,
+        to: Some(after),
+    }));
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+/// The full set of differences between two environments
+#[derive(Serialize)]
+struct EnvDiffReport<'a> {
+    packages: &'a [PackageChange],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<FileChange>>,
+}
+
+/// A single file that differs between two environments
+#[derive(Serialize)]
+struct FileChange {
+    change: &'static str,
+    path: String,
+    /// The previous path of a renamed file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<String>,
+}
+
+impl FileChange {
+    fn from_changeset(changes: &ChangeSet) -> Vec<Self> {
+        changes
+            .iter()
+            .map(|change| {
+                let (kind, from) = match &change.kind {
+                    ChangeKind::Added(_) => ("added", None),
+                    ChangeKind::Removed(_) => ("removed", None),
+                    ChangeKind::Modified(..) => ("modified", None),
+                    ChangeKind::Renamed { from, .. } => ("renamed", Some(from.to_string())),
+                };
+                Self {
+                    change: kind,
+                    path: change.path.to_string(),
+                    from,
+                }
+            })
+            .collect()
+    }
+}
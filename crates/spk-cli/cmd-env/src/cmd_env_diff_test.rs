@@ -0,0 +1,56 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_solve::solution::fixtures::solution_from_specs;
+use spk_solve::solution::Solution;
+use spk_solve::spec;
+
+use super::{diff_packages, PackageChangeKind};
+
+fn make_solution(packages: &[&str]) -> Solution {
+    solution_from_specs(packages.iter().map(|pkg| spec!({ "pkg": pkg })))
+}
+
+#[rstest]
+fn test_diff_packages() {
+    let from = make_solution(&[
+        "same/1.0.0/3I42H3S6",
+        "gone/1.0.0/3I42H3S6",
+        "newer/1.0.0/3I42H3S6",
+        "older/2.0.0/3I42H3S6",
+        "rebuilt/1.0.0/3I42H3S6",
+    ]);
+    let to = make_solution(&[
+        "same/1.0.0/3I42H3S6",
+        "added/1.0.0/3I42H3S6",
+        "newer/1.1.0/3I42H3S6",
+        "older/1.9.0/3I42H3S6",
+        "rebuilt/1.0.0/7CI5R7Y4",
+    ]);
+
+    let changes = diff_packages(&from, &to);
+    let actual: Vec<_> = changes
+        .iter()
+        .map(|change| (change.name.as_str(), change.kind))
+        .collect();
+    assert_eq!(
+        actual,
+        vec![
+            ("added", PackageChangeKind::Added),
+            ("gone", PackageChangeKind::Removed),
+            ("newer", PackageChangeKind::Upgraded),
+            ("older", PackageChangeKind::Downgraded),
+            ("rebuilt", PackageChangeKind::Rebuilt),
+        ]
+    );
+    assert_eq!(
+        changes[2].to_string(),
+        "upgraded   newer/1.0.0/3I42H3S6 -> 1.1.0/3I42H3S6"
+    );
+    assert!(
+        diff_packages(&from, &from).is_empty(),
+        "an environment should not differ from itself"
+    );
+}
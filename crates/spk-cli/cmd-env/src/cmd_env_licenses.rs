@@ -0,0 +1,476 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Args, ValueEnum};
+use miette::{IntoDiagnostic, Result};
+use serde::Serialize;
+use serde_json::json;
+use spk_cli_common::{current_env, flags, Reporter};
+use spk_schema::{BuildIdent, Package};
+use spk_solve::Solution;
+
+use crate::cmd_env_diff::resolve_environment;
+
+#[cfg(test)]
+#[path = "./cmd_env_licenses_test.rs"]
+mod cmd_env_licenses_test;
+
+/// Report the licenses of the packages in an environment
+///
+/// The license of each package is read from its metadata and checked
+/// against the license policy of the spk configuration. The environment
+/// is either a lockfile as written by 'spk env --freeze', or a quoted list
+/// of requests separated by spaces, and defaults to the current environment.
+/// Exits with 1 if any package uses a license that is not allowed. Use
+/// 'spk --output json' to get the report as json.
+#[derive(Args)]
+pub struct EnvLicenses {
+    #[clap(flatten)]
+    pub solver: flags::Solver,
+    #[clap(flatten)]
+    pub options: flags::Options,
+    #[clap(flatten)]
+    pub requests: flags::Requests,
+
+    #[clap(flatten)]
+    pub formatter_settings: flags::DecisionFormatterSettings,
+
+    /// Print a software bill of materials in this format instead of the report
+    #[clap(long, value_enum)]
+    pub sbom: Option<SbomFormat>,
+
+    /// The environment to report on
+    #[clap(value_name = "ENV")]
+    pub env: Option<String>,
+}
+
+/// The supported formats for a software bill of materials
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    /// An SPDX 2.3 json document
+    Spdx,
+    /// A CycloneDX 1.5 json document
+    Cyclonedx,
+}
+
+impl EnvLicenses {
+    pub async fn run(&self, verbosity: u8) -> Result<i32> {
+        let solution = match &self.env {
+            Some(env) => {
+                resolve_environment(
+                    env,
+                    &self.solver,
+                    &self.options,
+                    &self.requests,
+                    &self.formatter_settings,
+                    verbosity,
+                )
+                .await?
+            }
+            None => current_env().await?,
+        };
+        let config = spk_config::get_config()?;
+        let policy = LicensePolicy::from_config(&config.licenses);
+        let packages = collect_licenses(&solution, &policy);
+        let disallowed = packages.iter().filter(|p| !p.allowed).count();
+
+        if let Some(format) = self.sbom {
+            let name = self.env.as_deref().unwrap_or("current environment");
+            let document = match format {
+                SbomFormat::Spdx => spdx_document(name, &packages, Utc::now()),
+                SbomFormat::Cyclonedx => cyclonedx_document(&packages, Utc::now()),
+            };
+            let json = serde_json::to_string_pretty(&document).into_diagnostic()?;
+            println!("{json}");
+        } else {
+            Reporter::current().report(&packages, || {
+                let width = packages
+                    .iter()
+                    .map(|p| p.pkg.to_string().len())
+                    .max()
+                    .unwrap_or_default();
+                for package in packages.iter() {
+                    let license = package.license.as_deref().unwrap_or("(none)");
+                    let status = if package.allowed { "" } else { " DISALLOWED" };
+                    println!("{:<width$}  {license}{status}", package.pkg.to_string());
+                }
+                Ok(())
+            })?;
+        }
+
+        if disallowed > 0 {
+            tracing::warn!("{disallowed} package(s) use a license that is not allowed");
+            return Ok(1);
+        }
+        Ok(0)
+    }
+}
+
+/// A parsed SPDX license expression, eg: `MIT OR (Apache-2.0 AND BSD-3-Clause)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LicenseExpression {
+    /// A single license, optionally with an exception to its terms
+    License {
+        id: String,
+        exception: Option<String>,
+    },
+    /// All of these licenses apply
+    And(Vec<LicenseExpression>),
+    /// Any one of these licenses can be chosen
+    Or(Vec<LicenseExpression>),
+}
+
+impl LicenseExpression {
+    /// Parse the license from the metadata of a package.
+    ///
+    /// Text that is not a valid expression, such as a free-form
+    /// description of a license, is treated as a single license.
+    pub fn parse(text: &str) -> Self {
+        let tokens = tokenize(text);
+        let mut parser = ExpressionParser {
+            tokens: &tokens,
+            pos: 0,
+        };
+        match parser.or() {
+            Some(expr) if parser.pos == tokens.len() => expr,
+            _ => Self::License {
+                id: text.trim().to_string(),
+                exception: None,
+            },
+        }
+    }
+
+    /// The identifiers of every license in this expression
+    pub fn ids(&self) -> Vec<&str> {
+        match self {
+            Self::License { id, .. } => vec![id.as_str()],
+            Self::And(items) | Self::Or(items) => items.iter().flat_map(Self::ids).collect(),
+        }
+    }
+
+    /// True if this is just a single license identifier
+    pub fn is_single(&self) -> bool {
+        matches!(
+            self,
+            Self::License {
+                exception: None,
+                ..
+            }
+        )
+    }
+
+    /// True if every identifier in this expression could
+    /// be an SPDX identifier, rather than free-form text
+    pub fn is_spdx(&self) -> bool {
+        let valid = |id: &str| {
+            !id.is_empty()
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '+' | ':'))
+        };
+        match self {
+            Self::License { id, exception } => {
+                valid(id) && exception.as_deref().map(valid).unwrap_or(true)
+            }
+            Self::And(items) | Self::Or(items) => items.iter().all(Self::is_spdx),
+        }
+    }
+
+    /// True if the licenses of this expression can be used under the policy.
+    ///
+    /// Where there is a choice of licenses, only one of them needs to be allowed.
+    pub fn is_allowed(&self, policy: &LicensePolicy) -> bool {
+        match self {
+            Self::License { id, .. } => policy.is_allowed(id),
+            Self::And(items) => items.iter().all(|item| item.is_allowed(policy)),
+            Self::Or(items) => items.iter().any(|item| item.is_allowed(policy)),
+        }
+    }
+}
+
+impl std::fmt::Display for LicenseExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (items, operator) = match self {
+            Self::License { id, exception } => {
+                f.write_str(id)?;
+                if let Some(exception) = exception {
+                    write!(f, " WITH {exception}")?;
+                }
+                return Ok(());
+            }
+            Self::And(items) => (items, " AND "),
+            Self::Or(items) => (items, " OR "),
+        };
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                f.write_str(operator)?;
+            }
+            match item {
+                Self::License { .. } => write!(f, "{item}")?,
+                _ => write!(f, "({item})")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split an expression into identifiers, operators and parentheses
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        let mut rest = word;
+        while !rest.is_empty() {
+            let end = match rest.find(['(', ')']) {
+                Some(0) => 1,
+                Some(index) => index,
+                None => rest.len(),
+            };
+            tokens.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+fn is_operator(token: &str) -> bool {
+    ["AND", "OR", "WITH", "(", ")"]
+        .iter()
+        .any(|op| token.eq_ignore_ascii_case(op))
+}
+
+/// A recursive descent parser for license expressions, where
+/// AND binds more tightly than OR
+struct ExpressionParser<'a> {
+    tokens: &'a [&'a str],
+    pos: usize,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn peek_is(&self, operator: &str) -> bool {
+        self.tokens
+            .get(self.pos)
+            .is_some_and(|token| token.eq_ignore_ascii_case(operator))
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.tokens.get(self.pos).copied()?;
+        self.pos += 1;
+        Some(token)
+    }
+
+    fn or(&mut self) -> Option<LicenseExpression> {
+        self.list("OR", Self::and, LicenseExpression::Or)
+    }
+
+    fn and(&mut self) -> Option<LicenseExpression> {
+        self.list("AND", Self::license, LicenseExpression::And)
+    }
+
+    fn list(
+        &mut self,
+        operator: &str,
+        item: fn(&mut Self) -> Option<LicenseExpression>,
+        combine: fn(Vec<LicenseExpression>) -> LicenseExpression,
+    ) -> Option<LicenseExpression> {
+        let mut items = vec![item(self)?];
+        while self.peek_is(operator) {
+            self.pos += 1;
+            items.push(item(self)?);
+        }
+        if items.len() == 1 {
+            return items.pop();
+        }
+        Some(combine(items))
+    }
+
+    fn license(&mut self) -> Option<LicenseExpression> {
+        let token = self.next()?;
+        if token == "(" {
+            let expr = self.or()?;
+            return (self.next()? == ")").then_some(expr);
+        }
+        if is_operator(token) {
+            return None;
+        }
+        let mut exception = None;
+        if self.peek_is("WITH") {
+            self.pos += 1;
+            let token = self.next().filter(|token| !is_operator(token))?;
+            exception = Some(token.to_string());
+        }
+        Some(LicenseExpression::License {
+            id: token.to_string(),
+            exception,
+        })
+    }
+}
+
+/// The licenses that packages may use, from the spk configuration
+#[derive(Clone, Debug, Default)]
+pub struct LicensePolicy {
+    /// If not empty, the only licenses that may be used
+    pub allowed: Vec<String>,
+    /// Licenses that may not be used
+    pub disallowed: Vec<String>,
+}
+
+impl LicensePolicy {
+    pub fn from_config(config: &spk_config::Licenses) -> Self {
+        Self {
+            allowed: config.allowed().into_iter().map(String::from).collect(),
+            disallowed: config.disallowed().into_iter().map(String::from).collect(),
+        }
+    }
+
+    /// True if the license identifier may be used, ignoring case
+    pub fn is_allowed(&self, id: &str) -> bool {
+        let matches = |other: &String| other.eq_ignore_ascii_case(id);
+        !self.disallowed.iter().any(matches)
+            && (self.allowed.is_empty() || self.allowed.iter().any(matches))
+    }
+
+    /// True if a package with the given license may be used.
+    ///
+    /// Packages without a license are only allowed when
+    /// the policy does not limit the allowed licenses.
+    pub fn check(&self, license: Option<&LicenseExpression>) -> bool {
+        match license {
+            Some(license) => license.is_allowed(self),
+            None => self.allowed.is_empty(),
+        }
+    }
+}
+
+/// The license of a single package in an environment
+#[derive(Clone, Debug, Serialize)]
+pub struct PackageLicense {
+    pub pkg: BuildIdent,
+    /// The license of the package, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// False if the license is not allowed by the license policy
+    pub allowed: bool,
+    #[serde(skip)]
+    pub expression: Option<LicenseExpression>,
+}
+
+/// Collect and check the license of every package in a solution,
+/// sorted by package name.
+pub fn collect_licenses(solution: &Solution, policy: &LicensePolicy) -> Vec<PackageLicense> {
+    let mut packages: Vec<_> = solution
+        .items()
+        .map(|item| {
+            let meta = item.spec.metadata();
+            let expression = meta
+                .license
+                .as_deref()
+                .filter(|license| !license.trim().is_empty())
+                .map(LicenseExpression::parse);
+            PackageLicense {
+                pkg: item.spec.ident().clone(),
+                license: expression.as_ref().map(ToString::to_string),
+                homepage: meta.homepage.clone(),
+                allowed: policy.check(expression.as_ref()),
+                expression,
+            }
+        })
+        .collect();
+    packages.sort_by(|a, b| a.pkg.name().cmp(b.pkg.name()));
+    packages
+}
+
+/// Create an SPDX 2.3 document that lists the given packages
+pub fn spdx_document(
+    name: &str,
+    packages: &[PackageLicense],
+    created: DateTime<Utc>,
+) -> serde_json::Value {
+    let created = created.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let packages: Vec<_> = packages
+        .iter()
+        .map(|package| {
+            let mut entry = json!({
+                "name": package.pkg.name().as_str(),
+                "SPDXID": format!("SPDXRef-Package-{}", package.pkg.name()),
+                "versionInfo": package.pkg.version().to_string(),
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": "NOASSERTION",
+                "comment": package.pkg.to_string(),
+            });
+            match &package.expression {
+                Some(expr) if expr.is_spdx() => {
+                    entry["licenseDeclared"] = json!(expr.to_string());
+                }
+                Some(expr) => {
+                    entry["licenseComments"] = json!(expr.to_string());
+                }
+                None => {}
+            }
+            if let Some(homepage) = &package.homepage {
+                entry["homepage"] = json!(homepage);
+            }
+            entry
+        })
+        .collect();
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("urn:spk:env:{created}"),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: spk-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+    })
+}
+
+/// Create a CycloneDX 1.5 document that lists the given packages
+pub fn cyclonedx_document(
+    packages: &[PackageLicense],
+    created: DateTime<Utc>,
+) -> serde_json::Value {
+    let components: Vec<_> = packages
+        .iter()
+        .map(|package| {
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": package.pkg.to_string(),
+                "name": package.pkg.name().as_str(),
+                "version": package.pkg.version().to_string(),
+            });
+            let license = match &package.expression {
+                Some(expr) if expr.is_single() && expr.is_spdx() => {
+                    Some(json!({"license": {"id": expr.to_string()}}))
+                }
+                Some(expr) if expr.is_spdx() => Some(json!({"expression": expr.to_string()})),
+                Some(expr) => Some(json!({"license": {"name": expr.to_string()}})),
+                None => None,
+            };
+            if let Some(license) = license {
+                component["licenses"] = json!([license]);
+            }
+            if let Some(homepage) = &package.homepage {
+                component["externalReferences"] = json!([{"type": "website", "url": homepage}]);
+            }
+            component
+        })
+        .collect();
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": created.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "tools": [{"name": "spk", "version": env!("CARGO_PKG_VERSION")}],
+        },
+        "components": components,
+    })
+}
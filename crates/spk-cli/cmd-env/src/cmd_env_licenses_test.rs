@@ -0,0 +1,130 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use chrono::{TimeZone, Utc};
+use rstest::rstest;
+use spk_solve::solution::fixtures::solution_from_specs;
+use spk_solve::solution::Solution;
+use spk_solve::spec;
+
+use super::{
+    collect_licenses,
+    cyclonedx_document,
+    spdx_document,
+    LicenseExpression,
+    LicensePolicy,
+};
+
+fn policy(allowed: &[&str], disallowed: &[&str]) -> LicensePolicy {
+    LicensePolicy {
+        allowed: allowed.iter().map(|id| id.to_string()).collect(),
+        disallowed: disallowed.iter().map(|id| id.to_string()).collect(),
+    }
+}
+
+#[rstest]
+#[case::single("MIT", "MIT", vec!["MIT"])]
+#[case::precedence(
+    "MIT OR Apache-2.0 AND BSD-3-Clause",
+    "MIT OR (Apache-2.0 AND BSD-3-Clause)",
+    vec!["MIT", "Apache-2.0", "BSD-3-Clause"]
+)]
+#[case::parentheses(
+    "(MIT OR Apache-2.0)AND Zlib",
+    "(MIT OR Apache-2.0) AND Zlib",
+    vec!["MIT", "Apache-2.0", "Zlib"]
+)]
+#[case::exception(
+    "GPL-2.0-only WITH Classpath-exception-2.0",
+    "GPL-2.0-only WITH Classpath-exception-2.0",
+    vec!["GPL-2.0-only"]
+)]
+#[case::free_form("Apache 2.0", "Apache 2.0", vec!["Apache 2.0"])]
+#[case::unbalanced("(MIT OR Zlib", "(MIT OR Zlib", vec!["(MIT OR Zlib"])]
+fn test_license_expression_parse(
+    #[case] text: &str,
+    #[case] expected: &str,
+    #[case] ids: Vec<&str>,
+) {
+    let expr = LicenseExpression::parse(text);
+    assert_eq!(expr.to_string(), expected);
+    assert_eq!(expr.ids(), ids);
+}
+
+#[rstest]
+#[case::no_policy(policy(&[], &[]), "GPL-3.0-only", true)]
+#[case::disallowed(policy(&[], &["GPL-3.0-only"]), "gpl-3.0-only", false)]
+#[case::disallowed_choice(policy(&[], &["GPL-3.0-only"]), "MIT OR GPL-3.0-only", true)]
+#[case::disallowed_combined(policy(&[], &["GPL-3.0-only"]), "MIT AND GPL-3.0-only", false)]
+#[case::allowed(policy(&["MIT"], &[]), "MIT", true)]
+#[case::not_allowed(policy(&["MIT"], &[]), "Zlib", false)]
+fn test_license_policy(
+    #[case] policy: LicensePolicy,
+    #[case] license: &str,
+    #[case] expected: bool,
+) {
+    let expr = LicenseExpression::parse(license);
+    assert_eq!(policy.check(Some(&expr)), expected);
+}
+
+#[rstest]
+fn test_license_policy_missing_license() {
+    assert!(policy(&[], &["GPL-3.0-only"]).check(None));
+    assert!(
+        !policy(&["MIT"], &[]).check(None),
+        "a package without a license cannot use an allowed license"
+    );
+}
+
+fn make_solution() -> Solution {
+    solution_from_specs([
+        spec!({"pkg": "zlib/1.3.0/3I42H3S6", "meta": {"license": "Zlib"}}),
+        spec!({"pkg": "gpl-tool/1.0.0/3I42H3S6", "meta": {"license": "GPL-3.0-only"}}),
+        spec!({"pkg": "unlicensed/1.0.0/3I42H3S6"}),
+    ])
+}
+
+#[rstest]
+fn test_collect_licenses() {
+    let packages = collect_licenses(&make_solution(), &policy(&[], &["GPL-3.0-only"]));
+    let actual: Vec<_> = packages
+        .iter()
+        .map(|p| (p.pkg.name().as_str(), p.license.as_deref(), p.allowed))
+        .collect();
+    assert_eq!(
+        actual,
+        vec![
+            ("gpl-tool", Some("GPL-3.0-only"), false),
+            ("unlicensed", None, true),
+            ("zlib", Some("Zlib"), true),
+        ]
+    );
+}
+
+#[rstest]
+fn test_sbom_documents() {
+    let packages = collect_licenses(&make_solution(), &LicensePolicy::default());
+    let created = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+
+    let spdx = spdx_document("my-env", &packages, created);
+    assert_eq!(spdx["spdxVersion"], "SPDX-2.3");
+    assert_eq!(spdx["creationInfo"]["created"], "2024-01-02T03:04:05Z");
+    let declared: Vec<_> = spdx["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["licenseDeclared"].as_str().unwrap())
+        .collect();
+    assert_eq!(declared, vec!["GPL-3.0-only", "NOASSERTION", "Zlib"]);
+
+    let cyclonedx = cyclonedx_document(&packages, created);
+    assert_eq!(cyclonedx["bomFormat"], "CycloneDX");
+    let components = cyclonedx["components"].as_array().unwrap();
+    assert_eq!(components.len(), 3);
+    assert_eq!(components[2]["licenses"][0]["license"]["id"], "Zlib");
+    assert!(
+        components[1].get("licenses").is_none(),
+        "packages without a license should not list any"
+    );
+}
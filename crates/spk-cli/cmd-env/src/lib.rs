@@ -3,3 +3,6 @@
 // https://github.com/spkenv/spk
 
 pub mod cmd_env;
+pub mod cmd_env_check;
+pub mod cmd_env_diff;
+pub mod cmd_env_licenses;
@@ -4,7 +4,7 @@
 
 use clap::Args;
 use miette::Result;
-use spk_cli_common::{flags, CommandArgs, Run};
+use spk_cli_common::{flags, CommandArgs, Reporter, Run, SolutionReport};
 
 /// Show the resolve process for a set of packages.
 #[derive(Args)]
@@ -78,6 +78,16 @@ impl Run for Explain {
             solver.add_request(request)
         }
 
+        let reporter = Reporter::current();
+        if reporter.is_json() {
+            // The resolve process is logged instead so that
+            // only the solution is written to stdout
+            let formatter = self.formatter_settings.get_formatter(self.verbose)?;
+            let (solution, _) = formatter.run_and_log_resolve(&solver).await?;
+            reporter.report(&SolutionReport::from(&solution), || Ok(()))?;
+            return Ok(0);
+        }
+
         // Always show the solution packages for the solve
         let formatter = self
             .formatter_settings
@@ -9,8 +9,15 @@ use clap::Args;
 use colored::Colorize;
 use futures::TryFutureExt;
 use miette::{Context, IntoDiagnostic, Result};
-use spk_cli_common::{build_required_packages, current_env, flags, CommandArgs, Run};
-use spk_exec::setup_current_runtime;
+use spk_cli_common::{
+    build_required_packages,
+    current_env,
+    flags,
+    run_current_install_hooks,
+    CommandArgs,
+    Run,
+};
+use spk_exec::extend_current_runtime;
 use spk_schema::foundation::format::FormatIdent;
 use spk_schema::foundation::spec_ops::Named;
 use spk_schema::Package;
@@ -32,6 +39,10 @@ pub struct Install {
     #[clap(long, short)]
     yes: bool,
 
+    /// Do not run the install hooks of the packages in the environment
+    #[clap(long)]
+    no_install_hooks: bool,
+
     #[clap(flatten)]
     pub formatter_settings: flags::DecisionFormatterSettings,
 
@@ -75,6 +86,14 @@ impl Run for Install {
         let mut primary = Vec::new();
         let mut tertiary = Vec::new();
         for solved in solution.items() {
+            // packages that were already in the environment are
+            // not being installed, and their layers remain untouched
+            if env
+                .get(solved.spec.name().as_str())
+                .is_some_and(|existing| existing.spec.ident() == solved.spec.ident())
+            {
+                continue;
+            }
             if requested.contains(solved.spec.name()) {
                 primary.push(solved);
                 continue;
@@ -84,6 +103,11 @@ impl Run for Install {
             }
         }
 
+        if primary.is_empty() && tertiary.is_empty() {
+            println!("  Nothing to do, all requested packages are already installed");
+            return Ok(0);
+        }
+
         println!("  Requested:");
         for resolved in primary {
             let mut end = String::new();
@@ -122,7 +146,12 @@ impl Run for Install {
         let compiled_solution = build_required_packages(&solution)
             .await
             .wrap_err("Failed to build one or more packages from source")?;
-        setup_current_runtime(&compiled_solution).await?;
+        extend_current_runtime(&env, &compiled_solution).await?;
+        if !self.no_install_hooks {
+            // the environment has changed, so the hooks of every
+            // package are run again, not just the new ones
+            run_current_install_hooks(&compiled_solution).await?;
+        }
         Ok(0)
     }
 }
@@ -9,8 +9,11 @@ use futures::TryFutureExt;
 use itertools::Itertools;
 use miette::{bail, miette, Context, IntoDiagnostic, Report, Result};
 use spk_build::{BinaryPackageBuilder, BuildSource};
-use spk_cli_common::{flags, spk_exe, BuildArtifact, BuildResult, CommandArgs, Run};
+use spk_cli_common::{flags, spk_exe, BuildArtifact, BuildResult, CommandArgs, Reporter, Run};
 use spk_schema::foundation::format::FormatIdent;
+use spk_schema::foundation::ident_build::Build;
+use spk_schema::foundation::name::RepositoryNameBuf;
+use spk_schema::foundation::version::Compatibility;
 use spk_schema::ident::{PkgRequest, RangeIdent, RequestedBy};
 use spk_schema::option_map::HOST_OPTIONS;
 use spk_schema::prelude::*;
@@ -64,6 +67,20 @@ pub struct MakeBinary {
     #[clap(long)]
     pub here: bool,
 
+    /// Resolve the source package only from this repository
+    ///
+    /// This is useful when the source package was made and published
+    /// from another host, so that any local copy is not used instead.
+    #[clap(long, value_name = "REPO", conflicts_with = "here")]
+    pub source_repo: Option<RepositoryNameBuf>,
+
+    /// The expected digest of the source package being built from
+    ///
+    /// The build fails if the resolved source package does not have
+    /// this digest, as printed by `spk make-source --publish-to`.
+    #[clap(long, value_name = "DIGEST", conflicts_with = "here")]
+    pub source_digest: Option<spfs::encoding::Digest>,
+
     /// Setup the build, but instead of running the build script start an interactive shell
     #[clap(long, short)]
     pub interactive: bool,
@@ -88,6 +105,11 @@ pub struct MakeBinary {
     #[clap(long)]
     pub allow_circular_dependencies: bool,
 
+    /// Skip packages that do not support the current os and arch,
+    /// instead of failing the build.
+    #[clap(long)]
+    pub skip_unsupported: bool,
+
     /// Populated with created specs to generate a summary from the caller.
     #[clap(skip)]
     pub created_builds: BuildResult,
@@ -118,7 +140,12 @@ impl Run for MakeBinary {
             );
         }
 
+        if self.source_digest.is_some() && self.packages.len() > 1 {
+            bail!("--source-digest can only be used when building a single package");
+        }
+
         let options = self.options.get_options()?;
+        let config = spk_config::get_config()?;
         #[rustfmt::skip]
         let (_runtime, local, repos) = tokio::try_join!(
             self.runtime.ensure_active_runtime(&["make-binary", "mkbinary", "mkbin", "mkb"]),
@@ -147,6 +174,23 @@ impl Run for MakeBinary {
             .await?;
             let ident = recipe.ident();
 
+            if let Compatibility::Incompatible(reason) =
+                recipe.supported_platforms().check(&options)
+            {
+                if self.skip_unsupported {
+                    tracing::warn!(
+                        "Skipping {}, it does not support this platform: {reason}",
+                        ident.format_ident()
+                    );
+                    continue;
+                }
+                bail!(
+                    help = "Use --skip-unsupported to skip packages that cannot be built here",
+                    "{} does not support this platform: {reason}",
+                    ident.format_ident()
+                );
+            }
+
             tracing::info!("saving package recipe for {}", ident.format_ident());
             local.force_publish_recipe(&recipe).await?;
 
@@ -204,7 +248,10 @@ impl Run for MakeBinary {
                 let mut builder = BinaryPackageBuilder::from_recipe((*recipe).clone());
                 builder
                     .with_repositories(repos.iter().cloned())
+                    .with_namespace_routes(config.namespaces.clone())
+                    .with_repository_priorities(config.repositories.priorities.clone())
                     .set_interactive(self.interactive)
+                    .with_script_output_to_stderr(Reporter::current().is_json())
                     .with_source_resolver(&src_formatter)
                     .with_build_resolver(&build_formatter)
                     .with_allow_circular_dependencies(self.allow_circular_dependencies);
@@ -217,6 +264,13 @@ impl Run for MakeBinary {
                 } else if let Some(PackageSpecifier::WithSourceIdent((_, ref ident))) = package {
                     // Use the source package `AnyIdent` if the caller supplied one.
                     builder.with_source(BuildSource::SourcePackage(ident.clone()));
+                } else if let Some(repo_name) = &self.source_repo {
+                    let mut source: RangeIdent = ident.to_build(Build::Source).into();
+                    source.repository_name = Some(repo_name.clone());
+                    builder.with_source(BuildSource::SourcePackage(source));
+                }
+                if let Some(digest) = self.source_digest {
+                    builder.with_source_digest(digest);
                 }
                 let out = match builder.build_and_publish(&variant, &local).await {
                     Err(err @ spk_build::Error::SpkSolverError(_))
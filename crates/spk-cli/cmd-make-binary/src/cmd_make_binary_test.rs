@@ -81,3 +81,89 @@ build:
         .await
         .expect("With override, build script should succeed.");
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_source_digest_requires_single_package() {
+    let _rt = spfs_runtime().await;
+    let digest = spfs::encoding::Digest::from(spfs::encoding::EMPTY_DIGEST).to_string();
+
+    let res = Opt::try_parse_from(["make-binary", "--here", "--source-digest", &digest]);
+    assert!(
+        res.is_err(),
+        "a source digest cannot be checked when building from the current directory"
+    );
+
+    let mut opt = Opt::try_parse_from([
+        "make-binary",
+        "--no-runtime",
+        "--disable-repo=origin",
+        "--source-digest",
+        &digest,
+        "first/1.0.0",
+        "second/1.0.0",
+    ])
+    .unwrap();
+    let res = opt.mkb.run().await;
+    assert!(
+        res.is_err(),
+        "a source digest should only be given for a single package, got {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_unsupported_platform(tmpdir: tempfile::TempDir) {
+    let _rt = spfs_runtime().await;
+
+    let filename = tmpdir.path().join("unsupported.spk.yaml");
+    {
+        let mut file = File::create(&filename).unwrap();
+        file.write_all(
+            br#"
+pkg: unsupported/1.0.0
+platforms:
+  - os: windows
+build:
+  script:
+    - exit 1
+"#,
+        )
+        .unwrap();
+    }
+
+    let filename_str = filename.as_os_str().to_str().unwrap();
+
+    let mut opt = Opt::try_parse_from([
+        "make-binary",
+        "--no-runtime",
+        "--disable-repo=origin",
+        "--here",
+        "--opt",
+        "os=linux",
+        filename_str,
+    ])
+    .unwrap();
+    let res = opt.mkb.run().await;
+    assert!(
+        res.is_err(),
+        "a package should not be built for an unsupported platform, got {res:?}"
+    );
+
+    let mut opt = Opt::try_parse_from([
+        "make-binary",
+        "--no-runtime",
+        "--disable-repo=origin",
+        "--here",
+        "--skip-unsupported",
+        "--opt",
+        "os=linux",
+        filename_str,
+    ])
+    .unwrap();
+    opt.mkb
+        .run()
+        .await
+        .expect("an unsupported package should be skipped");
+    assert!(opt.mkb.created_builds.is_empty());
+}
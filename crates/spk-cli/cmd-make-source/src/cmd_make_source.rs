@@ -10,10 +10,12 @@ use miette::{bail, Context, Result};
 use spk_build::SourcePackageBuilder;
 use spk_cli_common::{flags, BuildArtifact, BuildResult, CommandArgs, Run};
 use spk_schema::foundation::format::FormatIdent;
+use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::spec_ops::Named;
 use spk_schema::ident::LocatedBuildIdent;
+use spk_schema::ident_ops::NormalizedTagStrategy;
 use spk_schema::{Package, Recipe, SpecTemplate, Template, TemplateExt};
-use spk_storage as storage;
+use spk_storage::{self as storage, Publisher};
 
 /// Build a source package from a spec file.
 #[derive(Args)]
@@ -32,6 +34,14 @@ pub struct MakeSource {
     #[clap(name = "PKG|SPEC_FILE")]
     pub packages: Vec<String>,
 
+    /// Also publish the source packages to this repository
+    ///
+    /// The identifier and digest of each published source package are
+    /// printed so that binary packages can be built from exactly these
+    /// sources on other hosts with `spk make-binary --source-digest`.
+    #[clap(long, value_name = "REPO")]
+    pub publish_to: Option<String>,
+
     /// Populated with the created src to generate a summary from the caller.
     #[clap(skip)]
     pub created_src: BuildResult,
@@ -61,7 +71,18 @@ impl MakeSource {
             .runtime
             .ensure_active_runtime(&["make-source", "mksource", "mksrc", "mks"])
             .await?;
-        let local: storage::RepositoryHandle = storage::local_repository().await?.into();
+        let local: Arc<storage::RepositoryHandle> =
+            Arc::new(storage::local_repository().await?.into());
+        let publisher = match &self.publish_to {
+            Some(name) => {
+                let target: storage::RepositoryHandle =
+                    storage::remote_repository::<_, NormalizedTagStrategy>(name)
+                        .await?
+                        .into();
+                Some(Publisher::new(Arc::clone(&local), Arc::new(target)))
+            }
+            None => None,
+        };
         let options = self.options.get_options()?;
 
         let mut packages: Vec<_> = self.packages.iter().cloned().map(Some).collect();
@@ -96,11 +117,21 @@ impl MakeSource {
             local.force_publish_recipe(&recipe).await?;
 
             tracing::info!("collecting sources for {}", ident.format_ident());
-            let (out, _components) = SourcePackageBuilder::from_recipe(recipe)
-                .build_and_publish(root, &local)
+            let (out, components) = SourcePackageBuilder::from_recipe(recipe)
+                .build_and_publish(root, &*local)
                 .await
                 .wrap_err("Failed to collect sources")?;
             tracing::info!("created {}", out.ident().format_ident());
+
+            if let Some(publisher) = &publisher {
+                publisher
+                    .publish(out.ident().to_any())
+                    .await
+                    .wrap_err("Failed to publish source package")?;
+                if let Some(digest) = components.get(&Component::Source) {
+                    println!("{} {digest}", out.ident());
+                }
+            }
             self.created_src.push(
                 template.file_path().display().to_string(),
                 BuildArtifact::Source(out.ident().clone()),
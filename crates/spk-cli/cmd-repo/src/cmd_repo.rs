@@ -2,9 +2,14 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::BTreeMap;
+
+use chrono::Local;
 use clap::{Args, Subcommand};
-use miette::{Context, Result};
+use colored::Colorize;
+use miette::{Context, IntoDiagnostic, Result};
 use spk_cli_common::{CommandArgs, Run};
+use spk_schema::foundation::name::PkgNameBuf;
 use spk_storage as storage;
 use storage::Repository;
 
@@ -45,19 +50,176 @@ pub enum RepoCommand {
         #[clap(name = "REPO")]
         repo: String,
     },
+    /// Show the rules for who may publish and remove packages in a repository.
+    Access {
+        /// The repository to inspect (name or path or url)
+        #[clap(name = "REPO")]
+        repo: String,
+    },
+    /// Replace the rules for who may publish and remove packages in a repository.
+    ///
+    /// The rules are read from a yaml file containing a list of rules, eg:
+    ///
+    ///   - packages: [gcc, "python*"]
+    ///     actions: [publish, remove]
+    ///     users: [pipeline]
+    ///
+    /// Packages that do not match any rule can be changed by anyone. An
+    /// empty list removes all restrictions from the repository.
+    SetAccess {
+        /// The repository to update (name or path or url)
+        #[clap(name = "REPO")]
+        repo: String,
+
+        /// The yaml file to read the rules from
+        #[clap(name = "FILE")]
+        file: std::path::PathBuf,
+    },
+    /// Show who published, promoted, removed or deprecated packages in a repository.
+    ///
+    /// Every change made through spk is recorded in the audit log of
+    /// the repository along with the user and host that made it, and
+    /// shown from newest to oldest.
+    Log {
+        /// The repository to inspect (name or path or url)
+        #[clap(name = "REPO")]
+        repo: String,
+
+        /// Only show changes to this package
+        #[clap(long, short)]
+        package: Option<PkgNameBuf>,
+
+        /// Only show changes made by this user
+        #[clap(long, short)]
+        user: Option<String>,
+
+        /// Show at most this many changes
+        #[clap(long, short = 'n')]
+        limit: Option<usize>,
+
+        /// Show the number of changes made of each kind and by each user instead
+        #[clap(long)]
+        stats: bool,
+    },
+    /// Create or rebuild the search and dependency indexes of a repository.
+    ///
+    /// The search index holds the metadata of every package version so
+    /// that 'spk search --description', '--label' and '--license' do not
+    /// need to read every recipe. The dependency index holds the packages
+    /// that every build depends on, for 'spk rdepends'. Once created, the
+    /// indexes are updated as packages are published and removed.
+    Index {
+        /// The repository to index (name or path or url)
+        #[clap(name = "REPO")]
+        repo: String,
+    },
 }
 
 impl RepoCommand {
     pub async fn run(&mut self) -> Result<i32> {
         let repo = match &self {
-            Self::Upgrade { repo } => repo,
+            Self::Upgrade { repo }
+            | Self::Access { repo }
+            | Self::SetAccess { repo, .. }
+            | Self::Log { repo, .. }
+            | Self::Index { repo } => repo,
         };
         let repo = match repo.as_str() {
             "local" => storage::local_repository().await?,
             _ => storage::remote_repository(repo).await?,
         };
-        let status = repo.upgrade().await.wrap_err("Upgrade failed")?;
-        tracing::info!("{}", status);
-        Ok(1)
+        match &self {
+            Self::Upgrade { .. } => {
+                let status = repo.upgrade().await.wrap_err("Upgrade failed")?;
+                tracing::info!("{}", status);
+                Ok(1)
+            }
+            Self::Access { .. } => {
+                let access = repo.read_access_control().await?;
+                if access.is_empty() {
+                    tracing::info!("No access rules, anyone can publish and remove packages");
+                    return Ok(0);
+                }
+                let yaml = serde_yaml::to_string(&access).into_diagnostic()?;
+                print!("{yaml}");
+                Ok(0)
+            }
+            Self::Index { .. } => {
+                let index = repo
+                    .build_search_index()
+                    .await
+                    .wrap_err("Failed to build the search index")?;
+                tracing::info!("Indexed {} package versions", index.len());
+                let index = repo
+                    .build_dependency_index()
+                    .await
+                    .wrap_err("Failed to build the dependency index")?;
+                tracing::info!("Indexed the dependencies of {} builds", index.len());
+                Ok(0)
+            }
+            Self::SetAccess { file, .. } => {
+                let yaml = std::fs::read_to_string(file)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("Failed to read {}", file.display()))?;
+                let access: storage::AccessControl = serde_yaml::from_str(&yaml)
+                    .into_diagnostic()
+                    .wrap_err("Invalid access rules")?;
+                repo.write_access_control(access).await?;
+                Ok(0)
+            }
+            Self::Log {
+                package,
+                user,
+                limit,
+                stats,
+                ..
+            } => {
+                let query = storage::AuditQuery {
+                    package: package.clone(),
+                    user: user.clone(),
+                    limit: *limit,
+                };
+                let entries = repo.read_audit_log(&query).await?.into_iter();
+                if *stats {
+                    print_log_stats(entries);
+                    return Ok(0);
+                }
+                for entry in entries {
+                    let from = entry
+                        .promoted_from
+                        .map(|repo| format!(" (from {repo})"))
+                        .unwrap_or_default();
+                    println!(
+                        "{} {} {} {}{from}",
+                        entry.time.with_timezone(&Local).to_string().green(),
+                        format!("{}@{}", entry.user, entry.host).bright_blue(),
+                        entry.action.to_string().yellow(),
+                        entry.package,
+                    );
+                }
+                Ok(0)
+            }
+        }
+    }
+}
+
+/// Print the number of entries for each action and user
+fn print_log_stats(entries: impl Iterator<Item = storage::AuditEntry>) {
+    let mut total = 0;
+    let mut by_action = BTreeMap::new();
+    let mut by_user = BTreeMap::new();
+    for entry in entries {
+        total += 1;
+        *by_action.entry(entry.action).or_insert(0) += 1;
+        *by_user.entry(entry.user).or_insert(0) += 1;
+    }
+    println!("{} {total}", "changes:".bold());
+    println!("{}", "by action:".bold());
+    for (action, count) in by_action {
+        println!("  {action}: {count}");
+    }
+    println!("{}", "by user:".bold());
+    for (user, count) in by_user {
+        println!("  {user}: {count}");
     }
 }
@@ -2,20 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use clap::Args;
-use miette::{Context, Result};
+use futures::StreamExt;
+use miette::{bail, Context, IntoDiagnostic, Result};
 use spk_build::BuildSource;
 use spk_cli_common::flags::VariantBuildStatus;
-use spk_cli_common::{flags, CommandArgs, Run};
+use spk_cli_common::{flags, CommandArgs, Reporter, Run};
 use spk_schema::foundation::format::FormatOptionMap;
 use spk_schema::foundation::ident_build::Build;
 use spk_schema::foundation::option_map::{OptionMap, HOST_OPTIONS};
 use spk_schema::prelude::*;
-use spk_schema::{Recipe, Request, TestStage};
+use spk_schema::variant::Override;
+use spk_schema::{Recipe, Request, SpecRecipe, SpecVariant, TestStage};
+use spk_storage::RepositoryHandle;
 
+use crate::report::{ReportTarget, TestReport};
 use crate::test::{PackageBuildTester, PackageInstallTester, PackageSourceTester, Tester};
 
 #[cfg(test)]
@@ -33,6 +40,8 @@ pub struct CmdTest {
     pub runtime: flags::Runtime,
     #[clap(flatten)]
     pub repos: flags::Repositories,
+    #[clap(flatten)]
+    pub repo_order: flags::RepositoryOrder,
 
     #[clap(short, long, global = true, action = clap::ArgAction::Count)]
     pub verbose: u8,
@@ -57,6 +66,51 @@ pub struct CmdTest {
     /// Test only the specified variants
     #[clap(flatten)]
     pub variant: flags::Variant,
+
+    /// The number of tests to run at the same time
+    ///
+    /// When more than one, each test is run in its own spfs runtime
+    /// by a separate spk process, and all tests are run even if some
+    /// of them fail.
+    #[clap(long, short, default_value = "1")]
+    jobs: NonZeroUsize,
+
+    /// Also write the test results to a file, as FORMAT=PATH
+    ///
+    /// The only supported format is 'junit', which produces JUnit-style
+    /// xml that can be ingested by most CI systems, eg: --report junit=results.xml
+    #[clap(long, value_name = "FORMAT=PATH")]
+    report: Vec<ReportTarget>,
+}
+
+/// Set in the environment of the child processes spawned to run
+/// tests concurrently, to select the one planned test each should run.
+const SPK_TEST_UNIT: &str = "SPK_TEST_UNIT";
+
+/// A single test script to be run against one variant of a recipe.
+struct PlannedTest {
+    recipe: Arc<SpecRecipe>,
+    stage: TestStage,
+    variant: Override<SpecVariant>,
+    index: usize,
+    script: String,
+    requirements: Vec<Request>,
+    environment: BTreeMap<String, String>,
+}
+
+impl PlannedTest {
+    fn report(&self, result: &Result<()>, duration: Duration) -> TestReport {
+        TestReport {
+            package: self.recipe.ident().to_string(),
+            stage: self.stage,
+            options: self.variant.options().into_owned(),
+            index: self.index,
+            passed: result.is_ok(),
+            duration,
+            error: result.as_ref().err().map(ToString::to_string),
+            output: None,
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -74,20 +128,58 @@ impl Run for CmdTest {
             .map(|(_, r)| Arc::new(r))
             .collect::<Vec<_>>();
 
-        let source = if self.here { Some(".".into()) } else { None };
+        let planned = self.plan_tests(&options, &repos).await?;
+
+        let reporter = Reporter::current();
+        let unit = std::env::var(SPK_TEST_UNIT).ok();
+        let reports = match unit {
+            // this process was spawned by another to run one test in its own runtime
+            Some(unit) => {
+                let test = unit
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|unit| planned.get(unit))
+                    .ok_or_else(|| miette::miette!("Invalid {SPK_TEST_UNIT}: {unit}"))?;
+                return self.run_test(test, &repos).await.map(|_| 0);
+            }
+            None if self.jobs.get() > 1 && planned.len() > 1 => {
+                self.run_tests_concurrently(&planned).await?
+            }
+            None => {
+                let mut reports = Vec::with_capacity(planned.len());
+                for test in planned.iter() {
+                    let start = Instant::now();
+                    let result = self.run_test(test, &repos).await;
+                    reports.push(test.report(&result, start.elapsed()));
+                    if let Err(err) = result {
+                        self.write_reports(&reporter, &reports)?;
+                        return Err(err);
+                    }
+                }
+                reports
+            }
+        };
 
+        self.write_reports(&reporter, &reports)?;
+        let failures = reports.iter().filter(|r| !r.passed).count();
+        if failures > 0 {
+            bail!("{failures} of {} tests failed", reports.len());
+        }
+        Ok(0)
+    }
+}
+
+impl CmdTest {
+    /// Find all of the tests that were requested, in a stable order.
+    async fn plan_tests(
+        &self,
+        options: &OptionMap,
+        repos: &[Arc<RepositoryHandle>],
+    ) -> Result<Vec<PlannedTest>> {
         let opt_host_options =
             (!self.options.no_host).then(|| HOST_OPTIONS.get().unwrap_or_default());
 
-        // This includes any host options added by command line flag,
-        // or not if --nohost was used.
-        let options_reqs: Vec<Request> = self
-            .options
-            .get_var_requests()?
-            .into_iter()
-            .map(Request::Var)
-            .collect();
-
+        let mut planned = Vec::new();
         for package in &self.packages {
             let (name, stages) = match package.split_once('@') {
                 Some((name, stage)) => {
@@ -101,19 +193,19 @@ impl Run for CmdTest {
             };
 
             let (recipe, filename) =
-                flags::find_package_recipe_from_template_or_repo(Some(&name), &options, &repos)
+                flags::find_package_recipe_from_template_or_repo(Some(&name), options, repos)
                     .await?;
 
             for stage in stages {
                 tracing::info!("Testing {}@{stage}...", filename.display());
 
-                let default_variants = recipe.default_variants(&options);
+                let default_variants = recipe.default_variants(options);
                 let variants_to_test = self
                     .variant
                     .requested_variants(
                         &recipe,
                         &default_variants,
-                        &options,
+                        options,
                         opt_host_options.as_ref(),
                     )
                     .collect::<Result<Vec<_>>>()?;
@@ -140,97 +232,207 @@ impl Run for CmdTest {
                         .wrap_err("Failed to select tests for this variant")?;
                     tracing::info!(
                         variant=%variant.options().format_option_map(),
-                        "Running {} relevant tests for this variant",
+                        "Found {} relevant tests for this variant",
                         selected.len()
                     );
                     for (index, test) in selected.into_iter().enumerate() {
-                        let mut builder = self
-                            .formatter_settings
-                            .get_formatter_builder(self.verbose)?;
-                        let src_formatter = builder.with_header("Source Resolver ").build();
-                        let build_src_formatter =
-                            builder.with_header("Build Source Resolver ").build();
-                        let build_formatter = builder.with_header("Build Resolver ").build();
-                        let install_formatter =
-                            builder.with_header("Install Env Resolver ").build();
-
-                        let mut tester: Box<dyn Tester> = match stage {
-                            TestStage::Sources => {
-                                let mut tester =
-                                    PackageSourceTester::new((*recipe).clone(), test.script());
-
-                                tester
-                                    .with_options(variant.options().into_owned())
-                                    .with_repositories(repos.iter().cloned())
-                                    .with_requirements(test.additional_requirements())
-                                    .with_source(source.clone())
-                                    .watch_environment_resolve(&src_formatter);
-
-                                Box::new(tester)
-                            }
-
-                            TestStage::Build => {
-                                let mut tester =
-                                    PackageBuildTester::new((*recipe).clone(), test.script());
-
-                                tester
-                                    .with_options(variant.options().into_owned())
-                                    .with_repositories(repos.iter().cloned())
-                                    .with_requirements(
-                                        variant
-                                            .additional_requirements()
-                                            .iter()
-                                            .cloned()
-                                            .chain(test.additional_requirements()),
-                                    )
-                                    .with_source(
-                                        source.clone().map(BuildSource::LocalPath).unwrap_or_else(
-                                            || {
-                                                BuildSource::SourcePackage(
-                                                    recipe
-                                                        .ident()
-                                                        .to_any(Some(Build::Source))
-                                                        .into(),
-                                                )
-                                            },
-                                        ),
-                                    )
-                                    .with_source_resolver(&build_src_formatter)
-                                    .with_build_resolver(&build_formatter);
-
-                                Box::new(tester)
-                            }
-
-                            TestStage::Install => {
-                                let mut tester = PackageInstallTester::new(
-                                    (*recipe).clone(),
-                                    test.script(),
-                                    &variant,
-                                );
-
-                                tester
-                                    .with_options(variant.options().into_owned())
-                                    .with_repositories(repos.iter().cloned())
-                                    .with_requirements(test.additional_requirements())
-                                    .with_requirements(options_reqs.clone())
-                                    .with_source(source.clone())
-                                    .watch_environment_resolve(&install_formatter);
-
-                                Box::new(tester)
-                            }
-                        };
+                        planned.push(PlannedTest {
+                            recipe: Arc::clone(&recipe),
+                            stage,
+                            variant: variant.clone(),
+                            index,
+                            script: test.script(),
+                            requirements: test.additional_requirements(),
+                            environment: test.environment(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(planned)
+    }
 
-                        tracing::info!(
-                            variant=%variant.options().format_option_map(),
-                            "Running selected test #{index}",
-                        );
+    /// Run a single test in the current runtime.
+    async fn run_test(&self, test: &PlannedTest, repos: &[Arc<RepositoryHandle>]) -> Result<()> {
+        let config = spk_config::get_config()?;
+        let priorities = self.repo_order.get_repository_priorities()?;
+        let source = if self.here { Some(".".into()) } else { None };
+        let recipe = &test.recipe;
+        let variant = &test.variant;
+
+        // This includes any host options added by command line flag,
+        // or not if --nohost was used.
+        let options_reqs: Vec<Request> = self
+            .options
+            .get_var_requests()?
+            .into_iter()
+            .map(Request::Var)
+            .collect();
+
+        let mut builder = self
+            .formatter_settings
+            .get_formatter_builder(self.verbose)?;
+        let src_formatter = builder.with_header("Source Resolver ").build();
+        let build_src_formatter = builder.with_header("Build Source Resolver ").build();
+        let build_formatter = builder.with_header("Build Resolver ").build();
+        let install_formatter = builder.with_header("Install Env Resolver ").build();
+
+        let mut tester: Box<dyn Tester> = match test.stage {
+            TestStage::Sources => {
+                let mut tester = PackageSourceTester::new((**recipe).clone(), test.script.clone());
+
+                tester
+                    .with_options(variant.options().into_owned())
+                    .with_repositories(repos.iter().cloned())
+                    .with_namespace_routes(config.namespaces.clone())
+                    .with_repository_priorities(priorities.clone())
+                    .with_requirements(test.requirements.clone())
+                    .with_environment(test.environment.clone())
+                    .with_source(source.clone())
+                    .watch_environment_resolve(&src_formatter);
+
+                Box::new(tester)
+            }
+
+            TestStage::Build => {
+                let mut tester = PackageBuildTester::new((**recipe).clone(), test.script.clone());
+
+                tester
+                    .with_options(variant.options().into_owned())
+                    .with_repositories(repos.iter().cloned())
+                    .with_namespace_routes(config.namespaces.clone())
+                    .with_repository_priorities(priorities.clone())
+                    .with_requirements(
+                        variant
+                            .additional_requirements()
+                            .iter()
+                            .cloned()
+                            .chain(test.requirements.clone()),
+                    )
+                    .with_environment(test.environment.clone())
+                    .with_source(
+                        source
+                            .clone()
+                            .map(BuildSource::LocalPath)
+                            .unwrap_or_else(|| {
+                                BuildSource::SourcePackage(
+                                    recipe.ident().to_any(Some(Build::Source)).into(),
+                                )
+                            }),
+                    )
+                    .with_source_resolver(&build_src_formatter)
+                    .with_build_resolver(&build_formatter);
+
+                Box::new(tester)
+            }
+
+            TestStage::Install => {
+                let mut tester =
+                    PackageInstallTester::new((**recipe).clone(), test.script.clone(), variant);
+
+                tester
+                    .with_options(variant.options().into_owned())
+                    .with_repositories(repos.iter().cloned())
+                    .with_namespace_routes(config.namespaces.clone())
+                    .with_repository_priorities(priorities.clone())
+                    .with_requirements(test.requirements.clone())
+                    .with_requirements(options_reqs)
+                    .with_environment(test.environment.clone())
+                    .with_source(source.clone())
+                    .watch_environment_resolve(&install_formatter);
+
+                Box::new(tester)
+            }
+        };
 
-                        tester.test().await?
+        tracing::info!(
+            variant=%variant.options().format_option_map(),
+            "Running selected test #{}",
+            test.index,
+        );
+
+        Ok(tester.test().await?)
+    }
+
+    /// Run each of the planned tests in its own spfs runtime, using
+    /// up to `--jobs` child processes at once.
+    ///
+    /// Every test is run to completion, even if some fail, and the
+    /// results are returned in the same order as the planned tests.
+    async fn run_tests_concurrently(&self, planned: &[PlannedTest]) -> Result<Vec<TestReport>> {
+        let exe = std::env::current_exe()
+            .into_diagnostic()
+            .wrap_err("Failed to determine the current spk executable")?;
+        tracing::info!(
+            "Running {} tests, {} at a time...",
+            planned.len(),
+            self.jobs
+        );
+
+        let mut results = futures::stream::iter(planned.iter().enumerate())
+            .map(|(unit, test)| {
+                let mut cmd = tokio::process::Command::new("spfs");
+                cmd.arg("run")
+                    .arg(spfs::tracking::ENV_SPEC_EMPTY)
+                    .arg("--")
+                    .arg(&exe)
+                    .args(std::env::args_os().skip(1))
+                    .env(flags::SPK_NO_RUNTIME, "1")
+                    .env(SPK_TEST_UNIT, unit.to_string())
+                    .env("NO_COLOR", "1")
+                    .stdin(std::process::Stdio::null());
+                async move {
+                    let start = Instant::now();
+                    let output = cmd.output().await;
+                    (unit, test, output, start.elapsed())
+                }
+            })
+            .buffer_unordered(self.jobs.get())
+            .map(|(unit, test, output, duration)| {
+                let output = output
+                    .into_diagnostic()
+                    .wrap_err("Failed to spawn spk test process")?;
+                let result = match output.status.success() {
+                    true => Ok(()),
+                    false => Err(miette::miette!("Test process failed: {}", output.status)),
+                };
+                let mut report = test.report(&result, duration);
+                let mut captured = String::from_utf8_lossy(&output.stdout).into_owned();
+                captured.push_str(&String::from_utf8_lossy(&output.stderr));
+                match &result {
+                    Ok(_) => tracing::info!(
+                        "{} {} passed in {:.1}s",
+                        report.package,
+                        report.name(),
+                        duration.as_secs_f64()
+                    ),
+                    Err(_) => {
+                        tracing::error!(
+                            "{} {} failed in {:.1}s:\n{captured}",
+                            report.package,
+                            report.name(),
+                            duration.as_secs_f64()
+                        );
                     }
                 }
-            }
+                report.output = Some(captured);
+                Ok((unit, report))
+            })
+            .collect::<Vec<Result<_>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        results.sort_by_key(|(unit, _)| *unit);
+        Ok(results.into_iter().map(|(_, report)| report).collect())
+    }
+
+    /// Write the test results as json, if requested, and to any `--report` targets.
+    fn write_reports(&self, reporter: &Reporter, reports: &[TestReport]) -> Result<()> {
+        for target in self.report.iter() {
+            target.write(reports)?;
         }
-        Ok(0)
+        reporter.report(reports, || Ok(()))
     }
 }
 
@@ -111,6 +111,70 @@ tests:
         .expect("spk test should not have a solver error");
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_install_test_requirements_fixtures_and_environment(tmpdir: tempfile::TempDir) {
+    let _rt = spfs_runtime().await;
+
+    build_package!(
+        tmpdir,
+        "dep.spk.yaml",
+        br#"
+pkg: dep/1.0.0
+build:
+  script:
+    - "true"
+"#
+    );
+
+    let filename_str = build_package!(
+        tmpdir,
+        "simple.spk.yaml",
+        br#"
+pkg: simple/1.0.0
+build:
+  options:
+    - pkg: dep
+  script:
+    - "true"
+install:
+  requirements:
+    - pkg: dep
+      fromBuildEnv: x
+
+test_fixtures:
+  setup:
+    - export FROM_FIXTURE=1
+
+tests:
+  - stage: install
+    fixtures: [setup]
+    requirements:
+      - pkg: dep
+        fromBuildEnv: x.x.x
+    environment:
+      GREETING: hello
+    script:
+      - test "$FROM_FIXTURE" = 1
+      - test "$GREETING" = hello
+"#
+    );
+
+    let mut opt = TestOpt::try_parse_from([
+        "test",
+        // Don't exec a new process to move into a new runtime, this confuses
+        // coverage testing.
+        "--no-runtime",
+        "--disable-repo=origin",
+        filename_str,
+    ])
+    .unwrap();
+    opt.test
+        .run()
+        .await
+        .expect("the test requirements should be pinned to the installed dep");
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_install_test_picks_same_digest_as_build_with_new_dep_in_variant(
@@ -497,3 +561,52 @@ tests:
         .await
         .expect_err("the test run should fail, otherwise the selectors aren't working properly");
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_junit_report_records_each_test(tmpdir: tempfile::TempDir) {
+    let _rt = spfs_runtime().await;
+
+    let filename_str = build_package!(
+        tmpdir,
+        "simple.spk.yaml",
+        br#"
+pkg: simple/1.0.0
+build:
+  script:
+    - "true"
+
+tests:
+  - stage: install
+    script:
+      - "true"
+  - stage: install
+    script:
+      - "false"
+"#
+    );
+
+    let report_path = tmpdir.path().join("results.xml");
+    let report_arg = format!("--report=junit={}", report_path.display());
+    let mut opt = TestOpt::try_parse_from([
+        "test",
+        // Don't exec a new process to move into a new runtime, this confuses
+        // coverage testing.
+        "--no-runtime",
+        "--disable-repo=origin",
+        &report_arg,
+        &format!("{filename_str}@install"),
+    ])
+    .unwrap();
+    opt.test
+        .run()
+        .await
+        .expect_err("the second test should fail");
+
+    let xml = std::fs::read_to_string(&report_path).expect("report should be written");
+    assert!(
+        xml.contains(r#"<testsuite name="simple/1.0.0" tests="2" failures="1""#),
+        "expected one passing and one failing test: {xml}"
+    );
+    assert!(xml.contains(r#"name="install[1]"#), "{xml}");
+}
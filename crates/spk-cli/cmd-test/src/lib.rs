@@ -4,4 +4,5 @@
 
 pub mod cmd_test;
 
+mod report;
 mod test;
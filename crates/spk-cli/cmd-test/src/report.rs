@@ -0,0 +1,180 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::{Serialize, Serializer};
+use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::TestStage;
+
+#[cfg(test)]
+#[path = "./report_test.rs"]
+mod report_test;
+
+/// A destination for test results, given on the command line as `FORMAT=PATH`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReportTarget {
+    /// JUnit-style xml, as understood by most CI systems
+    Junit(PathBuf),
+}
+
+impl ReportTarget {
+    /// Write the given test results to this target.
+    pub fn write(&self, reports: &[TestReport]) -> Result<()> {
+        match self {
+            Self::Junit(path) => std::fs::write(path, junit_xml(reports))
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to write junit report: {}", path.display())),
+        }
+    }
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let Some((format, path)) = s.split_once('=') else {
+            return Err(format!("expected FORMAT=PATH, got '{s}'"));
+        };
+        if path.is_empty() {
+            return Err(format!(
+                "a report path is required, eg: {format}=results.xml"
+            ));
+        }
+        match format {
+            "junit" => Ok(Self::Junit(path.into())),
+            _ => Err(format!("unknown report format '{format}', expected: junit")),
+        }
+    }
+}
+
+/// The outcome of a single test
+#[derive(Debug, Serialize)]
+pub struct TestReport {
+    pub package: String,
+    pub stage: TestStage,
+    pub options: OptionMap,
+    pub index: usize,
+    pub passed: bool,
+    #[serde(serialize_with = "serialize_seconds")]
+    pub duration: Duration,
+    pub error: Option<String>,
+    /// The captured output of the test, when it was run in its own process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl TestReport {
+    /// A name for this test that is unique within its package.
+    pub fn name(&self) -> String {
+        let name = format!("{}[{}]", self.stage, self.index);
+        if self.options.is_empty() {
+            return name;
+        }
+        // the formatted option map is colored, which is not wanted here
+        let options = self
+            .options
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{name} {{{options}}}")
+    }
+}
+
+fn serialize_seconds<S>(duration: &Duration, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Render a set of test results as a JUnit xml document.
+///
+/// One test suite is generated for each package, in the order that
+/// the packages first appear in the results.
+pub fn junit_xml(reports: &[TestReport]) -> String {
+    let mut packages: Vec<&str> = Vec::new();
+    for report in reports {
+        if !packages.contains(&report.package.as_str()) {
+            packages.push(&report.package);
+        }
+    }
+
+    let failures = reports.iter().filter(|r| !r.passed).count();
+    let time: Duration = reports.iter().map(|r| r.duration).sum();
+
+    // writing to a string cannot fail
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"spk test\" tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">",
+        reports.len(),
+        time.as_secs_f64(),
+    );
+    for package in packages {
+        let cases: Vec<_> = reports.iter().filter(|r| r.package == package).collect();
+        let failures = cases.iter().filter(|r| !r.passed).count();
+        let time: Duration = cases.iter().map(|r| r.duration).sum();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">",
+            escape(package),
+            cases.len(),
+            time.as_secs_f64(),
+        );
+        for case in cases {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape(package),
+                escape(&case.name()),
+                case.duration.as_secs_f64(),
+            );
+            if case.passed && case.output.is_none() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            if !case.passed {
+                let message = case.error.as_deref().unwrap_or("test failed");
+                let _ = writeln!(xml, "      <failure message=\"{}\"/>", escape(message));
+            }
+            if let Some(output) = &case.output {
+                let _ = writeln!(xml, "      <system-out>{}</system-out>", escape(output));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape a string for use in xml text or attribute values.
+///
+/// Control characters other than whitespace are not allowed in xml
+/// at all, and are dropped.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
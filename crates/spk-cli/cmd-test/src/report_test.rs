@@ -0,0 +1,70 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use rstest::rstest;
+use spk_schema::foundation::option_map;
+use spk_schema::TestStage;
+
+use super::{junit_xml, ReportTarget, TestReport};
+
+#[rstest]
+#[case("junit=results.xml", Ok(ReportTarget::Junit("results.xml".into())))]
+#[case("junit=", Err(()))]
+#[case("results.xml", Err(()))]
+#[case("tap=results.tap", Err(()))]
+fn test_parse_report_target(#[case] value: &str, #[case] expected: Result<ReportTarget, ()>) {
+    let actual = ReportTarget::from_str(value).map_err(|_| ());
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+fn test_junit_xml_summarizes_results() {
+    let reports = vec![
+        TestReport {
+            package: "my-pkg/1.0.0".into(),
+            stage: TestStage::Build,
+            options: option_map! {"debug" => "on"},
+            index: 0,
+            passed: true,
+            duration: Duration::from_millis(1500),
+            error: None,
+            output: None,
+        },
+        TestReport {
+            package: "my-pkg/1.0.0".into(),
+            stage: TestStage::Install,
+            options: Default::default(),
+            index: 1,
+            passed: false,
+            duration: Duration::from_millis(250),
+            error: Some("exit status <1> & \"more\"".into()),
+            output: Some("line 1\nline 2".into()),
+        },
+    ];
+
+    let xml = junit_xml(&reports);
+    assert!(
+        xml.contains(r#"<testsuites name="spk test" tests="2" failures="1" time="1.750">"#),
+        "{xml}"
+    );
+    assert!(
+        xml.contains(r#"<testsuite name="my-pkg/1.0.0" tests="2" failures="1" time="1.750">"#),
+        "{xml}"
+    );
+    assert!(
+        xml.contains(r#"name="build[0] {debug=on}" time="1.500"/>"#),
+        "{xml}"
+    );
+    assert!(
+        xml.contains(r#"<failure message="exit status &lt;1&gt; &amp; &quot;more&quot;"/>"#),
+        "{xml}"
+    );
+    assert!(
+        xml.contains("<system-out>line 1&#10;line 2</system-out>"),
+        "{xml}"
+    );
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -25,8 +26,11 @@ pub struct PackageBuildTester<'a> {
     recipe: SpecRecipe,
     script: String,
     repos: Vec<Arc<storage::RepositoryHandle>>,
+    namespace_routes: spk_config::Namespaces,
+    repository_priorities: HashMap<String, i64>,
     options: OptionMap,
     additional_requirements: Vec<Request>,
+    environment: BTreeMap<String, String>,
     source: BuildSource,
     source_resolver: BoxedResolverCallback<'a>,
     build_resolver: BoxedResolverCallback<'a>,
@@ -40,8 +44,11 @@ impl<'a> PackageBuildTester<'a> {
             recipe,
             script,
             repos: Vec::new(),
+            namespace_routes: Default::default(),
+            repository_priorities: HashMap::new(),
             options: OptionMap::default(),
             additional_requirements: Vec::new(),
+            environment: BTreeMap::new(),
             source,
             source_resolver: Box::new(DefaultResolver {}),
             build_resolver: Box::new(DefaultResolver {}),
@@ -61,6 +68,23 @@ impl<'a> PackageBuildTester<'a> {
         self
     }
 
+    /// Route the packages in each namespace to specific repositories
+    /// when resolving source and build environment packages
+    pub fn with_namespace_routes(&mut self, routes: spk_config::Namespaces) -> &mut Self {
+        self.namespace_routes = routes;
+        self
+    }
+
+    /// Prefer builds from the repositories with the highest given priority
+    /// when resolving source and build environment packages
+    pub fn with_repository_priorities(
+        &mut self,
+        priorities: impl IntoIterator<Item = (String, i64)>,
+    ) -> &mut Self {
+        self.repository_priorities.extend(priorities);
+        self
+    }
+
     /// Setting the source determines whether the script runs in
     /// the root of an existing source package or a local directory.
     pub fn with_source(&mut self, source: BuildSource) -> &mut Self {
@@ -73,6 +97,15 @@ impl<'a> PackageBuildTester<'a> {
         self
     }
 
+    /// Set additional environment variables when running the test script
+    pub fn with_environment(
+        &mut self,
+        environment: impl IntoIterator<Item = (String, String)>,
+    ) -> &mut Self {
+        self.environment.extend(environment);
+        self
+    }
+
     /// Provide a function that will be called when resolving the source package.
     ///
     /// This function should run the provided solver runtime to
@@ -119,6 +152,8 @@ impl<'a> PackageBuildTester<'a> {
         let mut solver = Solver::default();
         solver.set_binary_only(true);
         solver.update_options(self.options.clone());
+        solver.set_namespace_routes(self.namespace_routes.clone());
+        solver.set_repository_priorities(self.repository_priorities.clone());
         for repo in self.repos.iter().cloned() {
             solver.add_repository(repo);
         }
@@ -140,7 +175,8 @@ impl<'a> PackageBuildTester<'a> {
             .recipe
             .generate_binary_build(&self.options, &solution)?;
 
-        let env = solution.to_environment(Some(std::env::vars()));
+        let mut env = solution.to_environment(Some(std::env::vars()));
+        env.extend(self.environment.clone());
 
         let source_dir = match &self.source {
             BuildSource::SourcePackage(source) => {
@@ -155,6 +191,8 @@ impl<'a> PackageBuildTester<'a> {
     async fn resolve_source_package(&mut self, package: &AnyIdent) -> Result<Solution> {
         let mut solver = Solver::default();
         solver.update_options(self.options.clone());
+        solver.set_namespace_routes(self.namespace_routes.clone());
+        solver.set_repository_priorities(self.repository_priorities.clone());
         let local_repo: Arc<storage::RepositoryHandle> =
             Arc::new(storage::local_repository().await?.into());
         solver.add_repository(local_repo.clone());
@@ -2,16 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use spk_cli_common::Result;
+use spk_cli_common::{run_install_hooks, Result};
 use spk_exec::resolve_runtime_layers;
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::foundation::spec_ops::Named;
 use spk_schema::ident::{PkgRequest, PreReleasePolicy, RangeIdent, Request, RequestedBy};
 use spk_schema::ident_build::Build;
-use spk_schema::{Recipe, SpecRecipe, Variant, VariantExt};
+use spk_schema::{Package, Recipe, RequirementsList, SpecRecipe, Variant, VariantExt};
 use spk_solve::{BoxedResolverCallback, DefaultResolver, ResolverCallback, Solver};
 use spk_storage as storage;
 
@@ -22,8 +24,11 @@ pub struct PackageInstallTester<'a, V> {
     recipe: SpecRecipe,
     script: String,
     repos: Vec<Arc<storage::RepositoryHandle>>,
+    namespace_routes: spk_config::Namespaces,
+    repository_priorities: HashMap<String, i64>,
     options: OptionMap,
     additional_requirements: Vec<Request>,
+    environment: BTreeMap<String, String>,
     source: Option<PathBuf>,
     env_resolver: BoxedResolverCallback<'a>,
     variant: V,
@@ -39,8 +44,11 @@ where
             recipe,
             script,
             repos: Vec::new(),
+            namespace_routes: Default::default(),
+            repository_priorities: HashMap::new(),
             options: OptionMap::default(),
             additional_requirements: Vec::new(),
+            environment: BTreeMap::new(),
             source: None,
             env_resolver: Box::new(DefaultResolver {}),
             variant,
@@ -60,6 +68,23 @@ where
         self
     }
 
+    /// Route the packages in each namespace to specific repositories
+    /// when resolving the test environment
+    pub fn with_namespace_routes(&mut self, routes: spk_config::Namespaces) -> &mut Self {
+        self.namespace_routes = routes;
+        self
+    }
+
+    /// Prefer builds from the repositories with the highest given priority
+    /// when resolving the test environment
+    pub fn with_repository_priorities(
+        &mut self,
+        priorities: impl IntoIterator<Item = (String, i64)>,
+    ) -> &mut Self {
+        self.repository_priorities.extend(priorities);
+        self
+    }
+
     /// Run the test script in the given working dir rather
     /// than inheriting the current one.
     pub fn with_source(&mut self, source: Option<PathBuf>) -> &mut Self {
@@ -72,6 +97,15 @@ where
         self
     }
 
+    /// Set additional environment variables when running the test script
+    pub fn with_environment(
+        &mut self,
+        environment: impl IntoIterator<Item = (String, String)>,
+    ) -> &mut Self {
+        self.environment.extend(environment);
+        self
+    }
+
     /// Provide a function that will be called when resolving the test environment.
     ///
     /// This function should run the provided solver runtime to
@@ -97,6 +131,8 @@ where
         let mut solver = Solver::default();
         solver.set_binary_only(true);
         solver.update_options(self.options.clone());
+        solver.set_namespace_routes(self.namespace_routes.clone());
+        solver.set_repository_priorities(self.repository_priorities.clone());
         for repo in self.repos.iter().cloned() {
             solver.add_repository(repo);
         }
@@ -118,7 +154,11 @@ where
             .with_pin(None)
             .with_compat(None);
         solver.add_request(request.into());
-        for request in self.additional_requirements.drain(..) {
+        let mut requirements = std::mem::take(&mut self.additional_requirements);
+        if requirements.iter().any(is_pinned_from_build_env) {
+            requirements = self.render_requirement_pins(&solver, requirements).await?;
+        }
+        for request in requirements {
             solver.add_request(request)
         }
 
@@ -129,8 +169,10 @@ where
         }
         rt.save_state_to_storage().await?;
         spfs::remount_runtime(&rt).await?;
+        run_install_hooks(&mut rt, &solution).await?;
 
-        let env = solution.to_environment(Some(std::env::vars()));
+        let mut env = solution.to_environment(Some(std::env::vars()));
+        env.extend(self.environment.clone());
 
         let source_dir = match &self.source {
             Some(source) => source.clone(),
@@ -139,6 +181,40 @@ where
 
         self.execute_test_script(&source_dir, env, &rt)
     }
+
+    /// Render any `fromBuildEnv` pins in the given requirements.
+    ///
+    /// The pins are rendered against the environment that is resolved
+    /// for the package being tested on its own, and against the build
+    /// options of that package, so that tests can require the same
+    /// versions of packages without adding them to its runtime requirements.
+    async fn render_requirement_pins(
+        &self,
+        solver: &Solver,
+        requirements: Vec<Request>,
+    ) -> Result<Vec<Request>> {
+        let (solution, _) = DefaultResolver {}.solve(solver).await?;
+        let resolved = solution
+            .items()
+            .map(|item| (item.spec.name(), item.spec.ident()))
+            .collect::<HashMap<_, _>>();
+        let mut options = self.options.clone();
+        if let Some(tested) = solution.get(self.recipe.name().as_str()) {
+            options.extend(tested.spec.option_values());
+        }
+        let mut requirements = RequirementsList::try_from_iter(requirements)?;
+        requirements.render_all_pins(&options, &resolved)?;
+        Ok(requirements.to_vec())
+    }
+}
+
+/// True if the request must be rendered using `fromBuildEnv` before it is solved.
+fn is_pinned_from_build_env(request: &Request) -> bool {
+    match request {
+        Request::Pkg(request) => request.pin.is_some(),
+        Request::Var(request) => request.value.is_from_build_env(),
+        Request::AnyOf(_) => false,
+    }
 }
 
 #[async_trait::async_trait]
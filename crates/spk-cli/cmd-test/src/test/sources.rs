@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -23,8 +24,11 @@ pub struct PackageSourceTester<'a> {
     recipe: SpecRecipe,
     script: String,
     repos: Vec<Arc<storage::RepositoryHandle>>,
+    namespace_routes: spk_config::Namespaces,
+    repository_priorities: HashMap<String, i64>,
     options: OptionMap,
     additional_requirements: Vec<Request>,
+    environment: BTreeMap<String, String>,
     source: Option<PathBuf>,
     env_resolver: BoxedResolverCallback<'a>,
 }
@@ -36,8 +40,11 @@ impl<'a> PackageSourceTester<'a> {
             recipe,
             script,
             repos: Vec::new(),
+            namespace_routes: Default::default(),
+            repository_priorities: HashMap::new(),
             options: OptionMap::default(),
             additional_requirements: Vec::new(),
+            environment: BTreeMap::new(),
             source: None,
             env_resolver: Box::new(DefaultResolver {}),
         }
@@ -56,6 +63,23 @@ impl<'a> PackageSourceTester<'a> {
         self
     }
 
+    /// Route the packages in each namespace to specific repositories
+    /// when resolving the test environment
+    pub fn with_namespace_routes(&mut self, routes: spk_config::Namespaces) -> &mut Self {
+        self.namespace_routes = routes;
+        self
+    }
+
+    /// Prefer builds from the repositories with the highest given priority
+    /// when resolving the test environment
+    pub fn with_repository_priorities(
+        &mut self,
+        priorities: impl IntoIterator<Item = (String, i64)>,
+    ) -> &mut Self {
+        self.repository_priorities.extend(priorities);
+        self
+    }
+
     /// Setting the source path for this test will validate this
     /// local path rather than a source package's contents.
     pub fn with_source(&mut self, source: Option<PathBuf>) -> &mut Self {
@@ -69,6 +93,15 @@ impl<'a> PackageSourceTester<'a> {
         self
     }
 
+    /// Set additional environment variables when running the test script
+    pub fn with_environment(
+        &mut self,
+        environment: impl IntoIterator<Item = (String, String)>,
+    ) -> &mut Self {
+        self.environment.extend(environment);
+        self
+    }
+
     /// Provide a function that will be called when resolving the test environment.
     ///
     /// This function should run the provided solver runtime to
@@ -95,6 +128,8 @@ impl<'a> PackageSourceTester<'a> {
         let mut solver = Solver::default();
         solver.set_binary_only(true);
         solver.update_options(self.options.clone());
+        solver.set_namespace_routes(self.namespace_routes.clone());
+        solver.set_repository_priorities(self.repository_priorities.clone());
         for repo in self.repos.iter().cloned() {
             solver.add_repository(repo);
         }
@@ -124,7 +159,8 @@ impl<'a> PackageSourceTester<'a> {
         rt.save_state_to_storage().await?;
         spfs::remount_runtime(&rt).await?;
 
-        let env = solution.to_environment(Some(std::env::vars()));
+        let mut env = solution.to_environment(Some(std::env::vars()));
+        env.extend(self.environment.clone());
 
         let source_dir = match &self.source {
             Some(source) => source.clone(),
@@ -8,7 +8,8 @@ use std::io::Write;
 use std::path::Path;
 
 use spfs::runtime::Runtime;
-use spk_cli_common::{Error, Result, TestError};
+use spk_cli_common::{Error, Reporter, Result, TestError};
+use spk_schema::ScriptInterpreter;
 
 /// Common code and logic for all test flavors.
 #[async_trait::async_trait]
@@ -38,7 +39,10 @@ pub trait Tester: Send {
             .prefix("spk-test")
             .tempdir()
             .map_err(Error::TempDirError)?;
-        let script_path = tmpdir.path().join("test.sh");
+        let interpreter = ScriptInterpreter::from_script_text(self.script());
+        let script_path = tmpdir
+            .path()
+            .join(format!("test.{}", interpreter.file_extension()));
         let mut script_file = std::fs::File::create(&script_path)
             .map_err(|err| Error::FileWriteError(script_path.to_owned(), err))?;
         script_file
@@ -47,13 +51,22 @@ pub trait Tester: Send {
         script_file
             .sync_data()
             .map_err(|err| Error::FileWriteError(script_path.to_owned(), err))?;
+        let args = interpreter
+            .args()
+            .iter()
+            .map(OsString::from)
+            .chain(std::iter::once(script_path.into_os_string()));
         let cmd = spfs::build_shell_initialized_command(
             rt,
             Some("bash"),
-            OsString::from("bash"),
-            [OsString::from("-ex"), script_path.into_os_string()],
+            OsString::from(interpreter.program()),
+            args,
         )?;
         let mut cmd = cmd.into_std();
+        if Reporter::current().is_json() {
+            // keep the script's output out of the json report
+            cmd.stdout(std::process::Stdio::from(std::io::stderr()));
+        }
         let status = cmd
             .envs(env)
             .current_dir(source_dir)
@@ -61,7 +74,7 @@ pub trait Tester: Send {
             .status()
             .map_err(|err| {
                 Error::ProcessSpawnError(spfs::Error::process_spawn_error(
-                    "bash",
+                    interpreter.program(),
                     err,
                     Some(source_dir.to_owned()),
                 ))
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use serde::ser::SerializeStruct;
 use spk_schema::foundation::format::FormatIdent;
 use spk_schema::{BuildIdent, OptionMap};
 
@@ -27,6 +28,30 @@ impl std::fmt::Display for BuildArtifact {
     }
 }
 
+impl serde::Serialize for BuildArtifact {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            BuildArtifact::Source(ident) => {
+                let mut s = serializer.serialize_struct("BuildArtifact", 2)?;
+                s.serialize_field("kind", "source")?;
+                s.serialize_field("ident", &ident.to_string())?;
+                s.end()
+            }
+            BuildArtifact::Binary(ident, variant_location, options) => {
+                let mut s = serializer.serialize_struct("BuildArtifact", 4)?;
+                s.serialize_field("kind", "binary")?;
+                s.serialize_field("ident", &ident.to_string())?;
+                s.serialize_field("variant", &variant_location.to_string())?;
+                s.serialize_field("options", options)?;
+                s.end()
+            }
+        }
+    }
+}
+
 /// The result(s) of a build operation.
 #[derive(Debug, Default)]
 pub struct BuildResult {
@@ -58,3 +83,23 @@ impl BuildResult {
         self.artifacts.push((input, output));
     }
 }
+
+impl serde::Serialize for BuildResult {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(serde::Serialize)]
+        struct Entry<'a> {
+            input: &'a str,
+            #[serde(flatten)]
+            artifact: &'a BuildArtifact,
+        }
+
+        serializer.collect_seq(
+            self.artifacts
+                .iter()
+                .map(|(input, artifact)| Entry { input, artifact }),
+        )
+    }
+}
@@ -4,6 +4,7 @@
 
 use colored::Colorize;
 use miette::Diagnostic;
+use serde::Serialize;
 use spk_schema::foundation::format::FormatError;
 use thiserror::Error;
 
@@ -61,6 +62,10 @@ pub enum Error {
 
     /// Not running under an active spk environment
     #[error("No current spfs runtime environment")]
+    #[diagnostic(
+        code(spk::cli::no_environment),
+        help("Run this command from within an 'spk env' or 'spfs run' environment")
+    )]
     NoEnvironment,
 }
 
@@ -119,9 +124,51 @@ impl FormatError for Error {
     }
 }
 
+/// The broad kind of an error, so that tooling which wraps spk can
+/// react to a failure without parsing its message.
+///
+/// The category is derived from the error's diagnostic code, which
+/// is stable across releases (see <https://spkenv.dev/error_codes>).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Invalid input, such as a malformed package name or spec file
+    User,
+    /// A problem reading from or writing to a package repository
+    Repository,
+    /// The requested environment could not be resolved
+    Solver,
+    /// A package build or test failed
+    Build,
+    /// A problem with the spfs runtime or local system
+    Environment,
+    /// Any error that has no more specific code
+    Other,
+}
+
+impl ErrorCategory {
+    /// Identify the category of an error from its diagnostic code
+    pub fn from_code(code: &str) -> Self {
+        let mut parts = code.split("::");
+        match (parts.next(), parts.next()) {
+            (Some("spk"), Some("schema" | "cli")) => Self::User,
+            (Some("spk"), Some("storage")) => Self::Repository,
+            (Some("spk"), Some("solve")) => Self::Solver,
+            (Some("spk"), Some("build" | "test")) => Self::Build,
+            (Some("spfs"), Some("storage" | "failed_to_open_repo" | "unknown_remote")) => {
+                Self::Repository
+            }
+            (Some("spfs"), Some("generic")) => Self::Other,
+            (Some("spfs"), Some(_)) => Self::Environment,
+            _ => Self::Other,
+        }
+    }
+}
+
 /// Denotes that a test has failed or was invalid.
 #[derive(Debug, Diagnostic, Error)]
 #[error("Test error: {message}")]
+#[diagnostic(code(spk::test::failed))]
 pub struct TestError {
     pub message: String,
 }
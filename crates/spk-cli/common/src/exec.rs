@@ -10,7 +10,7 @@ use spk_schema::Package;
 use spk_solve::solution::{PackageSource, Solution};
 use spk_storage as storage;
 
-use crate::Result;
+use crate::{Reporter, Result};
 
 /// Build any packages in the given solution that need building.
 ///
@@ -37,6 +37,7 @@ pub async fn build_required_packages(solution: &Solution) -> Result<Solution> {
         );
         let (package, components) = BinaryPackageBuilder::from_recipe((**recipe).clone())
             .with_repositories(repos.clone())
+            .with_script_output_to_stderr(Reporter::current().is_json())
             .build_and_publish(&options, &*local_repo)
             .await?;
         let source = PackageSource::Repository {
@@ -4,13 +4,15 @@
 
 mod variant;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use clap::{Args, ValueEnum, ValueHint};
 use miette::{bail, miette, Context, IntoDiagnostic, Result};
-use solve::{DecisionFormatter, DecisionFormatterBuilder, MultiSolverKind};
+use solve::{DecisionFormatter, DecisionFormatterBuilder, MultiSolverKind, SolveRun};
 use spfs::runtime::LiveLayerFile;
 use spk_schema::foundation::format::FormatIdent;
 use spk_schema::foundation::ident_build::Build;
@@ -20,8 +22,18 @@ use spk_schema::foundation::option_map::OptionMap;
 use spk_schema::foundation::spec_ops::Named;
 use spk_schema::foundation::version::CompatRule;
 use spk_schema::ident::{parse_ident, AnyIdent, PkgRequest, Request, RequestedBy, VarRequest};
+use spk_schema::ident_ops::NormalizedTagStrategy;
 use spk_schema::option_map::HOST_OPTIONS;
-use spk_schema::{Recipe, SpecRecipe, SpecTemplate, Template, TemplateExt, TestStage, VariantExt};
+use spk_schema::{
+    Package,
+    Recipe,
+    SpecRecipe,
+    SpecTemplate,
+    Template,
+    TemplateExt,
+    TestStage,
+    VariantExt,
+};
 #[cfg(feature = "statsd")]
 use spk_solve::{get_metrics_client, SPK_RUN_TIME_METRIC};
 pub use variant::{Variant, VariantBuildStatus, VariantLocation};
@@ -34,7 +46,7 @@ use crate::Error;
 #[path = "./flags_test.rs"]
 mod flags_test;
 
-static SPK_NO_RUNTIME: &str = "SPK_NO_RUNTIME";
+pub static SPK_NO_RUNTIME: &str = "SPK_NO_RUNTIME";
 static SPK_KEEP_RUNTIME: &str = "SPK_KEEP_RUNTIME";
 
 #[derive(Args, Clone)]
@@ -226,13 +238,49 @@ pub struct Solver {
     /// requests, build validation before a resolve, and for build keys
     #[clap(long, env = "SPK_SOLVER_CHECK_IMPOSSIBLE_ALL")]
     pub check_impossible_all: bool,
+
+    /// Stop the solve once the solver has made this many decisions
+    ///
+    /// When a solve is stopped, the problems it hit most often are
+    /// reported to help explain why no solution was found. If this
+    /// is zero, which is the default, there is no limit.
+    #[clap(long, env = "SPK_SOLVER_MAX_DECISIONS", default_value_t = 0)]
+    pub max_decisions: usize,
+
+    /// The heuristic used to order the builds of each package version
+    #[clap(long, value_enum, env = "SPK_SOLVER_BUILD_ORDER", default_value_t = BuildOrder::OptionValues)]
+    pub build_order: BuildOrder,
+
+    /// The method used to search for a solution
+    ///
+    /// The sat backend can find solutions, or prove that there are
+    /// none, for large and heavily constrained sets of packages
+    /// that the graph solver takes too long on. It does not build
+    /// packages from source, and has no decisions to show or explain.
+    #[clap(long = "solver", value_enum, env = "SPK_SOLVER_BACKEND", default_value_t = SolverBackend::Graph)]
+    pub backend: SolverBackend,
+
+    #[clap(flatten)]
+    pub repo_order: RepositoryOrder,
+
+    /// Allow deprecated builds that are installed in the current environment
+    ///
+    /// Builds that were deprecated after they were installed can still be
+    /// resolved as they are, without being requested exactly. Packages that
+    /// are not already installed continue to use builds that are not
+    /// deprecated. This has no effect outside of an spk environment.
+    #[clap(long, env = "SPK_SOLVER_ALLOW_INSTALLED_DEPRECATED")]
+    pub allow_installed_deprecated: bool,
 }
 
 impl Solver {
     pub async fn get_solver(&self, options: &Options) -> Result<solve::Solver> {
         let option_map = options.get_options()?;
+        let config = spk_config::get_config()?;
         let mut solver = solve::Solver::default();
         solver.update_options(option_map);
+        solver.set_namespace_routes(config.namespaces.clone());
+        solver.set_repository_priorities(self.repo_order.get_repository_priorities()?);
         for (name, repo) in self.repos.get_repos_for_non_destructive_operation().await? {
             tracing::debug!(repo=%name, "using repository");
             solver.add_repository(repo);
@@ -247,6 +295,20 @@ impl Solver {
         solver.set_build_key_impossible_checks(
             self.check_impossible_builds || self.check_impossible_all,
         );
+        solver.set_max_decisions(Some(self.max_decisions).filter(|max| *max > 0));
+        solver.set_heuristic(self.build_order.into());
+        solver.set_backend(self.backend.into());
+        if self.allow_installed_deprecated {
+            match crate::current_env().await {
+                // the installed specs may have been deprecated since
+                // they were installed, so all of them are allowed
+                Ok(env) => {
+                    solver.set_grandfathered_builds(env.items().map(|s| s.spec.ident().clone()))
+                }
+                Err(Error::NoEnvironment) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
 
         for r in options.get_var_requests()? {
             solver.add_request(r.into());
@@ -255,6 +317,35 @@ impl Solver {
     }
 }
 
+#[derive(Args, Clone)]
+pub struct RepositoryOrder {
+    /// Repository names, from highest to lowest priority, used to choose
+    /// which repository a build is taken from when more than one has it
+    ///
+    /// This replaces the repository priorities from the spk config.
+    /// Repositories that are not listed come after those that are.
+    #[clap(long, value_delimiter = ',', env = "SPK_SOLVER_REPO_ORDER")]
+    pub repo_order: Vec<String>,
+}
+
+impl RepositoryOrder {
+    /// The priority of each repository, taken from --repo-order
+    /// when it is given and from the spk config otherwise.
+    pub fn get_repository_priorities(&self) -> Result<HashMap<String, i64>> {
+        if self.repo_order.is_empty() {
+            let config = spk_config::get_config()?;
+            return Ok(config.repositories.priorities.clone());
+        }
+        let count = self.repo_order.len() as i64;
+        Ok(self
+            .repo_order
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), count - index as i64))
+            .collect())
+    }
+}
+
 #[derive(Args, Clone)]
 pub struct Options {
     /// Specify build/resolve options
@@ -339,6 +430,8 @@ impl Options {
 #[derive(Args, Clone)]
 pub struct Requests {
     /// Allow pre-releases for all command line package requests
+    ///
+    /// This overrides the default pre-release policy from the spk config.
     #[clap(long)]
     pub pre: bool,
 }
@@ -854,6 +947,27 @@ pub struct Repositories {
     /// feature flag enabled.
     #[clap(long, hide = true)]
     pub legacy_spk_version_tags: bool,
+
+    /// Do not reuse anything already read from a repository during the command
+    ///
+    /// Package specs, tags and listings are normally cached for as
+    /// long as the command runs. This reads them from the repository
+    /// each time instead, which is slower but sees any changes made
+    /// while the command is running.
+    #[clap(long, env = "SPK_NO_CACHE")]
+    pub no_cache: bool,
+
+    /// Only reuse what was read from a repository for this many seconds
+    ///
+    /// By default, anything read from a repository is cached for as
+    /// long as the command runs.
+    #[clap(long, env = "SPK_CACHE_TTL", value_name = "SECONDS")]
+    pub cache_ttl: Option<u64>,
+
+    /// Use the packages that were published into this spfs tag
+    /// namespace of the repositories instead (eg: users/jdoe)
+    #[clap(long = "namespace", value_name = "NAMESPACE")]
+    pub tag_namespace: Option<String>,
 }
 
 impl Repositories {
@@ -882,12 +996,14 @@ impl Repositories {
             && !disabled.contains("local")
         {
             let mut repo = storage::local_repository().await?;
+            self.configure_tag_namespace(&mut repo)?;
             if let Some(ts) = self.when.as_ref() {
                 repo.pin_at_time(ts);
             }
             if self.legacy_spk_version_tags {
                 repo.set_legacy_spk_version_tags(true);
             }
+            self.configure_caching(&mut repo);
             repos.push(("local".into(), repo.into()));
         }
         for (name, ts) in enabled.iter() {
@@ -906,12 +1022,14 @@ impl Repositories {
                 "local" => storage::local_repository().await,
                 name => storage::remote_repository(name).await,
             }?;
+            self.configure_tag_namespace(&mut repo)?;
             if let Some(ts) = ts.as_ref().or(self.when.as_ref()) {
                 repo.pin_at_time(ts);
             }
             if self.legacy_spk_version_tags {
                 repo.set_legacy_spk_version_tags(true);
             }
+            self.configure_caching(&mut repo);
             repos.push((name.to_string(), repo.into()));
         }
         Ok(repos.into_iter().collect())
@@ -946,12 +1064,14 @@ impl Repositories {
             && !disabled.contains("local")
         {
             let mut repo = storage::local_repository().await?;
+            self.configure_tag_namespace(&mut repo)?;
             if let Some(ts) = self.when.as_ref() {
                 repo.pin_at_time(ts);
             }
             if self.legacy_spk_version_tags {
                 repo.set_legacy_spk_version_tags(true);
             }
+            self.configure_caching(&mut repo);
             repos.push(("local".into(), repo.into()));
         }
         if self.local_repo_only {
@@ -990,16 +1110,84 @@ impl Repositories {
                     other => other,
                 },
             }?;
+            self.configure_tag_namespace(&mut repo)?;
             if let Some(ts) = ts.as_ref().or(self.when.as_ref()) {
                 repo.pin_at_time(ts);
             }
             if self.legacy_spk_version_tags {
                 repo.set_legacy_spk_version_tags(true);
             }
+            self.configure_caching(&mut repo);
             repos.push((name.into(), repo.into()));
         }
         Ok(repos)
     }
+
+    /// Move a repository that is being enabled into the tag namespace, if any
+    fn configure_tag_namespace(
+        &self,
+        repo: &mut storage::SpfsRepository<NormalizedTagStrategy>,
+    ) -> Result<()> {
+        if let Some(namespace) = &self.tag_namespace {
+            repo.set_tag_namespace(Some(spfs::storage::TagNamespaceBuf::new(namespace)))?;
+        }
+        Ok(())
+    }
+
+    /// Apply the caching flags to a repository that is being enabled
+    fn configure_caching(&self, repo: &mut storage::SpfsRepository<NormalizedTagStrategy>) {
+        use storage::Repository;
+        if self.no_cache {
+            repo.set_cache_policy(storage::CachePolicy::BypassCache);
+        }
+        repo.set_cache_ttl(self.cache_ttl.map(Duration::from_secs));
+    }
+
+    /// Get a single handle that reads through all of the repositories
+    /// that [`Self::get_repos_for_non_destructive_operation`] would return,
+    /// in the same order.
+    pub async fn get_chained_repo_for_non_destructive_operation(
+        &self,
+    ) -> Result<storage::RepositoryHandle> {
+        let repos = self.get_repos_for_non_destructive_operation().await?;
+        Ok(storage::RepositoryHandle::new_chained(
+            repos.into_iter().map(|(_, repo)| repo).collect(),
+        ))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum BuildOrder {
+    /// Prefer builds based on the values of their build options
+    OptionValues,
+    /// Try builds in the order that the repositories list them
+    RepositoryOrder,
+}
+
+impl From<BuildOrder> for solve::BuildOrder {
+    fn from(item: BuildOrder) -> solve::BuildOrder {
+        match item {
+            BuildOrder::OptionValues => solve::BuildOrder::OptionValues,
+            BuildOrder::RepositoryOrder => solve::BuildOrder::RepositoryOrder,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum SolverBackend {
+    /// Search the graph of decisions one request at a time
+    Graph,
+    /// Encode the whole solve as a satisfiability problem
+    Sat,
+}
+
+impl From<SolverBackend> for solve::SolverBackendKind {
+    fn from(item: SolverBackend) -> solve::SolverBackendKind {
+        match item {
+            SolverBackend::Graph => solve::SolverBackendKind::Graph,
+            SolverBackend::Sat => solve::SolverBackendKind::Sat,
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -1108,6 +1296,22 @@ pub struct DecisionFormatterSettings {
     /// Pause the solver each time it makes a decision, until the user hits Enter.
     #[clap(long, alias = "decision")]
     step_on_decision: bool,
+
+    /// Save the requests and decisions of the solve to this json file
+    ///
+    /// The saved file can be replayed with --replay to reproduce the
+    /// solve, and is useful to include when reporting a solver problem.
+    #[clap(long, value_name = "FILE")]
+    save_solve_run: Option<PathBuf>,
+
+    /// Replay a solve that was saved with --save-solve-run
+    ///
+    /// The saved requests and options are used in place of any given on
+    /// the command line, and the first decision that differs from the
+    /// saved solve is reported. The same repositories should be enabled
+    /// as when the solve was saved.
+    #[clap(long, value_name = "FILE")]
+    replay: Option<PathBuf>,
 }
 
 impl DecisionFormatterSettings {
@@ -1122,6 +1326,7 @@ impl DecisionFormatterSettings {
     /// case some extra configuration might be needed before calling
     /// build.
     pub fn get_formatter_builder(&self, verbosity: u8) -> Result<DecisionFormatterBuilder> {
+        let replay = self.replay.as_deref().map(SolveRun::load).transpose()?;
         let mut builder =
             DecisionFormatterBuilder::try_from_config().wrap_err("Failed to load config")?;
         builder
@@ -1148,7 +1353,9 @@ impl DecisionFormatterSettings {
             .with_stop_on_block(self.stop_on_block)
             .with_step_on_block(self.step_on_block)
             .with_step_on_decision(self.step_on_decision)
-            .with_compare_solvers(self.compare_solvers);
+            .with_compare_solvers(self.compare_solvers)
+            .with_save_solve_run(self.save_solve_run.clone())
+            .with_replay(replay);
         Ok(builder)
     }
 }
@@ -34,3 +34,14 @@ fn test_option_flags_parsing(#[case] args: &[&str], #[case] expected: &[(&str, &
         .collect();
     assert_eq!(actual, expected);
 }
+
+#[rstest]
+fn test_repo_order_priorities() {
+    let order = super::RepositoryOrder {
+        repo_order: vec!["origin".into(), "local".into(), "other".into()],
+    };
+    let actual = order.get_repository_priorities().unwrap();
+    assert_eq!(actual["origin"], 3);
+    assert_eq!(actual["local"], 2);
+    assert_eq!(actual["other"], 1);
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use once_cell::sync::Lazy;
+
 mod build_result;
 mod cli;
 mod env;
@@ -9,7 +11,7 @@ mod error;
 pub mod exec;
 pub mod flags;
 pub mod parsing;
-mod publish;
+mod reporter;
 pub mod with_version_and_build_set;
 
 pub use build_result::{BuildArtifact, BuildResult};
@@ -17,14 +19,30 @@ pub use cli::{CommandArgs, Run};
 #[cfg(feature = "sentry")]
 pub use env::configure_sentry;
 pub use env::{configure_logging, current_env, spk_exe};
-pub use error::{Error, Result, TestError};
+pub use error::{Error, ErrorCategory, Result, TestError};
 pub use exec::build_required_packages;
-use once_cell::sync::Lazy;
-pub use publish::{PublishLabel, Publisher};
+pub use reporter::{
+    configure_output,
+    ErrorReport,
+    OutputFormat,
+    Reporter,
+    SolutionReport,
+    SolvedPackageReport,
+};
+pub use spk_build::install_hooks::{self, run_current_install_hooks, run_install_hooks};
 pub use with_version_and_build_set::{DefaultBuildStrategy, DefaultVersionStrategy};
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Contains label=value data for use with publishing
+#[deprecated(note = "use spk_storage::PublishLabel instead")]
+pub type PublishLabel = spk_storage::PublishLabel;
+
+/// Manages the publishing of packages from one repo to another.
+#[deprecated(note = "use spk_storage::Publisher instead")]
+pub type Publisher = spk_storage::Publisher;
+
+#[deprecated(note = "use spk::Client, or create a tokio runtime directly, instead")]
 pub static HANDLE: Lazy<tokio::runtime::Handle> = Lazy::new(|| {
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -0,0 +1,191 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use miette::{Context, IntoDiagnostic, Report, Result};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::Package;
+use spk_solve::solution::Solution;
+
+use crate::ErrorCategory;
+
+#[cfg(test)]
+#[path = "./reporter_test.rs"]
+mod reporter_test;
+
+static OUTPUT_FORMAT: OnceCell<OutputFormat> = OnceCell::new();
+
+/// The format that command results are written to stdout in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Formatted, human-readable output
+    #[default]
+    Text,
+    /// Machine-readable json output
+    Json,
+}
+
+/// Set the output format used by all commands for this process.
+///
+/// This should be called once, early in the process. When json
+/// output is requested, colored output is also disabled so that any
+/// formatted values included in the json are plain text.
+pub fn configure_output(format: OutputFormat) {
+    if OUTPUT_FORMAT.set(format).is_err() {
+        tracing::debug!("Output format was already configured");
+        return;
+    }
+    if format == OutputFormat::Json {
+        colored::control::set_override(false);
+    }
+}
+
+/// Emits the results of a command in the configured [`OutputFormat`].
+///
+/// Commands hand their results to the reporter along with a function
+/// that prints them for humans, and the reporter decides which
+/// one is written to stdout.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Reporter {
+    format: OutputFormat,
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat) -> Self {
+        Self { format }
+    }
+
+    /// A reporter for the output format configured for this process.
+    pub fn current() -> Self {
+        Self::new(OUTPUT_FORMAT.get().copied().unwrap_or_default())
+    }
+
+    /// The output format that this reporter writes
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// True if results should be written as json
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Report the result of a command.
+    ///
+    /// In text mode, the given function is called to print the
+    /// result, otherwise the value is written to stdout as json.
+    pub fn report<T, F>(&self, value: &T, text: F) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+        F: FnOnce() -> Result<()>,
+    {
+        match self.format {
+            OutputFormat::Text => text(),
+            OutputFormat::Json => {
+                let mut stdout = std::io::stdout().lock();
+                serde_json::to_writer(&mut stdout, value)
+                    .into_diagnostic()
+                    .wrap_err("Failed to serialize command output")?;
+                std::io::Write::write_all(&mut stdout, b"\n")
+                    .into_diagnostic()
+                    .wrap_err("Failed to write command output")
+            }
+        }
+    }
+
+    /// Report an error that caused a command to fail.
+    ///
+    /// In json mode, the error is written to stdout as an
+    /// [`ErrorReport`] under an `error` key, otherwise the given
+    /// function is called to print it.
+    pub fn report_error<F>(&self, err: &Report, text: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        #[derive(Serialize)]
+        struct Output {
+            error: ErrorReport,
+        }
+
+        let output = Output {
+            error: ErrorReport::from(err),
+        };
+        self.report(&output, text)
+    }
+}
+
+/// A machine-readable summary of a solved environment.
+#[derive(Debug, Serialize)]
+pub struct SolutionReport {
+    pub options: OptionMap,
+    pub packages: Vec<SolvedPackageReport>,
+}
+
+/// A machine-readable summary of a single package in a solution.
+#[derive(Debug, Serialize)]
+pub struct SolvedPackageReport {
+    pub ident: String,
+    pub components: Vec<String>,
+    /// The repository that the package was resolved from, if any
+    pub repository: Option<String>,
+    /// True if the package needs to be built from source
+    pub build_from_source: bool,
+    pub requested_by: Vec<String>,
+}
+
+impl From<&Solution> for SolutionReport {
+    fn from(solution: &Solution) -> Self {
+        Self {
+            options: solution.options().clone(),
+            packages: solution
+                .items()
+                .map(|item| SolvedPackageReport {
+                    ident: item.spec.ident().to_string(),
+                    components: item
+                        .selected_components()
+                        .into_iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    repository: item.repo_name().map(|r| r.to_string()),
+                    build_from_source: item.is_source_build(),
+                    requested_by: item
+                        .request
+                        .get_requesters()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A machine-readable description of a failed command.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    /// The stable diagnostic code of the error, eg: `spk::storage::package_not_found`
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+    /// The messages of each underlying error, outermost first
+    pub causes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl From<&Report> for ErrorReport {
+    fn from(err: &Report) -> Self {
+        let code = err
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "spk::generic".to_string());
+        Self {
+            category: ErrorCategory::from_code(&code),
+            code,
+            message: err.to_string(),
+            causes: err.chain().skip(1).map(ToString::to_string).collect(),
+            help: err.help().map(|h| h.to_string()),
+        }
+    }
+}
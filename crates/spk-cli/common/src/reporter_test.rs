@@ -0,0 +1,82 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::ident::parse_ident;
+use spk_solve::solution::fixtures::solution_from_specs;
+use spk_solve::spec;
+
+use super::{ErrorReport, OutputFormat, Reporter, SolutionReport};
+use crate::{Error, ErrorCategory};
+
+#[rstest]
+fn test_reporter_text_calls_formatter() {
+    let mut called = false;
+    Reporter::new(OutputFormat::Text)
+        .report(&["ignored"], || {
+            called = true;
+            Ok(())
+        })
+        .unwrap();
+    assert!(called, "text output should use the given formatter");
+}
+
+#[rstest]
+fn test_reporter_json_skips_formatter() {
+    let mut called = false;
+    Reporter::new(OutputFormat::Json)
+        .report(&["value"], || {
+            called = true;
+            Ok(())
+        })
+        .unwrap();
+    assert!(!called, "json output should not use the text formatter");
+}
+
+#[rstest]
+fn test_solution_report() {
+    let solution = solution_from_specs([spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"})]);
+
+    let report = SolutionReport::from(&solution);
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["packages"][0]["ident"], "my-pkg/1.0.0/3I42H3S6");
+    assert_eq!(json["packages"][0]["requested_by"][0], "spk's test suite");
+    assert_eq!(json["packages"][0]["build_from_source"], false);
+}
+
+#[rstest]
+#[case::storage("spk::storage::package_not_found", ErrorCategory::Repository)]
+#[case::solver("spk::solve::out_of_options", ErrorCategory::Solver)]
+#[case::build("spk::build::validation::empty_package", ErrorCategory::Build)]
+#[case::user("spk::schema::invalid_name", ErrorCategory::User)]
+#[case::spfs_repo("spfs::unknown_remote", ErrorCategory::Repository)]
+#[case::spfs_runtime("spfs::could_not_create_spfs_dir", ErrorCategory::Environment)]
+#[case::generic("spk::generic", ErrorCategory::Other)]
+fn test_error_category_from_code(#[case] code: &str, #[case] expected: ErrorCategory) {
+    assert_eq!(ErrorCategory::from_code(code), expected);
+}
+
+#[rstest]
+fn test_error_report_uses_wrapped_code() {
+    let ident = parse_ident("my-pkg/1.0.0").unwrap();
+    let err: miette::Report = Error::from(spk_storage::Error::PackageNotFound(ident)).into();
+    let err = err.wrap_err("Failed to load package");
+
+    let report = ErrorReport::from(&err);
+    assert_eq!(report.code, "spk::storage::package_not_found");
+    assert_eq!(report.category, ErrorCategory::Repository);
+    assert_eq!(report.message, "Failed to load package");
+    assert_eq!(report.causes, vec!["Package not found: my-pkg/1.0.0"]);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["category"], "repository");
+}
+
+#[rstest]
+fn test_error_report_defaults_to_generic() {
+    let err = miette::miette!("something went wrong");
+    let report = ErrorReport::from(&err);
+    assert_eq!(report.code, "spk::generic");
+    assert_eq!(report.category, ErrorCategory::Other);
+}
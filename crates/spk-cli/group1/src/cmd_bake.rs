@@ -6,10 +6,16 @@ use clap::Args;
 use futures::TryFutureExt;
 use miette::IntoDiagnostic;
 use serde::Serialize;
+use spfs::prelude::*;
 use spk_cli_common::{current_env, flags, CommandArgs, Run};
 use spk_schema::ident::RequestedBy;
 use spk_schema::Package;
-use spk_solve::solution::{get_spfs_layers_to_packages, LayerPackageAndComponents, PackageSource};
+use spk_solve::solution::{
+    get_spfs_layers_to_packages,
+    LayerPackageAndComponents,
+    PackageSource,
+    Solution,
+};
 
 // Verbosity level above which repo and component names will be
 // included in the package display values.
@@ -62,6 +68,17 @@ pub struct Bake {
     #[clap(flatten)]
     pub formatter_settings: flags::DecisionFormatterSettings,
 
+    /// Merge the environment into a single spfs layer and output its digest
+    ///
+    /// The new layer is stored in the local repository, and renders the
+    /// same files as the whole environment using only one layer.
+    #[clap(long)]
+    pub flatten: bool,
+
+    /// Tag the flattened layer in the local repository with this name
+    #[clap(long, requires = "flatten")]
+    pub tag: Option<String>,
+
     /// The requests to resolve and bake
     #[clap(name = "REQUESTS")]
     pub requested: Vec<String>,
@@ -93,6 +110,10 @@ impl Run for Bake {
     type Output = i32;
 
     async fn run(&mut self) -> miette::Result<Self::Output> {
+        if self.flatten {
+            return self.flatten_environment().await;
+        }
+
         // Get the layer data from either the active runtime, or the
         // requests made on the command line
         let layers = if self.requested.is_empty() {
@@ -145,6 +166,48 @@ impl CommandArgs for Bake {
 }
 
 impl Bake {
+    /// Flatten the requested environment, or the current one if
+    /// no requests were given, into a single layer.
+    async fn flatten_environment(&self) -> miette::Result<i32> {
+        let solution = if self.requested.is_empty() {
+            current_env().await?
+        } else {
+            let (_, solution) = tokio::try_join!(
+                self.runtime.ensure_active_runtime(&["bake"]),
+                self.solve_requests()
+            )?;
+            solution
+        };
+
+        let layer = spk_exec::flatten_solution(&solution).await?;
+        let digest = layer.digest().into_diagnostic()?;
+        if let Some(tag) = &self.tag {
+            let tag = spfs::tracking::TagSpec::parse(tag)?;
+            let repo = spfs::get_config()?.get_local_repository_handle().await?;
+            repo.push_tag(&tag, &digest).await?;
+            tracing::info!("created tag {tag} -> {digest}");
+        }
+        println!("{digest}");
+        Ok(0)
+    }
+
+    /// Resolve the requests given on the command line.
+    async fn solve_requests(&self) -> miette::Result<Solution> {
+        let mut solver = self.solver.get_solver(&self.options).await?;
+
+        let requests = self
+            .requests
+            .parse_requests(&self.requested, &self.options, solver.repositories())
+            .await?;
+        for request in requests {
+            solver.add_request(request)
+        }
+
+        let formatter = self.formatter_settings.get_formatter(self.verbose)?;
+        let (solution, _) = formatter.run_and_print_resolve(&solver).await?;
+        Ok(solution)
+    }
+
     /// Get the layers from the active stack. These are digests for
     /// the layers from any packages resolved into the current
     /// environment, and may include other layers added by other
@@ -220,20 +283,7 @@ impl Bake {
     /// anything in the current environment.
     ///
     async fn get_new_solve_info(&self) -> miette::Result<Vec<BakeLayer>> {
-        // Setup a solver for the requests and generate a solution
-        // with it.
-        let mut solver = self.solver.get_solver(&self.options).await?;
-
-        let requests = self
-            .requests
-            .parse_requests(&self.requested, &self.options, solver.repositories())
-            .await?;
-        for request in requests {
-            solver.add_request(request)
-        }
-
-        let formatter = self.formatter_settings.get_formatter(self.verbose)?;
-        let (solution, _) = formatter.run_and_print_resolve(&solver).await?;
+        let solution = self.solve_requests().await?;
 
         // The solution order is the order things were found during
         // the solve. Need to reverse it to match up with the spfs
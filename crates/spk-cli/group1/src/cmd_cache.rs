@@ -0,0 +1,81 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use miette::{Context, IntoDiagnostic, Result};
+use spk_cli_common::{CommandArgs, Run};
+
+/// Manage the caches that spk keeps between commands
+///
+/// Results read from a repository are otherwise only cached for
+/// as long as each command runs, see the --no-cache and --cache-ttl
+/// flags of commands that read from repositories.
+#[derive(Args)]
+pub struct Cache {
+    #[clap(subcommand)]
+    command: CacheCommand,
+}
+
+#[async_trait::async_trait]
+impl Run for Cache {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        self.command.run()
+    }
+}
+
+impl CommandArgs for Cache {
+    fn get_positional_args(&self) -> Vec<String> {
+        // There are no important positional args for the cache command
+        vec![]
+    }
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Remove everything that spk has cached for the current user
+    ///
+    /// This includes the package names and versions listed
+    /// for shell completion.
+    Clear,
+}
+
+impl CacheCommand {
+    pub fn run(&mut self) -> Result<i32> {
+        match self {
+            Self::Clear => {
+                let Some(root) = cache_root() else {
+                    tracing::info!("No cache directory on this platform, nothing to clear");
+                    return Ok(0);
+                };
+                if clear_cache(&root)? {
+                    tracing::info!("Cleared {}", root.display());
+                } else {
+                    tracing::info!("Nothing to clear in {}", root.display());
+                }
+                Ok(0)
+            }
+        }
+    }
+}
+
+/// The directory where spk keeps the caches of the current user, if any
+pub fn cache_root() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("spk"))
+}
+
+/// Remove the given cache directory and everything in it,
+/// returning false if it did not exist.
+fn clear_cache(root: &std::path::Path) -> Result<bool> {
+    match std::fs::remove_dir_all(root) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to clear {}", root.display())),
+    }
+}
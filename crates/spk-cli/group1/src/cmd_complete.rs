@@ -0,0 +1,274 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Dynamic completion of package names for the shell completion scripts
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::{Arg, ArgAction, Args, Command};
+use miette::Result;
+use serde::{Deserialize, Serialize};
+use spk_cli_common::{flags, CommandArgs};
+use spk_schema::foundation::name::PkgName;
+use spk_storage::RepositoryHandle;
+
+#[cfg(test)]
+#[path = "./cmd_complete_test.rs"]
+mod cmd_complete_test;
+
+/// The positional argument names that identify a package
+const PACKAGE_ARG_PREFIXES: &[&str] = &["PKG", "NAME", "REQUEST"];
+
+/// Print the possible package names and versions for a command line
+///
+/// This is called by the scripts that are generated by
+/// `spk completion` and is not intended to be used directly.
+#[derive(Args, Clone)]
+pub struct Complete {
+    #[clap(flatten)]
+    pub repos: flags::Repositories,
+
+    /// The longest time to wait for each repository, in seconds
+    #[clap(long, default_value_t = 2.0)]
+    pub timeout: f64,
+
+    /// How long to reuse the packages listed by a repository, in seconds
+    #[clap(long, default_value_t = 300)]
+    pub cache_ttl: u64,
+
+    /// The command line being completed, ending with the current word
+    #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub words: Vec<String>,
+}
+
+impl Complete {
+    pub async fn run(&mut self, mut cmd: Command) -> Result<i32> {
+        cmd.build();
+        let Some((current, previous)) = self.words.split_last() else {
+            return Ok(0);
+        };
+        if !completes_package(&cmd, previous) {
+            return Ok(0);
+        }
+
+        let timeout = Duration::from_secs_f64(self.timeout.max(0.0));
+        let repos = match tokio::time::timeout(
+            timeout,
+            self.repos.get_repos_for_non_destructive_operation(),
+        )
+        .await
+        {
+            Ok(repos) => repos?,
+            Err(_) => {
+                tracing::debug!("timed out loading repositories for completion");
+                return Ok(0);
+            }
+        };
+
+        let cache = CompletionCache::new(Duration::from_secs(self.cache_ttl));
+        let mut candidates = BTreeSet::new();
+        for (name, repo) in repos.iter() {
+            let found = match current.split_once('/') {
+                None => cache.list_packages(name, repo, timeout).await,
+                Some((package, _)) => match PkgName::new(package) {
+                    Ok(package) => cache.list_versions(name, repo, package, timeout).await,
+                    Err(_) => Vec::new(),
+                },
+            };
+            candidates.extend(
+                found
+                    .into_iter()
+                    .filter(|c| c.starts_with(current.as_str())),
+            );
+        }
+        for candidate in candidates {
+            println!("{candidate}");
+        }
+        Ok(0)
+    }
+}
+
+impl CommandArgs for Complete {
+    fn get_positional_args(&self) -> Vec<String> {
+        self.words.clone()
+    }
+}
+
+/// True if the word after the given command line is expected
+/// to name a package.
+///
+/// The first word is the name of the program itself. Options are
+/// matched against the given command and its subcommands to tell
+/// which positional argument, or option value, comes next.
+pub fn completes_package(cmd: &Command, words: &[String]) -> bool {
+    let mut cmd = cmd;
+    let mut positional = 0;
+    let mut option_value = None;
+    let mut only_positionals = false;
+    for word in words.iter().skip(1) {
+        if option_value.take().is_some() {
+            // this word is the value of the previous option
+            continue;
+        }
+        if only_positionals || word == "-" || !word.starts_with('-') {
+            match cmd.find_subcommand(word).filter(|_| positional == 0) {
+                Some(subcommand) => cmd = subcommand,
+                None => positional += 1,
+            }
+            continue;
+        }
+        if word == "--" {
+            only_positionals = true;
+            continue;
+        }
+        let arg = match word.strip_prefix("--") {
+            Some(long) if long.contains('=') => None,
+            Some(long) => cmd.get_arguments().find(|a| a.get_long() == Some(long)),
+            None => word
+                .chars()
+                .last()
+                .and_then(|short| cmd.get_arguments().find(|a| a.get_short() == Some(short))),
+        };
+        option_value = arg.filter(|a| a.get_action().takes_values());
+    }
+
+    if let Some(arg) = option_value {
+        return arg
+            .get_value_names()
+            .unwrap_or_default()
+            .iter()
+            .any(|name| is_package_name(name));
+    }
+    let positionals: Vec<_> = cmd.get_positionals().collect();
+    let current = match positionals.get(positional) {
+        Some(arg) => Some(*arg),
+        // the last positional argument may take any number of values
+        None => positionals.last().copied().filter(|arg| takes_many(arg)),
+    };
+    current
+        .map(|arg| is_package_name(arg.get_id().as_str()))
+        .unwrap_or_default()
+}
+
+fn takes_many(arg: &Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::Append)
+        || arg
+            .get_num_args()
+            .map(|n| n.max_values() > 1)
+            .unwrap_or_default()
+}
+
+fn is_package_name(name: &str) -> bool {
+    name.split('|')
+        .any(|part| PACKAGE_ARG_PREFIXES.iter().any(|p| part.starts_with(p)))
+}
+
+/// A cache of the package names and versions in each repository,
+/// so that completion remains fast while typing.
+struct CompletionCache {
+    root: Option<PathBuf>,
+    ttl: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// When the entry was written, in seconds since the unix epoch
+    time: u64,
+    values: Vec<String>,
+}
+
+impl CompletionCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            root: crate::cmd_cache::cache_root().map(|d| d.join("completion")),
+            ttl,
+        }
+    }
+
+    async fn list_packages(
+        &self,
+        repo_name: &str,
+        repo: &RepositoryHandle,
+        timeout: Duration,
+    ) -> Vec<String> {
+        let key = format!("{repo_name}/packages.json");
+        self.get_or_insert(&key, timeout, async {
+            let names = repo.list_packages().await?;
+            Ok(names.into_iter().map(|n| n.to_string()).collect())
+        })
+        .await
+    }
+
+    async fn list_versions(
+        &self,
+        repo_name: &str,
+        repo: &RepositoryHandle,
+        package: &PkgName,
+        timeout: Duration,
+    ) -> Vec<String> {
+        let key = format!("{repo_name}/versions/{package}.json");
+        self.get_or_insert(&key, timeout, async {
+            let versions = repo.list_package_versions(package).await?;
+            Ok(versions.iter().map(|v| format!("{package}/{v}")).collect())
+        })
+        .await
+    }
+
+    /// Read the cached values for the given key, or load and save
+    /// them if the cache is missing or out of date.
+    ///
+    /// Values that cannot be loaded within the timeout are treated
+    /// as empty, and are not saved.
+    async fn get_or_insert<F>(&self, key: &str, timeout: Duration, load: F) -> Vec<String>
+    where
+        F: std::future::Future<Output = spk_storage::Result<Vec<String>>>,
+    {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = self.root.as_ref().map(|root| root.join(key));
+        let cached = path
+            .as_ref()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice::<CacheEntry>(&data).ok());
+        if let Some(entry) = cached {
+            if now.saturating_sub(entry.time) < self.ttl.as_secs() {
+                return entry.values;
+            }
+        }
+
+        let values = match tokio::time::timeout(timeout, load).await {
+            Ok(Ok(values)) => values,
+            Ok(Err(err)) => {
+                tracing::debug!("failed to list packages for completion: {err}");
+                return Vec::new();
+            }
+            Err(_) => {
+                tracing::debug!("timed out listing packages for completion");
+                return Vec::new();
+            }
+        };
+        if let Some(path) = path {
+            let entry = CacheEntry {
+                time: now,
+                values: values.clone(),
+            };
+            let saved = path
+                .parent()
+                .map(std::fs::create_dir_all)
+                .transpose()
+                .and_then(|_| {
+                    let data = serde_json::to_vec(&entry).unwrap_or_default();
+                    std::fs::write(&path, data)
+                });
+            if let Err(err) = saved {
+                tracing::debug!("failed to save completion cache {}: {err}", path.display());
+            }
+        }
+        values
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::{Arg, ArgAction, Command};
+use rstest::rstest;
+
+use super::completes_package;
+
+fn command() -> Command {
+    let mut cmd = Command::new("spk")
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .global(true)
+                .action(ArgAction::Count),
+        )
+        .subcommand(
+            Command::new("install")
+                .arg(Arg::new("yes").long("yes").action(ArgAction::SetTrue))
+                .arg(Arg::new("PKG").num_args(1..).required(true)),
+        )
+        .subcommand(
+            Command::new("ls")
+                .arg(Arg::new("host").long("host").value_name("HOST"))
+                .arg(Arg::new("component").long("pkg").value_name("PKG"))
+                .arg(Arg::new("NAME[/VERSION]")),
+        )
+        .subcommand(Command::new("du").arg(Arg::new("REPO/PKG/VERSION/...").num_args(0..)));
+    cmd.build();
+    cmd
+}
+
+#[rstest]
+#[case(&["spk"], false)]
+#[case(&["spk", "install"], true)]
+#[case(&["spk", "install", "python"], true)]
+#[case(&["spk", "install", "--yes"], true)]
+#[case(&["spk", "-vv", "install"], true)]
+#[case(&["spk", "ls"], true)]
+#[case(&["spk", "ls", "python"], false)]
+#[case(&["spk", "ls", "--host"], false)]
+#[case(&["spk", "ls", "--host", "centos"], true)]
+#[case(&["spk", "ls", "--pkg"], true)]
+#[case(&["spk", "du"], false)]
+fn test_completes_package(#[case] words: &[&str], #[case] expected: bool) {
+    let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+    assert_eq!(completes_package(&command(), &words), expected);
+}
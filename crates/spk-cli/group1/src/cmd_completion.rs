@@ -25,12 +25,57 @@ impl Completion {
     pub fn run(&self, mut cmd: Command) -> Result<i32> {
         let mut buf = vec![];
         clap_complete::generate(self.shell, &mut cmd, "spk", &mut buf);
+        if let Some(script) = package_completion_script(self.shell) {
+            buf.extend_from_slice(script.as_bytes());
+        }
         std::io::stdout().write_all(&buf).unwrap_or(());
 
         Ok(0)
     }
 }
 
+/// Additional script that completes package names and versions by
+/// calling the hidden `spk complete` command, which lists them from
+/// the configured repositories.
+fn package_completion_script(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(
+            r#"
+_spk_with_packages() {
+    local packages
+    packages=$(spk complete -- "${COMP_WORDS[@]:0:COMP_CWORD+1}" 2>/dev/null)
+    if [[ -n "${packages}" ]]; then
+        COMPREPLY=( $(compgen -W "${packages}" -- "${COMP_WORDS[COMP_CWORD]}") )
+        return 0
+    fi
+    _spk "$@"
+}
+complete -F _spk_with_packages -o nosort -o bashdefault -o default spk
+"#,
+        ),
+        Shell::Zsh => Some(
+            r#"
+_spk_with_packages() {
+    local -a packages
+    packages=(${(f)"$(spk complete -- "${(@)words[1,CURRENT]}" 2>/dev/null)"})
+    if (( ${#packages} )); then
+        compadd -Q -- "${packages[@]}"
+        return 0
+    fi
+    _spk "$@"
+}
+compdef _spk_with_packages spk
+"#,
+        ),
+        Shell::Fish => Some(
+            r#"
+complete -c spk -a '(spk complete -- (commandline -opc) (commandline -ct | string collect -a) 2>/dev/null)'
+"#,
+        ),
+        _ => None,
+    }
+}
+
 impl CommandArgs for Completion {
     fn get_positional_args(&self) -> Vec<String> {
         let args: Vec<String> = vec![match self.shell {
@@ -10,7 +10,7 @@ use colored::Colorize;
 use miette::Result;
 use spk_cli_common::{flags, CommandArgs, Run};
 use spk_schema::foundation::format::FormatIdent;
-use spk_schema::ident::{parse_ident, AnyIdent};
+use spk_schema::ident::{parse_ident, parse_ident_range, AnyIdent, RangeIdent};
 use spk_schema::{Deprecate, DeprecateMut, Package, Recipe, Spec, SpecRecipe};
 use spk_storage as storage;
 
@@ -80,6 +80,14 @@ pub struct DeprecateCmd {
     #[clap(long, short)]
     pub yes: bool,
 
+    /// Suggest a package to use instead of the deprecated ones
+    ///
+    /// This can be given more than once, and each one can be a package
+    /// name, version or range (eg: foo, foo/2.0, foo/>=2). The suggestions
+    /// are included in solver errors and `spk ls --deprecated` output.
+    #[clap(long, value_name = "PKG")]
+    replaced_by: Vec<String>,
+
     /// The package version or build to deprecate
     ///
     /// By deprecating a package version, as opposed to an individual
@@ -96,10 +104,16 @@ impl Run for DeprecateCmd {
     type Output = i32;
 
     async fn run(&mut self) -> Result<Self::Output> {
+        let replaced_by = self
+            .replaced_by
+            .iter()
+            .map(|r| parse_ident_range(r))
+            .collect::<spk_schema::ident::Result<Vec<_>>>()?;
         change_deprecation_state(
             ChangeAction::Deprecate,
             &self.repos.get_repos_for_destructive_operation().await?,
             &self.packages,
+            &replaced_by,
             self.yes,
         )
         .await
@@ -131,10 +145,14 @@ pub(crate) fn ask_user(prompt: &str) -> String {
 
 /// Changes package builds' specs' deprecation field based on the
 /// given action. Deprecating sets it to true, undeprecating to false.
+///
+/// When deprecating, the given replacements are also recorded as
+/// suggestions for what to use instead. Undeprecating clears them.
 pub(crate) async fn change_deprecation_state(
     action: ChangeAction,
     repositories: &[(String, storage::RepositoryHandle)],
     packages: &[String],
+    replaced_by: &[RangeIdent],
     yes: bool,
 ) -> Result<i32> {
     let repos: Vec<_> = repositories
@@ -299,7 +317,8 @@ pub(crate) async fn change_deprecation_state(
     for (mut target, repo_name, repo) in to_action.into_iter() {
         let fmt = target.ident().format_ident();
 
-        if target.is_deprecated() == new_status {
+        let replacements_changed = new_status && target.replaced_by() != replaced_by;
+        if target.is_deprecated() == new_status && !replacements_changed {
             println!(
                 " {} {fmt} in {repo_name}, it is already {}.",
                 "Skipping".yellow(),
@@ -311,7 +330,10 @@ pub(crate) async fn change_deprecation_state(
         println!("{} {fmt} in {repo_name}", action.as_present_tense(),);
 
         match action {
-            ChangeAction::Deprecate => target.deprecate()?,
+            ChangeAction::Deprecate => {
+                target.deprecate()?;
+                target.set_replaced_by(replaced_by.to_vec())?;
+            }
             ChangeAction::Undeprecate => target.undeprecate()?,
         }
         match target {
@@ -334,6 +356,13 @@ impl Deprecate for DeprecationTarget {
             DeprecationTarget::Package(t) => t.is_deprecated(),
         }
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        match self {
+            DeprecationTarget::Recipe(t) => t.replaced_by(),
+            DeprecationTarget::Package(t) => t.replaced_by(),
+        }
+    }
 }
 
 impl DeprecateMut for DeprecationTarget {
@@ -368,6 +397,22 @@ impl DeprecateMut for DeprecationTarget {
         }
         Ok(())
     }
+
+    fn set_replaced_by(&mut self, replaced_by: Vec<RangeIdent>) -> spk_schema::Result<()> {
+        match self {
+            DeprecationTarget::Recipe(t) => {
+                let mut new = (**t).clone();
+                new.set_replaced_by(replaced_by)?;
+                let _ = std::mem::replace(t, new.into());
+            }
+            DeprecationTarget::Package(t) => {
+                let mut new = (**t).clone();
+                new.set_replaced_by(replaced_by)?;
+                let _ = std::mem::replace(t, new.into());
+            }
+        }
+        Ok(())
+    }
 }
 
 impl DeprecationTarget {
@@ -3,7 +3,7 @@
 // https://github.com/spkenv/spk
 
 use rstest::rstest;
-use spk_schema::ident::parse_version_ident;
+use spk_schema::ident::{parse_ident_range, parse_version_ident};
 use spk_schema::Deprecate;
 use spk_solve_macros::make_repo;
 
@@ -30,7 +30,8 @@ async fn test_deprecate_without_prompt() {
     // with the '--yes' flag to prevent it prompting.
     let packages = vec![name1.to_string(), name2.to_string(), name3.to_string()];
     let yes = true;
-    let result = change_deprecation_state(ChangeAction::Deprecate, &repos, &packages, yes).await;
+    let result =
+        change_deprecation_state(ChangeAction::Deprecate, &repos, &packages, &[], yes).await;
 
     match result {
         Ok(r) => assert_eq!(r, 0),
@@ -57,3 +58,49 @@ async fn test_deprecate_without_prompt() {
         }
     }
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_deprecate_with_replacements() {
+    let name = "my-pkg/1.0.0";
+    let repo = make_repo!([{"pkg": name}]);
+    let repos = vec![("test".to_string(), repo)];
+
+    let packages = vec![name.to_string()];
+    let replaced_by = vec![
+        parse_ident_range("my-pkg/2.0.0").unwrap(),
+        parse_ident_range("other-pkg").unwrap(),
+    ];
+    let result = change_deprecation_state(
+        ChangeAction::Deprecate,
+        &repos,
+        &packages,
+        &replaced_by,
+        true,
+    )
+    .await
+    .unwrap();
+    assert_eq!(result, 0);
+
+    let ident = parse_version_ident(name).unwrap();
+    let (_, r) = &repos[0];
+    let recipe = r.read_recipe(&ident).await.unwrap();
+    assert_eq!(recipe.replaced_by(), replaced_by.as_slice());
+    for b in r.list_package_builds(&ident).await.unwrap() {
+        let spec = r.read_package(&b).await.unwrap();
+        assert_eq!(spec.replaced_by(), replaced_by.as_slice());
+        assert_eq!(
+            spec.replacement_suggestion().as_deref(),
+            Some("use my-pkg/2.0.0 or other-pkg")
+        );
+    }
+
+    // undeprecating should also remove the suggested replacements
+    let result = change_deprecation_state(ChangeAction::Undeprecate, &repos, &packages, &[], true)
+        .await
+        .unwrap();
+    assert_eq!(result, 0);
+    let recipe = r.read_recipe(&ident).await.unwrap();
+    assert!(!recipe.is_deprecated());
+    assert!(recipe.replaced_by().is_empty());
+}
@@ -46,6 +46,7 @@ impl Run for Undeprecate {
             ChangeAction::Undeprecate,
             &self.repos.get_repos_for_destructive_operation().await?,
             &self.packages,
+            &[],
             self.yes,
         )
         .await
@@ -3,6 +3,8 @@
 // https://github.com/spkenv/spk
 
 pub mod cmd_bake;
+pub mod cmd_cache;
+pub mod cmd_complete;
 pub mod cmd_completion;
 pub mod cmd_deprecate;
 pub mod cmd_undeprecate;
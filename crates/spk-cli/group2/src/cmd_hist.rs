@@ -0,0 +1,157 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use chrono::Local;
+use clap::Args;
+use colored::Colorize;
+use miette::{bail, Result};
+use spk_cli_common::{flags, CommandArgs, Run};
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::ident::parse_ident;
+use spk_schema::spec_ops::WithVersion;
+use spk_schema::AnyIdent;
+use spk_storage as storage;
+
+#[cfg(test)]
+#[path = "./cmd_hist_test.rs"]
+mod cmd_hist_test;
+
+/// Show when each version or build of a package was published
+///
+/// Every publish or modification of a package (such as deprecation)
+/// is recorded in the repository, and shown from newest to oldest
+/// along with the user that made it. The number after the '~' can
+/// be given to --rollback to restore that entry.
+#[derive(Args)]
+pub struct Hist {
+    #[clap(flatten)]
+    pub repos: flags::Repositories,
+
+    /// Restore the package to this entry of its history
+    ///
+    /// A version or build must be given, and a single repository
+    /// selected (see --enable-repo). The local repository is used
+    /// by default.
+    #[clap(long, value_name = "N")]
+    rollback: Option<usize>,
+
+    /// The package to show the history of (eg: my-pkg, my-pkg/1.0, my-pkg/1.0/BUILD)
+    #[clap(name = "NAME[/VERSION[/BUILD]]")]
+    package: String,
+}
+
+#[async_trait::async_trait]
+impl Run for Hist {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let pkg = parse_ident(&self.package)?;
+
+        if let Some(index) = self.rollback {
+            return self.rollback(&pkg, index).await;
+        }
+
+        let repos = self.repos.get_repos_for_non_destructive_operation().await?;
+        let mut found = false;
+        for (repo_name, repo) in repos.iter() {
+            let idents = self.idents_to_show(repo, &pkg).await?;
+            let mut printed_repo_name = repos.len() == 1;
+            for ident in idents {
+                let history = match repo.read_publish_history(&ident).await {
+                    Ok(history) => history,
+                    Err(storage::Error::PackageNotFound(_)) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                if !printed_repo_name {
+                    println!("{}", format!("{repo_name}:").bold());
+                    printed_repo_name = true;
+                }
+                found = true;
+                for (i, tag) in history.iter().enumerate() {
+                    println!(
+                        "{} {}~{i} {} {}",
+                        tag.target.to_string()[..10].yellow(),
+                        ident.format_ident(),
+                        tag.user.bright_blue(),
+                        tag.time.with_timezone(&Local).to_string().green(),
+                    );
+                }
+            }
+        }
+
+        if !found {
+            bail!("No history found for {}", self.package);
+        }
+        Ok(0)
+    }
+}
+
+impl CommandArgs for Hist {
+    fn get_positional_args(&self) -> Vec<String> {
+        vec![self.package.clone()]
+    }
+}
+
+impl Hist {
+    /// Find the recipes and builds whose history should be shown
+    async fn idents_to_show(
+        &self,
+        repo: &storage::RepositoryHandle,
+        pkg: &AnyIdent,
+    ) -> Result<Vec<AnyIdent>> {
+        if pkg.build().is_some() {
+            return Ok(vec![pkg.clone()]);
+        }
+        if !self.package.contains('/') {
+            let versions = match repo.list_package_versions(pkg.name()).await {
+                Ok(versions) => versions,
+                Err(storage::Error::PackageNotFound(_)) => return Ok(Vec::new()),
+                Err(err) => return Err(err.into()),
+            };
+            return Ok(versions
+                .iter()
+                .map(|v| pkg.with_version((**v).clone()))
+                .collect());
+        }
+
+        let mut idents = vec![pkg.clone()];
+        let builds = match repo.list_package_builds(pkg.as_version()).await {
+            Ok(builds) => builds,
+            Err(storage::Error::PackageNotFound(_)) => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+        idents.extend(
+            builds
+                .into_iter()
+                // embedded stubs are published along with their
+                // parent package and have no history of their own
+                .filter(|b| !b.is_embedded())
+                .map(|b| b.to_any()),
+        );
+        Ok(idents)
+    }
+
+    async fn rollback(&self, pkg: &AnyIdent, index: usize) -> Result<i32> {
+        if !self.package.contains('/') {
+            bail!(
+                "A version or build must be given to roll back, eg: {}/1.0.0",
+                pkg.name()
+            );
+        }
+        let repos = self.repos.get_repos_for_destructive_operation().await?;
+        let [(repo_name, repo)] = repos.as_slice() else {
+            bail!(
+                "A single repository must be selected to roll back, found {}",
+                repos.len()
+            );
+        };
+        repo.rollback(pkg, index).await?;
+        tracing::info!(
+            "restored {}~{index} in {}",
+            pkg.format_ident(),
+            repo_name.bold()
+        );
+        Ok(0)
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::Parser;
+use spfs::config::Remote;
+use spfs::RemoteAddress;
+use spk_schema::ident::parse_ident;
+use spk_schema::{recipe, Recipe};
+use spk_storage::fixtures::*;
+
+use super::{Hist, Run};
+
+#[derive(Parser)]
+struct Opt {
+    #[clap(flatten)]
+    hist: Hist,
+}
+
+#[tokio::test]
+async fn test_hist_rollback_restores_previous_recipe() {
+    let mut rt = spfs_runtime().await;
+    let remote_repo = spfsrepo().await;
+
+    rt.add_remote_repo(
+        "origin",
+        Remote::Address(RemoteAddress {
+            address: remote_repo.address().clone(),
+        }),
+    )
+    .unwrap();
+
+    let first = recipe!({"pkg": "my-pkg/1.0.0", "build": {"script": "echo first"}});
+    let second = recipe!({"pkg": "my-pkg/1.0.0", "build": {"script": "echo second"}});
+    remote_repo.publish_recipe(&first).await.unwrap();
+    remote_repo.force_publish_recipe(&second).await.unwrap();
+
+    let ident = parse_ident("my-pkg/1.0.0").unwrap();
+    let history = remote_repo.read_publish_history(&ident).await.unwrap();
+    assert_eq!(history.len(), 2, "expected one entry for each publish");
+
+    let mut opt = Opt::try_parse_from([
+        "hist",
+        "--rollback",
+        "1",
+        "--no-local-repo",
+        "-r",
+        "origin",
+        "my-pkg/1.0.0",
+    ])
+    .unwrap();
+    opt.hist.run().await.unwrap();
+
+    let restored = remote_repo.read_recipe(first.ident()).await.unwrap();
+    assert_eq!(*restored, first, "the first recipe should be current again");
+    let history = remote_repo.read_publish_history(&ident).await.unwrap();
+    assert_eq!(
+        history.len(),
+        3,
+        "rolling back should add to the history rather than discard it"
+    );
+}
+
+#[tokio::test]
+async fn test_hist_rollback_requires_version() {
+    let _rt = spfs_runtime().await;
+
+    let mut opt = Opt::try_parse_from(["hist", "--rollback", "1", "my-pkg"]).unwrap();
+    opt.hist
+        .run()
+        .await
+        .expect_err("rolling back every version at once should not be allowed");
+}
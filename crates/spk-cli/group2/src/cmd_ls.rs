@@ -2,14 +2,16 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::Write;
 
 use clap::Args;
 use colored::Colorize;
+use futures::{StreamExt, TryStreamExt};
 use miette::{miette, Result};
 use nom::combinator::all_consuming;
-use spk_cli_common::{flags, CommandArgs, Run};
+use serde::Serialize;
+use spk_cli_common::{flags, CommandArgs, Reporter, Run};
 use spk_schema::foundation::format::{FormatComponents, FormatIdent, FormatOptionMap};
 use spk_schema::foundation::ident_component::ComponentSet;
 use spk_schema::foundation::name::{PkgName, PkgNameBuf};
@@ -28,21 +30,87 @@ pub trait Output: Default + Send + Sync {
     /// A line of output to display.
     fn println(&mut self, line: String);
 
+    /// A listed item to display.
+    fn entry(&mut self, entry: Entry) {
+        self.println(entry.text);
+    }
+
     /// A line of output to display as a warning.
     fn warn(&mut self, line: String);
+
+    /// Called once all output has been generated.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
 
+/// A single package, version or build listed by [`Ls`]
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Entry {
+    /// The formatted line that is displayed for this entry
+    #[serde(skip)]
+    text: String,
+    /// The package name, version or build that was listed
+    pub ident: String,
+    /// The repository that the entry was found in, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// True if the package, or all of the builds of the version, are deprecated
+    pub deprecated: bool,
+    /// True if only some of the builds of the listed version are deprecated
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub partially_deprecated: bool,
+    /// The suggested replacement for a deprecated package, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    /// The option values of a listed build, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<BTreeMap<String, String>>,
+    /// The components of a listed build, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<String>>,
+}
+
+impl Entry {
+    /// Create an entry that is displayed as the given text.
+    pub fn new(ident: impl ToString, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ident: ident.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes output lines to stdout as they are produced, or the
+/// listed entries as a single json array once complete when json
+/// output has been requested.
 #[derive(Default)]
-pub struct Console {}
+pub struct Console {
+    buffered: Vec<Entry>,
+}
 
 impl Output for Console {
     fn println(&mut self, line: String) {
         println!("{line}");
     }
 
+    fn entry(&mut self, entry: Entry) {
+        if Reporter::current().is_json() {
+            self.buffered.push(entry);
+        } else {
+            self.println(entry.text);
+        }
+    }
+
     fn warn(&mut self, line: String) {
         tracing::warn!("{line}");
     }
+
+    fn flush(&mut self) -> Result<()> {
+        let lines = std::mem::take(&mut self.buffered);
+        Reporter::current().report(&lines, || Ok(()))
+    }
 }
 
 /// List packages in one or more repositories
@@ -79,6 +147,20 @@ pub struct Ls<Output: Default = Console> {
     #[clap(long)]
     host: bool,
 
+    /// List the packages embedded in this package instead
+    ///
+    /// Given a name/version, the embedded packages of all its builds
+    /// are listed.
+    #[clap(long, value_name = "PKG", conflicts_with_all = ["recursive", "NAME[/VERSION]"])]
+    embedded_of: Option<String>,
+
+    /// After listing, continue to report new package builds as they
+    /// are published to the repositories, until interrupted
+    ///
+    /// Given a name, only the new builds of that package are reported.
+    #[clap(long, conflicts_with_all = ["recursive", "embedded_of"])]
+    watch: bool,
+
     /// Given a name, list versions. Given a name/version list builds.
     ///
     /// If nothing is provided, list all available packages.
@@ -94,6 +176,79 @@ impl<T: Output> Run for Ls<T> {
     type Output = i32;
 
     async fn run(&mut self) -> Result<Self::Output> {
+        let code = self.list().await?;
+        self.output.flush()?;
+        if self.watch && code == 0 {
+            return self.watch_builds().await;
+        }
+        Ok(code)
+    }
+}
+
+impl<T: Output> CommandArgs for Ls<T> {
+    fn get_positional_args(&self) -> Vec<String> {
+        // The important positional args for a ls are the packages
+        match &self.package {
+            Some(pkg) => vec![pkg.clone()],
+            None => vec![],
+        }
+    }
+}
+
+impl<T: Output> Ls<T> {
+    /// Report the builds that are published to any of the
+    /// repositories, until interrupted.
+    async fn watch_builds(&mut self) -> Result<i32> {
+        let name = match &self.package {
+            Some(package) => {
+                let name = package.split('/').next().unwrap_or_default();
+                Some(PkgName::new(name)?.to_owned())
+            }
+            None => None,
+        };
+        let repos = self.repos.get_repos_for_non_destructive_operation().await?;
+        let mut streams = Vec::with_capacity(repos.len());
+        for (repo_name, repo) in repos.iter() {
+            // a chain is watched through each of the repositories within it
+            let members = match repo {
+                storage::RepositoryHandle::Chained(chain) => chain.repos().iter().collect(),
+                repo => vec![repo],
+            };
+            for repo in members {
+                let builds = match repo {
+                    storage::RepositoryHandle::SPFS(repo) => {
+                        repo.watch_package_builds(name.as_deref()).boxed()
+                    }
+                    storage::RepositoryHandle::SPFSWithVerbatimTags(repo) => {
+                        repo.watch_package_builds(name.as_deref()).boxed()
+                    }
+                    _ => {
+                        self.output
+                            .warn(format!("Cannot watch for new builds in {}", repo.name()));
+                        continue;
+                    }
+                };
+                let repo_name = repo_name.clone();
+                streams.push(builds.map_ok(move |build| (repo_name.clone(), build)));
+            }
+        }
+
+        let mut builds = futures::stream::select_all(streams);
+        while let Some((repo_name, build)) = builds.try_next().await? {
+            let text = format!(
+                "{} {}",
+                build.format_ident(),
+                format!("({repo_name})").dimmed()
+            );
+            let mut entry = Entry::new(&build, text);
+            entry.repository = Some(repo_name);
+            self.output.entry(entry);
+            self.output.flush()?;
+        }
+        Ok(0)
+    }
+
+    async fn list(&mut self) -> Result<i32> {
         let config = spk_config::get_config()?;
         if config.cli.ls.host_filtering {
             if !self.no_host {
@@ -114,6 +269,10 @@ impl<T: Output> Run for Ls<T> {
 
         let repos = self.repos.get_repos_for_non_destructive_operation().await?;
 
+        if let Some(parent) = self.embedded_of.clone() {
+            return self.list_embedded(repos, &parent, &filter_by).await;
+        }
+
         if self.recursive {
             return self.list_recursively(repos, &filter_by).await;
         }
@@ -139,7 +298,10 @@ impl<T: Output> Run for Ls<T> {
                             .map(PkgNameBuf::into),
                     )
                 }
-                results = set.into_iter().collect();
+                results = set
+                    .into_iter()
+                    .map(|name: String| Entry::new(&name, name.clone()))
+                    .collect();
             }
             Some(package) if !package.contains('/') => {
                 // Given a package name, list all the versions of the package
@@ -182,6 +344,7 @@ impl<T: Output> Run for Ls<T> {
                     let mut builds_remaining = false;
                     let mut any_deprecated = false;
                     let mut any_not_deprecated = false;
+                    let mut replacement_suggestion = None;
                     while let Some(build) = builds.pop() {
                         match repo.read_package(&build).await {
                             Ok(spec) => {
@@ -192,6 +355,9 @@ impl<T: Output> Run for Ls<T> {
 
                                 if spec.is_deprecated() {
                                     any_deprecated = true;
+                                    if replacement_suggestion.is_none() {
+                                        replacement_suggestion = spec.replacement_suggestion();
+                                    }
                                 } else {
                                     any_not_deprecated = true;
                                 }
@@ -219,11 +385,27 @@ impl<T: Output> Run for Ls<T> {
                     // closer to the next Some(package) clause?
                     if self.deprecated {
                         // show deprecated versions
+                        let suggestion = replacement_suggestion
+                            .as_ref()
+                            .map(|s| format!(" ({s})"))
+                            .unwrap_or_default();
                         if all_deprecated {
-                            results.push(format!("{version} {}", "DEPRECATED".red()));
+                            let mut entry = Entry::new(
+                                &ident,
+                                format!("{version} {}{suggestion}", "DEPRECATED".red()),
+                            );
+                            entry.deprecated = true;
+                            entry.replacement = replacement_suggestion;
+                            results.push(entry);
                             continue;
                         } else if any_deprecated {
-                            results.push(format!("{version} {}", "(partially) DEPRECATED".red()));
+                            let mut entry = Entry::new(
+                                &ident,
+                                format!("{version} {}{suggestion}", "(partially) DEPRECATED".red()),
+                            );
+                            entry.partially_deprecated = true;
+                            entry.replacement = replacement_suggestion;
+                            results.push(entry);
                             continue;
                         }
                     } else {
@@ -232,14 +414,14 @@ impl<T: Output> Run for Ls<T> {
                             continue;
                         }
                     }
-                    results.push(version.to_string());
+                    results.push(Entry::new(&ident, version.to_string()));
                 }
             }
             Some(package) => {
                 // Like the None clause, the set provides the sorting
                 // but hides when a build is in multiple repos
                 // TODO: should this include the repo name in the output?
-                let mut set = BTreeSet::new();
+                let mut set = BTreeMap::new();
                 // Given a package version (or build), list all its builds
                 let pkg = parse_ident(package)?;
                 for (_, repo) in repos {
@@ -263,31 +445,20 @@ impl<T: Output> Run for Ls<T> {
                             // Hide deprecated packages by default
                             continue;
                         }
-                        set.insert(self.format_build(&spec, &repo).await?);
+                        let entry = self.format_build(&spec, &repo).await?;
+                        set.entry(entry.text.clone()).or_insert(entry);
                     }
                 }
-                results = set.into_iter().collect();
+                results = set.into_values().collect();
             }
         }
 
-        for item in results {
-            self.output.println(item.to_string());
+        for entry in results {
+            self.output.entry(entry);
         }
         Ok(0)
     }
-}
-
-impl<T: Output> CommandArgs for Ls<T> {
-    fn get_positional_args(&self) -> Vec<String> {
-        // The important positional args for a ls are the packages
-        match &self.package {
-            Some(pkg) => vec![pkg.clone()],
-            None => vec![],
-        }
-    }
-}
 
-impl<T: Output> Ls<T> {
     async fn list_recursively(
         &mut self,
         repos: Vec<(String, storage::RepositoryHandle)>,
@@ -384,18 +555,73 @@ impl<T: Output> Ls<T> {
                         continue;
                     }
 
-                    if self.verbose > 0 {
+                    if self.verbose > 0 && !Reporter::current().is_json() {
                         print!(
                             "{:>width$} ",
                             format!("[{repo_name}]"),
                             width = max_repo_name_len + 2
                         );
                     }
-                    self.output
-                        .println((self.format_build(&spec, repo).await?).to_string());
+                    let entry = self.format_build(&spec, repo).await?;
+                    self.output.entry(entry);
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    async fn list_embedded(
+        &mut self,
+        repos: Vec<(String, storage::RepositoryHandle)>,
+        parent: &str,
+        filter_by: &Option<Vec<OptFilter>>,
+    ) -> Result<i32> {
+        let parent = parse_ident(parent)?;
+        // the set provides the sorting but hides when
+        // a stub is in multiple repos
+        let mut set = BTreeMap::new();
+        for (_, repo) in repos.iter() {
+            let parents = match parent.build() {
+                Some(build) => vec![parent.to_build(build.clone())],
+                None => repo
+                    .list_package_builds(parent.as_version())
+                    .await?
+                    .into_iter()
+                    .filter(|b| !b.is_source() && !b.is_embedded())
+                    .collect(),
+            };
+            for build in parents {
+                let stubs = match repo.list_embedded_stubs(&build).await {
+                    Ok(stubs) => stubs,
+                    Err(storage::Error::PackageNotFound(_)) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                for stub in stubs {
+                    let spec = match repo.read_package(&stub).await {
+                        Ok(spec) => spec,
+                        Err(err) => {
+                            self.output.warn(format!("Skipping {stub}: {err}"));
+                            continue;
+                        }
+                    };
+
+                    if !spec.matches_all_filters(filter_by) {
+                        continue;
+                    }
+
+                    if spec.is_deprecated() && !self.deprecated {
+                        // Hide deprecated packages by default
+                        continue;
+                    }
+                    let entry = self.format_build(&spec, repo).await?;
+                    set.entry(entry.text.clone()).or_insert(entry);
                 }
             }
         }
+
+        for entry in set.into_values() {
+            self.output.entry(entry);
+        }
         Ok(0)
     }
 
@@ -458,7 +684,7 @@ impl<T: Output> Ls<T> {
                 // One version with a matching build is enough for
                 // this package to be counted has matching
                 if found_a_match {
-                    self.output.println(package.to_string());
+                    self.output.entry(Entry::new(&package, package.to_string()));
                     seen.insert(package);
                     break;
                 }
@@ -468,15 +694,23 @@ impl<T: Output> Ls<T> {
         Ok(0)
     }
 
-    async fn format_build(&self, spec: &Spec, repo: &storage::RepositoryHandle) -> Result<String> {
+    async fn format_build(&self, spec: &Spec, repo: &storage::RepositoryHandle) -> Result<Entry> {
         let mut item = spec.ident().format_ident();
+        let mut entry = Entry::new(spec.ident(), String::new());
+        entry.repository = Some(repo.name().to_string());
         if spec.is_deprecated() {
+            entry.deprecated = true;
             let _ = write!(item, " {}", "DEPRECATED".red());
+            if let Some(suggestion) = spec.replacement_suggestion() {
+                let _ = write!(item, " ({suggestion})");
+                entry.replacement = Some(suggestion);
+            }
         }
 
         // /src packages have no further info to display
         if spec.ident().is_source() {
-            return Ok(item);
+            entry.text = item;
+            return Ok(entry);
         }
 
         // Based on the verbosity, display more details for the
@@ -485,13 +719,27 @@ impl<T: Output> Ls<T> {
             let options = spec.option_values();
             item.push(' ');
             item.push_str(&options.format_option_map());
+            entry.options = Some(
+                options
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect(),
+            );
         }
 
         if self.verbose > 1 || self.components {
             let cmpts = repo.read_components(spec.ident()).await?;
+            let components = ComponentSet::from(cmpts.keys().cloned());
             item.push(' ');
-            item.push_str(&ComponentSet::from(cmpts.keys().cloned()).format_components());
+            item.push_str(&components.format_components());
+            let mut names = components
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            names.sort();
+            entry.components = Some(names);
         }
-        Ok(item)
+        entry.text = item;
+        Ok(entry)
     }
 }
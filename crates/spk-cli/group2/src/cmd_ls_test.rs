@@ -7,10 +7,10 @@ use futures::prelude::*;
 use relative_path::RelativePathBuf;
 use spfs::config::Remote;
 use spfs::prelude::*;
-use spfs::storage::EntryType;
+use spfs::storage::{EntryType, TagNamespaceBuf};
 use spfs::RemoteAddress;
 use spk_schema::foundation::ident_component::Component;
-use spk_schema::ident_ops::VerbatimTagStrategy;
+use spk_schema::ident_ops::{NormalizedTagStrategy, VerbatimTagStrategy};
 use spk_schema::name::OptName;
 use spk_schema::recipe;
 use spk_solve::option_map::HOST_OPTIONS;
@@ -18,11 +18,12 @@ use spk_solve::spec;
 use spk_storage::fixtures::*;
 use spk_storage::RepositoryHandle;
 
-use super::{Ls, Output, Run};
+use super::{Entry, Ls, Output, Run};
 
 #[derive(Default)]
 struct OutputToVec {
     vec: Vec<String>,
+    entries: Vec<Entry>,
     warnings: Vec<String>,
 }
 
@@ -31,6 +32,11 @@ impl Output for OutputToVec {
         self.vec.push(line);
     }
 
+    fn entry(&mut self, entry: Entry) {
+        self.vec.push(entry.text.clone());
+        self.entries.push(entry);
+    }
+
     fn warn(&mut self, line: String) {
         self.warnings.push(line);
     }
@@ -96,6 +102,52 @@ async fn test_ls_shows_remote_packages_with_no_host() {
     assert_ne!(opt.ls.output.vec.len(), 0);
 }
 
+/// The builds listed by `spk ls` carry structured details that
+/// are reported when json output is requested.
+#[tokio::test]
+async fn test_ls_entries_describe_builds() {
+    let mut rt = spfs_runtime().await;
+    let remote_repo = spfsrepo().await;
+
+    rt.add_remote_repo(
+        "origin",
+        Remote::Address(RemoteAddress {
+            address: remote_repo.address().clone(),
+        }),
+    )
+    .unwrap();
+
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    remote_repo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    remote_repo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let mut opt = Opt::try_parse_from(["ls", "--no-host", "--components", "my-pkg/1.0.0"]).unwrap();
+    opt.ls.run().await.unwrap();
+    assert_eq!(opt.ls.output.entries.len(), 1);
+    let entry = &opt.ls.output.entries[0];
+    assert_eq!(entry.ident, "my-pkg/1.0.0/BGSHW3CN");
+    assert_eq!(entry.repository.as_deref(), Some("origin"));
+    assert!(!entry.deprecated);
+    assert_eq!(entry.components, Some(vec!["run".to_string()]));
+
+    let json = serde_json::to_value(entry).unwrap();
+    assert_eq!(json["ident"], "my-pkg/1.0.0/BGSHW3CN");
+    assert_eq!(json["components"][0], "run");
+    assert!(
+        json.get("text").is_none(),
+        "the formatted text should not be included in the json"
+    );
+}
+
 /// `spk ls` is expected to list packages in the configured remote
 /// repositories that match the default filter for the current host
 #[tokio::test]
@@ -192,6 +244,41 @@ async fn test_ls_shows_local_and_remote_packages() {
     assert_eq!(opt.ls.output.vec.len(), 2);
 }
 
+/// `spk ls --namespace` is expected to list only the packages that were
+/// published into that tag namespace of the repositories.
+#[tokio::test]
+async fn test_ls_namespace_shows_namespaced_packages_only() {
+    let mut rt = spfs_runtime().await;
+    let remote_repo = spfsrepo().await;
+
+    rt.add_remote_repo(
+        "origin",
+        Remote::Address(RemoteAddress {
+            address: remote_repo.address().clone(),
+        }),
+    )
+    .unwrap();
+
+    let recipe = recipe!({"pkg": "my-remote-pkg/1.0.0"});
+    remote_repo.publish_recipe(&recipe).await.unwrap();
+    let mut scratch = spk_storage::remote_repository::<_, NormalizedTagStrategy>("origin")
+        .await
+        .unwrap();
+    scratch
+        .set_tag_namespace(Some(TagNamespaceBuf::new("users/test")))
+        .unwrap();
+    let recipe = recipe!({"pkg": "my-scratch-pkg/1.0.0"});
+    scratch.publish_recipe(&recipe).await.unwrap();
+
+    let mut opt = Opt::try_parse_from(["ls", "--no-host"]).unwrap();
+    opt.ls.run().await.unwrap();
+    assert_eq!(opt.ls.output.vec, vec!["my-remote-pkg".to_string()]);
+
+    let mut opt = Opt::try_parse_from(["ls", "--no-host", "--namespace", "users/test"]).unwrap();
+    opt.ls.run().await.unwrap();
+    assert_eq!(opt.ls.output.vec, vec!["my-scratch-pkg".to_string()]);
+}
+
 /// `spk ls -l` is expected to list packages in only the local repository.
 #[tokio::test]
 async fn test_ls_dash_l_shows_local_packages_only() {
@@ -484,6 +571,54 @@ async fn test_ls_hides_deprecated_version() {
     assert!(opt.ls.output.vec.first().unwrap().contains("DEPRECATED"));
 }
 
+#[tokio::test]
+async fn test_ls_shows_deprecated_replacements() {
+    let mut rt = spfs_runtime().await;
+    let remote_repo = spfsrepo().await;
+
+    rt.add_remote_repo(
+        "origin",
+        Remote::Address(RemoteAddress {
+            address: remote_repo.address().clone(),
+        }),
+    )
+    .unwrap();
+
+    let spec = spec!({
+        "pkg": "my-pkg/1.0.0/BGSHW3CN",
+        "deprecated": true,
+        "replaced_by": ["my-pkg/2.0.0", "other-pkg"],
+    });
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let mut opt = Opt::try_parse_from(["ls", "--deprecated", "my-pkg", "--no-host"]).unwrap();
+    opt.ls.run().await.unwrap();
+    assert_eq!(opt.ls.output.vec.len(), 1);
+    let line = opt.ls.output.vec.first().unwrap();
+    assert!(line.contains("DEPRECATED"));
+    assert!(
+        line.contains("use my-pkg/2.0.0 or other-pkg"),
+        "expected replacements to be listed; got: {line}"
+    );
+
+    let mut opt = Opt::try_parse_from(["ls", "--deprecated", "my-pkg/1.0.0", "--no-host"]).unwrap();
+    opt.ls.run().await.unwrap();
+    assert_eq!(opt.ls.output.vec.len(), 1);
+    let line = opt.ls.output.vec.first().unwrap();
+    assert!(
+        line.contains("use my-pkg/2.0.0 or other-pkg"),
+        "expected replacements to be listed; got: {line}"
+    );
+}
+
 #[tokio::test]
 async fn test_ls_shows_partially_deprecated_version() {
     let mut rt = spfs_runtime().await;
@@ -609,3 +744,42 @@ async fn test_ls_succeeds_for_package_saved_with_legacy_version_tag() {
     opt.ls.run().await.unwrap();
     assert_eq!(opt.ls.output.vec.len(), 1);
 }
+
+#[tokio::test]
+async fn test_ls_embedded_of() {
+    let mut rt = spfs_runtime().await;
+    let remote_repo = spfsrepo().await;
+
+    rt.add_remote_repo(
+        "origin",
+        Remote::Address(RemoteAddress {
+            address: remote_repo.address().clone(),
+        }),
+    )
+    .unwrap();
+
+    let recipe = recipe!({
+        "pkg": "my-pkg/1.0.0",
+        "install": {"embedded": [{"pkg": "my-embedded-pkg/1.0.0"}]}
+    });
+    remote_repo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({
+        "pkg": "my-pkg/1.0.0/3I42H3S6",
+        "install": {"embedded": [{"pkg": "my-embedded-pkg/1.0.0/embedded"}]}
+    });
+    remote_repo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let mut opt =
+        Opt::try_parse_from(["ls", "--no-host", "--embedded-of", "my-pkg/1.0.0"]).unwrap();
+    opt.ls.run().await.unwrap();
+    assert_eq!(opt.ls.output.vec.len(), 1, "{:?}", opt.ls.output.vec);
+    assert!(opt.ls.output.vec[0].contains("my-embedded-pkg"));
+}
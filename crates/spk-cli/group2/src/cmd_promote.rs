@@ -0,0 +1,89 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use clap::Args;
+use miette::Result;
+use spk_cli_common::{CommandArgs, Run};
+use spk_schema::ident_ops::NormalizedTagStrategy;
+use spk_schema::AnyIdent;
+use spk_storage::{self as storage, Promoter};
+
+/// Promote packages from one shared repository to another
+///
+/// Unlike publishing, promotion does not publish the packages again
+/// but tags the very same recipes, package specs and layers in the
+/// destination repository, so that they keep their digests. This is
+/// intended for moving packages through stages, eg from a staging
+/// repository into production, once they have been approved.
+#[derive(Args)]
+pub struct Promote {
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// The repository to promote packages from
+    #[clap(long, value_name = "REPO")]
+    from: String,
+
+    /// The repository to promote packages into
+    #[clap(long, value_name = "REPO")]
+    to: String,
+
+    /// Skip promoting the related source package, if any
+    #[clap(long)]
+    no_source: bool,
+
+    /// The packages to promote
+    ///
+    /// This can be an entire package version with all builds or a
+    /// single, specific build.
+    #[clap(name = "PKG", required = true)]
+    pub packages: Vec<AnyIdent>,
+}
+
+#[async_trait::async_trait]
+impl Run for Promote {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let (source, target) = tokio::try_join!(open_repo(&self.from), open_repo(&self.to))?;
+        let promoter =
+            Promoter::new(Arc::new(source), Arc::new(target)).skip_source_packages(self.no_source);
+
+        let mut promoted = Vec::new();
+        for pkg in self.packages.iter() {
+            promoted.extend(promoter.promote(pkg).await?);
+        }
+
+        if promoted.is_empty() {
+            tracing::warn!(
+                "No package builds were promoted, did you forget to specify a version number? (spk promote my-package/1.0.2 --from staging --to production)"
+            )
+        }
+
+        tracing::info!("done");
+        Ok(0)
+    }
+}
+
+/// Open the named repository, which may be the local one
+async fn open_repo(name: &str) -> storage::Result<storage::RepositoryHandle> {
+    Ok(match name {
+        "local" => storage::local_repository().await?.into(),
+        _ => storage::remote_repository::<_, NormalizedTagStrategy>(name)
+            .await?
+            .into(),
+    })
+}
+
+impl CommandArgs for Promote {
+    fn get_positional_args(&self) -> Vec<String> {
+        // The important positional args for a promote are the packages
+        self.packages
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeMap;
+
+use clap::Args;
+use colored::Colorize;
+use miette::{IntoDiagnostic, Result};
+use spfs::storage::TagNamespaceBuf;
+use spk_cli_common::{CommandArgs, Run};
+use spk_config;
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::ident_ops::NormalizedTagStrategy;
+use spk_storage as storage;
+
+/// Remove old packages from the tag namespaces of a repository
+///
+/// Packages are removed from each namespace that is listed in the
+/// `tag_namespaces.prune_after` value of the spk config, and from any
+/// namespace nested within it, once they have not been published for
+/// the configured amount of time (eg: users = "4w"). Each version is
+/// removed along with all of its builds, unless other packages in the
+/// namespace still depend on it. The data that the removed packages
+/// leave unused is removed by the next `spfs clean`.
+#[derive(Args)]
+pub struct Prune {
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// The repository to prune
+    #[clap(long, short = 'r', default_value = "origin")]
+    repo: String,
+
+    /// Only prune this one of the configured namespaces
+    #[clap(long, value_name = "NAMESPACE")]
+    namespace: Option<String>,
+
+    /// Report the packages that would be removed, without removing them
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[async_trait::async_trait]
+impl Run for Prune {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let config = spk_config::get_config()?;
+        let mut policies: BTreeMap<_, _> = config.tag_namespaces.prune_after.iter().collect();
+        if let Some(namespace) = &self.namespace {
+            policies.retain(|name, _| *name == namespace);
+            if policies.is_empty() {
+                miette::bail!(
+                    help = "Add it to tag_namespaces.prune_after in the spk config",
+                    "No prune policy is configured for the {namespace} tag namespace"
+                );
+            }
+        }
+        if policies.is_empty() {
+            tracing::warn!("No tag namespaces are configured to be pruned");
+            return Ok(0);
+        }
+
+        let repo = match self.repo.as_str() {
+            "local" => storage::local_repository().await?,
+            name => storage::remote_repository::<_, NormalizedTagStrategy>(name).await?,
+        };
+        let removed = if self.dry_run {
+            "would remove".yellow()
+        } else {
+            "removed".red()
+        };
+        let mut count = 0;
+        for (namespace, age) in policies {
+            let age = spfs::tracking::parse_duration(age)?;
+            let older_than =
+                chrono::Utc::now() - chrono::Duration::from_std(age).into_diagnostic()?;
+            let pruned = repo
+                .prune_tag_namespace(&TagNamespaceBuf::new(namespace), older_than, self.dry_run)
+                .await?;
+            for (namespace, pkg) in pruned.iter() {
+                println!(
+                    "{removed} {} {}",
+                    pkg.format_ident(),
+                    format!("({namespace})").dimmed()
+                );
+            }
+            count += pruned.len();
+        }
+
+        match self.dry_run {
+            true => tracing::info!("{count} package versions would be removed"),
+            false => tracing::info!("{count} package versions removed"),
+        }
+        Ok(0)
+    }
+}
+
+impl CommandArgs for Prune {
+    fn get_positional_args(&self) -> Vec<String> {
+        vec![]
+    }
+}
@@ -6,10 +6,11 @@ use std::sync::Arc;
 
 use clap::Args;
 use miette::Result;
-use spk_cli_common::{CommandArgs, PublishLabel, Publisher, Run};
+use spfs::storage::TagNamespaceBuf;
+use spk_cli_common::{CommandArgs, Run};
 use spk_schema::ident_ops::{NormalizedTagStrategy, VerbatimTagStrategy};
 use spk_schema::AnyIdent;
-use spk_storage as storage;
+use spk_storage::{self as storage, PublishLabel, Publisher};
 
 #[cfg(test)]
 #[path = "./cmd_publish_test.rs"]
@@ -23,9 +24,23 @@ pub struct Publish {
 
     /// The repository to publish to
     ///
-    /// Any configured spfs repository can be named here
+    /// Any configured spfs repository can be named here. When given
+    /// more than once, packages are published to all of the named
+    /// repositories or, if any one of them fails, to none of them.
     #[clap(long, short = 'r', default_value = "origin")]
-    target_repo: String,
+    target_repo: Vec<String>,
+
+    /// Publish into this spfs tag namespace of the repositories, where
+    /// packages do not collide with those published normally (eg: users/jdoe)
+    #[clap(long, value_name = "NAMESPACE", conflicts_with = "scratch")]
+    namespace: Option<String>,
+
+    /// Publish into the scratch tag namespace of the current user
+    ///
+    /// The scratch namespace is set by the `tag_namespaces.scratch`
+    /// value of the spk config (eg: users/{user}).
+    #[clap(long)]
+    scratch: bool,
 
     /// Skip publishing the related source package, if any
     ///
@@ -67,20 +82,35 @@ impl Run for Publish {
     type Output = i32;
 
     async fn run(&mut self) -> Result<Self::Output> {
-        let (source, target) = tokio::try_join!(storage::local_repository(), async {
-            if self.legacy_spk_version_tags_for_writes {
-                Ok(Into::<storage::RepositoryHandle>::into(
-                    storage::remote_repository::<_, VerbatimTagStrategy>(&self.target_repo).await?,
-                ))
-            } else {
-                Ok(Into::<storage::RepositoryHandle>::into(
-                    storage::remote_repository::<_, NormalizedTagStrategy>(&self.target_repo)
-                        .await?,
-                ))
-            }
-        })?;
-
-        let publisher = Publisher::new(Arc::new(source.into()), Arc::new(target))
+        let legacy = self.legacy_spk_version_tags_for_writes;
+        let namespace = match (&self.namespace, self.scratch) {
+            (Some(namespace), _) => Some(TagNamespaceBuf::new(namespace)),
+            (None, true) => match spk_config::get_config()?
+                .tag_namespaces
+                .scratch_for_current_user()
+            {
+                Some(namespace) => Some(TagNamespaceBuf::new(namespace)),
+                None => miette::bail!(
+                    help = "Set tag_namespaces.scratch in the spk config, or use --namespace",
+                    "No scratch tag namespace is configured"
+                ),
+            },
+            (None, false) => None,
+        };
+        let namespace = namespace.as_ref();
+        let (source, targets) = tokio::try_join!(
+            storage::local_repository(),
+            futures::future::try_join_all(self.target_repo.iter().map(|name| async move {
+                if legacy {
+                    open_target::<VerbatimTagStrategy>(name, namespace).await
+                } else {
+                    open_target::<NormalizedTagStrategy>(name, namespace).await
+                }
+            }))
+        )?;
+
+        let publisher = Publisher::new(Arc::new(source.into()), Arc::clone(&targets[0]))
+            .with_targets(targets)
             .skip_source_packages(self.no_source)
             .allow_existing_with_label(self.allow_existing_with_label.clone())
             .force(self.force);
@@ -101,6 +131,21 @@ impl Run for Publish {
     }
 }
 
+/// Open a repository to publish to, in the given tag namespace if any
+async fn open_target<TagStrategy>(
+    name: &str,
+    namespace: Option<&TagNamespaceBuf>,
+) -> storage::Result<Arc<storage::RepositoryHandle>>
+where
+    storage::SpfsRepository<TagStrategy>: Into<storage::RepositoryHandle>,
+{
+    let mut repo = storage::remote_repository::<_, TagStrategy>(name).await?;
+    if let Some(namespace) = namespace {
+        repo.set_tag_namespace(Some(namespace.clone()))?;
+    }
+    Ok(Arc::new(repo.into()))
+}
+
 impl CommandArgs for Publish {
     fn get_positional_args(&self) -> Vec<String> {
         // The important positional args for a publish are the packages
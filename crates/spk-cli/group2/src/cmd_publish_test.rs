@@ -8,7 +8,7 @@ use relative_path::RelativePathBuf;
 use rstest::rstest;
 use spfs::config::Remote;
 use spfs::prelude::*;
-use spfs::storage::EntryType;
+use spfs::storage::{EntryType, TagNamespaceBuf};
 use spfs::RemoteAddress;
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::ident_ops::{NormalizedTagStrategy, VerbatimTagStrategy};
@@ -160,3 +160,40 @@ async fn test_publish_writes_with_legacy_version_tags(
         _ => panic!("expected SPFSWithVerbatimTags"),
     }
 }
+
+#[tokio::test]
+async fn test_publish_into_tag_namespace() {
+    let mut rt = spfs_runtime().await;
+    let remote_repo = spfsrepo().await;
+
+    rt.add_remote_repo(
+        "origin",
+        Remote::Address(RemoteAddress {
+            address: remote_repo.address().clone(),
+        }),
+    )
+    .unwrap();
+
+    let recipe = recipe!({"pkg": "my-local-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+
+    let mut opt =
+        Opt::try_parse_from(["publish", "--namespace", "users/test", "my-local-pkg/1.0.0"])
+            .unwrap();
+    opt.publish.run().await.unwrap();
+
+    assert!(
+        remote_repo.list_packages().await.unwrap().is_empty(),
+        "packages should not be published outside of the namespace"
+    );
+    let mut scratch = spk_storage::remote_repository::<_, NormalizedTagStrategy>("origin")
+        .await
+        .unwrap();
+    scratch
+        .set_tag_namespace(Some(TagNamespaceBuf::new("users/test")))
+        .unwrap();
+    assert_eq!(
+        scratch.list_packages().await.unwrap(),
+        vec!["my-local-pkg".parse().unwrap()]
+    );
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::BTreeSet;
 use std::io::Write;
 
 use clap::Args;
@@ -12,8 +13,8 @@ use spk_cli_common::{flags, CommandArgs, Run};
 use spk_schema::foundation::format::FormatIdent;
 use spk_schema::ident::parse_ident;
 use spk_schema::spec_ops::WithVersion;
-use spk_schema::{BuildIdent, VersionIdent};
-use spk_storage as storage;
+use spk_schema::{AnyIdent, BuildIdent, VersionIdent};
+use spk_storage::{self as storage, DependencyIndex, DependencyKind};
 
 #[cfg(test)]
 #[path = "./cmd_remove_test.rs"]
@@ -30,6 +31,10 @@ pub struct Remove {
     #[clap(short, long)]
     yes: bool,
 
+    /// Remove packages even when other builds in the repository depend on them
+    #[clap(long)]
+    force: bool,
+
     #[clap(name = "PKG", required = true)]
     packages: Vec<String>,
 }
@@ -69,27 +74,77 @@ impl Run for Remove {
                     }
                 }
             }
+        }
 
-            for (repo_name, repo) in repos.iter() {
+        let mut removals = Vec::new();
+        for (repo_name, repo) in repos.iter() {
+            let mut versions = Vec::new();
+            for name in &self.packages {
                 let pkg = parse_ident(name)?;
-                let versions = if name.contains('/') {
-                    vec![pkg]
+                if name.contains('/') {
+                    versions.push(pkg);
                 } else {
-                    repo.list_package_versions(pkg.name())
-                        .await?
-                        .iter()
-                        .map(|v| pkg.with_version((**v).clone()))
-                        .collect()
-                };
-
-                for version in versions {
-                    match version.into_inner() {
-                        (version, None) => {
-                            remove_all(repo_name, repo, &version).await?;
-                        }
-                        (version, Some(build)) => {
-                            remove_build(repo_name, repo, &version.into_build(build)).await?;
-                        }
+                    versions.extend(
+                        repo.list_package_versions(pkg.name())
+                            .await?
+                            .iter()
+                            .map(|v| pkg.with_version((**v).clone())),
+                    );
+                }
+            }
+            // everything being removed from the repository is checked
+            // together, so that removing a package along with the
+            // builds that use it is not refused
+            let dependents = find_dependents(repo, &versions).await?;
+            removals.push((repo_name, repo, versions, dependents));
+        }
+
+        // Nothing is removed until all the packages have been checked,
+        // so that a refused removal does not leave any of them half done
+        let mut refused = false;
+        for (repo_name, _, _, dependents) in removals.iter() {
+            if dependents.is_empty() {
+                continue;
+            }
+            eprintln!(
+                "{}",
+                format!(
+                    "{} build(s) in {repo_name} depend on the packages being removed:",
+                    dependents.iter().map(|d| &d.build).dedup().count()
+                )
+                .yellow()
+            );
+            for dependent in dependents {
+                eprintln!(
+                    "  {} ({} requirement {})",
+                    dependent.build.format_ident(),
+                    dependent.kind,
+                    dependent.request
+                );
+            }
+            refused |= !self.force;
+        }
+        if refused {
+            eprintln!(
+                "{}",
+                "Removal cancelled, use --force to remove them anyway".yellow()
+            );
+            return Ok(1);
+        }
+
+        for (repo_name, repo, versions, dependents) in removals {
+            let broken = dependents
+                .into_iter()
+                .map(|dependent| dependent.build)
+                .dedup()
+                .collect::<Vec<_>>();
+            for version in versions {
+                match version.into_inner() {
+                    (version, None) => {
+                        remove_all(repo_name, repo, &version, &broken).await?;
+                    }
+                    (version, Some(build)) => {
+                        remove_build(repo_name, repo, &version.into_build(build), &broken).await?;
                     }
                 }
             }
@@ -105,12 +160,58 @@ impl CommandArgs for Remove {
     }
 }
 
+/// A build that depends on a package that is being removed,
+/// and that no remaining build of that package can satisfy.
+struct Dependent {
+    build: BuildIdent,
+    kind: DependencyKind,
+    /// The package that it requires, eg: `python/~3.7.3`
+    request: String,
+}
+
+/// Find the builds in the repository that depend on the given packages.
+///
+/// The dependency index of the repository is used when it has one,
+/// otherwise the dependencies of every build are read to create it.
+async fn find_dependents(
+    repo: &storage::RepositoryHandle,
+    targets: &[AnyIdent],
+) -> Result<Vec<Dependent>> {
+    let mut removed = BTreeSet::new();
+    for target in targets {
+        match target.build() {
+            Some(build) => {
+                removed.insert(target.to_build(build.clone()));
+            }
+            None => removed.extend(repo.list_package_builds(target.as_version()).await?),
+        }
+    }
+    if removed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let index = match repo.read_dependency_index().await? {
+        Some(index) => index,
+        None => DependencyIndex::from_repository(&**repo).await?,
+    };
+    Ok(index
+        .broken_by_removal(&removed)
+        .into_iter()
+        .map(|(build, dep)| Dependent {
+            build: build.clone(),
+            kind: dep.kind,
+            request: dep.to_string(),
+        })
+        .collect())
+}
+
 async fn remove_build(
     repo_name: &str,
     repo: &storage::RepositoryHandle,
     pkg: &BuildIdent,
+    broken: &[BuildIdent],
 ) -> Result<()> {
-    remove_build_impl(repo_name, repo, pkg, 0).await
+    remove_build_impl(repo_name, repo, pkg, 0, broken).await
 }
 
 async fn remove_build_impl(
@@ -118,6 +219,7 @@ async fn remove_build_impl(
     repo: &storage::RepositoryHandle,
     pkg: &BuildIdent,
     build_index: usize,
+    broken: &[BuildIdent],
 ) -> Result<()> {
     let repo_name = repo_name.bold();
     let pretty_pkg = pkg.format_ident();
@@ -128,7 +230,17 @@ async fn remove_build_impl(
         tracing::info!("removed recipe {pretty_pkg: >25} from {repo_name}")
     }
     if package.is_ok() {
-        tracing::info!("removed build  {pretty_pkg: >25} from {repo_name}")
+        tracing::info!("removed build  {pretty_pkg: >25} from {repo_name}");
+        // the build is already gone, so failing to leave a
+        // tombstone for it should not fail the removal
+        let tombstone = storage::Tombstone::new(pkg.clone(), broken.to_vec());
+        let written = match tombstone {
+            Ok(tombstone) => repo.write_tombstone(&tombstone).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = written {
+            tracing::warn!("failed to leave a tombstone for {pretty_pkg} in {repo_name}: {err}");
+        }
     }
 
     // When deleting multiple builds of the same package (by calling
@@ -174,6 +286,7 @@ async fn remove_all(
     repo_name: &str,
     repo: &storage::RepositoryHandle,
     pkg: &VersionIdent,
+    broken: &[BuildIdent],
 ) -> Result<()> {
     let mut deleted_something = false;
 
@@ -186,7 +299,7 @@ async fn remove_all(
         .filter(|build| !build.is_embedded())
         .enumerate()
     {
-        remove_build_impl(repo_name, repo, build, build_index).await?;
+        remove_build_impl(repo_name, repo, build, build_index, broken).await?;
         deleted_something = true;
     }
 
@@ -143,3 +143,107 @@ async fn test_remove_succeeds_for_package_saved_with_legacy_version_tag(
         }
     }
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_remove_refuses_to_break_dependents() {
+    let rt = spfs_runtime().await;
+    let components = vec![(Component::Run, empty_layer_digest())]
+        .into_iter()
+        .collect();
+    rt.tmprepo
+        .publish_recipe(&recipe!({"pkg": "my-dep/1.0.0"}))
+        .await
+        .unwrap();
+    let dep = spec!({"pkg": "my-dep/1.0.0/3I42H3S6"});
+    rt.tmprepo.publish_package(&dep, &components).await.unwrap();
+    let app = spec!({
+        "pkg": "my-app/1.0.0/3I42H3S6",
+        "build": {"options": [{"pkg": "my-dep", "static": "~1.0.0"}]},
+    });
+    rt.tmprepo.publish_package(&app, &components).await.unwrap();
+
+    let mut opt = Opt::try_parse_from(["remove", "--yes", "my-dep/1.0.0"]).unwrap();
+    assert_eq!(opt.remove.run().await.unwrap(), 1);
+    assert!(
+        rt.tmprepo.read_package(dep.ident()).await.is_ok(),
+        "a build with dependents should not be removed without --force"
+    );
+
+    let mut opt = Opt::try_parse_from(["remove", "--yes", "--force", "my-dep/1.0.0"]).unwrap();
+    assert_eq!(opt.remove.run().await.unwrap(), 0);
+    assert!(rt.tmprepo.read_package(dep.ident()).await.is_err());
+    let tombstone = rt
+        .tmprepo
+        .read_tombstone(dep.ident())
+        .await
+        .unwrap()
+        .expect("a tombstone should be left for the removed build");
+    assert_eq!(tombstone.broken, vec![app.ident().clone()]);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_remove_allows_dependents_satisfied_by_other_versions() {
+    let rt = spfs_runtime().await;
+    let components = vec![(Component::Run, empty_layer_digest())]
+        .into_iter()
+        .collect();
+    let dep = spec!({"pkg": "my-dep/1.0.0/3I42H3S6"});
+    rt.tmprepo.publish_package(&dep, &components).await.unwrap();
+    let newer = spec!({"pkg": "my-dep/1.0.1/3I42H3S6"});
+    rt.tmprepo
+        .publish_package(&newer, &components)
+        .await
+        .unwrap();
+    let app = spec!({
+        "pkg": "my-app/1.0.0/3I42H3S6",
+        "build": {"options": [{"pkg": "my-dep", "static": "~1.0.0"}]},
+    });
+    rt.tmprepo.publish_package(&app, &components).await.unwrap();
+
+    let mut opt = Opt::try_parse_from(["remove", "--yes", "my-dep/1.0.0/3I42H3S6"]).unwrap();
+    assert_eq!(
+        opt.remove.run().await.unwrap(),
+        0,
+        "my-dep/1.0.1 still satisfies the build of my-app"
+    );
+    let tombstone = rt
+        .tmprepo
+        .read_tombstone(dep.ident())
+        .await
+        .unwrap()
+        .expect("a tombstone should be left for the removed build");
+    assert!(tombstone.broken.is_empty());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_remove_allows_removing_dependents_together() {
+    let rt = spfs_runtime().await;
+    let components = vec![(Component::Run, empty_layer_digest())]
+        .into_iter()
+        .collect();
+    let dep = spec!({"pkg": "my-dep/1.0.0/3I42H3S6"});
+    rt.tmprepo.publish_package(&dep, &components).await.unwrap();
+    let app = spec!({
+        "pkg": "my-app/1.0.0/3I42H3S6",
+        "install": {"requirements": [{"pkg": "my-dep/1.0.0"}]},
+    });
+    rt.tmprepo.publish_package(&app, &components).await.unwrap();
+
+    let mut opt = Opt::try_parse_from([
+        "remove",
+        "--yes",
+        "my-dep/1.0.0/3I42H3S6",
+        "my-app/1.0.0/3I42H3S6",
+    ])
+    .unwrap();
+    assert_eq!(
+        opt.remove.run().await.unwrap(),
+        0,
+        "nothing is left that depends on my-dep"
+    );
+    assert!(rt.tmprepo.read_package(dep.ident()).await.is_err());
+    assert!(rt.tmprepo.read_package(app.ident()).await.is_err());
+}
@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+pub mod cmd_hist;
 pub mod cmd_ls;
 pub mod cmd_new;
 pub mod cmd_num_variants;
+pub mod cmd_promote;
+pub mod cmd_prune;
 pub mod cmd_publish;
 pub mod cmd_remove;
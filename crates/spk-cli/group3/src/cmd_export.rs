@@ -8,19 +8,24 @@ use clap::{Args, ValueHint};
 use colored::Colorize;
 use miette::Result;
 use spk_cli_common::{flags, CommandArgs, Run};
+use spk_schema::foundation::option_map::OptionMap;
 use spk_schema::ident_ops::{NormalizedTagStrategy, VerbatimTagStrategy};
+use spk_schema::AnyIdent;
+use spk_solve::{self as solve, PackageSource};
 use spk_storage as storage;
 
 #[cfg(test)]
 #[path = "./cmd_export_test.rs"]
 mod cmd_export_test;
 
-/// Export a package as a tar file
+/// Export a package, or a whole environment, as a tar file
 #[derive(Args)]
 pub struct Export {
     #[clap(flatten)]
     pub repos: flags::Repositories,
     #[clap(flatten)]
+    pub repo_order: flags::RepositoryOrder,
+    #[clap(flatten)]
     pub options: flags::Options,
     #[clap(flatten)]
     pub requests: flags::Requests,
@@ -32,6 +37,13 @@ pub struct Export {
     #[clap(name = "PKG")]
     pub package: String,
 
+    /// Export every package in the resolved environment of PKG, rather than just PKG
+    ///
+    /// The resulting archive can be imported on a machine with no access
+    /// to the original repositories in order to recreate the environment.
+    #[clap(long)]
+    pub env: bool,
+
     /// The file to export into (Defaults to the name and version of the package)
     #[arg(value_hint = ValueHint::FilePath, value_name = "FILE")]
     pub filename: Option<std::path::PathBuf>,
@@ -59,26 +71,33 @@ impl Run for Export {
             .map(|(_, r)| Arc::new(r))
             .collect::<Vec<_>>();
 
-        let pkg = self
-            .requests
-            .parse_idents(&options, [self.package.as_str()], &repos)
-            .await?
-            .pop()
-            .unwrap();
-
-        let mut build = String::new();
-        if let Some(b) = pkg.build() {
-            build = format!("_{b}");
-        }
-        let filename = self.filename.clone().unwrap_or_else(|| {
-            std::path::PathBuf::from(format!("{}_{}{build}.spk", pkg.name(), pkg.version()))
-        });
+        let (pkgs, default_filename) = if self.env {
+            self.resolve_environment(&options, &repos).await?
+        } else {
+            let pkg = self
+                .requests
+                .parse_idents(&options, [self.package.as_str()], &repos)
+                .await?
+                .pop()
+                .unwrap();
+            let mut build = String::new();
+            if let Some(b) = pkg.build() {
+                build = format!("_{b}");
+            }
+            let filename = format!("{}_{}{build}.spk", pkg.name(), pkg.version());
+            (vec![pkg], filename)
+        };
+
+        let filename = self
+            .filename
+            .clone()
+            .unwrap_or_else(|| std::path::PathBuf::from(default_filename));
         // TODO: this doesn't take the repos as an argument, but probably
         // should. It assumes/uses 'local' and 'origin' repos internally.
         let res = if self.legacy_spk_version_tags_for_writes {
-            storage::export_package::<VerbatimTagStrategy>(&pkg, &filename).await
+            storage::export_packages::<VerbatimTagStrategy, _>(pkgs, &filename).await
         } else {
-            storage::export_package::<NormalizedTagStrategy>(&pkg, &filename).await
+            storage::export_packages::<NormalizedTagStrategy, _>(pkgs, &filename).await
         };
         if let Err(spk_storage::Error::PackageNotFound(_)) = res {
             tracing::warn!("Ensure that you are specifying at least a package and");
@@ -95,6 +114,46 @@ impl Run for Export {
     }
 }
 
+impl Export {
+    /// Solve the environment for the requested package, returning
+    /// every package in it that can be exported and the default
+    /// name for the archive
+    async fn resolve_environment(
+        &self,
+        options: &OptionMap,
+        repos: &[Arc<storage::RepositoryHandle>],
+    ) -> Result<(Vec<AnyIdent>, String)> {
+        let requests = self
+            .requests
+            .parse_requests([self.package.as_str()], &self.options, repos)
+            .await?;
+        let name = match requests.first() {
+            Some(solve::Request::Pkg(request)) => request.pkg.name.to_string(),
+            _ => self.package.clone(),
+        };
+        let config = spk_config::get_config()?;
+        let mut solver = solve::Solver::default();
+        solver.update_options(options.clone());
+        solver.set_namespace_routes(config.namespaces.clone());
+        solver.set_repository_priorities(self.repo_order.get_repository_priorities()?);
+        solver.set_binary_only(true);
+        for repo in repos.iter() {
+            solver.add_repository(Arc::clone(repo));
+        }
+        for request in requests {
+            solver.add_request(request);
+        }
+        let solution = solver.solve().await?;
+
+        let pkgs = solution
+            .items()
+            .filter(|item| matches!(item.source, PackageSource::Repository { .. }))
+            .map(|item| item.spec.ident().to_any())
+            .collect();
+        Ok((pkgs, format!("{name}_env.spk")))
+    }
+}
+
 impl CommandArgs for Export {
     fn get_positional_args(&self) -> Vec<String> {
         // The important positional args for an export are the packages
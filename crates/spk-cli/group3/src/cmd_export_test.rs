@@ -96,6 +96,7 @@ async fn test_export_works_with_missing_builds() {
             "objects".to_string(),
             "payloads".to_string(),
             "renders".to_string(),
+            "spk-archive.json".to_string(),
             "tags".to_string(),
             "tags/spk".to_string(),
             "tags/spk/pkg".to_string(),
@@ -3,9 +3,8 @@
 // https://github.com/spkenv/spk
 
 use clap::Args;
-use futures::TryStreamExt;
 use miette::{Context, Result};
-use spfs::storage::TagStorage;
+use spfs::operation::CancellationToken;
 use spk_cli_common::{CommandArgs, Run};
 
 #[cfg(test)]
@@ -18,6 +17,13 @@ pub struct Import {
     #[clap(flatten)]
     sync: spfs_cli_common::Sync,
 
+    /// Refuse to import archives that were created without a manifest
+    ///
+    /// Archives created by older versions of spk do not contain a
+    /// manifest, and so their contents cannot be verified.
+    #[clap(long)]
+    pub require_manifest: bool,
+
     /// The archive to import from
     #[clap(name = "FILE", required = true)]
     pub files: Vec<std::path::PathBuf>,
@@ -30,25 +36,27 @@ impl Run for Import {
     async fn run(&mut self) -> Result<Self::Output> {
         let mut summary = spfs::sync::SyncSummary::default();
         let local_repo = spk_storage::local_repository().await?;
-        // src and dst are the same here which is useless, but we will
-        // be using this syncer to create more useful ones for each archive
-        let syncer = self.sync.get_syncer(&local_repo, &local_repo);
+        // stop the import with an error rather than leaving it to be
+        // killed part way through a sync when interrupted
+        let cancellation = CancellationToken::new();
+        tokio::spawn({
+            let cancellation = cancellation.clone();
+            async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    cancellation.cancel();
+                }
+            }
+        });
+        // src and dst are the same here which is useless, but the
+        // import creates more useful ones for each archive
+        let syncer = self
+            .sync
+            .get_syncer(&local_repo, &local_repo)
+            .with_cancellation(cancellation);
         for filename in self.files.iter() {
-            let tar_repo = spfs::storage::tar::TarRepository::open(&filename).await?;
-            let tar_repo: spfs::storage::RepositoryHandle = tar_repo.into();
-            let env_spec = tar_repo
-                .iter_tags()
-                .map_ok(|(spec, _)| spec)
-                .try_collect()
-                .await
-                .wrap_err("Failed to collect tags from archive")?;
-            tracing::info!(archive = ?filename, "importing");
-            summary += syncer
-                .clone_with_source(&tar_repo)
-                .sync_env(env_spec)
+            summary += spk_storage::import_archive(filename, &syncer, self.require_manifest)
                 .await
-                .wrap_err("Failed to sync archived data")?
-                .summary();
+                .wrap_err_with(|| format!("Failed to import {filename:?}"))?;
         }
         tracing::info!("{:#?}", summary);
         Ok(0)
@@ -51,6 +51,7 @@ async fn test_archive_io() {
             "objects".to_string(),
             "payloads".to_string(),
             "renders".to_string(),
+            "spk-archive.json".to_string(),
             "tags".to_string(),
             "tags/spk".to_string(),
             "tags/spk/pkg".to_string(),
@@ -74,7 +75,9 @@ async fn test_archive_io() {
             check: false,
             max_concurrent_manifests: 10,
             max_concurrent_payloads: 10,
+            limit_rate: None,
         },
+        require_manifest: true,
         files: vec![filename],
     }
     .run()
@@ -0,0 +1,100 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::Args;
+use itertools::Itertools;
+use miette::{bail, Result};
+use spk_cli_common::{flags, CommandArgs, Run};
+use spk_schema::ident::parse_ident;
+use spk_schema::ident_component::Component;
+use spk_schema::BuildIdent;
+use spk_storage::RepositoryHandle;
+
+/// Compare the files of two package builds
+#[derive(Args)]
+pub struct Diff {
+    #[clap(flatten)]
+    pub repos: flags::Repositories,
+
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only compare the files of these components (defaults to all components)
+    #[clap(long = "component", short = 'c', value_name = "COMPONENT")]
+    components: Vec<Component>,
+
+    /// The package to use as the base of the comparison (eg: my-pkg/1.0)
+    ///
+    /// A build must be given when the version has more than one
+    /// binary build.
+    #[clap(value_name = "FROM")]
+    from: String,
+
+    /// The package to compare against the base (eg: my-pkg/1.1)
+    #[clap(value_name = "TO")]
+    to: String,
+}
+
+#[async_trait::async_trait]
+impl Run for Diff {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let repo = self
+            .repos
+            .get_chained_repo_for_non_destructive_operation()
+            .await?;
+
+        let from = find_build(&repo, &self.from).await?;
+        let to = find_build(&repo, &self.to).await?;
+        tracing::debug!("comparing {from} to {to}");
+
+        let (from_manifest, to_manifest) = tokio::try_join!(
+            spk_storage::build_manifest(&repo, &from, &self.components),
+            spk_storage::build_manifest(&repo, &to, &self.components),
+        )?;
+        let changes = spfs::tracking::compute_changeset(&from_manifest, &to_manifest);
+        if changes.is_empty() {
+            tracing::info!("no changes");
+        } else {
+            println!("{}", spfs::io::format_changeset(&changes));
+        }
+        Ok(0)
+    }
+}
+
+impl CommandArgs for Diff {
+    fn get_positional_args(&self) -> Vec<String> {
+        vec![self.from.clone(), self.to.clone()]
+    }
+}
+
+/// Find the named package build in any of the given repositories.
+///
+/// When no build is specified, the version must have exactly one
+/// binary build.
+async fn find_build(repo: &RepositoryHandle, package: &str) -> Result<BuildIdent> {
+    let ident = parse_ident(package)?;
+    let builds = repo.list_package_builds(ident.as_version()).await?;
+    if let Some(build) = ident.build() {
+        return match builds.into_iter().find(|b| b.build() == build) {
+            Some(found) => Ok(found),
+            None => bail!("Package not found: {package}"),
+        };
+    }
+
+    let mut binary = builds
+        .into_iter()
+        .filter(|b| !b.is_source() && !b.is_embedded())
+        .sorted()
+        .collect_vec();
+    match binary.len() {
+        0 => bail!("Package not found: {package}"),
+        1 => Ok(binary.remove(0)),
+        _ => bail!(
+            "{package} has more than one build, please specify one of: {}",
+            binary.iter().map(|b| b.build().to_string()).join(", ")
+        ),
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::Args;
+use miette::{Context, Result};
+use spk_cli_common::{flags, CommandArgs, Reporter, Run};
+use spk_schema::foundation::option_map::HOST_OPTIONS;
+
+/// Print the options used to build and resolve packages
+///
+/// By default, this includes the options detected for the current
+/// host along with any given on the command line. The host options
+/// can be extended in the 'host_options' section of the spk config.
+#[derive(Args)]
+pub struct Options {
+    #[clap(flatten)]
+    pub options: flags::Options,
+
+    /// Only print the options for the current host
+    #[clap(long, conflicts_with = "no_host")]
+    pub host: bool,
+}
+
+#[async_trait::async_trait]
+impl Run for Options {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let options = match self.host {
+            true => HOST_OPTIONS
+                .get()
+                .wrap_err("Failed to compute options for current host")?,
+            false => self.options.get_options()?,
+        };
+
+        Reporter::current().report(&options, || {
+            for (name, value) in options.iter() {
+                println!("{name}: {value}");
+            }
+            Ok(())
+        })?;
+        Ok(0)
+    }
+}
+
+impl CommandArgs for Options {
+    fn get_positional_args(&self) -> Vec<String> {
+        // There are no important positional args for the options command
+        vec![]
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeSet, HashSet};
+
+use clap::Args;
+use colored::Colorize;
+use miette::{Context, Result};
+use serde::Serialize;
+use spk_cli_common::{flags, CommandArgs, Reporter, Run};
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::foundation::name::PkgName;
+use spk_schema::foundation::version::Version;
+use spk_schema::ident::parse_ident;
+use spk_schema::ident_component::Component;
+use spk_schema::BuildIdent;
+use spk_storage::{Dependency, DependencyIndex};
+
+/// List the builds in a repository that depend on a package
+///
+/// A build depends on a package when it was built against it, or
+/// requires it at runtime. The dependency index of a repository is
+/// used when it has one (see 'spk repo index'), otherwise every
+/// build in the repository is read, which can be slow.
+#[derive(Args)]
+pub struct Rdepends {
+    #[clap(flatten)]
+    pub repos: flags::Repositories,
+
+    /// Also list the builds that depend on those builds, up to this many levels deep
+    #[clap(long, short, default_value_t = 1)]
+    depth: usize,
+
+    /// Only list builds that use one of these components of the package
+    #[clap(long = "component", short = 'c', value_name = "COMPONENT")]
+    components: Vec<Component>,
+
+    /// The package to find the dependents of (eg: python, python/3.7.3)
+    #[clap(name = "PKG")]
+    package: String,
+}
+
+/// A build that depends on the requested package, either directly
+/// or through one of the other builds that were found
+#[derive(Debug, Serialize)]
+struct Dependent {
+    repo: String,
+    /// How many builds away from the requested package this
+    /// build is, starting with 1 for the builds that depend on it
+    depth: usize,
+    build: BuildIdent,
+    dependency: Dependency,
+}
+
+#[async_trait::async_trait]
+impl Run for Rdepends {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let pkg = parse_ident(&self.package)?;
+        let version = self.package.contains('/').then(|| pkg.version().clone());
+        let components = self.components.iter().cloned().collect::<BTreeSet<_>>();
+
+        let repos = self.repos.get_repos_for_non_destructive_operation().await?;
+        let mut dependents = Vec::new();
+        for (repo_name, repo) in repos.iter() {
+            let index = match repo.read_dependency_index().await? {
+                Some(index) => index,
+                None => {
+                    tracing::debug!("{repo_name} has no dependency index, reading every build");
+                    DependencyIndex::from_repository(&**repo)
+                        .await
+                        .wrap_err_with(|| format!("Failed to index the builds in {repo_name}"))?
+                }
+            };
+            dependents.extend(
+                find_dependents(
+                    &index,
+                    pkg.name(),
+                    version.as_ref(),
+                    &components,
+                    self.depth.max(1),
+                )
+                .into_iter()
+                .map(|(depth, build, dependency)| Dependent {
+                    repo: repo_name.clone(),
+                    depth,
+                    build,
+                    dependency,
+                }),
+            );
+        }
+
+        Reporter::current().report(&dependents, || {
+            for dependent in dependents.iter() {
+                println!(
+                    "{}{} {} {} {}",
+                    "  ".repeat(dependent.depth - 1),
+                    dependent.build.format_ident(),
+                    dependent.repo.bright_black(),
+                    dependent.dependency.kind.to_string().cyan(),
+                    dependent.dependency,
+                );
+            }
+            Ok(())
+        })?;
+
+        Ok(if dependents.is_empty() { 1 } else { 0 })
+    }
+}
+
+impl CommandArgs for Rdepends {
+    fn get_positional_args(&self) -> Vec<String> {
+        vec![self.package.clone()]
+    }
+}
+
+/// Find the builds that depend on a package, and then the builds
+/// that depend on those, up to the given depth.
+///
+/// The results are ordered so that each build is followed by the
+/// builds that depend on it, and every build is only listed once.
+/// The components only filter the builds that depend directly on
+/// the package.
+fn find_dependents(
+    index: &DependencyIndex,
+    name: &PkgName,
+    version: Option<&Version>,
+    components: &BTreeSet<Component>,
+    max_depth: usize,
+) -> Vec<(usize, BuildIdent, Dependency)> {
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = index
+        .dependents(name, version)
+        .filter(|(_, dep)| components.is_empty() || dep.uses_any_component(components))
+        .map(|(build, dep)| (1, build, dep))
+        .collect::<Vec<_>>();
+    stack.reverse();
+    while let Some((depth, build, dep)) = stack.pop() {
+        if !seen.insert(build) {
+            continue;
+        }
+        results.push((depth, build.clone(), dep.clone()));
+        if depth >= max_depth {
+            continue;
+        }
+        let next = index
+            .dependents(build.name(), Some(build.version()))
+            .filter(|(build, _)| !seen.contains(build))
+            .map(|(build, dep)| (depth + 1, build, dep))
+            .collect::<Vec<_>>();
+        stack.extend(next.into_iter().rev());
+    }
+    results
+}
@@ -8,8 +8,9 @@ use miette::Result;
 use spk_cli_common::{flags, CommandArgs, Run};
 use spk_schema::foundation::format::FormatIdent;
 use spk_schema::{Deprecate, VersionIdent};
+use spk_storage::{SearchEntry, SearchQuery};
 
-/// Search for packages by name/substring
+/// Search for packages by name/substring or by their metadata
 #[derive(Args)]
 pub struct Search {
     #[clap(flatten)]
@@ -22,8 +23,41 @@ pub struct Search {
     #[clap(long, short)]
     deprecated: bool,
 
+    /// Only show packages whose description contains all of these words
+    #[clap(long)]
+    description: Option<String>,
+
+    /// Only show packages with this label, or with a label
+    /// that contains the given value (eg: team or team=pipeline)
+    #[clap(long = "label", value_name = "KEY[=VALUE]")]
+    labels: Vec<String>,
+
+    /// Only show packages whose license contains this text
+    #[clap(long)]
+    license: Option<String>,
+
     /// The text/substring to search for in package names
-    term: String,
+    #[clap(required_unless_present_any = ["description", "labels", "license"])]
+    term: Option<String>,
+}
+
+impl Search {
+    fn query(&self) -> SearchQuery {
+        SearchQuery {
+            name: self.term.clone(),
+            description: self.description.clone(),
+            labels: self
+                .labels
+                .iter()
+                .map(|label| match label.split_once('=') {
+                    Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                    None => (label.clone(), None),
+                })
+                .collect(),
+            license: self.license.clone(),
+            deprecated: self.deprecated,
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -32,6 +66,7 @@ impl Run for Search {
 
     async fn run(&mut self) -> Result<Self::Output> {
         let repos = self.repos.get_repos_for_non_destructive_operation().await?;
+        let query = self.query();
 
         let width = repos
             .iter()
@@ -41,8 +76,15 @@ impl Run for Search {
             .unwrap_or_default();
         let mut exit = 1;
         for (repo_name, repo) in repos.iter() {
+            // The metadata of a package is only needed when searching by it,
+            // and the index saves reading the recipe of every version
+            let index = if query.is_name_only() {
+                None
+            } else {
+                repo.read_search_index().await?
+            };
             for name in repo.list_packages().await? {
-                if !name.as_str().contains(&self.term) {
+                if !query.matches_name(name.as_str()) {
                     continue;
                 }
                 let versions = repo.list_package_versions(&name).await?;
@@ -59,11 +101,22 @@ impl Run for Search {
                         continue;
                     }
 
-                    // Check recipe exists and for deprecation
+                    // Check recipe exists and for deprecation, using the
+                    // index instead of the recipe where possible
+                    let entry = match index.as_ref().and_then(|index| index.get(&ident)) {
+                        Some(entry) => Ok(entry.clone()),
+                        None => repo
+                            .read_recipe(&ident)
+                            .await
+                            .map(|recipe| SearchEntry::from_recipe(&*recipe)),
+                    };
                     let mut deprecation_status = "".black();
-                    match repo.read_recipe(&ident).await {
-                        Ok(recipe) => {
-                            if recipe.is_deprecated() {
+                    match entry {
+                        Ok(entry) => {
+                            if !query.matches_meta(&entry.meta) {
+                                continue;
+                            }
+                            if entry.deprecated {
                                 if self.deprecated {
                                     deprecation_status = " DEPRECATED".red();
                                 } else {
@@ -72,6 +125,10 @@ impl Run for Search {
                                 }
                             }
                         }
+                        Err(_) if !query.is_name_only() => {
+                            // Without a recipe there is no metadata to match
+                            continue;
+                        }
                         Err(_) => {
                             // It doesn't have a recipe, but it does
                             // have builds, so unless all the builds
@@ -112,6 +169,6 @@ impl Run for Search {
 impl CommandArgs for Search {
     fn get_positional_args(&self) -> Vec<String> {
         // The important positional arg for a search is the search term
-        vec![self.term.clone()]
+        self.term.iter().cloned().collect()
     }
 }
@@ -4,19 +4,31 @@
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::io::Write;
 use std::sync::Arc;
 
 use clap::Args;
 use colored::Colorize;
 use futures::{StreamExt, TryStreamExt};
 use miette::{bail, Context, IntoDiagnostic, Result};
+use relative_path::RelativePath;
 use serde::Serialize;
 use spfs::find_path::ObjectPathEntry;
 use spfs::graph::{HasKind, ObjectKind};
 use spfs::io::Pluralize;
+use spfs::prelude::*;
 use spfs::Digest;
+use spk_build::{build_log_path, build_provenance_path, BuildProvenance};
 use spk_cli_common::with_version_and_build_set::WithVersionSet;
-use spk_cli_common::{current_env, flags, CommandArgs, DefaultVersionStrategy, Run};
+use spk_cli_common::{
+    current_env,
+    flags,
+    CommandArgs,
+    DefaultVersionStrategy,
+    Reporter,
+    Run,
+    SolutionReport,
+};
 use spk_schema::foundation::format::{FormatChangeOptions, FormatRequest};
 use spk_schema::foundation::option_map::OptionMap;
 use spk_schema::foundation::spec_ops::Named;
@@ -26,6 +38,8 @@ use spk_schema::version::Version;
 use spk_schema::{
     AnyIdent,
     BuildIdent,
+    Deprecate,
+    Package,
     Recipe,
     RequirementsList,
     Spec,
@@ -34,7 +48,7 @@ use spk_schema::{
     VersionIdent,
 };
 use spk_solve::solution::{get_spfs_layers_to_packages, LayerPackageAndComponents};
-use spk_storage;
+use spk_storage::{self, BuildDetails, VersionDetails};
 use strum::{Display, EnumString, VariantNames};
 
 /// Constants for the valid output formats
@@ -102,6 +116,23 @@ pub struct View {
     #[clap(long)]
     variants: bool,
 
+    /// Display the provenance recorded when the given package build was made
+    #[clap(long, conflicts_with_all = &["filepath", "variants"])]
+    provenance: bool,
+
+    /// Display a summary of the given package version or build, including
+    /// its deprecation status, options, requirements and component sizes
+    #[clap(long, conflicts_with_all = &["filepath", "variants", "provenance"])]
+    details: bool,
+
+    /// Display the output of the build script that was saved
+    /// when the given package build was made
+    #[clap(
+        long,
+        conflicts_with_all = &["filepath", "variants", "provenance", "details"]
+    )]
+    log: bool,
+
     // TODO: we can remove this, along with the solving call, once the
     // no solving method is bedded in.
     /// Use the older full solve method of finding the package info.
@@ -115,6 +146,10 @@ impl Run for View {
     type Output = i32;
 
     async fn run(&mut self) -> Result<Self::Output> {
+        if self.format.is_none() && Reporter::current().is_json() {
+            self.format = Some(OutputFormat::Json);
+        }
+
         if self.variants {
             let options = self.options.get_options()?;
             return self.print_variants_info(&options);
@@ -158,6 +193,18 @@ impl Run for View {
             // value as a package.
         }
 
+        if self.provenance {
+            return self.print_build_provenance(package).await;
+        }
+
+        if self.log {
+            return self.print_build_log(package).await;
+        }
+
+        if self.details {
+            return self.print_package_details(package).await;
+        }
+
         if self.full_solve {
             // This is the older way. It runs a full solve. It's here
             // for backwards compatibility, and has to be opted-in to use.
@@ -189,9 +236,28 @@ struct PrintVariant<'a> {
     additional_requirements: Cow<'a, RequirementsList>,
 }
 
+#[derive(Serialize)]
+struct PrintBuildDetails {
+    #[serde(flatten)]
+    details: BuildDetails,
+    provenance: Option<BuildProvenance>,
+}
+
+#[derive(Serialize)]
+struct PrintVersionDetails<'a> {
+    #[serde(flatten)]
+    details: &'a VersionDetails,
+    variants: Vec<PrintVariant<'a>>,
+}
+
 impl View {
     async fn print_current_env(&self) -> Result<i32> {
         let solution = current_env().await?;
+        let reporter = Reporter::current();
+        if reporter.is_json() {
+            reporter.report(&SolutionReport::from(&solution), || Ok(()))?;
+            return Ok(0);
+        }
         let solver = self.solver.get_solver(&self.options).await?;
         println!(
             "{}",
@@ -403,6 +469,225 @@ impl View {
         Ok(0)
     }
 
+    /// Parse the identifier of a package build from the command line,
+    /// where `viewing` describes what is being displayed for errors.
+    async fn parse_build_ident(
+        &self,
+        package: &String,
+        repos: &[Arc<spk_storage::RepositoryHandle>],
+        viewing: &str,
+    ) -> Result<BuildIdent> {
+        let request = match self
+            .requests
+            .parse_request(&package, &self.options, repos)
+            .await?
+        {
+            Request::Pkg(pkg) => pkg,
+            parsed_request => bail!("Not a package request: {parsed_request:?}"),
+        };
+        if request.pkg.build.is_none() {
+            bail!("A package build is required to view its {viewing}, eg: {package}/<build>");
+        }
+        Ok(request.pkg.clone().try_into()?)
+    }
+
+    /// Display the provenance record saved in the given package build.
+    async fn print_build_provenance(&self, package: &String) -> Result<i32> {
+        let solver = self.solver.get_solver(&self.options).await?;
+        let repos = solver.repositories();
+        let ident = self.parse_build_ident(package, repos, "provenance").await?;
+
+        for repo in repos {
+            let Some(provenance) = read_build_provenance(repo, &ident).await? else {
+                continue;
+            };
+            match &self.format.clone().unwrap_or_default() {
+                OutputFormat::Yaml => serde_yaml::to_writer(std::io::stdout(), &provenance)
+                    .into_diagnostic()
+                    .wrap_err("Failed to serialize build provenance")?,
+                OutputFormat::Json => serde_json::to_writer(std::io::stdout(), &provenance)
+                    .into_diagnostic()
+                    .wrap_err("Failed to serialize build provenance")?,
+            }
+            return Ok(0);
+        }
+
+        tracing::error!("No provenance found for {ident}, it may have been built by an older spk");
+        Ok(1)
+    }
+
+    /// Display the build log saved in the given package build.
+    async fn print_build_log(&self, package: &String) -> Result<i32> {
+        let solver = self.solver.get_solver(&self.options).await?;
+        let repos = solver.repositories();
+        let ident = self.parse_build_ident(package, repos, "build log").await?;
+
+        for repo in repos {
+            let Some(log) = read_build_file(repo, &ident, &build_log_path(&ident)).await? else {
+                continue;
+            };
+            std::io::stdout()
+                .write_all(&log)
+                .into_diagnostic()
+                .wrap_err("Failed to write build log")?;
+            return Ok(0);
+        }
+
+        tracing::error!(
+            "No build log found for {ident}, it may have been built interactively or by an older spk"
+        );
+        Ok(1)
+    }
+
+    /// Display a summary of a package version or build from
+    /// the first repository that contains it.
+    async fn print_package_details(&self, package: &String) -> Result<i32> {
+        let solver = self.solver.get_solver(&self.options).await?;
+        let repos = solver.repositories();
+
+        let mut request = match self
+            .requests
+            .parse_request(&package, &self.options, repos)
+            .await?
+        {
+            Request::Pkg(pkg) => pkg,
+            parsed_request => bail!("Not a package request: {parsed_request:?}"),
+        };
+
+        if request.pkg.build.is_some() {
+            let ident: BuildIdent = request.pkg.clone().try_into()?;
+            for repo in repos {
+                let details = match spk_storage::describe_build(repo, &ident).await {
+                    Ok(details) => details,
+                    Err(err) if err.is_package_not_found() => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                let provenance = read_build_provenance(repo, &ident).await?;
+                return self.print_build_details(PrintBuildDetails {
+                    details,
+                    provenance,
+                });
+            }
+            tracing::error!("Error: no such package/version/build found: {package}");
+            return Ok(1);
+        }
+
+        request = request
+            .with_version_or_else(DefaultVersionStrategy::Highest, repos)
+            .await?;
+        let ident: AnyIdent = request.pkg.clone().try_into()?;
+        let ident = ident.to_version();
+        for repo in repos {
+            let details = match spk_storage::describe_version(repo, &ident).await {
+                Ok(details) => details,
+                Err(err) if err.is_package_not_found() => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let options = self.options.get_options()?;
+            return self.print_version_details(&details, &options);
+        }
+        tracing::error!("Error: no such package/version found: {package}");
+        Ok(1)
+    }
+
+    fn print_build_details(&self, info: PrintBuildDetails) -> Result<i32> {
+        match &self.format {
+            Some(OutputFormat::Yaml) => serde_yaml::to_writer(std::io::stdout(), &info)
+                .into_diagnostic()
+                .wrap_err("Failed to serialize package details")?,
+            Some(OutputFormat::Json) => serde_json::to_writer(std::io::stdout(), &info)
+                .into_diagnostic()
+                .wrap_err("Failed to serialize package details")?,
+            None => {
+                let details = &info.details;
+                println!(
+                    "{} (from {})",
+                    details.spec.ident().to_string().green(),
+                    details.repository
+                );
+                print_deprecation(&details.spec);
+                println!(" build options:");
+                for (name, value) in details.build_options.iter() {
+                    println!("   {name}: {value}");
+                }
+                println!(" runtime requirements:");
+                for request in details.runtime_requirements.iter() {
+                    println!("   {request}");
+                }
+                println!(" components:");
+                for (component, stats) in details.components.iter() {
+                    match (stats.file_count, stats.total_size) {
+                        (Some(count), Some(size)) => println!(
+                            "   {component}: {count} {}, {} ({})",
+                            "file".pluralize(count),
+                            spfs::io::format_size(size),
+                            stats.layer
+                        ),
+                        _ => println!("   {component}: {}", stats.layer),
+                    }
+                }
+                match &info.provenance {
+                    Some(provenance) => println!(
+                        " built by {}@{} at {} with spk {}",
+                        provenance.user,
+                        provenance.host,
+                        provenance.timestamp,
+                        provenance.spk_version
+                    ),
+                    None => println!(" {}", "no build provenance recorded".dimmed()),
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    fn print_version_details(&self, details: &VersionDetails, options: &OptionMap) -> Result<i32> {
+        let variants = details.recipe.default_variants(options);
+        match &self.format {
+            Some(format) => {
+                let info = PrintVersionDetails {
+                    details,
+                    variants: variants
+                        .iter()
+                        .map(|variant| PrintVariant {
+                            options: variant.options(),
+                            additional_requirements: variant.additional_requirements(),
+                        })
+                        .collect(),
+                };
+                match format {
+                    OutputFormat::Yaml => serde_yaml::to_writer(std::io::stdout(), &info)
+                        .into_diagnostic()
+                        .wrap_err("Failed to serialize package details")?,
+                    OutputFormat::Json => serde_json::to_writer(std::io::stdout(), &info)
+                        .into_diagnostic()
+                        .wrap_err("Failed to serialize package details")?,
+                }
+            }
+            None => {
+                println!(
+                    "{} (from {})",
+                    details.recipe.ident().to_string().green(),
+                    details.repository
+                );
+                print_deprecation(&details.recipe);
+                println!(" variants:");
+                for (index, variant) in variants.iter().enumerate() {
+                    println!("   {index}: {variant:#}");
+                }
+                println!(" builds:");
+                for build in details.builds.iter() {
+                    if build.deprecated {
+                        println!("   {} {}", build.ident, "DEPRECATED".red());
+                    } else {
+                        println!("   {}", build.ident);
+                    }
+                }
+            }
+        }
+        Ok(0)
+    }
+
     /// Display information on the package by looking up its
     /// specification or recipe directly based on these rules about
     /// what is in the given package identifier.
@@ -619,3 +904,87 @@ impl View {
         Ok(1)
     }
 }
+
+/// Print whether the given package is deprecated, and what to use instead.
+fn print_deprecation<T: Deprecate>(package: &T) {
+    if !package.is_deprecated() {
+        println!(" deprecated: no");
+        return;
+    }
+    match package.replacement_suggestion() {
+        Some(suggestion) => println!(" deprecated: {} ({suggestion})", "yes".red()),
+        None => println!(" deprecated: {}", "yes".red()),
+    }
+}
+
+/// Load the provenance record saved in a package build, if it has one.
+async fn read_build_provenance(
+    repo: &spk_storage::RepositoryHandle,
+    ident: &BuildIdent,
+) -> Result<Option<BuildProvenance>> {
+    let path = build_provenance_path(ident);
+    let Some(data) = read_build_file(repo, ident, &path).await? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&data)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Invalid provenance file: {path}"))
+}
+
+/// Load the contents of a metadata file saved in a package build,
+/// if it has one.
+async fn read_build_file(
+    repo: &spk_storage::RepositoryHandle,
+    ident: &BuildIdent,
+    path: &RelativePath,
+) -> Result<Option<Vec<u8>>> {
+    let spfs_repo: &spfs::storage::RepositoryHandle = match repo {
+        spk_storage::RepositoryHandle::SPFS(repo) => repo,
+        spk_storage::RepositoryHandle::SPFSWithVerbatimTags(repo) => repo,
+        spk_storage::RepositoryHandle::Runtime(_) => {
+            let file = path.to_path(spfs::env::SPFS_DIR);
+            return match std::fs::read(&file) {
+                Ok(data) => Ok(Some(data)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("Failed to read {}", file.display())),
+            };
+        }
+        spk_storage::RepositoryHandle::Mem(_) => return Ok(None),
+        spk_storage::RepositoryHandle::Chained(chain) => {
+            return match chain.find_build_repository(ident).await? {
+                Some(repo) => Box::pin(read_build_file(repo, ident, path)).await,
+                None => Ok(None),
+            };
+        }
+    };
+
+    let components = match repo.read_components(ident).await {
+        Ok(components) => components,
+        Err(err) if err.is_package_not_found() => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    // every component layer includes the same package metadata files
+    for digest in components.values() {
+        let layer = spfs_repo.read_layer(*digest).await?;
+        let Some(manifest) = layer.manifest() else {
+            continue;
+        };
+        let manifest = spfs_repo
+            .read_manifest(*manifest)
+            .await?
+            .to_tracking_manifest();
+        let Some(entry) = manifest.get_path(path) else {
+            continue;
+        };
+        let (mut payload, _filename) = spfs_repo.open_payload(entry.object).await?;
+        let mut data = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut payload, &mut data)
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read {path}"))?;
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
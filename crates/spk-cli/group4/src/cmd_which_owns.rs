@@ -0,0 +1,155 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use clap::Args;
+use colored::Colorize;
+use miette::{Context, Result};
+use serde::Serialize;
+use spk_cli_common::{current_env, flags, CommandArgs, Reporter, Run};
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::ident::parse_ident;
+use spk_schema::{BuildIdent, VersionIdent};
+use spk_storage::{self as storage, PathIndex, PathOwner};
+
+/// Find the packages that provide a file
+///
+/// By default, the packages in the current environment are searched.
+/// With --in-repos, the builds in the enabled repositories are
+/// searched instead, which can be slow unless limited with --pkg.
+#[derive(Args)]
+pub struct WhichOwns {
+    #[clap(flatten)]
+    pub repos: flags::Repositories,
+
+    /// Search the builds in the enabled repositories instead of the current environment
+    #[clap(long)]
+    pub in_repos: bool,
+
+    /// Only search these packages in the repositories (eg: my-pkg, my-pkg/1.0)
+    #[clap(
+        long = "pkg",
+        requires = "in_repos",
+        value_name = "NAME[/VERSION[/BUILD]]"
+    )]
+    pub packages: Vec<String>,
+
+    /// The files to find the owners of (eg: /spfs/bin/ffmpeg)
+    #[clap(name = "PATH", required = true)]
+    pub paths: Vec<String>,
+}
+
+/// The packages that provide one of the requested paths
+#[derive(Debug, Serialize)]
+struct PathOwners<'a> {
+    path: &'a str,
+    owners: &'a [PathOwner],
+}
+
+#[async_trait::async_trait]
+impl Run for WhichOwns {
+    type Output = i32;
+
+    async fn run(&mut self) -> Result<Self::Output> {
+        let index = if self.in_repos {
+            self.index_repos().await?
+        } else {
+            let solution = current_env().await?;
+            spk_exec::solution_to_resolved_runtime_layers(&solution)?
+                .path_index()
+                .await
+                .wrap_err("Failed to index the files of the current environment")?
+        };
+
+        let results: Vec<_> = self
+            .paths
+            .iter()
+            .map(|path| PathOwners {
+                path,
+                owners: index.owners_of(path),
+            })
+            .collect();
+
+        Reporter::current().report(&results, || {
+            for result in results.iter() {
+                if result.owners.is_empty() {
+                    println!("{}: {}", result.path, "not found".yellow());
+                    continue;
+                }
+                for owner in result.owners {
+                    println!(
+                        "{}: {} {} {}",
+                        result.path,
+                        owner.pkg.format_ident(),
+                        owner.component.to_string().cyan(),
+                        owner.digest.to_string()[..10].yellow(),
+                    );
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(if results.iter().all(|r| !r.owners.is_empty()) {
+            0
+        } else {
+            1
+        })
+    }
+}
+
+impl CommandArgs for WhichOwns {
+    fn get_positional_args(&self) -> Vec<String> {
+        self.paths.clone()
+    }
+}
+
+impl WhichOwns {
+    /// Index the files of every matching build in the enabled repositories
+    async fn index_repos(&self) -> Result<PathIndex> {
+        let repos = self.repos.get_repos_for_non_destructive_operation().await?;
+        let mut index = PathIndex::default();
+        for (repo_name, repo) in repos.iter() {
+            for build in self.builds_to_search(repo).await? {
+                // embedded stubs have no files of their own
+                if build.is_embedded() {
+                    continue;
+                }
+                index.insert_build(repo, &build).await.wrap_err_with(|| {
+                    format!("Failed to read the files of {build} in {repo_name}")
+                })?;
+            }
+        }
+        Ok(index)
+    }
+
+    async fn builds_to_search(&self, repo: &storage::RepositoryHandle) -> Result<Vec<BuildIdent>> {
+        let mut versions = Vec::new();
+        let mut builds = Vec::new();
+        if self.packages.is_empty() {
+            for name in repo.list_packages().await? {
+                for version in repo.list_package_versions(&name).await?.iter() {
+                    versions.push(VersionIdent::new(name.clone(), (**version).clone()));
+                }
+            }
+        }
+        for package in self.packages.iter() {
+            let pkg = parse_ident(package)?;
+            if pkg.build().is_some() {
+                builds.extend(pkg.into_build());
+            } else if package.contains('/') {
+                versions.push(pkg.to_version());
+            } else {
+                for version in repo.list_package_versions(pkg.name()).await?.iter() {
+                    versions.push(VersionIdent::new(
+                        pkg.name().to_owned(),
+                        (**version).clone(),
+                    ));
+                }
+            }
+        }
+        for version in versions {
+            builds.extend(repo.list_package_builds(&version).await?);
+        }
+        Ok(builds)
+    }
+}
@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+pub mod cmd_diff;
 pub mod cmd_lint;
+pub mod cmd_options;
+pub mod cmd_rdepends;
 pub mod cmd_search;
 pub mod cmd_version;
 pub mod cmd_view;
+pub mod cmd_which_owns;
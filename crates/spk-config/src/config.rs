@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 use config::Environment;
@@ -112,6 +113,154 @@ pub struct Cli {
     pub ls: Ls,
 }
 
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Namespaces {
+    /// Maps package namespaces (eg: `studio.animtools`) to a
+    /// comma-separated list of the only repositories that packages
+    /// in that namespace should be resolved from. Namespaces that
+    /// are not listed are resolved from all enabled repositories.
+    pub repositories: HashMap<String, String>,
+}
+
+impl Namespaces {
+    /// The names of the repositories that packages in the given namespace
+    /// are routed to, or None if the namespace is not routed.
+    pub fn repositories_for(&self, namespace: &str) -> Option<Vec<&str>> {
+        self.repositories.get(namespace).map(|repos| {
+            repos
+                .split(',')
+                .map(str::trim)
+                .filter(|r| !r.is_empty())
+                .collect()
+        })
+    }
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Repositories {
+    /// Maps repository names to their priority. When the same build
+    /// is found in more than one repository, the solver uses it from
+    /// the repository with the highest priority. Repositories that
+    /// are not listed have a priority of zero.
+    pub priorities: HashMap<String, i64>,
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PreReleases {
+    /// The pre-release policy used for package requests that do not
+    /// specify one (`ExcludeAll` or `IncludeAll`), defaults to `ExcludeAll`
+    pub policy: String,
+    /// Maps repository names to the pre-release policy used for packages
+    /// from that repository, replacing the policy above
+    pub repositories: HashMap<String, String>,
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HostOptions {
+    /// Options to add to those detected for the current host, which
+    /// replace any detected option of the same name (eg: `site: mtl`)
+    pub options: HashMap<String, String>,
+    /// List of commands that output additional host options as a
+    /// json or yaml mapping, applied in order before the options above
+    pub commands: Vec<HostOptionsCommand>,
+    /// The number of parts of the distro version to keep, by distro id.
+    ///
+    /// For example, `rocky: 1` reports a version of `9.3` as just `9`.
+    /// Distros that are not listed keep their full version.
+    pub distro_version_parts: HashMap<String, usize>,
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HostOptionsCommand {
+    /// List containing the executable and its arguments
+    pub command: Vec<String>,
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Licenses {
+    /// Comma-separated list of SPDX license identifiers that
+    /// packages in an environment are not allowed to use
+    pub disallowed: String,
+    /// Comma-separated list of the only SPDX license identifiers that
+    /// packages in an environment may use, if any are given
+    pub allowed: String,
+}
+
+impl Licenses {
+    /// The license identifiers that are not allowed
+    pub fn disallowed(&self) -> Vec<&str> {
+        split_list(&self.disallowed)
+    }
+
+    /// The only license identifiers that are allowed, or
+    /// an empty list if any license not disallowed is fine
+    pub fn allowed(&self) -> Vec<&str> {
+        split_list(&self.allowed)
+    }
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InstallHooks {
+    /// Never run the install hooks of packages, site-wide
+    pub disabled: bool,
+    /// Comma-separated list of the only package names whose
+    /// install hooks are run, if any are given
+    pub allowed_packages: String,
+    /// The number of seconds that a single hook can run for
+    /// before it is stopped, or zero for no limit
+    pub timeout_seconds: u64,
+    /// A file that a record of each install hook that is run
+    /// is appended to, as one json object per line
+    pub audit_log: String,
+}
+
+impl InstallHooks {
+    /// The only package names whose hooks are run, or an empty
+    /// list if the hooks of any package can be run
+    pub fn allowed_packages(&self) -> Vec<&str> {
+        split_list(&self.allowed_packages)
+    }
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TagNamespaces {
+    /// The spfs tag namespace that scratch builds are published to,
+    /// where `{user}` is replaced with the name of the current user
+    /// (eg: `users/{user}`)
+    pub scratch: String,
+    /// Maps spfs tag namespaces to how long packages are kept in them,
+    /// or in any namespace nested within them, before `spk prune`
+    /// removes them (eg: `users = "4w"`)
+    pub prune_after: HashMap<String, String>,
+}
+
+impl TagNamespaces {
+    /// The scratch namespace of the current user, or None if
+    /// no scratch namespace is configured
+    pub fn scratch_for_current_user(&self) -> Option<String> {
+        let scratch = self.scratch.trim();
+        if scratch.is_empty() {
+            return None;
+        }
+        Some(scratch.replace("{user}", &whoami::username()))
+    }
+}
+
+fn split_list(list: &str) -> Vec<&str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 /// Configuration values for spk.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(default)]
@@ -124,6 +273,13 @@ pub struct Config {
     pub statsd: Statsd,
     pub metadata: Metadata,
     pub cli: Cli,
+    pub namespaces: Namespaces,
+    pub repositories: Repositories,
+    pub prereleases: PreReleases,
+    pub host_options: HostOptions,
+    pub licenses: Licenses,
+    pub install_hooks: InstallHooks,
+    pub tag_namespaces: TagNamespaces,
 }
 
 impl Config {
@@ -0,0 +1,41 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Diagnostic, Debug, Error)]
+#[diagnostic(
+    url(
+        "https://spkenv.dev/error_codes#{}",
+        self.code().unwrap_or_else(|| Box::new("spk::generic"))
+    )
+)]
+pub enum Error {
+    #[error("Failed to run pip: {0}")]
+    PipSpawnError(#[source] std::io::Error),
+    #[error("Failed to resolve pip requirements:\n{0}")]
+    PipResolveError(String),
+    #[error("Failed to read pip installation report")]
+    InvalidPipReport(#[source] serde_json::Error),
+    #[error("Cannot convert python version '{0}' to an spk version")]
+    InvalidPythonVersion(String),
+    #[error("Unhandled pip version range prefix '{0}'")]
+    InvalidPythonVersionRange(String),
+    #[error("Failed to create temp dir: {0}")]
+    TempDirError(#[source] std::io::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkBuildError(#[from] spk_build::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkSchemaError(#[from] spk_schema::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkStorageError(#[from] spk_storage::Error),
+    #[error("Error: {0}")]
+    String(String),
+}
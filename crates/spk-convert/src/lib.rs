@@ -0,0 +1,11 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Converters that generate spk packages from other packaging systems.
+
+mod error;
+pub mod pip;
+
+pub use error::{Error, Result};
+pub use pip::{ConvertedPackage, PipConverter};
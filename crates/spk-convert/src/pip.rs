@@ -0,0 +1,627 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Generate spk packages from python packages on PyPI.
+//!
+//! Requirements are resolved using `pip install --dry-run --report`,
+//! which produces the full set of distributions needed to satisfy the
+//! requested packages for the target python version and abi. Each
+//! distribution is then turned into a recipe and built into the local
+//! repository, dependencies first.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::Stdio;
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use spk_build::{BinaryPackageBuilder, BuildSource};
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::prelude::*;
+use spk_schema::{BuildIdent, SpecRecipe};
+use spk_storage as storage;
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./pip_test.rs"]
+mod pip_test;
+
+/// Label added to all generated packages, identifying this converter
+pub const SPK_GENERATED_BY_LABEL: &str = "spk:generated_by";
+/// Value of the [`SPK_GENERATED_BY_LABEL`] for packages created from pip
+pub const SPK_GENERATED_BY_VALUE: &str = "spk-convert-pip";
+/// Label recording the command line that generated a package
+pub const CLI_LABEL: &str = "spk-convert-pip:cli";
+/// Label recording the original PyPI name of a generated package
+pub const PYPI_NAME_LABEL: &str = "spk-convert-pip:pypi_name";
+/// Label recording the original PyPI version of a generated package
+pub const PYPI_VERSION_LABEL: &str = "spk-convert-pip:pypi_version";
+
+/// Packages that are baked into the spk python package and
+/// cannot be replaced by a converted package.
+const BAKED_PYTHON_PACKAGES: &[&str] = &["setuptools", "pip", "wheel"];
+
+/// Longest value that will be put into the metadata license field
+const LICENSE_FIELD_LIMIT: usize = 80;
+const TRUNCATED_VALUE_INDICATOR: &str = "...";
+
+/// Matches a PEP 440 version string, capturing its release, pre,
+/// post and dev segments.
+static PEP440_VERSION: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(
+        r"(?x)^
+        v?
+        (?:\d+!)?
+        (?P<release>\d+(?:\.\d+)*)
+        (?:[-_.]?(?P<pre_l>a|alpha|b|beta|rc|c|pre|preview)[-_.]?(?P<pre_n>\d*))?
+        (?:-(?P<post_n1>\d+)|[-_.]?(?:post|rev|r)[-_.]?(?P<post_n2>\d*))?
+        (?:[-_.]?dev[-_.]?(?P<dev_n>\d*))?
+        (?:\+[a-z0-9]+(?:[-_.][a-z0-9]+)*)?
+        $",
+    )
+    .expect("valid pep 440 regex")
+});
+
+/// A package that was generated from a pip distribution.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConvertedPackage {
+    /// The name of the distribution on PyPI
+    pub pypi_name: String,
+    /// The version of the distribution on PyPI
+    pub pypi_version: String,
+    /// The spk package that was built from the distribution
+    pub ident: BuildIdent,
+}
+
+/// The report generated by `pip install --report`.
+///
+/// Only the fields needed for conversion are loaded.
+#[derive(Debug, Deserialize)]
+struct PipReport {
+    #[serde(default)]
+    install: Vec<PipInstallItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PipInstallItem {
+    metadata: PipMetadata,
+    #[serde(default)]
+    requested_extras: Vec<String>,
+}
+
+/// A single resolved distribution from pip.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PipMetadata {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub requires_dist: Vec<String>,
+    #[serde(default)]
+    pub requires_python: Option<String>,
+}
+
+/// A pip distribution that has been resolved for conversion.
+#[derive(Clone, Debug)]
+pub struct PipPackage {
+    pub metadata: PipMetadata,
+    /// The extras that were requested of this package during the resolve
+    pub extras: BTreeSet<String>,
+}
+
+/// Converts pip requirements into spk packages.
+///
+/// ```no_run
+/// # async fn demo() -> spk_convert::Result<()> {
+/// let converter = spk_convert::PipConverter::default();
+/// let packages = converter.resolve(&["pytest>=7".to_string()]).await?;
+/// for package in packages {
+///     let recipe = converter.generate_recipe(&package)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PipConverter {
+    pip: String,
+    python_version: String,
+    python_abi: Option<String>,
+    follow_deps: bool,
+    cli_args: String,
+}
+
+impl Default for PipConverter {
+    fn default() -> Self {
+        Self {
+            pip: "pip".to_string(),
+            python_version: "3.7".to_string(),
+            python_abi: Some("cp37m".to_string()),
+            follow_deps: true,
+            cli_args: String::new(),
+        }
+    }
+}
+
+impl PipConverter {
+    /// Use an alternate pip executable to resolve requirements
+    pub fn with_pip<S: Into<String>>(&mut self, pip: S) -> &mut Self {
+        self.pip = pip.into();
+        self
+    }
+
+    /// The version of python to convert packages for, eg `3.9`
+    pub fn with_python_version<S: Into<String>>(&mut self, version: S) -> &mut Self {
+        self.python_version = version.into();
+        self
+    }
+
+    /// The python abi to convert packages for, eg `cp39`
+    pub fn with_python_abi(&mut self, abi: Option<String>) -> &mut Self {
+        self.python_abi = abi;
+        self
+    }
+
+    /// Whether to also convert the dependencies of the requested packages
+    pub fn with_follow_deps(&mut self, follow_deps: bool) -> &mut Self {
+        self.follow_deps = follow_deps;
+        self
+    }
+
+    /// The command line to record in generated packages
+    pub fn with_cli_args<S: Into<String>>(&mut self, cli_args: S) -> &mut Self {
+        self.cli_args = cli_args.into();
+        self
+    }
+
+    /// Resolve the full set of pip distributions needed for the given requirements.
+    ///
+    /// Only binary distributions (wheels) can be resolved, as pip will not
+    /// resolve source distributions for a python version other than its own.
+    pub async fn resolve(&self, requirements: &[String]) -> Result<Vec<PipPackage>> {
+        let mut cmd = tokio::process::Command::new(&self.pip);
+        cmd.args([
+            "install",
+            "--dry-run",
+            "--quiet",
+            "--ignore-installed",
+            "--only-binary=:all:",
+            "--report=-",
+            // Allow enough time for larger wheels to download.
+            "--timeout=1000",
+            "--python-version",
+            self.python_version.as_str(),
+        ]);
+        if let Some(abi) = &self.python_abi {
+            cmd.args(["--abi", abi.as_str()]);
+        }
+        if !self.follow_deps {
+            cmd.arg("--no-deps");
+        }
+        cmd.args(requirements)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        tracing::debug!("resolving pip requirements: {cmd:?}");
+
+        let output = cmd.output().await.map_err(Error::PipSpawnError)?;
+        if !output.status.success() {
+            return Err(Error::PipResolveError(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+        let report: PipReport =
+            serde_json::from_slice(&output.stdout).map_err(Error::InvalidPipReport)?;
+
+        Ok(report
+            .install
+            .into_iter()
+            .filter(|item| {
+                if is_baked(&item.metadata.name) {
+                    tracing::warn!(
+                        "skipping {}, this package cannot be updated with the pip conversion since it's baked into the spk python package",
+                        item.metadata.name
+                    );
+                    return false;
+                }
+                true
+            })
+            .map(|item| PipPackage {
+                metadata: item.metadata,
+                extras: item.requested_extras.into_iter().collect(),
+            })
+            .collect())
+    }
+
+    /// Generate an spk recipe for the given pip distribution.
+    ///
+    /// Dependencies of the distribution become install requirements. The
+    /// environment markers on those dependencies are not evaluated here,
+    /// instead use [`Self::generate_recipes`] to only keep the dependencies
+    /// that pip included in the resolve.
+    pub fn generate_recipe(&self, package: &PipPackage) -> Result<SpecRecipe> {
+        self.generate_recipe_filtered(package, |_| true)
+    }
+
+    /// Generate spk recipes for all of the given pip distributions, in
+    /// the order that they need to be built.
+    ///
+    /// Dependencies are only kept if pip resolved them for the
+    /// target python version and extras, which stands in for
+    /// evaluating the environment markers of each dependency.
+    pub fn generate_recipes(
+        &self,
+        packages: &[PipPackage],
+    ) -> Result<Vec<(PipPackage, SpecRecipe)>> {
+        let resolved = packages
+            .iter()
+            .map(|p| (normalize_pypi_name(&p.metadata.name), p))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut ordered = Vec::with_capacity(packages.len());
+        let mut visited = BTreeSet::new();
+        fn visit<'a>(
+            name: &str,
+            resolved: &BTreeMap<String, &'a PipPackage>,
+            visited: &mut BTreeSet<String>,
+            ordered: &mut Vec<&'a PipPackage>,
+        ) {
+            if !visited.insert(name.to_string()) {
+                return;
+            }
+            let Some(package) = resolved.get(name) else {
+                return;
+            };
+            for dep in package.metadata.requires_dist.iter() {
+                if let Some(dep) = PipRequirement::parse(dep) {
+                    visit(&normalize_pypi_name(&dep.name), resolved, visited, ordered);
+                }
+            }
+            ordered.push(package);
+        }
+        for name in resolved.keys() {
+            visit(name, &resolved, &mut visited, &mut ordered);
+        }
+
+        ordered
+            .into_iter()
+            .map(|package| {
+                let recipe = self.generate_recipe_filtered(package, |dep| {
+                    resolved.contains_key(&normalize_pypi_name(&dep.name))
+                })?;
+                Ok((package.clone(), recipe))
+            })
+            .collect()
+    }
+
+    fn generate_recipe_filtered<F>(
+        &self,
+        package: &PipPackage,
+        mut include: F,
+    ) -> Result<SpecRecipe>
+    where
+        F: FnMut(&PipRequirement) -> bool,
+    {
+        let info = &package.metadata;
+        let spk_name = to_spk_name(&info.name);
+        let spk_version = to_spk_version(&info.version)?;
+
+        // The spk default is "Unlicensed", which seems odd
+        let mut license = info
+            .license
+            .clone()
+            .unwrap_or_else(|| "Unknown".to_string());
+        if license.len() > LICENSE_FIELD_LIMIT {
+            let mut end = LICENSE_FIELD_LIMIT - TRUNCATED_VALUE_INDICATOR.len();
+            while !license.is_char_boundary(end) {
+                end -= 1;
+            }
+            license.truncate(end);
+            license.push_str(TRUNCATED_VALUE_INDICATOR);
+        }
+
+        let mut options = vec![
+            serde_json::json!({"var": "os"}),
+            serde_json::json!({"var": "arch"}),
+            serde_json::json!({"var": "distro"}),
+            serde_json::json!({"pkg": format!("python/{}", self.python_version)}),
+            serde_json::json!({"pkg": "python-pip"}),
+        ];
+        for extra in package.extras.iter() {
+            options.push(serde_json::json!({"var": format!("python_extra_{extra}/true")}));
+        }
+        match &self.python_abi {
+            Some(abi) => options.push(serde_json::json!({"var": format!("python.abi/{abi}")})),
+            None => options.push(serde_json::json!({"var": "python.abi"})),
+        }
+
+        if info.requires_python.is_some() {
+            tracing::debug!(
+                "ignoring defined python range for {}, other versions of python will need to have this package reconverted",
+                info.name
+            );
+        }
+        // python packages can support a wide range of versions, and present dynamic
+        // requirements based on the version used - spk does not do this so instead
+        // we restrict the package to the python version that it's being converted for
+        let mut requirements =
+            vec![serde_json::json!({"pkg": format!("python/{}", self.python_version)})];
+
+        // the package may specify multiple requirements for the same package,
+        // for example:
+        //    Requires-Dist: numpy (>=1.17.0) ; python_version >= "3.7"
+        //    Requires-Dist: numpy (>=1.17.3) ; python_version >= "3.8"
+        // these are joined together and spk decides if they are compatible
+        let mut dependencies: BTreeMap<String, (Vec<String>, BTreeSet<String>)> = BTreeMap::new();
+        for dep in info
+            .requires_dist
+            .iter()
+            .filter_map(|d| PipRequirement::parse(d))
+        {
+            if is_baked(&dep.name) || !include(&dep) {
+                continue;
+            }
+            let (ranges, extras) = dependencies.entry(to_spk_name(&dep.name)).or_default();
+            if !dep.specifier.is_empty() {
+                ranges.push(to_spk_version_range(&dep.specifier)?);
+            }
+            extras.extend(dep.extras);
+        }
+        for (name, (ranges, extras)) in dependencies {
+            if ranges.is_empty() {
+                requirements.push(serde_json::json!({"pkg": name}));
+            } else {
+                requirements
+                    .push(serde_json::json!({"pkg": format!("{name}/{}", ranges.join(","))}));
+            }
+            for extra in extras {
+                requirements
+                    .push(serde_json::json!({"var": format!("{name}.python_extra_{extra}/true")}));
+            }
+        }
+
+        let mut spec = serde_json::json!({
+            "pkg": format!("{spk_name}/{spk_version}"),
+            "api": "v0/package",
+            "sources": [],
+            "meta": {
+                "license": license,
+                "labels": {
+                    SPK_GENERATED_BY_LABEL: SPK_GENERATED_BY_VALUE,
+                    CLI_LABEL: self.cli_args,
+                    PYPI_NAME_LABEL: info.name,
+                    PYPI_VERSION_LABEL: info.version,
+                },
+            },
+            "build": {
+                "options": options,
+                "script": [
+                    "export PYTHONNOUSERSITE=1",
+                    "export PYTHONDONTWRITEBYTECODE=1",
+                    format!("/spfs/bin/python -BEs -m pip install {}=={} --no-deps", info.name, info.version),
+                ],
+            },
+            "install": {
+                "requirements": requirements,
+            },
+        });
+        if normalize_pypi_name(&info.name) == "pip" {
+            spec["build"]["validation"] =
+                serde_json::json!({"rules": [{"allow": "RecursiveBuild"}]});
+        }
+
+        SpecRecipe::from_yaml(spec.to_string())
+            .map_err(spk_schema::Error::from)
+            .map_err(Error::from)
+    }
+
+    /// Build the given recipes into the local repository, in order.
+    ///
+    /// Each recipe is published to the local repository and then its
+    /// default variant is built using the given options.
+    pub async fn build_recipes(
+        &self,
+        recipes: Vec<(PipPackage, SpecRecipe)>,
+        options: &OptionMap,
+        local: &storage::RepositoryHandle,
+        repos: Vec<Arc<storage::RepositoryHandle>>,
+    ) -> Result<Vec<ConvertedPackage>> {
+        let sources = tempfile::tempdir().map_err(Error::TempDirError)?;
+        let mut converted = Vec::with_capacity(recipes.len());
+        for (package, recipe) in recipes {
+            tracing::info!(
+                "building generated package {}",
+                recipe.ident().format_ident()
+            );
+            local.force_publish_recipe(&recipe).await?;
+
+            let variant = recipe
+                .default_variants(options)
+                .first()
+                .cloned()
+                .ok_or_else(|| {
+                    Error::String(format!(
+                        "Generated recipe has no variants to build: {}",
+                        recipe.ident().format_ident()
+                    ))
+                })?
+                .with_overrides(options.clone());
+
+            // generated recipes have no sources, the build
+            // script installs the distribution directly from pip
+            let mut builder = BinaryPackageBuilder::from_recipe(recipe);
+            builder
+                .with_repositories(repos.iter().cloned())
+                .with_source(BuildSource::LocalPath(sources.path().to_owned()));
+            let (spec, _components) = builder.build_and_publish(&variant, local).await?;
+            converted.push(ConvertedPackage {
+                pypi_name: package.metadata.name,
+                pypi_version: package.metadata.version,
+                ident: spec.ident().clone(),
+            });
+        }
+        Ok(converted)
+    }
+}
+
+/// A single dependency from the `Requires-Dist` metadata of a distribution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PipRequirement {
+    pub name: String,
+    pub extras: BTreeSet<String>,
+    /// The version specifier, eg `>=1.0,<2`, or empty for any version
+    pub specifier: String,
+}
+
+impl PipRequirement {
+    /// Parse a PEP 508 requirement string, ignoring any environment markers.
+    ///
+    /// Returns None if the string does not start with a valid name, and
+    /// does not support url requirements.
+    pub fn parse(requirement: &str) -> Option<Self> {
+        let requirement = requirement.split(';').next()?.trim();
+        let name_end = requirement
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(requirement.len());
+        if name_end == 0 {
+            return None;
+        }
+        let (name, mut rest) = requirement.split_at(name_end);
+        rest = rest.trim_start();
+
+        let mut extras = BTreeSet::new();
+        if let Some(inner) = rest.strip_prefix('[') {
+            let (inner, after) = inner.split_once(']')?;
+            extras.extend(
+                inner
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|e| !e.is_empty())
+                    .map(normalize_pypi_name),
+            );
+            rest = after.trim_start();
+        }
+        if rest.starts_with('@') {
+            return None;
+        }
+        let specifier = rest
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        Some(Self {
+            name: name.to_string(),
+            extras,
+            specifier,
+        })
+    }
+}
+
+/// True if the named PyPI project is baked into the spk python package.
+fn is_baked(name: &str) -> bool {
+    BAKED_PYTHON_PACKAGES.contains(&normalize_pypi_name(name).as_str())
+}
+
+/// Normalize a PyPI project name as described in PEP 503.
+pub fn normalize_pypi_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Convert a PyPI project name to the name of an spk package.
+///
+/// ```
+/// assert_eq!(spk_convert::pip::to_spk_name("PyYAML"), "python-pyyaml");
+/// assert_eq!(spk_convert::pip::to_spk_name("python-dateutil"), "python-dateutil");
+/// ```
+pub fn to_spk_name(name: &str) -> String {
+    let name = normalize_pypi_name(name);
+    if name.starts_with("python-") {
+        name
+    } else {
+        format!("python-{name}")
+    }
+}
+
+/// Convert a PEP 440 version string into an spk version.
+///
+/// Pre-release segments become pre-release tags, post releases become
+/// post-release tags, dev segments become development releases and any
+/// local version label is dropped, as it has no equivalent in spk.
+pub fn to_spk_version(version: &str) -> Result<String> {
+    let lower = version.trim().to_ascii_lowercase();
+    let caps = PEP440_VERSION
+        .captures(&lower)
+        .ok_or_else(|| Error::InvalidPythonVersion(version.to_string()))?;
+    let number = |name: &str| {
+        caps.name(name)
+            .map(|m| m.as_str().parse::<u64>().unwrap_or_default())
+    };
+
+    let mut spk_version = caps["release"].to_string();
+    if let Some(name) = caps.name("pre_l") {
+        let name = match name.as_str() {
+            "alpha" => "a",
+            "beta" => "b",
+            "c" | "pre" | "preview" => "rc",
+            other => other,
+        };
+        let num = number("pre_n").unwrap_or_default();
+        spk_version.push_str(&format!("-{name}.{num}"));
+    }
+    if let Some(post) = number("post_n1").or_else(|| number("post_n2")) {
+        spk_version.push_str(&format!("+post.{post}"));
+    }
+    if let Some(dev) = number("dev_n") {
+        spk_version.push_str(&format!(".dev{dev}"));
+    }
+    Ok(spk_version)
+}
+
+/// Convert a PEP 440 version specifier into an spk version range.
+pub fn to_spk_version_range(specifier: &str) -> Result<String> {
+    let specifier: String = specifier.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut ranges = Vec::new();
+    for version in specifier.trim_matches(',').split(',') {
+        let stripped = version.trim_start_matches(['>', '<', '=', '!', '~']);
+        let prefix = &version[..version.len() - stripped.len()];
+        let converted = if stripped.contains('*') {
+            stripped.to_string()
+        } else {
+            // handle pre and post release tags added to version numbers if possible
+            to_spk_version(stripped)?
+        };
+        let prefix = match prefix {
+            ">" | "<" | ">=" | "<=" | "!=" | "" => prefix,
+            "==" | "===" => "=",
+            "~=" => "~",
+            _ => return Err(Error::InvalidPythonVersionRange(prefix.to_string())),
+        };
+        let range = match (prefix, converted.strip_suffix(".*")) {
+            // "=1.*" becomes "1.*"
+            ("=", Some(_)) => converted,
+            // we cannot combine '~=' and *, but a trailing * is the
+            // most common and is semantically equal to the same version
+            // without a wildcard: !=3.7.* => !=3.7
+            ("!=", Some(base)) => format!("{prefix}{base}"),
+            // spk uses a single equals sign for exact version, where pip
+            // would use a double: ==1.4.0 => =1.4.0
+            _ => format!("{prefix}{converted}"),
+        };
+        ranges.push(range);
+    }
+    Ok(ranges.join(","))
+}
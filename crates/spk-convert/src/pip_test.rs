@@ -0,0 +1,150 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeSet;
+
+use rstest::rstest;
+use spk_schema::foundation::version::parse_version;
+use spk_schema::prelude::*;
+
+use super::{
+    to_spk_name,
+    to_spk_version,
+    to_spk_version_range,
+    PipConverter,
+    PipMetadata,
+    PipPackage,
+    PipRequirement,
+};
+
+#[rstest]
+#[case("1.0.0", "1.0.0")]
+#[case("1.0.dev456", "1.0.dev456")]
+#[case("1.0a1", "1.0-a.1")]
+#[case("1.0a2.dev456", "1.0-a.2.dev456")]
+#[case("1.0a12.dev456", "1.0-a.12.dev456")]
+#[case("1.0a12", "1.0-a.12")]
+#[case("1.0b1.dev456", "1.0-b.1.dev456")]
+#[case("1.0b2", "1.0-b.2")]
+#[case("1.0b2.post345.dev456", "1.0-b.2+post.345.dev456")]
+#[case("1.0b2.post345", "1.0-b.2+post.345")]
+#[case("1.0rc1.dev456", "1.0-rc.1.dev456")]
+#[case("1.0rc1", "1.0-rc.1")]
+#[case("1.0", "1.0")]
+#[case("1.0+abc.5", "1.0")]
+#[case("1.0+5", "1.0")]
+#[case("1.0.post456.dev34", "1.0+post.456.dev34")]
+#[case("1.0.post456", "1.0+post.456")]
+#[case("1.1.dev1", "1.1.dev1")]
+fn test_to_spk_version(#[case] version: &str, #[case] expected: &str) {
+    let actual = to_spk_version(version).unwrap();
+    assert_eq!(actual, expected);
+    parse_version(&actual).expect("converted versions should be valid spk versions");
+}
+
+#[rstest]
+fn test_to_spk_version_ordering() {
+    // the example ordering from PEP 440
+    let versions = [
+        "1.0.dev456",
+        "1.0a1",
+        "1.0a2.dev456",
+        "1.0a12.dev456",
+        "1.0a12",
+        "1.0b1.dev456",
+        "1.0b2",
+        "1.0b2.post345.dev456",
+        "1.0b2.post345",
+        "1.0rc1.dev456",
+        "1.0rc1",
+        "1.0",
+        "1.0.post456.dev34",
+        "1.0.post456",
+        "1.1.dev1",
+    ]
+    .map(|v| parse_version(to_spk_version(v).unwrap()).unwrap());
+    for pair in versions.windows(2) {
+        assert!(pair[0] < pair[1], "expected {} < {}", pair[0], pair[1]);
+    }
+}
+
+#[rstest]
+#[case(">=1.17.0", ">=1.17.0")]
+#[case(">=1.0, <2", ">=1.0,<2")]
+#[case("==1.4.0", "=1.4.0")]
+#[case("==1.*", "1.*")]
+#[case("!=3.7.*", "!=3.7")]
+#[case("~=2.2", "~2.2")]
+#[case(">=1.0rc1", ">=1.0-rc.1")]
+fn test_to_spk_version_range(#[case] specifier: &str, #[case] expected: &str) {
+    assert_eq!(to_spk_version_range(specifier).unwrap(), expected);
+}
+
+#[rstest]
+#[case("PyYAML", "python-pyyaml")]
+#[case("zope.interface", "python-zope-interface")]
+#[case("typing_extensions", "python-typing-extensions")]
+#[case("python-dateutil", "python-dateutil")]
+fn test_to_spk_name(#[case] name: &str, #[case] expected: &str) {
+    assert_eq!(to_spk_name(name), expected);
+}
+
+#[rstest]
+#[case("numpy", "numpy", &[], "")]
+#[case("numpy (>=1.17.3) ; python_version >= \"3.8\"", "numpy", &[], ">=1.17.3")]
+#[case("requests[security,socks]>=2.8.1", "requests", &["security", "socks"], ">=2.8.1")]
+#[case("pytest>=7, <8; extra == 'test'", "pytest", &[], ">=7,<8")]
+fn test_parse_pip_requirement(
+    #[case] requirement: &str,
+    #[case] name: &str,
+    #[case] extras: &[&str],
+    #[case] specifier: &str,
+) {
+    let actual = PipRequirement::parse(requirement).expect("should parse");
+    assert_eq!(actual.name, name);
+    assert_eq!(
+        actual.extras,
+        extras
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<BTreeSet<_>>()
+    );
+    assert_eq!(actual.specifier, specifier);
+}
+
+#[rstest]
+fn test_generate_recipes_orders_dependencies() {
+    let package = |name: &str, requires_dist: &[&str]| PipPackage {
+        metadata: PipMetadata {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            license: None,
+            requires_dist: requires_dist.iter().map(|r| r.to_string()).collect(),
+            requires_python: None,
+        },
+        extras: BTreeSet::new(),
+    };
+    let packages = vec![
+        package("alpha", &["Zeta>=1", "unresolved; extra == 'docs'"]),
+        package("zeta", &[]),
+    ];
+
+    let converter = PipConverter::default();
+    let recipes = converter.generate_recipes(&packages).unwrap();
+    let names: Vec<_> = recipes
+        .iter()
+        .map(|(_, recipe)| recipe.name().to_string())
+        .collect();
+    assert_eq!(names, vec!["python-zeta", "python-alpha"]);
+
+    let alpha = serde_json::to_string(&recipes[1].1).unwrap();
+    assert!(
+        alpha.contains("python-zeta/>=1"),
+        "resolved dependencies should become install requirements: {alpha}"
+    );
+    assert!(
+        !alpha.contains("python-unresolved"),
+        "dependencies that pip did not resolve should be skipped: {alpha}"
+    );
+}
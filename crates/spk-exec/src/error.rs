@@ -26,6 +26,9 @@ pub enum Error {
     #[error(transparent)]
     #[diagnostic(forward(0))]
     SpkStorageError(#[from] spk_storage::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkSolutionError(#[from] spk_solve::solution::Error),
     #[error("Error: {0}")]
     String(String),
 }
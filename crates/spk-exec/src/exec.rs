@@ -21,7 +21,7 @@ use spk_solve::{BuildIdent, RepositoryHandle};
 use spk_storage as storage;
 use tokio::pin;
 
-use crate::{Error, Result};
+use crate::{filter_resolved_runtime_layers, Error, PathFilters, Result};
 
 #[cfg(test)]
 #[path = "./exec_test.rs"]
@@ -43,7 +43,7 @@ pub struct ResolvedLayer {
 
 /// A stack of layers of a resolved solution.
 #[derive(Clone)]
-pub struct ResolvedLayers(Vec<ResolvedLayer>);
+pub struct ResolvedLayers(pub(crate) Vec<ResolvedLayer>);
 
 impl ResolvedLayers {
     /// Return a stream over all the file objects described by the resolved
@@ -54,8 +54,12 @@ impl ResolvedLayers {
         use spfs::graph::object::Enum;
         try_stream! {
             for resolved_layer in self.0.iter() {
-                let manifest = match &*resolved_layer.repo {
-                    RepositoryHandle::SPFS(repo) => {
+                let spfs_repo = resolved_layer
+                    .repo
+                    .spfs_repository_for(resolved_layer.spec.ident())
+                    .await?;
+                let manifest = match spfs_repo {
+                    Some(repo) => {
                         let object = repo.read_object(resolved_layer.digest).await?;
                         match object.into_enum() {
                             Enum::Layer(obj) => {
@@ -72,7 +76,7 @@ impl ResolvedLayers {
                             _ => continue,
                         }
                     }
-                    _ => Err(Error::NonSpfsLayerInResolvedLayers)?,
+                    None => Err(Error::NonSpfsLayerInResolvedLayers)?,
                 };
                 let unlock = manifest.to_tracking_manifest();
                 let walker = unlock.walk();
@@ -83,6 +87,36 @@ impl ResolvedLayers {
         }
     }
 
+    /// Build an index of the files provided by each package component
+    /// in these layers, to find which packages own a path.
+    pub async fn path_index(&self) -> Result<storage::PathIndex> {
+        let mut index = storage::PathIndex::default();
+        for layer in self.0.iter() {
+            let ident = layer.spec.ident();
+            let manifest =
+                storage::build_manifest(&layer.repo, ident, std::slice::from_ref(&layer.component))
+                    .await?;
+            index.insert_manifest(ident, &layer.component, &manifest);
+        }
+        Ok(index)
+    }
+
+    /// Combine the files of these layers into a single manifest, where
+    /// the files of later layers replace those of earlier ones.
+    pub async fn merged_manifest(&self) -> Result<spfs::tracking::Manifest> {
+        let mut merged = spfs::tracking::Manifest::default();
+        for layer in self.0.iter() {
+            let manifest = storage::build_manifest(
+                &layer.repo,
+                layer.spec.ident(),
+                std::slice::from_ref(&layer.component),
+            )
+            .await?;
+            merged.update(&manifest);
+        }
+        Ok(merged)
+    }
+
     /// Return the resolved layers as a list of digests.
     pub fn layers(&self) -> Vec<Digest> {
         self.0.iter().map(|l| l.digest).collect()
@@ -243,8 +277,19 @@ pub fn solution_to_resolved_runtime_layers(solution: &Solution) -> Result<Resolv
 pub async fn resolve_runtime_layers(
     requires_localization: bool,
     solution: &Solution,
+) -> Result<Vec<Digest>> {
+    resolve_filtered_runtime_layers(requires_localization, solution, &PathFilters::default()).await
+}
+
+/// Same as [`resolve_runtime_layers`], but the layers of any filtered
+/// packages only include their selected paths.
+pub async fn resolve_filtered_runtime_layers(
+    requires_localization: bool,
+    solution: &Solution,
+    filters: &PathFilters,
 ) -> Result<Vec<Digest>> {
     let resolved = solution_to_resolved_runtime_layers(solution)?;
+    let resolved = filter_resolved_runtime_layers(resolved, filters, requires_localization).await?;
     if requires_localization {
         pull_resolved_runtime_layers(&resolved).await
     } else {
@@ -272,7 +317,7 @@ pub async fn pull_resolved_runtime_layers(resolved_layers: &ResolvedLayers) -> R
 
     let to_sync_count = to_sync.len();
     for (i, (spec, repo, digest)) in to_sync.into_iter().enumerate() {
-        if let storage::RepositoryHandle::SPFS(repo) = &*repo {
+        if let Some(repo) = repo.spfs_repository_for(spec.ident()).await? {
             tracing::info!(
                 "collecting {} of {} {}",
                 i + 1,
@@ -288,6 +333,27 @@ pub async fn pull_resolved_runtime_layers(resolved_layers: &ResolvedLayers) -> R
     Ok(stack)
 }
 
+/// Merge the layers of all packages in the given solution into a single layer.
+///
+/// Package layers are pulled into the local repository as needed and their
+/// manifests are combined in stack order, so that the new layer renders the
+/// same files as the full environment would. The new layer and its manifest
+/// are written to the local repository.
+pub async fn flatten_solution(solution: &Solution) -> Result<spfs::graph::Layer> {
+    let resolved = solution_to_resolved_runtime_layers(solution)?;
+    let env = spfs::tracking::EnvSpec::from_iter(pull_resolved_runtime_layers(&resolved).await?);
+    let repo = spfs::get_config()?.get_local_repository_handle().await?;
+    let manifest = spfs::compute_environment_manifest(&env, &repo).await?;
+
+    let storable_manifest = manifest.to_graph_manifest();
+    let layer = spfs::graph::Layer::new(storable_manifest.digest()?);
+    tokio::try_join!(
+        async { repo.write_object(&storable_manifest).await },
+        async { repo.write_object(&layer).await }
+    )?;
+    Ok(layer)
+}
+
 /// Modify the active spfs runtime to include exactly the packages in the given solution.
 pub async fn setup_current_runtime(solution: &Solution) -> Result<()> {
     let mut rt = spfs::active_runtime().await?;
@@ -295,9 +361,89 @@ pub async fn setup_current_runtime(solution: &Solution) -> Result<()> {
 }
 
 pub async fn setup_runtime(rt: &mut spfs::runtime::Runtime, solution: &Solution) -> Result<()> {
+    setup_filtered_runtime(rt, solution, &PathFilters::default()).await
+}
+
+/// Same as [`setup_runtime`], but only the selected paths of
+/// any filtered packages are included in the runtime.
+pub async fn setup_filtered_runtime(
+    rt: &mut spfs::runtime::Runtime,
+    solution: &Solution,
+    filters: &PathFilters,
+) -> Result<()> {
+    let stack = resolve_filtered_runtime_layers(
+        rt.config.mount_backend.requires_localization(),
+        solution,
+        filters,
+    )
+    .await?;
+    rt.status.stack = spfs::graph::Stack::from_iter(stack);
+    save_solution_and_remount(rt, solution).await
+}
+
+/// Modify the active spfs runtime to also include the packages in the given solution.
+///
+/// See [`extend_runtime`].
+pub async fn extend_current_runtime(previous: &Solution, solution: &Solution) -> Result<()> {
+    let mut rt = spfs::active_runtime().await?;
+    extend_runtime(&mut rt, previous, solution).await
+}
+
+/// Update the runtime's stack from the previous solution that it was
+/// set up with to the given one.
+///
+/// Unlike [`setup_runtime`], the existing stack is preserved, including any
+/// layers that were not added by spk. Layers of packages that are no longer
+/// in the solution, eg because they were upgraded, are removed and any new
+/// layers are added to the top of the stack. The solution is expected to be
+/// a superset of the packages already in the runtime, as it replaces the
+/// solve data stored in the runtime.
+pub async fn extend_runtime(
+    rt: &mut spfs::runtime::Runtime,
+    previous: &Solution,
+    solution: &Solution,
+) -> Result<()> {
+    let previous = solution_to_resolved_runtime_layers(previous)?.layers();
     let stack =
         resolve_runtime_layers(rt.config.mount_backend.requires_localization(), solution).await?;
-    rt.status.stack = spfs::graph::Stack::from_iter(stack);
+    rt.status.stack = replace_layers(&rt.status.stack, &previous, &stack);
+    save_solution_and_remount(rt, solution).await
+}
+
+/// Remove the previous layers from the stack that are not also in the new
+/// ones, and add any new layers that are not already in the stack on top.
+pub(crate) fn replace_layers(
+    stack: &spfs::graph::Stack,
+    previous: &[Digest],
+    layers: &[Digest],
+) -> spfs::graph::Stack {
+    let keep: HashSet<_> = layers.iter().collect();
+    let removed: HashSet<_> = previous.iter().filter(|d| !keep.contains(d)).collect();
+    let mut updated = stack
+        .iter_bottom_up()
+        .filter(|digest| !removed.contains(&digest))
+        .collect::<spfs::graph::Stack>();
+    let existing: HashSet<_> = updated.iter_bottom_up().collect();
+    for digest in layers {
+        if !existing.contains(digest) {
+            updated.push(*digest);
+        }
+    }
+    updated
+}
+
+async fn save_solution_and_remount(
+    rt: &mut spfs::runtime::Runtime,
+    solution: &Solution,
+) -> Result<()> {
+    let conflicts = solution.environment_conflicts();
+    if !conflicts.is_empty() {
+        tracing::warn!("Packages in the solution set conflicting environment variables:");
+        for conflict in conflicts {
+            tracing::warn!(" > {conflict}");
+        }
+        tracing::warn!(" > The last value is used, unless the packages are given a priority");
+    }
 
     let spfs_config = spfs::Config::current()?;
     // Annotations are only supported with FlatFileBuffers
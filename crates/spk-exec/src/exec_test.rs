@@ -13,6 +13,7 @@ use spk_solve::{DecisionFormatterBuilder, Solver};
 use spk_solve_macros::request;
 use spk_storage::fixtures::*;
 
+use super::replace_layers;
 use crate::solution_to_resolved_runtime_layers;
 
 #[fixture]
@@ -82,3 +83,66 @@ build:
     assert!(environment.get_path("subdir/one.txt").is_some());
     assert!(environment.get_path("subdir/two.txt").is_some());
 }
+
+/// The path index of a solution should identify the package
+/// and component that provided each file.
+#[rstest]
+#[tokio::test]
+async fn path_index_finds_owning_package(tmpdir: tempfile::TempDir, mut solver: Solver) {
+    let rt = spfs_runtime().await;
+
+    build_package!(
+        tmpdir,
+        "one.spk.yaml",
+        br#"
+api: v0/package
+pkg: one/1.0.0
+
+build:
+  script:
+    - mkdir "$PREFIX"/bin
+    - touch "$PREFIX"/bin/one
+"#,
+    );
+
+    let formatter = DecisionFormatterBuilder::default()
+        .with_verbosity(0)
+        .build();
+
+    solver.add_repository(Arc::clone(&rt.tmprepo));
+    solver.add_request(request!("one"));
+
+    let (solution, _) = formatter.run_and_log_resolve(&solver).await.unwrap();
+
+    let resolved_layers = solution_to_resolved_runtime_layers(&solution).unwrap();
+    let index = resolved_layers.path_index().await.unwrap();
+
+    let owners = index.owners_of("/spfs/bin/one");
+    assert!(!owners.is_empty(), "expected the file to have an owner");
+    assert!(owners.iter().all(|o| o.pkg.name().as_str() == "one"));
+    assert!(index.owners_of("/spfs/bin/two").is_empty());
+}
+
+/// Layers of packages that were replaced by a new solution should
+/// be removed from the stack, while layers that were not added by
+/// spk are left in place.
+#[rstest]
+fn replace_layers_removes_upgraded_packages() {
+    let unchanged = spfs::fixtures::random_digest();
+    let upgraded_from = spfs::fixtures::random_digest();
+    let not_from_spk = spfs::fixtures::random_digest();
+    let upgraded_to = spfs::fixtures::random_digest();
+    let installed = spfs::fixtures::random_digest();
+
+    let stack = spfs::graph::Stack::from_iter([unchanged, upgraded_from, not_from_spk]);
+    let updated = replace_layers(
+        &stack,
+        &[unchanged, upgraded_from],
+        &[unchanged, upgraded_to, installed],
+    );
+
+    assert_eq!(
+        updated.iter_bottom_up().collect::<Vec<_>>(),
+        vec![unchanged, not_from_spk, upgraded_to, installed]
+    );
+}
@@ -4,15 +4,29 @@
 
 mod error;
 mod exec;
+mod path_filter;
+mod runtime_dir;
 
 pub use error::{Error, Result};
 pub use exec::{
+    extend_current_runtime,
+    extend_runtime,
+    flatten_solution,
     pull_resolved_runtime_layers,
+    resolve_filtered_runtime_layers,
     resolve_runtime_layers,
     setup_current_runtime,
+    setup_filtered_runtime,
     setup_runtime,
     solution_to_resolved_runtime_layers,
     ConflictingPackagePair,
     ResolvedLayer,
     ResolvedLayers,
 };
+pub use path_filter::{filter_resolved_runtime_layers, PathFilters};
+pub use runtime_dir::{
+    activation_script,
+    activation_script_path,
+    prepare_runtime_dir,
+    render_runtime_dir,
+};
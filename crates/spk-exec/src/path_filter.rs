@@ -0,0 +1,136 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::HashMap;
+
+use relative_path::RelativePath;
+use spfs::prelude::*;
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::foundation::name::{PkgName, PkgNameBuf};
+use spk_schema::foundation::spec_ops::FileMatcher;
+use spk_schema::prelude::*;
+use spk_storage as storage;
+
+use crate::{Error, ResolvedLayer, ResolvedLayers, Result};
+
+#[cfg(test)]
+#[path = "./path_filter_test.rs"]
+mod path_filter_test;
+
+/// Paths that are kept in every filtered layer, because spk
+/// and spfs need them to find and set up the package
+const ALWAYS_INCLUDED_PATHS: &[&str] = &["spk", "etc/spfs"];
+
+/// Limits some packages in an environment to the files under
+/// selected paths, so that only part of each is checked out.
+///
+/// This is useful for large packages where only a small part of
+/// them is needed, as less data has to be synced and rendered.
+#[derive(Clone, Debug, Default)]
+pub struct PathFilters {
+    filters: HashMap<PkgNameBuf, Vec<String>>,
+}
+
+impl PathFilters {
+    /// True if no package is filtered
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Only include the files of the named package that match the given
+    /// patterns, in addition to any that were already added for it.
+    ///
+    /// The patterns follow the gitignore format, relative to /spfs.
+    pub fn add<I, S>(&mut self, name: PkgNameBuf, patterns: I) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut rules = self.filters.get(&name).cloned().unwrap_or_default();
+        rules.extend(patterns.into_iter().map(Into::into));
+        // validate the patterns now, rather than when they are used
+        FileMatcher::new(rules.iter().cloned()).map_err(|err| Error::String(err.to_string()))?;
+        self.filters.insert(name, rules);
+        Ok(())
+    }
+
+    /// The names of the filtered packages
+    pub fn names(&self) -> impl Iterator<Item = &PkgName> {
+        self.filters.keys().map(|name| name.as_ref())
+    }
+
+    /// The file matcher for the named package, if it is filtered
+    pub fn get(&self, name: &PkgName) -> Option<FileMatcher> {
+        let rules = self.filters.get(name)?;
+        // the rules were validated when they were added
+        Some(FileMatcher::new(rules.iter().cloned()).expect("rules should be valid"))
+    }
+
+    /// Reports true if the given path of a filtered package is kept
+    /// when the package is filtered with the given matcher.
+    pub fn keeps(matcher: &FileMatcher, path: &RelativePath, is_dir: bool) -> bool {
+        ALWAYS_INCLUDED_PATHS
+            .iter()
+            .any(|included| path.starts_with(included))
+            || matcher.matches(path.to_path("/"), is_dir)
+    }
+}
+
+/// Replace the layers of any filtered packages with new layers that
+/// only hold the selected paths.
+///
+/// The new layers are written to the local repository. When the runtime
+/// requires localization, only the payloads of the selected files are
+/// synced, otherwise they are read from the package repository as needed.
+pub async fn filter_resolved_runtime_layers(
+    resolved: ResolvedLayers,
+    filters: &PathFilters,
+    requires_localization: bool,
+) -> Result<ResolvedLayers> {
+    if filters.is_empty() {
+        return Ok(resolved);
+    }
+    let local_repo = storage::local_repository().await?;
+    let mut layers = Vec::with_capacity(resolved.0.len());
+    for layer in resolved.0.into_iter() {
+        let Some(matcher) = filters.get(layer.spec.name()) else {
+            layers.push(layer);
+            continue;
+        };
+        let Some(repo) = layer.repo.spfs_repository_for(layer.spec.ident()).await? else {
+            return Err(Error::NonSpfsLayerInResolvedLayers);
+        };
+        let source = repo.read_layer(layer.digest).await?;
+        let Some(manifest_digest) = source.manifest() else {
+            layers.push(layer);
+            continue;
+        };
+        let manifest = repo
+            .read_manifest(*manifest_digest)
+            .await?
+            .to_tracking_manifest()
+            .filter(|path, is_dir| PathFilters::keeps(&matcher, path, is_dir))
+            .to_graph_manifest();
+        let filtered = spfs::graph::Layer::new(manifest.digest()?);
+        let digest = filtered.digest()?;
+        if !local_repo.has_object(digest).await {
+            tracing::info!(
+                "filtering {} {}",
+                layer.spec.ident().format_ident(),
+                layer.component
+            );
+            if requires_localization {
+                spfs::Syncer::new(repo, &local_repo)
+                    .with_reporter(spfs::sync::ConsoleSyncReporter::default())
+                    .sync_manifest(manifest)
+                    .await?;
+            } else {
+                local_repo.write_object(&manifest).await?;
+            }
+            local_repo.write_object(&filtered).await?;
+        }
+        layers.push(ResolvedLayer { digest, ..layer });
+    }
+    Ok(ResolvedLayers(layers))
+}
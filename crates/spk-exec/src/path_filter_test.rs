@@ -0,0 +1,61 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use relative_path::RelativePath;
+use rstest::rstest;
+use spk_schema::foundation::pkg_name;
+
+use super::PathFilters;
+
+#[rstest]
+#[case("lib", true, true)]
+#[case("lib/libbig.so", false, true)]
+#[case("lib/python/big.py", false, true)]
+#[case("bin/big", false, false)]
+#[case("share", true, false)]
+#[case("spk/pkg/big/1.0.0/3I42H3S6/spec.yaml", false, true)]
+#[case("etc/spfs/startup.d/spk_big.sh", false, true)]
+#[case("etc/big.conf", false, false)]
+fn test_path_filters_keeps(#[case] path: &str, #[case] is_dir: bool, #[case] expected: bool) {
+    let mut filters = PathFilters::default();
+    filters.add(pkg_name!("big").to_owned(), ["/lib/"]).unwrap();
+    let matcher = filters.get(pkg_name!("big")).unwrap();
+    assert_eq!(
+        PathFilters::keeps(&matcher, RelativePath::new(path), is_dir),
+        expected,
+        "unexpected result for {path}"
+    );
+    assert!(filters.get(pkg_name!("other")).is_none());
+}
+
+#[rstest]
+fn test_path_filters_add_combines_patterns() {
+    let mut filters = PathFilters::default();
+    filters.add(pkg_name!("big").to_owned(), ["/lib/"]).unwrap();
+    filters.add(pkg_name!("big").to_owned(), ["*.h"]).unwrap();
+    let matcher = filters.get(pkg_name!("big")).unwrap();
+    assert!(PathFilters::keeps(
+        &matcher,
+        RelativePath::new("lib/libbig.so"),
+        false
+    ));
+    assert!(PathFilters::keeps(
+        &matcher,
+        RelativePath::new("include/big.h"),
+        false
+    ));
+    assert!(!PathFilters::keeps(
+        &matcher,
+        RelativePath::new("include/big.hpp"),
+        false
+    ));
+}
+
+#[rstest]
+fn test_path_filters_invalid_pattern() {
+    let mut filters = PathFilters::default();
+    filters
+        .add(pkg_name!("big").to_owned(), ["lib/[z-a]"])
+        .expect_err("should reject an invalid pattern");
+}
@@ -0,0 +1,184 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! Render a solved environment into a plain directory.
+//!
+//! A runtime directory holds the same files that would be seen in
+//! /spfs, along with activation scripts that set up the environment
+//! of each package. It can be used on machines or in containers that
+//! cannot mount an spfs runtime, but packages that refer to /spfs by
+//! absolute path will not work from within it.
+
+use std::path::{Path, PathBuf};
+
+use relative_path::RelativePath;
+use spfs::storage::fallback::FallbackProxy;
+use spk_schema::prelude::*;
+use spk_schema::{EnvOp, SetEnv};
+use spk_solve::solution::Solution;
+
+use crate::{resolve_runtime_layers, Error, Result};
+
+#[cfg(test)]
+#[path = "./runtime_dir_test.rs"]
+mod runtime_dir_test;
+
+/// The shells that activation scripts are generated for, along
+/// with the file extension of the script for each shell.
+const ACTIVATION_SCRIPT_SHELLS: &[(spfs::ShellKind, &str)] = &[
+    (spfs::ShellKind::Bash, "sh"),
+    (spfs::ShellKind::Tcsh, "csh"),
+    (spfs::ShellKind::Fish, "fish"),
+    (spfs::ShellKind::Powershell, "ps1"),
+];
+
+/// The location of the activation script for the given
+/// shell file extension, relative to the runtime directory.
+pub fn activation_script_path(extension: &str) -> relative_path::RelativePathBuf {
+    RelativePath::new("etc/spfs").join(format!("activate.{extension}"))
+}
+
+/// Render all the packages in a solution into the given empty directory,
+/// and generate the activation scripts for the environment.
+///
+/// Payloads are copied rather than linked from the local repository,
+/// so that the directory can be modified or moved without risk.
+pub async fn render_runtime_dir(solution: &Solution, root: &Path) -> Result<()> {
+    let stack = resolve_runtime_layers(true, solution).await?;
+    let config = spfs::get_config()?;
+    let local = config.get_opened_local_repository().await?;
+
+    // any payloads that were not localized can be
+    // pulled from the repositories used in the solution
+    let mut fallbacks = Vec::new();
+    for repo in solution
+        .repositories()
+        .iter()
+        .filter(|repo| repo.is_spfs() && repo.name() != "local")
+    {
+        if let Ok(handle) = spfs::open_repository(repo.address()).await {
+            fallbacks.push(handle);
+        }
+    }
+
+    tracing::info!("Rendering into dir: {root:?}");
+    if fallbacks.is_empty() {
+        spfs::storage::fs::Renderer::new(&local)
+            .with_reporter(spfs::storage::fs::ConsoleRenderReporter::default())
+            .render_into_directory(stack, root, spfs::storage::fs::RenderType::Copy)
+            .await?;
+    } else {
+        let fallback = FallbackProxy::new(local, fallbacks);
+        spfs::storage::fs::Renderer::new(&fallback)
+            .with_reporter(spfs::storage::fs::ConsoleRenderReporter::default())
+            .render_into_directory(stack, root, spfs::storage::fs::RenderType::Copy)
+            .await?;
+    }
+
+    for (shell, extension) in ACTIVATION_SCRIPT_SHELLS {
+        let path = activation_script_path(extension).to_path(root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| {
+                Error::String(format!("Failed to create {}: {err}", parent.display()))
+            })?;
+        }
+        std::fs::write(&path, activation_script(solution, root, *shell)).map_err(|err| {
+            Error::String(format!(
+                "Failed to write activation script {}: {err}",
+                path.display()
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+/// Generate a script that activates the environment of a
+/// solution that was rendered into the given directory.
+///
+/// The runtime environment of each package, and of its installed
+/// components, is included in solve order, with any references to
+/// /spfs replaced by the directory.
+pub fn activation_script(solution: &Solution, root: &Path, shell: spfs::ShellKind) -> String {
+    let root = root.to_string_lossy();
+    let mut vars = solution
+        .to_environment(None::<Vec<_>>)
+        .into_iter()
+        .collect::<Vec<_>>();
+    vars.sort();
+    vars.push(("SPK_RUNTIME_DIR".to_string(), root.to_string()));
+
+    let mut lines = vec![format!(
+        "# Activates the spk environment that was rendered into {root}"
+    )];
+    for (name, value) in vars {
+        let value = match name.as_str() {
+            "SPK_ACTIVE_PREFIX" => root.to_string(),
+            _ => value,
+        };
+        lines.push(EnvOp::Set(SetEnv { set: name, value }).source_for_shell(shell));
+    }
+    for item in solution.items() {
+        let ops = item.runtime_environment();
+        if ops.is_empty() {
+            continue;
+        }
+        lines.push(String::new());
+        lines.push(format!("# {}", item.spec.ident()));
+        for op in ops.iter() {
+            if op.priority().is_some() {
+                continue;
+            }
+            let op = match op.value() {
+                Some(value) => op.update_value(relocate_value(value, &root)),
+                None => op.clone(),
+            };
+            lines.push(op.source_for_shell(shell));
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Replace references to /spfs in an environment value
+/// with the given runtime directory.
+fn relocate_value(value: &str, root: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(index) = rest.find("/spfs") {
+        let after = &rest[index + "/spfs".len()..];
+        result.push_str(&rest[..index]);
+        let starts_path = result.is_empty() || result.ends_with([':', ';', '=', ' ', '"']);
+        let ends_path = after.is_empty() || after.starts_with(['/', ':', ';', ' ', '"']);
+        match starts_path && ends_path {
+            true => result.push_str(root),
+            false => result.push_str("/spfs"),
+        }
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Check that the given directory can be rendered into,
+/// creating it if needed, and return its absolute path.
+pub fn prepare_runtime_dir(path: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(path).map_err(|err| {
+        Error::String(format!(
+            "Failed to create runtime directory {}: {err}",
+            path.display()
+        ))
+    })?;
+    let is_empty = std::fs::read_dir(path)
+        .map_err(|err| Error::String(format!("Failed to read {}: {err}", path.display())))?
+        .next()
+        .is_none();
+    if !is_empty {
+        return Err(Error::String(format!(
+            "Runtime directory does not appear to be empty: {}",
+            path.display()
+        )));
+    }
+    dunce::canonicalize(path)
+        .map_err(|err| Error::String(format!("Failed to resolve {}: {err}", path.display())))
+}
@@ -0,0 +1,37 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::path::Path;
+
+use rstest::rstest;
+use spk_schema::foundation::option_map::OptionMap;
+use spk_solve::solution::Solution;
+
+use super::{activation_script, relocate_value};
+
+#[rstest]
+#[case("/spfs/bin", "/opt/env/bin")]
+#[case("/spfs", "/opt/env")]
+#[case(
+    "/spfs/lib:/usr/lib:/spfs/lib64",
+    "/opt/env/lib:/usr/lib:/opt/env/lib64"
+)]
+#[case("/mnt/spfs/lib", "/mnt/spfs/lib")]
+#[case("/spfsx/lib", "/spfsx/lib")]
+#[case("$HOME/bin", "$HOME/bin")]
+#[case("/spfs/spfs", "/opt/env/spfs")]
+fn test_relocate_value(#[case] value: &str, #[case] expected: &str) {
+    assert_eq!(relocate_value(value, "/opt/env"), expected);
+}
+
+#[rstest]
+fn test_activation_script_sets_prefix() {
+    let solution = Solution::new(OptionMap::default());
+    let script = activation_script(&solution, Path::new("/opt/env"), spfs::ShellKind::Bash);
+    assert!(script.contains("export SPK_RUNTIME_DIR=\"/opt/env\""));
+    assert!(
+        script.contains("export SPK_ACTIVE_PREFIX=\"/opt/env\""),
+        "active prefix should refer to the runtime dir, got:\n{script}"
+    );
+}
@@ -58,6 +58,7 @@ macro_rules! opt_name {
 /// Denotes that an invalid package name was given.
 #[derive(Diagnostic, Debug, Error)]
 #[error("Invalid name: {message}")]
+#[diagnostic(code(spk::schema::invalid_name))]
 pub struct InvalidNameError {
     pub message: String,
 }
@@ -87,12 +88,48 @@ impl Borrow<OptName> for PkgNameBuf {
 impl PkgName {
     pub const MIN_LEN: usize = 2;
     pub const MAX_LEN: usize = 64;
+    /// Separates the namespace of a package from its base name
+    pub const NAMESPACE_SEP: char = '/';
 
     /// Validate the given string as a package name
     pub fn validate<S: AsRef<str> + ?Sized>(s: &S) -> Result<()> {
         validate_pkg_name(s)
     }
 
+    /// Validate the given string as a package namespace
+    pub fn validate_namespace<S: AsRef<str> + ?Sized>(s: &S) -> Result<()> {
+        validate_pkg_namespace(s)
+    }
+
+    /// The namespace (organization) that this package belongs to, if any
+    ///
+    /// ```
+    /// # #[macro_use] extern crate spk_schema_foundation;
+    /// # fn main() {
+    /// assert_eq!(pkg_name!("studio.animtools/my-pkg").namespace(), Some("studio.animtools"));
+    /// assert_eq!(pkg_name!("my-pkg").namespace(), None);
+    /// # }
+    /// ```
+    pub fn namespace(&self) -> Option<&str> {
+        self.0.split_once(Self::NAMESPACE_SEP).map(|(ns, _)| ns)
+    }
+
+    /// The name of this package without any leading namespace
+    ///
+    /// ```
+    /// # #[macro_use] extern crate spk_schema_foundation;
+    /// # fn main() {
+    /// assert_eq!(pkg_name!("studio.animtools/my-pkg").base_name(), "my-pkg");
+    /// assert_eq!(pkg_name!("my-pkg").base_name(), "my-pkg");
+    /// # }
+    /// ```
+    pub fn base_name(&self) -> &str {
+        self.0
+            .split_once(Self::NAMESPACE_SEP)
+            .map(|(_, n)| n)
+            .unwrap_or(&self.0)
+    }
+
     /// Interpret this package name as an option name
     pub fn as_opt_name(&self) -> &OptName {
         self.borrow()
@@ -107,8 +144,58 @@ impl AsRef<OptName> for PkgName {
     }
 }
 
-/// Ensure that the provided string is a valid package name
+/// Ensure that the provided string is a valid package name,
+/// with or without a leading namespace.
 fn validate_pkg_name<S: AsRef<str>>(name: S) -> Result<()> {
+    match name.as_ref().split_once(PkgName::NAMESPACE_SEP) {
+        Some((ns, base)) => {
+            validate_pkg_namespace(ns)?;
+            validate_pkg_base_name(base)
+        }
+        None => validate_pkg_base_name(name),
+    }
+}
+
+/// Ensure that the provided string is a valid package namespace.
+///
+/// Namespaces are made up of two or more dot-separated parts, eg:
+/// `studio.animtools`. Each part must begin with a letter, and is
+/// otherwise made up of the same characters as a package name. This
+/// keeps namespaces distinct from repository names and versions
+/// when parsing identifiers.
+fn validate_pkg_namespace<S: AsRef<str>>(namespace: S) -> Result<()> {
+    let namespace = namespace.as_ref();
+    if namespace.len() > PkgName::MAX_LEN {
+        return Err(InvalidNameError::new_error(format!(
+            "Invalid package namespace, must be no more than {} characters, got {namespace} [{}]",
+            PkgName::MAX_LEN,
+            namespace.len(),
+        )));
+    }
+    if !namespace.contains('.') {
+        return Err(InvalidNameError::new_error(format!(
+            "Invalid package namespace, must contain at least two dot-separated parts, got {namespace}"
+        )));
+    }
+    for part in namespace.split('.') {
+        if !part.starts_with(|c: char| c.is_ascii_lowercase()) {
+            return Err(InvalidNameError::new_error(format!(
+                "Invalid package namespace, each part must begin with a letter, got {namespace}"
+            )));
+        }
+        let index = validate_source_str(part, is_valid_pkg_name_char);
+        if index > -1 {
+            return Err(InvalidNameError::new_error(format!(
+                "Invalid package namespace, invalid character in '{part}', got {namespace}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Ensure that the provided string is a valid package name
+/// without any leading namespace.
+fn validate_pkg_base_name<S: AsRef<str>>(name: S) -> Result<()> {
     if name.as_ref().len() < PkgName::MIN_LEN {
         return Err(InvalidNameError::new_error(format!(
             "Invalid package name, must be at least {} characters, got {} [{}]",
@@ -201,15 +288,14 @@ impl OptName {
     /// # }
     /// ```
     pub fn base_name(&self) -> &str {
-        self.split_once(Self::SEP)
+        split_opt_namespace(&self.0)
             .map(|(_, n)| n)
             .unwrap_or(&self.0)
     }
 
     /// The package namespace defined in this option, if any
     pub fn namespace(&self) -> Option<&PkgName> {
-        self.0
-            .split_once(Self::SEP)
+        split_opt_namespace(&self.0)
             // Safety: from_str skips validation, but we've already validated
             // the namespace as a package name in [`Self::new`] if it is set
             .map(|(ns, _)| unsafe { PkgName::from_str(ns) })
@@ -238,6 +324,21 @@ impl OptName {
     }
 }
 
+/// Split an option name into its package and base name, if it has a package.
+///
+/// The package portion may itself have a namespace that contains
+/// dots, so only the first [`OptName::SEP`] after any
+/// [`PkgName::NAMESPACE_SEP`] is considered.
+fn split_opt_namespace(name: &str) -> Option<(&str, &str)> {
+    let start = name
+        .find(PkgName::NAMESPACE_SEP)
+        .map(|i| i + 1)
+        .unwrap_or_default();
+    name[start..]
+        .find(OptName::SEP)
+        .map(|i| (&name[..start + i], &name[start + i + 1..]))
+}
+
 impl OptNameBuf {
     /// Construct a valid OptNameBuf, invalid characters will be
     /// removed, the length will be padded or truncated, and uppercase
@@ -268,7 +369,7 @@ impl OptNameBuf {
 
 impl EnvName for OptNameBuf {
     fn env_name(&self) -> String {
-        self.0.replace('-', "_")
+        self.0.replace(['-', PkgName::NAMESPACE_SEP], "_")
     }
 }
 
@@ -277,11 +378,13 @@ impl EnvName for OptNameBuf {
 /// This is for checking option names with or without any leading
 /// package namespace.
 fn validate_opt_name<S: AsRef<str>>(name: S) -> Result<()> {
-    match name.as_ref().split_once(OptName::SEP) {
+    match split_opt_namespace(name.as_ref()) {
         Some((ns, opt)) => {
             validate_pkg_name(ns)?;
             validate_opt_base_name(opt)
         }
+        // options can be named after a namespaced package
+        None if name.as_ref().contains(PkgName::NAMESPACE_SEP) => validate_pkg_name(name),
         None => validate_opt_base_name(name),
     }
 }
@@ -371,8 +474,9 @@ where
 impl RepositoryName {
     /// Validate the given string as a repository name
     pub fn validate<S: AsRef<str> + ?Sized>(s: &S) -> Result<()> {
-        // Using the same validation strategy as package names.
-        validate_pkg_name(s)
+        // Using the same validation strategy as package names,
+        // but repositories cannot be namespaced.
+        validate_pkg_base_name(s)
     }
 }
 
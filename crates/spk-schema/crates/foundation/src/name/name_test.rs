@@ -17,6 +17,16 @@ use rstest::rstest;
 #[case("has_dashes")] // no underscores
 #[should_panic]
 #[case("name!!")] // no special characters
+#[case("studio.animtools/my-pkg")]
+#[case("org.team-1.sub/pkg")]
+#[should_panic]
+#[case("studio/my-pkg")] // namespaces need at least two parts
+#[should_panic]
+#[case("studio.1tools/my-pkg")] // namespace parts start with a letter
+#[should_panic]
+#[case("studio.animtools/")] // missing base name
+#[should_panic]
+#[case("a.b/c.d/my-pkg")] // only one namespace
 fn test_pkg_validation(#[case] input: &str) {
     super::validate_pkg_name(input).unwrap();
     super::validate_opt_name(input).expect("all valid package names should be valid option names");
@@ -59,9 +69,39 @@ fn test_opt_name_buf_new_lossy(#[case] input: &str, #[case] result: &str) {
 #[rstest]
 #[case("my_opt", None, "my_opt")]
 #[case("my-pkg.my_opt", Some("my-pkg"), "my_opt")]
+#[case(
+    "studio.animtools/my-pkg.my_opt",
+    Some("studio.animtools/my-pkg"),
+    "my_opt"
+)]
 fn test_opt_name_namespace(#[case] input: &str, #[case] ns: Option<&str>, #[case] name: &str) {
     let full = super::OptName::new(input).expect("invalid option name");
     let ns = ns.map(|ns| super::PkgName::new(ns).unwrap());
     assert_eq!(full.namespace(), ns);
     assert_eq!(full.base_name(), name);
 }
+
+#[rstest]
+#[case("my-pkg", None, "my-pkg")]
+#[case("studio.animtools/my-pkg", Some("studio.animtools"), "my-pkg")]
+fn test_pkg_name_namespace(#[case] input: &str, #[case] ns: Option<&str>, #[case] name: &str) {
+    let pkg = super::PkgName::new(input).expect("invalid package name");
+    assert_eq!(pkg.namespace(), ns);
+    assert_eq!(pkg.base_name(), name);
+}
+
+#[rstest]
+#[case("studio.animtools/my-pkg", "studio.animtools/my-pkg", "")]
+#[case("studio.animtools/my-pkg/1.0.0", "studio.animtools/my-pkg", "/1.0.0")]
+#[case("my-pkg/1.0.0", "my-pkg", "/1.0.0")]
+#[case("studio/my-pkg", "studio", "/my-pkg")]
+fn test_parse_package_name_namespace(
+    #[case] input: &str,
+    #[case] expected: &str,
+    #[case] remaining: &str,
+) {
+    let (rest, name) =
+        super::parsing::package_name::<nom::error::VerboseError<&str>>(input).unwrap();
+    assert_eq!(name.as_str(), expected);
+    assert_eq!(rest, remaining);
+}
@@ -8,6 +8,7 @@ pub use name::{
     is_legal_package_name_chr,
     known_repository_name,
     package_name,
+    package_namespace,
     repository_name,
     tag_name,
 };
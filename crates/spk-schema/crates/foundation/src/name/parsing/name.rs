@@ -4,11 +4,12 @@
 
 use std::collections::HashSet;
 
-use nom::bytes::complete::{is_not, take_till, take_while1, take_while_m_n};
-use nom::character::complete::char;
-use nom::combinator::{fail, map, not, peek, recognize, verify};
+use nom::bytes::complete::{is_not, take_till, take_while, take_while1, take_while_m_n};
+use nom::character::complete::{char, satisfy};
+use nom::combinator::{fail, map, not, opt, peek, recognize, verify};
 use nom::error::{ContextError, ParseError};
 use nom::multi::many1;
+use nom::sequence::{pair, preceded, terminated};
 use nom::IResult;
 
 use crate::name::{PkgName, RepositoryName};
@@ -60,27 +61,24 @@ where
     }
 }
 
-/// Parse a package name.
+/// Parse a package name, including its namespace if present.
 ///
 /// Examples:
 /// - `"pkg1"`
 /// - `"pkg-name"`
+/// - `"studio.animtools/pkg-name"`
 ///
-/// A package name must be at least [`PkgName::MIN_LEN`] characters and no
-/// more than [`PkgName::MAX_LEN`] characters.
+/// The base package name must be at least [`PkgName::MIN_LEN`] characters
+/// and no more than [`PkgName::MAX_LEN`] characters.
 pub fn package_name<'a, E>(input: &'a str) -> IResult<&'a str, &PkgName, E>
 where
     E: ParseError<&'a str> + ContextError<&'a str>,
 {
-    // Package names may not begin with a '-'
-    let (input, _) = not(peek(char('-')))(input)?;
-
     map(
-        take_while_m_n(
-            PkgName::MIN_LEN,
-            PkgName::MAX_LEN,
-            is_legal_package_name_chr,
-        ),
+        recognize(pair(
+            opt(terminated(package_namespace, char(PkgName::NAMESPACE_SEP))),
+            package_base_name,
+        )),
         |s: &str| {
             // Safety: we only generate valid package names
             unsafe { PkgName::from_str(s) }
@@ -88,6 +86,49 @@ where
     )(input)
 }
 
+/// Parse a package name without any namespace.
+fn package_base_name<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    // Package names may not begin with a '-'
+    let (input, _) = not(peek(char('-')))(input)?;
+
+    take_while_m_n(
+        PkgName::MIN_LEN,
+        PkgName::MAX_LEN,
+        is_legal_package_name_chr,
+    )(input)
+}
+
+/// Parse a package namespace.
+///
+/// Examples:
+/// - `"studio.animtools"`
+/// - `"org.team-1.sub"`
+///
+/// A namespace has at least two dot-separated parts that each begin
+/// with a letter.
+pub fn package_namespace<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    fn part<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+    where
+        E: ParseError<&'a str> + ContextError<&'a str>,
+    {
+        recognize(pair(
+            satisfy(|c| c.is_ascii_lowercase()),
+            take_while(is_legal_package_name_chr),
+        ))(input)
+    }
+
+    verify(
+        recognize(pair(part, many1(preceded(char('.'), part)))),
+        |s: &str| s.len() <= PkgName::MAX_LEN,
+    )(input)
+}
+
 /// Parse a repository name.
 ///
 /// Examples:
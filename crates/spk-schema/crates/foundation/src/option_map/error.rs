@@ -5,6 +5,8 @@
 use miette::Diagnostic;
 use thiserror::Error;
 
+use super::OptionValueType;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Clone, Diagnostic, Debug, Error)]
@@ -17,4 +19,11 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error("Error: {0}")]
     String(String),
+    #[error("Invalid {value_type} option value '{value}': {reason}")]
+    #[diagnostic(code(spk::schema::invalid_option_value))]
+    InvalidOptionValue {
+        value: String,
+        value_type: OptionValueType,
+        reason: String,
+    },
 }
@@ -0,0 +1,133 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::process::{Command, Stdio};
+
+use super::{Error, OptionMap, Result};
+use crate::name::{OptName, OptNameBuf};
+
+#[cfg(test)]
+#[path = "./host_test.rs"]
+mod host_test;
+
+/// Detect the options for the current host system, and then
+/// apply any additions or overrides from the given config.
+pub fn detect_host_options(config: &spk_config::HostOptions) -> Result<OptionMap> {
+    let mut opts = OptionMap::default();
+    opts.insert(OptName::os().to_owned(), std::env::consts::OS.into());
+    opts.insert(OptName::arch().to_owned(), std::env::consts::ARCH.into());
+
+    let info = match sys_info::linux_os_release() {
+        Ok(i) => i,
+        Err(err) => {
+            return Err(Error::String(format!("Failed to get linux info: {err:?}")));
+        }
+    };
+
+    if let Some(id) = info.id {
+        add_distro_options(&mut opts, &id, info.version_id.as_deref(), config);
+    }
+
+    apply_host_options_config(&mut opts, config)?;
+    Ok(opts)
+}
+
+/// Add the `distro` option and distro version option for the
+/// given os-release id and version.
+///
+/// The id is made into a valid option name if needed, and the
+/// version is shortened as configured for that distro.
+pub fn add_distro_options(
+    opts: &mut OptionMap,
+    id: &str,
+    version_id: Option<&str>,
+    config: &spk_config::HostOptions,
+) {
+    let Some(name) = normalize_distro_id(id) else {
+        tracing::warn!("Reported distro id is not a valid option name: {id}");
+        opts.insert(OptName::distro().to_owned(), id.to_string());
+        return;
+    };
+    opts.insert(OptName::distro().to_owned(), name.to_string());
+    if let Some(version_id) = version_id {
+        let version = match config
+            .distro_version_parts
+            .get(id)
+            .or_else(|| config.distro_version_parts.get(name.as_str()))
+        {
+            Some(parts) => version_id
+                .split('.')
+                .take((*parts).max(1))
+                .collect::<Vec<_>>()
+                .join("."),
+            None => version_id.to_string(),
+        };
+        opts.insert(name, version);
+    }
+}
+
+/// Turn an os-release id into a valid option name.
+///
+/// Ids are meant to be lowercase with only a few punctuation
+/// characters, any of which that are not valid in option
+/// names are replaced with a dash.
+fn normalize_distro_id(id: &str) -> Option<OptNameBuf> {
+    let name: String = id
+        .trim()
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect();
+    OptNameBuf::try_from(name.trim_matches('-')).ok()
+}
+
+/// Apply the options from configured commands and then the
+/// options listed in the config itself, replacing any
+/// existing options with the same name.
+pub fn apply_host_options_config(
+    opts: &mut OptionMap,
+    config: &spk_config::HostOptions,
+) -> Result<()> {
+    for command in config.commands.iter() {
+        opts.extend(run_host_options_command(&command.command)?);
+    }
+    for (name, value) in config.options.iter() {
+        let name = OptNameBuf::try_from(name.as_str())
+            .map_err(|err| Error::String(format!("Invalid host option name in config: {err}")))?;
+        opts.insert(name, value.clone());
+    }
+    Ok(())
+}
+
+fn run_host_options_command(cmd: &[String]) -> Result<OptionMap> {
+    let Some(executable) = cmd.first() else {
+        tracing::warn!("Empty command in host options config");
+        return Ok(OptionMap::default());
+    };
+
+    let out = Command::new(executable)
+        .args(&cmd[1..])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| {
+            Error::String(format!(
+                "Failed to run configured host options command {executable:?}: {err}"
+            ))
+        })?;
+    if !out.status.success() {
+        return Err(Error::String(format!(
+            "Configured host options command {executable:?} failed: {}",
+            out.status
+        )));
+    }
+    serde_yaml::from_slice(&out.stdout).map_err(|err| {
+        Error::String(format!(
+            "Invalid output from host options command {executable:?}, expected a mapping of options: {err}"
+        ))
+    })
+}
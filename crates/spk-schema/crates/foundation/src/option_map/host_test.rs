@@ -0,0 +1,92 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{add_distro_options, apply_host_options_config, detect_host_options};
+use crate::name::OptName;
+use crate::option_map::OptionMap;
+use crate::{opt_name, option_map};
+
+#[rstest]
+#[case::plain("rocky", Some("9.3"), &[], option_map!{"distro" => "rocky", "rocky" => "9.3"})]
+#[case::shortened("rocky", Some("9.3"), &[("rocky", 1)], option_map!{"distro" => "rocky", "rocky" => "9"})]
+#[case::longer_than_version("ubuntu", Some("22.04"), &[("ubuntu", 3)], option_map!{"distro" => "ubuntu", "ubuntu" => "22.04"})]
+#[case::normalized_id("opensuse.leap", Some("15.5"), &[], option_map!{"distro" => "opensuse-leap", "opensuse-leap" => "15.5"})]
+#[case::no_version("arch", None, &[], option_map!{"distro" => "arch"})]
+fn test_add_distro_options(
+    #[case] id: &str,
+    #[case] version_id: Option<&str>,
+    #[case] parts: &[(&str, usize)],
+    #[case] expected: OptionMap,
+) {
+    let config = spk_config::HostOptions {
+        distro_version_parts: parts.iter().map(|(d, p)| (d.to_string(), *p)).collect(),
+        ..Default::default()
+    };
+    let mut opts = OptionMap::default();
+    add_distro_options(&mut opts, id, version_id, &config);
+    assert_eq!(opts, expected);
+}
+
+#[rstest]
+fn test_apply_host_options_config_overrides() {
+    let config = spk_config::HostOptions {
+        options: [("site", "mtl"), ("gpu", "a6000")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        commands: vec![spk_config::HostOptionsCommand {
+            command: vec!["echo".into(), "{gpu: none, cores: 8}".into()],
+        }],
+        ..Default::default()
+    };
+    let mut opts = option_map! {"os" => "linux", "site" => "unknown"};
+    apply_host_options_config(&mut opts, &config).unwrap();
+    assert_eq!(
+        opts,
+        option_map! {"os" => "linux", "site" => "mtl", "gpu" => "a6000", "cores" => "8"},
+        "config options should replace both detected and command options"
+    );
+}
+
+#[rstest]
+fn test_apply_host_options_config_invalid_name() {
+    let config = spk_config::HostOptions {
+        options: [("Not Valid".to_string(), "value".to_string())]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+    let mut opts = OptionMap::default();
+    apply_host_options_config(&mut opts, &config)
+        .expect_err("invalid option names in the config should be reported");
+}
+
+#[rstest]
+fn test_detect_host_options_applies_config() {
+    let config = spk_config::HostOptions {
+        options: [("site", "mtl"), ("arch", "custom")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let opts = detect_host_options(&config).unwrap();
+    assert_eq!(
+        opts.get(OptName::os()).map(String::as_str),
+        Some(std::env::consts::OS),
+        "detected options should be kept"
+    );
+    assert_eq!(
+        opts.get(OptName::arch()).map(String::as_str),
+        Some("custom"),
+        "config options should replace detected ones"
+    );
+    assert_eq!(
+        opts.get(opt_name!("site")).map(String::as_str),
+        Some("mtl"),
+        "config options should be added"
+    );
+}
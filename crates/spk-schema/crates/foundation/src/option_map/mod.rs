@@ -3,7 +3,6 @@
 // https://github.com/spkenv/spk
 
 use std::collections::{BTreeMap, HashMap};
-use std::convert::TryFrom;
 use std::future::Future;
 use std::iter::FromIterator;
 use std::sync::Arc;
@@ -13,15 +12,19 @@ use itertools::Itertools;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-use crate::name::{OptName, OptNameBuf, PkgName};
+use crate::name::{OptNameBuf, PkgName};
 use crate::spec_ops::EnvName;
 
 mod error;
 mod filters;
 mod format;
+mod host;
+mod value;
 
 pub use error::{Error, Result};
 pub use filters::{get_host_options_filters, OptFilter};
+pub use host::{add_distro_options, apply_host_options_config, detect_host_options};
+pub use value::{OptionValue, OptionValueType};
 
 #[cfg(test)]
 #[path = "./option_map_test.rs"]
@@ -68,34 +71,12 @@ impl HostOptions {
         (**self.0.load()).clone()
     }
 
-    /// Detect and return the default options for the current host system.
+    /// Detect and return the default options for the current host system,
+    /// including any configured in the `host_options` section of the spk config.
     fn host_options() -> Result<OptionMap> {
-        let mut opts = OptionMap::default();
-        opts.insert(OptName::os().to_owned(), std::env::consts::OS.into());
-        opts.insert(OptName::arch().to_owned(), std::env::consts::ARCH.into());
-
-        let info = match sys_info::linux_os_release() {
-            Ok(i) => i,
-            Err(err) => {
-                return Err(Error::String(format!("Failed to get linux info: {err:?}")));
-            }
-        };
-
-        if let Some(id) = info.id {
-            opts.insert(OptName::distro().to_owned(), id.clone());
-            match OptNameBuf::try_from(id) {
-                Ok(id) => {
-                    if let Some(version_id) = info.version_id {
-                        opts.insert(id, version_id);
-                    }
-                }
-                Err(err) => {
-                    tracing::warn!("Reported distro id is not a valid option name: {err}");
-                }
-            }
-        }
-
-        Ok(opts)
+        let config = spk_config::get_config()
+            .map_err(|err| Error::String(format!("Failed to load spk config: {err}")))?;
+        detect_host_options(&config.host_options)
     }
 
     /// Change [`HOST_OPTIONS`] to return the provided substitute options for
@@ -0,0 +1,143 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Result};
+use crate::version::{parse_version, Version};
+
+#[cfg(test)]
+#[path = "./value_test.rs"]
+mod value_test;
+
+/// The words that are accepted for a true boolean option value
+const TRUE_VALUES: &[&str] = &["true", "on", "yes", "1"];
+/// The words that are accepted for a false boolean option value
+const FALSE_VALUES: &[&str] = &["false", "off", "no", "0"];
+
+/// The kind of value that an option is expected to hold.
+///
+/// Option values are always stored as strings in an [`super::OptionMap`],
+/// this type describes how those strings should be interpreted and
+/// compared when an option declares one.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionValueType {
+    /// Any string, compared exactly (the default)
+    #[default]
+    String,
+    /// A boolean, as one of true/false, on/off, yes/no or 1/0
+    Bool,
+    /// A signed integer
+    Int,
+    /// A package version number
+    Version,
+}
+
+impl OptionValueType {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Parse the given string as a value of this type.
+    pub fn parse<S: AsRef<str>>(&self, value: S) -> Result<OptionValue> {
+        let value = value.as_ref();
+        let invalid = |reason: String| Error::InvalidOptionValue {
+            value: value.to_string(),
+            value_type: *self,
+            reason,
+        };
+        match self {
+            Self::String => Ok(OptionValue::String(value.to_string())),
+            Self::Bool => {
+                let lower = value.trim().to_ascii_lowercase();
+                if TRUE_VALUES.contains(&lower.as_str()) {
+                    Ok(OptionValue::Bool(true))
+                } else if FALSE_VALUES.contains(&lower.as_str()) {
+                    Ok(OptionValue::Bool(false))
+                } else {
+                    Err(invalid(format!(
+                        "expected one of {}",
+                        TRUE_VALUES.iter().chain(FALSE_VALUES).join(", ")
+                    )))
+                }
+            }
+            Self::Int => value
+                .trim()
+                .parse()
+                .map(OptionValue::Int)
+                .map_err(|err| invalid(format!("expected a whole number ({err})"))),
+            Self::Version => parse_version(value.trim())
+                .map(OptionValue::Version)
+                .map_err(|err| invalid(err.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for OptionValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::String => f.write_str("string"),
+            Self::Bool => f.write_str("bool"),
+            Self::Int => f.write_str("int"),
+            Self::Version => f.write_str("version"),
+        }
+    }
+}
+
+impl std::str::FromStr for OptionValueType {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "string" | "str" => Ok(Self::String),
+            "bool" | "boolean" => Ok(Self::Bool),
+            "int" | "integer" => Ok(Self::Int),
+            "version" => Ok(Self::Version),
+            _ => Err(Error::String(format!(
+                "Invalid option type '{value}', must be one of string, bool, int or version"
+            ))),
+        }
+    }
+}
+
+/// An option value that has been parsed as a specific type.
+///
+/// Values can only be meaningfully compared with others of the
+/// same type, in which case bools, integers and versions are compared
+/// by what they represent rather than how they were written (eg: `on`
+/// is equal to `true` and `1.0` is equal to `1.0.0`).
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum OptionValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Version(Version),
+}
+
+impl OptionValue {
+    /// The type of this value.
+    pub fn value_type(&self) -> OptionValueType {
+        match self {
+            Self::String(_) => OptionValueType::String,
+            Self::Bool(_) => OptionValueType::Bool,
+            Self::Int(_) => OptionValueType::Int,
+            Self::Version(_) => OptionValueType::Version,
+        }
+    }
+}
+
+impl std::fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::String(v) => v.fmt(f),
+            Self::Bool(v) => v.fmt(f),
+            Self::Int(v) => v.fmt(f),
+            Self::Version(v) => v.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{OptionValue, OptionValueType};
+use crate::version::Version;
+
+#[rstest]
+#[case(OptionValueType::Bool, "true", OptionValue::Bool(true))]
+#[case(OptionValueType::Bool, "On", OptionValue::Bool(true))]
+#[case(OptionValueType::Bool, "no", OptionValue::Bool(false))]
+#[case(OptionValueType::Bool, "0", OptionValue::Bool(false))]
+#[case(OptionValueType::Int, "42", OptionValue::Int(42))]
+#[case(OptionValueType::Int, "-7", OptionValue::Int(-7))]
+#[case(
+    OptionValueType::Version,
+    "1.2",
+    OptionValue::Version(Version::new(1, 2, 0))
+)]
+#[case(OptionValueType::String, "on", OptionValue::String("on".into()))]
+fn test_parse_option_value(
+    #[case] value_type: OptionValueType,
+    #[case] value: &str,
+    #[case] expected: OptionValue,
+) {
+    let parsed = value_type.parse(value).expect("value should be valid");
+    assert_eq!(parsed, expected);
+    assert_eq!(parsed.value_type(), value_type);
+}
+
+#[rstest]
+#[case(OptionValueType::Bool, "maybe")]
+#[case(OptionValueType::Int, "4.2")]
+#[case(OptionValueType::Int, "")]
+#[case(OptionValueType::Version, "one.two")]
+fn test_parse_invalid_option_value(#[case] value_type: OptionValueType, #[case] value: &str) {
+    let err = value_type
+        .parse(value)
+        .expect_err("value should fail to parse");
+    let message = err.to_string();
+    assert!(
+        message.contains(&format!("Invalid {value_type} option value '{value}'")),
+        "error should describe the invalid value, got: {message}"
+    );
+}
+
+#[rstest]
+fn test_option_value_type_yaml() {
+    let parsed: OptionValueType = serde_yaml::from_str("bool").unwrap();
+    assert_eq!(parsed, OptionValueType::Bool);
+    assert_eq!(
+        serde_yaml::to_string(&OptionValueType::Int).unwrap().trim(),
+        "int"
+    );
+}
@@ -15,6 +15,6 @@ where
     T: Named,
 {
     fn env_name(&self) -> String {
-        self.name().replace('-', "_")
+        self.name().replace(['-', '/', '.'], "_")
     }
 }
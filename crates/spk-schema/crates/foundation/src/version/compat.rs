@@ -353,6 +353,15 @@ impl Compat {
     }
 
     fn check_compat(&self, base: &Version, other: &Version, required: CompatRule) -> Compatibility {
+        // Versions from different epochs use unrelated numbering
+        // schemes, so they can never be compatible with each other.
+        if base.epoch != other.epoch {
+            return Compatibility::incompatible(format!(
+                "Not compatible with {base} [{self}: has epoch {}, requires {}]",
+                other.epoch, base.epoch
+            ));
+        }
+
         // If `base` and `other` only differ by the pre or post parts, then
         // compatibility is determined by our pre/post rules.
         if base.parts == other.parts {
@@ -42,6 +42,17 @@ mod version_test;
 pub const VERSION_SEP: &str = ".";
 pub const TAG_SET_SEP: &str = ",";
 pub const TAG_SEP: &str = ".";
+/// Separates the epoch from the rest of a version, eg: `1!2.0.0`
+pub const EPOCH_SEP: &str = "!";
+/// Replaces the [`EPOCH_SEP`] in spfs tags, where it is not a valid character
+pub const EPOCH_TAG_SEP: &str = "_";
+/// Precedes the number of a development release, eg: `1.0.0.dev3`
+pub const DEV_RELEASE_SEP: &str = ".dev";
+/// Precedes the number of a post release, eg: `1.0.0.post1`, which
+/// is the same as the `post` post-release tag (`1.0.0+post.1`)
+pub const POST_RELEASE_SEP: &str = ".post";
+/// The name of the post-release tag that is created by [`POST_RELEASE_SEP`]
+pub const POST_RELEASE_TAG: &str = "post";
 
 // Labels for the names of the components, or positions, in a version
 // number.
@@ -328,9 +339,19 @@ impl std::hash::Hash for VersionParts {
 /// Version specifies a package version number.
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub struct Version {
+    /// Versions from a higher epoch are always greater than those
+    /// from a lower one, which allows a package to move to a new
+    /// versioning scheme (eg: when the upstream project resets its
+    /// version numbers). Most versions have an epoch of zero, which
+    /// is not shown.
+    pub epoch: u32,
     pub parts: VersionParts,
     pub pre: TagSet,
     pub post: TagSet,
+    /// A development release comes before the version that it is
+    /// attached to, including the pre- or post-release that it
+    /// extends (eg: `1.0.0.dev1 < 1.0.0-a.1.dev1 < 1.0.0-a.1`).
+    pub dev: Option<u32>,
 }
 
 impl<S> std::cmp::PartialEq<S> for Version
@@ -392,7 +413,45 @@ impl Version {
         self.parts.get(2).copied().unwrap_or_default()
     }
 
-    /// Format just the pre- and post- release tags (if any).
+    /// Set the epoch of this version.
+    pub fn with_epoch(mut self, epoch: u32) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    /// Format just the epoch and its separator (if any).
+    pub fn format_epoch(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.epoch != 0 {
+            f.write_str(&self.epoch.to_string())?;
+            f.write_str(EPOCH_SEP)?;
+        }
+        Ok(())
+    }
+
+    /// Set the development release number of this version.
+    pub fn with_dev(mut self, dev: Option<u32>) -> Self {
+        self.dev = dev;
+        self
+    }
+
+    /// True if this is a pre-release or development release, which
+    /// are not considered by default when resolving packages.
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty() || self.dev.is_some()
+    }
+
+    fn pre_release_rank(&self) -> PreReleaseRank {
+        if !self.pre.is_empty() {
+            PreReleaseRank::Pre
+        } else if self.dev.is_some() && self.post.is_empty() {
+            PreReleaseRank::Dev
+        } else {
+            PreReleaseRank::Release
+        }
+    }
+
+    /// Format just the pre- and post- release tags and
+    /// development release number (if any).
     pub fn format_tags(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         if !self.pre.tags.is_empty() {
             f.write_char('-')?;
@@ -402,6 +461,10 @@ impl Version {
             f.write_char('+')?;
             f.write_str(&self.post.to_string())?;
         }
+        if let Some(dev) = self.dev {
+            f.write_str(DEV_RELEASE_SEP)?;
+            f.write_str(&dev.to_string())?;
+        }
         Ok(())
     }
 
@@ -454,7 +517,7 @@ impl Version {
 
     /// Reports if this version is exactly 0.0.0... etc.
     pub fn is_zero(&self) -> bool {
-        if !self.pre.is_empty() || !self.post.is_empty() {
+        if self.epoch != 0 || self.is_prerelease() || !self.post.is_empty() {
             return false;
         }
         !self.parts.iter().any(|x| x > &0)
@@ -464,7 +527,14 @@ impl Version {
     /// spfs tag path.
     pub fn to_storage_string(&self) -> String {
         format!(
-            "{}{}{}{}{}",
+            "{}{}{}{}{}{}{}",
+            {
+                if self.epoch == 0 {
+                    String::new()
+                } else {
+                    format!("{}{EPOCH_SEP}", self.epoch)
+                }
+            },
             self.parts
                 .iter_for_storage()
                 .map(|p| p.to_string())
@@ -485,6 +555,10 @@ impl Version {
                 }
             },
             self.post,
+            match self.dev {
+                Some(dev) => format!("{DEV_RELEASE_SEP}{dev}"),
+                None => String::new(),
+            },
         )
     }
 }
@@ -498,7 +572,15 @@ impl MetadataPath for Version {
 impl TagPath for Version {
     fn tag_path<S: TagPathStrategy>(&self) -> RelativePathBuf {
         RelativePathBuf::from(format!(
-            "{base}{pre_sep}{pre}{post_sep}{post}",
+            "{epoch}{base}{pre_sep}{pre}{post_sep}{post}{dev}",
+            // the "!" character is not a valid spfs tag character either,
+            // so the epoch is separated by an underscore instead, which is
+            // not valid anywhere else in a version
+            epoch = if self.epoch == 0 {
+                String::new()
+            } else {
+                format!("{}{EPOCH_TAG_SEP}", self.epoch)
+            },
             base = if S::strategy_type().is_normalized() {
                 self.parts
                     .iter_for_storage()
@@ -517,6 +599,10 @@ impl TagPath for Version {
             // for spk package names
             post_sep = if self.post.is_empty() { "" } else { ".." },
             post = self.post,
+            dev = match self.dev {
+                Some(dev) => format!("{DEV_RELEASE_SEP}{dev}"),
+                None => String::new(),
+            },
         ))
     }
 }
@@ -552,6 +638,7 @@ impl FromStr for Version {
 /// otherwise it will be normalized to at least three parts.
 impl std::fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.format_epoch(f)?;
         if f.alternate() {
             f.write_str(&self.base_verbatim())?;
         } else {
@@ -569,6 +656,11 @@ impl PartialOrd for Version {
 
 impl Ord for Version {
     fn cmp(&self, other: &Self) -> Ordering {
+        match self.epoch.cmp(&other.epoch) {
+            Ordering::Equal => (),
+            cmp => return cmp,
+        }
+
         let self_parts = self.parts.iter();
         let mut other_parts = other.parts.iter();
 
@@ -601,11 +693,21 @@ impl Ord for Version {
             }
         }
 
-        match (self.pre.is_empty(), other.pre.is_empty()) {
-            (true, true) => (),
-            (true, false) => return Ordering::Greater,
-            (false, true) => return Ordering::Less,
-            (false, false) => match self.pre.cmp(&other.pre) {
+        // A development release of the version itself comes before
+        // any of its pre-releases, which come before the release:
+        //
+        //     1.0.0.dev1 < 1.0.0-a.1 < 1.0.0
+        //
+        match (self.pre_release_rank(), other.pre_release_rank()) {
+            (PreReleaseRank::Dev, PreReleaseRank::Dev) => match self.dev.cmp(&other.dev) {
+                Ordering::Equal => (),
+                cmp => return cmp,
+            },
+            (PreReleaseRank::Pre, PreReleaseRank::Pre) => match self.pre.cmp(&other.pre) {
+                Ordering::Equal => (),
+                cmp => return cmp,
+            },
+            (a, b) => match a.cmp(&b) {
                 Ordering::Equal => (),
                 cmp => return cmp,
             },
@@ -621,7 +723,43 @@ impl Ord for Version {
             _ => (),
         }
 
-        self.post.cmp(&other.post)
+        match self.post.cmp(&other.post) {
+            Ordering::Equal => (),
+            cmp => return cmp,
+        }
+
+        // Otherwise, a development release comes just before
+        // the pre- or post-release that it extends:
+        //
+        //     1.0.0-a.1.dev1 < 1.0.0-a.1 < 1.0.0+r.1.dev1 < 1.0.0+r.1
+        //
+        match (self.dev, other.dev) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+/// How a version is sorted against others with the same base numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PreReleaseRank {
+    /// A development release without any pre- or post-release tags
+    Dev,
+    /// A version with pre-release tags
+    Pre,
+    /// A release or post-release
+    Release,
+}
+
+/// Parse a version from the name of an spfs tag, as written by
+/// [`TagPath::tag_path`].
+pub fn parse_tag_version<S: AsRef<str>>(tag: S) -> Result<Version> {
+    let tag = tag.as_ref().replace("..", "+");
+    match tag.split_once(EPOCH_TAG_SEP) {
+        Some((epoch, version)) => parse_version(format!("{epoch}{EPOCH_SEP}{version}")),
+        None => parse_version(tag),
     }
 }
 
@@ -632,8 +770,41 @@ pub fn parse_version<S: AsRef<str>>(version: S) -> Result<Version> {
         return Ok(Version::default());
     }
 
+    let (epoch, version) = match version.split_once(EPOCH_SEP) {
+        Some((epoch, version)) => match epoch.parse() {
+            Ok(epoch) => (epoch, version),
+            Err(_) => {
+                return Err(InvalidVersionError::new_error(format!(
+                    "Version epoch must be an integer, got '{epoch}' [{version}]"
+                )))
+            }
+        },
+        None => (0, version),
+    };
+    let (version, dev) = match version.rsplit_once(DEV_RELEASE_SEP) {
+        Some((version, dev)) => match dev.parse() {
+            Ok(num) if dev.bytes().all(|c| c.is_ascii_digit()) => (version, Some(num)),
+            _ => {
+                return Err(InvalidVersionError::new_error(format!(
+                    "Development release must be an integer, got '{dev}' [{version}]"
+                )))
+            }
+        },
+        None => (version, None),
+    };
     let (version, post) = break_string(version, "+");
     let (version, pre) = break_string(version, "-");
+    let (version, post_release) = match version.split_once(POST_RELEASE_SEP) {
+        Some((version, post_release)) => match post_release.parse::<u32>() {
+            Ok(post_release) => (version, Some(post_release)),
+            Err(_) => {
+                return Err(InvalidVersionError::new_error(format!(
+                    "Post release must be an integer, got '{post_release}' [{version}]"
+                )))
+            }
+        },
+        None => (version, None),
+    };
 
     let str_parts = version.split(VERSION_SEP);
     let mut parts = Vec::new();
@@ -648,9 +819,19 @@ pub fn parse_version<S: AsRef<str>>(version: S) -> Result<Version> {
         }
     }
 
-    let mut v = Version::from_parts(parts);
+    let mut v = Version::from_parts(parts).with_epoch(epoch).with_dev(dev);
     v.pre = parse_tag_set(pre)?;
     v.post = parse_tag_set(post)?;
+    if let Some(post_release) = post_release {
+        if v.post.tags.contains_key(POST_RELEASE_TAG) {
+            return Err(InvalidVersionError::new_error(format!(
+                "duplicate tag: {POST_RELEASE_TAG}"
+            )));
+        }
+        v.post
+            .tags
+            .insert(POST_RELEASE_TAG.to_string(), post_release);
+    }
     Ok(v)
 }
 
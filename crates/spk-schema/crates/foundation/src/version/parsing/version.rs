@@ -6,17 +6,24 @@ use std::collections::{BTreeMap, HashSet};
 
 use nom::branch::alt;
 use nom::character::complete::{char, digit1};
-use nom::combinator::{eof, map, map_res, opt, peek, recognize};
+use nom::combinator::{eof, map_res, not, opt, peek, recognize};
 use nom::error::{ContextError, FromExternalError, ParseError};
 use nom::multi::separated_list1;
-use nom::sequence::{pair, preceded, separated_pair, terminated};
+use nom::sequence::{pair, preceded, separated_pair, terminated, tuple};
 use nom::IResult;
 use nom_supreme::tag::complete::tag;
 use nom_supreme::tag::TagError;
 
 use super::separated_list1_with_cut;
 use crate::name::parsing::tag_name;
-use crate::version::{InvalidVersionError, TagSet, Version};
+use crate::version::{
+    InvalidVersionError,
+    TagSet,
+    Version,
+    DEV_RELEASE_SEP,
+    POST_RELEASE_SEP,
+    POST_RELEASE_TAG,
+};
 
 /// Parse a valid version pre- or post-tag.
 ///
@@ -69,7 +76,14 @@ where
             // `!=0+a.0,0.0.*` as a ptag.
             terminated(
                 ptag,
-                peek(alt((tag(","), tag("/"), tag("+"), tag("]"), eof))),
+                peek(alt((
+                    tag(","),
+                    tag("/"),
+                    tag("+"),
+                    tag("]"),
+                    tag(DEV_RELEASE_SEP),
+                    eof,
+                ))),
             ),
         ),
         |vec| {
@@ -108,7 +122,14 @@ where
             // `!=0+a.0,0.0.*` as a ptag.
             terminated(
                 ptag_str,
-                peek(alt((tag(","), tag("/"), tag("+"), tag("]"), eof))),
+                peek(alt((
+                    tag(","),
+                    tag("/"),
+                    tag("+"),
+                    tag("]"),
+                    tag(DEV_RELEASE_SEP),
+                    eof,
+                ))),
             ),
         ),
         |tags| {
@@ -125,6 +146,50 @@ where
     )(input)
 }
 
+/// Parse the epoch of a version, including its separator.
+///
+/// Example: `"1!"`
+pub(crate) fn epoch<'a, E>(input: &'a str) -> IResult<&'a str, u32, E>
+where
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, std::num::ParseIntError>
+        + TagError<&'a str, &'static str>,
+{
+    terminated(
+        map_res(digit1, |n: &str| n.parse::<u32>()),
+        tag(crate::version::EPOCH_SEP),
+    )(input)
+}
+
+/// Parse the separator between the numbers of a version, which
+/// must not be the start of a post or development release.
+fn release_sep<'a, E>(input: &'a str) -> IResult<&'a str, char, E>
+where
+    E: ParseError<&'a str> + TagError<&'a str, &'static str>,
+{
+    terminated(
+        char('.'),
+        not(alt((
+            tag(&POST_RELEASE_SEP[1..]),
+            tag(&DEV_RELEASE_SEP[1..]),
+        ))),
+    )(input)
+}
+
+/// Parse the number of a post or development release,
+/// including the given separator.
+///
+/// Examples: `".post1"`, `".dev3"`
+fn release_number<'a, E>(sep: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, u32, E>
+where
+    E: ParseError<&'a str>
+        + FromExternalError<&'a str, std::num::ParseIntError>
+        + TagError<&'a str, &'static str>,
+{
+    preceded(tag(sep), map_res(digit1, |n: &str| n.parse::<u32>()))
+}
+
 /// Parse a version string into a [`Version`].
 ///
 /// See [version_str] for examples of valid version strings.
@@ -136,29 +201,53 @@ where
         + FromExternalError<&'a str, std::num::ParseIntError>
         + TagError<&'a str, &'static str>,
 {
-    map(
-        pair(
-            separated_list1_with_cut(char('.'), map_res(digit1, |n: &str| n.parse::<u32>())),
+    map_res(
+        tuple((
+            opt(epoch),
+            separated_list1_with_cut(release_sep, map_res(digit1, |n: &str| n.parse::<u32>())),
+            opt(release_number(POST_RELEASE_SEP)),
             pair(
                 opt(preceded(char('-'), ptagset)),
                 opt(preceded(char('+'), ptagset)),
             ),
-        ),
-        |(parts, (pre, post))| Version {
-            parts: parts.into(),
-            pre: pre.unwrap_or_default(),
-            post: post.unwrap_or_default(),
+            opt(release_number(DEV_RELEASE_SEP)),
+        )),
+        |(epoch, parts, post_release, (pre, post), dev)| {
+            let mut post: TagSet = post.unwrap_or_default();
+            if let Some(post_release) = post_release {
+                if post
+                    .tags
+                    .insert(POST_RELEASE_TAG.to_string(), post_release)
+                    .is_some()
+                {
+                    return Err(InvalidVersionError::new_error(format!(
+                        "duplicate tag: {POST_RELEASE_TAG}"
+                    )));
+                }
+            }
+            Ok(Version {
+                epoch: epoch.unwrap_or_default(),
+                parts: parts.into(),
+                pre: pre.unwrap_or_default(),
+                post,
+                dev,
+            })
         },
     )(input)
 }
 
 /// Parse a version.
 ///
-/// A version is a version number followed by optional
-/// pre-release tags and optional post-release tags.
+/// A version is an optional epoch, then a version number followed
+/// by an optional post release number, optional pre-release tags,
+/// optional post-release tags and an optional development release.
 ///
 /// Examples:
 /// - `"1.0"`
+/// - `"1!1.0"`
+/// - `"1.0.post1"`
+/// - `"1.0.dev3"`
+/// - `"1.0-a.0.dev3"`
 /// - `"1.0-a.0"`
 /// - `"1.0-a.0,b.1"`
 /// - `"1.0+c.0"`
@@ -173,11 +262,14 @@ where
         + FromExternalError<&'a str, crate::version::error::Error>
         + TagError<&'a str, &'static str>,
 {
-    recognize(pair(
-        separated_list1_with_cut(char('.'), digit1),
+    recognize(tuple((
+        opt(terminated(digit1, tag(crate::version::EPOCH_SEP))),
+        separated_list1_with_cut(release_sep, digit1),
+        opt(preceded(tag(POST_RELEASE_SEP), digit1)),
         pair(
             opt(preceded(char('-'), recognize(ptagset_str))),
             opt(preceded(char('+'), recognize(ptagset_str))),
         ),
-    ))(input)
+        opt(preceded(tag(DEV_RELEASE_SEP), digit1)),
+    )))(input)
 }
@@ -6,7 +6,8 @@ use std::cmp::{Ord, Ordering};
 
 use rstest::rstest;
 
-use super::{parse_version, TagSet, Version};
+use super::{parse_tag_version, parse_version, TagSet, Version};
+use crate::ident_ops::{NormalizedTagStrategy, TagPath};
 
 #[rstest]
 fn test_version_nonzero() {
@@ -26,6 +27,19 @@ fn test_version_nonzero() {
 #[case("6.3", "6.3-pre.0", true)]
 #[case("6.3-pre.1", "6.3-pre.0", true)]
 #[case("6.3+r.1", "6.3+other.1,r.1", true)]
+#[case("1!1.0.0", "9.9.9", true)]
+#[case("9.9.9", "1!1.0.0", false)]
+#[case("2!1.0.0", "1!5.0.0", true)]
+#[case("1.0.0", "1.0.0.dev1", true)]
+#[case("1.0.0-a.1", "1.0.0.dev1", true)]
+#[case("1.0.0.dev2", "1.0.0.dev1", true)]
+#[case("1.0.0-a.1", "1.0.0-a.1.dev1", true)]
+#[case("1.0.0-a.1.dev1", "1.0.0-a.0", true)]
+#[case("1.0.0+post.1.dev1", "1.0.0", true)]
+#[case("1.0.0+post.1", "1.0.0+post.1.dev1", true)]
+#[case("1.0.0.post1", "1.0.0.post1.dev1", true)]
+#[case("1.0.1.dev1", "1.0.0.post1", true)]
+#[case("1!1.0.0", "1!1.0.0", false)]
 fn test_is_gt(#[case] base: &str, #[case] test: &str, #[case] expected: bool) {
     let a = parse_version(base).unwrap();
     let b = parse_version(test).unwrap();
@@ -51,8 +65,21 @@ fn test_is_gt(#[case] base: &str, #[case] test: &str, #[case] expected: bool) {
      Version{
          parts: vec![1, 2, 5, 7].into(),
          pre:TagSet::single("alpha", 4), post:TagSet::single("rev", 6),
+         ..Default::default()
     },
 )]
+#[case("2!1.0", Version::from_parts([1, 0]).with_epoch(2))]
+#[case("1.0.dev3", Version::from_parts([1, 0]).with_dev(Some(3)))]
+#[case("1.0.post1", Version{
+    parts: vec![1, 0].into(), post: TagSet::single("post", 1), ..Default::default()
+})]
+#[case("1.0-rc.1+r.2.dev3", Version{
+    parts: vec![1, 0].into(),
+    pre: TagSet::single("rc", 1), post: TagSet::single("r", 2),
+    dev: Some(3),
+    ..Default::default()
+})]
+#[case("0!1.0", Version::from_parts([1, 0]))]
 fn test_parse_version(#[case] string: &str, #[case] expected: Version) {
     let actual = parse_version(string).unwrap();
     assert_eq!(actual, expected)
@@ -63,6 +90,11 @@ fn test_parse_version(#[case] string: &str, #[case] expected: Version) {
 #[case("my-version")]
 #[case("1.0+post.1-pre.2")]
 #[case("1.2.5-alpha.a")]
+#[case("a!1.0.0")]
+#[case("!1.0.0")]
+#[case("1.0.dev")]
+#[case("1.0.deva")]
+#[case("1.0.post1+post.2")]
 fn test_parse_version_invalid(#[case] string: &str) {
     let result = parse_version(string);
     if let Err(super::Error::InvalidVersionError(_)) = result {
@@ -105,3 +137,38 @@ fn test_parse_version_clone(#[case] string: &str) {
 fn test_tag_set_order(#[case] a: TagSet, #[case] b: TagSet, #[case] expected: Ordering) {
     assert_eq!(a.cmp(&b), expected);
 }
+
+#[rstest]
+#[case("1.2.3", "1.2.3")]
+#[case("1!1.2.3", "1!1.2.3")]
+#[case("0!1.2", "1.2.0")]
+#[case("3!1.0-pre.1+r.2", "3!1.0.0-pre.1+r.2")]
+fn test_version_epoch_display(#[case] string: &str, #[case] expected: &str) {
+    let version = parse_version(string).unwrap();
+    assert_eq!(version.to_string(), expected);
+    assert_eq!(parse_version(version.to_string()).unwrap(), version);
+}
+
+#[rstest]
+#[case("1.2.3+r.1", "1.2.3..r.1")]
+#[case("2!1.2.3+r.1", "2_1.2.3..r.1")]
+fn test_version_epoch_tag_path(#[case] string: &str, #[case] expected: &str) {
+    let version = parse_version(string).unwrap();
+    let tag = version.tag_path::<NormalizedTagStrategy>();
+    assert_eq!(tag.as_str(), expected);
+    assert_eq!(parse_tag_version(tag.as_str()).unwrap(), version);
+}
+
+#[rstest]
+#[case("1.0.dev3", "1.0.0.dev3")]
+#[case("1.0.post1", "1.0.0+post.1")]
+#[case("1.0.post1.dev2", "1.0.0+post.1.dev2")]
+#[case("1.0-a.1.dev2", "1.0.0-a.1.dev2")]
+fn test_version_dev_and_post_release_display(#[case] string: &str, #[case] expected: &str) {
+    let version = parse_version(string).unwrap();
+    assert_eq!(version.to_string(), expected);
+    assert_eq!(parse_version(version.to_string()).unwrap(), version);
+    let parsed = crate::version::parsing::version::<nom::error::VerboseError<&str>>(string)
+        .expect("nom parser should also accept the version");
+    assert_eq!(parsed, ("", version));
+}
@@ -359,13 +359,13 @@ impl Ranged for SemverRange {
                 continue;
             }
             parts[i] = p + 1;
-            return Some(Version::from_parts(parts.drain(..i + 1)));
+            return Some(Version::from_parts(parts.drain(..i + 1)).with_epoch(self.minimum.epoch));
         }
 
         if let Some(last) = parts.last_mut() {
             *last += 1;
         }
-        Some(Version::from(parts))
+        Some(Version::from(parts).with_epoch(self.minimum.epoch))
     }
 }
 
@@ -486,6 +486,14 @@ impl Ranged for WildcardRange {
     }
 
     fn is_applicable(&self, version: &Version) -> Compatibility {
+        // wildcards cannot specify an epoch, and so only
+        // match versions that do not have one
+        if version.epoch != 0 {
+            return Compatibility::incompatible(format!(
+                "Out of range: {self} [has epoch {}, requires none]",
+                version.epoch
+            ));
+        }
         for (i, (a, b)) in self.parts.iter().zip(&*version.parts).enumerate() {
             if let Some(a) = a {
                 if a != b {
@@ -557,7 +565,7 @@ impl Ranged for LowestSpecifiedRange {
         if let Some(last) = parts.last_mut() {
             *last += 1;
         }
-        Some(Version::from_parts(parts.clone()))
+        Some(Version::from_parts(parts.clone()).with_epoch(self.base.epoch))
     }
 }
 
@@ -569,6 +577,7 @@ impl Display for LowestSpecifiedRange {
             .collect_vec()
             .join(VERSION_SEP);
         f.write_char('~')?;
+        self.base.format_epoch(f)?;
         f.write_str(&base_str)?;
         self.base.format_tags(f)
     }
@@ -777,11 +786,11 @@ impl Ranged for EqualsVersion {
     }
 
     fn is_applicable(&self, other: &Version) -> Compatibility {
-        if self.version.parts != other.parts {
+        if self.version.epoch != other.epoch || self.version.parts != other.parts {
             return Compatibility::incompatible(format!("{} !! {} [not equal]", &other, self));
         }
 
-        if self.version.pre != other.pre {
+        if self.version.pre != other.pre || self.version.dev != other.dev {
             return Compatibility::incompatible(format!(
                 "{other} !! {self} [not equal @ prerelease]",
             ));
@@ -838,16 +847,22 @@ impl Ranged for NotEqualsVersion {
 
     fn is_applicable(&self, version: &Version) -> Compatibility {
         // Is some part of the specified version different?
-        if version
-            .parts
-            .iter()
-            .zip(self.base.parts.iter())
-            .take(self.specified)
-            .any(|(l, r)| l != r)
+        if version.epoch != self.base.epoch
+            || version
+                .parts
+                .iter()
+                .zip(self.base.parts.iter())
+                .take(self.specified)
+                .any(|(l, r)| l != r)
         {
             return Compatibility::Compatible;
         }
 
+        // Development releases are never equal to their release
+        if self.base.dev != version.dev {
+            return Compatibility::Compatible;
+        }
+
         // To mirror `ExactVersion`, different post releases are unequal,
         // but unspecified post release is considered equal.
         if !self.base.post.is_empty() && self.base.post != version.post {
@@ -869,6 +884,7 @@ impl Display for NotEqualsVersion {
             .collect_vec()
             .join(VERSION_SEP);
         f.write_str("!=")?;
+        self.base.format_epoch(f)?;
         f.write_str(&base_str)?;
         self.base.format_tags(f)
     }
@@ -905,13 +921,13 @@ impl Ranged for DoubleEqualsVersion {
     }
 
     fn is_applicable(&self, other: &Version) -> Compatibility {
-        if self.version.parts != other.parts {
+        if self.version.epoch != other.epoch || self.version.parts != other.parts {
             return Compatibility::incompatible(
                 format!("{other} !! {self} [not equal precisely]",),
             );
         }
 
-        if self.version.pre != other.pre {
+        if self.version.pre != other.pre || self.version.dev != other.dev {
             return Compatibility::incompatible(format!(
                 "{other} !! {self} [not equal precisely @ prerelease]",
             ));
@@ -963,16 +979,22 @@ impl Ranged for DoubleNotEqualsVersion {
 
     fn is_applicable(&self, version: &Version) -> Compatibility {
         // Is some part of the specified version different?
-        if version
-            .parts
-            .iter()
-            .zip(self.base.parts.iter())
-            .take(self.specified)
-            .any(|(l, r)| l != r)
+        if version.epoch != self.base.epoch
+            || version
+                .parts
+                .iter()
+                .zip(self.base.parts.iter())
+                .take(self.specified)
+                .any(|(l, r)| l != r)
         {
             return Compatibility::Compatible;
         }
 
+        // Development releases are never equal to their release
+        if self.base.dev != version.dev {
+            return Compatibility::Compatible;
+        }
+
         // To mirror `PreciseExactVersion`, any differences in post
         // releases makes these unequal.
         if self.base.post != version.post {
@@ -994,6 +1016,7 @@ impl Display for DoubleNotEqualsVersion {
             .collect_vec()
             .join(VERSION_SEP);
         f.write_str("!==")?;
+        self.base.format_epoch(f)?;
         f.write_str(&base_str)?;
         self.base.format_tags(f)
     }
@@ -102,6 +102,15 @@ impl IntoCompatRange for Version {
     "localx/333",
     RangeIdent{repository_name: None, name: "localx".parse().unwrap(), version: parse_version("333").unwrap().into_compat_range(), components: BTreeSet::default(), build: None}
 )]
+// namespaced package names
+#[case(
+    "studio.animtools/hello/1.0.0",
+    RangeIdent{repository_name: None, name: "studio.animtools/hello".parse().unwrap(), version: parse_version("1.0.0").unwrap().into_compat_range(), components: BTreeSet::default(), build: None}
+)]
+#[case(
+    "local/studio.animtools/hello/1.0.0/src",
+    RangeIdent{repository_name: Some("local".try_into().unwrap()), name: "studio.animtools/hello".parse().unwrap(), version: parse_version("1.0.0").unwrap().into_compat_range(), components: BTreeSet::default(), build: Some(Build::Source)}
+)]
 fn test_parse_range_ident(#[case] input: &str, #[case] expected: RangeIdent) {
     let actual = RangeIdent::from_str(input).unwrap();
     assert_eq!(actual, expected);
@@ -23,6 +23,8 @@ pub use ident_version::{parse_version_ident, VersionIdent};
 pub use range_ident::{parse_ident_range, RangeIdent};
 pub use request::{
     is_false,
+    AnyOfRequest,
+    ConflictRequest,
     InclusionPolicy,
     NameAndValue,
     PinPolicy,
@@ -229,11 +229,13 @@ fn arb_legal_version_min_len(min_len: usize) -> impl Strategy<Value = Version> {
         arb_legal_tagset(),
     )
         .prop_map(|(parts, pre, post)| Version {
+            epoch: 0,
             // We don't expect to generate any values that have `plus_epsilon`
             // enabled.
             parts: parts.into(),
             pre,
             post,
+            dev: None,
         })
 }
 
@@ -247,19 +249,23 @@ fn arb_illegal_version_min_len(min_len: usize) -> impl Strategy<Value = Version>
         .prop_map(|(parts, use_illegal_for_pre, legal, illegal)| {
             if use_illegal_for_pre {
                 Version {
+                    epoch: 0,
                     // We don't expect to generate any values that have `plus_epsilon`
                     // enabled.
                     parts: parts.into(),
                     pre: illegal,
                     post: legal,
+                    dev: None,
                 }
             } else {
                 Version {
+                    epoch: 0,
                     // We don't expect to generate any values that have `plus_epsilon`
                     // enabled.
                     parts: parts.into(),
                     pre: legal,
                     post: illegal,
+                    dev: None,
                 }
             }
         })
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::collections::BTreeMap;
 use std::fmt::Write;
 use std::marker::PhantomData;
@@ -10,6 +10,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use colored::Colorize;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use spk_schema_foundation::format::{
     FormatBuild,
@@ -20,6 +21,7 @@ use spk_schema_foundation::format::{
 use spk_schema_foundation::ident_component::ComponentSet;
 use spk_schema_foundation::name::{OptName, OptNameBuf, PkgName};
 use spk_schema_foundation::option_map::Stringified;
+use spk_schema_foundation::spec_ops::Named;
 use spk_schema_foundation::version::{CompatRule, Compatibility, Version, API_STR, BINARY_STR};
 use spk_schema_foundation::version_range::{
     DoubleEqualsVersion,
@@ -170,14 +172,19 @@ impl<'de> Deserialize<'de> for PinPolicy {
 pub enum Request {
     Pkg(PkgRequest),
     Var(VarRequest<PinnableValue>),
+    AnyOf(AnyOfRequest),
 }
 
 impl Request {
     /// Return the canonical name of this request."""
+    ///
+    /// Requests for any one of a set of alternatives are named
+    /// after their first, preferred, alternative.
     pub fn name(&self) -> &OptName {
         match self {
             Request::Var(r) => &r.var,
             Request::Pkg(r) => r.pkg.name.as_opt_name(),
+            Request::AnyOf(r) => r.preferred().pkg.name.as_opt_name(),
         }
     }
 
@@ -202,6 +209,17 @@ impl Request {
             _ => None,
         }
     }
+
+    pub fn is_any_of(&self) -> bool {
+        matches!(self, Self::AnyOf(_))
+    }
+
+    pub fn into_any_of(self) -> Option<AnyOfRequest> {
+        match self {
+            Self::AnyOf(a) => Some(a),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Request {
@@ -209,6 +227,7 @@ impl std::fmt::Display for Request {
         match self {
             Self::Pkg(p) => p.fmt(f),
             Self::Var(v) => v.fmt(f),
+            Self::AnyOf(a) => a.fmt(f),
         }
     }
 }
@@ -225,6 +244,12 @@ impl From<PkgRequest> for Request {
     }
 }
 
+impl From<AnyOfRequest> for Request {
+    fn from(req: AnyOfRequest) -> Self {
+        Self::AnyOf(req)
+    }
+}
+
 impl<'de> Deserialize<'de> for Request {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -245,9 +270,11 @@ impl<'de> Deserialize<'de> for Request {
         #[derive(Default)]
         struct RequestVisitor {
             // PkgRequest
-            pkg: Option<RangeIdent>,
+            pkg: Option<PkgField>,
             prerelease_policy: Option<PreReleasePolicy>,
             inclusion_policy: Option<InclusionPolicy>,
+            if_present_in_env: Option<bool>,
+            priority: Option<i64>,
 
             // VarRequest
             var: Option<OptNameBuf>,
@@ -273,7 +300,7 @@ impl<'de> Deserialize<'de> for Request {
                 while let Some(mut key) = map.next_key::<Stringified>()? {
                     key.make_ascii_lowercase();
                     match key.as_str() {
-                        "pkg" => self.pkg = Some(map.next_value::<RangeIdent>()?),
+                        "pkg" => self.pkg = Some(map.next_value::<PkgField>()?),
                         "prereleasepolicy" => {
                             self.prerelease_policy = Some(map.next_value::<PreReleasePolicy>()?)
                         }
@@ -283,6 +310,10 @@ impl<'de> Deserialize<'de> for Request {
                         "include" => {
                             self.inclusion_policy = Some(map.next_value::<InclusionPolicy>()?)
                         }
+                        "ifpresentinenv" => {
+                            self.if_present_in_env = Some(map.next_value::<bool>()?)
+                        }
+                        "priority" => self.priority = Some(map.next_value::<i64>()?),
                         "frombuildenv" => self.pin = Some(map.next_value::<PinValue>()?),
                         "var" => {
                             let NameAndValue(name, value) = map.next_value()?;
@@ -301,16 +332,51 @@ impl<'de> Deserialize<'de> for Request {
                     }
                 }
 
+                // `ifPresentInEnv` is shorthand for the `IfAlreadyPresent` inclusion policy
+                match (self.if_present_in_env, self.inclusion_policy) {
+                    (Some(true), Some(InclusionPolicy::Always))
+                    | (Some(false), Some(InclusionPolicy::IfAlreadyPresent)) => {
+                        return Err(serde::de::Error::custom(
+                            "`ifPresentInEnv` contradicts the given `include` policy",
+                        ));
+                    }
+                    (Some(true), _) => {
+                        self.inclusion_policy = Some(InclusionPolicy::IfAlreadyPresent)
+                    }
+                    _ => {}
+                }
+
                 match (self.pkg, self.var) {
-                    (Some(pkg), None) if self.pin.as_ref().map(PinValue::is_some).unwrap_or_default() && !pkg.version.is_empty() => {
+                    (Some(PkgField::AnyOf(_)), None) if self.pin.as_ref().map(PinValue::is_some).unwrap_or_default() => {
+                        Err(serde::de::Error::custom(
+                            "a request for any one of several packages cannot use `fromBuildEnv`"
+                        ))
+                    },
+                    (Some(PkgField::AnyOf(alternatives)), None) => Ok(Request::AnyOf(AnyOfRequest {
+                        alternatives: alternatives
+                            .into_iter()
+                            .map(|pkg| PkgRequest {
+                                pkg,
+                                prerelease_policy: self.prerelease_policy,
+                                inclusion_policy: self.inclusion_policy.unwrap_or_default(),
+                                priority: self.priority.unwrap_or_default(),
+                                pin_policy: self.pin_policy.unwrap_or_default(),
+                                pin: None,
+                                required_compat: None,
+                                requested_by: Default::default(),
+                            })
+                            .collect(),
+                    })),
+                    (Some(PkgField::Single(pkg)), None) if self.pin.as_ref().map(PinValue::is_some).unwrap_or_default() && !pkg.version.is_empty() => {
                         Err(serde::de::Error::custom(
                             format!("request for `{}` cannot specify a value `/{:#}` when `fromBuildEnv` is specified", pkg.name, pkg.version)
                         ))
                     },
-                    (Some(pkg), None) => Ok(Request::Pkg(PkgRequest {
+                    (Some(PkgField::Single(pkg)), None) => Ok(Request::Pkg(PkgRequest {
                         pkg,
                         prerelease_policy: self.prerelease_policy,
                         inclusion_policy: self.inclusion_policy.unwrap_or_default(),
+                        priority: self.priority.unwrap_or_default(),
                         pin_policy: self.pin_policy.unwrap_or_default(),
                         pin: self.pin.unwrap_or_default().into_pkg_pin(),
                         required_compat: None,
@@ -342,6 +408,235 @@ impl<'de> Deserialize<'de> for Request {
     }
 }
 
+/// The value of the `pkg` field in a request, which is usually a single
+/// package but may instead list alternatives under the `any` key.
+enum PkgField {
+    Single(RangeIdent),
+    AnyOf(Vec<RangeIdent>),
+}
+
+impl<'de> Deserialize<'de> for PkgField {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct PkgFieldVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PkgFieldVisitor {
+            type Value = PkgField;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a package identifier or a mapping with a list of `any` alternatives")
+            }
+
+            fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                RangeIdent::from_str(v)
+                    .map(PkgField::Single)
+                    .map_err(E::custom)
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut alternatives = None;
+                while let Some(key) = map.next_key::<Stringified>()? {
+                    match key.as_str() {
+                        "any" => alternatives = Some(map.next_value::<Vec<RangeIdent>>()?),
+                        other => {
+                            return Err(serde::de::Error::unknown_field(other, &["any"]));
+                        }
+                    }
+                }
+                match alternatives {
+                    Some(alternatives) if !alternatives.is_empty() => {
+                        Ok(PkgField::AnyOf(alternatives))
+                    }
+                    Some(_) => Err(serde::de::Error::custom(
+                        "`any` must list at least one package",
+                    )),
+                    None => Err(serde::de::Error::missing_field("any")),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(PkgFieldVisitor)
+    }
+}
+
+/// A request that can be satisfied by any one of several packages.
+///
+/// The alternatives are listed in order of preference, and the
+/// solver will only fall back to a later alternative when the
+/// earlier ones cannot be resolved.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct AnyOfRequest {
+    pub alternatives: Vec<PkgRequest>,
+}
+
+impl AnyOfRequest {
+    /// Create a request that can be satisfied by any of the given requests.
+    ///
+    /// # Panics
+    /// If no alternatives are given.
+    pub fn new(alternatives: Vec<PkgRequest>) -> Self {
+        assert!(
+            !alternatives.is_empty(),
+            "a request for any one of several packages needs at least one alternative"
+        );
+        Self { alternatives }
+    }
+
+    /// The first, most preferred alternative
+    pub fn preferred(&self) -> &PkgRequest {
+        &self.alternatives[0]
+    }
+
+    /// Add a requester to all of the alternatives in this request.
+    pub fn add_requester(&mut self, requester: RequestedBy) {
+        for alternative in self.alternatives.iter_mut() {
+            alternative.add_requester(requester.clone());
+        }
+    }
+
+    /// True if this request is at least as restrictive as the other.
+    ///
+    /// Every alternative in this request must be at least as
+    /// restrictive as one of the alternatives in the other.
+    pub fn contains(&self, other: &Self) -> Compatibility {
+        for ours in self.alternatives.iter() {
+            let contained = other
+                .alternatives
+                .iter()
+                .any(|theirs| ours.pkg.name == theirs.pkg.name && ours.contains(theirs).is_ok());
+            if !contained {
+                return Compatibility::incompatible(format!(
+                    "{} is not covered by any of [{other}]",
+                    ours.pkg
+                ));
+            }
+        }
+        Compatibility::Compatible
+    }
+
+    /// Return the alternative that is satisfied by the given
+    /// package, if any.
+    pub fn satisfied_alternative<T>(&self, satisfy: &T) -> Option<&PkgRequest>
+    where
+        T: Satisfy<PkgRequest> + Named,
+    {
+        self.alternatives.iter().find(|alternative| {
+            &*alternative.pkg.name == satisfy.name() && alternative.is_satisfied_by(satisfy).is_ok()
+        })
+    }
+}
+
+impl std::fmt::Display for AnyOfRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, alternative) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            alternative.pkg.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for AnyOfRequest {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        #[derive(Serialize)]
+        struct Alternatives<'a> {
+            any: Vec<&'a RangeIdent>,
+        }
+
+        let preferred = self.preferred();
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry(
+            "pkg",
+            &Alternatives {
+                any: self.alternatives.iter().map(|a| &a.pkg).collect(),
+            },
+        )?;
+        if let Some(policy) = &preferred.prerelease_policy {
+            map.serialize_entry("prereleasePolicy", policy)?;
+        }
+        if !preferred.inclusion_policy.is_default() {
+            map.serialize_entry("include", &preferred.inclusion_policy)?;
+        }
+        if !is_default_priority(&preferred.priority) {
+            map.serialize_entry("priority", &preferred.priority)?;
+        }
+        if !preferred.pin_policy.is_default() {
+            map.serialize_entry("ifPresentInBuildEnv", &preferred.pin_policy)?;
+        }
+        map.end()
+    }
+}
+
+/// Declares that a package cannot be used alongside any version of
+/// another package that falls within a range.
+///
+/// Conflicts do not cause the other package to be resolved, they only
+/// prevent it from being resolved into the same environment.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ConflictRequest {
+    pub pkg: RangeIdent,
+}
+
+impl ConflictRequest {
+    pub fn new(pkg: RangeIdent) -> Self {
+        Self { pkg }
+    }
+
+    /// Return true if the given package falls within this conflict.
+    pub fn is_conflicting_package(&self, pkg: &BuildIdent) -> bool {
+        self.pkg.is_applicable(&pkg.to_any())
+    }
+}
+
+impl std::fmt::Display for ConflictRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char('!')?;
+        self.pkg.fmt(f)
+    }
+}
+
+impl FromStr for ConflictRequest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let pkg = s.strip_prefix('!').unwrap_or(s);
+        Ok(Self::new(pkg.parse()?))
+    }
+}
+
+impl Serialize for ConflictRequest {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(&self.pkg)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConflictRequest {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RangeIdent::deserialize(deserializer).map(Self::new)
+    }
+}
+
 /// A set of restrictions placed on selected packages' build options.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct VarRequest<T = PinnableValue> {
@@ -577,6 +872,12 @@ pub enum RequestedBy {
     CommandLine,
     /// Embedded in another package
     Embedded(BuildIdent),
+    /// Embedded in another package, along with whatever requested
+    /// that package, so that the full chain can be reported
+    EmbeddedChain {
+        parent: BuildIdent,
+        parent_requested_by: Vec<RequestedBy>,
+    },
     /// A source package that made the request during a source build resolve
     SourceBuild(AnyIdent),
     /// A package that made the request as part of a binary build env setup
@@ -620,6 +921,20 @@ impl std::fmt::Display for RequestedBy {
         match self {
             RequestedBy::CommandLine => write!(f, "command line"),
             RequestedBy::Embedded(ident) => write!(f, "embedded in {ident}"),
+            RequestedBy::EmbeddedChain {
+                parent,
+                parent_requested_by,
+            } => {
+                write!(f, "embedded in {parent}")?;
+                if !parent_requested_by.is_empty() {
+                    write!(
+                        f,
+                        " (requested by {})",
+                        parent_requested_by.iter().join(", ")
+                    )?;
+                }
+                Ok(())
+            }
             RequestedBy::SourceBuild(ident) => write!(f, "{ident} source build"),
             RequestedBy::BinaryBuild(ident) => write!(f, "{ident} binary build"),
             RequestedBy::SourceTest(ident) => write!(f, "{ident} source test"),
@@ -653,6 +968,13 @@ pub struct PkgRequest {
         skip_serializing_if = "InclusionPolicy::is_default"
     )]
     pub inclusion_policy: InclusionPolicy,
+    /// Requests with a higher priority are decided on first by the solver.
+    ///
+    /// Requests with the same priority are decided on in the order
+    /// that they were made, which for the requirements of a package
+    /// is the order that they are declared in its spec.
+    #[serde(default, skip_serializing_if = "is_default_priority")]
+    pub priority: i64,
     #[serde(
         rename = "fromBuildEnv",
         default,
@@ -708,6 +1030,8 @@ impl std::hash::Hash for PkgRequest {
         self.pkg.hash(state);
         self.prerelease_policy.hash(state);
         self.inclusion_policy.hash(state);
+        // The 'priority' field is also not included in the hash,
+        // as it only changes the order that requests are decided on.
         match &self.pin {
             Some(p) => p.hash(state),
             None => {}
@@ -733,6 +1057,7 @@ impl PkgRequest {
             pkg,
             prerelease_policy: Default::default(),
             inclusion_policy: Default::default(),
+            priority: Default::default(),
             pin_policy: Default::default(),
             pin: Default::default(),
             required_compat: Some(CompatRule::Binary),
@@ -795,6 +1120,11 @@ impl PkgRequest {
         self
     }
 
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn with_pin(mut self, pin: Option<String>) -> Self {
         self.pin = pin;
         self
@@ -912,7 +1242,7 @@ impl PkgRequest {
     pub fn is_version_applicable(&self, version: &Version) -> Compatibility {
         if (self.prerelease_policy.is_none()
             || self.prerelease_policy == Some(PreReleasePolicy::ExcludeAll))
-            && !version.pre.is_empty()
+            && version.is_prerelease()
         {
             Compatibility::incompatible("prereleases not allowed".to_owned())
         } else {
@@ -977,6 +1307,7 @@ impl PkgRequest {
             (a, b) => a.or(b),
         };
         self.inclusion_policy = min(self.inclusion_policy, other.inclusion_policy);
+        self.priority = max(self.priority, other.priority);
         // Allow otherwise impossible to satisfy combinations of requests
         // to be merged if the combined inclusion policy is `IfAlreadyPresent`.
         //
@@ -1056,6 +1387,9 @@ impl FormatRequest for PkgRequest {
                     self.inclusion_policy.to_string().cyan()
                 ));
             }
+            if show_full_value || !is_default_priority(&self.priority) {
+                differences.push(format!("Priority: {}", self.priority.to_string().cyan()));
+            }
             if let Some(pin) = &self.pin {
                 differences.push(format!("fromBuildEnv: {}", pin.to_string().cyan()));
             }
@@ -1089,6 +1423,10 @@ pub fn is_false(value: &bool) -> bool {
     !*value
 }
 
+fn is_default_priority(priority: &i64) -> bool {
+    *priority == 0
+}
+
 /// A deserializable name and optional value where
 /// the value it identified by its position following
 /// a forward slash (eg: `/<value>`)
@@ -3,11 +3,17 @@
 // https://github.com/spkenv/spk
 
 use rstest::rstest;
-use spk_schema_foundation::version::{Compatibility, IncompatibleReason, API_STR, BINARY_STR};
+use spk_schema_foundation::version::{
+    parse_version,
+    Compatibility,
+    IncompatibleReason,
+    API_STR,
+    BINARY_STR,
+};
 use spk_schema_foundation::FromYaml;
 
-use super::{InclusionPolicy, PreReleasePolicy, Request};
-use crate::parse_build_ident;
+use super::{ConflictRequest, InclusionPolicy, PinPolicy, PreReleasePolicy, Request};
+use crate::{parse_build_ident, parse_ident_range};
 
 #[rstest]
 // 1. IncludeAll + ExcludeAll
@@ -153,6 +159,24 @@ fn test_prerelease_policy_contains(
     )
 }
 
+#[rstest]
+#[case("{pkg: something}", "1.0.0", true)]
+#[case("{pkg: something}", "1.0.0-rc.1", false)]
+#[case("{pkg: something}", "1.0.0.dev1", false)]
+#[case("{pkg: something, prereleasePolicy: IncludeAll}", "1.0.0.dev1", true)]
+fn test_prerelease_policy_version_applicable(
+    #[case] request: &str,
+    #[case] version: &str,
+    #[case] expected: bool,
+) {
+    let request = serde_yaml::from_str::<Request>(request)
+        .unwrap()
+        .into_pkg()
+        .expect("expected pkg request");
+    let version = parse_version(version).unwrap();
+    assert_eq!(request.is_version_applicable(&version).is_ok(), expected);
+}
+
 #[rstest]
 fn test_inclusion_policy() {
     let mut a = serde_yaml::from_str::<Request>("{pkg: something, include: IfAlreadyPresent}")
@@ -263,6 +287,37 @@ fn test_inclusion_policy_and_merge(
     }
 }
 
+#[rstest]
+fn test_priority_roundtrip_and_merge() {
+    let mut a = serde_yaml::from_str::<Request>("{pkg: something, priority: 10}")
+        .unwrap()
+        .into_pkg()
+        .unwrap();
+    assert_eq!(a.priority, 10);
+    let yaml = serde_yaml::to_string(&a).unwrap();
+    assert!(
+        yaml.contains("priority: 10"),
+        "should serialize priority: {yaml}"
+    );
+
+    let b = serde_yaml::from_str::<Request>("{pkg: something/1}")
+        .unwrap()
+        .into_pkg()
+        .unwrap();
+    assert_eq!(b.priority, 0, "priority should default to zero");
+    let yaml = serde_yaml::to_string(&b).unwrap();
+    assert!(
+        !yaml.contains("priority"),
+        "should skip default priority: {yaml}"
+    );
+
+    a.restrict(&b).unwrap();
+    assert_eq!(
+        a.priority, 10,
+        "merged request should keep the highest priority"
+    );
+}
+
 #[rstest]
 fn test_deserialize_value_or_pin() {
     let res = serde_yaml::from_str::<Request>("{var: python.abi/cp27m}");
@@ -478,3 +533,106 @@ fn test_deserialize_pkg_pin_string_or_bool() {
         ]
     );
 }
+
+#[rstest]
+#[case("oldlib", "oldlib/1.0.0/3I42H3S6", true)]
+#[case("oldlib/<2", "oldlib/1.0.0/3I42H3S6", true)]
+#[case("oldlib/<2", "oldlib/2.0.0/3I42H3S6", false)]
+#[case("!oldlib/<2", "oldlib/1.0.0/3I42H3S6", true)]
+#[case("oldlib", "newlib/1.0.0/3I42H3S6", false)]
+fn test_conflict_request_is_conflicting_package(
+    #[case] conflict: &str,
+    #[case] pkg: &str,
+    #[case] expected: bool,
+) {
+    let conflict: ConflictRequest = conflict.parse().unwrap();
+    let pkg = parse_build_ident(pkg).unwrap();
+    assert_eq!(conflict.is_conflicting_package(&pkg), expected);
+}
+
+#[rstest]
+fn test_conflict_request_roundtrip() {
+    let conflict = serde_yaml::from_str::<ConflictRequest>("oldlib/<2").unwrap();
+    assert_eq!(conflict.to_string(), "!oldlib/<2.0.0");
+    let yaml = serde_yaml::to_string(&conflict).unwrap();
+    assert_eq!(
+        serde_yaml::from_str::<ConflictRequest>(&yaml).unwrap(),
+        conflict
+    );
+}
+
+#[rstest]
+#[case("{pkg: maya, ifPresentInEnv: true}", InclusionPolicy::IfAlreadyPresent)]
+#[case("{pkg: maya, ifPresentInEnv: false}", InclusionPolicy::Always)]
+#[case(
+    "{pkg: maya, ifPresentInEnv: true, include: IfAlreadyPresent}",
+    InclusionPolicy::IfAlreadyPresent
+)]
+fn test_if_present_in_env(#[case] yaml: &str, #[case] expected: InclusionPolicy) {
+    let req = serde_yaml::from_str::<Request>(yaml).unwrap();
+    assert_eq!(req.into_pkg().unwrap().inclusion_policy, expected);
+}
+
+#[rstest]
+fn test_if_present_in_env_contradicts_include() {
+    let res = serde_yaml::from_str::<Request>("{pkg: maya, ifPresentInEnv: true, include: Always}");
+    assert!(res.is_err(), "contradicting inclusion settings should fail");
+}
+
+#[rstest]
+fn test_any_of_request_roundtrip() {
+    let req = serde_yaml::from_str::<Request>(
+        "{pkg: {any: [ffmpeg/4, libav/12]}, prereleasePolicy: IncludeAll}",
+    )
+    .unwrap();
+    let any_of = req
+        .clone()
+        .into_any_of()
+        .expect("expected an any-of request");
+    assert_eq!(any_of.alternatives.len(), 2);
+    assert_eq!(any_of.preferred().pkg.name.as_str(), "ffmpeg");
+    assert!(any_of
+        .alternatives
+        .iter()
+        .all(|a| a.prerelease_policy == Some(PreReleasePolicy::IncludeAll)));
+    assert_eq!(req.name().as_str(), "ffmpeg");
+    assert_eq!(
+        req.to_string(),
+        format!(
+            "{} | {}",
+            parse_ident_range("ffmpeg/4").unwrap(),
+            parse_ident_range("libav/12").unwrap()
+        )
+    );
+
+    let yaml = serde_yaml::to_string(&req).unwrap();
+    assert_eq!(serde_yaml::from_str::<Request>(&yaml).unwrap(), req);
+}
+
+#[rstest]
+fn test_any_of_request_keeps_pin_policy() {
+    let req = serde_yaml::from_str::<Request>(
+        "{pkg: {any: [ffmpeg/4, libav/12]}, ifPresentInBuildEnv: true}",
+    )
+    .unwrap();
+    let any_of = req
+        .clone()
+        .into_any_of()
+        .expect("expected an any-of request");
+    assert!(any_of
+        .alternatives
+        .iter()
+        .all(|a| a.pin_policy == PinPolicy::IfPresentInBuildEnv));
+
+    let yaml = serde_yaml::to_string(&req).unwrap();
+    assert_eq!(serde_yaml::from_str::<Request>(&yaml).unwrap(), req);
+}
+
+#[rstest]
+#[case("{pkg: {any: []}}")]
+#[case("{pkg: {all: [ffmpeg/4]}}")]
+#[case("{pkg: {any: [ffmpeg/4, libav]}, fromBuildEnv: true}")]
+fn test_any_of_request_invalid(#[case] yaml: &str) {
+    let res = serde_yaml::from_str::<Request>(yaml);
+    assert!(res.is_err(), "expected an invalid request: {yaml}");
+}
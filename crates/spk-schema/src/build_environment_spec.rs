@@ -0,0 +1,98 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./build_environment_spec_test.rs"]
+mod build_environment_spec_test;
+
+/// The host environment variables that are passed to every build script
+/// unless they are denied.
+///
+/// These are the variables needed for the shell, spfs and common tools
+/// to function, and which are not expected to change the build output.
+pub const DEFAULT_ALLOWED_HOST_VARS: &[&str] = &[
+    "HOME", "LANG", "LC_*", "LOGNAME", "PATH", "SPFS_*", "TERM", "TMPDIR", "TZ", "USER",
+];
+
+/// Controls which variables from the host environment reach the build script.
+///
+/// Only the variables in [`DEFAULT_ALLOWED_HOST_VARS`] and the ones
+/// listed in `allow` are passed through, so that builds do not change
+/// depending on who runs them. Variables set by spk for the build
+/// itself, such as `PREFIX` and the package options, are always set.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct BuildEnvironmentSpec {
+    /// Additional host variables to pass to the build, which may
+    /// contain `*` wildcards. `"*"` passes the entire host environment.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Host variables that are never passed to the build, even if allowed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// Variables to set to a fixed value, in place of any host value
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, String>,
+}
+
+impl BuildEnvironmentSpec {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// True if the named host variable should be passed to the build
+    pub fn is_allowed(&self, name: &str) -> bool {
+        let allowed = DEFAULT_ALLOWED_HOST_VARS
+            .iter()
+            .copied()
+            .chain(self.allow.iter().map(String::as_str))
+            .any(|pattern| matches_pattern(pattern, name));
+        allowed
+            && !self
+                .deny
+                .iter()
+                .any(|pattern| matches_pattern(pattern, name))
+    }
+
+    /// Filter the given host environment down to the variables
+    /// that should be passed to the build, along with any set ones.
+    pub fn host_environment<I>(&self, host: I) -> HashMap<String, String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut env: HashMap<_, _> = host
+            .into_iter()
+            .filter(|(name, _)| self.is_allowed(name))
+            .collect();
+        env.extend(self.set.iter().map(|(k, v)| (k.clone(), v.clone())));
+        env
+    }
+}
+
+/// Match a variable name against a pattern where `*`
+/// stands for any sequence of characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            // the last part must be at the very end of the name
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    // there were no wildcards in the pattern
+    rest.is_empty()
+}
@@ -0,0 +1,86 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{matches_pattern, BuildEnvironmentSpec};
+
+#[rstest]
+#[case("PATH", "PATH", true)]
+#[case("PATH", "PATHEXT", false)]
+#[case("LC_*", "LC_ALL", true)]
+#[case("LC_*", "LANG", false)]
+#[case("*", "ANYTHING", true)]
+#[case("*_PROXY", "HTTPS_PROXY", true)]
+#[case("*_PROXY", "PROXY", false)]
+#[case("CUDA_*_DIR", "CUDA_HOME_DIR", true)]
+#[case("CUDA_*_DIR", "CUDA_HOME", false)]
+fn test_matches_pattern(#[case] pattern: &str, #[case] name: &str, #[case] expected: bool) {
+    assert_eq!(matches_pattern(pattern, name), expected);
+}
+
+#[rstest]
+fn test_default_environment_is_minimal() {
+    let spec = BuildEnvironmentSpec::default();
+    let env = spec.host_environment([
+        ("PATH".to_string(), "/usr/bin".to_string()),
+        ("LC_ALL".to_string(), "C".to_string()),
+        ("LD_LIBRARY_PATH".to_string(), "/opt/lib".to_string()),
+        ("CFLAGS".to_string(), "-O3".to_string()),
+    ]);
+    assert_eq!(env.get("PATH").map(String::as_str), Some("/usr/bin"));
+    assert_eq!(env.get("LC_ALL").map(String::as_str), Some("C"));
+    assert!(
+        !env.contains_key("LD_LIBRARY_PATH") && !env.contains_key("CFLAGS"),
+        "variables that affect the build should not be passed by default"
+    );
+}
+
+#[rstest]
+fn test_environment_allow_deny_and_set() {
+    let spec: BuildEnvironmentSpec = serde_yaml::from_str(
+        r#"
+        allow: ["*_PROXY", CFLAGS]
+        deny: [TMPDIR, NO_PROXY]
+        set:
+          LANG: C.UTF-8
+          CFLAGS: -O2
+        "#,
+    )
+    .unwrap();
+    let env = spec.host_environment([
+        ("HTTP_PROXY".to_string(), "proxy:3128".to_string()),
+        ("NO_PROXY".to_string(), "localhost".to_string()),
+        ("TMPDIR".to_string(), "/scratch".to_string()),
+        ("LANG".to_string(), "en_US.UTF-8".to_string()),
+        ("CFLAGS".to_string(), "-O3".to_string()),
+    ]);
+    assert_eq!(
+        env.get("HTTP_PROXY").map(String::as_str),
+        Some("proxy:3128")
+    );
+    assert!(
+        !env.contains_key("NO_PROXY"),
+        "denied variables are removed"
+    );
+    assert!(!env.contains_key("TMPDIR"), "defaults can also be denied");
+    assert_eq!(env.get("LANG").map(String::as_str), Some("C.UTF-8"));
+    assert_eq!(env.get("CFLAGS").map(String::as_str), Some("-O2"));
+}
+
+#[rstest]
+fn test_environment_round_trip() {
+    let spec = BuildEnvironmentSpec {
+        allow: vec!["*".to_string()],
+        deny: vec!["LD_PRELOAD".to_string()],
+        ..Default::default()
+    };
+    let yaml = serde_yaml::to_string(&spec).unwrap();
+    assert!(
+        !yaml.contains("set"),
+        "empty fields should not be serialized"
+    );
+    let parsed: BuildEnvironmentSpec = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed, spec);
+}
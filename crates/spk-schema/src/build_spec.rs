@@ -12,7 +12,15 @@ use spk_schema_foundation::name::PkgName;
 use spk_schema_foundation::option_map::{OptionMap, Stringified, HOST_OPTIONS};
 use strum::Display;
 
-use super::{v0, Opt, ValidationSpec};
+use super::{
+    v0,
+    BuildEnvironmentSpec,
+    CaptureSpec,
+    Opt,
+    RelocateSpec,
+    SandboxSpec,
+    ValidationSpec,
+};
 use crate::name::{OptName, OptNameBuf};
 use crate::option::{PkgOpt, VarOpt};
 use crate::{Error, Result, Variant};
@@ -118,7 +126,7 @@ pub struct BuildSpec {
     /// The raw variant specs as they were parsed from the recipe, so the
     /// recipe can be serialized back out with the same variant spec.
     #[serde(default, rename = "variants", skip_serializing_if = "Vec::is_empty")]
-    raw_variants: Vec<v0::VariantSpec>,
+    raw_variants: Vec<v0::VariantSpecOrMatrix>,
     /// The parsed variants, which are used for building.
     #[serde(skip)]
     pub variants: Vec<v0::Variant>,
@@ -126,17 +134,40 @@ pub struct BuildSpec {
     pub validation: ValidationSpec,
     #[serde(default, skip_serializing_if = "AutoHostVars::is_default")]
     pub auto_host_vars: AutoHostVars,
+    /// If set, the build script is run within a sandbox
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxSpec>,
+    /// Which host environment variables are passed to the build script
+    #[serde(default, skip_serializing_if = "BuildEnvironmentSpec::is_default")]
+    pub environment: BuildEnvironmentSpec,
+    /// If set, the build output is checked for references to the build prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relocate: Option<RelocateSpec>,
+    /// If set, only some of the files produced by the build are collected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<CaptureSpec>,
+    /// Packages that make up the compiler toolchain for this build
+    ///
+    /// These are added as package options for every variant, and
+    /// downstream builds must use the same version of each one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toolchain: Vec<PkgOpt>,
 }
 
 impl Default for BuildSpec {
     fn default() -> Self {
         Self {
-            script: Script(vec!["sh ./build.sh".into()]),
+            script: Script::new(["sh ./build.sh"]),
             options: Vec::new(),
             raw_variants: Vec::new(),
             variants: Vec::new(),
             validation: ValidationSpec::default(),
             auto_host_vars: AutoHostVars::default(),
+            sandbox: None,
+            environment: BuildEnvironmentSpec::default(),
+            relocate: None,
+            capture: None,
+            toolchain: Vec::new(),
         }
     }
 }
@@ -153,6 +184,13 @@ impl BuildSpec {
         V: Variant,
     {
         let mut opts = self.options.clone();
+        // toolchain packages are added as options, unless already
+        // present (which is always the case for built packages)
+        for pkg in self.toolchain.iter() {
+            if !opts.iter().any(|o| o.full_name() == pkg.pkg.as_opt_name()) {
+                opts.push(Opt::Pkg(pkg.clone()));
+            }
+        }
         let mut known = opts
             .iter()
             .map(Opt::full_name)
@@ -279,8 +317,14 @@ impl BuildSpec {
         V: Variant,
     {
         let (options, opts) = self.resolve_options_for_pkg_name(pkg_name, variant)?;
+        // secret values are not published, so they cannot identify the build
+        let secret = opts
+            .iter()
+            .filter(|opt| opt.is_secret())
+            .map(|opt| opt.full_name().to_owned())
+            .collect::<HashSet<_>>();
         let mut hasher = ring::digest::Context::new(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
-        for (name, value) in options.iter() {
+        for (name, value) in options.iter().filter(|(name, _)| !secret.contains(*name)) {
             hasher.update(name.as_bytes());
             hasher.update(b"=");
             hasher.update(value.as_bytes());
@@ -333,6 +377,25 @@ impl TryFrom<UncheckedBuildSpec> for BuildSpec {
         // Instead of comparing via build id, we just compare the variant
         // content to check that they are unique.
 
+        let secret = bs
+            .options
+            .iter()
+            .filter(|opt| opt.is_secret())
+            .map(Opt::full_name)
+            .collect::<HashSet<_>>();
+        for variant in bs.variants.iter() {
+            let options = variant.options();
+            let used = options.keys().find(|name| {
+                let name: &OptName = name;
+                secret.contains(name) || secret.contains(name.without_namespace())
+            });
+            if let Some(name) = used {
+                return Err(crate::Error::String(format!(
+                    "Option '{name}' is secret and cannot be set by a variant, since it does not contribute to the build digest"
+                )));
+            }
+        }
+
         let mut unique_variants = HashMap::new();
         for variant in bs.variants.iter() {
             let variant_uniqueness_key = {
@@ -448,6 +511,27 @@ impl<'de> Deserialize<'de> for UncheckedBuildSpec {
                         "auto_host_vars" => {
                             unchecked.auto_host_vars = map.next_value::<AutoHostVars>()?
                         }
+                        "sandbox" => unchecked.sandbox = map.next_value::<Option<SandboxSpec>>()?,
+                        "environment" => {
+                            unchecked.environment = map.next_value::<BuildEnvironmentSpec>()?
+                        }
+                        "relocate" => {
+                            unchecked.relocate = map.next_value::<Option<RelocateSpec>>()?
+                        }
+                        "capture" => unchecked.capture = map.next_value::<Option<CaptureSpec>>()?,
+                        "toolchain" => {
+                            unchecked.toolchain = map
+                                .next_value::<Vec<Opt>>()?
+                                .into_iter()
+                                .map(|opt| match opt {
+                                    Opt::Pkg(pkg) => Ok(pkg),
+                                    Opt::Var(var) => Err(serde::de::Error::custom(format!(
+                                        "toolchain entries must be packages, got var: {}",
+                                        var.var
+                                    ))),
+                                })
+                                .collect::<std::result::Result<_, _>>()?;
+                        }
                         _ => {
                             // for forwards compatibility we ignore any unrecognized
                             // field, but consume it just the same
@@ -457,6 +541,16 @@ impl<'de> Deserialize<'de> for UncheckedBuildSpec {
                     }
                 }
 
+                let mut unique_toolchain = HashSet::new();
+                for pkg in unchecked.toolchain.iter() {
+                    if !unique_toolchain.insert(&pkg.pkg) {
+                        return Err(serde::de::Error::custom(format!(
+                            "toolchain package was specified more than once: {}",
+                            pkg.pkg
+                        )));
+                    }
+                }
+
                 if variants.is_empty() {
                     variants.push(Default::default());
                 }
@@ -466,7 +560,8 @@ impl<'de> Deserialize<'de> for UncheckedBuildSpec {
                 unchecked.variants = unchecked
                     .raw_variants
                     .iter()
-                    .map(|o| v0::Variant::from_spec(o.clone(), &unchecked.options))
+                    .flat_map(v0::VariantSpecOrMatrix::expand)
+                    .map(|o| v0::Variant::from_spec(o, &unchecked.options))
                     .collect::<Result<Vec<_>>>()
                     .map_err(serde::de::Error::custom)?;
 
@@ -479,14 +574,20 @@ impl<'de> Deserialize<'de> for UncheckedBuildSpec {
 }
 
 /// Some shell script to be executed
+///
+/// Scripts are run with `bash -ex` unless they name another
+/// interpreter, either explicitly or with a shebang on their first line.
 #[derive(Hash, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Script(Vec<String>);
+pub struct Script {
+    lines: Vec<String>,
+    interpreter: Option<String>,
+}
 
 impl std::ops::Deref for Script {
     type Target = Vec<String>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.lines
     }
 }
 
@@ -497,13 +598,59 @@ impl Script {
         I: IntoIterator<Item = S>,
         S: Into<String>,
     {
-        Self(script.into_iter().map(Into::into).collect())
+        Self {
+            lines: script.into_iter().map(Into::into).collect(),
+            interpreter: None,
+        }
+    }
+
+    /// Run this script with the given interpreter command rather than bash.
+    pub fn with_interpreter<S: Into<String>>(mut self, interpreter: S) -> Self {
+        self.interpreter = Some(interpreter.into());
+        self
+    }
+
+    /// The interpreter command explicitly requested for this script, if any.
+    pub fn explicit_interpreter(&self) -> Option<&str> {
+        self.interpreter.as_deref()
+    }
+
+    /// The interpreter that will be used to run this script.
+    pub fn interpreter(&self) -> ScriptInterpreter {
+        match self
+            .interpreter
+            .as_deref()
+            .and_then(ScriptInterpreter::parse)
+        {
+            Some(interpreter) if interpreter.is_shell() => ScriptInterpreter::default(),
+            Some(interpreter) => interpreter,
+            None => ScriptInterpreter::from_script_text(&self.lines.join("\n")),
+        }
+    }
+
+    /// The full text of this script, as it should be written to disk.
+    ///
+    /// When an interpreter was requested explicitly, a shebang naming
+    /// it is added to the first line so that the text can be run with
+    /// [`ScriptInterpreter::from_script_text`] later on.
+    pub fn to_source(&self) -> String {
+        let body = self.lines.join("\n");
+        match self.interpreter.as_deref() {
+            Some(interpreter) if !body.starts_with("#!") => {
+                if interpreter.starts_with('/') {
+                    format!("#!{interpreter}\n{body}")
+                } else {
+                    format!("#!/usr/bin/env {interpreter}\n{body}")
+                }
+            }
+            _ => body,
+        }
     }
 }
 
 impl From<Vec<String>> for Script {
     fn from(v: Vec<String>) -> Self {
-        Self(v)
+        Self::new(v)
     }
 }
 
@@ -512,10 +659,13 @@ impl<'de> Deserialize<'de> for Script {
     where
         D: serde::Deserializer<'de>,
     {
-        struct ScriptVisitor;
+        /// The lines of a script, without any interpreter
+        struct Lines(Vec<String>);
 
-        impl<'de> serde::de::Visitor<'de> for ScriptVisitor {
-            type Value = Vec<String>;
+        struct LinesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for LinesVisitor {
+            type Value = Lines;
 
             fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
                 f.write_str("a string or list of strings")
@@ -529,17 +679,81 @@ impl<'de> Deserialize<'de> for Script {
                 while let Some(line) = seq.next_element::<Stringified>()? {
                     script.push(line.0)
                 }
-                Ok(script)
+                Ok(Lines(script))
             }
 
             fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
             where
                 E: serde::de::Error,
             {
-                Ok(v.split('\n').map(String::from).collect())
+                Ok(Lines(v.split('\n').map(String::from).collect()))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Lines {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer.deserialize_any(LinesVisitor)
             }
         }
-        deserializer.deserialize_any(ScriptVisitor).map(Self)
+
+        struct ScriptVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ScriptVisitor {
+            type Value = Script;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a string, list of strings or mapping with 'interpreter' and 'run'")
+            }
+
+            fn visit_seq<A>(self, seq: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                LinesVisitor.visit_seq(seq).map(|l| Script::new(l.0))
+            }
+
+            fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                LinesVisitor.visit_str(v).map(|l| Script::new(l.0))
+            }
+
+            fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut interpreter = None;
+                let mut lines = None;
+                while let Some(key) = map.next_key::<Stringified>()? {
+                    match key.as_str() {
+                        "interpreter" => interpreter = Some(map.next_value::<String>()?),
+                        "run" => lines = Some(map.next_value::<Lines>()?.0),
+                        _ => {
+                            // ignore any unrecognized field, but consume the value anyway
+                            // TODO: could we warn about fields that we don't recognize?
+                            map.next_value::<serde::de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                let lines = lines.ok_or_else(|| serde::de::Error::missing_field("run"))?;
+                let interpreter = interpreter
+                    .map(|i| i.trim().to_string())
+                    .filter(|i| !i.is_empty());
+                if let Some(i) = interpreter.as_deref() {
+                    if ScriptInterpreter::parse(i).is_none() {
+                        return Err(serde::de::Error::custom(format!(
+                            "invalid script interpreter: {i:?}"
+                        )));
+                    }
+                }
+                Ok(Script { lines, interpreter })
+            }
+        }
+        deserializer.deserialize_any(ScriptVisitor)
     }
 }
 
@@ -548,6 +762,115 @@ impl serde::ser::Serialize for Script {
     where
         S: serde::Serializer,
     {
-        serializer.collect_seq(self.0.iter())
+        use serde::ser::SerializeMap;
+
+        match &self.interpreter {
+            None => serializer.collect_seq(self.lines.iter()),
+            Some(interpreter) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("interpreter", interpreter)?;
+                map.serialize_entry("run", &self.lines)?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// The program used to run a script, along with the arguments
+/// that are given to it before the path of the script itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptInterpreter {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Default for ScriptInterpreter {
+    /// Scripts are run with bash, printing each command
+    /// and exiting at the first failure.
+    fn default() -> Self {
+        Self {
+            program: "bash".into(),
+            args: vec!["-ex".into()],
+        }
+    }
+}
+
+impl ScriptInterpreter {
+    /// Parse an interpreter command line, eg: `python3 -u`.
+    ///
+    /// Returns None if the command is empty.
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut parts = command.split_whitespace().map(String::from);
+        let program = parts.next()?;
+        Some(Self {
+            program,
+            args: parts.collect(),
+        })
+    }
+
+    /// Identify the interpreter for the given script text from its shebang.
+    ///
+    /// Scripts without a shebang, or whose shebang names a
+    /// shell that is compatible with bash, use the default interpreter.
+    pub fn from_script_text(text: &str) -> Self {
+        let first_line = text.lines().next().unwrap_or_default();
+        let Some(shebang) = first_line.strip_prefix("#!") else {
+            return Self::default();
+        };
+        match Self::parse(shebang) {
+            Some(interpreter) if !interpreter.is_shell() => interpreter,
+            _ => Self::default(),
+        }
+    }
+
+    /// The program to execute.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// The arguments given to the program before the script path.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The name of the language interpreter, eg: `python3`.
+    ///
+    /// This looks past `env` when it is used to locate the interpreter.
+    pub fn name(&self) -> &str {
+        fn base(path: &str) -> &str {
+            path.rsplit('/').next().unwrap_or(path)
+        }
+        let program = base(&self.program);
+        if program != "env" {
+            return program;
+        }
+        self.args
+            .iter()
+            .map(String::as_str)
+            .find(|a| !a.starts_with('-'))
+            .map(base)
+            .unwrap_or(program)
+    }
+
+    /// True if this interpreter is a shell that runs bash scripts.
+    pub fn is_shell(&self) -> bool {
+        matches!(self.name(), "bash" | "sh")
+    }
+
+    /// The file extension used when staging scripts for this interpreter.
+    pub fn file_extension(&self) -> &'static str {
+        let name = self.name();
+        let language = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        match language {
+            "bash" | "sh" | "zsh" | "dash" | "ksh" => "sh",
+            "python" => "py",
+            "perl" => "pl",
+            "ruby" => "rb",
+            "node" => "js",
+            "pwsh" | "powershell" => "ps1",
+            "tclsh" => "tcl",
+            "lua" => "lua",
+            _ => "script",
+        }
     }
 }
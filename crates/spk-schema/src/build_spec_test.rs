@@ -3,10 +3,11 @@
 // https://github.com/spkenv/spk
 
 use rstest::rstest;
-use spk_schema_foundation::{option_map, pkg_name, FromYaml};
+use spk_schema_foundation::{opt_name, option_map, pkg_name, FromYaml};
 
-use super::{AutoHostVars, BuildSpec};
+use super::{AutoHostVars, BuildSpec, Script, ScriptInterpreter};
 use crate::build_spec::UncheckedBuildSpec;
+use crate::Variant;
 
 #[rstest]
 fn test_auto_host_vars_default() {
@@ -223,3 +224,203 @@ options:
         .unwrap();
     assert_ne!(build_id1, build_id2);
 }
+
+#[rstest]
+fn test_toolchain_is_added_to_variant_options() {
+    let build_spec: BuildSpec = serde_yaml::from_str(
+        r#"{
+        auto_host_vars: None,
+        options: [{var: debug/off}],
+        toolchain: [{pkg: gcc/>=9}, {pkg: cmake}],
+    }"#,
+    )
+    .unwrap();
+    let opt_names: Vec<String> = build_spec
+        .opts_for_variant(&option_map! {})
+        .unwrap()
+        .iter()
+        .map(|o| o.full_name().to_string())
+        .collect();
+    assert_eq!(opt_names, vec!["debug", "gcc", "cmake"]);
+}
+
+#[rstest]
+fn test_toolchain_defers_to_existing_option() {
+    let build_spec: BuildSpec = serde_yaml::from_str(
+        r#"{
+        auto_host_vars: None,
+        options: [{pkg: gcc/9}],
+        toolchain: [{pkg: gcc/>=9}],
+    }"#,
+    )
+    .unwrap();
+    let opts = build_spec.opts_for_variant(&option_map! {}).unwrap();
+    assert_eq!(opts.len(), 1, "toolchain should not duplicate an option");
+}
+
+#[rstest]
+#[case::var("toolchain: [{var: gcc/9}]")]
+#[case::duplicate("toolchain: [{pkg: gcc/9}, {pkg: gcc/10}]")]
+fn test_toolchain_invalid(#[case] yaml: &str) {
+    serde_yaml::from_str::<BuildSpec>(yaml).expect_err("toolchain should be rejected");
+}
+
+#[rstest]
+fn test_variant_matrix_expansion() {
+    let build_spec: BuildSpec = serde_yaml::from_str(
+        r#"{
+        options: [{var: python}, {var: gcc}],
+        variants: [
+            {matrix: {python: [2.7, 3.7], gcc: [6.3, 9.3]}, exclude: [{python: 2.7, gcc: 9.3}]},
+            {python: "3.9", gcc: "11.2"},
+        ],
+    }"#,
+    )
+    .unwrap();
+    let variants: Vec<_> = build_spec
+        .variants
+        .iter()
+        .map(|v| {
+            let options = v.options();
+            (
+                options.get(opt_name!("python")).unwrap().clone(),
+                options.get(opt_name!("gcc")).unwrap().clone(),
+            )
+        })
+        .collect();
+    let expected: Vec<_> = [
+        ("2.7", "6.3"),
+        ("3.7", "6.3"),
+        ("3.7", "9.3"),
+        ("3.9", "11.2"),
+    ]
+    .iter()
+    .map(|(python, gcc)| (python.to_string(), gcc.to_string()))
+    .collect();
+    assert_eq!(variants, expected);
+}
+
+#[rstest]
+fn test_variant_matrix_round_trip() {
+    let yaml = r#"{
+        variants: [{matrix: {python: [2.7, 3.7], debug: ["on", "off"]}, exclude: [{debug: "on"}]}],
+    }"#;
+    let build_spec: BuildSpec = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(build_spec.variants.len(), 2);
+
+    let serialized = serde_yaml::to_string(&build_spec).unwrap();
+    assert!(
+        serialized.contains("matrix:"),
+        "the matrix should be kept when serialized, got:\n{serialized}"
+    );
+    let parsed: BuildSpec = serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(parsed, build_spec);
+}
+
+#[rstest]
+#[case::empty("variants: [{matrix: {}}]")]
+#[case::no_values("variants: [{matrix: {python: []}}]")]
+#[case::unknown_exclude("variants: [{matrix: {python: [2.7]}, exclude: [{gcc: 6.3}]}]")]
+#[case::empty_exclude("variants: [{matrix: {python: [2.7]}, exclude: [{}]}]")]
+#[case::exclude_without_matrix("variants: [{python: 2.7, exclude: [{python: 2.7}]}]")]
+#[case::extra_field("variants: [{matrix: {python: [2.7]}, gcc: 6.3}]")]
+#[case::duplicates("variants: [{matrix: {python: [2.7, 2.7]}}]")]
+fn test_variant_matrix_invalid(#[case] yaml: &str) {
+    serde_yaml::from_str::<BuildSpec>(yaml).expect_err("variant matrix should be rejected");
+}
+
+#[rstest]
+fn test_script_interpreter_round_trip() {
+    let yaml = r#"{
+        script: {interpreter: python3, run: "import sys\nprint(sys.version)"},
+    }"#;
+    let build_spec: BuildSpec = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(build_spec.script.explicit_interpreter(), Some("python3"));
+    assert_eq!(build_spec.script.interpreter().name(), "python3");
+    assert_eq!(
+        build_spec.script.to_source(),
+        "#!/usr/bin/env python3\nimport sys\nprint(sys.version)"
+    );
+
+    let serialized = serde_yaml::to_string(&build_spec).unwrap();
+    let parsed: BuildSpec = serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(parsed, build_spec);
+}
+
+#[rstest]
+#[case::default("make install", "bash", &["-ex"], "sh")]
+#[case::shell_shebang("#!/bin/bash\nmake install", "bash", &["-ex"], "sh")]
+#[case::env_shebang("#!/usr/bin/env python3\nprint()", "/usr/bin/env", &["python3"], "py")]
+#[case::direct_shebang("#!/usr/bin/perl -w\nprint", "/usr/bin/perl", &["-w"], "pl")]
+#[case::unknown_shebang("#!/opt/bin/mylang\nx", "/opt/bin/mylang", &[], "script")]
+fn test_script_interpreter_from_script_text(
+    #[case] text: &str,
+    #[case] program: &str,
+    #[case] args: &[&str],
+    #[case] extension: &str,
+) {
+    let interpreter = ScriptInterpreter::from_script_text(text);
+    assert_eq!(interpreter.program(), program);
+    assert_eq!(interpreter.args(), args);
+    assert_eq!(interpreter.file_extension(), extension);
+}
+
+#[rstest]
+fn test_script_explicit_shell_interpreter_uses_default() {
+    let script = Script::new(["make install"]).with_interpreter("sh");
+    assert_eq!(script.interpreter(), ScriptInterpreter::default());
+}
+
+#[rstest]
+fn test_script_interpreter_requires_run() {
+    serde_yaml::from_str::<Script>("{interpreter: python3}")
+        .expect_err("a script mapping without any lines should be rejected");
+}
+
+#[rstest]
+fn test_secret_options_do_not_change_build_id() {
+    let yaml = r#"
+options:
+  - var: debug
+  - var: license_server
+    secret: true
+"#;
+    let spec = serde_yaml::from_str::<BuildSpec>(yaml).unwrap();
+    let build_id1 = spec
+        .build_digest(
+            pkg_name!("dummy"),
+            &option_map! {"debug" => "on", "license_server" => "one"},
+        )
+        .unwrap();
+    let build_id2 = spec
+        .build_digest(
+            pkg_name!("dummy"),
+            &option_map! {"debug" => "on", "license_server" => "two"},
+        )
+        .unwrap();
+    let build_id3 = spec
+        .build_digest(
+            pkg_name!("dummy"),
+            &option_map! {"debug" => "off", "license_server" => "two"},
+        )
+        .unwrap();
+    assert_eq!(build_id1, build_id2);
+    assert_ne!(build_id2, build_id3);
+}
+
+#[rstest]
+#[case("{license_server: one}")]
+#[case("{dummy.license_server: one}")]
+fn test_secret_options_cannot_be_used_in_variants(#[case] variant: &str) {
+    let yaml = format!(
+        r#"{{
+        options: [{{var: license_server, secret: true}}],
+        variants: [{variant}],
+    }}"#
+    );
+    let err = serde_yaml::from_str::<BuildSpec>(&yaml).expect_err("secret option in variant");
+    assert!(
+        err.to_string().contains("is secret"),
+        "unexpected error: {err}"
+    );
+}
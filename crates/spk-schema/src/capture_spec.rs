@@ -0,0 +1,28 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./capture_spec_test.rs"]
+mod capture_spec_test;
+
+/// Selects which of the files produced by a build are
+/// collected into the package.
+///
+/// Patterns are globs that are matched against paths relative to
+/// the build prefix (eg: `lib/*.so`), and those without any `/` are
+/// matched against file and directory names at any depth (eg: `*.o`).
+/// The package's own metadata is always collected.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct CaptureSpec {
+    /// Only collect files that match one of these patterns,
+    /// or all files when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Never collect files that match one of these
+    /// patterns, or anything within a matching directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
@@ -0,0 +1,30 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::CaptureSpec;
+
+#[rstest]
+fn test_capture_round_trip() {
+    let capture: CaptureSpec =
+        serde_yaml::from_str("{include: ['lib/**', 'include/**'], exclude: ['*.o', '.cache']}")
+            .unwrap();
+    assert_eq!(capture.include, vec!["lib/**", "include/**"]);
+    assert_eq!(capture.exclude, vec!["*.o", ".cache"]);
+    let yaml = serde_yaml::to_string(&capture).unwrap();
+    let capture2: CaptureSpec = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(capture2, capture);
+}
+
+#[rstest]
+fn test_capture_defaults_to_everything() {
+    let capture: CaptureSpec = serde_yaml::from_str("{exclude: ['*.pyc']}").unwrap();
+    assert!(capture.include.is_empty());
+    let yaml = serde_yaml::to_string(&capture).unwrap();
+    assert!(
+        !yaml.contains("include"),
+        "empty include should not be serialized"
+    );
+}
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::foundation::ident_component::Component;
 use crate::foundation::spec_ops::{ComponentOps, FileMatcher};
-use crate::Result;
+use crate::{EnvOp, OpKind, Result};
 
 #[cfg(test)]
 #[path = "./component_spec_test.rs"]
@@ -39,6 +39,14 @@ pub struct ComponentSpec {
     pub embedded: super::EmbeddedPackagesList,
     #[serde(default)]
     pub file_match_mode: ComponentFileMatchMode,
+    /// Environment operations that are only applied
+    /// when this component is installed
+    #[serde(
+        default,
+        deserialize_with = "deserialize_component_env",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub environment: Vec<EnvOp>,
 }
 
 impl ComponentSpec {
@@ -54,6 +62,7 @@ impl ComponentSpec {
             requirements: Default::default(),
             embedded: Default::default(),
             file_match_mode: Default::default(),
+            environment: Default::default(),
         })
     }
 
@@ -67,6 +76,7 @@ impl ComponentSpec {
             requirements: Default::default(),
             embedded: Default::default(),
             file_match_mode: Default::default(),
+            environment: Default::default(),
         }
     }
 
@@ -80,10 +90,24 @@ impl ComponentSpec {
             requirements: Default::default(),
             embedded: Default::default(),
             file_match_mode: Default::default(),
+            environment: Default::default(),
         }
     }
 }
 
+fn deserialize_component_env<'de, D>(deserializer: D) -> std::result::Result<Vec<EnvOp>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let ops = Vec::<EnvOp>::deserialize(deserializer)?;
+    if ops.iter().any(|op| op.kind() == OpKind::Priority) {
+        return Err(serde::de::Error::custom(
+            "priority can only be set in the package's install environment, not for a component",
+        ));
+    }
+    Ok(ops)
+}
+
 impl ComponentOps for ComponentSpec {
     fn files(&self) -> &FileMatcher {
         &self.files
@@ -47,3 +47,27 @@ fn test_component_name_serialize() {
         serde_yaml::from_str("other").unwrap()
     );
 }
+
+#[rstest]
+fn test_component_environment() {
+    let spec = serde_yaml::from_str::<ComponentSpec>(
+        "{name: python, environment: [{prepend: PYTHONPATH, value: /spfs/lib/python}]}",
+    )
+    .unwrap();
+    assert_eq!(spec.environment.len(), 1);
+    let inter = serde_yaml::to_string(&spec).unwrap();
+    let spec2 = serde_yaml::from_str::<ComponentSpec>(&inter).unwrap();
+    assert_eq!(spec, spec2, "expected no changes going through yaml");
+
+    let yaml = serde_yaml::to_string(&ComponentSpec::new("other").unwrap()).unwrap();
+    assert!(
+        !yaml.contains("environment"),
+        "an empty environment should not be serialized"
+    );
+}
+
+#[rstest]
+fn test_component_environment_rejects_priority() {
+    serde_yaml::from_str::<ComponentSpec>("{name: python, environment: [{priority: 10}]}")
+        .expect_err("priority should only be allowed for the whole package");
+}
@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use itertools::Itertools;
+use spk_schema_ident::RangeIdent;
+
 use crate::Result;
 
 /// Can be deprecated
@@ -9,6 +12,22 @@ use crate::Result;
 pub trait Deprecate {
     /// Report true if this instance has been deprecated
     fn is_deprecated(&self) -> bool;
+
+    /// The packages that are suggested for use instead of this
+    /// deprecated instance, if any
+    fn replaced_by(&self) -> &[RangeIdent] {
+        &[]
+    }
+
+    /// A message suggesting what to use instead of this deprecated
+    /// instance, eg: "use foo/2.0 or bar"
+    fn replacement_suggestion(&self) -> Option<String> {
+        let replaced_by = self.replaced_by();
+        if replaced_by.is_empty() {
+            return None;
+        }
+        Some(format!("use {}", replaced_by.iter().join(" or ")))
+    }
 }
 
 #[enum_dispatch::enum_dispatch]
@@ -26,7 +45,13 @@ pub trait DeprecateMut: Deprecate {
     }
 
     /// Mark this instance as no longer deprecated
+    ///
+    /// This also clears any suggested replacements.
     fn undeprecate(&mut self) -> Result<()>;
+
+    /// Set the packages that are suggested for use instead of this
+    /// instance once it has been deprecated
+    fn set_replaced_by(&mut self, replaced_by: Vec<RangeIdent>) -> Result<()>;
 }
 
 impl<T> Deprecate for std::sync::Arc<T>
@@ -36,6 +61,10 @@ where
     fn is_deprecated(&self) -> bool {
         (**self).is_deprecated()
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        (**self).replaced_by()
+    }
 }
 
 impl<T> Deprecate for Box<T>
@@ -45,6 +74,10 @@ where
     fn is_deprecated(&self) -> bool {
         (**self).is_deprecated()
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        (**self).replaced_by()
+    }
 }
 
 impl<T> DeprecateMut for Box<T>
@@ -58,6 +91,10 @@ where
     fn undeprecate(&mut self) -> Result<()> {
         (**self).undeprecate()
     }
+
+    fn set_replaced_by(&mut self, replaced_by: Vec<RangeIdent>) -> Result<()> {
+        (**self).set_replaced_by(replaced_by)
+    }
 }
 
 impl<T> Deprecate for &T
@@ -67,6 +104,10 @@ where
     fn is_deprecated(&self) -> bool {
         (**self).is_deprecated()
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        (**self).replaced_by()
+    }
 }
 
 impl<T> Deprecate for &mut T
@@ -76,6 +117,10 @@ where
     fn is_deprecated(&self) -> bool {
         (**self).is_deprecated()
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        (**self).replaced_by()
+    }
 }
 
 impl<T> DeprecateMut for &mut T
@@ -89,4 +134,8 @@ where
     fn undeprecate(&mut self) -> Result<()> {
         (**self).undeprecate()
     }
+
+    fn set_replaced_by(&mut self, replaced_by: Vec<RangeIdent>) -> Result<()> {
+        (**self).set_replaced_by(replaced_by)
+    }
 }
@@ -4,7 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use spk_schema_foundation::ident_build::EmbeddedSource;
-use spk_schema_ident::AnyIdent;
+use spk_schema_ident::{AnyIdent, VersionIdent};
 
 use super::{BuildSpec, InstallSpec, Spec};
 use crate::foundation::ident_build::Build;
@@ -18,6 +18,23 @@ mod embedded_packages_list_test;
 #[serde(transparent)]
 pub struct EmbeddedPackagesList(Vec<Spec>);
 
+impl EmbeddedPackagesList {
+    /// Create the stub packages that represent each of the given
+    /// provided packages, in the same way as embedded packages.
+    pub fn from_provided(provides: &[VersionIdent]) -> Self {
+        Self(
+            provides
+                .iter()
+                .map(|provided| {
+                    Spec::V0Package(super::v0::Spec::new(
+                        provided.to_build(Build::Embedded(EmbeddedSource::Unknown)),
+                    ))
+                })
+                .collect(),
+        )
+    }
+}
+
 impl std::ops::Deref for EmbeddedPackagesList {
     type Target = Vec<Spec>;
     fn deref(&self) -> &Self::Target {
@@ -5,6 +5,8 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use spk_schema_foundation::ident_component::Component;
+use spk_schema_foundation::name::PkgName;
 use spk_schema_foundation::option_map::Stringified;
 
 #[cfg(test)]
@@ -16,6 +18,13 @@ const DEFAULT_VAR_SEP: &str = ";";
 #[cfg(unix)]
 const DEFAULT_VAR_SEP: &str = ":";
 
+/// A portable placeholder for the root of the spfs filesystem,
+/// expanded for the target shell when startup scripts are generated
+pub const PREFIX_PLACEHOLDER: &str = "${PREFIX}";
+/// A portable placeholder for the separator of path-like variables,
+/// expanded for the target shell when startup scripts are generated
+pub const PATHSEP_PLACEHOLDER: &str = "${PATHSEP}";
+
 const OP_APPEND: &str = "append";
 const OP_COMMENT: &str = "comment";
 const OP_PREPEND: &str = "prepend";
@@ -58,11 +67,51 @@ impl EnvOp {
 
     /// Construct the source representation for this operation in the
     /// format of the identified shell.
+    ///
+    /// Any portable placeholders are expanded for the shell first
+    /// (see [`EnvOp::to_portable`]).
     pub fn source_for_shell(&self, shell: spfs::ShellKind) -> String {
+        let op = self.to_portable(shell);
         match shell {
-            spfs::ShellKind::Bash => self.bash_source(),
-            spfs::ShellKind::Tcsh => self.tcsh_source(),
-            spfs::ShellKind::Powershell => self.powershell_source(),
+            spfs::ShellKind::Bash => op.bash_source(),
+            spfs::ShellKind::Tcsh => op.tcsh_source(),
+            spfs::ShellKind::Fish => op.fish_source(),
+            spfs::ShellKind::Powershell => op.powershell_source(),
+        }
+    }
+
+    /// Returns the EnvOp object with the portable placeholders in its
+    /// value and separator expanded for the given shell.
+    ///
+    /// `${PREFIX}` becomes the root of the spfs filesystem and `${PATHSEP}`
+    /// the separator of path-like variables on the operating system that
+    /// runs the shell, so that one recipe works on both linux and windows.
+    pub fn to_portable(&self, shell: spfs::ShellKind) -> Self {
+        let (prefix, pathsep) = match shell {
+            spfs::ShellKind::Bash | spfs::ShellKind::Tcsh | spfs::ShellKind::Fish => ("/spfs", ":"),
+            spfs::ShellKind::Powershell => ("C:\\spfs", ";"),
+        };
+        let expand = |value: &str| {
+            value
+                .replace(PREFIX_PLACEHOLDER, prefix)
+                .replace(PATHSEP_PLACEHOLDER, pathsep)
+        };
+        match self {
+            Self::Append(op) => Self::Append(AppendEnv {
+                append: op.append.clone(),
+                value: expand(&op.value),
+                separator: op.separator.as_deref().map(expand),
+            }),
+            Self::Prepend(op) => Self::Prepend(PrependEnv {
+                prepend: op.prepend.clone(),
+                value: expand(&op.value),
+                separator: op.separator.as_deref().map(expand),
+            }),
+            Self::Set(op) => Self::Set(SetEnv {
+                set: op.set.clone(),
+                value: expand(&op.value),
+            }),
+            Self::Comment(_) | Self::Priority(_) => self.clone(),
         }
     }
 
@@ -144,12 +193,78 @@ impl EnvOp {
         }
     }
 
+    /// Construct the fish source representation for this operation
+    pub fn fish_source(&self) -> String {
+        match self {
+            Self::Append(op) => op.fish_source(),
+            Self::Comment(op) => op.fish_source(),
+            Self::Prepend(op) => op.fish_source(),
+            Self::Priority(op) => op.fish_source(),
+            Self::Set(op) => op.fish_source(),
+        }
+    }
+
     /// Construct the powershell source representation for this operation
     pub fn powershell_source(&self) -> String {
-        todo!()
+        match self {
+            Self::Append(op) => op.powershell_source(),
+            Self::Comment(op) => op.powershell_source(),
+            Self::Prepend(op) => op.powershell_source(),
+            Self::Priority(op) => op.powershell_source(),
+            Self::Set(op) => op.powershell_source(),
+        }
+    }
+}
+
+/// The file name, without any extension, of the startup scripts
+/// generated for a package with the given runtime environment.
+///
+/// Startup scripts are sourced in file name order. Packages that
+/// declare a priority are prefixed with it, so that they are sourced
+/// before any package without one. The names are compared as text, so
+/// priorities of 100 or more sort amongst the two digit ones (eg: `100`
+/// before `20`), which is the order that existing packages rely on.
+pub fn startup_script_stem(name: &PkgName, ops: &[EnvOp]) -> String {
+    // namespaced package names cannot be used as-is in a file name
+    let name = name.as_str().replace(PkgName::NAMESPACE_SEP, "_");
+    match ops.iter().filter_map(EnvOp::priority).last() {
+        Some(priority) => format!("{priority:02}_spk_{name}"),
+        None => format!("spk_{name}"),
     }
 }
 
+/// The file name, without extension, of the startup script that applies
+/// the environment of one component of a package.
+///
+/// The ops are those of the whole package, so that component scripts
+/// share its priority and are sourced directly after its own script.
+pub fn component_startup_script_stem(
+    name: &PkgName,
+    component: &Component,
+    ops: &[EnvOp],
+) -> String {
+    format!("{}_{component}", startup_script_stem(name, ops))
+}
+
+/// Rewrite any `${NAME}` style variable references in the given
+/// value into the `{$NAME}` form that is understood by fish.
+fn fish_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut remaining = value;
+    while let Some(start) = remaining.find("${") {
+        let Some(end) = remaining[start..].find('}') else {
+            break;
+        };
+        result.push_str(&remaining[..start]);
+        result.push_str("{$");
+        result.push_str(&remaining[start + 2..start + end]);
+        result.push('}');
+        remaining = &remaining[start + end + 1..];
+    }
+    result.push_str(remaining);
+    result
+}
+
 impl<'de> Deserialize<'de> for EnvOp {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -358,6 +473,43 @@ impl AppendEnv {
         ]
         .join("\n")
     }
+
+    /// Construct the fish source representation for this operation
+    pub fn fish_source(&self) -> String {
+        let value = fish_value(&self.value);
+        [
+            format!("if set -q {}", self.append),
+            format!(
+                "    set -gx {} \"${}{}{}\"",
+                self.append,
+                self.append,
+                self.sep(),
+                value,
+            ),
+            "else".to_string(),
+            format!("    set -gx {} \"{}\"", self.append, value),
+            "end".to_string(),
+        ]
+        .join("\n")
+    }
+
+    /// Construct the powershell source representation for this operation
+    pub fn powershell_source(&self) -> String {
+        [
+            format!("if ($env:{}) {{", self.append),
+            format!(
+                "    $env:{} = \"$env:{}{}{}\"",
+                self.append,
+                self.append,
+                self.sep(),
+                self.value,
+            ),
+            "} else {".to_string(),
+            format!("    $env:{} = \"{}\"", self.append, self.value),
+            "}".to_string(),
+        ]
+        .join("\n")
+    }
 }
 
 /// Adds a comment to the generated environment script
@@ -376,9 +528,23 @@ impl EnvComment {
         // Both bash and tcsh source use the same comment syntax
         self.bash_source()
     }
+
+    /// Construct the fish source representation for this operation
+    pub fn fish_source(&self) -> String {
+        self.bash_source()
+    }
+
+    /// Construct the powershell source representation for this operation
+    pub fn powershell_source(&self) -> String {
+        self.bash_source()
+    }
 }
 
 /// Assigns a priority to the generated environment script
+///
+/// The priority is encoded into the file names of the startup scripts,
+/// which controls the order that they are sourced in relative to the
+/// scripts of other packages (see [`startup_script_stem`]).
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct EnvPriority {
     pub priority: u8,
@@ -395,6 +561,16 @@ impl EnvPriority {
         String::from("")
     }
 
+    /// Construct the fish source representation for this operation
+    pub fn fish_source(&self) -> String {
+        String::from("")
+    }
+
+    /// Construct the powershell source representation for this operation
+    pub fn powershell_source(&self) -> String {
+        String::from("")
+    }
+
     pub fn priority(&self) -> u8 {
         self.priority
     }
@@ -447,6 +623,43 @@ impl PrependEnv {
         ]
         .join("\n")
     }
+
+    /// Construct the fish source representation for this operation
+    pub fn fish_source(&self) -> String {
+        let value = fish_value(&self.value);
+        [
+            format!("if set -q {}", self.prepend),
+            format!(
+                "    set -gx {} \"{}{}${}\"",
+                self.prepend,
+                value,
+                self.sep(),
+                self.prepend,
+            ),
+            "else".to_string(),
+            format!("    set -gx {} \"{}\"", self.prepend, value),
+            "end".to_string(),
+        ]
+        .join("\n")
+    }
+
+    /// Construct the powershell source representation for this operation
+    pub fn powershell_source(&self) -> String {
+        [
+            format!("if ($env:{}) {{", self.prepend),
+            format!(
+                "    $env:{} = \"{}{}$env:{}\"",
+                self.prepend,
+                self.value,
+                self.sep(),
+                self.prepend,
+            ),
+            "} else {".to_string(),
+            format!("    $env:{} = \"{}\"", self.prepend, self.value),
+            "}".to_string(),
+        ]
+        .join("\n")
+    }
 }
 
 /// Operates on an environment variable by setting it to a value
@@ -465,4 +678,14 @@ impl SetEnv {
     pub fn tcsh_source(&self) -> String {
         format!("setenv {} \"{}\"", self.set, self.value)
     }
+
+    /// Construct the fish source representation for this operation
+    pub fn fish_source(&self) -> String {
+        format!("set -gx {} \"{}\"", self.set, fish_value(&self.value))
+    }
+
+    /// Construct the powershell source representation for this operation
+    pub fn powershell_source(&self) -> String {
+        format!("$env:{} = \"{}\"", self.set, self.value)
+    }
 }
@@ -3,8 +3,9 @@
 // https://github.com/spkenv/spk
 
 use rstest::rstest;
+use spk_schema_foundation::name::PkgName;
 
-use super::EnvOp;
+use super::{fish_value, startup_script_stem, EnvOp};
 
 #[rstest]
 #[case("{comment: This is a test}")]
@@ -57,6 +58,47 @@ fn test_valid_tcsh(#[case] op: &str) {
     assert!(out.status.success(), "failed to execute tcsh source");
 }
 
+#[rstest]
+#[case("{comment: This is a test}")]
+#[case("{append: SPK_TEST_VAR, value: simple}")]
+#[case("{prepend: SPK_TEST_VAR, value: simple}")]
+#[case("{set: SPK_TEST_VAR, value: simple}")]
+fn test_valid_fish(#[case] op: &str) {
+    let op: EnvOp = serde_yaml::from_str(op).unwrap();
+    println!("source:\n{}", op.fish_source());
+
+    let mut fish = std::process::Command::new("fish");
+    fish.arg("--no-config");
+    fish.arg("-c");
+    fish.arg(op.fish_source());
+    fish.stdin(std::process::Stdio::piped());
+    fish.stderr(std::process::Stdio::piped());
+    fish.stdout(std::process::Stdio::piped());
+    let out = match fish.output() {
+        Ok(out) => out,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            println!("fish not available on this system");
+            return;
+        }
+        Err(err) => panic!("failed to run fish: {err}"),
+    };
+    println!(
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(out.stdout.as_slice()),
+        String::from_utf8_lossy(out.stderr.as_slice())
+    );
+    assert!(out.status.success(), "failed to execute fish source");
+}
+
+#[rstest]
+#[case("simple", "simple")]
+#[case("${PREFIX}/bin", "{$PREFIX}/bin")]
+#[case("$PREFIX/lib:${HOME}/lib", "$PREFIX/lib:{$HOME}/lib")]
+#[case("unclosed${PREFIX", "unclosed${PREFIX")]
+fn test_fish_value(#[case] value: &str, #[case] expected: &str) {
+    assert_eq!(fish_value(value), expected);
+}
+
 #[rstest]
 #[case("{append: SPK_TEST_VAR, value: simple}")]
 #[case("{prepend: SPK_TEST_VAR, value: simple}")]
@@ -97,3 +139,47 @@ fn test_var_expansion(#[case] op: &str, #[case] vars: &[(&str, &str)], #[case] e
     );
     assert_eq!(expanded.value().unwrap(), expected);
 }
+
+#[rstest]
+#[case("my-pkg", &[], "spk_my-pkg")]
+#[case("my-pkg", &["{set: SPK_TEST_VAR, value: simple}"], "spk_my-pkg")]
+#[case("my-pkg", &["{priority: 5}", "{set: SPK_TEST_VAR, value: simple}"], "05_spk_my-pkg")]
+#[case("studio.tools/my-pkg", &["{priority: 25}"], "25_spk_studio.tools_my-pkg")]
+fn test_startup_script_stem(#[case] name: &str, #[case] ops: &[&str], #[case] expected: &str) {
+    let name = PkgName::new(name).unwrap();
+    let ops: Vec<EnvOp> = ops
+        .iter()
+        .map(|op| serde_yaml::from_str(op).unwrap())
+        .collect();
+    assert_eq!(startup_script_stem(name, &ops), expected);
+}
+
+#[rstest]
+#[case(spfs::ShellKind::Bash, r#"export PATH="/spfs/opt/bin:${PATH}""#)]
+#[case(
+    spfs::ShellKind::Powershell,
+    "$env:PATH = \"C:\\spfs/opt/bin;$env:PATH\""
+)]
+fn test_portable_placeholders(#[case] shell: spfs::ShellKind, #[case] expected: &str) {
+    let op: EnvOp = serde_yaml::from_str(
+        "{prepend: PATH, value: '${PREFIX}/opt/bin', separator: '${PATHSEP}'}",
+    )
+    .unwrap();
+    let source = op.source_for_shell(shell);
+    assert!(
+        source.lines().any(|line| line.trim() == expected),
+        "expected line {expected:?} in:\n{source}"
+    );
+}
+
+#[rstest]
+fn test_portable_placeholders_other_variables() {
+    let op: EnvOp =
+        serde_yaml::from_str("{set: SPK_TEST_VAR, value: '${PREFIX}${PATHSEP}${HOME}'}").unwrap();
+    let portable = op.to_portable(spfs::ShellKind::Fish);
+    assert_eq!(portable.value().unwrap(), "/spfs:${HOME}");
+    assert_eq!(
+        portable.source_for_shell(spfs::ShellKind::Fish),
+        "set -gx SPK_TEST_VAR \"/spfs:{$HOME}\""
+    );
+}
@@ -26,6 +26,7 @@ pub enum Error {
     #[error("Invalid inheritance: {0}")]
     InvalidInheritance(#[source] serde_yaml::Error),
     #[error("Invalid package spec file {0}: {1}")]
+    #[diagnostic(code(spk::schema::invalid_spec_file))]
     InvalidPackageSpecFile(
         std::path::PathBuf,
         #[source] Box<format_serde_error::SerdeError>,
@@ -0,0 +1,51 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use serde::{Deserialize, Serialize};
+
+use super::{SandboxSpec, Script};
+
+#[cfg(test)]
+#[path = "./install_hook_test.rs"]
+mod install_hook_test;
+
+/// A script that is run each time the package is added to an environment.
+///
+/// Hooks are meant for work that depends on the rest of the
+/// environment and so cannot be done when the package is built,
+/// such as generating font or plugin caches. They always run in a
+/// sandbox, which has no network access unless it is allowed.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstallHook {
+    /// A short name for this hook, unique within the package
+    #[serde(deserialize_with = "deserialize_hook_name")]
+    pub name: String,
+    /// The script to run, once the environment has been set up
+    pub script: Script,
+    /// Restrictions placed on the hook script
+    #[serde(default, skip_serializing_if = "is_default_sandbox")]
+    pub sandbox: SandboxSpec,
+}
+
+fn is_default_sandbox(sandbox: &SandboxSpec) -> bool {
+    sandbox == &SandboxSpec::default()
+}
+
+fn deserialize_hook_name<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(serde::de::Error::custom(format!(
+            "Invalid install hook name '{name}', must be non-empty and contain only letters, numbers, '-' or '_'"
+        )));
+    }
+    Ok(name)
+}
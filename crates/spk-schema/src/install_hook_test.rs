@@ -0,0 +1,55 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::InstallHook;
+use crate::InstallSpec;
+
+#[rstest]
+fn test_install_hook_deserialize() {
+    let spec: InstallSpec = serde_yaml::from_str(
+        r#"
+hooks:
+  - name: fontcache
+    script: fc-cache -f /spfs/share/fonts
+  - name: plugins
+    script:
+      interpreter: python3
+      run: import plugins; plugins.scan()
+    sandbox: {network: true}
+"#,
+    )
+    .unwrap();
+    assert_eq!(spec.hooks.len(), 2);
+    assert_eq!(spec.hooks[0].name, "fontcache");
+    assert!(
+        !spec.hooks[0].sandbox.network,
+        "hooks should have no network by default"
+    );
+    assert_eq!(spec.hooks[1].script.interpreter().name(), "python3");
+    assert!(spec.hooks[1].sandbox.network);
+    assert!(!spec.is_default());
+}
+
+#[rstest]
+fn test_install_hook_round_trip() {
+    let hook: InstallHook = serde_yaml::from_str("{name: fontcache, script: fc-cache -f}").unwrap();
+    let yaml = serde_yaml::to_string(&hook).unwrap();
+    assert!(
+        !yaml.contains("sandbox"),
+        "default sandbox should not be saved: {yaml}"
+    );
+    let hook2: InstallHook = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(hook2, hook);
+}
+
+#[rstest]
+#[case("")]
+#[case("font cache")]
+#[case("../fontcache")]
+fn test_install_hook_invalid_name(#[case] name: &str) {
+    let yaml = format!("{{name: '{name}', script: fc-cache -f}}");
+    serde_yaml::from_str::<InstallHook>(&yaml).expect_err("should reject an invalid hook name");
+}
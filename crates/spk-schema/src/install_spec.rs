@@ -3,9 +3,16 @@
 // https://github.com/spkenv/spk
 
 use serde::{Deserialize, Serialize};
-use spk_schema_ident::BuildIdent;
+use spk_schema_ident::{BuildIdent, ConflictRequest, VersionIdent};
 
-use super::{ComponentSpecList, EmbeddedPackagesList, EnvOp, OpKind, RequirementsList};
+use super::{
+    ComponentSpecList,
+    EmbeddedPackagesList,
+    EnvOp,
+    InstallHook,
+    OpKind,
+    RequirementsList,
+};
 use crate::foundation::option_map::OptionMap;
 use crate::Result;
 
@@ -19,7 +26,12 @@ pub struct InstallSpec {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub requirements: RequirementsList,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<ConflictRequest>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub embedded: EmbeddedPackagesList,
+    /// Other packages that this one can stand in for, each at a specific version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provides: Vec<VersionIdent>,
     #[serde(default)]
     pub components: ComponentSpecList,
     #[serde(
@@ -28,11 +40,23 @@ pub struct InstallSpec {
         skip_serializing_if = "Vec::is_empty"
     )]
     pub environment: Vec<EnvOp>,
+    /// Scripts that are run each time this package is added to an environment
+    #[serde(
+        default,
+        deserialize_with = "deserialize_hooks",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub hooks: Vec<InstallHook>,
 }
 
 impl InstallSpec {
     pub fn is_default(&self) -> bool {
-        self.requirements.is_empty() && self.embedded.is_empty() && self.components.is_default()
+        self.requirements.is_empty()
+            && self.conflicts.is_empty()
+            && self.embedded.is_empty()
+            && self.provides.is_empty()
+            && self.components.is_default()
+            && self.hooks.is_empty()
     }
 
     /// Render all requests with a package pin using the given resolved packages.
@@ -87,3 +111,19 @@ where
     }
     deserializer.deserialize_seq(EnvConfVisitor)
 }
+
+fn deserialize_hooks<'de, D>(deserializer: D) -> std::result::Result<Vec<InstallHook>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let hooks = Vec::<InstallHook>::deserialize(deserializer)?;
+    for (i, hook) in hooks.iter().enumerate() {
+        if hooks[..i].iter().any(|other| other.name == hook.name) {
+            return Err(serde::de::Error::custom(format!(
+                "Multiple install hooks are named '{}'",
+                hook.name
+            )));
+        }
+    }
+    Ok(hooks)
+}
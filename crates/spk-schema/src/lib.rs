@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+mod build_environment_spec;
 mod build_spec;
+mod capture_spec;
 mod component_spec;
 mod component_spec_list;
 mod deprecate;
@@ -10,13 +12,17 @@ mod embedded_packages_list;
 mod environ;
 mod error;
 mod input_variant;
+mod install_hook;
 mod install_spec;
 mod metadata;
 mod option;
 mod package;
+mod platform_support;
 pub mod prelude;
 mod recipe;
+mod relocate_spec;
 mod requirements_list;
+mod sandbox_spec;
 mod source_spec;
 mod spec;
 mod template;
@@ -25,21 +31,40 @@ pub mod v0;
 pub mod validation;
 pub mod variant;
 
-pub use build_spec::{BuildSpec, Script};
+pub use build_environment_spec::{BuildEnvironmentSpec, DEFAULT_ALLOWED_HOST_VARS};
+pub use build_spec::{BuildSpec, Script, ScriptInterpreter};
+pub use capture_spec::CaptureSpec;
 pub use component_spec::{ComponentFileMatchMode, ComponentSpec};
 pub use component_spec_list::ComponentSpecList;
 pub use deprecate::{Deprecate, DeprecateMut};
 pub use embedded_packages_list::EmbeddedPackagesList;
-pub use environ::{AppendEnv, EnvComment, EnvOp, EnvPriority, OpKind, PrependEnv, SetEnv};
+pub use environ::{
+    component_startup_script_stem,
+    startup_script_stem,
+    AppendEnv,
+    EnvComment,
+    EnvOp,
+    EnvPriority,
+    OpKind,
+    PrependEnv,
+    SetEnv,
+    PATHSEP_PLACEHOLDER,
+    PREFIX_PLACEHOLDER,
+};
 pub use error::{Error, Result};
 pub use input_variant::InputVariant;
+pub use install_hook::InstallHook;
 pub use install_spec::InstallSpec;
+pub use metadata::Meta;
 pub use option::{Inheritance, Opt};
 pub use package::{Package, PackageMut};
+pub use platform_support::{PlatformSupport, SupportedPlatform, SupportedPlatforms};
 pub use recipe::{BuildEnv, Recipe};
+pub use relocate_spec::{RelocateFileKind, RelocateMode, RelocateSpec};
 pub use requirements_list::RequirementsList;
+pub use sandbox_spec::{MemoryLimit, SandboxSpec};
 pub use serde_json;
-pub use source_spec::{GitSource, LocalSource, ScriptSource, SourceSpec, TarSource};
+pub use source_spec::{GitSource, GitSubmodules, LocalSource, ScriptSource, SourceSpec, TarSource};
 pub use spec::{Spec, SpecRecipe, SpecTemplate, SpecVariant};
 pub use spk_schema_foundation::option_map::{self, OptionMap};
 pub use spk_schema_foundation::{
@@ -8,7 +8,12 @@ use std::str::FromStr;
 use indexmap::set::IndexSet;
 use serde::{Deserialize, Serialize};
 use spk_schema_foundation::ident_component::ComponentBTreeSetBuf;
-use spk_schema_foundation::option_map::Stringified;
+use spk_schema_foundation::option_map::{
+    Error as OptionMapError,
+    OptionValue,
+    OptionValueType,
+    Stringified,
+};
 use spk_schema_ident::{NameAndValue, PinnableValue, RangeIdent};
 
 use crate::foundation::name::{OptName, OptNameBuf, PkgName, PkgNameBuf};
@@ -145,6 +150,11 @@ impl Opt {
         matches!(self, Self::Var(_))
     }
 
+    /// True if this is a var option whose value is kept out of the published package.
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Self::Var(v) if v.secret)
+    }
+
     pub fn into_var(self) -> Option<VarOpt> {
         match self {
             Self::Var(v) => Some(v),
@@ -179,10 +189,17 @@ impl TryFrom<Request> for Opt {
                 var,
                 default: value.as_pinned().map(str::to_string).unwrap_or_default(),
                 choices: Default::default(),
+                value_type: Default::default(),
+                min: None,
+                max: None,
                 inheritance: Default::default(),
                 description,
+                secret: false,
                 value: None,
             })),
+            Request::AnyOf(request) => Err(Error::String(format!(
+                "cannot create a build option from a choice of packages: {request}"
+            ))),
         }
     }
 }
@@ -214,6 +231,10 @@ impl<'de> Deserialize<'de> for Opt {
             var: Option<OptNameBuf>,
             choices: Option<IndexSet<String>>,
             inheritance: Option<Inheritance>,
+            value_type: Option<OptionValueType>,
+            min: Option<String>,
+            max: Option<String>,
+            secret: Option<bool>,
 
             // Both
             default: Option<String>,
@@ -272,6 +293,16 @@ impl<'de> Deserialize<'de> for Opt {
                             )
                         }
                         "inheritance" => self.inheritance = Some(map.next_value::<Inheritance>()?),
+                        "type" => {
+                            self.value_type = Some(
+                                map.next_value::<Stringified>()?
+                                    .parse()
+                                    .map_err(serde::de::Error::custom)?,
+                            )
+                        }
+                        "min" => self.min = Some(map.next_value::<Stringified>()?.0),
+                        "max" => self.max = Some(map.next_value::<Stringified>()?.0),
+                        "secret" => self.secret = Some(map.next_value::<bool>()?),
                         "default" => {
                             check_existing_default(&self)?;
                             self.default = Some(map.next_value::<Stringified>()?.0);
@@ -291,6 +322,9 @@ impl<'de> Deserialize<'de> for Opt {
                 }
 
                 match (self.pkg, self.var) {
+                    (Some(_), None) if self.secret.is_some() => Err(serde::de::Error::custom(
+                        "only var options can be marked as secret, package options are always recorded"
+                    )),
                     (Some(pkg), None) => Ok(Opt::Pkg(PkgOpt {
                         pkg: pkg.name,
                         components: pkg.components,
@@ -299,14 +333,29 @@ impl<'de> Deserialize<'de> for Opt {
                         default: self.default.unwrap_or_default(),
                         value: self.value,
                     })),
-                    (None, Some(var)) =>Ok(Opt::Var(VarOpt {
-                        var,
-                        choices: self.choices.unwrap_or_default(),
-                        inheritance: self.inheritance.unwrap_or_default(),
-                        default: self.default.unwrap_or_default(),
-                        description: self.description,
-                        value: self.value,
-                    })),
+                    (None, Some(var)) => {
+                        let value_type = self.value_type.unwrap_or_default();
+                        let parse_bound = |bound: Option<String>| {
+                            bound
+                                .map(|b| value_type.parse(b))
+                                .transpose()
+                                .map_err(serde::de::Error::custom)
+                        };
+                        let opt = VarOpt {
+                            var,
+                            choices: self.choices.unwrap_or_default(),
+                            inheritance: self.inheritance.unwrap_or_default(),
+                            default: self.default.unwrap_or_default(),
+                            value_type,
+                            min: parse_bound(self.min)?,
+                            max: parse_bound(self.max)?,
+                            description: self.description,
+                            secret: self.secret.unwrap_or_default(),
+                            value: self.value,
+                        };
+                        opt.validate_definition().map_err(serde::de::Error::custom)?;
+                        Ok(Opt::Var(opt))
+                    }
                     (Some(_), Some(_)) => Err(serde::de::Error::custom(
                         "could not determine option type, it may only contain one of the `pkg` or `var` fields"
                     )),
@@ -327,7 +376,17 @@ pub struct VarOpt {
     pub default: String,
     pub choices: IndexSet<String>,
     pub inheritance: Inheritance,
+    /// How values of this option are parsed and compared
+    pub value_type: OptionValueType,
+    /// The smallest allowed value, for int and version options
+    pub min: Option<OptionValue>,
+    /// The largest allowed value, for int and version options
+    pub max: Option<OptionValue>,
     pub description: Option<String>,
+    /// Values of secret options, such as license servers or tokens, are
+    /// given to the build but never published with the package, and do
+    /// not contribute to its build digest
+    pub secret: bool,
     value: Option<String>,
 }
 
@@ -343,7 +402,11 @@ impl std::hash::Hash for VarOpt {
             choice.hash(state);
         }
         self.inheritance.hash(state);
+        self.value_type.hash(state);
+        self.min.hash(state);
+        self.max.hash(state);
         self.description.hash(state);
+        self.secret.hash(state);
         self.value.hash(state)
     }
 }
@@ -366,7 +429,19 @@ impl Ord for VarOpt {
             std::cmp::Ordering::Equal => {}
             ord => return ord,
         }
+        match (&self.value_type, &self.min, &self.max).cmp(&(
+            &other.value_type,
+            &other.min,
+            &other.max,
+        )) {
+            std::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
         let _ = self.description.cmp(&other.value);
+        match self.secret.cmp(&other.secret) {
+            std::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
         self.value.cmp(&other.value)
     }
 }
@@ -384,7 +459,11 @@ impl VarOpt {
             default: String::default(),
             choices: IndexSet::default(),
             inheritance: Inheritance::default(),
+            value_type: OptionValueType::default(),
+            min: None,
+            max: None,
             description: None,
+            secret: false,
             value: None,
         })
     }
@@ -405,16 +484,101 @@ impl VarOpt {
     }
 
     pub fn set_value(&mut self, value: String) -> Result<()> {
-        if !self.choices.is_empty() && !value.is_empty() && !self.choices.contains(&value) {
-            return Err(Error::String(format!(
-                "Invalid value '{}' for option '{}', must be one of {:?}",
-                value, self.var, self.choices
-            )));
+        if !value.is_empty() {
+            if let Err(reason) = self.check_value(&value) {
+                return Err(Error::String(format!(
+                    "Invalid value '{}' for option '{}', {reason}",
+                    value, self.var
+                )));
+            }
         }
         self.value = Some(value);
         Ok(())
     }
 
+    /// Parse the given value as the type of this option, checking
+    /// that it is one of the choices and within the allowed range.
+    ///
+    /// On failure, the returned message describes why the value
+    /// is not allowed.
+    pub fn check_value(&self, value: &str) -> std::result::Result<OptionValue, String> {
+        let parsed = self.value_type.parse(value).map_err(|err| match err {
+            OptionMapError::InvalidOptionValue {
+                value_type, reason, ..
+            } => format!("not a valid {value_type}, {reason}"),
+            err => err.to_string(),
+        })?;
+        if !self.choices.is_empty()
+            && !self
+                .choices
+                .iter()
+                .any(|c| self.value_type.parse(c).ok().as_ref() == Some(&parsed))
+        {
+            return Err(format!("must be one of {:?}", self.choices));
+        }
+        if let Some(min) = self.min.as_ref().filter(|min| &parsed < *min) {
+            return Err(format!("must be at least {min}"));
+        }
+        if let Some(max) = self.max.as_ref().filter(|max| &parsed > *max) {
+            return Err(format!("must be at most {max}"));
+        }
+        Ok(parsed)
+    }
+
+    /// Check that the type, choices, range and default of this
+    /// option are consistent with each other.
+    pub fn validate_definition(&self) -> Result<()> {
+        let invalid = |reason: String| {
+            Error::String(format!(
+                "Invalid definition for option '{}': {reason}",
+                self.var
+            ))
+        };
+        if (self.min.is_some() || self.max.is_some())
+            && !matches!(
+                self.value_type,
+                OptionValueType::Int | OptionValueType::Version
+            )
+        {
+            return Err(invalid(format!(
+                "min and max can only be used with int or version options, not {}",
+                self.value_type
+            )));
+        }
+        for bound in [&self.min, &self.max].into_iter().flatten() {
+            if bound.value_type() != self.value_type {
+                return Err(invalid(format!(
+                    "range value '{bound}' is not a valid {}",
+                    self.value_type
+                )));
+            }
+        }
+        if self.secret && self.value.as_deref().is_some_and(|v| !v.is_empty()) {
+            return Err(invalid(
+                "secret options cannot have a static value, since it would be published"
+                    .to_string(),
+            ));
+        }
+        for choice in self.choices.iter() {
+            self.value_type
+                .parse(choice)
+                .map_err(|err| invalid(err.to_string()))?;
+        }
+        if self.value_type.is_default() {
+            // untyped options have never required that their default
+            // be one of the choices, and existing packages rely on it
+            return Ok(());
+        }
+        for value in [&self.default, self.value.as_deref().unwrap_or_default()] {
+            if !value.is_empty() {
+                self.check_value(value).map_err(|reason| {
+                    invalid(format!("value '{value}' is not allowed, {reason}"))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn validate(&self, value: Option<&str>) -> Compatibility {
         if value.is_none() && self.value.is_some() {
             return self.validate(self.value.as_deref());
@@ -423,7 +587,11 @@ impl VarOpt {
         match (value, assigned) {
             (None, Some(_)) => Compatibility::Compatible,
             (Some(value), Some(assigned)) => {
-                if value == assigned {
+                let same_value = |a: &str, b: &str| {
+                    let a = self.value_type.parse(a).ok();
+                    a.is_some() && a == self.value_type.parse(b).ok()
+                };
+                if value == assigned || same_value(value, assigned) {
                     Compatibility::Compatible
                 } else {
                     Compatibility::incompatible(format!(
@@ -432,13 +600,14 @@ impl VarOpt {
                 }
             }
             (Some(value), _) => {
-                if !self.choices.is_empty() && !self.choices.contains(value) {
-                    Compatibility::incompatible(format!(
-                        "invalid value '{}', must be one of {:?}",
-                        value, self.choices
-                    ))
-                } else {
-                    Compatibility::Compatible
+                if value.is_empty() && self.choices.is_empty() {
+                    return Compatibility::Compatible;
+                }
+                match self.check_value(value) {
+                    Ok(_) => Compatibility::Compatible,
+                    Err(reason) => {
+                        Compatibility::incompatible(format!("invalid value '{value}', {reason}"))
+                    }
                 }
             }
             (_, None) => Compatibility::Compatible,
@@ -462,8 +631,16 @@ struct VarOptSchema {
     choices: Vec<String>,
     #[serde(skip_serializing_if = "Inheritance::is_default")]
     inheritance: Inheritance,
+    #[serde(rename = "type", skip_serializing_if = "OptionValueType::is_default")]
+    value_type: OptionValueType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<String>,
     #[serde(skip_serializing_if = "String::is_empty")]
     description: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    secret: bool,
     #[serde(rename = "static", skip_serializing_if = "String::is_empty")]
     value: String,
 }
@@ -477,7 +654,11 @@ impl Serialize for VarOpt {
             var: self.var.to_string(),
             choices: self.choices.iter().map(String::to_owned).collect(),
             inheritance: self.inheritance,
+            value_type: self.value_type,
+            min: self.min.as_ref().map(ToString::to_string),
+            max: self.max.as_ref().map(ToString::to_string),
             description: self.description.clone().unwrap_or_default(),
+            secret: self.secret,
             value: self.value.clone().unwrap_or_default(),
         };
         if !self.default.is_empty() {
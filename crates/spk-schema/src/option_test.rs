@@ -30,6 +30,14 @@ fn test_pkg_opt_validation(#[case] spec: &str, #[case] value: &str, #[case] expe
 #[case("{var: my-var, choices: [hello, world]}", "hello", false)]
 #[case("{var: my-var, choices: [hello, world]}", "bad", true)]
 #[case("{var: my-var, choices: [hello, world]}", "", false)]
+#[case("{var: my-var, type: bool}", "on", false)]
+#[case("{var: my-var, type: bool}", "maybe", true)]
+#[case("{var: my-var, type: bool, choices: [on, off]}", "true", false)]
+#[case("{var: my-var, type: int, min: 1, max: 10}", "10", false)]
+#[case("{var: my-var, type: int, min: 1, max: 10}", "11", true)]
+#[case("{var: my-var, type: int}", "1.5", true)]
+#[case("{var: my-var, type: version, min: '3.7'}", "3.10", false)]
+#[case("{var: my-var, type: version, min: '3.7'}", "3.6.9", true)]
 fn test_var_opt_validation(#[case] spec: &str, #[case] value: &str, #[case] expect_err: bool) {
     let mut opt = Opt::from_yaml(spec).unwrap().into_var().unwrap();
     let res = opt.set_value(value.to_string());
@@ -49,6 +57,36 @@ fn test_var_opt_parse_value(#[case] spec: &str, #[case] expected: Option<&str>)
     assert_eq!(actual.as_deref(), expected);
 }
 
+#[rstest]
+#[case("{var: my-var, type: float}")]
+#[case("{var: my-var/maybe, type: bool}")]
+#[case("{var: my-var, type: int, choices: [one, two]}")]
+#[case("{var: my-var, type: int, min: low}")]
+#[case("{var: my-var/20, type: int, max: 10}")]
+#[case("{var: my-var, min: 1}")]
+fn test_var_opt_invalid_definition(#[case] spec: &str) {
+    Opt::from_yaml(spec).expect_err("option definition should be rejected");
+}
+
+#[rstest]
+fn test_var_opt_typed_compatibility() {
+    let mut opt = Opt::from_yaml("{var: debug, type: bool}")
+        .unwrap()
+        .into_var()
+        .unwrap();
+    opt.set_value("on".to_string()).unwrap();
+    assert!(opt.validate(Some("true")).is_ok());
+    assert!(!opt.validate(Some("off")).is_ok());
+}
+
+#[rstest]
+fn test_var_opt_typed_round_trip() {
+    let opt = Opt::from_yaml("{var: jobs/4, type: int, min: 1, max: 64}").unwrap();
+    let yaml = serde_yaml::to_string(&opt).unwrap();
+    let parsed = Opt::from_yaml(&yaml).unwrap();
+    assert_eq!(parsed, opt);
+}
+
 /// Confirm that the error provided when both 'var' or 'pkg' field
 /// exist is meaningful and positioned reasonably
 #[rstest]
@@ -90,3 +128,29 @@ fn test_yaml_error_undetermined() {
     let message = err.to_string();
     assert_eq!(message, expected);
 }
+
+#[rstest]
+fn test_secret_option_round_trip() {
+    let opt = Opt::from_yaml("{var: token, secret: true}").unwrap();
+    assert!(opt.is_secret());
+    let yaml = serde_yaml::to_string(&opt).unwrap();
+    assert!(
+        yaml.contains("secret: true"),
+        "secret should be kept, got:\n{yaml}"
+    );
+
+    let opt = Opt::from_yaml("{var: token}").unwrap();
+    assert!(!opt.is_secret());
+    let yaml = serde_yaml::to_string(&opt).unwrap();
+    assert!(
+        !yaml.contains("secret"),
+        "secret should be omitted, got:\n{yaml}"
+    );
+}
+
+#[rstest]
+#[case::pkg("{pkg: my-pkg, secret: true}")]
+#[case::static_value("{var: token, secret: true, static: abc}")]
+fn test_secret_option_invalid(#[case] yaml: &str) {
+    Opt::from_yaml(yaml).expect_err("secret option should be rejected");
+}
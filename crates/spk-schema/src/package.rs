@@ -9,7 +9,7 @@ use spk_schema_foundation::ident_build::Build;
 use spk_schema_foundation::option_map::OptFilter;
 use spk_schema_foundation::spec_ops::{Named, Versioned};
 use spk_schema_foundation::version::VERSION_SEP;
-use spk_schema_ident::BuildIdent;
+use spk_schema_ident::{BuildIdent, ConflictRequest, VersionIdent};
 
 use super::RequirementsList;
 use crate::foundation::ident_component::Component;
@@ -24,7 +24,15 @@ mod package_test;
 /// Can be resolved into an environment.
 #[enum_dispatch::enum_dispatch]
 pub trait Package:
-    Named + Versioned + super::Deprecate + Clone + Eq + std::hash::Hash + Sync + Send
+    Named
+    + Versioned
+    + super::Deprecate
+    + super::PlatformSupport
+    + Clone
+    + Eq
+    + std::hash::Hash
+    + Sync
+    + Send
 {
     type Package;
 
@@ -54,17 +62,28 @@ pub trait Package:
     /// Return both top-level embedded packages and packages that are
     /// embedded inside a component. The returned list is a pair of the
     /// embedded package and the component it came from, if any.
+    /// Stubs for any provided packages are also included, with no
+    /// component.
     #[allow(clippy::type_complexity)]
     fn embedded_as_packages(
         &self,
     ) -> std::result::Result<Vec<(Self::Package, Option<Component>)>, &str>;
 
+    /// The other packages that this one can stand in for
+    fn provides(&self) -> &[VersionIdent];
+
     /// The components defined by this package
     fn components(&self) -> &super::ComponentSpecList;
 
     /// The set of operations to perform on the environment when running this package
     fn runtime_environment(&self) -> &Vec<super::EnvOp>;
 
+    /// The packages that cannot be used in the same environment as this one
+    fn runtime_conflicts(&self) -> &Vec<ConflictRequest>;
+
+    /// The scripts to run each time this package is added to an environment
+    fn install_hooks(&self) -> &[super::InstallHook];
+
     /// The list of build options for this package
     fn get_build_options(&self) -> &Vec<Opt>;
 
@@ -134,6 +153,20 @@ pub trait Package:
     /// Return the build script for building package
     fn build_script(&self) -> String;
 
+    /// Return the sandbox that the build script should be run in, if any
+    fn build_sandbox(&self) -> Option<&super::SandboxSpec>;
+
+    /// Return which host environment variables reach the build script
+    fn build_environment(&self) -> &super::BuildEnvironmentSpec;
+
+    /// Return how the build output should be checked for
+    /// references to the build prefix, if at all
+    fn build_relocate(&self) -> Option<&super::RelocateSpec>;
+
+    /// Return which of the files produced by the build
+    /// should be collected into the package, if not all
+    fn build_capture(&self) -> Option<&super::CaptureSpec>;
+
     /// Validate the given options against the options in this spec.
     fn validate_options(&self, given_options: &OptionMap) -> Compatibility;
 }
@@ -176,6 +209,10 @@ impl<T: Package + Send + Sync> Package for std::sync::Arc<T> {
         (**self).embedded_as_packages()
     }
 
+    fn provides(&self) -> &[VersionIdent] {
+        (**self).provides()
+    }
+
     fn components(&self) -> &super::ComponentSpecList {
         (**self).components()
     }
@@ -184,6 +221,14 @@ impl<T: Package + Send + Sync> Package for std::sync::Arc<T> {
         (**self).runtime_environment()
     }
 
+    fn runtime_conflicts(&self) -> &Vec<ConflictRequest> {
+        (**self).runtime_conflicts()
+    }
+
+    fn install_hooks(&self) -> &[super::InstallHook] {
+        (**self).install_hooks()
+    }
+
     fn get_build_options(&self) -> &Vec<Opt> {
         (**self).get_build_options()
     }
@@ -218,6 +263,22 @@ impl<T: Package + Send + Sync> Package for std::sync::Arc<T> {
         (**self).build_script()
     }
 
+    fn build_sandbox(&self) -> Option<&super::SandboxSpec> {
+        (**self).build_sandbox()
+    }
+
+    fn build_environment(&self) -> &super::BuildEnvironmentSpec {
+        (**self).build_environment()
+    }
+
+    fn build_relocate(&self) -> Option<&super::RelocateSpec> {
+        (**self).build_relocate()
+    }
+
+    fn build_capture(&self) -> Option<&super::CaptureSpec> {
+        (**self).build_capture()
+    }
+
     fn validate_options(&self, given_options: &OptionMap) -> Compatibility {
         (**self).validate_options(given_options)
     }
@@ -256,6 +317,10 @@ impl<T: Package + Send + Sync> Package for Box<T> {
         (**self).embedded_as_packages()
     }
 
+    fn provides(&self) -> &[VersionIdent] {
+        (**self).provides()
+    }
+
     fn components(&self) -> &super::ComponentSpecList {
         (**self).components()
     }
@@ -264,6 +329,14 @@ impl<T: Package + Send + Sync> Package for Box<T> {
         (**self).runtime_environment()
     }
 
+    fn runtime_conflicts(&self) -> &Vec<ConflictRequest> {
+        (**self).runtime_conflicts()
+    }
+
+    fn install_hooks(&self) -> &[super::InstallHook] {
+        (**self).install_hooks()
+    }
+
     fn get_build_options(&self) -> &Vec<Opt> {
         (**self).get_build_options()
     }
@@ -298,6 +371,22 @@ impl<T: Package + Send + Sync> Package for Box<T> {
         (**self).build_script()
     }
 
+    fn build_sandbox(&self) -> Option<&super::SandboxSpec> {
+        (**self).build_sandbox()
+    }
+
+    fn build_environment(&self) -> &super::BuildEnvironmentSpec {
+        (**self).build_environment()
+    }
+
+    fn build_relocate(&self) -> Option<&super::RelocateSpec> {
+        (**self).build_relocate()
+    }
+
+    fn build_capture(&self) -> Option<&super::CaptureSpec> {
+        (**self).build_capture()
+    }
+
     fn validate_options(&self, given_options: &OptionMap) -> Compatibility {
         (**self).validate_options(given_options)
     }
@@ -336,6 +425,10 @@ impl<T: Package + Send + Sync> Package for &T {
         (**self).embedded_as_packages()
     }
 
+    fn provides(&self) -> &[VersionIdent] {
+        (**self).provides()
+    }
+
     fn components(&self) -> &super::ComponentSpecList {
         (**self).components()
     }
@@ -344,6 +437,14 @@ impl<T: Package + Send + Sync> Package for &T {
         (**self).runtime_environment()
     }
 
+    fn runtime_conflicts(&self) -> &Vec<ConflictRequest> {
+        (**self).runtime_conflicts()
+    }
+
+    fn install_hooks(&self) -> &[super::InstallHook] {
+        (**self).install_hooks()
+    }
+
     fn get_build_options(&self) -> &Vec<Opt> {
         (**self).get_build_options()
     }
@@ -378,6 +479,22 @@ impl<T: Package + Send + Sync> Package for &T {
         (**self).build_script()
     }
 
+    fn build_sandbox(&self) -> Option<&super::SandboxSpec> {
+        (**self).build_sandbox()
+    }
+
+    fn build_environment(&self) -> &super::BuildEnvironmentSpec {
+        (**self).build_environment()
+    }
+
+    fn build_relocate(&self) -> Option<&super::RelocateSpec> {
+        (**self).build_relocate()
+    }
+
+    fn build_capture(&self) -> Option<&super::CaptureSpec> {
+        (**self).build_capture()
+    }
+
     fn validate_options(&self, given_options: &OptionMap) -> Compatibility {
         (**self).validate_options(given_options)
     }
@@ -0,0 +1,186 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::foundation::name::OptName;
+use crate::foundation::option_map::OptionMap;
+use crate::foundation::version::Compatibility;
+
+#[cfg(test)]
+#[path = "./platform_support_test.rs"]
+mod platform_support_test;
+
+/// Supports every operating system and architecture
+static ANY_PLATFORM: SupportedPlatforms = SupportedPlatforms::any();
+
+/// Can be limited to specific operating systems and architectures
+#[enum_dispatch::enum_dispatch]
+pub trait PlatformSupport {
+    /// The operating systems and architectures that this supports
+    fn supported_platforms(&self) -> &SupportedPlatforms {
+        &ANY_PLATFORM
+    }
+}
+
+impl<T> PlatformSupport for std::sync::Arc<T>
+where
+    T: PlatformSupport,
+{
+    fn supported_platforms(&self) -> &SupportedPlatforms {
+        (**self).supported_platforms()
+    }
+}
+
+impl<T> PlatformSupport for Box<T>
+where
+    T: PlatformSupport,
+{
+    fn supported_platforms(&self) -> &SupportedPlatforms {
+        (**self).supported_platforms()
+    }
+}
+
+impl<T> PlatformSupport for &T
+where
+    T: PlatformSupport,
+{
+    fn supported_platforms(&self) -> &SupportedPlatforms {
+        (**self).supported_platforms()
+    }
+}
+
+/// The operating systems and architectures that a package can be
+/// built and used on, as given by the `platforms` and `arch` fields
+/// of a recipe.
+///
+/// Each one is compared to the `os` and `arch` options, which
+/// default to those of the current host.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SupportedPlatforms {
+    /// The supported operating systems, each with the
+    /// architectures supported on it. Any if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<SupportedPlatform>,
+    /// The supported architectures, on any operating system. Any if empty.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "one_or_many"
+    )]
+    pub arch: Vec<String>,
+}
+
+/// A supported operating system, eg: `{os: linux, arch: [x86_64, aarch64]}`
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SupportedPlatform {
+    pub os: String,
+    /// The architectures supported on this operating system. Any if empty.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "one_or_many"
+    )]
+    pub arch: Vec<String>,
+}
+
+impl SupportedPlatforms {
+    /// Supports every operating system and architecture
+    pub const fn any() -> Self {
+        Self {
+            platforms: Vec::new(),
+            arch: Vec::new(),
+        }
+    }
+
+    /// True if every operating system and architecture is supported
+    pub fn is_default(&self) -> bool {
+        self.platforms.is_empty() && self.arch.is_empty()
+    }
+
+    /// True if the operating system and architecture are supported.
+    ///
+    /// A value that is not known is assumed to be supported.
+    pub fn supports(&self, os: Option<&str>, arch: Option<&str>) -> bool {
+        let arch_in = |list: &[String]| match arch {
+            Some(arch) => list.is_empty() || list.iter().any(|a| a == arch),
+            None => true,
+        };
+        if !arch_in(&self.arch) {
+            return false;
+        }
+        let Some(os) = os else {
+            return true;
+        };
+        self.platforms.is_empty()
+            || self
+                .platforms
+                .iter()
+                .any(|platform| platform.os == os && arch_in(&platform.arch))
+    }
+
+    /// Check the `os` and `arch` options against the supported platforms.
+    pub fn check(&self, options: &OptionMap) -> Compatibility {
+        let os = options.get(OptName::os()).map(String::as_str);
+        let arch = options.get(OptName::arch()).map(String::as_str);
+        if self.supports(os, arch) {
+            return Compatibility::Compatible;
+        }
+        Compatibility::incompatible(format!(
+            "{}/{} is not a supported platform, only {self}",
+            os.unwrap_or("*"),
+            arch.unwrap_or("*"),
+        ))
+    }
+}
+
+impl std::fmt::Display for SupportedPlatforms {
+    /// Format the supported platforms, eg: `linux/x86_64, darwin/*`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let any = ["*".to_string()];
+        let arch = if self.arch.is_empty() {
+            &any[..]
+        } else {
+            &self.arch[..]
+        };
+        if self.platforms.is_empty() {
+            return write!(f, "{}", arch.iter().map(|a| format!("*/{a}")).join(", "));
+        }
+        let supported = self.platforms.iter().flat_map(|platform| {
+            let arch = if platform.arch.is_empty() {
+                arch
+            } else {
+                &platform.arch[..]
+            };
+            arch.iter().map(|a| format!("{}/{a}", platform.os))
+        });
+        write!(f, "{}", supported.format(", "))
+    }
+}
+
+/// Either a single string or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub(crate) fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(value) => vec![value],
+            Self::Many(values) => values,
+        }
+    }
+}
+
+/// Deserialize either a single string or a list of them
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    OneOrMany::deserialize(deserializer).map(OneOrMany::into_vec)
+}
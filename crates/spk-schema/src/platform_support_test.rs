@@ -0,0 +1,98 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema_foundation::option_map;
+use spk_schema_foundation::option_map::OptionMap;
+
+use super::{PlatformSupport, SupportedPlatform, SupportedPlatforms};
+use crate::recipe;
+
+fn platform(os: &str, arch: &[&str]) -> SupportedPlatform {
+    SupportedPlatform {
+        os: os.to_string(),
+        arch: arch.iter().map(|a| a.to_string()).collect(),
+    }
+}
+
+fn platforms(platforms: Vec<SupportedPlatform>, arch: &[&str]) -> SupportedPlatforms {
+    SupportedPlatforms {
+        platforms,
+        arch: arch.iter().map(|a| a.to_string()).collect(),
+    }
+}
+
+#[rstest]
+#[case::any(SupportedPlatforms::any(), Some("linux"), Some("x86_64"), true)]
+#[case::os(platforms(vec![platform("linux", &[])], &[]), Some("linux"), Some("x86_64"), true)]
+#[case::other_os(platforms(vec![platform("linux", &[])], &[]), Some("windows"), None, false)]
+#[case::arch(platforms(vec![], &["aarch64"]), Some("darwin"), Some("aarch64"), true)]
+#[case::other_arch(platforms(vec![], &["aarch64"]), Some("linux"), Some("x86_64"), false)]
+#[case::os_arch(
+    platforms(vec![platform("linux", &["x86_64"]), platform("darwin", &["aarch64"])], &[]),
+    Some("darwin"),
+    Some("aarch64"),
+    true
+)]
+#[case::os_other_arch(
+    platforms(vec![platform("linux", &["x86_64"]), platform("darwin", &["aarch64"])], &[]),
+    Some("linux"),
+    Some("aarch64"),
+    false
+)]
+#[case::unknown(platforms(vec![platform("linux", &["x86_64"])], &[]), None, None, true)]
+fn test_supports(
+    #[case] supported: SupportedPlatforms,
+    #[case] os: Option<&str>,
+    #[case] arch: Option<&str>,
+    #[case] expected: bool,
+) {
+    assert_eq!(supported.supports(os, arch), expected);
+}
+
+#[rstest]
+#[case::any(SupportedPlatforms::any(), "*/*")]
+#[case::arch(platforms(vec![], &["x86_64", "aarch64"]), "*/x86_64, */aarch64")]
+#[case::os(
+    platforms(vec![platform("linux", &[]), platform("darwin", &["aarch64"])], &["x86_64"]),
+    "linux/x86_64, darwin/aarch64"
+)]
+fn test_display(#[case] supported: SupportedPlatforms, #[case] expected: &str) {
+    assert_eq!(supported.to_string(), expected);
+}
+
+#[rstest]
+fn test_recipe_platforms() {
+    let recipe = recipe!({
+        "pkg": "my-pkg/1.0.0",
+        "platforms": [{"os": "linux", "arch": "x86_64"}, {"os": "darwin"}],
+        "arch": ["x86_64", "aarch64"],
+    });
+    let expected = platforms(
+        vec![platform("linux", &["x86_64"]), platform("darwin", &[])],
+        &["x86_64", "aarch64"],
+    );
+    assert_eq!(recipe.supported_platforms(), &expected);
+
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    assert!(
+        recipe.supported_platforms().is_default(),
+        "recipes should support any platform by default"
+    );
+}
+
+#[rstest]
+fn test_check_options() {
+    let supported = platforms(vec![platform("linux", &["x86_64"])], &[]);
+    assert!(supported
+        .check(&option_map! {"os" => "linux", "arch" => "x86_64"})
+        .is_ok());
+    assert!(supported.check(&OptionMap::default()).is_ok());
+
+    let compat = supported.check(&option_map! {"os" => "windows", "arch" => "x86_64"});
+    assert_eq!(
+        compat.to_string(),
+        "windows/x86_64 is not a supported platform, only linux/x86_64"
+    );
+}
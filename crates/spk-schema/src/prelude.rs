@@ -9,6 +9,7 @@ pub use super::{
     DeprecateMut,
     FromYaml,
     Package,
+    PlatformSupport,
     Recipe,
     Template,
     Test,
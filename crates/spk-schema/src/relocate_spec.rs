@@ -0,0 +1,76 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+#[path = "./relocate_spec_test.rs"]
+mod relocate_spec_test;
+
+/// Checks the files produced by a build for hard-coded
+/// references to the build prefix (eg: `/spfs`).
+///
+/// Packages that refer to the build prefix by absolute path cannot
+/// be installed under any other prefix. This section is only checked
+/// when present in the recipe, and can either report the offending
+/// files or rewrite them to be relative to their own location.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct RelocateSpec {
+    /// What to do with any references to the build prefix that are found
+    #[serde(default)]
+    pub mode: RelocateMode,
+    /// The kinds of files that are checked, defaults to all of them
+    #[serde(
+        default = "RelocateFileKind::all",
+        skip_serializing_if = "is_all_kinds"
+    )]
+    pub files: Vec<RelocateFileKind>,
+}
+
+impl RelocateSpec {
+    /// True if the given kind of file should be checked.
+    pub fn includes(&self, kind: RelocateFileKind) -> bool {
+        self.files.contains(&kind)
+    }
+}
+
+fn is_all_kinds(files: &[RelocateFileKind]) -> bool {
+    RelocateFileKind::all()
+        .into_iter()
+        .all(|kind| files.contains(&kind))
+}
+
+/// What is done with references to the build prefix.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum RelocateMode {
+    /// Log a warning for each reference that is found
+    #[default]
+    Report,
+    /// Fail the build if any reference is found
+    Error,
+    /// Rewrite references to be relative where possible, and
+    /// log a warning for any that cannot be rewritten
+    Rewrite,
+}
+
+/// The kinds of files that can contain references to the build prefix.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, strum::Display,
+)]
+pub enum RelocateFileKind {
+    /// The RPATH or RUNPATH of ELF executables and libraries
+    Elf,
+    /// The interpreter named by a script's shebang line
+    Script,
+    /// Paths within pkg-config (.pc) files
+    PkgConfig,
+}
+
+impl RelocateFileKind {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Elf, Self::Script, Self::PkgConfig]
+    }
+}
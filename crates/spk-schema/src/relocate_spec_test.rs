@@ -0,0 +1,35 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{RelocateFileKind, RelocateMode, RelocateSpec};
+
+#[rstest]
+fn test_relocate_defaults_to_report_all() {
+    let relocate: RelocateSpec = serde_yaml::from_str("{}").unwrap();
+    assert_eq!(relocate.mode, RelocateMode::Report);
+    for kind in RelocateFileKind::all() {
+        assert!(
+            relocate.includes(kind),
+            "should check {kind} files by default"
+        );
+    }
+    let yaml = serde_yaml::to_string(&relocate).unwrap();
+    assert!(
+        !yaml.contains("files"),
+        "default file kinds should not be serialized"
+    );
+}
+
+#[rstest]
+fn test_relocate_round_trip() {
+    let relocate: RelocateSpec =
+        serde_yaml::from_str("{mode: Rewrite, files: [Elf, PkgConfig]}").unwrap();
+    assert_eq!(relocate.mode, RelocateMode::Rewrite);
+    assert!(!relocate.includes(RelocateFileKind::Script));
+    let yaml = serde_yaml::to_string(&relocate).unwrap();
+    let relocate2: RelocateSpec = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(relocate2, relocate);
+}
@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt::Write;
 
 use serde::{Deserialize, Serialize};
-use spk_schema_foundation::name::{OptName, PkgName};
+use spk_schema_foundation::name::{OptName, OptNameBuf, PkgName};
 use spk_schema_foundation::version::Compatibility;
-use spk_schema_ident::{BuildIdent, PinPolicy};
+use spk_schema_ident::{AnyOfRequest, BuildIdent, PinPolicy};
 
 use crate::foundation::option_map::OptionMap;
 use crate::ident::Request;
@@ -22,7 +22,13 @@ mod requirements_list_test;
 ///
 /// Requirements lists cannot contain multiple requests with the
 /// same name, requiring instead that they be combined into a single
-/// request as needed.
+/// request as needed. Requests for any one of several packages are
+/// identified by the names of all of their alternatives instead, and
+/// so never collide with a request for just one of those packages.
+///
+/// The order of the requirements is preserved, and is used as a hint
+/// for the order that the solver decides on them in, see
+/// [`RequirementsList::iter_by_priority`].
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 #[serde(transparent)]
 pub struct RequirementsList(Vec<Request>);
@@ -41,9 +47,9 @@ impl RequirementsList {
     /// given one. Otherwise the new request is appended to the list.
     /// Returns the replaced request, if any.
     pub fn insert_or_replace(&mut self, request: Request) -> Option<Request> {
-        let name = request.name();
+        let key = RequestKey::of(&request);
         for existing in self.0.iter_mut() {
-            if existing.name() == name {
+            if RequestKey::of(existing) == key {
                 return Some(std::mem::replace(existing, request));
             }
         }
@@ -57,15 +63,16 @@ impl RequirementsList {
     /// restrictions of this one. Otherwise the new request is
     /// appended to the list. Returns the newly inserted or updated request.
     pub fn insert_or_merge(&mut self, request: Request) -> Result<()> {
-        let name = request.name();
+        let key = RequestKey::of(&request);
         for existing in self.0.iter_mut() {
-            if existing.name() != name {
+            if RequestKey::of(existing) != key {
                 continue;
             }
             match (existing, &request) {
                 (Request::Pkg(existing), Request::Pkg(request)) => {
                     existing.restrict(request)?;
                 }
+                (Request::AnyOf(existing), Request::AnyOf(request)) if existing == request => {}
                 (existing, _) => {
                     return Err(Error::String(format!("Cannot insert requirement: one already exists and only pkg requests can be merged: {existing} + {request}")))
                 }
@@ -76,6 +83,20 @@ impl RequirementsList {
         Ok(())
     }
 
+    /// Iterate the requirements in the order that they should be decided on.
+    ///
+    /// Package requests with a higher `priority` come first, and the
+    /// requirements with the same priority are kept in the order that
+    /// they were declared. Other requests have the default priority.
+    pub fn iter_by_priority(&self) -> impl Iterator<Item = &Request> {
+        let mut ordered = self.0.iter().collect::<Vec<_>>();
+        ordered.sort_by_key(|request| match request {
+            Request::Pkg(request) => std::cmp::Reverse(request.priority),
+            Request::Var(_) | Request::AnyOf(_) => std::cmp::Reverse(0),
+        });
+        ordered.into_iter()
+    }
+
     /// Reports whether the provided requests would be satisfied by
     /// this list of requests. The provided request does not need to
     /// exist in this list exactly, so long as there is a request in this
@@ -87,6 +108,11 @@ impl RequirementsList {
                 (Request::Pkg(ours), Request::Pkg(theirs)) if ours.pkg.name == theirs.pkg.name => {
                     return ours.contains(theirs);
                 }
+                (Request::AnyOf(ours), Request::AnyOf(theirs))
+                    if ours.preferred().pkg.name == theirs.preferred().pkg.name =>
+                {
+                    return ours.contains(theirs);
+                }
                 // a var request satisfy another if they have the same opt name or
                 // if our request is package-less and has the same base name, eg:
                 // name/value     [contains] name/value
@@ -119,12 +145,33 @@ impl RequirementsList {
     /// Remove a requirement from this list.
     ///
     /// All requests with the same name as the given name are removed
-    /// from the list.
+    /// from the list. Requests for any one of several packages only
+    /// lose the alternative with that name, and are removed once no
+    /// alternatives remain.
     pub fn remove_all<N>(&mut self, name: &N)
     where
         N: AsRef<OptName> + ?Sized,
     {
-        self.0.retain(|existing| existing.name() != name.as_ref());
+        let name = name.as_ref();
+        self.0 = std::mem::take(&mut self.0)
+            .into_iter()
+            .filter_map(|existing| match existing {
+                Request::AnyOf(mut any_of) => {
+                    let count = any_of.alternatives.len();
+                    any_of
+                        .alternatives
+                        .retain(|alternative| alternative.pkg.name.as_opt_name() != name);
+                    match any_of.alternatives.len() {
+                        0 => None,
+                        n if n == count => Some(Request::AnyOf(any_of)),
+                        1 => any_of.alternatives.pop().map(Request::Pkg),
+                        _ => Some(Request::AnyOf(any_of)),
+                    }
+                }
+                existing if existing.name() == name => None,
+                existing => Some(existing),
+            })
+            .collect();
     }
 
     /// Render all requests with a package pin using the given resolved packages.
@@ -158,6 +205,24 @@ impl RequirementsList {
                         }
                     }
                 }
+                Request::AnyOf(any_of) => {
+                    // alternatives that are only wanted if they were in the
+                    // build environment are dropped when they were not
+                    let alternatives = any_of
+                        .alternatives
+                        .iter()
+                        .filter(|alternative| {
+                            alternative.pin_policy != PinPolicy::IfPresentInBuildEnv
+                                || resolved_by_name.contains_key(alternative.pkg.name())
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    match alternatives.len() {
+                        0 => None,
+                        n if n == any_of.alternatives.len() => Some(Ok(request)),
+                        _ => Some(Ok(Request::AnyOf(AnyOfRequest::new(alternatives)))),
+                    }
+                }
                 Request::Var(var_request) => {
                     if !var_request.value.is_from_build_env() {
                         return Some(Ok(request));
@@ -251,12 +316,12 @@ impl<'de> Deserialize<'de> for RequirementsList {
             {
                 let size_hint = seq.size_hint().unwrap_or(0);
                 let mut requirements = Vec::with_capacity(size_hint);
-                let mut requirement_names = HashSet::with_capacity(size_hint);
+                let mut requirement_keys = HashSet::with_capacity(size_hint);
                 while let Some(request) = seq.next_element::<Request>()? {
-                    let name = request.name();
-                    if !requirement_names.insert(name.to_owned()) {
+                    if !requirement_keys.insert(RequestKey::of(&request)) {
                         return Err(serde::de::Error::custom(format!(
-                            "found multiple install requirements for '{name}'"
+                            "found multiple install requirements for '{}'",
+                            request.name()
                         )));
                     }
                     requirements.push(request);
@@ -268,3 +333,26 @@ impl<'de> Deserialize<'de> for RequirementsList {
         deserializer.deserialize_seq(RequirementsListVisitor)
     }
 }
+
+/// Identifies the requests in a [`RequirementsList`] that
+/// refer to the same thing.
+#[derive(Debug, Eq, Hash, PartialEq)]
+enum RequestKey {
+    Name(OptNameBuf),
+    AnyOf(BTreeSet<OptNameBuf>),
+}
+
+impl RequestKey {
+    fn of(request: &Request) -> Self {
+        match request {
+            Request::AnyOf(any_of) => Self::AnyOf(
+                any_of
+                    .alternatives
+                    .iter()
+                    .map(|alternative| alternative.pkg.name.as_opt_name().to_owned())
+                    .collect(),
+            ),
+            request => Self::Name(request.name().to_owned()),
+        }
+    }
+}
@@ -5,6 +5,7 @@
 use rstest::rstest;
 use serde_json::json;
 use spk_schema_foundation::fixtures::*;
+use spk_schema_foundation::opt_name;
 use spk_schema_foundation::version::Compatibility;
 use spk_schema_ident::Request;
 
@@ -67,3 +68,82 @@ fn test_contains_request(#[case] requests: serde_json::Value, #[case] contains:
     tracing::debug!("is {contains} contained within this? {reqs}");
     assert_eq!(reqs.contains_request(&contains), Compatibility::Compatible);
 }
+
+#[rstest]
+fn test_any_of_is_not_merged_with_pkg_request() {
+    let mut reqs: RequirementsList =
+        serde_yaml::from_str("[{pkg: {any: [ffmpeg/4, libav/12]}}, {pkg: ffmpeg}]")
+            .expect("an any-of request should not collide with one of its alternatives");
+
+    let ffmpeg: Request = serde_yaml::from_str("{pkg: ffmpeg/4.2}").unwrap();
+    reqs.insert_or_merge(ffmpeg).unwrap();
+    let any_of: Request = serde_yaml::from_str("{pkg: {any: [ffmpeg/5, libav/13]}}").unwrap();
+    let replaced = reqs.insert_or_replace(any_of.clone());
+
+    assert!(matches!(replaced, Some(Request::AnyOf(_))));
+    assert_eq!(reqs.len(), 2);
+    assert_eq!(reqs[0], any_of);
+    assert!(
+        matches!(&reqs[1], Request::Pkg(pkg) if pkg.pkg.name.as_str() == "ffmpeg"),
+        "the pkg request should be merged on its own: {reqs}"
+    );
+}
+
+#[rstest]
+fn test_remove_all_keeps_other_alternatives() {
+    let mut reqs: RequirementsList =
+        serde_yaml::from_str("[{pkg: {any: [ffmpeg/4, libav/12, gstreamer]}}, {pkg: ffmpeg}]")
+            .unwrap();
+
+    reqs.remove_all(opt_name!("ffmpeg"));
+    assert_eq!(
+        reqs.len(),
+        1,
+        "only the pkg request should be removed: {reqs}"
+    );
+    let any_of = reqs[0]
+        .clone()
+        .into_any_of()
+        .expect("expected an any-of request");
+    assert_eq!(
+        any_of
+            .alternatives
+            .iter()
+            .map(|a| a.pkg.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["libav", "gstreamer"]
+    );
+
+    reqs.remove_all(opt_name!("libav"));
+    assert!(
+        matches!(&reqs[0], Request::Pkg(pkg) if pkg.pkg.name.as_str() == "gstreamer"),
+        "a single remaining alternative should become a plain request: {reqs}"
+    );
+}
+
+#[rstest]
+fn test_iter_by_priority() {
+    let reqs: RequirementsList = serde_yaml::from_str(
+        "[{pkg: first}, {var: debug/off}, {pkg: heavy, priority: 10}, {pkg: second}, {pkg: light, priority: -1}]",
+    )
+    .unwrap();
+
+    let names = reqs
+        .iter_by_priority()
+        .map(|req| match req {
+            Request::Pkg(req) => req.pkg.name.to_string(),
+            Request::Var(req) => req.var.to_string(),
+            Request::AnyOf(_) => panic!("no any-of requests were given"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["heavy", "first", "debug", "second", "light"],
+        "higher priorities should come first, then in declared order"
+    );
+    assert_eq!(
+        reqs[2].name().as_str(),
+        "heavy",
+        "the declared order is kept in the list itself"
+    );
+}
@@ -0,0 +1,148 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./sandbox_spec_test.rs"]
+mod sandbox_spec_test;
+
+/// Restrictions placed on the environment that a build script runs in.
+///
+/// Builds are only sandboxed when this section is present in the
+/// recipe, at which point network access is disabled unless it
+/// is explicitly allowed.
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SandboxSpec {
+    /// Allow the build script to access the network
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub network: bool,
+    /// The maximum number of cpus that the build can make use of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<NonZeroU32>,
+    /// The maximum amount of memory that the build can make use of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryLimit>,
+    /// Paths on the host that cannot be modified by the build
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_only_paths: Vec<PathBuf>,
+}
+
+impl SandboxSpec {
+    /// True if this sandbox places limits on the resources
+    /// that the build can consume.
+    pub fn has_resource_limits(&self) -> bool {
+        self.cpus.is_some() || self.memory.is_some()
+    }
+}
+
+/// An amount of memory, in bytes.
+///
+/// Can be specified as a number of bytes or with one of the
+/// K, M, G or T binary suffixes, eg: `512M` or `8G`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MemoryLimit(u64);
+
+impl MemoryLimit {
+    const SUFFIXES: &'static [(char, u64)] = &[
+        ('T', 1 << 40),
+        ('G', 1 << 30),
+        ('M', 1 << 20),
+        ('K', 1 << 10),
+    ];
+
+    pub fn from_bytes(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for MemoryLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (suffix, size) in Self::SUFFIXES {
+            if self.0 != 0 && self.0 % size == 0 {
+                return write!(f, "{}{suffix}", self.0 / size);
+            }
+        }
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for MemoryLimit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (number, multiplier) = match s.chars().last() {
+            Some(last) if last.is_ascii_alphabetic() => {
+                let suffix = last.to_ascii_uppercase();
+                let Some((_, size)) = Self::SUFFIXES.iter().find(|(c, _)| *c == suffix) else {
+                    return Err(Error::String(format!(
+                        "Invalid memory limit '{s}', expected a suffix of K, M, G or T"
+                    )));
+                };
+                (&s[..s.len() - 1], *size)
+            }
+            _ => (s, 1),
+        };
+        number
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .map(Self)
+            .ok_or_else(|| Error::String(format!("Invalid memory limit '{s}'")))
+    }
+}
+
+impl Serialize for MemoryLimit {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MemoryLimit {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct MemoryLimitVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for MemoryLimitVisitor {
+            type Value = MemoryLimit;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a number of bytes or a size such as 512M or 8G")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(MemoryLimit(v))
+            }
+
+            fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                MemoryLimit::from_str(v).map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(MemoryLimitVisitor)
+    }
+}
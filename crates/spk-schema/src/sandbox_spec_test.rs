@@ -0,0 +1,50 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::str::FromStr;
+
+use rstest::rstest;
+
+use super::{MemoryLimit, SandboxSpec};
+
+#[rstest]
+#[case("1024", 1024, "1K")]
+#[case("512M", 512 << 20, "512M")]
+#[case("8g", 8 << 30, "8G")]
+#[case("1536K", 1536 << 10, "1536K")]
+#[case("1000", 1000, "1000")]
+fn test_memory_limit_parsing(#[case] input: &str, #[case] bytes: u64, #[case] display: &str) {
+    let limit = MemoryLimit::from_str(input).unwrap();
+    assert_eq!(limit.bytes(), bytes);
+    assert_eq!(limit.to_string(), display);
+}
+
+#[rstest]
+#[case("")]
+#[case("8X")]
+#[case("lots")]
+#[case("99999999999T")]
+fn test_memory_limit_invalid(#[case] input: &str) {
+    assert!(MemoryLimit::from_str(input).is_err());
+}
+
+#[rstest]
+fn test_sandbox_defaults_to_no_network() {
+    let sandbox: SandboxSpec = serde_yaml::from_str("{}").unwrap();
+    assert!(!sandbox.network);
+    assert!(!sandbox.has_resource_limits());
+}
+
+#[rstest]
+fn test_sandbox_round_trip() {
+    let sandbox: SandboxSpec = serde_yaml::from_str(
+        "{network: true, cpus: 4, memory: 8G, read_only_paths: [/mnt/shared]}",
+    )
+    .unwrap();
+    assert_eq!(sandbox.cpus.map(|c| c.get()), Some(4));
+    assert_eq!(sandbox.memory.map(|m| m.bytes()), Some(8 << 30));
+    let yaml = serde_yaml::to_string(&sandbox).unwrap();
+    let sandbox2: SandboxSpec = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(sandbox2, sandbox);
+}
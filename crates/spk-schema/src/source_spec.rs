@@ -156,6 +156,7 @@ impl LocalSource {
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub struct GitSource {
     pub git: String,
+    /// The branch, tag or full commit hash to check out
     #[serde(default, rename = "ref", skip_serializing_if = "String::is_empty")]
     pub reference: String,
     #[serde(
@@ -163,55 +164,199 @@ pub struct GitSource {
         skip_serializing_if = "is_default_git_clone_depth"
     )]
     pub depth: u32,
+    /// Which submodules of the repository are also cloned
+    #[serde(default, skip_serializing_if = "GitSubmodules::is_default")]
+    pub submodules: GitSubmodules,
+    /// Fetch the contents of any git-lfs files in the repository,
+    /// rather than leaving their pointer files in the sources
+    #[serde(
+        default = "default_git_lfs",
+        skip_serializing_if = "is_default_git_lfs"
+    )]
+    pub lfs: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub subdir: Option<String>,
 }
 
+/// Which submodules of a git repository are cloned as sources.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum GitSubmodules {
+    /// Clone all submodules, and any submodules that they have
+    #[default]
+    Recursive,
+    /// Clone only the submodules of the repository itself
+    TopLevel,
+    /// Do not clone any submodules
+    Skip,
+}
+
+impl GitSubmodules {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
 impl GitSource {
+    /// True if the reference of this source names a single
+    /// commit by its full hash, rather than a branch or tag.
+    pub fn is_commit_reference(&self) -> bool {
+        matches!(self.reference.len(), 40 | 64)
+            && self.reference.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
     /// Collect the represented sources files into the given directory.
     pub fn collect(&self, dirname: &Path) -> Result<()> {
-        let mut git_cmd = std::process::Command::new("git");
-        git_cmd.arg("clone");
-        git_cmd.arg("--depth");
-        git_cmd.arg(self.depth.to_string());
-        if !self.reference.is_empty() {
-            git_cmd.arg("-b");
-            git_cmd.arg(&self.reference);
+        let mut commands = Vec::new();
+        if self.is_commit_reference() {
+            // a commit cannot be named when cloning, but
+            // can be fetched on its own into a new repository
+            let mut init_cmd = std::process::Command::new("git");
+            init_cmd.args(["init", "--quiet"]);
+            let mut remote_cmd = std::process::Command::new("git");
+            remote_cmd.args(["remote", "add", "origin", &self.git]);
+            let mut fetch_cmd = std::process::Command::new("git");
+            fetch_cmd.args(["fetch", "--depth"]);
+            fetch_cmd.arg(self.depth.to_string());
+            fetch_cmd.args(["origin", &self.reference]);
+            let mut checkout_cmd = std::process::Command::new("git");
+            checkout_cmd.args(["checkout", "--quiet", "FETCH_HEAD"]);
+            commands.extend([init_cmd, remote_cmd, fetch_cmd, checkout_cmd]);
+        } else {
+            let mut git_cmd = std::process::Command::new("git");
+            git_cmd.arg("clone");
+            git_cmd.arg("--depth");
+            git_cmd.arg(self.depth.to_string());
+            if !self.reference.is_empty() {
+                git_cmd.arg("-b");
+                git_cmd.arg(&self.reference);
+            }
+            git_cmd.arg(&self.git);
+            git_cmd.arg(dirname);
+            commands.push(git_cmd);
         }
-        git_cmd.arg(&self.git);
-        git_cmd.arg(dirname);
-
-        let mut submodule_cmd = std::process::Command::new("git");
-        submodule_cmd.args(["submodule", "update", "--init", "--recursive"]);
-        if git_supports_submodule_depth() {
-            submodule_cmd.arg("--depth");
-            submodule_cmd.arg(self.depth.to_string());
+
+        if self.submodules != GitSubmodules::Skip {
+            let mut submodule_cmd = std::process::Command::new("git");
+            submodule_cmd.args(["submodule", "update", "--init"]);
+            if self.submodules == GitSubmodules::Recursive {
+                submodule_cmd.arg("--recursive");
+            }
+            if git_supports_submodule_depth() {
+                submodule_cmd.arg("--depth");
+                submodule_cmd.arg(self.depth.to_string());
+            }
+            commands.push(submodule_cmd);
         }
 
-        for mut cmd in vec![git_cmd, submodule_cmd].into_iter() {
-            tracing::debug!(?cmd, "running");
-            cmd.current_dir(dirname);
-            match cmd
-                .status()
-                .map_err(|err| {
-                    Error::ProcessSpawnError(spfs::Error::process_spawn_error(
-                        "git",
-                        err,
-                        Some(dirname.to_owned()),
-                    ))
-                })?
-                .code()
-            {
-                Some(0) => (),
-                code => {
-                    return Err(Error::String(format!(
-                        "git command failed with exit code {code:?}"
-                    )))
-                }
+        for mut cmd in commands.into_iter() {
+            // git-lfs files are always fetched separately, so that
+            // they are never silently left out when it's not installed
+            cmd.env("GIT_LFS_SKIP_SMUDGE", "1");
+            run_git_command(cmd, dirname)?;
+        }
+
+        if !uses_git_lfs(dirname) {
+            return Ok(());
+        }
+        if !self.lfs {
+            tracing::warn!(
+                "{} uses git-lfs, but only the pointer files will be collected (lfs: false)",
+                self.git
+            );
+            return Ok(());
+        }
+        let mut lfs_cmd = std::process::Command::new("git");
+        lfs_cmd.args(["lfs", "pull"]);
+        let mut commands = vec![lfs_cmd];
+        if self.submodules != GitSubmodules::Skip {
+            let mut submodule_cmd = std::process::Command::new("git");
+            submodule_cmd.args(["submodule", "foreach", "--quiet"]);
+            if self.submodules == GitSubmodules::Recursive {
+                submodule_cmd.arg("--recursive");
             }
+            submodule_cmd.args(["git", "lfs", "pull"]);
+            commands.push(submodule_cmd);
+        }
+        for cmd in commands.into_iter() {
+            run_git_command(cmd, dirname).map_err(|err| {
+                Error::String(format!(
+                    "{err}: {} uses git-lfs, which must be installed to collect its files \
+                     (or use 'lfs: false' to collect only the pointer files)",
+                    self.git
+                ))
+            })?;
         }
         Ok(())
     }
+
+    /// The full hash of the commit that was collected into the given directory.
+    pub fn collected_commit(&self, dirname: &Path) -> Result<String> {
+        let mut cmd = std::process::Command::new("git");
+        cmd.args(["rev-parse", "HEAD"]);
+        cmd.current_dir(dirname);
+        let output = cmd.output().map_err(|err| {
+            Error::ProcessSpawnError(spfs::Error::process_spawn_error(
+                "git",
+                err,
+                Some(dirname.to_owned()),
+            ))
+        })?;
+        if !output.status.success() {
+            return Err(Error::String(format!(
+                "git rev-parse failed with exit code {:?}",
+                output.status.code()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+fn run_git_command(mut cmd: std::process::Command, dirname: &Path) -> Result<()> {
+    tracing::debug!(?cmd, "running");
+    cmd.current_dir(dirname);
+    match cmd
+        .status()
+        .map_err(|err| {
+            Error::ProcessSpawnError(spfs::Error::process_spawn_error(
+                "git",
+                err,
+                Some(dirname.to_owned()),
+            ))
+        })?
+        .code()
+    {
+        Some(0) => Ok(()),
+        code => Err(Error::String(format!(
+            "git command failed with exit code {code:?}"
+        ))),
+    }
+}
+
+/// True if any of the .gitattributes files in the given
+/// directory, or its subdirectories, refer to git-lfs.
+fn uses_git_lfs(dirname: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(dirname) else {
+        return false;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if entry.file_name() != ".git" && uses_git_lfs(&path) {
+                return true;
+            }
+        } else if entry.file_name() == ".gitattributes" {
+            let attributes = std::fs::read_to_string(&path).unwrap_or_default();
+            if attributes.contains("filter=lfs") {
+                return true;
+            }
+        }
+    }
+    false
 }
 
 /// Package source files from a local or remote tar archive.
@@ -315,17 +460,19 @@ impl ScriptSource {
 
     /// Collect the represented sources files into the given directory.
     pub fn collect(&self, dirname: &Path, env: &HashMap<String, String>) -> Result<()> {
-        let mut bash = std::process::Command::new("bash");
-        bash.arg("-ex"); // print each command, exit on failure
-        bash.arg("-"); // read from stdin
-        bash.stdin(std::process::Stdio::piped());
-        bash.envs(env);
-        bash.current_dir(dirname);
+        // by default this is bash, printing each command and exiting on failure
+        let interpreter = self.script.interpreter();
+        let mut cmd = std::process::Command::new(interpreter.program());
+        cmd.args(interpreter.args());
+        cmd.arg("-"); // read from stdin
+        cmd.stdin(std::process::Stdio::piped());
+        cmd.envs(env);
+        cmd.current_dir(dirname);
 
         tracing::debug!("running sources script");
-        let mut child = bash.spawn().map_err(|err| {
+        let mut child = cmd.spawn().map_err(|err| {
             Error::ProcessSpawnError(spfs::Error::process_spawn_error(
-                "bash",
+                interpreter.program(),
                 err,
                 Some(dirname.to_owned()),
             ))
@@ -333,13 +480,17 @@ impl ScriptSource {
         let stdin = match child.stdin.as_mut() {
             Some(s) => s,
             None => {
-                return Err(Error::String(
-                    "failed to get stdin handle for bash".to_string(),
-                ))
+                return Err(Error::String(format!(
+                    "failed to get stdin handle for {}",
+                    interpreter.name()
+                )))
             }
         };
-        if let Err(err) = stdin.write_all(self.script.join("\n").as_bytes()) {
-            return Err(Error::wrap_io("failed to write source script to bash", err));
+        if let Err(err) = stdin.write_all(self.script.to_source().as_bytes()) {
+            return Err(Error::wrap_io(
+                format!("failed to write source script to {}", interpreter.name()),
+                err,
+            ));
         }
 
         match child.wait().map_err(Error::ProcessWaitError)?.code() {
@@ -380,3 +531,11 @@ fn default_git_clone_depth() -> u32 {
 fn is_default_git_clone_depth(depth: &u32) -> bool {
     depth == &default_git_clone_depth()
 }
+
+fn default_git_lfs() -> bool {
+    true
+}
+
+fn is_default_git_lfs(lfs: &bool) -> bool {
+    lfs == &default_git_lfs()
+}
@@ -4,7 +4,7 @@
 
 use rstest::rstest;
 
-use super::{GitSource, LocalSource, ScriptSource, TarSource};
+use super::{GitSource, GitSubmodules, LocalSource, ScriptSource, TarSource};
 use crate::foundation::fixtures::*;
 
 #[rstest]
@@ -66,6 +66,62 @@ fn test_git_sources(tmpdir: tempfile::TempDir) {
     assert!(dest_dir.join(".git").is_dir());
 }
 
+#[rstest]
+fn test_git_sources_by_commit(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let repo_dir = std::env::current_dir()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_owned();
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(&repo_dir)
+        .output()
+        .unwrap();
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let spec = format!("{{git: {repo_dir:?}, ref: {commit:?}}}");
+    let source: GitSource = serde_yaml::from_str(&spec).unwrap();
+    assert!(source.is_commit_reference());
+    source.collect(tmpdir.path()).unwrap();
+
+    assert_eq!(source.collected_commit(tmpdir.path()).unwrap(), commit);
+}
+
+#[rstest]
+#[case("main", false)]
+#[case("v1.0.0", false)]
+#[case("deadbeef", false)]
+#[case("0123456789abcdef0123456789abcdef01234567", true)]
+#[case("0123456789abcdef0123456789abcdef0123456z", false)]
+fn test_git_commit_reference(#[case] reference: &str, #[case] expected: bool) {
+    let spec = format!("{{git: https://example.com/repo.git, ref: {reference:?}}}");
+    let source: GitSource = serde_yaml::from_str(&spec).unwrap();
+    assert_eq!(source.is_commit_reference(), expected);
+}
+
+#[rstest]
+fn test_git_source_defaults() {
+    let source: GitSource = serde_yaml::from_str("{git: https://example.com/repo.git}").unwrap();
+    assert_eq!(source.submodules, GitSubmodules::Recursive);
+    assert!(source.lfs);
+    let yaml = serde_yaml::to_string(&source).unwrap();
+    assert!(
+        !yaml.contains("submodules"),
+        "defaults should not be serialized"
+    );
+    assert!(!yaml.contains("lfs"), "defaults should not be serialized");
+
+    let source: GitSource =
+        serde_yaml::from_str("{git: https://example.com/repo.git, submodules: Skip, lfs: false}")
+            .unwrap();
+    assert_eq!(source.submodules, GitSubmodules::Skip);
+    assert!(!source.lfs);
+}
+
 #[rstest]
 fn test_tar_sources(tmpdir: tempfile::TempDir) {
     init_logging();
@@ -3,6 +3,7 @@
 // https://github.com/spkenv/spk
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::Path;
 use std::str::FromStr;
@@ -33,6 +34,7 @@ use crate::{
     Opt,
     Package,
     PackageMut,
+    PlatformSupport,
     Recipe,
     RequirementsList,
     Result,
@@ -223,11 +225,12 @@ impl TemplateExt for SpecTemplate {
             ))
         })?;
 
-        let name = PkgNameBuf::from_str(
-            // it should never be possible for split to return 0 results
-            // but this trick avoids the use of unwrap
-            pkg.split('/').next().unwrap_or(pkg),
-        )?;
+        // the package name may include a namespace, which is
+        // separated from the name with the same '/' as the version
+        let name = match crate::foundation::name::parsing::package_name::<()>(pkg) {
+            Ok((rest, name)) if rest.is_empty() || rest.starts_with('/') => name.to_owned(),
+            _ => PkgNameBuf::from_str(pkg.split('/').next().unwrap_or(pkg))?,
+        };
 
         Ok(Self {
             file_path,
@@ -244,7 +247,7 @@ impl TemplateExt for SpecTemplate {
 /// file or machine-managed persistent storage.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize)]
 #[serde(tag = "api")]
-#[enum_dispatch(Deprecate, DeprecateMut)]
+#[enum_dispatch(Deprecate, DeprecateMut, PlatformSupport)]
 pub enum SpecRecipe {
     #[serde(rename = "v0/package")]
     V0Package(super::v0::Spec<VersionIdent>),
@@ -499,6 +502,12 @@ impl Test for SpecTest {
             Self::V0(t) => t.additional_requirements(),
         }
     }
+
+    fn environment(&self) -> BTreeMap<String, String> {
+        match self {
+            Self::V0(t) => t.environment(),
+        }
+    }
 }
 
 /// Specifies some data object within the spk ecosystem.
@@ -507,7 +516,7 @@ impl Test for SpecTest {
 /// and deserialized from a `Repository`.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize)]
 #[serde(tag = "api")]
-#[enum_dispatch(Deprecate, DeprecateMut)]
+#[enum_dispatch(Deprecate, DeprecateMut, PlatformSupport)]
 pub enum Spec {
     #[serde(rename = "v0/package")]
     V0Package(super::v0::Spec<BuildIdent>),
@@ -603,6 +612,12 @@ impl Package for Spec {
         }
     }
 
+    fn provides(&self) -> &[VersionIdent] {
+        match self {
+            Spec::V0Package(spec) => spec.provides(),
+        }
+    }
+
     fn components(&self) -> &super::ComponentSpecList {
         match self {
             Spec::V0Package(spec) => spec.components(),
@@ -615,6 +630,12 @@ impl Package for Spec {
         }
     }
 
+    fn runtime_conflicts(&self) -> &Vec<crate::ident::ConflictRequest> {
+        match self {
+            Spec::V0Package(spec) => spec.runtime_conflicts(),
+        }
+    }
+
     fn get_build_options(&self) -> &Vec<Opt> {
         match self {
             Spec::V0Package(spec) => spec.get_build_options(),
@@ -645,6 +666,36 @@ impl Package for Spec {
         }
     }
 
+    fn build_sandbox(&self) -> Option<&super::SandboxSpec> {
+        match self {
+            Spec::V0Package(spec) => spec.build_sandbox(),
+        }
+    }
+
+    fn install_hooks(&self) -> &[super::InstallHook] {
+        match self {
+            Spec::V0Package(spec) => spec.install_hooks(),
+        }
+    }
+
+    fn build_environment(&self) -> &super::BuildEnvironmentSpec {
+        match self {
+            Spec::V0Package(spec) => spec.build_environment(),
+        }
+    }
+
+    fn build_relocate(&self) -> Option<&super::RelocateSpec> {
+        match self {
+            Spec::V0Package(spec) => spec.build_relocate(),
+        }
+    }
+
+    fn build_capture(&self) -> Option<&super::CaptureSpec> {
+        match self {
+            Spec::V0Package(spec) => spec.build_capture(),
+        }
+    }
+
     fn downstream_build_requirements<'a>(
         &self,
         components: impl IntoIterator<Item = &'a Component>,
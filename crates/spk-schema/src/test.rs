@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
@@ -17,9 +18,18 @@ const TEST_STAGES: &[&str] = &[BUILD_NAME, INSTALL_NAME, SOURCES_NAME];
 pub trait Test {
     fn script(&self) -> String;
 
+    /// Requests to add to the test environment.
+    ///
+    /// For install tests, these may use `fromBuildEnv` to be pinned
+    /// to the packages that are installed along with the one being tested.
     fn additional_requirements(&self) -> Vec<Request> {
         Vec::new()
     }
+
+    /// Additional environment variables to set when running the script.
+    fn environment(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -21,7 +21,7 @@ pub struct TestSpec {
 
 impl crate::Test for TestSpec {
     fn script(&self) -> String {
-        self.script.join("\n")
+        self.script.to_source()
     }
 
     fn additional_requirements(&self) -> Vec<Request> {
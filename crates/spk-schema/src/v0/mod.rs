@@ -12,4 +12,4 @@ pub use platform::Platform;
 pub use spec::Spec;
 pub use test_spec::TestSpec;
 pub use variant::Variant;
-pub use variant_spec::VariantSpec;
+pub use variant_spec::{VariantMatrix, VariantSpec, VariantSpecOrMatrix};
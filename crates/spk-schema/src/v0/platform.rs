@@ -15,6 +15,7 @@ use spk_schema_ident::{
     BuildIdent,
     InclusionPolicy,
     PkgRequest,
+    RangeIdent,
     Request,
     RequestedBy,
     VersionIdent,
@@ -33,6 +34,7 @@ use crate::{
     InputVariant,
     Opt,
     Package,
+    PlatformSupport,
     Recipe,
     RequirementsList,
     Result,
@@ -117,6 +119,9 @@ pub struct Platform {
     pub compat: Compat,
     #[serde(default, skip_serializing_if = "is_false")]
     pub deprecated: bool,
+    /// Packages that are suggested for use instead of this one, once deprecated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced_by: Vec<RangeIdent>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub base: Option<VersionIdent>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -134,8 +139,14 @@ impl Deprecate for Platform {
     fn is_deprecated(&self) -> bool {
         self.deprecated
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        &self.replaced_by
+    }
 }
 
+impl PlatformSupport for Platform {}
+
 impl DeprecateMut for Platform {
     fn deprecate(&mut self) -> Result<()> {
         self.deprecated = true;
@@ -144,6 +155,12 @@ impl DeprecateMut for Platform {
 
     fn undeprecate(&mut self) -> Result<()> {
         self.deprecated = false;
+        self.replaced_by.clear();
+        Ok(())
+    }
+
+    fn set_replaced_by(&mut self, replaced_by: Vec<RangeIdent>) -> Result<()> {
+        self.replaced_by = replaced_by;
         Ok(())
     }
 }
@@ -236,6 +253,7 @@ impl Recipe for Platform {
             meta,
             compat,
             deprecated: _deprecated,
+            replaced_by: _replaced_by,
             base,
             requirements,
         } = self;
@@ -413,6 +431,7 @@ struct PlatformVisitor {
     meta: Option<Meta>,
     compat: Option<Compat>,
     deprecated: Option<bool>,
+    replaced_by: Option<Vec<RangeIdent>>,
     requirements: Option<PlatformRequirementsVisitor>,
 }
 
@@ -434,6 +453,7 @@ impl<'de> serde::de::Visitor<'de> for PlatformVisitor {
                 "meta" => self.meta = Some(map.next_value::<Meta>()?),
                 "compat" => self.compat = Some(map.next_value::<Compat>()?),
                 "deprecated" => self.deprecated = Some(map.next_value::<bool>()?),
+                "replaced_by" => self.replaced_by = Some(map.next_value::<Vec<RangeIdent>>()?),
                 "requirements" => {
                     self.requirements = Some(map.next_value::<PlatformRequirementsVisitor>()?)
                 }
@@ -454,6 +474,7 @@ impl<'de> serde::de::Visitor<'de> for PlatformVisitor {
             meta: self.meta.take().unwrap_or_default(),
             compat: self.compat.take().unwrap_or_default(),
             deprecated: self.deprecated.take().unwrap_or_default(),
+            replaced_by: self.replaced_by.take().unwrap_or_default(),
             platform,
             base: self.base.take(),
             requirements: self.requirements.take().map(Into::into),
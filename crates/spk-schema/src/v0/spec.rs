@@ -3,13 +3,13 @@
 // https://github.com/spkenv/spk
 
 use std::borrow::Cow;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryInto;
 use std::path::Path;
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use spk_schema_foundation::ident_build::BuildId;
+use spk_schema_foundation::ident_build::{BuildId, EmbeddedSource};
 use spk_schema_foundation::ident_component::ComponentBTreeSet;
 use spk_schema_foundation::name::PkgNameBuf;
 use spk_schema_foundation::option_map::{OptFilter, Stringified};
@@ -27,6 +27,7 @@ use crate::foundation::version::{Compat, CompatRule, Compatibility, Version};
 use crate::foundation::version_range::Ranged;
 use crate::ident::{
     is_false,
+    ConflictRequest,
     PkgRequest,
     PreReleasePolicy,
     Request,
@@ -36,9 +37,12 @@ use crate::ident::{
 };
 use crate::metadata::Meta;
 use crate::option::VarOpt;
+use crate::platform_support::OneOrMany;
 use crate::{
     BuildEnv,
+    BuildEnvironmentSpec,
     BuildSpec,
+    CaptureSpec,
     ComponentSpec,
     ComponentSpecList,
     Deprecate,
@@ -48,15 +52,22 @@ use crate::{
     Error,
     Inheritance,
     InputVariant,
+    InstallHook,
     InstallSpec,
     LocalSource,
     Opt,
     Package,
     PackageMut,
+    PlatformSupport,
     Recipe,
+    RelocateSpec,
     RequirementsList,
     Result,
+    SandboxSpec,
+    Script,
     SourceSpec,
+    SupportedPlatform,
+    SupportedPlatforms,
     TestStage,
     ValidationSpec,
     Variant,
@@ -75,12 +86,21 @@ pub struct Spec<Ident> {
     pub compat: Compat,
     #[serde(default, skip_serializing_if = "is_false")]
     pub deprecated: bool,
+    /// Packages that are suggested for use instead of this one, once deprecated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced_by: Vec<RangeIdent>,
+    /// The operating systems and architectures that this package supports
+    #[serde(flatten)]
+    pub supported_platforms: SupportedPlatforms,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sources: Vec<SourceSpec>,
     #[serde(default, skip_serializing_if = "BuildSpec::is_default")]
     pub build: BuildSpec,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tests: Vec<TestSpec>,
+    /// Named scripts that can be shared by any number of tests
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub test_fixtures: BTreeMap<String, Script>,
     #[serde(default, skip_serializing_if = "InstallSpec::is_default")]
     pub install: InstallSpec,
 }
@@ -93,9 +113,12 @@ impl<Ident> Spec<Ident> {
             meta: Meta::default(),
             compat: Compat::default(),
             deprecated: bool::default(),
+            replaced_by: Vec::new(),
+            supported_platforms: SupportedPlatforms::default(),
             sources: Vec::new(),
             build: BuildSpec::default(),
             tests: Vec::new(),
+            test_fixtures: BTreeMap::new(),
             install: InstallSpec::default(),
         }
     }
@@ -104,6 +127,38 @@ impl<Ident> Spec<Ident> {
         Cow::Borrowed(self.build.options.as_slice())
     }
 
+    /// Prepend the scripts of the fixtures used by a test to its own script.
+    fn resolve_test_fixtures(&self, mut test: TestSpec) -> Result<TestSpec> {
+        if test.fixtures.is_empty() {
+            return Ok(test);
+        }
+        // fixtures are joined into the test's own script, so
+        // they must all be written for the same interpreter
+        let interpreter = test.script.interpreter();
+        let mut script = Vec::new();
+        for name in test.fixtures.drain(..) {
+            let fixture = self.test_fixtures.get(&name).ok_or_else(|| {
+                Error::String(format!(
+                    "Test uses an undefined fixture '{name}', it must be listed in test_fixtures"
+                ))
+            })?;
+            if fixture.interpreter() != interpreter {
+                return Err(Error::String(format!(
+                    "Test fixture '{name}' must use the same interpreter as the test ({})",
+                    interpreter.name()
+                )));
+            }
+            script.extend(fixture.iter().cloned());
+        }
+        script.extend(test.script.iter().cloned());
+        let mut merged = Script::new(script);
+        if let Some(explicit) = test.script.explicit_interpreter() {
+            merged = merged.with_interpreter(explicit);
+        }
+        test.script = merged;
+        Ok(test)
+    }
+
     /// Convert the ident type associated to this package
     pub fn map_ident<F, ToIdent>(self, map: F) -> Spec<ToIdent>
     where
@@ -114,9 +169,12 @@ impl<Ident> Spec<Ident> {
             meta: self.meta,
             compat: self.compat,
             deprecated: self.deprecated,
+            replaced_by: self.replaced_by,
+            supported_platforms: self.supported_platforms,
             sources: self.sources,
             build: self.build,
             tests: self.tests,
+            test_fixtures: self.test_fixtures,
             install: self.install,
         }
     }
@@ -127,6 +185,7 @@ impl<Ident> Spec<Ident> {
         self.install.requirements.clear();
         self.build = Default::default();
         self.tests.clear();
+        self.test_fixtures.clear();
         self.install.components.clear();
         self.install.components.push(ComponentSpec {
             name: Component::Source,
@@ -135,6 +194,7 @@ impl<Ident> Spec<Ident> {
             requirements: Default::default(),
             embedded: Default::default(),
             file_match_mode: Default::default(),
+            environment: Default::default(),
         });
     }
 }
@@ -145,6 +205,10 @@ impl Spec<BuildIdent> {
         match request {
             Request::Pkg(request) => Satisfy::check_satisfies_request(self, &request),
             Request::Var(request) => Satisfy::check_satisfies_request(self, &request),
+            Request::AnyOf(request) => match request.satisfied_alternative(self) {
+                Some(_) => Compatibility::Compatible,
+                None => Compatibility::incompatible(format!("package is not one of {request}")),
+            },
         }
     }
 
@@ -177,6 +241,26 @@ impl Spec<BuildIdent> {
             .map(Cow::Owned)
             .expect("build opts do not contain duplicates")
     }
+
+    /// Return var requirements for the version of each toolchain
+    /// package that this package was built with.
+    ///
+    /// These are not namespaced, so that they apply to any package
+    /// that was also built with the same toolchain.
+    fn toolchain_requirements(&self) -> impl Iterator<Item = Request> + '_ {
+        self.build.toolchain.iter().filter_map(|toolchain| {
+            let value = self.build.options.iter().find_map(|opt| match opt {
+                Opt::Pkg(opt) if opt.pkg == toolchain.pkg => opt.get_value(None),
+                _ => None,
+            })?;
+            (!value.is_empty()).then(|| {
+                Request::Var(VarRequest::new_with_value(
+                    toolchain.pkg.as_opt_name().to_owned(),
+                    value,
+                ))
+            })
+        })
+    }
 }
 
 impl<Ident: Named> Named for Spec<Ident> {
@@ -201,6 +285,16 @@ impl<Ident> Deprecate for Spec<Ident> {
     fn is_deprecated(&self) -> bool {
         self.deprecated
     }
+
+    fn replaced_by(&self) -> &[RangeIdent] {
+        &self.replaced_by
+    }
+}
+
+impl<Ident> PlatformSupport for Spec<Ident> {
+    fn supported_platforms(&self) -> &SupportedPlatforms {
+        &self.supported_platforms
+    }
 }
 
 impl<Ident> DeprecateMut for Spec<Ident> {
@@ -211,6 +305,12 @@ impl<Ident> DeprecateMut for Spec<Ident> {
 
     fn undeprecate(&mut self) -> Result<()> {
         self.deprecated = false;
+        self.replaced_by.clear();
+        Ok(())
+    }
+
+    fn set_replaced_by(&mut self, replaced_by: Vec<RangeIdent>) -> Result<()> {
+        self.replaced_by = replaced_by;
         Ok(())
     }
 }
@@ -228,7 +328,7 @@ impl Package for Spec<BuildIdent> {
 
     fn option_values(&self) -> OptionMap {
         let mut opts = OptionMap::default();
-        for opt in self.build.options.iter() {
+        for opt in self.build.options.iter().filter(|opt| !opt.is_secret()) {
             // we are assuming that this spec has been updated to represent
             // a build and had all of the options pinned/resolved.
             opts.insert(opt.full_name().to_owned(), opt.get_value(None));
@@ -270,9 +370,27 @@ impl Package for Spec<BuildIdent> {
                     .map(move |embed| (embed.clone(), Some(cs.name.clone())))
             }))
             .map(|(recipe, component)| recipe.try_into().map(|r| (r, component)))
+            .chain(self.install.provides.iter().map(|provided| {
+                let mut stub =
+                    Spec::new(provided.to_build(Build::Embedded(EmbeddedSource::Unknown)));
+                // a provided package can only be used alongside the
+                // exact build that provides it, so resolving the stub
+                // must also bring in this package
+                stub.install.requirements.insert_or_replace(Request::Pkg(
+                    PkgRequest::from_ident_exact(
+                        self.pkg.to_any(),
+                        RequestedBy::Embedded(self.pkg.clone()),
+                    ),
+                ));
+                Ok((stub, None))
+            }))
             .collect()
     }
 
+    fn provides(&self) -> &[VersionIdent] {
+        &self.install.provides
+    }
+
     fn components(&self) -> &ComponentSpecList {
         &self.install.components
     }
@@ -281,6 +399,10 @@ impl Package for Spec<BuildIdent> {
         &self.install.environment
     }
 
+    fn runtime_conflicts(&self) -> &Vec<ConflictRequest> {
+        &self.install.conflicts
+    }
+
     fn get_build_options(&self) -> &Vec<Opt> {
         &self.build.options
     }
@@ -298,6 +420,9 @@ impl Package for Spec<BuildIdent> {
                     }
                     requests.insert_or_merge(req.into())?;
                 }
+                // secret values are not published by any package,
+                // so they could never be matched by a var request
+                Opt::Var(opt) if opt.secret => {}
                 Opt::Var(opt) => {
                     // If no value was specified in the spec, there's
                     // no need to turn that into a requirement to
@@ -311,6 +436,9 @@ impl Package for Spec<BuildIdent> {
                 }
             }
         }
+        for request in self.toolchain_requirements() {
+            requests.insert_or_merge(request)?;
+        }
         Ok(Cow::Owned(requests))
     }
 
@@ -322,7 +450,15 @@ impl Package for Spec<BuildIdent> {
         &self,
         _components: impl IntoIterator<Item = &'a Component>,
     ) -> Cow<'_, RequirementsList> {
-        self.downstream_requirements(|o| o.inheritance != Inheritance::Weak)
+        let requirements = self.downstream_requirements(|o| o.inheritance != Inheritance::Weak);
+        if self.build.toolchain.is_empty() {
+            return requirements;
+        }
+        let mut requirements = requirements.into_owned();
+        for request in self.toolchain_requirements() {
+            requirements.insert_or_replace(request);
+        }
+        Cow::Owned(requirements)
     }
 
     fn downstream_runtime_requirements<'a>(
@@ -337,7 +473,27 @@ impl Package for Spec<BuildIdent> {
     }
 
     fn build_script(&self) -> String {
-        self.build.script.join("\n")
+        self.build.script.to_source()
+    }
+
+    fn build_sandbox(&self) -> Option<&SandboxSpec> {
+        self.build.sandbox.as_ref()
+    }
+
+    fn install_hooks(&self) -> &[InstallHook] {
+        &self.install.hooks
+    }
+
+    fn build_environment(&self) -> &BuildEnvironmentSpec {
+        &self.build.environment
+    }
+
+    fn build_relocate(&self) -> Option<&RelocateSpec> {
+        self.build.relocate.as_ref()
+    }
+
+    fn build_capture(&self) -> Option<&CaptureSpec> {
+        self.build.capture.as_ref()
     }
 
     fn validate_options(&self, given_options: &OptionMap) -> Compatibility {
@@ -454,8 +610,7 @@ impl Recipe for Spec<VersionIdent> {
         V: Variant,
     {
         let options = self.resolve_options(variant)?;
-        Ok(self
-            .tests
+        self.tests
             .iter()
             .filter(|t| t.stage == stage)
             .filter(|t| {
@@ -507,6 +662,7 @@ impl Recipe for Spec<VersionIdent> {
                                                 && var_request_value.as_pinned()
                                                     == Some(value.as_str())
                                         }
+                                        Request::AnyOf(_) => false,
                                     })
                                 {
                                     return false;
@@ -523,7 +679,8 @@ impl Recipe for Spec<VersionIdent> {
                 true
             })
             .cloned()
-            .collect())
+            .map(|test| self.resolve_test_fixtures(test))
+            .collect()
     }
 
     fn generate_source_build(&self, root: &Path) -> Result<Spec<BuildIdent>> {
@@ -557,6 +714,9 @@ impl Recipe for Spec<VersionIdent> {
 
         for opt in updated.build.options.iter_mut() {
             match opt {
+                // the value of a secret option is only given to the build
+                // script and must not be recorded in the published spec
+                Opt::Var(opt) if opt.secret => continue,
                 Opt::Var(opt) => {
                     opt.set_value(
                         build_options
@@ -608,7 +768,7 @@ impl Recipe for Spec<VersionIdent> {
                 match build_requirements.contains_request(request) {
                     Compatibility::Compatible => continue,
                     Compatibility::Incompatible(_) => match request {
-                        Request::Pkg(_) => continue,
+                        Request::Pkg(_) | Request::AnyOf(_) => continue,
                         Request::Var(var) => {
                             let Some(value) = var.value.as_pinned() else {
                                 continue;
@@ -632,7 +792,7 @@ impl Recipe for Spec<VersionIdent> {
                 match updated.install.requirements.contains_request(request) {
                     Compatibility::Compatible => continue,
                     Compatibility::Incompatible(_) => match request {
-                        Request::Pkg(_) => continue,
+                        Request::Pkg(_) | Request::AnyOf(_) => continue,
                         Request::Var(var) => {
                             let Some(value) = var.value.as_pinned() else {
                                 continue;
@@ -654,6 +814,24 @@ impl Recipe for Spec<VersionIdent> {
         }
 
         for req in missing_build_requirements {
+            // a toolchain package (or other package option) with the same
+            // name as an inherited var must have been resolved to the same
+            // version that the upstream package was built with
+            if let Some(existing) = updated
+                .build
+                .options
+                .iter()
+                .find(|opt| opt.full_name() == &req.0)
+            {
+                let value = existing.get_value(None);
+                if value != req.1 {
+                    return Err(Error::String(format!(
+                        "Build requires {} to be {}, but it resolved to {value}",
+                        req.0, req.1
+                    )));
+                }
+                continue;
+            }
             let mut var = VarOpt::new(req.0)?;
             var.set_value(req.1)?;
             updated.build.options.push(Opt::Var(var));
@@ -701,15 +879,17 @@ impl Satisfy<PkgRequest> for Spec<BuildIdent> {
             // deprecated builds are only okay if their build
             // was specifically requested
             if pkg_request.pkg.build.as_ref() != Some(self.pkg.build()) {
-                return Compatibility::incompatible(
-                    "Build is deprecated and was not specifically requested".to_string(),
-                );
+                let message = "Build is deprecated and was not specifically requested";
+                return Compatibility::incompatible(match self.replacement_suggestion() {
+                    Some(suggestion) => format!("{message}, {suggestion}"),
+                    None => message.to_string(),
+                });
             }
         }
 
         if (pkg_request.prerelease_policy.is_none()
             || pkg_request.prerelease_policy == Some(PreReleasePolicy::ExcludeAll))
-            && !self.version().pre.is_empty()
+            && self.version().is_prerelease()
         {
             return Compatibility::incompatible("prereleases not allowed".to_string());
         }
@@ -872,9 +1052,13 @@ struct SpecVisitor<B, T> {
     meta: Option<Meta>,
     compat: Option<Compat>,
     deprecated: Option<bool>,
+    replaced_by: Option<Vec<RangeIdent>>,
+    platforms: Option<Vec<SupportedPlatform>>,
+    arch: Option<Vec<String>>,
     sources: Option<Vec<SourceSpec>>,
     build: Option<UncheckedBuildSpec>,
     tests: Option<Vec<TestSpec>>,
+    test_fixtures: Option<BTreeMap<String, Script>>,
     install: Option<InstallSpec>,
     check_build_spec: bool,
 }
@@ -887,9 +1071,13 @@ impl<B, T> SpecVisitor<B, T> {
             meta: None,
             compat: None,
             deprecated: None,
+            replaced_by: None,
+            platforms: None,
+            arch: None,
             sources: None,
             build: None,
             tests: None,
+            test_fixtures: None,
             install: None,
             check_build_spec,
         }
@@ -938,9 +1126,15 @@ where
                 "meta" => self.meta = Some(map.next_value::<Meta>()?),
                 "compat" => self.compat = Some(map.next_value::<Compat>()?),
                 "deprecated" => self.deprecated = Some(map.next_value::<bool>()?),
+                "replaced_by" => self.replaced_by = Some(map.next_value::<Vec<RangeIdent>>()?),
+                "platforms" => self.platforms = Some(map.next_value::<Vec<SupportedPlatform>>()?),
+                "arch" => self.arch = Some(map.next_value::<OneOrMany>()?.into_vec()),
                 "sources" => self.sources = Some(map.next_value::<Vec<SourceSpec>>()?),
                 "build" => self.build = Some(map.next_value::<UncheckedBuildSpec>()?),
                 "tests" => self.tests = Some(map.next_value::<Vec<TestSpec>>()?),
+                "test_fixtures" => {
+                    self.test_fixtures = Some(map.next_value::<BTreeMap<String, Script>>()?)
+                }
                 "install" => self.install = Some(map.next_value::<InstallSpec>()?),
                 _ => {
                     // ignore any unrecognized field, but consume the value anyway
@@ -958,6 +1152,11 @@ where
             meta: self.meta.take().unwrap_or_default(),
             compat: self.compat.take().unwrap_or_default(),
             deprecated: self.deprecated.take().unwrap_or_default(),
+            replaced_by: self.replaced_by.take().unwrap_or_default(),
+            supported_platforms: SupportedPlatforms {
+                platforms: self.platforms.take().unwrap_or_default(),
+                arch: self.arch.take().unwrap_or_default(),
+            },
             sources: self
                 .sources
                 .take()
@@ -971,6 +1170,7 @@ where
                 None => Default::default(),
             },
             tests: self.tests.take().unwrap_or_default(),
+            test_fixtures: self.test_fixtures.take().unwrap_or_default(),
             install: self.install.take().unwrap_or_default(),
             pkg,
         })
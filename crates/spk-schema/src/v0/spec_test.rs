@@ -18,7 +18,19 @@ use crate::foundation::option_map::OptionMap;
 use crate::foundation::FromYaml;
 use crate::option::PkgOpt;
 use crate::spec::SpecTemplate;
-use crate::{BuildEnv, Opt, Recipe, Template, TemplateExt, Variant, VariantExt};
+use crate::{
+    BuildEnv,
+    Deprecate,
+    Opt,
+    Package,
+    Recipe,
+    Template,
+    TemplateExt,
+    Test,
+    TestStage,
+    Variant,
+    VariantExt,
+};
 
 #[rstest]
 fn test_spec_is_valid_with_only_name() {
@@ -31,6 +43,83 @@ fn test_explicit_no_sources() {
     assert!(spec.sources.is_empty());
 }
 
+#[rstest]
+fn test_deprecated_replacements() {
+    let spec: Spec<VersionIdent> = serde_yaml::from_str(
+        "{pkg: test-pkg/1.0.0, deprecated: true, replaced_by: [test-pkg/2.0.0, other-pkg]}",
+    )
+    .unwrap();
+    assert!(spec.is_deprecated());
+    assert_eq!(spec.replaced_by.len(), 2);
+    assert_eq!(
+        spec.replacement_suggestion().as_deref(),
+        Some("use test-pkg/2.0.0 or other-pkg")
+    );
+
+    let yaml = serde_yaml::to_string(&spec).unwrap();
+    let roundtrip: Spec<VersionIdent> = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(roundtrip, spec);
+}
+
+#[rstest]
+fn test_tests_run_their_fixtures_first() {
+    let spec: Spec<VersionIdent> = serde_yaml::from_str(
+        r#"
+pkg: test-pkg/1.0.0
+test_fixtures:
+  setup:
+    - export DATA=/tmp/data
+tests:
+  - stage: install
+    fixtures: [setup]
+    environment:
+      LANG: C
+    script:
+      - test -n "$DATA"
+  - stage: build
+    fixtures: [undefined]
+    script:
+      - "true"
+"#,
+    )
+    .unwrap();
+
+    let tests = spec
+        .get_tests(TestStage::Install, &OptionMap::default())
+        .unwrap();
+    assert_eq!(tests.len(), 1);
+    assert_eq!(
+        tests[0].script(),
+        "export DATA=/tmp/data\ntest -n \"$DATA\"",
+        "fixture scripts should run before the test script"
+    );
+    assert_eq!(
+        tests[0].environment().get("LANG").map(String::as_str),
+        Some("C")
+    );
+
+    spec.get_tests(TestStage::Build, &OptionMap::default())
+        .expect_err("tests should not use undefined fixtures");
+}
+
+#[rstest]
+fn test_provided_packages_become_stubs() {
+    let spec: Spec<BuildIdent> =
+        serde_yaml::from_str("{pkg: openjpeg/2.4.0/3I42H3S6, install: {provides: [jpeg2000/2.3]}}")
+            .unwrap();
+    assert_eq!(spec.provides(), &["jpeg2000/2.3".parse().unwrap()]);
+
+    let stubs = spec.embedded_as_packages().unwrap();
+    assert_eq!(stubs.len(), 1);
+    let (stub, component) = &stubs[0];
+    assert_eq!(component, &None);
+    assert_eq!(stub.ident().to_string(), "jpeg2000/2.3/embedded");
+    let Some(Request::Pkg(req)) = stub.install.requirements.first() else {
+        panic!("a provided stub should require the package that provides it");
+    };
+    assert_eq!(req.pkg.to_string(), "openjpeg/==2.4.0/3I42H3S6");
+}
+
 #[rstest]
 fn test_sources_relative_to_spec_file(tmpdir: tempfile::TempDir) {
     let spec_dir = dunce::canonicalize(tmpdir.path()).unwrap().join("dir");
@@ -127,6 +216,30 @@ fn test_yaml_error_context(#[case] yaml: &str, #[case] expected: &str) {
     );
 }
 
+#[rstest]
+fn test_install_conflicts_roundtrip() {
+    let spec: Spec<BuildIdent> = serde_yaml::from_str(
+        r#"
+        api: v0/package
+        pkg: my-plugin/1.0.0/3I42H3S6
+        install:
+          conflicts: [oldlib/<2, otherlib]
+    "#,
+    )
+    .unwrap();
+    assert_eq!(
+        spec.runtime_conflicts()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["!oldlib/<2.0.0", "!otherlib"]
+    );
+
+    let yaml = serde_yaml::to_string(&spec).unwrap();
+    let roundtrip: Spec<BuildIdent> = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(roundtrip.runtime_conflicts(), spec.runtime_conflicts());
+}
+
 #[rstest]
 fn test_build_options_respect_components() {
     let spec: Spec<AnyIdent> = serde_yaml::from_str(
@@ -217,7 +330,7 @@ fn test_strong_inheritance_injection() {
             .requirements
             .iter()
             .any(|request| match request {
-                spk_schema_ident::Request::Pkg(_) => false,
+                spk_schema_ident::Request::Pkg(_) | spk_schema_ident::Request::AnyOf(_) => false,
                 spk_schema_ident::Request::Var(var) =>
                     var.var == "base.inherit-me" && var.value == "1.2.3".into(),
             }),
@@ -285,7 +398,7 @@ fn test_strong_inheritance_injection_transitivity() {
             .requirements
             .iter()
             .any(|request| match request {
-                spk_schema_ident::Request::Pkg(_) => false,
+                spk_schema_ident::Request::Pkg(_) | spk_schema_ident::Request::AnyOf(_) => false,
                 spk_schema_ident::Request::Var(var) =>
                     var.var == "base.inherit-me" && var.value == "1.2.3".into(),
             }),
@@ -293,6 +406,71 @@ fn test_strong_inheritance_injection_transitivity() {
     );
 }
 
+#[rstest]
+#[case::same_toolchain("10.0.0", true)]
+#[case::different_toolchain("9.3.0", false)]
+fn test_toolchain_must_match_upstream(#[case] gcc_version: &str, #[case] compatible: bool) {
+    struct TestBuildEnv(String);
+
+    impl BuildEnv for TestBuildEnv {
+        type Package = Spec<BuildIdent>;
+
+        fn build_env(&self) -> Vec<Self::Package> {
+            vec![
+                serde_yaml::from_str(
+                    r#"
+                    api: v0/package
+                    pkg: base/1.0.0/3TCOOP2W
+                    build:
+                      toolchain:
+                        - pkg: gcc
+                      options:
+                        - pkg: gcc
+                          static: ~10.0.0
+                "#,
+                )
+                .unwrap(),
+                serde_yaml::from_str(&format!(
+                    "{{api: v0/package, pkg: gcc/{}/3TCOOP2W}}",
+                    self.0
+                ))
+                .unwrap(),
+            ]
+        }
+
+        fn env_vars(&self) -> HashMap<String, String> {
+            HashMap::default()
+        }
+    }
+
+    let build_env = TestBuildEnv(gcc_version.to_string());
+
+    let spec: Spec<VersionIdent> = serde_yaml::from_str(
+        r#"
+        api: v0/package
+        pkg: test-pkg/1.0.0
+        build:
+          toolchain:
+            - pkg: gcc
+          options:
+            - pkg: base
+    "#,
+    )
+    .unwrap();
+
+    let result = spec.generate_binary_build(&option_map! {}, &build_env);
+    if compatible {
+        result.expect("the same toolchain as upstream should be allowed");
+    } else {
+        let err = result.expect_err("a different toolchain than upstream should be rejected");
+        assert!(
+            err.to_string()
+                .contains("Build requires gcc to be ~10.0.0, but it resolved to ~9.3.0"),
+            "unexpected error: {err}"
+        );
+    }
+}
+
 #[rstest]
 fn test_variants_can_introduce_components() {
     let spec: Spec<AnyIdent> = serde_yaml::from_str(
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::ident::Request;
@@ -21,14 +23,24 @@ pub struct TestSpec {
     pub selectors: Vec<super::VariantSpec>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub requirements: Vec<Request>,
+    /// Names of the spec's test fixtures to run before this test's own script
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixtures: Vec<String>,
+    /// Additional environment variables to set when running this test
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
 }
 
 impl crate::Test for TestSpec {
     fn script(&self) -> String {
-        self.script.join("\n")
+        self.script.to_source()
     }
 
     fn additional_requirements(&self) -> Vec<Request> {
         self.requirements.clone()
     }
+
+    fn environment(&self) -> BTreeMap<String, String> {
+        self.environment.clone()
+    }
 }
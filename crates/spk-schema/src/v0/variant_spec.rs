@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use serde::de::value::StrDeserializer;
 use serde::de::MapAccess;
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Serialize};
@@ -45,6 +46,15 @@ pub enum VariantSpecEntryKey {
     Opt(OptNameBuf),
 }
 
+impl std::fmt::Display for VariantSpecEntryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PkgOrOpt(pkg) => write!(f, "{}{}", pkg.0.name, pkg.0.components),
+            Self::Opt(opt) => opt.fmt(f),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct VariantSpec {
     pub entries: Vec<(VariantSpecEntryKey, Stringified)>,
@@ -96,3 +106,238 @@ impl Serialize for VariantSpec {
         map.end()
     }
 }
+
+/// An entry in the list of build variants of a recipe, which is
+/// either a single variant or a matrix of them.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum VariantSpecOrMatrix {
+    Spec(VariantSpec),
+    Matrix(VariantMatrix),
+}
+
+impl VariantSpecOrMatrix {
+    /// The concrete variants described by this entry, in order.
+    pub fn expand(&self) -> Vec<VariantSpec> {
+        match self {
+            Self::Spec(spec) => vec![spec.clone()],
+            Self::Matrix(matrix) => matrix.expand(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VariantSpecOrMatrix {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct VariantSpecOrMatrixVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for VariantSpecOrMatrixVisitor {
+            type Value = VariantSpecOrMatrix;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a variant specification or variant matrix")
+            }
+
+            fn visit_map<M>(self, mut access: M) -> std::result::Result<Self::Value, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0));
+                let mut matrix = None;
+                let mut exclude = None;
+
+                while let Some(key) = access.next_key::<Stringified>()? {
+                    match key.as_str() {
+                        "matrix" => matrix = Some(access.next_value::<MatrixAxes>()?.0),
+                        "exclude" => exclude = Some(access.next_value::<Vec<VariantSpec>>()?),
+                        name => {
+                            let key = VariantSpecEntryKey::deserialize(
+                                StrDeserializer::<M::Error>::new(name),
+                            )?;
+                            entries.push((key, access.next_value::<Stringified>()?));
+                        }
+                    }
+                }
+
+                let Some(axes) = matrix else {
+                    if exclude.is_some() {
+                        return Err(serde::de::Error::custom(
+                            "exclude can only be used in a variant matrix",
+                        ));
+                    }
+                    return Ok(VariantSpecOrMatrix::Spec(VariantSpec { entries }));
+                };
+                if let Some((key, _)) = entries.first() {
+                    return Err(serde::de::Error::custom(format!(
+                        "unexpected field in variant matrix: {key}, expected only 'matrix' and 'exclude'",
+                    )));
+                }
+                VariantMatrix::new(axes, exclude.unwrap_or_default())
+                    .map(VariantSpecOrMatrix::Matrix)
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_map(VariantSpecOrMatrixVisitor)
+    }
+}
+
+impl Serialize for VariantSpecOrMatrix {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Spec(spec) => spec.serialize(serializer),
+            Self::Matrix(matrix) => matrix.serialize(serializer),
+        }
+    }
+}
+
+/// A set of build variants given as every combination of some
+/// option values, eg: `{matrix: {python: [2.7, 3.7], gcc: [6.3, 9.3]}}`.
+///
+/// Combinations are generated in the order that the options and values
+/// are listed, with the last option changing the fastest. Any combination
+/// that contains all of the entries of an `exclude` item is dropped.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct VariantMatrix {
+    axes: Vec<(VariantSpecEntryKey, Vec<Stringified>)>,
+    exclude: Vec<VariantSpec>,
+}
+
+impl VariantMatrix {
+    /// Create a matrix from its options and values, and the
+    /// combinations that should be left out.
+    pub fn new(
+        axes: Vec<(VariantSpecEntryKey, Vec<Stringified>)>,
+        exclude: Vec<VariantSpec>,
+    ) -> Result<Self, String> {
+        if axes.is_empty() {
+            return Err("variant matrix must have at least one option".to_string());
+        }
+        for (index, (key, values)) in axes.iter().enumerate() {
+            if values.is_empty() {
+                return Err(format!("variant matrix option has no values: {key}"));
+            }
+            if axes[..index].iter().any(|(other, _)| other == key) {
+                return Err(format!(
+                    "variant matrix option was specified more than once: {key}"
+                ));
+            }
+        }
+        for excluded in exclude.iter() {
+            if excluded.entries.is_empty() {
+                return Err("variant matrix exclude cannot be empty".to_string());
+            }
+            for (key, _) in excluded.entries.iter() {
+                if !axes.iter().any(|(axis, _)| axis == key) {
+                    return Err(format!(
+                        "variant matrix exclude refers to an option not in the matrix: {key}"
+                    ));
+                }
+            }
+        }
+        Ok(Self { axes, exclude })
+    }
+
+    /// The options of the matrix, and the values for each one.
+    pub fn axes(&self) -> &[(VariantSpecEntryKey, Vec<Stringified>)] {
+        &self.axes
+    }
+
+    /// The combinations that are left out of the matrix.
+    pub fn exclude(&self) -> &[VariantSpec] {
+        &self.exclude
+    }
+
+    /// Generate every combination of the matrix that is not excluded.
+    pub fn expand(&self) -> Vec<VariantSpec> {
+        let mut variants = vec![VariantSpec::default()];
+        for (key, values) in self.axes.iter() {
+            variants = variants
+                .into_iter()
+                .flat_map(|variant| {
+                    values.iter().map(move |value| {
+                        let mut variant = variant.clone();
+                        variant.entries.push((key.clone(), value.clone()));
+                        variant
+                    })
+                })
+                .collect();
+        }
+        variants.retain(|variant| !self.is_excluded(variant));
+        variants
+    }
+
+    fn is_excluded(&self, variant: &VariantSpec) -> bool {
+        self.exclude.iter().any(|excluded| {
+            excluded
+                .entries
+                .iter()
+                .all(|entry| variant.entries.contains(entry))
+        })
+    }
+}
+
+impl Serialize for VariantMatrix {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let len = if self.exclude.is_empty() { 1 } else { 2 };
+        let mut map = serializer.serialize_map(Some(len))?;
+        map.serialize_entry("matrix", &MatrixAxesRef(&self.axes))?;
+        if !self.exclude.is_empty() {
+            map.serialize_entry("exclude", &self.exclude)?;
+        }
+        map.end()
+    }
+}
+
+/// The options of a variant matrix, in the order they were written
+struct MatrixAxes(Vec<(VariantSpecEntryKey, Vec<Stringified>)>);
+
+impl<'de> Deserialize<'de> for MatrixAxes {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct MatrixAxesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for MatrixAxesVisitor {
+            type Value = MatrixAxes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a mapping of option names to lists of values")
+            }
+
+            fn visit_map<M>(self, mut access: M) -> std::result::Result<Self::Value, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let mut axes = Vec::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((key, values)) =
+                    access.next_entry::<VariantSpecEntryKey, Vec<Stringified>>()?
+                {
+                    axes.push((key, values));
+                }
+                Ok(MatrixAxes(axes))
+            }
+        }
+
+        deserializer.deserialize_map(MatrixAxesVisitor)
+    }
+}
+
+struct MatrixAxesRef<'a>(&'a [(VariantSpecEntryKey, Vec<Stringified>)]);
+
+impl Serialize for MatrixAxesRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(key, values)| (key, values)))
+    }
+}
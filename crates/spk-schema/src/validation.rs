@@ -5,6 +5,7 @@
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Serialize};
 use spk_schema_foundation::name::{PkgName, PkgNameBuf};
+use spk_schema_foundation::spec_ops::FileMatcher;
 
 #[cfg(test)]
 #[path = "./validation_test.rs"]
@@ -227,6 +228,147 @@ pub enum ValidationMatcher {
         packages: Vec<PkgNameBuf>,
     },
     SpdxLicense,
+    /// Files that are larger than the limit, or [`DEFAULT_LARGE_FILE_LIMIT`]
+    LargeFiles {
+        limit: Option<FileSize>,
+        paths: Option<FileMatcher>,
+    },
+    /// Files whose contents include the absolute path
+    /// that the package was built into
+    EmbeddedPrefix {
+        paths: Option<FileMatcher>,
+    },
+    /// Files with the setuid or setgid permission bits
+    SetuidFiles {
+        paths: Option<FileMatcher>,
+    },
+    /// Symlinks whose target does not exist
+    BrokenSymlinks {
+        paths: Option<FileMatcher>,
+    },
+    /// Shared libraries that no longer export all of the symbols of
+    /// the previous version of the package, even though this version
+    /// claims to be binary compatible with it
+    ///
+    /// Any rule for this condition also saves the exported symbols of
+    /// the package's libraries alongside the other build metadata.
+    RemovedSymbols {
+        paths: Option<FileMatcher>,
+    },
+}
+
+impl ValidationMatcher {
+    /// The files that this matcher is limited to, if any
+    pub fn paths(&self) -> Option<&FileMatcher> {
+        match self {
+            Self::LargeFiles { paths, .. }
+            | Self::EmbeddedPrefix { paths }
+            | Self::SetuidFiles { paths }
+            | Self::BrokenSymlinks { paths }
+            | Self::RemovedSymbols { paths } => paths.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// The size above which files are matched by [`ValidationMatcher::LargeFiles`]
+/// when no other limit is given
+pub const DEFAULT_LARGE_FILE_LIMIT: FileSize = FileSize(1024 * 1024 * 1024);
+
+/// A number of bytes, which can be written with a K, M, G or T
+/// suffix in the spec file (eg: 500M, 2GiB)
+///
+/// Suffixes are always treated as powers of 1024.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FileSize(pub u64);
+
+const FILE_SIZE_UNITS: [(char, u64); 4] = [
+    ('T', 1024 * 1024 * 1024 * 1024),
+    ('G', 1024 * 1024 * 1024),
+    ('M', 1024 * 1024),
+    ('K', 1024),
+];
+
+impl std::fmt::Display for FileSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (suffix, multiplier) in FILE_SIZE_UNITS {
+            if self.0 >= multiplier && self.0 % multiplier == 0 {
+                return write!(f, "{}{suffix}", self.0 / multiplier);
+            }
+        }
+        self.0.fmt(f)
+    }
+}
+
+impl std::str::FromStr for FileSize {
+    type Err = crate::Error;
+
+    fn from_str(value: &str) -> crate::Result<Self> {
+        let trimmed = value.trim();
+        let number = trimmed
+            .strip_suffix(['B', 'b'])
+            .unwrap_or(trimmed)
+            .trim_end_matches(['i', 'I']);
+        let (number, multiplier) = match number.char_indices().last() {
+            Some((i, c)) => match FILE_SIZE_UNITS
+                .iter()
+                .find(|(suffix, _)| suffix.eq_ignore_ascii_case(&c))
+            {
+                Some((_, multiplier)) => (&number[..i], *multiplier),
+                None => (number, 1),
+            },
+            None => (number, 1),
+        };
+        let invalid = || {
+            crate::Error::String(format!(
+                "Invalid file size '{value}', expected a number like 500M or 2G"
+            ))
+        };
+        let number: u64 = number.trim().parse().map_err(|_| invalid())?;
+        number.checked_mul(multiplier).map(Self).ok_or_else(invalid)
+    }
+}
+
+impl<'de> Deserialize<'de> for FileSize {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct FileSizeVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for FileSizeVisitor {
+            type Value = FileSize;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a number of bytes, or a size like 500M")
+            }
+
+            fn visit_u64<E>(self, value: u64) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Ok(FileSize(value))
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(FileSizeVisitor)
+    }
+}
+
+impl Serialize for FileSize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
 }
 
 #[derive(
@@ -368,8 +510,50 @@ impl<'de> Deserialize<'de> for ValidationRule {
                         Ok(ValidationMatcher::CollectExistingFiles { packages })
                     }
                     Kind::RecursiveBuild => Ok(ValidationMatcher::RecursiveBuild),
+                    Kind::LargeFiles => {
+                        let mut limit = None;
+                        let mut paths = None;
+                        while let Some(name) = map.next_key::<String>()? {
+                            match name.as_str() {
+                                "limit" => limit = Some(map.next_value()?),
+                                "paths" => paths = Some(map.next_value()?),
+                                unknown => {
+                                    return Err(serde::de::Error::unknown_field(
+                                        unknown,
+                                        &["limit", "paths"],
+                                    ));
+                                }
+                            }
+                        }
+                        Ok(ValidationMatcher::LargeFiles { limit, paths })
+                    }
+                    Kind::EmbeddedPrefix => Ok(ValidationMatcher::EmbeddedPrefix {
+                        paths: Self::deserialize_paths(map)?,
+                    }),
+                    Kind::SetuidFiles => Ok(ValidationMatcher::SetuidFiles {
+                        paths: Self::deserialize_paths(map)?,
+                    }),
+                    Kind::BrokenSymlinks => Ok(ValidationMatcher::BrokenSymlinks {
+                        paths: Self::deserialize_paths(map)?,
+                    }),
+                    Kind::RemovedSymbols => Ok(ValidationMatcher::RemovedSymbols {
+                        paths: Self::deserialize_paths(map)?,
+                    }),
                 }
             }
+
+            fn deserialize_paths<A>(mut map: A) -> Result<Option<FileMatcher>, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let Some((name, value)) = map.next_entry::<String, FileMatcher>()? else {
+                    return Ok(None);
+                };
+                if name != "paths" {
+                    return Err(serde::de::Error::unknown_field(&name, &["paths"]));
+                }
+                Ok(Some(value))
+            }
         }
 
         deserializer.deserialize_map(ValidationRuleVisitor)
@@ -411,6 +595,22 @@ impl Serialize for ValidationRule {
                     map.serialize_entry("packages", packages)?;
                 }
             }
+            ValidationMatcher::LargeFiles { limit, paths } => {
+                if let Some(limit) = limit {
+                    map.serialize_entry("limit", limit)?;
+                }
+                if let Some(paths) = paths {
+                    map.serialize_entry("paths", paths)?;
+                }
+            }
+            ValidationMatcher::EmbeddedPrefix { paths }
+            | ValidationMatcher::SetuidFiles { paths }
+            | ValidationMatcher::BrokenSymlinks { paths }
+            | ValidationMatcher::RemovedSymbols { paths } => {
+                if let Some(paths) = paths {
+                    map.serialize_entry("paths", paths)?;
+                }
+            }
         }
         map.end()
     }
@@ -16,3 +16,37 @@ fn test_validation_rule_expansion() {
         }
     }));
 }
+
+#[rstest::rstest]
+#[case("500", 500)]
+#[case("2K", 2 * 1024)]
+#[case("500MB", 500 * 1024 * 1024)]
+#[case("1GiB", 1024 * 1024 * 1024)]
+fn test_file_size_parsing(#[case] source: &str, #[case] expected: u64) {
+    let size: super::FileSize = source.parse().unwrap();
+    assert_eq!(size.0, expected);
+}
+
+#[test]
+fn test_file_size_invalid() {
+    "lots"
+        .parse::<super::FileSize>()
+        .expect_err("should fail to parse");
+}
+
+#[test]
+fn test_validation_rule_file_options_roundtrip() {
+    let source = "{rules: [{deny: LargeFiles, limit: 500M, paths: [/lib]}, {allow: SetuidFiles, paths: [/bin/tool]}]}";
+    let spec: ValidationSpec = serde_yaml::from_str(source).unwrap();
+    let rules = unsafe { spec.unexpanded_rules() };
+    match rules[0].condition() {
+        super::ValidationMatcher::LargeFiles { limit, paths } => {
+            assert_eq!(limit, &Some(super::FileSize(500 * 1024 * 1024)));
+            assert_eq!(paths.as_ref().unwrap().rules(), &vec!["/lib".to_string()]);
+        }
+        other => panic!("expected a large files rule, got {other:?}"),
+    }
+    let serialized = serde_yaml::to_string(&spec).unwrap();
+    let reparsed: ValidationSpec = serde_yaml::from_str(&serialized).unwrap();
+    assert_eq!(reparsed, spec);
+}
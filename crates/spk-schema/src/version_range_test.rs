@@ -74,6 +74,24 @@ fn test_parse_version_range_tilde() {
 #[case("=1.0.0", "1.0.0+r.1", true)]
 #[case("==1.0.0", "1.0.0+r.1", false)]
 #[case("=1.0.0+r.2", "1.0.0+r.1", false)]
+#[case(">=1.0.0", "1!0.1.0", true)]
+#[case("<2.0.0", "1!0.1.0", false)]
+#[case("^1!1.0.0", "1!1.4.0", true)]
+#[case("^1!1.0.0", "1.4.0", false)]
+#[case("^1!1.0.0", "1!2.0.0", false)]
+#[case("~1!1.0", "1!1.0.5", true)]
+#[case("=1!1.0.0", "1.0.0", false)]
+#[case("!=1!1.0", "1.0.0", true)]
+#[case("1.*", "1!1.0.0", false)]
+#[case(">=1.0.0", "1.0.0.dev1", false)]
+#[case("<1.0.0", "1.0.0.dev1", true)]
+#[case(">1.0.0", "1.0.0.post1", true)]
+#[case("=1.0.0", "1.0.0.dev1", false)]
+#[case("=1.0.0.dev1", "1.0.0.dev1", true)]
+#[case("==1.0.0+post.1", "1.0.0.post1", true)]
+#[case("!=1.0.0", "1.0.0.dev1", true)]
+#[case("!=1.0.0.dev1", "1.0.0.dev1", false)]
+#[case("~1.0.dev1", "1.0.0", true)]
 fn test_version_range_is_applicable(
     #[case] range: &str,
     #[case] version: &str,
@@ -161,6 +179,9 @@ fn test_version_range_is_satisfied_recipe(
 #[case("Binary:5.12.2.1", spec!({"pkg": "test/5.12.2/JRSXNRF4", "compat": "x.x.ab"}), false)]
 // asking for "3.10" is expected to allow "3.10.10"
 #[case::under_specified_version_and_x_compat("API:3.10", spec!({"pkg": "test/3.10.10/JRSXNRF4", "compat": "x.x.x"}), true)]
+// versions from a newer epoch are not compatible with older ones
+#[case("API:1.0.0", spec!({"pkg": "test/1!1.0.0/JRSXNRF4", "compat": "x.x.x"}), false)]
+#[case("API:1!1.0.0", spec!({"pkg": "test/1!1.2.0/JRSXNRF4", "compat": "x.a.b"}), true)]
 fn test_version_range_is_satisfied_spec(
     #[case] range: &str,
     #[case] spec: Spec,
@@ -224,12 +245,14 @@ fn arb_version_min_len(min_len: usize) -> impl Strategy<Value = Version> {
         arb_tagset(),
     )
         .prop_map(|(parts, pre, post)| Version {
+            epoch: 0,
             parts: VersionParts {
                 parts,
                 plus_epsilon: false,
             },
             pre,
             post,
+            dev: None,
         })
 }
 
@@ -266,6 +289,7 @@ fn arb_lowest_specified_range_from_version(
                     VersionRange::LowestSpecified(LowestSpecifiedRange::new(
                         parts_to_generate,
                         Version {
+                            epoch: version.epoch,
                             parts: version
                                 .parts
                                 .iter()
@@ -285,6 +309,7 @@ fn arb_lowest_specified_range_from_version(
                             // the smallest value we generated without it.
                             pre: version.pre,
                             post: version.post,
+                            dev: version.dev,
                         },
                     ))
                 })
@@ -324,6 +349,7 @@ fn arb_semver_range_from_version(version: Version) -> impl Strategy<Value = Vers
                 |(version, parts_to_generate, values_to_use)| {
                     let mut found_non_zero = false;
                     VersionRange::Semver(SemverRange::new(Version {
+                        epoch: version.epoch,
                         parts: version
                             .parts
                             .iter()
@@ -346,6 +372,7 @@ fn arb_semver_range_from_version(version: Version) -> impl Strategy<Value = Vers
                         // the smallest value we generated without it.
                         pre: version.pre,
                         post: version.post,
+                        dev: version.dev,
                     }))
                 },
             )
@@ -20,7 +20,14 @@ use spk_schema::foundation::name::{OptNameBuf, PkgName, PkgNameBuf};
 use spk_schema::foundation::option_map;
 use spk_schema::foundation::option_map::OptionMap;
 use spk_schema::foundation::version::Compatibility;
-use spk_schema::ident::{InclusionPolicy, PkgRequest, Request, RequestedBy, VarRequest};
+use spk_schema::ident::{
+    AnyOfRequest,
+    InclusionPolicy,
+    PkgRequest,
+    Request,
+    RequestedBy,
+    VarRequest,
+};
 use spk_schema::prelude::*;
 use spk_schema::{
     AnyIdent,
@@ -30,6 +37,7 @@ use spk_schema::{
     RequirementsList,
     Spec,
     SpecRecipe,
+    VersionIdent,
 };
 use spk_solve_package_iterator::{PackageIterator, PromotionPatterns};
 use spk_solve_solution::{PackageSource, Solution};
@@ -71,6 +79,10 @@ pub type Result<T> = std::result::Result<T, GraphError>;
 pub enum Change {
     RequestPackage(RequestPackage),
     RequestVar(RequestVar),
+    /// Adds a request that can be satisfied by any one of several
+    /// packages. The solver chooses between them once all the other
+    /// package requests have been resolved.
+    RequestAnyOf(RequestAnyOf),
     SetOptions(SetOptions),
     /// Adds a package to the solution. The package must have already been
     /// checked that it is compatible with the current solution and valid to
@@ -85,6 +97,7 @@ impl Change {
         match self {
             Change::RequestPackage(rp) => rp.apply(parent, base),
             Change::RequestVar(rv) => rv.apply(parent, base),
+            Change::RequestAnyOf(ra) => ra.apply(parent, base),
             Change::SetOptions(so) => so.apply(parent, base),
             Change::SetPackage(sp) => sp.apply(parent, base),
             Change::SetPackageBuild(spb) => spb.apply(parent, base),
@@ -145,6 +158,14 @@ impl FormatChange for Change {
                     }
                 )
             }
+            RequestAnyOf(c) => {
+                format!(
+                    "{} {} {}",
+                    Self::get_request_change_label(format_settings.level).blue(),
+                    "ANY OF".blue(),
+                    c.request
+                )
+            }
             SetPackageBuild(c) => {
                 format!("{} {}", "BUILD".yellow(), c.spec.ident().format_ident())
             }
@@ -166,7 +187,7 @@ impl FormatChange for Change {
                                             .to_string()]
                                     }
                                     PackageSource::Embedded { parent } => {
-                                        vec![RequestedBy::Embedded(parent.clone()).to_string()]
+                                        vec![s.embedded_requester(parent).to_string()]
                                     }
                                     _ => {
                                         // Don't think this should happen
@@ -283,6 +304,7 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
                 .extend(self.requirements_to_changes(&spec.runtime_requirements(), &requested_by));
             changes.extend(self.components_to_changes(spec.components(), requester_ident));
             changes.extend(self.embedded_to_changes(spec.embedded(), requester_ident));
+            changes.extend(self.provided_to_changes(spec.provides(), requester_ident));
             changes.push(Self::options_to_change(spec));
 
             Ok(changes)
@@ -306,6 +328,7 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
         changes.extend(self.requirements_to_changes(&spec.runtime_requirements(), &requested_by));
         changes.extend(self.components_to_changes(spec.components(), requester_ident));
         changes.extend(self.embedded_to_changes(spec.embedded(), requester_ident));
+        changes.extend(self.provided_to_changes(spec.provides(), requester_ident));
         changes.push(Self::options_to_change(&spec));
 
         changes
@@ -318,6 +341,15 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
         }
     }
 
+    /// Create a new decision to satisfy a request for any one of
+    /// several packages by requesting one of its alternatives.
+    pub fn choose_alternative(self, alternative: &PkgRequest) -> Decision {
+        Decision {
+            changes: self.pkg_request_to_changes(alternative),
+            notes: Vec::default(),
+        }
+    }
+
     /// Make this package the next request to be considered.
     pub fn reconsider_package(
         self,
@@ -353,7 +385,7 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
         requested_by: &RequestedBy,
     ) -> Vec<Change> {
         requirements
-            .iter()
+            .iter_by_priority()
             .flat_map(|req| match req {
                 Request::Pkg(req) => {
                     let mut req = req.clone();
@@ -361,6 +393,11 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
                     self.pkg_request_to_changes(&req)
                 }
                 Request::Var(req) => vec![Change::RequestVar(RequestVar::new(req.clone()))],
+                Request::AnyOf(req) => {
+                    let mut req = req.clone();
+                    req.add_requester(requested_by.clone());
+                    vec![Change::RequestAnyOf(RequestAnyOf::new(req))]
+                }
             })
             .collect()
     }
@@ -444,7 +481,7 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
                 let mut changes = vec![Change::RequestPackage(RequestPackage::new(
                     PkgRequest::from_ident(
                         embedded.ident().to_any(),
-                        RequestedBy::Embedded(parent.clone()),
+                        self.base.embedded_requester(parent),
                     ),
                 ))];
                 changes.extend(self.set_package(
@@ -458,6 +495,16 @@ impl<'state, 'cmpt> DecisionBuilder<'state, 'cmpt> {
             .collect()
     }
 
+    /// Provided packages are added to the solve as stubs, in the same
+    /// way as embedded packages, so that requests for them are satisfied
+    /// by the package that provides them.
+    fn provided_to_changes(&self, provides: &[VersionIdent], parent: &BuildIdent) -> Vec<Change> {
+        if provides.is_empty() {
+            return Vec::new();
+        }
+        self.embedded_to_changes(&EmbeddedPackagesList::from_provided(provides), parent)
+    }
+
     fn options_to_change(spec: &Spec) -> Change {
         let mut opts = OptionMap::default();
         opts.insert(
@@ -707,6 +754,11 @@ impl Node {
         Ok(())
     }
 
+    /// True if a decision from this node has already led to the given state.
+    pub fn has_output(&self, state: &State) -> bool {
+        self.outputs.contains(&state.id())
+    }
+
     pub fn get_iterator(
         &self,
         package_name: &PkgName,
@@ -875,10 +927,12 @@ impl RequestPackage {
             // next selected by the solver.
             new_requests.push(Arc::new(self.request.clone().into()));
 
-            // Apply the configured request priority ordering to the request
-            // list.
-            REQUESTS_PRIORITY_ORDER
-                .promote_names(new_requests.as_mut_slice(), |req| req.pkg.name().as_str());
+            // Apply the configured request priority ordering, and the
+            // priority of the requests themselves, to the request list.
+            order_requests_by_priority(&mut new_requests);
+        } else if self.request.priority != 0 {
+            // The merged request may have been given a higher priority
+            order_requests_by_priority(&mut new_requests);
         }
 
         if self.prioritize {
@@ -928,6 +982,57 @@ impl RequestVar {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct RequestAnyOf {
+    pub request: AnyOfRequest,
+}
+
+impl RequestAnyOf {
+    pub fn new(request: AnyOfRequest) -> Self {
+        RequestAnyOf { request }
+    }
+
+    pub fn apply(&self, parent: &Arc<State>, base: &Arc<State>) -> Arc<State> {
+        let mut new_requests = Arc::clone(&base.any_of_requests);
+        // Avoid adding duplicate requests, which only differ by requester.
+        let duplicate = base.any_of_requests.iter().any(|existing| {
+            existing.alternatives.iter().map(|a| &a.pkg).eq(self
+                .request
+                .alternatives
+                .iter()
+                .map(|a| &a.pkg))
+        });
+        if !duplicate {
+            Arc::make_mut(&mut new_requests).push(self.request.clone());
+        }
+        Arc::new(base.with_any_of_requests(parent, new_requests))
+    }
+}
+
+/// Order a request list by the configured request priority order and
+/// then by the explicit priority of the requests.
+///
+/// Packages that match an earlier pattern of the `request_priority_order`
+/// in the spk config come first, and packages that match the same pattern,
+/// or none, are ordered by the highest priority of any of their requests.
+/// Both are decided per package, so the sort being stable keeps all of the
+/// requests for one package in the order that they were made, and keeps
+/// the requests for the same package as a new request at the front of any
+/// other packages with the same priority.
+fn order_requests_by_priority(requests: &mut [Arc<CachedHash<PkgRequest>>]) {
+    let mut priorities: HashMap<PkgNameBuf, i64> = HashMap::new();
+    for req in requests.iter().filter(|req| req.priority != 0) {
+        let priority = priorities.entry(req.pkg.name.clone()).or_default();
+        *priority = (*priority).max(req.priority);
+    }
+    requests.sort_by_cached_key(|req| {
+        (
+            REQUESTS_PRIORITY_ORDER.promotion_index(req.pkg.name().as_str()),
+            std::cmp::Reverse(priorities.get(req.pkg.name()).copied().unwrap_or_default()),
+        )
+    });
+}
+
 #[derive(Clone, Debug)]
 pub struct SetOptions {
     pub options: OptionMap,
@@ -1012,6 +1117,7 @@ pub struct StateId {
     var_requests_hash: u64,
     // A set of what `VarRequest` hashes exist in this `StateId`.
     var_requests_membership: Arc<HashSet<u64>>,
+    any_of_requests_hash: u64,
     packages_hash: u64,
     options_hash: u64,
     full_hash: u64,
@@ -1027,6 +1133,7 @@ impl StateId {
         pkg_requests_hash: u64,
         var_requests_hash: u64,
         var_requests_membership: Arc<HashSet<u64>>,
+        any_of_requests_hash: u64,
         packages_hash: u64,
         options_hash: u64,
     ) -> Self {
@@ -1034,6 +1141,7 @@ impl StateId {
             let mut hasher = DefaultHasher::new();
             pkg_requests_hash.hash(&mut hasher);
             var_requests_hash.hash(&mut hasher);
+            any_of_requests_hash.hash(&mut hasher);
             packages_hash.hash(&mut hasher);
             options_hash.hash(&mut hasher);
             hasher.finish()
@@ -1042,6 +1150,7 @@ impl StateId {
             pkg_requests_hash,
             var_requests_hash,
             var_requests_membership,
+            any_of_requests_hash,
             packages_hash,
             options_hash,
             full_hash,
@@ -1060,6 +1169,12 @@ impl StateId {
         hasher.finish()
     }
 
+    fn any_of_requests_hash(any_of_requests: &[AnyOfRequest]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        any_of_requests.hash(&mut hasher);
+        hasher.finish()
+    }
+
     fn packages_hash(packages: &StatePackages) -> u64 {
         let mut hasher = DefaultHasher::new();
         for (spec, _, _) in packages.values() {
@@ -1089,6 +1204,7 @@ impl StateId {
             self.pkg_requests_hash,
             self.var_requests_hash,
             Arc::clone(&self.var_requests_membership),
+            self.any_of_requests_hash,
             self.packages_hash,
             StateId::options_hash(options),
         )
@@ -1099,6 +1215,7 @@ impl StateId {
             StateId::pkg_requests_hash(pkg_requests),
             self.var_requests_hash,
             Arc::clone(&self.var_requests_membership),
+            self.any_of_requests_hash,
             self.packages_hash,
             self.options_hash,
         )
@@ -1109,6 +1226,7 @@ impl StateId {
             self.pkg_requests_hash,
             self.var_requests_hash,
             Arc::clone(&self.var_requests_membership),
+            self.any_of_requests_hash,
             StateId::packages_hash(packages),
             self.options_hash,
         )
@@ -1124,10 +1242,22 @@ impl StateId {
             self.pkg_requests_hash,
             var_requests_hash,
             Arc::new(var_requests_membership),
+            self.any_of_requests_hash,
             self.packages_hash,
             StateId::options_hash(options),
         )
     }
+
+    fn with_any_of_requests(&self, any_of_requests: &[AnyOfRequest]) -> Self {
+        Self::new(
+            self.pkg_requests_hash,
+            self.var_requests_hash,
+            Arc::clone(&self.var_requests_membership),
+            StateId::any_of_requests_hash(any_of_requests),
+            self.packages_hash,
+            self.options_hash,
+        )
+    }
 }
 
 /// For caching the hash of an `PkgRequest`.
@@ -1181,6 +1311,7 @@ type StatePackages = Arc<
 pub struct State {
     pkg_requests: Arc<Vec<Arc<CachedHash<PkgRequest>>>>,
     var_requests: Arc<BTreeSet<VarRequest>>,
+    any_of_requests: Arc<Vec<AnyOfRequest>>,
     packages: StatePackages,
     // A list of the packages in the order they were resolved and
     // added to the state. It differs from the "packages" field in
@@ -1219,12 +1350,14 @@ impl State {
             StateId::pkg_requests_hash(&pkg_requests),
             var_requests_hash,
             Arc::new(var_requests_membership),
+            StateId::any_of_requests_hash(&[]),
             0,
             StateId::options_hash(&options),
         );
         let mut s = State {
             pkg_requests: Arc::new(pkg_requests),
             var_requests: Arc::new(var_requests),
+            any_of_requests: Arc::new(Vec::new()),
             packages: Arc::new(BTreeMap::new()),
             packages_in_solve_order: Arc::new(Vec::new()),
             options: Arc::new(options),
@@ -1324,6 +1457,21 @@ impl State {
         }
     }
 
+    /// Describe what requested a package that is embedded in `parent`.
+    ///
+    /// When the parent was itself requested in this state, its requesters
+    /// are included so that the chain back to the original request can be
+    /// reported.
+    pub fn embedded_requester(&self, parent: &BuildIdent) -> RequestedBy {
+        match self.get_merged_request(parent.name()) {
+            Ok(request) => RequestedBy::EmbeddedChain {
+                parent: parent.clone(),
+                parent_requested_by: request.get_requesters(),
+            },
+            Err(_) => RequestedBy::Embedded(parent.clone()),
+        }
+    }
+
     pub fn get_next_request(&self) -> Result<Option<PkgRequest>> {
         // Note: The next request this returns may not be as expected
         // due to the interaction of multiple requests and
@@ -1361,6 +1509,21 @@ impl State {
         &self.var_requests
     }
 
+    pub fn get_any_of_requests(&self) -> &Vec<AnyOfRequest> {
+        &self.any_of_requests
+    }
+
+    /// Return the first request for any one of several packages that
+    /// is not yet satisfied by a resolved package.
+    pub fn get_next_any_of_request(&self) -> Option<&AnyOfRequest> {
+        self.any_of_requests.iter().find(|request| {
+            !self
+                .packages
+                .values()
+                .any(|(spec, _, _)| request.satisfied_alternative(&***spec).is_some())
+        })
+    }
+
     /// Get a mapping of pkg name -> merged request for the unresolved
     /// PkgRequests in this state
     pub fn get_unresolved_requests(&self) -> &HashMap<PkgNameBuf, PkgRequest> {
@@ -1402,6 +1565,7 @@ impl State {
         Self {
             pkg_requests: Arc::clone(&self.pkg_requests),
             var_requests: Arc::clone(&self.var_requests),
+            any_of_requests: Arc::clone(&self.any_of_requests),
             packages: Arc::clone(&self.packages),
             packages_in_solve_order: Arc::clone(&self.packages_in_solve_order),
             options: Arc::new(options),
@@ -1431,6 +1595,7 @@ impl State {
         Self {
             pkg_requests: Arc::clone(&self.pkg_requests),
             var_requests: Arc::clone(&self.var_requests),
+            any_of_requests: Arc::clone(&self.any_of_requests),
             packages,
             packages_in_solve_order,
             options: Arc::clone(&self.options),
@@ -1452,6 +1617,7 @@ impl State {
         Self {
             pkg_requests: Arc::new(pkg_requests),
             var_requests: Arc::clone(&self.var_requests),
+            any_of_requests: Arc::clone(&self.any_of_requests),
             packages: Arc::clone(&self.packages),
             packages_in_solve_order: Arc::clone(&self.packages_in_solve_order),
             options: Arc::clone(&self.options),
@@ -1476,6 +1642,7 @@ impl State {
         Self {
             pkg_requests: Arc::clone(&self.pkg_requests),
             var_requests,
+            any_of_requests: Arc::clone(&self.any_of_requests),
             packages: Arc::clone(&self.packages),
             packages_in_solve_order: Arc::clone(&self.packages_in_solve_order),
             options: Arc::new(options),
@@ -1488,6 +1655,24 @@ impl State {
         }
     }
 
+    fn with_any_of_requests(&self, parent: &Self, any_of_requests: Arc<Vec<AnyOfRequest>>) -> Self {
+        let state_id = self.state_id.with_any_of_requests(&any_of_requests);
+        Self {
+            pkg_requests: Arc::clone(&self.pkg_requests),
+            var_requests: Arc::clone(&self.var_requests),
+            any_of_requests,
+            packages: Arc::clone(&self.packages),
+            packages_in_solve_order: Arc::clone(&self.packages_in_solve_order),
+            options: Arc::clone(&self.options),
+            state_id,
+            // options are the same
+            cached_option_map: Arc::clone(&self.cached_option_map),
+            state_depth: parent.state_depth + 1,
+            // unresolved pkg requests are the same
+            cached_unresolved_pkg_requests: Arc::clone(&self.cached_unresolved_pkg_requests),
+        }
+    }
+
     pub fn get_option_map(&self) -> &OptionMap {
         self.cached_option_map
             .get_or_init(|| (&self.options).into())
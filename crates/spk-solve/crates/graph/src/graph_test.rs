@@ -9,11 +9,12 @@ use spk_schema::foundation::format::{FormatChange, FormatChangeOptions};
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::name::PkgName;
 use spk_schema::foundation::{opt_name, option_map};
+use spk_schema::ident::{parse_ident_range, PkgRequest, RequestedBy};
 use spk_schema::{recipe, spec};
 use spk_solve_solution::PackageSource;
 
 use super::DecisionBuilder;
-use crate::{graph, Decision};
+use crate::{graph, Decision, RequestPackage};
 
 #[rstest]
 fn test_resolve_build_same_result() {
@@ -139,3 +140,69 @@ fn test_request_default_component() {
         "default component should be injected when none specified"
     );
 }
+
+#[rstest]
+fn test_request_priority_order() {
+    let spec = Arc::new(spec!({
+        "pkg": "parent/1.0.0/3I42H3S6",
+        "install": {
+          "requirements": [
+            {"pkg": "first/1.0.0"},
+            {"pkg": "second/1.0.0"},
+            {"pkg": "heavy/1.0.0", "priority": 10},
+          ]
+        }
+    }));
+    let base = std::sync::Arc::new(super::State::default_state());
+
+    let state = DecisionBuilder::new(&base)
+        .resolve_package(&spec, PackageSource::SpkInternalTest)
+        .apply(&base);
+    let names: Vec<_> = state
+        .get_pkg_requests()
+        .iter()
+        .map(|r| r.pkg.name.to_string())
+        .collect();
+    assert_eq!(
+        names,
+        ["heavy", "first", "second"],
+        "requests with a higher priority should come first, then in declared order"
+    );
+    let next = state.get_next_request().unwrap().unwrap();
+    assert_eq!(next.pkg.name.as_str(), "heavy");
+}
+
+#[rstest]
+fn test_request_priority_keeps_same_package_order() {
+    let spec = Arc::new(spec!({
+        "pkg": "parent/1.0.0/3I42H3S6",
+        "install": {
+          "requirements": [
+            {"pkg": "other/1.0.0"},
+            {"pkg": "lib/1.0.0"},
+          ]
+        }
+    }));
+    let base = std::sync::Arc::new(super::State::default_state());
+    let state = DecisionBuilder::new(&base)
+        .resolve_package(&spec, PackageSource::SpkInternalTest)
+        .apply(&base);
+
+    // a request that cannot be merged into the existing one for lib
+    let request = PkgRequest::new(
+        parse_ident_range("lib/2.0.0").unwrap(),
+        RequestedBy::SpkInternalTest,
+    )
+    .with_priority(10);
+    let state = RequestPackage::new(request).apply(&state, &state);
+    let requests: Vec<_> = state
+        .get_pkg_requests()
+        .iter()
+        .map(|r| format!("{}/{}", r.pkg.name, r.pkg.version))
+        .collect();
+    assert_eq!(
+        requests,
+        ["lib/1.0.0", "lib/2.0.0", "other/1.0.0"],
+        "the priority should move the package ahead without reordering its own requests"
+    );
+}
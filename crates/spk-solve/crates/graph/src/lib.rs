@@ -21,6 +21,7 @@ pub use graph::{
     GraphError,
     Node,
     Note,
+    RequestAnyOf,
     RequestPackage,
     RequestVar,
     SetOptions,
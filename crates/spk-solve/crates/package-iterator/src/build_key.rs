@@ -394,6 +394,8 @@ impl BuildKeyExpandedVersionRange {
 /// { digits: [6, 4, 0], posttag: Some(['r', 2]), pretag: None }
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 struct BuildKeyVersionNumber {
+    /// The epoch of the version, which outranks all of its digits
+    epoch: u32,
     /// The major, minor, patch, and tail digits, e.g. [6, 4, 0]
     digits: Vec<BuildKeyVersionNumberPiece>,
     /// If the version in `digits` should be treated as infinitesimally larger
@@ -406,10 +408,17 @@ struct BuildKeyVersionNumber {
     notags: bool,
     /// Any pre-release tag pieces, e.g. Some(['r', 2]) or None
     pretag: Option<Vec<BuildKeyVersionNumberPiece>>,
+    /// Marker for a version that is not a development release,
+    /// followed by the development release number, if any, so that
+    /// development releases come before the version they extend.
+    notdev: (bool, u32),
 }
 
 impl std::fmt::Display for BuildKeyVersionNumber {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
         f.write_str(
             &self
                 .digits
@@ -441,6 +450,10 @@ impl std::fmt::Display for BuildKeyVersionNumber {
                     .join("."),
             )?;
         }
+
+        if !self.notdev.0 {
+            write!(f, ".dev{}", self.notdev.1)?;
+        }
         f.write_str("")
     }
 }
@@ -490,18 +503,22 @@ impl BuildKeyVersionNumber {
             Some(pretags)
         };
 
-        let notags = pretag.is_none() && posttag.is_none();
+        // development releases without other tags come before all the
+        // pre-releases of their version, as well as the version itself
+        let notags = pretag.is_none() && posttag.is_none() && v.dev.is_none();
 
-        // Combine the pieces in a form suitable for sorting. Digits
-        // are first as the most important, then plus_epsilon, then
-        // post tags, then ones with no tags, and finally pre tags
+        // Combine the pieces in a form suitable for sorting. The epoch
+        // and digits are first as the most important, then plus_epsilon,
+        // then post tags, then ones with no tags, and finally pre tags
         // last, i.e.  1.0+e > 1.0+r.1 > 1.0 > 1.0-r.1
         BuildKeyVersionNumber {
+            epoch: v.epoch,
             digits,
             plus_epsilon: v.parts.plus_epsilon,
             posttag,
             notags,
             pretag,
+            notdev: (v.dev.is_none(), v.dev.unwrap_or_default()),
         }
     }
 }
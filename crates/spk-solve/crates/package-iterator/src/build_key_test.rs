@@ -55,6 +55,7 @@ fn make_expanded_version_range_part(
 
     BuildKeyExpandedVersionRange {
         max: BuildKeyVersionNumber {
+            epoch: 0,
             digits: max_digits
                 .iter()
                 .map(|n| BuildKeyVersionNumberPiece::Number(*n))
@@ -63,8 +64,10 @@ fn make_expanded_version_range_part(
             posttag: post_max,
             notags: max_notags,
             pretag: pre_max,
+            notdev: (true, 0),
         },
         min: BuildKeyVersionNumber {
+            epoch: 0,
             digits: min_digits
                 .iter()
                 .map(|n| BuildKeyVersionNumberPiece::Number(*n))
@@ -73,6 +76,7 @@ fn make_expanded_version_range_part(
             posttag: post_min,
             notags: min_notags,
             pretag: pre_min,
+            notdev: (true, 0),
         },
         tie_breaker: BuildKeyExpandedVersionRange::generate_tie_breaker(version),
     }
@@ -116,18 +120,22 @@ fn test_parse_value_to_build_key_extended_version_range(
 #[case("25.0.8-alpha.0,test.1",
        BuildKeyExpandedVersionRange {
            max: BuildKeyVersionNumber {
+               epoch: 0,
                digits: vec![],
                plus_epsilon: false,
                posttag: Some(vec![]),
                notags: true,
                pretag: Some(vec![]),
+               notdev: (true, 0),
            },
            min: BuildKeyVersionNumber {
+               epoch: 0,
                digits: vec![],
                plus_epsilon: false,
                posttag: Some(vec![]),
                notags: true,
                pretag: Some(vec![]),
+               notdev: (true, 0),
            },
            tie_breaker: BuildKeyExpandedVersionRange::generate_tie_breaker("25.0.8-alpha.0,test.1")
        }
@@ -141,18 +149,22 @@ fn test_parse_value_to_build_key_extended_version_range(
 #[case("4.1.0/DIGEST",
        BuildKeyExpandedVersionRange {
            max: BuildKeyVersionNumber {
+               epoch: 0,
                digits: vec![],
                plus_epsilon: false,
                posttag: Some(vec![]),
                notags: true,
                pretag: Some(vec![]),
+               notdev: (true, 0),
            },
            min: BuildKeyVersionNumber {
+               epoch: 0,
                digits: vec![],
                plus_epsilon: false,
                posttag: Some(vec![]),
                notags: true,
                pretag: Some(vec![]),
+               notdev: (true, 0),
            },
            tie_breaker: BuildKeyExpandedVersionRange::generate_tie_breaker("4.1.0/DIGEST")
        }
@@ -164,18 +176,22 @@ fn test_parse_value_to_build_key_extended_version_range(
 #[case("somepkg/4.1.0/DIGEST",
        BuildKeyExpandedVersionRange {
            max: BuildKeyVersionNumber {
+               epoch: 0,
                digits: vec![],
                plus_epsilon: false,
                posttag: Some(vec![]),
                notags: true,
                pretag: Some(vec![]),
+               notdev: (true, 0),
            },
            min: BuildKeyVersionNumber {
+               epoch: 0,
                digits: vec![],
                plus_epsilon: false,
                posttag: Some(vec![]),
                notags: true,
                pretag: Some(vec![]),
+               notdev: (true, 0),
            },
            tie_breaker: BuildKeyExpandedVersionRange::generate_tie_breaker("somepkg/4.1.0/DIGEST")
        }
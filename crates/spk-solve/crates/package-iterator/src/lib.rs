@@ -10,6 +10,7 @@ mod promotion_patterns;
 pub use error::{Error, Result};
 pub use package_iterator::{
     BuildIterator,
+    BuildOrder,
     EmptyBuildIterator,
     PackageIterator,
     RepositoryPackageIterator,
@@ -433,6 +433,19 @@ impl EmptyBuildIterator {
     }
 }
 
+/// The heuristics that can be used to order the builds
+/// of a package version before the solver tries them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BuildOrder {
+    /// Prefer builds based on the values of their build options,
+    /// putting builds that make impossible requests last
+    #[default]
+    OptionValues,
+    /// Try builds in the order that the repositories list them,
+    /// which avoids the cost of sorting versions with many builds
+    RepositoryOrder,
+}
+
 #[derive(Clone, Debug)]
 pub struct SortedBuildIterator {
     builds: VecDeque<BuildWithRepos>,
@@ -470,9 +483,25 @@ struct ChangeCounter {
 
 impl SortedBuildIterator {
     pub async fn new(
+        options: OptionMap,
+        source: Arc<tokio::sync::Mutex<dyn BuildIterator + Send>>,
+        builds_with_impossible_requests: HashMap<BuildIdent, Compatibility>,
+    ) -> Result<Self> {
+        Self::new_with_build_order(
+            options,
+            source,
+            builds_with_impossible_requests,
+            BuildOrder::default(),
+        )
+        .await
+    }
+
+    /// Create a sorted iterator that orders builds using the given heuristic
+    pub async fn new_with_build_order(
         _options: OptionMap,
         source: Arc<tokio::sync::Mutex<dyn BuildIterator + Send>>,
         builds_with_impossible_requests: HashMap<BuildIdent, Compatibility>,
+        build_order: BuildOrder,
     ) -> Result<Self> {
         // Note: _options is unused in this implementation, it was used
         // in the by_distance sorting implementation
@@ -486,8 +515,13 @@ impl SortedBuildIterator {
 
         let mut sbi = SortedBuildIterator { builds };
 
-        sbi.sort_by_build_option_values(builds_with_impossible_requests)
-            .await;
+        match build_order {
+            BuildOrder::OptionValues => {
+                sbi.sort_by_build_option_values(builds_with_impossible_requests)
+                    .await
+            }
+            BuildOrder::RepositoryOrder => {}
+        }
         Ok(sbi)
     }
 
@@ -33,13 +33,15 @@ impl PromotionPatterns {
     where
         F: Fn(&N) -> &str,
     {
-        names.sort_by_cached_key(|name| {
-            self.0
-                .iter()
-                .enumerate()
-                .find(|(_, pattern)| pattern.matches(f(name)))
-                .map(|(index, _)| index)
-                .unwrap_or(usize::MAX)
-        })
+        names.sort_by_cached_key(|name| self.promotion_index(f(name)))
+    }
+
+    /// The index of the first pattern that matches the given name, or
+    /// [`usize::MAX`] if none do, for sorting names in promotion order.
+    pub fn promotion_index(&self, name: &str) -> usize {
+        self.0
+            .iter()
+            .position(|pattern| pattern.matches(name))
+            .unwrap_or(usize::MAX)
     }
 }
@@ -2,9 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use itertools::Itertools;
 use miette::Diagnostic;
 use thiserror::Error;
 
+use crate::EnvConflict;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Diagnostic, Debug, Error)]
@@ -22,6 +25,17 @@ pub enum Error {
     EmbeddedHasNoComponentLayers,
     #[error("Spk internal test has no component layers")]
     SpkInternalTestHasNoComponentLayers,
+    #[error(
+        "Packages in the solution set conflicting environment variables:\n  {}",
+        .0.iter().join("\n  ")
+    )]
+    #[diagnostic(
+        code("spk::solve::conflicting_environment"),
+        help(
+            "Give each package that sets the variable a priority in its install environment to control which value is used"
+        )
+    )]
+    ConflictingEnvironment(Vec<EnvConflict>),
     #[error("Error: {0}")]
     String(String),
 }
@@ -0,0 +1,27 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use spk_schema::ident::{PkgRequest, RequestedBy};
+use spk_schema::{Package, Spec};
+
+use crate::{PackageSource, Solution};
+
+/// Create a solution that contains each of the given package specs.
+///
+/// The packages are requested by [`RequestedBy::SpkInternalTest`]
+/// and do not come from any repository.
+pub fn solution_from_specs<I>(specs: I) -> Solution
+where
+    I: IntoIterator<Item = Spec>,
+{
+    let mut solution = Solution::default();
+    for spec in specs {
+        let spec = Arc::new(spec);
+        let request = PkgRequest::from_ident(spec.ident().to_any(), RequestedBy::SpkInternalTest);
+        solution.add(request, spec, PackageSource::SpkInternalTest);
+    }
+    solution
+}
@@ -3,6 +3,7 @@
 // https://github.com/spkenv/spk
 
 mod error;
+pub mod fixtures;
 mod package_solve_data;
 mod solution;
 
@@ -11,6 +12,7 @@ pub use package_solve_data::{PackageSolveData, PackagesToSolveData, SPK_SOLVE_EX
 pub use solution::{
     find_highest_package_version,
     get_spfs_layers_to_packages,
+    EnvConflict,
     LayerPackageAndComponents,
     PackageSource,
     Solution,
@@ -19,15 +19,28 @@ use spk_schema::foundation::format::{
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::option_map::OptionMap;
 use spk_schema::foundation::version::VERSION_SEP;
-use spk_schema::ident::{PkgRequest, RequestedBy};
+use spk_schema::ident::{PkgRequest, PreReleasePolicy, RangeIdent, RequestedBy};
 use spk_schema::name::{PkgNameBuf, RepositoryNameBuf};
 use spk_schema::prelude::*;
 use spk_schema::version::Version;
-use spk_schema::{BuildEnv, BuildIdent, Package, Spec, SpecRecipe, VersionIdent};
+use spk_schema::{
+    startup_script_stem,
+    BuildEnv,
+    BuildIdent,
+    EnvOp,
+    Package,
+    Spec,
+    SpecRecipe,
+    VersionIdent,
+};
 use spk_storage::RepositoryHandle;
 
 use crate::{Error, PackageSolveData, PackagesToSolveData, Result};
 
+#[cfg(test)]
+#[path = "./solution_test.rs"]
+mod solution_test;
+
 const SOLUTION_FORMAT_EMPTY_REPORT: &str = "Nothing Installed";
 const SOLUTION_FORMAT_HEADING: &str = "Installed Packages:\n";
 const SOLUTION_FORMAT_FOOTER: &str = "Number of Packages:";
@@ -134,6 +147,38 @@ impl SolvedRequest {
         installed_components
     }
 
+    /// The environment operations that are applied for this resolved item.
+    ///
+    /// The operations of the whole package come first, followed by those
+    /// of each installed component in the order that their startup
+    /// scripts are sourced.
+    pub fn runtime_environment(&self) -> Vec<EnvOp> {
+        let mut ops = self.spec.runtime_environment().clone();
+        let installed = self
+            .spec
+            .components()
+            .resolve_uses(self.selected_components().into_iter());
+        let mut components = self
+            .spec
+            .components()
+            .iter()
+            .filter(|component| installed.contains(&component.name))
+            .collect::<Vec<_>>();
+        components.sort_by_cached_key(|component| component.name.to_string());
+        for component in components {
+            ops.extend(component.environment.iter().cloned());
+        }
+        ops
+    }
+
+    /// Create a request that pins this exact package build and
+    /// the components that were selected for it.
+    pub fn to_pinned_request(&self, requester: RequestedBy) -> PkgRequest {
+        let components = self.selected_components().into_iter().cloned();
+        let pkg = RangeIdent::double_equals(&self.spec.ident().to_any(), components);
+        PkgRequest::new(pkg, requester).with_prerelease(Some(PreReleasePolicy::IncludeAll))
+    }
+
     /// Format this solved request as an installed package(build)
     pub fn format_as_installed_package(&self) -> String {
         let mut installed =
@@ -278,6 +323,28 @@ pub fn get_spfs_layers_to_packages<'a>(
     Ok(layers_to_packages)
 }
 
+/// An environment variable that is set to different values
+/// by more than one package in a solution.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnvConflict {
+    pub variable: String,
+    /// Each package that sets the variable along with the value
+    /// that it sets, in the order that their startup scripts are
+    /// sourced. The last value is the one that takes effect.
+    pub values: Vec<(BuildIdent, String)>,
+}
+
+impl std::fmt::Display for EnvConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|(ident, value)| format!("{ident} sets {value:?}"))
+            .join(", then ");
+        write!(f, "{}: {values}", self.variable)
+    }
+}
+
 /// Represents a set of resolved packages.
 #[derive(Clone, Debug, Default)]
 pub struct Solution {
@@ -335,6 +402,19 @@ impl Solution {
         }
     }
 
+    /// Generate requests that pin every package in this solution to
+    /// the exact build and components that were resolved.
+    ///
+    /// Embedded packages are not included, as they are always brought
+    /// in by the package that embeds them.
+    pub fn to_pinned_requests(&self, requester: RequestedBy) -> Vec<PkgRequest> {
+        self.resolved
+            .iter()
+            .filter(|r| !matches!(r.source, PackageSource::Embedded { .. }))
+            .map(|r| r.to_pinned_request(requester.clone()))
+            .collect()
+    }
+
     /// Return the set of repositories in this solution.
     pub fn repositories(&self) -> Vec<Arc<RepositoryHandle>> {
         let mut seen = HashSet::new();
@@ -621,6 +701,67 @@ impl Solution {
             .collect::<BTreeMap<BuildIdent, PackageSolveData>>()
             .into()
     }
+
+    /// Find any environment variables that are set to different
+    /// values by more than one package in this solution.
+    ///
+    /// Variables that are only set by packages that declare a priority
+    /// are not reported, as the order that those packages set them in,
+    /// and so the value that is used, has been chosen deliberately.
+    pub fn environment_conflicts(&self) -> Vec<EnvConflict> {
+        // visit packages in the same order that their startup scripts
+        // will be sourced so that the last value set is the one that
+        // wins, which is the order of the script file names
+        let mut resolved = self
+            .resolved
+            .iter()
+            .map(|resolved| {
+                let ops = resolved.spec.runtime_environment();
+                let priority = ops.iter().filter_map(EnvOp::priority).last();
+                let stem = startup_script_stem(resolved.spec.name(), ops);
+                (resolved, priority, stem)
+            })
+            .collect::<Vec<_>>();
+        resolved.sort_by(|(_, _, a), (_, _, b)| a.cmp(b));
+
+        let mut values_by_var: BTreeMap<String, Vec<(BuildIdent, String)>> = BTreeMap::new();
+        let mut unprioritized = HashSet::new();
+        for (resolved, priority, _) in resolved {
+            let spec = &resolved.spec;
+            for op in resolved.runtime_environment() {
+                let EnvOp::Set(op) = op else {
+                    continue;
+                };
+                if priority.is_none() {
+                    unprioritized.insert(op.set.clone());
+                }
+                let values = values_by_var.entry(op.set).or_default();
+                match values.last_mut() {
+                    // only the last value set by each package is relevant
+                    Some((ident, value)) if ident == spec.ident() => value.clone_from(&op.value),
+                    _ => values.push((spec.ident().clone(), op.value)),
+                }
+            }
+        }
+
+        values_by_var
+            .into_iter()
+            .filter(|(variable, _)| unprioritized.contains(variable))
+            .filter(|(_, values)| values.iter().map(|(_, value)| value).unique().count() > 1)
+            .map(|(variable, values)| EnvConflict { variable, values })
+            .collect()
+    }
+
+    /// Validate that no two packages in this solution set the same
+    /// environment variable to different values.
+    pub fn validate_environment(&self) -> Result<()> {
+        let conflicts = self.environment_conflicts();
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ConflictingEnvironment(conflicts))
+        }
+    }
 }
 
 impl BuildEnv for Solution {
@@ -0,0 +1,135 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use rstest::rstest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::ident::{PkgRequest, RequestedBy};
+use spk_schema::prelude::*;
+use spk_schema::spec;
+
+use super::{PackageSource, Solution};
+use crate::fixtures::solution_from_specs;
+use crate::Error;
+
+#[rstest]
+fn test_environment_conflicts_different_values() {
+    let solution = solution_from_specs([
+        spec!({
+            "pkg": "pkg-b/1.0.0/3I42H3S6",
+            "install": {"environment": [{"set": "SHARED", "value": "b"}]},
+        }),
+        spec!({
+            "pkg": "pkg-a/1.0.0/3I42H3S6",
+            "install": {"environment": [{"set": "SHARED", "value": "a"}]},
+        }),
+    ]);
+
+    let conflicts = solution.environment_conflicts();
+    assert_eq!(conflicts.len(), 1, "expected one conflict: {conflicts:?}");
+    assert_eq!(conflicts[0].variable, "SHARED");
+    let values = conflicts[0]
+        .values
+        .iter()
+        .map(|(ident, value)| (ident.name().as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![("pkg-a", "a"), ("pkg-b", "b")]);
+    assert!(matches!(
+        solution.validate_environment(),
+        Err(Error::ConflictingEnvironment(_))
+    ));
+}
+
+#[rstest]
+fn test_environment_conflicts_same_value() {
+    let solution = solution_from_specs(["pkg-a", "pkg-b"].map(|name| {
+        spec!({
+            "pkg": format!("{name}/1.0.0/3I42H3S6"),
+            "install": {"environment": [{"set": "SHARED", "value": "same"}]},
+        })
+    }));
+
+    assert!(solution.environment_conflicts().is_empty());
+    solution
+        .validate_environment()
+        .expect("the same value set twice is not a conflict");
+}
+
+#[rstest]
+fn test_environment_conflicts_prioritized_packages() {
+    // startup scripts are sourced in file name order, so
+    // priority 100 is sourced before priority 25
+    let prioritized = [
+        spec!({
+            "pkg": "pkg-late/1.0.0/3I42H3S6",
+            "install": {"environment": [
+                {"priority": 100},
+                {"set": "SHARED", "value": "late"},
+            ]},
+        }),
+        spec!({
+            "pkg": "pkg-early/1.0.0/3I42H3S6",
+            "install": {"environment": [
+                {"priority": 25},
+                {"set": "SHARED", "value": "early"},
+            ]},
+        }),
+    ];
+    let solution = solution_from_specs(prioritized.clone());
+    assert!(
+        solution.environment_conflicts().is_empty(),
+        "variables only set by prioritized packages should not conflict"
+    );
+
+    let plain = spec!({
+        "pkg": "pkg-plain/1.0.0/3I42H3S6",
+        "install": {"environment": [{"set": "SHARED", "value": "plain"}]},
+    });
+    let solution = solution_from_specs(prioritized.into_iter().chain([plain]));
+    let conflicts = solution.environment_conflicts();
+    assert_eq!(conflicts.len(), 1, "expected one conflict: {conflicts:?}");
+    let values = conflicts[0]
+        .values
+        .iter()
+        .map(|(ident, value)| (ident.name().as_str(), value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        vec![
+            ("pkg-late", "late"),
+            ("pkg-early", "early"),
+            ("pkg-plain", "plain"),
+        ]
+    );
+}
+
+#[rstest]
+fn test_to_pinned_requests() {
+    let mut solution = Solution::default();
+    let spec = Arc::new(spec!({"pkg": "pkg-a/1.0.0/3I42H3S6"}));
+    let mut request = PkgRequest::from_ident(spec.ident().to_any(), RequestedBy::SpkInternalTest);
+    request.pkg.components.insert(Component::Run);
+    solution.add(request, Arc::clone(&spec), PackageSource::SpkInternalTest);
+
+    let embedded = Arc::new(spec!({"pkg": "pkg-embedded/2.0.0/embedded"}));
+    solution.add(
+        PkgRequest::from_ident(embedded.ident().to_any(), RequestedBy::SpkInternalTest),
+        embedded,
+        PackageSource::Embedded {
+            parent: spec.ident().clone(),
+        },
+    );
+
+    let pinned = solution.to_pinned_requests(RequestedBy::SpkInternalTest);
+    let pinned = pinned
+        .iter()
+        .map(|request| request.pkg.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pinned,
+        vec!["pkg-a:run/==1.0.0/3I42H3S6"],
+        "should pin the exact build and components, without embedded packages"
+    );
+}
@@ -26,7 +26,15 @@ use crate::validators::{
     DeprecationValidator,
     PkgRequestValidator,
 };
-use crate::{Error, GetMergedRequest, Result, ValidatorT, Validators};
+use crate::{
+    with_default_prerelease_policy,
+    DefaultPreReleasePolicies,
+    Error,
+    GetMergedRequest,
+    Result,
+    ValidatorT,
+    Validators,
+};
 
 #[cfg(test)]
 #[path = "./impossible_checks_test.rs"]
@@ -51,7 +59,7 @@ const REQUEST_FORMAT_OPTIONS: FormatChangeOptions = FormatChangeOptions {
 pub const fn default_impossible_version_validators() -> &'static [Validators] {
     // The validators that detect issues with pkg version requests only.
     &[
-        Validators::Deprecation(DeprecationValidator {}),
+        Validators::Deprecation(DeprecationValidator::DEFAULT),
         Validators::PackageRequest(PkgRequestValidator {}),
         Validators::Components(ComponentsValidator {}),
     ]
@@ -159,6 +167,17 @@ impl ImpossibleRequestsChecker {
         }
     }
 
+    /// Replace the validator that decides which deprecated builds
+    /// can be used to satisfy requests
+    pub fn set_deprecation_validator(&self, validator: DeprecationValidator) {
+        let mut validators_lock = self.validators.lock().unwrap();
+        for v in validators_lock.iter_mut() {
+            if let Validators::Deprecation(existing) = v {
+                *existing = validator.clone();
+            }
+        }
+    }
+
     /// Reset the ImpossibleChecker's counters and request caches
     pub fn reset(&self) {
         self.impossible_requests.clear();
@@ -284,9 +303,13 @@ impl ImpossibleRequestsChecker {
         // unresolved request. The implicit embedded request will be
         // possible on its own because the embedded package will
         // satisfy it, so it doesn't need to be checked for
-        // impossibility here.
-        for embedded_package in package.embedded().iter() {
-            let embedded_id = embedded_package.ident().clone().to_version();
+        // impossibility here. Provided packages are handled the same way.
+        let embedded_ids = package
+            .embedded()
+            .iter()
+            .map(|embedded_package| embedded_package.ident().clone().to_version())
+            .chain(package.provides().iter().cloned());
+        for embedded_id in embedded_ids {
             tracing::debug!(target: IMPOSSIBLE_CHECKS_TARGET, "{} Embedded id: {embedded_id}", package.ident());
             match unresolved_requests.get(embedded_id.name()) {
                 None => {}
@@ -324,8 +347,9 @@ impl ImpossibleRequestsChecker {
 
         for req in requirements.iter() {
             let request = match req {
-                Request::Var(_) => {
-                    // Any var requests are not part of these checks
+                Request::Var(_) | Request::AnyOf(_) => {
+                    // Any var or alternative requests are not
+                    // part of these checks
                     continue;
                 }
                 Request::Pkg(r) => r,
@@ -668,8 +692,11 @@ async fn make_task_per_version(
 ) -> Result<()> {
     let mut number = 0;
     for repo in repos.iter() {
+        let default_policy =
+            DefaultPreReleasePolicies::current().policy_for(Some(repo.name().as_str()));
+        let version_request = with_default_prerelease_policy(&request, default_policy);
         for version in repo.list_package_versions(package.name()).await?.iter() {
-            let compat = request.is_version_applicable(version);
+            let compat = version_request.is_version_applicable(version);
             if !compat.is_ok() {
                 tracing::debug!(
                     target: IMPOSSIBLE_CHECKS_TARGET,
@@ -4,9 +4,11 @@
 
 mod error;
 mod impossible_checks;
+mod prerelease_policy;
 mod validation;
 pub mod validators;
 
 pub use error::{Error, Result};
 pub use impossible_checks::{ImpossibleRequestsChecker, IMPOSSIBLE_CHECKS_TARGET};
+pub use prerelease_policy::{with_default_prerelease_policy, DefaultPreReleasePolicies};
 pub use validation::{default_validators, GetMergedRequest, ValidatorT, Validators};
@@ -0,0 +1,83 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+use spk_schema::ident::{PkgRequest, PreReleasePolicy};
+
+#[cfg(test)]
+#[path = "./prerelease_policy_test.rs"]
+mod prerelease_policy_test;
+
+static DEFAULT_PRERELEASE_POLICIES: Lazy<DefaultPreReleasePolicies> = Lazy::new(|| {
+    spk_config::get_config()
+        .map(|c| DefaultPreReleasePolicies::from_config(&c.prereleases))
+        .unwrap_or_default()
+});
+
+/// The pre-release policies that are used for package requests
+/// that do not specify one, as configured for the site and for
+/// individual repositories.
+#[derive(Clone, Debug, Default)]
+pub struct DefaultPreReleasePolicies {
+    site: PreReleasePolicy,
+    repositories: HashMap<String, PreReleasePolicy>,
+}
+
+impl DefaultPreReleasePolicies {
+    /// The default policies from the current spk config.
+    pub fn current() -> &'static Self {
+        &DEFAULT_PRERELEASE_POLICIES
+    }
+
+    /// Parse the default policies from the given config section.
+    ///
+    /// Any policy that cannot be parsed is logged and
+    /// treated as the default `ExcludeAll` policy.
+    pub fn from_config(config: &spk_config::PreReleases) -> Self {
+        let parse = |policy: &str| -> PreReleasePolicy {
+            let policy = policy.trim();
+            if policy.is_empty() {
+                return PreReleasePolicy::default();
+            }
+            PreReleasePolicy::from_str(policy).unwrap_or_else(|err| {
+                tracing::warn!("Invalid pre-release policy in spk config: {err}");
+                PreReleasePolicy::default()
+            })
+        };
+        Self {
+            site: parse(&config.policy),
+            repositories: config
+                .repositories
+                .iter()
+                .map(|(name, policy)| (name.clone(), parse(policy)))
+                .collect(),
+        }
+    }
+
+    /// The default policy for packages from the named repository,
+    /// or the site-wide policy if the repository is not configured.
+    pub fn policy_for(&self, repository: Option<&str>) -> PreReleasePolicy {
+        repository
+            .and_then(|name| self.repositories.get(name))
+            .copied()
+            .unwrap_or(self.site)
+    }
+}
+
+/// Return the given request with its pre-release policy
+/// set to `default` if it does not already specify one.
+pub fn with_default_prerelease_policy(
+    request: &PkgRequest,
+    default: PreReleasePolicy,
+) -> Cow<'_, PkgRequest> {
+    // requests without a policy already exclude all pre-releases
+    if request.prerelease_policy.is_some() || default.is_default() {
+        return Cow::Borrowed(request);
+    }
+    Cow::Owned(request.clone().with_prerelease(Some(default)))
+}
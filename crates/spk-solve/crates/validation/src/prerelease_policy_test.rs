@@ -0,0 +1,67 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use rstest::rstest;
+use spk_schema::foundation::version::Version;
+use spk_schema::ident::{PkgRequest, PreReleasePolicy, RangeIdent, RequestedBy};
+
+use super::{with_default_prerelease_policy, DefaultPreReleasePolicies};
+
+#[rstest]
+fn test_default_policies_from_config() {
+    let mut config = spk_config::PreReleases::default();
+    config.policy = "ExcludeAll".to_string();
+    config
+        .repositories
+        .insert("test".to_string(), "IncludeAll".to_string());
+    config
+        .repositories
+        .insert("broken".to_string(), "Sometimes".to_string());
+    let defaults = DefaultPreReleasePolicies::from_config(&config);
+
+    assert_eq!(defaults.policy_for(None), PreReleasePolicy::ExcludeAll);
+    assert_eq!(
+        defaults.policy_for(Some("origin")),
+        PreReleasePolicy::ExcludeAll
+    );
+    assert_eq!(
+        defaults.policy_for(Some("test")),
+        PreReleasePolicy::IncludeAll
+    );
+    assert_eq!(
+        defaults.policy_for(Some("broken")),
+        PreReleasePolicy::ExcludeAll,
+        "invalid policies should fall back to the default"
+    );
+}
+
+#[rstest]
+fn test_default_policy_is_exclude_all() {
+    let defaults = DefaultPreReleasePolicies::from_config(&Default::default());
+    assert_eq!(defaults.policy_for(None), PreReleasePolicy::ExcludeAll);
+}
+
+#[rstest]
+fn test_with_default_prerelease_policy() {
+    let request = PkgRequest::new(
+        RangeIdent::from_str("my-pkg").unwrap(),
+        RequestedBy::SpkInternalTest,
+    );
+    let version = Version::from_str("1.0.0-pre.1").unwrap();
+    assert!(!request.is_version_applicable(&version).is_ok());
+
+    let defaulted = with_default_prerelease_policy(&request, PreReleasePolicy::ExcludeAll);
+    assert!(matches!(defaulted, Cow::Borrowed(_)));
+
+    let defaulted = with_default_prerelease_policy(&request, PreReleasePolicy::IncludeAll);
+    assert!(defaulted.is_version_applicable(&version).is_ok());
+
+    // a policy on the request is never replaced
+    let request = request.with_prerelease(Some(PreReleasePolicy::ExcludeAll));
+    let defaulted = with_default_prerelease_policy(&request, PreReleasePolicy::IncludeAll);
+    assert!(!defaulted.is_version_applicable(&version).is_ok());
+}
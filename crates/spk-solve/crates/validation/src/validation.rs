@@ -22,9 +22,11 @@ mod validation_test;
 pub enum Validators {
     BinaryOnly(BinaryOnlyValidator),
     Components(ComponentsValidator),
+    Conflicts(ConflictsValidator),
     Deprecation(DeprecationValidator),
     EmbeddedPackage(EmbeddedPackageValidator),
     Options(OptionsValidator),
+    Platforms(PlatformsValidator),
     PackageRequest(PkgRequestValidator),
     PkgRequirements(PkgRequirementsValidator),
     VarRequirements(VarRequirementsValidator),
@@ -92,12 +94,14 @@ pub trait ValidatorT {
 pub const fn default_validators() -> &'static [Validators] {
     // This controls the order the validators are checked
     &[
-        Validators::Deprecation(DeprecationValidator {}),
+        Validators::Deprecation(DeprecationValidator::DEFAULT),
+        Validators::Platforms(PlatformsValidator {}),
         Validators::PackageRequest(PkgRequestValidator {}),
         Validators::Components(ComponentsValidator {}),
         Validators::Options(OptionsValidator {}),
         Validators::VarRequirements(VarRequirementsValidator {}),
         Validators::PkgRequirements(PkgRequirementsValidator {}),
+        Validators::Conflicts(ConflictsValidator {}),
         Validators::EmbeddedPackage(EmbeddedPackageValidator {}),
     ]
 }
@@ -13,7 +13,13 @@ use spk_solve_graph::State;
 use spk_solve_macros::recipe;
 use spk_solve_solution::PackageSource;
 
-use super::{default_validators, OptionsValidator, ValidatorT, VarRequirementsValidator};
+use super::{
+    default_validators,
+    OptionsValidator,
+    PlatformsValidator,
+    ValidatorT,
+    VarRequirementsValidator,
+};
 
 #[rstest]
 fn test_src_package_install_requests_are_not_considered() {
@@ -147,3 +153,55 @@ fn test_qualified_var_supersedes_unqualified() {
         "qualified var requests should supersede unqualified ones, got: {compat}",
     );
 }
+
+#[rstest]
+fn test_unsupported_platforms_are_invalid() {
+    let validator = PlatformsValidator::default();
+
+    let state = State::new(
+        vec![],
+        vec![],
+        vec![],
+        vec![
+            (opt_name!("os").to_owned(), "linux".to_string()),
+            (opt_name!("arch").to_owned(), "x86_64".to_string()),
+        ],
+    );
+    let source = PackageSource::SpkInternalTest;
+
+    let spec = Arc::new(spec!(
+        {
+            "pkg": "my-package/1.0.0/3I42H3S6",
+            "platforms": [{"os": "linux", "arch": ["x86_64", "aarch64"]}],
+        }
+    ));
+    let compat = validator.validate_package(&state, &*spec, &source).unwrap();
+    assert!(
+        compat.is_ok(),
+        "linux/x86_64 should be supported, got: {compat}"
+    );
+
+    let spec = Arc::new(spec!(
+        {
+            "pkg": "my-package/1.0.0/3I42H3S6",
+            "platforms": [{"os": "windows"}],
+        }
+    ));
+    let compat = validator.validate_package(&state, &*spec, &source).unwrap();
+    assert!(
+        !compat.is_ok(),
+        "a build that only supports windows should not be used on linux"
+    );
+
+    let spec = Arc::new(spec!(
+        {
+            "pkg": "my-package/1.0.0/src",
+            "platforms": [{"os": "windows"}],
+        }
+    ));
+    let compat = validator.validate_package(&state, &*spec, &source).unwrap();
+    assert!(
+        compat.is_ok(),
+        "source packages should be valid on any platform, got: {compat}"
+    );
+}
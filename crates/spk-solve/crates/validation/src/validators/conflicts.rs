@@ -0,0 +1,54 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use super::prelude::*;
+use crate::ValidatorT;
+
+/// Validates that a package does not conflict with any resolved package, and
+/// that no resolved package declares a conflict with it.
+#[derive(Clone, Copy, Default)]
+pub struct ConflictsValidator {}
+
+impl ValidatorT for ConflictsValidator {
+    fn validate_package<P: Package>(
+        &self,
+        state: &State,
+        spec: &P,
+        _source: &PackageSource,
+    ) -> crate::Result<Compatibility> {
+        let conflicts = spec.runtime_conflicts();
+        for resolved in state.get_ordered_resolved_packages().iter() {
+            if let Some(conflict) = conflicts
+                .iter()
+                .find(|c| c.is_conflicting_package(resolved.ident()))
+            {
+                return Ok(Compatibility::incompatible(format!(
+                    "package conflicts with resolved package {}: {conflict}",
+                    resolved.ident()
+                )));
+            }
+            if let Some(conflict) = resolved
+                .runtime_conflicts()
+                .iter()
+                .find(|c| c.is_conflicting_package(spec.ident()))
+            {
+                return Ok(Compatibility::incompatible(format!(
+                    "resolved package {} conflicts with this package: {conflict}",
+                    resolved.ident()
+                )));
+            }
+        }
+        Ok(Compatibility::Compatible)
+    }
+
+    fn validate_recipe<R: Recipe>(
+        &self,
+        _state: &State,
+        _recipe: &R,
+    ) -> crate::Result<Compatibility> {
+        // the recipe cannot tell us what the
+        // runtime conflicts will be
+        Ok(Compatibility::Compatible)
+    }
+}
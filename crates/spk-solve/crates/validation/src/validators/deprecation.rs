@@ -2,12 +2,46 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use spk_schema::{BuildIdent, Deprecate};
+
 use super::prelude::*;
 use crate::ValidatorT;
 
 /// Ensures that deprecated packages are not included unless specifically requested.
-#[derive(Clone, Copy)]
-pub struct DeprecationValidator {}
+///
+/// Deprecated builds can also be grandfathered in, such as those that are
+/// already installed in the current environment, so that deprecating a build
+/// does not stop the environments that are using it from being solved again.
+#[derive(Clone, Default)]
+pub struct DeprecationValidator {
+    grandfathered: Option<Arc<HashSet<BuildIdent>>>,
+}
+
+impl DeprecationValidator {
+    /// Allows no deprecated builds unless they are requested exactly.
+    pub const DEFAULT: Self = Self {
+        grandfathered: None,
+    };
+
+    /// Also allow these deprecated builds, even when they are not requested exactly.
+    pub fn with_grandfathered_builds<I>(builds: I) -> Self
+    where
+        I: IntoIterator<Item = BuildIdent>,
+    {
+        let builds: HashSet<_> = builds.into_iter().collect();
+        Self {
+            grandfathered: Some(Arc::new(builds)).filter(|b| !b.is_empty()),
+        }
+    }
+
+    /// The deprecated builds that are allowed without being requested exactly.
+    pub fn grandfathered_builds(&self) -> impl Iterator<Item = &BuildIdent> {
+        self.grandfathered.iter().flat_map(|builds| builds.iter())
+    }
+}
 
 impl ValidatorT for DeprecationValidator {
     fn validate_package<P>(
@@ -28,9 +62,10 @@ impl ValidatorT for DeprecationValidator {
         recipe: &R,
     ) -> crate::Result<Compatibility> {
         if recipe.is_deprecated() {
-            Ok(Compatibility::incompatible(
-                "recipe is deprecated for this version".to_owned(),
-            ))
+            Ok(Compatibility::incompatible(with_suggestion(
+                "recipe is deprecated for this version",
+                recipe,
+            )))
         } else {
             Ok(Compatibility::Compatible)
         }
@@ -53,8 +88,24 @@ impl ValidatorT for DeprecationValidator {
         if request.pkg.build.as_ref() == Some(package.ident().build()) {
             return Ok(Compatibility::Compatible);
         }
-        Ok(Compatibility::incompatible(
-            "build is deprecated (and not requested exactly)".to_owned(),
-        ))
+        if self
+            .grandfathered
+            .as_ref()
+            .is_some_and(|builds| builds.contains(package.ident()))
+        {
+            return Ok(Compatibility::Compatible);
+        }
+        Ok(Compatibility::incompatible(with_suggestion(
+            "build is deprecated (and not requested exactly)",
+            package,
+        )))
+    }
+}
+
+/// Add any suggested replacements for a deprecated item to the given message.
+fn with_suggestion<D: Deprecate + ?Sized>(message: &str, deprecated: &D) -> String {
+    match deprecated.replacement_suggestion() {
+        Some(suggestion) => format!("{message}, {suggestion}"),
+        None => message.to_owned(),
     }
 }
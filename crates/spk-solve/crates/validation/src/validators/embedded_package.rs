@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use spk_schema::EmbeddedPackagesList;
+
 use super::prelude::*;
 use crate::ValidatorT;
 
@@ -18,7 +20,10 @@ impl ValidatorT for EmbeddedPackageValidator {
     where
         P: Package,
     {
-        for embedded in spec.embedded().iter() {
+        // provided packages are treated as embedded ones, and must be
+        // compatible with the current state in the same way
+        let provided = EmbeddedPackagesList::from_provided(spec.provides());
+        for embedded in spec.embedded().iter().chain(provided.iter()) {
             let compat = Self::validate_embedded_package_against_state(spec, embedded, state)?;
             if !&compat {
                 return Ok(compat);
@@ -4,19 +4,23 @@
 
 mod binary_only;
 mod components;
+mod conflicts;
 mod deprecation;
 mod embedded_package;
 mod options;
 mod pkg_request;
 mod pkg_requirements;
+mod platforms;
 mod prelude;
 mod var_requirements;
 
 pub use binary_only::BinaryOnlyValidator;
 pub use components::ComponentsValidator;
+pub use conflicts::ConflictsValidator;
 pub use deprecation::DeprecationValidator;
 pub use embedded_package::EmbeddedPackageValidator;
 pub use options::OptionsValidator;
 pub use pkg_request::PkgRequestValidator;
 pub use pkg_requirements::PkgRequirementsValidator;
+pub use platforms::PlatformsValidator;
 pub use var_requirements::VarRequirementsValidator;
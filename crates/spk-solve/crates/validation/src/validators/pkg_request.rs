@@ -3,7 +3,7 @@
 // https://github.com/spkenv/spk
 
 use super::prelude::*;
-use crate::ValidatorT;
+use crate::{with_default_prerelease_policy, DefaultPreReleasePolicies, ValidatorT};
 
 /// Ensures that a package meets all requested version criteria.
 #[derive(Clone, Copy)]
@@ -42,6 +42,9 @@ impl ValidatorT for PkgRequestValidator {
                 )))
             }
         };
+        // recipes are not tied to any one repository
+        let default_policy = DefaultPreReleasePolicies::current().policy_for(None);
+        let request = with_default_prerelease_policy(&request, default_policy);
         Ok(request.is_version_applicable(recipe.version()))
     }
 
@@ -103,6 +106,13 @@ impl ValidatorT for PkgRequestValidator {
                 }
             };
         }
+        let repository = match source {
+            PackageSource::Repository { repo, .. } => Some(repo.name().as_str()),
+            _ => None,
+        };
+        let default_policy = DefaultPreReleasePolicies::current().policy_for(repository);
+        let request = with_default_prerelease_policy(&request, default_policy);
+
         // the initial check is more general and provides more user
         // friendly error messages that we'd like to get
         let mut compat = request.is_version_applicable(package.version());
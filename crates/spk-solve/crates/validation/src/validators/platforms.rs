@@ -0,0 +1,39 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use spk_schema::PlatformSupport;
+
+use super::prelude::*;
+use crate::ValidatorT;
+
+/// Ensures that packages support the requested operating system and architecture.
+#[derive(Clone, Copy, Default)]
+pub struct PlatformsValidator {}
+
+impl ValidatorT for PlatformsValidator {
+    fn validate_package<P>(
+        &self,
+        state: &State,
+        spec: &P,
+        _source: &PackageSource,
+    ) -> crate::Result<Compatibility>
+    where
+        P: Satisfy<PkgRequest> + Satisfy<VarRequest> + Package,
+    {
+        if spec.ident().is_source() {
+            // source packages can be used to build
+            // the package on any supported platform
+            return Ok(Compatibility::Compatible);
+        }
+        Ok(spec.supported_platforms().check(state.get_option_map()))
+    }
+
+    fn validate_recipe<R: Recipe>(
+        &self,
+        state: &State,
+        recipe: &R,
+    ) -> crate::Result<Compatibility> {
+        Ok(recipe.supported_platforms().check(state.get_option_map()))
+    }
+}
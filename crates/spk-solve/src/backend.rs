@@ -0,0 +1,72 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::str::FromStr;
+
+use crate::sat::SatSolverBackend;
+use crate::{Error, Result, Solution, Solver};
+
+/// A method of finding a solution for the requests given to a [`Solver`].
+#[async_trait::async_trait]
+pub trait SolverBackend: Send + Sync {
+    /// Find a set of packages that satisfies all the requests
+    /// and options of the given solver.
+    async fn solve(&self, solver: &Solver) -> Result<Solution>;
+}
+
+/// The default backend, which walks a graph of decisions one
+/// request at a time and steps back when it hits a dead end.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GraphSolverBackend;
+
+#[async_trait::async_trait]
+impl SolverBackend for GraphSolverBackend {
+    async fn solve(&self, solver: &Solver) -> Result<Solution> {
+        let mut runtime = solver.run();
+        runtime.solution().await
+    }
+}
+
+/// The backends that a [`Solver`] can be configured to use.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SolverBackendKind {
+    /// Search the graph of decisions, see [`GraphSolverBackend`]
+    #[default]
+    Graph,
+    /// Encode the solve as a satisfiability problem, see [`SatSolverBackend`]
+    Sat,
+}
+
+impl SolverBackendKind {
+    /// An instance of this kind of backend.
+    pub fn backend(&self) -> Box<dyn SolverBackend> {
+        match self {
+            Self::Graph => Box::new(GraphSolverBackend),
+            Self::Sat => Box::new(SatSolverBackend),
+        }
+    }
+}
+
+impl std::fmt::Display for SolverBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Graph => f.write_str("graph"),
+            Self::Sat => f.write_str("sat"),
+        }
+    }
+}
+
+impl FromStr for SolverBackendKind {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "graph" => Ok(Self::Graph),
+            "sat" => Ok(Self::Sat),
+            _ => Err(Error::String(format!(
+                "Unknown solver backend '{value}', must be one of: graph, sat"
+            ))),
+        }
+    }
+}
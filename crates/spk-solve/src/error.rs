@@ -23,7 +23,11 @@ pub enum Error {
     #[diagnostic(forward(0))]
     OutOfOptions(#[from] OutOfOptions),
     #[error("Solver interrupted: {0}")]
+    #[diagnostic(code(spk::solve::interrupted))]
     SolverInterrupted(String),
+    #[error("No solution satisfies all of the requests: {0}")]
+    #[diagnostic(code(spk::solve::unsatisfiable))]
+    Unsatisfiable(String),
     #[error(transparent)]
     #[diagnostic(forward(0))]
     SpkIdentComponentError(#[from] spk_schema::foundation::ident_component::Error),
@@ -51,6 +55,7 @@ pub enum Error {
     #[error("Status bar IO error: {0}")]
     StatusBarIOError(#[source] std::io::Error),
     #[error("Initial requests contain {0} impossible request{plural}.", plural = if *.0 == 1 { "" } else { "s" } )]
+    #[diagnostic(code(spk::solve::impossible_requests))]
     InitialRequestsContainImpossibleError(usize),
     #[error(transparent)]
     #[diagnostic(forward(0))]
@@ -61,6 +66,7 @@ pub enum Error {
 
 #[derive(Diagnostic, Debug, Error)]
 #[error("Out of options for {pkg}", pkg = .request.pkg)]
+#[diagnostic(code(spk::solve::out_of_options))]
 pub struct OutOfOptions {
     pub request: PkgRequest,
     pub notes: Vec<Note>,
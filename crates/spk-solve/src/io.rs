@@ -5,6 +5,7 @@
 use std::cmp::max;
 use std::collections::VecDeque;
 use std::fmt::{Display, Write};
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -51,7 +52,10 @@ use crate::{
     ResolverCallback,
     Result,
     Solution,
+    SolveRun,
+    SolveRunSolver,
     Solver,
+    SolverBackendKind,
     SolverRuntime,
     StatusLine,
 };
@@ -212,6 +216,7 @@ where
             StepBack(_) => 1,
             RequestPackage(_) => 2,
             RequestVar(_) => 2,
+            RequestAnyOf(_) => 2,
             SetOptions(_) => 3,
             SetPackageBuild(_) => 1,
         };
@@ -491,6 +496,8 @@ pub struct DecisionFormatterBuilder {
     stop_on_block: bool,
     step_on_block: bool,
     step_on_decision: bool,
+    save_solve_run: Option<PathBuf>,
+    replay: Option<Arc<SolveRun>>,
 }
 
 impl Default for DecisionFormatterBuilder {
@@ -512,6 +519,8 @@ impl Default for DecisionFormatterBuilder {
             stop_on_block: false,
             step_on_block: false,
             step_on_decision: false,
+            save_solve_run: None,
+            replay: None,
         }
     }
 }
@@ -615,6 +624,19 @@ impl DecisionFormatterBuilder {
         self
     }
 
+    /// Save the inputs and decisions of each solve to this file.
+    pub fn with_save_solve_run(&mut self, path: Option<PathBuf>) -> &mut Self {
+        self.save_solve_run = path;
+        self
+    }
+
+    /// Replay the given solve run instead of the solver's own requests,
+    /// and report whether the same decisions are made.
+    pub fn with_replay(&mut self, replay: Option<SolveRun>) -> &mut Self {
+        self.replay = replay.map(Arc::new);
+        self
+    }
+
     pub fn build(&self) -> DecisionFormatter {
         let too_long_seconds = if self.verbosity_increase_seconds == 0
             || (self.verbosity_increase_seconds > self.timeout && self.timeout > 0)
@@ -658,6 +680,8 @@ impl DecisionFormatterBuilder {
                 stop_on_block: self.stop_on_block,
                 step_on_block: self.step_on_block,
                 step_on_decision: self.step_on_decision,
+                save_solve_run: self.save_solve_run.clone(),
+                replay: self.replay.clone(),
             },
         }
     }
@@ -720,6 +744,8 @@ pub(crate) struct DecisionFormatterSettings {
     pub(crate) stop_on_block: bool,
     pub(crate) step_on_block: bool,
     pub(crate) step_on_decision: bool,
+    pub(crate) save_solve_run: Option<PathBuf>,
+    pub(crate) replay: Option<Arc<SolveRun>>,
 }
 
 enum LoopOutcome {
@@ -777,6 +803,7 @@ struct SolverTaskDone {
     pub(crate) verbosity: u8,
     pub(crate) solver_kind: MultiSolverKind,
     pub(crate) can_ignore_failure: bool,
+    pub(crate) solve_run: Option<SolveRun>,
 }
 
 struct SolverResult {
@@ -812,6 +839,8 @@ impl DecisionFormatter {
                 stop_on_block: false,
                 step_on_block: false,
                 step_on_decision: false,
+                save_solve_run: None,
+                replay: None,
             },
         }
     }
@@ -824,6 +853,9 @@ impl DecisionFormatter {
         &self,
         solver: &Solver,
     ) -> Result<(Solution, Arc<tokio::sync::RwLock<Graph>>)> {
+        if solver.backend() != SolverBackendKind::Graph {
+            return self.run_backend_solve(solver, OutputKind::Println).await;
+        }
         let solvers = self.setup_solvers(solver);
         self.run_multi_solve(solvers, OutputKind::Println).await
     }
@@ -840,7 +872,7 @@ impl DecisionFormatter {
         // be able to remove this method.
         let start = Instant::now();
         let output_to = OutputKind::Println;
-        let loop_outcome = self.run_solver_loop(runtime, output_to).await;
+        let loop_outcome = self.run_solver_loop(runtime, output_to, None).await;
         let solve_time = start.elapsed();
 
         #[cfg(feature = "statsd")]
@@ -858,6 +890,9 @@ impl DecisionFormatter {
         &self,
         solver: &Solver,
     ) -> Result<(Solution, Arc<tokio::sync::RwLock<Graph>>)> {
+        if solver.backend() != SolverBackendKind::Graph {
+            return self.run_backend_solve(solver, OutputKind::Tracing).await;
+        }
         let solvers = self.setup_solvers(solver);
         self.run_multi_solve(solvers, OutputKind::Tracing).await
     }
@@ -874,7 +909,7 @@ impl DecisionFormatter {
         // to remove this method.
         let start = Instant::now();
         let output_to = OutputKind::Tracing;
-        let loop_outcome = self.run_solver_loop(runtime, output_to).await;
+        let loop_outcome = self.run_solver_loop(runtime, output_to, None).await;
         let solve_time = start.elapsed();
 
         #[cfg(feature = "statsd")]
@@ -884,7 +919,69 @@ impl DecisionFormatter {
             .await
     }
 
+    /// Run a solve using a backend other than the graph, which
+    /// has no decisions to show while it runs.
+    async fn run_backend_solve(
+        &self,
+        solver: &Solver,
+        output_location: OutputKind,
+    ) -> Result<(Solution, Arc<tokio::sync::RwLock<Graph>>)> {
+        // other backends check the solver's own limits as they run,
+        // so the timeout of this formatter is passed on to them
+        let mut solver = solver.clone();
+        if self.settings.timeout > 0 {
+            let timeout = Duration::from_secs(self.settings.timeout);
+            solver.set_timeout(Some(solver.timeout().map_or(timeout, |t| t.min(timeout))));
+        }
+        let solver = &solver;
+        let start = Instant::now();
+        let result = solver.backend().backend().solve(solver).await;
+        let solve_time = start.elapsed();
+
+        if self.settings.report_time {
+            output_location.output_message(format!(
+                "Solved with the {} backend in {:.3} secs",
+                solver.backend(),
+                solve_time.as_secs_f64()
+            ));
+        }
+        let solution = result?;
+
+        if self.settings.show_solution {
+            output_location.output_message(format!(
+                "{}{}",
+                self.settings.heading_prefix,
+                solution
+                    .format_solution_with_highest_versions(
+                        self.settings.verbosity,
+                        solver.repositories(),
+                        false,
+                    )
+                    .await?
+            ));
+        }
+        Ok((solution, Arc::new(tokio::sync::RwLock::new(Graph::new()))))
+    }
+
     fn setup_solvers(&self, base_solver: &Solver) -> Vec<SolverTaskSettings> {
+        if let Some(replay) = &self.settings.replay {
+            // Only the solver that made the saved run is replayed,
+            // because which of several solvers finishes first
+            // can change from one run to the next.
+            let mut solver = base_solver.clone();
+            replay.configure_solver(&mut solver);
+            if replay.solver == SolveRunSolver::AllImpossibleChecks {
+                solver.set_initial_request_impossible_checks(true);
+                solver.set_resolve_validation_impossible_checks(true);
+                solver.set_build_key_impossible_checks(true);
+            }
+            return Vec::from([SolverTaskSettings {
+                solver,
+                solver_kind: replay.solver.into(),
+                ignore_failure: false,
+            }]);
+        }
+
         // Leave the first solver as is.
         let solver_with_no_change = base_solver.clone();
 
@@ -942,6 +1039,15 @@ impl DecisionFormatter {
                 task_formatter.settings.status_bar = false;
             }
             let mut task_solver_runtime = solver_settings.solver.run();
+            let mut solve_run = (self.settings.save_solve_run.is_some()
+                || self.settings.replay.is_some())
+            .then(|| {
+                let kind = match solver_settings.solver_kind {
+                    MultiSolverKind::AllImpossibleChecks => SolveRunSolver::AllImpossibleChecks,
+                    _ => SolveRunSolver::Unchanged,
+                };
+                SolveRun::new(&solver_settings.solver, kind)
+            });
 
             let task = async move {
                 #[cfg(feature = "statsd")]
@@ -949,7 +1055,11 @@ impl DecisionFormatter {
 
                 let start = Instant::now();
                 let loop_outcome = task_formatter
-                    .run_solver_loop(&mut task_solver_runtime, output_location)
+                    .run_solver_loop(
+                        &mut task_solver_runtime,
+                        output_location,
+                        solve_run.as_mut(),
+                    )
                     .await;
 
                 SolverTaskDone {
@@ -959,6 +1069,7 @@ impl DecisionFormatter {
                     verbosity: task_formatter.settings.verbosity,
                     solver_kind: solver_settings.solver_kind,
                     can_ignore_failure: solver_settings.ignore_failure,
+                    solve_run,
                 }
             };
 
@@ -1036,6 +1147,7 @@ impl DecisionFormatter {
                     verbosity,
                     solver_kind,
                     can_ignore_failure,
+                    solve_run,
                 }) => {
                     // If the solver that finished first is one we can
                     // ignore failures from and it failed, then ignore
@@ -1078,6 +1190,11 @@ impl DecisionFormatter {
                         )
                         .await;
 
+                    // Only the first result is used when comparing solvers
+                    if !self.settings.compare_solvers || solver_results.is_empty() {
+                        self.finish_solve_run(solve_run);
+                    }
+
                     if self.settings.verbosity > 0 && verbosity == 0 {
                         let solver_outcome = if result.is_ok() {
                             "a solution"
@@ -1127,14 +1244,55 @@ impl DecisionFormatter {
         }
     }
 
+    /// Save or check the record of a finished solve,
+    /// as requested in the settings.
+    fn finish_solve_run(&self, solve_run: Option<SolveRun>) {
+        let Some(solve_run) = solve_run else {
+            return;
+        };
+        if let Some(path) = &self.settings.save_solve_run {
+            match solve_run.save(path) {
+                Ok(()) => tracing::info!("Saved solve run to {}", path.display()),
+                Err(err) => tracing::error!("{err}"),
+            }
+        }
+        let Some(replay) = &self.settings.replay else {
+            return;
+        };
+        match replay.first_difference(&solve_run) {
+            None => tracing::info!(
+                "Replay made the same {} decisions as the saved solve run",
+                solve_run.decisions.len()
+            ),
+            Some((index, saved, replayed)) => {
+                let describe = |decision: Option<&crate::SolveRunDecision>| {
+                    decision
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| "no more decisions".to_string())
+                };
+                tracing::warn!(
+                    "Replay differs from the saved solve run at decision {}:\n saved:    {}\n replayed: {}",
+                    index + 1,
+                    describe(saved),
+                    describe(replayed),
+                );
+            }
+        }
+    }
+
     async fn run_solver_loop(
         &self,
         runtime: &mut SolverRuntime,
         output_location: OutputKind,
+        mut solve_run: Option<&mut SolveRun>,
     ) -> LoopOutcome {
         // This block exists to shorten the scope of `runtime`'s borrow.
         let loop_outcome = {
-            let decisions = runtime.iter();
+            let decisions = runtime.iter().inspect(|item| {
+                if let (Some(solve_run), Ok((_, decision))) = (solve_run.as_deref_mut(), item) {
+                    solve_run.record(decision);
+                }
+            });
             let mut formatted_decisions = self.formatted_decisions_iter(decisions);
             let iter = formatted_decisions.iter();
             tokio::pin!(iter);
@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+mod backend;
 mod error;
 mod io;
 #[cfg(feature = "statsd")]
 mod metrics;
+mod sat;
 mod search_space;
+mod solve_run;
 mod solver;
 mod status_line;
 
 use std::sync::Arc;
 
+pub use backend::{GraphSolverBackend, SolverBackend, SolverBackendKind};
 pub use error::{Error, Result};
 use graph::Graph;
 pub use io::{DecisionFormatter, DecisionFormatterBuilder, MultiSolverKind};
@@ -28,7 +32,9 @@ pub use metrics::{
     SPK_SOLVER_RUN_TIME_METRIC,
     SPK_SOLVER_SOLUTION_SIZE_METRIC,
 };
+pub use sat::SatSolverBackend;
 pub(crate) use search_space::show_search_space_stats;
+pub use solve_run::{SolveRun, SolveRunDecision, SolveRunInput, SolveRunSolver};
 pub use solver::{Solver, SolverRuntime};
 pub use spk_schema::foundation::ident_build::Build;
 pub use spk_schema::foundation::ident_component::Component;
@@ -43,6 +49,7 @@ pub use spk_schema::ident::{
     RequestedBy,
 };
 pub use spk_schema::{recipe, spec, v0, Package, Recipe, Spec, SpecRecipe};
+pub use spk_solve_package_iterator::BuildOrder;
 pub use spk_solve_solution::{PackageSource, Solution};
 pub use spk_storage::RepositoryHandle;
 pub(crate) use status_line::StatusLine;
@@ -74,6 +81,11 @@ impl ResolverCallback for DefaultResolver {
         &'s self,
         r: &'a Solver,
     ) -> Result<(Solution, Arc<tokio::sync::RwLock<Graph>>)> {
+        if r.backend() != SolverBackendKind::Graph {
+            // other backends do not build a graph of their decisions
+            let solution = r.backend().backend().solve(r).await?;
+            return Ok((solution, Arc::new(tokio::sync::RwLock::new(Graph::new()))));
+        }
         let mut runtime = r.run();
         let solution = runtime.solution().await;
         match solution {
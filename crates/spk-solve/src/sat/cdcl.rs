@@ -0,0 +1,520 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! A small conflict-driven clause learning (CDCL) satisfiability solver
+//!
+//! This is not intended to compete with dedicated SAT solvers, but it
+//! learns from each conflict and jumps back past irrelevant decisions,
+//! which is what makes it useful for the dependency graphs that cause
+//! the default search to revisit the same dead ends over and over.
+
+use std::collections::BTreeSet;
+use std::ops::Not;
+
+#[cfg(test)]
+#[path = "./cdcl_test.rs"]
+mod cdcl_test;
+
+/// A boolean variable in the problem.
+pub(crate) type Var = usize;
+
+/// A variable or its negation.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Lit(usize);
+
+impl Lit {
+    /// The literal that is true when the variable is true.
+    pub fn positive(var: Var) -> Self {
+        Self(var << 1)
+    }
+
+    /// The literal that is true when the variable is false.
+    pub fn negative(var: Var) -> Self {
+        Self((var << 1) | 1)
+    }
+
+    pub fn var(&self) -> Var {
+        self.0 >> 1
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl Not for Lit {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+/// The outcome of [`CdclSolver::solve`].
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SatOutcome {
+    /// The value of every variable in an assignment that
+    /// satisfies all of the clauses
+    Satisfiable(Vec<bool>),
+    /// No assignment satisfies all of the clauses
+    Unsatisfiable,
+    /// The solve was stopped before an answer was found
+    Interrupted,
+}
+
+/// Counts of the work done by a solver, for reporting.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SatStats {
+    pub decisions: usize,
+    pub conflicts: usize,
+    pub learned: usize,
+}
+
+/// A CDCL solver with two watched literals per clause.
+///
+/// Unlike a general purpose solver, decisions follow the order in
+/// which "requirement" clauses were added: the first clause that
+/// must be satisfied but is not yet is satisfied using its first
+/// available literal. This keeps the result stable and lets callers
+/// express a preference (eg: newer versions first) through the order
+/// of the literals in those clauses. Any variable that no requirement
+/// needs is left false.
+///
+/// Clauses can be added in numbered groups, and when the problem is
+/// unsatisfiable the groups of the clauses that the final conflict
+/// was derived from are available as its unsatisfiable core.
+#[derive(Default)]
+pub(crate) struct CdclSolver {
+    clauses: Vec<Vec<Lit>>,
+    /// For each clause, the group that it was added in
+    groups: Vec<Option<usize>>,
+    /// For each clause, the clauses that it was derived from, which
+    /// includes the reasons for any literals that were already false
+    /// when an original clause was added
+    antecedents: Vec<Vec<usize>>,
+    /// The group of the clauses being added
+    group: Option<usize>,
+    /// The clause that could not be satisfied at the top level
+    final_conflict: Option<usize>,
+    /// For each literal, the clauses that are watching it
+    watches: Vec<Vec<usize>>,
+    /// Requirement clauses in their original order
+    requirements: Vec<Vec<Lit>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    /// The position in the trail where each decision level starts
+    trail_limits: Vec<usize>,
+    /// The position in the trail of the next literal to propagate
+    propagated: usize,
+    /// Set when a clause was added that can never be satisfied
+    unsatisfiable: bool,
+    stats: SatStats,
+}
+
+impl CdclSolver {
+    /// Add a new variable to the problem.
+    pub fn new_var(&mut self) -> Var {
+        let var = self.values.len();
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        var
+    }
+
+    /// The number of variables in the problem.
+    pub fn num_vars(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The number of clauses in the problem, including learned ones.
+    pub fn num_clauses(&self) -> usize {
+        self.clauses.len()
+    }
+
+    pub fn stats(&self) -> SatStats {
+        self.stats
+    }
+
+    /// Add the clauses that follow to the given group, or to none.
+    pub fn set_group(&mut self, group: Option<usize>) {
+        self.group = group;
+    }
+
+    /// The groups of the clauses that were needed to show that the
+    /// problem is unsatisfiable, once [`Self::solve`] has said so.
+    ///
+    /// Clauses that were added without a group are still followed,
+    /// but are not reported.
+    pub fn unsatisfiable_core(&self) -> Vec<usize> {
+        let Some(conflict) = self.final_conflict else {
+            return Vec::new();
+        };
+        let mut visited = vec![false; self.clauses.len()];
+        let mut pending = vec![conflict];
+        let mut core = BTreeSet::new();
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut visited[index], true) {
+                continue;
+            }
+            core.extend(self.groups[index]);
+            pending.extend(self.antecedents[index].iter().copied());
+            // literals that are false at the top level are false
+            // because of the clauses that implied their negation
+            for lit in self.clauses[index].iter() {
+                if self.lit_value(*lit) == Some(false) && self.levels[lit.var()] == 0 {
+                    pending.extend(self.reasons[lit.var()]);
+                }
+            }
+        }
+        core.into_iter().collect()
+    }
+
+    /// Require that at least one of the given literals is true.
+    ///
+    /// Clauses can be added after a call to [`Self::solve`], in
+    /// which case the next solve starts again from the beginning
+    /// but keeps everything that was learned.
+    pub fn add_clause<I: IntoIterator<Item = Lit>>(&mut self, lits: I) {
+        self.backtrack(0);
+        let mut clause: Vec<Lit> = Vec::new();
+        let mut antecedents = Vec::new();
+        for lit in lits {
+            debug_assert!(lit.var() < self.num_vars(), "literal for unknown variable");
+            match self.lit_value(lit) {
+                // values at the top level can never change
+                Some(true) => return,
+                Some(false) => {
+                    antecedents.extend(self.reasons[lit.var()]);
+                    continue;
+                }
+                None => {}
+            }
+            if clause.contains(&!lit) {
+                // always satisfied
+                return;
+            }
+            if !clause.contains(&lit) {
+                clause.push(lit);
+            }
+        }
+        let group = self.group;
+        match clause.len() {
+            0 => {
+                let index = self.store_clause(clause, group, antecedents);
+                self.final_conflict = Some(index);
+                self.unsatisfiable = true;
+            }
+            1 => {
+                let lit = clause[0];
+                let index = self.store_clause(clause, group, antecedents);
+                self.assign(lit, Some(index));
+            }
+            _ => {
+                self.watch_clause(clause, group, antecedents);
+            }
+        }
+    }
+
+    /// Require that at least one of the given literals is true,
+    /// and use this clause to guide the search.
+    ///
+    /// Once any negative literals in the clause are known to be
+    /// false, the first positive literal that is still possible is
+    /// tried before any others.
+    pub fn add_requirement<I: IntoIterator<Item = Lit>>(&mut self, lits: I) {
+        let lits: Vec<Lit> = lits.into_iter().collect();
+        self.add_clause(lits.iter().copied());
+        self.requirements.push(lits);
+    }
+
+    /// Require that at most one of the given literals is true.
+    pub fn add_at_most_one(&mut self, lits: &[Lit]) {
+        const PAIRWISE_LIMIT: usize = 6;
+        if lits.len() <= PAIRWISE_LIMIT {
+            for (i, a) in lits.iter().enumerate() {
+                for b in lits.iter().skip(i + 1) {
+                    self.add_clause([!*a, !*b]);
+                }
+            }
+            return;
+        }
+        // sequential counter encoding, where each new variable
+        // is true if any of the literals up to it are true
+        let mut previous: Option<Lit> = None;
+        for (i, lit) in lits.iter().enumerate() {
+            let is_last = i + 1 == lits.len();
+            if let Some(previous) = previous {
+                self.add_clause([!*lit, !previous]);
+            }
+            if is_last {
+                break;
+            }
+            let seen = Lit::positive(self.new_var());
+            self.add_clause([!*lit, seen]);
+            if let Some(previous) = previous {
+                self.add_clause([!previous, seen]);
+            }
+            previous = Some(seen);
+        }
+    }
+
+    /// Search for an assignment that satisfies every clause.
+    ///
+    /// The given function is called periodically, and the
+    /// solve is stopped if it returns true.
+    pub fn solve<F>(&mut self, mut should_stop: F) -> SatOutcome
+    where
+        F: FnMut(&SatStats) -> bool,
+    {
+        const CHECK_INTERVAL: usize = 64;
+        if self.unsatisfiable {
+            return SatOutcome::Unsatisfiable;
+        }
+        let mut steps = 0;
+        loop {
+            steps += 1;
+            if steps % CHECK_INTERVAL == 0 && should_stop(&self.stats) {
+                return SatOutcome::Interrupted;
+            }
+            if let Some(conflict) = self.propagate() {
+                self.stats.conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.final_conflict = Some(conflict);
+                    self.unsatisfiable = true;
+                    return SatOutcome::Unsatisfiable;
+                }
+                let (learned, level, antecedents) = self.analyze(conflict);
+                self.backtrack(level);
+                self.learn(learned, antecedents);
+                continue;
+            }
+            let Some(decision) = self.pick_decision() else {
+                let model = self.values.iter().map(|v| v.unwrap_or(false)).collect();
+                return SatOutcome::Satisfiable(model);
+            };
+            self.stats.decisions += 1;
+            self.trail_limits.push(self.trail.len());
+            self.assign(decision, None);
+        }
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|v| v != lit.is_negative())
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(!lit.is_negative());
+        self.levels[var] = self.decision_level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Keep a clause without watching it, for those with
+    /// less than two literals.
+    fn store_clause(
+        &mut self,
+        clause: Vec<Lit>,
+        group: Option<usize>,
+        antecedents: Vec<usize>,
+    ) -> usize {
+        let index = self.clauses.len();
+        self.clauses.push(clause);
+        self.groups.push(group);
+        self.antecedents.push(antecedents);
+        index
+    }
+
+    fn watch_clause(
+        &mut self,
+        clause: Vec<Lit>,
+        group: Option<usize>,
+        antecedents: Vec<usize>,
+    ) -> usize {
+        self.watches[clause[0].index()].push(self.clauses.len());
+        self.watches[clause[1].index()].push(self.clauses.len());
+        self.store_clause(clause, group, antecedents)
+    }
+
+    /// Assign every literal that is implied by the current assignment,
+    /// returning the index of a clause that cannot be satisfied, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = !self.trail[self.propagated];
+            self.propagated += 1;
+
+            let watchers = std::mem::take(&mut self.watches[false_lit.index()]);
+            let mut kept = Vec::with_capacity(watchers.len());
+            let mut conflict = None;
+            for (i, &index) in watchers.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watchers[i..]);
+                    break;
+                }
+                // keep the literal that just became false in the second slot
+                if self.clauses[index][0] == false_lit {
+                    self.clauses[index].swap(0, 1);
+                }
+                let first = self.clauses[index][0];
+                if self.lit_value(first) == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+                let replacement = (2..self.clauses[index].len())
+                    .find(|&k| self.lit_value(self.clauses[index][k]) != Some(false));
+                if let Some(k) = replacement {
+                    self.clauses[index].swap(1, k);
+                    let watched = self.clauses[index][1];
+                    self.watches[watched.index()].push(index);
+                    continue;
+                }
+                kept.push(index);
+                match self.lit_value(first) {
+                    Some(false) => conflict = Some(index),
+                    _ => self.assign(first, Some(index)),
+                }
+            }
+            self.watches[false_lit.index()] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Find the first unique implication point of a conflict,
+    /// returning the clause to learn, the level to jump back to
+    /// and the clauses that the learned one was derived from.
+    ///
+    /// The first literal of the learned clause is the one that
+    /// becomes true after jumping back.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize, Vec<usize>) {
+        let current_level = self.decision_level();
+        let mut seen = vec![false; self.num_vars()];
+        let mut learned = vec![Lit(0)];
+        let mut antecedents = Vec::new();
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut position = self.trail.len();
+        let asserting = loop {
+            antecedents.push(clause);
+            // the first literal of a reason clause is the one it implied
+            let skip = if clause == conflict { 0 } else { 1 };
+            for &lit in self.clauses[clause].iter().skip(skip) {
+                let var = lit.var();
+                if seen[var] {
+                    continue;
+                }
+                if self.levels[var] == 0 {
+                    // left out of the learned clause, because
+                    // of whatever made it false at the top level
+                    antecedents.extend(self.reasons[var]);
+                    continue;
+                }
+                seen[var] = true;
+                if self.levels[var] == current_level {
+                    pending += 1;
+                } else {
+                    learned.push(lit);
+                }
+            }
+            // the most recent assignment involved in the conflict
+            let lit = loop {
+                position -= 1;
+                let lit = self.trail[position];
+                if seen[lit.var()] {
+                    break lit;
+                }
+            };
+            seen[lit.var()] = false;
+            pending -= 1;
+            if pending == 0 {
+                break lit;
+            }
+            clause = self.reasons[lit.var()]
+                .expect("a literal implied at the current level should have a reason");
+        };
+        learned[0] = !asserting;
+
+        // watch the literal that will be unassigned last after jumping back
+        let mut level = 0;
+        if learned.len() > 1 {
+            let deepest = (1..learned.len())
+                .max_by_key(|&i| self.levels[learned[i].var()])
+                .expect("learned clause has more than one literal");
+            learned.swap(1, deepest);
+            level = self.levels[learned[1].var()];
+        }
+        (learned, level, antecedents)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let limit = self.trail_limits[level];
+        for lit in self.trail.drain(limit..) {
+            self.values[lit.var()] = None;
+            self.reasons[lit.var()] = None;
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    fn learn(&mut self, learned: Vec<Lit>, antecedents: Vec<usize>) {
+        self.stats.learned += 1;
+        let asserting = learned[0];
+        let index = if learned.len() == 1 {
+            self.store_clause(learned, None, antecedents)
+        } else {
+            self.watch_clause(learned, None, antecedents)
+        };
+        self.assign(asserting, Some(index));
+    }
+
+    /// Choose the next literal to assign, or none if every variable
+    /// can be left as it is.
+    fn pick_decision(&self) -> Option<Lit> {
+        for requirement in self.requirements.iter() {
+            let mut candidate = None;
+            let mut active = true;
+            for &lit in requirement.iter() {
+                match self.lit_value(lit) {
+                    Some(true) => {
+                        active = false;
+                        break;
+                    }
+                    Some(false) => {}
+                    // a requirement that depends on something
+                    // still undecided does not need to be met yet
+                    None if lit.is_negative() => active = false,
+                    None => {
+                        candidate = candidate.or(Some(lit));
+                    }
+                }
+            }
+            if active {
+                if let Some(lit) = candidate {
+                    return Some(lit);
+                }
+            }
+        }
+        self.values
+            .iter()
+            .position(Option::is_none)
+            .map(Lit::negative)
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+
+use super::{CdclSolver, Lit, SatOutcome};
+
+fn vars(solver: &mut CdclSolver, count: usize) -> Vec<Lit> {
+    (0..count)
+        .map(|_| Lit::positive(solver.new_var()))
+        .collect()
+}
+
+fn expect_model(outcome: SatOutcome) -> Vec<bool> {
+    match outcome {
+        SatOutcome::Satisfiable(model) => model,
+        other => panic!("expected a satisfying assignment, got {other:?}"),
+    }
+}
+
+/// Put `pigeons` into `holes` so that no hole holds more than one
+fn pigeonhole(pigeons: usize, holes: usize) -> CdclSolver {
+    let mut solver = CdclSolver::default();
+    let grid: Vec<Vec<Lit>> = (0..pigeons).map(|_| vars(&mut solver, holes)).collect();
+    for pigeon in grid.iter() {
+        solver.add_clause(pigeon.iter().copied());
+    }
+    for hole in 0..holes {
+        let column: Vec<Lit> = grid.iter().map(|pigeon| pigeon[hole]).collect();
+        solver.add_at_most_one(&column);
+    }
+    solver
+}
+
+#[rstest]
+fn test_solve_simple_implications() {
+    let mut solver = CdclSolver::default();
+    let x = vars(&mut solver, 3);
+    solver.add_clause([x[0]]);
+    solver.add_clause([!x[0], x[1]]);
+    solver.add_clause([!x[1], !x[2]]);
+    let model = expect_model(solver.solve(|_| false));
+    assert_eq!(model, vec![true, true, false]);
+}
+
+#[rstest]
+#[case(2, 2, true)]
+#[case(3, 2, false)]
+#[case(5, 5, true)]
+#[case(6, 5, false)]
+#[case(8, 8, true)]
+fn test_solve_pigeonhole(#[case] pigeons: usize, #[case] holes: usize, #[case] expected: bool) {
+    let mut solver = pigeonhole(pigeons, holes);
+    let outcome = solver.solve(|_| false);
+    if !expected {
+        assert_eq!(outcome, SatOutcome::Unsatisfiable);
+        return;
+    }
+    let model = expect_model(outcome);
+    for hole in 0..holes {
+        let used = (0..pigeons).filter(|p| model[p * holes + hole]).count();
+        assert!(used <= 1, "hole {hole} should hold at most one pigeon");
+    }
+    for pigeon in 0..pigeons {
+        assert!(
+            (0..holes).any(|h| model[pigeon * holes + h]),
+            "pigeon {pigeon} should be placed"
+        );
+    }
+}
+
+#[rstest]
+fn test_requirements_prefer_earlier_literals() {
+    let mut solver = CdclSolver::default();
+    let x = vars(&mut solver, 4);
+    // when x0 is chosen, either x2 or x3 is needed, but x2 rules out x0
+    solver.add_requirement([x[0], x[1]]);
+    solver.add_requirement([!x[0], x[2], x[3]]);
+    solver.add_clause([!x[2], !x[0]]);
+    let model = expect_model(solver.solve(|_| false));
+    assert_eq!(model, vec![true, false, false, true]);
+}
+
+#[rstest]
+fn test_unrequired_variables_are_false() {
+    let mut solver = CdclSolver::default();
+    let x = vars(&mut solver, 3);
+    solver.add_requirement([!x[0], x[1]]);
+    solver.add_clause([!x[2], x[1]]);
+    let model = expect_model(solver.solve(|_| false));
+    assert_eq!(model, vec![false, false, false]);
+}
+
+#[rstest]
+fn test_clauses_added_after_solving() {
+    let mut solver = CdclSolver::default();
+    let x = vars(&mut solver, 3);
+    solver.add_requirement(x.iter().copied());
+    solver.add_at_most_one(&x);
+    for expected in 0..3 {
+        let model = expect_model(solver.solve(|_| false));
+        assert!(model[expected], "expected x{expected} to be chosen next");
+        solver.add_clause([!x[expected]]);
+    }
+    assert_eq!(solver.solve(|_| false), SatOutcome::Unsatisfiable);
+}
+
+#[rstest]
+fn test_solve_can_be_interrupted() {
+    let mut solver = pigeonhole(9, 8);
+    assert_eq!(solver.solve(|_| true), SatOutcome::Interrupted);
+}
+
+#[rstest]
+fn test_unsatisfiable_core_at_top_level() {
+    let mut solver = CdclSolver::default();
+    let x = vars(&mut solver, 4);
+    solver.set_group(Some(0));
+    solver.add_clause([x[2], x[3]]);
+    solver.set_group(Some(1));
+    solver.add_clause([x[0]]);
+    solver.add_clause([!x[0], x[1]]);
+    solver.set_group(Some(2));
+    solver.add_clause([!x[1]]);
+
+    assert_eq!(solver.solve(|_| false), SatOutcome::Unsatisfiable);
+    assert_eq!(solver.unsatisfiable_core(), vec![1, 2]);
+}
+
+#[rstest]
+fn test_unsatisfiable_core_after_search() {
+    let mut solver = CdclSolver::default();
+    let x = vars(&mut solver, 6);
+    solver.set_group(Some(0));
+    solver.add_requirement([x[0], x[1]]);
+    solver.set_group(Some(1));
+    solver.add_clause([!x[0], x[2]]);
+    solver.add_clause([!x[1], x[2]]);
+    solver.set_group(Some(2));
+    solver.add_clause([!x[2], x[3]]);
+    solver.add_clause([!x[2], !x[3]]);
+    solver.set_group(Some(3));
+    solver.add_requirement([x[4], x[5]]);
+
+    assert_eq!(solver.solve(|_| false), SatOutcome::Unsatisfiable);
+    assert_eq!(
+        solver.unsatisfiable_core(),
+        vec![0, 1, 2],
+        "only the groups involved in the conflict should be reported"
+    );
+}
@@ -0,0 +1,822 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! A solver backend that encodes a solve as a boolean satisfiability problem
+//!
+//! Every build that can be reached from the initial requests is loaded up
+//! front and becomes a variable that is true when the build is part of the
+//! solution. Each request becomes a clause that needs one of the builds
+//! that satisfy it whenever whatever made the request is itself part of
+//! the solution, and the rules that the validators apply between packages
+//! (one build per package, var requirements, conflicts and embedded
+//! packages) become clauses between those variables.
+//!
+//! The model that is found is replayed through the same decisions that the
+//! graph solver makes, so the final solution is checked by the validators
+//! just like any other. A combination that they reject is ruled out and
+//! the problem is solved again.
+
+mod cdcl;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
+
+use cdcl::{CdclSolver, Lit, SatOutcome};
+use itertools::Itertools;
+use spk_schema::foundation::ident_build::Build;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::name::PkgNameBuf;
+use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::foundation::spec_ops::{Named, Versioned};
+use spk_schema::ident::{InclusionPolicy, PkgRequest, Request, RequestedBy, VarRequest};
+use spk_schema::{AnyIdent, BuildIdent, EmbeddedPackagesList, Package, RequirementsList, Spec};
+use spk_solve_graph::{Change, Decision, GraphError, RequestPackage, State};
+use spk_solve_package_iterator::{
+    BuildIterator,
+    PackageIterator,
+    RepositoryPackageIterator,
+    SortedBuildIterator,
+};
+use spk_solve_solution::{PackageSource, Solution};
+use spk_solve_validation::{with_default_prerelease_policy, DefaultPreReleasePolicies};
+
+use crate::error::OutOfOptions;
+use crate::{Error, Result, Solver, SolverBackend};
+
+#[cfg(test)]
+#[path = "./sat_test.rs"]
+mod sat_test;
+
+/// Finds a solution by handing the whole solve to a SAT solver.
+///
+/// This is slower than the graph solver for most solves, because
+/// every reachable build has to be read before the search can start,
+/// but it does not get stuck retrying the same incompatible
+/// combinations on deep or heavily constrained package graphs.
+///
+/// Builds are not made from source by this backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SatSolverBackend;
+
+#[async_trait::async_trait]
+impl SolverBackend for SatSolverBackend {
+    async fn solve(&self, solver: &Solver) -> Result<Solution> {
+        let start = Instant::now();
+        let mut problem = Problem::new(solver, start);
+        problem.explore().await?;
+        problem.encode()?;
+        tracing::debug!(
+            "SAT solve of {} builds uses {} variables and {} clauses",
+            problem.candidates.len(),
+            problem.sat.num_vars(),
+            problem.sat.num_clauses(),
+        );
+
+        loop {
+            let outcome = problem
+                .sat
+                .solve(|stats| solver.check_limits(stats.decisions, start).is_err());
+            let stats = problem.sat.stats();
+            match outcome {
+                SatOutcome::Satisfiable(model) => match problem.replay(&model)? {
+                    Replay::Solved(solution) => {
+                        tracing::debug!(
+                            "SAT solve finished after {} decisions and {} conflicts",
+                            stats.decisions,
+                            stats.conflicts
+                        );
+                        return Ok(solution);
+                    }
+                    Replay::Rejected(lits) => {
+                        // this exact combination can never work
+                        problem.sat.set_group(None);
+                        problem.sat.add_clause(lits.into_iter().map(|lit| !lit));
+                    }
+                },
+                SatOutcome::Unsatisfiable => return Err(problem.unsatisfiable()),
+                SatOutcome::Interrupted => {
+                    solver.check_limits(stats.decisions, start)?;
+                    return Err(Error::SolverInterrupted(
+                        "SAT solve was stopped before finding a solution".into(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// A build that could be part of the solution.
+struct Candidate {
+    spec: Arc<Spec>,
+    source: PackageSource,
+    lit: Lit,
+    /// For builds that are embedded in (or provided by) another,
+    /// the literal that is true when that other build is used
+    embedded_by: Option<Lit>,
+    /// The literal that is true when each component that has
+    /// requirements or embedded packages is used
+    components: Vec<(Component, Lit)>,
+    /// True once the requirements of this build have been added
+    expanded: bool,
+}
+
+/// A request for a package and the condition under which it is made.
+struct Requirement {
+    request: PkgRequest,
+    /// The literal that is true when this request is made,
+    /// or None if it is one of the initial requests
+    condition: Option<Lit>,
+    /// True for the alternatives of a request for any one of several
+    /// packages, which only need to be met when they are chosen
+    alternative: bool,
+    /// The candidates that satisfy this request, in the order
+    /// that they should be tried
+    satisfied_by: Vec<usize>,
+    /// The initial state with this request added, for validation
+    state: Option<Arc<State>>,
+}
+
+/// A request for one of several packages and the condition under which it is made.
+struct AnyOfRequirement {
+    alternatives: Vec<usize>,
+    condition: Option<Lit>,
+}
+
+/// A var request made by a candidate.
+struct VarRequirement {
+    request: VarRequest,
+    condition: Lit,
+    owner: usize,
+    /// True for the runtime requirements of the package
+    /// itself, as opposed to those of one of its components
+    package_level: bool,
+}
+
+/// Everything known about the builds of one package.
+#[derive(Default)]
+struct PackageCandidates {
+    loaded: bool,
+    /// Builds from the repositories grouped by version, in the
+    /// order that they should be tried
+    versions: Vec<(AnyIdent, Vec<usize>)>,
+    /// Builds that are embedded in other candidates
+    embedded: Vec<usize>,
+    /// The requirements made for this package
+    requirements: Vec<usize>,
+}
+
+impl PackageCandidates {
+    fn candidates(&self) -> impl Iterator<Item = usize> + '_ {
+        self.versions
+            .iter()
+            .flat_map(|(_, builds)| builds.iter().copied())
+            .chain(self.embedded.iter().copied())
+    }
+}
+
+/// A rule that was encoded as clauses, which is reported when
+/// it is part of the reason that a problem is unsatisfiable.
+enum Constraint {
+    /// A requirement must be satisfied by one of its candidates
+    Request(usize),
+    /// One of the alternatives of a request must be satisfied
+    AnyOf(usize),
+    /// Only one build of a package can be used
+    OneBuild(PkgNameBuf),
+    /// The builds that conflict with a var requirement cannot be used
+    Var(usize),
+    /// Two builds that conflict cannot be used together
+    Conflict(usize, usize),
+}
+
+enum Replay {
+    Solved(Solution),
+    /// The literals of the builds that could not be used together
+    Rejected(Vec<Lit>),
+}
+
+struct Problem<'a> {
+    solver: &'a Solver,
+    start: Instant,
+    initial: Arc<State>,
+    sat: CdclSolver,
+    candidates: Vec<Candidate>,
+    packages: BTreeMap<PkgNameBuf, PackageCandidates>,
+    requirements: Vec<Requirement>,
+    any_of: Vec<AnyOfRequirement>,
+    var_requirements: Vec<VarRequirement>,
+    /// The rules that the clauses of each group were made from
+    constraints: Vec<Constraint>,
+    /// Requirements before this one have had their candidates found
+    next_requirement: usize,
+}
+
+impl<'a> Problem<'a> {
+    fn new(solver: &'a Solver, start: Instant) -> Self {
+        let initial = solver.get_initial_state();
+        let mut problem = Self {
+            solver,
+            start,
+            initial: Arc::clone(&initial),
+            sat: CdclSolver::default(),
+            candidates: Vec::new(),
+            packages: BTreeMap::new(),
+            requirements: Vec::new(),
+            any_of: Vec::new(),
+            var_requirements: Vec::new(),
+            constraints: Vec::new(),
+            next_requirement: 0,
+        };
+        for request in initial.get_pkg_requests().iter() {
+            problem.add_requirement((***request).clone(), None, false);
+        }
+        for request in initial.get_any_of_requests().iter() {
+            let alternatives = request
+                .alternatives
+                .iter()
+                .map(|alternative| problem.add_requirement(alternative.clone(), None, true))
+                .collect();
+            problem.any_of.push(AnyOfRequirement {
+                alternatives,
+                condition: None,
+            });
+        }
+        problem
+    }
+
+    /// Find the candidates for every request that can be reached
+    /// from the initial ones.
+    async fn explore(&mut self) -> Result<()> {
+        while self.next_requirement < self.requirements.len() {
+            self.solver.check_limits(0, self.start)?;
+            let index = self.next_requirement;
+            self.next_requirement += 1;
+            self.load_package(index).await?;
+
+            let request = &self.requirements[index].request;
+            let package = &self.packages[&request.pkg.name];
+            let defaults = DefaultPreReleasePolicies::current();
+            let default_policy = self
+                .solver
+                .repos_for_package(&request.pkg.name)
+                .iter()
+                .map(|repo| defaults.policy_for(Some(repo.name().as_str())))
+                .max()
+                .unwrap_or_default();
+            let version_request = with_default_prerelease_policy(request, default_policy);
+            let options: Vec<usize> = package
+                .versions
+                .iter()
+                .filter(|(version, _)| {
+                    version_request
+                        .is_version_applicable(version.version())
+                        .is_ok()
+                })
+                .flat_map(|(_, builds)| builds.iter().copied())
+                .chain(package.embedded.iter().copied())
+                .collect();
+            drop(version_request);
+
+            for candidate in options {
+                if self.satisfies(index, candidate)? {
+                    self.requirements[index].satisfied_by.push(candidate);
+                    self.expand(candidate)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read all the builds of the package named by a requirement,
+    /// if that has not been done already.
+    async fn load_package(&mut self, requirement: usize) -> Result<()> {
+        let request = &self.requirements[requirement].request;
+        let name = request.pkg.name.clone();
+        if self.packages.get(&name).is_some_and(|p| p.loaded) {
+            return Ok(());
+        }
+        let must_exist = self.requirements[requirement].condition.is_none()
+            && !self.requirements[requirement].alternative
+            && request.inclusion_policy == InclusionPolicy::Always;
+
+        let repos = self.solver.repos_for_package(&name);
+        let mut iterator = RepositoryPackageIterator::new(name.clone(), repos);
+        let mut versions = Vec::new();
+        loop {
+            let (pkg, builds) = match iterator.next().await {
+                Ok(Some(item)) => item,
+                Ok(None) => break,
+                Err(spk_solve_package_iterator::Error::SpkStorageError(
+                    spk_storage::Error::PackageNotFound(_),
+                )) => {
+                    if must_exist {
+                        return Err(spk_solve_graph::Error::PackageNotFoundDuringSolve(
+                            self.requirements[requirement].request.clone(),
+                        )
+                        .into());
+                    }
+                    // other requests for a missing package are
+                    // left with nothing to satisfy them
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let mut sorted = SortedBuildIterator::new_with_build_order(
+                self.initial.get_option_map().clone(),
+                builds,
+                HashMap::new(),
+                self.solver.build_order(),
+            )
+            .await?;
+            let mut found = Vec::new();
+            while let Some(builds) = sorted.next().await? {
+                for (spec, source) in self.solver.order_by_repository_priority(builds) {
+                    if let PackageSource::Embedded { .. } = source {
+                        // embedded packages are added along with
+                        // the candidates that embed them
+                        continue;
+                    }
+                    found.push(self.add_candidate(spec, source, None));
+                }
+            }
+            versions.push((pkg, found));
+        }
+
+        let package = self.packages.entry(name).or_default();
+        package.loaded = true;
+        package.versions = versions;
+        Ok(())
+    }
+
+    fn add_candidate(
+        &mut self,
+        spec: Arc<Spec>,
+        source: PackageSource,
+        embedded_by: Option<Lit>,
+    ) -> usize {
+        let lit = Lit::positive(self.sat.new_var());
+        self.candidates.push(Candidate {
+            spec,
+            source,
+            lit,
+            embedded_by,
+            components: Vec::new(),
+            expanded: false,
+        });
+        self.candidates.len() - 1
+    }
+
+    fn add_requirement(
+        &mut self,
+        mut request: PkgRequest,
+        condition: Option<Lit>,
+        alternative: bool,
+    ) -> usize {
+        if request.pkg.components.is_empty() {
+            if request.pkg.is_source() {
+                request.pkg.components.insert(Component::Source);
+            } else {
+                request.pkg.components.insert(Component::default_for_run());
+            }
+        }
+        let index = self.requirements.len();
+        self.packages
+            .entry(request.pkg.name.clone())
+            .or_default()
+            .requirements
+            .push(index);
+        self.requirements.push(Requirement {
+            request,
+            condition,
+            alternative,
+            satisfied_by: Vec::new(),
+            state: None,
+        });
+        index
+    }
+
+    /// Add the requests made by a candidate, which only
+    /// apply when the given condition is true.
+    fn add_requests(
+        &mut self,
+        requests: &RequirementsList,
+        condition: Lit,
+        owner: usize,
+        package_level: bool,
+    ) {
+        let requester = RequestedBy::PackageBuild(self.candidates[owner].spec.ident().clone());
+        for request in requests.iter() {
+            match request {
+                Request::Pkg(request) => {
+                    let mut request = request.clone();
+                    request.add_requester(requester.clone());
+                    self.add_requirement(request, Some(condition), false);
+                }
+                Request::Var(request) => self.var_requirements.push(VarRequirement {
+                    request: request.clone(),
+                    condition,
+                    owner,
+                    package_level,
+                }),
+                Request::AnyOf(request) => {
+                    let mut request = request.clone();
+                    request.add_requester(requester.clone());
+                    let alternatives = request
+                        .alternatives
+                        .into_iter()
+                        .map(|alternative| self.add_requirement(alternative, Some(condition), true))
+                        .collect();
+                    self.any_of.push(AnyOfRequirement {
+                        alternatives,
+                        condition: Some(condition),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Add the requirements and embedded packages of a candidate.
+    fn expand(&mut self, candidate: usize) -> Result<()> {
+        if self.candidates[candidate].expanded {
+            return Ok(());
+        }
+        self.candidates[candidate].expanded = true;
+        let spec = Arc::clone(&self.candidates[candidate].spec);
+        let lit = self.candidates[candidate].lit;
+
+        self.add_requests(&spec.runtime_requirements(), lit, candidate, true);
+        let provided = EmbeddedPackagesList::from_provided(spec.provides());
+        for embedded in spec.embedded().iter().chain(provided.iter()) {
+            self.add_embedded(embedded, spec.ident(), lit)?;
+        }
+        for component in spec.components().iter() {
+            if component.requirements.is_empty() && component.embedded.is_empty() {
+                continue;
+            }
+            let used = Lit::positive(self.sat.new_var());
+            self.candidates[candidate]
+                .components
+                .push((component.name.clone(), used));
+            self.add_requests(&component.requirements, used, candidate, false);
+            for embedded in component.embedded.iter() {
+                self.add_embedded(embedded, spec.ident(), used)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_embedded(&mut self, embedded: &Spec, parent: &BuildIdent, condition: Lit) -> Result<()> {
+        let source = PackageSource::Embedded {
+            parent: parent.clone(),
+        };
+        let index = self.add_candidate(Arc::new(embedded.clone()), source, Some(condition));
+        let next_requirement = self.next_requirement;
+        let package = self.packages.entry(embedded.name().to_owned()).or_default();
+        package.embedded.push(index);
+        // requests that have already been explored will not see this one
+        let explored: Vec<usize> = package
+            .requirements
+            .iter()
+            .copied()
+            .filter(|r| *r < next_requirement)
+            .collect();
+        for requirement in explored {
+            if self.satisfies(requirement, index)? {
+                self.requirements[requirement].satisfied_by.push(index);
+            }
+        }
+        self.expand(index)
+    }
+
+    /// True if the candidate is valid for the given requirement when
+    /// it is added to the initial requests.
+    fn satisfies(&mut self, requirement: usize, candidate: usize) -> Result<bool> {
+        let spec = &self.candidates[candidate].spec;
+        let request = &self.requirements[requirement].request;
+        if spec.ident().is_source() && request.pkg.build != Some(Build::Source) {
+            return Ok(false);
+        }
+        let state = match &self.requirements[requirement].state {
+            Some(state) => Arc::clone(state),
+            None => {
+                let change = Change::RequestPackage(RequestPackage::new(request.clone()));
+                let state = change.apply(&self.initial, &self.initial);
+                self.requirements[requirement].state = Some(Arc::clone(&state));
+                state
+            }
+        };
+        let Candidate { spec, source, .. } = &self.candidates[candidate];
+        Ok(self.solver.validate_package(&state, spec, source)?.is_ok())
+    }
+
+    /// Turn everything that was found into clauses.
+    fn encode(&mut self) -> Result<()> {
+        // only one build of each package can be used
+        let groups: Vec<(PkgNameBuf, Vec<Lit>)> = self
+            .packages
+            .iter()
+            .map(|(name, package)| {
+                let lits = package
+                    .candidates()
+                    .map(|c| self.candidates[c].lit)
+                    .collect();
+                (name.clone(), lits)
+            })
+            .collect();
+        for (name, group) in groups {
+            self.constraints.push(Constraint::OneBuild(name));
+            self.sat.set_group(Some(self.constraints.len() - 1));
+            self.sat.add_at_most_one(&group);
+        }
+
+        // the conditions under which each candidate is requested
+        let mut justified_by: Vec<Vec<Option<Lit>>> = vec![Vec::new(); self.candidates.len()];
+        for (index, requirement) in self.requirements.iter().enumerate() {
+            self.constraints.push(Constraint::Request(index));
+            self.sat.set_group(Some(self.constraints.len() - 1));
+            let guard = requirement.condition.map(|c| !c);
+            let satisfied = requirement
+                .satisfied_by
+                .iter()
+                .map(|c| self.candidates[*c].lit);
+            let always = requirement.request.inclusion_policy == InclusionPolicy::Always;
+            if always && !requirement.alternative {
+                if guard.is_none() && requirement.satisfied_by.is_empty() {
+                    return Err(Error::OutOfOptions(OutOfOptions {
+                        request: requirement.request.clone(),
+                        notes: Vec::new(),
+                    }));
+                }
+                self.sat.add_requirement(guard.into_iter().chain(satisfied));
+            } else if !always {
+                let package = &self.packages[&requirement.request.pkg.name];
+                for candidate in package.candidates() {
+                    if !requirement.satisfied_by.contains(&candidate) {
+                        let lit = self.candidates[candidate].lit;
+                        self.sat.add_clause(guard.into_iter().chain([!lit]));
+                    }
+                }
+            }
+
+            for &candidate in requirement.satisfied_by.iter() {
+                if always || requirement.alternative {
+                    justified_by[candidate].push(requirement.condition);
+                }
+                let Candidate {
+                    spec,
+                    lit,
+                    components,
+                    ..
+                } = &self.candidates[candidate];
+                if components.is_empty() {
+                    continue;
+                }
+                let used = spec
+                    .components()
+                    .resolve_uses(requirement.request.pkg.components.iter());
+                for (name, component) in components.iter() {
+                    if used.contains(name) {
+                        self.sat
+                            .add_clause(guard.into_iter().chain([!*lit, *component]));
+                    }
+                }
+            }
+        }
+
+        for (index, any_of) in self.any_of.iter().enumerate() {
+            self.constraints.push(Constraint::AnyOf(index));
+            self.sat.set_group(Some(self.constraints.len() - 1));
+            let guard = any_of.condition.map(|c| !c);
+            let satisfied = any_of
+                .alternatives
+                .iter()
+                .flat_map(|r| self.requirements[*r].satisfied_by.iter())
+                .map(|c| self.candidates[*c].lit);
+            self.sat.add_requirement(guard.into_iter().chain(satisfied));
+        }
+
+        // these only follow from the rules above
+        self.sat.set_group(None);
+        for (candidate, justification) in self.candidates.iter().zip(justified_by) {
+            match candidate.embedded_by {
+                Some(condition) => {
+                    // embedded packages come and go with what embeds them
+                    self.sat.add_clause([!condition, candidate.lit]);
+                    self.sat.add_clause([!candidate.lit, condition]);
+                }
+                None if !candidate.expanded => self.sat.add_clause([!candidate.lit]),
+                None if justification.iter().any(Option::is_none) => {}
+                None => {
+                    let conditions = justification.into_iter().flatten();
+                    self.sat
+                        .add_clause([!candidate.lit].into_iter().chain(conditions));
+                }
+            }
+        }
+
+        let usable: Vec<usize> = (0..self.candidates.len())
+            .filter(|c| self.candidates[*c].expanded || self.candidates[*c].embedded_by.is_some())
+            .collect();
+        let options: HashMap<usize, OptionMap> = usable
+            .iter()
+            .map(|c| (*c, contributed_options(&self.candidates[*c].spec)))
+            .collect();
+        for (index, requirement) in self.var_requirements.iter().enumerate() {
+            self.constraints.push(Constraint::Var(index));
+            self.sat.set_group(Some(self.constraints.len() - 1));
+            let owner = &self.candidates[requirement.owner];
+            for &other in usable.iter() {
+                let candidate = &self.candidates[other];
+                if candidate.spec.name() == owner.spec.name() {
+                    continue;
+                }
+                let conflicts = (requirement.package_level
+                    && conflicts_with_options(&requirement.request, &options[&other]))
+                    || !requirement
+                        .request
+                        .is_satisfied_by(&*candidate.spec)
+                        .is_ok();
+                if conflicts {
+                    self.sat
+                        .add_clause([!requirement.condition, !candidate.lit]);
+                }
+            }
+        }
+
+        for &candidate in usable.iter() {
+            let conflicts = self.candidates[candidate].spec.runtime_conflicts();
+            if conflicts.is_empty() {
+                continue;
+            }
+            for &other in usable.iter() {
+                let ident = self.candidates[other].spec.ident();
+                if other != candidate && conflicts.iter().any(|c| c.is_conflicting_package(ident)) {
+                    self.constraints
+                        .push(Constraint::Conflict(candidate, other));
+                    self.sat.set_group(Some(self.constraints.len() - 1));
+                    let (a, b) = (self.candidates[candidate].lit, self.candidates[other].lit);
+                    self.sat.add_clause([!a, !b]);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Make the decisions for the chosen builds in the same way as the
+    /// graph solver, so that they are checked by the validators.
+    fn replay(&self, model: &[bool]) -> Result<Replay> {
+        let mut selected = HashMap::new();
+        for (index, candidate) in self.candidates.iter().enumerate() {
+            if model[candidate.lit.var()] {
+                selected.insert(candidate.spec.name().to_owned(), index);
+            }
+        }
+
+        let mut state = Arc::clone(&self.initial);
+        let mut resolved = Vec::new();
+        loop {
+            let next = state.get_pkg_requests().iter().find_map(|request| {
+                if request.inclusion_policy == InclusionPolicy::IfAlreadyPresent
+                    || state.get_current_resolve(&request.pkg.name).is_ok()
+                {
+                    return None;
+                }
+                match selected.get(&request.pkg.name) {
+                    // resolved along with the package that embeds it
+                    Some(c) if self.candidates[*c].embedded_by.is_some() => None,
+                    candidate => Some((request.pkg.name.clone(), candidate.copied())),
+                }
+            });
+            if let Some((name, candidate)) = next {
+                let Some(candidate) = candidate else {
+                    return Ok(Replay::Rejected(resolved));
+                };
+                let Candidate {
+                    spec, source, lit, ..
+                } = &self.candidates[candidate];
+                resolved.push(*lit);
+                let compat = self.solver.validate_package(&state, spec, source)?;
+                if !compat.is_ok() {
+                    tracing::debug!("SAT solution rejected {}: {compat}", spec.ident());
+                    return Ok(Replay::Rejected(resolved));
+                }
+                let request = state
+                    .get_merged_request(&name)
+                    .map_err(GraphError::RequestError)?;
+                state = Decision::builder(&state)
+                    .with_components(&request.pkg.components)
+                    .resolve_package(spec, source.clone())
+                    .apply(&state);
+                continue;
+            }
+
+            if let Some(request) = state.get_next_any_of_request() {
+                let alternative = request.alternatives.iter().find(|alternative| {
+                    selected.contains_key(&alternative.pkg.name)
+                        && state.get_current_resolve(&alternative.pkg.name).is_err()
+                });
+                let Some(alternative) = alternative.cloned() else {
+                    return Ok(Replay::Rejected(resolved));
+                };
+                state = Decision::builder(&state)
+                    .choose_alternative(&alternative)
+                    .apply(&state);
+                continue;
+            }
+            break;
+        }
+
+        let unresolved = state.get_pkg_requests().iter().any(|request| {
+            request.inclusion_policy == InclusionPolicy::Always
+                && state.get_current_resolve(&request.pkg.name).is_err()
+        });
+        if unresolved {
+            return Ok(Replay::Rejected(resolved));
+        }
+        Ok(Replay::Solved(state.as_solution()?))
+    }
+
+    fn unsatisfiable(&self) -> Error {
+        let builds = self.candidates.len();
+        let packages = self.packages.len();
+        let mut message = format!(
+            "none of the combinations of the {builds} builds found for {packages} packages satisfies every request"
+        );
+        let core: Vec<String> = self
+            .sat
+            .unsatisfiable_core()
+            .into_iter()
+            .map(|group| self.describe(&self.constraints[group]))
+            .unique()
+            .collect();
+        if !core.is_empty() {
+            message.push_str(", because these cannot all be met together:");
+            for reason in core {
+                message.push_str("\n - ");
+                message.push_str(&reason);
+            }
+        }
+        Error::Unsatisfiable(message)
+    }
+
+    fn describe(&self, constraint: &Constraint) -> String {
+        let describe_request = |request: &PkgRequest| {
+            let requesters = request
+                .get_requesters()
+                .iter()
+                .map(ToString::to_string)
+                .unique()
+                .join(", ");
+            format!("{} (requested by {requesters})", request.pkg)
+        };
+        match constraint {
+            Constraint::Request(index) => describe_request(&self.requirements[*index].request),
+            Constraint::AnyOf(index) => self.any_of[*index]
+                .alternatives
+                .iter()
+                .map(|r| describe_request(&self.requirements[*r].request))
+                .join(" or "),
+            Constraint::OneBuild(name) => format!("only one build of {name} can be used"),
+            Constraint::Var(index) => {
+                let requirement = &self.var_requirements[*index];
+                format!(
+                    "{} requires {}",
+                    self.candidates[requirement.owner].spec.ident(),
+                    requirement.request
+                )
+            }
+            Constraint::Conflict(a, b) => format!(
+                "{} conflicts with {}",
+                self.candidates[*a].spec.ident(),
+                self.candidates[*b].spec.ident()
+            ),
+        }
+    }
+}
+
+/// The options that are added to a solve when the given package is resolved.
+fn contributed_options(spec: &Spec) -> OptionMap {
+    let mut options = OptionMap::default();
+    options.insert(
+        spec.name().as_opt_name().to_owned(),
+        spec.compat().render(spec.version()),
+    );
+    for (name, value) in spec.option_values() {
+        if !value.is_empty() {
+            options.insert(name.with_default_namespace(spec.name()), value);
+        }
+    }
+    options
+}
+
+/// True if a package with the given var request could not be
+/// resolved alongside the given options.
+fn conflicts_with_options(request: &VarRequest, options: &OptionMap) -> bool {
+    let requested = request.value.as_pinned().unwrap_or_default();
+    options.iter().any(|(name, value)| {
+        let is_related = *name == request.var || request.var.base_name() == name.base_name();
+        is_related && !value.is_empty() && requested != value.as_str()
+    })
+}
@@ -0,0 +1,280 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use rstest::{fixture, rstest};
+use spk_schema::ident_build::{Build, EmbeddedSource};
+use spk_schema::prelude::*;
+use spk_solve_macros::{make_repo, request};
+
+use crate::{Error, Solution, Solver, SolverBackendKind};
+
+#[fixture]
+fn solver() -> Solver {
+    let mut solver = Solver::default();
+    solver.set_backend(SolverBackendKind::Sat);
+    solver
+}
+
+fn resolved_version(solution: &Solution, name: &str) -> String {
+    solution
+        .get(name)
+        .unwrap_or_else(|| panic!("expected {name} to be in solution"))
+        .spec
+        .version()
+        .to_string()
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_simple_deps(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {"pkg": "pkg-a/1.0.0"},
+            {"pkg": "pkg-a/1.2.1"},
+            {"pkg": "pkg-a/2.0.0"},
+            {"pkg": "pkg-b/1.0.0", "install": {"requirements": [{"pkg": "pkg-a/2.0"}]}},
+            {"pkg": "pkg-b/1.1.0", "install": {"requirements": [{"pkg": "pkg-a/1.2"}]}},
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("pkg-b/1.1"));
+
+    let solution = solver.solve().await.unwrap();
+    assert_eq!(solution.len(), 2, "expected two resolved packages");
+    assert_eq!(resolved_version(&solution, "pkg-a"), "1.2.1");
+    assert_eq!(resolved_version(&solution, "pkg-b"), "1.1.0");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_prefers_newest_valid_combination(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {"pkg": "maya/2019"},
+            {"pkg": "maya/2020"},
+            {
+                "pkg": "my-plugin/1.1.0",
+                "install": {"requirements": [{"pkg": "maya/2020"}]},
+            },
+            {
+                "pkg": "my-plugin/1.0.0",
+                "install": {"requirements": [{"pkg": "maya/2019"}]},
+            },
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-plugin/1"));
+    solver.add_request(request!("maya/2019"));
+
+    let solution = solver.solve().await.unwrap();
+    assert_eq!(resolved_version(&solution, "my-plugin"), "1.0.0");
+    assert_eq!(resolved_version(&solution, "maya"), "2019.0.0");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_unsatisfiable(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {"pkg": "maya/2019.0.0"},
+            {"pkg": "maya/2020.0.0"},
+            {
+                "pkg": "my-plugin/1.0.0",
+                "install": {"requirements": [{"pkg": "maya/2020"}]},
+            },
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-plugin/1"));
+    solver.add_request(request!("maya/2019"));
+
+    let res = solver.solve().await;
+    let Err(Error::Unsatisfiable(message)) = res else {
+        panic!("expected the solve to be unsatisfiable, got {res:?}");
+    };
+    assert!(
+        message.contains("/1 (requested by")
+            && message.contains("/2020 (requested by my-plugin/1.0.0"),
+        "expected the conflicting requests to be reported, got: {message}"
+    );
+    assert!(
+        !message.contains("only one build of my-plugin"),
+        "expected only the rules involved to be reported, got: {message}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_missing_initial_request(mut solver: Solver) {
+    let repo = make_repo!([{"pkg": "maya/2019.0.0"}]);
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("maya/2020"));
+
+    let res = solver.solve().await;
+    assert!(
+        matches!(res, Err(Error::OutOfOptions(_))),
+        "expected no options for the initial request, got {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_conflicts(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {"pkg": "maya/2019"},
+            {
+                "pkg": "my-plugin/1.1.0",
+                "install": {"conflicts": ["maya/<2020"]},
+            },
+            {"pkg": "my-plugin/1.0.0"},
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("maya/2019"));
+    solver.add_request(request!("my-plugin/1"));
+
+    let solution = solver.solve().await.unwrap();
+    assert_eq!(resolved_version(&solution, "my-plugin"), "1.0.0");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_var_requirements(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "python/2.7.5",
+                "build": {"options": [{"var": "abi", "static": "cp27mu"}]},
+            },
+            {
+                "pkg": "python/3.7.3",
+                "build": {"options": [{"var": "abi", "static": "cp37m"}]},
+            },
+            {
+                "pkg": "my-app/1.0.0",
+                "install": {
+                    "requirements": [{"pkg": "python"}, {"var": "python.abi/cp27mu"}]
+                },
+            },
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-app/1"));
+
+    let solution = solver.solve().await.unwrap();
+    assert_eq!(resolved_version(&solution, "python"), "2.7.5");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_embedded_package(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "maya/2019.2",
+                "build": {"script": "echo BUILD"},
+                "install": {"embedded": [{"pkg": "qt/5.12.6"}]},
+            },
+            {
+                "pkg": "qt/5.13.0",
+                "build": {"script": "echo BUILD"},
+            },
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("qt"));
+    solver.add_request(request!("maya"));
+
+    let solution = solver.solve().await.unwrap();
+    assert_eq!(resolved_version(&solution, "qt"), "5.12.6");
+    let qt = solution.get("qt").unwrap();
+    assert_eq!(
+        qt.spec.ident().build(),
+        &Build::Embedded(EmbeddedSource::Unknown)
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_component_requirements(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "mypkg/1.0.0",
+                "install": {
+                    "components": [
+                        {"name": "build", "uses": ["build2"]},
+                        {"name": "build2", "requirements": [{"pkg": "depb"}]},
+                        {"name": "run", "requirements": [{"pkg": "depr"}]},
+                    ],
+                },
+            },
+            {"pkg": "depb"},
+            {"pkg": "depr"},
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("mypkg:build"));
+
+    let solution = solver.solve().await.unwrap();
+    solution.get("depb").expect("should exist");
+    assert!(solution.get("depr").is_none());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_deep_backtracking(mut solver: Solver) {
+    // the newest version of every library needs a newer base than
+    // the application allows, which the graph solver can only find
+    // out by stepping back through each of the libraries in turn
+    let repo = make_repo!(
+        [
+            {"pkg": "base/1.0.0"},
+            {"pkg": "base/2.0.0"},
+            {"pkg": "lib-a/1.0.0", "install": {"requirements": [{"pkg": "base/1"}]}},
+            {"pkg": "lib-a/2.0.0", "install": {"requirements": [{"pkg": "base/2"}]}},
+            {"pkg": "lib-b/1.0.0", "install": {"requirements": [{"pkg": "base/1"}]}},
+            {"pkg": "lib-b/2.0.0", "install": {"requirements": [{"pkg": "base/2"}]}},
+            {"pkg": "lib-c/1.0.0", "install": {"requirements": [{"pkg": "base/1"}]}},
+            {"pkg": "lib-c/2.0.0", "install": {"requirements": [{"pkg": "base/2"}]}},
+            {
+                "pkg": "app/1.0.0",
+                "install": {
+                    "requirements": [
+                        {"pkg": "lib-a"},
+                        {"pkg": "lib-b"},
+                        {"pkg": "lib-c"},
+                        {"pkg": "base/1"},
+                    ]
+                },
+            },
+        ]
+    );
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("app"));
+
+    let solution = solver.solve().await.unwrap();
+    for lib in ["lib-a", "lib-b", "lib-c"] {
+        assert_eq!(resolved_version(&solution, lib), "1.0.0");
+    }
+    assert_eq!(resolved_version(&solution, "base"), "1.0.0");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_sat_solver_timeout(mut solver: Solver) {
+    let repo = make_repo!([{"pkg": "my-pkg/1.0.0"}]);
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-pkg"));
+    solver.set_timeout(Some(std::time::Duration::ZERO));
+
+    let res = solver.solve().await;
+    assert!(
+        matches!(res, Err(Error::SolverInterrupted(ref message)) if message.contains("time limit")),
+        "expected the solve to time out, got {res:?}"
+    );
+}
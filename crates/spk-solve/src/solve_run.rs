@@ -0,0 +1,210 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::ident::Request;
+use spk_schema::{BuildIdent, Package};
+use spk_solve_graph::{Change, Decision};
+
+use crate::{Error, MultiSolverKind, Result, Solver};
+
+#[cfg(test)]
+#[path = "./solve_run_test.rs"]
+mod solve_run_test;
+
+/// A record of the inputs to a solve and the decisions that it made.
+///
+/// The solver does not make any random choices, so running the same
+/// inputs against the same repositories will make the same decisions.
+/// A saved run can be replayed later on to check for this, or shared
+/// to help reproduce a problem with the solver.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveRun {
+    /// The version of spk that made this run
+    pub spk_version: String,
+    /// The names of the repositories that were searched, in order
+    pub repositories: Vec<String>,
+    /// The solver that made the decisions, when
+    /// more than one solver was run at once
+    pub solver: SolveRunSolver,
+    /// True if only existing binary packages could be resolved
+    pub binary_only: bool,
+    /// The deprecated builds that could be resolved
+    /// without being requested exactly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grandfathered_builds: Vec<BuildIdent>,
+    /// The requests and options that the solve started with, in order
+    pub inputs: Vec<SolveRunInput>,
+    /// Every decision that the solver made, in order
+    pub decisions: Vec<SolveRunDecision>,
+}
+
+/// Identifies which of the solvers that can be
+/// run by the formatter made a solve run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SolveRunSolver {
+    #[default]
+    Unchanged,
+    AllImpossibleChecks,
+}
+
+impl From<SolveRunSolver> for MultiSolverKind {
+    fn from(value: SolveRunSolver) -> Self {
+        match value {
+            SolveRunSolver::Unchanged => MultiSolverKind::Unchanged,
+            SolveRunSolver::AllImpossibleChecks => MultiSolverKind::AllImpossibleChecks,
+        }
+    }
+}
+
+/// One of the initial requests or options given to a solve.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SolveRunInput {
+    Request(Request),
+    Options(OptionMap),
+}
+
+/// A single decision that was made during a solve.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SolveRunDecision {
+    /// An existing package build was added to the solution
+    Resolve(BuildIdent),
+    /// A new build of a package from its recipe was added to the solution
+    Build(BuildIdent),
+    /// The solver could not continue and stepped back
+    Blocked(String),
+}
+
+impl std::fmt::Display for SolveRunDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Resolve(ident) => write!(f, "RESOLVE {ident}"),
+            Self::Build(ident) => write!(f, "BUILD {ident}"),
+            Self::Blocked(cause) => write!(f, "BLOCKED {cause}"),
+        }
+    }
+}
+
+impl SolveRun {
+    /// Start a new record of a solve from the solver that will run it.
+    pub fn new(solver: &Solver, kind: SolveRunSolver) -> Self {
+        let inputs = solver
+            .initial_state_builders()
+            .iter()
+            .filter_map(|change| match change {
+                Change::RequestPackage(c) => Some(SolveRunInput::Request(c.request.clone().into())),
+                Change::RequestVar(c) => Some(SolveRunInput::Request(c.request.clone().into())),
+                Change::RequestAnyOf(c) => Some(SolveRunInput::Request(c.request.clone().into())),
+                Change::SetOptions(c) => Some(SolveRunInput::Options(c.options.clone())),
+                Change::SetPackage(_) | Change::SetPackageBuild(_) | Change::StepBack(_) => None,
+            })
+            .collect();
+        Self {
+            spk_version: env!("CARGO_PKG_VERSION").to_string(),
+            repositories: solver
+                .repositories()
+                .iter()
+                .map(|repo| repo.name().to_string())
+                .collect(),
+            solver: kind,
+            binary_only: solver.is_binary_only(),
+            grandfathered_builds: solver.grandfathered_builds(),
+            inputs,
+            decisions: Vec::new(),
+        }
+    }
+
+    /// Load a saved solve run from a json file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).map_err(|err| {
+            Error::String(format!(
+                "Failed to open solve run {}: {err}",
+                path.display()
+            ))
+        })?;
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|err| Error::String(format!("Invalid solve run {}: {err}", path.display())))
+    }
+
+    /// Save this solve run to a json file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path).map_err(|err| {
+            Error::String(format!(
+                "Failed to create solve run {}: {err}",
+                path.display()
+            ))
+        })?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), self).map_err(|err| {
+            Error::String(format!(
+                "Failed to save solve run {}: {err}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Add the changes of a decision made by the solver to this run.
+    pub fn record(&mut self, decision: &Decision) {
+        for change in decision.changes.iter() {
+            let decision = match change {
+                Change::SetPackage(c) => SolveRunDecision::Resolve(c.spec.ident().clone()),
+                Change::SetPackageBuild(c) => SolveRunDecision::Build(c.spec.ident().clone()),
+                Change::StepBack(c) => SolveRunDecision::Blocked(c.cause.clone()),
+                _ => continue,
+            };
+            self.decisions.push(decision);
+        }
+    }
+
+    /// Replace the requests and options of the given solver with
+    /// the inputs of this run, so that it can be replayed.
+    ///
+    /// The solver's repositories are kept, and should
+    /// be the same ones that this run was made against.
+    pub fn configure_solver(&self, solver: &mut Solver) {
+        let repositories: Vec<_> = solver
+            .repositories()
+            .iter()
+            .map(|repo| repo.name().to_string())
+            .collect();
+        if repositories != self.repositories {
+            tracing::warn!(
+                "Replaying a solve that used different repositories: [{}], now using: [{}]",
+                self.repositories.join(", "),
+                repositories.join(", ")
+            );
+        }
+        solver.clear_initial_state();
+        for input in self.inputs.iter() {
+            match input {
+                SolveRunInput::Request(request) => solver.add_request(request.clone()),
+                SolveRunInput::Options(options) => solver.update_options(options.clone()),
+            }
+        }
+        solver.set_binary_only(self.binary_only);
+        solver.set_grandfathered_builds(self.grandfathered_builds.iter().cloned());
+    }
+
+    /// Find the first decision where this run and another one differ,
+    /// returning its index and the decision from each run, if any.
+    pub fn first_difference<'a>(
+        &'a self,
+        other: &'a SolveRun,
+    ) -> Option<(
+        usize,
+        Option<&'a SolveRunDecision>,
+        Option<&'a SolveRunDecision>,
+    )> {
+        let length = self.decisions.len().max(other.decisions.len());
+        (0..length).find_map(|index| {
+            let ours = self.decisions.get(index);
+            let theirs = other.decisions.get(index);
+            (ours != theirs).then_some((index, ours, theirs))
+        })
+    }
+}
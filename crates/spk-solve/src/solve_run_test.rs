@@ -0,0 +1,86 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use rstest::rstest;
+use spk_schema::ident::build_ident;
+use spk_solve_macros::{make_repo, request};
+
+use super::{SolveRun, SolveRunDecision, SolveRunSolver};
+use crate::Solver;
+
+/// Run the solver to completion, recording all of its decisions
+async fn record_solve_run(solver: &Solver) -> SolveRun {
+    let mut solve_run = SolveRun::new(solver, SolveRunSolver::Unchanged);
+    let mut runtime = solver.run();
+    let iter = runtime.iter();
+    tokio::pin!(iter);
+    while let Some((_, decision)) = iter.try_next().await.unwrap() {
+        solve_run.record(&decision);
+    }
+    solve_run
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solve_run_replay_makes_same_decisions() {
+    let repo = Arc::new(make_repo!([
+        {"pkg": "pkg-a/1.0.0", "install": {"requirements": [{"pkg": "pkg-b"}]}},
+        {"pkg": "pkg-b/1.0.0"},
+        {"pkg": "pkg-b/2.0.0", "install": {"requirements": [{"pkg": "pkg-c/2"}]}},
+        {"pkg": "pkg-c/1.0.0"},
+    ]));
+    let mut solver = Solver::default();
+    solver.add_repository(repo.clone());
+    solver.add_request(request!("pkg-a"));
+
+    let saved = record_solve_run(&solver).await;
+    assert!(
+        saved
+            .decisions
+            .iter()
+            .any(|d| matches!(d, SolveRunDecision::Blocked(_))),
+        "expected the solve to step back at least once: {:?}",
+        saved.decisions
+    );
+    let json = serde_json::to_string(&saved).unwrap();
+    let saved: SolveRun = serde_json::from_str(&json).unwrap();
+
+    // the replay starts from the saved requests, not the solver's own
+    let mut replay = Solver::default();
+    replay.add_repository(repo);
+    replay.add_request(request!("pkg-c"));
+    saved.configure_solver(&mut replay);
+
+    let replayed = record_solve_run(&replay).await;
+    assert_eq!(replayed.inputs, saved.inputs);
+    assert_eq!(saved.first_difference(&replayed), None);
+}
+
+#[rstest]
+fn test_solve_run_first_difference() {
+    let resolve_a = SolveRunDecision::Resolve(build_ident!("pkg-a/1.0.0/3I42H3S6"));
+    let resolve_b = SolveRunDecision::Resolve(build_ident!("pkg-b/1.0.0/3I42H3S6"));
+    let saved = SolveRun {
+        decisions: vec![resolve_a.clone(), resolve_b.clone()],
+        ..Default::default()
+    };
+
+    let mut replayed = saved.clone();
+    assert_eq!(saved.first_difference(&replayed), None);
+
+    replayed.decisions[1] = SolveRunDecision::Blocked("out of options".into());
+    assert_eq!(
+        saved.first_difference(&replayed),
+        Some((1, Some(&resolve_b), Some(&replayed.decisions[1])))
+    );
+
+    replayed.decisions.truncate(1);
+    assert_eq!(
+        saved.first_difference(&replayed),
+        Some((1, Some(&resolve_b), None))
+    );
+}
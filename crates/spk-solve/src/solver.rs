@@ -7,7 +7,7 @@ use std::collections::{HashMap, HashSet};
 use std::mem::take;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use async_stream::stream;
 use futures::stream::{FuturesUnordered, StreamExt};
@@ -15,9 +15,9 @@ use futures::{Stream, TryStreamExt};
 use priority_queue::priority_queue::PriorityQueue;
 use spk_schema::foundation::ident_build::Build;
 use spk_schema::foundation::ident_component::Component;
-use spk_schema::foundation::name::{PkgName, PkgNameBuf};
+use spk_schema::foundation::name::{PkgName, PkgNameBuf, RepositoryNameBuf};
 use spk_schema::foundation::version::Compatibility;
-use spk_schema::ident::{PkgRequest, Request, RequestedBy, Satisfy, VarRequest};
+use spk_schema::ident::{AnyOfRequest, PkgRequest, Request, RequestedBy, Satisfy, VarRequest};
 use spk_schema::ident_build::EmbeddedSource;
 use spk_schema::version::IncompatibleReason;
 use spk_schema::{try_recipe, BuildIdent, Deprecate, Package, Recipe, Spec, SpecRecipe};
@@ -27,6 +27,7 @@ use spk_solve_graph::{
     Graph,
     Node,
     Note,
+    RequestAnyOf,
     RequestPackage,
     RequestVar,
     SetOptions,
@@ -37,15 +38,18 @@ use spk_solve_graph::{
 };
 use spk_solve_package_iterator::{
     BuildIterator,
+    BuildOrder,
     EmptyBuildIterator,
     PackageIterator,
     RepositoryPackageIterator,
     SortedBuildIterator,
 };
 use spk_solve_solution::{PackageSource, Solution};
-use spk_solve_validation::validators::BinaryOnlyValidator;
+use spk_solve_validation::validators::{BinaryOnlyValidator, DeprecationValidator};
 use spk_solve_validation::{
     default_validators,
+    with_default_prerelease_policy,
+    DefaultPreReleasePolicies,
     ImpossibleRequestsChecker,
     ValidatorT,
     Validators,
@@ -56,7 +60,7 @@ use spk_storage::RepositoryHandle;
 use super::error;
 use crate::error::OutOfOptions;
 use crate::option_map::OptionMap;
-use crate::{Error, Result};
+use crate::{Error, Result, SolverBackendKind};
 
 // Public to allow other tests to use its macros
 #[cfg(test)]
@@ -101,6 +105,20 @@ pub struct Solver {
     request_validator: Arc<ImpossibleRequestsChecker>,
     // For holding the settings that say which impossible checks are enabled
     impossible_checks: ImpossibleChecksSettings,
+    // The number of decisions after which a solve is stopped, if any
+    max_decisions: Option<usize>,
+    // The amount of time after which a solve is stopped, if any
+    timeout: Option<Duration>,
+    // The heuristic used to order the builds of each package version
+    build_order: BuildOrder,
+    // The method used to find a solution by [`Solver::solve`]
+    backend: SolverBackendKind,
+    // The priority of each repository by name, used to choose which
+    // repository a build is taken from when more than one has it
+    repository_priorities: HashMap<String, i64>,
+    // The repositories that packages in each namespace are routed
+    // to, namespaces that are not listed use all repositories
+    namespace_routes: spk_config::Namespaces,
     // For counting the number of steps (forward) taken in a solve
     number_of_steps: usize,
     // For counting number of builds skipped for some reason
@@ -133,6 +151,12 @@ impl Default for Solver {
             validators: Cow::from(default_validators()),
             request_validator: Arc::new(ImpossibleRequestsChecker::default()),
             impossible_checks: ImpossibleChecksSettings::default(),
+            max_decisions: None,
+            timeout: None,
+            build_order: BuildOrder::default(),
+            backend: SolverBackendKind::default(),
+            repository_priorities: HashMap::default(),
+            namespace_routes: spk_config::Namespaces::default(),
             number_of_steps: 0,
             number_builds_skipped: 0,
             number_incompat_versions: 0,
@@ -225,6 +249,7 @@ impl Solver {
                 Change::RequestPackage(RequestPackage::new(request))
             }
             Request::Var(request) => Change::RequestVar(RequestVar::new(request)),
+            Request::AnyOf(request) => Change::RequestAnyOf(RequestAnyOf::new(request)),
         };
         self.initial_state_builders.push(request);
     }
@@ -242,6 +267,17 @@ impl Solver {
         &self.repos
     }
 
+    /// The changes that make up the initial state of a solve,
+    /// from the requests and options given to this solver.
+    pub(crate) fn initial_state_builders(&self) -> &[Change] {
+        &self.initial_state_builders
+    }
+
+    /// Remove all the requests and options given to this solver.
+    pub(crate) fn clear_initial_state(&mut self) {
+        self.initial_state_builders.clear();
+    }
+
     pub fn get_initial_state(&self) -> Arc<State> {
         let mut state = None;
         let base = State::default_state();
@@ -335,11 +371,31 @@ impl Solver {
         package_name: PkgNameBuf,
     ) -> Arc<tokio::sync::Mutex<Box<dyn PackageIterator + Send>>> {
         debug_assert!(!self.repos.is_empty());
+        let repos = self.repos_for_package(&package_name);
         Arc::new(tokio::sync::Mutex::new(Box::new(
-            RepositoryPackageIterator::new(package_name, self.repos.clone()),
+            RepositoryPackageIterator::new(package_name, repos),
         )))
     }
 
+    /// The repositories that the given package should be resolved from.
+    ///
+    /// Packages in a namespace that is routed to specific repositories
+    /// (see [`Solver::set_namespace_routes`]) are only resolved from
+    /// those repositories.
+    pub(crate) fn repos_for_package(&self, package_name: &PkgName) -> Vec<Arc<RepositoryHandle>> {
+        let Some(namespace) = package_name.namespace() else {
+            return self.repos.clone();
+        };
+        let Some(routed) = self.namespace_routes.repositories_for(namespace) else {
+            return self.repos.clone();
+        };
+        self.repos
+            .iter()
+            .filter(|repo| routed.contains(&repo.name().as_str()))
+            .cloned()
+            .collect()
+    }
+
     /// Resolve the build environment, and generate a build for
     /// the given recipe and state.
     ///
@@ -374,6 +430,8 @@ impl Solver {
 
         let mut solver = Solver {
             repos: self.repos.clone(),
+            repository_priorities: self.repository_priorities.clone(),
+            namespace_routes: self.namespace_routes.clone(),
             ..Default::default()
         };
         solver.update_options(opts.clone());
@@ -463,6 +521,62 @@ impl Solver {
             .map_err(Error::ValidationError)
     }
 
+    /// Choose the next untried alternative for a request that can
+    /// be satisfied by any one of several packages.
+    ///
+    /// Alternatives are tried in the order that they were listed,
+    /// skipping any that have already been attempted from this node.
+    async fn choose_alternative(
+        &mut self,
+        node: &Arc<Node>,
+        request: AnyOfRequest,
+        mut notes: Vec<Note>,
+    ) -> Result<Decision> {
+        for alternative in request.alternatives.iter() {
+            if let Ok((existing, _, _)) = node.state.get_current_resolve(&alternative.pkg.name) {
+                // the package is already resolved, but does not
+                // satisfy this alternative or it would have been used
+                notes.push(Note::Other(format!(
+                    "{} is already resolved to {}",
+                    alternative.pkg,
+                    existing.ident()
+                )));
+                continue;
+            }
+            if !self.package_exists(&alternative.pkg.name).await? {
+                notes.push(Note::Other(format!(
+                    "{} does not exist in any repository",
+                    alternative.pkg.name
+                )));
+                continue;
+            }
+            let mut decision = Decision::builder(&node.state).choose_alternative(alternative);
+            if node.has_output(&decision.apply(&node.state)) {
+                // this alternative has already been tried, and
+                // did not lead to a solution
+                continue;
+            }
+            self.number_of_steps += 1;
+            decision.add_notes(notes);
+            return Ok(decision);
+        }
+        Err(Error::OutOfOptions(OutOfOptions {
+            request: request.preferred().clone(),
+            notes,
+        }))
+    }
+
+    /// True if any of the repositories that the named package would be
+    /// resolved from contain at least one version of it.
+    async fn package_exists(&self, package_name: &PkgName) -> Result<bool> {
+        for repo in self.repos_for_package(package_name) {
+            if !repo.list_package_versions(package_name).await?.is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     async fn step_state(
         &mut self,
         graph: &Arc<tokio::sync::RwLock<Graph>>,
@@ -471,6 +585,14 @@ impl Solver {
         let mut notes = Vec::<Note>::new();
         let request = if let Some(request) = node.state.get_next_request()? {
             request
+        } else if let Some(request) = node.state.get_next_any_of_request() {
+            // All the other requests are resolved, so pick one of the
+            // alternatives that have been requested
+            let request = request.clone();
+            return self
+                .choose_alternative(node, request, notes)
+                .await
+                .map(Some);
         } else {
             // May have a valid solution, but verify that all embedded packages
             // that are part of the solve also have their source packages
@@ -511,6 +633,18 @@ impl Solver {
         // This is a step forward in the solve
         self.number_of_steps += 1;
 
+        // versions are merged from all of the package's repositories,
+        // so the most inclusive of their default policies is used here
+        // and each build is checked against its own repository's policy
+        let defaults = DefaultPreReleasePolicies::current();
+        let default_policy = self
+            .repos_for_package(&request.pkg.name)
+            .iter()
+            .map(|repo| defaults.policy_for(Some(repo.name().as_str())))
+            .max()
+            .unwrap_or_default();
+        let version_request = with_default_prerelease_policy(&request, default_policy);
+
         let iterator = self.get_iterator(node, &request.pkg.name).await;
         let mut iterator_lock = iterator.lock().await;
         loop {
@@ -531,7 +665,7 @@ impl Solver {
                 Err(e) => return Err(e.into()),
             };
 
-            let mut compat = request.is_version_applicable(pkg.version());
+            let mut compat = version_request.is_version_applicable(pkg.version());
             if !&compat {
                 // Count this version and its builds as incompatible
                 self.number_incompat_versions += 1;
@@ -552,7 +686,9 @@ impl Solver {
             let builds = if !builds.lock().await.is_sorted_build_iterator() {
                 // TODO: this could be a HashSet if build key generation
                 // only looks at the idents in the hashmap.
-                let builds_with_impossible_requests = if self.impossible_checks.use_in_build_keys {
+                let builds_with_impossible_requests = if self.impossible_checks.use_in_build_keys
+                    && self.build_order == BuildOrder::OptionValues
+                {
                     let impossible_check_start = Instant::now();
                     let start_number = self.request_validator.num_build_specs_read();
                     let unresolved = node.state.get_unresolved_requests();
@@ -575,10 +711,11 @@ impl Solver {
                 };
 
                 let builds = Arc::new(tokio::sync::Mutex::new(
-                    SortedBuildIterator::new(
+                    SortedBuildIterator::new_with_build_order(
                         node.state.get_option_map().clone(),
                         builds.clone(),
                         builds_with_impossible_requests,
+                        self.build_order,
                     )
                     .await?,
                 ));
@@ -594,8 +731,9 @@ impl Solver {
 
                 self.number_total_builds += 1;
 
-                // Try all the hash map values to check all repos.
-                for (spec, source) in hm.values() {
+                // Try the build from each repo that has it, in order
+                // of the repositories' priorities.
+                for (spec, source) in self.order_by_repository_priority(hm).iter() {
                     let spec = Arc::clone(spec);
                     let build_from_source =
                         spec.ident().is_source() && request.pkg.build != Some(Build::Source);
@@ -675,21 +813,25 @@ impl Solver {
                             }
                         }
                     } else {
-                        if let PackageSource::Embedded { .. } = source {
+                        if let PackageSource::Embedded { parent } = source {
+                            // name the package that embeds this one, since
+                            // it cannot be changed independently of its parent
                             notes.push(Note::SkipPackageNote(SkipPackageNote::new_from_message(
                                 spec.ident().to_any(),
-                                &compat,
+                                format!("{compat} [{}]", node.state.embedded_requester(parent)),
                             )));
                             self.number_builds_skipped += 1;
                             continue;
                         }
                         let recipe = match source.read_recipe(spec.ident().base()).await {
                             Ok(r) if r.is_deprecated() => {
+                                let message = "cannot build from source, version is deprecated";
+                                let message = match r.replacement_suggestion() {
+                                    Some(suggestion) => format!("{message}, {suggestion}"),
+                                    None => message.to_owned(),
+                                };
                                 notes.push(Note::SkipPackageNote(
-                                    SkipPackageNote::new_from_message(
-                                        pkg.clone(),
-                                        "cannot build from source, version is deprecated",
-                                    ),
+                                    SkipPackageNote::new_from_message(pkg.clone(), message),
                                 ));
                                 continue;
                             }
@@ -767,6 +909,7 @@ impl Solver {
                 }
             }
         }
+        drop(version_request);
 
         Err(error::Error::OutOfOptions(error::OutOfOptions {
             request,
@@ -784,7 +927,7 @@ impl Solver {
         Ok(Compatibility::Compatible)
     }
 
-    fn validate_package<P>(
+    pub(crate) fn validate_package<P>(
         &self,
         state: &State,
         spec: &P,
@@ -899,6 +1042,12 @@ impl Solver {
         self.initial_state_builders.truncate(0);
         self.validators = Cow::from(default_validators());
         (*self.request_validator).reset();
+        self.request_validator
+            .set_deprecation_validator(DeprecationValidator::DEFAULT);
+        self.max_decisions = None;
+        self.timeout = None;
+        self.build_order = BuildOrder::default();
+        self.backend = SolverBackendKind::default();
 
         self.number_of_steps = 0;
         self.number_builds_skipped = 0;
@@ -950,6 +1099,53 @@ impl Solver {
         }
     }
 
+    /// True if this solver only resolves pre-built binary packages.
+    pub fn is_binary_only(&self) -> bool {
+        self.validators
+            .iter()
+            .any(|v| matches!(v, Validators::BinaryOnly(_)))
+    }
+
+    /// Allow these deprecated builds to be resolved, even when they are
+    /// not requested exactly.
+    ///
+    /// This is used to grandfather in the builds that are already installed
+    /// in an environment, so that it can still be solved again after one of
+    /// them is deprecated. Any other deprecated builds are still rejected,
+    /// so packages that are newly added to the environment use builds that
+    /// are not deprecated. This replaces any builds that were set before.
+    pub fn set_grandfathered_builds<I>(&mut self, builds: I)
+    where
+        I: IntoIterator<Item = BuildIdent>,
+    {
+        let validator = DeprecationValidator::with_grandfathered_builds(builds);
+        self.request_validator
+            .set_deprecation_validator(validator.clone());
+        for v in self.validators.to_mut().iter_mut() {
+            if let Validators::Deprecation(existing) = v {
+                *existing = validator.clone();
+            }
+        }
+    }
+
+    /// The deprecated builds that this solver allows without
+    /// being requested exactly, see [`Self::set_grandfathered_builds`].
+    pub fn grandfathered_builds(&self) -> Vec<BuildIdent> {
+        let mut builds: Vec<_> = self
+            .validators
+            .iter()
+            .filter_map(|v| match v {
+                Validators::Deprecation(d) => Some(d.grandfathered_builds()),
+                _ => None,
+            })
+            .flatten()
+            .cloned()
+            .collect();
+        builds.sort();
+        builds.dedup();
+        builds
+    }
+
     /// Enable or disable running impossible checks on the initial requests
     /// before the solve starts
     pub fn set_initial_request_impossible_checks(&mut self, enabled: bool) {
@@ -968,6 +1164,191 @@ impl Solver {
         self.impossible_checks.use_in_build_keys = enabled;
     }
 
+    /// Stop the solve with an error once this many decisions have been made.
+    ///
+    /// The error includes the problems that were most frequently hit
+    /// by the solve so far. When None, which is the default, the solve
+    /// runs until it finds a solution or runs out of options.
+    pub fn set_max_decisions(&mut self, max_decisions: Option<usize>) {
+        self.max_decisions = max_decisions;
+    }
+
+    /// Stop the solve with an error once it has run for this long.
+    ///
+    /// The error includes the problems that were most frequently hit
+    /// by the solve so far. When None, which is the default, the solve
+    /// runs until it finds a solution or runs out of options.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// The time limit of the solve, if it has one
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Set the heuristic used to order the builds of each package
+    /// version before they are tried
+    pub fn set_heuristic(&mut self, build_order: BuildOrder) {
+        self.build_order = build_order;
+    }
+
+    /// The heuristic used to order the builds of each package version
+    pub(crate) fn build_order(&self) -> BuildOrder {
+        self.build_order
+    }
+
+    /// Set the priority of a repository.
+    ///
+    /// When the same build is found in more than one repository, the
+    /// one from the repository with the highest priority is tried first.
+    /// Repositories have a priority of zero unless one is set.
+    pub fn set_repository_priority<S: Into<String>>(&mut self, name: S, priority: i64) {
+        self.repository_priorities.insert(name.into(), priority);
+    }
+
+    /// Set the priorities of many repositories at once, such as those
+    /// from the `repositories.priorities` section of the spk config.
+    pub fn set_repository_priorities<I, S>(&mut self, priorities: I)
+    where
+        I: IntoIterator<Item = (S, i64)>,
+        S: Into<String>,
+    {
+        for (name, priority) in priorities {
+            self.set_repository_priority(name, priority);
+        }
+    }
+
+    /// Replace the priorities of all repositories with the given order,
+    /// from highest to lowest priority.
+    ///
+    /// Repositories that are not in the list have a lower
+    /// priority than all of those that are.
+    pub fn set_repository_order<I, S>(&mut self, names: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let names = names.into_iter().map(Into::into).collect::<Vec<_>>();
+        let count = names.len() as i64;
+        self.repository_priorities = names
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| (name, count - index as i64))
+            .collect();
+    }
+
+    /// Set the repositories that packages in each namespace are routed to.
+    ///
+    /// Packages in a routed namespace are only resolved from the named
+    /// repositories. No namespaces are routed by default.
+    pub fn set_namespace_routes(&mut self, routes: spk_config::Namespaces) {
+        self.namespace_routes = routes;
+    }
+
+    /// The priority of the named repository
+    pub fn repository_priority(&self, name: &str) -> i64 {
+        self.repository_priorities
+            .get(name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Order the copies of a build from different repositories so that
+    /// the one from the repository with the highest priority is first.
+    ///
+    /// Repositories with the same priority are ordered by when they
+    /// were added to this solver, so the result does not depend on
+    /// the order that the copies were found in.
+    pub(crate) fn order_by_repository_priority(
+        &self,
+        builds: HashMap<RepositoryNameBuf, (Arc<Spec>, PackageSource)>,
+    ) -> Vec<(Arc<Spec>, PackageSource)> {
+        let mut builds = builds.into_iter().collect::<Vec<_>>();
+        builds.sort_by_cached_key(|(name, _)| {
+            let added = self
+                .repos
+                .iter()
+                .position(|repo| repo.name() == &**name)
+                .unwrap_or(usize::MAX);
+            (
+                std::cmp::Reverse(self.repository_priority(name.as_str())),
+                added,
+                name.clone(),
+            )
+        });
+        builds.into_iter().map(|(_, build)| build).collect()
+    }
+
+    /// Set the method used to find a solution.
+    ///
+    /// Only the default graph backend records its decisions, so
+    /// other backends cannot be stepped through or explained.
+    pub fn set_backend(&mut self, backend: SolverBackendKind) {
+        self.backend = backend;
+    }
+
+    /// The method used to find a solution
+    pub fn backend(&self) -> SolverBackendKind {
+        self.backend
+    }
+
+    /// Summarize the problems hit so far in this solve, to explain
+    /// why a solve that was stopped early had not found a solution.
+    pub fn partial_explanation(&self) -> String {
+        const MAX_ENTRIES: usize = 5;
+        let mut explanation = String::new();
+
+        let mut errors: Vec<_> = self.error_frequency.iter().collect();
+        errors.sort_by(|a, b| b.1.counter.cmp(&a.1.counter).then(a.0.cmp(b.0)));
+        if !errors.is_empty() {
+            explanation.push_str("The most frequent problems hit so far were:");
+            for (key, freq) in errors.into_iter().take(MAX_ENTRIES) {
+                let message = freq.get_message(key.clone());
+                let times = if freq.counter == 1 { "time" } else { "times" };
+                explanation.push_str(&format!("\n  {message} ({} {times})", freq.counter));
+            }
+        }
+
+        let mut packages: Vec<_> = self.problem_packages.iter().collect();
+        packages.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        if !packages.is_empty() {
+            if !explanation.is_empty() {
+                explanation.push('\n');
+            }
+            explanation.push_str("The packages most often involved in blocked requests were:");
+            for (pkg, count) in packages.into_iter().take(MAX_ENTRIES) {
+                explanation.push_str(&format!("\n  {pkg} ({count})"));
+            }
+        }
+
+        if explanation.is_empty() {
+            explanation.push_str("No problems were hit before the solve was stopped");
+        }
+        explanation
+    }
+
+    /// Return an error if this solve has reached any of its limits
+    pub(crate) fn check_limits(&self, decisions: usize, start: Instant) -> Result<()> {
+        let reason = match (self.max_decisions, self.timeout) {
+            (Some(max), _) if decisions >= max => {
+                let plural = if max == 1 { "" } else { "s" };
+                format!(
+                    "Solve reached its limit of {max} decision{plural} without finding a solution"
+                )
+            }
+            (_, Some(timeout)) if start.elapsed() >= timeout => format!(
+                "Solve reached its time limit of {} secs without finding a solution",
+                timeout.as_secs_f64()
+            ),
+            _ => return Ok(()),
+        };
+        Err(Error::SolverInterrupted(format!(
+            "{reason}.\n{}",
+            self.partial_explanation()
+        )))
+    }
+
     /// Return true is any of the impossible request checks are
     /// enabled for this solver, otherwise false
     pub fn any_impossible_checks_enabled(&self) -> bool {
@@ -977,6 +1358,9 @@ impl Solver {
     }
 
     pub async fn solve(&mut self) -> Result<Solution> {
+        if self.backend != SolverBackendKind::Graph {
+            return self.backend.backend().solve(self).await;
+        }
         let mut runtime = self.run();
         {
             let iter = runtime.iter();
@@ -1174,6 +1558,8 @@ impl SolverRuntime {
     pub fn iter(&mut self) -> impl Stream<Item = Result<(Arc<Node>, Arc<Decision>)>> + Send + '_ {
         stream! {
             let mut first_iter = true;
+            let start = Instant::now();
+            let mut decisions = 0;
             'outer: loop {
                 if self.decision.is_none()
                     || (self.current_node.is_some()
@@ -1189,6 +1575,12 @@ impl SolverRuntime {
                     break 'outer;
                 }
 
+                if let Err(err) = self.solver.check_limits(decisions, start) {
+                    yield Err(err);
+                    break 'outer;
+                }
+                decisions += 1;
+
                 let to_yield = (
                     // A clone of Some(current_node) or the root node
                     {
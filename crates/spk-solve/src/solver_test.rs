@@ -427,6 +427,58 @@ async fn test_solver_dependency_incompatible_stepback(mut solver: Solver) {
     assert_resolved!(packages, "maya", "2019.0.0");
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_solver_conflict_with_resolved_package(mut solver: Solver) {
+    // a package that declares a conflict with an already resolved
+    // package should be skipped in favor of an older version that does not
+    let repo = make_repo!(
+        [
+            {"pkg": "maya/2019"},
+            {
+                "pkg": "my-plugin/1.1.0",
+                "install": {"conflicts": ["maya/<2020"]},
+            },
+            {"pkg": "my-plugin/1.0.0"},
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("maya/2019"));
+    solver.add_request(request!("my-plugin/1"));
+
+    let packages = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    assert_resolved!(packages, "my-plugin", "1.0.0");
+    assert_resolved!(packages, "maya", "2019.0.0");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_conflict_declared_by_resolved_package(mut solver: Solver) {
+    // a conflict declared by an already resolved package must
+    // also be honored when choosing later packages
+    let repo = make_repo!(
+        [
+            {"pkg": "maya/2019"},
+            {"pkg": "maya/2020"},
+            {
+                "pkg": "my-plugin/1.0.0",
+                "install": {"conflicts": ["maya/2020"]},
+            },
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-plugin/1"));
+    solver.add_request(request!("maya"));
+
+    let packages = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    assert_resolved!(packages, "my-plugin", "1.0.0");
+    assert_resolved!(packages, "maya", "2019.0.0");
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_solver_dependency_already_satisfied(mut solver: Solver) {
@@ -991,6 +1043,39 @@ async fn test_solver_deprecated_build(mut solver: Solver) {
     );
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_solver_grandfathered_deprecated_build(mut solver: Solver) {
+    let installed = make_build!({"pkg": "my-pkg/1.0.0", "deprecated": true});
+    let installed_build = installed.ident().clone();
+    let not_installed = make_build!({"pkg": "my-dep/1.0.0", "deprecated": true});
+    let repo = make_repo!([
+        {"pkg": "my-pkg/0.9.0"},
+        {"pkg": "my-dep/0.9.0"},
+        installed,
+        not_installed,
+    ]);
+
+    solver.add_repository(Arc::new(repo));
+    solver.set_grandfathered_builds([installed_build]);
+    solver.add_request(request!("my-pkg"));
+    solver.add_request(request!("my-dep"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+    assert_resolved!(
+        solution,
+        "my-pkg",
+        "1.0.0",
+        "should resolve the grandfathered deprecated build"
+    );
+    assert_resolved!(
+        solution,
+        "my-dep",
+        "0.9.0",
+        "should not resolve other deprecated builds"
+    );
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_solver_deprecated_version(mut solver: Solver) {
@@ -1165,6 +1250,39 @@ async fn test_solver_embedded_package_adds_request(mut solver: Solver) {
     );
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_solver_embedded_package_requested_by_chain(mut solver: Solver) {
+    // test when there is an embedded package
+    // - the request for the embedded package names its parent
+    // - and also whatever requested the parent package
+
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "maya/2019.2",
+                "build": {"script": "echo BUILD"},
+                "install": {"embedded": [{"pkg": "qt/5.12.6"}]},
+            },
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("maya"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    let maya = solution.get("maya").unwrap().spec.ident().clone();
+    let requesters = solution.get("qt").unwrap().request.get_requesters();
+    assert!(
+        requesters.contains(&RequestedBy::EmbeddedChain {
+            parent: maya,
+            parent_requested_by: vec![RequestedBy::SpkInternalTest],
+        }),
+        "expected the embedding chain in {requesters:?}"
+    );
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_solver_embedded_package_solvable(mut solver: Solver) {
@@ -1294,6 +1412,148 @@ async fn test_solver_embedded_package_replaces_real_package(mut solver: Solver)
     assert_not_resolved!(solution, "unwanted-dep");
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_solver_any_of_request_prefers_first(mut solver: Solver) {
+    // test when a package requires any one of several others
+    // - the first alternative is used when it can be resolved
+
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "my-player/1.0.0",
+                "install": {"requirements": [{"pkg": {"any": ["ffmpeg/4", "libav/12"]}}]},
+            },
+            {"pkg": "ffmpeg/4.4.0"},
+            {"pkg": "libav/12.3.0"},
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-player"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    assert_resolved!(solution, "ffmpeg", "4.4.0");
+    assert_not_resolved!(solution, "libav");
+}
+
+#[rstest]
+#[case::first_missing(false)]
+#[case::first_incompatible(true)]
+#[tokio::test]
+async fn test_solver_any_of_request_fallback(mut solver: Solver, #[case] first_exists: bool) {
+    // test when a package requires any one of several others
+    // - later alternatives are tried when earlier ones cannot be resolved
+
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "my-player/1.0.0",
+                "install": {"requirements": [{"pkg": {"any": ["ffmpeg/4", "libav/12"]}}]},
+            },
+            {"pkg": "libav/12.3.0"},
+        ]
+    );
+    if first_exists {
+        let (spec, components) = make_build_and_components!({"pkg": "ffmpeg/5.0.0"});
+        repo.publish_package(&spec, &components).await.unwrap();
+    }
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-player"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    assert_resolved!(solution, "libav", "12.3.0");
+    assert_not_resolved!(solution, "ffmpeg");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_any_of_request_already_satisfied(mut solver: Solver) {
+    // test when a package requires any one of several others
+    // - an alternative that is already resolved satisfies the request
+
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "my-player/1.0.0",
+                "install": {"requirements": [{"pkg": {"any": ["ffmpeg/4", "libav/12"]}}]},
+            },
+            {"pkg": "ffmpeg/4.4.0"},
+            {"pkg": "libav/12.3.0"},
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("libav"));
+    solver.add_request(request!("my-player"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    assert_resolved!(solution, "libav", "12.3.0");
+    assert_not_resolved!(solution, "ffmpeg");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_provides_satisfies_request(mut solver: Solver) {
+    // test when a package provides another
+    // - a request for the provided package is satisfied by the provider
+    // - the providing package is brought into the solution
+
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "my-viewer/1.0.0",
+                "install": {"requirements": [{"pkg": "jpeg2000/2"}]},
+            },
+            {
+                "pkg": "openjpeg/2.4.0",
+                "install": {"provides": ["jpeg2000/2.3"]},
+            },
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-viewer"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+
+    assert_resolved!(solution, "openjpeg", "2.4.0");
+    assert_resolved!(solution, "jpeg2000", "2.3");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_provides_unsolvable(mut solver: Solver) {
+    // test when a package provides another
+    // - the provided version conflicts with an existing request
+
+    let repo = make_repo!(
+        [
+            {
+                "pkg": "my-viewer/1.0.0",
+                "install": {"requirements": [{"pkg": "openjpeg"}, {"pkg": "jpeg2000/3"}]},
+            },
+            {
+                "pkg": "openjpeg/2.4.0",
+                "install": {"provides": ["jpeg2000/2.3"]},
+            },
+            {
+                "pkg": "jpeg2000/3.0.0",
+            },
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-viewer"));
+
+    let res = run_and_print_resolve_for_tests(&solver).await;
+    assert!(res.is_err());
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_solver_initial_request_impossible_masks_embedded_package_solution(
@@ -2485,3 +2745,116 @@ async fn test_version_number_masking(
     );
     assert_ne!(resolved.spec.ident().build(), &Build::Source);
 }
+
+/// Test that when the same build is in more than one repo, it is
+/// taken from the repo with the highest priority, or otherwise from
+/// the repo that was added to the solver first.
+#[rstest]
+#[case::added_order(None, None, 0)]
+#[case::priority(Some(10), None, 1)]
+#[case::repo_order(Some(10), Some(0), 0)]
+#[tokio::test]
+async fn test_solver_repository_priority(
+    mut solver: Solver,
+    #[case] second_priority: Option<i64>,
+    #[case] first_in_order: Option<usize>,
+    #[case] expected_repo: usize,
+) {
+    let repo1 = make_repo(RepoKind::Mem).await;
+    let repo2 = make_repo(RepoKind::Mem).await;
+    let (spec, components) = make_package!(repo1, {"pkg": "my-pkg/1.0.0"}, option_map! {});
+    repo1.publish_package(&spec, &components).await.unwrap();
+    let (spec, components) = make_package!(repo2, {"pkg": "my-pkg/1.0.0"}, option_map! {});
+    repo2.publish_package(&spec, &components).await.unwrap();
+    let repos = [repo1, repo2];
+    for repo in repos.iter() {
+        solver.add_repository(Arc::clone(&repo.repo));
+    }
+    if let Some(priority) = second_priority {
+        solver.set_repository_priority(repos[1].name().as_str(), priority);
+    }
+    if let Some(first) = first_in_order {
+        solver.set_repository_order([repos[first].name().to_string()]);
+    }
+    solver.add_request(request!("my-pkg"));
+
+    let solution = run_and_print_resolve_for_tests(&solver).await.unwrap();
+    let resolved = solution.get("my-pkg").unwrap();
+    assert_eq!(
+        resolved.repo_name().as_deref(),
+        Some(repos[expected_repo].name()),
+        "the build should come from the expected repo"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_max_decisions(mut solver: Solver) {
+    let repo = make_repo!([{"pkg": "my-pkg/1.0.0"}]);
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-pkg"));
+    solver.set_max_decisions(Some(1));
+
+    let res = solver.solve().await;
+    assert!(
+        matches!(res, Err(Error::SolverInterrupted(ref message)) if message.contains("limit of 1 decision ")),
+        "expected the solve to stop after one decision, got {res:?}"
+    );
+}
+
+#[rstest]
+fn test_solver_partial_explanation(mut solver: Solver) {
+    assert!(solver
+        .partial_explanation()
+        .starts_with("No problems were hit"));
+
+    for _ in 0..2 {
+        solver.increment_error_count(ErrorDetails::Message("some problem".into()));
+    }
+    solver.increment_problem_package_count("my-plugin".into());
+
+    let explanation = solver.partial_explanation();
+    assert!(
+        explanation.contains("some problem (2 times)"),
+        "should list the most frequent problems: {explanation}"
+    );
+    assert!(
+        explanation.contains("my-plugin (1)"),
+        "should list the problem packages: {explanation}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_timeout(mut solver: Solver) {
+    let repo = make_repo!([{"pkg": "my-pkg/1.0.0"}]);
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-pkg"));
+    solver.set_timeout(Some(std::time::Duration::ZERO));
+
+    let res = solver.solve().await;
+    assert!(
+        matches!(res, Err(Error::SolverInterrupted(ref message)) if message.contains("time limit")),
+        "expected the solve to time out, got {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_solver_repository_build_order(mut solver: Solver) {
+    let repo = make_repo!(
+        [
+            {"pkg": "my-pkg/1.0.0", "build": {"options": [{"var": "debug/on"}]}},
+            {"pkg": "my-pkg/1.0.0", "build": {"options": [{"var": "debug/off"}]}},
+        ]
+    );
+
+    solver.add_repository(Arc::new(repo));
+    solver.add_request(request!("my-pkg"));
+    solver.set_heuristic(crate::BuildOrder::RepositoryOrder);
+
+    let packages = run_and_print_resolve_for_tests(&solver).await.unwrap();
+    assert_resolved!(packages, "my-pkg", "1.0.0");
+}
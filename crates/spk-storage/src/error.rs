@@ -3,9 +3,12 @@
 // https://github.com/spkenv/spk
 
 use miette::Diagnostic;
+use spk_schema::foundation::name::PkgNameBuf;
 use spk_schema::{AnyIdent, VersionIdent};
 use thiserror::Error;
 
+use crate::storage::AccessAction;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Diagnostic, Debug, Error)]
@@ -16,6 +19,24 @@ pub type Result<T> = std::result::Result<T, Error>;
     )
 )]
 pub enum Error {
+    #[error("Archive {0} failed verification: {1}")]
+    #[diagnostic(
+        code(spk::storage::archive_verification_failed),
+        help("The archive may be incomplete or corrupted, try exporting it again")
+    )]
+    ArchiveVerificationFailed(std::path::PathBuf, String),
+    #[error(
+        "Archive {path} uses format version {version}, but only up to {supported} is supported"
+    )]
+    #[diagnostic(
+        code(spk::storage::unsupported_archive_version),
+        help("The archive was created by a newer version of spk")
+    )]
+    UnsupportedArchiveVersion {
+        path: std::path::PathBuf,
+        version: u32,
+        supported: u32,
+    },
     #[error("Failed to create directory {0}")]
     DirectoryCreateError(std::path::PathBuf, #[source] std::io::Error),
     #[error("Failed to open file {0}")]
@@ -23,6 +44,7 @@ pub enum Error {
     #[error("Failed to read file {0}")]
     FileReadError(std::path::PathBuf, #[source] std::io::Error),
     #[error("Invalid package spec for {0}: {1}")]
+    #[diagnostic(code(spk::storage::invalid_package_spec))]
     InvalidPackageSpec(
         AnyIdent,
         // ideally this would contain the original format_serde_error instance
@@ -31,10 +53,45 @@ pub enum Error {
         String,
     ),
     #[error("Invalid repository metadata: {0}")]
+    #[diagnostic(code(spk::storage::invalid_repository_metadata))]
     InvalidRepositoryMetadata(#[source] serde_yaml::Error),
+    #[error("Invalid audit log entry: {0}")]
+    #[diagnostic(code(spk::storage::invalid_audit_entry))]
+    InvalidAuditEntry(#[source] serde_yaml::Error),
+    #[error("Invalid search index: {0}")]
+    #[diagnostic(
+        code(spk::storage::invalid_search_index),
+        help("The index can be rebuilt with 'spk repo index'")
+    )]
+    InvalidSearchIndex(#[source] serde_yaml::Error),
+    #[error("Invalid dependency index: {0}")]
+    #[diagnostic(
+        code(spk::storage::invalid_dependency_index),
+        help("The index can be rebuilt with 'spk repo index'")
+    )]
+    InvalidDependencyIndex(#[source] serde_yaml::Error),
+    #[error("Invalid tombstone: {0}")]
+    #[diagnostic(code(spk::storage::invalid_tombstone))]
+    InvalidTombstone(#[source] serde_yaml::Error),
     #[error("Package not found: {0}")]
+    #[diagnostic(
+        code(spk::storage::package_not_found),
+        help("Check the spelling of the name, and that the right repositories are enabled")
+    )]
     PackageNotFound(AnyIdent),
+    #[error("{user} is not allowed to {action} {pkg} in the {repo} repository")]
+    #[diagnostic(
+        code(spk::storage::permission_denied),
+        help("Changes to this package are restricted, see 'spk repo access {repo}' for who may make them")
+    )]
+    PermissionDenied {
+        user: String,
+        action: AccessAction,
+        pkg: PkgNameBuf,
+        repo: String,
+    },
     #[error("Version exists: {0}")]
+    #[diagnostic(code(spk::storage::version_exists))]
     VersionExists(VersionIdent),
     #[error(transparent)]
     #[diagnostic(forward(0))]
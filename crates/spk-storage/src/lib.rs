@@ -4,21 +4,60 @@
 
 mod error;
 pub mod fixtures;
+mod promote;
+mod publish;
 mod storage;
 
 pub use error::{Error, Result};
+pub use promote::Promoter;
+pub use publish::{PublishLabel, Publisher};
+#[cfg(feature = "server")]
+pub use storage::AccessPolicy;
 pub use storage::{
+    build_manifest,
+    describe_build,
+    describe_version,
     export_package,
+    export_package_with_reporter,
+    export_packages,
+    export_packages_with_reporter,
     find_path_providers,
+    import_archive,
     local_repository,
     pretty_print_filepath,
     remote_repository,
+    verify_archive,
+    AccessAction,
+    AccessControl,
+    AccessRule,
+    ArchiveManifest,
+    AuditAction,
+    AuditEntry,
+    AuditQuery,
+    BuildDetails,
+    BuildSummary,
     CachePolicy,
+    ChainedRepository,
+    ComponentDetails,
+    Dependency,
+    DependencyEntry,
+    DependencyIndex,
+    DependencyKind,
     MemRepository,
     NameAndRepositoryWithTagStrategy,
+    PackagePattern,
+    PathIndex,
+    PathOwner,
     Repository,
     RepositoryHandle,
     RuntimeRepository,
+    SearchEntry,
+    SearchIndex,
+    SearchQuery,
     SpfsRepository,
     Storage,
+    Tombstone,
+    VersionDetails,
+    ARCHIVE_FORMAT_VERSION,
+    ARCHIVE_MANIFEST_FILE,
 };
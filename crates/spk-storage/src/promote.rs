@@ -0,0 +1,118 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use spk_schema::foundation::format::FormatIdent;
+use spk_schema::{AnyIdent, BuildIdent};
+
+use crate::{with_cache_policy, CachePolicy, Error, RepositoryHandle, Result};
+
+#[cfg(test)]
+#[path = "./promote_test.rs"]
+mod promote_test;
+
+/// Manages the promotion of packages from one repo to another.
+///
+/// Promotion is meant for moving packages through a series of
+/// repositories, such as from a staging repository to a production
+/// one. Unlike the [`crate::Publisher`], the recipes, package specs and
+/// component layers are not published again but re-tagged in the
+/// destination, so that they keep the same digests as in the source.
+pub struct Promoter {
+    from: Arc<RepositoryHandle>,
+    to: Arc<RepositoryHandle>,
+    skip_source_packages: bool,
+}
+
+impl Promoter {
+    /// Create a new promoter that moves packages from 'source' to 'destination'.
+    ///
+    /// The promoter can be further configured before calling [`Promoter::promote`]
+    /// to run the operation.
+    pub fn new(source: Arc<RepositoryHandle>, destination: Arc<RepositoryHandle>) -> Self {
+        Self {
+            from: source,
+            to: destination,
+            skip_source_packages: false,
+        }
+    }
+
+    /// Do not promote source packages, even if they exist for the version being promoted.
+    pub fn skip_source_packages(mut self, skip_source_packages: bool) -> Self {
+        self.skip_source_packages = skip_source_packages;
+        self
+    }
+
+    /// Promote the identified package as configured.
+    ///
+    /// When a version is given, its recipe and all of its builds are
+    /// promoted. When a single build is given, its recipe is also
+    /// promoted if the destination does not already have one.
+    pub async fn promote<I>(&self, pkg: I) -> Result<Vec<BuildIdent>>
+    where
+        I: AsRef<AnyIdent>,
+    {
+        let pkg = pkg.as_ref();
+        let recipe_ident = pkg.as_version();
+        let builds = match pkg.build() {
+            None => {
+                tracing::info!("promoting recipe: {}", recipe_ident.format_ident());
+                self.promote_one(pkg).await?;
+                with_cache_policy!(self.from, CachePolicy::BypassCache, {
+                    self.from.list_package_builds(recipe_ident)
+                })
+                .await?
+            }
+            Some(build) => {
+                let has_recipe = with_cache_policy!(self.to, CachePolicy::BypassCache, {
+                    self.to.read_recipe(recipe_ident)
+                })
+                .await
+                .is_ok();
+                if !has_recipe {
+                    tracing::info!("promoting recipe: {}", recipe_ident.format_ident());
+                    match self.promote_one(&recipe_ident.to_any(None)).await {
+                        // builds can be promoted without a recipe, as they are published
+                        Err(Error::PackageNotFound(_)) => (),
+                        res => res?,
+                    }
+                }
+                vec![pkg.to_build(build.clone())]
+            }
+        };
+
+        let mut promoted = Vec::with_capacity(builds.len());
+        for build in builds.into_iter() {
+            if build.is_source() && self.skip_source_packages {
+                tracing::info!("skipping source package: {}", build.format_ident());
+                continue;
+            }
+            if build.is_embedded() {
+                // The stub is recreated when promoting its provider.
+                continue;
+            }
+            tracing::info!("promoting package: {}", build.format_ident());
+            self.promote_one(&build.to_any()).await?;
+            promoted.push(build);
+        }
+        Ok(promoted)
+    }
+
+    async fn promote_one(&self, pkg: &AnyIdent) -> Result<()> {
+        use RepositoryHandle::{SPFSWithVerbatimTags, SPFS};
+
+        match (&*self.from, &*self.to) {
+            (SPFS(src), SPFS(dest)) => dest.promote_from(src, pkg).await,
+            (SPFS(src), SPFSWithVerbatimTags(dest)) => dest.promote_from(src, pkg).await,
+            (SPFSWithVerbatimTags(src), SPFS(dest)) => dest.promote_from(src, pkg).await,
+            (SPFSWithVerbatimTags(src), SPFSWithVerbatimTags(dest)) => {
+                dest.promote_from(src, pkg).await
+            }
+            _ => Err(Error::String(
+                "Source and destination must both be spfs repositories".into(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::{recipe, spec, Package};
+
+use super::Promoter;
+use crate::fixtures::*;
+use crate::AuditAction;
+
+#[rstest]
+#[tokio::test]
+async fn test_promote_keeps_digests() {
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let destination = spfsrepo().await;
+    let promoter = Promoter::new(rt.tmprepo.clone(), destination.repo.clone());
+    let promoted = promoter
+        .promote(spec.ident().base().to_any(None))
+        .await
+        .unwrap();
+    assert_eq!(promoted, vec![spec.ident().clone()]);
+
+    assert_eq!(
+        destination.read_components(spec.ident()).await.unwrap(),
+        rt.tmprepo.read_components(spec.ident()).await.unwrap()
+    );
+    for pkg in [spec.ident().base().to_any(None), spec.ident().to_any()] {
+        let source = rt.tmprepo.read_publish_history(&pkg).await.unwrap();
+        let dest = destination.read_publish_history(&pkg).await.unwrap();
+        assert_eq!(
+            dest[0].target, source[0].target,
+            "promoted specs should not be published again"
+        );
+    }
+
+    let log = destination
+        .read_audit_log(&crate::AuditQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 2, "expected the recipe and build to be logged");
+    let source_name = rt.tmprepo.name().to_string();
+    assert!(log.iter().all(|entry| entry.action == AuditAction::Promote
+        && entry.promoted_from.as_ref() == Some(&source_name)));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_promote_build_also_promotes_recipe() {
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let destination = spfsrepo().await;
+    let promoter = Promoter::new(rt.tmprepo.clone(), destination.repo.clone());
+    promoter.promote(spec.ident().to_any()).await.unwrap();
+    destination
+        .read_recipe(spec.ident().as_version())
+        .await
+        .expect("the recipe should be promoted along with the build");
+}
@@ -0,0 +1,417 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use spfs::prelude::*;
+use spk_schema::foundation::format::{FormatComponents, FormatIdent};
+use spk_schema::foundation::ident_component::{Component, ComponentSet};
+use spk_schema::{AnyIdent, BuildIdent, Package, Recipe, Spec, SpecRecipe, VersionIdent};
+
+use crate::{with_cache_policy, AccessAction, CachePolicy, Error, RepositoryHandle, Result};
+
+#[cfg(test)]
+#[path = "./publish_test.rs"]
+mod publish_test;
+
+/// Contains label=value data for use with publishing
+#[derive(Debug, Clone)]
+pub struct PublishLabel {
+    label: String,
+    value: String,
+}
+
+impl FromStr for PublishLabel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some((label, value)) = s.split_once('=') {
+            return Ok(Self {
+                label: label.to_string(),
+                value: value.to_string(),
+            });
+        }
+        Err(Error::String(format!(
+            "Invalid: {s} is not in 'label=value' format"
+        )))
+    }
+}
+
+/// Manages the publishing of packages from one repo to another.
+///
+/// Usually, the publish process moves packages from the local
+/// repo to a shared one, but this is not strictly required.
+/// The publisher can be customized after creation before calling
+/// the publish method to execute.
+pub struct Publisher {
+    from: Arc<RepositoryHandle>,
+    to: Vec<Arc<RepositoryHandle>>,
+    skip_source_packages: bool,
+    allow_existing_label: Option<PublishLabel>,
+    force: bool,
+}
+
+/// The components of each package whose layers already existed
+/// in a target repository, and so were not transferred again
+type ReusedLayers = HashMap<BuildIdent, ComponentSet>;
+
+/// A recipe or package that was newly created in a target
+/// repository, and which can be removed again if the publish fails
+enum Published {
+    Recipe(Arc<RepositoryHandle>, VersionIdent),
+    Package(Arc<RepositoryHandle>, BuildIdent),
+}
+
+impl Publisher {
+    /// Create a new publisher that moves packages from 'source' to 'destination'.
+    ///
+    /// The publisher can be further configured before calling [`Publisher::publish`]
+    /// to run the operation.
+    pub fn new(source: Arc<RepositoryHandle>, destination: Arc<RepositoryHandle>) -> Self {
+        Self {
+            from: source,
+            to: vec![destination],
+            skip_source_packages: false,
+            allow_existing_label: None,
+            force: false,
+        }
+    }
+
+    /// Change the source repository to publish packages from.
+    pub fn with_source(mut self, repo: Arc<RepositoryHandle>) -> Self {
+        self.from = repo;
+        self
+    }
+
+    /// Change the destination repository to publish packages into.
+    pub fn with_target(mut self, repo: Arc<RepositoryHandle>) -> Self {
+        self.to = vec![repo];
+        self
+    }
+
+    /// Publish packages into all of the given repositories at once.
+    ///
+    /// The payloads of each package are synced to every target before
+    /// any recipe or package is published. If publishing fails for any
+    /// one target, the recipes and packages that were already created
+    /// in the others are removed again so that the targets stay in sync.
+    pub fn with_targets(mut self, repos: Vec<Arc<RepositoryHandle>>) -> Self {
+        self.to = repos;
+        self
+    }
+
+    /// Do not publish source packages, even if they exist for the version being published.
+    pub fn skip_source_packages(mut self, skip_source_packages: bool) -> Self {
+        self.skip_source_packages = skip_source_packages;
+        self
+    }
+
+    /// Allow publishing builds when the version already exists and
+    /// the existing version recipe contains the given metadata label
+    /// and value.
+    pub fn allow_existing_with_label(mut self, existing_label: Option<PublishLabel>) -> Self {
+        self.allow_existing_label = existing_label;
+        self
+    }
+
+    /// Forcefully publishing a package will overwrite an existing publish if it exists.
+    ///
+    /// Recipes that are overwritten this way cannot be restored if the
+    /// publish later fails for another target.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    async fn allow_existing_version(
+        &self,
+        target: &RepositoryHandle,
+        dest_recipe_ident: &VersionIdent,
+    ) -> Result<bool> {
+        if let Some(label) = &self.allow_existing_label {
+            let dest_recipe = target.read_recipe(dest_recipe_ident).await?;
+            let result = dest_recipe
+                .metadata()
+                .has_label_with_value(&label.label, &label.value);
+            if !result {
+                tracing::debug!(
+                    "Package version does not have '{}={}' in its metadata",
+                    label.label,
+                    label.value
+                );
+            }
+            Ok(result)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Publish the identified package as configured.
+    pub async fn publish<I>(&self, pkg: I) -> Result<Vec<BuildIdent>>
+    where
+        I: AsRef<AnyIdent>,
+    {
+        let pkg = pkg.as_ref();
+        if self.to.is_empty() {
+            return Err(Error::String(
+                "No destination repositories to publish into".into(),
+            ));
+        }
+        // check permissions up front so that nothing is copied
+        // into any target repository that will reject the package
+        for target in self.to.iter() {
+            target
+                .check_access(AccessAction::Publish, pkg.name())
+                .await?;
+        }
+
+        let recipe_ident = pkg.as_version();
+        tracing::info!("loading recipe: {}", recipe_ident.format_ident());
+        let recipe = match with_cache_policy!(self.from, CachePolicy::BypassCache, {
+            self.from.read_recipe(recipe_ident).await
+        }) {
+            Err(err @ Error::PackageNotFound(_)) if self.force => {
+                return Err(
+                    format!("Can't force publish; missing package spec locally: {err}").into(),
+                );
+            }
+            Err(Error::PackageNotFound(_)) => {
+                // If it was not found locally, allow the publish to proceed;
+                // if it is also missing on the remote, that will be caught
+                // and the publish will be rejected by the storage.
+                None
+            }
+            Err(err) => return Err(err),
+            Ok(recipe) => Some(recipe),
+        };
+
+        let builds = match pkg.build() {
+            None => {
+                with_cache_policy!(self.from, CachePolicy::BypassCache, {
+                    self.from.list_package_builds(recipe_ident)
+                })
+                .await?
+            }
+            Some(build) => vec![pkg.to_build(build.clone())],
+        };
+
+        let mut packages = Vec::with_capacity(builds.len());
+        for build in builds.iter() {
+            if build.is_source() && self.skip_source_packages {
+                tracing::info!("skipping source package: {}", build.format_ident());
+                continue;
+            }
+
+            if build.is_embedded() {
+                // Don't attempt to publish an embedded package; the stub
+                // will be recreated when publishing its provider.
+                continue;
+            }
+
+            tracing::debug!("   loading package: {}", build.format_ident());
+            let spec = self.from.read_package(build).await?;
+            let components = self.from.read_components(build).await?;
+            packages.push((spec, components));
+        }
+
+        // payloads are synced to every target before anything
+        // is published, since they are not visible until a recipe
+        // or package refers to them
+        let mut reused = Vec::with_capacity(self.to.len());
+        for target in self.to.iter() {
+            let mut reused_in_target = ReusedLayers::new();
+            for (spec, components) in packages.iter() {
+                let unchanged = self.sync_payloads(target, spec.ident(), components).await?;
+                if !unchanged.is_empty() {
+                    reused_in_target.insert(spec.ident().clone(), unchanged);
+                }
+            }
+            reused.push(reused_in_target);
+        }
+
+        let mut published = Vec::new();
+        for (target, reused) in self.to.iter().zip(reused.iter()) {
+            if let Err(err) = self
+                .publish_to(
+                    target,
+                    pkg,
+                    recipe.as_deref(),
+                    &packages,
+                    reused,
+                    &mut published,
+                )
+                .await
+            {
+                Self::roll_back(published).await;
+                return Err(err);
+            }
+        }
+
+        Ok(builds)
+    }
+
+    /// Sync the component layers of one build into the target.
+    ///
+    /// The syncer only transfers the objects and payloads that are
+    /// missing from the target, so layers that already exist, such as
+    /// when a package is rebuilt without changing the contents of some
+    /// components, are not transferred again. The components that were
+    /// reused this way are returned.
+    async fn sync_payloads(
+        &self,
+        target: &RepositoryHandle,
+        build: &BuildIdent,
+        components: &HashMap<Component, spfs::encoding::Digest>,
+    ) -> Result<ComponentSet> {
+        use spfs::sync::{SyncEnvItemResult, SyncLayerResult, SyncObjectResult};
+        use RepositoryHandle::{SPFSWithVerbatimTags, SPFS};
+
+        let env_spec = components.values().cloned().collect();
+        tracing::debug!(
+            " syncing components: {} {}",
+            build.format_ident(),
+            ComponentSet::from(components.keys().cloned()).format_components()
+        );
+        let syncer = match (&*self.from, target) {
+            (SPFS(src), SPFS(dest)) => spfs::Syncer::new(src, dest),
+            (SPFS(src), SPFSWithVerbatimTags(dest)) => spfs::Syncer::new(src, dest),
+            (SPFSWithVerbatimTags(src), SPFS(dest)) => spfs::Syncer::new(src, dest),
+            (SPFSWithVerbatimTags(src), SPFSWithVerbatimTags(dest)) => spfs::Syncer::new(src, dest),
+            _ => {
+                return Err(Error::String(
+                    "Source and destination must both be spfs repositories".into(),
+                ))
+            }
+        };
+        let result = syncer
+            .with_reporter(spfs::sync::ConsoleSyncReporter::default())
+            .sync_env(env_spec)
+            .await?;
+
+        let synced = result
+            .results
+            .iter()
+            .filter_map(|res| match res {
+                SyncEnvItemResult::Object(SyncObjectResult::Layer(SyncLayerResult::Synced {
+                    layer,
+                    ..
+                })) => layer.digest().ok(),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let reused = ComponentSet::from(
+            components
+                .iter()
+                .filter(|(_, digest)| !synced.contains(*digest))
+                .map(|(component, _)| component.clone()),
+        );
+        if !reused.is_empty() {
+            tracing::debug!(
+                " reused unchanged components: {} {}",
+                build.format_ident(),
+                reused.format_components()
+            );
+        }
+        Ok(reused)
+    }
+
+    /// Publish the recipe and packages into one target, recording
+    /// everything that was newly created there in `published`.
+    async fn publish_to(
+        &self,
+        target: &Arc<RepositoryHandle>,
+        pkg: &AnyIdent,
+        recipe: Option<&SpecRecipe>,
+        packages: &[(Arc<Spec>, HashMap<Component, spfs::encoding::Digest>)],
+        reused: &ReusedLayers,
+        published: &mut Vec<Published>,
+    ) -> Result<()> {
+        if let Some(recipe) = recipe {
+            tracing::info!("publishing recipe: {}", recipe.ident().format_ident());
+            if self.force {
+                target.force_publish_recipe(recipe).await?;
+            } else {
+                match target.publish_recipe(recipe).await {
+                    Ok(_) => {
+                        published.push(Published::Recipe(
+                            Arc::clone(target),
+                            recipe.ident().clone(),
+                        ));
+                    }
+                    Err(Error::VersionExists(dest_recipe_ident)) => {
+                        if self
+                            .allow_existing_version(target, &dest_recipe_ident)
+                            .await?
+                        {
+                            // The existing version was generated and published by a
+                            // conversion process, e.g. spk convert pip/spk-convert-pip,
+                            // so allow these builds to be published.
+                            tracing::info!("Package version exists, allow-existing-with-label specified, and matched: publishing new builds allowed");
+                        } else {
+                            match pkg.build() {
+                                Some(_) => (), // If build provided, we can silently fail.
+                                None => {
+                                    return Err(format!(
+                                        "Failed to publish recipe {}: Version exists",
+                                        recipe.ident(),
+                                    )
+                                    .into());
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        return Err(
+                            format!("Failed to publish recipe {}: {err}", recipe.ident()).into(),
+                        );
+                    }
+                }
+            }
+        }
+
+        for (spec, components) in packages.iter() {
+            match reused.get(spec.ident()) {
+                Some(unchanged) => tracing::info!(
+                    "publishing package: {} (reused unchanged layers: {})",
+                    spec.ident().format_ident(),
+                    unchanged.format_components()
+                ),
+                None => tracing::info!("publishing package: {}", spec.ident().format_ident()),
+            }
+            let existed = with_cache_policy!(target, CachePolicy::BypassCache, {
+                target.read_package(spec.ident()).await
+            })
+            .is_ok();
+            target.publish_package(spec, components).await?;
+            if !existed {
+                published.push(Published::Package(Arc::clone(target), spec.ident().clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove everything that was published, in reverse order.
+    ///
+    /// Failures are logged rather than returned so that the
+    /// original publish error is the one that gets reported.
+    async fn roll_back(published: Vec<Published>) {
+        for item in published.into_iter().rev() {
+            let result = match &item {
+                Published::Package(repo, build) => {
+                    tracing::warn!("rolling back package: {}", build.format_ident());
+                    repo.remove_package(build).await
+                }
+                Published::Recipe(repo, version) => {
+                    tracing::warn!("rolling back recipe: {}", version.format_ident());
+                    repo.remove_recipe(version).await
+                }
+            };
+            if let Err(err) = result {
+                tracing::error!("failed to roll back partial publish: {err}");
+            }
+        }
+    }
+}
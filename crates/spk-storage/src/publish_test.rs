@@ -0,0 +1,279 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spfs::prelude::*;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::{recipe, spec, Package, Recipe};
+
+use super::Publisher;
+use crate::fixtures::*;
+use crate::RepositoryHandle;
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_no_version_spec() {
+    let rt = spfs_runtime().await;
+    let spec = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&spec).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let destination = spfsrepo().await;
+    let publisher = Publisher::new(rt.tmprepo.clone(), destination.repo.clone());
+    publisher
+        .publish(spec.ident().base().to_any(None))
+        .await
+        .unwrap();
+    destination.read_components(spec.ident()).await.unwrap();
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_build_also_publishes_spec() {
+    // This test only publishes a single build and verifies that the spec
+    // is also published.
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let destination = spfsrepo().await;
+    let publisher = Publisher::new(rt.tmprepo.clone(), destination.repo.clone());
+    // Include build when publishing this spec.
+    publisher.publish(spec.ident().to_any()).await.unwrap();
+    let r = destination.read_recipe(spec.ident().as_version()).await;
+    assert!(
+        r.is_ok(),
+        "Expected to be able to read spec, but got error: {}",
+        r.err().unwrap()
+    )
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_multiple_builds_individually() {
+    // This test publishes multiple builds and verifies that subsequent builds
+    // don't fail to publish because the spec is already there.
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+
+    let destination = spfsrepo().await;
+    let publisher = Publisher::new(rt.tmprepo.clone(), destination.repo.clone());
+
+    {
+        let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+        rt.tmprepo
+            .publish_package(
+                &spec,
+                &vec![(Component::Run, empty_layer_digest())]
+                    .into_iter()
+                    .collect(),
+            )
+            .await
+            .unwrap();
+
+        // Include build when publishing this spec.
+        publisher.publish(spec.ident().to_any()).await.unwrap();
+    }
+
+    {
+        // Publish a second, different build here.
+        let spec = spec!({"pkg": "my-pkg/1.0.0/CU7ZWOIF"});
+        rt.tmprepo
+            .publish_package(
+                &spec,
+                &vec![(Component::Run, empty_layer_digest())]
+                    .into_iter()
+                    .collect(),
+            )
+            .await
+            .unwrap();
+
+        // Include build when publishing this spec.
+        let r = publisher.publish(spec.ident().to_any()).await;
+        assert!(
+            r.is_ok(),
+            "Expected second publish to succeed, but got error: {}",
+            r.err().unwrap()
+        )
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_multiple_targets() {
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let first = spfsrepo().await;
+    let second = spfsrepo().await;
+    let publisher = Publisher::new(rt.tmprepo.clone(), first.repo.clone())
+        .with_targets(vec![first.repo.clone(), second.repo.clone()]);
+    publisher
+        .publish(spec.ident().base().to_any(None))
+        .await
+        .unwrap();
+    for destination in [&first, &second] {
+        destination.read_recipe(recipe.ident()).await.unwrap();
+        destination.read_components(spec.ident()).await.unwrap();
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_multiple_targets_rolls_back() {
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let first = spfsrepo().await;
+    let second = spfsrepo().await;
+    // the version already exists in the second target, which
+    // fails the publish after the first target has been written
+    second.publish_recipe(&recipe).await.unwrap();
+
+    let publisher = Publisher::new(rt.tmprepo.clone(), first.repo.clone())
+        .with_targets(vec![first.repo.clone(), second.repo.clone()]);
+    publisher
+        .publish(spec.ident().base().to_any(None))
+        .await
+        .expect_err("should fail when the version exists in one target");
+
+    first
+        .read_recipe(recipe.ident())
+        .await
+        .expect_err("recipe should be removed from the first target");
+    first
+        .read_package(spec.ident())
+        .await
+        .expect_err("package should be removed from the first target");
+    second
+        .read_recipe(recipe.ident())
+        .await
+        .expect("existing recipe should not be removed");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_reuses_existing_layers() {
+    let rt = spfs_runtime().await;
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    let RepositoryHandle::SPFS(source) = &*rt.tmprepo else {
+        panic!("expected an spfs repository for the runtime");
+    };
+    let changed = spfs::graph::Layer::new_with_annotation(
+        "spk:test",
+        spfs::graph::AnnotationValue::string("rebuilt"),
+    );
+    source.write_object(&changed).await.unwrap();
+    let components = vec![
+        (Component::Run, empty_layer_digest()),
+        (Component::Build, changed.digest().unwrap()),
+    ]
+    .into_iter()
+    .collect();
+
+    // the empty layer is already present in any new repo
+    let destination = spfsrepo().await;
+    let publisher = Publisher::new(rt.tmprepo.clone(), destination.repo.clone());
+    let reused = publisher
+        .sync_payloads(&destination.repo, spec.ident(), &components)
+        .await
+        .unwrap();
+    assert_eq!(
+        *reused,
+        [Component::Run].into_iter().collect(),
+        "only the existing layer should be reused"
+    );
+
+    // a rebuild with the same component contents has nothing new to sync
+    let reused = publisher
+        .sync_payloads(&destination.repo, spec.ident(), &components)
+        .await
+        .unwrap();
+    assert_eq!(
+        *reused,
+        [Component::Run, Component::Build].into_iter().collect(),
+        "all layers should be reused"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_multiple_targets_syncs_before_publishing() {
+    let rt = spfs_runtime().await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    rt.tmprepo.publish_recipe(&recipe).await.unwrap();
+    let spec = spec!({"pkg": "my-pkg/1.0.0/BGSHW3CN"});
+    rt.tmprepo
+        .publish_package(
+            &spec,
+            &vec![(Component::Run, empty_layer_digest())]
+                .into_iter()
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+    let first = spfsrepo().await;
+    // payloads cannot be synced into a non-spfs repository, so the
+    // publish fails while syncing to the second target
+    let second = make_repo(RepoKind::Mem).await;
+
+    let publisher = Publisher::new(rt.tmprepo.clone(), first.repo.clone())
+        .with_targets(vec![first.repo.clone(), second.repo.clone()]);
+    publisher
+        .publish(spec.ident().base().to_any(None))
+        .await
+        .expect_err("should fail when payloads cannot be synced to one target");
+
+    first
+        .read_recipe(recipe.ident())
+        .await
+        .expect_err("nothing should be published before all payloads are synced");
+    first
+        .read_package(spec.ident())
+        .await
+        .expect_err("nothing should be published before all payloads are synced");
+}
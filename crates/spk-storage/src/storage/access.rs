@@ -0,0 +1,241 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use serde::{Deserialize, Serialize};
+use spk_schema::foundation::name::{PkgName, RepositoryName};
+
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./access_test.rs"]
+mod access_test;
+
+/// A change to a repository that can be restricted by its access rules
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessAction {
+    /// Publishing or modifying recipes and builds
+    Publish,
+    /// Removing recipes and builds
+    Remove,
+}
+
+impl AccessAction {
+    fn all() -> Vec<Self> {
+        vec![Self::Publish, Self::Remove]
+    }
+}
+
+impl std::fmt::Display for AccessAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Publish => f.write_str("publish"),
+            Self::Remove => f.write_str("remove"),
+        }
+    }
+}
+
+/// The rules for who may change which packages in a repository.
+///
+/// Packages that are not matched by any rule can be changed by anyone.
+/// Once a package is matched by a rule for some action, only the users
+/// listed in the matching rules may perform that action.
+///
+/// These rules are checked using the user name from the spfs config,
+/// which is not verified and so they guard against mistakes rather
+/// than malicious changes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct AccessControl {
+    rules: Vec<AccessRule>,
+}
+
+impl AccessControl {
+    pub fn new(rules: Vec<AccessRule>) -> Self {
+        Self { rules }
+    }
+
+    /// True if there are no rules, and so anyone can change anything
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn rules(&self) -> &[AccessRule] {
+        &self.rules
+    }
+
+    /// Return true if the named user may perform the action on the package
+    pub fn is_allowed(&self, action: AccessAction, pkg: &PkgName, user: &str) -> bool {
+        let mut restricted = false;
+        for rule in self.rules.iter().filter(|r| r.applies_to(action, pkg)) {
+            if rule.allows(user) {
+                return true;
+            }
+            restricted = true;
+        }
+        !restricted
+    }
+
+    /// Check that the named user may perform the action on the package.
+    ///
+    /// # Errors:
+    /// - PermissionDenied: if the user is not allowed to
+    pub fn check(
+        &self,
+        action: AccessAction,
+        pkg: &PkgName,
+        user: &str,
+        repo: &RepositoryName,
+    ) -> Result<()> {
+        if self.is_allowed(action, pkg, user) {
+            return Ok(());
+        }
+        Err(Error::PermissionDenied {
+            user: user.to_string(),
+            action,
+            pkg: pkg.to_owned(),
+            repo: repo.to_string(),
+        })
+    }
+}
+
+/// Restricts some actions on a set of packages to a list of users
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AccessRule {
+    /// The names of the packages that this rule applies to,
+    /// which may contain glob wildcards such as `python*`
+    pub packages: Vec<PackagePattern>,
+    /// The actions that this rule restricts, all of them by default
+    #[serde(default = "AccessAction::all")]
+    pub actions: Vec<AccessAction>,
+    /// The users allowed to perform these actions, or `*` for anyone
+    pub users: Vec<String>,
+}
+
+impl AccessRule {
+    /// True if this rule restricts the action on the named package
+    pub fn applies_to(&self, action: AccessAction, pkg: &PkgName) -> bool {
+        self.actions.contains(&action) && self.packages.iter().any(|p| p.matches(pkg))
+    }
+
+    /// True if this rule lists the given user
+    pub fn allows(&self, user: &str) -> bool {
+        self.users.iter().any(|u| u == "*" || u == user)
+    }
+}
+
+/// A glob pattern that is matched against package names
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PackagePattern(glob::Pattern);
+
+impl PackagePattern {
+    pub fn matches(&self, pkg: &PkgName) -> bool {
+        self.0.matches(pkg.as_str())
+    }
+}
+
+impl std::str::FromStr for PackagePattern {
+    type Err = glob::PatternError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        glob::Pattern::new(s).map(Self)
+    }
+}
+
+impl TryFrom<String> for PackagePattern {
+    type Error = glob::PatternError;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PackagePattern> for String {
+    fn from(value: PackagePattern) -> Self {
+        value.0.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for PackagePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+/// Identify the package that an spk tag in an spfs repository belongs to.
+///
+/// Embed stubs are not included, since they are published and
+/// removed along with the package that they are embedded in.
+#[cfg(feature = "server")]
+fn tag_package(tag: &spfs::tracking::TagSpec) -> Option<spk_schema::foundation::name::PkgNameBuf> {
+    use spk_schema::foundation::ident_build::EMBEDDED;
+
+    let path = tag.path();
+    let mut parts = path.components().map(|c| c.as_str());
+    match (parts.next(), parts.next(), parts.next(), parts.nth(1)) {
+        (_, _, _, Some(build)) if build.starts_with(EMBEDDED) => None,
+        (Some("spk"), Some("pkg" | "spec"), Some(name), _) => name.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Enforces the access rules of an spk repository on the tags
+/// that clients change through an spfs server.
+#[cfg(feature = "server")]
+#[derive(Debug)]
+pub struct AccessPolicy {
+    repo: std::sync::Arc<crate::RepositoryHandle>,
+}
+
+#[cfg(feature = "server")]
+impl AccessPolicy {
+    /// Create a policy that reads the rules from the
+    /// spk repository being served
+    pub fn new(repo: std::sync::Arc<crate::RepositoryHandle>) -> Self {
+        Self { repo }
+    }
+
+    /// Create a policy that reads the rules from an spfs repository,
+    /// as the spk repository with the given name
+    pub fn from_spfs_repository(name: &str, repo: spfs::storage::RepositoryHandle) -> Result<Self> {
+        use spk_schema::ident_ops::NormalizedTagStrategy;
+
+        let repo: crate::storage::SpfsRepository<NormalizedTagStrategy> =
+            crate::storage::NameAndRepositoryWithTagStrategy::<_, _, NormalizedTagStrategy>::new(
+                name, repo,
+            )
+            .try_into()?;
+        Ok(Self::new(std::sync::Arc::new(repo.into())))
+    }
+
+    async fn check(
+        &self,
+        action: AccessAction,
+        tag: &spfs::tracking::TagSpec,
+        user: &str,
+    ) -> spfs::Result<()> {
+        let Some(pkg) = tag_package(tag) else {
+            return Ok(());
+        };
+        self.repo
+            .read_access_control()
+            .await
+            .and_then(|acl| acl.check(action, &pkg, user, self.repo.name()))
+            .map_err(|err| spfs::Error::String(err.to_string()))
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait::async_trait]
+impl spfs::server::TagPolicy for AccessPolicy {
+    async fn check_insert(&self, tag: &spfs::tracking::Tag, user: &str) -> spfs::Result<()> {
+        self.check(AccessAction::Publish, &tag.to_spec(0), user)
+            .await
+    }
+
+    async fn check_remove(&self, tag: &spfs::tracking::TagSpec, user: &str) -> spfs::Result<()> {
+        self.check(AccessAction::Remove, tag, user).await
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::name::PkgName;
+use spk_schema::foundation::pkg_name;
+use spk_schema::{recipe, Recipe};
+
+#[cfg(feature = "server")]
+use super::AccessPolicy;
+use super::{AccessAction, AccessControl, AccessRule};
+use crate::fixtures::*;
+use crate::{Error, Repository, RepositoryHandle};
+
+fn rule(packages: &[&str], actions: &[AccessAction], users: &[&str]) -> AccessRule {
+    AccessRule {
+        packages: packages.iter().map(|p| p.parse().unwrap()).collect(),
+        actions: actions.to_vec(),
+        users: users.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[rstest]
+#[case::unmatched_package("other", AccessAction::Publish, "someone", true)]
+#[case::listed_user("gcc", AccessAction::Publish, "pipeline", true)]
+#[case::unlisted_user("gcc", AccessAction::Publish, "someone", false)]
+#[case::glob_match("python-six", AccessAction::Remove, "someone", false)]
+#[case::unrestricted_action("gcc", AccessAction::Remove, "someone", true)]
+#[case::any_matching_rule("python", AccessAction::Remove, "admin", true)]
+fn test_access_is_allowed(
+    #[case] pkg: &str,
+    #[case] action: AccessAction,
+    #[case] user: &str,
+    #[case] expected: bool,
+) {
+    let access = AccessControl::new(vec![
+        rule(&["gcc", "python*"], &[AccessAction::Publish], &["pipeline"]),
+        rule(&["python*"], &[AccessAction::Remove], &["admin"]),
+    ]);
+    let pkg = PkgName::new(pkg).unwrap();
+    assert_eq!(access.is_allowed(action, pkg, user), expected);
+}
+
+#[rstest]
+fn test_access_deserialize_defaults_to_all_actions() {
+    let access: AccessControl = serde_yaml::from_str(
+        r#"
+        - packages: [gcc]
+          users: [pipeline]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(access.rules().len(), 1);
+    assert_eq!(
+        access.rules()[0].actions,
+        vec![AccessAction::Publish, AccessAction::Remove]
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_spfs_repo_enforces_access() {
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+
+    let user = spfs::get_config().unwrap().user.name.clone();
+    let access = AccessControl::new(vec![rule(
+        &["my-pkg"],
+        &[AccessAction::Publish],
+        &[&format!("not-{user}")],
+    )]);
+    spfs_repo
+        .write_access_control(access.clone())
+        .await
+        .unwrap();
+    assert_eq!(repo.read_access_control().await.unwrap(), access);
+
+    let res = repo.publish_recipe(&recipe!({"pkg": "my-pkg/1.0.0"})).await;
+    assert!(
+        matches!(res, Err(Error::PermissionDenied { .. })),
+        "expected publish to be denied, got {res:?}"
+    );
+    assert!(repo
+        .list_package_versions(pkg_name!("my-pkg"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let other = recipe!({"pkg": "other-pkg/1.0.0"});
+    repo.publish_recipe(&other)
+        .await
+        .expect("packages without rules can be published by anyone");
+    repo.remove_recipe(other.ident())
+        .await
+        .expect("actions without rules can be performed by anyone");
+}
+
+#[cfg(feature = "server")]
+async fn insert_tag_as(
+    service: &spfs::server::TagService,
+    path: &str,
+    user: &str,
+) -> spfs::proto::InsertTagResponse {
+    use spfs::proto::tag_service_server::TagService;
+
+    let mut tag =
+        spfs::tracking::Tag::new(None, path, spfs::encoding::EMPTY_DIGEST.into()).unwrap();
+    tag.user = user.to_string();
+    let request = spfs::proto::InsertTagRequest {
+        tag: Some((&tag).into()),
+        namespace: String::new(),
+    };
+    service
+        .insert_tag(tonic::Request::new(request))
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[cfg(feature = "server")]
+#[rstest]
+#[tokio::test]
+async fn test_access_policy_rejects_denied_tags() {
+    use spfs::prelude::*;
+    use spfs::proto::insert_tag_response::Result as InsertResult;
+
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+    spfs_repo
+        .write_access_control(AccessControl::new(vec![rule(
+            &["my-pkg"],
+            &[AccessAction::Publish],
+            &["pipeline"],
+        )]))
+        .await
+        .unwrap();
+
+    let served = spfs::open_repository(spfs_repo.address().as_str())
+        .await
+        .unwrap();
+    let service = spfs::server::TagService::new(std::sync::Arc::new(served))
+        .with_policy(std::sync::Arc::new(AccessPolicy::new(repo.repo.clone())));
+
+    let res = insert_tag_as(&service, "spk/pkg/my-pkg/1.0.0/src", "someone").await;
+    assert!(
+        matches!(res.result, Some(InsertResult::Error(_))),
+        "expected an unlisted user to be denied, got {res:?}"
+    );
+    assert!(
+        !spfs_repo
+            .has_tag(&"spk/pkg/my-pkg/1.0.0/src".parse().unwrap())
+            .await,
+        "a denied tag should not be created"
+    );
+
+    let res = insert_tag_as(&service, "spk/pkg/my-pkg/1.0.0/src", "pipeline").await;
+    assert!(
+        matches!(res.result, Some(InsertResult::Ok(_))),
+        "expected a listed user to be allowed, got {res:?}"
+    );
+    let res = insert_tag_as(&service, "spk/pkg/other-pkg/1.0.0/src", "someone").await;
+    assert!(
+        matches!(res.result, Some(InsertResult::Ok(_))),
+        "expected packages without rules to be allowed, got {res:?}"
+    );
+    let res = insert_tag_as(&service, "not/an/spk/tag", "someone").await;
+    assert!(
+        matches!(res.result, Some(InsertResult::Ok(_))),
+        "expected tags outside of spk to be allowed, got {res:?}"
+    );
+}
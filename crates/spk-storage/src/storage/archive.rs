@@ -2,20 +2,334 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use spfs::encoding::Digest;
+use spfs::operation::CancellationToken;
+use spfs::prelude::*;
+use spfs::sync::SyncReporter;
 use spk_schema::ident_ops::TagPathStrategy;
 use spk_schema::{AnyIdent, BuildIdent, VersionIdent};
 
 use super::{Repository, SpfsRepository};
 use crate::{Error, NameAndRepositoryWithTagStrategy, Result};
 
+#[cfg(test)]
+#[path = "./archive_test.rs"]
+mod archive_test;
+
+/// The current version of the archive format written by [`export_package`]
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// The name of the file in each archive that holds its [`ArchiveManifest`]
+pub const ARCHIVE_MANIFEST_FILE: &str = "spk-archive.json";
+
+/// Describes the contents of an exported archive so that
+/// it can be verified before being imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// The version of the archive format
+    pub version: u32,
+    /// The packages that were requested when the archive was created
+    pub packages: Vec<AnyIdent>,
+    /// Every object stored in the archive
+    pub objects: BTreeSet<Digest>,
+    /// Every payload stored in the archive
+    pub payloads: BTreeSet<Digest>,
+    /// The target of every tag in the archive, by tag spec
+    pub tags: BTreeMap<String, Digest>,
+    /// A checksum of all of the above, see [`ArchiveManifest::compute_checksum`]
+    pub checksum: Digest,
+}
+
+impl ArchiveManifest {
+    /// Build a manifest of everything that is currently stored in the given repository.
+    async fn collect(
+        repo: &spfs::storage::RepositoryHandle,
+        packages: Vec<AnyIdent>,
+    ) -> Result<Self> {
+        let (objects, payloads, tags) = tokio::try_join!(
+            repo.find_digests(spfs::graph::DigestSearchCriteria::All)
+                .try_collect(),
+            repo.iter_payload_digests().try_collect(),
+            repo.iter_tags()
+                .map_ok(|(spec, tag)| (spec.to_string(), tag.target))
+                .try_collect(),
+        )?;
+        let mut manifest = Self {
+            version: ARCHIVE_FORMAT_VERSION,
+            packages,
+            objects,
+            payloads,
+            tags,
+            checksum: spfs::encoding::NULL_DIGEST.into(),
+        };
+        manifest.checksum = manifest.compute_checksum();
+        Ok(manifest)
+    }
+
+    /// Calculate the checksum of this manifest's contents.
+    ///
+    /// The stored checksum is not included in the calculation.
+    pub fn compute_checksum(&self) -> Digest {
+        let mut hasher = spfs::encoding::Hasher::new_sync();
+        hasher.update(&self.version.to_le_bytes());
+        for pkg in self.packages.iter() {
+            hasher.update(pkg.to_string().as_bytes());
+            hasher.update(b"\n");
+        }
+        for digest in self.objects.iter().chain(self.payloads.iter()) {
+            hasher.update(digest.as_bytes());
+        }
+        for (spec, target) in self.tags.iter() {
+            hasher.update(spec.as_bytes());
+            hasher.update(b"\n");
+            hasher.update(target.as_bytes());
+        }
+        hasher.digest()
+    }
+
+    /// Load the manifest from an unpacked archive, if it has one.
+    ///
+    /// Archives created by older versions of spk will not have a manifest.
+    pub fn load(archive: &spfs::storage::tar::TarRepository) -> Result<Option<Self>> {
+        let path = archive.root().join(ARCHIVE_MANIFEST_FILE);
+        let file = match std::fs::File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::FileOpenError(path, err)),
+        };
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .map(Some)
+            .map_err(|err| {
+                Error::ArchiveVerificationFailed(
+                    archive_path(archive),
+                    format!("invalid {ARCHIVE_MANIFEST_FILE}: {err}"),
+                )
+            })
+    }
+
+    fn save(&self, archive: &spfs::storage::tar::TarRepository) -> Result<()> {
+        let path = archive.root().join(ARCHIVE_MANIFEST_FILE);
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|err| Error::String(format!("Failed to serialize archive manifest: {err}")))?;
+        std::fs::write(&path, data).map_err(|err| {
+            spfs::Error::StorageWriteError("write of archive manifest", path, err).into()
+        })
+    }
+}
+
+/// Check that the contents of an archive match its manifest.
+///
+/// Every object and payload in the archive is re-hashed to ensure that
+/// it has not been corrupted, and every tag must point to the same
+/// target as when the archive was created. Returns the manifest of the archive, or
+/// `None` for older archives that were created without one and so
+/// cannot be verified.
+pub async fn verify_archive(
+    repo: &spfs::storage::RepositoryHandle,
+) -> Result<Option<ArchiveManifest>> {
+    let spfs::storage::RepositoryHandle::Tar(archive) = repo else {
+        return Err(Error::String(format!(
+            "Only archives can be verified, got: {}",
+            repo.address()
+        )));
+    };
+    let path = archive_path(archive);
+    let failed = |reason: String| Error::ArchiveVerificationFailed(path.clone(), reason);
+    let Some(manifest) = ArchiveManifest::load(archive)? else {
+        return Ok(None);
+    };
+    if manifest.version > ARCHIVE_FORMAT_VERSION {
+        return Err(Error::UnsupportedArchiveVersion {
+            path,
+            version: manifest.version,
+            supported: ARCHIVE_FORMAT_VERSION,
+        });
+    }
+    if manifest.compute_checksum() != manifest.checksum {
+        return Err(failed("the manifest checksum does not match".into()));
+    }
+
+    let found = ArchiveManifest::collect(repo, manifest.packages.clone()).await?;
+    if let Some(missing) = manifest
+        .objects
+        .difference(&found.objects)
+        .chain(manifest.payloads.difference(&found.payloads))
+        .next()
+    {
+        return Err(failed(format!("{missing} is missing from the archive")));
+    }
+    if let Some(extra) = found
+        .objects
+        .difference(&manifest.objects)
+        .chain(found.payloads.difference(&manifest.payloads))
+        .next()
+    {
+        return Err(failed(format!("{extra} is not listed in the manifest")));
+    }
+    for (spec, target) in manifest.tags.iter() {
+        match found.tags.get(spec) {
+            None => return Err(failed(format!("tag {spec} is missing from the archive"))),
+            Some(actual) if actual != target => {
+                return Err(failed(format!(
+                    "tag {spec} points to {actual} instead of {target}"
+                )));
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(extra) = found
+        .tags
+        .keys()
+        .find(|spec| !manifest.tags.contains_key(*spec))
+    {
+        return Err(failed(format!("tag {extra} is not listed in the manifest")));
+    }
+
+    for digest in manifest.objects.iter() {
+        let actual = repo.read_object(*digest).await?.digest()?;
+        if actual != *digest {
+            return Err(failed(format!("object {digest} has been modified")));
+        }
+    }
+    for digest in manifest.payloads.iter() {
+        let (mut payload, filename) = repo.open_payload(*digest).await?;
+        let mut hasher =
+            spfs::encoding::Hasher::with_algorithm(repo.digest_algorithm(), tokio::io::sink());
+        tokio::io::copy(&mut payload, &mut hasher)
+            .await
+            .map_err(|err| spfs::Error::StorageReadError("copy of payload", filename, err))?;
+        if hasher.digest() != *digest {
+            return Err(failed(format!("payload {digest} has been modified")));
+        }
+    }
+    Ok(Some(manifest))
+}
+
+/// Import all of the data in an archive using the given syncer.
+///
+/// The archive is verified before anything is imported (see [`verify_archive`]),
+/// and archives that were created without a manifest are only imported
+/// when `require_manifest` is false. The data is synced into the
+/// destination of the syncer, reporting progress to its reporter and
+/// stopping with an error if its cancellation token is cancelled.
+pub async fn import_archive<R>(
+    filename: impl AsRef<Path>,
+    syncer: &spfs::Syncer<'_, '_, R>,
+    require_manifest: bool,
+) -> Result<spfs::sync::SyncSummary>
+where
+    R: SyncReporter + 'static,
+{
+    let filename = filename.as_ref();
+    let tar_repo = spfs::storage::tar::TarRepository::open(filename).await?;
+    let tar_repo: spfs::storage::RepositoryHandle = tar_repo.into();
+    match verify_archive(&tar_repo).await? {
+        Some(manifest) => tracing::info!(
+            archive = ?filename,
+            packages = manifest.packages.len(),
+            objects = manifest.objects.len(),
+            payloads = manifest.payloads.len(),
+            "verified",
+        ),
+        None if require_manifest => {
+            return Err(Error::String(format!(
+                "Archive {filename:?} has no manifest and cannot be verified"
+            )));
+        }
+        None => tracing::warn!(
+            archive = ?filename,
+            "archive was created by an older version of spk and cannot be verified"
+        ),
+    }
+    let env_spec = tar_repo
+        .iter_tags()
+        .map_ok(|(spec, _)| spec)
+        .try_collect()
+        .await?;
+    tracing::info!(archive = ?filename, "importing");
+    let result = syncer
+        .clone_with_source(&tar_repo)
+        .sync_env(env_spec)
+        .await?;
+    Ok(result.summary())
+}
+
 pub async fn export_package<S>(pkg: impl AsRef<AnyIdent>, filename: impl AsRef<Path>) -> Result<()>
 where
     S: TagPathStrategy + Send + Sync,
 {
-    let pkg = pkg.as_ref();
+    export_package_with_reporter::<S, _, _>(
+        pkg,
+        filename,
+        spfs::sync::ConsoleSyncReporter::default,
+        &CancellationToken::new(),
+    )
+    .await
+}
+
+/// Export a package in the same way as [`export_package`], reporting
+/// the sync of each package build to a reporter created by `reporter`.
+///
+/// The export stops with an error once the given token is cancelled.
+pub async fn export_package_with_reporter<S, F, R>(
+    pkg: impl AsRef<AnyIdent>,
+    filename: impl AsRef<Path>,
+    reporter: F,
+    cancellation: &CancellationToken,
+) -> Result<()>
+where
+    S: TagPathStrategy + Send + Sync,
+    F: Fn() -> R + Send + Sync,
+    R: SyncReporter + 'static,
+{
+    export_packages_with_reporter::<S, _, _, _>(
+        [pkg.as_ref().clone()],
+        filename,
+        reporter,
+        cancellation,
+    )
+    .await
+}
+
+/// Export multiple packages into a single archive, eg: all of the
+/// packages in a solved environment.
+pub async fn export_packages<S, I>(pkgs: I, filename: impl AsRef<Path>) -> Result<()>
+where
+    S: TagPathStrategy + Send + Sync,
+    I: IntoIterator<Item = AnyIdent>,
+{
+    export_packages_with_reporter::<S, _, _, _>(
+        pkgs,
+        filename,
+        spfs::sync::ConsoleSyncReporter::default,
+        &CancellationToken::new(),
+    )
+    .await
+}
+
+/// Export multiple packages in the same way as [`export_packages`],
+/// reporting the sync of each package build to a reporter created by `reporter`.
+///
+/// The export stops with an error once the given token is cancelled.
+pub async fn export_packages_with_reporter<S, I, F, R>(
+    pkgs: I,
+    filename: impl AsRef<Path>,
+    reporter: F,
+    cancellation: &CancellationToken,
+) -> Result<()>
+where
+    S: TagPathStrategy + Send + Sync,
+    I: IntoIterator<Item = AnyIdent>,
+    F: Fn() -> R + Send + Sync,
+    R: SyncReporter + 'static,
+{
+    let pkgs: Vec<_> = pkgs.into_iter().collect();
     // Make filename absolute as spfs::runtime::makedirs_with_perms does not handle
     // relative paths properly.
     let filename = std::env::current_dir()
@@ -62,30 +376,32 @@ where
 
     // these are sorted to ensure that the recipe is published
     // before any build - it's only an error in testing, but still best practice
-    let mut to_transfer = std::collections::BTreeSet::new();
-    to_transfer.insert(pkg.clone());
-    if pkg.build().is_none() {
-        to_transfer.extend(
-            local_repo
-                .list_package_builds(pkg.as_version())
-                .await?
-                .into_iter()
-                .map(|pkg| pkg.into_any()),
-        );
-        if remote_repo.is_err() {
-            return remote_repo.map(|_| ());
+    let mut to_transfer = BTreeSet::new();
+    for pkg in pkgs.iter() {
+        to_transfer.insert(pkg.clone());
+        if pkg.build().is_none() {
+            to_transfer.extend(
+                local_repo
+                    .list_package_builds(pkg.as_version())
+                    .await?
+                    .into_iter()
+                    .map(|pkg| pkg.into_any()),
+            );
+            if remote_repo.is_err() {
+                return remote_repo.map(|_| ());
+            }
+            to_transfer.extend(
+                remote_repo
+                    .as_ref()
+                    .unwrap()
+                    .list_package_builds(pkg.as_version())
+                    .await?
+                    .into_iter()
+                    .map(|pkg| pkg.into_any()),
+            );
+        } else {
+            to_transfer.insert(pkg.with_build(None));
         }
-        to_transfer.extend(
-            remote_repo
-                .as_ref()
-                .unwrap()
-                .list_package_builds(pkg.as_version())
-                .await?
-                .into_iter()
-                .map(|pkg| pkg.into_any()),
-        );
-    } else {
-        to_transfer.insert(pkg.with_build(None));
     }
 
     for transfer_pkg in to_transfer.into_iter() {
@@ -113,7 +429,15 @@ where
             }
         }
 
-        let local_err = match copy_any(transfer_pkg.clone(), &local_repo, &target_repo).await {
+        let local_err = match copy_any(
+            transfer_pkg.clone(),
+            &local_repo,
+            &target_repo,
+            &reporter,
+            cancellation,
+        )
+        .await
+        {
             Ok(_) => continue,
             Err(Error::PackageNotFound(ident)) => {
                 if ident.build().is_some() {
@@ -131,6 +455,8 @@ where
             transfer_pkg.clone(),
             remote_repo.as_ref().unwrap(),
             &target_repo,
+            &reporter,
+            cancellation,
         )
         .await
         {
@@ -156,7 +482,7 @@ where
         // if only the "spec build" exists and that info could be used here.
         if matches!(local_err, CopyResult::BuildNotFound)
             && matches!(remote_err, CopyResult::BuildNotFound)
-            && pkg.build().is_none()
+            && !pkgs.contains(&transfer_pkg)
         {
             continue;
         }
@@ -172,24 +498,42 @@ where
     tracing::info!(path=?filename, "building archive");
     use std::ops::Deref;
     if let spfs::storage::RepositoryHandle::Tar(tar) = target_repo.deref() {
+        ArchiveManifest::collect(target_repo.deref(), pkgs)
+            .await?
+            .save(tar)?;
         tar.flush()?;
     }
     Ok(())
 }
 
-async fn copy_any<S1, S2>(
+fn archive_path(archive: &spfs::storage::tar::TarRepository) -> PathBuf {
+    archive.address().to_file_path().unwrap_or_default()
+}
+
+async fn copy_any<S1, S2, F, R>(
     pkg: AnyIdent,
     src_repo: &SpfsRepository<S1>,
     dst_repo: &SpfsRepository<S2>,
+    reporter: &F,
+    cancellation: &CancellationToken,
 ) -> Result<()>
 where
     S1: TagPathStrategy + Send + Sync,
     S2: TagPathStrategy + Send + Sync,
+    F: Fn() -> R + Send + Sync,
+    R: SyncReporter + 'static,
 {
     match pkg.into_inner() {
         (base, None) => copy_recipe(&base, src_repo, dst_repo).await,
         (base, Some(build)) => {
-            copy_package(&BuildIdent::new(base, build), src_repo, dst_repo).await
+            copy_package(
+                &BuildIdent::new(base, build),
+                src_repo,
+                dst_repo,
+                reporter(),
+                cancellation,
+            )
+            .await
         }
     }
 }
@@ -209,20 +553,24 @@ where
     Ok(())
 }
 
-async fn copy_package<S1, S2>(
+async fn copy_package<S1, S2, R>(
     pkg: &BuildIdent,
     src_repo: &SpfsRepository<S1>,
     dst_repo: &SpfsRepository<S2>,
+    reporter: R,
+    cancellation: &CancellationToken,
 ) -> Result<()>
 where
     S1: TagPathStrategy + Send + Sync,
     S2: TagPathStrategy + Send + Sync,
+    R: SyncReporter + 'static,
 {
     let spec = src_repo.read_package(pkg).await?;
     let components = src_repo.read_components(pkg).await?;
     tracing::info!(%pkg, "exporting");
     let syncer = spfs::Syncer::new(src_repo, dst_repo)
-        .with_reporter(spfs::sync::ConsoleSyncReporter::default());
+        .with_reporter(reporter)
+        .with_cancellation(cancellation.clone());
     let desired = components.iter().map(|i| *i.1).collect();
     syncer.sync_env(desired).await?;
     dst_repo.publish_package(&spec, &components).await?;
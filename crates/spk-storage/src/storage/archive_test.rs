@@ -0,0 +1,151 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::str::FromStr;
+
+use rstest::rstest;
+use spfs::operation::CancellationToken;
+use spfs::prelude::*;
+use spfs::storage::tar::TarRepository;
+use spk_schema::foundation::fixtures::*;
+use spk_schema::ident::parse_build_ident;
+
+use super::{import_archive, verify_archive, ArchiveManifest, ARCHIVE_FORMAT_VERSION};
+use crate::Error;
+
+/// Create an archive containing a single blob and a tag that
+/// points to it, returning the archive and a manifest of its contents.
+async fn archive_with_manifest(
+    tmpdir: &tempfile::TempDir,
+) -> (spfs::storage::RepositoryHandle, ArchiveManifest) {
+    let archive = TarRepository::create(tmpdir.path().join("archive.spk"))
+        .await
+        .unwrap();
+    let repo: spfs::storage::RepositoryHandle = archive.into();
+    let digest = repo
+        .commit_blob(Box::pin(b"some data".as_slice()))
+        .await
+        .unwrap();
+    repo.push_tag(&archive_tag(), &digest).await.unwrap();
+    let pkg = parse_build_ident("my-pkg/1.0.0/src").unwrap().into_any();
+    let manifest = ArchiveManifest::collect(&repo, vec![pkg]).await.unwrap();
+    (repo, manifest)
+}
+
+fn archive_tag() -> spfs::tracking::TagSpec {
+    spfs::tracking::TagSpec::from_str("spk/pkg/my-pkg/1.0.0/src").unwrap()
+}
+
+fn save(repo: &spfs::storage::RepositoryHandle, manifest: &ArchiveManifest) {
+    let spfs::storage::RepositoryHandle::Tar(archive) = repo else {
+        panic!("expected a tar repository");
+    };
+    manifest.save(archive).unwrap();
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_archive_without_manifest(tmpdir: tempfile::TempDir) {
+    let (repo, _) = archive_with_manifest(&tmpdir).await;
+
+    let res = verify_archive(&repo).await.unwrap();
+    assert!(
+        res.is_none(),
+        "archives without a manifest are not verified"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_archive_roundtrip(tmpdir: tempfile::TempDir) {
+    let (repo, manifest) = archive_with_manifest(&tmpdir).await;
+    assert!(!manifest.payloads.is_empty());
+    assert!(!manifest.tags.is_empty());
+    save(&repo, &manifest);
+
+    let verified = verify_archive(&repo).await.unwrap();
+    assert_eq!(verified, Some(manifest));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_archive_checksum_mismatch(tmpdir: tempfile::TempDir) {
+    let (repo, mut manifest) = archive_with_manifest(&tmpdir).await;
+    manifest.packages.clear();
+    save(&repo, &manifest);
+
+    let res = verify_archive(&repo).await;
+    assert!(
+        matches!(res, Err(Error::ArchiveVerificationFailed(..))),
+        "expected a checksum failure, got: {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_archive_newer_version(tmpdir: tempfile::TempDir) {
+    let (repo, mut manifest) = archive_with_manifest(&tmpdir).await;
+    manifest.version = ARCHIVE_FORMAT_VERSION + 1;
+    manifest.checksum = manifest.compute_checksum();
+    save(&repo, &manifest);
+
+    let res = verify_archive(&repo).await;
+    assert!(
+        matches!(res, Err(Error::UnsupportedArchiveVersion { .. })),
+        "expected an unsupported version, got: {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_archive_unlisted_data(tmpdir: tempfile::TempDir) {
+    let (repo, manifest) = archive_with_manifest(&tmpdir).await;
+    save(&repo, &manifest);
+    repo.commit_blob(Box::pin(b"some other data".as_slice()))
+        .await
+        .unwrap();
+
+    let res = verify_archive(&repo).await;
+    assert!(
+        matches!(res, Err(Error::ArchiveVerificationFailed(..))),
+        "expected unlisted data to fail verification, got: {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_verify_archive_moved_tag(tmpdir: tempfile::TempDir) {
+    let (repo, manifest) = archive_with_manifest(&tmpdir).await;
+    save(&repo, &manifest);
+    repo.push_tag(&archive_tag(), &spfs::encoding::EMPTY_DIGEST.into())
+        .await
+        .unwrap();
+
+    let res = verify_archive(&repo).await;
+    assert!(
+        matches!(res, Err(Error::ArchiveVerificationFailed(..))),
+        "expected a moved tag to fail verification, got: {res:?}"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_import_archive_cancelled(tmpdir: tempfile::TempDir) {
+    let (repo, _) = archive_with_manifest(&tmpdir).await;
+    drop(repo);
+    let dest: spfs::storage::RepositoryHandle =
+        spfs::storage::fs::FsRepository::create(tmpdir.path().join("dest"))
+            .await
+            .unwrap()
+            .into();
+
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    let syncer = spfs::Syncer::new(&dest, &dest).with_cancellation(cancellation);
+    let res = import_archive(tmpdir.path().join("archive.spk"), &syncer, false).await;
+    assert!(
+        matches!(res, Err(Error::SPFS(spfs::Error::OperationCancelled))),
+        "expected the import to be cancelled, got: {res:?}"
+    );
+}
@@ -0,0 +1,134 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use spk_schema::foundation::name::{PkgNameBuf, RepositoryName};
+use spk_schema::AnyIdent;
+
+use crate::Result;
+
+#[cfg(test)]
+#[path = "./audit_test.rs"]
+mod audit_test;
+
+/// A change to a repository that is recorded in its audit log
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    /// A recipe or build was published
+    Publish,
+    /// A recipe or build was removed
+    Remove,
+    /// A build was marked as deprecated
+    Deprecate,
+    /// A build was no longer marked as deprecated
+    Undeprecate,
+    /// A build was modified in some other way
+    Modify,
+    /// A recipe or build was restored to an earlier entry in its history
+    Rollback,
+    /// A recipe or build was promoted from another repository
+    Promote,
+}
+
+impl AuditAction {
+    /// The action that describes an update to an existing build,
+    /// given whether it was deprecated before, if it existed at all
+    pub fn for_update(was_deprecated: Option<bool>, is_deprecated: bool) -> Self {
+        match (was_deprecated, is_deprecated) {
+            (Some(false), true) => Self::Deprecate,
+            (Some(true), false) => Self::Undeprecate,
+            (None, _) => Self::Publish,
+            _ => Self::Modify,
+        }
+    }
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Publish => f.write_str("publish"),
+            Self::Remove => f.write_str("remove"),
+            Self::Deprecate => f.write_str("deprecate"),
+            Self::Undeprecate => f.write_str("undeprecate"),
+            Self::Modify => f.write_str("modify"),
+            Self::Rollback => f.write_str("rollback"),
+            Self::Promote => f.write_str("promote"),
+        }
+    }
+}
+
+/// A single change recorded in the audit log of a repository.
+///
+/// Like access rules, the user and host are taken from the spfs
+/// config of the client that made the change and are not verified.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// When the change was made
+    pub time: DateTime<Utc>,
+    /// The user that made the change
+    pub user: String,
+    /// The host that the change was made from
+    pub host: String,
+    /// What was done
+    pub action: AuditAction,
+    /// The recipe or build that was changed
+    pub package: AnyIdent,
+    /// The repository that a promoted recipe or build came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promoted_from: Option<String>,
+}
+
+impl AuditEntry {
+    /// Describe an action taken now by the current user on this host
+    pub fn new(action: AuditAction, package: AnyIdent) -> Result<Self> {
+        let config = spfs::get_config()?;
+        Ok(Self {
+            time: Utc::now(),
+            user: config.user.name.clone(),
+            host: config.user.domain.clone(),
+            action,
+            package,
+            promoted_from: None,
+        })
+    }
+
+    /// Describe the promotion of a recipe or build from the named repository
+    pub fn promoted(package: AnyIdent, from: &RepositoryName) -> Result<Self> {
+        let mut entry = Self::new(AuditAction::Promote, package)?;
+        entry.promoted_from = Some(from.to_string());
+        Ok(entry)
+    }
+}
+
+/// Selects the entries that are read from an audit log.
+///
+/// Entries are read newest first, and reading stops once
+/// the limit is reached.
+#[derive(Clone, Debug, Default)]
+pub struct AuditQuery {
+    /// Only read the changes to this package
+    pub package: Option<PkgNameBuf>,
+    /// Only read the changes made by this user
+    pub user: Option<String>,
+    /// Read at most this many entries
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// True if the given entry is selected by this query,
+    /// ignoring the limit.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.package
+            .as_ref()
+            .map_or(true, |pkg| pkg == entry.package.name())
+            && self.user.as_ref().map_or(true, |user| *user == entry.user)
+    }
+
+    /// True if the given number of entries satisfies the limit.
+    pub fn is_full(&self, count: usize) -> bool {
+        self.limit.is_some_and(|limit| count >= limit)
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::{recipe, spec, DeprecateMut, Package, Recipe};
+
+use super::{AuditAction, AuditQuery};
+use crate::fixtures::*;
+
+#[rstest]
+#[case::new(None, false, AuditAction::Publish)]
+#[case::deprecated(Some(false), true, AuditAction::Deprecate)]
+#[case::undeprecated(Some(true), false, AuditAction::Undeprecate)]
+#[case::unchanged(Some(false), false, AuditAction::Modify)]
+fn test_audit_action_for_update(
+    #[case] was_deprecated: Option<bool>,
+    #[case] is_deprecated: bool,
+    #[case] expected: AuditAction,
+) {
+    assert_eq!(
+        AuditAction::for_update(was_deprecated, is_deprecated),
+        expected
+    );
+}
+
+#[rstest]
+#[case::mem(RepoKind::Mem)]
+#[case::spfs(RepoKind::Spfs)]
+#[tokio::test]
+async fn test_repo_audit_log_records_changes(#[case] repo: RepoKind) {
+    let repo = make_repo(repo).await;
+    assert!(
+        repo.read_audit_log(&AuditQuery::default())
+            .await
+            .unwrap()
+            .is_empty(),
+        "a new repository should have an empty audit log"
+    );
+
+    let mut recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    repo.publish_recipe(&recipe).await.unwrap();
+    let mut package = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    repo.publish_package(
+        &package,
+        &vec![(Component::Run, empty_layer_digest())]
+            .into_iter()
+            .collect(),
+    )
+    .await
+    .unwrap();
+    package.deprecate().unwrap();
+    repo.update_package(&package).await.unwrap();
+    recipe.deprecate().unwrap();
+    repo.force_publish_recipe(&recipe).await.unwrap();
+    repo.remove_package(package.ident()).await.unwrap();
+    repo.remove_recipe(recipe.ident()).await.unwrap();
+
+    let log = repo.read_audit_log(&AuditQuery::default()).await.unwrap();
+    let actions: Vec<_> = log
+        .iter()
+        .map(|entry| (entry.action, entry.package.to_string()))
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            (AuditAction::Remove, "my-pkg/1.0.0".to_string()),
+            (AuditAction::Remove, "my-pkg/1.0.0/3I42H3S6".to_string()),
+            (AuditAction::Deprecate, "my-pkg/1.0.0".to_string()),
+            (AuditAction::Deprecate, "my-pkg/1.0.0/3I42H3S6".to_string()),
+            (AuditAction::Publish, "my-pkg/1.0.0/3I42H3S6".to_string()),
+            (AuditAction::Publish, "my-pkg/1.0.0".to_string()),
+        ],
+        "expected every change to be logged, newest first"
+    );
+
+    let user = spfs::get_config().unwrap().user.name.clone();
+    assert!(log.iter().all(|entry| entry.user == user));
+    assert!(
+        log.windows(2).all(|pair| pair[0].time >= pair[1].time),
+        "entries should be in order of when they were made"
+    );
+}
+
+#[rstest]
+#[case::mem(RepoKind::Mem)]
+#[case::spfs(RepoKind::Spfs)]
+#[tokio::test]
+async fn test_repo_audit_log_query(#[case] repo: RepoKind) {
+    let repo = make_repo(repo).await;
+    for pkg in [
+        "my-pkg/1.0.0",
+        "other-pkg/1.0.0",
+        "my-pkg/2.0.0",
+        "other-pkg/2.0.0",
+    ] {
+        repo.publish_recipe(&recipe!({ "pkg": pkg })).await.unwrap();
+    }
+
+    let read = |package: Option<&str>, limit: Option<usize>| {
+        let query = AuditQuery {
+            package: package.map(|name| name.parse().unwrap()),
+            user: None,
+            limit,
+        };
+        let repo = &repo;
+        async move {
+            repo.read_audit_log(&query)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.package.to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        read(None, None).await,
+        vec![
+            "other-pkg/2.0.0",
+            "my-pkg/2.0.0",
+            "other-pkg/1.0.0",
+            "my-pkg/1.0.0"
+        ],
+        "the logs of all packages should be merged, newest first"
+    );
+    assert_eq!(
+        read(None, Some(3)).await,
+        vec!["other-pkg/2.0.0", "my-pkg/2.0.0", "other-pkg/1.0.0"]
+    );
+    assert_eq!(
+        read(Some("my-pkg"), Some(1)).await,
+        vec!["my-pkg/2.0.0"],
+        "the limit should apply after filtering by package"
+    );
+    assert!(read(Some("missing-pkg"), None).await.is_empty());
+
+    let query = AuditQuery {
+        user: Some("someone-else".to_string()),
+        ..Default::default()
+    };
+    assert!(repo.read_audit_log(&query).await.unwrap().is_empty());
+}
@@ -0,0 +1,269 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::name::{PkgName, PkgNameBuf, RepositoryName, RepositoryNameBuf};
+use spk_schema::foundation::version::Version;
+use spk_schema::{AnyIdent, BuildIdent, Spec, SpecRecipe, VersionIdent};
+
+use super::repository::{PublishPolicy, Storage};
+use super::{
+    AccessControl,
+    AuditAction,
+    AuditEntry,
+    AuditQuery,
+    CachePolicy,
+    Repository,
+    RepositoryHandle,
+    Tombstone,
+};
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./chained_test.rs"]
+mod chained_test;
+
+/// A sequence of repositories that act as a single one.
+///
+/// Reads are served by the first repository in the chain that has
+/// the requested package, and listings include the contents of every
+/// repository. Changes are only ever written to the first repository,
+/// which is usually the local one.
+#[derive(Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChainedRepository {
+    address: url::Url,
+    name: RepositoryNameBuf,
+    repos: Vec<RepositoryHandle>,
+}
+
+impl ChainedRepository {
+    /// Create a chain that reads from the given repositories in order.
+    pub fn new(repos: Vec<RepositoryHandle>) -> Self {
+        let mut address = url::Url::parse("chained:")
+            .expect("[INTERNAL ERROR] static chained address should be a valid url");
+        address
+            .query_pairs_mut()
+            .extend_pairs(repos.iter().map(|r| ("repo", r.address().as_str())));
+        let name = std::iter::once("chained")
+            .chain(repos.iter().map(|r| r.name().as_str()))
+            .collect::<Vec<_>>()
+            .join("-")
+            .try_into()
+            .expect("joined repository names should be a valid repository name");
+        Self {
+            address,
+            name,
+            repos,
+        }
+    }
+
+    /// The repositories in this chain, in the order that they are read.
+    pub fn repos(&self) -> &[RepositoryHandle] {
+        &self.repos
+    }
+
+    /// Consume this chain, returning the repositories within it.
+    pub fn into_repos(self) -> Vec<RepositoryHandle> {
+        self.repos
+    }
+
+    /// Find the first repository in the chain that holds the given build.
+    pub async fn find_build_repository(
+        &self,
+        pkg: &BuildIdent,
+    ) -> Result<Option<&RepositoryHandle>> {
+        for repo in self.repos.iter() {
+            if repo
+                .list_package_builds(pkg.as_version())
+                .await?
+                .contains(pkg)
+            {
+                return Ok(Some(repo));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The repository that all changes are written to.
+    fn writable(&self) -> Result<&RepositoryHandle> {
+        self.repos.first().ok_or_else(|| {
+            Error::String("Cannot write to a chain that has no repositories".to_string())
+        })
+    }
+}
+
+/// Return the first successful read from the repositories in the chain,
+/// skipping over any that do not have the requested package.
+macro_rules! read_first_found {
+    ($repos:expr, $not_found:expr, |$repo:ident| $read:expr) => {{
+        let mut result = Err(Error::PackageNotFound($not_found));
+        for $repo in $repos.iter() {
+            match $read.await {
+                Err(err) if err.is_package_not_found() => continue,
+                other => {
+                    result = other;
+                    break;
+                }
+            }
+        }
+        result
+    }};
+}
+
+#[async_trait::async_trait]
+impl Storage for ChainedRepository {
+    type Recipe = SpecRecipe;
+    type Package = Spec;
+
+    async fn get_concrete_package_builds(&self, pkg: &VersionIdent) -> Result<HashSet<BuildIdent>> {
+        let mut builds = HashSet::new();
+        for repo in self.repos.iter() {
+            builds.extend(repo.get_concrete_package_builds(pkg).await?);
+        }
+        Ok(builds)
+    }
+
+    async fn get_embedded_package_builds(&self, pkg: &VersionIdent) -> Result<HashSet<BuildIdent>> {
+        let mut builds = HashSet::new();
+        for repo in self.repos.iter() {
+            builds.extend(repo.get_embedded_package_builds(pkg).await?);
+        }
+        Ok(builds)
+    }
+
+    async fn publish_embed_stub_to_storage(&self, spec: &Self::Package) -> Result<()> {
+        self.writable()?.publish_embed_stub_to_storage(spec).await
+    }
+
+    async fn publish_package_to_storage(
+        &self,
+        package: &<Self::Recipe as spk_schema::Recipe>::Output,
+        components: &HashMap<Component, spfs::encoding::Digest>,
+    ) -> Result<()> {
+        self.writable()?
+            .publish_package_to_storage(package, components)
+            .await
+    }
+
+    async fn publish_recipe_to_storage(
+        &self,
+        spec: &Self::Recipe,
+        publish_policy: PublishPolicy,
+    ) -> Result<()> {
+        self.writable()?
+            .publish_recipe_to_storage(spec, publish_policy)
+            .await
+    }
+
+    async fn read_components_from_storage(
+        &self,
+        pkg: &BuildIdent,
+    ) -> Result<HashMap<Component, spfs::encoding::Digest>> {
+        read_first_found!(self.repos, pkg.to_any(), |repo| repo
+            .read_components_from_storage(pkg))
+    }
+
+    async fn read_package_from_storage(
+        &self,
+        pkg: &BuildIdent,
+    ) -> Result<Arc<<Self::Recipe as spk_schema::Recipe>::Output>> {
+        read_first_found!(self.repos, pkg.to_any(), |repo| repo
+            .read_package_from_storage(pkg))
+    }
+
+    async fn remove_embed_stub_from_storage(&self, pkg: &BuildIdent) -> Result<()> {
+        self.writable()?.remove_embed_stub_from_storage(pkg).await
+    }
+
+    async fn remove_package_from_storage(&self, pkg: &BuildIdent) -> Result<()> {
+        self.writable()?.remove_package_from_storage(pkg).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Repository for ChainedRepository {
+    fn address(&self) -> &url::Url {
+        &self.address
+    }
+
+    async fn list_packages(&self) -> Result<Vec<PkgNameBuf>> {
+        let mut packages = BTreeSet::new();
+        for repo in self.repos.iter() {
+            packages.extend(repo.list_packages().await?);
+        }
+        Ok(packages.into_iter().collect())
+    }
+
+    async fn list_package_versions(&self, name: &PkgName) -> Result<Arc<Vec<Arc<Version>>>> {
+        let mut versions = BTreeSet::new();
+        for repo in self.repos.iter() {
+            versions.extend(repo.list_package_versions(name).await?.iter().cloned());
+        }
+        Ok(Arc::new(versions.into_iter().collect()))
+    }
+
+    async fn list_build_components(&self, pkg: &BuildIdent) -> Result<Vec<Component>> {
+        match self.find_build_repository(pkg).await? {
+            Some(repo) => repo.list_build_components(pkg).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn name(&self) -> &RepositoryName {
+        self.name.as_ref()
+    }
+
+    async fn read_embed_stub(&self, pkg: &BuildIdent) -> Result<Arc<Self::Package>> {
+        read_first_found!(self.repos, pkg.to_any(), |repo| repo.read_embed_stub(pkg))
+    }
+
+    async fn read_recipe(&self, pkg: &VersionIdent) -> Result<Arc<Self::Recipe>> {
+        read_first_found!(self.repos, pkg.to_any(None), |repo| repo.read_recipe(pkg))
+    }
+
+    async fn remove_recipe(&self, pkg: &VersionIdent) -> Result<()> {
+        self.writable()?.remove_recipe(pkg).await
+    }
+
+    async fn read_access_control(&self) -> Result<AccessControl> {
+        // access is only ever checked before making changes,
+        // which are always made to the first repository
+        self.writable()?.read_access_control().await
+    }
+
+    async fn record_audit_event(&self, action: AuditAction, pkg: &AnyIdent) {
+        match self.writable() {
+            Ok(repo) => repo.record_audit_event(action, pkg).await,
+            Err(err) => {
+                tracing::warn!("Failed to record {action} of {pkg} in the audit log: {err}")
+            }
+        }
+    }
+
+    async fn read_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        // only the first repository is ever changed through the chain
+        self.writable()?.read_audit_log(query).await
+    }
+
+    async fn write_tombstone(&self, tombstone: &Tombstone) -> Result<()> {
+        self.writable()?.write_tombstone(tombstone).await
+    }
+
+    async fn read_tombstone(&self, pkg: &BuildIdent) -> Result<Option<Tombstone>> {
+        self.writable()?.read_tombstone(pkg).await
+    }
+
+    fn set_cache_policy(&self, cache_policy: CachePolicy) -> CachePolicy {
+        let mut previous = None;
+        for repo in self.repos.iter() {
+            let old = repo.set_cache_policy(cache_policy);
+            previous.get_or_insert(old);
+        }
+        previous.unwrap_or(CachePolicy::BypassCache)
+    }
+}
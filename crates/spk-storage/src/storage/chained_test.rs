@@ -0,0 +1,106 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::pkg_name;
+use spk_schema::{recipe, spec, Package, Recipe};
+
+use super::ChainedRepository;
+use crate::fixtures::empty_layer_digest;
+use crate::{Repository, RepositoryHandle};
+
+fn run_component() -> std::collections::HashMap<Component, spfs::encoding::Digest> {
+    vec![(Component::Run, empty_layer_digest())]
+        .into_iter()
+        .collect()
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chained_reads_fall_through() {
+    let chain = ChainedRepository::new(vec![
+        RepositoryHandle::new_mem(),
+        RepositoryHandle::new_mem(),
+    ]);
+    let first = spec!({"pkg": "first/1.0.0/3I42H3S6"});
+    let second = spec!({"pkg": "second/1.0.0/3I42H3S6"});
+    chain.repos()[0]
+        .publish_package(&first, &run_component())
+        .await
+        .unwrap();
+    chain.repos()[1]
+        .publish_package(&second, &run_component())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        chain.list_packages().await.unwrap(),
+        vec![
+            pkg_name!("first").to_owned(),
+            pkg_name!("second").to_owned()
+        ],
+        "should list the packages of every repository"
+    );
+    let read = chain.read_package(second.ident()).await.unwrap();
+    assert_eq!(read.ident(), second.ident());
+    let found = chain.find_build_repository(second.ident()).await.unwrap();
+    assert_eq!(found, Some(&chain.repos()[1]));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chained_prefers_first_repository() {
+    let chain = ChainedRepository::new(vec![
+        RepositoryHandle::new_mem(),
+        RepositoryHandle::new_mem(),
+    ]);
+    let spec = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    for repo in chain.repos() {
+        repo.publish_package(&spec, &run_component()).await.unwrap();
+    }
+
+    let builds = chain
+        .list_package_builds(spec.ident().as_version())
+        .await
+        .unwrap();
+    assert_eq!(builds.len(), 1, "builds should not be duplicated");
+    let found = chain.find_build_repository(spec.ident()).await.unwrap();
+    assert_eq!(found, Some(&chain.repos()[0]));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chained_writes_to_first_repository() {
+    let chain = ChainedRepository::new(vec![
+        RepositoryHandle::new_mem(),
+        RepositoryHandle::new_mem(),
+    ]);
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    chain.publish_recipe(&recipe).await.unwrap();
+
+    chain.repos()[0]
+        .read_recipe(recipe.ident())
+        .await
+        .expect("recipe should be written to the first repository");
+    chain.repos()[1]
+        .read_recipe(recipe.ident())
+        .await
+        .expect_err("recipe should not be written to other repositories");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_chained_not_found() {
+    let chain = ChainedRepository::new(vec![
+        RepositoryHandle::new_mem(),
+        RepositoryHandle::new_mem(),
+    ]);
+    let spec = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    let err = chain
+        .read_package(spec.ident())
+        .await
+        .expect_err("package should not exist in any repository");
+    assert!(err.is_package_not_found());
+}
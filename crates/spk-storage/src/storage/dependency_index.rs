@@ -0,0 +1,294 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::name::{PkgName, PkgNameBuf};
+use spk_schema::foundation::version::Version;
+use spk_schema::foundation::version_range::{parse_version_range, Ranged};
+use spk_schema::{BuildIdent, Opt, Package, Request, VersionIdent};
+
+use super::Repository;
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./dependency_index_test.rs"]
+mod dependency_index_test;
+
+/// How a build depends on another package
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    /// The package was used to build it, as recorded in its build options
+    Build,
+    /// The package is required when it is installed
+    Runtime,
+}
+
+impl std::fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Build => f.write_str("build"),
+            Self::Runtime => f.write_str("runtime"),
+        }
+    }
+}
+
+/// A package that a build depends on
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Dependency {
+    /// The name of the package that is depended on
+    pub pkg: PkgNameBuf,
+    /// The versions of the package that satisfy the dependency, eg: `~3.7.3`.
+    /// Any version does if empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+    /// The components of the package that are used. The
+    /// default run component is used if none are listed.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub components: BTreeSet<Component>,
+    pub kind: DependencyKind,
+}
+
+impl Dependency {
+    /// True if the given version of the package satisfies this dependency
+    pub fn matches_version(&self, version: &Version) -> bool {
+        if self.version.is_empty() {
+            return true;
+        }
+        parse_version_range(&self.version)
+            .map(|range| range.is_applicable(version).is_ok())
+            .unwrap_or_default()
+    }
+
+    /// True if this dependency uses any of the given components
+    pub fn uses_any_component(&self, components: &BTreeSet<Component>) -> bool {
+        if components.contains(&Component::All) {
+            return true;
+        }
+        if self.components.is_empty() {
+            return components.contains(&Component::default_for_run());
+        }
+        self.components
+            .iter()
+            .any(|c| c == &Component::All || components.contains(c))
+    }
+}
+
+impl std::fmt::Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.pkg.as_str())?;
+        if !self.components.is_empty() {
+            let components = self.components.iter().map(Component::as_str);
+            write!(f, ":{{{}}}", components.collect::<Vec<_>>().join(","))?;
+        }
+        if !self.version.is_empty() {
+            write!(f, "/{}", self.version)?;
+        }
+        Ok(())
+    }
+}
+
+/// The packages that one build depends on
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DependencyEntry {
+    /// The build that is described
+    pub build: BuildIdent,
+    /// The packages that it was built against, or requires at runtime
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends: Vec<Dependency>,
+}
+
+impl DependencyEntry {
+    /// Describe the dependencies of a build from its package spec
+    pub fn from_package<P: Package>(package: &P) -> Self {
+        let mut depends = BTreeSet::new();
+        for opt in package.get_build_options() {
+            let Opt::Pkg(opt) = opt else {
+                continue;
+            };
+            depends.insert(Dependency {
+                pkg: opt.pkg.clone(),
+                version: opt.get_value(None).unwrap_or_default(),
+                components: (*opt.components).clone(),
+                kind: DependencyKind::Build,
+            });
+        }
+        for request in package.runtime_requirements().iter() {
+            let Request::Pkg(request) = request else {
+                continue;
+            };
+            depends.insert(Dependency {
+                pkg: request.pkg.name.clone(),
+                version: request.pkg.version.to_string(),
+                components: request.pkg.components.clone(),
+                kind: DependencyKind::Runtime,
+            });
+        }
+        Self {
+            build: package.ident().clone(),
+            depends: depends.into_iter().collect(),
+        }
+    }
+}
+
+/// An index of the packages that each build in a repository depends on.
+///
+/// The index is stored in the repository so that the builds which
+/// depend on a package can be found without reading every package
+/// spec. Like the [`super::SearchIndex`], it is updated as builds
+/// are published and removed once it has been created for a
+/// repository, see `spk repo index`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "Vec<DependencyEntry>", into = "Vec<DependencyEntry>")]
+pub struct DependencyIndex {
+    entries: BTreeMap<BuildIdent, DependencyEntry>,
+}
+
+impl From<Vec<DependencyEntry>> for DependencyIndex {
+    fn from(entries: Vec<DependencyEntry>) -> Self {
+        Self::from_iter(entries)
+    }
+}
+
+impl From<DependencyIndex> for Vec<DependencyEntry> {
+    fn from(index: DependencyIndex) -> Self {
+        index.entries.into_values().collect()
+    }
+}
+
+impl FromIterator<DependencyEntry> for DependencyIndex {
+    fn from_iter<I: IntoIterator<Item = DependencyEntry>>(iter: I) -> Self {
+        let mut index = Self::default();
+        for entry in iter {
+            index.insert(entry);
+        }
+        index
+    }
+}
+
+impl DependencyIndex {
+    /// Index the dependencies of every build in a repository.
+    ///
+    /// Source packages and embedded package stubs are not included,
+    /// as they do not record what they were built against.
+    pub async fn from_repository<R>(repo: &R) -> Result<Self>
+    where
+        R: Repository + ?Sized,
+    {
+        let mut index = Self::default();
+        for name in repo.list_packages().await? {
+            for version in repo.list_package_versions(&name).await?.iter() {
+                let pkg = VersionIdent::new(name.clone(), (**version).clone());
+                for build in repo.list_package_builds(&pkg).await? {
+                    if build.is_embedded() || build.is_source() {
+                        continue;
+                    }
+                    match repo.read_package(&build).await {
+                        Ok(package) => index.insert(DependencyEntry::from_package(&*package)),
+                        // the build may have been removed since it was listed
+                        Err(Error::PackageNotFound(_)) => continue,
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Add or replace the entry for a build
+    pub fn insert(&mut self, entry: DependencyEntry) {
+        self.entries.insert(entry.build.clone(), entry);
+    }
+
+    /// Remove the entry for a build, if there is one
+    pub fn remove(&mut self, build: &BuildIdent) -> Option<DependencyEntry> {
+        self.entries.remove(build)
+    }
+
+    /// The entry for a build, if it has been indexed
+    pub fn get(&self, build: &BuildIdent) -> Option<&DependencyEntry> {
+        self.entries.get(build)
+    }
+
+    /// Iterate all of the entries in this index, ordered by build
+    pub fn iter(&self) -> impl Iterator<Item = &DependencyEntry> {
+        self.entries.values()
+    }
+
+    /// The builds that depend on the named package, ordered by build.
+    ///
+    /// When a version is given, only the dependencies that it
+    /// satisfies are included.
+    pub fn dependents<'a>(
+        &'a self,
+        name: &'a PkgName,
+        version: Option<&'a Version>,
+    ) -> impl Iterator<Item = (&'a BuildIdent, &'a Dependency)> + 'a {
+        self.iter().flat_map(move |entry| {
+            entry
+                .depends
+                .iter()
+                .filter(move |dep| {
+                    dep.pkg == *name && version.map(|v| dep.matches_version(v)).unwrap_or(true)
+                })
+                .map(move |dep| (&entry.build, dep))
+        })
+    }
+
+    /// The dependencies that no build left in the repository would
+    /// satisfy once the given builds are removed, ordered by build.
+    ///
+    /// Only dependencies on a removed build are reported, and the
+    /// removed builds are never reported as dependents themselves, so
+    /// that a package can be removed along with everything using it.
+    pub fn broken_by_removal<'a>(
+        &'a self,
+        removed: &BTreeSet<BuildIdent>,
+    ) -> Vec<(&'a BuildIdent, &'a Dependency)> {
+        let mut removed_versions: BTreeMap<&PkgName, Vec<&Version>> = BTreeMap::new();
+        let mut remaining_versions: BTreeMap<&PkgName, Vec<&Version>> = BTreeMap::new();
+        for build in self.entries.keys() {
+            let versions = match removed.contains(build) {
+                true => &mut removed_versions,
+                false => &mut remaining_versions,
+            };
+            versions
+                .entry(build.name())
+                .or_default()
+                .push(build.version());
+        }
+
+        let mut broken = Vec::new();
+        for entry in self.iter() {
+            if removed.contains(&entry.build) {
+                continue;
+            }
+            for dep in entry.depends.iter() {
+                let matches = |versions: &BTreeMap<&PkgName, Vec<&Version>>| {
+                    versions
+                        .get(dep.pkg.as_ref())
+                        .map(|versions| versions.iter().any(|v| dep.matches_version(v)))
+                        .unwrap_or_default()
+                };
+                if matches(&removed_versions) && !matches(&remaining_versions) {
+                    broken.push((&entry.build, dep));
+                }
+            }
+        }
+        broken
+    }
+
+    /// The number of indexed builds
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if no builds have been indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeSet;
+
+use rstest::rstest;
+use spfs::prelude::*;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::name::PkgName;
+use spk_schema::foundation::version::parse_version;
+use spk_schema::{spec, Package};
+
+use super::{Dependency, DependencyEntry, DependencyIndex, DependencyKind};
+use crate::fixtures::*;
+use crate::RepositoryHandle;
+
+fn dependency(pkg: &str, version: &str, components: &[Component]) -> Dependency {
+    Dependency {
+        pkg: PkgName::new(pkg).unwrap().to_owned(),
+        version: version.to_string(),
+        components: components.iter().cloned().collect(),
+        kind: DependencyKind::Runtime,
+    }
+}
+
+#[rstest]
+fn test_entry_from_package() {
+    let package = spec!({
+        "pkg": "my-app/1.0.0/3I42H3S6",
+        "build": {"options": [{"pkg": "python", "static": "~3.7.3"}, {"var": "debug/off"}]},
+        "install": {"requirements": [{"pkg": "python:{run,dev}/=3.7.0"}]},
+    });
+    let entry = DependencyEntry::from_package(&package);
+    assert_eq!(&entry.build, package.ident());
+    let runtime = dependency(
+        "python",
+        "=3.7.0",
+        &[Component::Run, Component::Named("dev".to_string())],
+    );
+    let mut build = dependency("python", "~3.7.3", &[]);
+    build.kind = DependencyKind::Build;
+    assert_eq!(entry.depends, vec![runtime, build]);
+}
+
+#[rstest]
+#[case::any("", "1.2.3", true)]
+#[case::compatible("~1.2.0", "1.2.3", true)]
+#[case::incompatible("~1.2.0", "1.3.0", false)]
+#[case::invalid("not a version", "1.2.3", false)]
+fn test_dependency_matches_version(
+    #[case] range: &str,
+    #[case] version: &str,
+    #[case] expected: bool,
+) {
+    let dep = dependency("my-pkg", range, &[]);
+    assert_eq!(
+        dep.matches_version(&parse_version(version).unwrap()),
+        expected
+    );
+}
+
+#[rstest]
+#[case::default_run(&[], &[Component::Run], true)]
+#[case::default_not_build(&[], &[Component::Build], false)]
+#[case::named(&[Component::Build], &[Component::Build], true)]
+#[case::other(&[Component::Build], &[Component::Run], false)]
+#[case::all_used(&[Component::All], &[Component::Build], true)]
+#[case::all_requested(&[Component::Build], &[Component::All], true)]
+fn test_dependency_uses_any_component(
+    #[case] used: &[Component],
+    #[case] requested: &[Component],
+    #[case] expected: bool,
+) {
+    let dep = dependency("my-pkg", "", used);
+    let requested = requested.iter().cloned().collect::<BTreeSet<_>>();
+    assert_eq!(dep.uses_any_component(&requested), expected);
+}
+
+#[rstest]
+fn test_index_dependents() {
+    let index: DependencyIndex = [
+        spec!({"pkg": "app-a/1.0.0/3I42H3S6", "install": {"requirements": [{"pkg": "lib/~1.0"}]}}),
+        spec!({"pkg": "app-b/1.0.0/3I42H3S6", "install": {"requirements": [{"pkg": "lib/~2.0"}]}}),
+        spec!({"pkg": "other/1.0.0/3I42H3S6"}),
+    ]
+    .iter()
+    .map(DependencyEntry::from_package)
+    .collect();
+
+    let names = |version: Option<&str>| {
+        let version = version.map(|v| parse_version(v).unwrap());
+        index
+            .dependents(PkgName::new("lib").unwrap(), version.as_ref())
+            .map(|(build, _)| build.name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(None), vec!["app-a", "app-b"]);
+    assert_eq!(names(Some("1.0.5")), vec!["app-a"]);
+    assert!(names(Some("3.0.0")).is_empty());
+
+    let yaml = serde_yaml::to_string(&index).unwrap();
+    let parsed: DependencyIndex = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed, index, "should survive a round-trip encoding");
+}
+
+#[rstest]
+fn test_index_broken_by_removal() {
+    let lib_1 = spec!({"pkg": "lib/1.0.0/3I42H3S6"});
+    let lib_1_other = spec!({"pkg": "lib/1.0.0/BGSHW3CN"});
+    let lib_2 = spec!({"pkg": "lib/2.0.0/3I42H3S6"});
+    let app =
+        spec!({"pkg": "app/1.0.0/3I42H3S6", "install": {"requirements": [{"pkg": "lib/~1.0"}]}});
+    let index: DependencyIndex = [&lib_1, &lib_1_other, &lib_2, &app]
+        .into_iter()
+        .map(DependencyEntry::from_package)
+        .collect();
+
+    let broken = |removed: &[&spk_schema::Spec]| {
+        let removed = removed
+            .iter()
+            .map(|spec| spec.ident().clone())
+            .collect::<BTreeSet<_>>();
+        index
+            .broken_by_removal(&removed)
+            .into_iter()
+            .map(|(build, _)| build.name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert!(
+        broken(&[&lib_1]).is_empty(),
+        "another build of lib/1.0.0 is left"
+    );
+    assert!(broken(&[&lib_2]).is_empty(), "app does not use lib/2.0.0");
+    assert_eq!(broken(&[&lib_1, &lib_1_other]), vec!["app"]);
+    assert!(
+        broken(&[&lib_1, &lib_1_other, &app]).is_empty(),
+        "removing the dependent too breaks nothing"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_spfs_dependency_index_follows_builds() {
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+    let components = vec![(Component::Run, empty_layer_digest())]
+        .into_iter()
+        .collect();
+    let lib = spec!({"pkg": "lib/1.0.0/3I42H3S6"});
+    repo.publish_package(&lib, &components).await.unwrap();
+    assert!(
+        repo.read_dependency_index().await.unwrap().is_none(),
+        "the index should not exist until it is built"
+    );
+
+    let index = spfs_repo.build_dependency_index().await.unwrap();
+    assert_eq!(index.len(), 1);
+
+    let app = spec!({
+        "pkg": "app/1.0.0/3I42H3S6",
+        "install": {"requirements": [{"pkg": "lib/1.0"}]},
+    });
+    repo.publish_package(&app, &components).await.unwrap();
+    let index = repo.read_dependency_index().await.unwrap().unwrap();
+    assert_eq!(index.len(), 2, "published builds should be indexed");
+    assert_eq!(
+        index
+            .dependents(lib.ident().name(), Some(lib.ident().version()))
+            .count(),
+        1
+    );
+
+    let app_tag = spfs::tracking::TagSpec::parse("spk/index/depends/app").unwrap();
+    let inner: &spfs::storage::RepositoryHandle = spfs_repo;
+    assert!(
+        inner.has_tag(&app_tag).await,
+        "each package should have its own index entries"
+    );
+
+    repo.remove_package(app.ident()).await.unwrap();
+    let index = repo.read_dependency_index().await.unwrap().unwrap();
+    assert!(
+        index.get(app.ident()).is_none(),
+        "removed builds should be dropped from the index"
+    );
+}
@@ -0,0 +1,246 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+use spfs::encoding::Digest;
+use spfs::prelude::*;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::name::RepositoryNameBuf;
+use spk_schema::foundation::option_map::OptionMap;
+use spk_schema::ident::RangeIdent;
+use spk_schema::{
+    BuildIdent,
+    Deprecate,
+    Package,
+    RequirementsList,
+    Spec,
+    SpecRecipe,
+    VersionIdent,
+};
+
+use super::{Repository, RepositoryHandle};
+use crate::{Error, Result};
+
+#[cfg(test)]
+#[path = "./details_test.rs"]
+mod details_test;
+
+/// The files that make up a single component of a package build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentDetails {
+    /// The spfs layer that holds the component's files
+    pub layer: Digest,
+    /// The number of regular files in the component, if known
+    pub file_count: Option<usize>,
+    /// The total size of the component's files in bytes, if known
+    pub total_size: Option<u64>,
+}
+
+/// Everything that a repository knows about one package build.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildDetails {
+    /// The repository that the build was read from
+    pub repository: RepositoryNameBuf,
+    /// The published build spec
+    pub spec: Arc<Spec>,
+    /// True if the build has been deprecated
+    pub deprecated: bool,
+    /// The packages that have been suggested in place of this one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replaced_by: Vec<RangeIdent>,
+    /// The option values that the build was made with
+    pub build_options: OptionMap,
+    /// The requests that are added to an environment with this build
+    pub runtime_requirements: RequirementsList,
+    /// The payload of each published component
+    pub components: BTreeMap<Component, ComponentDetails>,
+}
+
+/// The deprecation state of one build of a package version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildSummary {
+    /// The identifier of the build
+    pub ident: BuildIdent,
+    /// True if the build has been deprecated
+    pub deprecated: bool,
+}
+
+/// Everything that a repository knows about one package version.
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionDetails {
+    /// The repository that the version was read from
+    pub repository: RepositoryNameBuf,
+    /// The published version recipe
+    pub recipe: Arc<SpecRecipe>,
+    /// True if the version has been deprecated
+    pub deprecated: bool,
+    /// The packages that have been suggested in place of this one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replaced_by: Vec<RangeIdent>,
+    /// All of the builds published for this version
+    pub builds: Vec<BuildSummary>,
+}
+
+/// Collect the spec, options, requirements and component
+/// payload statistics of a package build in one repository.
+///
+/// File counts and sizes are only available for repositories
+/// that are backed by spfs, and are left empty otherwise.
+pub async fn describe_build(repo: &RepositoryHandle, ident: &BuildIdent) -> Result<BuildDetails> {
+    let (spec, components) =
+        tokio::try_join!(repo.read_package(ident), repo.read_components(ident))?;
+
+    let local;
+    let spfs_repo: Option<&spfs::storage::RepositoryHandle> = match repo {
+        RepositoryHandle::SPFS(repo) => Some(repo),
+        RepositoryHandle::SPFSWithVerbatimTags(repo) => Some(repo),
+        RepositoryHandle::Runtime(_) => {
+            local = spfs::get_config()?.get_local_repository_handle().await?;
+            Some(&local)
+        }
+        RepositoryHandle::Mem(_) => None,
+        RepositoryHandle::Chained(chain) => {
+            // describe the build using the repository that actually holds it
+            let repo = chain
+                .find_build_repository(ident)
+                .await?
+                .ok_or_else(|| Error::PackageNotFound(ident.to_any()))?;
+            return Box::pin(describe_build(repo, ident)).await;
+        }
+    };
+
+    let mut details = BTreeMap::new();
+    for (component, layer) in components {
+        let stats = match spfs_repo {
+            Some(spfs_repo) => Some(layer_file_stats(spfs_repo, layer).await?),
+            None => None,
+        };
+        details.insert(
+            component,
+            ComponentDetails {
+                layer,
+                file_count: stats.map(|(count, _)| count),
+                total_size: stats.map(|(_, size)| size),
+            },
+        );
+    }
+
+    Ok(BuildDetails {
+        repository: repo.name().to_owned(),
+        deprecated: spec.is_deprecated(),
+        replaced_by: spec.replaced_by().to_vec(),
+        build_options: spec.option_values(),
+        runtime_requirements: spec.runtime_requirements().into_owned(),
+        components: details,
+        spec,
+    })
+}
+
+/// Collect the recipe and builds of a package version in one repository.
+pub async fn describe_version(
+    repo: &RepositoryHandle,
+    ident: &VersionIdent,
+) -> Result<VersionDetails> {
+    let recipe = repo.read_recipe(ident).await?;
+    let mut builds = Vec::new();
+    for build in repo.list_package_builds(ident).await? {
+        let deprecated = repo.read_package(&build).await?.is_deprecated();
+        builds.push(BuildSummary {
+            ident: build,
+            deprecated,
+        });
+    }
+
+    Ok(VersionDetails {
+        repository: repo.name().to_owned(),
+        deprecated: recipe.is_deprecated(),
+        replaced_by: recipe.replaced_by().to_vec(),
+        recipe,
+        builds,
+    })
+}
+
+/// Merge the files of a package build's components into one manifest.
+///
+/// Only the given components are included, or all of them when none
+/// are given. Package files can only be read from repositories that
+/// are backed by spfs.
+pub async fn build_manifest(
+    repo: &RepositoryHandle,
+    ident: &BuildIdent,
+    components: &[Component],
+) -> Result<spfs::tracking::Manifest> {
+    let local;
+    let spfs_repo: &spfs::storage::RepositoryHandle = match repo {
+        RepositoryHandle::SPFS(repo) => repo,
+        RepositoryHandle::SPFSWithVerbatimTags(repo) => repo,
+        RepositoryHandle::Runtime(_) => {
+            local = spfs::get_config()?.get_local_repository_handle().await?;
+            &local
+        }
+        RepositoryHandle::Mem(_) => {
+            return Err(Error::String(format!(
+                "Cannot read the files of {ident}, repository {} is not backed by spfs",
+                repo.name()
+            )));
+        }
+        RepositoryHandle::Chained(chain) => {
+            let repo = chain
+                .find_build_repository(ident)
+                .await?
+                .ok_or_else(|| Error::PackageNotFound(ident.to_any()))?;
+            return Box::pin(build_manifest(repo, ident, components)).await;
+        }
+    };
+
+    let published = repo.read_components(ident).await?;
+    let mut layers = Vec::new();
+    if components.is_empty() {
+        // sorted so that the result is the same for overlapping components
+        let mut all: Vec<_> = published.iter().collect();
+        all.sort_by_key(|(component, _)| *component);
+        layers.extend(all.into_iter().map(|(_, layer)| *layer));
+    } else {
+        for component in components {
+            let Some(layer) = published.get(component) else {
+                return Err(Error::String(format!(
+                    "{ident} does not have a {component} component"
+                )));
+            };
+            layers.push(*layer);
+        }
+    }
+
+    let mut manifest = spfs::tracking::Manifest::default();
+    for layer in layers {
+        let layer = spfs_repo.read_layer(layer).await?;
+        let Some(digest) = layer.manifest() else {
+            continue;
+        };
+        let layer_manifest = spfs_repo.read_manifest(*digest).await?;
+        manifest.update(&layer_manifest.to_tracking_manifest());
+    }
+    Ok(manifest)
+}
+
+/// Count the regular files in a layer, and their total size.
+async fn layer_file_stats(
+    repo: &spfs::storage::RepositoryHandle,
+    layer: Digest,
+) -> Result<(usize, u64)> {
+    let layer = repo.read_layer(layer).await?;
+    let Some(manifest) = layer.manifest() else {
+        return Ok((0, 0));
+    };
+    let manifest = repo.read_manifest(*manifest).await?.to_tracking_manifest();
+    Ok(manifest
+        .walk()
+        .filter(|node| node.entry.is_regular_file())
+        .fold((0, 0), |(count, size), node| {
+            (count + 1, size + node.entry.size())
+        }))
+}
@@ -0,0 +1,155 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spfs::prelude::*;
+use spk_schema::foundation::fixtures::*;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::foundation::opt_name;
+use spk_schema::{recipe, spec, DeprecateMut, Package};
+
+use super::{build_manifest, describe_build, describe_version};
+use crate::fixtures::*;
+use crate::RepositoryHandle;
+
+#[rstest]
+#[tokio::test]
+async fn test_describe_build_mem() {
+    let repo = make_repo(RepoKind::Mem).await;
+    let spec = spec!({
+        "pkg": "my-pkg/1.0.0/3I42H3S6",
+        "build": {"options": [{"var": "debug/on"}]},
+        "install": {"requirements": [{"pkg": "dep/1"}]},
+    });
+    repo.publish_package(
+        &spec,
+        &vec![(Component::Run, empty_layer_digest())]
+            .into_iter()
+            .collect(),
+    )
+    .await
+    .unwrap();
+
+    let details = describe_build(&repo, spec.ident()).await.unwrap();
+    assert!(!details.deprecated);
+    assert_eq!(details.runtime_requirements.len(), 1);
+    let run = &details.components[&Component::Run];
+    assert_eq!(run.layer, empty_layer_digest());
+    assert_eq!(
+        run.file_count, None,
+        "memory repositories have no payloads to count"
+    );
+    assert_eq!(details.build_options.get(opt_name!("debug")).unwrap(), "on");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_describe_build_file_stats(tmpdir: tempfile::TempDir) {
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+    std::fs::create_dir_all(tmpdir.path().join("bin")).unwrap();
+    std::fs::write(tmpdir.path().join("bin/tool"), b"12345").unwrap();
+    std::fs::write(tmpdir.path().join("README"), b"123").unwrap();
+    let manifest = spfs::Committer::new(spfs_repo)
+        .commit_dir(tmpdir.path())
+        .await
+        .unwrap();
+    let layer = spfs_repo
+        .create_layer_from_manifest(&manifest)
+        .await
+        .unwrap();
+
+    let spec = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    repo.publish_package(
+        &spec,
+        &vec![(Component::Run, layer.digest().unwrap())]
+            .into_iter()
+            .collect(),
+    )
+    .await
+    .unwrap();
+
+    let details = describe_build(&repo, spec.ident()).await.unwrap();
+    let run = &details.components[&Component::Run];
+    assert_eq!(run.file_count, Some(2));
+    assert_eq!(run.total_size, Some(8));
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_describe_version_deprecation() {
+    let repo = make_repo(RepoKind::Mem).await;
+    let recipe = recipe!({"pkg": "my-pkg/1.0.0"});
+    repo.publish_recipe(&recipe).await.unwrap();
+    let mut spec = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    spec.deprecate().unwrap();
+    repo.publish_package(
+        &spec,
+        &vec![(Component::Run, empty_layer_digest())]
+            .into_iter()
+            .collect(),
+    )
+    .await
+    .unwrap();
+
+    let details = describe_version(&repo, recipe.ident()).await.unwrap();
+    assert!(!details.deprecated);
+    assert_eq!(details.builds.len(), 1);
+    assert_eq!(&details.builds[0].ident, spec.ident());
+    assert!(details.builds[0].deprecated);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_build_manifest_components(tmpdir: tempfile::TempDir) {
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+    let mut layers = Vec::new();
+    for name in ["bin/tool", "lib/libtool.so"] {
+        let root = tmpdir.path().join(name.replace('/', "-"));
+        let path = root.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, name).unwrap();
+        let manifest = spfs::Committer::new(spfs_repo)
+            .commit_dir(&root)
+            .await
+            .unwrap();
+        let layer = spfs_repo
+            .create_layer_from_manifest(&manifest)
+            .await
+            .unwrap();
+        layers.push(layer.digest().unwrap());
+    }
+
+    let spec = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    repo.publish_package(
+        &spec,
+        &vec![(Component::Run, layers[0]), (Component::Build, layers[1])]
+            .into_iter()
+            .collect(),
+    )
+    .await
+    .unwrap();
+
+    let all = build_manifest(&repo, spec.ident(), &[]).await.unwrap();
+    assert!(all.get_path("bin/tool").is_some());
+    assert!(all.get_path("lib/libtool.so").is_some());
+
+    let run = build_manifest(&repo, spec.ident(), &[Component::Run])
+        .await
+        .unwrap();
+    assert!(run.get_path("bin/tool").is_some());
+    assert!(
+        run.get_path("lib/libtool.so").is_none(),
+        "only the requested components should be included"
+    );
+
+    build_manifest(&repo, spec.ident(), &[Component::Source])
+        .await
+        .expect_err("a missing component should be an error");
+}
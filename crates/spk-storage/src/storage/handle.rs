@@ -3,9 +3,10 @@
 // https://github.com/spkenv/spk
 
 use spk_schema::ident_ops::{NormalizedTagStrategy, VerbatimTagStrategy};
-use spk_schema::{Spec, SpecRecipe};
+use spk_schema::{BuildIdent, Spec, SpecRecipe};
 
 use super::Repository;
+use crate::Result;
 
 type Handle = dyn Repository<Recipe = SpecRecipe, Package = Spec>;
 
@@ -16,6 +17,7 @@ pub enum RepositoryHandle {
     SPFSWithVerbatimTags(super::SpfsRepository<VerbatimTagStrategy>),
     Mem(super::MemRepository<SpecRecipe>),
     Runtime(super::RuntimeRepository),
+    Chained(super::ChainedRepository),
 }
 
 impl RepositoryHandle {
@@ -32,6 +34,12 @@ impl RepositoryHandle {
         Self::Runtime(Default::default())
     }
 
+    /// Create a repository handle that reads from each of the given
+    /// repositories in order, and writes only to the first one
+    pub fn new_chained(repos: Vec<RepositoryHandle>) -> Self {
+        Self::Chained(super::ChainedRepository::new(repos))
+    }
+
     pub fn is_spfs(&self) -> bool {
         matches!(self, Self::SPFS(_) | Self::SPFSWithVerbatimTags(_))
     }
@@ -44,12 +52,37 @@ impl RepositoryHandle {
         matches!(self, Self::Runtime(_))
     }
 
+    pub fn is_chained(&self) -> bool {
+        matches!(self, Self::Chained(_))
+    }
+
+    /// The spfs repository that holds the layers of the given build.
+    ///
+    /// Chained repositories defer to the repository in the chain that
+    /// holds the build. None is returned for repositories that are not
+    /// backed by spfs, or a chain where no repository has the build.
+    pub async fn spfs_repository_for(
+        &self,
+        pkg: &BuildIdent,
+    ) -> Result<Option<&spfs::storage::RepositoryHandle>> {
+        match self {
+            Self::SPFS(repo) => Ok(Some(&**repo)),
+            Self::SPFSWithVerbatimTags(repo) => Ok(Some(&**repo)),
+            Self::Mem(_) | Self::Runtime(_) => Ok(None),
+            Self::Chained(chain) => match chain.find_build_repository(pkg).await? {
+                Some(repo) => Box::pin(repo.spfs_repository_for(pkg)).await,
+                None => Ok(None),
+            },
+        }
+    }
+
     pub fn to_repo(self) -> Box<Handle> {
         match self {
             Self::SPFS(repo) => Box::new(repo),
             Self::SPFSWithVerbatimTags(repo) => Box::new(repo),
             Self::Mem(repo) => Box::new(repo),
             Self::Runtime(repo) => Box::new(repo),
+            Self::Chained(repo) => Box::new(repo),
         }
     }
 }
@@ -63,6 +96,7 @@ impl std::ops::Deref for RepositoryHandle {
             RepositoryHandle::SPFSWithVerbatimTags(repo) => repo,
             RepositoryHandle::Mem(repo) => repo,
             RepositoryHandle::Runtime(repo) => repo,
+            RepositoryHandle::Chained(repo) => repo,
         }
     }
 }
@@ -74,6 +108,7 @@ impl std::ops::DerefMut for RepositoryHandle {
             RepositoryHandle::SPFSWithVerbatimTags(repo) => repo,
             RepositoryHandle::Mem(repo) => repo,
             RepositoryHandle::Runtime(repo) => repo,
+            RepositoryHandle::Chained(repo) => repo,
         }
     }
 }
@@ -101,3 +136,9 @@ impl From<super::RuntimeRepository> for RepositoryHandle {
         RepositoryHandle::Runtime(repo)
     }
 }
+
+impl From<super::ChainedRepository> for RepositoryHandle {
+    fn from(repo: super::ChainedRepository) -> Self {
+        RepositoryHandle::Chained(repo)
+    }
+}
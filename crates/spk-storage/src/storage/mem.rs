@@ -10,11 +10,11 @@ use spk_schema::foundation::ident_build::Build;
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::name::{PkgName, PkgNameBuf, RepositoryName, RepositoryNameBuf};
 use spk_schema::foundation::version::Version;
-use spk_schema::{BuildIdent, Spec, SpecRecipe, VersionIdent};
+use spk_schema::{AnyIdent, BuildIdent, Spec, SpecRecipe, VersionIdent};
 use tokio::sync::RwLock;
 
 use super::repository::{PublishPolicy, Storage};
-use super::Repository;
+use super::{AuditAction, AuditEntry, AuditQuery, Repository, Tombstone};
 use crate::{Error, Result};
 
 type ComponentMap = HashMap<Component, spfs::encoding::Digest>;
@@ -34,6 +34,8 @@ where
     specs: Arc<RwLock<PackageMap<Arc<Recipe>>>>,
     packages: Arc<RwLock<PackageMap<BuildMap<Recipe::Output>>>>,
     embedded_stubs: Arc<RwLock<PackageMap<StubMap<Package>>>>,
+    audit_log: Arc<RwLock<Vec<AuditEntry>>>,
+    tombstones: Arc<RwLock<HashMap<BuildIdent, Tombstone>>>,
     _marker: std::marker::PhantomData<Package>,
 }
 
@@ -56,6 +58,8 @@ where
             specs,
             packages: Arc::default(),
             embedded_stubs: Arc::default(),
+            audit_log: Arc::default(),
+            tombstones: Arc::default(),
             _marker: std::marker::PhantomData,
         }
     }
@@ -363,6 +367,43 @@ where
             }
             None => return Err(Error::PackageNotFound(pkg.to_any(None))),
         };
+        drop(specs);
+        self.record_audit_event(AuditAction::Remove, &pkg.to_any(None))
+            .await;
         Ok(())
     }
+
+    async fn record_audit_event(&self, action: AuditAction, pkg: &AnyIdent) {
+        match AuditEntry::new(action, pkg.clone()) {
+            Ok(entry) => self.audit_log.write().await.push(entry),
+            Err(err) => {
+                tracing::warn!("Failed to record {action} of {pkg} in the audit log: {err}")
+            }
+        }
+    }
+
+    async fn read_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        Ok(self
+            .audit_log
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect())
+    }
+
+    async fn write_tombstone(&self, tombstone: &Tombstone) -> Result<()> {
+        self.tombstones
+            .write()
+            .await
+            .insert(tombstone.package.clone(), tombstone.clone());
+        Ok(())
+    }
+
+    async fn read_tombstone(&self, pkg: &BuildIdent) -> Result<Option<Tombstone>> {
+        Ok(self.tombstones.read().await.get(pkg).cloned())
+    }
 }
@@ -2,18 +2,54 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+mod access;
 mod archive;
+mod audit;
+mod chained;
+mod dependency_index;
+mod details;
 mod handle;
 mod mem;
+mod path_index;
 mod repository;
 mod runtime;
+mod search_index;
 mod spfs;
+mod tombstone;
 
-pub use archive::export_package;
+#[cfg(feature = "server")]
+pub use access::AccessPolicy;
+pub use access::{AccessAction, AccessControl, AccessRule, PackagePattern};
+pub use archive::{
+    export_package,
+    export_package_with_reporter,
+    export_packages,
+    export_packages_with_reporter,
+    import_archive,
+    verify_archive,
+    ArchiveManifest,
+    ARCHIVE_FORMAT_VERSION,
+    ARCHIVE_MANIFEST_FILE,
+};
+pub use audit::{AuditAction, AuditEntry, AuditQuery};
+pub use chained::ChainedRepository;
+pub use dependency_index::{Dependency, DependencyEntry, DependencyIndex, DependencyKind};
+pub use details::{
+    build_manifest,
+    describe_build,
+    describe_version,
+    BuildDetails,
+    BuildSummary,
+    ComponentDetails,
+    VersionDetails,
+};
 pub use handle::RepositoryHandle;
 pub use mem::MemRepository;
+pub use path_index::{PathIndex, PathOwner};
 pub use repository::{CachePolicy, Repository, Storage};
 pub use runtime::{find_path_providers, pretty_print_filepath, RuntimeRepository};
+pub use search_index::{SearchEntry, SearchIndex, SearchQuery};
+pub use tombstone::Tombstone;
 
 pub use self::spfs::{
     local_repository,
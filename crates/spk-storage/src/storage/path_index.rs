@@ -0,0 +1,111 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeMap;
+
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::Serialize;
+use spfs::encoding::Digest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::BuildIdent;
+
+use super::{build_manifest, Repository, RepositoryHandle};
+use crate::Result;
+
+#[cfg(test)]
+#[path = "./path_index_test.rs"]
+mod path_index_test;
+
+/// A component of a package build that provides a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PathOwner {
+    /// The package build that provides the file
+    pub pkg: BuildIdent,
+    /// The component of the package that the file was collected into
+    pub component: Component,
+    /// The digest of the file's contents
+    pub digest: Digest,
+}
+
+/// An index of the files in a set of package components, used to
+/// find the packages that provide a given path.
+///
+/// Only files and symlinks are indexed, since directories are
+/// typically shared by many packages.
+#[derive(Debug, Clone, Default)]
+pub struct PathIndex {
+    owners: BTreeMap<RelativePathBuf, Vec<PathOwner>>,
+}
+
+impl PathIndex {
+    /// Record that the given package component provides a path
+    pub fn insert<P: AsRef<RelativePath>>(&mut self, path: P, owner: PathOwner) {
+        let owners = self.owners.entry(path.as_ref().normalize()).or_default();
+        if !owners.contains(&owner) {
+            owners.push(owner);
+        }
+    }
+
+    /// Index every file in the manifest of one package component
+    pub fn insert_manifest<T>(
+        &mut self,
+        pkg: &BuildIdent,
+        component: &Component,
+        manifest: &spfs::tracking::Manifest<T>,
+    ) {
+        for node in manifest.walk() {
+            if !node.entry.kind.is_blob() {
+                continue;
+            }
+            self.insert(
+                &node.path,
+                PathOwner {
+                    pkg: pkg.clone(),
+                    component: component.clone(),
+                    digest: node.entry.object,
+                },
+            );
+        }
+    }
+
+    /// Index every file in the published components of a package build.
+    ///
+    /// Package files can only be read from repositories that
+    /// are backed by spfs.
+    pub async fn insert_build(&mut self, repo: &RepositoryHandle, pkg: &BuildIdent) -> Result<()> {
+        let mut components: Vec<_> = repo.read_components(pkg).await?.into_keys().collect();
+        components.sort();
+        for component in components {
+            let manifest = build_manifest(repo, pkg, std::slice::from_ref(&component)).await?;
+            self.insert_manifest(pkg, &component, &manifest);
+        }
+        Ok(())
+    }
+
+    /// The components that provide the given path, in the order that they were indexed.
+    ///
+    /// The path can be given either relative to or including
+    /// the leading /spfs directory.
+    pub fn owners_of(&self, path: &str) -> &[PathOwner] {
+        let path = match path.strip_prefix("/spfs") {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            _ => path,
+        };
+        let path = RelativePath::new(path.trim_start_matches('/')).normalize();
+        self.owners
+            .get(&path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The number of indexed paths
+    pub fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    /// True if no paths have been indexed
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+}
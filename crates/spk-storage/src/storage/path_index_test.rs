@@ -0,0 +1,86 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spfs::prelude::*;
+use spk_schema::foundation::fixtures::*;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::{spec, Package};
+
+use super::PathIndex;
+use crate::fixtures::*;
+use crate::RepositoryHandle;
+
+#[rstest]
+#[tokio::test]
+async fn test_path_index_insert_build(tmpdir: tempfile::TempDir) {
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+    let mut layers = Vec::new();
+    for name in ["bin/tool", "lib/libtool.so"] {
+        let root = tmpdir.path().join(name.replace('/', "-"));
+        let path = root.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, name).unwrap();
+        let manifest = spfs::Committer::new(spfs_repo)
+            .commit_dir(&root)
+            .await
+            .unwrap();
+        let layer = spfs_repo
+            .create_layer_from_manifest(&manifest)
+            .await
+            .unwrap();
+        layers.push(layer.digest().unwrap());
+    }
+
+    let spec = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    repo.publish_package(
+        &spec,
+        &vec![(Component::Run, layers[0]), (Component::Build, layers[1])]
+            .into_iter()
+            .collect(),
+    )
+    .await
+    .unwrap();
+
+    let mut index = PathIndex::default();
+    index.insert_build(&repo, spec.ident()).await.unwrap();
+
+    let owners = index.owners_of("/spfs/bin/tool");
+    assert_eq!(owners.len(), 1, "expected one owner, got {owners:?}");
+    assert_eq!(&owners[0].pkg, spec.ident());
+    assert_eq!(owners[0].component, Component::Run);
+    assert_eq!(index.owners_of("bin/tool"), owners);
+
+    let owners = index.owners_of("/spfs/lib/libtool.so");
+    assert_eq!(owners.len(), 1);
+    assert_eq!(owners[0].component, Component::Build);
+
+    assert!(
+        index.owners_of("/spfs/bin").is_empty(),
+        "directories should not be indexed"
+    );
+    assert!(index.owners_of("/spfs/bin/missing").is_empty());
+}
+
+#[rstest]
+fn test_path_index_multiple_owners() {
+    let first = spec!({"pkg": "first/1.0.0/3I42H3S6"});
+    let second = spec!({"pkg": "second/1.0.0/3I42H3S6"});
+    let mut manifest = spfs::tracking::Manifest::default();
+    manifest.mkdirs("etc").unwrap();
+    manifest.mkfile("etc/config").unwrap();
+
+    let mut index = PathIndex::default();
+    index.insert_manifest(first.ident(), &Component::Run, &manifest);
+    index.insert_manifest(second.ident(), &Component::Run, &manifest);
+    index.insert_manifest(second.ident(), &Component::Run, &manifest);
+
+    let owners = index.owners_of("/spfs/etc/config");
+    assert_eq!(owners.len(), 2, "each owner should only be listed once");
+    assert_eq!(&owners[0].pkg, first.ident());
+    assert_eq!(&owners[1].pkg, second.ident());
+}
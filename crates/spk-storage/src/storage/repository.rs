@@ -11,9 +11,19 @@ use spk_schema::foundation::name::{PkgName, PkgNameBuf, RepositoryName};
 use spk_schema::foundation::version::Version;
 use spk_schema::ident_build::{Build, EmbeddedSource, InvalidBuildError};
 use spk_schema::option_map::get_host_options_filters;
-use spk_schema::{BuildIdent, Deprecate, Package, PackageMut, VersionIdent};
+use spk_schema::{AnyIdent, BuildIdent, Deprecate, Package, PackageMut, VersionIdent};
 
 use self::internal::RepositoryExt;
+use super::{
+    AccessAction,
+    AccessControl,
+    AuditAction,
+    AuditEntry,
+    AuditQuery,
+    DependencyIndex,
+    SearchIndex,
+    Tombstone,
+};
 use crate::{Error, Result};
 
 #[cfg(test)]
@@ -273,6 +283,44 @@ pub trait Repository: Storage + Sync {
         Ok(concrete.into_iter().collect())
     }
 
+    /// Return the embed stubs that were published for the packages
+    /// embedded in the given build.
+    ///
+    /// # Errors:
+    /// - PackageNotFound: If the given build does not exist
+    async fn list_embedded_stubs(&self, parent: &BuildIdent) -> Result<Vec<BuildIdent>> {
+        let spec = self.read_package(parent).await?;
+        let mut stubs = Vec::new();
+        for (embedded, _) in spec.embedded_as_packages()?.into_iter() {
+            stubs.extend(
+                self.get_embedded_package_builds(embedded.ident().as_version())
+                    .await?
+                    .into_iter()
+                    .filter(|build| match build.build() {
+                        Build::Embedded(EmbeddedSource::Package(source)) => source.ident == parent,
+                        _ => false,
+                    }),
+            );
+        }
+        stubs.sort();
+        stubs.dedup();
+        Ok(stubs)
+    }
+
+    /// Return the builds that embed the given package version, as
+    /// recorded by its embed stubs.
+    async fn list_embedding_parents(&self, pkg: &VersionIdent) -> Result<Vec<BuildIdent>> {
+        let mut parents = Vec::new();
+        for build in self.get_embedded_package_builds(pkg).await? {
+            if let Build::Embedded(EmbeddedSource::Package(source)) = build.build() {
+                parents.push(BuildIdent::try_from(&source.ident)?);
+            }
+        }
+        parents.sort();
+        parents.dedup();
+        Ok(parents)
+    }
+
     /// Returns the set of components published for a package build
     async fn list_build_components(&self, pkg: &BuildIdent) -> Result<Vec<Component>>;
 
@@ -301,7 +349,10 @@ pub trait Repository: Storage + Sync {
     /// - VersionExists: if the recipe version is already present
     async fn publish_recipe(&self, spec: &Self::Recipe) -> Result<()> {
         self.publish_recipe_to_storage(spec, PublishPolicy::DoNotOverwriteVersion)
-            .await
+            .await?;
+        self.record_audit_event(AuditAction::Publish, &spec.ident().to_any(None))
+            .await;
+        Ok(())
     }
 
     /// Remove a package recipe from this repository.
@@ -316,8 +367,21 @@ pub trait Repository: Storage + Sync {
     /// Same as [`Self::publish_recipe`] except that it clobbers any existing
     /// recipe with the same version.
     async fn force_publish_recipe(&self, spec: &Self::Recipe) -> Result<()> {
+        // recipes are also deprecated by publishing them again
+        let original = crate::with_cache_policy!(self, CachePolicy::BypassCache, {
+            self.read_recipe(spec.ident())
+        })
+        .await
+        .ok();
         self.publish_recipe_to_storage(spec, PublishPolicy::OverwriteVersion)
-            .await
+            .await?;
+        let action = AuditAction::for_update(
+            original.map(|recipe| recipe.is_deprecated()),
+            spec.is_deprecated(),
+        );
+        self.record_audit_event(action, &spec.ident().to_any(None))
+            .await;
+        Ok(())
     }
 
     /// Read package information for a specific version and build.
@@ -353,7 +417,22 @@ pub trait Repository: Storage + Sync {
             )));
         }
 
+        // builds that were removed with `spk rm` may be published
+        // again, but that is worth drawing attention to
+        match self.read_tombstone(package.ident()).await {
+            Ok(Some(tombstone)) => {
+                tracing::warn!("Publishing a build that was previously removed: {tombstone}");
+            }
+            Ok(None) => {}
+            Err(err) => tracing::warn!(
+                "Failed to check for a tombstone of {}: {err}",
+                package.ident()
+            ),
+        }
+
         self.publish_package_to_storage(package, components).await?;
+        self.record_audit_event(AuditAction::Publish, &package.ident().to_any())
+            .await;
 
         // After successfully publishing a package, also publish stubs for any
         // embedded packages in this package.
@@ -384,25 +463,27 @@ pub trait Repository: Storage + Sync {
     {
         // Read the contents of the existing spec, if any, before it is
         // overwritten.
-        let original_spec = if package.ident().can_embed() {
-            Some(
-                crate::with_cache_policy!(self, CachePolicy::BypassCache, {
-                    self.read_package(package.ident())
-                })
-                .await,
-            )
-        } else {
-            None
-        };
+        let original_spec = crate::with_cache_policy!(self, CachePolicy::BypassCache, {
+            self.read_package(package.ident())
+        })
+        .await
+        .ok();
 
         let components = self.read_components(package.ident()).await?;
         self.publish_package_to_storage(package, &components)
             .await?;
+        let action = AuditAction::for_update(
+            original_spec.as_ref().map(|spec| spec.is_deprecated()),
+            package.is_deprecated(),
+        );
+        self.record_audit_event(action, &package.ident().to_any())
+            .await;
 
         // Changes that affect embedded stubs:
         // - change in deprecation status
         // - adding/removing embedded packages
-        if let Some(Ok(original_spec)) = original_spec {
+        let original_spec = original_spec.filter(|_| package.ident().can_embed());
+        if let Some(original_spec) = original_spec {
             let original_embedded_providers = self.get_embedded_providers(&original_spec)?;
             let new_embedded_providers = self.get_embedded_providers(package)?;
             // No change case #1: no embedded packages involved.
@@ -482,6 +563,10 @@ pub trait Repository: Storage + Sync {
     ///
     /// The given package identifier must identify a full package build.
     async fn remove_package(&self, pkg: &BuildIdent) -> Result<()> {
+        // Check before touching the embedded package stubs, so
+        // that they are not removed without the package itself.
+        self.check_access(AccessAction::Remove, pkg.name()).await?;
+
         // Attempt to find and remove any related embedded package stubs.
         if let Ok(spec) = self.read_package(pkg).await {
             if spec.ident().can_embed() {
@@ -494,7 +579,10 @@ pub trait Repository: Storage + Sync {
             }
         }
 
-        self.remove_package_from_storage(pkg).await
+        self.remove_package_from_storage(pkg).await?;
+        self.record_audit_event(AuditAction::Remove, &pkg.to_any())
+            .await;
+        Ok(())
     }
 
     /// Identify the payloads for this identified package's components.
@@ -528,6 +616,104 @@ pub trait Repository: Storage + Sync {
         Ok("Nothing to do.".to_string())
     }
 
+    /// Read the rules for who may change which packages in this repository.
+    ///
+    /// Repositories that cannot store access rules allow anyone to make changes.
+    async fn read_access_control(&self) -> Result<AccessControl> {
+        Ok(AccessControl::default())
+    }
+
+    /// Check that the current user may perform the action on the named package.
+    ///
+    /// # Errors:
+    /// - PermissionDenied: if the access rules of this repository do not allow it
+    async fn check_access(&self, action: AccessAction, pkg: &PkgName) -> Result<()> {
+        let acl = self.read_access_control().await?;
+        if acl.is_empty() {
+            return Ok(());
+        }
+        let config = spfs::get_config()?;
+        acl.check(action, pkg, &config.user.name, self.name())
+    }
+
+    /// Read the publish history of a recipe or package build, newest first.
+    ///
+    /// Each entry is the spfs tag that was written when the spec was
+    /// published or modified, with the first entry being the current one.
+    ///
+    /// # Errors:
+    /// - PackageNotFound: if the recipe or build does not exist
+    async fn read_publish_history(&self, pkg: &AnyIdent) -> Result<Vec<spfs::tracking::Tag>> {
+        Err(Error::String(format!(
+            "Cannot read the history of {pkg}, the {} repository does not keep one",
+            self.name()
+        )))
+    }
+
+    /// Restore a recipe or package build to an earlier entry in its publish history.
+    ///
+    /// The entry is identified by its position in [`Self::read_publish_history`].
+    /// Restoring an entry adds to the history rather than discarding the newer
+    /// entries, so it can also be undone.
+    async fn rollback(&self, pkg: &AnyIdent, _index: usize) -> Result<()> {
+        Err(Error::String(format!(
+            "Cannot roll back {pkg}, the {} repository does not keep a history",
+            self.name()
+        )))
+    }
+
+    /// Append an entry to the audit log of this repository.
+    ///
+    /// The publish and remove methods of this trait already record
+    /// their changes, so this is only needed when a repository changes
+    /// its contents in some other way. Repositories that cannot store
+    /// an audit log ignore the entry.
+    ///
+    /// The change has already been made by the time that it is recorded,
+    /// so failing to record it is logged as a warning instead of failing
+    /// the change.
+    async fn record_audit_event(&self, _action: AuditAction, _pkg: &AnyIdent) {}
+
+    /// Read the entries of the audit log of this repository that are
+    /// selected by the given query, newest first.
+    async fn read_audit_log(&self, _query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        Err(Error::String(format!(
+            "Cannot read the audit log, the {} repository does not keep one",
+            self.name()
+        )))
+    }
+
+    /// Record that a build was removed from this repository.
+    ///
+    /// Repositories that cannot store tombstones ignore them.
+    async fn write_tombstone(&self, _tombstone: &Tombstone) -> Result<()> {
+        Ok(())
+    }
+
+    /// Read the tombstone that was left when the given build was
+    /// last removed from this repository, if any.
+    async fn read_tombstone(&self, _pkg: &BuildIdent) -> Result<Option<Tombstone>> {
+        Ok(None)
+    }
+
+    /// Read the index of package metadata that is stored in this
+    /// repository, if it has one.
+    ///
+    /// Repositories without an index can still be searched
+    /// by reading the recipe of each package version.
+    async fn read_search_index(&self) -> Result<Option<SearchIndex>> {
+        Ok(None)
+    }
+
+    /// Read the index of the packages that each build in this
+    /// repository depends on, if it has one.
+    ///
+    /// Repositories without an index can still be checked
+    /// with [`DependencyIndex::from_repository`].
+    async fn read_dependency_index(&self) -> Result<Option<DependencyIndex>> {
+        Ok(None)
+    }
+
     /// Change the active cache policy.
     ///
     /// The old cache policy is returned. Not all storage types may support
@@ -379,6 +379,26 @@ async fn test_repo_publish_package_creates_embed_stubs(#[case] repo: RepoKind) {
         .any(|pkg| pkg == "my-embedded-pkg"));
 }
 
+#[rstest]
+#[case::mem(RepoKind::Mem)]
+#[case::spfs(RepoKind::Spfs)]
+#[tokio::test]
+async fn test_repo_list_embedded_stubs(#[case] repo: RepoKind) {
+    let repo = make_repo(repo).await;
+    let (_, spec) = create_repo_for_embed_stubs_test(&repo).await;
+
+    let stubs = repo.list_embedded_stubs(spec.ident()).await.unwrap();
+    assert_eq!(stubs.len(), 1, "expected one stub: {stubs:?}");
+    assert_eq!(stubs[0].name(), "my-embedded-pkg");
+    assert!(stubs[0].is_embedded());
+
+    let parents = repo
+        .list_embedding_parents(stubs[0].as_version())
+        .await
+        .unwrap();
+    assert_eq!(parents, vec![spec.ident().clone()]);
+}
+
 #[rstest]
 #[case::mem(RepoKind::Mem)]
 #[case::spfs(RepoKind::Spfs)]
@@ -393,18 +393,33 @@ impl Repository for RuntimeRepository {
     }
 
     async fn list_packages(&self) -> Result<Vec<PkgNameBuf>> {
-        Ok(get_all_filenames(&self.root)
+        let folders = get_all_filenames(&self.root)
             .await?
             .into_iter()
-            .filter_map(|entry| {
-                if entry.ends_with('/') {
-                    Some(entry[0..entry.len() - 1].to_string())
-                } else {
-                    None
-                }
-            })
-            .filter_map(|e| PkgNameBuf::try_from(e).ok())
-            .collect())
+            .filter_map(|entry| entry.strip_suffix('/').map(str::to_string));
+        let mut packages = Vec::new();
+        for folder in folders {
+            if PkgName::validate_namespace(&folder).is_ok() {
+                // namespaced packages are installed under
+                // a folder for their namespace
+                packages.extend(
+                    get_all_filenames(self.root.join(&folder))
+                        .await?
+                        .into_iter()
+                        .filter_map(|entry| {
+                            let name = entry.strip_suffix('/')?;
+                            PkgNameBuf::try_from(format!(
+                                "{folder}{}{name}",
+                                PkgName::NAMESPACE_SEP
+                            ))
+                            .ok()
+                        }),
+                );
+            } else if let Ok(name) = PkgNameBuf::try_from(folder) {
+                packages.push(name);
+            }
+        }
+        Ok(packages)
     }
 
     async fn list_package_versions(&self, name: &PkgName) -> Result<Arc<Vec<Arc<Version>>>> {
@@ -0,0 +1,182 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use spk_schema::{Deprecate, Meta, Recipe, VersionIdent};
+
+#[cfg(test)]
+#[path = "./search_index_test.rs"]
+mod search_index_test;
+
+/// The searchable information about one version of a package
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SearchEntry {
+    /// The package version that is described
+    pub pkg: VersionIdent,
+    /// The metadata from the recipe of the package
+    #[serde(default, skip_serializing_if = "Meta::is_default")]
+    pub meta: Meta,
+    /// True if the recipe of the package is deprecated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+}
+
+impl SearchEntry {
+    /// Describe a package version from its recipe
+    pub fn from_recipe<R: Recipe>(recipe: &R) -> Self {
+        Self {
+            pkg: recipe.ident().clone(),
+            meta: recipe.metadata().clone(),
+            deprecated: recipe.is_deprecated(),
+        }
+    }
+}
+
+/// An index of the metadata of the package versions in a repository.
+///
+/// The index is stored in the repository so that packages can be
+/// searched by their metadata without reading every recipe. It is
+/// updated as recipes are published and removed, but only once it
+/// has been created for a repository, see `spk repo index`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "Vec<SearchEntry>", into = "Vec<SearchEntry>")]
+pub struct SearchIndex {
+    entries: BTreeMap<VersionIdent, SearchEntry>,
+}
+
+impl From<Vec<SearchEntry>> for SearchIndex {
+    fn from(entries: Vec<SearchEntry>) -> Self {
+        Self::from_iter(entries)
+    }
+}
+
+impl From<SearchIndex> for Vec<SearchEntry> {
+    fn from(index: SearchIndex) -> Self {
+        index.entries.into_values().collect()
+    }
+}
+
+impl FromIterator<SearchEntry> for SearchIndex {
+    fn from_iter<I: IntoIterator<Item = SearchEntry>>(iter: I) -> Self {
+        let mut index = Self::default();
+        for entry in iter {
+            index.insert(entry);
+        }
+        index
+    }
+}
+
+impl SearchIndex {
+    /// Add or replace the entry for a package version
+    pub fn insert(&mut self, entry: SearchEntry) {
+        self.entries.insert(entry.pkg.clone(), entry);
+    }
+
+    /// Remove the entry for a package version, if there is one
+    pub fn remove(&mut self, pkg: &VersionIdent) -> Option<SearchEntry> {
+        self.entries.remove(pkg)
+    }
+
+    /// The entry for a package version, if it has been indexed
+    pub fn get(&self, pkg: &VersionIdent) -> Option<&SearchEntry> {
+        self.entries.get(pkg)
+    }
+
+    /// Iterate all of the entries in this index, ordered by package
+    pub fn iter(&self) -> impl Iterator<Item = &SearchEntry> {
+        self.entries.values()
+    }
+
+    /// The entries in this index that match the query, ordered by package
+    pub fn search<'a>(&'a self, query: &'a SearchQuery) -> impl Iterator<Item = &'a SearchEntry> {
+        self.iter().filter(|entry| query.matches(entry))
+    }
+
+    /// The number of indexed package versions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if no package versions have been indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Criteria for finding package versions in a [`SearchIndex`].
+///
+/// All text is compared without regard to case, and an
+/// entry must satisfy every given criteria to match.
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    /// Text that must appear in the package name
+    pub name: Option<String>,
+    /// Words that must all appear in the description, in any order
+    pub description: Option<String>,
+    /// Labels that the package must have, with the value
+    /// that they must contain, if any
+    pub labels: Vec<(String, Option<String>)>,
+    /// Text that must appear in the license
+    pub license: Option<String>,
+    /// If true, deprecated package versions can also match
+    pub deprecated: bool,
+}
+
+impl SearchQuery {
+    /// True if this query only checks the package name, and
+    /// so does not require the metadata of the package
+    pub fn is_name_only(&self) -> bool {
+        self.description.is_none() && self.labels.is_empty() && self.license.is_none()
+    }
+
+    /// True if the package name matches this query
+    pub fn matches_name(&self, name: &str) -> bool {
+        self.name
+            .as_deref()
+            .map(|term| contains_ignore_case(name, term))
+            .unwrap_or(true)
+    }
+
+    /// True if the entry matches all of the criteria of this query
+    pub fn matches(&self, entry: &SearchEntry) -> bool {
+        if entry.deprecated && !self.deprecated {
+            return false;
+        }
+        self.matches_name(entry.pkg.name().as_str()) && self.matches_meta(&entry.meta)
+    }
+
+    /// True if the metadata matches all of the criteria of this query
+    pub fn matches_meta(&self, meta: &Meta) -> bool {
+        if let Some(description) = &self.description {
+            let Some(text) = &meta.description else {
+                return false;
+            };
+            if !description
+                .split_whitespace()
+                .all(|word| contains_ignore_case(text, word))
+            {
+                return false;
+            }
+        }
+        if let Some(license) = &self.license {
+            match &meta.license {
+                Some(text) if contains_ignore_case(text, license) => {}
+                _ => return false,
+            }
+        }
+        self.labels
+            .iter()
+            .all(|(label, value)| match (meta.labels.get(label), value) {
+                (Some(actual), Some(value)) => contains_ignore_case(actual, value),
+                (Some(_), None) => true,
+                (None, _) => false,
+            })
+    }
+}
+
+fn contains_ignore_case(text: &str, term: &str) -> bool {
+    text.to_lowercase().contains(&term.to_lowercase())
+}
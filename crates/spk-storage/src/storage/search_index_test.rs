@@ -0,0 +1,141 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spfs::prelude::*;
+use spk_schema::ident::parse_version_ident;
+use spk_schema::{recipe, DeprecateMut, Meta, Recipe};
+
+use super::{SearchEntry, SearchIndex, SearchQuery};
+use crate::fixtures::*;
+use crate::RepositoryHandle;
+
+fn entry(pkg: &str, description: &str, labels: &[(&str, &str)]) -> SearchEntry {
+    SearchEntry {
+        pkg: parse_version_ident(pkg).unwrap(),
+        meta: Meta {
+            description: Some(description.to_string()),
+            license: Some("Apache-2.0".to_string()),
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        },
+        deprecated: false,
+    }
+}
+
+fn query(description: Option<&str>, labels: &[(&str, Option<&str>)]) -> SearchQuery {
+    SearchQuery {
+        description: description.map(String::from),
+        labels: labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.map(String::from)))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[rstest]
+#[case::all_words(query(Some("color management"), &[]), true)]
+#[case::any_order(query(Some("Management COLOR"), &[]), true)]
+#[case::missing_word(query(Some("color science"), &[]), false)]
+#[case::label_exists(query(None, &[("team", None)]), true)]
+#[case::label_value(query(None, &[("team", Some("pipe"))]), true)]
+#[case::label_wrong_value(query(None, &[("team", Some("lighting"))]), false)]
+#[case::label_missing(query(None, &[("owner", None)]), false)]
+fn test_search_query_matches(#[case] query: SearchQuery, #[case] expected: bool) {
+    let entry = entry(
+        "opencolorio/2.3.0",
+        "A complete color management solution",
+        &[("team", "pipeline")],
+    );
+    assert_eq!(query.matches(&entry), expected);
+}
+
+#[rstest]
+fn test_search_query_deprecated() {
+    let mut entry = entry("my-pkg/1.0.0", "something", &[]);
+    entry.deprecated = true;
+    let mut query = SearchQuery::default();
+    assert!(
+        !query.matches(&entry),
+        "deprecated versions should not match by default"
+    );
+    query.deprecated = true;
+    assert!(query.matches(&entry));
+}
+
+#[rstest]
+fn test_search_index_yaml_round_trip() {
+    let index: SearchIndex = [
+        entry("my-pkg/1.0.0", "first", &[("team", "pipeline")]),
+        entry("my-pkg/2.0.0", "second", &[]),
+        entry("other/1.0.0", "third", &[]),
+    ]
+    .into_iter()
+    .collect();
+    let yaml = serde_yaml::to_string(&index).unwrap();
+    let parsed: SearchIndex = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed, index, "should survive a round-trip encoding");
+}
+
+#[rstest]
+fn test_search_index_insert_replaces() {
+    let mut index = SearchIndex::default();
+    index.insert(entry("my-pkg/1.0.0", "old", &[]));
+    index.insert(entry("my-pkg/1.0.0", "new", &[]));
+    assert_eq!(index.len(), 1);
+    let pkg = parse_version_ident("my-pkg/1.0.0").unwrap();
+    assert_eq!(
+        index.get(&pkg).unwrap().meta.description.as_deref(),
+        Some("new")
+    );
+    index.remove(&pkg);
+    assert!(index.is_empty());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_spfs_search_index_follows_recipes() {
+    let repo = make_repo(RepoKind::Spfs).await;
+    let RepositoryHandle::SPFS(spfs_repo) = &*repo.repo else {
+        panic!("expected an spfs repository");
+    };
+    let mut recipe = recipe!({
+        "pkg": "my-pkg/1.0.0",
+        "meta": {"description": "A color management library"},
+    });
+    repo.publish_recipe(&recipe).await.unwrap();
+    assert!(
+        repo.read_search_index().await.unwrap().is_none(),
+        "the index should not exist until it is built"
+    );
+
+    let index = spfs_repo.build_search_index().await.unwrap();
+    assert_eq!(index.len(), 1);
+
+    recipe.deprecate().unwrap();
+    repo.force_publish_recipe(&recipe).await.unwrap();
+    let other = recipe!({"pkg": "other/1.0.0"});
+    repo.publish_recipe(&other).await.unwrap();
+    let index = repo.read_search_index().await.unwrap().unwrap();
+    assert_eq!(index.len(), 2, "published recipes should be indexed");
+    assert!(index.get(recipe.ident()).unwrap().deprecated);
+
+    let other_tag = spfs::tracking::TagSpec::parse("spk/index/search/other").unwrap();
+    let inner: &spfs::storage::RepositoryHandle = spfs_repo;
+    assert!(
+        inner.has_tag(&other_tag).await,
+        "each package should have its own index entries"
+    );
+
+    repo.remove_recipe(other.ident()).await.unwrap();
+    let index = repo.read_search_index().await.unwrap().unwrap();
+    assert!(
+        index.get(other.ident()).is_none(),
+        "removed recipes should be dropped from the index"
+    );
+}
@@ -2,26 +2,28 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
-use std::collections::{hash_map, HashMap, HashSet};
+use std::collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::marker::PhantomData;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
-use futures::{Future, StreamExt};
+use futures::{Future, StreamExt, TryStreamExt};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
-use relative_path::RelativePathBuf;
+use relative_path::{RelativePath, RelativePathBuf};
 use serde::{Deserialize, Serialize};
 use spfs::prelude::*;
-use spfs::storage::{EntryType, Repository};
+use spfs::storage::{EntryType, Repository, TagNamespace, TagNamespaceBuf, TagStorageMut};
 use spfs::tracking::{self, Tag, TagSpec};
 use spk_schema::foundation::ident_build::{parse_build, Build};
 use spk_schema::foundation::ident_component::Component;
 use spk_schema::foundation::name::{PkgName, PkgNameBuf, RepositoryName, RepositoryNameBuf};
-use spk_schema::foundation::version::{parse_version, Version};
+use spk_schema::foundation::version::{parse_tag_version, Version};
 use spk_schema::ident::{ToAnyWithoutBuild, VersionIdent};
 use spk_schema::ident_build::parsing::embedded_source_package;
 use spk_schema::ident_build::{EmbeddedSource, EmbeddedSourcePackage};
@@ -32,8 +34,20 @@ use spk_schema::{AnyIdent, BuildIdent, FromYaml, Package, Recipe, Spec, SpecReci
 use tokio::io::AsyncReadExt;
 use tokio::task::JoinSet;
 
-use super::repository::{PublishPolicy, Storage};
-use super::CachePolicy;
+use super::repository::{PublishPolicy, Repository as _, Storage};
+use super::{
+    AccessAction,
+    AccessControl,
+    AuditAction,
+    AuditEntry,
+    AuditQuery,
+    CachePolicy,
+    DependencyEntry,
+    DependencyIndex,
+    SearchEntry,
+    SearchIndex,
+    Tombstone,
+};
 use crate::storage::repository::internal::RepositoryExt;
 use crate::{with_cache_policy, Error, Result};
 
@@ -42,6 +56,17 @@ use crate::{with_cache_policy, Error, Result};
 mod spfs_test;
 
 const REPO_METADATA_TAG: &str = "spk/repo";
+const REPO_AUDIT_TAG: &str = "spk/audit";
+const REPO_SEARCH_INDEX_TAG: &str = "spk/index/search";
+const REPO_DEPENDENCY_INDEX_TAG: &str = "spk/index/depends";
+/// The tag in an index folder that marks the index as created. Package
+/// names cannot contain an underscore, so it never names a package.
+const INDEX_CREATED_TAG_NAME: &str = "_created";
+/// How many times the index entries of a package are read and written
+/// again when they are changed by another process at the same time.
+const INDEX_UPDATE_ATTEMPTS: usize = 5;
+/// The number of packages whose index entries are read at once
+const INDEX_READ_CONCURRENCY: usize = 32;
 const REPO_VERSION: &str = "1.0.0";
 
 macro_rules! verbatim_build_spec_tag_if_enabled {
@@ -78,6 +103,7 @@ pub struct SpfsRepository<S> {
     name: RepositoryNameBuf,
     inner: Arc<spfs::storage::RepositoryHandle>,
     cache_policy: Arc<ArcSwap<CachePolicy>>,
+    cache_ttl: Option<Duration>,
     caches: CachesForAddress,
     tag_strategy: PhantomData<S>,
     legacy_spk_version_tags: bool,
@@ -166,6 +192,7 @@ where
             name: name_and_repo.name.as_ref().try_into()?,
             inner: Arc::new(inner),
             cache_policy: Arc::new(ArcSwap::new(Arc::new(CachePolicy::CacheOk))),
+            cache_ttl: None,
             tag_strategy: PhantomData,
             legacy_spk_version_tags: cfg!(feature = "legacy-spk-version-tags"),
         })
@@ -182,6 +209,7 @@ impl<S> SpfsRepository<S> {
             name: name.try_into()?,
             inner: Arc::new(inner),
             cache_policy: Arc::new(ArcSwap::new(Arc::new(CachePolicy::CacheOk))),
+            cache_ttl: None,
             tag_strategy: PhantomData,
             legacy_spk_version_tags: cfg!(feature = "legacy-spk-version-tags"),
         })
@@ -204,10 +232,52 @@ impl<S> SpfsRepository<S> {
             .append_pair("when", &ts.to_string());
     }
 
+    /// Read and write the tags of this repository within the given spfs
+    /// tag namespace, or at the root of the repository if None.
+    ///
+    /// This must be done before the repository is pinned or cloned.
+    pub fn set_tag_namespace(&mut self, namespace: Option<TagNamespaceBuf>) -> Result<()> {
+        let Some(inner) = Arc::get_mut(&mut self.inner) else {
+            return Err(Error::String(format!(
+                "Cannot change the tag namespace of the {} repository once it is shared",
+                self.name
+            )));
+        };
+        inner.try_set_tag_namespace(namespace.clone())?;
+        // the namespace is part of the address so that the packages
+        // of each namespace are cached separately
+        let query: Vec<(String, String)> = self
+            .address
+            .query_pairs()
+            .filter(|(key, _)| key != "tag_namespace")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        self.address.set_query(None);
+        if !query.is_empty() || namespace.is_some() {
+            let mut pairs = self.address.query_pairs_mut();
+            pairs.extend_pairs(query);
+            if let Some(namespace) = &namespace {
+                pairs.append_pair("tag_namespace", namespace.as_rel_path().as_str());
+            }
+        }
+        self.caches = CachesForAddress::new(&self.address);
+        Ok(())
+    }
+
     /// Enable or disable the use of legacy spk version tags
     pub fn set_legacy_spk_version_tags(&mut self, enabled: bool) {
         self.legacy_spk_version_tags = enabled;
     }
+
+    /// Limit how long cached results are reused for.
+    ///
+    /// Once this much time has passed since the caches for this
+    /// repository were last cleared, they are cleared again before
+    /// the next cached result would be used. Results are cached for
+    /// as long as the process runs when this is None, the default.
+    pub fn set_cache_ttl(&mut self, ttl: Option<Duration>) {
+        self.cache_ttl = ttl;
+    }
 }
 
 #[derive(Clone)]
@@ -264,6 +334,8 @@ struct CachesForAddress {
     recipe: Arc<DashMap<VersionIdent, CacheValue<Arc<spk_schema::SpecRecipe>>>>,
     /// Recipe specs cache for read_recipe()
     tag_spec: Arc<DashMap<tracking::TagSpec, CacheValue<tracking::Tag>>>,
+    /// When these caches were created or last cleared
+    cleared: Arc<ArcSwap<Instant>>,
 }
 
 static CACHES_FOR_ADDRESS: Lazy<std::sync::Mutex<HashMap<String, CachesForAddress>>> =
@@ -282,6 +354,7 @@ impl CachesForAddress {
                     package_versions: Arc::new(DashMap::new()),
                     recipe: Arc::new(DashMap::new()),
                     tag_spec: Arc::new(DashMap::new()),
+                    cleared: Arc::new(ArcSwap::new(Arc::new(Instant::now()))),
                 })
                 .clone(),
         }
@@ -441,6 +514,8 @@ where
         package: &<Self::Recipe as spk_schema::Recipe>::Output,
         components: &HashMap<Component, spfs::encoding::Digest>,
     ) -> Result<()> {
+        self.check_access(AccessAction::Publish, package.name())
+            .await?;
         let tag_path = Self::build_package_tag::<TagStrategy, _>(package.ident());
 
         // We will also publish the 'run' component in the old style
@@ -458,17 +533,8 @@ where
         };
 
         self.inner.push_tag(&legacy_tag, &legacy_component).await?;
-
-        let components: std::result::Result<Vec<_>, _> = components
-            .iter()
-            .map(|(name, digest)| {
-                spfs::tracking::TagSpec::parse(tag_path.join(name.as_str()))
-                    .map(|spec| (spec, digest))
-            })
-            .collect();
-        for (tag_spec, digest) in components?.into_iter() {
-            self.inner.push_tag(&tag_spec, digest).await?;
-        }
+        self.push_component_tags(package.ident(), components)
+            .await?;
 
         // TODO: dedupe this part with force_publish_recipe
         let tag_path = Self::build_spec_tag::<TagStrategy, _>(package.ident());
@@ -481,6 +547,13 @@ where
             .await?;
         self.inner.push_tag(&tag_spec, &digest).await?;
         self.invalidate_caches();
+        if !package.ident().is_source() {
+            let entry = DependencyEntry::from_package(package);
+            self.update_dependency_index(package.ident().name(), |index| {
+                index.insert(entry.clone())
+            })
+            .await;
+        }
         Ok(())
     }
 
@@ -490,13 +563,17 @@ where
         publish_policy: PublishPolicy,
     ) -> Result<()> {
         let ident = spec.ident();
+        self.check_access(AccessAction::Publish, ident.name())
+            .await?;
         let tag_path = Self::build_spec_tag::<TagStrategy, _>(ident);
         let tag_spec = spfs::tracking::TagSpec::parse(tag_path.as_str())?;
-        if matches!(publish_policy, PublishPolicy::DoNotOverwriteVersion)
-            && self.inner.has_tag(&tag_spec).await
-        {
-            // BUG(rbottriell): this creates a race condition but is not super dangerous
-            // because of the non-destructive tag history
+        let overwrite = match publish_policy {
+            PublishPolicy::OverwriteVersion => true,
+            PublishPolicy::DoNotOverwriteVersion => false,
+        };
+        if !overwrite && self.inner.has_tag(&tag_spec).await {
+            // avoids writing the recipe when it would be rejected anyway,
+            // but the tag is still checked again when it is pushed
             return Err(Error::VersionExists(ident.clone()));
         }
 
@@ -506,8 +583,26 @@ where
             .inner
             .commit_blob(Box::pin(std::io::Cursor::new(payload.into_bytes())))
             .await?;
-        self.inner.push_tag(&tag_spec, &digest).await?;
+        if overwrite {
+            self.inner.push_tag(&tag_spec, &digest).await?;
+        } else {
+            // the tag is only created if no one else has published
+            // this version since it was checked above
+            match self
+                .inner
+                .push_tag_if_matches(&tag_spec, None, &digest)
+                .await
+            {
+                Err(spfs::Error::TagChanged(_)) => {
+                    return Err(Error::VersionExists(ident.clone()));
+                }
+                res => res?,
+            };
+        }
         self.invalidate_caches();
+        let entry = SearchEntry::from_recipe(spec);
+        self.update_search_index(ident.name(), |index| index.insert(entry.clone()))
+            .await;
         Ok(())
     }
 
@@ -574,6 +669,8 @@ where
     }
 
     async fn remove_package_from_storage(&self, pkg: &BuildIdent) -> Result<()> {
+        self.check_access(AccessAction::Remove, pkg.name()).await?;
+
         // The three things this method is responsible for deleting are:
         //
         // 1. Component build tags like: `spk/pkg/example/4.2.1/GMTG3CXY/build`.
@@ -643,7 +740,7 @@ where
         //
         // Allow manual_try_fold since this logic can't short-circuit all errors.
         #[allow(clippy::manual_try_fold)]
-        [
+        let result = [
             component_tags_result,
             build_recipe_tags_result,
             // Check legacy tags last because errors deleting legacy tags are
@@ -673,7 +770,14 @@ where
             } else {
                 Err(Error::PackageNotFound(pkg.to_any()))
             }
-        })
+        });
+        if result.is_ok() {
+            self.update_dependency_index(pkg.name(), |index| {
+                index.remove(pkg);
+            })
+            .await;
+        }
+        result
     }
 }
 
@@ -689,17 +793,40 @@ where
     async fn list_packages(&self) -> Result<Vec<PkgNameBuf>> {
         let path = relative_path::RelativePath::new("spk/spec");
         // XXX: infallible vs return type
-        Ok(self
+        let folders = self
             .ls_tags(path)
             .await
             .into_iter()
             .filter_map(|entry| match entry {
-                Ok(EntryType::Folder(name)) => name.parse().ok(),
+                Ok(EntryType::Folder(name)) => Some(name),
                 Ok(EntryType::Tag(_)) => None,
                 Ok(EntryType::Namespace { .. }) => None,
                 Err(_) => None,
             })
-            .collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut packages = Vec::with_capacity(folders.len());
+        for folder in folders {
+            if let Ok(name) = folder.parse() {
+                packages.push(name);
+                continue;
+            }
+            // namespaced packages are stored one level deeper,
+            // under a folder for their namespace
+            if PkgName::validate_namespace(&folder).is_err() {
+                continue;
+            }
+            let namespace = format!("{folder}{}", PkgName::NAMESPACE_SEP);
+            packages.extend(
+                self.ls_tags(&path.join(&folder))
+                    .await
+                    .into_iter()
+                    .filter_map(|entry| match entry {
+                        Ok(EntryType::Folder(name)) => format!("{namespace}{name}").parse().ok(),
+                        _ => None,
+                    }),
+            );
+        }
+        Ok(packages)
     }
 
     async fn list_package_versions(&self, name: &PkgName) -> Result<Arc<Vec<Arc<Version>>>> {
@@ -717,13 +844,13 @@ where
                 .await
                 .into_iter()
                 .filter_map(|entry| match entry {
-                    // undo our encoding of the invalid '+' character in spfs tags
-                    Ok(EntryType::Folder(name)) => Some(name.replace("..", "+")),
-                    Ok(EntryType::Tag(name)) => Some(name.replace("..", "+")),
+                    Ok(EntryType::Folder(name)) => Some(name),
+                    Ok(EntryType::Tag(name)) => Some(name),
                     Ok(EntryType::Namespace { .. }) => None,
                     Err(_) => None,
                 })
-                .filter_map(|v| match parse_version(&v) {
+                // undo our encoding of the characters that are invalid in spfs tags
+                .filter_map(|v| match parse_tag_version(&v) {
                     Ok(v) => Some(v),
                     Err(_) => {
                         tracing::warn!("Invalid version found in spfs tags: {}", v);
@@ -834,6 +961,7 @@ where
     }
 
     async fn remove_recipe(&self, pkg: &VersionIdent) -> Result<()> {
+        self.check_access(AccessAction::Remove, pkg.name()).await?;
         self.with_build_spec_tag_for_pkg(pkg, |pkg, tag_spec, _| async move {
             match self.inner.remove_tag_stream(&tag_spec).await {
                 Err(spfs::Error::UnknownReference(_)) => {
@@ -846,7 +974,195 @@ where
                 }
             }
         })
-        .await
+        .await?;
+        self.update_search_index(pkg.name(), |index| {
+            index.remove(pkg);
+        })
+        .await;
+        self.record_audit_event(AuditAction::Remove, &pkg.to_any(None))
+            .await;
+        Ok(())
+    }
+
+    async fn read_access_control(&self) -> Result<AccessControl> {
+        Ok(self.read_metadata().await?.access)
+    }
+
+    async fn record_audit_event(&self, action: AuditAction, pkg: &AnyIdent) {
+        self.record_audit_entry(AuditEntry::new(action, pkg.clone()))
+            .await
+    }
+
+    async fn read_audit_log(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let packages = match &query.package {
+            Some(pkg) => vec![pkg.clone()],
+            None => {
+                self.inner
+                    .ls_tags(RelativePath::new(REPO_AUDIT_TAG))
+                    .try_filter_map(|entry| async move {
+                        match entry {
+                            EntryType::Tag(name) => Ok(name.parse().ok()),
+                            _ => Ok(None),
+                        }
+                    })
+                    .try_collect()
+                    .await?
+            }
+        };
+
+        // each package has its own log, which are merged newest first
+        // so that only the entries being returned are read
+        let mut logs = Vec::with_capacity(packages.len());
+        for pkg in packages {
+            let mut log = match self.inner.read_tag(&Self::audit_tag(&pkg)?).await {
+                Ok(log) => log,
+                Err(spfs::Error::UnknownReference(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if let Some(tag) = log.try_next().await? {
+                logs.push((tag, log));
+            }
+        }
+        let mut entries = Vec::new();
+        while !query.is_full(entries.len()) {
+            let Some(newest) = logs
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, (tag, _))| tag.time)
+                .map(|(i, _)| i)
+            else {
+                break;
+            };
+            let (tag, log) = &mut logs[newest];
+            let target = tag.target;
+            match log.try_next().await? {
+                Some(next) => *tag = next,
+                None => drop(logs.swap_remove(newest)),
+            }
+            let entry = self.read_audit_entry(target).await?;
+            if query.matches(&entry) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn read_search_index(&self) -> Result<Option<SearchIndex>> {
+        Ok(self
+            .read_index_entries(REPO_SEARCH_INDEX_TAG, Error::InvalidSearchIndex)
+            .await?
+            .map(SearchIndex::from))
+    }
+
+    async fn write_tombstone(&self, tombstone: &Tombstone) -> Result<()> {
+        let tag_path = Self::build_tombstone_tag::<TagStrategy, _>(&tombstone.package);
+        let tag_spec = spfs::tracking::TagSpec::parse(tag_path)?;
+        let yaml = serde_yaml::to_string(tombstone).map_err(Error::InvalidTombstone)?;
+        let digest = self
+            .inner
+            .commit_blob(Box::pin(std::io::Cursor::new(yaml.into_bytes())))
+            .await?;
+        self.inner.push_tag(&tag_spec, &digest).await?;
+        Ok(())
+    }
+
+    async fn read_tombstone(&self, pkg: &BuildIdent) -> Result<Option<Tombstone>> {
+        let tag_path = Self::build_tombstone_tag::<TagStrategy, _>(pkg);
+        let tag_spec = spfs::tracking::TagSpec::parse(tag_path)?;
+        let digest = match self.inner.resolve_tag(&tag_spec).await {
+            Ok(tag) => tag.target,
+            Err(spfs::Error::UnknownReference(_)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let (mut reader, _) = self.inner.open_payload(digest).await?;
+        let mut yaml = String::new();
+        reader
+            .read_to_string(&mut yaml)
+            .await
+            .map_err(|err| Error::FileReadError(digest.to_string().into(), err))?;
+        serde_yaml::from_str(&yaml)
+            .map(Some)
+            .map_err(Error::InvalidTombstone)
+    }
+
+    async fn read_dependency_index(&self) -> Result<Option<DependencyIndex>> {
+        Ok(self
+            .read_index_entries(REPO_DEPENDENCY_INDEX_TAG, Error::InvalidDependencyIndex)
+            .await?
+            .map(DependencyIndex::from))
+    }
+
+    async fn read_publish_history(&self, pkg: &AnyIdent) -> Result<Vec<Tag>> {
+        // the recipe and build tags are found with different ident types,
+        // which cannot share a single closure
+        match pkg.build() {
+            None => {
+                self.with_build_spec_tag_for_pkg(pkg.as_version(), |_, tag_spec, _| async move {
+                    self.read_tag_history(&tag_spec).await
+                })
+                .await
+            }
+            Some(build) => {
+                let build = pkg.to_build(build.clone());
+                self.with_build_spec_tag_for_pkg(&build, |_, tag_spec, _| async move {
+                    self.read_tag_history(&tag_spec).await
+                })
+                .await
+            }
+        }
+    }
+
+    async fn rollback(&self, pkg: &AnyIdent, index: usize) -> Result<()> {
+        self.check_access(AccessAction::Publish, pkg.name()).await?;
+        let history = self.read_publish_history(pkg).await?;
+        let Some(restored) = history.get(index) else {
+            return Err(Error::String(format!(
+                "Cannot roll back {pkg} to entry {index}, there are only {} entries in its history",
+                history.len()
+            )));
+        };
+        if index == 0 {
+            return Ok(());
+        }
+
+        let Some(build) = pkg.build() else {
+            self.with_build_spec_tag_for_pkg(pkg.as_version(), |_, tag_spec, _| async move {
+                self.inner.push_tag(&tag_spec, &restored.target).await?;
+                Ok(())
+            })
+            .await?;
+            self.invalidate_caches();
+            self.record_audit_event(AuditAction::Rollback, pkg).await;
+            return Ok(());
+        };
+        let build = pkg.to_build(build.clone());
+        self.with_build_spec_tag_for_pkg(&build, |_, tag_spec, _| async move {
+            self.inner.push_tag(&tag_spec, &restored.target).await?;
+            Ok(())
+        })
+        .await?;
+
+        // The component tags have their own history, and are
+        // restored to whatever they were when the spec was published
+        if !build.is_embedded() {
+            let stored = with_cache_policy!(self, CachePolicy::BypassCache, {
+                self.lookup_package(&build)
+            })
+            .await?;
+            for tag_spec in stored.tags() {
+                let previous = self
+                    .read_tag_history(tag_spec)
+                    .await?
+                    .into_iter()
+                    .find(|tag| tag.time <= restored.time);
+                if let Some(previous) = previous {
+                    self.inner.push_tag(tag_spec, &previous.target).await?;
+                }
+            }
+        }
+        self.invalidate_caches();
+        self.record_audit_event(AuditAction::Rollback, pkg).await;
+        Ok(())
     }
 
     async fn upgrade(&self) -> Result<String> {
@@ -934,7 +1250,15 @@ where
     TagStrategy: TagPathStrategy + Send + Sync,
 {
     fn cached_result_permitted(&self) -> bool {
-        self.cache_policy.load().cached_result_permitted()
+        if !self.cache_policy.load().cached_result_permitted() {
+            return false;
+        }
+        if let Some(ttl) = self.cache_ttl {
+            if self.caches.cleared.load().elapsed() >= ttl {
+                self.invalidate_caches();
+            }
+        }
+        true
     }
 
     async fn has_tag<F>(&self, for_pkg: F, tag: &tracking::TagSpec) -> bool
@@ -953,6 +1277,7 @@ where
         self.caches.package.clear();
         self.caches.tag_spec.clear();
         self.caches.list_build_components.clear();
+        self.caches.cleared.store(Arc::new(Instant::now()));
     }
 
     /// Return all the possible part lengths for a version that should be
@@ -1005,12 +1330,14 @@ where
                     .copied()
                     .collect::<Vec<_>>();
                 pkg.with_version(Version {
+                    epoch: pkg.version().epoch,
                     parts: VersionParts {
                         parts: new_parts,
                         plus_epsilon: pkg.version().parts.plus_epsilon,
                     },
                     pre: pkg.version().pre.clone(),
                     post: pkg.version().post.clone(),
+                    dev: pkg.version().dev,
                 })
             })
     }
@@ -1125,6 +1452,473 @@ where
         Ok(meta)
     }
 
+    /// Stream the builds of packages as they are published to this repository.
+    ///
+    /// Builds are reported once their package has been completely
+    /// published, only for the named package if one is given. A build
+    /// may be reported more than once if it is published again.
+    pub fn watch_package_builds(
+        &self,
+        name: Option<&PkgName>,
+    ) -> impl futures::Stream<Item = Result<BuildIdent>> + Send + 'static {
+        // the build spec is the last tag to be pushed when publishing
+        let pattern = match name {
+            Some(name) => format!("spk/spec/{name}/*/*"),
+            None => "spk/spec/*/*/*".to_string(),
+        };
+        self.inner
+            .watch_tags(&pattern)
+            .map_err(Error::from)
+            .try_filter_map(|tag| async move { Ok(build_from_spec_tag(&tag.path())) })
+    }
+
+    /// Promote a recipe or package build from another repository into this one.
+    ///
+    /// Unlike publishing, the very same objects that are tagged in the source
+    /// repository are synced and tagged here, so the recipe, package spec and
+    /// component layers all keep their digests. Promoting a version only
+    /// promotes its recipe, not its builds. The promotion is recorded in the
+    /// audit log of this repository.
+    pub async fn promote_from<S>(&self, source: &SpfsRepository<S>, pkg: &AnyIdent) -> Result<()>
+    where
+        S: TagPathStrategy + Send + Sync,
+    {
+        self.check_access(AccessAction::Publish, pkg.name()).await?;
+        let syncer = spfs::Syncer::new(&source.inner, &self.inner)
+            .with_reporter(spfs::sync::ConsoleSyncReporter::default());
+
+        let Some(build) = pkg.build() else {
+            let version = pkg.as_version();
+            let digest = source
+                .with_build_spec_tag_for_pkg(version, |_, _, tag| async move { Ok(tag.target) })
+                .await?;
+            syncer.sync_digest(digest).await?;
+            let tag_spec = TagSpec::parse(Self::build_spec_tag::<TagStrategy, _>(version))?;
+            self.inner.push_tag(&tag_spec, &digest).await?;
+            self.invalidate_caches();
+            self.record_audit_entry(AuditEntry::promoted(pkg.clone(), &source.name))
+                .await;
+            return Ok(());
+        };
+
+        let build = pkg.to_build(build.clone());
+        if build.is_embedded() {
+            return Err(Error::String(format!(
+                "Cannot promote embedded package {build}, promote the package that provides it instead"
+            )));
+        }
+        let components = source.read_components_from_storage(&build).await?;
+        let spec_digest = source
+            .with_build_spec_tag_for_pkg(&build, |_, _, tag| async move { Ok(tag.target) })
+            .await?;
+        let env_spec = components
+            .values()
+            .copied()
+            .chain(std::iter::once(spec_digest))
+            .collect();
+        syncer.sync_env(env_spec).await?;
+
+        let tag_path = Self::build_package_tag::<TagStrategy, _>(&build);
+        let legacy_component = if build.is_source() {
+            components.get(&Component::Source)
+        } else {
+            components.get(&Component::Run)
+        };
+        if let Some(digest) = legacy_component {
+            let legacy_tag = TagSpec::parse(&tag_path)?;
+            self.inner.push_tag(&legacy_tag, digest).await?;
+        }
+        self.push_component_tags(&build, &components).await?;
+        // the build spec is pushed last, as it is when publishing,
+        // so that the build is not visible until it is complete
+        let tag_spec = TagSpec::parse(Self::build_spec_tag::<TagStrategy, _>(&build))?;
+        self.inner.push_tag(&tag_spec, &spec_digest).await?;
+        self.invalidate_caches();
+        self.record_audit_entry(AuditEntry::promoted(pkg.clone(), &source.name))
+            .await;
+
+        if build.can_embed() {
+            let package = self.read_package_from_storage(&build).await?;
+            for (embed, components) in self.get_embedded_providers(&package)?.into_iter() {
+                self.create_embedded_stub_for_spec(&package, &embed, components)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tag each of the component layers of a package build.
+    async fn push_component_tags(
+        &self,
+        pkg: &BuildIdent,
+        components: &HashMap<Component, spfs::encoding::Digest>,
+    ) -> Result<()> {
+        let tag_path = Self::build_package_tag::<TagStrategy, _>(pkg);
+        for (name, digest) in components.iter() {
+            let tag_spec = TagSpec::parse(tag_path.join(name.as_str()))?;
+            self.inner.push_tag(&tag_spec, digest).await?;
+        }
+        Ok(())
+    }
+
+    /// The tag that holds the audit log of one package.
+    fn audit_tag(pkg: &PkgName) -> Result<TagSpec> {
+        Ok(TagSpec::parse(
+            RelativePath::new(REPO_AUDIT_TAG).join(pkg.as_str()),
+        )?)
+    }
+
+    /// Append an entry to the audit log of this repository, logging
+    /// a warning if it cannot be created or written.
+    async fn record_audit_entry(&self, entry: Result<AuditEntry>) {
+        let result = match entry {
+            Ok(entry) => self.write_audit_entry(&entry).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            tracing::warn!(
+                "Failed to record a change in the audit log of the {} repository: {err}",
+                self.name
+            );
+        }
+    }
+
+    async fn write_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        let tag_spec = Self::audit_tag(entry.package.name())?;
+        let yaml = serde_yaml::to_string(entry).map_err(Error::InvalidAuditEntry)?;
+        let digest = self
+            .inner
+            .commit_blob(Box::pin(std::io::Cursor::new(yaml.into_bytes())))
+            .await?;
+        // the entries of each package are pushed onto its own tag
+        // stream, which spfs only ever appends to
+        self.inner.push_tag(&tag_spec, &digest).await?;
+        Ok(())
+    }
+
+    async fn read_audit_entry(&self, digest: spfs::encoding::Digest) -> Result<AuditEntry> {
+        let (mut reader, _) = self.inner.open_payload(digest).await?;
+        let mut yaml = String::new();
+        reader
+            .read_to_string(&mut yaml)
+            .await
+            .map_err(|err| Error::FileReadError(digest.to_string().into(), err))?;
+        serde_yaml::from_str(&yaml).map_err(Error::InvalidAuditEntry)
+    }
+
+    /// Read all the entries of a tag stream, newest first.
+    async fn read_tag_history(&self, tag_spec: &TagSpec) -> Result<Vec<Tag>> {
+        Ok(self.inner.read_tag(tag_spec).await?.try_collect().await?)
+    }
+
+    /// Create or replace the search index of this repository from
+    /// the recipe of every package version that it contains.
+    ///
+    /// Once created, the index is kept up to date as recipes are
+    /// published and removed through spk.
+    pub async fn build_search_index(&self) -> Result<SearchIndex> {
+        let mut index = SearchIndex::default();
+        for name in self.list_packages().await? {
+            for version in self.list_package_versions(&name).await?.iter() {
+                let pkg = VersionIdent::new(name.clone(), (**version).clone());
+                match self.read_recipe(&pkg).await {
+                    Ok(recipe) => index.insert(SearchEntry::from_recipe(&recipe)),
+                    // versions that only exist as embedded
+                    // packages have no recipe to index
+                    Err(Error::PackageNotFound(_)) => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+        self.write_search_index(&index).await?;
+        Ok(index)
+    }
+
+    /// Replace the search index of this repository.
+    pub async fn write_search_index(&self, index: &SearchIndex) -> Result<()> {
+        let mut packages: BTreeMap<PkgNameBuf, Vec<&SearchEntry>> = BTreeMap::new();
+        for entry in index.iter() {
+            packages
+                .entry(entry.pkg.name().to_owned())
+                .or_default()
+                .push(entry);
+        }
+        self.write_index(REPO_SEARCH_INDEX_TAG, packages, Error::InvalidSearchIndex)
+            .await
+    }
+
+    /// Apply a change to the search index entries of the named
+    /// package, if this repository has a search index.
+    ///
+    /// The index is only an aid to searching, so failing to
+    /// update it does not fail the change that was made.
+    async fn update_search_index<F>(&self, name: &PkgName, update: F)
+    where
+        F: Fn(&mut SearchIndex) + Send + Sync,
+    {
+        let result = self
+            .update_index_package(
+                REPO_SEARCH_INDEX_TAG,
+                name,
+                Error::InvalidSearchIndex,
+                update,
+            )
+            .await;
+        if let Err(err) = result {
+            tracing::warn!(
+                "Failed to update the search index of {}: {err}",
+                self.name()
+            );
+        }
+    }
+
+    /// Create or replace the dependency index of this repository from
+    /// the package spec of every build that it contains.
+    ///
+    /// Once created, the index is kept up to date as builds are
+    /// published and removed through spk.
+    pub async fn build_dependency_index(&self) -> Result<DependencyIndex> {
+        let index = DependencyIndex::from_repository(self).await?;
+        self.write_dependency_index(&index).await?;
+        Ok(index)
+    }
+
+    /// Replace the dependency index of this repository.
+    pub async fn write_dependency_index(&self, index: &DependencyIndex) -> Result<()> {
+        let mut packages: BTreeMap<PkgNameBuf, Vec<&DependencyEntry>> = BTreeMap::new();
+        for entry in index.iter() {
+            packages
+                .entry(entry.build.name().to_owned())
+                .or_default()
+                .push(entry);
+        }
+        self.write_index(
+            REPO_DEPENDENCY_INDEX_TAG,
+            packages,
+            Error::InvalidDependencyIndex,
+        )
+        .await
+    }
+
+    /// Apply a change to the dependency index entries of the named
+    /// package, if this repository has a dependency index.
+    ///
+    /// Like the search index, failing to update it does
+    /// not fail the change that was made.
+    async fn update_dependency_index<F>(&self, name: &PkgName, update: F)
+    where
+        F: Fn(&mut DependencyIndex) + Send + Sync,
+    {
+        let result = self
+            .update_index_package(
+                REPO_DEPENDENCY_INDEX_TAG,
+                name,
+                Error::InvalidDependencyIndex,
+                update,
+            )
+            .await;
+        if let Err(err) = result {
+            tracing::warn!(
+                "Failed to update the dependency index of {}: {err}",
+                self.name()
+            );
+        }
+    }
+
+    /// The tag that holds the index entries of one package.
+    ///
+    /// Each package has its own entries so that publishing or removing
+    /// a package never needs to read or rewrite the whole index.
+    fn index_package_tag(base: &str, name: &PkgName) -> Result<TagSpec> {
+        Ok(TagSpec::parse(format!("{base}/{name}"))?)
+    }
+
+    /// The tag that marks the index under the given tag folder as created.
+    fn index_created_tag(base: &str) -> TagSpec {
+        TagSpec::parse(format!("{base}/{INDEX_CREATED_TAG_NAME}"))
+            .expect("index tags should be valid")
+    }
+
+    /// Find the tags that hold the entries of each package
+    /// in the index under the given tag folder.
+    async fn find_index_tags(&self, base: &str) -> Result<Vec<TagSpec>> {
+        let mut tags = Vec::new();
+        let mut folders = vec![RelativePathBuf::from(base)];
+        while let Some(folder) = folders.pop() {
+            let mut entries = self.inner.ls_tags(&folder);
+            while let Some(entry) = entries.try_next().await? {
+                match entry {
+                    EntryType::Folder(name) => folders.push(folder.join(name)),
+                    EntryType::Tag(name) if name != INDEX_CREATED_TAG_NAME => {
+                        tags.push(TagSpec::parse(folder.join(name).as_str())?);
+                    }
+                    EntryType::Tag(_) | EntryType::Namespace(_) => {}
+                }
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Read the index entries held by a tag, along with the
+    /// digest that the tag points to, if the tag exists.
+    async fn read_index_tag<E>(
+        &self,
+        tag_spec: &TagSpec,
+        invalid: fn(serde_yaml::Error) -> Error,
+    ) -> Result<Option<(spfs::encoding::Digest, Vec<E>)>>
+    where
+        E: serde::de::DeserializeOwned,
+    {
+        let digest = match self.inner.resolve_tag(tag_spec).await {
+            Ok(tag) => tag.target,
+            Err(spfs::Error::UnknownReference(_)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let (mut reader, _) = self.inner.open_payload(digest).await?;
+        let mut yaml = String::new();
+        reader
+            .read_to_string(&mut yaml)
+            .await
+            .map_err(|err| Error::FileReadError(digest.to_string().into(), err))?;
+        let entries = serde_yaml::from_str(&yaml).map_err(invalid)?;
+        Ok(Some((digest, entries)))
+    }
+
+    /// Read and merge the entries of every package in the index under
+    /// the given tag folder, or None if the index was never created.
+    async fn read_index_entries<E>(
+        &self,
+        base: &str,
+        invalid: fn(serde_yaml::Error) -> Error,
+    ) -> Result<Option<Vec<E>>>
+    where
+        E: serde::de::DeserializeOwned + Send,
+    {
+        if !self.inner.has_tag(&Self::index_created_tag(base)).await {
+            return Ok(None);
+        }
+        let tags = self.find_index_tags(base).await?;
+        let packages = futures::stream::iter(tags.iter())
+            .map(|tag_spec| self.read_index_tag::<E>(tag_spec, invalid))
+            .buffer_unordered(INDEX_READ_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(Some(
+            packages
+                .into_iter()
+                .flatten()
+                .flat_map(|(_, entries)| entries)
+                .collect(),
+        ))
+    }
+
+    /// Write the index entries of one package, returning the digest of
+    /// the written blob that the package tag should point to.
+    async fn commit_index_entries<E>(
+        &self,
+        entries: &[E],
+        invalid: fn(serde_yaml::Error) -> Error,
+    ) -> Result<spfs::encoding::Digest>
+    where
+        E: Serialize,
+    {
+        let yaml = serde_yaml::to_string(entries).map_err(invalid)?;
+        Ok(self
+            .inner
+            .commit_blob(Box::pin(std::io::Cursor::new(yaml.into_bytes())))
+            .await?)
+    }
+
+    /// Replace the index under the given tag folder with
+    /// the given entries of each package, and mark it as created.
+    async fn write_index<E>(
+        &self,
+        base: &str,
+        packages: BTreeMap<PkgNameBuf, Vec<E>>,
+        invalid: fn(serde_yaml::Error) -> Error,
+    ) -> Result<()>
+    where
+        E: Serialize,
+    {
+        let mut written = HashSet::new();
+        for (name, entries) in packages.iter() {
+            let tag_spec = Self::index_package_tag(base, name)?;
+            let digest = self.commit_index_entries(entries, invalid).await?;
+            self.inner.push_tag(&tag_spec, &digest).await?;
+            written.insert(tag_spec);
+        }
+        // packages that are no longer in the repository
+        for tag_spec in self.find_index_tags(base).await? {
+            if written.contains(&tag_spec) {
+                continue;
+            }
+            match self.inner.remove_tag_stream(&tag_spec).await {
+                Ok(()) | Err(spfs::Error::UnknownReference(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let digest = self.commit_index_entries::<E>(&[], invalid).await?;
+        self.inner
+            .push_tag(&Self::index_created_tag(base), &digest)
+            .await?;
+        Ok(())
+    }
+
+    /// Apply a change to the entries of the named package in the index
+    /// under the given tag folder, if the index has been created.
+    ///
+    /// The change is applied again to the latest entries if they
+    /// were changed by another process while it was being made.
+    async fn update_index_package<E, I, F>(
+        &self,
+        base: &str,
+        name: &PkgName,
+        invalid: fn(serde_yaml::Error) -> Error,
+        update: F,
+    ) -> Result<()>
+    where
+        E: Serialize + serde::de::DeserializeOwned + Send,
+        I: From<Vec<E>>,
+        Vec<E>: From<I>,
+        F: Fn(&mut I) + Send + Sync,
+    {
+        if !self.inner.has_tag(&Self::index_created_tag(base)).await {
+            return Ok(());
+        }
+        let tag_spec = Self::index_package_tag(base, name)?;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let (expected, entries) = match self.read_index_tag(&tag_spec, invalid).await? {
+                Some((digest, entries)) => (Some(digest), entries),
+                None => (None, Vec::new()),
+            };
+            let mut index = I::from(entries);
+            update(&mut index);
+            let digest = self
+                .commit_index_entries(&Vec::from(index), invalid)
+                .await?;
+            match self
+                .inner
+                .push_tag_if_matches(&tag_spec, expected.as_ref(), &digest)
+                .await
+            {
+                Err(spfs::Error::TagChanged(_)) if attempts < INDEX_UPDATE_ATTEMPTS => continue,
+                res => {
+                    res?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Replace the rules for who may change which packages in this repository.
+    pub async fn write_access_control(&self, access: AccessControl) -> Result<()> {
+        let mut meta = self.read_metadata().await?;
+        meta.access = access;
+        self.write_metadata(&meta).await
+    }
+
     async fn resolve_tag<F>(
         &self,
         for_pkg: F,
@@ -1226,6 +2020,139 @@ where
         tag
     }
 
+    /// Construct an spfs tag string to record the removal of a build.
+    fn build_tombstone_tag<S, T>(pkg: &T) -> RelativePathBuf
+    where
+        S: TagPathStrategy,
+        T: TagPath,
+    {
+        let mut tag = RelativePathBuf::from("spk");
+        tag.push("tombstone");
+        tag.push(pkg.tag_path::<S>());
+
+        tag
+    }
+
+    /// Remove the packages in the given spfs tag namespace, and in any
+    /// namespace nested within it, that have not been published since
+    /// `older_than`.
+    ///
+    /// A version is pruned along with all of its builds, once neither its
+    /// recipe nor any of its builds have been published since then, and
+    /// is kept while other builds left in the namespace depend on it. The
+    /// packages are removed like any others, recording each removal in
+    /// the audit log of the namespace, and the data that they leave unused
+    /// is removed by the next `spfs clean` of the repository. Returns the
+    /// namespace and identifier of each pruned version, which are not
+    /// actually removed when `dry_run` is set.
+    pub async fn prune_tag_namespace(
+        &self,
+        namespace: &TagNamespace,
+        older_than: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<Vec<(TagNamespaceBuf, VersionIdent)>> {
+        let mut pruned = Vec::new();
+        let mut namespaces = vec![namespace.to_owned()];
+        while let Some(namespace) = namespaces.pop() {
+            // nested namespaces are listed at the root of their parent
+            let mut entries = self
+                .inner
+                .ls_tags_in_namespace(Some(&namespace), RelativePath::new(""));
+            while let Some(entry) = entries.try_next().await? {
+                if let EntryType::Namespace(name) = entry {
+                    namespaces.push(TagNamespaceBuf::new(namespace.as_rel_path().join(name)));
+                }
+            }
+
+            let mut repo = Self::new(self.name.as_str(), self.address.as_str()).await?;
+            repo.set_tag_namespace(Some(namespace.clone()))?;
+            repo.set_legacy_spk_version_tags(self.legacy_spk_version_tags);
+            repo.set_cache_policy(CachePolicy::BypassCache);
+            for (version, builds) in repo.find_prunable_versions(older_than).await? {
+                if !dry_run {
+                    for build in builds.iter() {
+                        repo.remove_package(build).await?;
+                    }
+                    match repo.remove_recipe(&version).await {
+                        Err(err) if err.is_package_not_found() => (),
+                        res => res?,
+                    }
+                }
+                pruned.push((namespace.clone(), version));
+            }
+        }
+        if !dry_run && !pruned.is_empty() {
+            self.invalidate_caches();
+        }
+        Ok(pruned)
+    }
+
+    /// Find the package versions that have not been published since
+    /// `older_than`, along with the builds to remove with each of them.
+    async fn find_prunable_versions(
+        &self,
+        older_than: DateTime<Utc>,
+    ) -> Result<Vec<(VersionIdent, Vec<BuildIdent>)>> {
+        let mut prunable = Vec::new();
+        for name in self.list_packages().await? {
+            for version in self.list_package_versions(&name).await?.iter() {
+                let pkg = VersionIdent::new(name.clone(), (**version).clone());
+                // embedded stubs are removed along with their provider
+                let builds = self
+                    .list_package_builds(&pkg)
+                    .await?
+                    .into_iter()
+                    .filter(|build| !build.is_embedded())
+                    .collect::<Vec<_>>();
+                let mut published = None;
+                for ident in
+                    std::iter::once(pkg.to_any(None)).chain(builds.iter().map(|b| b.to_any()))
+                {
+                    let history = match self.read_publish_history(&ident).await {
+                        Ok(history) => history,
+                        Err(Error::PackageNotFound(_))
+                        | Err(Error::SPFS(spfs::Error::UnknownReference(_))) => continue,
+                        Err(err) => return Err(err),
+                    };
+                    published = published.max(history.iter().map(|tag| tag.time).max());
+                }
+                if matches!(published, Some(time) if time < older_than) {
+                    prunable.push((pkg, builds));
+                }
+            }
+        }
+
+        // keep the versions that remaining builds still depend on, which
+        // may in turn be using other versions that would be pruned
+        let index = match self.read_dependency_index().await? {
+            Some(index) => index,
+            None => DependencyIndex::from_repository(self).await?,
+        };
+        loop {
+            let removed = prunable
+                .iter()
+                .flat_map(|(_, builds)| builds.iter().cloned())
+                .collect::<BTreeSet<_>>();
+            let broken = index.broken_by_removal(&removed);
+            let before = prunable.len();
+            prunable.retain(|(pkg, _)| {
+                let needed = broken
+                    .iter()
+                    .find(|(_, dep)| dep.pkg == *pkg.name() && dep.matches_version(pkg.version()));
+                if let Some((build, dep)) = needed {
+                    tracing::warn!(
+                        "Not pruning {pkg}, it is still required by {build} ({} requirement {dep})",
+                        dep.kind
+                    );
+                }
+                needed.is_none()
+            });
+            if prunable.len() == before {
+                return Ok(prunable);
+            }
+        }
+    }
+
     pub fn flush(&self) -> Result<()> {
         match &*self.inner {
             spfs::storage::RepositoryHandle::Tar(tar) => Ok(tar.flush()?),
@@ -1234,9 +2161,24 @@ where
     }
 }
 
+/// Identify the build that a `spk/spec/NAME/VERSION/BUILD` tag is for.
+fn build_from_spec_tag(path: &str) -> Option<BuildIdent> {
+    let mut parts = path.strip_prefix("spk/spec/")?.split('/');
+    let (name, version, build) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let name = PkgName::new(name).ok()?.to_owned();
+    let version = parse_tag_version(version).ok()?;
+    let build = parse_build(build).ok()?;
+    Some(VersionIdent::new(name, version).into_build(build))
+}
+
 #[derive(Deserialize, Serialize, Default, Debug, PartialEq, Eq)]
 pub struct RepositoryMetadata {
     version: Version,
+    #[serde(default, skip_serializing_if = "AccessControl::is_empty")]
+    access: AccessControl,
 }
 
 /// A simple enum that allows us to represent both the old and new form
@@ -1290,6 +2232,7 @@ pub async fn local_repository() -> Result<SpfsRepository<NormalizedTagStrategy>>
         name: "local".try_into()?,
         inner: Arc::new(inner),
         cache_policy: Arc::new(ArcSwap::new(Arc::new(CachePolicy::CacheOk))),
+        cache_ttl: None,
         tag_strategy: PhantomData,
         legacy_spk_version_tags: cfg!(feature = "legacy-spk-version-tags"),
     })
@@ -1310,6 +2253,7 @@ pub async fn remote_repository<S: AsRef<str>, TagStrategy>(
         name: name.as_ref().try_into()?,
         inner: Arc::new(inner),
         cache_policy: Arc::new(ArcSwap::new(Arc::new(CachePolicy::CacheOk))),
+        cache_ttl: None,
         tag_strategy: PhantomData,
         legacy_spk_version_tags: cfg!(feature = "legacy-spk-version-tags"),
     })
@@ -4,9 +4,11 @@
 
 use std::convert::TryFrom;
 use std::str::FromStr;
+use std::time::Duration;
 
 use rstest::rstest;
 use spfs::prelude::*;
+use spfs::storage::TagNamespaceBuf;
 use spk_schema::foundation::fixtures::*;
 use spk_schema::foundation::version::Version;
 use spk_schema::ident_ops::NormalizedTagStrategy;
@@ -28,6 +30,21 @@ fn test_repo_version_is_valid() {
         .expect("repo current version must be a valid spk version string");
 }
 
+#[rstest]
+#[case(
+    "spk/spec/my-pkg/1.0.0..r.1/3I42H3S6",
+    Some("my-pkg/1.0.0+r.1/3I42H3S6")
+)]
+#[case("spk/spec/my-pkg/1_2.0.0/src", Some("my-pkg/1!2.0.0/src"))]
+#[case("spk/spec/my-pkg/1.0.0/src", Some("my-pkg/1.0.0/src"))]
+#[case("spk/spec/my-pkg/1.0.0", None)]
+#[case("spk/pkg/my-pkg/1.0.0/3I42H3S6", None)]
+#[case("spk/spec/my-pkg/1.0.0/3I42H3S6/run", None)]
+fn test_build_from_spec_tag(#[case] path: &str, #[case] expected: Option<&str>) {
+    let expected = expected.map(|s| BuildIdent::from_str(s).unwrap());
+    assert_eq!(super::build_from_spec_tag(path), expected);
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_metadata_io(tmpdir: tempfile::TempDir) {
@@ -132,3 +149,190 @@ async fn test_upgrade_changes_tags(tmpdir: tempfile::TempDir) {
     .unwrap();
     assert!(matches!(pkg, super::StoredPackage::WithComponents(_)));
 }
+
+#[rstest]
+#[tokio::test]
+async fn test_publish_recipe_concurrent(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let repo = SpfsRepository::try_from(NameAndRepositoryWithTagStrategy::<
+        _,
+        _,
+        NormalizedTagStrategy,
+    >::new(
+        "test-repo",
+        spfs::storage::fs::FsRepository::create(tmpdir.path())
+            .await
+            .unwrap(),
+    ))
+    .unwrap();
+
+    let recipe = spk_schema::recipe!({"pkg": "my-pkg/1.0.0"});
+    let results = futures::future::join_all((0..5).map(|_| repo.publish_recipe(&recipe))).await;
+
+    let published = results.iter().filter(|r| r.is_ok()).count();
+    assert_eq!(published, 1, "only one publish should succeed");
+    for result in results {
+        match result {
+            Ok(_) | Err(crate::Error::VersionExists(_)) => {}
+            Err(err) => panic!("expected version exists error, got {err:?}"),
+        }
+    }
+}
+
+#[rstest]
+#[case::cached(None, CachePolicy::CacheOk, false)]
+#[case::expired(Some(Duration::ZERO), CachePolicy::CacheOk, true)]
+#[case::not_expired(Some(Duration::from_secs(3600)), CachePolicy::CacheOk, false)]
+#[case::bypassed(None, CachePolicy::BypassCache, true)]
+#[tokio::test]
+async fn test_cache_ttl_and_policy(
+    tmpdir: tempfile::TempDir,
+    #[case] ttl: Option<Duration>,
+    #[case] policy: CachePolicy,
+    #[case] expect_change: bool,
+) {
+    init_logging();
+    let repo_root = tmpdir.path();
+    let spfs_repo = spfs::storage::fs::FsRepository::create(repo_root)
+        .await
+        .unwrap();
+    let mut repo = SpfsRepository::<NormalizedTagStrategy>::new(
+        "test-repo",
+        &format!("file://{}", repo_root.display()),
+    )
+    .await
+    .unwrap();
+    repo.set_cache_ttl(ttl);
+    repo.set_cache_policy(policy);
+    assert!(repo.list_packages().await.unwrap().is_empty());
+
+    // changes made outside of spk are not seen while
+    // the cached results can still be used
+    let ident = BuildIdent::from_str("mypkg/1.0.0/src").unwrap();
+    let tag = spfs::tracking::TagSpec::from_str(
+        SpfsRepository::<NormalizedTagStrategy>::build_spec_tag::<NormalizedTagStrategy, _>(&ident)
+            .as_str(),
+    )
+    .unwrap();
+    spfs_repo
+        .push_tag(&tag, &spfs::encoding::EMPTY_DIGEST.into())
+        .await
+        .unwrap();
+
+    let packages = repo.list_packages().await.unwrap();
+    assert_eq!(!packages.is_empty(), expect_change);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_tag_namespace_isolates_packages(tmpdir: tempfile::TempDir) {
+    init_logging();
+    let repo_root = tmpdir.path();
+    spfs::storage::fs::FsRepository::create(repo_root)
+        .await
+        .unwrap();
+    let address = format!("file://{}", repo_root.display());
+    let repo = SpfsRepository::<NormalizedTagStrategy>::new("test-repo", &address)
+        .await
+        .unwrap();
+    let mut scratch = SpfsRepository::<NormalizedTagStrategy>::new("test-repo", &address)
+        .await
+        .unwrap();
+    scratch
+        .set_tag_namespace(Some(TagNamespaceBuf::new("users/test")))
+        .unwrap();
+    assert_ne!(
+        repo.address(),
+        scratch.address(),
+        "namespaced repositories should have their own address"
+    );
+
+    let recipe = spk_schema::recipe!({"pkg": "my-pkg/1.0.0"});
+    scratch.publish_recipe(&recipe).await.unwrap();
+    assert_eq!(
+        scratch.list_packages().await.unwrap(),
+        vec!["my-pkg".parse().unwrap()]
+    );
+    assert!(
+        repo.list_packages().await.unwrap().is_empty(),
+        "packages in a namespace should not be seen outside of it"
+    );
+    // the same version can still be published outside of the namespace
+    repo.publish_recipe(&recipe).await.unwrap();
+}
+
+#[rstest]
+#[case::old(chrono::Duration::minutes(1), 1)]
+#[case::recent(chrono::Duration::hours(-1), 0)]
+#[tokio::test]
+async fn test_prune_tag_namespace(
+    tmpdir: tempfile::TempDir,
+    #[case] since_now: chrono::Duration,
+    #[case] expected_removed: usize,
+) {
+    init_logging();
+    let repo_root = tmpdir.path();
+    spfs::storage::fs::FsRepository::create(repo_root)
+        .await
+        .unwrap();
+    let address = format!("file://{}", repo_root.display());
+    let repo = SpfsRepository::<NormalizedTagStrategy>::new("test-repo", &address)
+        .await
+        .unwrap();
+    let mut scratch = SpfsRepository::<NormalizedTagStrategy>::new("test-repo", &address)
+        .await
+        .unwrap();
+    scratch
+        .set_tag_namespace(Some(TagNamespaceBuf::new("users/test")))
+        .unwrap();
+    let recipe = spk_schema::recipe!({"pkg": "my-pkg/1.0.0"});
+    scratch.publish_recipe(&recipe).await.unwrap();
+    repo.publish_recipe(&recipe).await.unwrap();
+    let build = spk_schema::spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    let components = vec![(
+        spk_schema::foundation::ident_component::Component::Run,
+        spfs::encoding::EMPTY_DIGEST.into(),
+    )]
+    .into_iter()
+    .collect();
+    scratch.publish_package(&build, &components).await.unwrap();
+
+    // pruning a parent namespace also prunes those nested within it
+    let pruned = repo
+        .prune_tag_namespace(
+            &TagNamespaceBuf::new("users"),
+            chrono::Utc::now() + since_now,
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(pruned.len(), expected_removed);
+    scratch.set_cache_policy(CachePolicy::BypassCache);
+    assert_eq!(
+        scratch.list_packages().await.unwrap().is_empty(),
+        expected_removed > 0
+    );
+    assert!(
+        !repo.list_packages().await.unwrap().is_empty(),
+        "packages outside of the namespace should never be pruned"
+    );
+    if expected_removed > 0 {
+        assert!(
+            scratch.read_package(build.ident()).await.is_err(),
+            "the builds of a version should be pruned with its recipe"
+        );
+        let removed = scratch
+            .read_audit_log(&crate::AuditQuery::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|entry| entry.action == crate::AuditAction::Remove)
+            .map(|entry| entry.package.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            removed.len(),
+            2,
+            "pruning should be recorded in the audit log: {removed:?}"
+        );
+    }
+}
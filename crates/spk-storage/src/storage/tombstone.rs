@@ -0,0 +1,65 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use spk_schema::BuildIdent;
+
+use crate::Result;
+
+#[cfg(test)]
+#[path = "./tombstone_test.rs"]
+mod tombstone_test;
+
+/// A record of a build that was removed from a repository.
+///
+/// Tombstones are left behind by `spk rm` so that publishing the
+/// same build again later can be flagged, since other packages may
+/// have stopped depending on it when it was removed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Tombstone {
+    /// The build that was removed
+    pub package: BuildIdent,
+    /// When the build was removed
+    pub time: DateTime<Utc>,
+    /// The user that removed the build
+    pub user: String,
+    /// The host that the build was removed from
+    pub host: String,
+    /// Builds that depended on the removed build, and were
+    /// knowingly left without it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub broken: Vec<BuildIdent>,
+}
+
+impl Tombstone {
+    /// Describe the removal of a build now by the current user on this host
+    pub fn new(package: BuildIdent, broken: Vec<BuildIdent>) -> Result<Self> {
+        let config = spfs::get_config()?;
+        Ok(Self {
+            package,
+            time: Utc::now(),
+            user: config.user.name.clone(),
+            host: config.user.domain.clone(),
+            broken,
+        })
+    }
+}
+
+impl std::fmt::Display for Tombstone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} was removed by {}@{} on {}",
+            self.package,
+            self.user,
+            self.host,
+            self.time.format("%Y-%m-%d %H:%M"),
+        )?;
+        if !self.broken.is_empty() {
+            write!(f, ", breaking {} dependent build(s)", self.broken.len())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use rstest::rstest;
+use spk_schema::foundation::ident_component::Component;
+use spk_schema::{spec, Package};
+
+use super::Tombstone;
+use crate::fixtures::*;
+
+#[rstest]
+#[case::mem(RepoKind::Mem)]
+#[case::spfs(RepoKind::Spfs)]
+#[tokio::test]
+async fn test_repo_tombstones(#[case] repo: RepoKind) {
+    let repo = make_repo(repo).await;
+    let package = spec!({"pkg": "my-pkg/1.0.0/3I42H3S6"});
+    let components = vec![(Component::Run, empty_layer_digest())]
+        .into_iter()
+        .collect();
+    repo.publish_package(&package, &components).await.unwrap();
+    assert!(
+        repo.read_tombstone(package.ident())
+            .await
+            .unwrap()
+            .is_none(),
+        "a published build should not have a tombstone"
+    );
+
+    repo.remove_package(package.ident()).await.unwrap();
+    let dependent = spec!({"pkg": "my-app/2.0.0/3I42H3S6"});
+    let tombstone =
+        Tombstone::new(package.ident().clone(), vec![dependent.ident().clone()]).unwrap();
+    repo.write_tombstone(&tombstone).await.unwrap();
+    assert_eq!(
+        repo.read_tombstone(package.ident()).await.unwrap(),
+        Some(tombstone.clone())
+    );
+
+    // the tombstone is kept when the build is published again,
+    // so that every later publish is flagged as well
+    repo.publish_package(&package, &components).await.unwrap();
+    assert_eq!(
+        repo.read_tombstone(package.ident()).await.unwrap(),
+        Some(tombstone)
+    );
+}
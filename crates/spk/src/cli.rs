@@ -11,11 +11,44 @@ use colored::Colorize;
 use miette::{Context, Result};
 #[cfg(feature = "sentry")]
 use spk_cli_common::configure_sentry;
-use spk_cli_common::{configure_logging, CommandArgs, Error, Run};
-use spk_cli_group1::{cmd_bake, cmd_completion, cmd_deprecate, cmd_undeprecate};
-use spk_cli_group2::{cmd_ls, cmd_new, cmd_num_variants, cmd_publish, cmd_remove};
+use spk_cli_common::{
+    configure_logging,
+    configure_output,
+    CommandArgs,
+    Error,
+    OutputFormat,
+    Reporter,
+    Run,
+};
+use spk_cli_group1::{
+    cmd_bake,
+    cmd_cache,
+    cmd_complete,
+    cmd_completion,
+    cmd_deprecate,
+    cmd_undeprecate,
+};
+use spk_cli_group2::{
+    cmd_hist,
+    cmd_ls,
+    cmd_new,
+    cmd_num_variants,
+    cmd_promote,
+    cmd_prune,
+    cmd_publish,
+    cmd_remove,
+};
 use spk_cli_group3::{cmd_export, cmd_import};
-use spk_cli_group4::{cmd_lint, cmd_search, cmd_version, cmd_view};
+use spk_cli_group4::{
+    cmd_diff,
+    cmd_lint,
+    cmd_options,
+    cmd_rdepends,
+    cmd_search,
+    cmd_version,
+    cmd_view,
+    cmd_which_owns,
+};
 use spk_cmd_build::cmd_build;
 use spk_cmd_convert::cmd_convert;
 use spk_cmd_debug::cmd_debug;
@@ -45,6 +78,13 @@ use spk_solve::{
 pub struct Opt {
     #[clap(short, long, global = true, action = clap::ArgAction::Count)]
     pub verbose: u8,
+    /// The format to write command results in
+    ///
+    /// When json is selected, commands that support it write their
+    /// results to stdout as a single json document, leaving logs
+    /// and progress on stderr.
+    #[clap(long, global = true, value_enum, default_value_t, env = "SPK_OUTPUT")]
+    pub output: OutputFormat,
     #[clap(subcommand)]
     pub cmd: Command,
 }
@@ -63,6 +103,8 @@ impl Opt {
             client
         };
 
+        configure_output(self.output);
+
         let res = configure_logging(self.verbose).wrap_err("Failed to initialize output log");
         if let Err(err) = res {
             eprintln!("{}", err.to_string().red());
@@ -142,14 +184,19 @@ impl Opt {
 pub enum Command {
     Bake(cmd_bake::Bake),
     Build(cmd_build::Build),
+    Cache(cmd_cache::Cache),
+    #[clap(hide = true)]
+    Complete(cmd_complete::Complete),
     Completion(cmd_completion::Completion),
     Convert(cmd_convert::Convert),
     Debug(cmd_debug::Debug),
     Deprecate(cmd_deprecate::DeprecateCmd),
+    Diff(cmd_diff::Diff),
     Du(cmd_du::Du),
     Env(cmd_env::Env),
     Explain(cmd_explain::Explain),
     Export(cmd_export::Export),
+    Hist(cmd_hist::Hist),
     Import(cmd_import::Import),
     Install(cmd_install::Install),
     Lint(cmd_lint::Lint),
@@ -160,7 +207,11 @@ pub enum Command {
     New(cmd_new::New),
     #[clap(alias = "variant-count", hide = true)]
     NumVariants(cmd_num_variants::NumVariants),
+    Options(cmd_options::Options),
+    Promote(cmd_promote::Promote),
+    Prune(cmd_prune::Prune),
     Publish(cmd_publish::Publish),
+    Rdepends(cmd_rdepends::Rdepends),
     Remove(cmd_remove::Remove),
     Render(cmd_render::Render),
     Repo(cmd_repo::Repo),
@@ -169,6 +220,7 @@ pub enum Command {
     Undeprecate(cmd_undeprecate::Undeprecate),
     Version(cmd_version::Version),
     View(cmd_view::View),
+    WhichOwns(cmd_which_owns::WhichOwns),
 }
 
 // At the time of writing, enum_dispatch is not working to generate this code
@@ -182,14 +234,18 @@ impl Run for Command {
         match self {
             Command::Bake(cmd) => cmd.run().await,
             Command::Build(cmd) => cmd.run().await.map(Into::into),
+            Command::Cache(cmd) => cmd.run().await,
+            Command::Complete(cmd) => cmd.run(Opt::command()).await,
             Command::Completion(cmd) => cmd.run(Opt::command()),
             Command::Convert(cmd) => cmd.run().await,
             Command::Debug(cmd) => cmd.run().await,
             Command::Deprecate(cmd) => cmd.run().await,
+            Command::Diff(cmd) => cmd.run().await,
             Command::Du(cmd) => cmd.run().await,
             Command::Env(cmd) => cmd.run().await,
             Command::Explain(cmd) => cmd.run().await,
             Command::Export(cmd) => cmd.run().await,
+            Command::Hist(cmd) => cmd.run().await,
             Command::Import(cmd) => cmd.run().await,
             Command::Install(cmd) => cmd.run().await,
             Command::Lint(cmd) => cmd.run().await,
@@ -199,7 +255,11 @@ impl Run for Command {
             Command::MakeRecipe(cmd) => cmd.run().await,
             Command::New(cmd) => cmd.run().await,
             Command::NumVariants(cmd) => cmd.run().await,
+            Command::Options(cmd) => cmd.run().await,
+            Command::Promote(cmd) => cmd.run().await,
+            Command::Prune(cmd) => cmd.run().await,
             Command::Publish(cmd) => cmd.run().await,
+            Command::Rdepends(cmd) => cmd.run().await,
             Command::Remove(cmd) => cmd.run().await,
             Command::Render(cmd) => cmd.run().await,
             Command::Repo(cmd) => cmd.run().await,
@@ -208,6 +268,7 @@ impl Run for Command {
             Command::Undeprecate(cmd) => cmd.run().await,
             Command::Version(cmd) => cmd.run().await,
             Command::View(cmd) => cmd.run().await,
+            Command::WhichOwns(cmd) => cmd.run().await,
         }
     }
 }
@@ -217,14 +278,18 @@ impl CommandArgs for Command {
         match self {
             Command::Bake(cmd) => cmd.get_positional_args(),
             Command::Build(cmd) => cmd.get_positional_args(),
+            Command::Cache(cmd) => cmd.get_positional_args(),
             Command::Convert(cmd) => cmd.get_positional_args(),
+            Command::Complete(cmd) => cmd.get_positional_args(),
             Command::Completion(cmd) => cmd.get_positional_args(),
             Command::Debug(cmd) => cmd.get_positional_args(),
             Command::Deprecate(cmd) => cmd.get_positional_args(),
+            Command::Diff(cmd) => cmd.get_positional_args(),
             Command::Du(cmd) => cmd.get_positional_args(),
             Command::Env(cmd) => cmd.get_positional_args(),
             Command::Explain(cmd) => cmd.get_positional_args(),
             Command::Export(cmd) => cmd.get_positional_args(),
+            Command::Hist(cmd) => cmd.get_positional_args(),
             Command::Import(cmd) => cmd.get_positional_args(),
             Command::Install(cmd) => cmd.get_positional_args(),
             Command::Lint(cmd) => cmd.get_positional_args(),
@@ -234,7 +299,11 @@ impl CommandArgs for Command {
             Command::MakeRecipe(cmd) => cmd.get_positional_args(),
             Command::New(cmd) => cmd.get_positional_args(),
             Command::NumVariants(cmd) => cmd.get_positional_args(),
+            Command::Options(cmd) => cmd.get_positional_args(),
+            Command::Promote(cmd) => cmd.get_positional_args(),
+            Command::Prune(cmd) => cmd.get_positional_args(),
             Command::Publish(cmd) => cmd.get_positional_args(),
+            Command::Rdepends(cmd) => cmd.get_positional_args(),
             Command::Remove(cmd) => cmd.get_positional_args(),
             Command::Render(cmd) => cmd.get_positional_args(),
             Command::Repo(cmd) => cmd.get_positional_args(),
@@ -243,6 +312,7 @@ impl CommandArgs for Command {
             Command::Undeprecate(cmd) => cmd.get_positional_args(),
             Command::Version(cmd) => cmd.get_positional_args(),
             Command::View(cmd) => cmd.get_positional_args(),
+            Command::WhichOwns(cmd) => cmd.get_positional_args(),
         }
     }
 }
@@ -253,10 +323,16 @@ async fn main() -> ExitCode {
     let code = match opts.run().await {
         Ok(code) => code,
         Err(err) => {
-            let root = err.root_cause();
-            if let Some(err) = root.downcast_ref::<Error>() {
-                eprintln!("{}", err.format_error(opts.verbose));
-            } else {
+            let res = Reporter::current().report_error(&err, || {
+                let root = err.root_cause();
+                if let Some(err) = root.downcast_ref::<Error>() {
+                    eprintln!("{}", err.format_error(opts.verbose));
+                } else {
+                    tracing::error!("{:?}", err);
+                }
+                Ok(())
+            });
+            if let Err(err) = res {
                 tracing::error!("{:?}", err);
             }
             1
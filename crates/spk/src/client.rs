@@ -0,0 +1,197 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+//! An async interface for embedding spk into other programs
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use spk_build::{BinaryPackageBuilder, SourcePackageBuilder};
+use spk_schema::ident_ops::NormalizedTagStrategy;
+use spk_schema::{AnyIdent, BuildIdent, OptionMap, Request, Spec, SpecRecipe, Variant};
+use spk_solve::{Solution, Solver, SolverRuntime};
+use spk_storage::{self as storage, Publisher, RepositoryHandle};
+
+use crate::Result;
+
+#[cfg(test)]
+#[path = "./client_test.rs"]
+mod client_test;
+
+/// Solves, builds, publishes and sets up environments for spk packages.
+///
+/// Every operation is async and runs on the caller's tokio runtime,
+/// so that spk can be embedded in an existing application. No runtime
+/// is created or blocked on by the client itself.
+///
+/// Builds and environment setup modify the active spfs runtime, and
+/// so must be run from within one.
+#[derive(Clone)]
+pub struct Client {
+    local: Arc<RepositoryHandle>,
+    repos: Vec<Arc<RepositoryHandle>>,
+    options: OptionMap,
+    binary_only: bool,
+    namespace_routes: spk_config::Namespaces,
+    repository_priorities: HashMap<String, i64>,
+}
+
+impl Client {
+    /// Create a client for the local repository and the configured 'origin' remote.
+    ///
+    /// The client also uses the namespace routes and repository
+    /// priorities from the spk config.
+    pub async fn new() -> Result<Self> {
+        let config = spk_config::get_config()?;
+        let (local, origin) = tokio::try_join!(
+            storage::local_repository(),
+            storage::remote_repository::<_, NormalizedTagStrategy>("origin"),
+        )?;
+        let mut client = Self::from_repositories(Arc::new(local.into()), [Arc::new(origin.into())]);
+        client
+            .with_namespace_routes(config.namespaces.clone())
+            .with_repository_priorities(config.repositories.priorities.clone());
+        Ok(client)
+    }
+
+    /// Create a client that builds into the given local repository.
+    ///
+    /// Packages are resolved from the local repository, followed
+    /// by each of the given remotes in order.
+    pub fn from_repositories<I>(local: Arc<RepositoryHandle>, remotes: I) -> Self
+    where
+        I: IntoIterator<Item = Arc<RepositoryHandle>>,
+    {
+        let repos = std::iter::once(Arc::clone(&local)).chain(remotes).collect();
+        Self {
+            local,
+            repos,
+            options: OptionMap::default(),
+            binary_only: true,
+            namespace_routes: Default::default(),
+            repository_priorities: HashMap::new(),
+        }
+    }
+
+    /// Set the options used when solving environments.
+    pub fn with_options(&mut self, options: OptionMap) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    /// Only resolve existing binary packages when solving, rather than
+    /// also considering new builds from source (defaults to true).
+    pub fn with_binary_only(&mut self, binary_only: bool) -> &mut Self {
+        self.binary_only = binary_only;
+        self
+    }
+
+    /// Route the packages in each namespace to specific repositories
+    /// when solving and building.
+    pub fn with_namespace_routes(&mut self, routes: spk_config::Namespaces) -> &mut Self {
+        self.namespace_routes = routes;
+        self
+    }
+
+    /// Prefer builds from the repositories with the highest given priority
+    /// when solving and building.
+    pub fn with_repository_priorities(
+        &mut self,
+        priorities: impl IntoIterator<Item = (String, i64)>,
+    ) -> &mut Self {
+        self.repository_priorities.extend(priorities);
+        self
+    }
+
+    /// The repository that new builds are published into.
+    pub fn local_repository(&self) -> &Arc<RepositoryHandle> {
+        &self.local
+    }
+
+    /// All repositories that packages are resolved from, in order.
+    pub fn repositories(&self) -> &[Arc<RepositoryHandle>] {
+        &self.repos
+    }
+
+    /// Resolve the given requests into a complete set of packages.
+    pub async fn solve<I>(&self, requests: I) -> Result<Solution>
+    where
+        I: IntoIterator<Item = Request>,
+    {
+        Ok(self.solver_runtime(requests).solution().await?)
+    }
+
+    /// Start resolving the given requests, without running the solver.
+    ///
+    /// Iterating the returned runtime yields each node and decision as
+    /// the solver makes them, which allows callers to report progress
+    /// or abandon the solve early by dropping it.
+    pub fn solver_runtime<I>(&self, requests: I) -> SolverRuntime
+    where
+        I: IntoIterator<Item = Request>,
+    {
+        let mut solver = Solver::default();
+        solver.update_options(self.options.clone());
+        solver.set_binary_only(self.binary_only);
+        solver.set_namespace_routes(self.namespace_routes.clone());
+        solver.set_repository_priorities(self.repository_priorities.clone());
+        for repo in self.repos.iter() {
+            solver.add_repository(Arc::clone(repo));
+        }
+        for request in requests {
+            solver.add_request(request);
+        }
+        solver.run()
+    }
+
+    /// Collect sources from the given root directory into a source
+    /// package, publishing it to the local repository.
+    pub async fn build_source<P: AsRef<Path>>(&self, recipe: SpecRecipe, root: P) -> Result<Spec> {
+        let (package, _components) = SourcePackageBuilder::from_recipe(recipe)
+            .build_and_publish(root, &self.local)
+            .await?;
+        Ok(package)
+    }
+
+    /// Build a binary package for one variant of the given recipe,
+    /// publishing it to the local repository.
+    ///
+    /// The recipe's source package must already exist in one of
+    /// the client's repositories.
+    pub async fn build_binary<V>(&self, recipe: SpecRecipe, variant: V) -> Result<Spec>
+    where
+        V: Variant + Clone + Send + Sync,
+    {
+        let mut builder = BinaryPackageBuilder::from_recipe(recipe);
+        builder
+            .with_repositories(self.repos.iter().cloned())
+            .with_namespace_routes(self.namespace_routes.clone())
+            .with_repository_priorities(self.repository_priorities.clone());
+        let (package, _components) = builder.build_and_publish(variant, &self.local).await?;
+        Ok(package)
+    }
+
+    /// Publish the identified package from the local repository into another.
+    ///
+    /// Returns the builds that were published. See [`Publisher`] for
+    /// more control over the process.
+    pub async fn publish(
+        &self,
+        pkg: &AnyIdent,
+        destination: Arc<RepositoryHandle>,
+    ) -> Result<Vec<BuildIdent>> {
+        let publisher = Publisher::new(Arc::clone(&self.local), destination);
+        Ok(publisher.publish(pkg).await?)
+    }
+
+    /// Modify the active spfs runtime to contain exactly the packages in the given solution.
+    ///
+    /// The install hooks of the packages are run once the runtime is set up,
+    /// according to the site policy.
+    pub async fn setup_env(&self, solution: &Solution) -> Result<()> {
+        spk_exec::setup_current_runtime(solution).await?;
+        Ok(spk_build::install_hooks::run_current_install_hooks(solution).await?)
+    }
+}
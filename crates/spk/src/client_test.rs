@@ -0,0 +1,53 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use rstest::rstest;
+use spk_schema::Package;
+use spk_solve_macros::{make_repo, request};
+
+use super::Client;
+
+#[rstest]
+#[tokio::test]
+async fn test_client_solve_uses_external_runtime() {
+    let repo = make_repo!([
+        {"pkg": "my-pkg/1.0.0", "install": {"requirements": [{"pkg": "dep"}]}},
+        {"pkg": "dep/2.0.0"},
+    ]);
+    let client = Client::from_repositories(Arc::new(repo), []);
+
+    let solution = client.solve([request!("my-pkg")]).await.unwrap();
+    let mut names = solution
+        .items()
+        .map(|item| item.spec.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["dep", "my-pkg"]);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_client_solver_runtime_yields_each_decision() {
+    let repo = make_repo!([
+        {"pkg": "my-pkg/1.0.0", "install": {"requirements": [{"pkg": "dep"}]}},
+        {"pkg": "dep/2.0.0"},
+    ]);
+    let client = Client::from_repositories(Arc::new(repo), []);
+
+    let mut runtime = client.solver_runtime([request!("my-pkg")]);
+    let mut steps = 0;
+    {
+        let iter = runtime.iter();
+        tokio::pin!(iter);
+        while iter.try_next().await.unwrap().is_some() {
+            steps += 1;
+        }
+    }
+    assert!(steps >= 2, "expected a decision for each package");
+    let solution = runtime.current_solution().await.unwrap();
+    assert_eq!(solution.len(), 2);
+}
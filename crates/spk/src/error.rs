@@ -0,0 +1,33 @@
+// Copyright (c) Contributors to the SPK project.
+// SPDX-License-Identifier: Apache-2.0
+// https://github.com/spkenv/spk
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Diagnostic, Debug, Error)]
+#[diagnostic(
+    url(
+        "https://spkenv.dev/error_codes#{}",
+        self.code().unwrap_or_else(|| Box::new("spk::generic"))
+    )
+)]
+pub enum Error {
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkBuildError(#[from] spk_build::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkConfigError(#[from] spk_config::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkExecError(#[from] spk_exec::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkSolverError(#[from] spk_solve::Error),
+    #[error(transparent)]
+    #[diagnostic(forward(0))]
+    SpkStorageError(#[from] spk_storage::Error),
+}
@@ -2,6 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 // https://github.com/spkenv/spk
 
+mod client;
+mod error;
+
+pub use client::Client;
+pub use error::{Error, Result};
 pub use {
     spk_build as build,
     spk_exec as exec,